@@ -0,0 +1,112 @@
+//! Auto-generated FSM: CoolantPump
+//! Generated by Oxidate
+//!
+//! CoolantPumpActions methods:
+//! - `fn close_valve(&mut self)`: entry of Idle
+//! - `fn load_config(&mut self)`: start ([*] --> Idle)
+//! - `fn open_valve(&mut self)`: Idle --> Pumping : start / open_valve
+//! - `fn self_test(&mut self)`: start ([*] --> Idle)
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CoolantPumpState {
+    Idle,
+    Pumping,
+}
+
+impl CoolantPumpState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            CoolantPumpState::Idle => "Idle",
+            CoolantPumpState::Pumping => "Pumping",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CoolantPumpEvent {
+    Start,
+    Stop,
+}
+
+impl CoolantPumpEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            CoolantPumpEvent::Start => "start",
+            CoolantPumpEvent::Stop => "stop",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct CoolantPump<T: CoolantPumpActions> {
+    state: CoolantPumpState,
+    context: T,
+    started: bool,
+}
+
+impl<T: CoolantPumpActions> CoolantPump<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub fn new(context: T) -> Self {
+        Self {
+            state: CoolantPumpState::Idle,
+            context,
+            started: false,
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        self.context.load_config();
+        self.context.self_test();
+        self.context.close_valve();
+        true
+    }
+
+    pub fn state(&self) -> CoolantPumpState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: CoolantPumpEvent) -> bool {
+        match (self.state, event) {
+            (CoolantPumpState::Idle, CoolantPumpEvent::Start) => {
+                self.context.open_valve();
+                self.state = CoolantPumpState::Pumping;
+                true
+            }
+            (CoolantPumpState::Pumping, CoolantPumpEvent::Stop) => {
+                self.state = CoolantPumpState::Idle;
+                self.context.close_valve();
+                true
+            }
+            _ => false // No transition
+        }
+    }
+}
+
+// ── Actions trait ──
+pub trait CoolantPumpActions {
+    fn close_valve(&mut self);
+    fn load_config(&mut self);
+    fn open_valve(&mut self);
+    fn self_test(&mut self);
+}
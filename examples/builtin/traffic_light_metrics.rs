@@ -0,0 +1,241 @@
+//! Auto-generated FSM: TrafficLight
+//! Generated by Oxidate
+//!
+//! TrafficLightActions methods:
+//! - `fn activate_green_light(&mut self)`: entry of Green
+//! - `fn activate_red_light(&mut self)`: entry of Red
+//! - `fn activate_yellow_light(&mut self)`: entry of Yellow
+//! - `fn day_mode(&self) -> bool`: Red --> Green : timer_expired [day_mode]
+//! - `fn deactivate_green_light(&mut self)`: exit of Green
+//! - `fn deactivate_red_light(&mut self)`: exit of Red
+//! - `fn deactivate_yellow_light(&mut self)`: exit of Yellow
+//! - `fn night_mode(&self) -> bool`: Red --> Yellow : timer_expired [night_mode]
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrafficLightState {
+    /// Stop - Wait for green
+    Red,
+    /// Caution
+    Yellow,
+    /// Go!
+    Green,
+}
+
+impl TrafficLightState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            TrafficLightState::Red => "Red",
+            TrafficLightState::Yellow => "Yellow",
+            TrafficLightState::Green => "Green",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrafficLightEvent {
+    TimerExpired,
+}
+
+impl TrafficLightEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            TrafficLightEvent::TimerExpired => "timer_expired",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// Dispatch counters of `TrafficLight`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrafficLightMetrics {
+    state_entered: [u16; 3],
+    event_received: [u32; 1],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl TrafficLightMetrics {
+    fn started(initial: TrafficLightState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 3],
+            event_received: [0; 1],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: TrafficLightState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub fn entered(&self, state: TrafficLightState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: TrafficLightEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub fn received(&self, event: TrafficLightEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub struct TrafficLight<T: TrafficLightActions> {
+    state: TrafficLightState,
+    context: T,
+    started: bool,
+    queue: [Option<TrafficLightEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: TrafficLightMetrics,
+}
+
+impl<T: TrafficLightActions> TrafficLight<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub fn new(context: T) -> Self {
+        Self {
+            state: TrafficLightState::Red,
+            context,
+            started: false,
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: TrafficLightMetrics::started(TrafficLightState::Red),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        self.context.activate_red_light();
+        true
+    }
+
+    pub fn state(&self) -> TrafficLightState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub fn metrics(&self) -> &TrafficLightMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; gives the event back if the queue is full
+    pub fn post(&mut self, event: TrafficLightEvent) -> Result<(), TrafficLightEvent> {
+        if self.queue_len == 4 {
+            return Err(event);
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        Ok(())
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event) {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: TrafficLightEvent) -> bool {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (TrafficLightState::Red, TrafficLightEvent::TimerExpired) if self.context.day_mode() => {
+                self.context.deactivate_red_light();
+                self.state = TrafficLightState::Green;
+                self.context.activate_green_light();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(TrafficLightState::Green);
+                true
+            }
+            (TrafficLightState::Red, TrafficLightEvent::TimerExpired) if self.context.night_mode() => {
+                self.context.deactivate_red_light();
+                self.state = TrafficLightState::Yellow;
+                self.context.activate_yellow_light();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(TrafficLightState::Yellow);
+                true
+            }
+            (TrafficLightState::Green, TrafficLightEvent::TimerExpired) => {
+                self.context.deactivate_green_light();
+                self.state = TrafficLightState::Yellow;
+                self.context.activate_yellow_light();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(TrafficLightState::Yellow);
+                true
+            }
+            (TrafficLightState::Yellow, TrafficLightEvent::TimerExpired) => {
+                self.context.deactivate_yellow_light();
+                self.state = TrafficLightState::Red;
+                self.context.activate_red_light();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(TrafficLightState::Red);
+                true
+            }
+            _ => false // No transition
+        }
+    }
+}
+
+// ── Actions trait ──
+pub trait TrafficLightActions {
+    fn activate_green_light(&mut self);
+    fn activate_red_light(&mut self);
+    fn activate_yellow_light(&mut self);
+    fn deactivate_green_light(&mut self);
+    fn deactivate_red_light(&mut self);
+    fn deactivate_yellow_light(&mut self);
+    fn day_mode(&self) -> bool;
+    fn night_mode(&self) -> bool;
+}
@@ -0,0 +1,176 @@
+//! Auto-generated FSM: TrafficLight
+//! Generated by Oxidate
+//!
+//! TrafficLightActions methods:
+//! - `fn activate_green_light(&mut self)`: entry of Green
+//! - `fn activate_red_light(&mut self)`: entry of Red
+//! - `fn activate_yellow_light(&mut self)`: entry of Yellow
+//! - `fn day_mode(&self) -> bool`: Red --> Green : timer_expired [day_mode]
+//! - `fn deactivate_green_light(&mut self)`: exit of Green
+//! - `fn deactivate_red_light(&mut self)`: exit of Red
+//! - `fn deactivate_yellow_light(&mut self)`: exit of Yellow
+//! - `fn night_mode(&self) -> bool`: Red --> Yellow : timer_expired [night_mode]
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrafficLightState {
+    /// Stop - Wait for green
+    Red,
+    /// Caution
+    Yellow,
+    /// Go!
+    Green,
+}
+
+impl TrafficLightState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            TrafficLightState::Red => "Red",
+            TrafficLightState::Yellow => "Yellow",
+            TrafficLightState::Green => "Green",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrafficLightEvent {
+    TimerExpired,
+}
+
+impl TrafficLightEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            TrafficLightEvent::TimerExpired => "timer_expired",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct TrafficLight<T: TrafficLightActions> {
+    state: TrafficLightState,
+    context: T,
+    started: bool,
+    queue: [Option<TrafficLightEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    dropped_events: u32,
+}
+
+impl<T: TrafficLightActions> TrafficLight<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub fn new(context: T) -> Self {
+        Self {
+            state: TrafficLightState::Red,
+            context,
+            started: false,
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            dropped_events: 0,
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        self.context.activate_red_light();
+        true
+    }
+
+    pub fn state(&self) -> TrafficLightState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the event is
+    /// discarded and counted in `dropped_events()`
+    pub fn post(&mut self, event: TrafficLightEvent) {
+        if self.queue_len == 4 {
+            self.dropped_events = self.dropped_events.saturating_add(1);
+            return;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+    }
+
+    /// Events discarded by `post()` because the queue was full (saturates at `u32::MAX`)
+    pub fn dropped_events(&self) -> u32 {
+        self.dropped_events
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event) {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: TrafficLightEvent) -> bool {
+        match (self.state, event) {
+            (TrafficLightState::Red, TrafficLightEvent::TimerExpired) if self.context.day_mode() => {
+                self.context.deactivate_red_light();
+                self.state = TrafficLightState::Green;
+                self.context.activate_green_light();
+                true
+            }
+            (TrafficLightState::Red, TrafficLightEvent::TimerExpired) if self.context.night_mode() => {
+                self.context.deactivate_red_light();
+                self.state = TrafficLightState::Yellow;
+                self.context.activate_yellow_light();
+                true
+            }
+            (TrafficLightState::Green, TrafficLightEvent::TimerExpired) => {
+                self.context.deactivate_green_light();
+                self.state = TrafficLightState::Yellow;
+                self.context.activate_yellow_light();
+                true
+            }
+            (TrafficLightState::Yellow, TrafficLightEvent::TimerExpired) => {
+                self.context.deactivate_yellow_light();
+                self.state = TrafficLightState::Red;
+                self.context.activate_red_light();
+                true
+            }
+            _ => false // No transition
+        }
+    }
+}
+
+// ── Actions trait ──
+pub trait TrafficLightActions {
+    fn activate_green_light(&mut self);
+    fn activate_red_light(&mut self);
+    fn activate_yellow_light(&mut self);
+    fn deactivate_green_light(&mut self);
+    fn deactivate_red_light(&mut self);
+    fn deactivate_yellow_light(&mut self);
+    fn day_mode(&self) -> bool;
+    fn night_mode(&self) -> bool;
+}
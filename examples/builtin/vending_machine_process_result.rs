@@ -0,0 +1,198 @@
+//! Auto-generated FSM: VendingMachine
+//! Generated by Oxidate
+//!
+//! VendingMachineActions methods:
+//! - `fn add_coin(&mut self)`: AcceptingCoins --> AcceptingCoins : coin_inserted / add_coin
+//! - `fn add_to_balance(&mut self)`: internal of AcceptingCoins : coin_inserted / add_to_balance
+//! - `fn calculate_change(&mut self)`: entry of ReturningChange
+//! - `fn clear_display(&mut self)`: exit of Idle
+//! - `fn dispense_change(&mut self)`: exit of ReturningChange
+//! - `fn dispense_product(&mut self)`: entry of Dispensing
+//! - `fn display_welcome(&mut self)`: entry of Idle
+//! - `fn has_change(&self) -> bool`: Dispensing --> ReturningChange : dispensed [has_change]
+//! - `fn no_change(&self) -> bool`: Dispensing --> Idle : dispensed [no_change]
+//! - `fn return_coins(&mut self)`: AcceptingCoins --> Idle : cancel / return_coins
+//! - `fn show_balance(&mut self)`: entry of AcceptingCoins
+//! - `fn sufficient_funds(&self) -> bool`: AcceptingCoins --> Dispensing : select_product [sufficient_funds]
+//! - `fn update_inventory(&mut self)`: exit of Dispensing
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VendingMachineState {
+    /// Insert coins
+    Idle,
+    /// Accepting payment
+    AcceptingCoins,
+    /// Delivering product
+    Dispensing,
+    /// Giving change
+    ReturningChange,
+}
+
+impl VendingMachineState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            VendingMachineState::Idle => "Idle",
+            VendingMachineState::AcceptingCoins => "AcceptingCoins",
+            VendingMachineState::Dispensing => "Dispensing",
+            VendingMachineState::ReturningChange => "ReturningChange",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VendingMachineEvent {
+    Cancel,
+    ChangeReturned,
+    CoinInserted,
+    Dispensed,
+    SelectProduct,
+}
+
+impl VendingMachineEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            VendingMachineEvent::Cancel => "cancel",
+            VendingMachineEvent::ChangeReturned => "change_returned",
+            VendingMachineEvent::CoinInserted => "coin_inserted",
+            VendingMachineEvent::Dispensed => "dispensed",
+            VendingMachineEvent::SelectProduct => "select_product",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `VendingMachine::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VendingMachineProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: VendingMachineState, to: VendingMachineState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl VendingMachineProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+pub struct VendingMachine<T: VendingMachineActions> {
+    state: VendingMachineState,
+    context: T,
+    started: bool,
+}
+
+impl<T: VendingMachineActions> VendingMachine<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub fn new(context: T) -> Self {
+        Self {
+            state: VendingMachineState::Idle,
+            context,
+            started: false,
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        self.context.display_welcome();
+        true
+    }
+
+    pub fn state(&self) -> VendingMachineState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    ///
+    /// A state's own transitions, self-transitions included, are tried before its
+    /// internal transitions, which run only their actions
+    pub fn process(&mut self, event: VendingMachineEvent) -> VendingMachineProcessResult {
+        match (self.state, event) {
+            (VendingMachineState::Idle, VendingMachineEvent::CoinInserted) => {
+                self.context.clear_display();
+                self.state = VendingMachineState::AcceptingCoins;
+                self.context.show_balance();
+                VendingMachineProcessResult::Transitioned { from: VendingMachineState::Idle, to: VendingMachineState::AcceptingCoins }
+            }
+            (VendingMachineState::AcceptingCoins, VendingMachineEvent::CoinInserted) => {
+                self.context.add_coin();
+                self.state = VendingMachineState::AcceptingCoins;
+                self.context.show_balance();
+                VendingMachineProcessResult::Transitioned { from: VendingMachineState::AcceptingCoins, to: VendingMachineState::AcceptingCoins }
+            }
+            (VendingMachineState::AcceptingCoins, VendingMachineEvent::SelectProduct) if self.context.sufficient_funds() => {
+                self.state = VendingMachineState::Dispensing;
+                self.context.dispense_product();
+                VendingMachineProcessResult::Transitioned { from: VendingMachineState::AcceptingCoins, to: VendingMachineState::Dispensing }
+            }
+            (VendingMachineState::AcceptingCoins, VendingMachineEvent::Cancel) => {
+                self.context.return_coins();
+                self.state = VendingMachineState::Idle;
+                self.context.display_welcome();
+                VendingMachineProcessResult::Transitioned { from: VendingMachineState::AcceptingCoins, to: VendingMachineState::Idle }
+            }
+            (VendingMachineState::Dispensing, VendingMachineEvent::Dispensed) if self.context.has_change() => {
+                self.context.update_inventory();
+                self.state = VendingMachineState::ReturningChange;
+                self.context.calculate_change();
+                VendingMachineProcessResult::Transitioned { from: VendingMachineState::Dispensing, to: VendingMachineState::ReturningChange }
+            }
+            (VendingMachineState::Dispensing, VendingMachineEvent::Dispensed) if self.context.no_change() => {
+                self.context.update_inventory();
+                self.state = VendingMachineState::Idle;
+                self.context.display_welcome();
+                VendingMachineProcessResult::Transitioned { from: VendingMachineState::Dispensing, to: VendingMachineState::Idle }
+            }
+            (VendingMachineState::ReturningChange, VendingMachineEvent::ChangeReturned) => {
+                self.context.dispense_change();
+                self.state = VendingMachineState::Idle;
+                self.context.display_welcome();
+                VendingMachineProcessResult::Transitioned { from: VendingMachineState::ReturningChange, to: VendingMachineState::Idle }
+            }
+            (VendingMachineState::Dispensing, VendingMachineEvent::Dispensed) => VendingMachineProcessResult::GuardRejected,
+            (VendingMachineState::AcceptingCoins, VendingMachineEvent::SelectProduct) => VendingMachineProcessResult::GuardRejected,
+            _ => VendingMachineProcessResult::Ignored,
+        }
+    }
+}
+
+// ── Actions trait ──
+pub trait VendingMachineActions {
+    fn add_coin(&mut self);
+    fn add_to_balance(&mut self);
+    fn calculate_change(&mut self);
+    fn clear_display(&mut self);
+    fn dispense_change(&mut self);
+    fn dispense_product(&mut self);
+    fn display_welcome(&mut self);
+    fn return_coins(&mut self);
+    fn show_balance(&mut self);
+    fn update_inventory(&mut self);
+    fn has_change(&self) -> bool;
+    fn no_change(&self) -> bool;
+    fn sufficient_funds(&self) -> bool;
+}
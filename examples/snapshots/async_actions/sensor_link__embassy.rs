@@ -0,0 +1,22 @@
+//! Embassy code generation requires Oxidate Pro
+//!
+//! FSM: SensorLink
+//!
+//! To generate Embassy code:
+//!   1. Purchase/access: https://github.com/JoseClaudioSJr/Oxidate/discussions
+//!   2. Then use: oxidate-pro generate --target embassy your_fsm.fsm
+//!
+//! Oxidate Pro includes:
+//!   - Embassy async Active Object pattern
+//!   - RTIC real-time event queues
+//!   - Events with payload
+//!   - HSM hierarchical states
+//!   - Priority support
+//!
+//! Awaited actions (native `async fn` in the actions trait, Rust 1.75+; dispatch
+//! awaits them in order, guards stay sync):
+//!   - `async fn send_frame(&mut self, arg0: u32)`
+//!   - `async fn power_up(&mut self)`
+//!   - `async fn write_register(&mut self, arg0: u32)`
+
+compile_error!("This target requires Oxidate Pro. Contact: https://github.com/JoseClaudioSJr/Oxidate/discussions");
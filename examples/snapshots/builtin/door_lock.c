@@ -0,0 +1,109 @@
+// Auto-generated FSM: DoorLock
+// Generated by Oxidate
+
+#include "door_lock.h"
+
+// ── Dispatch ──
+void door_lock_init(door_lock_t *fsm, const door_lock_actions_t *actions, void *user)
+{
+    fsm->state = DOOR_LOCK_STATE_LOCKED;
+    fsm->actions = actions;
+    fsm->user = user;
+    actions->engage_lock(user);
+}
+
+bool door_lock_dispatch(door_lock_t *fsm, door_lock_event_t event)
+{
+    switch (fsm->state) {
+    case DOOR_LOCK_STATE_LOCKED:
+        if (event == DOOR_LOCK_EVENT_VALID_KEY) {
+            fsm->actions->disengage_lock(fsm->user);
+            fsm->state = DOOR_LOCK_STATE_UNLOCKED;
+            fsm->actions->notify_unlocked(fsm->user);
+            return true;
+        }
+        if (event == DOOR_LOCK_EVENT_INVALID_KEY && fsm->actions->too_many_attempts(fsm->user)) {
+            fsm->actions->disengage_lock(fsm->user);
+            fsm->state = DOOR_LOCK_STATE_ALARMING;
+            fsm->actions->sound_alarm(fsm->user);
+            return true;
+        }
+        break;
+    case DOOR_LOCK_STATE_UNLOCKED:
+        if (event == DOOR_LOCK_EVENT_LOCK_CMD) {
+            fsm->state = DOOR_LOCK_STATE_LOCKED;
+            fsm->actions->engage_lock(fsm->user);
+            return true;
+        }
+        if (event == DOOR_LOCK_EVENT_DOOR_OPENED) {
+            fsm->state = DOOR_LOCK_STATE_OPEN;
+            fsm->actions->start_open_timer(fsm->user);
+            return true;
+        }
+        break;
+    case DOOR_LOCK_STATE_OPEN:
+        if (event == DOOR_LOCK_EVENT_DOOR_CLOSED) {
+            fsm->actions->stop_open_timer(fsm->user);
+            fsm->state = DOOR_LOCK_STATE_UNLOCKED;
+            fsm->actions->notify_unlocked(fsm->user);
+            return true;
+        }
+        if (event == DOOR_LOCK_EVENT_TIMEOUT && fsm->actions->held_too_long(fsm->user)) {
+            fsm->actions->stop_open_timer(fsm->user);
+            fsm->state = DOOR_LOCK_STATE_ALARMING;
+            fsm->actions->sound_alarm(fsm->user);
+            return true;
+        }
+        break;
+    case DOOR_LOCK_STATE_ALARMING:
+        if (event == DOOR_LOCK_EVENT_RESET_ALARM) {
+            fsm->actions->silence_alarm(fsm->user);
+            fsm->state = DOOR_LOCK_STATE_LOCKED;
+            fsm->actions->engage_lock(fsm->user);
+            return true;
+        }
+        break;
+    default:
+        break;
+    }
+    return false;
+}
+
+// ── Names ──
+const char *door_lock_state_name(door_lock_state_t state)
+{
+    switch (state) {
+    case DOOR_LOCK_STATE_LOCKED:
+        return "Locked";
+    case DOOR_LOCK_STATE_UNLOCKED:
+        return "Unlocked";
+    case DOOR_LOCK_STATE_OPEN:
+        return "Open";
+    case DOOR_LOCK_STATE_ALARMING:
+        return "Alarming";
+    default:
+        return "";
+    }
+}
+
+const char *door_lock_event_name(door_lock_event_t event)
+{
+    switch (event) {
+    case DOOR_LOCK_EVENT_DOOR_CLOSED:
+        return "door_closed";
+    case DOOR_LOCK_EVENT_DOOR_OPENED:
+        return "door_opened";
+    case DOOR_LOCK_EVENT_INVALID_KEY:
+        return "invalid_key";
+    case DOOR_LOCK_EVENT_LOCK_CMD:
+        return "lock_cmd";
+    case DOOR_LOCK_EVENT_RESET_ALARM:
+        return "reset_alarm";
+    case DOOR_LOCK_EVENT_TIMEOUT:
+        return "timeout";
+    case DOOR_LOCK_EVENT_VALID_KEY:
+        return "valid_key";
+    default:
+        return "";
+    }
+}
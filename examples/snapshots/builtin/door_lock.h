@@ -0,0 +1,76 @@
+// Auto-generated FSM: DoorLock
+// Generated by Oxidate
+
+#ifndef DOOR_LOCK_H
+#define DOOR_LOCK_H
+
+#include <stdbool.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// ── States ──
+typedef enum {
+    /// Door is secured
+    DOOR_LOCK_STATE_LOCKED,
+    /// Door can be opened
+    DOOR_LOCK_STATE_UNLOCKED,
+    /// Door is open
+    DOOR_LOCK_STATE_OPEN,
+    /// Security alert!
+    DOOR_LOCK_STATE_ALARMING,
+    DOOR_LOCK_STATE_COUNT
+} door_lock_state_t;
+
+// ── Events ──
+typedef enum {
+    DOOR_LOCK_EVENT_DOOR_CLOSED,
+    DOOR_LOCK_EVENT_DOOR_OPENED,
+    DOOR_LOCK_EVENT_INVALID_KEY,
+    DOOR_LOCK_EVENT_LOCK_CMD,
+    DOOR_LOCK_EVENT_RESET_ALARM,
+    DOOR_LOCK_EVENT_TIMEOUT,
+    DOOR_LOCK_EVENT_VALID_KEY,
+    DOOR_LOCK_EVENT_COUNT
+} door_lock_event_t;
+
+// ── Actions table ──
+/// Actions and guards the machine calls, each with the `user` pointer given to
+/// door_lock_init()
+typedef struct {
+    void (*disengage_lock)(void *user);
+    void (*engage_lock)(void *user);
+    void (*notify_unlocked)(void *user);
+    void (*silence_alarm)(void *user);
+    void (*sound_alarm)(void *user);
+    void (*start_open_timer)(void *user);
+    void (*stop_open_timer)(void *user);
+    bool (*held_too_long)(void *user);
+    bool (*too_many_attempts)(void *user);
+} door_lock_actions_t;
+
+// ── Machine ──
+typedef struct {
+    door_lock_state_t state;
+    const door_lock_actions_t *actions;
+    void *user;
+} door_lock_t;
+
+/// Put the machine in its initial state, then run the start actions and the
+/// initial state's entry actions; every call passes `user` back
+void door_lock_init(door_lock_t *fsm, const door_lock_actions_t *actions, void *user);
+/// Process one event; returns whether a transition took it
+///
+/// A transition runs the exit actions of the state it leaves, then its own
+/// actions, then the entry actions of the state it enters (UML order)
+bool door_lock_dispatch(door_lock_t *fsm, door_lock_event_t event);
+/// Names as written in the DSL
+const char *door_lock_state_name(door_lock_state_t state);
+const char *door_lock_event_name(door_lock_event_t event);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // DOOR_LOCK_H
@@ -0,0 +1,157 @@
+//! Auto-generated FSM: DoorLock
+//! Generated by Oxidate
+//!
+//! DoorLockActions methods:
+//! - `fn disengage_lock(&mut self)`: exit of Locked
+//! - `fn engage_lock(&mut self)`: entry of Locked
+//! - `fn held_too_long(&self) -> bool`: Open --> Alarming : timeout [held_too_long]
+//! - `fn notify_unlocked(&mut self)`: entry of Unlocked
+//! - `fn silence_alarm(&mut self)`: exit of Alarming
+//! - `fn sound_alarm(&mut self)`: entry of Alarming
+//! - `fn start_open_timer(&mut self)`: entry of Open
+//! - `fn stop_open_timer(&mut self)`: exit of Open
+//! - `fn too_many_attempts(&self) -> bool`: Locked --> Alarming : invalid_key [too_many_attempts]
+
+#![no_std]
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DoorLockState {
+    /// Door is secured
+    Locked,
+    /// Door can be opened
+    Unlocked,
+    /// Door is open
+    Open,
+    /// Security alert!
+    Alarming,
+}
+
+impl DoorLockState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            DoorLockState::Locked => "Locked",
+            DoorLockState::Unlocked => "Unlocked",
+            DoorLockState::Open => "Open",
+            DoorLockState::Alarming => "Alarming",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DoorLockEvent {
+    DoorClosed,
+    DoorOpened,
+    InvalidKey,
+    LockCmd,
+    ResetAlarm,
+    Timeout,
+    ValidKey,
+}
+
+impl DoorLockEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            DoorLockEvent::DoorClosed => "door_closed",
+            DoorLockEvent::DoorOpened => "door_opened",
+            DoorLockEvent::InvalidKey => "invalid_key",
+            DoorLockEvent::LockCmd => "lock_cmd",
+            DoorLockEvent::ResetAlarm => "reset_alarm",
+            DoorLockEvent::Timeout => "timeout",
+            DoorLockEvent::ValidKey => "valid_key",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct DoorLock<T: DoorLockActions> {
+    state: DoorLockState,
+    context: T,
+}
+
+impl<T: DoorLockActions> DoorLock<T> {
+    /// Create the machine and run its start actions, then the initial state's entry actions
+    pub fn new(mut context: T) -> Self {
+        context.engage_lock();
+        Self {
+            state: DoorLockState::Locked,
+            context,
+        }
+    }
+
+    pub fn state(&self) -> DoorLockState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: DoorLockEvent) -> bool {
+        match (self.state, event) {
+            (DoorLockState::Locked, DoorLockEvent::ValidKey) => {
+                self.context.disengage_lock();
+                self.state = DoorLockState::Unlocked;
+                self.context.notify_unlocked();
+                true
+            }
+            (DoorLockState::Locked, DoorLockEvent::InvalidKey) if self.context.too_many_attempts() => {
+                self.context.disengage_lock();
+                self.state = DoorLockState::Alarming;
+                self.context.sound_alarm();
+                true
+            }
+            (DoorLockState::Unlocked, DoorLockEvent::LockCmd) => {
+                self.state = DoorLockState::Locked;
+                self.context.engage_lock();
+                true
+            }
+            (DoorLockState::Unlocked, DoorLockEvent::DoorOpened) => {
+                self.state = DoorLockState::Open;
+                self.context.start_open_timer();
+                true
+            }
+            (DoorLockState::Open, DoorLockEvent::DoorClosed) => {
+                self.context.stop_open_timer();
+                self.state = DoorLockState::Unlocked;
+                self.context.notify_unlocked();
+                true
+            }
+            (DoorLockState::Open, DoorLockEvent::Timeout) if self.context.held_too_long() => {
+                self.context.stop_open_timer();
+                self.state = DoorLockState::Alarming;
+                self.context.sound_alarm();
+                true
+            }
+            (DoorLockState::Alarming, DoorLockEvent::ResetAlarm) => {
+                self.context.silence_alarm();
+                self.state = DoorLockState::Locked;
+                self.context.engage_lock();
+                true
+            }
+            _ => false // No transition
+        }
+    }
+}
+
+// ── Actions trait ──
+pub trait DoorLockActions {
+    fn disengage_lock(&mut self);
+    fn engage_lock(&mut self);
+    fn notify_unlocked(&mut self);
+    fn silence_alarm(&mut self);
+    fn sound_alarm(&mut self);
+    fn start_open_timer(&mut self);
+    fn stop_open_timer(&mut self);
+    fn held_too_long(&self) -> bool;
+    fn too_many_attempts(&self) -> bool;
+}
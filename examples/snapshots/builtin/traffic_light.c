@@ -0,0 +1,77 @@
+// Auto-generated FSM: TrafficLight
+// Generated by Oxidate
+
+#include "traffic_light.h"
+
+// ── Dispatch ──
+void traffic_light_init(traffic_light_t *fsm, const traffic_light_actions_t *actions, void *user)
+{
+    fsm->state = TRAFFIC_LIGHT_STATE_RED;
+    fsm->actions = actions;
+    fsm->user = user;
+    actions->activate_red_light(user);
+}
+
+bool traffic_light_dispatch(traffic_light_t *fsm, traffic_light_event_t event)
+{
+    switch (fsm->state) {
+    case TRAFFIC_LIGHT_STATE_RED:
+        if (event == TRAFFIC_LIGHT_EVENT_TIMER_EXPIRED && fsm->actions->day_mode(fsm->user)) {
+            fsm->actions->deactivate_red_light(fsm->user);
+            fsm->state = TRAFFIC_LIGHT_STATE_GREEN;
+            fsm->actions->activate_green_light(fsm->user);
+            return true;
+        }
+        if (event == TRAFFIC_LIGHT_EVENT_TIMER_EXPIRED && fsm->actions->night_mode(fsm->user)) {
+            fsm->actions->deactivate_red_light(fsm->user);
+            fsm->state = TRAFFIC_LIGHT_STATE_YELLOW;
+            fsm->actions->activate_yellow_light(fsm->user);
+            return true;
+        }
+        break;
+    case TRAFFIC_LIGHT_STATE_YELLOW:
+        if (event == TRAFFIC_LIGHT_EVENT_TIMER_EXPIRED) {
+            fsm->actions->deactivate_yellow_light(fsm->user);
+            fsm->state = TRAFFIC_LIGHT_STATE_RED;
+            fsm->actions->activate_red_light(fsm->user);
+            return true;
+        }
+        break;
+    case TRAFFIC_LIGHT_STATE_GREEN:
+        if (event == TRAFFIC_LIGHT_EVENT_TIMER_EXPIRED) {
+            fsm->actions->deactivate_green_light(fsm->user);
+            fsm->state = TRAFFIC_LIGHT_STATE_YELLOW;
+            fsm->actions->activate_yellow_light(fsm->user);
+            return true;
+        }
+        break;
+    default:
+        break;
+    }
+    return false;
+}
+
+// ── Names ──
+const char *traffic_light_state_name(traffic_light_state_t state)
+{
+    switch (state) {
+    case TRAFFIC_LIGHT_STATE_RED:
+        return "Red";
+    case TRAFFIC_LIGHT_STATE_YELLOW:
+        return "Yellow";
+    case TRAFFIC_LIGHT_STATE_GREEN:
+        return "Green";
+    default:
+        return "";
+    }
+}
+
+const char *traffic_light_event_name(traffic_light_event_t event)
+{
+    switch (event) {
+    case TRAFFIC_LIGHT_EVENT_TIMER_EXPIRED:
+        return "timer_expired";
+    default:
+        return "";
+    }
+}
@@ -0,0 +1,67 @@
+// Auto-generated FSM: TrafficLight
+// Generated by Oxidate
+
+#ifndef TRAFFIC_LIGHT_H
+#define TRAFFIC_LIGHT_H
+
+#include <stdbool.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// ── States ──
+typedef enum {
+    /// Stop - Wait for green
+    TRAFFIC_LIGHT_STATE_RED,
+    /// Caution
+    TRAFFIC_LIGHT_STATE_YELLOW,
+    /// Go!
+    TRAFFIC_LIGHT_STATE_GREEN,
+    TRAFFIC_LIGHT_STATE_COUNT
+} traffic_light_state_t;
+
+// ── Events ──
+typedef enum {
+    TRAFFIC_LIGHT_EVENT_TIMER_EXPIRED,
+    TRAFFIC_LIGHT_EVENT_COUNT
+} traffic_light_event_t;
+
+// ── Actions table ──
+/// Actions and guards the machine calls, each with the `user` pointer given to
+/// traffic_light_init()
+typedef struct {
+    void (*activate_green_light)(void *user);
+    void (*activate_red_light)(void *user);
+    void (*activate_yellow_light)(void *user);
+    void (*deactivate_green_light)(void *user);
+    void (*deactivate_red_light)(void *user);
+    void (*deactivate_yellow_light)(void *user);
+    bool (*day_mode)(void *user);
+    bool (*night_mode)(void *user);
+} traffic_light_actions_t;
+
+// ── Machine ──
+typedef struct {
+    traffic_light_state_t state;
+    const traffic_light_actions_t *actions;
+    void *user;
+} traffic_light_t;
+
+/// Put the machine in its initial state, then run the start actions and the
+/// initial state's entry actions; every call passes `user` back
+void traffic_light_init(traffic_light_t *fsm, const traffic_light_actions_t *actions, void *user);
+/// Process one event; returns whether a transition took it
+///
+/// A transition runs the exit actions of the state it leaves, then its own
+/// actions, then the entry actions of the state it enters (UML order)
+bool traffic_light_dispatch(traffic_light_t *fsm, traffic_light_event_t event);
+/// Names as written in the DSL
+const char *traffic_light_state_name(traffic_light_state_t state);
+const char *traffic_light_event_name(traffic_light_event_t event);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // TRAFFIC_LIGHT_H
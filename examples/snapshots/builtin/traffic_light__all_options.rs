@@ -0,0 +1,304 @@
+//! Auto-generated FSM: TrafficLight
+//! Generated by Oxidate
+//!
+//! Snapshot of every option
+//!
+//! TrafficLightActions methods:
+//! - `fn activate_green_light(&mut self)`: entry of Green
+//! - `fn activate_red_light(&mut self)`: entry of Red
+//! - `fn activate_yellow_light(&mut self)`: entry of Yellow
+//! - `fn day_mode(&self) -> bool`: Red --> Green : timer_expired [day_mode]
+//! - `fn deactivate_green_light(&mut self)`: exit of Green
+//! - `fn deactivate_red_light(&mut self)`: exit of Red
+//! - `fn deactivate_yellow_light(&mut self)`: exit of Yellow
+//! - `fn night_mode(&self) -> bool`: Red --> Yellow : timer_expired [night_mode]
+//! - `fn on_transition(&mut self, from, event, to)`: every transition
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum TrafficLightState {
+    /// Stop - Wait for green
+    Red,
+    /// Caution
+    Yellow,
+    /// Go!
+    Green,
+}
+
+impl TrafficLightState {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            TrafficLightState::Red => "Red",
+            TrafficLightState::Yellow => "Yellow",
+            TrafficLightState::Green => "Green",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum TrafficLightEvent {
+    TimerExpired = 0,
+}
+
+impl TrafficLightEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub(crate) const EVENT_NAMES: [&'static str; 1] = ["timer_expired"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub(crate) const fn id(&self) -> u8 {
+        match self {
+            TrafficLightEvent::TimerExpired => 0,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            TrafficLightEvent::TimerExpired => "timer_expired",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `TrafficLight::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum TrafficLightProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: TrafficLightState, to: TrafficLightState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl TrafficLightProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub(crate) const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+/// Dispatch counters of `TrafficLight`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TrafficLightMetrics {
+    state_entered: [u16; 3],
+    event_received: [u32; 1],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl TrafficLightMetrics {
+    fn started(initial: TrafficLightState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 3],
+            event_received: [0; 1],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: TrafficLightState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub(crate) fn entered(&self, state: TrafficLightState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: TrafficLightEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub(crate) fn received(&self, event: TrafficLightEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub(crate) fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub(crate) struct TrafficLight<T: TrafficLightActions> {
+    state: TrafficLightState,
+    context: T,
+    started: bool,
+    queue: [Option<TrafficLightEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: TrafficLightMetrics,
+}
+
+impl<T: TrafficLightActions> TrafficLight<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub(crate) fn new(context: T) -> Self {
+        Self {
+            state: TrafficLightState::Red,
+            context,
+            started: false,
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: TrafficLightMetrics::started(TrafficLightState::Red),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub(crate) fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        self.context.activate_red_light();
+        true
+    }
+
+    pub(crate) fn state(&self) -> TrafficLightState {
+        self.state
+    }
+
+    pub(crate) fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub(crate) fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub(crate) fn metrics(&self) -> &TrafficLightMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued
+    /// event is dropped to make room and returned
+    pub(crate) fn post(&mut self, event: TrafficLightEvent) -> Option<TrafficLightEvent> {
+        let mut dropped = None;
+        if self.queue_len == 4 {
+            dropped = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        dropped
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub(crate) fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event).is_handled() {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub(crate) fn process(&mut self, event: TrafficLightEvent) -> TrafficLightProcessResult {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (TrafficLightState::Red, TrafficLightEvent::TimerExpired) if self.context.day_mode() => {
+                self.context.deactivate_red_light();
+                self.state = TrafficLightState::Green;
+                self.context.activate_green_light();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(TrafficLightState::Green);
+                self.context.on_transition(TrafficLightState::Red, TrafficLightEvent::TimerExpired, TrafficLightState::Green);
+                TrafficLightProcessResult::Transitioned { from: TrafficLightState::Red, to: TrafficLightState::Green }
+            }
+            (TrafficLightState::Red, TrafficLightEvent::TimerExpired) if self.context.night_mode() => {
+                self.context.deactivate_red_light();
+                self.state = TrafficLightState::Yellow;
+                self.context.activate_yellow_light();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(TrafficLightState::Yellow);
+                self.context.on_transition(TrafficLightState::Red, TrafficLightEvent::TimerExpired, TrafficLightState::Yellow);
+                TrafficLightProcessResult::Transitioned { from: TrafficLightState::Red, to: TrafficLightState::Yellow }
+            }
+            (TrafficLightState::Green, TrafficLightEvent::TimerExpired) => {
+                self.context.deactivate_green_light();
+                self.state = TrafficLightState::Yellow;
+                self.context.activate_yellow_light();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(TrafficLightState::Yellow);
+                self.context.on_transition(TrafficLightState::Green, TrafficLightEvent::TimerExpired, TrafficLightState::Yellow);
+                TrafficLightProcessResult::Transitioned { from: TrafficLightState::Green, to: TrafficLightState::Yellow }
+            }
+            (TrafficLightState::Yellow, TrafficLightEvent::TimerExpired) => {
+                self.context.deactivate_yellow_light();
+                self.state = TrafficLightState::Red;
+                self.context.activate_red_light();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(TrafficLightState::Red);
+                self.context.on_transition(TrafficLightState::Yellow, TrafficLightEvent::TimerExpired, TrafficLightState::Red);
+                TrafficLightProcessResult::Transitioned { from: TrafficLightState::Yellow, to: TrafficLightState::Red }
+            }
+            (TrafficLightState::Red, TrafficLightEvent::TimerExpired) => TrafficLightProcessResult::GuardRejected,
+            _ => TrafficLightProcessResult::Ignored,
+        }
+    }
+}
+
+#[cfg(feature = "shared-events")]
+impl<T: TrafficLightActions> TrafficLight<T> {
+    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it
+    pub(crate) fn process_shared(&mut self, event: super::events::SharedEvent) -> TrafficLightProcessResult {
+        match TrafficLightEvent::try_from(event) {
+            Ok(event) => self.process(event),
+            Err(_) => TrafficLightProcessResult::Ignored,
+        }
+    }
+
+    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room
+    pub(crate) fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {
+        TrafficLightEvent::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)
+    }
+}
+
+// ── Actions trait ──
+pub(crate) trait TrafficLightActions {
+    fn activate_green_light(&mut self);
+    fn activate_red_light(&mut self);
+    fn activate_yellow_light(&mut self);
+    fn deactivate_green_light(&mut self);
+    fn deactivate_red_light(&mut self);
+    fn deactivate_yellow_light(&mut self);
+    fn day_mode(&self) -> bool;
+    fn night_mode(&self) -> bool;
+
+    /// Called after every successful transition (e.g. for defmt/RTT logging)
+    fn on_transition(&mut self, _from: TrafficLightState, _event: TrafficLightEvent, _to: TrafficLightState) {}
+}
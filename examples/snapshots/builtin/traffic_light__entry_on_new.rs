@@ -0,0 +1,124 @@
+//! Auto-generated FSM: TrafficLight
+//! Generated by Oxidate
+//!
+//! TrafficLightActions methods:
+//! - `fn activate_green_light(&mut self)`: entry of Green
+//! - `fn activate_red_light(&mut self)`: entry of Red
+//! - `fn activate_yellow_light(&mut self)`: entry of Yellow
+//! - `fn day_mode(&self) -> bool`: Red --> Green : timer_expired [day_mode]
+//! - `fn deactivate_green_light(&mut self)`: exit of Green
+//! - `fn deactivate_red_light(&mut self)`: exit of Red
+//! - `fn deactivate_yellow_light(&mut self)`: exit of Yellow
+//! - `fn night_mode(&self) -> bool`: Red --> Yellow : timer_expired [night_mode]
+
+#![no_std]
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrafficLightState {
+    /// Stop - Wait for green
+    Red,
+    /// Caution
+    Yellow,
+    /// Go!
+    Green,
+}
+
+impl TrafficLightState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            TrafficLightState::Red => "Red",
+            TrafficLightState::Yellow => "Yellow",
+            TrafficLightState::Green => "Green",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrafficLightEvent {
+    TimerExpired,
+}
+
+impl TrafficLightEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            TrafficLightEvent::TimerExpired => "timer_expired",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct TrafficLight<T: TrafficLightActions> {
+    state: TrafficLightState,
+    context: T,
+}
+
+impl<T: TrafficLightActions> TrafficLight<T> {
+    /// Create the machine and run its start actions, then the initial state's entry actions
+    pub fn new(mut context: T) -> Self {
+        context.activate_red_light();
+        Self {
+            state: TrafficLightState::Red,
+            context,
+        }
+    }
+
+    pub fn state(&self) -> TrafficLightState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: TrafficLightEvent) -> bool {
+        match (self.state, event) {
+            (TrafficLightState::Red, TrafficLightEvent::TimerExpired) if self.context.day_mode() => {
+                self.context.deactivate_red_light();
+                self.state = TrafficLightState::Green;
+                self.context.activate_green_light();
+                true
+            }
+            (TrafficLightState::Red, TrafficLightEvent::TimerExpired) if self.context.night_mode() => {
+                self.context.deactivate_red_light();
+                self.state = TrafficLightState::Yellow;
+                self.context.activate_yellow_light();
+                true
+            }
+            (TrafficLightState::Green, TrafficLightEvent::TimerExpired) => {
+                self.context.deactivate_green_light();
+                self.state = TrafficLightState::Yellow;
+                self.context.activate_yellow_light();
+                true
+            }
+            (TrafficLightState::Yellow, TrafficLightEvent::TimerExpired) => {
+                self.context.deactivate_yellow_light();
+                self.state = TrafficLightState::Red;
+                self.context.activate_red_light();
+                true
+            }
+            _ => false // No transition
+        }
+    }
+}
+
+// ── Actions trait ──
+pub trait TrafficLightActions {
+    fn activate_green_light(&mut self);
+    fn activate_red_light(&mut self);
+    fn activate_yellow_light(&mut self);
+    fn deactivate_green_light(&mut self);
+    fn deactivate_red_light(&mut self);
+    fn deactivate_yellow_light(&mut self);
+    fn day_mode(&self) -> bool;
+    fn night_mode(&self) -> bool;
+}
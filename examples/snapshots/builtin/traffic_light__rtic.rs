@@ -0,0 +1,16 @@
+//! RTIC code generation requires Oxidate Pro
+//!
+//! FSM: TrafficLight
+//!
+//! To generate RTIC code:
+//!   1. Purchase/access: https://github.com/JoseClaudioSJr/Oxidate/discussions
+//!   2. Then use: oxidate-pro generate --target rtic your_fsm.fsm
+//!
+//! Oxidate Pro includes:
+//!   - Embassy async Active Object pattern
+//!   - RTIC real-time event queues
+//!   - Events with payload
+//!   - HSM hierarchical states
+//!   - Priority support
+
+compile_error!("This target requires Oxidate Pro. Contact: https://github.com/JoseClaudioSJr/Oxidate/discussions");
@@ -0,0 +1,359 @@
+//! Auto-generated FSM: VendingMachine
+//! Generated by Oxidate
+//!
+//! Snapshot of every option
+//!
+//! VendingMachineActions methods:
+//! - `fn add_coin(&mut self)`: AcceptingCoins --> AcceptingCoins : coin_inserted / add_coin
+//! - `fn add_to_balance(&mut self)`: internal of AcceptingCoins : coin_inserted / add_to_balance
+//! - `fn calculate_change(&mut self)`: entry of ReturningChange
+//! - `fn clear_display(&mut self)`: exit of Idle
+//! - `fn dispense_change(&mut self)`: exit of ReturningChange
+//! - `fn dispense_product(&mut self)`: entry of Dispensing
+//! - `fn display_welcome(&mut self)`: entry of Idle
+//! - `fn has_change(&self) -> bool`: Dispensing --> ReturningChange : dispensed [has_change]
+//! - `fn no_change(&self) -> bool`: Dispensing --> Idle : dispensed [no_change]
+//! - `fn on_transition(&mut self, from, event, to)`: every transition
+//! - `fn return_coins(&mut self)`: AcceptingCoins --> Idle : cancel / return_coins
+//! - `fn show_balance(&mut self)`: entry of AcceptingCoins
+//! - `fn sufficient_funds(&self) -> bool`: AcceptingCoins --> Dispensing : select_product [sufficient_funds]
+//! - `fn update_inventory(&mut self)`: exit of Dispensing
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum VendingMachineState {
+    /// Insert coins
+    Idle,
+    /// Accepting payment
+    AcceptingCoins,
+    /// Delivering product
+    Dispensing,
+    /// Giving change
+    ReturningChange,
+}
+
+impl VendingMachineState {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            VendingMachineState::Idle => "Idle",
+            VendingMachineState::AcceptingCoins => "AcceptingCoins",
+            VendingMachineState::Dispensing => "Dispensing",
+            VendingMachineState::ReturningChange => "ReturningChange",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum VendingMachineEvent {
+    Cancel = 0,
+    ChangeReturned = 1,
+    CoinInserted = 2,
+    Dispensed = 3,
+    SelectProduct = 4,
+}
+
+impl VendingMachineEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub(crate) const EVENT_NAMES: [&'static str; 5] = ["cancel", "change_returned", "coin_inserted", "dispensed", "select_product"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub(crate) const fn id(&self) -> u8 {
+        match self {
+            VendingMachineEvent::Cancel => 0,
+            VendingMachineEvent::ChangeReturned => 1,
+            VendingMachineEvent::CoinInserted => 2,
+            VendingMachineEvent::Dispensed => 3,
+            VendingMachineEvent::SelectProduct => 4,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            VendingMachineEvent::Cancel => "cancel",
+            VendingMachineEvent::ChangeReturned => "change_returned",
+            VendingMachineEvent::CoinInserted => "coin_inserted",
+            VendingMachineEvent::Dispensed => "dispensed",
+            VendingMachineEvent::SelectProduct => "select_product",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `VendingMachine::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum VendingMachineProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: VendingMachineState, to: VendingMachineState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl VendingMachineProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub(crate) const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+/// Dispatch counters of `VendingMachine`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct VendingMachineMetrics {
+    state_entered: [u16; 4],
+    event_received: [u32; 5],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl VendingMachineMetrics {
+    fn started(initial: VendingMachineState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 4],
+            event_received: [0; 5],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: VendingMachineState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub(crate) fn entered(&self, state: VendingMachineState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: VendingMachineEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub(crate) fn received(&self, event: VendingMachineEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub(crate) fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub(crate) struct VendingMachine<T: VendingMachineActions> {
+    state: VendingMachineState,
+    context: T,
+    started: bool,
+    queue: [Option<VendingMachineEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: VendingMachineMetrics,
+}
+
+impl<T: VendingMachineActions> VendingMachine<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub(crate) fn new(context: T) -> Self {
+        Self {
+            state: VendingMachineState::Idle,
+            context,
+            started: false,
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: VendingMachineMetrics::started(VendingMachineState::Idle),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub(crate) fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        self.context.display_welcome();
+        true
+    }
+
+    pub(crate) fn state(&self) -> VendingMachineState {
+        self.state
+    }
+
+    pub(crate) fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub(crate) fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub(crate) fn metrics(&self) -> &VendingMachineMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued
+    /// event is dropped to make room and returned
+    pub(crate) fn post(&mut self, event: VendingMachineEvent) -> Option<VendingMachineEvent> {
+        let mut dropped = None;
+        if self.queue_len == 4 {
+            dropped = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        dropped
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub(crate) fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event).is_handled() {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    ///
+    /// A state's own transitions, self-transitions included, are tried before its
+    /// internal transitions, which run only their actions
+    pub(crate) fn process(&mut self, event: VendingMachineEvent) -> VendingMachineProcessResult {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (VendingMachineState::Idle, VendingMachineEvent::CoinInserted) => {
+                self.context.clear_display();
+                self.state = VendingMachineState::AcceptingCoins;
+                self.context.show_balance();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(VendingMachineState::AcceptingCoins);
+                self.context.on_transition(VendingMachineState::Idle, VendingMachineEvent::CoinInserted, VendingMachineState::AcceptingCoins);
+                VendingMachineProcessResult::Transitioned { from: VendingMachineState::Idle, to: VendingMachineState::AcceptingCoins }
+            }
+            (VendingMachineState::AcceptingCoins, VendingMachineEvent::CoinInserted) => {
+                self.context.add_coin();
+                self.state = VendingMachineState::AcceptingCoins;
+                self.context.show_balance();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(VendingMachineState::AcceptingCoins);
+                self.context.on_transition(VendingMachineState::AcceptingCoins, VendingMachineEvent::CoinInserted, VendingMachineState::AcceptingCoins);
+                VendingMachineProcessResult::Transitioned { from: VendingMachineState::AcceptingCoins, to: VendingMachineState::AcceptingCoins }
+            }
+            (VendingMachineState::AcceptingCoins, VendingMachineEvent::SelectProduct) if self.context.sufficient_funds() => {
+                self.state = VendingMachineState::Dispensing;
+                self.context.dispense_product();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(VendingMachineState::Dispensing);
+                self.context.on_transition(VendingMachineState::AcceptingCoins, VendingMachineEvent::SelectProduct, VendingMachineState::Dispensing);
+                VendingMachineProcessResult::Transitioned { from: VendingMachineState::AcceptingCoins, to: VendingMachineState::Dispensing }
+            }
+            (VendingMachineState::AcceptingCoins, VendingMachineEvent::Cancel) => {
+                self.context.return_coins();
+                self.state = VendingMachineState::Idle;
+                self.context.display_welcome();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(VendingMachineState::Idle);
+                self.context.on_transition(VendingMachineState::AcceptingCoins, VendingMachineEvent::Cancel, VendingMachineState::Idle);
+                VendingMachineProcessResult::Transitioned { from: VendingMachineState::AcceptingCoins, to: VendingMachineState::Idle }
+            }
+            (VendingMachineState::Dispensing, VendingMachineEvent::Dispensed) if self.context.has_change() => {
+                self.context.update_inventory();
+                self.state = VendingMachineState::ReturningChange;
+                self.context.calculate_change();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(VendingMachineState::ReturningChange);
+                self.context.on_transition(VendingMachineState::Dispensing, VendingMachineEvent::Dispensed, VendingMachineState::ReturningChange);
+                VendingMachineProcessResult::Transitioned { from: VendingMachineState::Dispensing, to: VendingMachineState::ReturningChange }
+            }
+            (VendingMachineState::Dispensing, VendingMachineEvent::Dispensed) if self.context.no_change() => {
+                self.context.update_inventory();
+                self.state = VendingMachineState::Idle;
+                self.context.display_welcome();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(VendingMachineState::Idle);
+                self.context.on_transition(VendingMachineState::Dispensing, VendingMachineEvent::Dispensed, VendingMachineState::Idle);
+                VendingMachineProcessResult::Transitioned { from: VendingMachineState::Dispensing, to: VendingMachineState::Idle }
+            }
+            (VendingMachineState::ReturningChange, VendingMachineEvent::ChangeReturned) => {
+                self.context.dispense_change();
+                self.state = VendingMachineState::Idle;
+                self.context.display_welcome();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(VendingMachineState::Idle);
+                self.context.on_transition(VendingMachineState::ReturningChange, VendingMachineEvent::ChangeReturned, VendingMachineState::Idle);
+                VendingMachineProcessResult::Transitioned { from: VendingMachineState::ReturningChange, to: VendingMachineState::Idle }
+            }
+            (VendingMachineState::Dispensing, VendingMachineEvent::Dispensed) => VendingMachineProcessResult::GuardRejected,
+            (VendingMachineState::AcceptingCoins, VendingMachineEvent::SelectProduct) => VendingMachineProcessResult::GuardRejected,
+            _ => VendingMachineProcessResult::Ignored,
+        }
+    }
+}
+
+#[cfg(feature = "shared-events")]
+impl<T: VendingMachineActions> VendingMachine<T> {
+    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it
+    pub(crate) fn process_shared(&mut self, event: super::events::SharedEvent) -> VendingMachineProcessResult {
+        match VendingMachineEvent::try_from(event) {
+            Ok(event) => self.process(event),
+            Err(_) => VendingMachineProcessResult::Ignored,
+        }
+    }
+
+    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room
+    pub(crate) fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {
+        VendingMachineEvent::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)
+    }
+}
+
+// ── Actions trait ──
+pub(crate) trait VendingMachineActions {
+    fn add_coin(&mut self);
+    fn add_to_balance(&mut self);
+    fn calculate_change(&mut self);
+    fn clear_display(&mut self);
+    fn dispense_change(&mut self);
+    fn dispense_product(&mut self);
+    fn display_welcome(&mut self);
+    fn return_coins(&mut self);
+    fn show_balance(&mut self);
+    fn update_inventory(&mut self);
+    fn has_change(&self) -> bool;
+    fn no_change(&self) -> bool;
+    fn sufficient_funds(&self) -> bool;
+
+    /// Called after every successful transition (e.g. for defmt/RTT logging)
+    fn on_transition(&mut self, _from: VendingMachineState, _event: VendingMachineEvent, _to: VendingMachineState) {}
+}
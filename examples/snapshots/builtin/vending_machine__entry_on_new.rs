@@ -0,0 +1,165 @@
+//! Auto-generated FSM: VendingMachine
+//! Generated by Oxidate
+//!
+//! VendingMachineActions methods:
+//! - `fn add_coin(&mut self)`: AcceptingCoins --> AcceptingCoins : coin_inserted / add_coin
+//! - `fn add_to_balance(&mut self)`: internal of AcceptingCoins : coin_inserted / add_to_balance
+//! - `fn calculate_change(&mut self)`: entry of ReturningChange
+//! - `fn clear_display(&mut self)`: exit of Idle
+//! - `fn dispense_change(&mut self)`: exit of ReturningChange
+//! - `fn dispense_product(&mut self)`: entry of Dispensing
+//! - `fn display_welcome(&mut self)`: entry of Idle
+//! - `fn has_change(&self) -> bool`: Dispensing --> ReturningChange : dispensed [has_change]
+//! - `fn no_change(&self) -> bool`: Dispensing --> Idle : dispensed [no_change]
+//! - `fn return_coins(&mut self)`: AcceptingCoins --> Idle : cancel / return_coins
+//! - `fn show_balance(&mut self)`: entry of AcceptingCoins
+//! - `fn sufficient_funds(&self) -> bool`: AcceptingCoins --> Dispensing : select_product [sufficient_funds]
+//! - `fn update_inventory(&mut self)`: exit of Dispensing
+
+#![no_std]
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VendingMachineState {
+    /// Insert coins
+    Idle,
+    /// Accepting payment
+    AcceptingCoins,
+    /// Delivering product
+    Dispensing,
+    /// Giving change
+    ReturningChange,
+}
+
+impl VendingMachineState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            VendingMachineState::Idle => "Idle",
+            VendingMachineState::AcceptingCoins => "AcceptingCoins",
+            VendingMachineState::Dispensing => "Dispensing",
+            VendingMachineState::ReturningChange => "ReturningChange",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VendingMachineEvent {
+    Cancel,
+    ChangeReturned,
+    CoinInserted,
+    Dispensed,
+    SelectProduct,
+}
+
+impl VendingMachineEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            VendingMachineEvent::Cancel => "cancel",
+            VendingMachineEvent::ChangeReturned => "change_returned",
+            VendingMachineEvent::CoinInserted => "coin_inserted",
+            VendingMachineEvent::Dispensed => "dispensed",
+            VendingMachineEvent::SelectProduct => "select_product",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct VendingMachine<T: VendingMachineActions> {
+    state: VendingMachineState,
+    context: T,
+}
+
+impl<T: VendingMachineActions> VendingMachine<T> {
+    /// Create the machine and run its start actions, then the initial state's entry actions
+    pub fn new(mut context: T) -> Self {
+        context.display_welcome();
+        Self {
+            state: VendingMachineState::Idle,
+            context,
+        }
+    }
+
+    pub fn state(&self) -> VendingMachineState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    ///
+    /// A state's own transitions, self-transitions included, are tried before its
+    /// internal transitions, which run only their actions
+    pub fn process(&mut self, event: VendingMachineEvent) -> bool {
+        match (self.state, event) {
+            (VendingMachineState::Idle, VendingMachineEvent::CoinInserted) => {
+                self.context.clear_display();
+                self.state = VendingMachineState::AcceptingCoins;
+                self.context.show_balance();
+                true
+            }
+            (VendingMachineState::AcceptingCoins, VendingMachineEvent::CoinInserted) => {
+                self.context.add_coin();
+                self.state = VendingMachineState::AcceptingCoins;
+                self.context.show_balance();
+                true
+            }
+            (VendingMachineState::AcceptingCoins, VendingMachineEvent::SelectProduct) if self.context.sufficient_funds() => {
+                self.state = VendingMachineState::Dispensing;
+                self.context.dispense_product();
+                true
+            }
+            (VendingMachineState::AcceptingCoins, VendingMachineEvent::Cancel) => {
+                self.context.return_coins();
+                self.state = VendingMachineState::Idle;
+                self.context.display_welcome();
+                true
+            }
+            (VendingMachineState::Dispensing, VendingMachineEvent::Dispensed) if self.context.has_change() => {
+                self.context.update_inventory();
+                self.state = VendingMachineState::ReturningChange;
+                self.context.calculate_change();
+                true
+            }
+            (VendingMachineState::Dispensing, VendingMachineEvent::Dispensed) if self.context.no_change() => {
+                self.context.update_inventory();
+                self.state = VendingMachineState::Idle;
+                self.context.display_welcome();
+                true
+            }
+            (VendingMachineState::ReturningChange, VendingMachineEvent::ChangeReturned) => {
+                self.context.dispense_change();
+                self.state = VendingMachineState::Idle;
+                self.context.display_welcome();
+                true
+            }
+            _ => false // No transition
+        }
+    }
+}
+
+// ── Actions trait ──
+pub trait VendingMachineActions {
+    fn add_coin(&mut self);
+    fn add_to_balance(&mut self);
+    fn calculate_change(&mut self);
+    fn clear_display(&mut self);
+    fn dispense_change(&mut self);
+    fn dispense_product(&mut self);
+    fn display_welcome(&mut self);
+    fn return_coins(&mut self);
+    fn show_balance(&mut self);
+    fn update_inventory(&mut self);
+    fn has_change(&self) -> bool;
+    fn no_change(&self) -> bool;
+    fn sufficient_funds(&self) -> bool;
+}
@@ -0,0 +1,437 @@
+//! Auto-generated FSM: ConnectionManager
+//! Generated by Oxidate
+//!
+//! Snapshot of every option
+//!
+//! ConnectionManagerActions methods:
+//! - `fn graceful_close(&mut self)`: Connected --> Disconnected : Disconnect / graceful_close
+//! - `fn initiate_connection(&mut self)`: entry of Connecting
+//! - `fn log_failure(&mut self)`: Connecting --> Disconnected : ConnectionFailed / log_failure
+//! - `fn log_timeout(&mut self)`: Connecting --> Disconnected : ConnectTimeout / log_timeout
+//! - `fn on_connected(&mut self)`: Connecting --> Connected : ConnectionEstablished / on_connected
+//! - `fn on_disconnected(&mut self)`: Connected --> Reconnecting : ConnectionLost / on_disconnected
+//! - `fn on_transition(&mut self, from, event, to)`: every transition
+//! - `fn reset_connection(&mut self)`: entry of Disconnected
+//! - `fn send_keepalive(&mut self)`: internal of Connected : KeepaliveTick / send_keepalive
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum ConnectionManagerState {
+    /// "No active connection"
+    Disconnected,
+    /// "Establishing connection"
+    Connecting,
+    /// "Connection active"
+    Connected,
+    /// "Waiting to reconnect"
+    Reconnecting,
+}
+
+impl ConnectionManagerState {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionManagerState::Disconnected => "Disconnected",
+            ConnectionManagerState::Connecting => "Connecting",
+            ConnectionManagerState::Connected => "Connected",
+            ConnectionManagerState::Reconnecting => "Reconnecting",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum ConnectionManagerEvent {
+    Cancel = 0,
+    Connect = 1,
+    ConnectTimeout = 2,
+    ConnectionEstablished = 3,
+    ConnectionFailed = 4,
+    ConnectionLost = 5,
+    Disconnect = 6,
+    KeepaliveTick = 7,
+    ReconnectTimer = 8,
+}
+
+impl ConnectionManagerEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub(crate) const EVENT_NAMES: [&'static str; 9] = ["Cancel", "Connect", "ConnectTimeout", "ConnectionEstablished", "ConnectionFailed", "ConnectionLost", "Disconnect", "KeepaliveTick", "ReconnectTimer"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub(crate) const fn id(&self) -> u8 {
+        match self {
+            ConnectionManagerEvent::Cancel => 0,
+            ConnectionManagerEvent::Connect => 1,
+            ConnectionManagerEvent::ConnectTimeout => 2,
+            ConnectionManagerEvent::ConnectionEstablished => 3,
+            ConnectionManagerEvent::ConnectionFailed => 4,
+            ConnectionManagerEvent::ConnectionLost => 5,
+            ConnectionManagerEvent::Disconnect => 6,
+            ConnectionManagerEvent::KeepaliveTick => 7,
+            ConnectionManagerEvent::ReconnectTimer => 8,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionManagerEvent::Cancel => "Cancel",
+            ConnectionManagerEvent::Connect => "Connect",
+            ConnectionManagerEvent::ConnectTimeout => "ConnectTimeout",
+            ConnectionManagerEvent::ConnectionEstablished => "ConnectionEstablished",
+            ConnectionManagerEvent::ConnectionFailed => "ConnectionFailed",
+            ConnectionManagerEvent::ConnectionLost => "ConnectionLost",
+            ConnectionManagerEvent::Disconnect => "Disconnect",
+            ConnectionManagerEvent::KeepaliveTick => "KeepaliveTick",
+            ConnectionManagerEvent::ReconnectTimer => "ReconnectTimer",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `ConnectionManager::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum ConnectionManagerProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: ConnectionManagerState, to: ConnectionManagerState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl ConnectionManagerProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub(crate) const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+/// Dispatch counters of `ConnectionManager`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ConnectionManagerMetrics {
+    state_entered: [u16; 4],
+    event_received: [u32; 9],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl ConnectionManagerMetrics {
+    fn started(initial: ConnectionManagerState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 4],
+            event_received: [0; 9],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: ConnectionManagerState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub(crate) fn entered(&self, state: ConnectionManagerState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: ConnectionManagerEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub(crate) fn received(&self, event: ConnectionManagerEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub(crate) fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub(crate) struct ConnectionManager<T: ConnectionManagerActions> {
+    state: ConnectionManagerState,
+    context: T,
+    started: bool,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 3],
+    queue: [Option<ConnectionManagerEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: ConnectionManagerMetrics,
+}
+
+impl<T: ConnectionManagerActions> ConnectionManager<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub(crate) fn new(context: T) -> Self {
+        Self {
+            state: ConnectionManagerState::Disconnected,
+            context,
+            started: false,
+            timers: [None; 3],
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: ConnectionManagerMetrics::started(ConnectionManagerState::Disconnected),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub(crate) fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        self.context.reset_connection();
+        true
+    }
+
+    pub(crate) fn state(&self) -> ConnectionManagerState {
+        self.state
+    }
+
+    pub(crate) fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub(crate) fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub(crate) fn metrics(&self) -> &ConnectionManagerMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued
+    /// event is dropped to make room and returned
+    pub(crate) fn post(&mut self, event: ConnectionManagerEvent) -> Option<ConnectionManagerEvent> {
+        let mut dropped = None;
+        if self.queue_len == 4 {
+            dropped = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        dropped
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub(crate) fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event).is_handled() {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    ///
+    /// A state's own transitions, self-transitions included, are tried before its
+    /// internal transitions, which run only their actions
+    pub(crate) fn process(&mut self, event: ConnectionManagerEvent) -> ConnectionManagerProcessResult {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (ConnectionManagerState::Disconnected, ConnectionManagerEvent::Connect) => {
+                self.timers = [None; 3];
+                self.state = ConnectionManagerState::Connecting;
+                self.context.initiate_connection();
+                self.timers[Self::TIMER_CONNECT_TIMEOUT] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(ConnectionManagerState::Connecting);
+                self.context.on_transition(ConnectionManagerState::Disconnected, ConnectionManagerEvent::Connect, ConnectionManagerState::Connecting);
+                ConnectionManagerProcessResult::Transitioned { from: ConnectionManagerState::Disconnected, to: ConnectionManagerState::Connecting }
+            }
+            (ConnectionManagerState::Connecting, ConnectionManagerEvent::ConnectionEstablished) => {
+                self.timers = [None; 3];
+                self.context.on_connected();
+                self.state = ConnectionManagerState::Connected;
+                self.timers[Self::TIMER_KEEPALIVE] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(ConnectionManagerState::Connected);
+                self.context.on_transition(ConnectionManagerState::Connecting, ConnectionManagerEvent::ConnectionEstablished, ConnectionManagerState::Connected);
+                ConnectionManagerProcessResult::Transitioned { from: ConnectionManagerState::Connecting, to: ConnectionManagerState::Connected }
+            }
+            (ConnectionManagerState::Connecting, ConnectionManagerEvent::ConnectTimeout) => {
+                self.timers = [None; 3];
+                self.context.log_timeout();
+                self.state = ConnectionManagerState::Disconnected;
+                self.context.reset_connection();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(ConnectionManagerState::Disconnected);
+                self.context.on_transition(ConnectionManagerState::Connecting, ConnectionManagerEvent::ConnectTimeout, ConnectionManagerState::Disconnected);
+                ConnectionManagerProcessResult::Transitioned { from: ConnectionManagerState::Connecting, to: ConnectionManagerState::Disconnected }
+            }
+            (ConnectionManagerState::Connecting, ConnectionManagerEvent::ConnectionFailed) => {
+                self.timers = [None; 3];
+                self.context.log_failure();
+                self.state = ConnectionManagerState::Disconnected;
+                self.context.reset_connection();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(ConnectionManagerState::Disconnected);
+                self.context.on_transition(ConnectionManagerState::Connecting, ConnectionManagerEvent::ConnectionFailed, ConnectionManagerState::Disconnected);
+                ConnectionManagerProcessResult::Transitioned { from: ConnectionManagerState::Connecting, to: ConnectionManagerState::Disconnected }
+            }
+            (ConnectionManagerState::Connected, ConnectionManagerEvent::ConnectionLost) => {
+                self.timers = [None; 3];
+                self.context.on_disconnected();
+                self.state = ConnectionManagerState::Reconnecting;
+                self.timers[Self::TIMER_RECONNECT_DELAY] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(ConnectionManagerState::Reconnecting);
+                self.context.on_transition(ConnectionManagerState::Connected, ConnectionManagerEvent::ConnectionLost, ConnectionManagerState::Reconnecting);
+                ConnectionManagerProcessResult::Transitioned { from: ConnectionManagerState::Connected, to: ConnectionManagerState::Reconnecting }
+            }
+            (ConnectionManagerState::Reconnecting, ConnectionManagerEvent::ReconnectTimer) => {
+                self.timers = [None; 3];
+                self.state = ConnectionManagerState::Connecting;
+                self.context.initiate_connection();
+                self.timers[Self::TIMER_CONNECT_TIMEOUT] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(ConnectionManagerState::Connecting);
+                self.context.on_transition(ConnectionManagerState::Reconnecting, ConnectionManagerEvent::ReconnectTimer, ConnectionManagerState::Connecting);
+                ConnectionManagerProcessResult::Transitioned { from: ConnectionManagerState::Reconnecting, to: ConnectionManagerState::Connecting }
+            }
+            (ConnectionManagerState::Connected, ConnectionManagerEvent::Disconnect) => {
+                self.timers = [None; 3];
+                self.context.graceful_close();
+                self.state = ConnectionManagerState::Disconnected;
+                self.context.reset_connection();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(ConnectionManagerState::Disconnected);
+                self.context.on_transition(ConnectionManagerState::Connected, ConnectionManagerEvent::Disconnect, ConnectionManagerState::Disconnected);
+                ConnectionManagerProcessResult::Transitioned { from: ConnectionManagerState::Connected, to: ConnectionManagerState::Disconnected }
+            }
+            (ConnectionManagerState::Connecting, ConnectionManagerEvent::Cancel) => {
+                self.timers = [None; 3];
+                self.state = ConnectionManagerState::Disconnected;
+                self.context.reset_connection();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(ConnectionManagerState::Disconnected);
+                self.context.on_transition(ConnectionManagerState::Connecting, ConnectionManagerEvent::Cancel, ConnectionManagerState::Disconnected);
+                ConnectionManagerProcessResult::Transitioned { from: ConnectionManagerState::Connecting, to: ConnectionManagerState::Disconnected }
+            }
+            (ConnectionManagerState::Reconnecting, ConnectionManagerEvent::Cancel) => {
+                self.timers = [None; 3];
+                self.state = ConnectionManagerState::Disconnected;
+                self.context.reset_connection();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(ConnectionManagerState::Disconnected);
+                self.context.on_transition(ConnectionManagerState::Reconnecting, ConnectionManagerEvent::Cancel, ConnectionManagerState::Disconnected);
+                ConnectionManagerProcessResult::Transitioned { from: ConnectionManagerState::Reconnecting, to: ConnectionManagerState::Disconnected }
+            }
+            (ConnectionManagerState::Connected, ConnectionManagerEvent::KeepaliveTick) => {
+                self.context.send_keepalive();
+                ConnectionManagerProcessResult::InternalHandled
+            }
+            _ => ConnectionManagerProcessResult::Ignored,
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_CONNECT_TIMEOUT: usize = 0;
+    const TIMER_KEEPALIVE: usize = 1;
+    const TIMER_RECONNECT_DELAY: usize = 2;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, ConnectionManagerEvent, bool); 3] = [
+        (10000, ConnectionManagerEvent::ConnectTimeout, false), // connect_timeout
+        (30000, ConnectionManagerEvent::KeepaliveTick, true), // keepalive
+        (5000, ConnectionManagerEvent::ReconnectTimer, false), // reconnect_delay
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub(crate) fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+#[cfg(feature = "shared-events")]
+impl<T: ConnectionManagerActions> ConnectionManager<T> {
+    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it
+    pub(crate) fn process_shared(&mut self, event: super::events::SharedEvent) -> ConnectionManagerProcessResult {
+        match ConnectionManagerEvent::try_from(event) {
+            Ok(event) => self.process(event),
+            Err(_) => ConnectionManagerProcessResult::Ignored,
+        }
+    }
+
+    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room
+    pub(crate) fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {
+        ConnectionManagerEvent::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)
+    }
+}
+
+// ── Actions trait ──
+pub(crate) trait ConnectionManagerActions {
+    fn graceful_close(&mut self);
+    fn initiate_connection(&mut self);
+    fn log_failure(&mut self);
+    fn log_timeout(&mut self);
+    fn on_connected(&mut self);
+    fn on_disconnected(&mut self);
+    fn reset_connection(&mut self);
+    fn send_keepalive(&mut self);
+
+    /// Called after every successful transition (e.g. for defmt/RTT logging)
+    fn on_transition(&mut self, _from: ConnectionManagerState, _event: ConnectionManagerEvent, _to: ConnectionManagerState) {}
+}
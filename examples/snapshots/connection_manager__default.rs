@@ -0,0 +1,246 @@
+//! Auto-generated FSM: ConnectionManager
+//! Generated by Oxidate
+//!
+//! ConnectionManagerActions methods:
+//! - `fn graceful_close(&mut self)`: Connected --> Disconnected : Disconnect / graceful_close
+//! - `fn initiate_connection(&mut self)`: entry of Connecting
+//! - `fn log_failure(&mut self)`: Connecting --> Disconnected : ConnectionFailed / log_failure
+//! - `fn log_timeout(&mut self)`: Connecting --> Disconnected : ConnectTimeout / log_timeout
+//! - `fn on_connected(&mut self)`: Connecting --> Connected : ConnectionEstablished / on_connected
+//! - `fn on_disconnected(&mut self)`: Connected --> Reconnecting : ConnectionLost / on_disconnected
+//! - `fn reset_connection(&mut self)`: entry of Disconnected
+//! - `fn send_keepalive(&mut self)`: internal of Connected : KeepaliveTick / send_keepalive
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConnectionManagerState {
+    /// "No active connection"
+    Disconnected,
+    /// "Establishing connection"
+    Connecting,
+    /// "Connection active"
+    Connected,
+    /// "Waiting to reconnect"
+    Reconnecting,
+}
+
+impl ConnectionManagerState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionManagerState::Disconnected => "Disconnected",
+            ConnectionManagerState::Connecting => "Connecting",
+            ConnectionManagerState::Connected => "Connected",
+            ConnectionManagerState::Reconnecting => "Reconnecting",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConnectionManagerEvent {
+    Cancel,
+    Connect,
+    ConnectTimeout,
+    ConnectionEstablished,
+    ConnectionFailed,
+    ConnectionLost,
+    Disconnect,
+    KeepaliveTick,
+    ReconnectTimer,
+}
+
+impl ConnectionManagerEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionManagerEvent::Cancel => "Cancel",
+            ConnectionManagerEvent::Connect => "Connect",
+            ConnectionManagerEvent::ConnectTimeout => "ConnectTimeout",
+            ConnectionManagerEvent::ConnectionEstablished => "ConnectionEstablished",
+            ConnectionManagerEvent::ConnectionFailed => "ConnectionFailed",
+            ConnectionManagerEvent::ConnectionLost => "ConnectionLost",
+            ConnectionManagerEvent::Disconnect => "Disconnect",
+            ConnectionManagerEvent::KeepaliveTick => "KeepaliveTick",
+            ConnectionManagerEvent::ReconnectTimer => "ReconnectTimer",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct ConnectionManager<T: ConnectionManagerActions> {
+    state: ConnectionManagerState,
+    context: T,
+    started: bool,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 3],
+}
+
+impl<T: ConnectionManagerActions> ConnectionManager<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub fn new(context: T) -> Self {
+        Self {
+            state: ConnectionManagerState::Disconnected,
+            context,
+            started: false,
+            timers: [None; 3],
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        self.context.reset_connection();
+        true
+    }
+
+    pub fn state(&self) -> ConnectionManagerState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    ///
+    /// A state's own transitions, self-transitions included, are tried before its
+    /// internal transitions, which run only their actions
+    pub fn process(&mut self, event: ConnectionManagerEvent) -> bool {
+        match (self.state, event) {
+            (ConnectionManagerState::Disconnected, ConnectionManagerEvent::Connect) => {
+                self.timers = [None; 3];
+                self.state = ConnectionManagerState::Connecting;
+                self.context.initiate_connection();
+                self.timers[Self::TIMER_CONNECT_TIMEOUT] = Some(0);
+                true
+            }
+            (ConnectionManagerState::Connecting, ConnectionManagerEvent::ConnectionEstablished) => {
+                self.timers = [None; 3];
+                self.context.on_connected();
+                self.state = ConnectionManagerState::Connected;
+                self.timers[Self::TIMER_KEEPALIVE] = Some(0);
+                true
+            }
+            (ConnectionManagerState::Connecting, ConnectionManagerEvent::ConnectTimeout) => {
+                self.timers = [None; 3];
+                self.context.log_timeout();
+                self.state = ConnectionManagerState::Disconnected;
+                self.context.reset_connection();
+                true
+            }
+            (ConnectionManagerState::Connecting, ConnectionManagerEvent::ConnectionFailed) => {
+                self.timers = [None; 3];
+                self.context.log_failure();
+                self.state = ConnectionManagerState::Disconnected;
+                self.context.reset_connection();
+                true
+            }
+            (ConnectionManagerState::Connected, ConnectionManagerEvent::ConnectionLost) => {
+                self.timers = [None; 3];
+                self.context.on_disconnected();
+                self.state = ConnectionManagerState::Reconnecting;
+                self.timers[Self::TIMER_RECONNECT_DELAY] = Some(0);
+                true
+            }
+            (ConnectionManagerState::Reconnecting, ConnectionManagerEvent::ReconnectTimer) => {
+                self.timers = [None; 3];
+                self.state = ConnectionManagerState::Connecting;
+                self.context.initiate_connection();
+                self.timers[Self::TIMER_CONNECT_TIMEOUT] = Some(0);
+                true
+            }
+            (ConnectionManagerState::Connected, ConnectionManagerEvent::Disconnect) => {
+                self.timers = [None; 3];
+                self.context.graceful_close();
+                self.state = ConnectionManagerState::Disconnected;
+                self.context.reset_connection();
+                true
+            }
+            (ConnectionManagerState::Connecting, ConnectionManagerEvent::Cancel) => {
+                self.timers = [None; 3];
+                self.state = ConnectionManagerState::Disconnected;
+                self.context.reset_connection();
+                true
+            }
+            (ConnectionManagerState::Reconnecting, ConnectionManagerEvent::Cancel) => {
+                self.timers = [None; 3];
+                self.state = ConnectionManagerState::Disconnected;
+                self.context.reset_connection();
+                true
+            }
+            (ConnectionManagerState::Connected, ConnectionManagerEvent::KeepaliveTick) => {
+                self.context.send_keepalive();
+                true
+            }
+            _ => false // No transition
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_CONNECT_TIMEOUT: usize = 0;
+    const TIMER_KEEPALIVE: usize = 1;
+    const TIMER_RECONNECT_DELAY: usize = 2;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, ConnectionManagerEvent, bool); 3] = [
+        (10000, ConnectionManagerEvent::ConnectTimeout, false), // connect_timeout
+        (30000, ConnectionManagerEvent::KeepaliveTick, true), // keepalive
+        (5000, ConnectionManagerEvent::ReconnectTimer, false), // reconnect_delay
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+// ── Actions trait ──
+pub trait ConnectionManagerActions {
+    fn graceful_close(&mut self);
+    fn initiate_connection(&mut self);
+    fn log_failure(&mut self);
+    fn log_timeout(&mut self);
+    fn on_connected(&mut self);
+    fn on_disconnected(&mut self);
+    fn reset_connection(&mut self);
+    fn send_keepalive(&mut self);
+}
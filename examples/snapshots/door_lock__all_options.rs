@@ -0,0 +1,387 @@
+//! Auto-generated FSM: DoorLock
+//! Generated by Oxidate
+//!
+//! Snapshot of every option
+//!
+//! DoorLockActions methods:
+//! - `fn arm_alarm(&mut self)`: entry of Locked
+//! - `fn authorized(&self) -> bool`: Alarming --> Locked : AlarmReset [authorized]
+//! - `fn disengage_lock(&mut self)`: entry of Unlocked
+//! - `fn engage_lock(&mut self)`: entry of Locked
+//! - `fn notify_security(&mut self)`: entry of Alarming
+//! - `fn on_transition(&mut self, from, event, to)`: every transition
+//! - `fn sound_alarm(&mut self)`: entry of Alarming
+//! - `fn too_many_attempts(&self) -> bool`: Locked --> Alarming : InvalidCode [too_many_attempts]
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum DoorLockState {
+    /// "Door is secured"
+    Locked,
+    /// "Door can be opened"
+    Unlocked,
+    /// "Intrusion detected!"
+    Alarming,
+}
+
+impl DoorLockState {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            DoorLockState::Locked => "Locked",
+            DoorLockState::Unlocked => "Unlocked",
+            DoorLockState::Alarming => "Alarming",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum DoorLockEvent {
+    AlarmReset = 0,
+    AutoLock = 1,
+    InvalidCode = 2,
+    LockButton = 3,
+    TamperDetected = 4,
+    ValidCode = 5,
+}
+
+impl DoorLockEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub(crate) const EVENT_NAMES: [&'static str; 6] = ["AlarmReset", "AutoLock", "InvalidCode", "LockButton", "TamperDetected", "ValidCode"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub(crate) const fn id(&self) -> u8 {
+        match self {
+            DoorLockEvent::AlarmReset => 0,
+            DoorLockEvent::AutoLock => 1,
+            DoorLockEvent::InvalidCode => 2,
+            DoorLockEvent::LockButton => 3,
+            DoorLockEvent::TamperDetected => 4,
+            DoorLockEvent::ValidCode => 5,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            DoorLockEvent::AlarmReset => "AlarmReset",
+            DoorLockEvent::AutoLock => "AutoLock",
+            DoorLockEvent::InvalidCode => "InvalidCode",
+            DoorLockEvent::LockButton => "LockButton",
+            DoorLockEvent::TamperDetected => "TamperDetected",
+            DoorLockEvent::ValidCode => "ValidCode",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `DoorLock::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum DoorLockProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: DoorLockState, to: DoorLockState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl DoorLockProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub(crate) const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+/// Dispatch counters of `DoorLock`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DoorLockMetrics {
+    state_entered: [u16; 3],
+    event_received: [u32; 6],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl DoorLockMetrics {
+    fn started(initial: DoorLockState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 3],
+            event_received: [0; 6],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: DoorLockState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub(crate) fn entered(&self, state: DoorLockState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: DoorLockEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub(crate) fn received(&self, event: DoorLockEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub(crate) fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub(crate) struct DoorLock<T: DoorLockActions> {
+    state: DoorLockState,
+    context: T,
+    started: bool,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 1],
+    queue: [Option<DoorLockEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: DoorLockMetrics,
+}
+
+impl<T: DoorLockActions> DoorLock<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub(crate) fn new(context: T) -> Self {
+        Self {
+            state: DoorLockState::Locked,
+            context,
+            started: false,
+            timers: [None; 1],
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: DoorLockMetrics::started(DoorLockState::Locked),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub(crate) fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        self.context.engage_lock();
+        self.context.arm_alarm();
+        true
+    }
+
+    pub(crate) fn state(&self) -> DoorLockState {
+        self.state
+    }
+
+    pub(crate) fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub(crate) fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub(crate) fn metrics(&self) -> &DoorLockMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued
+    /// event is dropped to make room and returned
+    pub(crate) fn post(&mut self, event: DoorLockEvent) -> Option<DoorLockEvent> {
+        let mut dropped = None;
+        if self.queue_len == 4 {
+            dropped = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        dropped
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub(crate) fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event).is_handled() {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub(crate) fn process(&mut self, event: DoorLockEvent) -> DoorLockProcessResult {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (DoorLockState::Locked, DoorLockEvent::ValidCode) => {
+                self.timers = [None; 1];
+                self.state = DoorLockState::Unlocked;
+                self.context.disengage_lock();
+                self.timers[Self::TIMER_AUTO_LOCK] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(DoorLockState::Unlocked);
+                self.context.on_transition(DoorLockState::Locked, DoorLockEvent::ValidCode, DoorLockState::Unlocked);
+                DoorLockProcessResult::Transitioned { from: DoorLockState::Locked, to: DoorLockState::Unlocked }
+            }
+            (DoorLockState::Unlocked, DoorLockEvent::LockButton) => {
+                self.timers = [None; 1];
+                self.state = DoorLockState::Locked;
+                self.context.engage_lock();
+                self.context.arm_alarm();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(DoorLockState::Locked);
+                self.context.on_transition(DoorLockState::Unlocked, DoorLockEvent::LockButton, DoorLockState::Locked);
+                DoorLockProcessResult::Transitioned { from: DoorLockState::Unlocked, to: DoorLockState::Locked }
+            }
+            (DoorLockState::Unlocked, DoorLockEvent::AutoLock) => {
+                self.timers = [None; 1];
+                self.state = DoorLockState::Locked;
+                self.context.engage_lock();
+                self.context.arm_alarm();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(DoorLockState::Locked);
+                self.context.on_transition(DoorLockState::Unlocked, DoorLockEvent::AutoLock, DoorLockState::Locked);
+                DoorLockProcessResult::Transitioned { from: DoorLockState::Unlocked, to: DoorLockState::Locked }
+            }
+            (DoorLockState::Locked, DoorLockEvent::TamperDetected) => {
+                self.timers = [None; 1];
+                self.state = DoorLockState::Alarming;
+                self.context.sound_alarm();
+                self.context.notify_security();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(DoorLockState::Alarming);
+                self.context.on_transition(DoorLockState::Locked, DoorLockEvent::TamperDetected, DoorLockState::Alarming);
+                DoorLockProcessResult::Transitioned { from: DoorLockState::Locked, to: DoorLockState::Alarming }
+            }
+            (DoorLockState::Locked, DoorLockEvent::InvalidCode) if self.context.too_many_attempts() => {
+                self.timers = [None; 1];
+                self.state = DoorLockState::Alarming;
+                self.context.sound_alarm();
+                self.context.notify_security();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(DoorLockState::Alarming);
+                self.context.on_transition(DoorLockState::Locked, DoorLockEvent::InvalidCode, DoorLockState::Alarming);
+                DoorLockProcessResult::Transitioned { from: DoorLockState::Locked, to: DoorLockState::Alarming }
+            }
+            (DoorLockState::Alarming, DoorLockEvent::AlarmReset) if self.context.authorized() => {
+                self.timers = [None; 1];
+                self.state = DoorLockState::Locked;
+                self.context.engage_lock();
+                self.context.arm_alarm();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(DoorLockState::Locked);
+                self.context.on_transition(DoorLockState::Alarming, DoorLockEvent::AlarmReset, DoorLockState::Locked);
+                DoorLockProcessResult::Transitioned { from: DoorLockState::Alarming, to: DoorLockState::Locked }
+            }
+            (DoorLockState::Alarming, DoorLockEvent::AlarmReset) => DoorLockProcessResult::GuardRejected,
+            (DoorLockState::Locked, DoorLockEvent::InvalidCode) => DoorLockProcessResult::GuardRejected,
+            _ => DoorLockProcessResult::Ignored,
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_AUTO_LOCK: usize = 0;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, DoorLockEvent, bool); 1] = [
+        (30000, DoorLockEvent::AutoLock, false), // auto_lock
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub(crate) fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+#[cfg(feature = "shared-events")]
+impl<T: DoorLockActions> DoorLock<T> {
+    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it
+    pub(crate) fn process_shared(&mut self, event: super::events::SharedEvent) -> DoorLockProcessResult {
+        match DoorLockEvent::try_from(event) {
+            Ok(event) => self.process(event),
+            Err(_) => DoorLockProcessResult::Ignored,
+        }
+    }
+
+    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room
+    pub(crate) fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {
+        DoorLockEvent::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)
+    }
+}
+
+// ── Actions trait ──
+pub(crate) trait DoorLockActions {
+    fn arm_alarm(&mut self);
+    fn disengage_lock(&mut self);
+    fn engage_lock(&mut self);
+    fn notify_security(&mut self);
+    fn sound_alarm(&mut self);
+    fn authorized(&self) -> bool;
+    fn too_many_attempts(&self) -> bool;
+
+    /// Called after every successful transition (e.g. for defmt/RTT logging)
+    fn on_transition(&mut self, _from: DoorLockState, _event: DoorLockEvent, _to: DoorLockState) {}
+}
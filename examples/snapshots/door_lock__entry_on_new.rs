@@ -0,0 +1,195 @@
+//! Auto-generated FSM: DoorLock
+//! Generated by Oxidate
+//!
+//! DoorLockActions methods:
+//! - `fn arm_alarm(&mut self)`: entry of Locked
+//! - `fn authorized(&self) -> bool`: Alarming --> Locked : AlarmReset [authorized]
+//! - `fn disengage_lock(&mut self)`: entry of Unlocked
+//! - `fn engage_lock(&mut self)`: entry of Locked
+//! - `fn notify_security(&mut self)`: entry of Alarming
+//! - `fn sound_alarm(&mut self)`: entry of Alarming
+//! - `fn too_many_attempts(&self) -> bool`: Locked --> Alarming : InvalidCode [too_many_attempts]
+
+#![no_std]
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DoorLockState {
+    /// "Door is secured"
+    Locked,
+    /// "Door can be opened"
+    Unlocked,
+    /// "Intrusion detected!"
+    Alarming,
+}
+
+impl DoorLockState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            DoorLockState::Locked => "Locked",
+            DoorLockState::Unlocked => "Unlocked",
+            DoorLockState::Alarming => "Alarming",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DoorLockEvent {
+    AlarmReset,
+    AutoLock,
+    InvalidCode,
+    LockButton,
+    TamperDetected,
+    ValidCode,
+}
+
+impl DoorLockEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            DoorLockEvent::AlarmReset => "AlarmReset",
+            DoorLockEvent::AutoLock => "AutoLock",
+            DoorLockEvent::InvalidCode => "InvalidCode",
+            DoorLockEvent::LockButton => "LockButton",
+            DoorLockEvent::TamperDetected => "TamperDetected",
+            DoorLockEvent::ValidCode => "ValidCode",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct DoorLock<T: DoorLockActions> {
+    state: DoorLockState,
+    context: T,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 1],
+}
+
+impl<T: DoorLockActions> DoorLock<T> {
+    /// Create the machine and run its start actions, then the initial state's entry actions
+    pub fn new(mut context: T) -> Self {
+        context.engage_lock();
+        context.arm_alarm();
+        Self {
+            state: DoorLockState::Locked,
+            context,
+            timers: [None; 1],
+        }
+    }
+
+    pub fn state(&self) -> DoorLockState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: DoorLockEvent) -> bool {
+        match (self.state, event) {
+            (DoorLockState::Locked, DoorLockEvent::ValidCode) => {
+                self.timers = [None; 1];
+                self.state = DoorLockState::Unlocked;
+                self.context.disengage_lock();
+                self.timers[Self::TIMER_AUTO_LOCK] = Some(0);
+                true
+            }
+            (DoorLockState::Unlocked, DoorLockEvent::LockButton) => {
+                self.timers = [None; 1];
+                self.state = DoorLockState::Locked;
+                self.context.engage_lock();
+                self.context.arm_alarm();
+                true
+            }
+            (DoorLockState::Unlocked, DoorLockEvent::AutoLock) => {
+                self.timers = [None; 1];
+                self.state = DoorLockState::Locked;
+                self.context.engage_lock();
+                self.context.arm_alarm();
+                true
+            }
+            (DoorLockState::Locked, DoorLockEvent::TamperDetected) => {
+                self.timers = [None; 1];
+                self.state = DoorLockState::Alarming;
+                self.context.sound_alarm();
+                self.context.notify_security();
+                true
+            }
+            (DoorLockState::Locked, DoorLockEvent::InvalidCode) if self.context.too_many_attempts() => {
+                self.timers = [None; 1];
+                self.state = DoorLockState::Alarming;
+                self.context.sound_alarm();
+                self.context.notify_security();
+                true
+            }
+            (DoorLockState::Alarming, DoorLockEvent::AlarmReset) if self.context.authorized() => {
+                self.timers = [None; 1];
+                self.state = DoorLockState::Locked;
+                self.context.engage_lock();
+                self.context.arm_alarm();
+                true
+            }
+            _ => false // No transition
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_AUTO_LOCK: usize = 0;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, DoorLockEvent, bool); 1] = [
+        (30000, DoorLockEvent::AutoLock, false), // auto_lock
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+// ── Actions trait ──
+pub trait DoorLockActions {
+    fn arm_alarm(&mut self);
+    fn disengage_lock(&mut self);
+    fn engage_lock(&mut self);
+    fn notify_security(&mut self);
+    fn sound_alarm(&mut self);
+    fn authorized(&self) -> bool;
+    fn too_many_attempts(&self) -> bool;
+}
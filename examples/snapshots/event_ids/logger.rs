@@ -0,0 +1,138 @@
+//! Auto-generated FSM: Logger
+//! Generated by Oxidate
+//!
+//! LoggerActions methods:
+//! - `fn apply_rate(&mut self, hz: u16)`: Running --> Running : set_rate(hz) / apply_rate(hz)
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LoggerState {
+    Idle,
+    Running,
+    Fault,
+}
+
+impl LoggerState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            LoggerState::Idle => "Idle",
+            LoggerState::Running => "Running",
+            LoggerState::Fault => "Fault",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum LoggerEvent {
+    Start = 1,
+    Stop = 2,
+    /// New sample rate
+    SetRate { hz: u16 } = 5,
+    Overheat = 6,
+    Reset = 7,
+}
+
+impl LoggerEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub const EVENT_NAMES: [&'static str; 8] = ["", "start", "stop", "", "", "set_rate", "overheat", "reset"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub const fn id(&self) -> u8 {
+        match self {
+            LoggerEvent::Start => 1,
+            LoggerEvent::Stop => 2,
+            LoggerEvent::SetRate { .. } => 5,
+            LoggerEvent::Overheat => 6,
+            LoggerEvent::Reset => 7,
+        }
+    }
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            LoggerEvent::Start => "start",
+            LoggerEvent::Stop => "stop",
+            LoggerEvent::SetRate { .. } => "set_rate",
+            LoggerEvent::Overheat => "overheat",
+            LoggerEvent::Reset => "reset",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct Logger<T: LoggerActions> {
+    state: LoggerState,
+    context: T,
+    started: bool,
+}
+
+impl<T: LoggerActions> Logger<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub fn new(context: T) -> Self {
+        Self {
+            state: LoggerState::Idle,
+            context,
+            started: false,
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub fn state(&self) -> LoggerState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: LoggerEvent) -> bool {
+        match (self.state, event) {
+            (LoggerState::Idle, LoggerEvent::Start) => {
+                self.state = LoggerState::Running;
+                true
+            }
+            (LoggerState::Running, LoggerEvent::SetRate { hz }) => {
+                self.context.apply_rate(hz);
+                self.state = LoggerState::Running;
+                true
+            }
+            (LoggerState::Running, LoggerEvent::Stop) => {
+                self.state = LoggerState::Idle;
+                true
+            }
+            (LoggerState::Running, LoggerEvent::Overheat) => {
+                self.state = LoggerState::Fault;
+                true
+            }
+            (LoggerState::Fault, LoggerEvent::Reset) => {
+                self.state = LoggerState::Idle;
+                true
+            }
+            _ => false // No transition
+        }
+    }
+}
+
+// ── Actions trait ──
+pub trait LoggerActions {
+    fn apply_rate(&mut self, hz: u16);
+}
@@ -0,0 +1,336 @@
+//! Auto-generated FSM: FormSubmission
+//! Generated by Oxidate
+//!
+//! Snapshot of every option
+//!
+//! FormSubmissionActions methods:
+//! - `fn clear_form(&mut self)`: entry of Success
+//! - `fn on_transition(&mut self, from, event, to)`: every transition
+//! - `fn run_full_validation(&mut self)`: entry of Validating
+//! - `fn send_to_server(&mut self)`: entry of Submitting
+//! - `fn show_error_message(&mut self)`: entry of Error
+//! - `fn show_success_message(&mut self)`: entry of Success
+//! - `fn validate_field(&mut self)`: internal of Editing : DataChanged / validate_field
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum FormSubmissionState {
+    /// "User is filling the form"
+    Editing,
+    /// "Running validation"
+    Validating,
+    /// "Sending to server"
+    Submitting,
+    /// "Submission complete"
+    Success,
+    /// "Submission failed"
+    Error,
+}
+
+impl FormSubmissionState {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            FormSubmissionState::Editing => "Editing",
+            FormSubmissionState::Validating => "Validating",
+            FormSubmissionState::Submitting => "Submitting",
+            FormSubmissionState::Success => "Success",
+            FormSubmissionState::Error => "Error",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum FormSubmissionEvent {
+    DataChanged = 0,
+    Done = 1,
+    NewForm = 2,
+    ResponseReceived = 3,
+    Retry = 4,
+    Submit = 5,
+    ValidationComplete = 6,
+}
+
+impl FormSubmissionEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub(crate) const EVENT_NAMES: [&'static str; 7] = ["DataChanged", "Done", "NewForm", "ResponseReceived", "Retry", "Submit", "ValidationComplete"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub(crate) const fn id(&self) -> u8 {
+        match self {
+            FormSubmissionEvent::DataChanged => 0,
+            FormSubmissionEvent::Done => 1,
+            FormSubmissionEvent::NewForm => 2,
+            FormSubmissionEvent::ResponseReceived => 3,
+            FormSubmissionEvent::Retry => 4,
+            FormSubmissionEvent::Submit => 5,
+            FormSubmissionEvent::ValidationComplete => 6,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            FormSubmissionEvent::DataChanged => "DataChanged",
+            FormSubmissionEvent::Done => "Done",
+            FormSubmissionEvent::NewForm => "NewForm",
+            FormSubmissionEvent::ResponseReceived => "ResponseReceived",
+            FormSubmissionEvent::Retry => "Retry",
+            FormSubmissionEvent::Submit => "Submit",
+            FormSubmissionEvent::ValidationComplete => "ValidationComplete",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `FormSubmission::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum FormSubmissionProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: FormSubmissionState, to: FormSubmissionState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl FormSubmissionProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub(crate) const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+/// Dispatch counters of `FormSubmission`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FormSubmissionMetrics {
+    state_entered: [u16; 5],
+    event_received: [u32; 7],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl FormSubmissionMetrics {
+    fn started(initial: FormSubmissionState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 5],
+            event_received: [0; 7],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: FormSubmissionState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub(crate) fn entered(&self, state: FormSubmissionState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: FormSubmissionEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub(crate) fn received(&self, event: FormSubmissionEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub(crate) fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub(crate) struct FormSubmission<T: FormSubmissionActions> {
+    state: FormSubmissionState,
+    context: T,
+    started: bool,
+    queue: [Option<FormSubmissionEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: FormSubmissionMetrics,
+}
+
+impl<T: FormSubmissionActions> FormSubmission<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub(crate) fn new(context: T) -> Self {
+        Self {
+            state: FormSubmissionState::Editing,
+            context,
+            started: false,
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: FormSubmissionMetrics::started(FormSubmissionState::Editing),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub(crate) fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub(crate) fn state(&self) -> FormSubmissionState {
+        self.state
+    }
+
+    pub(crate) fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub(crate) fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub(crate) fn metrics(&self) -> &FormSubmissionMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued
+    /// event is dropped to make room and returned
+    pub(crate) fn post(&mut self, event: FormSubmissionEvent) -> Option<FormSubmissionEvent> {
+        let mut dropped = None;
+        if self.queue_len == 4 {
+            dropped = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        dropped
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub(crate) fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event).is_handled() {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    ///
+    /// A state's own transitions, self-transitions included, are tried before its
+    /// internal transitions, which run only their actions
+    pub(crate) fn process(&mut self, event: FormSubmissionEvent) -> FormSubmissionProcessResult {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (FormSubmissionState::Editing, FormSubmissionEvent::Submit) => {
+                self.state = FormSubmissionState::Validating;
+                self.context.run_full_validation();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(FormSubmissionState::Validating);
+                self.context.on_transition(FormSubmissionState::Editing, FormSubmissionEvent::Submit, FormSubmissionState::Validating);
+                FormSubmissionProcessResult::Transitioned { from: FormSubmissionState::Editing, to: FormSubmissionState::Validating }
+            }
+            (FormSubmissionState::Validating, FormSubmissionEvent::ValidationComplete) => {
+                self.state = FormSubmissionState::<<ValidationResult>>;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(FormSubmissionState::<<ValidationResult>>);
+                self.context.on_transition(FormSubmissionState::Validating, FormSubmissionEvent::ValidationComplete, FormSubmissionState::<<ValidationResult>>);
+                FormSubmissionProcessResult::Transitioned { from: FormSubmissionState::Validating, to: FormSubmissionState::<<ValidationResult>> }
+            }
+            (FormSubmissionState::Submitting, FormSubmissionEvent::ResponseReceived) => {
+                self.state = FormSubmissionState::<<SubmitResult>>;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(FormSubmissionState::<<SubmitResult>>);
+                self.context.on_transition(FormSubmissionState::Submitting, FormSubmissionEvent::ResponseReceived, FormSubmissionState::<<SubmitResult>>);
+                FormSubmissionProcessResult::Transitioned { from: FormSubmissionState::Submitting, to: FormSubmissionState::<<SubmitResult>> }
+            }
+            (FormSubmissionState::Error, FormSubmissionEvent::Retry) => {
+                self.state = FormSubmissionState::Editing;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(FormSubmissionState::Editing);
+                self.context.on_transition(FormSubmissionState::Error, FormSubmissionEvent::Retry, FormSubmissionState::Editing);
+                FormSubmissionProcessResult::Transitioned { from: FormSubmissionState::Error, to: FormSubmissionState::Editing }
+            }
+            (FormSubmissionState::Success, FormSubmissionEvent::NewForm) => {
+                self.state = FormSubmissionState::Editing;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(FormSubmissionState::Editing);
+                self.context.on_transition(FormSubmissionState::Success, FormSubmissionEvent::NewForm, FormSubmissionState::Editing);
+                FormSubmissionProcessResult::Transitioned { from: FormSubmissionState::Success, to: FormSubmissionState::Editing }
+            }
+            (FormSubmissionState::Success, FormSubmissionEvent::Done) => {
+                self.state = FormSubmissionState::[*];
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(FormSubmissionState::[*]);
+                self.context.on_transition(FormSubmissionState::Success, FormSubmissionEvent::Done, FormSubmissionState::[*]);
+                FormSubmissionProcessResult::Transitioned { from: FormSubmissionState::Success, to: FormSubmissionState::[*] }
+            }
+            (FormSubmissionState::Editing, FormSubmissionEvent::DataChanged) => {
+                self.context.validate_field();
+                FormSubmissionProcessResult::InternalHandled
+            }
+            _ => FormSubmissionProcessResult::Ignored,
+        }
+    }
+}
+
+#[cfg(feature = "shared-events")]
+impl<T: FormSubmissionActions> FormSubmission<T> {
+    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it
+    pub(crate) fn process_shared(&mut self, event: super::events::SharedEvent) -> FormSubmissionProcessResult {
+        match FormSubmissionEvent::try_from(event) {
+            Ok(event) => self.process(event),
+            Err(_) => FormSubmissionProcessResult::Ignored,
+        }
+    }
+
+    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room
+    pub(crate) fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {
+        FormSubmissionEvent::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)
+    }
+}
+
+// ── Actions trait ──
+pub(crate) trait FormSubmissionActions {
+    fn clear_form(&mut self);
+    fn run_full_validation(&mut self);
+    fn send_to_server(&mut self);
+    fn show_error_message(&mut self);
+    fn show_success_message(&mut self);
+    fn validate_field(&mut self);
+
+    /// Called after every successful transition (e.g. for defmt/RTT logging)
+    fn on_transition(&mut self, _from: FormSubmissionState, _event: FormSubmissionEvent, _to: FormSubmissionState) {}
+}
@@ -0,0 +1,156 @@
+//! Auto-generated FSM: FormSubmission
+//! Generated by Oxidate
+//!
+//! FormSubmissionActions methods:
+//! - `fn clear_form(&mut self)`: entry of Success
+//! - `fn run_full_validation(&mut self)`: entry of Validating
+//! - `fn send_to_server(&mut self)`: entry of Submitting
+//! - `fn show_error_message(&mut self)`: entry of Error
+//! - `fn show_success_message(&mut self)`: entry of Success
+//! - `fn validate_field(&mut self)`: internal of Editing : DataChanged / validate_field
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FormSubmissionState {
+    /// "User is filling the form"
+    Editing,
+    /// "Running validation"
+    Validating,
+    /// "Sending to server"
+    Submitting,
+    /// "Submission complete"
+    Success,
+    /// "Submission failed"
+    Error,
+}
+
+impl FormSubmissionState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            FormSubmissionState::Editing => "Editing",
+            FormSubmissionState::Validating => "Validating",
+            FormSubmissionState::Submitting => "Submitting",
+            FormSubmissionState::Success => "Success",
+            FormSubmissionState::Error => "Error",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FormSubmissionEvent {
+    DataChanged,
+    Done,
+    NewForm,
+    ResponseReceived,
+    Retry,
+    Submit,
+    ValidationComplete,
+}
+
+impl FormSubmissionEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            FormSubmissionEvent::DataChanged => "DataChanged",
+            FormSubmissionEvent::Done => "Done",
+            FormSubmissionEvent::NewForm => "NewForm",
+            FormSubmissionEvent::ResponseReceived => "ResponseReceived",
+            FormSubmissionEvent::Retry => "Retry",
+            FormSubmissionEvent::Submit => "Submit",
+            FormSubmissionEvent::ValidationComplete => "ValidationComplete",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct FormSubmission<T: FormSubmissionActions> {
+    state: FormSubmissionState,
+    context: T,
+    started: bool,
+}
+
+impl<T: FormSubmissionActions> FormSubmission<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub fn new(context: T) -> Self {
+        Self {
+            state: FormSubmissionState::Editing,
+            context,
+            started: false,
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub fn state(&self) -> FormSubmissionState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    ///
+    /// A state's own transitions, self-transitions included, are tried before its
+    /// internal transitions, which run only their actions
+    pub fn process(&mut self, event: FormSubmissionEvent) -> bool {
+        match (self.state, event) {
+            (FormSubmissionState::Editing, FormSubmissionEvent::Submit) => {
+                self.state = FormSubmissionState::Validating;
+                self.context.run_full_validation();
+                true
+            }
+            (FormSubmissionState::Validating, FormSubmissionEvent::ValidationComplete) => {
+                self.state = FormSubmissionState::<<ValidationResult>>;
+                true
+            }
+            (FormSubmissionState::Submitting, FormSubmissionEvent::ResponseReceived) => {
+                self.state = FormSubmissionState::<<SubmitResult>>;
+                true
+            }
+            (FormSubmissionState::Error, FormSubmissionEvent::Retry) => {
+                self.state = FormSubmissionState::Editing;
+                true
+            }
+            (FormSubmissionState::Success, FormSubmissionEvent::NewForm) => {
+                self.state = FormSubmissionState::Editing;
+                true
+            }
+            (FormSubmissionState::Success, FormSubmissionEvent::Done) => {
+                self.state = FormSubmissionState::[*];
+                true
+            }
+            (FormSubmissionState::Editing, FormSubmissionEvent::DataChanged) => {
+                self.context.validate_field();
+                true
+            }
+            _ => false // No transition
+        }
+    }
+}
+
+// ── Actions trait ──
+pub trait FormSubmissionActions {
+    fn clear_form(&mut self);
+    fn run_full_validation(&mut self);
+    fn send_to_server(&mut self);
+    fn show_error_message(&mut self);
+    fn show_success_message(&mut self);
+    fn validate_field(&mut self);
+}
@@ -0,0 +1,86 @@
+// Auto-generated FSM: Bootloader
+// Generated by Oxidate
+
+#include "bootloader.h"
+
+/// `recovery_pin_set || !image_valid`
+static bool bootloader_guard_recovery(const bootloader_t *fsm)
+{
+    return fsm->actions->recovery_pin_set(fsm->user) || !fsm->actions->image_valid(fsm->user);
+}
+
+// ── Dispatch ──
+void bootloader_init(bootloader_t *fsm, const bootloader_actions_t *actions, void *user)
+{
+    fsm->state = BOOTLOADER_STATE_IDLE;
+    fsm->actions = actions;
+    fsm->user = user;
+    if (bootloader_guard_recovery(fsm)) {
+        fsm->state = BOOTLOADER_STATE_RECOVERY;
+        actions->open_console(user);
+    } else if (actions->update_pending(user)) {
+        fsm->state = BOOTLOADER_STATE_UPDATING;
+        actions->log_update(user);
+    } else {
+        actions->init_clocks(user);
+        actions->jump_to_app(user);
+    }
+}
+
+bool bootloader_dispatch(bootloader_t *fsm, bootloader_event_t event)
+{
+    switch (fsm->state) {
+    case BOOTLOADER_STATE_IDLE:
+        if (event == BOOTLOADER_EVENT_UPDATE_REQUESTED) {
+            fsm->state = BOOTLOADER_STATE_UPDATING;
+            return true;
+        }
+        break;
+    case BOOTLOADER_STATE_RECOVERY:
+        if (event == BOOTLOADER_EVENT_REBOOT) {
+            fsm->state = BOOTLOADER_STATE_IDLE;
+            fsm->actions->jump_to_app(fsm->user);
+            return true;
+        }
+        break;
+    case BOOTLOADER_STATE_UPDATING:
+        if (event == BOOTLOADER_EVENT_DONE) {
+            fsm->state = BOOTLOADER_STATE_IDLE;
+            fsm->actions->jump_to_app(fsm->user);
+            return true;
+        }
+        break;
+    default:
+        break;
+    }
+    return false;
+}
+
+// ── Names ──
+const char *bootloader_state_name(bootloader_state_t state)
+{
+    switch (state) {
+    case BOOTLOADER_STATE_IDLE:
+        return "Idle";
+    case BOOTLOADER_STATE_RECOVERY:
+        return "Recovery";
+    case BOOTLOADER_STATE_UPDATING:
+        return "Updating";
+    default:
+        return "";
+    }
+}
+
+const char *bootloader_event_name(bootloader_event_t event)
+{
+    switch (event) {
+    case BOOTLOADER_EVENT_DONE:
+        return "done";
+    case BOOTLOADER_EVENT_REBOOT:
+        return "reboot";
+    case BOOTLOADER_EVENT_UPDATE_REQUESTED:
+        return "update_requested";
+    default:
+        return "";
+    }
+}
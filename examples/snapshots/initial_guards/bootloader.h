@@ -0,0 +1,65 @@
+// Auto-generated FSM: Bootloader
+// Generated by Oxidate
+
+#ifndef BOOTLOADER_H
+#define BOOTLOADER_H
+
+#include <stdbool.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// ── States ──
+typedef enum {
+    BOOTLOADER_STATE_IDLE,
+    BOOTLOADER_STATE_RECOVERY,
+    BOOTLOADER_STATE_UPDATING,
+    BOOTLOADER_STATE_COUNT
+} bootloader_state_t;
+
+// ── Events ──
+typedef enum {
+    BOOTLOADER_EVENT_DONE,
+    BOOTLOADER_EVENT_REBOOT,
+    BOOTLOADER_EVENT_UPDATE_REQUESTED,
+    BOOTLOADER_EVENT_COUNT
+} bootloader_event_t;
+
+// ── Actions table ──
+/// Actions and guards the machine calls, each with the `user` pointer given to
+/// bootloader_init()
+typedef struct {
+    void (*init_clocks)(void *user);
+    void (*jump_to_app)(void *user);
+    void (*log_update)(void *user);
+    void (*open_console)(void *user);
+    bool (*image_valid)(void *user);
+    bool (*recovery_pin_set)(void *user);
+    bool (*update_pending)(void *user);
+} bootloader_actions_t;
+
+// ── Machine ──
+typedef struct {
+    bootloader_state_t state;
+    const bootloader_actions_t *actions;
+    void *user;
+} bootloader_t;
+
+/// Put the machine in its initial state, then run the start actions and the
+/// initial state's entry actions; every call passes `user` back
+void bootloader_init(bootloader_t *fsm, const bootloader_actions_t *actions, void *user);
+/// Process one event; returns whether a transition took it
+///
+/// A transition runs the exit actions of the state it leaves, then its own
+/// actions, then the entry actions of the state it enters (UML order)
+bool bootloader_dispatch(bootloader_t *fsm, bootloader_event_t event);
+/// Names as written in the DSL
+const char *bootloader_state_name(bootloader_state_t state);
+const char *bootloader_event_name(bootloader_event_t event);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // BOOTLOADER_H
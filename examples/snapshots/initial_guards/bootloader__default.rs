@@ -0,0 +1,142 @@
+//! Auto-generated FSM: Bootloader
+//! Generated by Oxidate
+//!
+//! BootloaderActions methods:
+//! - `fn image_valid(&self) -> bool`: guard recovery
+//! - `fn init_clocks(&mut self)`: start ([*] --> Idle)
+//! - `fn jump_to_app(&mut self)`: entry of Idle
+//! - `fn log_update(&mut self)`: start ([*] --> Updating : [update_pending])
+//! - `fn open_console(&mut self)`: entry of Recovery
+//! - `fn recovery(&self) -> bool`: start ([*] --> Recovery : [recovery]), guard recovery = recovery_pin_set || !image_valid
+//! - `fn recovery_pin_set(&self) -> bool`: guard recovery
+//! - `fn update_pending(&self) -> bool`: start ([*] --> Updating : [update_pending])
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BootloaderState {
+    Idle,
+    Recovery,
+    Updating,
+}
+
+impl BootloaderState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            BootloaderState::Idle => "Idle",
+            BootloaderState::Recovery => "Recovery",
+            BootloaderState::Updating => "Updating",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BootloaderEvent {
+    Done,
+    Reboot,
+    UpdateRequested,
+}
+
+impl BootloaderEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            BootloaderEvent::Done => "done",
+            BootloaderEvent::Reboot => "reboot",
+            BootloaderEvent::UpdateRequested => "update_requested",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct Bootloader<T: BootloaderActions> {
+    state: BootloaderState,
+    context: T,
+    started: bool,
+}
+
+impl<T: BootloaderActions> Bootloader<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub fn new(context: T) -> Self {
+        Self {
+            state: BootloaderState::Idle,
+            context,
+            started: false,
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    ///
+    /// The guarded initial transitions are tried in order: the first whose guard
+    /// holds picks the initial state, else the machine stays in the default one
+    pub fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        if self.context.recovery() {
+            self.state = BootloaderState::Recovery;
+            self.context.open_console();
+        } else if self.context.update_pending() {
+            self.state = BootloaderState::Updating;
+            self.context.log_update();
+        } else {
+            self.context.init_clocks();
+            self.context.jump_to_app();
+        }
+        true
+    }
+
+    pub fn state(&self) -> BootloaderState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: BootloaderEvent) -> bool {
+        match (self.state, event) {
+            (BootloaderState::Idle, BootloaderEvent::UpdateRequested) => {
+                self.state = BootloaderState::Updating;
+                true
+            }
+            (BootloaderState::Updating, BootloaderEvent::Done) => {
+                self.state = BootloaderState::Idle;
+                self.context.jump_to_app();
+                true
+            }
+            (BootloaderState::Recovery, BootloaderEvent::Reboot) => {
+                self.state = BootloaderState::Idle;
+                self.context.jump_to_app();
+                true
+            }
+            _ => false // No transition
+        }
+    }
+}
+
+// ── Actions trait ──
+pub trait BootloaderActions {
+    fn init_clocks(&mut self);
+    fn jump_to_app(&mut self);
+    fn log_update(&mut self);
+    fn open_console(&mut self);
+    fn image_valid(&self) -> bool;
+    fn recovery_pin_set(&self) -> bool;
+    fn update_pending(&self) -> bool;
+
+    /// `recovery_pin_set || !image_valid`
+    fn recovery(&self) -> bool {
+        self.recovery_pin_set() || !self.image_valid()
+    }
+}
@@ -0,0 +1,186 @@
+//! Auto-generated FSM: Bootloader
+//! Generated by Oxidate
+//!
+//! BootloaderActions methods:
+//! - `fn image_valid(&self) -> bool`: guard recovery
+//! - `fn init_clocks(&mut self)`: start ([*] --> Idle)
+//! - `fn jump_to_app(&mut self)`: entry of Idle
+//! - `fn log_update(&mut self)`: start ([*] --> Updating : [update_pending])
+//! - `fn open_console(&mut self)`: entry of Recovery
+//! - `fn recovery(&self) -> bool`: start ([*] --> Recovery : [recovery]), guard recovery = recovery_pin_set || !image_valid
+//! - `fn recovery_pin_set(&self) -> bool`: guard recovery
+//! - `fn update_pending(&self) -> bool`: start ([*] --> Updating : [update_pending])
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BootloaderState {
+    Idle,
+    Recovery,
+    Updating,
+}
+
+impl BootloaderState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            BootloaderState::Idle => "Idle",
+            BootloaderState::Recovery => "Recovery",
+            BootloaderState::Updating => "Updating",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BootloaderEvent {
+    Done,
+    Reboot,
+    UpdateRequested,
+}
+
+impl BootloaderEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            BootloaderEvent::Done => "done",
+            BootloaderEvent::Reboot => "reboot",
+            BootloaderEvent::UpdateRequested => "update_requested",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// Dispatch counters of `Bootloader`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootloaderMetrics {
+    state_entered: [u16; 3],
+    event_received: [u32; 3],
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl BootloaderMetrics {
+    fn started(initial: BootloaderState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 3],
+            event_received: [0; 3],
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: BootloaderState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub fn entered(&self, state: BootloaderState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: BootloaderEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub fn received(&self, event: BootloaderEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+}
+
+pub struct Bootloader<T: BootloaderActions> {
+    state: BootloaderState,
+    context: T,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: BootloaderMetrics,
+}
+
+impl<T: BootloaderActions> Bootloader<T> {
+    /// Create the machine and run its start actions, then the initial state's entry actions
+    /// (those of the first initial transition whose guard holds, else of the default)
+    pub fn new(mut context: T) -> Self {
+        let state = if context.recovery() {
+            context.open_console();
+            BootloaderState::Recovery
+        } else if context.update_pending() {
+            context.log_update();
+            BootloaderState::Updating
+        } else {
+            context.init_clocks();
+            context.jump_to_app();
+            BootloaderState::Idle
+        };
+        Self {
+            state,
+            context,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: BootloaderMetrics::started(state),
+        }
+    }
+
+    pub fn state(&self) -> BootloaderState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub fn metrics(&self) -> &BootloaderMetrics {
+        &self.metrics
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: BootloaderEvent) -> bool {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (BootloaderState::Idle, BootloaderEvent::UpdateRequested) => {
+                self.state = BootloaderState::Updating;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(BootloaderState::Updating);
+                true
+            }
+            (BootloaderState::Updating, BootloaderEvent::Done) => {
+                self.state = BootloaderState::Idle;
+                self.context.jump_to_app();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(BootloaderState::Idle);
+                true
+            }
+            (BootloaderState::Recovery, BootloaderEvent::Reboot) => {
+                self.state = BootloaderState::Idle;
+                self.context.jump_to_app();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(BootloaderState::Idle);
+                true
+            }
+            _ => false // No transition
+        }
+    }
+}
+
+// ── Actions trait ──
+pub trait BootloaderActions {
+    fn init_clocks(&mut self);
+    fn jump_to_app(&mut self);
+    fn log_update(&mut self);
+    fn open_console(&mut self);
+    fn image_valid(&self) -> bool;
+    fn recovery_pin_set(&self) -> bool;
+    fn update_pending(&self) -> bool;
+
+    /// `recovery_pin_set || !image_valid`
+    fn recovery(&self) -> bool {
+        self.recovery_pin_set() || !self.image_valid()
+    }
+}
@@ -0,0 +1,82 @@
+// Auto-generated FSM: Motor
+// Generated by Oxidate
+
+#include "motor.h"
+
+// ── Dispatch ──
+void motor_init(motor_t *fsm, const motor_actions_t *actions, void *user)
+{
+    fsm->state = MOTOR_STATE_IDLE;
+    fsm->actions = actions;
+    fsm->user = user;
+}
+
+bool motor_dispatch(motor_t *fsm, motor_event_t event)
+{
+    return motor_dispatch_args(fsm, event, NULL);
+}
+
+bool motor_dispatch_args(motor_t *fsm, motor_event_t event, const motor_event_args_t *args)
+{
+    switch (fsm->state) {
+    case MOTOR_STATE_IDLE:
+        if (event == MOTOR_EVENT_START) {
+            fsm->state = MOTOR_STATE_RUNNING;
+            return true;
+        }
+        break;
+    case MOTOR_STATE_RUNNING:
+        if (event == MOTOR_EVENT_SET_SPEED && args != NULL && fsm->actions->speed_ok(fsm->user, args->set_speed.rpm)) {
+            fsm->actions->apply_speed(fsm->user, args->set_speed.rpm);
+            fsm->state = MOTOR_STATE_RUNNING;
+            return true;
+        }
+        if (event == MOTOR_EVENT_SET_LIMITS && args != NULL) {
+            fsm->actions->apply_limits(fsm->user, args->set_limits.high, args->set_limits.low);
+            fsm->state = MOTOR_STATE_RUNNING;
+            return true;
+        }
+        if (event == MOTOR_EVENT_STOP) {
+            fsm->actions->brake(fsm->user);
+            fsm->state = MOTOR_STATE_IDLE;
+            return true;
+        }
+        if (event == MOTOR_EVENT_SET_SPEED && args != NULL) {
+            fsm->actions->log_speed(fsm->user, args->set_speed.rpm);
+            return true;
+        }
+        break;
+    default:
+        break;
+    }
+    return false;
+}
+
+// ── Names ──
+const char *motor_state_name(motor_state_t state)
+{
+    switch (state) {
+    case MOTOR_STATE_IDLE:
+        return "Idle";
+    case MOTOR_STATE_RUNNING:
+        return "Running";
+    default:
+        return "";
+    }
+}
+
+const char *motor_event_name(motor_event_t event)
+{
+    switch (event) {
+    case MOTOR_EVENT_START:
+        return "start";
+    case MOTOR_EVENT_SET_SPEED:
+        return "set_speed";
+    case MOTOR_EVENT_SET_LIMITS:
+        return "set_limits";
+    case MOTOR_EVENT_STOP:
+        return "stop";
+    default:
+        return "";
+    }
+}
@@ -0,0 +1,87 @@
+// Auto-generated FSM: Motor
+// Generated by Oxidate
+
+#ifndef MOTOR_H
+#define MOTOR_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// ── States ──
+typedef enum {
+    MOTOR_STATE_IDLE,
+    MOTOR_STATE_RUNNING,
+    MOTOR_STATE_COUNT
+} motor_state_t;
+
+// ── Events ──
+typedef enum {
+    MOTOR_EVENT_START,
+    /// New target speed
+    MOTOR_EVENT_SET_SPEED,
+    MOTOR_EVENT_SET_LIMITS,
+    MOTOR_EVENT_STOP,
+    MOTOR_EVENT_COUNT
+} motor_event_t;
+
+/// Payload of set_speed
+typedef struct {
+    uint16_t rpm;
+} motor_set_speed_args_t;
+
+/// Payload of set_limits
+typedef struct {
+    uint16_t low;
+    uint16_t high;
+} motor_set_limits_args_t;
+
+/// Payload of the event being dispatched
+typedef union {
+    motor_set_speed_args_t set_speed;
+    motor_set_limits_args_t set_limits;
+} motor_event_args_t;
+
+// ── Actions table ──
+/// Actions and guards the machine calls, each with the `user` pointer given to
+/// motor_init()
+typedef struct {
+    void (*apply_limits)(void *user, uint16_t high, uint16_t low);
+    void (*apply_speed)(void *user, uint16_t rpm);
+    void (*brake)(void *user);
+    void (*log_limits)(void *user);
+    void (*log_speed)(void *user, uint16_t target);
+    bool (*speed_ok)(void *user, uint16_t rpm);
+} motor_actions_t;
+
+// ── Machine ──
+typedef struct {
+    motor_state_t state;
+    const motor_actions_t *actions;
+    void *user;
+} motor_t;
+
+/// Put the machine in its initial state, then run the start actions and the
+/// initial state's entry actions; every call passes `user` back
+void motor_init(motor_t *fsm, const motor_actions_t *actions, void *user);
+/// Process one event; returns whether a transition took it
+///
+/// A transition runs the exit actions of the state it leaves, then its own
+/// actions, then the entry actions of the state it enters (UML order)
+bool motor_dispatch(motor_t *fsm, motor_event_t event);
+/// Process one event with its payload (`args` may be NULL for an event without one;
+/// transitions that pass a payload field on are skipped then)
+bool motor_dispatch_args(motor_t *fsm, motor_event_t event, const motor_event_args_t *args);
+/// Names as written in the DSL
+const char *motor_state_name(motor_state_t state);
+const char *motor_event_name(motor_event_t event);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // MOTOR_H
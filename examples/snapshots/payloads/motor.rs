@@ -0,0 +1,134 @@
+//! Auto-generated FSM: Motor
+//! Generated by Oxidate
+//!
+//! MotorActions methods:
+//! - `fn apply_limits(&mut self, high: u16, low: u16)`: Running --> Running : set_limits(low, high) / apply_limits(high, low)
+//! - `fn apply_speed(&mut self, rpm: u16)`: Running --> Running : set_speed(rpm) [speed_ok(rpm)] / apply_speed(rpm)
+//! - `fn brake(&mut self)`: Running --> Idle : stop / brake
+//! - `fn log_limits(&mut self)`: internal of Running : set_limits / log_limits
+//! - `fn log_speed(&mut self, target: u16)`: internal of Running : set_speed(target) / log_speed(target)
+//! - `fn speed_ok(&self, rpm: u16) -> bool`: Running --> Running : set_speed(rpm) [speed_ok(rpm)] / apply_speed(rpm)
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MotorState {
+    Idle,
+    Running,
+}
+
+impl MotorState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            MotorState::Idle => "Idle",
+            MotorState::Running => "Running",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MotorEvent {
+    Start,
+    /// New target speed
+    SetSpeed { rpm: u16 },
+    SetLimits { low: u16, high: u16 },
+    Stop,
+}
+
+impl MotorEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            MotorEvent::Start => "start",
+            MotorEvent::SetSpeed { .. } => "set_speed",
+            MotorEvent::SetLimits { .. } => "set_limits",
+            MotorEvent::Stop => "stop",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct Motor<T: MotorActions> {
+    state: MotorState,
+    context: T,
+    started: bool,
+}
+
+impl<T: MotorActions> Motor<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub fn new(context: T) -> Self {
+        Self {
+            state: MotorState::Idle,
+            context,
+            started: false,
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub fn state(&self) -> MotorState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    ///
+    /// A state's own transitions, self-transitions included, are tried before its
+    /// internal transitions, which run only their actions
+    pub fn process(&mut self, event: MotorEvent) -> bool {
+        match (self.state, event) {
+            (MotorState::Idle, MotorEvent::Start) => {
+                self.state = MotorState::Running;
+                true
+            }
+            (MotorState::Running, MotorEvent::SetSpeed { rpm }) if self.context.speed_ok(rpm) => {
+                self.context.apply_speed(rpm);
+                self.state = MotorState::Running;
+                true
+            }
+            (MotorState::Running, MotorEvent::SetLimits { low, high }) => {
+                self.context.apply_limits(high, low);
+                self.state = MotorState::Running;
+                true
+            }
+            (MotorState::Running, MotorEvent::Stop) => {
+                self.context.brake();
+                self.state = MotorState::Idle;
+                true
+            }
+            (MotorState::Running, MotorEvent::SetSpeed { rpm: target }) => {
+                self.context.log_speed(target);
+                true
+            }
+            _ => false // No transition
+        }
+    }
+}
+
+// ── Actions trait ──
+pub trait MotorActions {
+    fn apply_limits(&mut self, high: u16, low: u16);
+    fn apply_speed(&mut self, rpm: u16);
+    fn brake(&mut self);
+    fn log_limits(&mut self);
+    fn log_speed(&mut self, target: u16);
+    fn speed_ok(&self, rpm: u16) -> bool;
+}
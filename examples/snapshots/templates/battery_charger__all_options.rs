@@ -0,0 +1,361 @@
+//! Auto-generated FSM: BatteryCharger
+//! Generated by Oxidate
+//!
+//! Snapshot of every option
+//!
+//! BatteryChargerActions methods:
+//! - `fn charger_off(&mut self)`: entry of Charged, entry of Fault
+//! - `fn hold_voltage(&mut self)`: entry of ConstantVoltage
+//! - `fn on_transition(&mut self, from, event, to)`: every transition
+//! - `fn set_current_full(&mut self)`: entry of ConstantCurrent
+//! - `fn set_current_low(&mut self)`: entry of Precharge
+//! - `fn signal_fault(&mut self)`: entry of Fault
+//! - `fn voltage_low(&self) -> bool`: Idle --> Precharge : battery_inserted [voltage_low]
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum BatteryChargerState {
+    /// No battery
+    Idle,
+    /// Deeply discharged cell
+    Precharge,
+    ConstantCurrent,
+    ConstantVoltage,
+    Charged,
+    Fault,
+}
+
+impl BatteryChargerState {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            BatteryChargerState::Idle => "Idle",
+            BatteryChargerState::Precharge => "Precharge",
+            BatteryChargerState::ConstantCurrent => "ConstantCurrent",
+            BatteryChargerState::ConstantVoltage => "ConstantVoltage",
+            BatteryChargerState::Charged => "Charged",
+            BatteryChargerState::Fault => "Fault",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum BatteryChargerEvent {
+    BatteryInserted = 0,
+    BatteryRemoved = 1,
+    CurrentTapered = 2,
+    FaultCleared = 3,
+    OverTemperature = 4,
+    VoltageDropped = 5,
+    VoltageOk = 6,
+    VoltageReached = 7,
+}
+
+impl BatteryChargerEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub(crate) const EVENT_NAMES: [&'static str; 8] = ["battery_inserted", "battery_removed", "current_tapered", "fault_cleared", "over_temperature", "voltage_dropped", "voltage_ok", "voltage_reached"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub(crate) const fn id(&self) -> u8 {
+        match self {
+            BatteryChargerEvent::BatteryInserted => 0,
+            BatteryChargerEvent::BatteryRemoved => 1,
+            BatteryChargerEvent::CurrentTapered => 2,
+            BatteryChargerEvent::FaultCleared => 3,
+            BatteryChargerEvent::OverTemperature => 4,
+            BatteryChargerEvent::VoltageDropped => 5,
+            BatteryChargerEvent::VoltageOk => 6,
+            BatteryChargerEvent::VoltageReached => 7,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            BatteryChargerEvent::BatteryInserted => "battery_inserted",
+            BatteryChargerEvent::BatteryRemoved => "battery_removed",
+            BatteryChargerEvent::CurrentTapered => "current_tapered",
+            BatteryChargerEvent::FaultCleared => "fault_cleared",
+            BatteryChargerEvent::OverTemperature => "over_temperature",
+            BatteryChargerEvent::VoltageDropped => "voltage_dropped",
+            BatteryChargerEvent::VoltageOk => "voltage_ok",
+            BatteryChargerEvent::VoltageReached => "voltage_reached",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `BatteryCharger::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum BatteryChargerProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: BatteryChargerState, to: BatteryChargerState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl BatteryChargerProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub(crate) const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+/// Dispatch counters of `BatteryCharger`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BatteryChargerMetrics {
+    state_entered: [u16; 6],
+    event_received: [u32; 8],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl BatteryChargerMetrics {
+    fn started(initial: BatteryChargerState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 6],
+            event_received: [0; 8],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: BatteryChargerState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub(crate) fn entered(&self, state: BatteryChargerState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: BatteryChargerEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub(crate) fn received(&self, event: BatteryChargerEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub(crate) fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub(crate) struct BatteryCharger<T: BatteryChargerActions> {
+    state: BatteryChargerState,
+    context: T,
+    started: bool,
+    queue: [Option<BatteryChargerEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: BatteryChargerMetrics,
+}
+
+impl<T: BatteryChargerActions> BatteryCharger<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub(crate) fn new(context: T) -> Self {
+        Self {
+            state: BatteryChargerState::Idle,
+            context,
+            started: false,
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: BatteryChargerMetrics::started(BatteryChargerState::Idle),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub(crate) fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub(crate) fn state(&self) -> BatteryChargerState {
+        self.state
+    }
+
+    pub(crate) fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub(crate) fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub(crate) fn metrics(&self) -> &BatteryChargerMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued
+    /// event is dropped to make room and returned
+    pub(crate) fn post(&mut self, event: BatteryChargerEvent) -> Option<BatteryChargerEvent> {
+        let mut dropped = None;
+        if self.queue_len == 4 {
+            dropped = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        dropped
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub(crate) fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event).is_handled() {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub(crate) fn process(&mut self, event: BatteryChargerEvent) -> BatteryChargerProcessResult {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (BatteryChargerState::Idle, BatteryChargerEvent::BatteryInserted) if self.context.voltage_low() => {
+                self.state = BatteryChargerState::Precharge;
+                self.context.set_current_low();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(BatteryChargerState::Precharge);
+                self.context.on_transition(BatteryChargerState::Idle, BatteryChargerEvent::BatteryInserted, BatteryChargerState::Precharge);
+                BatteryChargerProcessResult::Transitioned { from: BatteryChargerState::Idle, to: BatteryChargerState::Precharge }
+            }
+            (BatteryChargerState::Idle, BatteryChargerEvent::BatteryInserted) => {
+                self.state = BatteryChargerState::ConstantCurrent;
+                self.context.set_current_full();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(BatteryChargerState::ConstantCurrent);
+                self.context.on_transition(BatteryChargerState::Idle, BatteryChargerEvent::BatteryInserted, BatteryChargerState::ConstantCurrent);
+                BatteryChargerProcessResult::Transitioned { from: BatteryChargerState::Idle, to: BatteryChargerState::ConstantCurrent }
+            }
+            (BatteryChargerState::Precharge, BatteryChargerEvent::VoltageOk) => {
+                self.state = BatteryChargerState::ConstantCurrent;
+                self.context.set_current_full();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(BatteryChargerState::ConstantCurrent);
+                self.context.on_transition(BatteryChargerState::Precharge, BatteryChargerEvent::VoltageOk, BatteryChargerState::ConstantCurrent);
+                BatteryChargerProcessResult::Transitioned { from: BatteryChargerState::Precharge, to: BatteryChargerState::ConstantCurrent }
+            }
+            (BatteryChargerState::ConstantCurrent, BatteryChargerEvent::VoltageReached) => {
+                self.state = BatteryChargerState::ConstantVoltage;
+                self.context.hold_voltage();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(BatteryChargerState::ConstantVoltage);
+                self.context.on_transition(BatteryChargerState::ConstantCurrent, BatteryChargerEvent::VoltageReached, BatteryChargerState::ConstantVoltage);
+                BatteryChargerProcessResult::Transitioned { from: BatteryChargerState::ConstantCurrent, to: BatteryChargerState::ConstantVoltage }
+            }
+            (BatteryChargerState::ConstantVoltage, BatteryChargerEvent::CurrentTapered) => {
+                self.state = BatteryChargerState::Charged;
+                self.context.charger_off();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(BatteryChargerState::Charged);
+                self.context.on_transition(BatteryChargerState::ConstantVoltage, BatteryChargerEvent::CurrentTapered, BatteryChargerState::Charged);
+                BatteryChargerProcessResult::Transitioned { from: BatteryChargerState::ConstantVoltage, to: BatteryChargerState::Charged }
+            }
+            (BatteryChargerState::Charged, BatteryChargerEvent::VoltageDropped) => {
+                self.state = BatteryChargerState::ConstantCurrent;
+                self.context.set_current_full();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(BatteryChargerState::ConstantCurrent);
+                self.context.on_transition(BatteryChargerState::Charged, BatteryChargerEvent::VoltageDropped, BatteryChargerState::ConstantCurrent);
+                BatteryChargerProcessResult::Transitioned { from: BatteryChargerState::Charged, to: BatteryChargerState::ConstantCurrent }
+            }
+            (BatteryChargerState::Fault, BatteryChargerEvent::FaultCleared) => {
+                self.state = BatteryChargerState::Idle;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(BatteryChargerState::Idle);
+                self.context.on_transition(BatteryChargerState::Fault, BatteryChargerEvent::FaultCleared, BatteryChargerState::Idle);
+                BatteryChargerProcessResult::Transitioned { from: BatteryChargerState::Fault, to: BatteryChargerState::Idle }
+            }
+            (_, BatteryChargerEvent::OverTemperature) => {
+                let from = self.state;
+                self.state = BatteryChargerState::Fault;
+                self.context.charger_off();
+                self.context.signal_fault();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(BatteryChargerState::Fault);
+                self.context.on_transition(from, BatteryChargerEvent::OverTemperature, BatteryChargerState::Fault);
+                BatteryChargerProcessResult::Transitioned { from, to: BatteryChargerState::Fault }
+            }
+            (_, BatteryChargerEvent::BatteryRemoved) => {
+                let from = self.state;
+                self.state = BatteryChargerState::Idle;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(BatteryChargerState::Idle);
+                self.context.on_transition(from, BatteryChargerEvent::BatteryRemoved, BatteryChargerState::Idle);
+                BatteryChargerProcessResult::Transitioned { from, to: BatteryChargerState::Idle }
+            }
+            _ => BatteryChargerProcessResult::Ignored,
+        }
+    }
+}
+
+#[cfg(feature = "shared-events")]
+impl<T: BatteryChargerActions> BatteryCharger<T> {
+    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it
+    pub(crate) fn process_shared(&mut self, event: super::events::SharedEvent) -> BatteryChargerProcessResult {
+        match BatteryChargerEvent::try_from(event) {
+            Ok(event) => self.process(event),
+            Err(_) => BatteryChargerProcessResult::Ignored,
+        }
+    }
+
+    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room
+    pub(crate) fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {
+        BatteryChargerEvent::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)
+    }
+}
+
+// ── Actions trait ──
+pub(crate) trait BatteryChargerActions {
+    fn charger_off(&mut self);
+    fn hold_voltage(&mut self);
+    fn set_current_full(&mut self);
+    fn set_current_low(&mut self);
+    fn signal_fault(&mut self);
+    fn voltage_low(&self) -> bool;
+
+    /// Called after every successful transition (e.g. for defmt/RTT logging)
+    fn on_transition(&mut self, _from: BatteryChargerState, _event: BatteryChargerEvent, _to: BatteryChargerState) {}
+}
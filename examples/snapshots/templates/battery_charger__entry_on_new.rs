@@ -0,0 +1,158 @@
+//! Auto-generated FSM: BatteryCharger
+//! Generated by Oxidate
+//!
+//! BatteryChargerActions methods:
+//! - `fn charger_off(&mut self)`: entry of Charged, entry of Fault
+//! - `fn hold_voltage(&mut self)`: entry of ConstantVoltage
+//! - `fn set_current_full(&mut self)`: entry of ConstantCurrent
+//! - `fn set_current_low(&mut self)`: entry of Precharge
+//! - `fn signal_fault(&mut self)`: entry of Fault
+//! - `fn voltage_low(&self) -> bool`: Idle --> Precharge : battery_inserted [voltage_low]
+
+#![no_std]
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BatteryChargerState {
+    /// No battery
+    Idle,
+    /// Deeply discharged cell
+    Precharge,
+    ConstantCurrent,
+    ConstantVoltage,
+    Charged,
+    Fault,
+}
+
+impl BatteryChargerState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            BatteryChargerState::Idle => "Idle",
+            BatteryChargerState::Precharge => "Precharge",
+            BatteryChargerState::ConstantCurrent => "ConstantCurrent",
+            BatteryChargerState::ConstantVoltage => "ConstantVoltage",
+            BatteryChargerState::Charged => "Charged",
+            BatteryChargerState::Fault => "Fault",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BatteryChargerEvent {
+    BatteryInserted,
+    BatteryRemoved,
+    CurrentTapered,
+    FaultCleared,
+    OverTemperature,
+    VoltageDropped,
+    VoltageOk,
+    VoltageReached,
+}
+
+impl BatteryChargerEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            BatteryChargerEvent::BatteryInserted => "battery_inserted",
+            BatteryChargerEvent::BatteryRemoved => "battery_removed",
+            BatteryChargerEvent::CurrentTapered => "current_tapered",
+            BatteryChargerEvent::FaultCleared => "fault_cleared",
+            BatteryChargerEvent::OverTemperature => "over_temperature",
+            BatteryChargerEvent::VoltageDropped => "voltage_dropped",
+            BatteryChargerEvent::VoltageOk => "voltage_ok",
+            BatteryChargerEvent::VoltageReached => "voltage_reached",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct BatteryCharger<T: BatteryChargerActions> {
+    state: BatteryChargerState,
+    context: T,
+}
+
+impl<T: BatteryChargerActions> BatteryCharger<T> {
+    /// Create the machine and run its start actions, then the initial state's entry actions
+    pub fn new(mut context: T) -> Self {
+        Self {
+            state: BatteryChargerState::Idle,
+            context,
+        }
+    }
+
+    pub fn state(&self) -> BatteryChargerState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: BatteryChargerEvent) -> bool {
+        match (self.state, event) {
+            (BatteryChargerState::Idle, BatteryChargerEvent::BatteryInserted) if self.context.voltage_low() => {
+                self.state = BatteryChargerState::Precharge;
+                self.context.set_current_low();
+                true
+            }
+            (BatteryChargerState::Idle, BatteryChargerEvent::BatteryInserted) => {
+                self.state = BatteryChargerState::ConstantCurrent;
+                self.context.set_current_full();
+                true
+            }
+            (BatteryChargerState::Precharge, BatteryChargerEvent::VoltageOk) => {
+                self.state = BatteryChargerState::ConstantCurrent;
+                self.context.set_current_full();
+                true
+            }
+            (BatteryChargerState::ConstantCurrent, BatteryChargerEvent::VoltageReached) => {
+                self.state = BatteryChargerState::ConstantVoltage;
+                self.context.hold_voltage();
+                true
+            }
+            (BatteryChargerState::ConstantVoltage, BatteryChargerEvent::CurrentTapered) => {
+                self.state = BatteryChargerState::Charged;
+                self.context.charger_off();
+                true
+            }
+            (BatteryChargerState::Charged, BatteryChargerEvent::VoltageDropped) => {
+                self.state = BatteryChargerState::ConstantCurrent;
+                self.context.set_current_full();
+                true
+            }
+            (BatteryChargerState::Fault, BatteryChargerEvent::FaultCleared) => {
+                self.state = BatteryChargerState::Idle;
+                true
+            }
+            (_, BatteryChargerEvent::OverTemperature) => {
+                self.state = BatteryChargerState::Fault;
+                self.context.charger_off();
+                self.context.signal_fault();
+                true
+            }
+            (_, BatteryChargerEvent::BatteryRemoved) => {
+                self.state = BatteryChargerState::Idle;
+                true
+            }
+            _ => false // No transition
+        }
+    }
+}
+
+// ── Actions trait ──
+pub trait BatteryChargerActions {
+    fn charger_off(&mut self);
+    fn hold_voltage(&mut self);
+    fn set_current_full(&mut self);
+    fn set_current_low(&mut self);
+    fn signal_fault(&mut self);
+    fn voltage_low(&self) -> bool;
+}
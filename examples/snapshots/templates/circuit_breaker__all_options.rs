@@ -0,0 +1,357 @@
+//! Auto-generated FSM: CircuitBreaker
+//! Generated by Oxidate
+//!
+//! Snapshot of every option
+//!
+//! CircuitBreakerActions methods:
+//! - `fn below_threshold(&self) -> bool`: ClosedCircuit --> ClosedCircuit : call_failed [below_threshold] / count_failure
+//! - `fn count_failure(&mut self)`: ClosedCircuit --> ClosedCircuit : call_failed [below_threshold] / count_failure
+//! - `fn on_transition(&mut self, from, event, to)`: every transition
+//! - `fn reset_failures(&mut self)`: HalfOpen --> ClosedCircuit : call_ok / reset_failures, internal of ClosedCircuit : call_ok / reset_failures
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum CircuitBreakerState {
+    /// Calls pass through
+    ClosedCircuit,
+    /// Calls fail fast
+    OpenCircuit,
+    /// One trial call allowed
+    HalfOpen,
+}
+
+impl CircuitBreakerState {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            CircuitBreakerState::ClosedCircuit => "ClosedCircuit",
+            CircuitBreakerState::OpenCircuit => "OpenCircuit",
+            CircuitBreakerState::HalfOpen => "HalfOpen",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum CircuitBreakerEvent {
+    CoolDownElapsed = 0,
+    CallFailed = 1,
+    CallOk = 2,
+}
+
+impl CircuitBreakerEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub(crate) const EVENT_NAMES: [&'static str; 3] = ["CoolDownElapsed", "call_failed", "call_ok"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub(crate) const fn id(&self) -> u8 {
+        match self {
+            CircuitBreakerEvent::CoolDownElapsed => 0,
+            CircuitBreakerEvent::CallFailed => 1,
+            CircuitBreakerEvent::CallOk => 2,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            CircuitBreakerEvent::CoolDownElapsed => "CoolDownElapsed",
+            CircuitBreakerEvent::CallFailed => "call_failed",
+            CircuitBreakerEvent::CallOk => "call_ok",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `CircuitBreaker::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum CircuitBreakerProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: CircuitBreakerState, to: CircuitBreakerState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl CircuitBreakerProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub(crate) const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+/// Dispatch counters of `CircuitBreaker`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CircuitBreakerMetrics {
+    state_entered: [u16; 3],
+    event_received: [u32; 3],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl CircuitBreakerMetrics {
+    fn started(initial: CircuitBreakerState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 3],
+            event_received: [0; 3],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: CircuitBreakerState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub(crate) fn entered(&self, state: CircuitBreakerState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: CircuitBreakerEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub(crate) fn received(&self, event: CircuitBreakerEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub(crate) fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub(crate) struct CircuitBreaker<T: CircuitBreakerActions> {
+    state: CircuitBreakerState,
+    context: T,
+    started: bool,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 1],
+    queue: [Option<CircuitBreakerEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: CircuitBreakerMetrics,
+}
+
+impl<T: CircuitBreakerActions> CircuitBreaker<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub(crate) fn new(context: T) -> Self {
+        Self {
+            state: CircuitBreakerState::ClosedCircuit,
+            context,
+            started: false,
+            timers: [None; 1],
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: CircuitBreakerMetrics::started(CircuitBreakerState::ClosedCircuit),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub(crate) fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub(crate) fn state(&self) -> CircuitBreakerState {
+        self.state
+    }
+
+    pub(crate) fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub(crate) fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub(crate) fn metrics(&self) -> &CircuitBreakerMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued
+    /// event is dropped to make room and returned
+    pub(crate) fn post(&mut self, event: CircuitBreakerEvent) -> Option<CircuitBreakerEvent> {
+        let mut dropped = None;
+        if self.queue_len == 4 {
+            dropped = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        dropped
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub(crate) fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event).is_handled() {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    ///
+    /// A state's own transitions, self-transitions included, are tried before its
+    /// internal transitions, which run only their actions
+    pub(crate) fn process(&mut self, event: CircuitBreakerEvent) -> CircuitBreakerProcessResult {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (CircuitBreakerState::ClosedCircuit, CircuitBreakerEvent::CallFailed) if self.context.below_threshold() => {
+                self.timers = [None; 1];
+                self.context.count_failure();
+                self.state = CircuitBreakerState::ClosedCircuit;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(CircuitBreakerState::ClosedCircuit);
+                self.context.on_transition(CircuitBreakerState::ClosedCircuit, CircuitBreakerEvent::CallFailed, CircuitBreakerState::ClosedCircuit);
+                CircuitBreakerProcessResult::Transitioned { from: CircuitBreakerState::ClosedCircuit, to: CircuitBreakerState::ClosedCircuit }
+            }
+            (CircuitBreakerState::ClosedCircuit, CircuitBreakerEvent::CallFailed) => {
+                self.timers = [None; 1];
+                self.state = CircuitBreakerState::OpenCircuit;
+                self.timers[Self::TIMER_COOL_DOWN] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(CircuitBreakerState::OpenCircuit);
+                self.context.on_transition(CircuitBreakerState::ClosedCircuit, CircuitBreakerEvent::CallFailed, CircuitBreakerState::OpenCircuit);
+                CircuitBreakerProcessResult::Transitioned { from: CircuitBreakerState::ClosedCircuit, to: CircuitBreakerState::OpenCircuit }
+            }
+            (CircuitBreakerState::OpenCircuit, CircuitBreakerEvent::CoolDownElapsed) => {
+                self.timers = [None; 1];
+                self.state = CircuitBreakerState::HalfOpen;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(CircuitBreakerState::HalfOpen);
+                self.context.on_transition(CircuitBreakerState::OpenCircuit, CircuitBreakerEvent::CoolDownElapsed, CircuitBreakerState::HalfOpen);
+                CircuitBreakerProcessResult::Transitioned { from: CircuitBreakerState::OpenCircuit, to: CircuitBreakerState::HalfOpen }
+            }
+            (CircuitBreakerState::HalfOpen, CircuitBreakerEvent::CallOk) => {
+                self.timers = [None; 1];
+                self.context.reset_failures();
+                self.state = CircuitBreakerState::ClosedCircuit;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(CircuitBreakerState::ClosedCircuit);
+                self.context.on_transition(CircuitBreakerState::HalfOpen, CircuitBreakerEvent::CallOk, CircuitBreakerState::ClosedCircuit);
+                CircuitBreakerProcessResult::Transitioned { from: CircuitBreakerState::HalfOpen, to: CircuitBreakerState::ClosedCircuit }
+            }
+            (CircuitBreakerState::HalfOpen, CircuitBreakerEvent::CallFailed) => {
+                self.timers = [None; 1];
+                self.state = CircuitBreakerState::OpenCircuit;
+                self.timers[Self::TIMER_COOL_DOWN] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(CircuitBreakerState::OpenCircuit);
+                self.context.on_transition(CircuitBreakerState::HalfOpen, CircuitBreakerEvent::CallFailed, CircuitBreakerState::OpenCircuit);
+                CircuitBreakerProcessResult::Transitioned { from: CircuitBreakerState::HalfOpen, to: CircuitBreakerState::OpenCircuit }
+            }
+            (CircuitBreakerState::ClosedCircuit, CircuitBreakerEvent::CallOk) => {
+                self.context.reset_failures();
+                CircuitBreakerProcessResult::InternalHandled
+            }
+            _ => CircuitBreakerProcessResult::Ignored,
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_COOL_DOWN: usize = 0;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, CircuitBreakerEvent, bool); 1] = [
+        (10000, CircuitBreakerEvent::CoolDownElapsed, false), // cool_down
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub(crate) fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+#[cfg(feature = "shared-events")]
+impl<T: CircuitBreakerActions> CircuitBreaker<T> {
+    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it
+    pub(crate) fn process_shared(&mut self, event: super::events::SharedEvent) -> CircuitBreakerProcessResult {
+        match CircuitBreakerEvent::try_from(event) {
+            Ok(event) => self.process(event),
+            Err(_) => CircuitBreakerProcessResult::Ignored,
+        }
+    }
+
+    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room
+    pub(crate) fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {
+        CircuitBreakerEvent::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)
+    }
+}
+
+// ── Actions trait ──
+pub(crate) trait CircuitBreakerActions {
+    fn count_failure(&mut self);
+    fn reset_failures(&mut self);
+    fn below_threshold(&self) -> bool;
+
+    /// Called after every successful transition (e.g. for defmt/RTT logging)
+    fn on_transition(&mut self, _from: CircuitBreakerState, _event: CircuitBreakerEvent, _to: CircuitBreakerState) {}
+}
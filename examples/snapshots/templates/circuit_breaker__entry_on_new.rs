@@ -0,0 +1,173 @@
+//! Auto-generated FSM: CircuitBreaker
+//! Generated by Oxidate
+//!
+//! CircuitBreakerActions methods:
+//! - `fn below_threshold(&self) -> bool`: ClosedCircuit --> ClosedCircuit : call_failed [below_threshold] / count_failure
+//! - `fn count_failure(&mut self)`: ClosedCircuit --> ClosedCircuit : call_failed [below_threshold] / count_failure
+//! - `fn reset_failures(&mut self)`: HalfOpen --> ClosedCircuit : call_ok / reset_failures, internal of ClosedCircuit : call_ok / reset_failures
+
+#![no_std]
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CircuitBreakerState {
+    /// Calls pass through
+    ClosedCircuit,
+    /// Calls fail fast
+    OpenCircuit,
+    /// One trial call allowed
+    HalfOpen,
+}
+
+impl CircuitBreakerState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            CircuitBreakerState::ClosedCircuit => "ClosedCircuit",
+            CircuitBreakerState::OpenCircuit => "OpenCircuit",
+            CircuitBreakerState::HalfOpen => "HalfOpen",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CircuitBreakerEvent {
+    CoolDownElapsed,
+    CallFailed,
+    CallOk,
+}
+
+impl CircuitBreakerEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            CircuitBreakerEvent::CoolDownElapsed => "CoolDownElapsed",
+            CircuitBreakerEvent::CallFailed => "call_failed",
+            CircuitBreakerEvent::CallOk => "call_ok",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct CircuitBreaker<T: CircuitBreakerActions> {
+    state: CircuitBreakerState,
+    context: T,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 1],
+}
+
+impl<T: CircuitBreakerActions> CircuitBreaker<T> {
+    /// Create the machine and run its start actions, then the initial state's entry actions
+    pub fn new(mut context: T) -> Self {
+        Self {
+            state: CircuitBreakerState::ClosedCircuit,
+            context,
+            timers: [None; 1],
+        }
+    }
+
+    pub fn state(&self) -> CircuitBreakerState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    ///
+    /// A state's own transitions, self-transitions included, are tried before its
+    /// internal transitions, which run only their actions
+    pub fn process(&mut self, event: CircuitBreakerEvent) -> bool {
+        match (self.state, event) {
+            (CircuitBreakerState::ClosedCircuit, CircuitBreakerEvent::CallFailed) if self.context.below_threshold() => {
+                self.timers = [None; 1];
+                self.context.count_failure();
+                self.state = CircuitBreakerState::ClosedCircuit;
+                true
+            }
+            (CircuitBreakerState::ClosedCircuit, CircuitBreakerEvent::CallFailed) => {
+                self.timers = [None; 1];
+                self.state = CircuitBreakerState::OpenCircuit;
+                self.timers[Self::TIMER_COOL_DOWN] = Some(0);
+                true
+            }
+            (CircuitBreakerState::OpenCircuit, CircuitBreakerEvent::CoolDownElapsed) => {
+                self.timers = [None; 1];
+                self.state = CircuitBreakerState::HalfOpen;
+                true
+            }
+            (CircuitBreakerState::HalfOpen, CircuitBreakerEvent::CallOk) => {
+                self.timers = [None; 1];
+                self.context.reset_failures();
+                self.state = CircuitBreakerState::ClosedCircuit;
+                true
+            }
+            (CircuitBreakerState::HalfOpen, CircuitBreakerEvent::CallFailed) => {
+                self.timers = [None; 1];
+                self.state = CircuitBreakerState::OpenCircuit;
+                self.timers[Self::TIMER_COOL_DOWN] = Some(0);
+                true
+            }
+            (CircuitBreakerState::ClosedCircuit, CircuitBreakerEvent::CallOk) => {
+                self.context.reset_failures();
+                true
+            }
+            _ => false // No transition
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_COOL_DOWN: usize = 0;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, CircuitBreakerEvent, bool); 1] = [
+        (10000, CircuitBreakerEvent::CoolDownElapsed, false), // cool_down
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+// ── Actions trait ──
+pub trait CircuitBreakerActions {
+    fn count_failure(&mut self);
+    fn reset_failures(&mut self);
+    fn below_threshold(&self) -> bool;
+}
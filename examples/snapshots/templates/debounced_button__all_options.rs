@@ -0,0 +1,386 @@
+//! Auto-generated FSM: DebouncedButton
+//! Generated by Oxidate
+//!
+//! Snapshot of every option
+//!
+//! DebouncedButtonActions methods:
+//! - `fn emit_long_press(&mut self)`: entry of Held
+//! - `fn emit_press(&mut self)`: entry of Pressed
+//! - `fn emit_release(&mut self)`: ReleaseBouncing --> Released : Settled [pin_high] / emit_release
+//! - `fn on_transition(&mut self, from, event, to)`: every transition
+//! - `fn pin_high(&self) -> bool`: ReleaseBouncing --> Released : Settled [pin_high] / emit_release
+//! - `fn pin_low(&self) -> bool`: PressBouncing --> Pressed : Settled [pin_low]
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum DebouncedButtonState {
+    Released,
+    PressBouncing,
+    Pressed,
+    Held,
+    ReleaseBouncing,
+}
+
+impl DebouncedButtonState {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            DebouncedButtonState::Released => "Released",
+            DebouncedButtonState::PressBouncing => "PressBouncing",
+            DebouncedButtonState::Pressed => "Pressed",
+            DebouncedButtonState::Held => "Held",
+            DebouncedButtonState::ReleaseBouncing => "ReleaseBouncing",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum DebouncedButtonEvent {
+    LongPress = 0,
+    Settled = 1,
+    Edge = 2,
+}
+
+impl DebouncedButtonEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub(crate) const EVENT_NAMES: [&'static str; 3] = ["LongPress", "Settled", "edge"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub(crate) const fn id(&self) -> u8 {
+        match self {
+            DebouncedButtonEvent::LongPress => 0,
+            DebouncedButtonEvent::Settled => 1,
+            DebouncedButtonEvent::Edge => 2,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            DebouncedButtonEvent::LongPress => "LongPress",
+            DebouncedButtonEvent::Settled => "Settled",
+            DebouncedButtonEvent::Edge => "edge",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `DebouncedButton::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum DebouncedButtonProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: DebouncedButtonState, to: DebouncedButtonState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl DebouncedButtonProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub(crate) const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+/// Dispatch counters of `DebouncedButton`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DebouncedButtonMetrics {
+    state_entered: [u16; 5],
+    event_received: [u32; 3],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl DebouncedButtonMetrics {
+    fn started(initial: DebouncedButtonState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 5],
+            event_received: [0; 3],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: DebouncedButtonState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub(crate) fn entered(&self, state: DebouncedButtonState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: DebouncedButtonEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub(crate) fn received(&self, event: DebouncedButtonEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub(crate) fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub(crate) struct DebouncedButton<T: DebouncedButtonActions> {
+    state: DebouncedButtonState,
+    context: T,
+    started: bool,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 2],
+    queue: [Option<DebouncedButtonEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: DebouncedButtonMetrics,
+}
+
+impl<T: DebouncedButtonActions> DebouncedButton<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub(crate) fn new(context: T) -> Self {
+        Self {
+            state: DebouncedButtonState::Released,
+            context,
+            started: false,
+            timers: [None; 2],
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: DebouncedButtonMetrics::started(DebouncedButtonState::Released),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub(crate) fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub(crate) fn state(&self) -> DebouncedButtonState {
+        self.state
+    }
+
+    pub(crate) fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub(crate) fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub(crate) fn metrics(&self) -> &DebouncedButtonMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued
+    /// event is dropped to make room and returned
+    pub(crate) fn post(&mut self, event: DebouncedButtonEvent) -> Option<DebouncedButtonEvent> {
+        let mut dropped = None;
+        if self.queue_len == 4 {
+            dropped = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        dropped
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub(crate) fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event).is_handled() {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub(crate) fn process(&mut self, event: DebouncedButtonEvent) -> DebouncedButtonProcessResult {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (DebouncedButtonState::Released, DebouncedButtonEvent::Edge) => {
+                self.timers = [None; 2];
+                self.state = DebouncedButtonState::PressBouncing;
+                self.timers[Self::TIMER_DEBOUNCE] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(DebouncedButtonState::PressBouncing);
+                self.context.on_transition(DebouncedButtonState::Released, DebouncedButtonEvent::Edge, DebouncedButtonState::PressBouncing);
+                DebouncedButtonProcessResult::Transitioned { from: DebouncedButtonState::Released, to: DebouncedButtonState::PressBouncing }
+            }
+            (DebouncedButtonState::PressBouncing, DebouncedButtonEvent::Settled) if self.context.pin_low() => {
+                self.timers = [None; 2];
+                self.state = DebouncedButtonState::Pressed;
+                self.context.emit_press();
+                self.timers[Self::TIMER_LONG_PRESS] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(DebouncedButtonState::Pressed);
+                self.context.on_transition(DebouncedButtonState::PressBouncing, DebouncedButtonEvent::Settled, DebouncedButtonState::Pressed);
+                DebouncedButtonProcessResult::Transitioned { from: DebouncedButtonState::PressBouncing, to: DebouncedButtonState::Pressed }
+            }
+            (DebouncedButtonState::PressBouncing, DebouncedButtonEvent::Settled) => {
+                self.timers = [None; 2];
+                self.state = DebouncedButtonState::Released;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(DebouncedButtonState::Released);
+                self.context.on_transition(DebouncedButtonState::PressBouncing, DebouncedButtonEvent::Settled, DebouncedButtonState::Released);
+                DebouncedButtonProcessResult::Transitioned { from: DebouncedButtonState::PressBouncing, to: DebouncedButtonState::Released }
+            }
+            (DebouncedButtonState::Pressed, DebouncedButtonEvent::LongPress) => {
+                self.timers = [None; 2];
+                self.state = DebouncedButtonState::Held;
+                self.context.emit_long_press();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(DebouncedButtonState::Held);
+                self.context.on_transition(DebouncedButtonState::Pressed, DebouncedButtonEvent::LongPress, DebouncedButtonState::Held);
+                DebouncedButtonProcessResult::Transitioned { from: DebouncedButtonState::Pressed, to: DebouncedButtonState::Held }
+            }
+            (DebouncedButtonState::Pressed, DebouncedButtonEvent::Edge) => {
+                self.timers = [None; 2];
+                self.state = DebouncedButtonState::ReleaseBouncing;
+                self.timers[Self::TIMER_DEBOUNCE] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(DebouncedButtonState::ReleaseBouncing);
+                self.context.on_transition(DebouncedButtonState::Pressed, DebouncedButtonEvent::Edge, DebouncedButtonState::ReleaseBouncing);
+                DebouncedButtonProcessResult::Transitioned { from: DebouncedButtonState::Pressed, to: DebouncedButtonState::ReleaseBouncing }
+            }
+            (DebouncedButtonState::Held, DebouncedButtonEvent::Edge) => {
+                self.timers = [None; 2];
+                self.state = DebouncedButtonState::ReleaseBouncing;
+                self.timers[Self::TIMER_DEBOUNCE] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(DebouncedButtonState::ReleaseBouncing);
+                self.context.on_transition(DebouncedButtonState::Held, DebouncedButtonEvent::Edge, DebouncedButtonState::ReleaseBouncing);
+                DebouncedButtonProcessResult::Transitioned { from: DebouncedButtonState::Held, to: DebouncedButtonState::ReleaseBouncing }
+            }
+            (DebouncedButtonState::ReleaseBouncing, DebouncedButtonEvent::Settled) if self.context.pin_high() => {
+                self.timers = [None; 2];
+                self.context.emit_release();
+                self.state = DebouncedButtonState::Released;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(DebouncedButtonState::Released);
+                self.context.on_transition(DebouncedButtonState::ReleaseBouncing, DebouncedButtonEvent::Settled, DebouncedButtonState::Released);
+                DebouncedButtonProcessResult::Transitioned { from: DebouncedButtonState::ReleaseBouncing, to: DebouncedButtonState::Released }
+            }
+            (DebouncedButtonState::ReleaseBouncing, DebouncedButtonEvent::Settled) => {
+                self.timers = [None; 2];
+                self.state = DebouncedButtonState::Pressed;
+                self.context.emit_press();
+                self.timers[Self::TIMER_LONG_PRESS] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(DebouncedButtonState::Pressed);
+                self.context.on_transition(DebouncedButtonState::ReleaseBouncing, DebouncedButtonEvent::Settled, DebouncedButtonState::Pressed);
+                DebouncedButtonProcessResult::Transitioned { from: DebouncedButtonState::ReleaseBouncing, to: DebouncedButtonState::Pressed }
+            }
+            _ => DebouncedButtonProcessResult::Ignored,
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_DEBOUNCE: usize = 0;
+    const TIMER_LONG_PRESS: usize = 1;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, DebouncedButtonEvent, bool); 2] = [
+        (20, DebouncedButtonEvent::Settled, false), // debounce
+        (1000, DebouncedButtonEvent::LongPress, false), // long_press
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub(crate) fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+#[cfg(feature = "shared-events")]
+impl<T: DebouncedButtonActions> DebouncedButton<T> {
+    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it
+    pub(crate) fn process_shared(&mut self, event: super::events::SharedEvent) -> DebouncedButtonProcessResult {
+        match DebouncedButtonEvent::try_from(event) {
+            Ok(event) => self.process(event),
+            Err(_) => DebouncedButtonProcessResult::Ignored,
+        }
+    }
+
+    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room
+    pub(crate) fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {
+        DebouncedButtonEvent::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)
+    }
+}
+
+// ── Actions trait ──
+pub(crate) trait DebouncedButtonActions {
+    fn emit_long_press(&mut self);
+    fn emit_press(&mut self);
+    fn emit_release(&mut self);
+    fn pin_high(&self) -> bool;
+    fn pin_low(&self) -> bool;
+
+    /// Called after every successful transition (e.g. for defmt/RTT logging)
+    fn on_transition(&mut self, _from: DebouncedButtonState, _event: DebouncedButtonEvent, _to: DebouncedButtonState) {}
+}
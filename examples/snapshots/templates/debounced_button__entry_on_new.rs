@@ -0,0 +1,193 @@
+//! Auto-generated FSM: DebouncedButton
+//! Generated by Oxidate
+//!
+//! DebouncedButtonActions methods:
+//! - `fn emit_long_press(&mut self)`: entry of Held
+//! - `fn emit_press(&mut self)`: entry of Pressed
+//! - `fn emit_release(&mut self)`: ReleaseBouncing --> Released : Settled [pin_high] / emit_release
+//! - `fn pin_high(&self) -> bool`: ReleaseBouncing --> Released : Settled [pin_high] / emit_release
+//! - `fn pin_low(&self) -> bool`: PressBouncing --> Pressed : Settled [pin_low]
+
+#![no_std]
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DebouncedButtonState {
+    Released,
+    PressBouncing,
+    Pressed,
+    Held,
+    ReleaseBouncing,
+}
+
+impl DebouncedButtonState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            DebouncedButtonState::Released => "Released",
+            DebouncedButtonState::PressBouncing => "PressBouncing",
+            DebouncedButtonState::Pressed => "Pressed",
+            DebouncedButtonState::Held => "Held",
+            DebouncedButtonState::ReleaseBouncing => "ReleaseBouncing",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DebouncedButtonEvent {
+    LongPress,
+    Settled,
+    Edge,
+}
+
+impl DebouncedButtonEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            DebouncedButtonEvent::LongPress => "LongPress",
+            DebouncedButtonEvent::Settled => "Settled",
+            DebouncedButtonEvent::Edge => "edge",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct DebouncedButton<T: DebouncedButtonActions> {
+    state: DebouncedButtonState,
+    context: T,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 2],
+}
+
+impl<T: DebouncedButtonActions> DebouncedButton<T> {
+    /// Create the machine and run its start actions, then the initial state's entry actions
+    pub fn new(mut context: T) -> Self {
+        Self {
+            state: DebouncedButtonState::Released,
+            context,
+            timers: [None; 2],
+        }
+    }
+
+    pub fn state(&self) -> DebouncedButtonState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: DebouncedButtonEvent) -> bool {
+        match (self.state, event) {
+            (DebouncedButtonState::Released, DebouncedButtonEvent::Edge) => {
+                self.timers = [None; 2];
+                self.state = DebouncedButtonState::PressBouncing;
+                self.timers[Self::TIMER_DEBOUNCE] = Some(0);
+                true
+            }
+            (DebouncedButtonState::PressBouncing, DebouncedButtonEvent::Settled) if self.context.pin_low() => {
+                self.timers = [None; 2];
+                self.state = DebouncedButtonState::Pressed;
+                self.context.emit_press();
+                self.timers[Self::TIMER_LONG_PRESS] = Some(0);
+                true
+            }
+            (DebouncedButtonState::PressBouncing, DebouncedButtonEvent::Settled) => {
+                self.timers = [None; 2];
+                self.state = DebouncedButtonState::Released;
+                true
+            }
+            (DebouncedButtonState::Pressed, DebouncedButtonEvent::LongPress) => {
+                self.timers = [None; 2];
+                self.state = DebouncedButtonState::Held;
+                self.context.emit_long_press();
+                true
+            }
+            (DebouncedButtonState::Pressed, DebouncedButtonEvent::Edge) => {
+                self.timers = [None; 2];
+                self.state = DebouncedButtonState::ReleaseBouncing;
+                self.timers[Self::TIMER_DEBOUNCE] = Some(0);
+                true
+            }
+            (DebouncedButtonState::Held, DebouncedButtonEvent::Edge) => {
+                self.timers = [None; 2];
+                self.state = DebouncedButtonState::ReleaseBouncing;
+                self.timers[Self::TIMER_DEBOUNCE] = Some(0);
+                true
+            }
+            (DebouncedButtonState::ReleaseBouncing, DebouncedButtonEvent::Settled) if self.context.pin_high() => {
+                self.timers = [None; 2];
+                self.context.emit_release();
+                self.state = DebouncedButtonState::Released;
+                true
+            }
+            (DebouncedButtonState::ReleaseBouncing, DebouncedButtonEvent::Settled) => {
+                self.timers = [None; 2];
+                self.state = DebouncedButtonState::Pressed;
+                self.context.emit_press();
+                self.timers[Self::TIMER_LONG_PRESS] = Some(0);
+                true
+            }
+            _ => false // No transition
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_DEBOUNCE: usize = 0;
+    const TIMER_LONG_PRESS: usize = 1;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, DebouncedButtonEvent, bool); 2] = [
+        (20, DebouncedButtonEvent::Settled, false), // debounce
+        (1000, DebouncedButtonEvent::LongPress, false), // long_press
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+// ── Actions trait ──
+pub trait DebouncedButtonActions {
+    fn emit_long_press(&mut self);
+    fn emit_press(&mut self);
+    fn emit_release(&mut self);
+    fn pin_high(&self) -> bool;
+    fn pin_low(&self) -> bool;
+}
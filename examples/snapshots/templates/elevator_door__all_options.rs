@@ -0,0 +1,382 @@
+//! Auto-generated FSM: ElevatorDoor
+//! Generated by Oxidate
+//!
+//! Snapshot of every option
+//!
+//! ElevatorDoorActions methods:
+//! - `fn lock_door(&mut self)`: entry of Closed
+//! - `fn motor_close(&mut self)`: entry of Closing
+//! - `fn motor_open(&mut self)`: entry of Opening
+//! - `fn on_transition(&mut self, from, event, to)`: every transition
+//! - `fn unlock_door(&mut self)`: exit of Closed
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum ElevatorDoorState {
+    Closed,
+    Opening,
+    /// Waiting for passengers
+    Open,
+    Closing,
+}
+
+impl ElevatorDoorState {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            ElevatorDoorState::Closed => "Closed",
+            ElevatorDoorState::Opening => "Opening",
+            ElevatorDoorState::Open => "Open",
+            ElevatorDoorState::Closing => "Closing",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum ElevatorDoorEvent {
+    DwellElapsed = 0,
+    CloseRequest = 1,
+    FullyClosed = 2,
+    FullyOpen = 3,
+    Obstruction = 4,
+    OpenRequest = 5,
+}
+
+impl ElevatorDoorEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub(crate) const EVENT_NAMES: [&'static str; 6] = ["DwellElapsed", "close_request", "fully_closed", "fully_open", "obstruction", "open_request"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub(crate) const fn id(&self) -> u8 {
+        match self {
+            ElevatorDoorEvent::DwellElapsed => 0,
+            ElevatorDoorEvent::CloseRequest => 1,
+            ElevatorDoorEvent::FullyClosed => 2,
+            ElevatorDoorEvent::FullyOpen => 3,
+            ElevatorDoorEvent::Obstruction => 4,
+            ElevatorDoorEvent::OpenRequest => 5,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            ElevatorDoorEvent::DwellElapsed => "DwellElapsed",
+            ElevatorDoorEvent::CloseRequest => "close_request",
+            ElevatorDoorEvent::FullyClosed => "fully_closed",
+            ElevatorDoorEvent::FullyOpen => "fully_open",
+            ElevatorDoorEvent::Obstruction => "obstruction",
+            ElevatorDoorEvent::OpenRequest => "open_request",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `ElevatorDoor::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum ElevatorDoorProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: ElevatorDoorState, to: ElevatorDoorState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl ElevatorDoorProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub(crate) const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+/// Dispatch counters of `ElevatorDoor`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ElevatorDoorMetrics {
+    state_entered: [u16; 4],
+    event_received: [u32; 6],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl ElevatorDoorMetrics {
+    fn started(initial: ElevatorDoorState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 4],
+            event_received: [0; 6],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: ElevatorDoorState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub(crate) fn entered(&self, state: ElevatorDoorState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: ElevatorDoorEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub(crate) fn received(&self, event: ElevatorDoorEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub(crate) fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub(crate) struct ElevatorDoor<T: ElevatorDoorActions> {
+    state: ElevatorDoorState,
+    context: T,
+    started: bool,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 1],
+    queue: [Option<ElevatorDoorEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: ElevatorDoorMetrics,
+}
+
+impl<T: ElevatorDoorActions> ElevatorDoor<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub(crate) fn new(context: T) -> Self {
+        Self {
+            state: ElevatorDoorState::Closed,
+            context,
+            started: false,
+            timers: [None; 1],
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: ElevatorDoorMetrics::started(ElevatorDoorState::Closed),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub(crate) fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        self.context.lock_door();
+        true
+    }
+
+    pub(crate) fn state(&self) -> ElevatorDoorState {
+        self.state
+    }
+
+    pub(crate) fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub(crate) fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub(crate) fn metrics(&self) -> &ElevatorDoorMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued
+    /// event is dropped to make room and returned
+    pub(crate) fn post(&mut self, event: ElevatorDoorEvent) -> Option<ElevatorDoorEvent> {
+        let mut dropped = None;
+        if self.queue_len == 4 {
+            dropped = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        dropped
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub(crate) fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event).is_handled() {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub(crate) fn process(&mut self, event: ElevatorDoorEvent) -> ElevatorDoorProcessResult {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (ElevatorDoorState::Closed, ElevatorDoorEvent::OpenRequest) => {
+                self.timers = [None; 1];
+                self.context.unlock_door();
+                self.state = ElevatorDoorState::Opening;
+                self.context.motor_open();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(ElevatorDoorState::Opening);
+                self.context.on_transition(ElevatorDoorState::Closed, ElevatorDoorEvent::OpenRequest, ElevatorDoorState::Opening);
+                ElevatorDoorProcessResult::Transitioned { from: ElevatorDoorState::Closed, to: ElevatorDoorState::Opening }
+            }
+            (ElevatorDoorState::Opening, ElevatorDoorEvent::FullyOpen) => {
+                self.timers = [None; 1];
+                self.state = ElevatorDoorState::Open;
+                self.timers[Self::TIMER_DWELL] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(ElevatorDoorState::Open);
+                self.context.on_transition(ElevatorDoorState::Opening, ElevatorDoorEvent::FullyOpen, ElevatorDoorState::Open);
+                ElevatorDoorProcessResult::Transitioned { from: ElevatorDoorState::Opening, to: ElevatorDoorState::Open }
+            }
+            (ElevatorDoorState::Open, ElevatorDoorEvent::DwellElapsed) => {
+                self.timers = [None; 1];
+                self.state = ElevatorDoorState::Closing;
+                self.context.motor_close();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(ElevatorDoorState::Closing);
+                self.context.on_transition(ElevatorDoorState::Open, ElevatorDoorEvent::DwellElapsed, ElevatorDoorState::Closing);
+                ElevatorDoorProcessResult::Transitioned { from: ElevatorDoorState::Open, to: ElevatorDoorState::Closing }
+            }
+            (ElevatorDoorState::Open, ElevatorDoorEvent::CloseRequest) => {
+                self.timers = [None; 1];
+                self.state = ElevatorDoorState::Closing;
+                self.context.motor_close();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(ElevatorDoorState::Closing);
+                self.context.on_transition(ElevatorDoorState::Open, ElevatorDoorEvent::CloseRequest, ElevatorDoorState::Closing);
+                ElevatorDoorProcessResult::Transitioned { from: ElevatorDoorState::Open, to: ElevatorDoorState::Closing }
+            }
+            (ElevatorDoorState::Closing, ElevatorDoorEvent::FullyClosed) => {
+                self.timers = [None; 1];
+                self.state = ElevatorDoorState::Closed;
+                self.context.lock_door();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(ElevatorDoorState::Closed);
+                self.context.on_transition(ElevatorDoorState::Closing, ElevatorDoorEvent::FullyClosed, ElevatorDoorState::Closed);
+                ElevatorDoorProcessResult::Transitioned { from: ElevatorDoorState::Closing, to: ElevatorDoorState::Closed }
+            }
+            (ElevatorDoorState::Closing, ElevatorDoorEvent::Obstruction) => {
+                self.timers = [None; 1];
+                self.state = ElevatorDoorState::Opening;
+                self.context.motor_open();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(ElevatorDoorState::Opening);
+                self.context.on_transition(ElevatorDoorState::Closing, ElevatorDoorEvent::Obstruction, ElevatorDoorState::Opening);
+                ElevatorDoorProcessResult::Transitioned { from: ElevatorDoorState::Closing, to: ElevatorDoorState::Opening }
+            }
+            (ElevatorDoorState::Closing, ElevatorDoorEvent::OpenRequest) => {
+                self.timers = [None; 1];
+                self.state = ElevatorDoorState::Opening;
+                self.context.motor_open();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(ElevatorDoorState::Opening);
+                self.context.on_transition(ElevatorDoorState::Closing, ElevatorDoorEvent::OpenRequest, ElevatorDoorState::Opening);
+                ElevatorDoorProcessResult::Transitioned { from: ElevatorDoorState::Closing, to: ElevatorDoorState::Opening }
+            }
+            _ => ElevatorDoorProcessResult::Ignored,
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_DWELL: usize = 0;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, ElevatorDoorEvent, bool); 1] = [
+        (4000, ElevatorDoorEvent::DwellElapsed, false), // dwell
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub(crate) fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+#[cfg(feature = "shared-events")]
+impl<T: ElevatorDoorActions> ElevatorDoor<T> {
+    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it
+    pub(crate) fn process_shared(&mut self, event: super::events::SharedEvent) -> ElevatorDoorProcessResult {
+        match ElevatorDoorEvent::try_from(event) {
+            Ok(event) => self.process(event),
+            Err(_) => ElevatorDoorProcessResult::Ignored,
+        }
+    }
+
+    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room
+    pub(crate) fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {
+        ElevatorDoorEvent::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)
+    }
+}
+
+// ── Actions trait ──
+pub(crate) trait ElevatorDoorActions {
+    fn lock_door(&mut self);
+    fn motor_close(&mut self);
+    fn motor_open(&mut self);
+    fn unlock_door(&mut self);
+
+    /// Called after every successful transition (e.g. for defmt/RTT logging)
+    fn on_transition(&mut self, _from: ElevatorDoorState, _event: ElevatorDoorEvent, _to: ElevatorDoorState) {}
+}
@@ -0,0 +1,189 @@
+//! Auto-generated FSM: ElevatorDoor
+//! Generated by Oxidate
+//!
+//! ElevatorDoorActions methods:
+//! - `fn lock_door(&mut self)`: entry of Closed
+//! - `fn motor_close(&mut self)`: entry of Closing
+//! - `fn motor_open(&mut self)`: entry of Opening
+//! - `fn unlock_door(&mut self)`: exit of Closed
+
+#![no_std]
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ElevatorDoorState {
+    Closed,
+    Opening,
+    /// Waiting for passengers
+    Open,
+    Closing,
+}
+
+impl ElevatorDoorState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            ElevatorDoorState::Closed => "Closed",
+            ElevatorDoorState::Opening => "Opening",
+            ElevatorDoorState::Open => "Open",
+            ElevatorDoorState::Closing => "Closing",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ElevatorDoorEvent {
+    DwellElapsed,
+    CloseRequest,
+    FullyClosed,
+    FullyOpen,
+    Obstruction,
+    OpenRequest,
+}
+
+impl ElevatorDoorEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            ElevatorDoorEvent::DwellElapsed => "DwellElapsed",
+            ElevatorDoorEvent::CloseRequest => "close_request",
+            ElevatorDoorEvent::FullyClosed => "fully_closed",
+            ElevatorDoorEvent::FullyOpen => "fully_open",
+            ElevatorDoorEvent::Obstruction => "obstruction",
+            ElevatorDoorEvent::OpenRequest => "open_request",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct ElevatorDoor<T: ElevatorDoorActions> {
+    state: ElevatorDoorState,
+    context: T,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 1],
+}
+
+impl<T: ElevatorDoorActions> ElevatorDoor<T> {
+    /// Create the machine and run its start actions, then the initial state's entry actions
+    pub fn new(mut context: T) -> Self {
+        context.lock_door();
+        Self {
+            state: ElevatorDoorState::Closed,
+            context,
+            timers: [None; 1],
+        }
+    }
+
+    pub fn state(&self) -> ElevatorDoorState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: ElevatorDoorEvent) -> bool {
+        match (self.state, event) {
+            (ElevatorDoorState::Closed, ElevatorDoorEvent::OpenRequest) => {
+                self.timers = [None; 1];
+                self.context.unlock_door();
+                self.state = ElevatorDoorState::Opening;
+                self.context.motor_open();
+                true
+            }
+            (ElevatorDoorState::Opening, ElevatorDoorEvent::FullyOpen) => {
+                self.timers = [None; 1];
+                self.state = ElevatorDoorState::Open;
+                self.timers[Self::TIMER_DWELL] = Some(0);
+                true
+            }
+            (ElevatorDoorState::Open, ElevatorDoorEvent::DwellElapsed) => {
+                self.timers = [None; 1];
+                self.state = ElevatorDoorState::Closing;
+                self.context.motor_close();
+                true
+            }
+            (ElevatorDoorState::Open, ElevatorDoorEvent::CloseRequest) => {
+                self.timers = [None; 1];
+                self.state = ElevatorDoorState::Closing;
+                self.context.motor_close();
+                true
+            }
+            (ElevatorDoorState::Closing, ElevatorDoorEvent::FullyClosed) => {
+                self.timers = [None; 1];
+                self.state = ElevatorDoorState::Closed;
+                self.context.lock_door();
+                true
+            }
+            (ElevatorDoorState::Closing, ElevatorDoorEvent::Obstruction) => {
+                self.timers = [None; 1];
+                self.state = ElevatorDoorState::Opening;
+                self.context.motor_open();
+                true
+            }
+            (ElevatorDoorState::Closing, ElevatorDoorEvent::OpenRequest) => {
+                self.timers = [None; 1];
+                self.state = ElevatorDoorState::Opening;
+                self.context.motor_open();
+                true
+            }
+            _ => false // No transition
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_DWELL: usize = 0;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, ElevatorDoorEvent, bool); 1] = [
+        (4000, ElevatorDoorEvent::DwellElapsed, false), // dwell
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+// ── Actions trait ──
+pub trait ElevatorDoorActions {
+    fn lock_door(&mut self);
+    fn motor_close(&mut self);
+    fn motor_open(&mut self);
+    fn unlock_door(&mut self);
+}
@@ -0,0 +1,333 @@
+//! Auto-generated FSM: FirmwareUpdate
+//! Generated by Oxidate
+//!
+//! Snapshot of every option
+//!
+//! FirmwareUpdateActions methods:
+//! - `fn checksum_ok(&self) -> bool`: Verifying --> Applying : [checksum_ok]
+//! - `fn compute_checksum(&mut self)`: entry of Verifying
+//! - `fn confirm_image(&mut self)`: Applying --> Running : reboot_done / confirm_image
+//! - `fn discard_image(&mut self)`: entry of Rejected
+//! - `fn on_transition(&mut self, from, event, to)`: every transition
+//! - `fn request_image(&mut self)`: entry of Downloading
+//! - `fn write_image(&mut self)`: entry of Applying
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum FirmwareUpdateState {
+    /// Current image
+    Running,
+    Downloading,
+    Verifying,
+    Applying,
+    Rejected,
+}
+
+impl FirmwareUpdateState {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            FirmwareUpdateState::Running => "Running",
+            FirmwareUpdateState::Downloading => "Downloading",
+            FirmwareUpdateState::Verifying => "Verifying",
+            FirmwareUpdateState::Applying => "Applying",
+            FirmwareUpdateState::Rejected => "Rejected",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum FirmwareUpdateEvent {
+    DownloadComplete = 0,
+    DownloadFailed = 1,
+    RebootDone = 2,
+    UpdateAvailable = 3,
+}
+
+impl FirmwareUpdateEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub(crate) const EVENT_NAMES: [&'static str; 4] = ["download_complete", "download_failed", "reboot_done", "update_available"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub(crate) const fn id(&self) -> u8 {
+        match self {
+            FirmwareUpdateEvent::DownloadComplete => 0,
+            FirmwareUpdateEvent::DownloadFailed => 1,
+            FirmwareUpdateEvent::RebootDone => 2,
+            FirmwareUpdateEvent::UpdateAvailable => 3,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            FirmwareUpdateEvent::DownloadComplete => "download_complete",
+            FirmwareUpdateEvent::DownloadFailed => "download_failed",
+            FirmwareUpdateEvent::RebootDone => "reboot_done",
+            FirmwareUpdateEvent::UpdateAvailable => "update_available",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `FirmwareUpdate::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum FirmwareUpdateProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: FirmwareUpdateState, to: FirmwareUpdateState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl FirmwareUpdateProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub(crate) const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+/// Dispatch counters of `FirmwareUpdate`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FirmwareUpdateMetrics {
+    state_entered: [u16; 5],
+    event_received: [u32; 4],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl FirmwareUpdateMetrics {
+    fn started(initial: FirmwareUpdateState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 5],
+            event_received: [0; 4],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: FirmwareUpdateState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub(crate) fn entered(&self, state: FirmwareUpdateState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: FirmwareUpdateEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub(crate) fn received(&self, event: FirmwareUpdateEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub(crate) fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub(crate) struct FirmwareUpdate<T: FirmwareUpdateActions> {
+    state: FirmwareUpdateState,
+    context: T,
+    started: bool,
+    queue: [Option<FirmwareUpdateEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: FirmwareUpdateMetrics,
+}
+
+impl<T: FirmwareUpdateActions> FirmwareUpdate<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub(crate) fn new(context: T) -> Self {
+        Self {
+            state: FirmwareUpdateState::Running,
+            context,
+            started: false,
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: FirmwareUpdateMetrics::started(FirmwareUpdateState::Running),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub(crate) fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub(crate) fn state(&self) -> FirmwareUpdateState {
+        self.state
+    }
+
+    pub(crate) fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub(crate) fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub(crate) fn metrics(&self) -> &FirmwareUpdateMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued
+    /// event is dropped to make room and returned
+    pub(crate) fn post(&mut self, event: FirmwareUpdateEvent) -> Option<FirmwareUpdateEvent> {
+        let mut dropped = None;
+        if self.queue_len == 4 {
+            dropped = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        dropped
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub(crate) fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event).is_handled() {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub(crate) fn process(&mut self, event: FirmwareUpdateEvent) -> FirmwareUpdateProcessResult {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (FirmwareUpdateState::Running, FirmwareUpdateEvent::UpdateAvailable) => {
+                self.state = FirmwareUpdateState::Downloading;
+                self.context.request_image();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(FirmwareUpdateState::Downloading);
+                self.context.on_transition(FirmwareUpdateState::Running, FirmwareUpdateEvent::UpdateAvailable, FirmwareUpdateState::Downloading);
+                FirmwareUpdateProcessResult::Transitioned { from: FirmwareUpdateState::Running, to: FirmwareUpdateState::Downloading }
+            }
+            (FirmwareUpdateState::Downloading, FirmwareUpdateEvent::DownloadComplete) => {
+                self.state = FirmwareUpdateState::Verifying;
+                self.context.compute_checksum();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(FirmwareUpdateState::Verifying);
+                self.context.on_transition(FirmwareUpdateState::Downloading, FirmwareUpdateEvent::DownloadComplete, FirmwareUpdateState::Verifying);
+                self.run_completions();
+                FirmwareUpdateProcessResult::Transitioned { from: FirmwareUpdateState::Downloading, to: self.state }
+            }
+            (FirmwareUpdateState::Downloading, FirmwareUpdateEvent::DownloadFailed) => {
+                self.state = FirmwareUpdateState::Running;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(FirmwareUpdateState::Running);
+                self.context.on_transition(FirmwareUpdateState::Downloading, FirmwareUpdateEvent::DownloadFailed, FirmwareUpdateState::Running);
+                FirmwareUpdateProcessResult::Transitioned { from: FirmwareUpdateState::Downloading, to: FirmwareUpdateState::Running }
+            }
+            (FirmwareUpdateState::Applying, FirmwareUpdateEvent::RebootDone) => {
+                self.context.confirm_image();
+                self.state = FirmwareUpdateState::Running;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(FirmwareUpdateState::Running);
+                self.context.on_transition(FirmwareUpdateState::Applying, FirmwareUpdateEvent::RebootDone, FirmwareUpdateState::Running);
+                FirmwareUpdateProcessResult::Transitioned { from: FirmwareUpdateState::Applying, to: FirmwareUpdateState::Running }
+            }
+            _ => FirmwareUpdateProcessResult::Ignored,
+        }
+    }
+
+    /// Take the completion (event-less) transitions of the state just entered, one
+    /// after the other, at most 16 in a row
+    fn run_completions(&mut self) {
+        const MAX_COMPLETION_HOPS: usize = 16;
+        for _ in 0..MAX_COMPLETION_HOPS {
+            match self.state {
+                FirmwareUpdateState::Verifying if self.context.checksum_ok() => {
+                    self.state = FirmwareUpdateState::Applying;
+                    self.context.write_image();
+                    #[cfg(feature = "fsm-metrics")]
+                    self.metrics.count_entry(FirmwareUpdateState::Applying);
+                }
+                FirmwareUpdateState::Verifying => {
+                    self.state = FirmwareUpdateState::Rejected;
+                    self.context.discard_image();
+                    #[cfg(feature = "fsm-metrics")]
+                    self.metrics.count_entry(FirmwareUpdateState::Rejected);
+                }
+                FirmwareUpdateState::Rejected => {
+                    self.state = FirmwareUpdateState::Running;
+                    #[cfg(feature = "fsm-metrics")]
+                    self.metrics.count_entry(FirmwareUpdateState::Running);
+                }
+                _ => return,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "shared-events")]
+impl<T: FirmwareUpdateActions> FirmwareUpdate<T> {
+    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it
+    pub(crate) fn process_shared(&mut self, event: super::events::SharedEvent) -> FirmwareUpdateProcessResult {
+        match FirmwareUpdateEvent::try_from(event) {
+            Ok(event) => self.process(event),
+            Err(_) => FirmwareUpdateProcessResult::Ignored,
+        }
+    }
+
+    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room
+    pub(crate) fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {
+        FirmwareUpdateEvent::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)
+    }
+}
+
+// ── Actions trait ──
+pub(crate) trait FirmwareUpdateActions {
+    fn compute_checksum(&mut self);
+    fn confirm_image(&mut self);
+    fn discard_image(&mut self);
+    fn request_image(&mut self);
+    fn write_image(&mut self);
+    fn checksum_ok(&self) -> bool;
+
+    /// Called after every successful transition (e.g. for defmt/RTT logging)
+    fn on_transition(&mut self, _from: FirmwareUpdateState, _event: FirmwareUpdateEvent, _to: FirmwareUpdateState) {}
+}
@@ -0,0 +1,156 @@
+//! Auto-generated FSM: FirmwareUpdate
+//! Generated by Oxidate
+//!
+//! FirmwareUpdateActions methods:
+//! - `fn checksum_ok(&self) -> bool`: Verifying --> Applying : [checksum_ok]
+//! - `fn compute_checksum(&mut self)`: entry of Verifying
+//! - `fn confirm_image(&mut self)`: Applying --> Running : reboot_done / confirm_image
+//! - `fn discard_image(&mut self)`: entry of Rejected
+//! - `fn request_image(&mut self)`: entry of Downloading
+//! - `fn write_image(&mut self)`: entry of Applying
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FirmwareUpdateState {
+    /// Current image
+    Running,
+    Downloading,
+    Verifying,
+    Applying,
+    Rejected,
+}
+
+impl FirmwareUpdateState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            FirmwareUpdateState::Running => "Running",
+            FirmwareUpdateState::Downloading => "Downloading",
+            FirmwareUpdateState::Verifying => "Verifying",
+            FirmwareUpdateState::Applying => "Applying",
+            FirmwareUpdateState::Rejected => "Rejected",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FirmwareUpdateEvent {
+    DownloadComplete,
+    DownloadFailed,
+    RebootDone,
+    UpdateAvailable,
+}
+
+impl FirmwareUpdateEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            FirmwareUpdateEvent::DownloadComplete => "download_complete",
+            FirmwareUpdateEvent::DownloadFailed => "download_failed",
+            FirmwareUpdateEvent::RebootDone => "reboot_done",
+            FirmwareUpdateEvent::UpdateAvailable => "update_available",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct FirmwareUpdate<T: FirmwareUpdateActions> {
+    state: FirmwareUpdateState,
+    context: T,
+    started: bool,
+}
+
+impl<T: FirmwareUpdateActions> FirmwareUpdate<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub fn new(context: T) -> Self {
+        Self {
+            state: FirmwareUpdateState::Running,
+            context,
+            started: false,
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub fn state(&self) -> FirmwareUpdateState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: FirmwareUpdateEvent) -> bool {
+        match (self.state, event) {
+            (FirmwareUpdateState::Running, FirmwareUpdateEvent::UpdateAvailable) => {
+                self.state = FirmwareUpdateState::Downloading;
+                self.context.request_image();
+                true
+            }
+            (FirmwareUpdateState::Downloading, FirmwareUpdateEvent::DownloadComplete) => {
+                self.state = FirmwareUpdateState::Verifying;
+                self.context.compute_checksum();
+                self.run_completions();
+                true
+            }
+            (FirmwareUpdateState::Downloading, FirmwareUpdateEvent::DownloadFailed) => {
+                self.state = FirmwareUpdateState::Running;
+                true
+            }
+            (FirmwareUpdateState::Applying, FirmwareUpdateEvent::RebootDone) => {
+                self.context.confirm_image();
+                self.state = FirmwareUpdateState::Running;
+                true
+            }
+            _ => false // No transition
+        }
+    }
+
+    /// Take the completion (event-less) transitions of the state just entered, one
+    /// after the other, at most 16 in a row
+    fn run_completions(&mut self) {
+        const MAX_COMPLETION_HOPS: usize = 16;
+        for _ in 0..MAX_COMPLETION_HOPS {
+            match self.state {
+                FirmwareUpdateState::Verifying if self.context.checksum_ok() => {
+                    self.state = FirmwareUpdateState::Applying;
+                    self.context.write_image();
+                }
+                FirmwareUpdateState::Verifying => {
+                    self.state = FirmwareUpdateState::Rejected;
+                    self.context.discard_image();
+                }
+                FirmwareUpdateState::Rejected => {
+                    self.state = FirmwareUpdateState::Running;
+                }
+                _ => return,
+            }
+        }
+    }
+}
+
+// ── Actions trait ──
+pub trait FirmwareUpdateActions {
+    fn compute_checksum(&mut self);
+    fn confirm_image(&mut self);
+    fn discard_image(&mut self);
+    fn request_image(&mut self);
+    fn write_image(&mut self);
+    fn checksum_ok(&self) -> bool;
+}
@@ -0,0 +1,396 @@
+//! Auto-generated FSM: LoginSession
+//! Generated by Oxidate
+//!
+//! Snapshot of every option
+//!
+//! LoginSessionActions methods:
+//! - `fn check_credentials(&mut self)`: entry of Authenticating
+//! - `fn count_attempt(&mut self)`: Authenticating --> LoggedOut : auth_failed [else] / count_attempt
+//! - `fn notify_expired(&mut self)`: LoggedIn --> LoggedOut : IdleTimeout / notify_expired
+//! - `fn on_transition(&mut self, from, event, to)`: every transition
+//! - `fn reset_attempts(&mut self)`: exit of LockedOut, Authenticating --> LoggedIn : auth_ok / reset_attempts
+//! - `fn restart_idle_timer(&mut self)`: internal of LoggedIn : activity / restart_idle_timer
+//! - `fn too_many_attempts(&self) -> bool`: Authenticating --> LockedOut : auth_failed [too_many_attempts]
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum LoginSessionState {
+    LoggedOut,
+    Authenticating,
+    LoggedIn,
+    /// Too many failed attempts
+    LockedOut,
+}
+
+impl LoginSessionState {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            LoginSessionState::LoggedOut => "LoggedOut",
+            LoginSessionState::Authenticating => "Authenticating",
+            LoginSessionState::LoggedIn => "LoggedIn",
+            LoginSessionState::LockedOut => "LockedOut",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum LoginSessionEvent {
+    IdleTimeout = 0,
+    LockoutElapsed = 1,
+    Activity = 2,
+    AuthFailed = 3,
+    AuthOk = 4,
+    Logout = 5,
+    Submit = 6,
+}
+
+impl LoginSessionEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub(crate) const EVENT_NAMES: [&'static str; 7] = ["IdleTimeout", "LockoutElapsed", "activity", "auth_failed", "auth_ok", "logout", "submit"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub(crate) const fn id(&self) -> u8 {
+        match self {
+            LoginSessionEvent::IdleTimeout => 0,
+            LoginSessionEvent::LockoutElapsed => 1,
+            LoginSessionEvent::Activity => 2,
+            LoginSessionEvent::AuthFailed => 3,
+            LoginSessionEvent::AuthOk => 4,
+            LoginSessionEvent::Logout => 5,
+            LoginSessionEvent::Submit => 6,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            LoginSessionEvent::IdleTimeout => "IdleTimeout",
+            LoginSessionEvent::LockoutElapsed => "LockoutElapsed",
+            LoginSessionEvent::Activity => "activity",
+            LoginSessionEvent::AuthFailed => "auth_failed",
+            LoginSessionEvent::AuthOk => "auth_ok",
+            LoginSessionEvent::Logout => "logout",
+            LoginSessionEvent::Submit => "submit",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `LoginSession::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum LoginSessionProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: LoginSessionState, to: LoginSessionState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl LoginSessionProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub(crate) const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+/// Dispatch counters of `LoginSession`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LoginSessionMetrics {
+    state_entered: [u16; 4],
+    event_received: [u32; 7],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl LoginSessionMetrics {
+    fn started(initial: LoginSessionState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 4],
+            event_received: [0; 7],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: LoginSessionState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub(crate) fn entered(&self, state: LoginSessionState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: LoginSessionEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub(crate) fn received(&self, event: LoginSessionEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub(crate) fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub(crate) struct LoginSession<T: LoginSessionActions> {
+    state: LoginSessionState,
+    context: T,
+    started: bool,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 2],
+    queue: [Option<LoginSessionEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: LoginSessionMetrics,
+}
+
+impl<T: LoginSessionActions> LoginSession<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub(crate) fn new(context: T) -> Self {
+        Self {
+            state: LoginSessionState::LoggedOut,
+            context,
+            started: false,
+            timers: [None; 2],
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: LoginSessionMetrics::started(LoginSessionState::LoggedOut),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub(crate) fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub(crate) fn state(&self) -> LoginSessionState {
+        self.state
+    }
+
+    pub(crate) fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub(crate) fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub(crate) fn metrics(&self) -> &LoginSessionMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued
+    /// event is dropped to make room and returned
+    pub(crate) fn post(&mut self, event: LoginSessionEvent) -> Option<LoginSessionEvent> {
+        let mut dropped = None;
+        if self.queue_len == 4 {
+            dropped = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        dropped
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub(crate) fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event).is_handled() {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    ///
+    /// A state's own transitions, self-transitions included, are tried before its
+    /// internal transitions, which run only their actions
+    pub(crate) fn process(&mut self, event: LoginSessionEvent) -> LoginSessionProcessResult {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (LoginSessionState::LoggedOut, LoginSessionEvent::Submit) => {
+                self.timers = [None; 2];
+                self.state = LoginSessionState::Authenticating;
+                self.context.check_credentials();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(LoginSessionState::Authenticating);
+                self.context.on_transition(LoginSessionState::LoggedOut, LoginSessionEvent::Submit, LoginSessionState::Authenticating);
+                LoginSessionProcessResult::Transitioned { from: LoginSessionState::LoggedOut, to: LoginSessionState::Authenticating }
+            }
+            (LoginSessionState::Authenticating, LoginSessionEvent::AuthOk) => {
+                self.timers = [None; 2];
+                self.context.reset_attempts();
+                self.state = LoginSessionState::LoggedIn;
+                self.timers[Self::TIMER_IDLE] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(LoginSessionState::LoggedIn);
+                self.context.on_transition(LoginSessionState::Authenticating, LoginSessionEvent::AuthOk, LoginSessionState::LoggedIn);
+                LoginSessionProcessResult::Transitioned { from: LoginSessionState::Authenticating, to: LoginSessionState::LoggedIn }
+            }
+            (LoginSessionState::Authenticating, LoginSessionEvent::AuthFailed) if self.context.too_many_attempts() => {
+                self.timers = [None; 2];
+                self.state = LoginSessionState::LockedOut;
+                self.timers[Self::TIMER_LOCKOUT] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(LoginSessionState::LockedOut);
+                self.context.on_transition(LoginSessionState::Authenticating, LoginSessionEvent::AuthFailed, LoginSessionState::LockedOut);
+                LoginSessionProcessResult::Transitioned { from: LoginSessionState::Authenticating, to: LoginSessionState::LockedOut }
+            }
+            (LoginSessionState::Authenticating, LoginSessionEvent::AuthFailed) => {
+                self.timers = [None; 2];
+                self.context.count_attempt();
+                self.state = LoginSessionState::LoggedOut;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(LoginSessionState::LoggedOut);
+                self.context.on_transition(LoginSessionState::Authenticating, LoginSessionEvent::AuthFailed, LoginSessionState::LoggedOut);
+                LoginSessionProcessResult::Transitioned { from: LoginSessionState::Authenticating, to: LoginSessionState::LoggedOut }
+            }
+            (LoginSessionState::LoggedIn, LoginSessionEvent::Logout) => {
+                self.timers = [None; 2];
+                self.state = LoginSessionState::LoggedOut;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(LoginSessionState::LoggedOut);
+                self.context.on_transition(LoginSessionState::LoggedIn, LoginSessionEvent::Logout, LoginSessionState::LoggedOut);
+                LoginSessionProcessResult::Transitioned { from: LoginSessionState::LoggedIn, to: LoginSessionState::LoggedOut }
+            }
+            (LoginSessionState::LoggedIn, LoginSessionEvent::IdleTimeout) => {
+                self.timers = [None; 2];
+                self.context.notify_expired();
+                self.state = LoginSessionState::LoggedOut;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(LoginSessionState::LoggedOut);
+                self.context.on_transition(LoginSessionState::LoggedIn, LoginSessionEvent::IdleTimeout, LoginSessionState::LoggedOut);
+                LoginSessionProcessResult::Transitioned { from: LoginSessionState::LoggedIn, to: LoginSessionState::LoggedOut }
+            }
+            (LoginSessionState::LockedOut, LoginSessionEvent::LockoutElapsed) => {
+                self.timers = [None; 2];
+                self.context.reset_attempts();
+                self.state = LoginSessionState::LoggedOut;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(LoginSessionState::LoggedOut);
+                self.context.on_transition(LoginSessionState::LockedOut, LoginSessionEvent::LockoutElapsed, LoginSessionState::LoggedOut);
+                LoginSessionProcessResult::Transitioned { from: LoginSessionState::LockedOut, to: LoginSessionState::LoggedOut }
+            }
+            (LoginSessionState::LoggedIn, LoginSessionEvent::Activity) => {
+                self.context.restart_idle_timer();
+                LoginSessionProcessResult::InternalHandled
+            }
+            _ => LoginSessionProcessResult::Ignored,
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_LOCKOUT: usize = 0;
+    const TIMER_IDLE: usize = 1;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, LoginSessionEvent, bool); 2] = [
+        (60000, LoginSessionEvent::LockoutElapsed, false), // lockout
+        (900000, LoginSessionEvent::IdleTimeout, false), // idle
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub(crate) fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+#[cfg(feature = "shared-events")]
+impl<T: LoginSessionActions> LoginSession<T> {
+    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it
+    pub(crate) fn process_shared(&mut self, event: super::events::SharedEvent) -> LoginSessionProcessResult {
+        match LoginSessionEvent::try_from(event) {
+            Ok(event) => self.process(event),
+            Err(_) => LoginSessionProcessResult::Ignored,
+        }
+    }
+
+    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room
+    pub(crate) fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {
+        LoginSessionEvent::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)
+    }
+}
+
+// ── Actions trait ──
+pub(crate) trait LoginSessionActions {
+    fn check_credentials(&mut self);
+    fn count_attempt(&mut self);
+    fn notify_expired(&mut self);
+    fn reset_attempts(&mut self);
+    fn restart_idle_timer(&mut self);
+    fn too_many_attempts(&self) -> bool;
+
+    /// Called after every successful transition (e.g. for defmt/RTT logging)
+    fn on_transition(&mut self, _from: LoginSessionState, _event: LoginSessionEvent, _to: LoginSessionState) {}
+}
@@ -0,0 +1,213 @@
+//! Auto-generated FSM: LoginSession
+//! Generated by Oxidate
+//!
+//! LoginSessionActions methods:
+//! - `fn check_credentials(&mut self)`: entry of Authenticating
+//! - `fn count_attempt(&mut self)`: Authenticating --> LoggedOut : auth_failed [else] / count_attempt
+//! - `fn notify_expired(&mut self)`: LoggedIn --> LoggedOut : IdleTimeout / notify_expired
+//! - `fn reset_attempts(&mut self)`: exit of LockedOut, Authenticating --> LoggedIn : auth_ok / reset_attempts
+//! - `fn restart_idle_timer(&mut self)`: internal of LoggedIn : activity / restart_idle_timer
+//! - `fn too_many_attempts(&self) -> bool`: Authenticating --> LockedOut : auth_failed [too_many_attempts]
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LoginSessionState {
+    LoggedOut,
+    Authenticating,
+    LoggedIn,
+    /// Too many failed attempts
+    LockedOut,
+}
+
+impl LoginSessionState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            LoginSessionState::LoggedOut => "LoggedOut",
+            LoginSessionState::Authenticating => "Authenticating",
+            LoginSessionState::LoggedIn => "LoggedIn",
+            LoginSessionState::LockedOut => "LockedOut",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LoginSessionEvent {
+    IdleTimeout,
+    LockoutElapsed,
+    Activity,
+    AuthFailed,
+    AuthOk,
+    Logout,
+    Submit,
+}
+
+impl LoginSessionEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            LoginSessionEvent::IdleTimeout => "IdleTimeout",
+            LoginSessionEvent::LockoutElapsed => "LockoutElapsed",
+            LoginSessionEvent::Activity => "activity",
+            LoginSessionEvent::AuthFailed => "auth_failed",
+            LoginSessionEvent::AuthOk => "auth_ok",
+            LoginSessionEvent::Logout => "logout",
+            LoginSessionEvent::Submit => "submit",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct LoginSession<T: LoginSessionActions> {
+    state: LoginSessionState,
+    context: T,
+    started: bool,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 2],
+}
+
+impl<T: LoginSessionActions> LoginSession<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub fn new(context: T) -> Self {
+        Self {
+            state: LoginSessionState::LoggedOut,
+            context,
+            started: false,
+            timers: [None; 2],
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub fn state(&self) -> LoginSessionState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    ///
+    /// A state's own transitions, self-transitions included, are tried before its
+    /// internal transitions, which run only their actions
+    pub fn process(&mut self, event: LoginSessionEvent) -> bool {
+        match (self.state, event) {
+            (LoginSessionState::LoggedOut, LoginSessionEvent::Submit) => {
+                self.timers = [None; 2];
+                self.state = LoginSessionState::Authenticating;
+                self.context.check_credentials();
+                true
+            }
+            (LoginSessionState::Authenticating, LoginSessionEvent::AuthOk) => {
+                self.timers = [None; 2];
+                self.context.reset_attempts();
+                self.state = LoginSessionState::LoggedIn;
+                self.timers[Self::TIMER_IDLE] = Some(0);
+                true
+            }
+            (LoginSessionState::Authenticating, LoginSessionEvent::AuthFailed) if self.context.too_many_attempts() => {
+                self.timers = [None; 2];
+                self.state = LoginSessionState::LockedOut;
+                self.timers[Self::TIMER_LOCKOUT] = Some(0);
+                true
+            }
+            (LoginSessionState::Authenticating, LoginSessionEvent::AuthFailed) => {
+                self.timers = [None; 2];
+                self.context.count_attempt();
+                self.state = LoginSessionState::LoggedOut;
+                true
+            }
+            (LoginSessionState::LoggedIn, LoginSessionEvent::Logout) => {
+                self.timers = [None; 2];
+                self.state = LoginSessionState::LoggedOut;
+                true
+            }
+            (LoginSessionState::LoggedIn, LoginSessionEvent::IdleTimeout) => {
+                self.timers = [None; 2];
+                self.context.notify_expired();
+                self.state = LoginSessionState::LoggedOut;
+                true
+            }
+            (LoginSessionState::LockedOut, LoginSessionEvent::LockoutElapsed) => {
+                self.timers = [None; 2];
+                self.context.reset_attempts();
+                self.state = LoginSessionState::LoggedOut;
+                true
+            }
+            (LoginSessionState::LoggedIn, LoginSessionEvent::Activity) => {
+                self.context.restart_idle_timer();
+                true
+            }
+            _ => false // No transition
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_LOCKOUT: usize = 0;
+    const TIMER_IDLE: usize = 1;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, LoginSessionEvent, bool); 2] = [
+        (60000, LoginSessionEvent::LockoutElapsed, false), // lockout
+        (900000, LoginSessionEvent::IdleTimeout, false), // idle
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+// ── Actions trait ──
+pub trait LoginSessionActions {
+    fn check_credentials(&mut self);
+    fn count_attempt(&mut self);
+    fn notify_expired(&mut self);
+    fn reset_attempts(&mut self);
+    fn restart_idle_timer(&mut self);
+    fn too_many_attempts(&self) -> bool;
+}
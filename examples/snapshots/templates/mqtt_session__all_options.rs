@@ -0,0 +1,398 @@
+//! Auto-generated FSM: MqttSession
+//! Generated by Oxidate
+//!
+//! Snapshot of every option
+//!
+//! MqttSessionActions methods:
+//! - `fn accepted(&self) -> bool`: Connecting --> Connected : connack [accepted]
+//! - `fn drop_socket(&mut self)`: AwaitingPong --> Disconnected : PingTimeout / drop_socket
+//! - `fn on_transition(&mut self, from, event, to)`: every transition
+//! - `fn report_refused(&mut self)`: Connecting --> Disconnected : connack [else] / report_refused
+//! - `fn send_connect(&mut self)`: Disconnected --> Connecting : connect / send_connect
+//! - `fn send_disconnect(&mut self)`: Connected --> Disconnected : disconnect / send_disconnect
+//! - `fn send_pingreq(&mut self)`: internal of Connected : KeepAlive / send_pingreq
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum MqttSessionState {
+    Disconnected,
+    /// CONNECT sent
+    Connecting,
+    /// Session up
+    Connected,
+    /// PINGREQ outstanding
+    AwaitingPong,
+}
+
+impl MqttSessionState {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            MqttSessionState::Disconnected => "Disconnected",
+            MqttSessionState::Connecting => "Connecting",
+            MqttSessionState::Connected => "Connected",
+            MqttSessionState::AwaitingPong => "AwaitingPong",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum MqttSessionEvent {
+    KeepAlive = 0,
+    PingTimeout = 1,
+    Connack = 2,
+    Connect = 3,
+    Disconnect = 4,
+    PingDue = 5,
+    Pingresp = 6,
+}
+
+impl MqttSessionEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub(crate) const EVENT_NAMES: [&'static str; 7] = ["KeepAlive", "PingTimeout", "connack", "connect", "disconnect", "ping_due", "pingresp"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub(crate) const fn id(&self) -> u8 {
+        match self {
+            MqttSessionEvent::KeepAlive => 0,
+            MqttSessionEvent::PingTimeout => 1,
+            MqttSessionEvent::Connack => 2,
+            MqttSessionEvent::Connect => 3,
+            MqttSessionEvent::Disconnect => 4,
+            MqttSessionEvent::PingDue => 5,
+            MqttSessionEvent::Pingresp => 6,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            MqttSessionEvent::KeepAlive => "KeepAlive",
+            MqttSessionEvent::PingTimeout => "PingTimeout",
+            MqttSessionEvent::Connack => "connack",
+            MqttSessionEvent::Connect => "connect",
+            MqttSessionEvent::Disconnect => "disconnect",
+            MqttSessionEvent::PingDue => "ping_due",
+            MqttSessionEvent::Pingresp => "pingresp",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `MqttSession::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum MqttSessionProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: MqttSessionState, to: MqttSessionState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl MqttSessionProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub(crate) const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+/// Dispatch counters of `MqttSession`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MqttSessionMetrics {
+    state_entered: [u16; 4],
+    event_received: [u32; 7],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl MqttSessionMetrics {
+    fn started(initial: MqttSessionState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 4],
+            event_received: [0; 7],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: MqttSessionState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub(crate) fn entered(&self, state: MqttSessionState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: MqttSessionEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub(crate) fn received(&self, event: MqttSessionEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub(crate) fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub(crate) struct MqttSession<T: MqttSessionActions> {
+    state: MqttSessionState,
+    context: T,
+    started: bool,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 2],
+    queue: [Option<MqttSessionEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: MqttSessionMetrics,
+}
+
+impl<T: MqttSessionActions> MqttSession<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub(crate) fn new(context: T) -> Self {
+        Self {
+            state: MqttSessionState::Disconnected,
+            context,
+            started: false,
+            timers: [None; 2],
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: MqttSessionMetrics::started(MqttSessionState::Disconnected),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub(crate) fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub(crate) fn state(&self) -> MqttSessionState {
+        self.state
+    }
+
+    pub(crate) fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub(crate) fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub(crate) fn metrics(&self) -> &MqttSessionMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued
+    /// event is dropped to make room and returned
+    pub(crate) fn post(&mut self, event: MqttSessionEvent) -> Option<MqttSessionEvent> {
+        let mut dropped = None;
+        if self.queue_len == 4 {
+            dropped = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        dropped
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub(crate) fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event).is_handled() {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    ///
+    /// A state's own transitions, self-transitions included, are tried before its
+    /// internal transitions, which run only their actions
+    pub(crate) fn process(&mut self, event: MqttSessionEvent) -> MqttSessionProcessResult {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (MqttSessionState::Disconnected, MqttSessionEvent::Connect) => {
+                self.timers = [None; 2];
+                self.context.send_connect();
+                self.state = MqttSessionState::Connecting;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(MqttSessionState::Connecting);
+                self.context.on_transition(MqttSessionState::Disconnected, MqttSessionEvent::Connect, MqttSessionState::Connecting);
+                MqttSessionProcessResult::Transitioned { from: MqttSessionState::Disconnected, to: MqttSessionState::Connecting }
+            }
+            (MqttSessionState::Connecting, MqttSessionEvent::Connack) if self.context.accepted() => {
+                self.timers = [None; 2];
+                self.state = MqttSessionState::Connected;
+                self.timers[Self::TIMER_KEEP_ALIVE] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(MqttSessionState::Connected);
+                self.context.on_transition(MqttSessionState::Connecting, MqttSessionEvent::Connack, MqttSessionState::Connected);
+                MqttSessionProcessResult::Transitioned { from: MqttSessionState::Connecting, to: MqttSessionState::Connected }
+            }
+            (MqttSessionState::Connecting, MqttSessionEvent::Connack) => {
+                self.timers = [None; 2];
+                self.context.report_refused();
+                self.state = MqttSessionState::Disconnected;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(MqttSessionState::Disconnected);
+                self.context.on_transition(MqttSessionState::Connecting, MqttSessionEvent::Connack, MqttSessionState::Disconnected);
+                MqttSessionProcessResult::Transitioned { from: MqttSessionState::Connecting, to: MqttSessionState::Disconnected }
+            }
+            (MqttSessionState::Connected, MqttSessionEvent::PingDue) => {
+                self.timers = [None; 2];
+                self.state = MqttSessionState::AwaitingPong;
+                self.timers[Self::TIMER_PING_TIMEOUT] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(MqttSessionState::AwaitingPong);
+                self.context.on_transition(MqttSessionState::Connected, MqttSessionEvent::PingDue, MqttSessionState::AwaitingPong);
+                MqttSessionProcessResult::Transitioned { from: MqttSessionState::Connected, to: MqttSessionState::AwaitingPong }
+            }
+            (MqttSessionState::AwaitingPong, MqttSessionEvent::Pingresp) => {
+                self.timers = [None; 2];
+                self.state = MqttSessionState::Connected;
+                self.timers[Self::TIMER_KEEP_ALIVE] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(MqttSessionState::Connected);
+                self.context.on_transition(MqttSessionState::AwaitingPong, MqttSessionEvent::Pingresp, MqttSessionState::Connected);
+                MqttSessionProcessResult::Transitioned { from: MqttSessionState::AwaitingPong, to: MqttSessionState::Connected }
+            }
+            (MqttSessionState::AwaitingPong, MqttSessionEvent::PingTimeout) => {
+                self.timers = [None; 2];
+                self.context.drop_socket();
+                self.state = MqttSessionState::Disconnected;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(MqttSessionState::Disconnected);
+                self.context.on_transition(MqttSessionState::AwaitingPong, MqttSessionEvent::PingTimeout, MqttSessionState::Disconnected);
+                MqttSessionProcessResult::Transitioned { from: MqttSessionState::AwaitingPong, to: MqttSessionState::Disconnected }
+            }
+            (MqttSessionState::Connected, MqttSessionEvent::Disconnect) => {
+                self.timers = [None; 2];
+                self.context.send_disconnect();
+                self.state = MqttSessionState::Disconnected;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(MqttSessionState::Disconnected);
+                self.context.on_transition(MqttSessionState::Connected, MqttSessionEvent::Disconnect, MqttSessionState::Disconnected);
+                MqttSessionProcessResult::Transitioned { from: MqttSessionState::Connected, to: MqttSessionState::Disconnected }
+            }
+            (MqttSessionState::Connected, MqttSessionEvent::KeepAlive) => {
+                self.context.send_pingreq();
+                MqttSessionProcessResult::InternalHandled
+            }
+            _ => MqttSessionProcessResult::Ignored,
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_KEEP_ALIVE: usize = 0;
+    const TIMER_PING_TIMEOUT: usize = 1;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, MqttSessionEvent, bool); 2] = [
+        (30000, MqttSessionEvent::KeepAlive, true), // keep_alive
+        (5000, MqttSessionEvent::PingTimeout, false), // ping_timeout
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub(crate) fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+#[cfg(feature = "shared-events")]
+impl<T: MqttSessionActions> MqttSession<T> {
+    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it
+    pub(crate) fn process_shared(&mut self, event: super::events::SharedEvent) -> MqttSessionProcessResult {
+        match MqttSessionEvent::try_from(event) {
+            Ok(event) => self.process(event),
+            Err(_) => MqttSessionProcessResult::Ignored,
+        }
+    }
+
+    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room
+    pub(crate) fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {
+        MqttSessionEvent::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)
+    }
+}
+
+// ── Actions trait ──
+pub(crate) trait MqttSessionActions {
+    fn drop_socket(&mut self);
+    fn report_refused(&mut self);
+    fn send_connect(&mut self);
+    fn send_disconnect(&mut self);
+    fn send_pingreq(&mut self);
+    fn accepted(&self) -> bool;
+
+    /// Called after every successful transition (e.g. for defmt/RTT logging)
+    fn on_transition(&mut self, _from: MqttSessionState, _event: MqttSessionEvent, _to: MqttSessionState) {}
+}
@@ -0,0 +1,215 @@
+//! Auto-generated FSM: MqttSession
+//! Generated by Oxidate
+//!
+//! MqttSessionActions methods:
+//! - `fn accepted(&self) -> bool`: Connecting --> Connected : connack [accepted]
+//! - `fn drop_socket(&mut self)`: AwaitingPong --> Disconnected : PingTimeout / drop_socket
+//! - `fn report_refused(&mut self)`: Connecting --> Disconnected : connack [else] / report_refused
+//! - `fn send_connect(&mut self)`: Disconnected --> Connecting : connect / send_connect
+//! - `fn send_disconnect(&mut self)`: Connected --> Disconnected : disconnect / send_disconnect
+//! - `fn send_pingreq(&mut self)`: internal of Connected : KeepAlive / send_pingreq
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MqttSessionState {
+    Disconnected,
+    /// CONNECT sent
+    Connecting,
+    /// Session up
+    Connected,
+    /// PINGREQ outstanding
+    AwaitingPong,
+}
+
+impl MqttSessionState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            MqttSessionState::Disconnected => "Disconnected",
+            MqttSessionState::Connecting => "Connecting",
+            MqttSessionState::Connected => "Connected",
+            MqttSessionState::AwaitingPong => "AwaitingPong",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MqttSessionEvent {
+    KeepAlive,
+    PingTimeout,
+    Connack,
+    Connect,
+    Disconnect,
+    PingDue,
+    Pingresp,
+}
+
+impl MqttSessionEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            MqttSessionEvent::KeepAlive => "KeepAlive",
+            MqttSessionEvent::PingTimeout => "PingTimeout",
+            MqttSessionEvent::Connack => "connack",
+            MqttSessionEvent::Connect => "connect",
+            MqttSessionEvent::Disconnect => "disconnect",
+            MqttSessionEvent::PingDue => "ping_due",
+            MqttSessionEvent::Pingresp => "pingresp",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct MqttSession<T: MqttSessionActions> {
+    state: MqttSessionState,
+    context: T,
+    started: bool,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 2],
+}
+
+impl<T: MqttSessionActions> MqttSession<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub fn new(context: T) -> Self {
+        Self {
+            state: MqttSessionState::Disconnected,
+            context,
+            started: false,
+            timers: [None; 2],
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub fn state(&self) -> MqttSessionState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    ///
+    /// A state's own transitions, self-transitions included, are tried before its
+    /// internal transitions, which run only their actions
+    pub fn process(&mut self, event: MqttSessionEvent) -> bool {
+        match (self.state, event) {
+            (MqttSessionState::Disconnected, MqttSessionEvent::Connect) => {
+                self.timers = [None; 2];
+                self.context.send_connect();
+                self.state = MqttSessionState::Connecting;
+                true
+            }
+            (MqttSessionState::Connecting, MqttSessionEvent::Connack) if self.context.accepted() => {
+                self.timers = [None; 2];
+                self.state = MqttSessionState::Connected;
+                self.timers[Self::TIMER_KEEP_ALIVE] = Some(0);
+                true
+            }
+            (MqttSessionState::Connecting, MqttSessionEvent::Connack) => {
+                self.timers = [None; 2];
+                self.context.report_refused();
+                self.state = MqttSessionState::Disconnected;
+                true
+            }
+            (MqttSessionState::Connected, MqttSessionEvent::PingDue) => {
+                self.timers = [None; 2];
+                self.state = MqttSessionState::AwaitingPong;
+                self.timers[Self::TIMER_PING_TIMEOUT] = Some(0);
+                true
+            }
+            (MqttSessionState::AwaitingPong, MqttSessionEvent::Pingresp) => {
+                self.timers = [None; 2];
+                self.state = MqttSessionState::Connected;
+                self.timers[Self::TIMER_KEEP_ALIVE] = Some(0);
+                true
+            }
+            (MqttSessionState::AwaitingPong, MqttSessionEvent::PingTimeout) => {
+                self.timers = [None; 2];
+                self.context.drop_socket();
+                self.state = MqttSessionState::Disconnected;
+                true
+            }
+            (MqttSessionState::Connected, MqttSessionEvent::Disconnect) => {
+                self.timers = [None; 2];
+                self.context.send_disconnect();
+                self.state = MqttSessionState::Disconnected;
+                true
+            }
+            (MqttSessionState::Connected, MqttSessionEvent::KeepAlive) => {
+                self.context.send_pingreq();
+                true
+            }
+            _ => false // No transition
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_KEEP_ALIVE: usize = 0;
+    const TIMER_PING_TIMEOUT: usize = 1;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, MqttSessionEvent, bool); 2] = [
+        (30000, MqttSessionEvent::KeepAlive, true), // keep_alive
+        (5000, MqttSessionEvent::PingTimeout, false), // ping_timeout
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+// ── Actions trait ──
+pub trait MqttSessionActions {
+    fn drop_socket(&mut self);
+    fn report_refused(&mut self);
+    fn send_connect(&mut self);
+    fn send_disconnect(&mut self);
+    fn send_pingreq(&mut self);
+    fn accepted(&self) -> bool;
+}
@@ -0,0 +1,333 @@
+//! Auto-generated FSM: OrderWorkflow
+//! Generated by Oxidate
+//!
+//! Snapshot of every option
+//!
+//! OrderWorkflowActions methods:
+//! - `fn cart_not_empty(&self) -> bool`: Cart --> PaymentPending : checkout [cart_not_empty]
+//! - `fn notify_customer(&mut self)`: entry of Shipped
+//! - `fn on_transition(&mut self, from, event, to)`: every transition
+//! - `fn refund_if_paid(&mut self)`: entry of Cancelled
+//! - `fn request_payment(&mut self)`: entry of PaymentPending
+//! - `fn send_receipt(&mut self)`: entry of Paid
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum OrderWorkflowState {
+    Cart,
+    PaymentPending,
+    Paid,
+    Shipped,
+    Delivered,
+    Cancelled,
+}
+
+impl OrderWorkflowState {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            OrderWorkflowState::Cart => "Cart",
+            OrderWorkflowState::PaymentPending => "PaymentPending",
+            OrderWorkflowState::Paid => "Paid",
+            OrderWorkflowState::Shipped => "Shipped",
+            OrderWorkflowState::Delivered => "Delivered",
+            OrderWorkflowState::Cancelled => "Cancelled",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum OrderWorkflowEvent {
+    Cancel = 0,
+    Checkout = 1,
+    Delivered = 2,
+    Dispatched = 3,
+    PaymentFailed = 4,
+    PaymentOk = 5,
+}
+
+impl OrderWorkflowEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub(crate) const EVENT_NAMES: [&'static str; 6] = ["cancel", "checkout", "delivered", "dispatched", "payment_failed", "payment_ok"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub(crate) const fn id(&self) -> u8 {
+        match self {
+            OrderWorkflowEvent::Cancel => 0,
+            OrderWorkflowEvent::Checkout => 1,
+            OrderWorkflowEvent::Delivered => 2,
+            OrderWorkflowEvent::Dispatched => 3,
+            OrderWorkflowEvent::PaymentFailed => 4,
+            OrderWorkflowEvent::PaymentOk => 5,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            OrderWorkflowEvent::Cancel => "cancel",
+            OrderWorkflowEvent::Checkout => "checkout",
+            OrderWorkflowEvent::Delivered => "delivered",
+            OrderWorkflowEvent::Dispatched => "dispatched",
+            OrderWorkflowEvent::PaymentFailed => "payment_failed",
+            OrderWorkflowEvent::PaymentOk => "payment_ok",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `OrderWorkflow::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum OrderWorkflowProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: OrderWorkflowState, to: OrderWorkflowState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl OrderWorkflowProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub(crate) const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+/// Dispatch counters of `OrderWorkflow`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct OrderWorkflowMetrics {
+    state_entered: [u16; 6],
+    event_received: [u32; 6],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl OrderWorkflowMetrics {
+    fn started(initial: OrderWorkflowState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 6],
+            event_received: [0; 6],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: OrderWorkflowState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub(crate) fn entered(&self, state: OrderWorkflowState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: OrderWorkflowEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub(crate) fn received(&self, event: OrderWorkflowEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub(crate) fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub(crate) struct OrderWorkflow<T: OrderWorkflowActions> {
+    state: OrderWorkflowState,
+    context: T,
+    started: bool,
+    queue: [Option<OrderWorkflowEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: OrderWorkflowMetrics,
+}
+
+impl<T: OrderWorkflowActions> OrderWorkflow<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub(crate) fn new(context: T) -> Self {
+        Self {
+            state: OrderWorkflowState::Cart,
+            context,
+            started: false,
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: OrderWorkflowMetrics::started(OrderWorkflowState::Cart),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub(crate) fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub(crate) fn state(&self) -> OrderWorkflowState {
+        self.state
+    }
+
+    pub(crate) fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub(crate) fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub(crate) fn metrics(&self) -> &OrderWorkflowMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued
+    /// event is dropped to make room and returned
+    pub(crate) fn post(&mut self, event: OrderWorkflowEvent) -> Option<OrderWorkflowEvent> {
+        let mut dropped = None;
+        if self.queue_len == 4 {
+            dropped = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        dropped
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub(crate) fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event).is_handled() {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub(crate) fn process(&mut self, event: OrderWorkflowEvent) -> OrderWorkflowProcessResult {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (OrderWorkflowState::Cart, OrderWorkflowEvent::Checkout) if self.context.cart_not_empty() => {
+                self.state = OrderWorkflowState::PaymentPending;
+                self.context.request_payment();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(OrderWorkflowState::PaymentPending);
+                self.context.on_transition(OrderWorkflowState::Cart, OrderWorkflowEvent::Checkout, OrderWorkflowState::PaymentPending);
+                OrderWorkflowProcessResult::Transitioned { from: OrderWorkflowState::Cart, to: OrderWorkflowState::PaymentPending }
+            }
+            (OrderWorkflowState::PaymentPending, OrderWorkflowEvent::PaymentOk) => {
+                self.state = OrderWorkflowState::Paid;
+                self.context.send_receipt();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(OrderWorkflowState::Paid);
+                self.context.on_transition(OrderWorkflowState::PaymentPending, OrderWorkflowEvent::PaymentOk, OrderWorkflowState::Paid);
+                OrderWorkflowProcessResult::Transitioned { from: OrderWorkflowState::PaymentPending, to: OrderWorkflowState::Paid }
+            }
+            (OrderWorkflowState::PaymentPending, OrderWorkflowEvent::PaymentFailed) => {
+                self.state = OrderWorkflowState::Cart;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(OrderWorkflowState::Cart);
+                self.context.on_transition(OrderWorkflowState::PaymentPending, OrderWorkflowEvent::PaymentFailed, OrderWorkflowState::Cart);
+                OrderWorkflowProcessResult::Transitioned { from: OrderWorkflowState::PaymentPending, to: OrderWorkflowState::Cart }
+            }
+            (OrderWorkflowState::Paid, OrderWorkflowEvent::Dispatched) => {
+                self.state = OrderWorkflowState::Shipped;
+                self.context.notify_customer();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(OrderWorkflowState::Shipped);
+                self.context.on_transition(OrderWorkflowState::Paid, OrderWorkflowEvent::Dispatched, OrderWorkflowState::Shipped);
+                OrderWorkflowProcessResult::Transitioned { from: OrderWorkflowState::Paid, to: OrderWorkflowState::Shipped }
+            }
+            (OrderWorkflowState::Shipped, OrderWorkflowEvent::Delivered) => {
+                self.state = OrderWorkflowState::Delivered;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(OrderWorkflowState::Delivered);
+                self.context.on_transition(OrderWorkflowState::Shipped, OrderWorkflowEvent::Delivered, OrderWorkflowState::Delivered);
+                OrderWorkflowProcessResult::Transitioned { from: OrderWorkflowState::Shipped, to: OrderWorkflowState::Delivered }
+            }
+            (OrderWorkflowState::PaymentPending, OrderWorkflowEvent::Cancel) => {
+                self.state = OrderWorkflowState::Cancelled;
+                self.context.refund_if_paid();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(OrderWorkflowState::Cancelled);
+                self.context.on_transition(OrderWorkflowState::PaymentPending, OrderWorkflowEvent::Cancel, OrderWorkflowState::Cancelled);
+                OrderWorkflowProcessResult::Transitioned { from: OrderWorkflowState::PaymentPending, to: OrderWorkflowState::Cancelled }
+            }
+            (OrderWorkflowState::Paid, OrderWorkflowEvent::Cancel) => {
+                self.state = OrderWorkflowState::Cancelled;
+                self.context.refund_if_paid();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(OrderWorkflowState::Cancelled);
+                self.context.on_transition(OrderWorkflowState::Paid, OrderWorkflowEvent::Cancel, OrderWorkflowState::Cancelled);
+                OrderWorkflowProcessResult::Transitioned { from: OrderWorkflowState::Paid, to: OrderWorkflowState::Cancelled }
+            }
+            (OrderWorkflowState::Cart, OrderWorkflowEvent::Checkout) => OrderWorkflowProcessResult::GuardRejected,
+            _ => OrderWorkflowProcessResult::Ignored,
+        }
+    }
+}
+
+#[cfg(feature = "shared-events")]
+impl<T: OrderWorkflowActions> OrderWorkflow<T> {
+    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it
+    pub(crate) fn process_shared(&mut self, event: super::events::SharedEvent) -> OrderWorkflowProcessResult {
+        match OrderWorkflowEvent::try_from(event) {
+            Ok(event) => self.process(event),
+            Err(_) => OrderWorkflowProcessResult::Ignored,
+        }
+    }
+
+    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room
+    pub(crate) fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {
+        OrderWorkflowEvent::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)
+    }
+}
+
+// ── Actions trait ──
+pub(crate) trait OrderWorkflowActions {
+    fn notify_customer(&mut self);
+    fn refund_if_paid(&mut self);
+    fn request_payment(&mut self);
+    fn send_receipt(&mut self);
+    fn cart_not_empty(&self) -> bool;
+
+    /// Called after every successful transition (e.g. for defmt/RTT logging)
+    fn on_transition(&mut self, _from: OrderWorkflowState, _event: OrderWorkflowEvent, _to: OrderWorkflowState) {}
+}
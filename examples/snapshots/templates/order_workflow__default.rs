@@ -0,0 +1,150 @@
+//! Auto-generated FSM: OrderWorkflow
+//! Generated by Oxidate
+//!
+//! OrderWorkflowActions methods:
+//! - `fn cart_not_empty(&self) -> bool`: Cart --> PaymentPending : checkout [cart_not_empty]
+//! - `fn notify_customer(&mut self)`: entry of Shipped
+//! - `fn refund_if_paid(&mut self)`: entry of Cancelled
+//! - `fn request_payment(&mut self)`: entry of PaymentPending
+//! - `fn send_receipt(&mut self)`: entry of Paid
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrderWorkflowState {
+    Cart,
+    PaymentPending,
+    Paid,
+    Shipped,
+    Delivered,
+    Cancelled,
+}
+
+impl OrderWorkflowState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            OrderWorkflowState::Cart => "Cart",
+            OrderWorkflowState::PaymentPending => "PaymentPending",
+            OrderWorkflowState::Paid => "Paid",
+            OrderWorkflowState::Shipped => "Shipped",
+            OrderWorkflowState::Delivered => "Delivered",
+            OrderWorkflowState::Cancelled => "Cancelled",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrderWorkflowEvent {
+    Cancel,
+    Checkout,
+    Delivered,
+    Dispatched,
+    PaymentFailed,
+    PaymentOk,
+}
+
+impl OrderWorkflowEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            OrderWorkflowEvent::Cancel => "cancel",
+            OrderWorkflowEvent::Checkout => "checkout",
+            OrderWorkflowEvent::Delivered => "delivered",
+            OrderWorkflowEvent::Dispatched => "dispatched",
+            OrderWorkflowEvent::PaymentFailed => "payment_failed",
+            OrderWorkflowEvent::PaymentOk => "payment_ok",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct OrderWorkflow<T: OrderWorkflowActions> {
+    state: OrderWorkflowState,
+    context: T,
+    started: bool,
+}
+
+impl<T: OrderWorkflowActions> OrderWorkflow<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub fn new(context: T) -> Self {
+        Self {
+            state: OrderWorkflowState::Cart,
+            context,
+            started: false,
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub fn state(&self) -> OrderWorkflowState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: OrderWorkflowEvent) -> bool {
+        match (self.state, event) {
+            (OrderWorkflowState::Cart, OrderWorkflowEvent::Checkout) if self.context.cart_not_empty() => {
+                self.state = OrderWorkflowState::PaymentPending;
+                self.context.request_payment();
+                true
+            }
+            (OrderWorkflowState::PaymentPending, OrderWorkflowEvent::PaymentOk) => {
+                self.state = OrderWorkflowState::Paid;
+                self.context.send_receipt();
+                true
+            }
+            (OrderWorkflowState::PaymentPending, OrderWorkflowEvent::PaymentFailed) => {
+                self.state = OrderWorkflowState::Cart;
+                true
+            }
+            (OrderWorkflowState::Paid, OrderWorkflowEvent::Dispatched) => {
+                self.state = OrderWorkflowState::Shipped;
+                self.context.notify_customer();
+                true
+            }
+            (OrderWorkflowState::Shipped, OrderWorkflowEvent::Delivered) => {
+                self.state = OrderWorkflowState::Delivered;
+                true
+            }
+            (OrderWorkflowState::PaymentPending, OrderWorkflowEvent::Cancel) => {
+                self.state = OrderWorkflowState::Cancelled;
+                self.context.refund_if_paid();
+                true
+            }
+            (OrderWorkflowState::Paid, OrderWorkflowEvent::Cancel) => {
+                self.state = OrderWorkflowState::Cancelled;
+                self.context.refund_if_paid();
+                true
+            }
+            _ => false // No transition
+        }
+    }
+}
+
+// ── Actions trait ──
+pub trait OrderWorkflowActions {
+    fn notify_customer(&mut self);
+    fn refund_if_paid(&mut self);
+    fn request_payment(&mut self);
+    fn send_receipt(&mut self);
+    fn cart_not_empty(&self) -> bool;
+}
@@ -0,0 +1,419 @@
+//! Auto-generated FSM: PumpControl
+//! Generated by Oxidate
+//!
+//! Snapshot of every option
+//!
+//! PumpControlActions methods:
+//! - `fn clear_alarm(&mut self)`: exit of Tripped
+//! - `fn interlocks_ok(&self) -> bool`: Stopped --> Priming : start [interlocks_ok], Tripped --> Stopped : reset [interlocks_ok]
+//! - `fn motor_off(&mut self)`: entry of Stopped, exit of Running
+//! - `fn motor_on(&mut self)`: entry of Running
+//! - `fn on_transition(&mut self, from, event, to)`: every transition
+//! - `fn open_inlet_valve(&mut self)`: entry of Priming
+//! - `fn pressure_ok(&self) -> bool`: Priming --> Running : Primed [pressure_ok]
+//! - `fn raise_alarm(&mut self)`: entry of Tripped
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum PumpControlState {
+    /// Motor off
+    Stopped,
+    /// Filling the suction line
+    Priming,
+    /// Pumping
+    Running,
+    /// Interlock tripped, needs a manual reset
+    Tripped,
+}
+
+impl PumpControlState {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            PumpControlState::Stopped => "Stopped",
+            PumpControlState::Priming => "Priming",
+            PumpControlState::Running => "Running",
+            PumpControlState::Tripped => "Tripped",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum PumpControlEvent {
+    Primed = 0,
+    EmergencyStop = 1,
+    LowPressure = 2,
+    OverTemperature = 3,
+    Reset = 4,
+    Start = 5,
+    Stop = 6,
+}
+
+impl PumpControlEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub(crate) const EVENT_NAMES: [&'static str; 7] = ["Primed", "emergency_stop", "low_pressure", "over_temperature", "reset", "start", "stop"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub(crate) const fn id(&self) -> u8 {
+        match self {
+            PumpControlEvent::Primed => 0,
+            PumpControlEvent::EmergencyStop => 1,
+            PumpControlEvent::LowPressure => 2,
+            PumpControlEvent::OverTemperature => 3,
+            PumpControlEvent::Reset => 4,
+            PumpControlEvent::Start => 5,
+            PumpControlEvent::Stop => 6,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            PumpControlEvent::Primed => "Primed",
+            PumpControlEvent::EmergencyStop => "emergency_stop",
+            PumpControlEvent::LowPressure => "low_pressure",
+            PumpControlEvent::OverTemperature => "over_temperature",
+            PumpControlEvent::Reset => "reset",
+            PumpControlEvent::Start => "start",
+            PumpControlEvent::Stop => "stop",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `PumpControl::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum PumpControlProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: PumpControlState, to: PumpControlState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl PumpControlProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub(crate) const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+/// Dispatch counters of `PumpControl`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PumpControlMetrics {
+    state_entered: [u16; 4],
+    event_received: [u32; 7],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl PumpControlMetrics {
+    fn started(initial: PumpControlState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 4],
+            event_received: [0; 7],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: PumpControlState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub(crate) fn entered(&self, state: PumpControlState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: PumpControlEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub(crate) fn received(&self, event: PumpControlEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub(crate) fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub(crate) struct PumpControl<T: PumpControlActions> {
+    state: PumpControlState,
+    context: T,
+    started: bool,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 1],
+    queue: [Option<PumpControlEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: PumpControlMetrics,
+}
+
+impl<T: PumpControlActions> PumpControl<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub(crate) fn new(context: T) -> Self {
+        Self {
+            state: PumpControlState::Stopped,
+            context,
+            started: false,
+            timers: [None; 1],
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: PumpControlMetrics::started(PumpControlState::Stopped),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub(crate) fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        self.context.motor_off();
+        true
+    }
+
+    pub(crate) fn state(&self) -> PumpControlState {
+        self.state
+    }
+
+    pub(crate) fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub(crate) fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub(crate) fn metrics(&self) -> &PumpControlMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued
+    /// event is dropped to make room and returned
+    pub(crate) fn post(&mut self, event: PumpControlEvent) -> Option<PumpControlEvent> {
+        let mut dropped = None;
+        if self.queue_len == 4 {
+            dropped = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        dropped
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub(crate) fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event).is_handled() {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub(crate) fn process(&mut self, event: PumpControlEvent) -> PumpControlProcessResult {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (PumpControlState::Stopped, PumpControlEvent::Start) if self.context.interlocks_ok() => {
+                self.timers = [None; 1];
+                self.state = PumpControlState::Priming;
+                self.context.open_inlet_valve();
+                self.timers[Self::TIMER_PRIME_TIME] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(PumpControlState::Priming);
+                self.context.on_transition(PumpControlState::Stopped, PumpControlEvent::Start, PumpControlState::Priming);
+                PumpControlProcessResult::Transitioned { from: PumpControlState::Stopped, to: PumpControlState::Priming }
+            }
+            (PumpControlState::Priming, PumpControlEvent::Primed) if self.context.pressure_ok() => {
+                self.timers = [None; 1];
+                self.state = PumpControlState::Running;
+                self.context.motor_on();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(PumpControlState::Running);
+                self.context.on_transition(PumpControlState::Priming, PumpControlEvent::Primed, PumpControlState::Running);
+                PumpControlProcessResult::Transitioned { from: PumpControlState::Priming, to: PumpControlState::Running }
+            }
+            (PumpControlState::Priming, PumpControlEvent::Primed) => {
+                self.timers = [None; 1];
+                self.state = PumpControlState::Tripped;
+                self.context.raise_alarm();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(PumpControlState::Tripped);
+                self.context.on_transition(PumpControlState::Priming, PumpControlEvent::Primed, PumpControlState::Tripped);
+                PumpControlProcessResult::Transitioned { from: PumpControlState::Priming, to: PumpControlState::Tripped }
+            }
+            (PumpControlState::Running, PumpControlEvent::Stop) => {
+                self.timers = [None; 1];
+                self.context.motor_off();
+                self.state = PumpControlState::Stopped;
+                self.context.motor_off();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(PumpControlState::Stopped);
+                self.context.on_transition(PumpControlState::Running, PumpControlEvent::Stop, PumpControlState::Stopped);
+                PumpControlProcessResult::Transitioned { from: PumpControlState::Running, to: PumpControlState::Stopped }
+            }
+            (PumpControlState::Running, PumpControlEvent::LowPressure) => {
+                self.timers = [None; 1];
+                self.context.motor_off();
+                self.state = PumpControlState::Tripped;
+                self.context.raise_alarm();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(PumpControlState::Tripped);
+                self.context.on_transition(PumpControlState::Running, PumpControlEvent::LowPressure, PumpControlState::Tripped);
+                PumpControlProcessResult::Transitioned { from: PumpControlState::Running, to: PumpControlState::Tripped }
+            }
+            (PumpControlState::Running, PumpControlEvent::OverTemperature) => {
+                self.timers = [None; 1];
+                self.context.motor_off();
+                self.state = PumpControlState::Tripped;
+                self.context.raise_alarm();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(PumpControlState::Tripped);
+                self.context.on_transition(PumpControlState::Running, PumpControlEvent::OverTemperature, PumpControlState::Tripped);
+                PumpControlProcessResult::Transitioned { from: PumpControlState::Running, to: PumpControlState::Tripped }
+            }
+            (PumpControlState::Tripped, PumpControlEvent::Reset) if self.context.interlocks_ok() => {
+                self.timers = [None; 1];
+                self.context.clear_alarm();
+                self.state = PumpControlState::Stopped;
+                self.context.motor_off();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(PumpControlState::Stopped);
+                self.context.on_transition(PumpControlState::Tripped, PumpControlEvent::Reset, PumpControlState::Stopped);
+                PumpControlProcessResult::Transitioned { from: PumpControlState::Tripped, to: PumpControlState::Stopped }
+            }
+            (_, PumpControlEvent::EmergencyStop) => {
+                let from = self.state;
+                self.timers = [None; 1];
+                match self.state {
+                    PumpControlState::Running => {
+                        self.context.motor_off();
+                    }
+                    PumpControlState::Tripped => {
+                        self.context.clear_alarm();
+                    }
+                    _ => {}
+                }
+                self.state = PumpControlState::Tripped;
+                self.context.raise_alarm();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(PumpControlState::Tripped);
+                self.context.on_transition(from, PumpControlEvent::EmergencyStop, PumpControlState::Tripped);
+                PumpControlProcessResult::Transitioned { from, to: PumpControlState::Tripped }
+            }
+            (PumpControlState::Tripped, PumpControlEvent::Reset) => PumpControlProcessResult::GuardRejected,
+            (PumpControlState::Stopped, PumpControlEvent::Start) => PumpControlProcessResult::GuardRejected,
+            _ => PumpControlProcessResult::Ignored,
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_PRIME_TIME: usize = 0;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, PumpControlEvent, bool); 1] = [
+        (5000, PumpControlEvent::Primed, false), // prime_time
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub(crate) fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+#[cfg(feature = "shared-events")]
+impl<T: PumpControlActions> PumpControl<T> {
+    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it
+    pub(crate) fn process_shared(&mut self, event: super::events::SharedEvent) -> PumpControlProcessResult {
+        match PumpControlEvent::try_from(event) {
+            Ok(event) => self.process(event),
+            Err(_) => PumpControlProcessResult::Ignored,
+        }
+    }
+
+    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room
+    pub(crate) fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {
+        PumpControlEvent::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)
+    }
+}
+
+// ── Actions trait ──
+pub(crate) trait PumpControlActions {
+    fn clear_alarm(&mut self);
+    fn motor_off(&mut self);
+    fn motor_on(&mut self);
+    fn open_inlet_valve(&mut self);
+    fn raise_alarm(&mut self);
+    fn interlocks_ok(&self) -> bool;
+    fn pressure_ok(&self) -> bool;
+
+    /// Called after every successful transition (e.g. for defmt/RTT logging)
+    fn on_transition(&mut self, _from: PumpControlState, _event: PumpControlEvent, _to: PumpControlState) {}
+}
@@ -0,0 +1,230 @@
+//! Auto-generated FSM: PumpControl
+//! Generated by Oxidate
+//!
+//! PumpControlActions methods:
+//! - `fn clear_alarm(&mut self)`: exit of Tripped
+//! - `fn interlocks_ok(&self) -> bool`: Stopped --> Priming : start [interlocks_ok], Tripped --> Stopped : reset [interlocks_ok]
+//! - `fn motor_off(&mut self)`: entry of Stopped, exit of Running
+//! - `fn motor_on(&mut self)`: entry of Running
+//! - `fn open_inlet_valve(&mut self)`: entry of Priming
+//! - `fn pressure_ok(&self) -> bool`: Priming --> Running : Primed [pressure_ok]
+//! - `fn raise_alarm(&mut self)`: entry of Tripped
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PumpControlState {
+    /// Motor off
+    Stopped,
+    /// Filling the suction line
+    Priming,
+    /// Pumping
+    Running,
+    /// Interlock tripped, needs a manual reset
+    Tripped,
+}
+
+impl PumpControlState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            PumpControlState::Stopped => "Stopped",
+            PumpControlState::Priming => "Priming",
+            PumpControlState::Running => "Running",
+            PumpControlState::Tripped => "Tripped",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PumpControlEvent {
+    Primed,
+    EmergencyStop,
+    LowPressure,
+    OverTemperature,
+    Reset,
+    Start,
+    Stop,
+}
+
+impl PumpControlEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            PumpControlEvent::Primed => "Primed",
+            PumpControlEvent::EmergencyStop => "emergency_stop",
+            PumpControlEvent::LowPressure => "low_pressure",
+            PumpControlEvent::OverTemperature => "over_temperature",
+            PumpControlEvent::Reset => "reset",
+            PumpControlEvent::Start => "start",
+            PumpControlEvent::Stop => "stop",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct PumpControl<T: PumpControlActions> {
+    state: PumpControlState,
+    context: T,
+    started: bool,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 1],
+}
+
+impl<T: PumpControlActions> PumpControl<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub fn new(context: T) -> Self {
+        Self {
+            state: PumpControlState::Stopped,
+            context,
+            started: false,
+            timers: [None; 1],
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        self.context.motor_off();
+        true
+    }
+
+    pub fn state(&self) -> PumpControlState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: PumpControlEvent) -> bool {
+        match (self.state, event) {
+            (PumpControlState::Stopped, PumpControlEvent::Start) if self.context.interlocks_ok() => {
+                self.timers = [None; 1];
+                self.state = PumpControlState::Priming;
+                self.context.open_inlet_valve();
+                self.timers[Self::TIMER_PRIME_TIME] = Some(0);
+                true
+            }
+            (PumpControlState::Priming, PumpControlEvent::Primed) if self.context.pressure_ok() => {
+                self.timers = [None; 1];
+                self.state = PumpControlState::Running;
+                self.context.motor_on();
+                true
+            }
+            (PumpControlState::Priming, PumpControlEvent::Primed) => {
+                self.timers = [None; 1];
+                self.state = PumpControlState::Tripped;
+                self.context.raise_alarm();
+                true
+            }
+            (PumpControlState::Running, PumpControlEvent::Stop) => {
+                self.timers = [None; 1];
+                self.context.motor_off();
+                self.state = PumpControlState::Stopped;
+                self.context.motor_off();
+                true
+            }
+            (PumpControlState::Running, PumpControlEvent::LowPressure) => {
+                self.timers = [None; 1];
+                self.context.motor_off();
+                self.state = PumpControlState::Tripped;
+                self.context.raise_alarm();
+                true
+            }
+            (PumpControlState::Running, PumpControlEvent::OverTemperature) => {
+                self.timers = [None; 1];
+                self.context.motor_off();
+                self.state = PumpControlState::Tripped;
+                self.context.raise_alarm();
+                true
+            }
+            (PumpControlState::Tripped, PumpControlEvent::Reset) if self.context.interlocks_ok() => {
+                self.timers = [None; 1];
+                self.context.clear_alarm();
+                self.state = PumpControlState::Stopped;
+                self.context.motor_off();
+                true
+            }
+            (_, PumpControlEvent::EmergencyStop) => {
+                self.timers = [None; 1];
+                match self.state {
+                    PumpControlState::Running => {
+                        self.context.motor_off();
+                    }
+                    PumpControlState::Tripped => {
+                        self.context.clear_alarm();
+                    }
+                    _ => {}
+                }
+                self.state = PumpControlState::Tripped;
+                self.context.raise_alarm();
+                true
+            }
+            _ => false // No transition
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_PRIME_TIME: usize = 0;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, PumpControlEvent, bool); 1] = [
+        (5000, PumpControlEvent::Primed, false), // prime_time
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+// ── Actions trait ──
+pub trait PumpControlActions {
+    fn clear_alarm(&mut self);
+    fn motor_off(&mut self);
+    fn motor_on(&mut self);
+    fn open_inlet_valve(&mut self);
+    fn raise_alarm(&mut self);
+    fn interlocks_ok(&self) -> bool;
+    fn pressure_ok(&self) -> bool;
+}
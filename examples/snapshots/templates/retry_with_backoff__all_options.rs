@@ -0,0 +1,381 @@
+//! Auto-generated FSM: RetryWithBackoff
+//! Generated by Oxidate
+//!
+//! Snapshot of every option
+//!
+//! RetryWithBackoffActions methods:
+//! - `fn count_attempt(&mut self)`: Requesting --> Waiting : response_error [retries_left] / count_attempt
+//! - `fn double_delay(&mut self)`: entry of Waiting
+//! - `fn on_transition(&mut self, from, event, to)`: every transition
+//! - `fn reset_attempts(&mut self)`: Idle --> Requesting : start / reset_attempts
+//! - `fn retries_left(&self) -> bool`: Requesting --> Waiting : response_error [retries_left] / count_attempt
+//! - `fn send_request(&mut self)`: entry of Requesting
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum RetryWithBackoffState {
+    Idle,
+    Requesting,
+    /// Backing off before the next attempt
+    Waiting,
+    Succeeded,
+    /// Gave up
+    Failed,
+}
+
+impl RetryWithBackoffState {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            RetryWithBackoffState::Idle => "Idle",
+            RetryWithBackoffState::Requesting => "Requesting",
+            RetryWithBackoffState::Waiting => "Waiting",
+            RetryWithBackoffState::Succeeded => "Succeeded",
+            RetryWithBackoffState::Failed => "Failed",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum RetryWithBackoffEvent {
+    BackoffElapsed = 0,
+    Done = 1,
+    ResponseError = 2,
+    ResponseOk = 3,
+    Start = 4,
+}
+
+impl RetryWithBackoffEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub(crate) const EVENT_NAMES: [&'static str; 5] = ["BackoffElapsed", "done", "response_error", "response_ok", "start"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub(crate) const fn id(&self) -> u8 {
+        match self {
+            RetryWithBackoffEvent::BackoffElapsed => 0,
+            RetryWithBackoffEvent::Done => 1,
+            RetryWithBackoffEvent::ResponseError => 2,
+            RetryWithBackoffEvent::ResponseOk => 3,
+            RetryWithBackoffEvent::Start => 4,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            RetryWithBackoffEvent::BackoffElapsed => "BackoffElapsed",
+            RetryWithBackoffEvent::Done => "done",
+            RetryWithBackoffEvent::ResponseError => "response_error",
+            RetryWithBackoffEvent::ResponseOk => "response_ok",
+            RetryWithBackoffEvent::Start => "start",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `RetryWithBackoff::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum RetryWithBackoffProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: RetryWithBackoffState, to: RetryWithBackoffState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl RetryWithBackoffProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub(crate) const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+/// Dispatch counters of `RetryWithBackoff`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RetryWithBackoffMetrics {
+    state_entered: [u16; 5],
+    event_received: [u32; 5],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl RetryWithBackoffMetrics {
+    fn started(initial: RetryWithBackoffState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 5],
+            event_received: [0; 5],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: RetryWithBackoffState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub(crate) fn entered(&self, state: RetryWithBackoffState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: RetryWithBackoffEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub(crate) fn received(&self, event: RetryWithBackoffEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub(crate) fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub(crate) struct RetryWithBackoff<T: RetryWithBackoffActions> {
+    state: RetryWithBackoffState,
+    context: T,
+    started: bool,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 1],
+    queue: [Option<RetryWithBackoffEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: RetryWithBackoffMetrics,
+}
+
+impl<T: RetryWithBackoffActions> RetryWithBackoff<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub(crate) fn new(context: T) -> Self {
+        Self {
+            state: RetryWithBackoffState::Idle,
+            context,
+            started: false,
+            timers: [None; 1],
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: RetryWithBackoffMetrics::started(RetryWithBackoffState::Idle),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub(crate) fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub(crate) fn state(&self) -> RetryWithBackoffState {
+        self.state
+    }
+
+    pub(crate) fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub(crate) fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub(crate) fn metrics(&self) -> &RetryWithBackoffMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued
+    /// event is dropped to make room and returned
+    pub(crate) fn post(&mut self, event: RetryWithBackoffEvent) -> Option<RetryWithBackoffEvent> {
+        let mut dropped = None;
+        if self.queue_len == 4 {
+            dropped = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        dropped
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub(crate) fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event).is_handled() {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub(crate) fn process(&mut self, event: RetryWithBackoffEvent) -> RetryWithBackoffProcessResult {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (RetryWithBackoffState::Idle, RetryWithBackoffEvent::Start) => {
+                self.timers = [None; 1];
+                self.context.reset_attempts();
+                self.state = RetryWithBackoffState::Requesting;
+                self.context.send_request();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(RetryWithBackoffState::Requesting);
+                self.context.on_transition(RetryWithBackoffState::Idle, RetryWithBackoffEvent::Start, RetryWithBackoffState::Requesting);
+                RetryWithBackoffProcessResult::Transitioned { from: RetryWithBackoffState::Idle, to: RetryWithBackoffState::Requesting }
+            }
+            (RetryWithBackoffState::Requesting, RetryWithBackoffEvent::ResponseOk) => {
+                self.timers = [None; 1];
+                self.state = RetryWithBackoffState::Succeeded;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(RetryWithBackoffState::Succeeded);
+                self.context.on_transition(RetryWithBackoffState::Requesting, RetryWithBackoffEvent::ResponseOk, RetryWithBackoffState::Succeeded);
+                RetryWithBackoffProcessResult::Transitioned { from: RetryWithBackoffState::Requesting, to: RetryWithBackoffState::Succeeded }
+            }
+            (RetryWithBackoffState::Requesting, RetryWithBackoffEvent::ResponseError) if self.context.retries_left() => {
+                self.timers = [None; 1];
+                self.context.count_attempt();
+                self.state = RetryWithBackoffState::Waiting;
+                self.context.double_delay();
+                self.timers[Self::TIMER_BACKOFF] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(RetryWithBackoffState::Waiting);
+                self.context.on_transition(RetryWithBackoffState::Requesting, RetryWithBackoffEvent::ResponseError, RetryWithBackoffState::Waiting);
+                RetryWithBackoffProcessResult::Transitioned { from: RetryWithBackoffState::Requesting, to: RetryWithBackoffState::Waiting }
+            }
+            (RetryWithBackoffState::Requesting, RetryWithBackoffEvent::ResponseError) => {
+                self.timers = [None; 1];
+                self.state = RetryWithBackoffState::Failed;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(RetryWithBackoffState::Failed);
+                self.context.on_transition(RetryWithBackoffState::Requesting, RetryWithBackoffEvent::ResponseError, RetryWithBackoffState::Failed);
+                RetryWithBackoffProcessResult::Transitioned { from: RetryWithBackoffState::Requesting, to: RetryWithBackoffState::Failed }
+            }
+            (RetryWithBackoffState::Waiting, RetryWithBackoffEvent::BackoffElapsed) => {
+                self.timers = [None; 1];
+                self.state = RetryWithBackoffState::Requesting;
+                self.context.send_request();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(RetryWithBackoffState::Requesting);
+                self.context.on_transition(RetryWithBackoffState::Waiting, RetryWithBackoffEvent::BackoffElapsed, RetryWithBackoffState::Requesting);
+                RetryWithBackoffProcessResult::Transitioned { from: RetryWithBackoffState::Waiting, to: RetryWithBackoffState::Requesting }
+            }
+            (RetryWithBackoffState::Succeeded, RetryWithBackoffEvent::Done) => {
+                self.timers = [None; 1];
+                self.state = RetryWithBackoffState::Idle;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(RetryWithBackoffState::Idle);
+                self.context.on_transition(RetryWithBackoffState::Succeeded, RetryWithBackoffEvent::Done, RetryWithBackoffState::Idle);
+                RetryWithBackoffProcessResult::Transitioned { from: RetryWithBackoffState::Succeeded, to: RetryWithBackoffState::Idle }
+            }
+            (RetryWithBackoffState::Failed, RetryWithBackoffEvent::Done) => {
+                self.timers = [None; 1];
+                self.state = RetryWithBackoffState::Idle;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(RetryWithBackoffState::Idle);
+                self.context.on_transition(RetryWithBackoffState::Failed, RetryWithBackoffEvent::Done, RetryWithBackoffState::Idle);
+                RetryWithBackoffProcessResult::Transitioned { from: RetryWithBackoffState::Failed, to: RetryWithBackoffState::Idle }
+            }
+            _ => RetryWithBackoffProcessResult::Ignored,
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_BACKOFF: usize = 0;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, RetryWithBackoffEvent, bool); 1] = [
+        (1000, RetryWithBackoffEvent::BackoffElapsed, false), // backoff
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub(crate) fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+#[cfg(feature = "shared-events")]
+impl<T: RetryWithBackoffActions> RetryWithBackoff<T> {
+    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it
+    pub(crate) fn process_shared(&mut self, event: super::events::SharedEvent) -> RetryWithBackoffProcessResult {
+        match RetryWithBackoffEvent::try_from(event) {
+            Ok(event) => self.process(event),
+            Err(_) => RetryWithBackoffProcessResult::Ignored,
+        }
+    }
+
+    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room
+    pub(crate) fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {
+        RetryWithBackoffEvent::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)
+    }
+}
+
+// ── Actions trait ──
+pub(crate) trait RetryWithBackoffActions {
+    fn count_attempt(&mut self);
+    fn double_delay(&mut self);
+    fn reset_attempts(&mut self);
+    fn send_request(&mut self);
+    fn retries_left(&self) -> bool;
+
+    /// Called after every successful transition (e.g. for defmt/RTT logging)
+    fn on_transition(&mut self, _from: RetryWithBackoffState, _event: RetryWithBackoffEvent, _to: RetryWithBackoffState) {}
+}
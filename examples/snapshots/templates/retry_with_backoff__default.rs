@@ -0,0 +1,200 @@
+//! Auto-generated FSM: RetryWithBackoff
+//! Generated by Oxidate
+//!
+//! RetryWithBackoffActions methods:
+//! - `fn count_attempt(&mut self)`: Requesting --> Waiting : response_error [retries_left] / count_attempt
+//! - `fn double_delay(&mut self)`: entry of Waiting
+//! - `fn reset_attempts(&mut self)`: Idle --> Requesting : start / reset_attempts
+//! - `fn retries_left(&self) -> bool`: Requesting --> Waiting : response_error [retries_left] / count_attempt
+//! - `fn send_request(&mut self)`: entry of Requesting
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RetryWithBackoffState {
+    Idle,
+    Requesting,
+    /// Backing off before the next attempt
+    Waiting,
+    Succeeded,
+    /// Gave up
+    Failed,
+}
+
+impl RetryWithBackoffState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            RetryWithBackoffState::Idle => "Idle",
+            RetryWithBackoffState::Requesting => "Requesting",
+            RetryWithBackoffState::Waiting => "Waiting",
+            RetryWithBackoffState::Succeeded => "Succeeded",
+            RetryWithBackoffState::Failed => "Failed",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RetryWithBackoffEvent {
+    BackoffElapsed,
+    Done,
+    ResponseError,
+    ResponseOk,
+    Start,
+}
+
+impl RetryWithBackoffEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            RetryWithBackoffEvent::BackoffElapsed => "BackoffElapsed",
+            RetryWithBackoffEvent::Done => "done",
+            RetryWithBackoffEvent::ResponseError => "response_error",
+            RetryWithBackoffEvent::ResponseOk => "response_ok",
+            RetryWithBackoffEvent::Start => "start",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct RetryWithBackoff<T: RetryWithBackoffActions> {
+    state: RetryWithBackoffState,
+    context: T,
+    started: bool,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 1],
+}
+
+impl<T: RetryWithBackoffActions> RetryWithBackoff<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub fn new(context: T) -> Self {
+        Self {
+            state: RetryWithBackoffState::Idle,
+            context,
+            started: false,
+            timers: [None; 1],
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub fn state(&self) -> RetryWithBackoffState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: RetryWithBackoffEvent) -> bool {
+        match (self.state, event) {
+            (RetryWithBackoffState::Idle, RetryWithBackoffEvent::Start) => {
+                self.timers = [None; 1];
+                self.context.reset_attempts();
+                self.state = RetryWithBackoffState::Requesting;
+                self.context.send_request();
+                true
+            }
+            (RetryWithBackoffState::Requesting, RetryWithBackoffEvent::ResponseOk) => {
+                self.timers = [None; 1];
+                self.state = RetryWithBackoffState::Succeeded;
+                true
+            }
+            (RetryWithBackoffState::Requesting, RetryWithBackoffEvent::ResponseError) if self.context.retries_left() => {
+                self.timers = [None; 1];
+                self.context.count_attempt();
+                self.state = RetryWithBackoffState::Waiting;
+                self.context.double_delay();
+                self.timers[Self::TIMER_BACKOFF] = Some(0);
+                true
+            }
+            (RetryWithBackoffState::Requesting, RetryWithBackoffEvent::ResponseError) => {
+                self.timers = [None; 1];
+                self.state = RetryWithBackoffState::Failed;
+                true
+            }
+            (RetryWithBackoffState::Waiting, RetryWithBackoffEvent::BackoffElapsed) => {
+                self.timers = [None; 1];
+                self.state = RetryWithBackoffState::Requesting;
+                self.context.send_request();
+                true
+            }
+            (RetryWithBackoffState::Succeeded, RetryWithBackoffEvent::Done) => {
+                self.timers = [None; 1];
+                self.state = RetryWithBackoffState::Idle;
+                true
+            }
+            (RetryWithBackoffState::Failed, RetryWithBackoffEvent::Done) => {
+                self.timers = [None; 1];
+                self.state = RetryWithBackoffState::Idle;
+                true
+            }
+            _ => false // No transition
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_BACKOFF: usize = 0;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, RetryWithBackoffEvent, bool); 1] = [
+        (1000, RetryWithBackoffEvent::BackoffElapsed, false), // backoff
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+// ── Actions trait ──
+pub trait RetryWithBackoffActions {
+    fn count_attempt(&mut self);
+    fn double_delay(&mut self);
+    fn reset_attempts(&mut self);
+    fn send_request(&mut self);
+    fn retries_left(&self) -> bool;
+}
@@ -0,0 +1,361 @@
+//! Auto-generated FSM: SetupWizard
+//! Generated by Oxidate
+//!
+//! Snapshot of every option
+//!
+//! SetupWizardActions methods:
+//! - `fn account_valid(&self) -> bool`: Account --> Preferences : next [account_valid]
+//! - `fn discard_changes(&mut self)`: * --> Welcome : cancel / discard_changes
+//! - `fn on_transition(&mut self, from, event, to)`: every transition
+//! - `fn save_settings(&mut self)`: entry of Saving
+//! - `fn show_errors(&mut self)`: Account --> Account : next [else] / show_errors, Saving --> Review : save_failed / show_errors
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum SetupWizardState {
+    Welcome,
+    /// Step 1 - name and email
+    Account,
+    /// Step 2 - options
+    Preferences,
+    /// Step 3 - confirm
+    Review,
+    Saving,
+    Finished,
+}
+
+impl SetupWizardState {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            SetupWizardState::Welcome => "Welcome",
+            SetupWizardState::Account => "Account",
+            SetupWizardState::Preferences => "Preferences",
+            SetupWizardState::Review => "Review",
+            SetupWizardState::Saving => "Saving",
+            SetupWizardState::Finished => "Finished",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum SetupWizardEvent {
+    Back = 0,
+    Cancel = 1,
+    Finish = 2,
+    Next = 3,
+    SaveFailed = 4,
+    Saved = 5,
+}
+
+impl SetupWizardEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub(crate) const EVENT_NAMES: [&'static str; 6] = ["back", "cancel", "finish", "next", "save_failed", "saved"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub(crate) const fn id(&self) -> u8 {
+        match self {
+            SetupWizardEvent::Back => 0,
+            SetupWizardEvent::Cancel => 1,
+            SetupWizardEvent::Finish => 2,
+            SetupWizardEvent::Next => 3,
+            SetupWizardEvent::SaveFailed => 4,
+            SetupWizardEvent::Saved => 5,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            SetupWizardEvent::Back => "back",
+            SetupWizardEvent::Cancel => "cancel",
+            SetupWizardEvent::Finish => "finish",
+            SetupWizardEvent::Next => "next",
+            SetupWizardEvent::SaveFailed => "save_failed",
+            SetupWizardEvent::Saved => "saved",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `SetupWizard::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum SetupWizardProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: SetupWizardState, to: SetupWizardState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl SetupWizardProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub(crate) const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+/// Dispatch counters of `SetupWizard`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SetupWizardMetrics {
+    state_entered: [u16; 6],
+    event_received: [u32; 6],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl SetupWizardMetrics {
+    fn started(initial: SetupWizardState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 6],
+            event_received: [0; 6],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: SetupWizardState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub(crate) fn entered(&self, state: SetupWizardState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: SetupWizardEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub(crate) fn received(&self, event: SetupWizardEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub(crate) fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub(crate) struct SetupWizard<T: SetupWizardActions> {
+    state: SetupWizardState,
+    context: T,
+    started: bool,
+    queue: [Option<SetupWizardEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: SetupWizardMetrics,
+}
+
+impl<T: SetupWizardActions> SetupWizard<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub(crate) fn new(context: T) -> Self {
+        Self {
+            state: SetupWizardState::Welcome,
+            context,
+            started: false,
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: SetupWizardMetrics::started(SetupWizardState::Welcome),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub(crate) fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub(crate) fn state(&self) -> SetupWizardState {
+        self.state
+    }
+
+    pub(crate) fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub(crate) fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub(crate) fn metrics(&self) -> &SetupWizardMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued
+    /// event is dropped to make room and returned
+    pub(crate) fn post(&mut self, event: SetupWizardEvent) -> Option<SetupWizardEvent> {
+        let mut dropped = None;
+        if self.queue_len == 4 {
+            dropped = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        dropped
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub(crate) fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event).is_handled() {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub(crate) fn process(&mut self, event: SetupWizardEvent) -> SetupWizardProcessResult {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (SetupWizardState::Welcome, SetupWizardEvent::Next) => {
+                self.state = SetupWizardState::Account;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(SetupWizardState::Account);
+                self.context.on_transition(SetupWizardState::Welcome, SetupWizardEvent::Next, SetupWizardState::Account);
+                SetupWizardProcessResult::Transitioned { from: SetupWizardState::Welcome, to: SetupWizardState::Account }
+            }
+            (SetupWizardState::Account, SetupWizardEvent::Next) if self.context.account_valid() => {
+                self.state = SetupWizardState::Preferences;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(SetupWizardState::Preferences);
+                self.context.on_transition(SetupWizardState::Account, SetupWizardEvent::Next, SetupWizardState::Preferences);
+                SetupWizardProcessResult::Transitioned { from: SetupWizardState::Account, to: SetupWizardState::Preferences }
+            }
+            (SetupWizardState::Account, SetupWizardEvent::Next) => {
+                self.context.show_errors();
+                self.state = SetupWizardState::Account;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(SetupWizardState::Account);
+                self.context.on_transition(SetupWizardState::Account, SetupWizardEvent::Next, SetupWizardState::Account);
+                SetupWizardProcessResult::Transitioned { from: SetupWizardState::Account, to: SetupWizardState::Account }
+            }
+            (SetupWizardState::Account, SetupWizardEvent::Back) => {
+                self.state = SetupWizardState::Welcome;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(SetupWizardState::Welcome);
+                self.context.on_transition(SetupWizardState::Account, SetupWizardEvent::Back, SetupWizardState::Welcome);
+                SetupWizardProcessResult::Transitioned { from: SetupWizardState::Account, to: SetupWizardState::Welcome }
+            }
+            (SetupWizardState::Preferences, SetupWizardEvent::Next) => {
+                self.state = SetupWizardState::Review;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(SetupWizardState::Review);
+                self.context.on_transition(SetupWizardState::Preferences, SetupWizardEvent::Next, SetupWizardState::Review);
+                SetupWizardProcessResult::Transitioned { from: SetupWizardState::Preferences, to: SetupWizardState::Review }
+            }
+            (SetupWizardState::Preferences, SetupWizardEvent::Back) => {
+                self.state = SetupWizardState::Account;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(SetupWizardState::Account);
+                self.context.on_transition(SetupWizardState::Preferences, SetupWizardEvent::Back, SetupWizardState::Account);
+                SetupWizardProcessResult::Transitioned { from: SetupWizardState::Preferences, to: SetupWizardState::Account }
+            }
+            (SetupWizardState::Review, SetupWizardEvent::Finish) => {
+                self.state = SetupWizardState::Saving;
+                self.context.save_settings();
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(SetupWizardState::Saving);
+                self.context.on_transition(SetupWizardState::Review, SetupWizardEvent::Finish, SetupWizardState::Saving);
+                SetupWizardProcessResult::Transitioned { from: SetupWizardState::Review, to: SetupWizardState::Saving }
+            }
+            (SetupWizardState::Review, SetupWizardEvent::Back) => {
+                self.state = SetupWizardState::Preferences;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(SetupWizardState::Preferences);
+                self.context.on_transition(SetupWizardState::Review, SetupWizardEvent::Back, SetupWizardState::Preferences);
+                SetupWizardProcessResult::Transitioned { from: SetupWizardState::Review, to: SetupWizardState::Preferences }
+            }
+            (SetupWizardState::Saving, SetupWizardEvent::Saved) => {
+                self.state = SetupWizardState::Finished;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(SetupWizardState::Finished);
+                self.context.on_transition(SetupWizardState::Saving, SetupWizardEvent::Saved, SetupWizardState::Finished);
+                SetupWizardProcessResult::Transitioned { from: SetupWizardState::Saving, to: SetupWizardState::Finished }
+            }
+            (SetupWizardState::Saving, SetupWizardEvent::SaveFailed) => {
+                self.context.show_errors();
+                self.state = SetupWizardState::Review;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(SetupWizardState::Review);
+                self.context.on_transition(SetupWizardState::Saving, SetupWizardEvent::SaveFailed, SetupWizardState::Review);
+                SetupWizardProcessResult::Transitioned { from: SetupWizardState::Saving, to: SetupWizardState::Review }
+            }
+            (_, SetupWizardEvent::Cancel) => {
+                let from = self.state;
+                self.context.discard_changes();
+                self.state = SetupWizardState::Welcome;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(SetupWizardState::Welcome);
+                self.context.on_transition(from, SetupWizardEvent::Cancel, SetupWizardState::Welcome);
+                SetupWizardProcessResult::Transitioned { from, to: SetupWizardState::Welcome }
+            }
+            _ => SetupWizardProcessResult::Ignored,
+        }
+    }
+}
+
+#[cfg(feature = "shared-events")]
+impl<T: SetupWizardActions> SetupWizard<T> {
+    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it
+    pub(crate) fn process_shared(&mut self, event: super::events::SharedEvent) -> SetupWizardProcessResult {
+        match SetupWizardEvent::try_from(event) {
+            Ok(event) => self.process(event),
+            Err(_) => SetupWizardProcessResult::Ignored,
+        }
+    }
+
+    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room
+    pub(crate) fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {
+        SetupWizardEvent::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)
+    }
+}
+
+// ── Actions trait ──
+pub(crate) trait SetupWizardActions {
+    fn discard_changes(&mut self);
+    fn save_settings(&mut self);
+    fn show_errors(&mut self);
+    fn account_valid(&self) -> bool;
+
+    /// Called after every successful transition (e.g. for defmt/RTT logging)
+    fn on_transition(&mut self, _from: SetupWizardState, _event: SetupWizardEvent, _to: SetupWizardState) {}
+}
@@ -0,0 +1,155 @@
+//! Auto-generated FSM: SetupWizard
+//! Generated by Oxidate
+//!
+//! SetupWizardActions methods:
+//! - `fn account_valid(&self) -> bool`: Account --> Preferences : next [account_valid]
+//! - `fn discard_changes(&mut self)`: * --> Welcome : cancel / discard_changes
+//! - `fn save_settings(&mut self)`: entry of Saving
+//! - `fn show_errors(&mut self)`: Account --> Account : next [else] / show_errors, Saving --> Review : save_failed / show_errors
+
+#![no_std]
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SetupWizardState {
+    Welcome,
+    /// Step 1 - name and email
+    Account,
+    /// Step 2 - options
+    Preferences,
+    /// Step 3 - confirm
+    Review,
+    Saving,
+    Finished,
+}
+
+impl SetupWizardState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            SetupWizardState::Welcome => "Welcome",
+            SetupWizardState::Account => "Account",
+            SetupWizardState::Preferences => "Preferences",
+            SetupWizardState::Review => "Review",
+            SetupWizardState::Saving => "Saving",
+            SetupWizardState::Finished => "Finished",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SetupWizardEvent {
+    Back,
+    Cancel,
+    Finish,
+    Next,
+    SaveFailed,
+    Saved,
+}
+
+impl SetupWizardEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            SetupWizardEvent::Back => "back",
+            SetupWizardEvent::Cancel => "cancel",
+            SetupWizardEvent::Finish => "finish",
+            SetupWizardEvent::Next => "next",
+            SetupWizardEvent::SaveFailed => "save_failed",
+            SetupWizardEvent::Saved => "saved",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct SetupWizard<T: SetupWizardActions> {
+    state: SetupWizardState,
+    context: T,
+}
+
+impl<T: SetupWizardActions> SetupWizard<T> {
+    /// Create the machine and run its start actions, then the initial state's entry actions
+    pub fn new(mut context: T) -> Self {
+        Self {
+            state: SetupWizardState::Welcome,
+            context,
+        }
+    }
+
+    pub fn state(&self) -> SetupWizardState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: SetupWizardEvent) -> bool {
+        match (self.state, event) {
+            (SetupWizardState::Welcome, SetupWizardEvent::Next) => {
+                self.state = SetupWizardState::Account;
+                true
+            }
+            (SetupWizardState::Account, SetupWizardEvent::Next) if self.context.account_valid() => {
+                self.state = SetupWizardState::Preferences;
+                true
+            }
+            (SetupWizardState::Account, SetupWizardEvent::Next) => {
+                self.context.show_errors();
+                self.state = SetupWizardState::Account;
+                true
+            }
+            (SetupWizardState::Account, SetupWizardEvent::Back) => {
+                self.state = SetupWizardState::Welcome;
+                true
+            }
+            (SetupWizardState::Preferences, SetupWizardEvent::Next) => {
+                self.state = SetupWizardState::Review;
+                true
+            }
+            (SetupWizardState::Preferences, SetupWizardEvent::Back) => {
+                self.state = SetupWizardState::Account;
+                true
+            }
+            (SetupWizardState::Review, SetupWizardEvent::Finish) => {
+                self.state = SetupWizardState::Saving;
+                self.context.save_settings();
+                true
+            }
+            (SetupWizardState::Review, SetupWizardEvent::Back) => {
+                self.state = SetupWizardState::Preferences;
+                true
+            }
+            (SetupWizardState::Saving, SetupWizardEvent::Saved) => {
+                self.state = SetupWizardState::Finished;
+                true
+            }
+            (SetupWizardState::Saving, SetupWizardEvent::SaveFailed) => {
+                self.context.show_errors();
+                self.state = SetupWizardState::Review;
+                true
+            }
+            (_, SetupWizardEvent::Cancel) => {
+                self.context.discard_changes();
+                self.state = SetupWizardState::Welcome;
+                true
+            }
+            _ => false // No transition
+        }
+    }
+}
+
+// ── Actions trait ──
+pub trait SetupWizardActions {
+    fn discard_changes(&mut self);
+    fn save_settings(&mut self);
+    fn show_errors(&mut self);
+    fn account_valid(&self) -> bool;
+}
@@ -0,0 +1,381 @@
+//! Auto-generated FSM: TcpHandshake
+//! Generated by Oxidate
+//!
+//! Snapshot of every option
+//!
+//! TcpHandshakeActions methods:
+//! - `fn on_transition(&mut self, from, event, to)`: every transition
+//! - `fn report_timeout(&mut self)`: SynSent --> Closed : SynTimeout / report_timeout
+//! - `fn send_ack(&mut self)`: SynSent --> Established : syn_ack / send_ack
+//! - `fn send_fin(&mut self)`: Established --> FinWait : close / send_fin
+//! - `fn send_syn(&mut self)`: Closed --> SynSent : connect / send_syn
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum TcpHandshakeState {
+    /// No connection
+    Closed,
+    /// Waiting for SYN-ACK
+    SynSent,
+    /// Data may flow
+    Established,
+    /// Waiting for the peer to close
+    FinWait,
+}
+
+impl TcpHandshakeState {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            TcpHandshakeState::Closed => "Closed",
+            TcpHandshakeState::SynSent => "SynSent",
+            TcpHandshakeState::Established => "Established",
+            TcpHandshakeState::FinWait => "FinWait",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum TcpHandshakeEvent {
+    SynTimeout = 0,
+    Close = 1,
+    Connect = 2,
+    FinAck = 3,
+    Rst = 4,
+    SynAck = 5,
+}
+
+impl TcpHandshakeEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub(crate) const EVENT_NAMES: [&'static str; 6] = ["SynTimeout", "close", "connect", "fin_ack", "rst", "syn_ack"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub(crate) const fn id(&self) -> u8 {
+        match self {
+            TcpHandshakeEvent::SynTimeout => 0,
+            TcpHandshakeEvent::Close => 1,
+            TcpHandshakeEvent::Connect => 2,
+            TcpHandshakeEvent::FinAck => 3,
+            TcpHandshakeEvent::Rst => 4,
+            TcpHandshakeEvent::SynAck => 5,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            TcpHandshakeEvent::SynTimeout => "SynTimeout",
+            TcpHandshakeEvent::Close => "close",
+            TcpHandshakeEvent::Connect => "connect",
+            TcpHandshakeEvent::FinAck => "fin_ack",
+            TcpHandshakeEvent::Rst => "rst",
+            TcpHandshakeEvent::SynAck => "syn_ack",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `TcpHandshake::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum TcpHandshakeProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: TcpHandshakeState, to: TcpHandshakeState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl TcpHandshakeProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub(crate) const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+/// Dispatch counters of `TcpHandshake`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TcpHandshakeMetrics {
+    state_entered: [u16; 4],
+    event_received: [u32; 6],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl TcpHandshakeMetrics {
+    fn started(initial: TcpHandshakeState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 4],
+            event_received: [0; 6],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: TcpHandshakeState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub(crate) fn entered(&self, state: TcpHandshakeState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: TcpHandshakeEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub(crate) fn received(&self, event: TcpHandshakeEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub(crate) fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub(crate) struct TcpHandshake<T: TcpHandshakeActions> {
+    state: TcpHandshakeState,
+    context: T,
+    started: bool,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 1],
+    queue: [Option<TcpHandshakeEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: TcpHandshakeMetrics,
+}
+
+impl<T: TcpHandshakeActions> TcpHandshake<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub(crate) fn new(context: T) -> Self {
+        Self {
+            state: TcpHandshakeState::Closed,
+            context,
+            started: false,
+            timers: [None; 1],
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: TcpHandshakeMetrics::started(TcpHandshakeState::Closed),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub(crate) fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        true
+    }
+
+    pub(crate) fn state(&self) -> TcpHandshakeState {
+        self.state
+    }
+
+    pub(crate) fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub(crate) fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub(crate) fn metrics(&self) -> &TcpHandshakeMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued
+    /// event is dropped to make room and returned
+    pub(crate) fn post(&mut self, event: TcpHandshakeEvent) -> Option<TcpHandshakeEvent> {
+        let mut dropped = None;
+        if self.queue_len == 4 {
+            dropped = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        dropped
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub(crate) fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event).is_handled() {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub(crate) fn process(&mut self, event: TcpHandshakeEvent) -> TcpHandshakeProcessResult {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (TcpHandshakeState::Closed, TcpHandshakeEvent::Connect) => {
+                self.timers = [None; 1];
+                self.context.send_syn();
+                self.state = TcpHandshakeState::SynSent;
+                self.timers[Self::TIMER_SYN_TIMEOUT] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(TcpHandshakeState::SynSent);
+                self.context.on_transition(TcpHandshakeState::Closed, TcpHandshakeEvent::Connect, TcpHandshakeState::SynSent);
+                TcpHandshakeProcessResult::Transitioned { from: TcpHandshakeState::Closed, to: TcpHandshakeState::SynSent }
+            }
+            (TcpHandshakeState::SynSent, TcpHandshakeEvent::SynAck) => {
+                self.timers = [None; 1];
+                self.context.send_ack();
+                self.state = TcpHandshakeState::Established;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(TcpHandshakeState::Established);
+                self.context.on_transition(TcpHandshakeState::SynSent, TcpHandshakeEvent::SynAck, TcpHandshakeState::Established);
+                TcpHandshakeProcessResult::Transitioned { from: TcpHandshakeState::SynSent, to: TcpHandshakeState::Established }
+            }
+            (TcpHandshakeState::SynSent, TcpHandshakeEvent::SynTimeout) => {
+                self.timers = [None; 1];
+                self.context.report_timeout();
+                self.state = TcpHandshakeState::Closed;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(TcpHandshakeState::Closed);
+                self.context.on_transition(TcpHandshakeState::SynSent, TcpHandshakeEvent::SynTimeout, TcpHandshakeState::Closed);
+                TcpHandshakeProcessResult::Transitioned { from: TcpHandshakeState::SynSent, to: TcpHandshakeState::Closed }
+            }
+            (TcpHandshakeState::SynSent, TcpHandshakeEvent::Rst) => {
+                self.timers = [None; 1];
+                self.state = TcpHandshakeState::Closed;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(TcpHandshakeState::Closed);
+                self.context.on_transition(TcpHandshakeState::SynSent, TcpHandshakeEvent::Rst, TcpHandshakeState::Closed);
+                TcpHandshakeProcessResult::Transitioned { from: TcpHandshakeState::SynSent, to: TcpHandshakeState::Closed }
+            }
+            (TcpHandshakeState::Established, TcpHandshakeEvent::Close) => {
+                self.timers = [None; 1];
+                self.context.send_fin();
+                self.state = TcpHandshakeState::FinWait;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(TcpHandshakeState::FinWait);
+                self.context.on_transition(TcpHandshakeState::Established, TcpHandshakeEvent::Close, TcpHandshakeState::FinWait);
+                TcpHandshakeProcessResult::Transitioned { from: TcpHandshakeState::Established, to: TcpHandshakeState::FinWait }
+            }
+            (TcpHandshakeState::Established, TcpHandshakeEvent::Rst) => {
+                self.timers = [None; 1];
+                self.state = TcpHandshakeState::Closed;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(TcpHandshakeState::Closed);
+                self.context.on_transition(TcpHandshakeState::Established, TcpHandshakeEvent::Rst, TcpHandshakeState::Closed);
+                TcpHandshakeProcessResult::Transitioned { from: TcpHandshakeState::Established, to: TcpHandshakeState::Closed }
+            }
+            (TcpHandshakeState::FinWait, TcpHandshakeEvent::FinAck) => {
+                self.timers = [None; 1];
+                self.state = TcpHandshakeState::Closed;
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(TcpHandshakeState::Closed);
+                self.context.on_transition(TcpHandshakeState::FinWait, TcpHandshakeEvent::FinAck, TcpHandshakeState::Closed);
+                TcpHandshakeProcessResult::Transitioned { from: TcpHandshakeState::FinWait, to: TcpHandshakeState::Closed }
+            }
+            _ => TcpHandshakeProcessResult::Ignored,
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_SYN_TIMEOUT: usize = 0;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, TcpHandshakeEvent, bool); 1] = [
+        (3000, TcpHandshakeEvent::SynTimeout, false), // syn_timeout
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub(crate) fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+#[cfg(feature = "shared-events")]
+impl<T: TcpHandshakeActions> TcpHandshake<T> {
+    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it
+    pub(crate) fn process_shared(&mut self, event: super::events::SharedEvent) -> TcpHandshakeProcessResult {
+        match TcpHandshakeEvent::try_from(event) {
+            Ok(event) => self.process(event),
+            Err(_) => TcpHandshakeProcessResult::Ignored,
+        }
+    }
+
+    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room
+    pub(crate) fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {
+        TcpHandshakeEvent::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)
+    }
+}
+
+// ── Actions trait ──
+pub(crate) trait TcpHandshakeActions {
+    fn report_timeout(&mut self);
+    fn send_ack(&mut self);
+    fn send_fin(&mut self);
+    fn send_syn(&mut self);
+
+    /// Called after every successful transition (e.g. for defmt/RTT logging)
+    fn on_transition(&mut self, _from: TcpHandshakeState, _event: TcpHandshakeEvent, _to: TcpHandshakeState) {}
+}
@@ -0,0 +1,188 @@
+//! Auto-generated FSM: TcpHandshake
+//! Generated by Oxidate
+//!
+//! TcpHandshakeActions methods:
+//! - `fn report_timeout(&mut self)`: SynSent --> Closed : SynTimeout / report_timeout
+//! - `fn send_ack(&mut self)`: SynSent --> Established : syn_ack / send_ack
+//! - `fn send_fin(&mut self)`: Established --> FinWait : close / send_fin
+//! - `fn send_syn(&mut self)`: Closed --> SynSent : connect / send_syn
+
+#![no_std]
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TcpHandshakeState {
+    /// No connection
+    Closed,
+    /// Waiting for SYN-ACK
+    SynSent,
+    /// Data may flow
+    Established,
+    /// Waiting for the peer to close
+    FinWait,
+}
+
+impl TcpHandshakeState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            TcpHandshakeState::Closed => "Closed",
+            TcpHandshakeState::SynSent => "SynSent",
+            TcpHandshakeState::Established => "Established",
+            TcpHandshakeState::FinWait => "FinWait",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TcpHandshakeEvent {
+    SynTimeout,
+    Close,
+    Connect,
+    FinAck,
+    Rst,
+    SynAck,
+}
+
+impl TcpHandshakeEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            TcpHandshakeEvent::SynTimeout => "SynTimeout",
+            TcpHandshakeEvent::Close => "close",
+            TcpHandshakeEvent::Connect => "connect",
+            TcpHandshakeEvent::FinAck => "fin_ack",
+            TcpHandshakeEvent::Rst => "rst",
+            TcpHandshakeEvent::SynAck => "syn_ack",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct TcpHandshake<T: TcpHandshakeActions> {
+    state: TcpHandshakeState,
+    context: T,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 1],
+}
+
+impl<T: TcpHandshakeActions> TcpHandshake<T> {
+    /// Create the machine and run its start actions, then the initial state's entry actions
+    pub fn new(mut context: T) -> Self {
+        Self {
+            state: TcpHandshakeState::Closed,
+            context,
+            timers: [None; 1],
+        }
+    }
+
+    pub fn state(&self) -> TcpHandshakeState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: TcpHandshakeEvent) -> bool {
+        match (self.state, event) {
+            (TcpHandshakeState::Closed, TcpHandshakeEvent::Connect) => {
+                self.timers = [None; 1];
+                self.context.send_syn();
+                self.state = TcpHandshakeState::SynSent;
+                self.timers[Self::TIMER_SYN_TIMEOUT] = Some(0);
+                true
+            }
+            (TcpHandshakeState::SynSent, TcpHandshakeEvent::SynAck) => {
+                self.timers = [None; 1];
+                self.context.send_ack();
+                self.state = TcpHandshakeState::Established;
+                true
+            }
+            (TcpHandshakeState::SynSent, TcpHandshakeEvent::SynTimeout) => {
+                self.timers = [None; 1];
+                self.context.report_timeout();
+                self.state = TcpHandshakeState::Closed;
+                true
+            }
+            (TcpHandshakeState::SynSent, TcpHandshakeEvent::Rst) => {
+                self.timers = [None; 1];
+                self.state = TcpHandshakeState::Closed;
+                true
+            }
+            (TcpHandshakeState::Established, TcpHandshakeEvent::Close) => {
+                self.timers = [None; 1];
+                self.context.send_fin();
+                self.state = TcpHandshakeState::FinWait;
+                true
+            }
+            (TcpHandshakeState::Established, TcpHandshakeEvent::Rst) => {
+                self.timers = [None; 1];
+                self.state = TcpHandshakeState::Closed;
+                true
+            }
+            (TcpHandshakeState::FinWait, TcpHandshakeEvent::FinAck) => {
+                self.timers = [None; 1];
+                self.state = TcpHandshakeState::Closed;
+                true
+            }
+            _ => false // No transition
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_SYN_TIMEOUT: usize = 0;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, TcpHandshakeEvent, bool); 1] = [
+        (3000, TcpHandshakeEvent::SynTimeout, false), // syn_timeout
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+// ── Actions trait ──
+pub trait TcpHandshakeActions {
+    fn report_timeout(&mut self);
+    fn send_ack(&mut self);
+    fn send_fin(&mut self);
+    fn send_syn(&mut self);
+}
@@ -0,0 +1,342 @@
+//! Auto-generated FSM: TrafficLight
+//! Generated by Oxidate
+//!
+//! Snapshot of every option
+//!
+//! TrafficLightActions methods:
+//! - `fn display_green(&mut self)`: entry of Green
+//! - `fn display_red(&mut self)`: entry of Red
+//! - `fn display_yellow(&mut self)`: entry of Yellow
+//! - `fn on_transition(&mut self, from, event, to)`: every transition
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum TrafficLightState {
+    /// "Stop - vehicles must wait"
+    Red,
+    /// "Caution - prepare to stop"
+    Yellow,
+    /// "Go - vehicles may proceed"
+    Green,
+}
+
+impl TrafficLightState {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            TrafficLightState::Red => "Red",
+            TrafficLightState::Yellow => "Yellow",
+            TrafficLightState::Green => "Green",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[repr(u8)]
+pub(crate) enum TrafficLightEvent {
+    GreenExpired = 0,
+    RedExpired = 1,
+    YellowExpired = 2,
+}
+
+impl TrafficLightEvent {
+    /// Name of each event by `id()`, `""` where no event has that id
+    pub(crate) const EVENT_NAMES: [&'static str; 3] = ["GreenExpired", "RedExpired", "YellowExpired"];
+
+    /// The discriminant of the variant, e.g. to log the event as one byte
+    pub(crate) const fn id(&self) -> u8 {
+        match self {
+            TrafficLightEvent::GreenExpired => 0,
+            TrafficLightEvent::RedExpired => 1,
+            TrafficLightEvent::YellowExpired => 2,
+        }
+    }
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            TrafficLightEvent::GreenExpired => "GreenExpired",
+            TrafficLightEvent::RedExpired => "RedExpired",
+            TrafficLightEvent::YellowExpired => "YellowExpired",
+        }
+    }
+}
+
+// ── Dispatch ──
+/// What `TrafficLight::process()` did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum TrafficLightProcessResult {
+    /// Left `from` and settled in `to` (after any completion transitions)
+    Transitioned { from: TrafficLightState, to: TrafficLightState },
+    /// Handled without leaving the state (internal or local self-transition)
+    InternalHandled,
+    /// The state has transitions for the event, but none of their guards passed
+    GuardRejected,
+    /// The state has no transition for the event
+    Ignored,
+}
+
+impl TrafficLightProcessResult {
+    /// Whether a transition took the event (what `process()` returns without this option)
+    pub(crate) const fn is_handled(&self) -> bool {
+        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)
+    }
+}
+
+/// Dispatch counters of `TrafficLight`, compiled in with the `fsm-metrics` feature
+#[cfg(feature = "fsm-metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TrafficLightMetrics {
+    state_entered: [u16; 3],
+    event_received: [u32; 3],
+    queue_high_watermark: usize,
+}
+
+#[cfg(feature = "fsm-metrics")]
+impl TrafficLightMetrics {
+    fn started(initial: TrafficLightState) -> Self {
+        let mut metrics = Self {
+            state_entered: [0; 3],
+            event_received: [0; 3],
+            queue_high_watermark: 0,
+        };
+        metrics.count_entry(initial);
+        metrics
+    }
+
+    fn count_entry(&mut self, state: TrafficLightState) {
+        let count = &mut self.state_entered[state as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)
+    pub(crate) fn entered(&self, state: TrafficLightState) -> u16 {
+        self.state_entered[state as usize]
+    }
+
+    fn count_event(&mut self, event: TrafficLightEvent) {
+        let count = &mut self.event_received[event as usize];
+        *count = count.saturating_add(1);
+    }
+
+    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)
+    pub(crate) fn received(&self, event: TrafficLightEvent) -> u32 {
+        self.event_received[event as usize]
+    }
+
+    fn record_queue_len(&mut self, len: usize) {
+        self.queue_high_watermark = self.queue_high_watermark.max(len);
+    }
+
+    /// Most events the queue has held at once
+    pub(crate) fn queue_high_watermark(&self) -> usize {
+        self.queue_high_watermark
+    }
+}
+
+pub(crate) struct TrafficLight<T: TrafficLightActions> {
+    state: TrafficLightState,
+    context: T,
+    started: bool,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 3],
+    queue: [Option<TrafficLightEvent>; 4],
+    queue_head: usize,
+    queue_len: usize,
+    #[cfg(feature = "fsm-metrics")]
+    metrics: TrafficLightMetrics,
+}
+
+impl<T: TrafficLightActions> TrafficLight<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub(crate) fn new(context: T) -> Self {
+        Self {
+            state: TrafficLightState::Red,
+            context,
+            started: false,
+            timers: [None; 3],
+            queue: [None; 4],
+            queue_head: 0,
+            queue_len: 0,
+            #[cfg(feature = "fsm-metrics")]
+            metrics: TrafficLightMetrics::started(TrafficLightState::Red),
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub(crate) fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        self.context.display_red();
+        self.timers[Self::TIMER_RED_TIMER] = Some(0);
+        true
+    }
+
+    pub(crate) fn state(&self) -> TrafficLightState {
+        self.state
+    }
+
+    pub(crate) fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub(crate) fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Dispatch counters since `new()`
+    #[cfg(feature = "fsm-metrics")]
+    pub(crate) fn metrics(&self) -> &TrafficLightMetrics {
+        &self.metrics
+    }
+
+    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued
+    /// event is dropped to make room and returned
+    pub(crate) fn post(&mut self, event: TrafficLightEvent) -> Option<TrafficLightEvent> {
+        let mut dropped = None;
+        if self.queue_len == 4 {
+            dropped = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+        }
+        let tail = (self.queue_head + self.queue_len) % 4;
+        self.queue[tail] = Some(event);
+        self.queue_len += 1;
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.record_queue_len(self.queue_len);
+        dropped
+    }
+
+    /// Process queued events in order; returns how many caused a transition
+    pub(crate) fn dispatch_pending(&mut self) -> usize {
+        let mut transitions = 0;
+        while self.queue_len > 0 {
+            let event = self.queue[self.queue_head].take();
+            self.queue_head = (self.queue_head + 1) % 4;
+            self.queue_len -= 1;
+            if let Some(event) = event {
+                if self.process(event).is_handled() {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub(crate) fn process(&mut self, event: TrafficLightEvent) -> TrafficLightProcessResult {
+        #[cfg(feature = "fsm-metrics")]
+        self.metrics.count_event(event);
+        match (self.state, event) {
+            (TrafficLightState::Red, TrafficLightEvent::RedExpired) => {
+                self.timers = [None; 3];
+                self.state = TrafficLightState::Green;
+                self.context.display_green();
+                self.timers[Self::TIMER_GREEN_TIMER] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(TrafficLightState::Green);
+                self.context.on_transition(TrafficLightState::Red, TrafficLightEvent::RedExpired, TrafficLightState::Green);
+                TrafficLightProcessResult::Transitioned { from: TrafficLightState::Red, to: TrafficLightState::Green }
+            }
+            (TrafficLightState::Green, TrafficLightEvent::GreenExpired) => {
+                self.timers = [None; 3];
+                self.state = TrafficLightState::Yellow;
+                self.context.display_yellow();
+                self.timers[Self::TIMER_YELLOW_TIMER] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(TrafficLightState::Yellow);
+                self.context.on_transition(TrafficLightState::Green, TrafficLightEvent::GreenExpired, TrafficLightState::Yellow);
+                TrafficLightProcessResult::Transitioned { from: TrafficLightState::Green, to: TrafficLightState::Yellow }
+            }
+            (TrafficLightState::Yellow, TrafficLightEvent::YellowExpired) => {
+                self.timers = [None; 3];
+                self.state = TrafficLightState::Red;
+                self.context.display_red();
+                self.timers[Self::TIMER_RED_TIMER] = Some(0);
+                #[cfg(feature = "fsm-metrics")]
+                self.metrics.count_entry(TrafficLightState::Red);
+                self.context.on_transition(TrafficLightState::Yellow, TrafficLightEvent::YellowExpired, TrafficLightState::Red);
+                TrafficLightProcessResult::Transitioned { from: TrafficLightState::Yellow, to: TrafficLightState::Red }
+            }
+            _ => TrafficLightProcessResult::Ignored,
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_RED_TIMER: usize = 0;
+    const TIMER_YELLOW_TIMER: usize = 1;
+    const TIMER_GREEN_TIMER: usize = 2;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, TrafficLightEvent, bool); 3] = [
+        (5000, TrafficLightEvent::RedExpired, false), // red_timer
+        (2000, TrafficLightEvent::YellowExpired, false), // yellow_timer
+        (4000, TrafficLightEvent::GreenExpired, false), // green_timer
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub(crate) fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+#[cfg(feature = "shared-events")]
+impl<T: TrafficLightActions> TrafficLight<T> {
+    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it
+    pub(crate) fn process_shared(&mut self, event: super::events::SharedEvent) -> TrafficLightProcessResult {
+        match TrafficLightEvent::try_from(event) {
+            Ok(event) => self.process(event),
+            Err(_) => TrafficLightProcessResult::Ignored,
+        }
+    }
+
+    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room
+    pub(crate) fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {
+        TrafficLightEvent::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)
+    }
+}
+
+// ── Actions trait ──
+pub(crate) trait TrafficLightActions {
+    fn display_green(&mut self);
+    fn display_red(&mut self);
+    fn display_yellow(&mut self);
+
+    /// Called after every successful transition (e.g. for defmt/RTT logging)
+    fn on_transition(&mut self, _from: TrafficLightState, _event: TrafficLightEvent, _to: TrafficLightState) {}
+}
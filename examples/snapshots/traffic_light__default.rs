@@ -0,0 +1,175 @@
+//! Auto-generated FSM: TrafficLight
+//! Generated by Oxidate
+//!
+//! TrafficLightActions methods:
+//! - `fn display_green(&mut self)`: entry of Green
+//! - `fn display_red(&mut self)`: entry of Red
+//! - `fn display_yellow(&mut self)`: entry of Yellow
+
+// ── States ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrafficLightState {
+    /// "Stop - vehicles must wait"
+    Red,
+    /// "Caution - prepare to stop"
+    Yellow,
+    /// "Go - vehicles may proceed"
+    Green,
+}
+
+impl TrafficLightState {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            TrafficLightState::Red => "Red",
+            TrafficLightState::Yellow => "Yellow",
+            TrafficLightState::Green => "Green",
+        }
+    }
+}
+
+// ── Events ──
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrafficLightEvent {
+    GreenExpired,
+    RedExpired,
+    YellowExpired,
+}
+
+impl TrafficLightEvent {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            TrafficLightEvent::GreenExpired => "GreenExpired",
+            TrafficLightEvent::RedExpired => "RedExpired",
+            TrafficLightEvent::YellowExpired => "YellowExpired",
+        }
+    }
+}
+
+// ── Dispatch ──
+pub struct TrafficLight<T: TrafficLightActions> {
+    state: TrafficLightState,
+    context: T,
+    started: bool,
+    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped
+    timers: [Option<u32>; 3],
+}
+
+impl<T: TrafficLightActions> TrafficLight<T> {
+    /// Create the machine in its initial state without running any action; call
+    /// `start()` before processing events
+    pub fn new(context: T) -> Self {
+        Self {
+            state: TrafficLightState::Red,
+            context,
+            started: false,
+            timers: [None; 3],
+        }
+    }
+
+    /// Run the start actions, then the initial state's entry actions; only the first
+    /// call does anything. Returns whether this call started the machine
+    pub fn start(&mut self) -> bool {
+        if self.started {
+            return false;
+        }
+        self.started = true;
+        self.context.display_red();
+        self.timers[Self::TIMER_RED_TIMER] = Some(0);
+        true
+    }
+
+    pub fn state(&self) -> TrafficLightState {
+        self.state
+    }
+
+    pub fn context(&self) -> &T {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut T {
+        &mut self.context
+    }
+
+    /// Handle `event` in the current state
+    ///
+    /// A transition runs the exit actions of the state it leaves, then its own
+    /// actions, then the entry actions of the state it enters (UML order)
+    pub fn process(&mut self, event: TrafficLightEvent) -> bool {
+        match (self.state, event) {
+            (TrafficLightState::Red, TrafficLightEvent::RedExpired) => {
+                self.timers = [None; 3];
+                self.state = TrafficLightState::Green;
+                self.context.display_green();
+                self.timers[Self::TIMER_GREEN_TIMER] = Some(0);
+                true
+            }
+            (TrafficLightState::Green, TrafficLightEvent::GreenExpired) => {
+                self.timers = [None; 3];
+                self.state = TrafficLightState::Yellow;
+                self.context.display_yellow();
+                self.timers[Self::TIMER_YELLOW_TIMER] = Some(0);
+                true
+            }
+            (TrafficLightState::Yellow, TrafficLightEvent::YellowExpired) => {
+                self.timers = [None; 3];
+                self.state = TrafficLightState::Red;
+                self.context.display_red();
+                self.timers[Self::TIMER_RED_TIMER] = Some(0);
+                true
+            }
+            _ => false // No transition
+        }
+    }
+
+    // Index of each timer in `TIMERS` and `timers`
+    const TIMER_RED_TIMER: usize = 0;
+    const TIMER_YELLOW_TIMER: usize = 1;
+    const TIMER_GREEN_TIMER: usize = 2;
+    /// Duration in ms, event and whether it restarts after firing, of each timer
+    const TIMERS: [(u32, TrafficLightEvent, bool); 3] = [
+        (5000, TrafficLightEvent::RedExpired, false), // red_timer
+        (2000, TrafficLightEvent::YellowExpired, false), // yellow_timer
+        (4000, TrafficLightEvent::GreenExpired, false), // green_timer
+    ];
+
+    /// Let `ms` milliseconds pass: each running timer that expires has its event
+    /// processed right away, in the order they expire (the first defined on a tie).
+    /// A periodic timer fires once per period until a state it runs in is left; a
+    /// one-shot timer stops. Returns how many timer events were processed
+    pub fn advance_time(&mut self, ms: u32) -> usize {
+        let mut left = ms;
+        let mut fired = 0;
+        loop {
+            // The running timer due soonest
+            let next = self
+                .timers
+                .iter()
+                .enumerate()
+                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))
+                .min_by_key(|&(_, due)| due);
+            let (index, due) = match next {
+                Some((index, due)) if due <= left => (index, due),
+                _ => break,
+            };
+            left -= due;
+            for elapsed in self.timers.iter_mut().flatten() {
+                *elapsed = elapsed.saturating_add(due);
+            }
+            let (_, event, periodic) = Self::TIMERS[index];
+            self.timers[index] = if periodic { Some(0) } else { None };
+            let _ = self.process(event);
+            fired += 1;
+        }
+        for elapsed in self.timers.iter_mut().flatten() {
+            *elapsed = elapsed.saturating_add(left);
+        }
+        fired
+    }
+}
+
+// ── Actions trait ──
+pub trait TrafficLightActions {
+    fn display_green(&mut self);
+    fn display_red(&mut self);
+    fn display_yellow(&mut self);
+}
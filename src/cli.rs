@@ -1,53 +1,508 @@
 //! Oxidate CLI - Command Line Interface for FSM parsing
 
-mod fsm;
-mod parser;
-
-use parser::parse_fsm;
+use oxidate_fsm::parser::loader::parse_fsm_file;
+use oxidate_fsm::{codegen, fsm, parser, project};
 use std::env;
-use std::fs;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let lenient_names = env::args().any(|a| a == "--lenient-names");
+    let shared_events = env::args().any(|a| a == "--shared-events");
+    let check_compile = env::args().any(|a| a == "--check-compile");
+    let metrics = env::args().any(|a| a == "--metrics");
+    let process_result = env::args().any(|a| a == "--process-result");
+    let remove_stale = env::args().any(|a| a == "--remove-stale");
+    let list_targets = env::args().any(|a| a == "--list-targets");
+    let flags = [
+        "--lenient-names", "--shared-events", "--check-compile", "--metrics", "--process-result", "--remove-stale",
+        "--list-targets",
+    ];
+    let mut args: Vec<String> = env::args().filter(|a| !flags.contains(&a.as_str())).collect();
+    let target = take_option(&mut args, "--target");
+    let header_file = take_option(&mut args, "--header-file");
+    let depth = take_option(&mut args, "--depth");
+    let event_order = take_option(&mut args, "--event-order");
+    let async_actions = take_option(&mut args, "--async-actions");
+    let bindings_file = take_option(&mut args, "--bindings");
+    let verify_manifest = take_option(&mut args, "--verify-manifest");
+    let import_events = take_option(&mut args, "--import-events");
+    let enum_name = take_option(&mut args, "--enum");
+
+    let backends = codegen::backend::BackendRegistry::builtin();
+    if list_targets {
+        for backend in backends.iter() {
+            println!("{:<10} {}", backend.name(), backend.description());
+        }
+        return;
+    }
+    if let Some(path) = verify_manifest {
+        if !verify_export_manifest(&path) {
+            std::process::exit(1);
+        }
+        return;
+    }
+    let backend = match target.as_deref().map(|name| (name, backends.get(name))) {
+        None => backends.get(codegen::CodegenTarget::Standard.backend_name()),
+        Some((_, Some(backend))) => Some(backend),
+        Some((name, None)) => {
+            eprintln!("❌ Unknown target '{}' (available: {})", name, backends.names().join(", "));
+            return;
+        }
+    };
+    let backend = backend.expect("the standard backend is built in");
 
     if args.len() < 2 {
         println!("Oxidate CLI - FSM Parser");
         println!("Usage: oxidate-cli <file.fsm>");
+        println!("       oxidate-cli <file.fsm> --event-matrix [FSM]   (print the event matrix as CSV)");
+        println!("       oxidate-cli <file.fsm> --analyze [FSM] [--depth N]");
+        println!("                                                      (explore every event in every reachable state");
+        println!("                                                       with guards both passing and failing, up to N");
+        println!("                                                       events deep, and list transitions, states and");
+        println!("                                                       actions that never come up)");
+        println!("       oxidate-cli <file.fsm> --format dot [FSM]     (print a Graphviz digraph)");
+        println!("       oxidate-cli <file.fsm> --format plantuml [FSM] (print a PlantUML state diagram)");
+        println!("       oxidate-cli <file.fsm> --format rust [FSM] [--lenient-names] [--metrics] [--process-result]");
+        println!("                                   [--target NAME]");
+        println!("                                                      (print generated Rust; --lenient-names");
+        println!("                                                       sanitizes names that are not identifiers,");
+        println!("                                                       --metrics adds dispatch counters behind");
+        println!("                                                       the fsm-metrics feature, --process-result");
+        println!("                                                       makes process() return a ProcessResult,");
+        println!("                                                       --target picks the codegen backend)");
+        println!("       oxidate-cli <file.fsm> --format c [FSM]       (print the C header and source, each after a");
+        println!("                                                       `// ==== file: NAME ====` line)");
+        println!("       oxidate-cli --list-targets                      (list the codegen backends)");
+        println!("       oxidate-cli <file.fsm> --check-compile [FSM] [--lenient-names] [--metrics] [--process-result]");
+        println!("                                                      (run cargo check on the generated Rust and");
+        println!("                                                       list its errors and warnings)");
+        println!("       oxidate-cli <oxidate.toml> [--lenient-names] [--shared-events] [--metrics] [--process-result]");
+        println!("                                 [--remove-stale]");
+        println!("                                                      (generate code for every project member into");
+        println!("                                                       the manifest's output folder, rewriting only");
+        println!("                                                       changed files; --shared-events adds events.rs");
+        println!("                                                       with the union of all events, --remove-stale");
+        println!("                                                       deletes generated files of removed FSMs;");
+        println!("                                                       oxidate_manifest.json lists what was made)");
+        println!("       oxidate-cli --verify-manifest PATH              (check the oxidate_manifest.json at PATH, or in");
+        println!("                                                       the folder PATH, against the files: exits with");
+        println!("                                                       1 if a generated file was edited or removed,");
+        println!("                                                       or an FSM's DSL changed since the export)");
+        println!("       oxidate-cli <file.fsm> --import-events HEADER [FSM] [--enum NAME]");
+        println!("                                                      (print the file with an events block declaring");
+        println!("                                                       the enumerators of the C or Rust enums in HEADER,");
+        println!("                                                       or of the one called NAME, in snake_case; those");
+        println!("                                                       the FSM declares already are listed on stderr)");
+        println!("       oxidate-cli <chart.scxml>                      (print an SCXML statechart as DSL; what could");
+        println!("                                                       not be imported is listed on stderr)");
+        println!("       --header-file PATH  (with --format rust/c or a manifest: put the file's lines as comments");
+        println!("                            at the top of every generated file; {{fsm_name}}, {{target}} and");
+        println!("                            {{tool_version}} are filled in)");
+        println!("       --event-order declaration  (with --format rust/c or a manifest: put the events no events");
+        println!("                                   block declares in order of first use instead of by name)");
+        println!("       --async-actions none|annotated|all  (which actions the machine awaits: the DSL's");
+        println!("                                  `async name` ones by default; only --target embassy can)");
+        println!("       --bindings FILE.toml  (with --format rust or --check-compile: implement the actions trait");
+        println!("                              with the HAL snippets of the file as a HalActions struct, and");
+        println!("                              list unbound methods and bindings that match nothing)");
         println!();
         println!("Example: oxidate-cli examples/traffic_light.fsm");
         return;
     }
 
+    let mut options = codegen::CodegenOptions::new()
+        .with_lenient_names(lenient_names)
+        .with_metrics(metrics)
+        .with_process_result(process_result);
+    match event_order.as_deref() {
+        None | Some("alphabetical") => {}
+        Some("declaration") => options = options.with_event_order(codegen::EventOrder::Declaration),
+        Some(other) => {
+            eprintln!("❌ Unknown event order '{}' (available: alphabetical, declaration)", other);
+            std::process::exit(1);
+        }
+    }
+    match async_actions.as_deref() {
+        None | Some("annotated") => {}
+        Some("none") => options = options.with_async_actions(codegen::AsyncActions::None),
+        Some("all") => options = options.with_async_actions(codegen::AsyncActions::All),
+        Some(other) => {
+            eprintln!("❌ Unknown async actions '{}' (available: none, annotated, all)", other);
+            std::process::exit(1);
+        }
+    }
+    if let Some(path) = header_file {
+        match std::fs::read_to_string(&path) {
+            Ok(template) => options = options.with_header_template(template),
+            Err(e) => {
+                eprintln!("❌ Could not read header file '{}': {}", path, e);
+                std::process::exit(1);
+            }
+        }
+    }
     let filename = &args[1];
+    if filename.ends_with(".scxml") {
+        print_scxml_import(filename);
+        return;
+    }
+    if let Some(header) = import_events {
+        print_event_import(filename, &header, args.get(2), enum_name.as_deref());
+        return;
+    }
+    if filename.ends_with(".toml") {
+        generate_project(filename, options.with_shared_events(shared_events), remove_stale);
+        return;
+    }
+    if let Some(path) = bindings_file {
+        match codegen::bindings::Bindings::load(&path) {
+            Ok(bindings) => options = options.with_bindings(bindings),
+            Err(e) => {
+                eprintln!("❌ {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    let event_matrix = args.get(2).map(|a| a == "--event-matrix").unwrap_or(false);
+    let analyze = args.get(2).is_some_and(|a| a == "--analyze");
+    let depth = match depth.map(|d| d.parse::<usize>()) {
+        None => fsm::explore::DEFAULT_EXPLORE_DEPTH,
+        Some(Ok(depth)) => depth,
+        Some(Err(_)) => {
+            eprintln!("❌ --depth takes a number of events");
+            std::process::exit(1);
+        }
+    };
+    let format = match args.get(2).map(String::as_str) {
+        Some("--format") => match args.get(3).map(String::as_str) {
+            Some(format @ ("dot" | "plantuml" | "rust" | "c")) => Some(format),
+            other => {
+                eprintln!("❌ Unknown format '{}' (supported: dot, plantuml, rust, c)", other.unwrap_or(""));
+                return;
+            }
+        },
+        _ => None,
+    };
     
-    match fs::read_to_string(filename) {
-        Ok(content) => {
-            match parse_fsm(&content) {
-                Ok(fsms) => {
-                    println!("✅ Successfully parsed {} FSM(s):", fsms.len());
-                    for fsm in &fsms {
-                        println!();
-                        println!("  FSM: {}", fsm.name);
-                        println!("  States: {}", fsm.states.len());
-                        for state in &fsm.states {
-                            println!("    - {} ({:?})", state.name, state.state_type);
-                        }
-                        println!("  Transitions: {}", fsm.transitions.len());
-                        for t in &fsm.transitions {
-                            println!("    {} --> {} : {}", t.source, t.target, t.label());
-                        }
-                        if let Some(ref initial) = fsm.initial_state {
-                            println!("  Initial State: {}", initial);
-                        }
+    match parse_fsm_file(filename) {
+        Ok(fsms) if check_compile => {
+            if let Some(fsm) = select_fsm(&fsms, args.get(2), filename) {
+                print_binding_warnings(fsm, &options);
+                if !check_rust_code(fsm, &options) {
+                    std::process::exit(1);
+                }
+            }
+        }
+        Ok(fsms) if event_matrix => {
+            if let Some(fsm) = select_fsm(&fsms, args.get(3), filename) {
+                print!("{}", fsm::event_matrix(fsm).to_csv());
+            }
+        }
+        Ok(fsms) if analyze => {
+            if let Some(fsm) = select_fsm(&fsms, args.get(3), filename) {
+                let exploration = fsm::explore(fsm, depth);
+                println!("🔬 {} explored {} events deep (guards taken as free booleans, an over-approximation)", fsm.name, depth);
+                if exploration.is_clean() && !exploration.truncated {
+                    println!("  ✅ Every transition, state and action comes up");
+                }
+                for finding in exploration.messages(fsm) {
+                    println!("  ⚠️  {}", finding);
+                }
+            }
+        }
+        Ok(fsms) if format == Some("dot") => {
+            if let Some(fsm) = select_fsm(&fsms, args.get(4), filename) {
+                print!("{}", codegen::dot::generate_dot(fsm));
+            }
+        }
+        Ok(fsms) if format == Some("plantuml") => {
+            if let Some(fsm) = select_fsm(&fsms, args.get(4), filename) {
+                print!("{}", codegen::generate_plantuml(fsm));
+            }
+        }
+        Ok(fsms) if format == Some("rust") => {
+            if let Some(fsm) = select_fsm(&fsms, args.get(4), filename) {
+                print_rust_code(fsm, backend, &options);
+                print_binding_warnings(fsm, &options);
+            }
+        }
+        Ok(fsms) if format == Some("c") => {
+            if let Some(fsm) = select_fsm(&fsms, args.get(4), filename) {
+                let c = backends.get(codegen::CodegenTarget::C.backend_name()).expect("the C backend is built in");
+                print_rust_code(fsm, c, &options);
+            }
+        }
+        Ok(fsms) => {
+            println!("✅ Successfully parsed {} FSM(s):", fsms.len());
+            for fsm in &fsms {
+                println!();
+                println!("  FSM: {}", fsm.name);
+                if let Some(ref file) = fsm.source_file {
+                    println!("  File: {}", file);
+                }
+                println!("  States: {}", fsm.states.len());
+                for state in &fsm.states {
+                    println!("    - {} ({:?})", state.name, state.state_type);
+                }
+                println!("  Transitions: {}", fsm.transitions.len());
+                for t in &fsm.transitions {
+                    println!("    {} --> {} : {}", t.source, t.target, t.label());
+                }
+                if let Some(initial) = fsm.initial_state() {
+                    println!("  Initial State: {}", initial);
+                }
+                for initial in fsm.initial_transitions.iter() {
+                    if let Some(ref guard) = initial.guard {
+                        println!("    or {} if [{}]", initial.target, guard.expression);
                     }
                 }
-                Err(e) => {
-                    eprintln!("❌ Parse error: {}", e);
+                for warning in fsm.warnings() {
+                    println!("  ⚠️  {}", warning);
+                }
+                for info in fsm.infos() {
+                    println!("  ℹ️  {}", info);
+                }
+                if let Err(errors) = fsm::naming::check_names(fsm, false) {
+                    for error in errors {
+                        println!("  ⚠️  {}", error);
+                    }
                 }
             }
         }
+        Err(parser::ParseError::Io { file, message }) => {
+            eprintln!("❌ Could not read file '{}': {}", file, message);
+        }
+        Err(e) => {
+            eprintln!("❌ Parse error: {}", e);
+        }
+    }
+}
+
+/// DSL of an SCXML file on stdout, import warnings on stderr
+fn print_scxml_import(filename: &str) {
+    let xml = match std::fs::read_to_string(filename) {
+        Ok(xml) => xml,
         Err(e) => {
             eprintln!("❌ Could not read file '{}': {}", filename, e);
+            std::process::exit(1);
+        }
+    };
+    match parser::import_scxml(&xml) {
+        Ok(import) => {
+            for warning in &import.warnings {
+                eprintln!("⚠️  {}", warning);
+            }
+            print!("{}", parser::format::format_fsms(&import.fsms));
+        }
+        Err(e) => {
+            eprintln!("❌ Could not import '{}': {}", filename, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// `filename` with the events of `header` declared in its FSM `name` on stdout, the
+/// enumerators left out on stderr
+fn print_event_import(filename: &str, header: &str, name: Option<&String>, enum_name: Option<&str>) {
+    let read = |path: &str| {
+        std::fs::read_to_string(path).unwrap_or_else(|e| {
+            eprintln!("❌ Could not read file '{}': {}", path, e);
+            std::process::exit(1);
+        })
+    };
+    let (source, text) = (read(filename), read(header));
+    let fsms = parser::parse_fsm(&source).unwrap_or_else(|e| {
+        eprintln!("❌ Parse error: {}", e);
+        std::process::exit(1);
+    });
+    let Some(fsm) = select_fsm(&fsms, name, filename) else {
+        std::process::exit(1);
+    };
+    let import = parser::import_events(&text, enum_name, fsm).unwrap_or_else(|e| {
+        eprintln!("❌ Could not import events from '{}': {}", header, e);
+        std::process::exit(1);
+    });
+    for conflict in &import.conflicts {
+        eprintln!("⚠️  {}", conflict);
+    }
+    let origin = std::path::Path::new(header).file_name().map_or(header.into(), |f| f.to_string_lossy());
+    let edited = match import.events_block(&origin) {
+        Some(block) => parser::edit::add_events_block(&source, fsm, &block).unwrap_or_else(|| {
+            eprintln!("❌ No line to add the events block on in {}", fsm.name);
+            std::process::exit(1);
+        }),
+        None => source,
+    };
+    print!("{}", edited);
+}
+
+/// The FSM named on the command line, or the first one in the file
+fn select_fsm<'a>(fsms: &'a [fsm::FsmDefinition], name: Option<&String>, filename: &str) -> Option<&'a fsm::FsmDefinition> {
+    let fsm = match name {
+        Some(name) => fsms.iter().find(|f| &f.name == name),
+        None => fsms.first(),
+    };
+    if fsm.is_none() {
+        match name {
+            Some(name) => eprintln!("❌ FSM '{}' not found", name),
+            None => eprintln!("❌ No FSM defined in '{}'", filename),
+        }
+    }
+    fsm
+}
+
+/// Code generated by `backend` on stdout; sanitized names and errors on stderr
+fn print_rust_code(fsm: &fsm::FsmDefinition, backend: &dyn codegen::CodegenBackend, options: &codegen::CodegenOptions) {
+    match codegen::generate_with_backend(fsm, backend, options) {
+        Ok(code) => {
+            if let Ok(renames) = fsm::naming::check_names(fsm, options.lenient_names) {
+                for rename in renames {
+                    eprintln!("ℹ️  {}", rename);
+                }
+            }
+            print!("{}", code);
+        }
+        Err(errors) => {
+            for error in errors {
+                eprintln!("❌ {}", error);
+            }
+        }
+    }
+}
+
+/// Methods the bindings file leaves unbound and bindings that match nothing, on stderr
+fn print_binding_warnings(fsm: &fsm::FsmDefinition, options: &codegen::CodegenOptions) {
+    if let Some(ref bindings) = options.bindings {
+        for warning in bindings.check(fsm, options).warnings(&fsm.name) {
+            eprintln!("⚠️  {}", warning);
         }
     }
 }
+
+/// `cargo check` the generated Rust; diagnostics on stderr, true if it compiled
+fn check_rust_code(fsm: &fsm::FsmDefinition, options: &codegen::CodegenOptions) -> bool {
+    use codegen::check::{check_generated_code, DiagnosticLevel};
+
+    let code = match codegen::try_generate_rust_code_with_options(fsm, codegen::CodegenTarget::Standard, options) {
+        Ok(code) => code,
+        Err(errors) => {
+            for error in errors {
+                eprintln!("❌ {}", error);
+            }
+            return false;
+        }
+    };
+    match check_generated_code(codegen::CodegenTarget::Standard, &code) {
+        Ok(report) => {
+            for diagnostic in &report.diagnostics {
+                let icon = match diagnostic.level {
+                    DiagnosticLevel::Error => "❌",
+                    DiagnosticLevel::Warning => "⚠️ ",
+                };
+                let line = diagnostic.line.map(|l| format!("line {}: ", l)).unwrap_or_default();
+                eprintln!("{} {}{}", icon, line, diagnostic.message);
+            }
+            let (errors, warnings) = (report.count(DiagnosticLevel::Error), report.count(DiagnosticLevel::Warning));
+            if report.success {
+                println!("✅ Generated code for {} compiles ({} warning(s))", fsm.name, warnings);
+            } else {
+                println!("❌ Generated code for {} does not compile ({} error(s), {} warning(s))", fsm.name, errors, warnings);
+            }
+            report.success
+        }
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            false
+        }
+    }
+}
+
+/// Parse every member of a project and write the `autogen` module for those that parsed;
+/// with `remove_stale`, delete generated files of FSMs that are gone
+fn generate_project(manifest: &str, options: codegen::CodegenOptions, remove_stale: bool) {
+    let project = match project::Project::load(manifest) {
+        Ok(project) => project,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
+    println!("Project: {}", project.name());
+
+    let mut fsms = Vec::new();
+    for member in project.parse_members() {
+        let file = member.path.strip_prefix(project.root()).unwrap_or(&member.path).display().to_string();
+        match member.result {
+            Ok(parsed) => {
+                let names: Vec<&str> = parsed.iter().map(|f| f.name.as_str()).collect();
+                println!("  ✅ {}: {}", file, names.join(", "));
+                fsms.extend(parsed);
+            }
+            Err(e) => eprintln!("  ❌ {}: {}", file, e),
+        }
+    }
+    for name in project::duplicate_fsm_names(&fsms) {
+        eprintln!("  ⚠️  FSM '{}' is defined by more than one member", name);
+    }
+
+    match codegen::autogen::write_autogen_folder(&project.output_dir(), &fsms, codegen::CodegenTarget::Standard, &options) {
+        Ok(mut summary) => {
+            if remove_stale {
+                if let Err(e) = codegen::autogen::remove_stale_files(&mut summary) {
+                    eprintln!("❌ Could not remove stale files: {}", e);
+                }
+            }
+            println!("📁 Exported {} FSM(s) to {} ({})", fsms.len(), summary.folder.display(), summary.describe());
+            for name in &summary.written {
+                println!("  ✏️  {}", name);
+            }
+            for name in &summary.removed {
+                println!("  🗑  {}", name);
+            }
+            for name in &summary.stale {
+                println!("  ⚠️  {} is stale (its FSM is gone); --remove-stale deletes it", name);
+            }
+        }
+        Err(codegen::autogen::AutogenError::SharedEvents(errors)) => {
+            for error in errors {
+                eprintln!("❌ {}", error);
+            }
+        }
+        Err(e) => eprintln!("❌ Could not write to '{}': {}", project.output_dir().display(), e),
+    }
+}
+
+/// Check an export manifest against its folder and the DSL files; true if nothing drifted
+fn verify_export_manifest(path: &str) -> bool {
+    use codegen::autogen::ExportManifest;
+
+    let manifest = match ExportManifest::load(path) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            eprintln!("❌ Could not read manifest '{}': {}", path, e);
+            return false;
+        }
+    };
+    let path = std::path::Path::new(path);
+    let folder = match path.is_dir() {
+        true => path,
+        false => path.parent().unwrap_or(std::path::Path::new(".")),
+    };
+    let mismatches = manifest.verify(folder);
+    for mismatch in &mismatches {
+        eprintln!("❌ {}", mismatch);
+    }
+    if mismatches.is_empty() {
+        println!(
+            "✅ {} file(s) of {} FSM(s) match the manifest",
+            manifest.all_files().count(),
+            manifest.fsms.len()
+        );
+    }
+    mismatches.is_empty()
+}
+
+/// Remove `flag` and the value after it from `args`, returning the value
+fn take_option(args: &mut Vec<String>, flag: &str) -> Option<String> {
+    let index = args.iter().position(|a| a == flag)?;
+    args.remove(index);
+    (index < args.len()).then(|| args.remove(index))
+}
@@ -0,0 +1,169 @@
+//! Generated Code View
+//! Read-only, highlighted view of the generated code whose marked sections
+//! (`// ── Events ──`, see [`code_sections`]) fold away. Which sections are folded is
+//! remembered per target and FSM for the session.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use eframe::egui;
+
+use crate::codegen::check::DiagnosticLevel;
+use crate::codegen::highlight::{tokens, TokenClass};
+use crate::codegen::{code_sections, CodeSection, CodegenTarget};
+
+#[cfg(test)]
+mod tests;
+
+/// Folded section titles by target and FSM name
+#[derive(Debug, Default)]
+pub struct CodeFolds {
+    folded: HashMap<(CodegenTarget, String), HashSet<String>>,
+}
+
+impl CodeFolds {
+    pub fn of(&mut self, target: CodegenTarget, fsm: &str) -> &mut HashSet<String> {
+        self.folded.entry((target, fsm.to_string())).or_default()
+    }
+}
+
+/// A run of lines shown together: plain code, or a section's header and (unless
+/// folded) body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Chunk {
+    Code(Range<usize>),
+    Header { title: String, lines: Range<usize>, folded: bool },
+}
+
+/// How `line_count` lines with `sections` are shown when the `folded` titles are
+pub fn chunks(sections: &[CodeSection], line_count: usize, folded: &HashSet<String>) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut next = 0;
+    for section in sections {
+        if next < section.lines.start {
+            chunks.push(Chunk::Code(next..section.lines.start));
+        }
+        let is_folded = folded.contains(&section.title);
+        chunks.push(Chunk::Header { title: section.title.clone(), lines: section.lines.clone(), folded: is_folded });
+        if !is_folded && section.lines.start + 1 < section.lines.end {
+            chunks.push(Chunk::Code(section.lines.start + 1..section.lines.end));
+        }
+        next = section.lines.end;
+    }
+    if next < line_count {
+        chunks.push(Chunk::Code(next..line_count));
+    }
+    chunks
+}
+
+/// `code` with its sections foldable through `folded`; `marks` are compile check
+/// results by 1-based line
+pub fn show(
+    ui: &mut egui::Ui,
+    code: &str,
+    font_size: f32,
+    marks: &HashMap<usize, DiagnosticLevel>,
+    folded: &mut HashSet<String>,
+) {
+    let lines: Vec<&str> = code.split_inclusive('\n').collect();
+    let font = egui::FontId::monospace(font_size);
+    let mut toggle = None;
+    for (index, chunk) in chunks(&code_sections(code), lines.len(), folded).into_iter().enumerate() {
+        match chunk {
+            Chunk::Code(range) => {
+                let mut text = lines[range.clone()].concat();
+                // The last newline would show as an empty line
+                if text.ends_with('\n') {
+                    text.pop();
+                }
+                let first_line = range.start + 1;
+                let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+                    let mut job = layout_job(ui.visuals(), &font, text, first_line, marks);
+                    job.wrap.max_width = wrap_width;
+                    ui.fonts(|fonts| fonts.layout_job(job))
+                };
+                ui.add(
+                    egui::TextEdit::multiline(&mut text.as_str())
+                        .id_salt(("generated_code", index))
+                        .font(font.clone())
+                        .code_editor()
+                        .frame(false)
+                        .desired_rows(1)
+                        .desired_width(f32::INFINITY)
+                        .layouter(&mut layouter),
+                );
+            }
+            Chunk::Header { title, lines: range, folded: is_folded } => {
+                let hidden = range.len() - 1;
+                let arrow = if is_folded { "▶" } else { "▼" };
+                let mut label = egui::RichText::new(format!("{arrow} {title}")).font(font.clone()).strong();
+                // A folded section still shows that the compile check found something in it
+                let level = range.clone().filter_map(|line| marks.get(&(line + 1))).max_by_key(|level| **level == DiagnosticLevel::Error);
+                if is_folded {
+                    label = match level {
+                        Some(DiagnosticLevel::Error) => label.color(egui::Color32::from_rgb(220, 70, 70)),
+                        Some(DiagnosticLevel::Warning) => label.color(egui::Color32::from_rgb(220, 170, 40)),
+                        None => label,
+                    };
+                }
+                let response = ui.add(egui::Label::new(label).sense(egui::Sense::click()));
+                let response = if is_folded {
+                    response.on_hover_text(format!("{hidden} lines folded, click to show"))
+                } else {
+                    response.on_hover_text("Click to fold")
+                };
+                if response.clicked() {
+                    toggle = Some(title);
+                }
+            }
+        }
+    }
+    if let Some(title) = toggle {
+        if !folded.remove(&title) {
+            folded.insert(title);
+        }
+    }
+}
+
+/// `text` (starting at 1-based line `first_line`) highlighted, with marked lines shaded
+fn layout_job(
+    visuals: &egui::Visuals,
+    font: &egui::FontId,
+    text: &str,
+    first_line: usize,
+    marks: &HashMap<usize, DiagnosticLevel>,
+) -> egui::text::LayoutJob {
+    let mut job = egui::text::LayoutJob::default();
+    let mut line = first_line;
+    for (class, token) in tokens(text) {
+        let color = token_color(visuals, class);
+        for piece in token.split_inclusive('\n') {
+            let mut format = egui::TextFormat::simple(font.clone(), color);
+            format.background = match marks.get(&line) {
+                Some(DiagnosticLevel::Error) => egui::Color32::from_rgba_unmultiplied(220, 50, 50, 60),
+                Some(DiagnosticLevel::Warning) => egui::Color32::from_rgba_unmultiplied(220, 180, 40, 50),
+                None => egui::Color32::TRANSPARENT,
+            };
+            job.append(piece, 0.0, format);
+            if piece.ends_with('\n') {
+                line += 1;
+            }
+        }
+    }
+    job
+}
+
+fn token_color(visuals: &egui::Visuals, class: TokenClass) -> egui::Color32 {
+    let dark = visuals.dark_mode;
+    match class {
+        TokenClass::Plain => visuals.text_color(),
+        TokenClass::Keyword if dark => egui::Color32::from_rgb(200, 130, 220),
+        TokenClass::Keyword => egui::Color32::from_rgb(160, 48, 138),
+        TokenClass::String if dark => egui::Color32::from_rgb(150, 200, 120),
+        TokenClass::String => egui::Color32::from_rgb(32, 112, 32),
+        TokenClass::Number if dark => egui::Color32::from_rgb(120, 180, 240),
+        TokenClass::Number => egui::Color32::from_rgb(16, 96, 160),
+        TokenClass::Comment if dark => egui::Color32::from_gray(130),
+        TokenClass::Comment => egui::Color32::from_gray(112),
+    }
+}
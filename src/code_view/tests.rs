@@ -0,0 +1,31 @@
+//! Unit tests for the generated code view
+
+use std::collections::HashSet;
+
+use crate::code_view::{chunks, Chunk};
+use crate::codegen::code_sections;
+
+const CODE: &str = "//! header\n\n// ── States ──\nenum S {}\n\n// ── Events ──\nenum E {}\n";
+
+#[test]
+fn test_chunks_fold_section_bodies() {
+    let sections = code_sections(CODE);
+    let header = |title: &str, lines, folded| Chunk::Header { title: title.to_string(), lines, folded };
+
+    assert_eq!(
+        chunks(&sections, 7, &HashSet::new()),
+        vec![
+            Chunk::Code(0..2),
+            header("States", 2..5, false),
+            Chunk::Code(3..5),
+            header("Events", 5..7, false),
+            Chunk::Code(6..7),
+        ]
+    );
+
+    let folded = HashSet::from(["States".to_string()]);
+    assert_eq!(
+        chunks(&sections, 7, &folded),
+        vec![Chunk::Code(0..2), header("States", 2..5, true), header("Events", 5..7, false), Chunk::Code(6..7)]
+    );
+}
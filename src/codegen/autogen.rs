@@ -0,0 +1,545 @@
+//! Autogen Module Export
+//! Writes generated code for several FSMs as an `autogen` module folder:
+//! one `<fsm>.rs` per machine, a `mod.rs` re-exporting them and a README
+//! (plus `events.rs` with shared events, if requested); for the C target, a
+//! `<fsm>.h`/`<fsm>.c` pair per machine and the README. Files already holding the
+//! generated contents are not rewritten, so incremental builds and diffs only see
+//! what changed. The `header_template` of the options goes first in every source
+//! file, above the generated marker.
+//!
+//! Folder exports also write `oxidate_manifest.json`, an [`ExportManifest`] telling
+//! build tools what was generated from what without parsing Rust: the options, and
+//! per FSM its source file, a hash of its DSL, its files with their hashes, its
+//! identifiers and the warnings of the export. [`ExportManifest::verify`] reports
+//! files edited since, and FSMs whose DSL changed without generating again.
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::c::split_c_files;
+use super::shared_events::{generate_shared_events, shared_events, SHARED_EVENTS_FILE};
+use super::{
+    collect_trait_methods, generate_rust_code_with_options, prepare_for_backend, prepare_fsm, CodegenError, CodegenOptions,
+    CodegenTarget,
+    MethodRole,
+};
+use crate::fsm::FsmDefinition;
+use crate::parser::format::format_fsm;
+use crate::parser::loader::parse_fsm_file;
+
+/// Name of the exported module folder
+pub const AUTOGEN_DIR: &str = "autogen";
+
+/// Header line marking a file as generated (in the leading comment and blank lines of every
+/// `.rs`, `.h` and `.c` file; `//` starts a comment in C too)
+const GENERATED_MARKER: &str = "//! DO NOT EDIT - Generated by Oxidate";
+
+/// Name of the manifest written into the `autogen` folder
+pub const MANIFEST_FILE: &str = "oxidate_manifest.json";
+
+#[derive(Error, Debug)]
+pub enum AutogenError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("{}", join_errors(.0))]
+    SharedEvents(Vec<CodegenError>),
+    #[error("Invalid manifest: {0}")]
+    Manifest(#[from] serde_json::Error),
+}
+
+fn join_errors(errors: &[CodegenError]) -> String {
+    errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; ")
+}
+
+/// Placeholder file contents listing why code generation failed
+pub fn failure_report(fsm_name: &str, errors: &[String]) -> String {
+    let mut code = format!("//! Auto-generated FSM: {}\n//! Code generation failed:\n", fsm_name);
+    for error in errors {
+        code.push_str(&format!("//! - {}\n", error));
+    }
+    code
+}
+
+/// File names and contents of the `autogen` folder; an FSM that fails to
+/// generate gets a `failure_report` instead of code. With `shared_events` (and the
+/// standard target) `events.rs` covers the FSMs that generate; event names that
+/// clash there fail the whole export.
+pub fn autogen_files(
+    fsms: &[FsmDefinition],
+    target: CodegenTarget,
+    options: &CodegenOptions,
+) -> Result<Vec<(String, String)>, Vec<CodegenError>> {
+    // The template goes above the export's own header lines, not below them
+    let file_header = |fsm_name: &str| options.file_header(fsm_name, target.backend_name());
+    let options = &CodegenOptions { header_template: None, ..options.clone() };
+    if target == CodegenTarget::C {
+        return Ok(c_files(fsms, options, &file_header));
+    }
+    let all_names = fsms.iter().map(|f| f.name.as_str()).collect::<Vec<_>>().join(", ");
+    let mut files = Vec::new();
+    let mut mod_content = format!("{}//! Auto-generated FSM code\n{}\n\n", file_header(&all_names), GENERATED_MARKER);
+
+    let prepared: Vec<_> = fsms.iter().map(|fsm| prepare_for_backend(fsm, target.backend(), options)).collect();
+    let shared = if options.shared_events && target == CodegenTarget::Standard {
+        let generated: Vec<FsmDefinition> = prepared.iter().filter_map(|p| p.as_ref().ok()).cloned().collect();
+        let events = shared_events(&generated)?;
+        (!events.is_empty()).then(|| generate_shared_events(&generated, &events, options))
+    } else {
+        None
+    };
+
+    for (fsm, prepared) in fsms.iter().zip(&prepared) {
+        let snake_name = fsm.file_stem();
+
+        let code = match prepared {
+            Ok(prepared) => generate_rust_code_with_options(prepared, target, options),
+            Err(errors) => {
+                let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                failure_report(&fsm.name, &errors)
+            }
+        };
+        let header = format!(
+            "{}//! Auto-generated code for {} FSM\n{}\n//! Target: {:?}\n\n",
+            file_header(&fsm.name),
+            fsm.name,
+            GENERATED_MARKER,
+            target
+        );
+        files.push((format!("{}.rs", snake_name), header + &code));
+
+        mod_content.push_str(&format!("pub mod {};\n", snake_name));
+    }
+    if shared.is_some() {
+        mod_content.push_str("pub mod events;\n");
+    }
+
+    // Also export pub use statements
+    mod_content.push_str("\n// Re-exports\n");
+    for fsm in fsms {
+        mod_content.push_str(&format!("pub use {}::*;\n", fsm.file_stem()));
+    }
+    if shared.is_some() {
+        mod_content.push_str("pub use events::SharedEvent;\n");
+    }
+    files.push(("mod.rs".to_string(), mod_content));
+
+    let readme = format!(
+        "# Auto-generated FSM Code\n\n\
+        Generated by Oxidate FSM Visualizer\n\n\
+        ## Files\n\n\
+        - `mod.rs` - Module declarations\n\
+        {}{}\n\n\
+        ## Usage\n\n\
+        Add to your `lib.rs` or `main.rs`:\n\n\
+        ```rust\n\
+        mod autogen;\n\
+        use autogen::*;\n\
+        ```\n\n\
+        ## Target: {:?}\n",
+        fsms.iter()
+            .map(|f| format!("- `{}.rs` - {} state machine", f.file_stem(), f.name))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        if shared.is_some() {
+            "\n- `events.rs` - `SharedEvent`, the union of all events (enable the `shared-events` feature \
+             for `process_shared()`/`post_shared()`)"
+        } else {
+            ""
+        },
+        target
+    );
+    files.push(("README.md".to_string(), readme));
+    if let Some(shared) = shared {
+        files.push((SHARED_EVENTS_FILE.to_string(), file_header(&all_names) + &shared));
+    }
+    Ok(files)
+}
+
+/// `<fsm>.h` and `<fsm>.c` per FSM (a failing one gets its `failure_report` as the
+/// header) and a README
+fn c_files(fsms: &[FsmDefinition], options: &CodegenOptions, file_header: &dyn Fn(&str) -> String) -> Vec<(String, String)> {
+    let mut files = Vec::new();
+    for fsm in fsms {
+        let header = format!(
+            "{}//! Auto-generated code for {} FSM\n{}\n//! Target: C\n\n",
+            file_header(&fsm.name),
+            fsm.name,
+            GENERATED_MARKER
+        );
+        match prepare_for_backend(fsm, CodegenTarget::C.backend(), options) {
+            Ok(prepared) => {
+                let code = generate_rust_code_with_options(&prepared, CodegenTarget::C, options);
+                files.extend(split_c_files(&code).into_iter().map(|(name, contents)| (name, header.clone() + &contents)));
+            }
+            Err(errors) => {
+                let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                files.push((format!("{}.h", fsm.file_stem()), header + &failure_report(&fsm.name, &errors)));
+            }
+        }
+    }
+
+    let readme = format!(
+        "# Auto-generated FSM Code\n\n\
+        Generated by Oxidate FSM Visualizer\n\n\
+        ## Files\n\n\
+        {}\n\n\
+        ## Usage\n\n\
+        Compile the `.c` files with your sources, fill in a `<fsm>_actions_t` table and\n\
+        call `<fsm>_init()`, then `<fsm>_dispatch()` for every event.\n\n\
+        ## Target: C\n",
+        fsms.iter()
+            .map(|f| format!("- `{0}.h`, `{0}.c` - {1} state machine", f.file_stem(), f.name))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    );
+    files.push(("README.md".to_string(), readme));
+    files
+}
+
+/// What `write_autogen_folder` did with the files of the `autogen` folder
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportSummary {
+    /// The `autogen` folder
+    pub folder: PathBuf,
+    /// Files created or changed
+    pub written: Vec<String>,
+    /// Files that already held the generated contents, left untouched (their
+    /// modification times do not move)
+    pub unchanged: Vec<String>,
+    /// Generated files in the folder for FSMs that are no longer exported
+    pub stale: Vec<String>,
+    /// Stale files deleted by `remove_stale_files`
+    pub removed: Vec<String>,
+}
+
+impl ExportSummary {
+    /// `2 written, 3 unchanged, 1 stale`, leaving out what did not happen
+    pub fn describe(&self) -> String {
+        let counts = [
+            (self.written.len(), "written"),
+            (self.unchanged.len(), "unchanged"),
+            (self.removed.len(), "removed"),
+            (self.stale.len(), "stale"),
+        ];
+        let parts: Vec<String> = counts.iter().filter(|(n, _)| *n > 0).map(|(n, what)| format!("{} {}", n, what)).collect();
+        match parts.is_empty() {
+            true => "nothing to write".to_string(),
+            false => parts.join(", "),
+        }
+    }
+}
+
+/// Write `autogen_files` into `folder/autogen`, creating it. Files whose contents
+/// are already up to date are skipped, and generated source files no other file
+/// replaces (an FSM that was deleted or renamed) are reported as stale.
+pub fn write_autogen_folder(
+    folder: &Path,
+    fsms: &[FsmDefinition],
+    target: CodegenTarget,
+    options: &CodegenOptions,
+) -> Result<ExportSummary, AutogenError> {
+    let files = autogen_files(fsms, target, options).map_err(AutogenError::SharedEvents)?;
+    let autogen_folder = folder.join(AUTOGEN_DIR);
+    std::fs::create_dir_all(&autogen_folder)?;
+
+    let mut summary = ExportSummary {
+        folder: autogen_folder.clone(),
+        ..ExportSummary::default()
+    };
+    for (name, contents) in &files {
+        let path = autogen_folder.join(name);
+        if std::fs::read(&path).is_ok_and(|existing| existing == contents.as_bytes()) {
+            summary.unchanged.push(name.clone());
+        } else {
+            std::fs::write(path, contents)?;
+            summary.written.push(name.clone());
+        }
+    }
+
+    // Only the time of an export that changed nothing is not worth a rewrite
+    let manifest = ExportManifest::new(fsms, target, options, &files);
+    let path = autogen_folder.join(MANIFEST_FILE);
+    let existing = std::fs::read_to_string(&path).ok().and_then(|json| ExportManifest::from_json(&json).ok());
+    if existing.is_some_and(|existing| ExportManifest { generated_at: manifest.generated_at, ..existing } == manifest) {
+        summary.unchanged.push(MANIFEST_FILE.to_string());
+    } else {
+        std::fs::write(path, manifest.to_json())?;
+        summary.written.push(MANIFEST_FILE.to_string());
+    }
+    summary.stale = stale_files(&autogen_folder, &files)?;
+    Ok(summary)
+}
+
+/// Delete the stale files `write_autogen_folder` reported, moving them to `removed`
+pub fn remove_stale_files(summary: &mut ExportSummary) -> std::io::Result<()> {
+    while let Some(name) = summary.stale.first() {
+        std::fs::remove_file(summary.folder.join(name))?;
+        let name = summary.stale.remove(0);
+        summary.removed.push(name);
+    }
+    Ok(())
+}
+
+/// Generated `.rs`, `.h` and `.c` files in `folder` that are not among `files` (files without the
+/// generated header are the user's and never stale)
+fn stale_files(folder: &Path, files: &[(String, String)]) -> std::io::Result<Vec<String>> {
+    let mut stale = Vec::new();
+    for entry in std::fs::read_dir(folder)? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        let source = [".rs", ".h", ".c"].iter().any(|ext| name.ends_with(ext));
+        if !source || files.iter().any(|(file, _)| *file == name) {
+            continue;
+        }
+        let generated = std::fs::read_to_string(folder.join(&name)).is_ok_and(|c| c.lines().take_while(|l| l.is_empty() || l.starts_with("//")).any(|l| l == GENERATED_MARKER));
+        if generated {
+            stale.push(name);
+        }
+    }
+    stale.sort();
+    Ok(stale)
+}
+
+/// `oxidate_manifest.json`: what a folder export generated, and from what
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportManifest {
+    /// Version of Oxidate that wrote the files
+    pub tool_version: String,
+    /// Seconds since the Unix epoch
+    pub generated_at: u64,
+    /// Backend name (`standard`, `c`, ...)
+    pub target: String,
+    pub options: ManifestOptions,
+    pub fsms: Vec<ManifestFsm>,
+    /// Files of no single FSM (`mod.rs`, `README.md`, `events.rs`)
+    #[serde(default)]
+    pub files: Vec<ManifestFile>,
+}
+
+/// The code generation options of an export
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ManifestOptions {
+    pub visibility: String,
+    pub no_std: bool,
+    pub repr_u8: bool,
+    pub event_queue_size: Option<usize>,
+    pub overflow_policy: String,
+    pub lenient_names: bool,
+    pub shared_events: bool,
+    pub metrics: bool,
+    pub tracing_hook: bool,
+    pub process_result: bool,
+    pub run_entry_on_new: bool,
+    pub max_completion_hops: Option<usize>,
+    pub action_order: String,
+    pub event_order: String,
+    pub state_derives: Vec<String>,
+    pub event_derives: Vec<String>,
+    /// Struct implementing the actions trait from a bindings file, if any
+    pub bindings: Option<String>,
+}
+
+/// One exported FSM
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ManifestFsm {
+    pub name: String,
+    /// DSL file it was parsed from (as opened; a relative path is taken from the
+    /// working directory), `None` for an unsaved editor buffer
+    pub source_file: Option<String>,
+    /// [`dsl_hash`] of the FSM
+    pub dsl_hash: String,
+    pub files: Vec<ManifestFile>,
+    /// States and events as the DSL names them
+    pub states: Vec<String>,
+    pub events: Vec<String>,
+    /// Methods of the actions trait
+    pub actions: Vec<String>,
+    pub guards: Vec<String>,
+    /// Validation and binding warnings, and the errors of an FSM that failed to generate
+    pub warnings: Vec<String>,
+}
+
+/// A generated file and the [`content_hash`] of its contents
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    pub name: String,
+    pub hash: String,
+}
+
+/// Something `ExportManifest::verify` found out of date
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ManifestMismatch {
+    #[error("{file} is missing")]
+    Missing { file: String },
+    #[error("{file} was changed after it was generated")]
+    Modified { file: String },
+    #[error("{fsm} changed in {source_file} since the code was generated")]
+    SourceChanged { fsm: String, source_file: String },
+    #[error("{fsm}: could not check {source_file}: {message}")]
+    SourceUnreadable { fsm: String, source_file: String, message: String },
+}
+
+/// Stable hash of file contents as the manifest records them (64-bit FNV-1a)
+pub fn content_hash(contents: &[u8]) -> String {
+    let hash = contents.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("fnv1a64:{:016x}", hash)
+}
+
+/// Hash of an FSM's DSL as the formatter prints it, so that the rest of its file
+/// does not count
+pub fn dsl_hash(fsm: &FsmDefinition) -> String {
+    content_hash(format_fsm(fsm).as_bytes())
+}
+
+impl ManifestOptions {
+    fn new(options: &CodegenOptions) -> Self {
+        Self {
+            visibility: options.visibility.as_str().to_string(),
+            no_std: options.no_std,
+            repr_u8: options.repr_u8,
+            event_queue_size: options.event_queue_size,
+            overflow_policy: options.overflow_policy.label().to_string(),
+            lenient_names: options.lenient_names,
+            shared_events: options.shared_events,
+            metrics: options.metrics,
+            tracing_hook: options.tracing_hook,
+            process_result: options.process_result,
+            run_entry_on_new: options.run_entry_on_new,
+            max_completion_hops: options.max_completion_hops,
+            action_order: format!("{:?}", options.action_order),
+            event_order: format!("{:?}", options.event_order),
+            state_derives: options.state_derives.clone(),
+            event_derives: options.event_derives.clone(),
+            bindings: options.bindings.as_ref().map(|b| b.struct_name().to_string()),
+        }
+    }
+}
+
+impl ManifestFsm {
+    fn new(fsm: &FsmDefinition, options: &CodegenOptions, files: Vec<ManifestFile>) -> Self {
+        let mut warnings = fsm.warnings();
+        let methods = match prepare_fsm(fsm, options) {
+            Ok(prepared) => {
+                if let Some(ref bindings) = options.bindings {
+                    warnings.extend(bindings.check(fsm, options).warnings(&fsm.name));
+                }
+                collect_trait_methods(&prepared, options)
+            }
+            Err(errors) => {
+                warnings.extend(errors.iter().map(|e| e.to_string()));
+                Vec::new()
+            }
+        };
+        let methods_of = |roles: &[MethodRole]| {
+            let mut names: Vec<String> =
+                methods.iter().filter(|m| roles.contains(&m.role)).map(|m| m.name.clone()).collect();
+            names.dedup();
+            names
+        };
+        Self {
+            name: fsm.name.clone(),
+            source_file: fsm.source_file.clone(),
+            dsl_hash: dsl_hash(fsm),
+            files,
+            states: fsm.states.iter().map(|s| s.name.clone()).collect(),
+            events: fsm.events_in(options.event_order).into_iter().map(|e| e.name).collect(),
+            actions: methods_of(&[MethodRole::Action]),
+            guards: methods_of(&[MethodRole::Guard, MethodRole::NamedGuard]),
+            warnings,
+        }
+    }
+}
+
+impl ExportManifest {
+    /// Manifest of `files`, the `autogen_files` of `fsms`, stamped with the current time
+    pub fn new(fsms: &[FsmDefinition], target: CodegenTarget, options: &CodegenOptions, files: &[(String, String)]) -> Self {
+        let entry = |(name, contents): &(String, String)| ManifestFile {
+            name: name.clone(),
+            hash: content_hash(contents.as_bytes()),
+        };
+        let stem = |name: &str| name.rsplit_once('.').map_or(name, |(stem, _)| stem).to_string();
+        let fsm_files = |fsm: &FsmDefinition| files.iter().filter(|(name, _)| stem(name) == fsm.file_stem()).map(entry).collect();
+        let stems: Vec<String> = fsms.iter().map(FsmDefinition::file_stem).collect();
+        Self {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            generated_at: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+            target: target.backend_name().to_string(),
+            options: ManifestOptions::new(options),
+            fsms: fsms.iter().map(|fsm| ManifestFsm::new(fsm, options, fsm_files(fsm))).collect(),
+            files: files.iter().filter(|(name, _)| !stems.contains(&stem(name))).map(entry).collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut json = serde_json::to_string_pretty(self).expect("the manifest serializes");
+        json.push('\n');
+        json
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Read a manifest, given its path or the folder holding it
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AutogenError> {
+        let path = path.as_ref();
+        let path = match path.is_dir() {
+            true => path.join(MANIFEST_FILE),
+            false => path.to_path_buf(),
+        };
+        Ok(Self::from_json(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Every file of the manifest, each FSM's first
+    pub fn all_files(&self) -> impl Iterator<Item = &ManifestFile> {
+        self.fsms.iter().flat_map(|fsm| &fsm.files).chain(&self.files)
+    }
+
+    /// Files in `folder` (the manifest's) that are missing or no longer hash the
+    /// same, then FSMs whose source file holds a different DSL now. FSMs without a
+    /// source file are not checked.
+    pub fn verify(&self, folder: &Path) -> Vec<ManifestMismatch> {
+        let mut mismatches = Vec::new();
+        for file in self.all_files() {
+            match std::fs::read(folder.join(&file.name)) {
+                Ok(contents) if content_hash(&contents) == file.hash => {}
+                Ok(_) => mismatches.push(ManifestMismatch::Modified { file: file.name.clone() }),
+                Err(_) => mismatches.push(ManifestMismatch::Missing { file: file.name.clone() }),
+            }
+        }
+
+        let mut parsed: Vec<(&str, Result<Vec<FsmDefinition>, String>)> = Vec::new();
+        for fsm in &self.fsms {
+            let Some(ref source_file) = fsm.source_file else {
+                continue;
+            };
+            if !parsed.iter().any(|(file, _)| file == source_file) {
+                parsed.push((source_file, parse_fsm_file(source_file).map_err(|e| e.to_string())));
+            }
+            let unreadable = |message: String| ManifestMismatch::SourceUnreadable {
+                fsm: fsm.name.clone(),
+                source_file: source_file.clone(),
+                message,
+            };
+            let (_, result) = parsed.iter().find(|(file, _)| file == source_file).expect("parsed above");
+            match result {
+                Err(e) => mismatches.push(unreadable(e.clone())),
+                Ok(fsms) => match fsms.iter().find(|f| f.name == fsm.name) {
+                    None => mismatches.push(unreadable(format!("no FSM named '{}'", fsm.name))),
+                    Some(current) if dsl_hash(current) != fsm.dsl_hash => mismatches.push(ManifestMismatch::SourceChanged {
+                        fsm: fsm.name.clone(),
+                        source_file: source_file.clone(),
+                    }),
+                    Some(_) => {}
+                },
+            }
+        }
+        mismatches
+    }
+}
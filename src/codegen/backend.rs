@@ -0,0 +1,75 @@
+//! Codegen Backends
+//! A backend turns the IR of one FSM into the text of one generated file. The
+//! built-in ones (`standard`, `embassy`, `rtic`, `c`) are registered by name, so
+//! tools like the CLI can list them and pick one; with the `custom-backends` feature
+//! a program can register its own.
+
+use std::collections::BTreeMap;
+
+use super::c::CBackend;
+use super::ir::FsmIr;
+use super::premium::{EmbassyBackend, RticBackend};
+use super::standard::StandardBackend;
+use super::CodegenOptions;
+
+pub trait CodegenBackend: Send + Sync {
+    /// Name to select the backend by (lowercase, e.g. `standard`)
+    fn name(&self) -> &'static str;
+
+    /// One line for listings
+    fn description(&self) -> &'static str;
+
+    fn generate(&self, ir: &FsmIr, options: &CodegenOptions) -> String;
+
+    /// Whether the generated machine can await actions (`async fn` in the actions
+    /// trait); generating an FSM with async actions fails for backends that cannot
+    fn awaits_actions(&self) -> bool {
+        false
+    }
+}
+
+/// Backends by name
+pub struct BackendRegistry {
+    backends: BTreeMap<&'static str, Box<dyn CodegenBackend>>,
+}
+
+impl BackendRegistry {
+    /// The backends that ship with Oxidate
+    pub fn builtin() -> Self {
+        let mut registry = Self { backends: BTreeMap::new() };
+        registry.insert(Box::new(StandardBackend));
+        registry.insert(Box::new(EmbassyBackend));
+        registry.insert(Box::new(RticBackend));
+        registry.insert(Box::new(CBackend));
+        registry
+    }
+
+    /// Add a backend, replacing any registered under the same name
+    #[cfg(feature = "custom-backends")]
+    pub fn register(&mut self, backend: Box<dyn CodegenBackend>) {
+        self.insert(backend);
+    }
+
+    fn insert(&mut self, backend: Box<dyn CodegenBackend>) {
+        self.backends.insert(backend.name(), backend);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn CodegenBackend> {
+        self.backends.get(name).map(|backend| backend.as_ref())
+    }
+
+    /// Every backend, by name
+    pub fn iter(&self) -> impl Iterator<Item = &dyn CodegenBackend> {
+        self.backends.values().map(|backend| backend.as_ref())
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.backends.keys().copied().collect()
+    }
+}
+
+impl Default for BackendRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
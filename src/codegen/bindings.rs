@@ -0,0 +1,245 @@
+//! HAL Bindings
+//! A bindings file maps the actions, guards and getters of the actions trait to the
+//! code that implements them on a board, so the generated file carries a ready
+//! implementation instead of a trait to fill in by hand:
+//!
+//! ```toml
+//! struct = "BoardActions"  # optional, `HalActions` if not set
+//!
+//! [imports]                # per backend name, or `all`
+//! all = ["crate::hal::{gpio_set, gpio_read, PA5, PB2, HIGH, LOW}"]
+//!
+//! [actions]
+//! activate_red_light = "gpio_set(PA5, HIGH)"
+//!
+//! [guards]
+//! door_closed = "gpio_read(PB2) == LOW"
+//!
+//! [getters]
+//! speed = "42"
+//! ```
+//!
+//! Keys are the DSL names or their snake_case method names. Methods without a
+//! binding get a `todo!()` body, and bindings that match no method are reported.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::{prepare_fsm, rust_trait_methods, CodegenOptions, MethodRole, TraitMethod};
+use crate::fsm::naming::to_snake_case;
+use crate::fsm::FsmDefinition;
+
+/// Name of the implementing struct when the file does not set one
+pub const DEFAULT_STRUCT_NAME: &str = "HalActions";
+
+/// Contents of a bindings file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bindings {
+    /// Name of the struct implementing the actions trait
+    #[serde(rename = "struct", default)]
+    pub struct_name: Option<String>,
+    /// `use` paths per backend name (`standard`, `embassy`, ...), or `all`
+    #[serde(default)]
+    pub imports: BTreeMap<String, Vec<String>>,
+    /// Body of each action method
+    #[serde(default)]
+    pub actions: BTreeMap<String, String>,
+    /// Expression of each guard method
+    #[serde(default)]
+    pub guards: BTreeMap<String, String>,
+    /// Expression of each getter method
+    #[serde(default)]
+    pub getters: BTreeMap<String, String>,
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BindingsError {
+    #[error("Could not read '{file}': {message}")]
+    Io { file: String, message: String },
+    #[error("{file}: {message}")]
+    Syntax { file: String, message: String },
+}
+
+/// How the bindings fit an FSM
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BindingReport {
+    /// Trait methods without a binding, which panic with `todo!()`
+    pub unbound: Vec<String>,
+    /// Bindings that match no method, as `[table] key`
+    pub stale: Vec<String>,
+}
+
+impl BindingReport {
+    /// One line per stale binding, and a summary of the unbound methods
+    pub fn warnings(&self, fsm_name: &str) -> Vec<String> {
+        let mut warnings: Vec<String> =
+            self.stale.iter().map(|binding| format!("Binding {} matches nothing in {}Actions", binding, fsm_name)).collect();
+        if !self.unbound.is_empty() {
+            warnings.push(format!(
+                "{} method(s) of {}Actions have no binding and panic with todo!(): {}",
+                self.unbound.len(),
+                fsm_name,
+                self.unbound.join(", ")
+            ));
+        }
+        warnings
+    }
+}
+
+impl Bindings {
+    /// Read and parse a bindings file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, BindingsError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(|e| BindingsError::Io {
+            file: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+        Self::from_source(&source, path)
+    }
+
+    /// Parse bindings text as if it were the contents of `path`
+    pub fn from_source(source: &str, path: impl AsRef<Path>) -> Result<Self, BindingsError> {
+        toml::from_str(source).map_err(|e| BindingsError::Syntax {
+            file: path.as_ref().display().to_string(),
+            message: e.message().to_string(),
+        })
+    }
+
+    pub fn struct_name(&self) -> &str {
+        self.struct_name.as_deref().unwrap_or(DEFAULT_STRUCT_NAME)
+    }
+
+    /// `use` paths for the `backend`: those of `all`, then its own
+    pub fn imports_for(&self, backend: &str) -> Vec<&str> {
+        ["all", backend]
+            .iter()
+            .filter_map(|key| self.imports.get(*key))
+            .flatten()
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// The snippet bound to `method`, if any
+    pub fn snippet(&self, method: &TraitMethod) -> Option<&str> {
+        let table = self.table(method.role)?;
+        table.iter().find(|(key, _)| binds(key, &method.name)).map(|(_, snippet)| snippet.as_str())
+    }
+
+    fn table(&self, role: MethodRole) -> Option<&BTreeMap<String, String>> {
+        match role {
+            MethodRole::Action => Some(&self.actions),
+            MethodRole::Guard => Some(&self.guards),
+            MethodRole::Getter => Some(&self.getters),
+            MethodRole::NamedGuard | MethodRole::Hook => None,
+        }
+    }
+
+    /// Unbound methods and stale bindings of `fsm` as generated with `options`
+    pub fn check(&self, fsm: &FsmDefinition, options: &CodegenOptions) -> BindingReport {
+        let fsm = prepare_fsm(fsm, options).unwrap_or_else(|_| fsm.clone());
+        let methods = bindable_methods(&fsm, options);
+        let unbound = methods.iter().filter(|m| self.snippet(m).is_none()).map(|m| m.name.clone()).collect();
+        let mut stale = Vec::new();
+        for (table, role) in [("actions", MethodRole::Action), ("guards", MethodRole::Guard), ("getters", MethodRole::Getter)] {
+            for key in self.table(role).into_iter().flat_map(BTreeMap::keys) {
+                if !methods.iter().any(|m| m.role == role && binds(key, &m.name)) {
+                    stale.push(format!("[{}] {}", table, key));
+                }
+            }
+        }
+        BindingReport { unbound, stale }
+    }
+}
+
+/// Whether the key `key` binds the method `method`
+fn binds(key: &str, method: &str) -> bool {
+    key == method || to_snake_case(key) == method
+}
+
+/// The methods a binding can provide, by role and then name, each once
+fn bindable_methods(fsm: &FsmDefinition, options: &CodegenOptions) -> Vec<TraitMethod> {
+    const ROLES: [MethodRole; 3] = [MethodRole::Action, MethodRole::Guard, MethodRole::Getter];
+    let mut methods: Vec<TraitMethod> =
+        rust_trait_methods(fsm, options).into_iter().filter(|m| ROLES.contains(&m.role)).collect();
+    let rank = |role: MethodRole| ROLES.iter().position(|r| *r == role);
+    methods.sort_by(|a, b| (rank(a.role), &a.name).cmp(&(rank(b.role), &b.name)));
+    methods.dedup_by(|a, b| a.name == b.name);
+    methods
+}
+
+/// The imports, the struct and its implementation of the actions trait for the
+/// `backend` of the given name
+pub(super) fn generate_hal_actions(
+    fsm: &FsmDefinition,
+    bindings: &Bindings,
+    backend: &str,
+    options: &CodegenOptions,
+) -> String {
+    let vis = options.visibility.as_str();
+    let struct_name = bindings.struct_name();
+    let methods = bindable_methods(fsm, options);
+    let mut code = String::new();
+
+    for import in bindings.imports_for(backend) {
+        code.push_str(&format!("#[allow(unused_imports)]\nuse {};\n", import.trim().trim_end_matches(';')));
+    }
+    if !code.is_empty() {
+        code.push('\n');
+    }
+
+    code.push_str(&format!("/// `{}Actions` implemented by the bindings file\n", fsm.name));
+    let unbound: Vec<&str> = methods.iter().filter(|m| bindings.snippet(m).is_none()).map(|m| m.name.as_str()).collect();
+    if !unbound.is_empty() {
+        code.push_str(&format!("///\n/// Not bound yet (these panic): `{}`\n", unbound.join("`, `")));
+    }
+    code.push_str("#[derive(Debug, Default, Clone, Copy)]\n");
+    code.push_str(&format!("{} struct {};\n\n", vis, struct_name));
+
+    code.push_str("#[allow(unused_variables)]\n");
+    code.push_str(&format!("impl {}Actions for {} {{\n", fsm.name, struct_name));
+    for (i, method) in methods.iter().enumerate() {
+        if i > 0 {
+            code.push('\n');
+        }
+        code.push_str(&format!("    {} {{\n", method.signature()));
+        match bindings.snippet(method) {
+            Some(snippet) => {
+                let snippet = snippet.trim();
+                let needs_semicolon =
+                    method.role == MethodRole::Action && !snippet.ends_with(';') && !snippet.ends_with('}');
+                for line in snippet.lines().map(str::trim_end) {
+                    match line.is_empty() {
+                        true => code.push('\n'),
+                        false => code.push_str(&format!("        {}\n", line)),
+                    }
+                }
+                if needs_semicolon {
+                    code.insert(code.len() - 1, ';');
+                }
+            }
+            None => {
+                code.push_str(&format!(
+                    "        // WARNING: no binding for `{}`; add it to the [{}] of the bindings file\n",
+                    method.name,
+                    binding_table(method.role)
+                ));
+                code.push_str(&format!("        todo!(\"bind {}\")\n", method.name));
+            }
+        }
+        code.push_str("    }\n");
+    }
+    code.push_str("}\n");
+    code
+}
+
+fn binding_table(role: MethodRole) -> &'static str {
+    match role {
+        MethodRole::Guard => "guards",
+        MethodRole::Getter => "getters",
+        _ => "actions",
+    }
+}
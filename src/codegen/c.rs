@@ -0,0 +1,693 @@
+//! C Target
+//! A C99 header and source for toolchains without Rust: state and event enums, a
+//! table of action and guard function pointers (the counterpart of the Rust actions
+//! trait), `<fsm>_init()` and `<fsm>_dispatch()`. Every identifier starts with the
+//! FSM's snake_case name. Both files come back as one string, each after a
+//! [`C_FILE_MARKER`] line; [`split_c_files`] cuts it into the two files.
+//!
+//! Event payloads become one `<fsm>_<event>_args_t` struct per event in a union that
+//! `<fsm>_dispatch_args()` takes alongside the event.
+
+use super::backend::CodegenBackend;
+use super::ir::{guard_ir, Arg, ArmSource, Call, FsmIr, GuardIr, GuardTerm, TransitionArm};
+use super::standard::method_docs;
+use super::{
+    action_order_doc, collect_trait_methods, completions, doc_comment, exit_and_actions, section_marker, CodegenOptions,
+    MethodRole, TraitMethod,
+};
+use crate::fsm::interpreter::DEFAULT_MAX_COMPLETION_HOPS;
+use crate::fsm::naming::{to_pascal_case, to_snake_case};
+use crate::fsm::{FsmDefinition, TimingKind};
+
+/// Start of the line before each file: the marker, then the file name
+pub const C_FILE_MARKER: &str = "// ==== file: ";
+
+pub struct CBackend;
+
+impl CodegenBackend for CBackend {
+    fn name(&self) -> &'static str {
+        "c"
+    }
+
+    fn description(&self) -> &'static str {
+        "C99 header and source with a function-pointer actions table"
+    }
+
+    fn generate(&self, ir: &FsmIr, options: &CodegenOptions) -> String {
+        let names = CNames::new(ir.fsm);
+        let file_header = options.file_header(&ir.name, self.name());
+        let mut code = format!("{}{}.h ====\n{}", C_FILE_MARKER, names.file, file_header);
+        code.push_str(&generate_header(ir, &names, options));
+        code.push_str(&format!("\n{}{}.c ====\n{}", C_FILE_MARKER, names.file, file_header));
+        code.push_str(&generate_source(ir, &names, options));
+        code
+    }
+}
+
+/// File names and contents of the C backend's output (the whole text as one
+/// unnamed file if it has no markers)
+pub fn split_c_files(code: &str) -> Vec<(String, String)> {
+    let mut files: Vec<(String, String)> = Vec::new();
+    for line in code.split_inclusive('\n') {
+        let name = line.strip_prefix(C_FILE_MARKER).and_then(|rest| rest.trim_end().strip_suffix(" ===="));
+        match (name, files.last_mut()) {
+            (Some(name), _) => files.push((name.to_string(), String::new())),
+            (None, Some((_, contents))) => contents.push_str(line),
+            (None, None) => files.push((String::new(), line.to_string())),
+        }
+    }
+    // The blank line before a marker separates the files
+    for (_, contents) in &mut files {
+        if contents.ends_with("\n\n") {
+            contents.pop();
+        }
+    }
+    files
+}
+
+/// Identifiers of one FSM's C API
+struct CNames {
+    /// snake_case FSM name
+    prefix: String,
+    upper: String,
+    /// Stem of the `.h` and `.c` files
+    file: String,
+}
+
+impl CNames {
+    fn new(fsm: &FsmDefinition) -> Self {
+        let prefix = to_snake_case(&fsm.name);
+        Self { upper: prefix.to_uppercase(), prefix, file: fsm.file_stem() }
+    }
+
+    fn state_type(&self) -> String {
+        format!("{}_state_t", self.prefix)
+    }
+
+    fn event_type(&self) -> String {
+        format!("{}_event_t", self.prefix)
+    }
+
+    fn actions_type(&self) -> String {
+        format!("{}_actions_t", self.prefix)
+    }
+
+    /// Payload struct of an event (DSL name)
+    fn args_type(&self, event: &str) -> String {
+        format!("{}_{}_args_t", self.prefix, to_snake_case(event))
+    }
+
+    /// Union of every payload
+    fn event_args_type(&self) -> String {
+        format!("{}_event_args_t", self.prefix)
+    }
+
+    fn machine_type(&self) -> String {
+        format!("{}_t", self.prefix)
+    }
+
+    /// Enumerator of a state (DSL name)
+    fn state(&self, name: &str) -> String {
+        format!("{}_STATE_{}", self.upper, to_snake_case(name).to_uppercase())
+    }
+
+    /// Enumerator of an event (DSL name)
+    fn event(&self, name: &str) -> String {
+        format!("{}_EVENT_{}", self.upper, to_snake_case(name).to_uppercase())
+    }
+}
+
+// ============================================================================
+// HEADER
+// ============================================================================
+
+fn generate_header(ir: &FsmIr, names: &CNames, options: &CodegenOptions) -> String {
+    let mut code = String::new();
+    code.push_str(&format!("// Auto-generated FSM: {}\n", ir.name));
+    code.push_str("// Generated by Oxidate\n");
+    if let Some(ref desc) = ir.fsm.description {
+        code.push_str("//\n");
+        code.push_str(&doc_comment(desc, "").replace("///", "//"));
+    }
+    let guard = format!("{}_H", names.upper);
+    code.push_str(&format!("\n#ifndef {}\n#define {}\n\n", guard, guard));
+    code.push_str("#include <stdbool.h>\n");
+    if ir.has_payloads() {
+        code.push_str("#include <stddef.h>\n#include <stdint.h>\n");
+    } else if ir.rate_limited_arms() > 0 {
+        code.push_str("#include <stdint.h>\n");
+    }
+    code.push('\n');
+    code.push_str("#ifdef __cplusplus\nextern \"C\" {\n#endif\n\n");
+
+    code.push_str(&section_marker("States"));
+    code.push_str("typedef enum {\n");
+    for state in &ir.states {
+        if let Some(ref desc) = state.description {
+            code.push_str(&doc_comment(desc, "    "));
+        }
+        code.push_str(&format!("    {},\n", names.state(&state.name)));
+    }
+    code.push_str(&format!("    {}_STATE_COUNT\n", names.upper));
+    code.push_str(&format!("}} {};\n\n", names.state_type()));
+
+    code.push_str(&section_marker("Events"));
+    code.push_str("typedef enum {\n");
+    for event in &ir.events {
+        if let Some(ref desc) = event.description {
+            code.push_str(&doc_comment(desc, "    "));
+        }
+        code.push_str(&format!("    {},\n", names.event(&event.name)));
+    }
+    code.push_str(&format!("    {}_EVENT_COUNT\n", names.upper));
+    code.push_str(&format!("}} {};\n\n", names.event_type()));
+
+    if ir.has_payloads() {
+        code.push_str(&generate_payload_types(ir, names));
+    }
+    code.push_str(&section_marker("Actions table"));
+    code.push_str(&generate_actions_table(ir, names, options));
+    code.push('\n');
+
+    code.push_str(&section_marker("Machine"));
+    code.push_str("typedef struct {\n");
+    code.push_str(&format!("    {} state;\n", names.state_type()));
+    code.push_str(&format!("    const {} *actions;\n", names.actions_type()));
+    code.push_str("    void *user;\n");
+    if ir.rate_limited_arms() > 0 {
+        code.push_str("    /// now_ms() when each @debounce transition last saw its event, or each\n");
+        code.push_str("    /// @throttle transition last fired (if `transition_seen`)\n");
+        code.push_str(&format!("    uint32_t transition_times[{}];\n", ir.rate_limited_arms()));
+        code.push_str(&format!("    bool transition_seen[{}];\n", ir.rate_limited_arms()));
+    }
+    code.push_str(&format!("}} {};\n\n", names.machine_type()));
+
+    code.push_str("/// Put the machine in its initial state, then run the start actions and the\n");
+    code.push_str("/// initial state's entry actions; every call passes `user` back\n");
+    code.push_str(&format!("{};\n", init_signature(names)));
+    code.push_str("/// Process one event; returns whether a transition took it\n///\n");
+    code.push_str(&action_order_doc(options.action_order, ""));
+    code.push_str(&format!("{};\n", dispatch_signature(names)));
+    if ir.has_payloads() {
+        code.push_str("/// Process one event with its payload (`args` may be NULL for an event without one;\n");
+        code.push_str("/// transitions that pass a payload field on are skipped then)\n");
+        code.push_str(&format!("{};\n", dispatch_args_signature(names)));
+    }
+    code.push_str("/// Names as written in the DSL\n");
+    code.push_str(&format!("const char *{}_state_name({} state);\n", names.prefix, names.state_type()));
+    code.push_str(&format!("const char *{}_event_name({} event);\n", names.prefix, names.event_type()));
+
+    code.push_str("\n#ifdef __cplusplus\n}\n#endif\n\n");
+    code.push_str(&format!("#endif // {}\n", guard));
+    code
+}
+
+/// A struct per event with a payload, then the union of them all
+fn generate_payload_types(ir: &FsmIr, names: &CNames) -> String {
+    let mut code = String::new();
+    let payloads: Vec<_> = ir.events.iter().filter(|e| !e.params.is_empty()).collect();
+    for event in &payloads {
+        code.push_str(&format!("/// Payload of {}\n", event.name));
+        code.push_str("typedef struct {\n");
+        for param in &event.params {
+            code.push_str(&format!("    {} {};\n", c_type(&param.ty), param.name));
+        }
+        code.push_str(&format!("}} {};\n\n", names.args_type(&event.name)));
+    }
+    code.push_str("/// Payload of the event being dispatched\n");
+    code.push_str("typedef union {\n");
+    for event in &payloads {
+        code.push_str(&format!("    {} {};\n", names.args_type(&event.name), to_snake_case(&event.name)));
+    }
+    code.push_str(&format!("}} {};\n\n", names.event_args_type()));
+    code
+}
+
+/// C type of a payload field's Rust type; other types are used as written (e.g. a
+/// typedef the including code provides)
+fn c_type(ty: &str) -> &str {
+    match ty {
+        "u8" => "uint8_t",
+        "u16" => "uint16_t",
+        "u32" | "char" => "uint32_t",
+        "u64" => "uint64_t",
+        "i8" => "int8_t",
+        "i16" => "int16_t",
+        "i32" => "int32_t",
+        "i64" => "int64_t",
+        "usize" => "size_t",
+        "isize" => "ptrdiff_t",
+        "f32" => "float",
+        "f64" => "double",
+        "&str" => "const char *",
+        other => other,
+    }
+}
+
+/// The function-pointer table, ordered like the Rust actions trait
+fn generate_actions_table(ir: &FsmIr, names: &CNames, options: &CodegenOptions) -> String {
+    let methods = collect_trait_methods(ir.fsm, options);
+    let sorted = |role: MethodRole| {
+        let mut of_role: Vec<&TraitMethod> = methods.iter().filter(|m| m.role == role).collect();
+        of_role.sort_by(|a, b| a.name.cmp(&b.name));
+        of_role.dedup_by(|a, b| a.name == b.name);
+        of_role
+    };
+    let (actions, guards, getters) = (sorted(MethodRole::Action), sorted(MethodRole::Guard), sorted(MethodRole::Getter));
+
+    let mut code = String::new();
+    code.push_str("/// Actions and guards the machine calls, each with the `user` pointer given to\n");
+    code.push_str(&format!("/// {}_init()\n", names.prefix));
+    code.push_str("typedef struct {\n");
+    for action in &actions {
+        code.push_str(&method_docs(ir.fsm, &action.name));
+        code.push_str(&format!("    {};\n", c_declaration(ir.fsm, action)));
+    }
+    for guard in &guards {
+        code.push_str(&method_docs(ir.fsm, &guard.name));
+        code.push_str(&format!("    {};\n", c_declaration(ir.fsm, guard)));
+    }
+    for getter in &getters {
+        code.push_str(&method_docs(ir.fsm, &getter.name));
+        code.push_str(&format!("    {};\n", c_declaration(ir.fsm, getter)));
+    }
+    if options.tracing_hook {
+        code.push_str("    /// Called after every successful transition (may be NULL)\n");
+        code.push_str(&format!(
+            "    void (*on_transition)(void *user, {} from, {} event, {} to);\n",
+            names.state_type(),
+            names.event_type(),
+            names.state_type()
+        ));
+    }
+    // C has no empty structs
+    if actions.is_empty() && guards.is_empty() && getters.is_empty() && !options.tracing_hook {
+        code.push_str("    char unused;\n");
+    }
+    code.push_str(&format!("}} {};\n", names.actions_type()));
+    code
+}
+
+/// How the C target declares `method`: a field of the actions table, or for a named
+/// guard the `static` helper generated from its definition
+pub(super) fn c_declaration(fsm: &FsmDefinition, method: &TraitMethod) -> String {
+    let names = CNames::new(fsm);
+    let params: String = method.params.iter().map(|(name, ty)| format!(", {} {}", c_type(ty), name)).collect();
+    match method.role {
+        MethodRole::Action => format!("void (*{})(void *user{})", method.name, params),
+        MethodRole::Guard => format!("bool (*{})(void *user{})", method.name, params),
+        MethodRole::Getter => format!("{} (*{})(void *user)", c_type(method.returns.as_deref().unwrap_or("u32")), method.name),
+        MethodRole::NamedGuard => format!("static bool {}_guard_{}(const {} *fsm)", names.prefix, method.name, names.machine_type()),
+        MethodRole::Hook => format!(
+            "void (*{})(void *user, {} from, {} event, {} to)",
+            method.name,
+            names.state_type(),
+            names.event_type(),
+            names.state_type()
+        ),
+    }
+}
+
+fn init_signature(names: &CNames) -> String {
+    format!(
+        "void {}_init({} *fsm, const {} *actions, void *user)",
+        names.prefix,
+        names.machine_type(),
+        names.actions_type()
+    )
+}
+
+fn dispatch_signature(names: &CNames) -> String {
+    format!("bool {}_dispatch({} *fsm, {} event)", names.prefix, names.machine_type(), names.event_type())
+}
+
+fn dispatch_args_signature(names: &CNames) -> String {
+    format!(
+        "bool {}_dispatch_args({} *fsm, {} event, const {} *args)",
+        names.prefix,
+        names.machine_type(),
+        names.event_type(),
+        names.event_args_type()
+    )
+}
+
+// ============================================================================
+// SOURCE
+// ============================================================================
+
+fn generate_source(ir: &FsmIr, names: &CNames, options: &CodegenOptions) -> String {
+    let fsm = ir.fsm;
+    let completion = completion_arms(ir);
+    let mut code = String::new();
+    code.push_str(&format!("// Auto-generated FSM: {}\n", ir.name));
+    code.push_str("// Generated by Oxidate\n\n");
+    code.push_str(&format!("#include \"{}.h\"\n\n", names.file));
+    if options.tracing_hook {
+        code.push_str("#include <stddef.h>\n\n");
+    }
+    if !completion.is_empty() {
+        code.push_str(&format!("static void {}_run_completions({} *fsm);\n\n", names.prefix, names.machine_type()));
+    }
+    code.push_str(&generate_named_guards(ir, names));
+    if ir.rate_limited_arms() > 0 {
+        code.push_str(&generate_rate_limited(names));
+    }
+
+    // Init
+    code.push_str(&section_marker("Dispatch"));
+    let initial = ir.fsm.initial_state().or(ir.states.first().map(|s| s.name.as_str()));
+    code.push_str(&format!("{}\n{{\n", init_signature(names)));
+    match initial {
+        Some(initial) => code.push_str(&format!("    fsm->state = {};\n", names.state(initial))),
+        None => code.push_str(&format!("    fsm->state = {}_STATE_COUNT;\n", names.upper)),
+    }
+    code.push_str("    fsm->actions = actions;\n");
+    code.push_str("    fsm->user = user;\n");
+    if ir.rate_limited_arms() > 0 {
+        code.push_str(&format!("    for (unsigned slot = 0; slot < {}; slot++) {{\n", ir.rate_limited_arms()));
+        code.push_str("        fsm->transition_seen[slot] = false;\n");
+        code.push_str("    }\n");
+    }
+    let calls = |actions: &[Call], indent: &str| -> String {
+        actions.iter().map(|action| format!("{}{};\n", indent, c_call_on(action, "actions", "user", &|_| None))).collect()
+    };
+    if ir.initial_branches.is_empty() {
+        code.push_str(&calls(&ir.start_actions, "    "));
+    } else {
+        // The first guarded initial transition that holds picks the state
+        for (i, branch) in ir.initial_branches.iter().enumerate() {
+            let condition = branch.guard.render(&mut |term| match term {
+                GuardTerm::Call(call) => c_call_on(call, "actions", "user", &|_| None),
+                GuardTerm::Named(method) => format!("{}_guard_{}(fsm)", names.prefix, method),
+            });
+            let keyword = if i == 0 { "if" } else { "} else if" };
+            code.push_str(&format!("    {} ({}) {{\n", keyword, condition));
+            code.push_str(&format!("        fsm->state = {};\n", names.state(&branch.state_name)));
+            code.push_str(&calls(&branch.actions, "        "));
+        }
+        if ir.start_actions.is_empty() {
+            code.push_str("    }\n");
+        } else {
+            code.push_str("    } else {\n");
+            code.push_str(&calls(&ir.start_actions, "        "));
+            code.push_str("    }\n");
+        }
+    }
+    if ir.fsm.initial_candidates().iter().any(|t| completions::has_completions(fsm, &t.target)) {
+        code.push_str(&format!("    {}_run_completions(fsm);\n", names.prefix));
+    }
+    code.push_str("}\n\n");
+
+    code.push_str(&generate_dispatch(ir, names, options));
+    if !completion.is_empty() {
+        code.push('\n');
+        code.push_str(&generate_run_completions(ir, names, options, &completion));
+    }
+    code.push('\n');
+    code.push_str(&section_marker("Names"));
+    code.push_str(&generate_name_functions(ir, names));
+    code
+}
+
+/// A state's own transitions and internal transitions in a `switch` on the state,
+/// then the wildcard ones, in the order the Rust `process()` matches them. With
+/// payloads this is `dispatch_args()`, and `dispatch()` calls it without any.
+fn generate_dispatch(ir: &FsmIr, names: &CNames, options: &CodegenOptions) -> String {
+    let mut code = String::new();
+    if ir.has_payloads() {
+        code.push_str(&format!("{}\n{{\n", dispatch_signature(names)));
+        code.push_str(&format!("    return {}_dispatch_args(fsm, event, NULL);\n}}\n\n", names.prefix));
+        code.push_str(&format!("{}\n{{\n", dispatch_args_signature(names)));
+        if !ir.arms.iter().any(|a| !a.bindings.is_empty()) {
+            code.push_str("    (void)args;\n");
+        }
+    } else {
+        code.push_str(&format!("{}\n{{\n", dispatch_signature(names)));
+    }
+    if ir.arms.is_empty() {
+        code.push_str("    (void)fsm;\n    (void)event;\n    return false;\n}\n");
+        return code;
+    }
+
+    let own: Vec<&TransitionArm> = ir.arms.iter().filter(|a| !a.is_from_any_state()).collect();
+    if !own.is_empty() {
+        code.push_str("    switch (fsm->state) {\n");
+        for state in &ir.states {
+            let arms: Vec<&&TransitionArm> = own.iter().filter(|a| a.source == ArmSource::State(state.variant.clone())).collect();
+            if arms.is_empty() {
+                continue;
+            }
+            code.push_str(&format!("    case {}:\n", names.state(&state.name)));
+            for arm in arms {
+                code.push_str(&generate_arm(ir, names, options, arm, &names.state(&state.name), "        "));
+            }
+            code.push_str("        break;\n");
+        }
+        code.push_str("    default:\n        break;\n    }\n");
+    }
+    let wildcards: Vec<&TransitionArm> = ir.arms.iter().filter(|a| a.is_from_any_state()).collect();
+    if !wildcards.is_empty() && !ir.final_states.is_empty() {
+        let finals: Vec<String> = ir.final_states.iter().map(|s| format!("fsm->state == {}", names.state(s))).collect();
+        code.push_str(&format!("    if ({}) {{\n        return false;\n    }}\n", finals.join(" || ")));
+    }
+    for arm in wildcards {
+        code.push_str(&generate_arm(ir, names, options, arm, "from", "    "));
+    }
+    code.push_str("    return false;\n}\n");
+    code
+}
+
+/// `if (event matches && guard) { ... return true; }` for one arm; `from` is what
+/// the tracing hook gets as the source state
+fn generate_arm(ir: &FsmIr, names: &CNames, options: &CodegenOptions, arm: &TransitionArm, from: &str, indent: &str) -> String {
+    let event_ir = ir.events.iter().find(|e| e.variant == arm.event);
+    let event = event_ir.map_or(arm.event.clone(), |e| names.event(&e.name));
+    let member = event_ir.map_or(String::new(), |e| to_snake_case(&e.name));
+    let mut condition = format!("event == {}", event);
+    if arm.bindings.iter().any(|(_, binding)| arm.uses_binding(binding)) {
+        condition.push_str(" && args != NULL");
+    }
+    if let Some(ref guard) = arm.guard {
+        condition.push_str(&format!(" && {}", c_guard(guard, names, arm, &member)));
+    }
+    let comment = arm.trailing_comment.as_ref().map_or(String::new(), |c| format!(" // {}", c));
+    let body = format!("{}    ", indent);
+
+    let mut code = format!("{}if ({}) {{{}\n", indent, condition, comment);
+    if let Some((constraint, slot)) = arm.timing {
+        let debounce = constraint.kind == TimingKind::Debounce;
+        code.push_str(&format!(
+            "{}if ({}_rate_limited(fsm, {}, {}u, {})) {{ // {}\n",
+            body, names.prefix, slot, constraint.window_ms, debounce, constraint
+        ));
+        code.push_str(&format!("{}    return false;\n{}}}\n", body, body));
+    }
+    let call = |action: &Call| format!("{}{};\n", body, c_call(action, arm, &member));
+    let actions: String = arm.actions.iter().map(call).collect();
+    if arm.internal {
+        code.push_str(&actions);
+        code.push_str(&format!("{}return true;\n{}}}\n", body, indent));
+        return code;
+    }
+    if arm.is_from_any_state() && options.tracing_hook {
+        code.push_str(&format!("{}{} from = fsm->state;\n", body, names.state_type()));
+    }
+    let mut exit = match arm.is_from_any_state() {
+        true => generate_exit_switch(ir, names, &body),
+        false => String::new(),
+    };
+    exit.extend(arm.exit.iter().map(call));
+    code.push_str(&exit_and_actions(options.action_order, exit, actions));
+    code.push_str(&format!("{}fsm->state = {};\n", body, names.state(&arm.target_name)));
+    code.extend(arm.entry.iter().map(call));
+    if options.tracing_hook {
+        code.push_str(&format!("{}if (fsm->actions->on_transition != NULL) {{\n", body));
+        code.push_str(&format!(
+            "{}    fsm->actions->on_transition(fsm->user, {}, {}, {});\n",
+            body,
+            from,
+            event,
+            names.state(&arm.target_name)
+        ));
+        code.push_str(&format!("{}}}\n", body));
+    }
+    if arm.enters_target && completions::has_completions(ir.fsm, &arm.target_name) {
+        code.push_str(&format!("{}{}_run_completions(fsm);\n", body, names.prefix));
+    }
+    code.push_str(&format!("{}return true;\n{}}}\n", body, indent));
+    code
+}
+
+/// `prefix_rate_limited()`, the bookkeeping of the @debounce / @throttle arms
+fn generate_rate_limited(names: &CNames) -> String {
+    let mut code = String::new();
+    code.push_str("/// Whether the transition with time slot `slot` is held back now: within\n");
+    code.push_str("/// `window_ms` of the last event it saw (`debounce`, which restarts the window)\n");
+    code.push_str("/// or of the last time it fired. now_ms() may wrap around\n");
+    code.push_str(&format!(
+        "static bool {}_rate_limited({} *fsm, unsigned slot, uint32_t window_ms, bool debounce)\n{{\n",
+        names.prefix,
+        names.machine_type()
+    ));
+    code.push_str("    uint32_t now = fsm->actions->now_ms(fsm->user);\n");
+    code.push_str("    bool too_soon = fsm->transition_seen[slot] && (uint32_t)(now - fsm->transition_times[slot]) < window_ms;\n");
+    code.push_str("    if (debounce || !too_soon) {\n");
+    code.push_str("        fsm->transition_times[slot] = now;\n");
+    code.push_str("        fsm->transition_seen[slot] = true;\n");
+    code.push_str("    }\n");
+    code.push_str("    return too_soon;\n");
+    code.push_str("}\n\n");
+    code
+}
+
+/// `fsm->actions->method(fsm->user, ...)`, passing the payload fields of `member` (the
+/// event's member of the args union) its arguments are bound to
+fn c_call(call: &Call, arm: &TransitionArm, member: &str) -> String {
+    let field = |name: &str| {
+        let (field, _) = arm.bindings.iter().find(|(_, binding)| binding == name)?;
+        Some(format!("args->{}.{}", member, field))
+    };
+    c_call_on(call, "fsm->actions", "fsm->user", &field)
+}
+
+/// `{actions}->method({user}, ...)`: a binding passes what `field` makes of it,
+/// a constant is passed as written and a getter is called like the method
+fn c_call_on(call: &Call, actions: &str, user: &str, field: &dyn Fn(&str) -> Option<String>) -> String {
+    let args: String = call
+        .args
+        .iter()
+        .filter_map(|arg| match arg {
+            Arg::Binding(name) => field(name),
+            Arg::Literal(value) => Some(value.clone()),
+            Arg::Getter(getter) => Some(format!("{}->{}({})", actions, getter, user)),
+        })
+        .map(|arg| format!(", {}", arg))
+        .collect();
+    format!("{}->{}({}{})", actions, call.method, user, args)
+}
+
+/// `guard` as a C condition; named guards are the static helpers of the source file
+fn c_guard(guard: &GuardIr, names: &CNames, arm: &TransitionArm, member: &str) -> String {
+    guard.render(&mut |term| match term {
+        GuardTerm::Call(call) => c_call(call, arm, member),
+        GuardTerm::Named(method) => format!("{}_guard_{}(fsm)", names.prefix, method),
+    })
+}
+
+/// A static function per named guard, each after the ones it uses
+fn generate_named_guards(ir: &FsmIr, names: &CNames) -> String {
+    let mut code = String::new();
+    for named in &ir.guards {
+        code.push_str(&format!("/// `{}`\n", named.expression));
+        code.push_str(&format!("static bool {}_guard_{}(const {} *fsm)\n{{\n", names.prefix, named.method, names.machine_type()));
+        let condition = named.guard.render(&mut |term| match term {
+            GuardTerm::Call(call) => format!("fsm->actions->{}(fsm->user)", call.method),
+            GuardTerm::Named(method) => format!("{}_guard_{}(fsm)", names.prefix, method),
+        });
+        code.push_str(&format!("    return {};\n}}\n\n", condition));
+    }
+    code
+}
+
+/// `switch` running the exit actions of whichever state a wildcard transition leaves
+fn generate_exit_switch(ir: &FsmIr, names: &CNames, indent: &str) -> String {
+    let exiting: Vec<_> = ir.states.iter().filter(|s| !s.exit.is_empty()).collect();
+    if exiting.is_empty() {
+        return String::new();
+    }
+    let mut code = format!("{}switch (fsm->state) {{\n", indent);
+    for state in exiting {
+        code.push_str(&format!("{}case {}:\n", indent, names.state(&state.name)));
+        for action in &state.exit {
+            code.push_str(&format!("{}    {};\n", indent, c_call_on(action, "fsm->actions", "fsm->user", &|_| None)));
+        }
+        code.push_str(&format!("{}    break;\n", indent));
+    }
+    code.push_str(&format!("{}default:\n{}    break;\n{}}}\n", indent, indent, indent));
+    code
+}
+
+/// Completion (event-less) transitions as arms, in declaration order
+fn completion_arms(ir: &FsmIr) -> Vec<TransitionArm> {
+    let fsm = ir.fsm;
+    completions::completion_transitions(fsm)
+        .map(|t| {
+            let actions_of = |name: &str, entry: bool| -> Vec<Call> {
+                ir.state(name).map_or(Vec::new(), |s| if entry { s.entry.clone() } else { s.exit.clone() })
+            };
+            TransitionArm {
+                source: ArmSource::State(to_pascal_case(&t.source)),
+                event: String::new(),
+                bindings: Vec::new(),
+                guard: guard_ir(fsm, t),
+                trailing_comment: t.trailing_comment.clone(),
+                internal: false,
+                exit: if t.exits_source() { actions_of(&t.source, false) } else { Vec::new() },
+                actions: t.actions.iter().map(|a| Call::action(a, t)).collect(),
+                target: to_pascal_case(&t.target),
+                target_name: t.target.clone(),
+                entry: if t.enters_target() { actions_of(&t.target, true) } else { Vec::new() },
+                exits_source: t.exits_source(),
+                enters_target: t.enters_target(),
+                timing: None,
+            }
+        })
+        .collect()
+}
+
+/// Takes the completion transitions of the state just entered, one after the other
+fn generate_run_completions(ir: &FsmIr, names: &CNames, options: &CodegenOptions, arms: &[TransitionArm]) -> String {
+    let max_hops = options.max_completion_hops.unwrap_or(DEFAULT_MAX_COMPLETION_HOPS);
+    let mut code = String::new();
+    code.push_str(&format!("static void {}_run_completions({} *fsm)\n{{\n", names.prefix, names.machine_type()));
+    code.push_str(&format!("    for (int hop = 0; hop < {}; hop++) {{\n", max_hops));
+    code.push_str("        switch (fsm->state) {\n");
+    for state in &ir.states {
+        let state_arms: Vec<&TransitionArm> = arms.iter().filter(|a| a.source == ArmSource::State(state.variant.clone())).collect();
+        if state_arms.is_empty() {
+            continue;
+        }
+        code.push_str(&format!("        case {}:\n", names.state(&state.name)));
+        for arm in state_arms {
+            let (indent, body) = match arm.guard {
+                Some(ref guard) => {
+                    code.push_str(&format!("            if ({}) {{\n", c_guard(guard, names, arm, "")));
+                    ("            ", "                ")
+                }
+                None => ("", "            "),
+            };
+            let call = |action: &Call| format!("{}{};\n", body, c_call(action, arm, ""));
+            let exit = arm.exit.iter().map(call).collect();
+            let actions = arm.actions.iter().map(call).collect();
+            code.push_str(&exit_and_actions(options.action_order, exit, actions));
+            code.push_str(&format!("{}fsm->state = {};\n", body, names.state(&arm.target_name)));
+            code.extend(arm.entry.iter().map(call));
+            code.push_str(&format!("{}continue;\n", body));
+            if !indent.is_empty() {
+                code.push_str(&format!("{}}}\n", indent));
+            }
+        }
+        code.push_str("            break;\n");
+    }
+    code.push_str("        default:\n            break;\n        }\n");
+    code.push_str("        return;\n");
+    code.push_str("    }\n}\n");
+    code
+}
+
+fn generate_name_functions(ir: &FsmIr, names: &CNames) -> String {
+    let mut code = format!("const char *{}_state_name({} state)\n{{\n", names.prefix, names.state_type());
+    code.push_str("    switch (state) {\n");
+    for state in &ir.states {
+        code.push_str(&format!("    case {}:\n        return \"{}\";\n", names.state(&state.name), state.name));
+    }
+    code.push_str("    default:\n        return \"\";\n    }\n}\n\n");
+
+    code.push_str(&format!("const char *{}_event_name({} event)\n{{\n", names.prefix, names.event_type()));
+    code.push_str("    switch (event) {\n");
+    for event in &ir.events {
+        code.push_str(&format!("    case {}:\n        return \"{}\";\n", names.event(&event.name), event.name));
+    }
+    code.push_str("    default:\n        return \"\";\n    }\n}\n");
+    code
+}
@@ -0,0 +1,168 @@
+//! Compile Check
+//! Runs `cargo check` on generated code inside a scaffold crate kept in the temp
+//! directory, so problems show up before the code is pasted into a project
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use thiserror::Error;
+
+use super::CodegenTarget;
+
+#[derive(Error, Debug)]
+pub enum CheckError {
+    #[error("cargo was not found on PATH")]
+    CargoNotFound,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// cargo failed without compiler diagnostics (e.g. dependencies could not be fetched)
+    #[error("cargo check failed: {0}")]
+    Cargo(String),
+    /// The target does not generate Rust
+    #[error("{0:?} code cannot be checked with cargo")]
+    NotRust(CodegenTarget),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticLevel {
+    Error,
+    Warning,
+}
+
+/// A compiler error or warning, located in the generated text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileDiagnostic {
+    pub level: DiagnosticLevel,
+    pub message: String,
+    /// Lint or error code, e.g. `E0425` or `unused_variables`
+    pub code: Option<String>,
+    /// 1-based line in the generated code
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// Full compiler output for the diagnostic
+    pub rendered: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileReport {
+    pub success: bool,
+    pub diagnostics: Vec<CompileDiagnostic>,
+}
+
+impl CompileReport {
+    pub fn count(&self, level: DiagnosticLevel) -> usize {
+        self.diagnostics.iter().filter(|d| d.level == level).count()
+    }
+}
+
+fn cargo() -> String {
+    std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string())
+}
+
+/// Whether `cargo` can be run at all
+pub fn cargo_available() -> bool {
+    Command::new(cargo()).arg("--version").output().is_ok_and(|output| output.status.success())
+}
+
+/// Scaffold crate for `target`; kept between runs so dependencies are only fetched once
+pub fn scaffold_dir(target: CodegenTarget) -> PathBuf {
+    let name = match target {
+        CodegenTarget::Standard => "standard",
+        CodegenTarget::Embassy => "embassy",
+        CodegenTarget::Rtic => "rtic",
+        CodegenTarget::C => "c",
+    };
+    std::env::temp_dir().join("oxidate-check").join(name)
+}
+
+/// `Cargo.toml` of the scaffold crate, with the dependencies `target`'s code uses and
+/// the `fsm-metrics` feature on, so the check covers the code behind it
+pub fn scaffold_manifest(target: CodegenTarget) -> String {
+    let dependencies = match target {
+        CodegenTarget::Standard | CodegenTarget::C => "",
+        CodegenTarget::Embassy => "embassy-sync = \"0.6\"\nembassy-futures = \"0.1\"\nheapless = \"0.8\"\n",
+        CodegenTarget::Rtic => "heapless = \"0.8\"\n",
+    };
+    format!(
+        "[package]\nname = \"oxidate-check\"\nversion = \"0.0.0\"\nedition = \"2021\"\npublish = false\n\n\
+         [lib]\npath = \"src/lib.rs\"\n\n[features]\ndefault = [\"{feature}\"]\n{feature} = []\n\n[dependencies]\n{}",
+        dependencies,
+        feature = super::metrics::METRICS_FEATURE,
+    )
+}
+
+/// Write the scaffold with `code` as its `src/lib.rs` (verbatim, so line numbers match);
+/// the manifest is only rewritten when it changed to keep cargo's build cache valid
+pub fn write_scaffold(dir: &Path, target: CodegenTarget, code: &str) -> io::Result<()> {
+    fs::create_dir_all(dir.join("src"))?;
+    let manifest = scaffold_manifest(target);
+    let manifest_path = dir.join("Cargo.toml");
+    if fs::read_to_string(&manifest_path).ok().as_deref() != Some(manifest.as_str()) {
+        fs::write(&manifest_path, manifest)?;
+    }
+    fs::write(dir.join("src").join("lib.rs"), code)
+}
+
+/// `cargo check` the generated `code` for `target` (blocks until cargo finishes)
+pub fn check_generated_code(target: CodegenTarget, code: &str) -> Result<CompileReport, CheckError> {
+    if target == CodegenTarget::C {
+        return Err(CheckError::NotRust(target));
+    }
+    let dir = scaffold_dir(target);
+    write_scaffold(&dir, target, code)?;
+    let output = Command::new(cargo())
+        .args(["check", "--quiet", "--message-format=json"])
+        .current_dir(&dir)
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => CheckError::CargoNotFound,
+            _ => CheckError::Io(e),
+        })?;
+
+    let report = CompileReport {
+        success: output.status.success(),
+        diagnostics: parse_cargo_messages(&String::from_utf8_lossy(&output.stdout)),
+    };
+    if !report.success && report.diagnostics.is_empty() {
+        return Err(CheckError::Cargo(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+    Ok(report)
+}
+
+/// Errors and warnings from `cargo --message-format=json` output, skipping the
+/// "aborting due to" summaries and anything that is not valid JSON
+pub fn parse_cargo_messages(json_lines: &str) -> Vec<CompileDiagnostic> {
+    let mut diagnostics = Vec::new();
+    for line in json_lines.lines() {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        if value["reason"] != "compiler-message" {
+            continue;
+        }
+        let message = &value["message"];
+        let level = match message["level"].as_str() {
+            Some("error") => DiagnosticLevel::Error,
+            Some("warning") => DiagnosticLevel::Warning,
+            _ => continue,
+        };
+        let text = message["message"].as_str().unwrap_or_default().to_string();
+        let spans = message["spans"].as_array().map(Vec::as_slice).unwrap_or_default();
+        if spans.is_empty() && text.starts_with("aborting due to") {
+            continue;
+        }
+        let primary = spans.iter().find(|span| span["is_primary"] == true).or(spans.first());
+        let position = |key: &str| primary.and_then(|span| span[key].as_u64()).map(|n| n as usize);
+        diagnostics.push(CompileDiagnostic {
+            level,
+            message: text,
+            code: message["code"]["code"].as_str().map(str::to_string),
+            line: position("line_start"),
+            column: position("column_start"),
+            rendered: message["rendered"].as_str().unwrap_or_default().trim_end().to_string(),
+        });
+    }
+    diagnostics
+}
@@ -0,0 +1,94 @@
+//! Completion Transitions
+//! `run_completions()`: after a state is entered, the generated machine takes its
+//! event-less transitions (`Validating --> Submitting : [all_valid]`) one after the
+//! other, like `fsm::interpreter::completions`, up to a hop limit
+
+use super::ir::{guard_ir, leaves_final_state, Call};
+use super::standard::{rust_guard, rust_statement, stop_timers, stops_timer};
+use super::{exit_and_actions, metrics, CodegenOptions};
+use crate::fsm::interpreter::DEFAULT_MAX_COMPLETION_HOPS;
+use crate::fsm::naming::to_pascal_case;
+use crate::fsm::{FsmDefinition, Transition};
+
+/// Completion transitions of the FSM, in declaration order (none out of a final state)
+pub(super) fn completion_transitions(fsm: &FsmDefinition) -> impl Iterator<Item = &Transition> {
+    fsm.transitions.iter().filter(move |t| t.is_completion() && !leaves_final_state(fsm, t))
+}
+
+/// Whether entering `state` (a DSL state name) can take a completion transition
+pub(super) fn has_completions(fsm: &FsmDefinition, state: &str) -> bool {
+    completion_transitions(fsm).any(|t| t.source == state)
+}
+
+/// `self.run_completions();` at `indent` after an entry into `state`, if it has
+/// completion transitions
+pub(super) fn call_after_entry(fsm: &FsmDefinition, state: &str, indent: &str) -> String {
+    match has_completions(fsm, state) {
+        true => format!("{}self.run_completions();\n", indent),
+        false => String::new(),
+    }
+}
+
+/// The private `run_completions()` method; empty without completion transitions
+pub(super) fn generate_run_completions(fsm: &FsmDefinition, options: &CodegenOptions) -> String {
+    let transitions: Vec<&Transition> = completion_transitions(fsm).collect();
+    if transitions.is_empty() {
+        return String::new();
+    }
+    let max_hops = options.max_completion_hops.unwrap_or(DEFAULT_MAX_COMPLETION_HOPS);
+
+    let mut code = String::new();
+    code.push_str("    /// Take the completion (event-less) transitions of the state just entered, one\n");
+    code.push_str(&format!("    /// after the other, at most {} in a row\n", max_hops));
+    code.push_str("    fn run_completions(&mut self) {\n");
+    code.push_str(&format!("        const MAX_COMPLETION_HOPS: usize = {};\n", max_hops));
+    code.push_str("        for _ in 0..MAX_COMPLETION_HOPS {\n");
+    code.push_str("            match self.state {\n");
+    for transition in &transitions {
+        let source = format!("{}State::{}", fsm.name, to_pascal_case(&transition.source));
+        match guard_ir(fsm, transition) {
+            Some(guard) => code.push_str(&format!(
+                "                {} if {} => {{\n",
+                source,
+                rust_guard(&guard, "self.context")
+            )),
+            None => code.push_str(&format!("                {} => {{\n", source)),
+        }
+
+        let calls = |calls: &mut dyn Iterator<Item = Call>| {
+            calls
+                .map(|call| format!("                    {};\n", rust_statement(fsm, &call, "self.context")))
+                .collect::<String>()
+        };
+        if transition.exits_source() {
+            code.push_str(&stop_timers(fsm, "                    "));
+        }
+        let exit = match fsm.states.iter().find(|s| s.name == transition.source && transition.exits_source()) {
+            Some(state) => calls(&mut state.exit_actions.iter().map(Call::state_action).filter(|c| !stops_timer(fsm, c))),
+            None => String::new(),
+        };
+        let actions = calls(&mut transition.actions.iter().map(|a| Call::action(a, transition)));
+        code.push_str(&exit_and_actions(options.action_order, exit, actions));
+        code.push_str(&format!(
+            "                    self.state = {}State::{};\n",
+            fsm.name,
+            to_pascal_case(&transition.target)
+        ));
+        if let Some(state) = fsm.states.iter().find(|s| s.name == transition.target && transition.enters_target()) {
+            code.push_str(&calls(&mut state.entry_actions.iter().map(Call::state_action)));
+        }
+        if options.metrics && transition.enters_target() {
+            code.push_str(&metrics::count_entry(fsm, &transition.target, "                    "));
+        }
+        code.push_str("                }\n");
+    }
+    // Every state leaving unconditionally would make the catch-all unreachable
+    let always_leaves = |state: &str| transitions.iter().any(|t| t.source == state && t.guard.as_ref().map_or(true, |g| g.is_else()));
+    if !fsm.states.iter().all(|s| always_leaves(&s.name)) {
+        code.push_str("                _ => return,\n");
+    }
+    code.push_str("            }\n");
+    code.push_str("        }\n");
+    code.push_str("    }\n");
+    code
+}
@@ -0,0 +1,164 @@
+//! Graphviz DOT Export
+//! Renders an FSM as a `digraph` for documentation pipelines and external tooling
+
+use super::edges::{edges, metadata_lines, state_actions, Endpoint};
+use crate::fsm::color::{is_light, to_hex};
+use crate::fsm::{FsmDefinition, LayoutHint, State, StateType};
+
+/// Node id of the initial pseudo-state
+const INITIAL_NODE: &str = "[*]";
+
+/// Node id of the source of wildcard (`* -->`) transitions
+const ANY_STATE_NODE: &str = "*";
+
+/// Generate a DOT digraph, laid out in the FSM's `direction` hint (top to bottom by default)
+pub fn generate_dot(fsm: &FsmDefinition) -> String {
+    generate_dot_with_direction(fsm, fsm.layout_hint.unwrap_or(LayoutHint::TB))
+}
+
+/// Generate a DOT digraph with an explicit `rankdir` (e.g. the GUI's layout direction)
+pub fn generate_dot_with_direction(fsm: &FsmDefinition, direction: LayoutHint) -> String {
+    let mut dot = String::new();
+
+    for line in metadata_lines(fsm) {
+        dot.push_str(format!("// {}", line).trim_end());
+        dot.push('\n');
+    }
+    dot.push_str(&format!("digraph {} {{\n", quote(&fsm.name)));
+    dot.push_str(&format!("    rankdir={};\n", rankdir(direction)));
+    dot.push_str("    node [shape=Mrecord];\n");
+    dot.push('\n');
+
+    if !fsm.initial_transitions.is_empty() {
+        dot.push_str(&format!(
+            "    {} [shape=point, style=filled, fillcolor=black, width=0.2];\n",
+            quote(INITIAL_NODE)
+        ));
+    }
+    if fsm.transitions.iter().any(|t| t.is_from_any_state()) {
+        dot.push_str(&format!(
+            "    {} [shape=circle, style=dashed, label=\"any\", fontsize=10, width=0.4, fixedsize=true];\n",
+            quote(ANY_STATE_NODE)
+        ));
+    }
+    for state in &fsm.states {
+        dot.push_str(&format!("    {} [{}];\n", quote(&state.name), state_attributes(state, direction)));
+    }
+    for choice in &fsm.choice_points {
+        dot.push_str(&format!(
+            "    {} [shape=diamond, label={}];\n",
+            quote(&choice_node(&choice.name)),
+            quote(&choice.name)
+        ));
+    }
+    dot.push('\n');
+
+    for edge in edges(fsm) {
+        dot.push_str(&edge_line(&node_id(edge.source), &node_id(edge.target), &edge.label, edge.dashed));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn rankdir(direction: LayoutHint) -> &'static str {
+    match direction {
+        LayoutHint::TB => "TB",
+        LayoutHint::BT => "BT",
+        LayoutHint::LR => "LR",
+        LayoutHint::RL => "RL",
+    }
+}
+
+/// Transitions into a choice point target `<<Name>>`, which is also its node id
+fn choice_node(name: &str) -> String {
+    format!("<<{}>>", name)
+}
+
+fn node_id(endpoint: Endpoint) -> String {
+    match endpoint {
+        Endpoint::Pseudo => INITIAL_NODE.to_string(),
+        Endpoint::AnyState => ANY_STATE_NODE.to_string(),
+        Endpoint::State(name) => name.to_string(),
+        Endpoint::Choice(name) => choice_node(name),
+    }
+}
+
+fn state_attributes(state: &State, direction: LayoutHint) -> String {
+    let actions = state_actions(state);
+
+    // Record fields: the name on top, one left-aligned line per action below
+    let mut label = escape_record(&state.name);
+    if !actions.is_empty() {
+        label.push('|');
+        for action in &actions {
+            label.push_str(&escape_record(action));
+            label.push_str("\\l");
+        }
+    }
+    // Records stack their fields across the rank direction; braces flip that for TB/BT
+    let mut attributes = match direction {
+        LayoutHint::TB | LayoutHint::BT => format!("label=\"{{{}}}\"", label),
+        LayoutHint::LR | LayoutHint::RL => format!("label=\"{}\"", label),
+    };
+    if state.state_type == StateType::Final {
+        attributes.push_str(", peripheries=2");
+    }
+    if let Some(rgb) = state.rgb() {
+        attributes.push_str(&format!(", style=filled, fillcolor=\"{}\"", to_hex(rgb)));
+        if !is_light(rgb) {
+            attributes.push_str(", fontcolor=white");
+        }
+    }
+    // Tags become SVG classes for styling the rendered output
+    if !state.tags.is_empty() {
+        attributes.push_str(&format!(", class={}", quote(&state.tags.join(" "))));
+    }
+    attributes
+}
+
+fn edge_line(source: &str, target: &str, label: &str, dashed: bool) -> String {
+    let mut attributes = Vec::new();
+    if !label.is_empty() {
+        attributes.push(format!("label={}", quote(label)));
+    }
+    if dashed {
+        attributes.push("style=dashed".to_string());
+    }
+    match attributes.is_empty() {
+        true => format!("    {} -> {};\n", quote(source), quote(target)),
+        false => format!("    {} -> {} [{}];\n", quote(source), quote(target), attributes.join(", ")),
+    }
+}
+
+/// Quoted DOT id or plain label
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\n' => quoted.push_str("\\n"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Record labels additionally treat `{ } | < >` as field syntax
+fn escape_record(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '{' | '}' | '|' | '<' | '>' | '"' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,106 @@
+//! Diagram Edges
+//! The nodes and edges the text diagram exporters (DOT, PlantUML) draw, in
+//! declaration order, so both agree on what an FSM looks like
+
+use crate::fsm::{Action, FsmDefinition, State, ANY_STATE};
+
+/// End of an edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Endpoint<'a> {
+    /// The initial or final pseudo-state `[*]`
+    Pseudo,
+    /// The source `*` of a wildcard transition, taken from any state
+    AnyState,
+    State(&'a str),
+    /// A choice point, referred to as `<<Name>>` in the DSL
+    Choice(&'a str),
+}
+
+impl<'a> Endpoint<'a> {
+    /// Endpoint of a transition's `source` or `target`
+    pub(super) fn from_reference(name: &'a str) -> Self {
+        if name == "[*]" {
+            return Endpoint::Pseudo;
+        }
+        if name == ANY_STATE {
+            return Endpoint::AnyState;
+        }
+        match name.strip_prefix("<<").and_then(|n| n.strip_suffix(">>")) {
+            Some(choice) => Endpoint::Choice(choice),
+            None => Endpoint::State(name),
+        }
+    }
+}
+
+/// A labelled arrow between two endpoints
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Edge<'a> {
+    pub source: Endpoint<'a>,
+    pub target: Endpoint<'a>,
+    /// `event [guard] / actions`, empty for an unlabelled arrow
+    pub label: String,
+    /// Written `-.->`
+    pub dashed: bool,
+}
+
+/// The initial arrows (guarded ones first), then every transition, then every
+/// choice branch
+pub(super) fn edges(fsm: &FsmDefinition) -> Vec<Edge<'_>> {
+    let mut edges = Vec::new();
+    for initial in fsm.initial_candidates() {
+        let guard = initial.guard.as_ref().map(|g| format!("[{}] ", g.expression)).unwrap_or_default();
+        edges.push(Edge {
+            source: Endpoint::Pseudo,
+            target: Endpoint::State(&initial.target),
+            label: format!("{}{}", guard, action_label(&initial.actions)).trim_end().to_string(),
+            dashed: false,
+        });
+    }
+    for transition in &fsm.transitions {
+        edges.push(Edge {
+            source: Endpoint::from_reference(&transition.source),
+            target: Endpoint::from_reference(&transition.target),
+            label: transition.label(),
+            dashed: transition.is_dashed(),
+        });
+    }
+    for choice in &fsm.choice_points {
+        for branch in &choice.branches {
+            let actions = action_label(&branch.actions);
+            let label = format!("[{}] {}", branch.guard.expression, actions).trim_end().to_string();
+            edges.push(Edge {
+                source: Endpoint::Choice(&choice.name),
+                target: Endpoint::from_reference(&branch.target),
+                label,
+                dashed: false,
+            });
+        }
+    }
+    edges
+}
+
+/// `/ a; b`, or nothing without actions
+fn action_label(actions: &[Action]) -> String {
+    if actions.is_empty() {
+        return String::new();
+    }
+    let names: Vec<&str> = actions.iter().map(|a| a.name.as_str()).collect();
+    format!("/ {}", names.join("; "))
+}
+
+/// The FSM's description and `meta` entries (`key: value`) as comment lines
+pub(super) fn metadata_lines(fsm: &FsmDefinition) -> Vec<String> {
+    let description = fsm.description.iter().flat_map(|d| d.lines()).map(str::to_string);
+    let metadata = fsm.metadata.iter().map(|(key, value)| format!("{}: {}", key, value.replace('\n', " ")));
+    description.chain(metadata).collect()
+}
+
+/// `entry / name` and `exit / name` lines of a state
+pub(super) fn state_actions(state: &State) -> Vec<String> {
+    state
+        .entry_actions
+        .iter()
+        .map(|a| format!("entry / {}", a.name))
+        .chain(state.exit_actions.iter().map(|a| format!("exit / {}", a.name)))
+        .collect()
+}
@@ -0,0 +1,70 @@
+//! Code Highlighting
+//! A lightweight lexer for the code the backends generate (Rust and C): enough to
+//! color comments, strings, numbers and keywords in the GUI and the HTML report,
+//! without knowing either grammar.
+
+use crate::fsm::naming;
+
+/// What a piece of code is colored as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenClass {
+    Plain,
+    Keyword,
+    String,
+    Number,
+    Comment,
+}
+
+/// Keywords of C that Rust does not have (Rust's are `naming::RUST_KEYWORDS`)
+const C_KEYWORDS: &[&str] = &["bool", "case", "char", "default", "include", "int", "sizeof", "switch", "typedef", "union", "void"];
+
+/// `code` cut into classified pieces that join back into it. Neighbouring plain
+/// characters come as one piece.
+pub fn tokens(code: &str) -> Vec<(TokenClass, &str)> {
+    let mut tokens: Vec<(TokenClass, &str)> = Vec::new();
+    let mut rest = code;
+    let mut plain_start: Option<usize> = None;
+    while let Some(c) = rest.chars().next() {
+        let offset = code.len() - rest.len();
+        let (class, end) = if rest.starts_with("//") {
+            (TokenClass::Comment, rest.find('\n').unwrap_or(rest.len()))
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            (TokenClass::Comment, comment.find("*/").map_or(rest.len(), |i| i + 4))
+        } else if c == '"' {
+            let mut escaped = false;
+            let close = rest[1..].find(|c| {
+                let quote = c == '"' && !escaped;
+                escaped = c == '\\' && !escaped;
+                quote
+            });
+            (TokenClass::String, close.map_or(rest.len(), |i| i + 2))
+        } else if c.is_ascii_digit() {
+            (TokenClass::Number, rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len()))
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest.find(|c: char| !c.is_alphanumeric() && c != '_').unwrap_or(rest.len());
+            let word = &rest[..end];
+            match naming::is_rust_keyword(word) || C_KEYWORDS.contains(&word) {
+                true => (TokenClass::Keyword, end),
+                false => (TokenClass::Plain, end),
+            }
+        } else {
+            (TokenClass::Plain, c.len_utf8())
+        };
+        match class {
+            TokenClass::Plain => {
+                plain_start.get_or_insert(offset);
+            }
+            _ => {
+                if let Some(start) = plain_start.take() {
+                    tokens.push((TokenClass::Plain, &code[start..offset]));
+                }
+                tokens.push((class, &rest[..end]));
+            }
+        }
+        rest = &rest[end..];
+    }
+    if let Some(start) = plain_start {
+        tokens.push((TokenClass::Plain, &code[start..]));
+    }
+    tokens
+}
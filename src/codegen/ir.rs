@@ -0,0 +1,596 @@
+//! Codegen IR
+//! What a backend needs to emit an FSM, worked out once from the definition: the
+//! identifiers states, events and actions become, the `process()` arms in match
+//! order with their guards, action sequences and payload bindings, and the timers. Backends format
+//! this instead of re-deriving it from the DSL structures.
+
+use super::{c, collect_trait_methods, rust_trait_methods, transition_usage, CodegenOptions, CodegenTarget, MethodRole, TraitMethod};
+use crate::fsm::guards::GuardExpr;
+use crate::fsm::naming::{to_pascal_case, to_snake_case};
+use crate::fsm::{
+    Action, Event, EventOrder, EventParam, FsmDefinition, Guard, State, TimerMode, TimerOp, TimingConstraint, Transition,
+    TransitionKind,
+};
+
+/// An FSM as backends see it (build it from a definition whose names passed
+/// `naming::check_names`, e.g. with `codegen::generate_with_backend`)
+#[derive(Debug, Clone)]
+pub struct FsmIr<'a> {
+    /// The definition the IR was built from, for what the IR does not cover
+    pub fsm: &'a FsmDefinition,
+    /// Prefix of the generated `{name}State`, `{name}Event` and `{name}Actions`
+    pub name: String,
+    /// States in declaration order
+    pub states: Vec<StateIr>,
+    /// Variant of the initial state
+    pub initial_state: Option<String>,
+    /// Methods the machine calls when it starts: the initial transition's actions,
+    /// then the initial state's entry actions
+    pub start_actions: Vec<Call>,
+    /// Guarded initial transitions, in the order a start tries them before falling
+    /// back to `initial_state` and `start_actions`
+    pub initial_branches: Vec<InitialBranch>,
+    /// Declared events first, then the inferred ones (see `FsmDefinition::events_in`),
+    /// then those only timers fire
+    pub events: Vec<EventIr>,
+    /// Arms of the event dispatch in match order: a state's own transitions, then its
+    /// internal transitions, then the wildcard (`* -->`) ones; arms that can never
+    /// match, and transitions out of final states, are left out
+    pub arms: Vec<TransitionArm>,
+    /// Variants of the final states, which the wildcard arms must not leave
+    pub final_states: Vec<String>,
+    /// Timers in definition order
+    pub timers: Vec<TimerBinding>,
+    /// Named guards, each after the ones its definition uses
+    pub guards: Vec<NamedGuardIr>,
+}
+
+/// A guarded `[*] --> State` line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitialBranch {
+    pub guard: GuardIr,
+    /// Variant of the state it starts in
+    pub state: String,
+    /// Name of that state in the definition
+    pub state_name: String,
+    /// Its actions, then the state's entry actions
+    pub actions: Vec<Call>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateIr {
+    /// Name in the DSL
+    pub name: String,
+    /// Enum variant
+    pub variant: String,
+    pub description: Option<String>,
+    pub trailing_comment: Option<String>,
+    /// Action calls, in order
+    pub entry: Vec<Call>,
+    pub exit: Vec<Call>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventIr {
+    /// Name in the DSL
+    pub name: String,
+    /// Enum variant
+    pub variant: String,
+    pub description: Option<String>,
+    /// Payload fields, in declaration order (empty for a plain event)
+    pub params: Vec<EventParam>,
+    /// Discriminant given in the `events` block
+    pub id: Option<u32>,
+}
+
+/// The states an arm matches
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArmSource {
+    /// A state's variant
+    State(String),
+    /// Any state (wildcard transition): the exit actions depend on the current state
+    Any,
+}
+
+/// A call of an actions trait method
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call {
+    pub method: String,
+    pub args: Vec<Arg>,
+    /// What a `start_timer(t)` / `stop_timer(t)` action does, and to which timer
+    pub timer: Option<(TimerOp, String)>,
+}
+
+/// What a call passes for a parameter written in the DSL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Arg {
+    /// A payload field of the arm, by the name the trigger binds it to
+    Binding(String),
+    /// A constant (`5000`, `-1`, `"door"`, `true`), passed as written
+    Literal(String),
+    /// Any other identifier: the value of the actions trait's getter of that name
+    Getter(String),
+}
+
+/// A term of a guard expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuardTerm {
+    /// A guard method of the actions trait
+    Call(Call),
+    /// The method of a named guard
+    Named(String),
+}
+
+/// A guard's terms combined as written (`!`, `&&`, `||`)
+pub type GuardIr = GuardExpr<GuardTerm>;
+
+/// A named guard (`guard lockout = attempts > 3 && !maintenance_mode`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedGuardIr {
+    pub method: String,
+    /// Definition in the DSL
+    pub expression: String,
+    pub guard: GuardIr,
+}
+
+/// One arm of the event dispatch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransitionArm {
+    pub source: ArmSource,
+    /// Event variant
+    pub event: String,
+    /// Payload fields the trigger binds, each with the name it binds it to
+    pub bindings: Vec<(String, String)>,
+    /// Guard; `[else]` arms have none (they follow their guarded siblings)
+    pub guard: Option<GuardIr>,
+    pub trailing_comment: Option<String>,
+    /// Runs its actions without leaving the state
+    pub internal: bool,
+    /// Exit actions of the source state (none for a wildcard, a local or an internal arm)
+    pub exit: Vec<Call>,
+    pub actions: Vec<Call>,
+    /// Target variant
+    pub target: String,
+    /// Target name in the DSL
+    pub target_name: String,
+    /// Entry actions of the target state (none unless it is entered)
+    pub entry: Vec<Call>,
+    pub exits_source: bool,
+    pub enters_target: bool,
+    /// `@debounce` / `@throttle`, with the arm's slot among the rate-limited arms (the
+    /// index of its time in the machine)
+    pub timing: Option<(TimingConstraint, usize)>,
+}
+
+impl TransitionArm {
+    pub fn is_from_any_state(&self) -> bool {
+        self.source == ArmSource::Any
+    }
+
+    /// Whether the guard or an action passes the binding `name`
+    pub fn uses_binding(&self, name: &str) -> bool {
+        let guard_calls = self.guard.iter().flat_map(|g| g.terms()).filter_map(|term| match term {
+            GuardTerm::Call(call) => Some(call),
+            GuardTerm::Named(_) => None,
+        });
+        guard_calls.chain(&self.actions).any(|call| call.args.contains(&Arg::Binding(name.to_string())))
+    }
+}
+
+impl Call {
+    pub fn new(method: impl Into<String>) -> Self {
+        Self { method: method.into(), args: Vec::new(), timer: None }
+    }
+
+    /// `transition`'s guard, passing the bindings its arguments name
+    pub fn guard(guard: &Guard, transition: &Transition) -> Self {
+        let bound = bindings_of(transition);
+        Self {
+            method: to_snake_case(&guard.method()),
+            args: guard.inputs().into_iter().filter(|a| bound.contains(a)).map(|a| Arg::Binding(a.to_string())).collect(),
+            timer: None,
+        }
+    }
+
+    /// An action of `transition`, passing its parameters (see [`action_args`])
+    pub fn action(action: &Action, transition: &Transition) -> Self {
+        Self { args: action_args(action, &bindings_of(transition)), ..Self::state_action(action) }
+    }
+
+    /// An entry, exit or start action, which has no bindings to pass
+    pub fn state_action(action: &Action) -> Self {
+        Self {
+            method: to_snake_case(&action.name),
+            args: action_args(action, &[]),
+            timer: action.timer_op().map(|(op, timer)| (op, timer.to_string())),
+        }
+    }
+}
+
+/// What a call of `action` passes, where `bound` are the names the trigger binds:
+/// bindings, constants and getters in the order written. The parameters of
+/// `send(Machine, event)` and of timer actions name things rather than values, so
+/// those pass nothing.
+pub fn action_args(action: &Action, bound: &[&str]) -> Vec<Arg> {
+    if action.is_builtin() {
+        return Vec::new();
+    }
+    action
+        .params
+        .iter()
+        .map(|param| match param.as_str() {
+            p if bound.contains(&p) => Arg::Binding(p.to_string()),
+            p if Action::literal_type(p).is_some() => Arg::Literal(p.to_string()),
+            p => Arg::Getter(to_snake_case(p)),
+        })
+        .collect()
+}
+
+fn bindings_of(transition: &Transition) -> Vec<&str> {
+    transition.event.iter().flat_map(|e| &e.bindings).map(String::as_str).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimerBinding {
+    /// Name in the DSL (what `start_timer(name)` refers to)
+    pub name: String,
+    pub duration_ms: u32,
+    /// Variant of the event the timer fires
+    pub event: String,
+    pub periodic: bool,
+    /// Variant of the state whose entry starts the timer
+    pub auto_start_state: Option<String>,
+}
+
+impl<'a> FsmIr<'a> {
+    pub fn new(fsm: &'a FsmDefinition) -> Self {
+        Self::with_event_order(fsm, EventOrder::default())
+    }
+
+    /// The IR with the undeclared events in `order`
+    pub fn with_event_order(fsm: &'a FsmDefinition, order: EventOrder) -> Self {
+        let states: Vec<StateIr> = fsm.states.iter().map(state_ir).collect();
+        let start = |actions: &[Action], target: &str| -> Vec<Call> {
+            let entry = states.iter().filter(|s| s.name == target).flat_map(|s| s.entry.iter().cloned());
+            actions.iter().map(Call::state_action).chain(entry).collect()
+        };
+        let start_actions = fsm.initial_state().map_or_else(Vec::new, |initial| start(fsm.initial_actions(), initial));
+        let initial_branches = fsm
+            .initial_candidates()
+            .into_iter()
+            .filter_map(|initial| {
+                Some(InitialBranch {
+                    guard: initial_guard_ir(fsm, initial.guard.as_ref()?),
+                    state: to_pascal_case(&initial.target),
+                    state_name: initial.target.clone(),
+                    actions: start(&initial.actions, &initial.target),
+                })
+            })
+            .collect();
+        let mut events = fsm.events_in(order);
+        for timer in &fsm.timers {
+            if !events.iter().any(|e| e.name == timer.event.name) {
+                events.push(Event::new(timer.event.name.clone()));
+            }
+        }
+        let events = events
+            .into_iter()
+            .map(|e| EventIr {
+                variant: to_pascal_case(&e.name),
+                name: e.name,
+                description: e.description,
+                params: e.params,
+                id: e.id,
+            })
+            .collect();
+        let timers = fsm
+            .timers
+            .iter()
+            .map(|t| TimerBinding {
+                name: t.name.clone(),
+                duration_ms: t.duration_ms,
+                event: to_pascal_case(&t.event.name),
+                periodic: t.mode == TimerMode::Periodic,
+                auto_start_state: t.auto_start_state.as_deref().map(to_pascal_case),
+            })
+            .collect();
+        Self {
+            fsm,
+            name: fsm.name.clone(),
+            states,
+            initial_state: fsm.initial_state().map(to_pascal_case),
+            start_actions,
+            initial_branches,
+            events,
+            arms: transition_arms(fsm),
+            final_states: fsm.states.iter().filter(|s| s.is_final()).map(|s| to_pascal_case(&s.name)).collect(),
+            timers,
+            guards: named_guards(fsm),
+        }
+    }
+
+    pub fn state(&self, name: &str) -> Option<&StateIr> {
+        self.states.iter().find(|s| s.name == name)
+    }
+
+    /// Whether some event has an explicit discriminant
+    pub fn has_event_ids(&self) -> bool {
+        self.events.iter().any(|e| e.id.is_some())
+    }
+
+    /// Discriminant of each event, as Rust numbers enum variants: its id, else one
+    /// more than the event before it (0 for the first)
+    pub fn event_discriminants(&self) -> Vec<u64> {
+        let mut next = 0;
+        self.events
+            .iter()
+            .map(|e| {
+                let id = e.id.map_or(next, u64::from);
+                next = id + 1;
+                id
+            })
+            .collect()
+    }
+
+    /// How many arms have a timing constraint, each with its own slot
+    pub fn rate_limited_arms(&self) -> usize {
+        self.arms.iter().filter(|a| a.timing.is_some()).count()
+    }
+
+    /// Whether some event carries a payload
+    pub fn has_payloads(&self) -> bool {
+        self.events.iter().any(|e| !e.params.is_empty())
+    }
+
+    /// Whether every payload field is known to be `Eq` and `Hash` (integers, `bool`, `char`)
+    pub fn payloads_are_eq(&self) -> bool {
+        const EQ_TYPES: [&str; 14] =
+            ["u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize", "bool", "char"];
+        self.events.iter().flat_map(|e| &e.params).all(|p| EQ_TYPES.contains(&p.ty.as_str()))
+    }
+}
+
+fn state_ir(state: &State) -> StateIr {
+    let methods = |actions: &[Action]| actions.iter().map(Call::state_action).collect();
+    StateIr {
+        name: state.name.clone(),
+        variant: to_pascal_case(&state.name),
+        description: state.description.clone(),
+        trailing_comment: state.trailing_comment.clone(),
+        entry: methods(&state.entry_actions),
+        exit: methods(&state.exit_actions),
+    }
+}
+
+/// The dispatch arms, in the order `interpreter::firing` tries transitions
+fn transition_arms(fsm: &FsmDefinition) -> Vec<TransitionArm> {
+    let (wildcards, specific): (Vec<&Transition>, Vec<&Transition>) =
+        fsm.transitions.iter().partition(|t| t.is_from_any_state());
+    let internal: Vec<&Transition> = fsm.states.iter().flat_map(|s| &s.internal_transitions).collect();
+    let mut arms = Vec::new();
+    for transition in [specific, internal, wildcards].into_iter().flat_map(else_last) {
+        // Initial transitions are not dispatched, and nothing leaves a final state
+        if transition.source == "[*]" || leaves_final_state(fsm, transition) {
+            continue;
+        }
+        let Some(ref event) = transition.event else {
+            continue;
+        };
+        let any_state = transition.is_from_any_state();
+        let internal = transition.kind == TransitionKind::Internal;
+        // Unreachable arms (and rustc warnings in the user's crate)
+        if (any_state && wildcard_shadowed(fsm, transition)) || (internal && internal_shadowed(fsm, transition)) {
+            continue;
+        }
+
+        let actions_of = |name: &str, entry: bool| -> Vec<Call> {
+            let state = fsm.states.iter().find(|s| s.name == name);
+            let actions = state.map_or(&[][..], |s| if entry { &s.entry_actions[..] } else { &s.exit_actions[..] });
+            actions.iter().map(Call::state_action).collect()
+        };
+        let exits = !internal && !any_state && transition.exits_source();
+        let enters = !internal && transition.enters_target();
+        let slot = arms.iter().filter(|a: &&TransitionArm| a.timing.is_some()).count();
+        arms.push(TransitionArm {
+            source: match any_state {
+                true => ArmSource::Any,
+                false => ArmSource::State(to_pascal_case(&transition.source)),
+            },
+            event: to_pascal_case(&event.name),
+            bindings: fsm
+                .event_params(&event.name)
+                .iter()
+                .zip(&event.bindings)
+                .map(|(param, binding)| (param.name.clone(), binding.clone()))
+                .collect(),
+            guard: guard_ir(fsm, transition),
+            trailing_comment: transition.trailing_comment.clone(),
+            internal,
+            exit: if exits { actions_of(&transition.source, false) } else { Vec::new() },
+            actions: transition.actions.iter().map(|a| Call::action(a, transition)).collect(),
+            target: to_pascal_case(&transition.target),
+            target_name: transition.target.clone(),
+            entry: if enters { actions_of(&transition.target, true) } else { Vec::new() },
+            exits_source: !internal && transition.exits_source(),
+            enters_target: enters,
+            timing: transition.timing_constraint.map(|constraint| (constraint, slot)),
+        });
+    }
+    arms
+}
+
+/// `transition`'s guard with its terms resolved to calls and named guards (`None`
+/// without one, or for `[else]`)
+pub(super) fn guard_ir(fsm: &FsmDefinition, transition: &Transition) -> Option<GuardIr> {
+    let guard = transition.guard.as_ref().filter(|g| !g.is_else())?;
+    Some(guard.parsed().map(&mut |term| match fsm.named_guard(term) {
+        Some(named) => GuardTerm::Named(to_snake_case(&named.name)),
+        None => GuardTerm::Call(Call::guard(&Guard::new(term.as_str()), transition)),
+    }))
+}
+
+/// The guard of a `[*] -->` line, which has no bindings to pass
+fn initial_guard_ir(fsm: &FsmDefinition, guard: &Guard) -> GuardIr {
+    guard.parsed().map(&mut |term| match fsm.named_guard(term) {
+        Some(named) => GuardTerm::Named(to_snake_case(&named.name)),
+        None => GuardTerm::Call(Call::new(to_snake_case(&Guard::new(term.as_str()).method()))),
+    })
+}
+
+/// The named guards, each after the ones it uses (a loop, which validation rejects,
+/// is cut where it closes)
+pub(super) fn named_guards(fsm: &FsmDefinition) -> Vec<NamedGuardIr> {
+    fn visit<'a>(fsm: &'a FsmDefinition, name: &'a str, visited: &mut Vec<&'a str>, guards: &mut Vec<NamedGuardIr>) {
+        let Some(named) = fsm.named_guard(name).filter(|_| !visited.contains(&name)) else {
+            return;
+        };
+        visited.push(name);
+        let parsed = GuardExpr::parse(&named.expression);
+        for term in parsed.terms() {
+            if let Some(used) = fsm.named_guard(term) {
+                visit(fsm, &used.name, visited, guards);
+            }
+        }
+        guards.push(NamedGuardIr {
+            method: to_snake_case(&named.name),
+            expression: named.expression.clone(),
+            guard: parsed.map(&mut |term| match fsm.named_guard(term) {
+                Some(used) => GuardTerm::Named(to_snake_case(&used.name)),
+                None => GuardTerm::Call(Call::new(to_snake_case(&Guard::new(term.as_str()).method()))),
+            }),
+        });
+    }
+    let (mut visited, mut guards) = (Vec::new(), Vec::new());
+    for named in &fsm.guards {
+        visit(fsm, &named.name, &mut visited, &mut guards);
+    }
+    guards
+}
+
+/// Whether `transition` starts in a final state (validation rejects those)
+pub(super) fn leaves_final_state(fsm: &FsmDefinition, transition: &Transition) -> bool {
+    fsm.states.iter().any(|s| s.name == transition.source && s.is_final())
+}
+
+/// Whether `transition` has a guard that can fail (`[else]` cannot)
+pub(super) fn has_guard(transition: &Transition) -> bool {
+    transition.guard.as_ref().is_some_and(|g| !g.is_else())
+}
+
+/// Transitions leaving `state` (a DSL state name) on `event`: its own, then its
+/// internal ones, in declaration order
+pub(super) fn handlers_of<'a>(fsm: &'a FsmDefinition, state: &'a State, event: &'a str) -> impl Iterator<Item = &'a Transition> {
+    fsm.transitions
+        .iter()
+        .chain(&state.internal_transitions)
+        .filter(move |t| t.source == state.name && t.event.as_ref().is_some_and(|e| e.name == event))
+}
+
+/// Every state (final ones aside) has an unconditional transition of its own for the
+/// wildcard's event
+pub(super) fn wildcard_shadowed(fsm: &FsmDefinition, wildcard: &Transition) -> bool {
+    let Some(ref event) = wildcard.event else {
+        return false;
+    };
+    let mut states = fsm.states.iter().filter(|s| !s.is_final()).peekable();
+    states.peek().is_some() && states.all(|state| handlers_of(fsm, state, &event.name).any(|t| !has_guard(t)))
+}
+
+/// An earlier transition of the same state always takes the internal transition's event
+fn internal_shadowed(fsm: &FsmDefinition, internal: &Transition) -> bool {
+    let (Some(state), Some(event)) = (fsm.states.iter().find(|s| s.name == internal.source), internal.event.as_ref()) else {
+        return false;
+    };
+    let (own, internals): (Vec<&Transition>, Vec<&Transition>) =
+        handlers_of(fsm, state, &event.name).partition(|t| !state.internal_transitions.iter().any(|i| std::ptr::eq(i, *t)));
+    else_last(own)
+        .into_iter()
+        .chain(else_last(internals))
+        .take_while(|t| !std::ptr::eq(*t, internal))
+        .any(|t| !has_guard(t))
+}
+
+/// `transitions` in the order `interpreter::select` tries them: an `[else]` one
+/// declared before a sibling (same source and event) moves to just after the last
+fn else_last(transitions: Vec<&Transition>) -> Vec<&Transition> {
+    let is_else = |t: &Transition| t.guard.as_ref().is_some_and(|g| g.is_else());
+    let event = |t: &Transition| t.event.as_ref().map(|e| e.name.clone());
+    let place = |index: usize, transition: &Transition| match is_else(transition) {
+        true => transitions
+            .iter()
+            .rposition(|t| !is_else(t) && t.source == transition.source && event(t) == event(transition))
+            .map_or(index, |last| last.max(index)),
+        false => index,
+    };
+    let mut ordered: Vec<(usize, &Transition)> = transitions.iter().copied().enumerate().collect();
+    ordered.sort_by_key(|&(index, transition)| (place(index, transition), is_else(transition)));
+    ordered.into_iter().map(|(_, transition)| transition).collect()
+}
+
+/// A method the generated code requires, as a target declares it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodSig {
+    /// Emitted identifier
+    pub name: String,
+    pub role: MethodRole,
+    /// Declaration in the target's language: the actions trait method for the Rust
+    /// targets, the actions table field (or a named guard's helper) for C
+    pub signature: String,
+    /// The method's other uses, e.g. `entry of Red` or `Red --> Green : go`
+    pub shared_with: Vec<String>,
+}
+
+/// Methods transition `transition_index` (into `FsmDefinition::transitions`) makes
+/// the generated code call: its actions and guard terms, and for a named guard the
+/// methods its definition uses, in the actions trait's first-use order. Names are
+/// resolved like codegen resolves them; an index out of range needs none.
+pub fn methods_for_transition(fsm: &FsmDefinition, transition_index: usize, target: CodegenTarget) -> Vec<MethodSig> {
+    match fsm.transitions.get(transition_index) {
+        Some(transition) => methods_used_by(fsm, &[transition_usage(transition)], target),
+        None => Vec::new(),
+    }
+}
+
+/// Entry and exit action methods of `state` (a DSL state name), like
+/// [`methods_for_transition`]
+pub fn methods_for_state(fsm: &FsmDefinition, state: &str, target: CodegenTarget) -> Vec<MethodSig> {
+    methods_used_by(fsm, &[format!("entry of {}", state), format!("exit of {}", state)], target)
+}
+
+fn methods_used_by(fsm: &FsmDefinition, usages: &[String], target: CodegenTarget) -> Vec<MethodSig> {
+    let methods = match target {
+        CodegenTarget::C => collect_trait_methods(fsm, &CodegenOptions::default()),
+        _ => rust_trait_methods(fsm, &CodegenOptions::default()),
+    };
+    let mut uses: Vec<String> = usages.to_vec();
+    let mut needed: Vec<&TraitMethod> = Vec::new();
+    // Named guards pull in what their definitions use (listed as `guard name`)
+    let mut index = 0;
+    while index < uses.len() {
+        let usage = uses[index].clone();
+        for method in methods.iter().filter(|m| m.used_by.contains(&usage)) {
+            if needed.iter().any(|n| std::ptr::eq(*n, method)) {
+                continue;
+            }
+            if let Some(named) = fsm.guards.iter().find(|g| method.role == MethodRole::NamedGuard && to_snake_case(&g.name) == method.name) {
+                uses.push(format!("guard {}", named.name));
+                uses.push(format!("guard {} = {}", named.name, named.expression));
+            }
+            needed.push(method);
+        }
+        index += 1;
+    }
+    needed.sort_by_key(|n| methods.iter().position(|m| std::ptr::eq(m, *n)));
+    needed
+        .into_iter()
+        .map(|method| MethodSig {
+            name: method.name.clone(),
+            role: method.role,
+            signature: match target {
+                CodegenTarget::C => c::c_declaration(fsm, method),
+                _ => method.signature(),
+            },
+            shared_with: method.used_by.iter().filter(|u| !uses.contains(u)).cloned().collect(),
+        })
+        .collect()
+}
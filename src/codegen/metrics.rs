@@ -0,0 +1,120 @@
+//! Dispatch Metrics
+//! Counters compiled into the generated FSM with the user's `fsm-metrics` feature:
+//! how often each state was entered, how many of each event were processed and
+//! the deepest the event queue got, for profiling a machine on the target
+
+use super::{collect_event_names, event_queue_size, CodegenOptions};
+use crate::fsm::naming::to_pascal_case;
+use crate::fsm::FsmDefinition;
+
+/// Cargo feature of the user's crate that compiles the metrics in
+pub const METRICS_FEATURE: &str = "fsm-metrics";
+
+/// `#[cfg(feature = "fsm-metrics")]` at `indent`
+pub(super) fn cfg_attribute(indent: &str) -> String {
+    format!("{}#[cfg(feature = \"{}\")]\n", indent, METRICS_FEATURE)
+}
+
+/// `#[cfg]`-gated statement recording entry into `state` (a DSL state name)
+pub(super) fn count_entry(fsm: &FsmDefinition, state: &str, indent: &str) -> String {
+    format!(
+        "{}{}self.metrics.count_entry({}State::{});\n",
+        cfg_attribute(indent),
+        indent,
+        fsm.name,
+        to_pascal_case(state)
+    )
+}
+
+/// The `{Fsm}Metrics` struct: only the counters that apply (no event counters
+/// without events, no watermark without a queue)
+pub(super) fn generate_metrics_struct(fsm: &FsmDefinition, options: &CodegenOptions) -> String {
+    let vis = options.visibility.as_str();
+    let has_events = !collect_event_names(fsm).is_empty();
+    let queue = event_queue_size(fsm, options).is_some();
+    let metrics = format!("{}Metrics", fsm.name);
+    let state = format!("{}State", fsm.name);
+    let event = format!("{}Event", fsm.name);
+    let state_count = fsm.states.len();
+    let event_count = collect_event_names(fsm).len();
+
+    let mut code = String::new();
+    code.push_str(&format!("/// Dispatch counters of `{}`, compiled in with the `{}` feature\n", fsm.name, METRICS_FEATURE));
+    code.push_str(&cfg_attribute(""));
+    code.push_str("#[derive(Debug, Clone, PartialEq, Eq)]\n");
+    code.push_str(&format!("{} struct {} {{\n", vis, metrics));
+    code.push_str(&format!("    state_entered: [u16; {}],\n", state_count));
+    if has_events {
+        code.push_str(&format!("    event_received: [u32; {}],\n", event_count));
+    }
+    if queue {
+        code.push_str("    queue_high_watermark: usize,\n");
+    }
+    code.push_str("}\n\n");
+
+    code.push_str(&cfg_attribute(""));
+    code.push_str(&format!("impl {} {{\n", metrics));
+    code.push_str(&format!("    fn started(initial: {}) -> Self {{\n", state));
+    code.push_str("        let mut metrics = Self {\n");
+    code.push_str(&format!("            state_entered: [0; {}],\n", state_count));
+    if has_events {
+        code.push_str(&format!("            event_received: [0; {}],\n", event_count));
+    }
+    if queue {
+        code.push_str("            queue_high_watermark: 0,\n");
+    }
+    code.push_str("        };\n");
+    code.push_str("        metrics.count_entry(initial);\n");
+    code.push_str("        metrics\n");
+    code.push_str("    }\n\n");
+
+    code.push_str(&format!("    fn count_entry(&mut self, state: {}) {{\n", state));
+    code.push_str("        let count = &mut self.state_entered[state as usize];\n");
+    code.push_str("        *count = count.saturating_add(1);\n");
+    code.push_str("    }\n\n");
+    code.push_str("    /// Times `state` was entered, the initial state's start included (saturates at `u16::MAX`)\n");
+    code.push_str(&format!("    {} fn entered(&self, state: {}) -> u16 {{\n", vis, state));
+    code.push_str("        self.state_entered[state as usize]\n");
+    code.push_str("    }\n");
+
+    if has_events {
+        // Events with a payload cannot be cast to an index, and numbered ones may
+        // leave gaps
+        let events = fsm.collect_events();
+        let payloads = events.iter().any(|e| !e.params.is_empty() || e.id.is_some());
+        let index = if payloads { "Self::event_index(event)" } else { "event as usize" };
+        code.push('\n');
+        code.push_str(&format!("    fn count_event(&mut self, event: {}) {{\n", event));
+        code.push_str(&format!("        let count = &mut self.event_received[{}];\n", index));
+        code.push_str("        *count = count.saturating_add(1);\n");
+        code.push_str("    }\n\n");
+        code.push_str("    /// Times `event` was processed, handled or not (saturates at `u32::MAX`)\n");
+        code.push_str(&format!("    {} fn received(&self, event: {}) -> u32 {{\n", vis, event));
+        code.push_str(&format!("        self.event_received[{}]\n", index));
+        code.push_str("    }\n");
+        if payloads {
+            code.push('\n');
+            code.push_str(&format!("    fn event_index(event: {}) -> usize {{\n", event));
+            code.push_str("        match event {\n");
+            for (i, e) in events.iter().enumerate() {
+                let payload = if e.params.is_empty() { "" } else { " { .. }" };
+                code.push_str(&format!("            {}::{}{} => {},\n", event, to_pascal_case(&e.name), payload, i));
+            }
+            code.push_str("        }\n");
+            code.push_str("    }\n");
+        }
+    }
+
+    if queue {
+        code.push('\n');
+        code.push_str("    fn record_queue_len(&mut self, len: usize) {\n");
+        code.push_str("        self.queue_high_watermark = self.queue_high_watermark.max(len);\n");
+        code.push_str("    }\n\n");
+        code.push_str("    /// Most events the queue has held at once\n");
+        code.push_str(&format!("    {} fn queue_high_watermark(&self) -> usize {{\n", vis));
+        code.push_str("        self.queue_high_watermark\n");
+        code.push_str("    }\n");
+    }
+    code.push_str("}\n");
+    code
+}
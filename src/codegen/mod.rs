@@ -5,6 +5,7 @@
 //! ## Available Targets
 //! 
 //! - **Standard** (MIT): Basic Rust FSM with states, events, and transitions
+//! - **C** (MIT): C99 header and source for non-Rust toolchains
 //! 
 //! ### Premium Targets (Oxidate Pro)
 //! 
@@ -17,10 +18,44 @@
 //! - Issues: https://github.com/JoseClaudioSJr/Oxidate/issues
 //! - Discussions: https://github.com/JoseClaudioSJr/Oxidate/discussions
 
-use crate::fsm::FsmDefinition;
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+pub use crate::fsm::interpreter::ActionOrder;
+use crate::fsm::naming::{self, to_snake_case, NameError};
+pub use crate::fsm::EventOrder;
+use crate::fsm::{Action, FsmDefinition, Guard, Transition};
+
+pub mod autogen;
+pub mod backend;
+pub mod bindings;
+pub mod c;
+pub mod check;
+mod completions;
+pub mod dot;
+mod edges;
+pub mod highlight;
+pub mod ir;
+pub mod metrics;
+pub mod plantuml;
+mod premium;
+pub mod shared_events;
+mod standard;
+
+pub use backend::CodegenBackend;
+pub use ir::FsmIr;
+pub use plantuml::generate_plantuml;
+
+use c::CBackend;
+use premium::{EmbassyBackend, RticBackend};
+use standard::StandardBackend;
+
+#[cfg(test)]
+mod tests;
 
 /// Code generation target
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CodegenTarget {
     /// Standard Rust (std) - MIT licensed
     Standard,
@@ -28,21 +63,37 @@ pub enum CodegenTarget {
     Embassy,
     /// RTIC (no_std, embedded) - Premium
     Rtic,
+    /// C99 header + source - MIT licensed
+    C,
 }
 
 impl CodegenTarget {
     /// Check if target is available (premium features)
     pub fn is_available(&self) -> bool {
         match self {
-            CodegenTarget::Standard => true,
+            CodegenTarget::Standard | CodegenTarget::C => true,
             CodegenTarget::Embassy | CodegenTarget::Rtic => false, // Premium
         }
     }
     
+    /// Name of the target's backend in [`backend::BackendRegistry`]
+    pub fn backend_name(&self) -> &'static str {
+        self.backend().name()
+    }
+
+    fn backend(&self) -> &'static dyn CodegenBackend {
+        match self {
+            CodegenTarget::Standard => &StandardBackend,
+            CodegenTarget::Embassy => &EmbassyBackend,
+            CodegenTarget::Rtic => &RticBackend,
+            CodegenTarget::C => &CBackend,
+        }
+    }
+    
     /// Get upgrade message for premium targets
     pub fn upgrade_message(&self) -> Option<&'static str> {
         match self {
-            CodegenTarget::Standard => None,
+            CodegenTarget::Standard | CodegenTarget::C => None,
             CodegenTarget::Embassy => Some(
                 "Embassy code generation is available in Oxidate Pro.\n\
                  Contact: https://github.com/JoseClaudioSJr/Oxidate/discussions"
@@ -61,6 +112,309 @@ impl Default for CodegenTarget {
     }
 }
 
+/// Visibility of the generated items
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Visibility {
+    /// `pub`
+    #[default]
+    Public,
+    /// `pub(crate)`
+    Crate,
+}
+
+impl Visibility {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Visibility::Public => "pub",
+            Visibility::Crate => "pub(crate)",
+        }
+    }
+}
+
+/// What the generated `post()` does when the event queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Give the new event back: `post()` returns `Result<(), Event>`
+    #[default]
+    Reject,
+    /// Drop the oldest queued event to make room: `post()` returns it
+    DropOldest,
+    /// Panic (a full queue is a design error)
+    Panic,
+    /// Discard the new event and count it in a saturating `dropped_events()`
+    Count,
+}
+
+/// Which actions the generated machine awaits (the trait declares them `async fn`,
+/// and `start()`/`process()` become `async fn` awaiting each action in order).
+/// Only the Embassy target can; guards are always sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AsyncActions {
+    /// None: the `async` annotations are ignored
+    None,
+    /// Those written `async name` in the DSL
+    #[default]
+    Annotated,
+    /// Every action
+    All,
+}
+
+impl AsyncActions {
+    pub const ALL: [AsyncActions; 3] = [AsyncActions::None, AsyncActions::Annotated, AsyncActions::All];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AsyncActions::None => "None",
+            AsyncActions::Annotated => "Annotated (async name)",
+            AsyncActions::All => "All",
+        }
+    }
+
+    /// Whether the action method `name` (as emitted) is awaited in `fsm`
+    fn awaits(&self, fsm: &FsmDefinition, name: &str) -> bool {
+        match self {
+            AsyncActions::None => false,
+            AsyncActions::Annotated => fsm.actions().any(|a| a.is_async && to_snake_case(&a.name) == name),
+            AsyncActions::All => true,
+        }
+    }
+}
+
+impl OverflowPolicy {
+    pub const ALL: [OverflowPolicy; 4] = [
+        OverflowPolicy::Reject,
+        OverflowPolicy::DropOldest,
+        OverflowPolicy::Panic,
+        OverflowPolicy::Count,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            OverflowPolicy::Reject => "Reject",
+            OverflowPolicy::DropOldest => "Drop oldest",
+            OverflowPolicy::Panic => "Panic",
+            OverflowPolicy::Count => "Count",
+        }
+    }
+}
+
+/// Options controlling optional parts and style of the generated code
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodegenOptions {
+    /// Emit an `on_transition` hook on the actions trait, called after every transition
+    pub tracing_hook: bool,
+    /// Extra derives on the state enum (e.g. `serde::Serialize`)
+    pub state_derives: Vec<String>,
+    /// Extra derives on the event enum
+    pub event_derives: Vec<String>,
+    /// Visibility of the generated enums, struct, trait and methods
+    pub visibility: Visibility,
+    /// Emit `#![no_std]` (for using the generated file as a crate root)
+    pub no_std: bool,
+    /// Emit `#[repr(u8)]` on the state and event enums, with the discriminant of
+    /// every event variant written out and an `EVENT_NAMES` table (explicit event
+    /// ids in the DSL turn this on for the event enum)
+    pub repr_u8: bool,
+    /// Generate a fixed-size event queue with `post()`/`dispatch_pending()`
+    pub event_queue_size: Option<usize>,
+    /// Behaviour of `post()` when the event queue is full
+    pub overflow_policy: OverflowPolicy,
+    /// Extra module-level doc lines placed after the generated header
+    pub doc_header: Option<String>,
+    /// Comment lines at the very top of every generated file (e.g. an SPDX license
+    /// header), with placeholders filled in by `file_header`
+    pub header_template: Option<String>,
+    /// Sanitize names that are not valid Rust identifiers instead of rejecting them
+    pub lenient_names: bool,
+    /// Emit `process_shared()`/`post_shared()` taking the `SharedEvent` of an `events.rs`
+    /// module (see `shared_events`); folder exports also write that module
+    pub shared_events: bool,
+    /// Count state entries, processed events and the queue's high watermark in a
+    /// `{Fsm}Metrics` block behind the `fsm-metrics` feature (see `metrics`)
+    pub metrics: bool,
+    /// Run the start and initial entry actions in `new()` instead of generating
+    /// `start()` (the behaviour of code generated before `start()` existed)
+    pub run_entry_on_new: bool,
+    /// Completion transitions `run_completions()` takes in a row at most; `None` for
+    /// `interpreter::DEFAULT_MAX_COMPLETION_HOPS`
+    pub max_completion_hops: Option<usize>,
+    /// `process()` returns a `{Fsm}ProcessResult` (transitioned, handled internally,
+    /// rejected by its guards or ignored) instead of whether the event was handled
+    pub process_result: bool,
+    /// Whether a transition's actions run after the exit actions of the state it
+    /// leaves (UML) or before them; the interpreter takes the same setting
+    pub action_order: ActionOrder,
+    /// Order of the event variants no `events` block declares
+    pub event_order: EventOrder,
+    /// Implement the actions trait with the snippets of a bindings file (see `bindings`)
+    pub bindings: Option<bindings::Bindings>,
+    /// Actions the machine awaits; targets that cannot await fail to generate if any
+    pub async_actions: AsyncActions,
+}
+
+impl CodegenOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_tracing_hook(mut self, enabled: bool) -> Self {
+        self.tracing_hook = enabled;
+        self
+    }
+
+    pub fn with_state_derives<I, S>(mut self, derives: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.state_derives.extend(derives.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_event_derives<I, S>(mut self, derives: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.event_derives.extend(derives.into_iter().map(Into::into));
+        self
+    }
+
+    /// Derive (or stop deriving) `serde::Serialize` and `serde::Deserialize` on both enums
+    pub fn with_serde_derives(mut self, enabled: bool) -> Self {
+        self.state_derives.retain(|d| !SERDE_DERIVES.contains(&d.as_str()));
+        self.event_derives.retain(|d| !SERDE_DERIVES.contains(&d.as_str()));
+        if enabled {
+            self = self.with_state_derives(SERDE_DERIVES).with_event_derives(SERDE_DERIVES);
+        }
+        self
+    }
+
+    pub fn with_visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    pub fn with_no_std(mut self, enabled: bool) -> Self {
+        self.no_std = enabled;
+        self
+    }
+
+    pub fn with_repr_u8(mut self, enabled: bool) -> Self {
+        self.repr_u8 = enabled;
+        self
+    }
+
+    pub fn with_event_queue_size(mut self, size: Option<usize>) -> Self {
+        self.event_queue_size = size;
+        self
+    }
+
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Replace unusable names with `naming::sanitize_ident` (see `naming::check_names`)
+    pub fn with_lenient_names(mut self, enabled: bool) -> Self {
+        self.lenient_names = enabled;
+        self
+    }
+
+    pub fn with_shared_events(mut self, enabled: bool) -> Self {
+        self.shared_events = enabled;
+        self
+    }
+
+    pub fn with_run_entry_on_new(mut self, enabled: bool) -> Self {
+        self.run_entry_on_new = enabled;
+        self
+    }
+
+    pub fn with_max_completion_hops(mut self, hops: usize) -> Self {
+        self.max_completion_hops = Some(hops);
+        self
+    }
+
+    pub fn with_process_result(mut self, enabled: bool) -> Self {
+        self.process_result = enabled;
+        self
+    }
+
+    pub fn with_action_order(mut self, order: ActionOrder) -> Self {
+        self.action_order = order;
+        self
+    }
+
+    pub fn with_event_order(mut self, order: EventOrder) -> Self {
+        self.event_order = order;
+        self
+    }
+
+    pub fn with_async_actions(mut self, async_actions: AsyncActions) -> Self {
+        self.async_actions = async_actions;
+        self
+    }
+
+    pub fn with_metrics(mut self, enabled: bool) -> Self {
+        self.metrics = enabled;
+        self
+    }
+
+    pub fn with_bindings(mut self, bindings: bindings::Bindings) -> Self {
+        self.bindings = Some(bindings);
+        self
+    }
+
+    pub fn with_doc_header(mut self, header: impl Into<String>) -> Self {
+        self.doc_header = Some(header.into());
+        self
+    }
+
+    pub fn with_header_template(mut self, template: impl Into<String>) -> Self {
+        self.header_template = Some(template.into());
+        self
+    }
+
+    /// `header_template` for a file of `fsm_name` generated by the `target` backend,
+    /// with `{fsm_name}`, `{target}` and `{tool_version}` replaced: one `//` comment
+    /// per line (lines already starting with `//` are kept as written) and a blank
+    /// line, or nothing without a template
+    pub fn file_header(&self, fsm_name: &str, target: &str) -> String {
+        let Some(ref template) = self.header_template else {
+            return String::new();
+        };
+        let filled = template
+            .replace("{fsm_name}", fsm_name)
+            .replace("{target}", target)
+            .replace("{tool_version}", env!("CARGO_PKG_VERSION"));
+        let mut header = String::new();
+        for line in filled.trim_end().lines().map(str::trim_end) {
+            match line {
+                "" => header.push_str("//\n"),
+                _ if line.starts_with("//") => header.push_str(&format!("{}\n", line)),
+                _ => header.push_str(&format!("// {}\n", line)),
+            }
+        }
+        header.push('\n');
+        header
+    }
+
+    /// Whether both enums derive the serde traits
+    pub fn has_serde_derives(&self) -> bool {
+        SERDE_DERIVES
+            .iter()
+            .all(|d| self.state_derives.iter().any(|x| x == d) && self.event_derives.iter().any(|x| x == d))
+    }
+
+    /// Queue capacity, if a (non-empty) queue was requested
+    fn queue_size(&self) -> Option<usize> {
+        self.event_queue_size.filter(|&n| n > 0)
+    }
+}
+
+const SERDE_DERIVES: [&str; 2] = ["serde::Serialize", "serde::Deserialize"];
+
 /// Generate Rust code from an FSM definition
 pub fn generate_rust_code(fsm: &FsmDefinition) -> String {
     generate_rust_code_with_target(fsm, CodegenTarget::Standard)
@@ -68,336 +422,690 @@ pub fn generate_rust_code(fsm: &FsmDefinition) -> String {
 
 /// Generate Rust code with specific target
 pub fn generate_rust_code_with_target(fsm: &FsmDefinition, target: CodegenTarget) -> String {
-    match target {
-        CodegenTarget::Standard => generate_standard_code(fsm),
-        CodegenTarget::Embassy => generate_premium_stub(fsm, "Embassy"),
-        CodegenTarget::Rtic => generate_premium_stub(fsm, "RTIC"),
-    }
-}
-
-/// Generate stub for premium features
-fn generate_premium_stub(fsm: &FsmDefinition, target_name: &str) -> String {
-    format!(
-        "//! {} code generation requires Oxidate Pro\n\
-         //!\n\
-         //! FSM: {}\n\
-         //!\n\
-         //! To generate {} code:\n\
-         //!   1. Purchase/access: https://github.com/JoseClaudioSJr/Oxidate/discussions\n\
-         //!   2. Then use: oxidate-pro generate --target {} your_fsm.fsm\n\
-         //!\n\
-         //! Oxidate Pro includes:\n\
-         //!   - Embassy async Active Object pattern\n\
-         //!   - RTIC real-time event queues\n\
-         //!   - Events with payload\n\
-         //!   - HSM hierarchical states\n\
-         //!   - Priority support\n\
-         \n\
-         compile_error!(\"This target requires Oxidate Pro. Contact: https://github.com/JoseClaudioSJr/Oxidate/discussions\");\n",
-        target_name,
-        fsm.name,
-        target_name,
-        target_name.to_lowercase()
-    )
+    generate_rust_code_with_options(fsm, target, &CodegenOptions::default())
 }
 
-// ============================================================================
-// STANDARD CODE GENERATION
-// ============================================================================
+/// Generate Rust code with specific target and options
+pub fn generate_rust_code_with_options(
+    fsm: &FsmDefinition,
+    target: CodegenTarget,
+    options: &CodegenOptions,
+) -> String {
+    target.backend().generate(&FsmIr::with_event_order(fsm, options.event_order), options)
+}
 
-fn generate_standard_code(fsm: &FsmDefinition) -> String {
-    let mut code = String::new();
-    
-    // Header
-    code.push_str(&format!(
-        "//! Auto-generated FSM: {}\n",
-        fsm.name
-    ));
-    code.push_str("//! Generated by Oxidate\n\n");
-    
-    // Generate state enum
-    code.push_str(&generate_state_enum(fsm));
-    code.push_str("\n");
-    
-    // Generate event enum
-    code.push_str(&generate_event_enum(fsm));
-    code.push_str("\n");
-    
-    // Generate FSM struct
-    code.push_str(&generate_fsm_struct(fsm));
-    code.push_str("\n");
-    
-    // Generate implementation
-    code.push_str(&generate_fsm_impl(fsm));
-    code.push_str("\n");
-    
-    // Generate action trait
-    code.push_str(&generate_action_trait(fsm));
-    
-    code
+/// Generate Rust code, failing if the actions trait would not compile
+pub fn try_generate_rust_code_with_target(
+    fsm: &FsmDefinition,
+    target: CodegenTarget,
+) -> Result<String, Vec<CodegenError>> {
+    try_generate_rust_code_with_options(fsm, target, &CodegenOptions::default())
 }
 
-fn generate_state_enum(fsm: &FsmDefinition) -> String {
-    let mut code = String::new();
-    
-    code.push_str("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\n");
-    code.push_str(&format!("pub enum {}State {{\n", fsm.name));
-    
-    for state in &fsm.states {
-        if let Some(ref desc) = state.description {
-            code.push_str(&format!("    /// {}\n", desc));
+/// Generate Rust code with options, failing if a name is not a valid identifier
+/// (unless `lenient_names` is set) or the actions trait would not compile
+pub fn try_generate_rust_code_with_options(
+    fsm: &FsmDefinition,
+    target: CodegenTarget,
+    options: &CodegenOptions,
+) -> Result<String, Vec<CodegenError>> {
+    generate_with_backend(fsm, target.backend(), options)
+}
+
+/// Generate code with any backend (e.g. one picked by name from a
+/// [`backend::BackendRegistry`]), failing like [`try_generate_rust_code_with_options`]
+pub fn generate_with_backend(
+    fsm: &FsmDefinition,
+    backend: &dyn CodegenBackend,
+    options: &CodegenOptions,
+) -> Result<String, Vec<CodegenError>> {
+    let fsm = prepare_for_backend(fsm, backend, options)?;
+    Ok(backend.generate(&FsmIr::with_event_order(&fsm, options.event_order), options))
+}
+
+/// [`prepare_fsm`], failing also if the FSM awaits actions and `backend` cannot
+fn prepare_for_backend(
+    fsm: &FsmDefinition,
+    backend: &dyn CodegenBackend,
+    options: &CodegenOptions,
+) -> Result<FsmDefinition, Vec<CodegenError>> {
+    let fsm = prepare_fsm(fsm, options)?;
+    let awaited: Vec<String> = collect_trait_methods(&fsm, options).into_iter().filter(|m| m.is_async).map(|m| m.name).collect();
+    if !awaited.is_empty() && !backend.awaits_actions() {
+        return Err(vec![CodegenError::AsyncActions { target: backend.name().to_string(), actions: awaited.join(", ") }]);
+    }
+    Ok(fsm)
+}
+
+/// The FSM with its names as they will be emitted, or why no code can be generated for it
+fn prepare_fsm(fsm: &FsmDefinition, options: &CodegenOptions) -> Result<FsmDefinition, Vec<CodegenError>> {
+    let renames = naming::check_names(fsm, options.lenient_names)
+        .map_err(|errors| errors.into_iter().map(CodegenError::from).collect::<Vec<_>>())?;
+    let fsm = naming::apply_renames(fsm, &renames);
+    // The generated guard methods would call each other forever
+    let cycles: Vec<CodegenError> =
+        fsm.guard_cycles().into_iter().map(|cycle| CodegenError::GuardCycle { cycle: cycle.join(" → ") }).collect();
+    if !cycles.is_empty() {
+        return Err(cycles);
+    }
+    check_bindings(&fsm)?;
+    check_payload_types(&fsm)?;
+    check_trait_methods(&collect_trait_methods(&fsm, options))?;
+    if options.shared_events {
+        let payloads: Vec<CodegenError> = fsm
+            .events
+            .iter()
+            .filter(|e| !e.params.is_empty())
+            .map(|e| CodegenError::SharedEventPayload { event: e.name.clone() })
+            .collect();
+        if !payloads.is_empty() {
+            return Err(payloads);
         }
-        code.push_str(&format!("    {},\n", to_pascal_case(&state.name)));
     }
-    
-    code.push_str("}\n");
-    code
+    let ir = FsmIr::with_event_order(&fsm, options.event_order);
+    if options.repr_u8 || ir.has_event_ids() {
+        check_event_ids(&ir)?;
+    }
+    Ok(fsm)
 }
 
-fn generate_event_enum(fsm: &FsmDefinition) -> String {
-    let mut code = String::new();
-    
-    // Collect unique events
-    let mut events: Vec<String> = fsm.transitions
+/// Every trigger binds as many names as its event declares parameters, since the
+/// dispatch arm destructures the event into them
+fn check_bindings(fsm: &FsmDefinition) -> Result<(), Vec<CodegenError>> {
+    let internal = fsm.states.iter().flat_map(|s| &s.internal_transitions);
+    let errors: Vec<CodegenError> = fsm
+        .transitions
         .iter()
-        .filter_map(|t| t.event.as_ref().map(|e| e.name.clone()))
+        .chain(internal)
+        .filter_map(|transition| {
+            let event = transition.event.as_ref().filter(|e| !e.bindings.is_empty())?;
+            let declared = fsm.event_params(&event.name).len();
+            (event.bindings.len() != declared).then(|| CodegenError::BindingArity {
+                transition: transition_usage(transition),
+                event: event.name.clone(),
+                bound: event.bindings.len(),
+                declared,
+            })
+        })
         .collect();
-    events.sort();
-    events.dedup();
-    
-    if events.is_empty() {
-        return String::new();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
     }
-    
-    code.push_str("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\n");
-    code.push_str(&format!("pub enum {}Event {{\n", fsm.name));
-    
-    for event in &events {
-        code.push_str(&format!("    {},\n", to_pascal_case(event)));
+}
+
+/// Every event discriminant fits the `u8` the event enum is represented as, and no
+/// two events share one
+fn check_event_ids(ir: &FsmIr) -> Result<(), Vec<CodegenError>> {
+    let discriminants = ir.event_discriminants();
+    let mut errors: Vec<CodegenError> = ir
+        .events
+        .iter()
+        .zip(&discriminants)
+        .filter(|(_, &id)| id > u64::from(u8::MAX))
+        .map(|(event, &id)| CodegenError::EventIdOutOfRange { event: event.name.clone(), id })
+        .collect();
+    let mut seen: Vec<u64> = Vec::new();
+    for &id in &discriminants {
+        if seen.contains(&id) {
+            continue;
+        }
+        seen.push(id);
+        let events: Vec<String> = ir
+            .events
+            .iter()
+            .zip(&discriminants)
+            .filter(|(_, &other)| other == id)
+            .map(|(event, _)| format!("`{}`", event.name))
+            .collect();
+        if events.len() > 1 {
+            errors.push(CodegenError::DuplicateEventId { id, events: events.join(", ") });
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
     }
-    
-    code.push_str("}\n");
-    code
 }
 
-fn generate_fsm_struct(fsm: &FsmDefinition) -> String {
-    let mut code = String::new();
-    
-    code.push_str(&format!("pub struct {}<T: {}Actions> {{\n", fsm.name, fsm.name));
-    code.push_str(&format!("    state: {}State,\n", fsm.name));
-    code.push_str("    context: T,\n");
-    code.push_str("}\n");
+/// Every payload type can be `Copy`, which the event enum derives. User types are
+/// taken on trust; the standard library's owning types (`String`, `PathBuf`, ...)
+/// are reported, however their path is written.
+fn check_payload_types(fsm: &FsmDefinition) -> Result<(), Vec<CodegenError>> {
+    const NOT_COPY: &[&str] = &[
+        "String", "Vec", "Box", "Rc", "Arc", "RefCell", "Cell", "HashMap", "HashSet", "BTreeMap", "BTreeSet", "VecDeque",
+        "BinaryHeap", "LinkedList", "PathBuf", "OsString", "CString",
+    ];
+    let not_copy = |ty: &str| ty.rsplit("::").next().is_some_and(|name| NOT_COPY.contains(&name));
+    let errors: Vec<CodegenError> = fsm
+        .events
+        .iter()
+        .flat_map(|event| event.params.iter().map(move |param| (event, param)))
+        .filter(|(_, param)| not_copy(&param.ty))
+        .map(|(event, param)| CodegenError::NonCopyPayload {
+            event: event.name.clone(),
+            param: param.name.clone(),
+            ty: param.ty.clone(),
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// `#[derive(...)]` (plus `#[repr(u8)]` if requested) for a generated enum; `Eq` and
+/// `Hash` only if `eq` (payloads may hold floats or types that have neither)
+fn enum_attributes(extra_derives: &[String], options: &CodegenOptions, eq: bool) -> String {
+    let mut derives = vec!["Debug", "Clone", "Copy", "PartialEq"];
+    if eq {
+        derives.extend(["Eq", "Hash"]);
+    }
+    for derive in extra_derives {
+        if !derives.contains(&derive.as_str()) {
+            derives.push(derive);
+        }
+    }
     
+    let mut code = format!("#[derive({})]\n", derives.join(", "));
+    if options.repr_u8 {
+        code.push_str("#[repr(u8)]\n");
+    }
     code
 }
 
-fn generate_fsm_impl(fsm: &FsmDefinition) -> String {
+/// Unique event names used by the FSM transitions (internal ones included), sorted
+fn collect_event_names(fsm: &FsmDefinition) -> Vec<String> {
+    fsm.collect_events().into_iter().map(|e| e.name).collect()
+}
+
+/// Capacity of the generated event queue, if one is generated (needs an event enum)
+fn event_queue_size(fsm: &FsmDefinition, options: &CodegenOptions) -> Option<usize> {
+    options.queue_size().filter(|_| !collect_event_names(fsm).is_empty())
+}
+
+/// `///` lines saying in which order a transition runs its actions (for the dispatch
+/// function of every target)
+fn action_order_doc(order: ActionOrder, indent: &str) -> String {
+    let text = match order {
+        ActionOrder::UmlStandard => {
+            "A transition runs the exit actions of the state it leaves, then its own\n\
+             actions, then the entry actions of the state it enters (UML order)"
+        }
+        ActionOrder::ActionFirst => {
+            "A transition runs its own actions, then the exit actions of the state it\n\
+             leaves, then the entry actions of the state it enters (action-first order)"
+        }
+    };
+    doc_comment(text, indent)
+}
+
+/// The exit action calls and a transition's own action calls in the order `order`
+/// runs them
+fn exit_and_actions(order: ActionOrder, exit: String, actions: String) -> String {
+    match order {
+        ActionOrder::UmlStandard => exit + &actions,
+        ActionOrder::ActionFirst => actions + &exit,
+    }
+}
+
+/// `///` lines for a (possibly multi-line) description
+fn doc_comment(text: &str, indent: &str) -> String {
     let mut code = String::new();
-    
-    let initial_state = fsm.initial_state.as_ref()
-        .map(|s| to_pascal_case(s))
-        .unwrap_or_else(|| "Unknown".to_string());
-    
-    code.push_str(&format!("impl<T: {}Actions> {}<T> {{\n", fsm.name, fsm.name));
-    
-    // Constructor
-    code.push_str("    pub fn new(mut context: T) -> Self {\n");
-    
-    // Call entry actions of initial state
-    if let Some(initial_state_name) = &fsm.initial_state {
-        if let Some(state) = fsm.states.iter().find(|s| &s.name == initial_state_name) {
-            for entry_action in &state.entry_actions {
-                code.push_str(&format!(
-                    "        context.{}();\n",
-                    to_snake_case(&entry_action.name)
-                ));
-            }
+    if text.trim().is_empty() {
+        return code;
+    }
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            code.push_str(&format!("{}///\n", indent));
+        } else {
+            code.push_str(&format!("{}/// {}\n", indent, line));
         }
     }
-    
-    code.push_str(&format!("        Self {{\n"));
-    code.push_str(&format!("            state: {}State::{},\n", fsm.name, initial_state));
-    code.push_str("            context,\n");
-    code.push_str("        }\n");
-    code.push_str("    }\n\n");
-    
-    // State getter
-    code.push_str(&format!("    pub fn state(&self) -> {}State {{\n", fsm.name));
-    code.push_str("        self.state\n");
-    code.push_str("    }\n\n");
-    
-    // Context getter
-    code.push_str("    pub fn context(&self) -> &T {\n");
-    code.push_str("        &self.context\n");
-    code.push_str("    }\n\n");
-    
-    // Context mutable getter
-    code.push_str("    pub fn context_mut(&mut self) -> &mut T {\n");
-    code.push_str("        &mut self.context\n");
-    code.push_str("    }\n\n");
-    
-    // Process event
-    code.push_str(&generate_process_event(fsm));
-    
-    code.push_str("}\n");
     code
 }
 
-fn generate_process_event(fsm: &FsmDefinition) -> String {
-    let mut code = String::new();
-    
-    code.push_str(&format!("    pub fn process(&mut self, event: {}Event) -> bool {{\n", fsm.name));
-    code.push_str("        match (self.state, event) {\n");
-    
-    for transition in &fsm.transitions {
-        if transition.source == "[*]" {
-            continue; // Skip initial transitions
-        }
-        
-        if let Some(ref event) = transition.event {
-            let source = to_pascal_case(&transition.source);
-            let target = to_pascal_case(&transition.target);
-            let event_name = to_pascal_case(&event.name);
-            
-            // Check for guard
-            if let Some(ref guard) = transition.guard {
-                code.push_str(&format!(
-                    "            ({}State::{}, {}Event::{}) if self.context.{} => {{\n",
-                    fsm.name, source, fsm.name, event_name, to_snake_case(&guard.expression)
-                ));
-            } else {
-                code.push_str(&format!(
-                    "            ({}State::{}, {}Event::{}) => {{\n",
-                    fsm.name, source, fsm.name, event_name
-                ));
-            }
-            
-            // Exit actions
-            if let Some(state) = fsm.states.iter().find(|s| s.name == transition.source) {
-                for exit_action in &state.exit_actions {
-                    code.push_str(&format!(
-                        "                self.context.{}();\n",
-                        to_snake_case(&exit_action.name)
-                    ));
-                }
-            }
-            
-            // Transition action
-            if let Some(ref action) = transition.action {
-                code.push_str(&format!(
-                    "                self.context.{}();\n",
-                    to_snake_case(&action.name)
-                ));
-            }
-            
-            // State change
-            code.push_str(&format!(
-                "                self.state = {}State::{};\n",
-                fsm.name, target
-            ));
-            
-            // Entry actions
-            if let Some(state) = fsm.states.iter().find(|s| s.name == transition.target) {
-                for entry_action in &state.entry_actions {
-                    code.push_str(&format!(
-                        "                self.context.{}();\n",
-                        to_snake_case(&entry_action.name)
-                    ));
-                }
+/// ` // comment` to end a generated line with, or nothing
+fn trailing_comment(comment: &Option<String>) -> String {
+    comment.as_ref().map_or_else(String::new, |c| format!(" // {}", c))
+}
+
+// ============================================================================
+// CODE SECTIONS
+// ============================================================================
+
+const SECTION_PREFIX: &str = "// ── ";
+const SECTION_SUFFIX: &str = " ──";
+
+/// Line opening a section of generated code, e.g. `// ── Events ──`. Every backend
+/// starts its states, events, dispatch and actions parts with one, so viewers can
+/// fold them.
+fn section_marker(title: &str) -> String {
+    format!("{SECTION_PREFIX}{title}{SECTION_SUFFIX}\n")
+}
+
+/// A part of generated code, from its marker line up to the next marker (or file
+/// boundary in C output)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeSection {
+    pub title: String,
+    /// 0-based line indices, the marker line first
+    pub lines: std::ops::Range<usize>,
+}
+
+/// The marked sections of `code` in order; lines before the first marker belong to none
+pub fn code_sections(code: &str) -> Vec<CodeSection> {
+    let mut sections: Vec<CodeSection> = Vec::new();
+    let mut open = false;
+    let mut count = 0;
+    for (index, line) in code.lines().enumerate() {
+        count = index + 1;
+        let title = line.strip_prefix(SECTION_PREFIX).and_then(|rest| rest.strip_suffix(SECTION_SUFFIX));
+        if title.is_some() || line.starts_with(c::C_FILE_MARKER) {
+            if let Some(section) = sections.last_mut().filter(|_| open) {
+                section.lines.end = index;
             }
-            
-            code.push_str("                true\n");
-            code.push_str("            }\n");
+            open = title.is_some();
+        }
+        if let Some(title) = title {
+            sections.push(CodeSection { title: title.to_string(), lines: index..index + 1 });
         }
     }
-    
-    // Default case - no transition
-    code.push_str("            _ => false // No transition\n");
-    code.push_str("        }\n");
-    code.push_str("    }\n");
-    
-    code
+    if let Some(section) = sections.last_mut().filter(|_| open) {
+        section.lines.end = count;
+    }
+    sections
 }
 
-fn generate_action_trait(fsm: &FsmDefinition) -> String {
-    let mut code = String::new();
-    
-    // Collect all actions
-    let mut actions: Vec<String> = Vec::new();
-    let mut guards: Vec<String> = Vec::new();
+// ============================================================================
+// ACTIONS TRAIT METHOD TABLE
+// ============================================================================
+
+/// Getter the machine reads the time from for `@debounce` / `@throttle` transitions
+pub const CLOCK_METHOD: &str = "now_ms";
+
+/// Role of a method on the generated actions trait
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MethodRole {
+    /// Entry/exit/transition action (`fn name(&mut self, ...)`)
+    Action,
+    /// Guard (`fn name(&self, ...) -> bool`)
+    Guard,
+    /// Named guard, provided from its definition (`fn name(&self) -> bool { ... }`)
+    NamedGuard,
+    /// Generated hook with a default body (e.g. `on_transition`)
+    Hook,
+    /// Value passed to an action by name (`fn name(&self) -> T`)
+    Getter,
+}
+
+impl std::fmt::Display for MethodRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MethodRole::Action => write!(f, "action"),
+            MethodRole::Guard => write!(f, "guard"),
+            MethodRole::NamedGuard => write!(f, "named guard"),
+            MethodRole::Hook => write!(f, "hook"),
+            MethodRole::Getter => write!(f, "getter"),
+        }
+    }
+}
+
+/// A method of the generated actions trait, with everything that uses it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraitMethod {
+    /// Emitted Rust identifier
+    pub name: String,
+    /// What the method is used for
+    pub role: MethodRole,
+    /// Number of parameters written in the DSL
+    pub param_count: usize,
+    /// The parameters the generated method takes, with their Rust types
+    pub params: Vec<(String, String)>,
+    /// Rust type a getter returns
+    pub returns: Option<String>,
+    /// Human-readable uses, e.g. `entry of Red` or `Red --> Green : go`
+    pub used_by: Vec<String>,
+    /// An action the machine awaits (see [`AsyncActions`])
+    pub is_async: bool,
+}
+
+impl TraitMethod {
+    pub fn new(name: String, role: MethodRole, param_count: usize, params: Vec<(String, String)>, usage: String) -> Self {
+        Self { name, role, param_count, params, returns: None, used_by: vec![usage], is_async: false }
+    }
+
+    /// The same method returning `ty`
+    pub fn with_returns(mut self, ty: String) -> Self {
+        self.returns = Some(ty);
+        self
+    }
+
+    /// Rust signature as emitted in the trait (a keyword name written raw)
+    pub fn signature(&self) -> String {
+        let params: String = self.params.iter().map(|(name, ty)| format!(", {}: {}", naming::rust_ident(name), ty)).collect();
+        let name = naming::rust_ident(&self.name);
+        match self.role {
+            MethodRole::Action if self.is_async => format!("async fn {}(&mut self{})", name, params),
+            MethodRole::Action => format!("fn {}(&mut self{})", name, params),
+            MethodRole::Guard => format!("fn {}(&self{}) -> bool", name, params),
+            MethodRole::NamedGuard => format!("fn {}(&self) -> bool", name),
+            MethodRole::Hook => format!("fn {}(&mut self, from, event, to)", name),
+            MethodRole::Getter => format!("fn {}(&self) -> {}", name, self.returns.as_deref().unwrap_or("u32")),
+        }
+    }
+
+    /// Types of the parameters, as in `(u16, bool)`
+    fn param_types(&self) -> String {
+        let types: Vec<&str> = self.params.iter().map(|(_, ty)| ty.as_str()).collect();
+        format!("({})", types.join(", "))
+    }
+
+    fn same_types(&self, other: &TraitMethod) -> bool {
+        self.params.len() == other.params.len() && self.params.iter().zip(&other.params).all(|(a, b)| a.1 == b.1)
+    }
+}
+
+/// Errors detected before emitting code
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CodegenError {
+    #[error("`{name}` is used as {first_role} ({first_uses}) and as {second_role} ({second_uses})")]
+    RoleCollision {
+        name: String,
+        first_role: MethodRole,
+        first_uses: String,
+        second_role: MethodRole,
+        second_uses: String,
+    },
+    #[error("`{name}` is called with {first_count} parameter(s) ({first_uses}) and with {second_count} ({second_uses})")]
+    SignatureCollision {
+        name: String,
+        first_count: usize,
+        first_uses: String,
+        second_count: usize,
+        second_uses: String,
+    },
+    #[error("`{name}` takes {first_types} for {first_uses} but {second_types} for {second_uses}")]
+    ParamTypeCollision {
+        name: String,
+        first_types: String,
+        first_uses: String,
+        second_types: String,
+        second_uses: String,
+    },
+    #[error("`{name}` returns {first_type} for {first_uses} but {second_type} for {second_uses}")]
+    GetterTypeCollision {
+        name: String,
+        first_type: String,
+        first_uses: String,
+        second_type: String,
+        second_uses: String,
+    },
+    #[error(transparent)]
+    Name(#[from] NameError),
+    #[error("events {uses} all become `SharedEvent::{variant}`")]
+    SharedEventCollision { variant: String, uses: String },
+    #[error("event `{event}` carries parameters, which shared events cannot")]
+    SharedEventPayload { event: String },
+    #[error("named guards {cycle} refer to each other in a loop")]
+    GuardCycle { cycle: String },
+    #[error("event `{event}` is numbered {id}, which does not fit the u8 the event enum is represented as")]
+    EventIdOutOfRange { event: String, id: u64 },
+    #[error("events {events} are all numbered {id}")]
+    DuplicateEventId { id: u64, events: String },
+    #[error("the {target} target cannot await the async actions {actions} (only Embassy can; set async actions to None to call them synchronously)")]
+    AsyncActions { target: String, actions: String },
+    #[error("{transition} binds {bound} parameter(s), but event `{event}` declares {declared}")]
+    BindingArity { transition: String, event: String, bound: usize, declared: usize },
+    #[error("event `{event}` carries `{param}: {ty}`, which is not `Copy` as the generated event enum needs")]
+    NonCopyPayload { event: String, param: String, ty: String },
+}
+
+/// How [`TraitMethod::used_by`] lists a use by a (non-internal) transition
+fn transition_usage(transition: &Transition) -> String {
+    format!("{} --> {} : {}", transition.source, transition.target, transition.label())
+}
+
+/// Build the complete set of actions trait methods in the order they are first used.
+///
+/// The same identifier appears more than once only when its uses conflict
+/// (different role, parameter count, parameter types or return type); see
+/// [`check_trait_methods`].
+pub fn collect_trait_methods(fsm: &FsmDefinition, options: &CodegenOptions) -> Vec<TraitMethod> {
+    let mut methods: Vec<TraitMethod> = Vec::new();
+    let known = known_param_types(fsm);
     
     for state in &fsm.states {
         for action in &state.entry_actions {
-            actions.push(action.name.clone());
+            add_action(&mut methods, &known, action, &[], format!("entry of {}", state.name));
         }
         for action in &state.exit_actions {
-            actions.push(action.name.clone());
+            add_action(&mut methods, &known, action, &[], format!("exit of {}", state.name));
         }
     }
     
-    for transition in &fsm.transitions {
-        if let Some(ref action) = transition.action {
-            actions.push(action.name.clone());
+    for initial in &fsm.initial_transitions {
+        let usage = match initial.guard {
+            Some(ref guard) => format!("start ([*] --> {} : [{}])", initial.target, guard.expression),
+            None => format!("start ([*] --> {})", initial.target),
+        };
+        for action in &initial.actions {
+            add_action(&mut methods, &known, action, &[], usage.clone());
         }
-        if let Some(ref guard) = transition.guard {
-            guards.push(guard.expression.clone());
+        for term in initial.guard.iter().flat_map(|g| g.terms()) {
+            add_method(&mut methods, guard_method(fsm, &term, Vec::new(), usage.clone()));
         }
     }
-    
-    actions.sort();
-    actions.dedup();
-    guards.sort();
-    guards.dedup();
-    
-    code.push_str(&format!("pub trait {}Actions {{\n", fsm.name));
-    
-    for action in &actions {
-        code.push_str(&format!("    fn {}(&mut self);\n", to_snake_case(action)));
+
+    let internal = fsm.states.iter().flat_map(|s| s.internal_transitions.iter().map(move |t| (Some(s), t)));
+    for (state, transition) in fsm.transitions.iter().map(|t| (None, t)).chain(internal) {
+        let usage = match state {
+            Some(state) => format!("internal of {} : {}", state.name, transition.label()),
+            None => transition_usage(transition),
+        };
+        // Bound parameters are typed by the event's payload
+        let bound = fsm.bound_params(transition);
+        let typed = |args: Vec<&str>| -> Vec<(String, String)> {
+            args.into_iter().filter_map(|arg| bound.iter().find(|(name, _)| name == arg).cloned()).collect()
+        };
+        for action in &transition.actions {
+            add_action(&mut methods, &known, action, &bound, usage.clone());
+        }
+        if transition.timing_constraint.is_some() {
+            let clock = TraitMethod::new(CLOCK_METHOD.to_string(), MethodRole::Getter, 0, Vec::new(), usage.clone());
+            add_method(&mut methods, clock.with_returns("u32".to_string()));
+        }
+        for term in transition.guard.iter().filter(|g| !g.is_else()).flat_map(|g| g.terms()) {
+            add_method(&mut methods, guard_method(fsm, &term, typed(term.inputs()), usage.clone()));
+        }
     }
-    
-    for guard in &guards {
-        code.push_str(&format!("    fn {}(&self) -> bool;\n", to_snake_case(guard)));
+
+    for named in &fsm.guards {
+        let usage = format!("guard {}", named.name);
+        let definition = format!("{} = {}", usage, named.expression);
+        add_method(&mut methods, TraitMethod::new(to_snake_case(&named.name), MethodRole::NamedGuard, 0, Vec::new(), definition));
+        for term in Guard::new(named.expression.as_str()).terms() {
+            add_method(&mut methods, guard_method(fsm, &term, Vec::new(), usage.clone()));
+        }
     }
     
-    code.push_str("}\n");
-    
-    code
+    if options.tracing_hook {
+        add_method(&mut methods, TraitMethod::new("on_transition".to_string(), MethodRole::Hook, 3, Vec::new(), "every transition".to_string()));
+    }
+
+    for method in methods.iter_mut().filter(|m| m.role == MethodRole::Action) {
+        method.is_async = options.async_actions.awaits(fsm, &method.name);
+    }
+    methods
 }
 
-fn to_pascal_case(s: &str) -> String {
-    s.split('_')
-        .map(|word| {
-            let mut chars = word.chars();
-            match chars.next() {
-                None => String::new(),
-                Some(first) => first.to_uppercase().chain(chars).collect(),
-            }
-        })
-        .collect()
+/// Method answering the guard `term`: a named guard's, or a guard method taking
+/// `params` (the typed arguments among the term's, or the bindings a comparison uses)
+fn guard_method(fsm: &FsmDefinition, term: &Guard, params: Vec<(String, String)>, usage: String) -> TraitMethod {
+    let count = if term.comparison().is_some() { params.len() } else { term.args().len() };
+    match fsm.named_guard(&term.expression) {
+        Some(named) => TraitMethod::new(to_snake_case(&named.name), MethodRole::NamedGuard, 0, Vec::new(), usage),
+        None => TraitMethod::new(to_snake_case(&term.method()), MethodRole::Guard, count, params, usage),
+    }
+}
+
+/// The actions trait of the Rust backends: [`collect_trait_methods`] without the
+/// `start_timer(t)` / `stop_timer(t)` actions of defined timers, which the generated
+/// machine runs itself (see `advance_time()`)
+pub fn rust_trait_methods(fsm: &FsmDefinition, options: &CodegenOptions) -> Vec<TraitMethod> {
+    let mut methods = collect_trait_methods(fsm, options);
+    methods.retain(|method| !is_timer_method(fsm, method));
+    methods
 }
 
-fn to_snake_case(s: &str) -> String {
-    let mut result = String::new();
-    for (i, c) in s.chars().enumerate() {
-        if c.is_uppercase() && i > 0 {
-            result.push('_');
+/// Whether every call of `method` is a timer action of a defined timer
+fn is_timer_method(fsm: &FsmDefinition, method: &TraitMethod) -> bool {
+    let defined = |timer: &str| fsm.timers.iter().any(|t| t.name == timer);
+    let mut calls = fsm.actions().filter(|a| to_snake_case(&a.name) == method.name).peekable();
+    method.role == MethodRole::Action
+        && calls.peek().is_some()
+        && calls.all(|a| a.timer_op().is_some_and(|(_, timer)| defined(timer)))
+}
+
+/// Add `method`, or its use to an identical method already there
+fn add_method(methods: &mut Vec<TraitMethod>, method: TraitMethod) {
+    if let Some(existing) = methods.iter_mut().find(|m| {
+        m.name == method.name
+            && m.role == method.role
+            && m.param_count == method.param_count
+            && m.same_types(&method)
+            && m.returns == method.returns
+    }) {
+        if !existing.used_by.contains(&method.used_by[0]) {
+            existing.used_by.extend(method.used_by);
         }
-        result.push(c.to_lowercase().next().unwrap_or(c));
+    } else {
+        methods.push(method);
     }
-    // Replace spaces and special chars
-    result.replace(' ', "_").replace('-', "_")
 }
 
-// ============================================================================
-// PREMIUM TARGETS (Embassy, RTIC)
-// ============================================================================
-// Available in Oxidate Pro (separately): https://github.com/JoseClaudioSJr/Oxidate/discussions
-// 
-// Features include:
-// - Embassy Active Object pattern (async embedded)
-// - RTIC real-time event queues
-// - Events with typed payloads
-// - HSM hierarchical states
-// - Software timers
-// - ISR-safe event posting
-// ============================================================================
\ No newline at end of file
+/// Add `action` and a getter for each identifier it is passed that is not one of the
+/// transition's `bound` parameters
+fn add_action(
+    methods: &mut Vec<TraitMethod>,
+    known: &HashMap<String, Vec<Option<String>>>,
+    action: &Action,
+    bound: &[(String, String)],
+    usage: String,
+) {
+    let name = to_snake_case(&action.name);
+    let args = ir::action_args(action, &bound.iter().map(|(b, _)| b.as_str()).collect::<Vec<_>>());
+    let known = known.get(&name).map_or(&[][..], Vec::as_slice);
+    let mut params: Vec<(String, String)> = Vec::new();
+    let mut getters: Vec<(String, String)> = Vec::new();
+    for (i, (arg, param)) in args.iter().zip(&action.params).enumerate() {
+        let ty = match arg {
+            ir::Arg::Binding(binding) => bound.iter().find(|(b, _)| b == binding).map(|(_, ty)| ty.clone()),
+            ir::Arg::Literal(_) => match known.get(i).cloned().flatten() {
+                Some(ty) if Action::literal_fits(param, &ty) => Some(ty),
+                _ => Action::literal_type(param).map(str::to_string),
+            },
+            ir::Arg::Getter(_) => known.get(i).cloned().flatten(),
+        }
+        .unwrap_or_else(|| "u32".to_string());
+        let name = match arg {
+            ir::Arg::Binding(binding) => to_snake_case(binding),
+            ir::Arg::Getter(getter) => getter.clone(),
+            ir::Arg::Literal(_) => format!("arg{}", i),
+        };
+        let name = if params.iter().any(|(p, _)| *p == name) { format!("arg{}", i) } else { name };
+        if let ir::Arg::Getter(getter) = arg {
+            getters.push((getter.clone(), ty.clone()));
+        }
+        params.push((name, ty));
+    }
+    add_method(methods, TraitMethod::new(name, MethodRole::Action, action.params.len(), params, usage.clone()));
+    for (getter, ty) in getters {
+        add_method(methods, TraitMethod::new(getter, MethodRole::Getter, 0, Vec::new(), usage.clone()).with_returns(ty));
+    }
+}
+
+/// Type of each parameter position of each action wherever some call passes a
+/// payload binding or a literal there (a binding's type wins), so identifiers and
+/// literals passed at the same position elsewhere take that type
+fn known_param_types(fsm: &FsmDefinition) -> HashMap<String, Vec<Option<String>>> {
+    let mut known: HashMap<String, Vec<Option<String>>> = HashMap::new();
+    let initial_actions = fsm.initial_transitions.iter().flat_map(|t| &t.actions);
+    let state_actions = fsm.states.iter().flat_map(|s| s.entry_actions.iter().chain(&s.exit_actions)).chain(initial_actions);
+    let calls = state_actions.map(|a| (a, Vec::new())).chain(
+        fsm.transitions
+            .iter()
+            .chain(fsm.states.iter().flat_map(|s| &s.internal_transitions))
+            .flat_map(|t| t.actions.iter().map(move |a| (a, fsm.bound_params(t)))),
+    );
+    let calls: Vec<_> = calls.collect();
+    for bindings in [true, false] {
+        for (action, bound) in &calls {
+            let types = known.entry(to_snake_case(&action.name)).or_default();
+            if types.len() < action.params.len() {
+                types.resize(action.params.len(), None);
+            }
+            for (i, param) in action.params.iter().enumerate() {
+                if types[i].is_none() {
+                    types[i] = match bindings {
+                        true => bound.iter().find(|(b, _)| b == param).map(|(_, ty)| ty.clone()),
+                        false => Action::literal_type(param).map(str::to_string),
+                    };
+                }
+            }
+        }
+    }
+    known
+}
+
+/// Report identifiers that would be emitted with conflicting roles or parameter lists
+pub fn check_trait_methods(methods: &[TraitMethod]) -> Result<(), Vec<CodegenError>> {
+    let mut errors = Vec::new();
+    
+    for (i, first) in methods.iter().enumerate() {
+        for second in methods.iter().skip(i + 1).filter(|m| m.name == first.name) {
+            if first.role != second.role {
+                errors.push(CodegenError::RoleCollision {
+                    name: first.name.clone(),
+                    first_role: first.role,
+                    first_uses: first.used_by.join(", "),
+                    second_role: second.role,
+                    second_uses: second.used_by.join(", "),
+                });
+            } else if first.returns != second.returns {
+                errors.push(CodegenError::GetterTypeCollision {
+                    name: first.name.clone(),
+                    first_type: first.returns.clone().unwrap_or_default(),
+                    first_uses: first.used_by.join(", "),
+                    second_type: second.returns.clone().unwrap_or_default(),
+                    second_uses: second.used_by.join(", "),
+                });
+            } else if first.param_count == second.param_count {
+                errors.push(CodegenError::ParamTypeCollision {
+                    name: first.name.clone(),
+                    first_types: first.param_types(),
+                    first_uses: first.used_by.join(", "),
+                    second_types: second.param_types(),
+                    second_uses: second.used_by.join(", "),
+                });
+            } else {
+                errors.push(CodegenError::SignatureCollision {
+                    name: first.name.clone(),
+                    first_count: first.param_count,
+                    first_uses: first.used_by.join(", "),
+                    second_count: second.param_count,
+                    second_uses: second.used_by.join(", "),
+                });
+            }
+        }
+    }
+    
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
@@ -0,0 +1,129 @@
+//! PlantUML Export
+//! Renders an FSM as a PlantUML state diagram for wikis and documentation
+//! pipelines that already render `@startuml` blocks
+
+use super::edges::{edges, metadata_lines, state_actions, Endpoint};
+use crate::fsm::color::to_hex;
+use crate::fsm::{FsmDefinition, LayoutHint, NotePosition, StateType};
+
+/// Id of the dashed node wildcard (`* -->`) transitions leave from
+const ANY_STATE_ID: &str = "any_state";
+
+/// Generate a PlantUML state diagram (`@startuml` ... `@enduml`); states, choice
+/// points and edges come out in declaration order
+pub fn generate_plantuml(fsm: &FsmDefinition) -> String {
+    let mut uml = String::new();
+
+    uml.push_str(&format!("@startuml {}\n", fsm.name));
+    for line in metadata_lines(fsm) {
+        uml.push_str(format!("' {}", line).trim_end());
+        uml.push('\n');
+    }
+    uml.push_str("hide empty description\n");
+    // PlantUML only knows top-to-bottom (the default) and left-to-right
+    if matches!(fsm.layout_hint, Some(LayoutHint::LR | LayoutHint::RL)) {
+        uml.push_str("left to right direction\n");
+    }
+    uml.push('\n');
+
+    for state in &fsm.states {
+        let id = state_id(fsm, &state.name);
+        // `#color(...)` becomes the state's background
+        let color = state.rgb().map(|rgb| format!(" {}", to_hex(rgb))).unwrap_or_default();
+        if id == state.name {
+            uml.push_str(&format!("state {}{}\n", id, color));
+        } else {
+            uml.push_str(&format!("state \"{}\" as {}{}\n", escape_name(&state.name), id, color));
+        }
+        let description = state.description.iter().flat_map(|d| d.lines());
+        let internal = state.internal_transitions.iter().map(|t| t.label());
+        for line in description.map(str::to_string).chain(state_actions(state)).chain(internal) {
+            uml.push_str(&format!("{} : {}\n", id, escape(&line)));
+        }
+    }
+    for choice in &fsm.choice_points {
+        uml.push_str(&format!("state {} <<choice>>\n", choice.name));
+    }
+    if fsm.transitions.iter().any(|t| t.is_from_any_state()) {
+        uml.push_str(&format!("state \"any state\" as {} ##[dashed]\n", ANY_STATE_ID));
+    }
+    uml.push('\n');
+
+    let mut finals: Vec<&str> = Vec::new();
+    for edge in edges(fsm) {
+        if let (Endpoint::State(name), Endpoint::Pseudo) = (edge.source, edge.target) {
+            finals.push(name);
+        }
+        let line = if edge.dashed { "-[dashed]->" } else { "-->" };
+        let arrow = format!("{} {} {}", node_id(fsm, edge.source), line, node_id(fsm, edge.target));
+        if edge.label.is_empty() {
+            uml.push_str(&format!("{}\n", arrow));
+        } else {
+            uml.push_str(&format!("{} : {}\n", arrow, escape(&edge.label)));
+        }
+    }
+    // Final states without an explicit `--> [*]` still get one
+    for state in &fsm.states {
+        if state.state_type == StateType::Final && !finals.contains(&state.name.as_str()) {
+            uml.push_str(&format!("{} --> [*]\n", state_id(fsm, &state.name)));
+        }
+    }
+
+    for note in &fsm.notes {
+        let side = match note.position {
+            NotePosition::Left => "left",
+            NotePosition::Right => "right",
+        };
+        uml.push_str(&format!("\nnote {} of {}\n", side, state_id(fsm, &note.target_state)));
+        for line in note.text.lines() {
+            uml.push_str(&format!("    {}\n", escape(line)));
+        }
+        uml.push_str("end note\n");
+    }
+
+    uml.push_str("@enduml\n");
+    uml
+}
+
+fn node_id(fsm: &FsmDefinition, endpoint: Endpoint) -> String {
+    match endpoint {
+        Endpoint::Pseudo => "[*]".to_string(),
+        Endpoint::AnyState => ANY_STATE_ID.to_string(),
+        Endpoint::State(name) => state_id(fsm, name),
+        Endpoint::Choice(name) => name.to_string(),
+    }
+}
+
+/// The state's name if PlantUML takes it as an id, otherwise `state_{index}` (declared
+/// with the name as its display text)
+fn state_id(fsm: &FsmDefinition, name: &str) -> String {
+    let plain = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    match fsm.states.iter().position(|s| s.name == name) {
+        Some(index) if !plain => format!("state_{}", index),
+        _ => name.to_string(),
+    }
+}
+
+/// Quoted display names cannot contain `"`, so it becomes an entity
+fn escape_name(name: &str) -> String {
+    escape(name).replace('"', "&#34;")
+}
+
+/// Text after a `:` or inside a note: keeps `\`, line breaks and `~`/`<` (Creole escapes
+/// and HTML tags) literal
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '~' | '<' => {
+                escaped.push('~');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,96 @@
+//! Premium Targets (Embassy, RTIC)
+//! Available in Oxidate Pro (separately): https://github.com/JoseClaudioSJr/Oxidate/discussions
+//!
+//! Features include:
+//! - Embassy Active Object pattern (async embedded)
+//! - RTIC real-time event queues
+//! - Events with typed payloads
+//! - HSM hierarchical states
+//! - Software timers
+//! - ISR-safe event posting
+//!
+//! This edition's backends emit a stub that fails to compile with a pointer there.
+//! The Embassy stub lists the actions the machine awaits (see `AsyncActions`).
+
+use super::backend::CodegenBackend;
+use super::ir::FsmIr;
+use super::{rust_trait_methods, CodegenOptions};
+
+pub struct EmbassyBackend;
+
+impl CodegenBackend for EmbassyBackend {
+    fn name(&self) -> &'static str {
+        "embassy"
+    }
+
+    fn description(&self) -> &'static str {
+        "Embassy async Active Object (Oxidate Pro)"
+    }
+
+    fn generate(&self, ir: &FsmIr, options: &CodegenOptions) -> String {
+        let awaited: Vec<String> = rust_trait_methods(ir.fsm, options)
+            .into_iter()
+            .filter(|method| method.is_async)
+            .map(|method| format!("//!   - `{}`\n", method.signature()))
+            .collect();
+        let notes = match awaited.is_empty() {
+            true => String::new(),
+            false => format!(
+                "//!\n\
+                 //! Awaited actions (native `async fn` in the actions trait, Rust 1.75+; dispatch\n\
+                 //! awaits them in order, guards stay sync):\n{}",
+                awaited.concat()
+            ),
+        };
+        options.file_header(&ir.name, self.name()) + &generate_premium_stub(ir, "Embassy", &notes)
+    }
+
+    fn awaits_actions(&self) -> bool {
+        true
+    }
+}
+
+pub struct RticBackend;
+
+impl CodegenBackend for RticBackend {
+    fn name(&self) -> &'static str {
+        "rtic"
+    }
+
+    fn description(&self) -> &'static str {
+        "RTIC real-time event queues (Oxidate Pro)"
+    }
+
+    fn generate(&self, ir: &FsmIr, options: &CodegenOptions) -> String {
+        options.file_header(&ir.name, self.name()) + &generate_premium_stub(ir, "RTIC", "")
+    }
+}
+
+/// Stub telling the user where to get the premium target, with `notes` (`//!` lines)
+/// on what the FSM needs of it
+fn generate_premium_stub(ir: &FsmIr, target_name: &str, notes: &str) -> String {
+    format!(
+        "//! {} code generation requires Oxidate Pro\n\
+         //!\n\
+         //! FSM: {}\n\
+         //!\n\
+         //! To generate {} code:\n\
+         //!   1. Purchase/access: https://github.com/JoseClaudioSJr/Oxidate/discussions\n\
+         //!   2. Then use: oxidate-pro generate --target {} your_fsm.fsm\n\
+         //!\n\
+         //! Oxidate Pro includes:\n\
+         //!   - Embassy async Active Object pattern\n\
+         //!   - RTIC real-time event queues\n\
+         //!   - Events with payload\n\
+         //!   - HSM hierarchical states\n\
+         //!   - Priority support\n\
+         {}\
+         \n\
+         compile_error!(\"This target requires Oxidate Pro. Contact: https://github.com/JoseClaudioSJr/Oxidate/discussions\");\n",
+        target_name,
+        ir.name,
+        target_name,
+        target_name.to_lowercase(),
+        notes
+    )
+}
@@ -0,0 +1,219 @@
+//! Shared Events Module
+//! One `SharedEvent` enum with the union of the events of several FSMs, so an
+//! integration layer can hand the same event to every machine without
+//! converting between their event enums
+
+use super::{collect_event_names, enum_attributes, CodegenError, CodegenOptions, OverflowPolicy};
+use crate::fsm::naming::to_pascal_case;
+use crate::fsm::FsmDefinition;
+
+/// Cargo feature of the user's crate that enables `process_shared()`/`post_shared()` on each FSM
+pub const SHARED_EVENTS_FEATURE: &str = "shared-events";
+
+/// File name of the shared module in the `autogen` folder
+pub const SHARED_EVENTS_FILE: &str = "events.rs";
+
+/// A variant of `SharedEvent`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedEvent {
+    /// Event name as written in the DSL
+    pub name: String,
+    /// FSMs handling the event, in input order
+    pub fsms: Vec<String>,
+}
+
+impl SharedEvent {
+    pub fn variant(&self) -> String {
+        to_pascal_case(&self.name)
+    }
+}
+
+/// Union of the events of `fsms`, sorted by variant. Events carry no payload, so the
+/// same name always means the same event; different names that become the same
+/// variant (`tick` and `Tick`) are errors.
+pub fn shared_events(fsms: &[FsmDefinition]) -> Result<Vec<SharedEvent>, Vec<CodegenError>> {
+    let mut events: Vec<SharedEvent> = Vec::new();
+    for fsm in fsms {
+        for name in collect_event_names(fsm) {
+            match events.iter_mut().find(|e| e.name == name) {
+                Some(event) => event.fsms.push(fsm.name.clone()),
+                None => events.push(SharedEvent {
+                    name,
+                    fsms: vec![fsm.name.clone()],
+                }),
+            }
+        }
+    }
+    events.sort_by(|a, b| a.variant().cmp(&b.variant()).then_with(|| a.name.cmp(&b.name)));
+
+    let mut errors = Vec::new();
+    for (i, event) in events.iter().enumerate() {
+        let variant = event.variant();
+        if events[..i].iter().any(|e| e.variant() == variant) {
+            continue;
+        }
+        let clashing: Vec<String> = events[i..]
+            .iter()
+            .filter(|e| e.variant() == variant)
+            .map(|e| format!("`{}` ({})", e.name, e.fsms.join(", ")))
+            .collect();
+        if clashing.len() > 1 {
+            errors.push(CodegenError::SharedEventCollision {
+                variant,
+                uses: clashing.join(", "),
+            });
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(events)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Contents of `events.rs`: the `SharedEvent` enum, `From<{Name}Event> for SharedEvent`
+/// and `TryFrom<SharedEvent> for {Name}Event` (giving back events the FSM does not handle).
+/// `fsms` must be the machines `events` was computed from, with their emitted names.
+pub fn generate_shared_events(fsms: &[FsmDefinition], events: &[SharedEvent], options: &CodegenOptions) -> String {
+    let vis = options.visibility.as_str();
+    let mut code = String::from(
+        "//! Events shared by the FSMs of this module\n\
+         //! DO NOT EDIT - Generated by Oxidate\n\
+         //!\n\
+         //! Every FSM event converts into `SharedEvent`; `SharedEvent` converts back with\n\
+         //! `TryFrom`, which gives the event back if the FSM does not handle it.\n\n",
+    );
+
+    let fsms: Vec<(&FsmDefinition, Vec<String>)> = fsms
+        .iter()
+        .map(|fsm| (fsm, collect_event_names(fsm)))
+        .filter(|(_, names)| !names.is_empty())
+        .collect();
+    for (fsm, _) in &fsms {
+        code.push_str(&format!("use super::{}::{}Event;\n", fsm.file_stem(), fsm.name));
+    }
+    code.push('\n');
+
+    code.push_str(&enum_attributes(&options.event_derives, options, true));
+    code.push_str(&format!("{} enum SharedEvent {{\n", vis));
+    for event in events {
+        code.push_str(&format!("    /// Handled by {}\n", event.fsms.join(", ")));
+        code.push_str(&format!("    {},\n", event.variant()));
+    }
+    code.push_str("}\n\n");
+
+    code.push_str("impl SharedEvent {\n");
+    code.push_str(&format!("    {} const fn as_str(&self) -> &'static str {{\n", vis));
+    code.push_str("        match self {\n");
+    for event in events {
+        code.push_str(&format!("            SharedEvent::{} => \"{}\",\n", event.variant(), event.name));
+    }
+    code.push_str("        }\n");
+    code.push_str("    }\n");
+    code.push_str("}\n");
+
+    for (fsm, names) in &fsms {
+        let event_enum = format!("{}Event", fsm.name);
+
+        code.push_str(&format!("\nimpl From<{}> for SharedEvent {{\n", event_enum));
+        code.push_str(&format!("    fn from(event: {}) -> Self {{\n", event_enum));
+        code.push_str("        match event {\n");
+        for name in names {
+            let variant = to_pascal_case(name);
+            code.push_str(&format!("            {}::{} => SharedEvent::{},\n", event_enum, variant, variant));
+        }
+        code.push_str("        }\n");
+        code.push_str("    }\n");
+        code.push_str("}\n");
+
+        code.push_str(&format!("\nimpl TryFrom<SharedEvent> for {} {{\n", event_enum));
+        code.push_str("    type Error = SharedEvent;\n\n");
+        code.push_str("    fn try_from(event: SharedEvent) -> Result<Self, SharedEvent> {\n");
+        code.push_str("        match event {\n");
+        for name in names {
+            let variant = to_pascal_case(name);
+            code.push_str(&format!("            SharedEvent::{} => Ok({}::{}),\n", variant, event_enum, variant));
+        }
+        if names.len() < events.len() {
+            code.push_str("            other => Err(other),\n");
+        }
+        code.push_str("        }\n");
+        code.push_str("    }\n");
+        code.push_str("}\n");
+    }
+
+    code
+}
+
+/// `process_shared()` (and `post_shared()` with an event queue) for one FSM, behind
+/// the `shared-events` feature so the file still builds without `events.rs`
+pub(super) fn generate_shared_event_methods(fsm: &FsmDefinition, options: &CodegenOptions, queue: bool) -> String {
+    let vis = options.visibility.as_str();
+    let mut code = String::new();
+
+    code.push_str(&format!("#[cfg(feature = \"{}\")]\n", SHARED_EVENTS_FEATURE));
+    code.push_str(&format!("impl<T: {}Actions> {}<T> {{\n", fsm.name, fsm.name));
+    if options.process_result {
+        code.push_str("    /// Process a `SharedEvent`; `Ignored` if this FSM does not handle it\n");
+        code.push_str(&format!(
+            "    {} fn process_shared(&mut self, event: super::events::SharedEvent) -> {}ProcessResult {{\n",
+            vis, fsm.name
+        ));
+        code.push_str(&format!("        match {}Event::try_from(event) {{\n", fsm.name));
+        code.push_str("            Ok(event) => self.process(event),\n");
+        code.push_str(&format!("            Err(_) => {}ProcessResult::Ignored,\n", fsm.name));
+        code.push_str("        }\n");
+    } else {
+        code.push_str("    /// Process a `SharedEvent`; `false` if this FSM does not handle it\n");
+        code.push_str(&format!(
+            "    {} fn process_shared(&mut self, event: super::events::SharedEvent) -> bool {{\n",
+            vis
+        ));
+        code.push_str(&format!(
+            "        {}Event::try_from(event).is_ok_and(|event| self.process(event))\n",
+            fsm.name
+        ));
+    }
+    code.push_str("    }\n");
+
+    if queue {
+        let shared = "super::events::SharedEvent";
+        code.push('\n');
+        match options.overflow_policy {
+            OverflowPolicy::Reject => {
+                code.push_str("    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); gives the event back if the queue is full\n");
+                code.push_str(&format!(
+                    "    {} fn post_shared(&mut self, event: {}) -> Result<(), {}> {{\n",
+                    vis, shared, shared
+                ));
+                code.push_str(&format!("        match {}Event::try_from(event) {{\n", fsm.name));
+                code.push_str("            Ok(event) => self.post(event).map_err(Into::into),\n");
+                code.push_str("            Err(_) => Ok(()),\n");
+                code.push_str("        }\n");
+            }
+            OverflowPolicy::DropOldest => {
+                code.push_str("    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); returns the oldest event if it was dropped to make room\n");
+                code.push_str(&format!(
+                    "    {} fn post_shared(&mut self, event: {}) -> Option<{}> {{\n",
+                    vis, shared, shared
+                ));
+                code.push_str(&format!(
+                    "        {}Event::try_from(event).ok().and_then(|event| self.post(event)).map(Into::into)\n",
+                    fsm.name
+                ));
+            }
+            OverflowPolicy::Panic | OverflowPolicy::Count => {
+                code.push_str("    /// Queue a `SharedEvent` (dropped if this FSM does not handle it); a full queue is handled like `post()`\n");
+                code.push_str(&format!("    {} fn post_shared(&mut self, event: {}) {{\n", vis, shared));
+                code.push_str(&format!("        if let Ok(event) = {}Event::try_from(event) {{\n", fsm.name));
+                code.push_str("            self.post(event);\n");
+                code.push_str("        }\n");
+            }
+        }
+        code.push_str("    }\n");
+    }
+
+    code.push_str("}\n");
+    code
+}
@@ -0,0 +1,1147 @@
+//! Standard Target
+//! The std/no_std Rust backend: state and event enums, the machine struct with
+//! `process()` (plus the optional queue, metrics, completions and shared events)
+//! and the actions trait the user implements
+
+use super::backend::CodegenBackend;
+use super::ir::{
+    handlers_of, has_guard, named_guards, wildcard_shadowed, Arg, ArmSource, Call, FsmIr, GuardIr, GuardTerm, StateIr, TransitionArm,
+};
+use super::{
+    action_order_doc, bindings, collect_event_names, completions, doc_comment, enum_attributes,
+    event_queue_size, exit_and_actions, metrics, section_marker, shared_events, trailing_comment, CodegenOptions,
+    rust_trait_methods, MethodRole, OverflowPolicy, CLOCK_METHOD, SERDE_DERIVES,
+};
+use crate::fsm::naming::{rust_ident, to_pascal_case, to_snake_case};
+use crate::fsm::{FsmDefinition, TimerOp, TimingKind, Transition};
+
+pub struct StandardBackend;
+
+impl CodegenBackend for StandardBackend {
+    fn name(&self) -> &'static str {
+        "standard"
+    }
+
+    fn description(&self) -> &'static str {
+        "Rust state machine for std or no_std (MIT)"
+    }
+
+    fn generate(&self, ir: &FsmIr, options: &CodegenOptions) -> String {
+        options.file_header(&ir.name, self.name()) + &generate_standard_code(ir, options)
+    }
+}
+
+
+fn generate_standard_code(ir: &FsmIr, options: &CodegenOptions) -> String {
+    let fsm = ir.fsm;
+    let mut code = String::new();
+    // Without events there is no `process()`, so no transition for the hook to report
+    let untraced;
+    let options = if options.tracing_hook && ir.events.is_empty() {
+        untraced = CodegenOptions { tracing_hook: false, ..options.clone() };
+        &untraced
+    } else {
+        options
+    };
+    
+    // Header
+    code.push_str(&format!(
+        "//! Auto-generated FSM: {}\n",
+        ir.name
+    ));
+    code.push_str("//! Generated by Oxidate\n");
+    code.push_str(&generate_fsm_metadata(fsm));
+    if let Some(ref header) = options.doc_header {
+        code.push_str("//!\n");
+        for line in header.lines() {
+            if line.trim().is_empty() {
+                code.push_str("//!\n");
+            } else {
+                code.push_str(&format!("//! {}\n", line));
+            }
+        }
+    }
+    let summary = generate_method_summary(fsm, options);
+    if !summary.is_empty() {
+        code.push_str("//!\n");
+        code.push_str(&summary);
+    }
+    code.push('\n');
+    if options.no_std {
+        code.push_str("#![no_std]\n\n");
+    }
+    if ir.states.is_empty() {
+        code.push_str(&generate_empty_machine(ir, options));
+        return code;
+    }
+    
+    // Generate state enum
+    code.push_str(&section_marker("States"));
+    code.push_str(&generate_state_enum(ir, options));
+    code.push('\n');
+    code.push_str(&generate_state_names(ir, options));
+    code.push('\n');
+    
+    // Generate event enum
+    let event_enum = generate_event_enum(ir, options);
+    if !event_enum.is_empty() {
+        code.push_str(&section_marker("Events"));
+        code.push_str(&event_enum);
+        code.push('\n');
+        code.push_str(&generate_event_names(ir, options));
+        code.push('\n');
+    }
+    
+    // Generate process result, metrics and FSM struct
+    code.push_str(&section_marker("Dispatch"));
+    if options.process_result && !event_enum.is_empty() {
+        code.push_str(&generate_process_result_enum(ir, options));
+        code.push('\n');
+    }
+    if options.metrics {
+        code.push_str(&metrics::generate_metrics_struct(fsm, options));
+        code.push('\n');
+    }
+    code.push_str(&generate_fsm_struct(ir, options));
+    code.push('\n');
+    
+    // Generate implementation
+    code.push_str(&generate_fsm_impl(ir, options));
+    code.push('\n');
+    if options.shared_events && !event_enum.is_empty() {
+        let queue = event_queue_size(fsm, options).is_some();
+        code.push_str(&shared_events::generate_shared_event_methods(fsm, options, queue));
+        code.push('\n');
+    }
+    
+    // Generate action trait
+    code.push_str(&section_marker("Actions trait"));
+    code.push_str(&generate_action_trait(fsm, options));
+    if let Some(ref bindings) = options.bindings {
+        code.push('\n');
+        code.push_str(&section_marker("HAL bindings"));
+        code.push_str(&bindings::generate_hal_actions(fsm, bindings, StandardBackend.name(), options));
+    }
+    
+    code
+}
+
+/// A machine without states (a fresh `fsm Foo { }`): the empty state enum, a struct
+/// holding the context and its constructor, and the empty actions trait
+fn generate_empty_machine(ir: &FsmIr, options: &CodegenOptions) -> String {
+    let vis = options.visibility.as_str();
+    let mut code = String::new();
+
+    code.push_str(&section_marker("States"));
+    code.push_str(&format!("/// `{}` has no states yet\n", ir.name));
+    // `#[repr(u8)]` needs at least one variant
+    code.push_str(&generate_state_enum(ir, &CodegenOptions { repr_u8: false, ..options.clone() }));
+    code.push('\n');
+
+    code.push_str(&section_marker("Dispatch"));
+    if let Some(ref desc) = ir.fsm.description {
+        code.push_str(&doc_comment(desc, ""));
+    }
+    code.push_str(&format!("{} struct {}<T: {}Actions> {{\n", vis, ir.name, ir.name));
+    code.push_str("    context: T,\n");
+    code.push_str("}\n\n");
+    code.push_str(&format!("impl<T: {}Actions> {}<T> {{\n", ir.name, ir.name));
+    code.push_str(&format!("    {} fn new(context: T) -> Self {{\n", vis));
+    code.push_str("        Self { context }\n");
+    code.push_str("    }\n\n");
+    code.push_str(&format!("    {} fn context(&self) -> &T {{\n", vis));
+    code.push_str("        &self.context\n");
+    code.push_str("    }\n\n");
+    code.push_str(&format!("    {} fn context_mut(&mut self) -> &mut T {{\n", vis));
+    code.push_str("        &mut self.context\n");
+    code.push_str("    }\n");
+    code.push_str("}\n\n");
+
+    code.push_str(&section_marker("Actions trait"));
+    code.push_str(&generate_action_trait(ir.fsm, options));
+    code
+}
+
+fn generate_state_enum(ir: &FsmIr, options: &CodegenOptions) -> String {
+    let mut code = String::new();
+    
+    code.push_str(&enum_attributes(&options.state_derives, options, true));
+    code.push_str(&format!("{} enum {}State {{\n", options.visibility.as_str(), ir.name));
+    
+    for state in &ir.states {
+        if let Some(ref desc) = state.description {
+            code.push_str(&doc_comment(desc, "    "));
+        }
+        code.push_str(&format!("    {},{}\n", state.variant, trailing_comment(&state.trailing_comment)));
+    }
+    
+    code.push_str("}\n");
+    code
+}
+
+/// `as_str()` for the state enum, usable for logging without `Debug` formatting
+fn generate_state_names(ir: &FsmIr, options: &CodegenOptions) -> String {
+    let mut code = String::new();
+    
+    code.push_str(&format!("impl {}State {{\n", ir.name));
+    code.push_str(&format!("    {} const fn as_str(&self) -> &'static str {{\n", options.visibility.as_str()));
+    code.push_str("        match self {\n");
+    for state in &ir.states {
+        code.push_str(&format!(
+            "            {}State::{} => \"{}\",\n",
+            ir.name, state.variant, state.name
+        ));
+    }
+    code.push_str("        }\n");
+    code.push_str("    }\n");
+    code.push_str("}\n");
+    code
+}
+
+fn generate_event_enum(ir: &FsmIr, options: &CodegenOptions) -> String {
+    let mut code = String::new();
+    
+    if ir.events.is_empty() {
+        return String::new();
+    }
+    
+    let numbered = numbered_events(ir, options);
+    let repr = CodegenOptions { repr_u8: numbered, ..options.clone() };
+    code.push_str(&enum_attributes(&options.event_derives, &repr, ir.payloads_are_eq()));
+    code.push_str(&format!("{} enum {}Event {{\n", options.visibility.as_str(), ir.name));
+    
+    // Declared events first, in declaration order
+    for (event, id) in ir.events.iter().zip(ir.event_discriminants()) {
+        if let Some(ref desc) = event.description {
+            code.push_str(&doc_comment(desc, "    "));
+        }
+        let discriminant = if numbered { format!(" = {}", id) } else { String::new() };
+        if event.params.is_empty() {
+            code.push_str(&format!("    {}{},\n", event.variant, discriminant));
+        } else {
+            let fields: Vec<String> = event.params.iter().map(|p| format!("{}: {}", p.name, p.ty)).collect();
+            code.push_str(&format!("    {} {{ {} }}{},\n", event.variant, fields.join(", "), discriminant));
+        }
+    }
+    
+    code.push_str("}\n");
+    code
+}
+
+/// Whether the event variants get explicit `u8` discriminants
+fn numbered_events(ir: &FsmIr, options: &CodegenOptions) -> bool {
+    options.repr_u8 || ir.has_event_ids()
+}
+
+/// `as_str()` for the event enum, usable for logging without `Debug` formatting;
+/// numbered events also get `id()` and the `EVENT_NAMES` table to decode it
+fn generate_event_names(ir: &FsmIr, options: &CodegenOptions) -> String {
+    let mut code = String::new();
+    let vis = options.visibility.as_str();
+    
+    code.push_str(&format!("impl {}Event {{\n", ir.name));
+    if numbered_events(ir, options) {
+        let discriminants = ir.event_discriminants();
+        let mut names = vec![""; discriminants.iter().max().map_or(0, |&max| max as usize + 1)];
+        for (event, &id) in ir.events.iter().zip(&discriminants) {
+            names[id as usize] = &event.name;
+        }
+        let names: Vec<String> = names.iter().map(|name| format!("\"{}\"", name)).collect();
+        code.push_str("    /// Name of each event by `id()`, `\"\"` where no event has that id\n");
+        code.push_str(&format!(
+            "    {} const EVENT_NAMES: [&'static str; {}] = [{}];\n\n",
+            vis,
+            names.len(),
+            names.join(", ")
+        ));
+        code.push_str("    /// The discriminant of the variant, e.g. to log the event as one byte\n");
+        code.push_str(&format!("    {} const fn id(&self) -> u8 {{\n", vis));
+        code.push_str("        match self {\n");
+        for (event, id) in ir.events.iter().zip(&discriminants) {
+            let payload = if event.params.is_empty() { "" } else { " { .. }" };
+            code.push_str(&format!("            {}Event::{}{} => {},\n", ir.name, event.variant, payload, id));
+        }
+        code.push_str("        }\n");
+        code.push_str("    }\n\n");
+    }
+    code.push_str(&format!("    {} const fn as_str(&self) -> &'static str {{\n", options.visibility.as_str()));
+    code.push_str("        match self {\n");
+    for event in &ir.events {
+        let payload = if event.params.is_empty() { "" } else { " { .. }" };
+        code.push_str(&format!(
+            "            {}Event::{}{} => \"{}\",\n",
+            ir.name, event.variant, payload, event.name
+        ));
+    }
+    code.push_str("        }\n");
+    code.push_str("    }\n");
+    code.push_str("}\n");
+    code
+}
+
+/// `//!` lines with the FSM's description and `meta` entries (nothing if it has neither)
+fn generate_fsm_metadata(fsm: &FsmDefinition) -> String {
+    let mut code = String::new();
+    if let Some(ref desc) = fsm.description {
+        code.push_str("//!\n");
+        for line in desc.lines() {
+            if line.trim().is_empty() {
+                code.push_str("//!\n");
+            } else {
+                code.push_str(&format!("//! {}\n", line));
+            }
+        }
+    }
+    if !fsm.metadata.is_empty() {
+        code.push_str("//!\n");
+        for (key, value) in &fsm.metadata {
+            code.push_str(&format!("//! - {}: {}\n", key, value.replace('\n', " ")));
+        }
+    }
+    code
+}
+
+/// `{Fsm}ProcessResult`, what `process()` did with an event (with `process_result`)
+fn generate_process_result_enum(ir: &FsmIr, options: &CodegenOptions) -> String {
+    let vis = options.visibility.as_str();
+    let result = format!("{}ProcessResult", ir.name);
+    let mut derives = vec!["Debug", "Clone", "Copy", "PartialEq", "Eq"];
+    if options.has_serde_derives() {
+        derives.extend(SERDE_DERIVES);
+    }
+
+    let mut code = String::new();
+    code.push_str(&format!("/// What `{}::process()` did with an event\n", ir.name));
+    code.push_str(&format!("#[derive({})]\n", derives.join(", ")));
+    code.push_str(&format!("{} enum {} {{\n", vis, result));
+    code.push_str("    /// Left `from` and settled in `to` (after any completion transitions)\n");
+    code.push_str(&format!("    Transitioned {{ from: {}State, to: {}State }},\n", ir.name, ir.name));
+    code.push_str("    /// Handled without leaving the state (internal or local self-transition)\n");
+    code.push_str("    InternalHandled,\n");
+    code.push_str("    /// The state has transitions for the event, but none of their guards passed\n");
+    code.push_str("    GuardRejected,\n");
+    if ir.rate_limited_arms() > 0 {
+        code.push_str("    /// A `@debounce` / `@throttle` transition took the event too soon after the last one\n");
+        code.push_str("    Suppressed,\n");
+    }
+    code.push_str("    /// The state has no transition for the event\n");
+    code.push_str("    Ignored,\n");
+    code.push_str("}\n\n");
+
+    code.push_str(&format!("impl {} {{\n", result));
+    code.push_str("    /// Whether a transition took the event (what `process()` returns without this option)\n");
+    code.push_str(&format!("    {} const fn is_handled(&self) -> bool {{\n", vis));
+    code.push_str("        matches!(self, Self::Transitioned { .. } | Self::InternalHandled)\n");
+    code.push_str("    }\n");
+    code.push_str("}\n");
+    code
+}
+
+fn generate_fsm_struct(ir: &FsmIr, options: &CodegenOptions) -> String {
+    let mut code = String::new();
+    
+    if let Some(ref desc) = ir.fsm.description {
+        code.push_str(&doc_comment(desc, ""));
+    }
+    code.push_str(&format!("{} struct {}<T: {}Actions> {{\n", options.visibility.as_str(), ir.name, ir.name));
+    code.push_str(&format!("    state: {}State,\n", ir.name));
+    code.push_str("    context: T,\n");
+    if !options.run_entry_on_new {
+        code.push_str("    started: bool,\n");
+    }
+    if !ir.timers.is_empty() {
+        code.push_str("    /// Milliseconds since each timer of `TIMERS` was started; `None` while stopped\n");
+        code.push_str(&format!("    timers: [Option<u32>; {}],\n", ir.timers.len()));
+    }
+    if ir.rate_limited_arms() > 0 {
+        code.push_str("    /// `now_ms()` when each `@debounce` transition last saw its event, or each\n");
+        code.push_str("    /// `@throttle` transition last fired; `None` before the first time\n");
+        code.push_str(&format!("    transition_times: [Option<u32>; {}],\n", ir.rate_limited_arms()));
+    }
+    if let Some(size) = event_queue_size(ir.fsm, options) {
+        code.push_str(&format!("    queue: [Option<{}Event>; {}],\n", ir.name, size));
+        code.push_str("    queue_head: usize,\n");
+        code.push_str("    queue_len: usize,\n");
+        if options.overflow_policy == OverflowPolicy::Count {
+            code.push_str("    dropped_events: u32,\n");
+        }
+    }
+    if options.metrics {
+        code.push_str(&metrics::cfg_attribute("    "));
+        code.push_str(&format!("    metrics: {}Metrics,\n", ir.name));
+    }
+    code.push_str("}\n");
+    
+    code
+}
+
+fn generate_fsm_impl(ir: &FsmIr, options: &CodegenOptions) -> String {
+    let fsm = ir.fsm;
+    let mut code = String::new();
+    
+    // Without `[*] -->` the machine starts in its first state, as in C
+    let initial_state = ir.initial_state.as_deref().or(ir.states.first().map(|s| s.variant.as_str())).unwrap_or_default();
+    
+    let vis = options.visibility.as_str();
+    let queue_size = event_queue_size(fsm, options);
+    
+    code.push_str(&format!("impl<T: {}Actions> {}<T> {{\n", ir.name, ir.name));
+    
+    // Constructor (runs the start actions itself only with `run_entry_on_new`)
+    let branches = !ir.initial_branches.is_empty();
+    if options.run_entry_on_new {
+        code.push_str("    /// Create the machine and run its start actions, then the initial state's entry actions\n");
+        if branches {
+            code.push_str("    /// (those of the first initial transition whose guard holds, else of the default)\n");
+        }
+        code.push_str(&format!("    {} fn new(mut context: T) -> Self {{\n", vis));
+        // Timer actions set the timers the machine is created with instead
+        let calls = |actions: &[Call], indent: &str| -> String {
+            let calls = actions.iter().filter(|a| timer_slot(fsm, a).is_none());
+            calls.map(|action| format!("{}{};\n", indent, rust_call(action, "context"))).collect()
+        };
+        if branches {
+            for (i, branch) in ir.initial_branches.iter().enumerate() {
+                let keyword = if i == 0 { "let state = if" } else { "} else if" };
+                code.push_str(&format!("        {} {} {{\n", keyword, rust_guard(&branch.guard, "context")));
+                code.push_str(&calls(&branch.actions, "            "));
+                code.push_str(&format!("            {}State::{}\n", ir.name, branch.state));
+            }
+            code.push_str("        } else {\n");
+            code.push_str(&calls(&ir.start_actions, "            "));
+            code.push_str(&format!("            {}State::{}\n", ir.name, initial_state));
+            code.push_str("        };\n");
+        } else {
+            code.push_str(&calls(&ir.start_actions, "        "));
+        }
+    } else {
+        code.push_str("    /// Create the machine in its initial state without running any action; call\n");
+        code.push_str("    /// `start()` before processing events\n");
+        code.push_str(&format!("    {} fn new(context: T) -> Self {{\n", vis));
+    }
+    
+    // Completions of the initial state run once the machine exists
+    let initial_completions = fsm.initial_candidates().iter().any(|t| completions::has_completions(fsm, &t.target));
+    let completions_on_new = options.run_entry_on_new && initial_completions;
+    match completions_on_new {
+        true => code.push_str("        let mut machine = Self {\n"),
+        false => code.push_str("        Self {\n"),
+    }
+    match options.run_entry_on_new && branches {
+        true => code.push_str("            state,\n"),
+        false => code.push_str(&format!("            state: {}State::{},\n", ir.name, initial_state)),
+    }
+    code.push_str("            context,\n");
+    if !options.run_entry_on_new {
+        code.push_str("            started: false,\n");
+    }
+    if !ir.timers.is_empty() {
+        code.push_str(&format!("            timers: {},\n", initial_timers(ir, options)));
+    }
+    if ir.rate_limited_arms() > 0 {
+        code.push_str(&format!("            transition_times: [None; {}],\n", ir.rate_limited_arms()));
+    }
+    if let Some(size) = queue_size {
+        code.push_str(&format!("            queue: [None; {}],\n", size));
+        code.push_str("            queue_head: 0,\n");
+        code.push_str("            queue_len: 0,\n");
+        if options.overflow_policy == OverflowPolicy::Count {
+            code.push_str("            dropped_events: 0,\n");
+        }
+    }
+    if options.metrics {
+        code.push_str(&metrics::cfg_attribute("            "));
+        match options.run_entry_on_new && branches {
+            true => code.push_str(&format!("            metrics: {}Metrics::started(state),\n", ir.name)),
+            false => code.push_str(&format!("            metrics: {}Metrics::started({}State::{}),\n", ir.name, ir.name, initial_state)),
+        }
+    }
+    if completions_on_new {
+        code.push_str("        };\n");
+        code.push_str("        machine.run_completions();\n");
+        code.push_str("        machine\n");
+    } else {
+        code.push_str("        }\n");
+    }
+    code.push_str("    }\n\n");
+    
+    // Start
+    if !options.run_entry_on_new {
+        code.push_str("    /// Run the start actions, then the initial state's entry actions; only the first\n");
+        code.push_str("    /// call does anything. Returns whether this call started the machine\n");
+        if branches {
+            code.push_str("    ///\n");
+            code.push_str("    /// The guarded initial transitions are tried in order: the first whose guard\n");
+            code.push_str("    /// holds picks the initial state, else the machine stays in the default one\n");
+        }
+        code.push_str(&format!("    {} fn start(&mut self) -> bool {{\n", vis));
+        code.push_str("        if self.started {\n");
+        code.push_str("            return false;\n");
+        code.push_str("        }\n");
+        code.push_str("        self.started = true;\n");
+        let statements = |actions: &[Call], indent: &str| -> String {
+            actions.iter().map(|action| format!("{}{};\n", indent, rust_statement(fsm, action, "self.context"))).collect()
+        };
+        if branches {
+            for (i, branch) in ir.initial_branches.iter().enumerate() {
+                let keyword = if i == 0 { "if" } else { "} else if" };
+                code.push_str(&format!("        {} {} {{\n", keyword, rust_guard(&branch.guard, "self.context")));
+                code.push_str(&format!("            self.state = {}State::{};\n", ir.name, branch.state));
+                code.push_str(&statements(&branch.actions, "            "));
+            }
+            if ir.start_actions.is_empty() {
+                code.push_str("        }\n");
+            } else {
+                code.push_str("        } else {\n");
+                code.push_str(&statements(&ir.start_actions, "            "));
+                code.push_str("        }\n");
+            }
+        } else {
+            code.push_str(&statements(&ir.start_actions, "        "));
+        }
+        if initial_completions {
+            code.push_str("        self.run_completions();\n");
+        }
+        code.push_str("        true\n");
+        code.push_str("    }\n\n");
+    }
+    
+    // State getter
+    code.push_str(&format!("    {} fn state(&self) -> {}State {{\n", vis, ir.name));
+    code.push_str("        self.state\n");
+    code.push_str("    }\n\n");
+    
+    // Context getter
+    code.push_str(&format!("    {} fn context(&self) -> &T {{\n", vis));
+    code.push_str("        &self.context\n");
+    code.push_str("    }\n\n");
+    
+    // Context mutable getter
+    code.push_str(&format!("    {} fn context_mut(&mut self) -> &mut T {{\n", vis));
+    code.push_str("        &mut self.context\n");
+    code.push_str("    }\n\n");
+
+    // Metrics getter
+    if options.metrics {
+        code.push_str("    /// Dispatch counters since `new()`\n");
+        code.push_str(&metrics::cfg_attribute("    "));
+        code.push_str(&format!("    {} fn metrics(&self) -> &{}Metrics {{\n", vis, ir.name));
+        code.push_str("        &self.metrics\n");
+        code.push_str("    }\n\n");
+    }
+    
+    // Event queue
+    if let Some(size) = queue_size {
+        code.push_str(&generate_event_queue(ir, options, size));
+    }
+    
+    // Process event (nothing to process without events)
+    if !ir.events.is_empty() {
+        code.push_str(&generate_process_event(ir, options));
+    }
+    if ir.rate_limited_arms() > 0 {
+        code.push('\n');
+        code.push_str(&generate_rate_limited());
+    }
+
+    // Timers
+    if !ir.timers.is_empty() {
+        code.push('\n');
+        code.push_str(&generate_advance_time(ir, options));
+    }
+    
+    // Completion transitions
+    let run_completions = completions::generate_run_completions(fsm, options);
+    if !run_completions.is_empty() {
+        code.push('\n');
+        code.push_str(&run_completions);
+    }
+    
+    code.push_str("}\n");
+    code
+}
+
+/// `post()`/`dispatch_pending()` over a fixed-size ring buffer of events
+fn generate_event_queue(ir: &FsmIr, options: &CodegenOptions, size: usize) -> String {
+    let mut code = String::new();
+    let vis = options.visibility.as_str();
+    
+    let event = format!("{}Event", ir.name);
+    match options.overflow_policy {
+        OverflowPolicy::Reject => {
+            code.push_str("    /// Queue an event for `dispatch_pending()`; gives the event back if the queue is full\n");
+            code.push_str(&format!("    {} fn post(&mut self, event: {}) -> Result<(), {}> {{\n", vis, event, event));
+            code.push_str(&format!("        if self.queue_len == {} {{\n", size));
+            code.push_str("            return Err(event);\n");
+            code.push_str("        }\n");
+        }
+        OverflowPolicy::DropOldest => {
+            code.push_str("    /// Queue an event for `dispatch_pending()`; if the queue is full the oldest queued\n");
+            code.push_str("    /// event is dropped to make room and returned\n");
+            code.push_str(&format!("    {} fn post(&mut self, event: {}) -> Option<{}> {{\n", vis, event, event));
+            code.push_str("        let mut dropped = None;\n");
+            code.push_str(&format!("        if self.queue_len == {} {{\n", size));
+            code.push_str("            dropped = self.queue[self.queue_head].take();\n");
+            code.push_str(&format!("            self.queue_head = (self.queue_head + 1) % {};\n", size));
+            code.push_str("            self.queue_len -= 1;\n");
+            code.push_str("        }\n");
+        }
+        OverflowPolicy::Panic => {
+            code.push_str("    /// Queue an event for `dispatch_pending()`\n");
+            code.push_str("    ///\n");
+            code.push_str("    /// # Panics\n");
+            code.push_str("    ///\n");
+            code.push_str(&format!("    /// If the queue already holds {} events\n", size));
+            code.push_str(&format!("    {} fn post(&mut self, event: {}) {{\n", vis, event));
+            code.push_str(&format!(
+                "        assert!(self.queue_len < {}, \"{} event queue full\");\n",
+                size, ir.name
+            ));
+        }
+        OverflowPolicy::Count => {
+            code.push_str("    /// Queue an event for `dispatch_pending()`; if the queue is full the event is\n");
+            code.push_str("    /// discarded and counted in `dropped_events()`\n");
+            code.push_str(&format!("    {} fn post(&mut self, event: {}) {{\n", vis, event));
+            code.push_str(&format!("        if self.queue_len == {} {{\n", size));
+            code.push_str("            self.dropped_events = self.dropped_events.saturating_add(1);\n");
+            code.push_str("            return;\n");
+            code.push_str("        }\n");
+        }
+    }
+    code.push_str(&format!("        let tail = (self.queue_head + self.queue_len) % {};\n", size));
+    code.push_str("        self.queue[tail] = Some(event);\n");
+    code.push_str("        self.queue_len += 1;\n");
+    if options.metrics {
+        code.push_str(&metrics::cfg_attribute("        "));
+        code.push_str("        self.metrics.record_queue_len(self.queue_len);\n");
+    }
+    match options.overflow_policy {
+        OverflowPolicy::Reject => code.push_str("        Ok(())\n"),
+        OverflowPolicy::DropOldest => code.push_str("        dropped\n"),
+        OverflowPolicy::Panic | OverflowPolicy::Count => {}
+    }
+    code.push_str("    }\n\n");
+
+    if options.overflow_policy == OverflowPolicy::Count {
+        code.push_str("    /// Events discarded by `post()` because the queue was full (saturates at `u32::MAX`)\n");
+        code.push_str(&format!("    {} fn dropped_events(&self) -> u32 {{\n", vis));
+        code.push_str("        self.dropped_events\n");
+        code.push_str("    }\n\n");
+    }
+    
+    code.push_str("    /// Process queued events in order; returns how many caused a transition\n");
+    code.push_str(&format!("    {} fn dispatch_pending(&mut self) -> usize {{\n", vis));
+    code.push_str("        let mut transitions = 0;\n");
+    code.push_str("        while self.queue_len > 0 {\n");
+    code.push_str("            let event = self.queue[self.queue_head].take();\n");
+    code.push_str(&format!("            self.queue_head = (self.queue_head + 1) % {};\n", size));
+    code.push_str("            self.queue_len -= 1;\n");
+    code.push_str("            if let Some(event) = event {\n");
+    match options.process_result {
+        true => code.push_str("                if self.process(event).is_handled() {\n"),
+        false => code.push_str("                if self.process(event) {\n"),
+    }
+    code.push_str("                    transitions += 1;\n");
+    code.push_str("                }\n");
+    code.push_str("            }\n");
+    code.push_str("        }\n");
+    code.push_str("        transitions\n");
+    code.push_str("    }\n\n");
+    
+    code
+}
+
+fn generate_process_event(ir: &FsmIr, options: &CodegenOptions) -> String {
+    let fsm = ir.fsm;
+    let mut code = String::new();
+    let result = format!("{}ProcessResult", ir.name);
+    
+    code.push_str("    /// Handle `event` in the current state\n    ///\n");
+    code.push_str(&action_order_doc(options.action_order, "    "));
+    if fsm.states.iter().any(|s| !s.internal_transitions.is_empty()) {
+        code.push_str("    ///\n");
+        code.push_str("    /// A state's own transitions, self-transitions included, are tried before its\n");
+        code.push_str("    /// internal transitions, which run only their actions\n");
+    }
+    match options.process_result {
+        true => code.push_str(&format!(
+            "    {} fn process(&mut self, event: {}Event) -> {} {{\n",
+            options.visibility.as_str(), ir.name, result
+        )),
+        false => code.push_str(&format!(
+            "    {} fn process(&mut self, event: {}Event) -> bool {{\n",
+            options.visibility.as_str(), ir.name
+        )),
+    }
+    if options.metrics {
+        code.push_str(&metrics::cfg_attribute("        "));
+        code.push_str("        self.metrics.count_event(event);\n");
+    }
+    code.push_str("        match (self.state, event) {\n");
+    
+    // A state's own transitions, then its internal transitions, then the wildcard
+    // (`* -->`) arms, like `interpreter::firing`
+    let first_wildcard = ir.arms.iter().position(|a| a.is_from_any_state());
+    for (index, arm) in ir.arms.iter().enumerate() {
+        let any_state = arm.is_from_any_state();
+        // The machine stays in a final state whatever the wildcards say
+        if Some(index) == first_wildcard && !ir.final_states.is_empty() {
+            let finals: Vec<String> = ir.final_states.iter().map(|s| format!("{}State::{}", ir.name, s)).collect();
+            let ignored = match options.process_result {
+                true => format!("{}::Ignored", result),
+                false => "false".to_string(),
+            };
+            code.push_str(&format!("            ({}, _) => {},\n", finals.join(" | "), ignored));
+        }
+        let source = match arm.source {
+            ArmSource::Any => "_".to_string(),
+            ArmSource::State(ref variant) => format!("{}State::{}", ir.name, variant),
+        };
+        let target = &arm.target;
+        let event_pattern = arm_event_pattern(ir, arm);
+        
+        // Check for guard ([else] is the unconditional arm after its guarded siblings)
+        if let Some(ref guard) = arm.guard {
+            code.push_str(&format!(
+                "            ({}, {}) if {} => {{{}\n",
+                source, event_pattern, rust_guard(guard, "self.context"),
+                trailing_comment(&arm.trailing_comment)
+            ));
+        } else {
+            code.push_str(&format!(
+                "            ({}, {}) => {{{}\n",
+                source, event_pattern, trailing_comment(&arm.trailing_comment)
+            ));
+        }
+        
+        if let Some((constraint, slot)) = arm.timing {
+            let debounce = constraint.kind == TimingKind::Debounce;
+            code.push_str(&format!(
+                "                if self.rate_limited({}, {}, {}) {{ // {}\n",
+                slot, constraint.window_ms, debounce, constraint
+            ));
+            match options.process_result {
+                true => code.push_str(&format!("                    return {}::Suppressed;\n", result)),
+                false => code.push_str("                    return false;\n"),
+            }
+            code.push_str("                }\n");
+        }
+
+        // Internal transitions only run their actions
+        if arm.internal {
+            for action in &arm.actions {
+                code.push_str(&format!("                {};\n", rust_statement(fsm, action, "self.context")));
+            }
+            match options.process_result {
+                true => code.push_str(&format!("                {}::InternalHandled\n", result)),
+                false => code.push_str("                true\n"),
+            }
+            code.push_str("            }\n");
+            continue;
+        }
+        
+        if any_state && (options.tracing_hook || options.process_result) {
+            code.push_str("                let from = self.state;\n");
+        }
+        if arm.exits_source {
+            code.push_str(&stop_timers(fsm, "                "));
+        }
+        
+        // Exit actions (a local transition stays inside its composite source) and the
+        // transition's actions in the chosen order, the state change, then entry actions
+        let mut exit = String::new();
+        if any_state {
+            exit.push_str(&generate_exit_dispatch(ir));
+        }
+        for action in arm.exit.iter().filter(|a| !(arm.exits_source && stops_timer(fsm, a))) {
+            exit.push_str(&format!("                {};\n", rust_statement(fsm, action, "self.context")));
+        }
+        let actions: String = arm
+            .actions
+            .iter()
+            .map(|action| format!("                {};\n", rust_statement(fsm, action, "self.context")))
+            .collect();
+        code.push_str(&exit_and_actions(options.action_order, exit, actions));
+        code.push_str(&format!(
+            "                self.state = {}State::{};\n",
+            ir.name, target
+        ));
+        for action in &arm.entry {
+            code.push_str(&format!("                {};\n", rust_statement(fsm, action, "self.context")));
+        }
+        if options.metrics && arm.enters_target {
+            code.push_str(&metrics::count_entry(fsm, &arm.target_name, "                "));
+        }
+        
+        // Tracing hook
+        let from = if any_state { "from" } else { source.as_str() };
+        if options.tracing_hook {
+            // The matched event itself when it carries a payload
+            let event = match ir.events.iter().any(|e| e.variant == arm.event && !e.params.is_empty()) {
+                true => "event".to_string(),
+                false => format!("{}Event::{}", ir.name, arm.event),
+            };
+            code.push_str(&format!(
+                "                self.context.on_transition({}, {}, {}State::{});\n",
+                from, event, ir.name, target
+            ));
+        }
+        let completions = match arm.enters_target {
+            true => completions::call_after_entry(fsm, &arm.target_name, "                "),
+            false => String::new(),
+        };
+        code.push_str(&completions);
+        
+        // A local self-transition stays in the state without exit or entry
+        match options.process_result {
+            true if !arm.exits_source && !arm.enters_target => {
+                code.push_str(&format!("                {}::InternalHandled\n", result))
+            }
+            true => {
+                // Completion transitions may have moved on from the target
+                let to = match completions.is_empty() {
+                    true => format!("{}State::{}", ir.name, target),
+                    false => "self.state".to_string(),
+                };
+                let from = match any_state {
+                    true => "from".to_string(),
+                    false => format!("from: {}", source),
+                };
+                code.push_str(&format!("                {}::Transitioned {{ {}, to: {} }}\n", result, from, to));
+            }
+            false => code.push_str("                true\n"),
+        }
+        code.push_str("            }\n");
+    }
+    
+    // Default case - no transition
+    match options.process_result {
+        true => {
+            code.push_str(&generate_guard_rejections(fsm));
+            code.push_str(&format!("            _ => {}::Ignored,\n", result));
+        }
+        false => code.push_str("            _ => false // No transition\n"),
+    }
+    code.push_str("        }\n");
+    code.push_str("    }\n");
+    
+    code
+}
+
+/// `rate_limited()`, the bookkeeping of the `@debounce` / `@throttle` arms of `process()`
+fn generate_rate_limited() -> String {
+    let mut code = String::new();
+    code.push_str("    /// Whether the transition with time slot `slot` is held back now: within\n");
+    code.push_str("    /// `window_ms` of the last event it saw (`debounce`, which restarts the window)\n");
+    code.push_str("    /// or of the last time it fired. `now_ms()` may wrap around\n");
+    code.push_str("    fn rate_limited(&mut self, slot: usize, window_ms: u32, debounce: bool) -> bool {\n");
+    code.push_str(&format!("        let now = self.context.{}();\n", CLOCK_METHOD));
+    code.push_str("        let too_soon = matches!(self.transition_times[slot], Some(last) if now.wrapping_sub(last) < window_ms);\n");
+    code.push_str("        if debounce || !too_soon {\n");
+    code.push_str("            self.transition_times[slot] = Some(now);\n");
+    code.push_str("        }\n");
+    code.push_str("        too_soon\n");
+    code.push_str("    }\n");
+    code
+}
+
+/// The event pattern of `arm`: the variant, binding the payload fields its guard and
+/// actions pass on
+fn arm_event_pattern(ir: &FsmIr, arm: &TransitionArm) -> String {
+    let variant = format!("{}Event::{}", ir.name, arm.event);
+    let Some(event) = ir.events.iter().find(|e| e.variant == arm.event && !e.params.is_empty()) else {
+        return variant;
+    };
+    let used: Vec<String> = arm
+        .bindings
+        .iter()
+        .filter(|(_, binding)| arm.uses_binding(binding))
+        .map(|(field, binding)| if field == binding { field.clone() } else { format!("{}: {}", field, binding) })
+        .collect();
+    match used.len() {
+        0 => format!("{} {{ .. }}", variant),
+        n if n < event.params.len() => format!("{} {{ {}, .. }}", variant, used.join(", ")),
+        _ => format!("{} {{ {} }}", variant, used.join(", ")),
+    }
+}
+
+/// `receiver.method(a, b)`, a trait method call passing its arguments (a getter's
+/// value read from the same receiver)
+pub(super) fn rust_call(call: &Call, receiver: &str) -> String {
+    let args: Vec<String> = call
+        .args
+        .iter()
+        .map(|arg| match arg {
+            Arg::Binding(name) | Arg::Literal(name) => name.clone(),
+            Arg::Getter(getter) => format!("{}.{}()", receiver, rust_ident(getter)),
+        })
+        .collect();
+    format!("{}.{}({})", receiver, rust_ident(&call.method), args.join(", "))
+}
+
+/// `call` as a statement of the machine's methods: the start or stop of a defined
+/// timer sets its slot in `self.timers`, anything else calls the trait method on
+/// `receiver` (see [`rust_call`])
+pub(super) fn rust_statement(fsm: &FsmDefinition, call: &Call, receiver: &str) -> String {
+    match timer_slot(fsm, call) {
+        Some((TimerOp::Start, timer)) => format!("self.timers[Self::{}] = Some(0)", timer_const(timer)),
+        Some((TimerOp::Stop, timer)) => format!("self.timers[Self::{}] = None", timer_const(timer)),
+        None => rust_call(call, receiver),
+    }
+}
+
+/// What `call` does to which defined timer; a timer action naming no `timer`
+/// definition stays a trait method
+fn timer_slot<'f>(fsm: &'f FsmDefinition, call: &Call) -> Option<(TimerOp, &'f str)> {
+    let (op, name) = call.timer.as_ref()?;
+    let timer = fsm.timers.iter().find(|t| &t.name == name)?;
+    Some((*op, timer.name.as_str()))
+}
+
+/// Whether `call` stops a defined timer, which leaving a state does anyway
+pub(super) fn stops_timer(fsm: &FsmDefinition, call: &Call) -> bool {
+    matches!(timer_slot(fsm, call), Some((TimerOp::Stop, _)))
+}
+
+/// `TIMER_KEEPALIVE`, the index of `timer` in `TIMERS`
+fn timer_const(timer: &str) -> String {
+    format!("TIMER_{}", to_snake_case(timer).to_uppercase())
+}
+
+/// `self.timers = [None; N];` at `indent`: leaving a state stops every timer (empty
+/// without timers)
+pub(super) fn stop_timers(fsm: &FsmDefinition, indent: &str) -> String {
+    match fsm.timers.len() {
+        0 => String::new(),
+        count => format!("{}self.timers = [None; {}];\n", indent, count),
+    }
+}
+
+/// The `timers` a new machine has: those its start actions start when `new()` runs
+/// them, else none
+fn initial_timers(ir: &FsmIr, options: &CodegenOptions) -> String {
+    let mut running = vec![false; ir.timers.len()];
+    if options.run_entry_on_new {
+        for (op, timer) in ir.start_actions.iter().filter_map(|a| timer_slot(ir.fsm, a)) {
+            if let Some(index) = ir.timers.iter().position(|t| t.name == timer) {
+                running[index] = op == TimerOp::Start;
+            }
+        }
+    }
+    match running.contains(&true) {
+        true => {
+            let slots: Vec<&str> = running.iter().map(|&r| if r { "Some(0)" } else { "None" }).collect();
+            format!("[{}]", slots.join(", "))
+        }
+        false => format!("[None; {}]", running.len()),
+    }
+}
+
+/// The timer table and `advance_time()`, which fires the timers as time passes
+fn generate_advance_time(ir: &FsmIr, options: &CodegenOptions) -> String {
+    let mut code = String::new();
+    code.push_str("    // Index of each timer in `TIMERS` and `timers`\n");
+    for (index, timer) in ir.timers.iter().enumerate() {
+        code.push_str(&format!("    const {}: usize = {};\n", timer_const(&timer.name), index));
+    }
+    code.push_str("    /// Duration in ms, event and whether it restarts after firing, of each timer\n");
+    code.push_str(&format!("    const TIMERS: [(u32, {}Event, bool); {}] = [\n", ir.name, ir.timers.len()));
+    for timer in &ir.timers {
+        // A zero period would expire forever at one instant
+        code.push_str(&format!(
+            "        ({}, {}Event::{}, {}), // {}\n",
+            timer.duration_ms.max(1), ir.name, timer.event, timer.periodic, timer.name
+        ));
+    }
+    code.push_str("    ];\n\n");
+
+    code.push_str("    /// Let `ms` milliseconds pass: each running timer that expires has its event\n");
+    code.push_str("    /// processed right away, in the order they expire (the first defined on a tie).\n");
+    code.push_str("    /// A periodic timer fires once per period until a state it runs in is left; a\n");
+    code.push_str("    /// one-shot timer stops. Returns how many timer events were processed\n");
+    code.push_str(&format!("    {} fn advance_time(&mut self, ms: u32) -> usize {{\n", options.visibility.as_str()));
+    code.push_str("        let mut left = ms;\n");
+    code.push_str("        let mut fired = 0;\n");
+    code.push_str("        loop {\n");
+    code.push_str("            // The running timer due soonest\n");
+    code.push_str("            let next = self\n");
+    code.push_str("                .timers\n");
+    code.push_str("                .iter()\n");
+    code.push_str("                .enumerate()\n");
+    code.push_str("                .filter_map(|(index, elapsed)| elapsed.map(|e| (index, Self::TIMERS[index].0.saturating_sub(e))))\n");
+    code.push_str("                .min_by_key(|&(_, due)| due);\n");
+    code.push_str("            let (index, due) = match next {\n");
+    code.push_str("                Some((index, due)) if due <= left => (index, due),\n");
+    code.push_str("                _ => break,\n");
+    code.push_str("            };\n");
+    code.push_str("            left -= due;\n");
+    code.push_str("            for elapsed in self.timers.iter_mut().flatten() {\n");
+    code.push_str("                *elapsed = elapsed.saturating_add(due);\n");
+    code.push_str("            }\n");
+    code.push_str("            let (_, event, periodic) = Self::TIMERS[index];\n");
+    code.push_str("            self.timers[index] = if periodic { Some(0) } else { None };\n");
+    code.push_str("            let _ = self.process(event);\n");
+    code.push_str("            fired += 1;\n");
+    code.push_str("        }\n");
+    code.push_str("        for elapsed in self.timers.iter_mut().flatten() {\n");
+    code.push_str("            *elapsed = elapsed.saturating_add(left);\n");
+    code.push_str("        }\n");
+    code.push_str("        fired\n");
+    code.push_str("    }\n");
+    code
+}
+
+/// `guard` as a Rust condition, calling its terms on `receiver`
+pub(super) fn rust_guard(guard: &GuardIr, receiver: &str) -> String {
+    guard.render(&mut |term| match term {
+        GuardTerm::Call(call) => rust_call(call, receiver),
+        GuardTerm::Named(method) => format!("{}.{}()", receiver, rust_ident(method)),
+    })
+}
+
+/// `GuardRejected` arms for the events whose every transition out of the current
+/// state (or out of any state, for a wildcard) is guarded, after all the handling arms
+fn generate_guard_rejections(fsm: &FsmDefinition) -> String {
+    let mut code = String::new();
+    let rejected = |source: &str, event: &str| {
+        let payload = if fsm.event_params(event).is_empty() { "" } else { " { .. }" };
+        format!(
+            "            ({}, {}Event::{}{}) => {}ProcessResult::GuardRejected,\n",
+            source, fsm.name, to_pascal_case(event), payload, fsm.name
+        )
+    };
+    for event in collect_event_names(fsm) {
+        let wildcards: Vec<&Transition> = fsm
+            .transitions
+            .iter()
+            .filter(|t| t.is_from_any_state() && t.event.as_ref().is_some_and(|e| e.name == event))
+            .filter(|t| !wildcard_shadowed(fsm, t))
+            .collect();
+        if !wildcards.is_empty() {
+            // Any state's own guards fall through to the wildcard's
+            if wildcards.iter().all(|t| has_guard(t)) {
+                code.push_str(&rejected("_", &event));
+            }
+            continue;
+        }
+        for state in fsm.states.iter().filter(|s| !s.is_final()) {
+            let mut handlers = handlers_of(fsm, state, &event).peekable();
+            if handlers.peek().is_some() && handlers.all(has_guard) {
+                code.push_str(&rejected(&format!("{}State::{}", fsm.name, to_pascal_case(&state.name)), &event));
+            }
+        }
+    }
+    code
+}
+
+/// `match self.state` running the exit actions of whichever state a wildcard
+/// transition leaves (nothing if no state has exit actions)
+fn generate_exit_dispatch(ir: &FsmIr) -> String {
+    let mut code = String::new();
+    // Stopping timers needs no dispatch: leaving any state stops them all
+    let exiting: Vec<&StateIr> = ir.states.iter().filter(|s| s.exit.iter().any(|a| !stops_timer(ir.fsm, a))).collect();
+    if exiting.is_empty() {
+        return code;
+    }
+    let exit_calls = |state: &StateIr, indent: &str| {
+        state.exit.iter().filter(|a| !stops_timer(ir.fsm, a)).map(|a| format!("{}{};\n", indent, rust_statement(ir.fsm, a, "self.context"))).collect::<String>()
+    };
+    // One state with exit actions among others: an `if` (a one-arm match is a clippy lint)
+    if let [state] = exiting[..] {
+        if ir.states.len() > 1 {
+            code.push_str(&format!("                if self.state == {}State::{} {{\n", ir.name, state.variant));
+            code.push_str(&exit_calls(state, "                    "));
+            code.push_str("                }\n");
+            return code;
+        }
+    }
+    code.push_str("                match self.state {\n");
+    for state in &exiting {
+        code.push_str(&format!("                    {}State::{} => {{\n", ir.name, state.variant));
+        code.push_str(&exit_calls(state, "                        "));
+        code.push_str("                    }\n");
+    }
+    if exiting.len() < ir.states.len() {
+        code.push_str("                    _ => {}\n");
+    }
+    code.push_str("                }\n");
+    code
+}
+
+fn generate_action_trait(fsm: &FsmDefinition, options: &CodegenOptions) -> String {
+    let mut code = String::new();
+    
+    let methods = rust_trait_methods(fsm, options);
+    let sorted = |role: MethodRole| {
+        let mut of_role: Vec<_> = methods.iter().filter(|m| m.role == role).collect();
+        of_role.sort_by(|a, b| a.name.cmp(&b.name));
+        of_role.dedup_by(|a, b| a.name == b.name);
+        of_role
+    };
+    
+    code.push_str(&format!("{} trait {}Actions {{\n", options.visibility.as_str(), fsm.name));
+    
+    for method in sorted(MethodRole::Action).into_iter().chain(sorted(MethodRole::Guard)).chain(sorted(MethodRole::Getter)) {
+        if method.role == MethodRole::Getter && method.name == CLOCK_METHOD {
+            code.push_str("    /// Milliseconds of a free-running clock (it may wrap), for `@debounce` / `@throttle`\n");
+        }
+        code.push_str(&method_docs(fsm, &method.name));
+        code.push_str(&format!("    {};\n", method.signature()));
+    }
+
+    // Named guards are provided, so each definition lives in one place
+    for named in named_guards(fsm) {
+        code.push_str(&format!("\n    /// `{}`\n", named.expression));
+        code.push_str(&method_docs(fsm, &named.method));
+        code.push_str(&format!("    fn {}(&self) -> bool {{\n", rust_ident(&named.method)));
+        code.push_str(&format!("        {}\n", rust_guard(&named.guard, "self")));
+        code.push_str("    }\n");
+    }
+    
+    if options.tracing_hook {
+        code.push_str("\n    /// Called after every successful transition (e.g. for defmt/RTT logging)\n");
+        code.push_str(&format!(
+            "    fn on_transition(&mut self, _from: {}State, _event: {}Event, _to: {}State) {{}}\n",
+            fsm.name, fsm.name, fsm.name
+        ));
+    }
+    
+    code.push_str("}\n");
+    
+    code
+}
+
+/// Descriptions of the documented transitions that call a trait method, as doc comments
+pub(super) fn method_docs(fsm: &FsmDefinition, method: &str) -> String {
+    let mut descriptions: Vec<&str> = Vec::new();
+    for transition in &fsm.transitions {
+        let Some(ref desc) = transition.description else {
+            continue;
+        };
+        let calls = transition.actions.iter().any(|a| to_snake_case(&a.name) == method)
+            || transition
+                .guard
+                .as_ref()
+                .is_some_and(|g| !g.is_else() && g.terms().iter().any(|term| to_snake_case(&term.method()) == method));
+        if calls && !descriptions.contains(&desc.as_str()) {
+            descriptions.push(desc);
+        }
+    }
+    doc_comment(&descriptions.join("\n\n"), "    ")
+}
+
+/// Summary of every actions trait method and what uses it
+fn generate_method_summary(fsm: &FsmDefinition, options: &CodegenOptions) -> String {
+    let mut methods = rust_trait_methods(fsm, options);
+    if methods.is_empty() {
+        return String::new();
+    }
+    methods.sort_by(|a, b| a.name.cmp(&b.name));
+    
+    let mut code = String::new();
+    code.push_str(&format!("//! {}Actions methods:\n", fsm.name));
+    for method in &methods {
+        code.push_str(&format!("//! - `{}`: {}\n", method.signature(), method.used_by.join(", ")));
+    }
+    code
+}
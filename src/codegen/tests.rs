@@ -0,0 +1,2218 @@
+//! Unit tests for the code generator
+
+use crate::codegen::autogen::{
+    autogen_files, content_hash, dsl_hash, remove_stale_files, write_autogen_folder, ExportManifest, ManifestMismatch,
+    MANIFEST_FILE,
+};
+use crate::codegen::check::{
+    cargo_available, check_generated_code, parse_cargo_messages, scaffold_manifest, CheckError, DiagnosticLevel,
+};
+use crate::codegen::dot::{generate_dot, generate_dot_with_direction};
+use crate::codegen::backend::BackendRegistry;
+use crate::codegen::bindings::{BindingReport, Bindings, BindingsError};
+use crate::codegen::c::split_c_files;
+use crate::codegen::ir::{methods_for_state, methods_for_transition, ArmSource, Call, FsmIr, GuardTerm};
+use crate::codegen::{
+    code_sections, generate_plantuml, generate_rust_code, generate_rust_code_with_options, generate_rust_code_with_target, generate_with_backend, try_generate_rust_code_with_options,
+    try_generate_rust_code_with_target, AsyncActions, CodegenError, CodegenOptions, CodegenTarget, EventOrder, MethodRole,
+    OverflowPolicy, Visibility,
+};
+use crate::fsm::guards::GuardExpr;
+use crate::fsm::{LayoutHint, StateType};
+use crate::parser::parse_fsm;
+
+const TRAFFIC_LIGHT: &str = r#"
+    fsm TrafficLight {
+        [*] --> Red
+        state Red
+        state Yellow
+        state Green
+        Red --> Green : timer_expired
+        Green --> Yellow : timer_expired
+        Yellow --> Red : timer_expired
+    }
+"#;
+
+#[test]
+fn test_state_and_event_as_str() {
+    let fsms = parse_fsm(TRAFFIC_LIGHT).expect("Should parse successfully");
+    let code = generate_rust_code(&fsms[0]);
+
+    assert!(code.contains("impl TrafficLightState {"));
+    assert!(code.contains("pub const fn as_str(&self) -> &'static str"));
+    assert!(code.contains("TrafficLightState::Red => \"Red\""));
+    assert!(code.contains("TrafficLightState::Yellow => \"Yellow\""));
+    assert!(code.contains("TrafficLightState::Green => \"Green\""));
+    assert!(code.contains("TrafficLightEvent::TimerExpired => \"timer_expired\""));
+}
+
+#[test]
+fn test_tracing_hook_disabled_by_default() {
+    let fsms = parse_fsm(TRAFFIC_LIGHT).expect("Should parse successfully");
+    let code = generate_rust_code(&fsms[0]);
+
+    assert!(!code.contains("on_transition"));
+}
+
+#[test]
+fn test_tracing_hook_enabled() {
+    let fsms = parse_fsm(TRAFFIC_LIGHT).expect("Should parse successfully");
+    let options = CodegenOptions::new().with_tracing_hook(true);
+    let code = generate_rust_code_with_options(&fsms[0], CodegenTarget::Standard, &options);
+
+    assert!(code.contains(
+        "fn on_transition(&mut self, _from: TrafficLightState, _event: TrafficLightEvent, _to: TrafficLightState) {}"
+    ));
+    assert!(code.contains(
+        "self.context.on_transition(TrafficLightState::Red, TrafficLightEvent::TimerExpired, TrafficLightState::Green);"
+    ));
+}
+
+#[test]
+fn test_method_summary_lists_uses() {
+    let source = r#"
+        fsm Door {
+            [*] --> Locked
+            state Locked {
+                entry / engage_lock()
+            }
+            Locked --> Unlocked : code [is_valid] / log_event()
+            Unlocked --> Locked : lock / log_event()
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let code = generate_rust_code(&fsms[0]);
+
+    assert!(code.contains("//! DoorActions methods:"));
+    assert!(code.contains("//! - `fn engage_lock(&mut self)`: entry of Locked"));
+    assert!(code.contains("//! - `fn is_valid(&self) -> bool`: Locked --> Unlocked : code [is_valid] / log_event"));
+    assert!(code.contains(
+        "//! - `fn log_event(&mut self)`: Locked --> Unlocked : code [is_valid] / log_event, Unlocked --> Locked : lock / log_event"
+    ));
+}
+
+#[test]
+fn test_guard_action_role_collision() {
+    let source = r#"
+        fsm Test {
+            [*] --> A
+            A --> B : go [ready]
+            B --> A : back / ready()
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let errors = try_generate_rust_code_with_target(&fsms[0], CodegenTarget::Standard).unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(&errors[0], CodegenError::RoleCollision { name, .. } if name == "ready"));
+}
+
+#[test]
+fn test_action_signature_collision() {
+    let source = r#"
+        fsm Test {
+            [*] --> A
+            A --> B : go / log_error(code)
+            B --> A : back / log_error()
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let errors = try_generate_rust_code_with_target(&fsms[0], CodegenTarget::Standard).unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        &errors[0],
+        CodegenError::SignatureCollision { name, first_count: 1, second_count: 0, .. } if name == "log_error"
+    ));
+}
+
+#[test]
+fn test_hook_collision() {
+    let source = r#"
+        fsm Test {
+            [*] --> A
+            A --> B : go / on_transition()
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+
+    assert!(try_generate_rust_code_with_target(&fsms[0], CodegenTarget::Standard).is_ok());
+
+    let options = CodegenOptions::new().with_tracing_hook(true);
+    let errors = try_generate_rust_code_with_options(&fsms[0], CodegenTarget::Standard, &options).unwrap_err();
+    assert!(matches!(&errors[0], CodegenError::RoleCollision { second_role: MethodRole::Hook, .. }));
+}
+
+#[test]
+fn test_transition_actions_emitted_in_order() {
+    let source = r#"
+        fsm Motor {
+            [*] --> Running
+            Running --> Stopped : halt / stop_timer; log_event; notify_host
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let code = generate_rust_code(&fsms[0]);
+
+    let stop = code.find("self.context.stop_timer();").unwrap();
+    let log = code.find("self.context.log_event();").unwrap();
+    let notify = code.find("self.context.notify_host();").unwrap();
+    assert!(stop < log && log < notify);
+}
+
+#[test]
+fn test_default_options_keep_previous_output() {
+    let fsms = parse_fsm(TRAFFIC_LIGHT).expect("Should parse successfully");
+    let code = generate_rust_code(&fsms[0]);
+
+    assert!(code.contains("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\npub enum TrafficLightState {"));
+    assert!(code.contains("pub struct TrafficLight<T: TrafficLightActions> {"));
+    assert!(!code.contains("#![no_std]"));
+    assert!(!code.contains("#[repr(u8)]"));
+    assert!(!code.contains("fn post("));
+}
+
+#[test]
+fn test_serde_derives_option() {
+    let fsms = parse_fsm(TRAFFIC_LIGHT).expect("Should parse successfully");
+    let options = CodegenOptions::new().with_serde_derives(true);
+    let code = generate_rust_code_with_options(&fsms[0], CodegenTarget::Standard, &options);
+
+    assert_eq!(
+        code.matches("#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]\n")
+            .count(),
+        2
+    );
+    assert!(options.has_serde_derives());
+    assert!(!options.with_serde_derives(false).has_serde_derives());
+}
+
+#[test]
+fn test_no_std_and_style_options() {
+    let fsms = parse_fsm(TRAFFIC_LIGHT).expect("Should parse successfully");
+    let options = CodegenOptions::new()
+        .with_no_std(true)
+        .with_repr_u8(true)
+        .with_visibility(Visibility::Crate)
+        .with_doc_header("Traffic controller\n\nSee the design doc.");
+    let code = generate_rust_code_with_options(&fsms[0], CodegenTarget::Standard, &options);
+
+    assert!(code.contains("\n#![no_std]\n"));
+    assert!(code.find("#![no_std]").unwrap() > code.rfind("//!").unwrap());
+    assert!(code.contains("#[repr(u8)]\npub(crate) enum TrafficLightState {"));
+    assert!(code.contains("pub(crate) trait TrafficLightActions {"));
+    assert!(code.contains("    pub(crate) fn process(&mut self, event: TrafficLightEvent) -> bool {"));
+    assert!(!code.contains("\npub "));
+    assert!(code.contains("//! Traffic controller\n//!\n//! See the design doc.\n"));
+}
+
+#[test]
+fn test_event_queue_option() {
+    let fsms = parse_fsm(TRAFFIC_LIGHT).expect("Should parse successfully");
+    let options = CodegenOptions::new().with_event_queue_size(Some(8));
+    let code = generate_rust_code_with_options(&fsms[0], CodegenTarget::Standard, &options);
+
+    assert!(code.contains("    queue: [Option<TrafficLightEvent>; 8],\n"));
+    assert!(code.contains("            queue: [None; 8],\n"));
+    assert!(code.contains("pub fn post(&mut self, event: TrafficLightEvent) -> Result<(), TrafficLightEvent> {"));
+    assert!(code.contains("pub fn dispatch_pending(&mut self) -> usize {"));
+}
+
+#[test]
+fn test_overflow_policy_golden() {
+    let fsms = parse_fsm(include_str!("../../examples/builtin/traffic_light.fsm")).expect("Should parse successfully");
+    let options = CodegenOptions::new()
+        .with_event_queue_size(Some(4))
+        .with_overflow_policy(OverflowPolicy::Count);
+    let code = generate_rust_code_with_options(&fsms[0], CodegenTarget::Standard, &options);
+
+    assert_eq!(code, include_str!("../../examples/builtin/traffic_light_queue_count.rs"));
+}
+
+/// Start actions run in `start()` before the initial state's entry actions
+#[test]
+fn test_start_actions_golden() {
+    let source = r#"
+        fsm CoolantPump {
+            [*] --> Idle : / load_config; self_test
+            state Idle {
+                entry / close_valve
+            }
+            Idle --> Pumping : start / open_valve
+            Pumping --> Idle : stop
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+
+    assert_eq!(generate_rust_code(&fsms[0]), include_str!("../../examples/builtin/coolant_pump_start.rs"));
+
+    // The constructor runs them instead when asked to, and there is no `start()`
+    let options = CodegenOptions::new().with_run_entry_on_new(true);
+    let code = generate_rust_code_with_options(&fsms[0], CodegenTarget::Standard, &options);
+    assert!(code.contains(
+        "    pub fn new(mut context: T) -> Self {\n        context.load_config();\n        context.self_test();\n        context.close_valve();\n"
+    ));
+    assert!(!code.contains("fn start(") && !code.contains("started"));
+    assert!(generate_dot(&fsms[0]).contains("    \"[*]\" -> \"Idle\" [label=\"/ load_config; self_test\"];\n"));
+}
+
+#[test]
+fn test_overflow_policies() {
+    let fsms = parse_fsm(TRAFFIC_LIGHT).expect("Should parse successfully");
+    let generate = |policy| {
+        let options = CodegenOptions::new()
+            .with_event_queue_size(Some(2))
+            .with_overflow_policy(policy)
+            .with_shared_events(true);
+        generate_rust_code_with_options(&fsms[0], CodegenTarget::Standard, &options)
+    };
+
+    let drop_oldest = generate(OverflowPolicy::DropOldest);
+    assert!(drop_oldest.contains("pub fn post(&mut self, event: TrafficLightEvent) -> Option<TrafficLightEvent> {"));
+    assert!(drop_oldest.contains("            dropped = self.queue[self.queue_head].take();\n"));
+    assert!(drop_oldest.contains("pub fn post_shared(&mut self, event: super::events::SharedEvent) -> Option<super::events::SharedEvent> {"));
+
+    let panic = generate(OverflowPolicy::Panic);
+    assert!(panic.contains("    /// # Panics\n"));
+    assert!(panic.contains("        assert!(self.queue_len < 2, \"TrafficLight event queue full\");\n"));
+    assert!(!panic.contains("dropped_events"));
+
+    // The default keeps the original `post()`
+    assert_eq!(generate(OverflowPolicy::Reject), generate(OverflowPolicy::default()));
+}
+
+#[test]
+fn test_local_transition_skips_exit_and_entry() {
+    let source = r#"
+        fsm Machine {
+            [*] --> Running
+            state Running {
+                entry / power_up
+                exit / power_down
+            }
+            Running --> Running : local reset / clear
+            Running --> Running : reboot
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let code = generate_rust_code(&fsms[0]);
+
+    assert!(code.contains(
+        "            (MachineState::Running, MachineEvent::Reset) => {\n\
+         \x20               self.context.clear();\n\
+         \x20               self.state = MachineState::Running;\n\
+         \x20               true\n"
+    ));
+    assert!(code.contains(
+        "            (MachineState::Running, MachineEvent::Reboot) => {\n\
+         \x20               self.context.power_down();\n"
+    ));
+}
+
+#[test]
+fn test_else_guard_is_final_unconditional_arm() {
+    let source = r#"
+        fsm Pump {
+            [*] --> Idle
+            Idle --> Fast : go [is_urgent]
+            Idle --> Slow : go [else]
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let code = generate_rust_code(&fsms[0]);
+
+    let guarded = code.find("(PumpState::Idle, PumpEvent::Go) if self.context.is_urgent").unwrap();
+    let fallback = code.find("(PumpState::Idle, PumpEvent::Go) => {").unwrap();
+    assert!(guarded < fallback);
+    assert!(!code.contains("fn else"));
+
+    // Declared before its guarded sibling, the `[else]` arm still comes after it
+    let source = r#"
+        fsm Pump {
+            [*] --> Idle
+            state Idle {
+                poll [else] / wait
+                poll [is_urgent] / hurry
+            }
+            Idle --> Slow : go [else]
+            Idle --> Fast : go [is_urgent]
+        }
+    "#;
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    let code = generate_rust_code(fsm);
+    let guarded = code.find("(PumpState::Idle, PumpEvent::Go) if self.context.is_urgent").unwrap();
+    let fallback = code.find("(PumpState::Idle, PumpEvent::Go) => {").unwrap();
+    assert!(guarded < fallback, "{}", code);
+    let guarded = code.find("(PumpState::Idle, PumpEvent::Poll) if self.context.is_urgent").unwrap();
+    let fallback = code.find("(PumpState::Idle, PumpEvent::Poll) => {").unwrap();
+    assert!(guarded < fallback, "{}", code);
+}
+
+#[test]
+fn test_descriptions_emitted_as_doc_comments() {
+    let source = r#"
+        /// Intersection controller
+        fsm TrafficLight {
+            [*] --> Red
+            state Red : Stop - Wait for green
+            /// Proceed with caution
+            state Yellow
+            state Green
+            /// Daytime cycle
+            Red --> Green : timer_expired [day_mode]
+            Green --> Yellow : timer_expired
+            /// Back to stop
+            Yellow --> Red : timer_expired / log_cycle
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let code = generate_rust_code(&fsms[0]);
+
+    assert!(code.contains("    /// Stop - Wait for green\n    Red,\n"));
+    assert!(code.contains("    /// Proceed with caution\n    Yellow,\n"));
+    assert!(code.contains("    Green,\n"));
+    assert!(code.contains("/// Intersection controller\npub struct TrafficLight<"));
+    assert!(code.contains("    /// Back to stop\n    fn log_cycle(&mut self);\n"));
+    assert!(code.contains("    /// Daytime cycle\n    fn day_mode(&self) -> bool;\n"));
+}
+
+#[test]
+fn test_declared_events_keep_order_and_docs() {
+    let source = r#"
+        fsm Door {
+            events {
+                /// Request to close
+                close
+                open
+            }
+            [*] --> Opened
+            Opened --> Closed : close
+            Closed --> Opened : open
+            Closed --> Locked : lock
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let code = generate_rust_code(&fsms[0]);
+
+    // Declared events in declaration order, then the inferred ones
+    assert!(code.contains("pub enum DoorEvent {\n    /// Request to close\n    Close,\n    Open,\n    Lock,\n}\n"), "{}", code);
+}
+
+#[test]
+fn test_trailing_comments_emitted() {
+    let source = r#"
+        fsm Door {
+            [*] --> Locked
+            state Locked // default after power-up
+            Locked --> Alarming : invalid_key [attempts > 3] // ticket ABC-123
+            Locked --> Unlocked : valid_key
+            Alarming --> Locked : reset
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let code = generate_rust_code(&fsms[0]);
+
+    let arm = code
+        .lines()
+        .find(|l| l.trim_start().starts_with("(DoorState::Locked, DoorEvent::InvalidKey) if "))
+        .expect("guarded arm");
+    assert!(arm.ends_with("=> { // ticket ABC-123"));
+    assert!(code.contains("(DoorState::Locked, DoorEvent::ValidKey) => {\n"));
+    assert!(code.contains("    Locked, // default after power-up\n"));
+    assert!(code.contains("    Alarming,\n"));
+}
+
+/// DOT output of the built-in examples, compared with the `.dot` files next to them
+#[test]
+fn test_dot_golden_examples() {
+    let examples = [
+        (include_str!("../../examples/builtin/traffic_light.fsm"), include_str!("../../examples/builtin/traffic_light.dot")),
+        (include_str!("../../examples/builtin/door_lock.fsm"), include_str!("../../examples/builtin/door_lock.dot")),
+        (include_str!("../../examples/builtin/vending_machine.fsm"), include_str!("../../examples/builtin/vending_machine.dot")),
+    ];
+    for (source, golden) in examples {
+        let fsms = parse_fsm(source).expect("Should parse successfully");
+        assert_eq!(generate_dot(&fsms[0]), golden);
+    }
+}
+
+#[test]
+fn test_dot_choice_points_and_escaping() {
+    let source = r#"
+        fsm Checkout {
+            [*] --> Cart
+            state Paid {
+                entry / send_receipt
+            }
+            state Rejected
+            Cart --> <<CheckFunds>> : pay
+            choice CheckFunds {
+                [balance >= total] -> Paid / charge()
+                [else] -> Rejected
+            }
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let dot = generate_dot_with_direction(&fsms[0], LayoutHint::LR);
+
+    assert!(dot.starts_with("digraph \"Checkout\" {\n    rankdir=LR;\n"));
+    assert!(dot.contains("    \"Paid\" [label=\"Paid|entry / send_receipt\\l\"];\n"));
+    assert!(dot.contains("    \"<<CheckFunds>>\" [shape=diamond, label=\"CheckFunds\"];\n"));
+    assert!(dot.contains("    \"Cart\" -> \"<<CheckFunds>>\" [label=\"pay\"];\n"));
+    assert!(dot.contains("    \"<<CheckFunds>>\" -> \"Paid\" [label=\"[balance >= total] / charge\"];\n"));
+    assert!(dot.contains("    \"<<CheckFunds>>\" -> \"Rejected\" [label=\"[else]\"];\n"));
+
+    let mut fsm = fsms[0].clone();
+    fsm.states[0].name = "Say \"hi\" | {now}".to_string();
+    let dot = generate_dot(&fsm);
+    assert!(dot.contains("    \"Say \\\"hi\\\" | {now}\" [label=\"{Say \\\"hi\\\" \\| \\{now\\}}\"];\n"));
+}
+
+/// PlantUML output of the built-in examples, compared with the `.puml` files next to them
+#[test]
+fn test_plantuml_golden_examples() {
+    let examples = [
+        (include_str!("../../examples/builtin/traffic_light.fsm"), include_str!("../../examples/builtin/traffic_light.puml")),
+        (include_str!("../../examples/builtin/door_lock.fsm"), include_str!("../../examples/builtin/door_lock.puml")),
+        (include_str!("../../examples/builtin/vending_machine.fsm"), include_str!("../../examples/builtin/vending_machine.puml")),
+    ];
+    for (source, golden) in examples {
+        let fsms = parse_fsm(source).expect("Should parse successfully");
+        assert_eq!(generate_plantuml(&fsms[0]), golden);
+    }
+}
+
+#[test]
+fn test_plantuml_choice_points_finals_and_escaping() {
+    let source = r#"
+        fsm Checkout {
+            direction LR
+            [*] --> Cart
+            state Paid {
+                entry / send_receipt
+            }
+            state Rejected
+            Cart --> <<CheckFunds>> : pay
+            Paid --> [*]
+            choice CheckFunds {
+                [balance < total] -> Rejected
+                [else] -> Paid / charge()
+            }
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let uml = generate_plantuml(&fsms[0]);
+
+    assert!(uml.starts_with("@startuml Checkout\nhide empty description\nleft to right direction\n"));
+    assert!(uml.ends_with("@enduml\n"));
+    assert!(uml.contains("state Paid\nPaid : entry / send_receipt\n"));
+    assert!(uml.contains("state CheckFunds <<choice>>\n"));
+    assert!(uml.contains("[*] --> Cart\n"));
+    assert!(uml.contains("Cart --> CheckFunds : pay\n"));
+    assert!(uml.contains("Paid --> [*]\n"));
+    assert!(uml.contains("CheckFunds --> Rejected : [balance ~< total]\n"));
+    assert!(uml.contains("CheckFunds --> Paid : [else] / charge\n"));
+
+    // Final states get a `--> [*]` even without the transition; odd names get an alias
+    let mut fsm = fsms[0].clone();
+    fsm.transitions.retain(|t| t.target != "[*]");
+    fsm.states.iter_mut().find(|s| s.name == "Paid").unwrap().state_type = StateType::Final;
+    fsm.states.iter_mut().find(|s| s.name == "Rejected").unwrap().name = "Say \"hi\"".to_string();
+    let uml = generate_plantuml(&fsm);
+    assert!(uml.contains("Paid --> [*]\n"));
+    let index = fsm.states.iter().position(|s| s.name.starts_with("Say")).unwrap();
+    assert!(uml.contains(&format!("state \"Say &#34;hi&#34;\" as state_{}\n", index)));
+}
+
+#[test]
+fn test_fsm_metadata_in_header_and_exports() {
+    let source = r#"
+        fsm Pump {
+            description "Controls the coolant pump"
+            meta version = "1.2"
+            meta author = "Ada"
+            [*] --> Idle
+            Idle --> Running : start
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+
+    let code = generate_rust_code(&fsms[0]);
+    assert!(code.starts_with(
+        "//! Auto-generated FSM: Pump\n//! Generated by Oxidate\n//!\n//! Controls the coolant pump\n//!\n//! - author: Ada\n//! - version: 1.2\n"
+    ));
+    let dot = generate_dot(&fsms[0]);
+    assert!(dot.starts_with("// Controls the coolant pump\n// author: Ada\n// version: 1.2\ndigraph \"Pump\" {\n"));
+    let uml = generate_plantuml(&fsms[0]);
+    assert!(uml.starts_with("@startuml Pump\n' Controls the coolant pump\n' author: Ada\n' version: 1.2\n"));
+}
+
+#[test]
+fn test_invalid_names_rejected_unless_lenient() {
+    let source = r#"
+        fsm Lamp {
+            [*] --> Off
+            Off --> On : press [power + 1 > 0] / turnOnLed
+            On --> Off : press / self
+        }
+    "#;
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+
+    let errors = try_generate_rust_code_with_options(fsm, CodegenTarget::Standard, &CodegenOptions::new()).unwrap_err();
+    let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        [
+            "guard `power + 1 > 0` (line 4) becomes `power_+_1_>_0`, which is not a valid Rust identifier (try `power_1_0`)",
+            "action `self` (line 5) becomes the Rust keyword `self`, which cannot be used even as a raw identifier (try `r_self`)",
+        ]
+    );
+
+    let options = CodegenOptions::new().with_lenient_names(true);
+    let code = try_generate_rust_code_with_options(fsm, CodegenTarget::Standard, &options).expect("lenient codegen");
+    assert!(code.contains("    fn power_1_0(&self) -> bool;\n"));
+    assert!(code.contains("    fn r_self(&mut self);\n"));
+    // Valid names are untouched
+    assert!(code.contains("    fn turn_on_led(&mut self);\n"));
+}
+
+#[test]
+fn test_keyword_and_unicode_names() {
+    let source = r#"
+        fsm Wächter {
+            [*] --> Überwachung
+            Überwachung --> Loop : match [loop] / type
+            Loop --> Überwachung : ruhe [bereit && async] / move(ziel)
+            state Loop {
+                entry / zählen
+            }
+        }
+    "#;
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    let code = generate_rust_code(fsm);
+    assert!(code.contains("pub enum WächterState {\n    Überwachung,\n    Loop,\n"));
+    assert!(code.contains("    Match,\n"));
+    // Methods named after keywords are declared and called raw
+    assert!(code.contains("    fn r#type(&mut self);\n"));
+    assert!(code.contains("    fn r#move(&mut self, ziel: u32);\n"));
+    assert!(code.contains("    fn r#loop(&self) -> bool;\n"));
+    assert!(code.contains("    fn zählen(&mut self);\n"));
+    assert!(code.contains("self.context.r#loop()"));
+    assert!(code.contains("self.context.bereit() && self.context.r#async()"));
+    assert!(code.contains("self.context.r#move(self.context.ziel());"));
+
+    // `Self` has no raw form, as a variant or a method
+    let fsm = &parse_fsm("fsm Odd {\n [*] --> Self\n Self --> Idle : go / super\n}").unwrap()[0];
+    let errors = try_generate_rust_code_with_options(fsm, CodegenTarget::Standard, &CodegenOptions::new()).unwrap_err();
+    let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        messages,
+        [
+            "state `Self` (line 2) becomes the Rust keyword `Self`, which cannot be used even as a raw identifier (try `r_self`)",
+            "action `super` (line 3) becomes the Rust keyword `super`, which cannot be used even as a raw identifier (try `r_super`)",
+        ]
+    );
+}
+
+#[test]
+fn test_autogen_files() {
+    let mut fsms = parse_fsm(TRAFFIC_LIGHT).expect("Should parse successfully");
+    fsms.extend(parse_fsm("fsm DoorLock {\n [*] --> Locked\n Locked --> Open : unlock [code * 2 > 3]\n}").unwrap());
+
+    let files = autogen_files(&fsms, CodegenTarget::Standard, &CodegenOptions::new()).unwrap();
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["traffic_light.rs", "door_lock.rs", "mod.rs", "README.md"]);
+
+    assert!(files[0].1.starts_with("//! Auto-generated code for TrafficLight FSM\n"));
+    assert!(files[0].1.contains("pub enum TrafficLightState"));
+    // A failing FSM still gets its file, listing the errors
+    assert!(files[1].1.contains("//! Code generation failed:\n//! - guard `code * 2 > 3`"));
+    assert!(files[2].1.contains("pub mod traffic_light;\npub mod door_lock;\n"));
+    assert!(files[2].1.contains("pub use door_lock::*;\n"));
+
+    // `@file_name` overrides the file and module name, not the types
+    let fsms = parse_fsm("fsm IOManager {\n @file_name(\"io_mgr\")\n [*] --> Idle\n}").unwrap();
+    let files = autogen_files(&fsms, CodegenTarget::Standard, &CodegenOptions::new()).unwrap();
+    assert_eq!(files[0].0, "io_mgr.rs");
+    assert!(files[0].1.contains("pub enum IOManagerState"));
+    assert!(files[1].1.contains("pub mod io_mgr;\n"));
+    let files = autogen_files(&fsms, CodegenTarget::C, &CodegenOptions::new()).unwrap();
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["io_mgr.h", "io_mgr.c", "README.md"]);
+    assert!(files[1].1.contains("#include \"io_mgr.h\"\n"));
+    assert!(files[0].1.contains("} io_manager_state_t;"));
+}
+
+#[test]
+fn test_autogen_export_skips_unchanged_and_reports_stale() {
+    let dir = std::env::temp_dir().join(format!("oxidate_autogen_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let mut fsms = parse_fsm(TRAFFIC_LIGHT).expect("Should parse successfully");
+    fsms.extend(parse_fsm("fsm Door {\n [*] --> Closed\n Closed --> Open : open\n}").unwrap());
+    let options = CodegenOptions::new();
+
+    let first = write_autogen_folder(&dir, &fsms, CodegenTarget::Standard, &options).unwrap();
+    assert_eq!(first.written, ["traffic_light.rs", "door.rs", "mod.rs", "README.md", MANIFEST_FILE]);
+    assert!(first.unchanged.is_empty() && first.stale.is_empty());
+    let folder = first.folder;
+    let modified = |name: &str| std::fs::metadata(folder.join(name)).unwrap().modified().unwrap();
+    let before = modified("traffic_light.rs");
+
+    // Nothing changed: nothing is written and the files keep their modification times
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    let second = write_autogen_folder(&dir, &fsms, CodegenTarget::Standard, &options).unwrap();
+    assert!(second.written.is_empty());
+    assert_eq!(second.describe(), "5 unchanged");
+    assert_eq!(modified("traffic_light.rs"), before);
+
+    // Without Door, its file is stale; a user file in the folder is not
+    std::fs::write(folder.join("helpers.rs"), "pub fn help() {}\n").unwrap();
+    let mut third = write_autogen_folder(&dir, &fsms[..1], CodegenTarget::Standard, &options).unwrap();
+    assert_eq!(third.written, ["mod.rs", "README.md", MANIFEST_FILE]);
+    assert_eq!(third.stale, ["door.rs"]);
+    assert_eq!(modified("traffic_light.rs"), before);
+    remove_stale_files(&mut third).unwrap();
+    assert_eq!(third.describe(), "3 written, 1 unchanged, 1 removed");
+    assert!(!folder.join("door.rs").exists());
+    assert!(folder.join("helpers.rs").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_export_manifest_round_trip() {
+    let mut fsms = parse_fsm(TRAFFIC_LIGHT).expect("Should parse successfully");
+    fsms.extend(parse_fsm("fsm Door {\n events {\n knock\n }\n [*] --> Closed\n Closed --> Open : open [unlocked] / chime\n Open --> Ajar : oops\n}").unwrap());
+    fsms[1].source_file = Some("door.fsm".to_string());
+    let options = CodegenOptions::new().with_process_result(true).with_event_queue_size(Some(4));
+    let files = autogen_files(&fsms, CodegenTarget::Standard, &options).unwrap();
+    let manifest = ExportManifest::new(&fsms, CodegenTarget::Standard, &options, &files);
+
+    assert_eq!(manifest.tool_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(manifest.target, "standard");
+    assert!(manifest.options.process_result && !manifest.options.metrics);
+    assert_eq!(manifest.options.event_queue_size, Some(4));
+    let door = &manifest.fsms[1];
+    assert_eq!(door.source_file.as_deref(), Some("door.fsm"));
+    assert_eq!(door.dsl_hash, dsl_hash(&fsms[1]));
+    assert_eq!(door.files.len(), 1);
+    assert_eq!(door.files[0].name, "door.rs");
+    assert_eq!(door.files[0].hash, content_hash(files[1].1.as_bytes()));
+    assert_eq!(door.states, ["Closed", "Open", "Ajar"]);
+    assert_eq!(door.events, ["knock", "oops", "open"]);
+    assert_eq!((door.actions.as_slice(), door.guards.as_slice()), (&["chime".to_string()][..], &["unlocked".to_string()][..]));
+    assert!(door.warnings.iter().any(|w| w.contains("knock")), "{:?}", door.warnings);
+    let shared: Vec<&str> = manifest.files.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(shared, ["mod.rs", "README.md"]);
+
+    let json = manifest.to_json();
+    assert_eq!(ExportManifest::from_json(&json).unwrap(), manifest);
+    assert!(json.contains("\"dsl_hash\": \"fnv1a64:"));
+    // Fields added later default when reading an older manifest
+    let older = json.replace("\"metrics\": false,\n", "");
+    assert_eq!(ExportManifest::from_json(&older).unwrap(), manifest);
+    assert!(ExportManifest::from_json("{}").is_err());
+
+    // The hash is stable across builds, and the DSL hash ignores the rest of the file
+    assert_eq!(content_hash(b""), "fnv1a64:cbf29ce484222325");
+    assert_eq!(content_hash(b"a"), "fnv1a64:af63dc4c8601ec8c");
+    let padded = parse_fsm(&format!("fsm Gate {{\n}}\n\n{}", crate::parser::format::format_fsm(&fsms[1]))).unwrap();
+    assert_eq!(dsl_hash(&padded[1]), door.dsl_hash);
+}
+
+#[test]
+fn test_export_manifest_detects_drift() {
+    let dir = std::env::temp_dir().join(format!("oxidate_manifest_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("door.fsm");
+    std::fs::write(&source, "fsm Door {\n [*] --> Closed\n Closed --> Open : open\n}\n").unwrap();
+    let fsms = crate::parser::loader::parse_fsm_file(&source).unwrap();
+    let options = CodegenOptions::new();
+
+    let summary = write_autogen_folder(&dir, &fsms, CodegenTarget::Standard, &options).unwrap();
+    let folder = summary.folder;
+    let manifest = ExportManifest::load(&folder).unwrap();
+    assert_eq!(manifest, ExportManifest::load(folder.join(MANIFEST_FILE)).unwrap());
+    assert_eq!(manifest.verify(&folder), []);
+
+    // A rewrite of the same export keeps the manifest, time and all
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    let again = write_autogen_folder(&dir, &fsms, CodegenTarget::Standard, &options).unwrap();
+    assert!(again.written.is_empty());
+    assert_eq!(ExportManifest::load(&folder).unwrap().generated_at, manifest.generated_at);
+
+    // Edited output, deleted output, and DSL changed without generating again
+    std::fs::write(folder.join("door.rs"), "// mine now\n").unwrap();
+    std::fs::remove_file(folder.join("README.md")).unwrap();
+    std::fs::write(&source, "fsm Door {\n [*] --> Closed\n Closed --> Open : push\n}\n").unwrap();
+    let file = |name: &str| name.to_string();
+    assert_eq!(
+        manifest.verify(&folder),
+        [
+            ManifestMismatch::Modified { file: file("door.rs") },
+            ManifestMismatch::Missing { file: file("README.md") },
+            ManifestMismatch::SourceChanged { fsm: file("Door"), source_file: source.display().to_string() },
+        ]
+    );
+    // Only spacing changed: not drift
+    std::fs::write(&source, "fsm Door {\n [*] --> Closed\n\n   Closed --> Open : open\n}\n").unwrap();
+    assert!(manifest.verify(&folder).iter().all(|m| !matches!(m, ManifestMismatch::SourceChanged { .. })));
+    std::fs::remove_file(&source).unwrap();
+    assert!(matches!(manifest.verify(&folder).last(), Some(ManifestMismatch::SourceUnreadable { .. })));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_header_template_tops_every_file() {
+    let fsms = parse_fsm(TRAFFIC_LIGHT).expect("Should parse successfully");
+    let options = CodegenOptions::new()
+        .with_header_template("SPDX-License-Identifier: MIT\n\n// {fsm_name} ({target}), Oxidate {tool_version}\n");
+    let header = format!(
+        "// SPDX-License-Identifier: MIT\n//\n// TrafficLight (standard), Oxidate {}\n\n",
+        env!("CARGO_PKG_VERSION")
+    );
+
+    let code = generate_rust_code_with_options(&fsms[0], CodegenTarget::Standard, &options);
+    assert!(code.starts_with(&format!("{header}//! Auto-generated FSM: TrafficLight\n")), "{code}");
+    let c = generate_rust_code_with_options(&fsms[0], CodegenTarget::C, &options);
+    for (name, contents) in split_c_files(&c) {
+        assert!(contents.starts_with(&header.replace("standard", "c")), "{name}: {contents}");
+    }
+    // No template, no header
+    assert!(generate_rust_code(&fsms[0]).starts_with("//! Auto-generated FSM: TrafficLight\n"));
+
+    // Exports put it above their own header, once
+    let files = autogen_files(&fsms, CodegenTarget::Standard, &options).unwrap();
+    let file = |name: &str| files.iter().find(|(n, _)| n == name).map(|(_, c)| c.as_str()).unwrap();
+    assert!(file("traffic_light.rs").starts_with(&format!("{header}//! Auto-generated code for TrafficLight FSM\n")));
+    assert_eq!(file("traffic_light.rs").matches("SPDX").count(), 1);
+    assert!(file("mod.rs").starts_with(&header));
+    assert!(!file("README.md").contains("SPDX"));
+    let files = autogen_files(&fsms, CodegenTarget::C, &options).unwrap();
+    assert!(files[0].1.starts_with(&header.replace("standard", "c")));
+
+    // The marker below a long header still makes a removed FSM's file stale
+    let dir = std::env::temp_dir().join(format!("oxidate_header_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let mut with_door = fsms.clone();
+    with_door.extend(parse_fsm("fsm Door {\n [*] --> Closed\n Closed --> Open : open\n}").unwrap());
+    write_autogen_folder(&dir, &with_door, CodegenTarget::Standard, &options).unwrap();
+    let summary = write_autogen_folder(&dir, &fsms, CodegenTarget::Standard, &options).unwrap();
+    assert_eq!(summary.stale, ["door.rs"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_shared_events_module() {
+    let mut fsms = parse_fsm("fsm Motor {\n [*] --> Off\n Off --> On : start\n On --> Off : fault\n}").unwrap();
+    fsms.extend(parse_fsm("fsm Safety {\n [*] --> Ok\n Ok --> Tripped : fault\n Tripped --> Ok : reset\n}").unwrap());
+    let options = CodegenOptions::new().with_shared_events(true).with_event_queue_size(Some(4));
+
+    let files = autogen_files(&fsms, CodegenTarget::Standard, &options).unwrap();
+    let file = |name: &str| files.iter().find(|(n, _)| n == name).map(|(_, c)| c.as_str()).unwrap();
+    let events = file("events.rs");
+    assert!(events.contains("pub enum SharedEvent {\n    /// Handled by Motor, Safety\n    Fault,\n"));
+    assert!(events.contains("impl From<MotorEvent> for SharedEvent {"));
+    assert!(events.contains("            SharedEvent::Start => Ok(MotorEvent::Start),\n            other => Err(other),\n"));
+    assert!(file("mod.rs").contains("pub mod events;\n"));
+    assert!(file("motor.rs").contains("#[cfg(feature = \"shared-events\")]\nimpl<T: MotorActions> Motor<T> {"));
+    assert!(file("safety.rs").contains("pub fn post_shared(&mut self, event: super::events::SharedEvent)"));
+
+    // Without the option nothing changes
+    let files = autogen_files(&fsms, CodegenTarget::Standard, &CodegenOptions::new()).unwrap();
+    assert!(files.iter().all(|(name, code)| name != "events.rs" && !code.contains("SharedEvent")));
+
+    // Different names for the same variant cannot share it
+    fsms.extend(parse_fsm("fsm Logger {\n [*] --> Idle\n Idle --> Idle : Fault\n}").unwrap());
+    let errors = autogen_files(&fsms, CodegenTarget::Standard, &options).unwrap_err();
+    assert_eq!(
+        errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+        ["events `Fault` (Logger), `fault` (Motor, Safety) all become `SharedEvent::Fault`"]
+    );
+}
+
+#[test]
+fn test_parse_cargo_check_messages() {
+    let output = [
+        r#"{"reason":"compiler-artifact","package_id":"x"}"#,
+        r#"{"reason":"compiler-message","message":{"level":"error","message":"cannot find function `undefined` in this scope","code":{"code":"E0425"},"spans":[{"file_name":"src/lib.rs","line_start":105,"column_start":21,"is_primary":true}],"rendered":"error[E0425]: cannot find function\n"}}"#,
+        r#"{"reason":"compiler-message","message":{"level":"warning","message":"unused variable: `y`","code":{"code":"unused_variables"},"spans":[{"file_name":"src/lib.rs","line_start":3,"column_start":1,"is_primary":false},{"file_name":"src/lib.rs","line_start":4,"column_start":5,"is_primary":true}],"rendered":""}}"#,
+        r#"{"reason":"compiler-message","message":{"level":"error","message":"aborting due to 1 previous error","code":null,"spans":[],"rendered":""}}"#,
+        "not json",
+        r#"{"reason":"build-finished","success":false}"#,
+    ]
+    .join("\n");
+
+    let diagnostics = parse_cargo_messages(&output);
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].level, DiagnosticLevel::Error);
+    assert_eq!(diagnostics[0].code.as_deref(), Some("E0425"));
+    assert_eq!((diagnostics[0].line, diagnostics[0].column), (Some(105), Some(21)));
+    assert_eq!(diagnostics[0].rendered, "error[E0425]: cannot find function");
+    assert_eq!(diagnostics[1].level, DiagnosticLevel::Warning);
+    assert_eq!((diagnostics[1].line, diagnostics[1].column), (Some(4), Some(5)));
+}
+
+#[test]
+fn test_check_scaffold_dependencies_per_target() {
+    assert!(scaffold_manifest(CodegenTarget::Standard).ends_with("[dependencies]\n"));
+    assert!(scaffold_manifest(CodegenTarget::Rtic).contains("heapless = "));
+    assert!(scaffold_manifest(CodegenTarget::Embassy).contains("embassy-sync = "));
+}
+
+#[test]
+fn test_generated_code_passes_cargo_check() {
+    if !cargo_available() {
+        return;
+    }
+    let fsm = &parse_fsm(TRAFFIC_LIGHT).unwrap()[0];
+    let code = generate_rust_code(fsm);
+    let report = check_generated_code(CodegenTarget::Standard, &code).expect("cargo check should run");
+    assert!(report.success, "{:?}", report.diagnostics);
+    assert_eq!(report.count(DiagnosticLevel::Error), 0);
+
+    let broken = format!("{}\nfn broken() -> u8 {{ missing_value }}\n", code);
+    let report = check_generated_code(CodegenTarget::Standard, &broken).expect("cargo check should run");
+    assert!(!report.success);
+    let error = report.diagnostics.iter().find(|d| d.level == DiagnosticLevel::Error).expect("an error");
+    assert_eq!(error.line, Some(code.lines().count() + 2));
+}
+
+#[test]
+fn test_dot_and_plantuml_state_colors() {
+    let source = r#"
+        fsm Link {
+            [*] --> Idle
+            state Idle #color(#FFE020)
+            state Fault #color(red) @tag(safety, latched)
+            Idle --> Fault : error
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+
+    let dot = generate_dot(&fsms[0]);
+    assert!(dot.contains("    \"Idle\" [label=\"{Idle}\", style=filled, fillcolor=\"#FFE020\"];\n"));
+    assert!(dot.contains(
+        "    \"Fault\" [label=\"{Fault}\", style=filled, fillcolor=\"#D03030\", fontcolor=white, class=\"safety latched\"];\n"
+    ));
+
+    let uml = generate_plantuml(&fsms[0]);
+    assert!(uml.contains("state Idle #FFE020\n"));
+    assert!(uml.contains("state Fault #D03030\n"));
+}
+
+/// `-.->` only changes how the edge is drawn, `<--` nothing at all
+#[test]
+fn test_dashed_and_reversed_arrows() {
+    let plain = parse_fsm("fsm Link {\n [*] --> Up\n Up --> Down : lost\n Down --> Up : found\n}").expect("Should parse successfully");
+    let styled = parse_fsm("fsm Link {\n [*] --> Up\n Up -.-> Down : lost\n Up <-- Down : found\n}").expect("Should parse successfully");
+
+    let dot = generate_dot(&styled[0]);
+    assert!(dot.contains("    \"Up\" -> \"Down\" [label=\"lost\", style=dashed];\n"));
+    assert!(dot.contains("    \"Down\" -> \"Up\" [label=\"found\"];\n"));
+    let uml = generate_plantuml(&styled[0]);
+    assert!(uml.contains("Up -[dashed]-> Down : lost\n"));
+    assert!(uml.contains("Down --> Up : found\n"));
+
+    assert_eq!(
+        generate_rust_code(&styled[0]),
+        generate_rust_code(&plain[0])
+    );
+}
+
+#[test]
+fn test_metrics_golden() {
+    let fsms = parse_fsm(include_str!("../../examples/builtin/traffic_light.fsm")).expect("Should parse successfully");
+    let options = CodegenOptions::new().with_event_queue_size(Some(4)).with_metrics(true);
+    let code = generate_rust_code_with_options(&fsms[0], CodegenTarget::Standard, &options);
+
+    assert_eq!(code, include_str!("../../examples/builtin/traffic_light_metrics.rs"));
+}
+
+/// Without an event queue there is no watermark, and without the option nothing at all
+#[test]
+fn test_metrics_only_when_enabled() {
+    let fsms = parse_fsm(TRAFFIC_LIGHT).expect("Should parse successfully");
+    let options = CodegenOptions::new().with_metrics(true);
+    let code = generate_rust_code_with_options(&fsms[0], CodegenTarget::Standard, &options);
+    assert!(code.contains("    event_received: [u32; 1],\n"));
+    assert!(!code.contains("watermark"));
+
+    assert!(!generate_rust_code(&fsms[0]).contains("fsm-metrics"));
+}
+
+#[test]
+fn test_completion_transitions_run_after_entry() {
+    let source = r#"
+        fsm Boot {
+            [*] --> PowerOn
+            state SelfTest {
+                exit / stop_tests
+            }
+            PowerOn --> SelfTest
+            SelfTest --> Ready : [checks_pass] / report
+            Ready --> SelfTest : retest
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let options = CodegenOptions::new().with_max_completion_hops(4);
+    let code = generate_rust_code_with_options(&fsms[0], CodegenTarget::Standard, &options);
+
+    assert!(code.contains(
+        "    fn run_completions(&mut self) {
+        const MAX_COMPLETION_HOPS: usize = 4;
+        for _ in 0..MAX_COMPLETION_HOPS {
+            match self.state {
+                BootState::PowerOn => {
+                    self.state = BootState::SelfTest;
+                }
+                BootState::SelfTest if self.context.checks_pass() => {
+                    self.context.stop_tests();
+                    self.context.report();
+                    self.state = BootState::Ready;
+                }
+                _ => return,
+            }
+        }
+    }
+"
+    ));
+    // After start, and after every arm entering a state with completion transitions
+    assert!(code.contains("        self.started = true;\n        self.run_completions();\n        true\n"));
+    assert!(code.contains("                self.state = BootState::SelfTest;\n                self.run_completions();\n                true\n"));
+    // Completion transitions are not events
+    assert!(!code.contains("BootEvent::ChecksPass"));
+
+    let code = generate_rust_code_with_options(&fsms[0], CodegenTarget::Standard, &options.with_run_entry_on_new(true));
+    assert!(code.contains("        };\n        machine.run_completions();\n        machine\n    }\n"));
+}
+
+#[test]
+fn test_wildcard_arms_after_own_arms() {
+    let source = r#"
+        fsm Press {
+            [*] --> Idle
+            state Idle
+            state Running {
+                exit / stop_motor
+            }
+            state Stopped {
+                exit / reset_latch
+            }
+            * --> Stopped : emergency_stop / kill_power
+            Running --> Idle : emergency_stop / brake
+            Idle --> Running : start
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let options = CodegenOptions::new().with_tracing_hook(true);
+    let code = generate_rust_code_with_options(&fsms[0], CodegenTarget::Standard, &options);
+
+    let own = code.find("(PressState::Running, PressEvent::EmergencyStop) => {").unwrap();
+    let wildcard = code.find("(_, PressEvent::EmergencyStop) => {").unwrap();
+    assert!(own < wildcard && code.find("(PressState::Idle, PressEvent::Start)").unwrap() < wildcard);
+    assert!(code.contains(
+        "            (_, PressEvent::EmergencyStop) => {
+                let from = self.state;
+                match self.state {
+                    PressState::Running => {
+                        self.context.stop_motor();
+                    }
+                    PressState::Stopped => {
+                        self.context.reset_latch();
+                    }
+                    _ => {}
+                }
+                self.context.kill_power();
+                self.state = PressState::Stopped;
+                self.context.on_transition(from, PressEvent::EmergencyStop, PressState::Stopped);
+"
+    ));
+
+    let dot = generate_dot(&fsms[0]);
+    assert!(dot.contains("    \"*\" -> \"Stopped\" [label=\"emergency_stop / kill_power\"];\n"));
+    let uml = generate_plantuml(&fsms[0]);
+    assert!(uml.contains("state \"any state\" as any_state ##[dashed]\n"));
+    assert!(uml.contains("any_state --> Stopped : emergency_stop / kill_power\n"));
+
+    // Every state handling the event itself leaves no arm for the wildcard
+    let source = "fsm Lamp {\n [*] --> Off\n Off --> On : toggle\n On --> Off : toggle\n * --> Off : toggle\n}";
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    assert!(!generate_rust_code(&fsms[0]).contains("(_, LampEvent::Toggle)"));
+}
+
+#[test]
+fn test_process_result_golden() {
+    let fsms = parse_fsm(include_str!("../../examples/builtin/vending_machine.fsm")).expect("Should parse successfully");
+    let off = generate_rust_code(&fsms[0]);
+    assert_eq!(off, include_str!("../../examples/builtin/vending_machine.rs"));
+    assert!(!off.contains("ProcessResult"));
+
+    let options = CodegenOptions::new().with_process_result(true);
+    let on = generate_rust_code_with_options(&fsms[0], CodegenTarget::Standard, &options);
+    assert_eq!(on, include_str!("../../examples/builtin/vending_machine_process_result.rs"));
+}
+
+/// Internal transitions get arms after the state's own, unless an unguarded own
+/// transition always takes their event; guarded wildcards reject in any state
+#[test]
+fn test_process_result_internal_and_rejected_arms() {
+    let source = r#"
+        fsm Heater {
+            [*] --> Off
+            state Heating {
+                tick / read_sensor
+                adjust [in_range] / trim
+                reset / ignored
+            }
+            Off --> Heating : power_on [supply_ok]
+            Heating --> Off : reset
+            * --> Off : overheat [sensor_trusted]
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let options = CodegenOptions::new().with_process_result(true).with_serde_derives(true);
+    let code = generate_rust_code_with_options(&fsms[0], CodegenTarget::Standard, &options);
+
+    assert!(code.contains("#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]\npub enum HeaterProcessResult {"));
+    assert!(code.contains(
+        "            (HeaterState::Heating, HeaterEvent::Tick) => {\n                self.context.read_sensor();\n                HeaterProcessResult::InternalHandled\n"
+    ));
+    assert!(code.contains("            (HeaterState::Heating, HeaterEvent::Adjust) if self.context.in_range() => {\n"));
+    assert!(!code.contains("self.context.ignored()"));
+    assert!(code.contains(
+        "                let from = self.state;\n                self.state = HeaterState::Off;\n                HeaterProcessResult::Transitioned { from, to: HeaterState::Off }\n"
+    ));
+
+    let rejected = code.split("            _ => HeaterProcessResult::Ignored,").next().unwrap();
+    assert!(rejected.ends_with(
+        "            (HeaterState::Heating, HeaterEvent::Adjust) => HeaterProcessResult::GuardRejected,\n            (_, HeaterEvent::Overheat) => HeaterProcessResult::GuardRejected,\n            (HeaterState::Off, HeaterEvent::PowerOn) => HeaterProcessResult::GuardRejected,\n"
+    ));
+
+    // Without the option internal transitions still run, reporting `true`
+    let code = generate_rust_code(&fsms[0]);
+    assert!(code.contains("                self.context.read_sensor();\n                true\n"));
+    assert!(code.contains("            _ => false // No transition\n"));
+}
+
+#[test]
+fn test_self_transition_arm_before_internal_arm() {
+    let source = r#"
+        fsm CoinBox {
+            [*] --> Accepting
+            state Accepting {
+                coin / add_to_balance
+            }
+            Accepting --> Accepting : coin [jammed] / eject_coin
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let code = generate_rust_code(&fsms[0]);
+    let external = code.find("(CoinBoxState::Accepting, CoinBoxEvent::Coin) if self.context.jammed() => {").unwrap();
+    let internal = code.find("(CoinBoxState::Accepting, CoinBoxEvent::Coin) => {").unwrap();
+    assert!(external < internal);
+    assert!(code.contains("    /// A state's own transitions, self-transitions included, are tried before its\n"));
+
+    // Only documented when there are internal transitions
+    let code = generate_rust_code(&parse_fsm("fsm A {\n [*] --> X\n X --> X : go\n}").unwrap()[0]);
+    assert!(!code.contains("self-transitions included"));
+}
+
+// ============================================================================
+// IR AND BACKENDS
+// ============================================================================
+
+#[test]
+fn test_ir_resolves_names_and_arm_order() {
+    let source = r#"
+        fsm Pump {
+            timer prime_timer = 500 -> primed
+            [*] --> Idle : / self_test
+            state Idle {
+                entry / close_valve
+                tick / count_tick
+            }
+            state Running {
+                exit / stop_motor
+            }
+            Idle --> Running : start [has_pressure]
+            Running --> Idle : stop
+            * --> Idle : emergency_stop
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let ir = FsmIr::new(&fsms[0]);
+    let methods = |calls: &[Call]| calls.iter().map(|c| c.method.clone()).collect::<Vec<_>>();
+
+    assert_eq!(ir.initial_state.as_deref(), Some("Idle"));
+    assert_eq!(methods(&ir.start_actions), ["self_test", "close_valve"]);
+    assert_eq!(methods(&ir.state("Running").unwrap().exit), ["stop_motor"]);
+    // Own transitions, then internal ones, then the wildcard
+    let arms: Vec<(&ArmSource, &str)> = ir.arms.iter().map(|a| (&a.source, a.event.as_str())).collect();
+    let state = |name: &str| ArmSource::State(name.to_string());
+    assert_eq!(
+        arms,
+        [(&state("Idle"), "Start"), (&state("Running"), "Stop"), (&state("Idle"), "Tick"), (&ArmSource::Any, "EmergencyStop")]
+    );
+    assert_eq!(ir.arms[0].guard, Some(GuardExpr::Term(GuardTerm::Call(Call::new("has_pressure")))));
+    assert_eq!(methods(&ir.arms[1].exit), ["stop_motor"]);
+    assert_eq!(methods(&ir.arms[1].entry), ["close_valve"]);
+    assert!(ir.arms[2].internal && ir.arms[2].entry.is_empty());
+    assert_eq!(ir.timers[0].event, "Primed");
+}
+
+#[test]
+fn test_timers_run_in_generated_code() {
+    let source = r#"
+        fsm Blinker {
+            timer blink = 500 -> toggle periodic
+            timer idle_timeout 10s -> sleep
+            [*] --> On
+            state On {
+                entry / led_on; start_timer(blink)
+                exit / stop_timer(blink); stop_timer(ghost)
+                toggle / flip
+            }
+            state Off {
+                entry / start_timer(idle_timeout)
+            }
+            On --> Off : button
+            Off --> On : button
+        }
+    "#;
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    let code = generate_rust_code(fsm);
+
+    // Timers are slots of the machine, not trait methods; an undefined one still is
+    assert!(code.contains("    timers: [Option<u32>; 2],\n"), "{code}");
+    assert!(code.contains("                self.timers = [None; 2];\n                self.context.stop_timer();\n"), "{code}");
+    assert!(code.contains("                self.timers[Self::TIMER_IDLE_TIMEOUT] = Some(0);\n"), "{code}");
+    assert!(!code.contains("self.timers[Self::TIMER_BLINK] = None"), "leaving a state stops its timers anyway:\n{code}");
+    assert!(code.contains("        (500, BlinkerEvent::Toggle, true), // blink\n"), "{code}");
+    assert!(code.contains("        (10000, BlinkerEvent::Sleep, false), // idle_timeout\n"), "{code}");
+    assert!(code.contains("    pub fn advance_time(&mut self, ms: u32) -> usize {\n"), "{code}");
+    let actions = &code[code.find("pub trait BlinkerActions").unwrap()..];
+    assert!(actions.contains("fn stop_timer(&mut self);") && !actions.contains("fn start_timer"), "{actions}");
+    // `sleep` is fired by a timer only, and still needs a variant
+    assert!(code.contains("    Sleep,\n"), "{code}");
+
+    // The entry actions `new()` runs start the timer the machine is created with
+    let options = CodegenOptions { run_entry_on_new: true, ..CodegenOptions::default() };
+    let code = generate_rust_code_with_options(fsm, CodegenTarget::Standard, &options);
+    assert!(code.contains("        context.led_on();\n        Self {\n"), "{code}");
+    assert!(code.contains("            timers: [Some(0), None],\n"), "{code}");
+
+    // C keeps its timer callbacks
+    let c: Vec<String> = methods_for_state(fsm, "On", CodegenTarget::C).into_iter().map(|m| m.name).collect();
+    assert_eq!(c, ["led_on", "start_timer", "stop_timer"]);
+    let rust: Vec<String> = methods_for_state(fsm, "On", CodegenTarget::Standard).into_iter().map(|m| m.name).collect();
+    assert_eq!(rust, ["led_on", "stop_timer"]);
+}
+
+#[test]
+fn test_backends_by_name() {
+    let registry = BackendRegistry::builtin();
+    assert_eq!(registry.names(), ["c", "embassy", "rtic", "standard"]);
+    assert!(registry.get("c_header").is_none());
+
+    let fsms = parse_fsm(TRAFFIC_LIGHT).expect("Should parse successfully");
+    let options = CodegenOptions::default();
+    for target in [CodegenTarget::Standard, CodegenTarget::Embassy, CodegenTarget::Rtic, CodegenTarget::C] {
+        let backend = registry.get(target.backend_name()).unwrap();
+        assert_eq!(
+            generate_with_backend(&fsms[0], backend, &options).unwrap(),
+            generate_rust_code_with_options(&fsms[0], target, &options)
+        );
+    }
+}
+
+#[cfg(feature = "custom-backends")]
+#[test]
+fn test_register_custom_backend() {
+    use crate::codegen::CodegenBackend;
+
+    struct StateList;
+    impl CodegenBackend for StateList {
+        fn name(&self) -> &'static str {
+            "states"
+        }
+        fn description(&self) -> &'static str {
+            "One state per line"
+        }
+        fn generate(&self, ir: &FsmIr, _options: &CodegenOptions) -> String {
+            ir.states.iter().map(|s| format!("{}\n", s.variant)).collect()
+        }
+    }
+
+    let mut registry = BackendRegistry::builtin();
+    registry.register(Box::new(StateList));
+    let fsms = parse_fsm(TRAFFIC_LIGHT).expect("Should parse successfully");
+    let code = generate_with_backend(&fsms[0], registry.get("states").unwrap(), &CodegenOptions::default()).unwrap();
+    assert_eq!(code, "Red\nYellow\nGreen\n");
+}
+
+// ============================================================================
+// C TARGET
+// ============================================================================
+
+#[test]
+fn test_c_target_dispatch() {
+    let source = r#"
+        fsm PumpCtl {
+            [*] --> Idle
+            state Idle
+            state Running {
+                exit / stop_motor
+                tick / count_tick
+            }
+            state Fault
+            Idle --> Running : start [primed] / open_valve
+            * --> Fault : overheat / alarm
+        }
+    "#;
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let options = CodegenOptions::new().with_tracing_hook(true);
+    let code = try_generate_rust_code_with_options(fsm, CodegenTarget::C, &options).unwrap();
+    let files = split_c_files(&code);
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["pump_ctl.h", "pump_ctl.c"]);
+    let (header, source) = (&files[0].1, &files[1].1);
+
+    assert!(header.starts_with("// Auto-generated FSM: PumpCtl\n"));
+    assert!(header.contains("    PUMP_CTL_STATE_RUNNING,\n"));
+    assert!(header.contains("    PUMP_CTL_EVENT_OVERHEAT,\n"));
+    // Actions, then guards, like the Rust trait
+    assert!(header.contains("    void (*stop_motor)(void *user);\n    bool (*primed)(void *user);\n"));
+    assert!(header.contains("bool pump_ctl_dispatch(pump_ctl_t *fsm, pump_ctl_event_t event);\n"));
+    assert!(header.ends_with("#endif // PUMP_CTL_H\n"));
+
+    assert!(source.contains(
+        "        if (event == PUMP_CTL_EVENT_START && fsm->actions->primed(fsm->user)) {\n            fsm->actions->open_valve(fsm->user);\n            fsm->state = PUMP_CTL_STATE_RUNNING;\n"
+    ));
+    // The internal transition stays in Running
+    assert!(source.contains("        if (event == PUMP_CTL_EVENT_TICK) {\n            fsm->actions->count_tick(fsm->user);\n            return true;\n"));
+    // The wildcard follows the switch and exits whichever state is current
+    let wildcard = source.find("    if (event == PUMP_CTL_EVENT_OVERHEAT) {").expect("wildcard arm");
+    assert!(wildcard > source.find("    switch (fsm->state) {").unwrap());
+    assert!(source[wildcard..].contains("        pump_ctl_state_t from = fsm->state;\n        switch (fsm->state) {\n        case PUMP_CTL_STATE_RUNNING:\n            fsm->actions->stop_motor(fsm->user);\n"));
+    assert!(source.contains("fsm->actions->on_transition(fsm->user, from, PUMP_CTL_EVENT_OVERHEAT, PUMP_CTL_STATE_FAULT);"));
+}
+
+#[test]
+fn test_c_target_completions_and_empty_tables() {
+    let fsm = &parse_fsm("fsm Boot {\n [*] --> Init\n Init --> Ready : [self_test_ok] / announce\n Init --> Failed\n}").unwrap()[0];
+    let code = try_generate_rust_code_with_options(fsm, CodegenTarget::C, &CodegenOptions::new()).unwrap();
+    let source = &split_c_files(&code)[1].1;
+    assert!(source.contains("static void boot_run_completions(boot_t *fsm);\n"));
+    assert!(!source.contains("    actions->announce(user);\n"));
+    assert!(source.contains("    boot_run_completions(fsm);\n}\n"));
+    assert!(source.contains("            if (fsm->actions->self_test_ok(fsm->user)) {\n                fsm->actions->announce(fsm->user);\n                fsm->state = BOOT_STATE_READY;\n                continue;\n            }\n            fsm->state = BOOT_STATE_FAILED;\n            continue;\n"));
+
+    // No actions, guards or events: still valid C
+    let fsm = &parse_fsm("fsm Idle {\n [*] --> Waiting\n state Waiting\n}").unwrap()[0];
+    let code = try_generate_rust_code_with_options(fsm, CodegenTarget::C, &CodegenOptions::new()).unwrap();
+    assert!(code.contains("typedef struct {\n    char unused;\n} idle_actions_t;\n"));
+    assert!(code.contains("    IDLE_EVENT_COUNT\n} idle_event_t;\n"));
+    assert!(code.contains("    (void)fsm;\n    (void)event;\n    return false;\n"));
+}
+
+#[test]
+fn test_named_guards_rust_and_c() {
+    let source = r#"
+        fsm Keypad {
+            events {
+                key(code: u16)
+                reset
+            }
+            guard lockout = attempts > 3 && !maintenance_mode
+            guard alarm = lockout || tampered
+
+            [*] --> Idle
+            Idle --> Locked : key(code) [lockout && valid(code)]
+            Idle --> Alarm : key [alarm]
+            Locked --> Idle : reset
+            Alarm --> Idle : [!alarm]
+        }
+    "#;
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let code = try_generate_rust_code_with_options(fsm, CodegenTarget::Standard, &CodegenOptions::new()).unwrap();
+    // Named guards are provided methods built on the required ones; a comparison
+    // becomes a method of its own
+    assert!(code.contains("    fn attempts_gt_3(&self) -> bool;\n    fn maintenance_mode(&self) -> bool;\n    fn tampered(&self) -> bool;\n    fn valid(&self, code: u16) -> bool;\n"));
+    assert!(code.contains("    /// `lockout || tampered`\n    fn alarm(&self) -> bool {\n        self.lockout() || self.tampered()\n    }\n"));
+    assert!(code.contains("    fn lockout(&self) -> bool {\n        self.attempts_gt_3() && !self.maintenance_mode()\n    }\n"));
+    assert!(!code.contains("fn lockout(&self) -> bool;"));
+    assert!(code.contains("(KeypadState::Idle, KeypadEvent::Key { code }) if self.context.lockout() && self.context.valid(code) => {"));
+    assert!(code.contains("                KeypadState::Alarm if !self.context.alarm() => {\n"));
+
+    let code = try_generate_rust_code_with_options(fsm, CodegenTarget::C, &CodegenOptions::new()).unwrap();
+    let (header, source) = (&split_c_files(&code)[0].1, &split_c_files(&code)[1].1);
+    assert!(!header.contains("lockout"));
+    // Each helper follows the ones it calls
+    assert!(source.contains(
+        "static bool keypad_guard_alarm(const keypad_t *fsm)\n{\n    return keypad_guard_lockout(fsm) || fsm->actions->tampered(fsm->user);\n}\n"
+    ));
+    assert!(source.contains("    return fsm->actions->attempts_gt_3(fsm->user) && !fsm->actions->maintenance_mode(fsm->user);\n"));
+    assert!(source.find("keypad_guard_lockout(const").unwrap() < source.find("keypad_guard_alarm(const").unwrap());
+    assert!(source.contains("if (event == KEYPAD_EVENT_KEY && args != NULL && keypad_guard_lockout(fsm) && fsm->actions->valid(fsm->user, args->key.code)) {"));
+    assert!(source.contains("            if (!keypad_guard_alarm(fsm)) {\n"));
+
+    // Methods calling each other in a loop would never return
+    let fsm = &parse_fsm("fsm Loop {\n guard a = !b\n guard b = a\n [*] --> A\n A --> B : go [a]\n}").unwrap()[0];
+    let errors = try_generate_rust_code_with_options(fsm, CodegenTarget::Standard, &CodegenOptions::new()).unwrap_err();
+    assert_eq!(errors, [CodegenError::GuardCycle { cycle: "a → b → a".to_string() }]);
+}
+
+#[test]
+fn test_final_states_ignore_wildcards() {
+    let source = "fsm Job {\n [*] --> Running\n Running --> Done : finish\n * --> Running : restart\n final state Done\n}";
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let ir = FsmIr::new(fsm);
+    assert_eq!(ir.final_states, ["Done"]);
+
+    let code = generate_rust_code(fsm);
+    assert!(code.contains("            (JobState::Done, _) => false,\n            (_, JobEvent::Restart) => {"), "{}", code);
+
+    let code = try_generate_rust_code_with_options(fsm, CodegenTarget::C, &CodegenOptions::new()).unwrap();
+    assert!(code.contains("    if (fsm->state == JOB_STATE_DONE) {\n        return false;\n    }\n"), "{}", code);
+}
+
+#[test]
+fn test_c_target_export_and_check() {
+    let fsms = parse_fsm(TRAFFIC_LIGHT).expect("Should parse successfully");
+    let files = autogen_files(&fsms, CodegenTarget::C, &CodegenOptions::new()).unwrap();
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["traffic_light.h", "traffic_light.c", "README.md"]);
+    assert!(files[0].1.starts_with("//! Auto-generated code for TrafficLight FSM\n//! DO NOT EDIT - Generated by Oxidate\n"));
+    assert!(files[1].1.contains("#include \"traffic_light.h\"\n"));
+
+    assert!(matches!(check_generated_code(CodegenTarget::C, ""), Err(CheckError::NotRust(CodegenTarget::C))));
+    assert_eq!(split_c_files("int x;\n"), [(String::new(), "int x;\n".to_string())]);
+}
+
+/// The C pair for the builtin TrafficLight and DoorLock examples, compared with
+/// `examples/snapshots/builtin/<fsm>.{h,c}` (`OXIDATE_UPDATE_SNAPSHOTS=1` rewrites them)
+#[test]
+fn test_c_golden_files() {
+    let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    let update = std::env::var_os("OXIDATE_UPDATE_SNAPSHOTS").is_some();
+    let mut mismatches = Vec::new();
+    for example in ["traffic_light", "door_lock"] {
+        let source = std::fs::read_to_string(root.join("builtin").join(format!("{}.fsm", example))).unwrap();
+        let fsm = &parse_fsm(&source).expect("Should parse successfully")[0];
+        let code = try_generate_rust_code_with_options(fsm, CodegenTarget::C, &CodegenOptions::new()).expect("Should generate");
+        for (name, contents) in split_c_files(&code) {
+            let file = root.join("snapshots").join("builtin").join(name);
+            if update {
+                std::fs::write(&file, &contents).unwrap();
+            } else if std::fs::read_to_string(&file).ok().as_deref() != Some(contents.as_str()) {
+                mismatches.push(file.display().to_string());
+            }
+        }
+    }
+    assert!(mismatches.is_empty(), "generated C differs from {:?}", mismatches);
+}
+
+// ============================================================================
+// EVENT PAYLOADS
+// ============================================================================
+
+const MOTOR: &str = r#"
+    fsm Motor {
+        events {
+            start
+            /// New target speed
+            set_speed(rpm: u16)
+            set_limits(low: u16, high: u16)
+            stop
+        }
+        [*] --> Idle
+        Idle --> Running : start
+        Running --> Running : set_speed(rpm) [speed_ok(rpm)] / apply_speed(rpm)
+        Running --> Running : set_limits(low, high) / apply_limits(high, low)
+        Running --> Idle : stop / brake
+        state Running {
+            set_speed(target) / log_speed(target)
+            set_limits / log_limits
+        }
+    }
+"#;
+
+/// A guard and actions taking payload fields, in Rust and C, compared with
+/// `examples/snapshots/payloads/motor.{rs,h,c}` (`OXIDATE_UPDATE_SNAPSHOTS=1` rewrites them)
+#[test]
+fn test_payload_golden_files() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/snapshots/payloads");
+    let update = std::env::var_os("OXIDATE_UPDATE_SNAPSHOTS").is_some();
+    let fsm = &parse_fsm(MOTOR).expect("Should parse successfully")[0];
+    assert_eq!(fsm.validate(), Ok(()));
+
+    let mut files = vec![("motor.rs".to_string(), generate_rust_code(fsm))];
+    let c = try_generate_rust_code_with_options(fsm, CodegenTarget::C, &CodegenOptions::new()).expect("Should generate");
+    files.extend(split_c_files(&c));
+    let mut mismatches = Vec::new();
+    for (name, contents) in files {
+        let file = dir.join(name);
+        if update {
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(&file, &contents).unwrap();
+        } else if std::fs::read_to_string(&file).ok().as_deref() != Some(contents.as_str()) {
+            mismatches.push(file.display().to_string());
+        }
+    }
+    assert!(mismatches.is_empty(), "generated code differs from {:?}", mismatches);
+}
+
+#[test]
+fn test_payload_types_and_collisions() {
+    let source = r#"
+        fsm Dimmer {
+            events {
+                level(value: u8)
+                fade(seconds: f32)
+            }
+            [*] --> On
+            On --> On : level(v) / apply(v)
+            On --> On : fade(s) / apply(s)
+        }
+    "#;
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    let errors = try_generate_rust_code_with_target(fsm, CodegenTarget::Standard).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].to_string(),
+        "`apply` takes (u8) for On --> On : level(v) / apply(v) but (f32) for On --> On : fade(s) / apply(s)"
+    );
+
+    // A float payload rules out `Eq` and `Hash`; shared events cannot carry payloads
+    let fsm = &parse_fsm(&source.replace("/ apply(s)", "/ fade_to(s)")).unwrap()[0];
+    let code = generate_rust_code(fsm);
+    assert!(code.contains("#[derive(Debug, Clone, Copy, PartialEq)]\npub enum DimmerEvent {\n    Level { value: u8 },\n    Fade { seconds: f32 },\n"));
+    let options = CodegenOptions::new().with_shared_events(true);
+    let errors = try_generate_rust_code_with_options(fsm, CodegenTarget::Standard, &options).unwrap_err();
+    assert!(errors.iter().all(|e| matches!(e, CodegenError::SharedEventPayload { .. })) && errors.len() == 2);
+}
+
+#[test]
+fn test_comparison_guards_take_bound_operands() {
+    let source = r#"
+        fsm Motor {
+            events {
+                set_speed(rpm: u16)
+                stop
+            }
+            [*] --> Running
+            Running --> Running : set_speed(rpm) [rpm < MAX] / apply_speed(rpm)
+            Running --> Stopped : stop
+        }
+    "#;
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    assert_eq!(fsm.validate(), Ok(()));
+    let code = generate_rust_code(fsm);
+    // Only the bound operand is passed; `MAX` is the context's to know
+    assert!(code.contains("    fn rpm_lt_max(&self, rpm: u16) -> bool;\n"), "{}", code);
+    assert!(code.contains("(MotorState::Running, MotorEvent::SetSpeed { rpm }) if self.context.rpm_lt_max(rpm) => {"));
+    let c = try_generate_rust_code_with_options(fsm, CodegenTarget::C, &CodegenOptions::new()).unwrap();
+    assert!(c.contains("    bool (*rpm_lt_max)(void *user, uint16_t rpm);\n"));
+    assert!(c.contains("fsm->actions->rpm_lt_max(fsm->user, args->set_speed.rpm)"));
+
+    // The dispatch arm destructures the payload, so a trigger binds every field or none
+    let fsm = &parse_fsm(&source.replace("Running --> Stopped : stop", "Running --> Stopped : stop(force) / log(force)")).unwrap()[0];
+    let errors = try_generate_rust_code_with_target(fsm, CodegenTarget::Standard).unwrap_err();
+    assert_eq!(
+        errors,
+        [CodegenError::BindingArity {
+            transition: "Running --> Stopped : stop(force) / log(force)".to_string(),
+            event: "stop".to_string(),
+            bound: 1,
+            declared: 0,
+        }]
+    );
+    assert_eq!(errors[0].to_string(), "Running --> Stopped : stop(force) / log(force) binds 1 parameter(s), but event `stop` declares 0");
+}
+
+#[test]
+fn test_non_copy_payload_is_reported() {
+    let source = r#"
+        fsm Profile {
+            events {
+                set_name(name: String)
+                set_path(path: std::path::PathBuf)
+                set_id(id: u32)
+            }
+            [*] --> Idle
+            Idle --> Idle : set_name(name) / store(name)
+            Idle --> Idle : set_path
+            Idle --> Idle : set_id
+        }
+    "#;
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    let errors = try_generate_rust_code_with_target(fsm, CodegenTarget::Standard).unwrap_err();
+    assert_eq!(
+        errors,
+        [
+            CodegenError::NonCopyPayload { event: "set_name".to_string(), param: "name".to_string(), ty: "String".to_string() },
+            CodegenError::NonCopyPayload { event: "set_path".to_string(), param: "path".to_string(), ty: "std::path::PathBuf".to_string() },
+        ]
+    );
+    assert_eq!(errors[0].to_string(), "event `set_name` carries `name: String`, which is not `Copy` as the generated event enum needs");
+}
+
+#[test]
+fn test_literal_action_arguments() {
+    let source = r#"
+        fsm Modem {
+            [*] --> Dialing
+            state Dialing {
+                entry / set_timeout(5000)
+            }
+            Dialing --> Online : connected / announce("online", true)
+            Online --> Dialing : dropped / set_timeout(0); adjust(-1)
+        }
+    "#;
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    let code = generate_rust_code(fsm);
+    assert!(code.contains("    fn set_timeout(&mut self, arg0: u32);\n"));
+    assert!(code.contains("    fn announce(&mut self, arg0: &str, arg1: bool);\n"));
+    assert!(code.contains("context.set_timeout(5000);"));
+    assert!(code.contains("self.context.announce(\"online\", true);"));
+    assert!(code.contains("    fn adjust(&mut self, arg0: i32);\n"));
+    assert!(code.contains("self.context.adjust(-1);"));
+
+    let c = try_generate_rust_code_with_options(fsm, CodegenTarget::C, &CodegenOptions::new()).unwrap();
+    assert!(c.contains("void (*announce)(void *user, const char * arg0, bool arg1);"));
+    assert!(c.contains("->set_timeout(fsm->user, 5000);"));
+}
+
+#[test]
+fn test_identifier_and_mixed_action_arguments() {
+    let source = r#"
+        fsm Heater {
+            events {
+                setpoint(celsius: i16)
+            }
+            [*] --> Idle
+            state Idle {
+                entry / set_power(default_power)
+            }
+            Idle --> Heating : setpoint(t) / regulate(t, max_power, 3)
+            Heating --> Idle : off / regulate(-40, max_power, 0)
+        }
+    "#;
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    let code = generate_rust_code(fsm);
+    // Identifiers that are not payload bindings are read from getters
+    assert!(code.contains("    fn set_power(&mut self, default_power: u32);\n"));
+    assert!(code.contains("    fn default_power(&self) -> u32;\n"));
+    assert!(code.contains("self.context.set_power(self.context.default_power());"));
+    // A literal takes the payload type passed at the same position elsewhere
+    assert!(code.contains("    fn regulate(&mut self, t: i16, max_power: u32, arg2: u32);\n"));
+    assert_eq!(code.matches("    fn regulate(").count(), 1);
+    assert!(code.contains("    fn max_power(&self) -> u32;\n"));
+    assert!(code.contains("self.context.regulate(t, self.context.max_power(), 3);"));
+    assert!(code.contains("self.context.regulate(-40, self.context.max_power(), 0);"));
+
+    let c = try_generate_rust_code_with_options(fsm, CodegenTarget::C, &CodegenOptions::new()).unwrap();
+    assert!(c.contains("uint32_t (*max_power)(void *user);"));
+    assert!(c.contains("->regulate(fsm->user, args->setpoint.celsius, fsm->actions->max_power(fsm->user), 3);"));
+}
+
+#[test]
+fn test_action_argument_type_collisions() {
+    let source = r#"
+        fsm Display {
+            [*] --> Off
+            Off --> On : wake / show(3)
+            On --> Off : sleep / show("bye")
+        }
+    "#;
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    let errors = try_generate_rust_code_with_target(fsm, CodegenTarget::Standard).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(&errors[0], CodegenError::ParamTypeCollision { name, .. } if name == "show"));
+
+    // A getter typed by the literals passed to two different actions
+    let fsm = &parse_fsm(&source.replace("show(3)", "dim(level); dim(3)").replace("show(\"bye\")", "label(level); label(\"off\")")).unwrap()[0];
+    let errors = try_generate_rust_code_with_target(fsm, CodegenTarget::Standard).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].to_string(),
+        "`level` returns u32 for Off --> On : wake / dim(level); dim(3) but &str for On --> Off : sleep / label(level); label(\"off\")"
+    );
+}
+
+// ============================================================================
+// EVENT IDS
+// ============================================================================
+
+const LOGGER: &str = r#"
+    fsm Logger {
+        events {
+            start = 1, stop = 2
+            /// New sample rate
+            set_rate(hz: u16) = 5
+        }
+        [*] --> Idle
+        Idle --> Running : start
+        Running --> Running : set_rate(hz) / apply_rate(hz)
+        Running --> Idle : stop
+        Running --> Fault : overheat
+        Fault --> Idle : reset
+    }
+"#;
+
+#[test]
+fn test_event_order() {
+    let variants = |fsm, order| -> Vec<String> {
+        FsmIr::with_event_order(fsm, order).events.into_iter().map(|e| e.variant).collect()
+    };
+    let fsm = &parse_fsm(LOGGER).expect("Should parse successfully")[0];
+    assert_eq!(variants(fsm, EventOrder::Alphabetical), ["Start", "Stop", "SetRate", "Overheat", "Reset"]);
+    assert_eq!(variants(fsm, EventOrder::Declaration), ["Start", "Stop", "SetRate", "Overheat", "Reset"]);
+    assert_eq!(FsmIr::new(fsm).event_discriminants(), [1, 2, 5, 6, 7]);
+
+    // Undeclared events by first use, not by name
+    let fsm = &parse_fsm(&LOGGER.replace("overheat", "zap")).unwrap()[0];
+    assert_eq!(variants(fsm, EventOrder::Alphabetical), ["Start", "Stop", "SetRate", "Reset", "Zap"]);
+    assert_eq!(variants(fsm, EventOrder::Declaration), ["Start", "Stop", "SetRate", "Zap", "Reset"]);
+    let code = generate_rust_code_with_options(
+        fsm,
+        CodegenTarget::Standard,
+        &CodegenOptions::new().with_event_order(EventOrder::Declaration),
+    );
+    assert!(code.contains("    Zap = 6,\n    Reset = 7,\n}"), "{}", code);
+}
+
+#[test]
+fn test_event_ids() {
+    let fsm = &parse_fsm(LOGGER).expect("Should parse successfully")[0];
+    let code = generate_rust_code(fsm);
+    assert!(code.contains("#[repr(u8)]\npub enum LoggerEvent {\n    Start = 1,\n"), "{}", code);
+    assert!(code.contains("    SetRate { hz: u16 } = 5,\n"));
+    assert!(code.contains(
+        "pub const EVENT_NAMES: [&'static str; 8] = [\"\", \"start\", \"stop\", \"\", \"\", \"set_rate\", \"overheat\", \"reset\"];"
+    ));
+    assert!(code.contains("            LoggerEvent::SetRate { .. } => 5,\n"));
+    // Only the event enum is numbered
+    assert!(!code.contains("#[repr(u8)]\npub enum LoggerState"));
+
+    // Without ids nothing changes unless `repr_u8` asks for discriminants
+    let plain = &parse_fsm(&LOGGER.replace(" = 1", "").replace(" = 2", "").replace(" = 5", "")).unwrap()[0];
+    let code = generate_rust_code(plain);
+    assert!(code.contains("pub enum LoggerEvent {\n    Start,\n"));
+    assert!(!code.contains("EVENT_NAMES"));
+    let code = generate_rust_code_with_options(plain, CodegenTarget::Standard, &CodegenOptions::new().with_repr_u8(true));
+    assert!(code.contains("    Start = 0,\n    Stop = 1,\n"));
+    assert!(code.contains("[&'static str; 5] = [\"start\", \"stop\", \"set_rate\", \"overheat\", \"reset\"];"));
+
+    // Metrics index the counters by position, not by discriminant
+    let code = generate_rust_code_with_options(fsm, CodegenTarget::Standard, &CodegenOptions::new().with_metrics(true));
+    assert!(code.contains("self.event_received[Self::event_index(event)]"));
+}
+
+#[test]
+fn test_event_id_errors() {
+    let fsm = &parse_fsm(&LOGGER.replace("stop = 2", "stop = 1")).unwrap()[0];
+    let errors = try_generate_rust_code_with_target(fsm, CodegenTarget::Standard).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].to_string(), "events `start`, `stop` are all numbered 1");
+
+    // `reset` follows `overheat` = 256
+    let fsm = &parse_fsm(&LOGGER.replace("set_rate(hz: u16) = 5", "set_rate(hz: u16) = 255")).unwrap()[0];
+    let errors = try_generate_rust_code_with_target(fsm, CodegenTarget::Standard).unwrap_err();
+    assert_eq!(
+        errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        [
+            "event `overheat` is numbered 256, which does not fit the u8 the event enum is represented as",
+            "event `reset` is numbered 257, which does not fit the u8 the event enum is represented as",
+        ]
+    );
+
+    // An implicit discriminant running into an explicit one
+    let fsm = &parse_fsm(&LOGGER.replace("stop = 2", "stop")).unwrap()[0];
+    assert!(try_generate_rust_code_with_target(fsm, CodegenTarget::Standard).is_ok());
+    let fsm = &parse_fsm(&LOGGER.replace("start = 1, stop = 2", "start = 4, stop")).unwrap()[0];
+    let errors = try_generate_rust_code_with_target(fsm, CodegenTarget::Standard).unwrap_err();
+    assert!(matches!(&errors[..], [CodegenError::DuplicateEventId { id: 5, .. }]), "{:?}", errors);
+
+    assert!(parse_fsm(&LOGGER.replace("= 5", "= 99999999999")).is_err());
+}
+
+/// `LOGGER` before and after a transition with a new event is added, in declaration
+/// order, compared with `examples/snapshots/event_ids/logger*.rs`
+/// (`OXIDATE_UPDATE_SNAPSHOTS=1` rewrites them): every event keeps its number
+#[test]
+fn test_event_id_golden_files() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/snapshots/event_ids");
+    let update = std::env::var_os("OXIDATE_UPDATE_SNAPSHOTS").is_some();
+    let options = CodegenOptions::new().with_event_order(EventOrder::Declaration);
+    let extended = LOGGER.replace("Fault --> Idle : reset", "Fault --> Idle : reset\n        Idle --> Idle : calibrate");
+    let mut mismatches = Vec::new();
+    let mut ids = Vec::new();
+    for (name, source) in [("logger.rs", LOGGER), ("logger_extended.rs", extended.as_str())] {
+        let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+        let code = try_generate_rust_code_with_options(fsm, CodegenTarget::Standard, &options).expect("Should generate");
+        ids.push(code.lines().filter(|line| line.starts_with("            LoggerEvent::") && line.ends_with(',')).map(str::to_string).collect::<Vec<_>>());
+        let file = dir.join(name);
+        if update {
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(&file, &code).unwrap();
+        } else if std::fs::read_to_string(&file).ok().as_deref() != Some(code.as_str()) {
+            mismatches.push(file.display().to_string());
+        }
+    }
+    assert!(mismatches.is_empty(), "generated code differs from {:?}", mismatches);
+
+    // `id()` arms: the old ones unchanged, `calibrate` numbered after them
+    let (before, after) = (&ids[0], &ids[1]);
+    let numbered: Vec<&String> = before.iter().filter(|line| !line.contains('"')).collect();
+    assert_eq!(numbered.len(), 5);
+    assert!(numbered.iter().all(|line| after.contains(line)), "{:?}\n{:?}", before, after);
+    assert!(after.contains(&"            LoggerEvent::Calibrate => 8,".to_string()));
+
+    // By name, `calibrate` would have taken the number of `overheat`
+    let fsm = &parse_fsm(&extended).unwrap()[0];
+    assert_eq!(FsmIr::new(fsm).events[3].variant, "Calibrate");
+}
+
+const SENSOR_LINK: &str = r#"
+    fsm SensorLink {
+        [*] --> Idle : / async power_up
+        state Sending {
+            entry / async send_frame(42)
+            exit / log_sent
+        }
+        Idle --> Sending : frame_ready [bus_free] / async write_register(7); count_frame
+        Sending --> Idle : sent / count_frame
+    }
+"#;
+
+/// The Embassy output of a machine with async actions, annotated and with every
+/// action awaited, compared with `examples/snapshots/async_actions/*.rs`
+/// (`OXIDATE_UPDATE_SNAPSHOTS=1` rewrites them)
+#[test]
+fn test_async_actions_golden_files() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/snapshots/async_actions");
+    let update = std::env::var_os("OXIDATE_UPDATE_SNAPSHOTS").is_some();
+    let fsm = &parse_fsm(SENSOR_LINK).expect("Should parse successfully")[0];
+    let mut mismatches = Vec::new();
+    for (name, mode) in [("sensor_link__embassy.rs", AsyncActions::Annotated), ("sensor_link__embassy_all.rs", AsyncActions::All)] {
+        let options = CodegenOptions::new().with_async_actions(mode);
+        let code = try_generate_rust_code_with_options(fsm, CodegenTarget::Embassy, &options).expect("Should generate");
+        let file = dir.join(name);
+        if update {
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(&file, &code).unwrap();
+        } else if std::fs::read_to_string(&file).ok().as_deref() != Some(code.as_str()) {
+            mismatches.push(file.display().to_string());
+        }
+    }
+    assert!(mismatches.is_empty(), "generated code differs from {:?}", mismatches);
+
+    // Only the annotated actions are awaited, however often the others are called
+    let awaited: Vec<String> = crate::codegen::rust_trait_methods(fsm, &CodegenOptions::default())
+        .into_iter()
+        .filter(|m| m.is_async)
+        .map(|m| m.signature())
+        .collect();
+    assert_eq!(awaited, ["async fn send_frame(&mut self, arg0: u32)", "async fn power_up(&mut self)", "async fn write_register(&mut self, arg0: u32)"]);
+    let sigs: Vec<String> = methods_for_state(fsm, "Sending", CodegenTarget::Embassy).into_iter().map(|m| m.signature).collect();
+    assert_eq!(sigs, ["async fn send_frame(&mut self, arg0: u32)", "fn log_sent(&mut self)"]);
+}
+
+#[test]
+fn test_async_actions_rejected_where_they_cannot_be_awaited() {
+    let fsm = &parse_fsm(SENSOR_LINK).expect("Should parse successfully")[0];
+    let errors = try_generate_rust_code_with_target(fsm, CodegenTarget::Rtic).unwrap_err();
+    assert_eq!(
+        errors,
+        [CodegenError::AsyncActions { target: "rtic".to_string(), actions: "send_frame, power_up, write_register".to_string() }]
+    );
+    assert_eq!(
+        errors[0].to_string(),
+        "the rtic target cannot await the async actions send_frame, power_up, write_register \
+         (only Embassy can; set async actions to None to call them synchronously)"
+    );
+    for target in [CodegenTarget::Standard, CodegenTarget::C] {
+        let errors = try_generate_rust_code_with_target(fsm, target).unwrap_err();
+        assert!(matches!(&errors[..], [CodegenError::AsyncActions { .. }]), "{:?}", errors);
+    }
+    // Folder exports report it in place of the code
+    let files = autogen_files(std::slice::from_ref(fsm), CodegenTarget::Rtic, &CodegenOptions::default()).unwrap();
+    assert!(files[0].1.contains("cannot await the async actions"), "{}", files[0].1);
+
+    // Without awaiting, the annotations are ignored and the Standard machine calls them
+    let options = CodegenOptions::new().with_async_actions(AsyncActions::None);
+    let code = try_generate_rust_code_with_options(fsm, CodegenTarget::Standard, &options).expect("Should generate");
+    assert!(code.contains("    fn send_frame(&mut self, arg0: u32);\n"), "{}", code);
+    assert!(!code.contains("async fn"));
+    // All, on the other hand, awaits even an unannotated machine
+    let plain = &parse_fsm(&SENSOR_LINK.replace("async ", "")).expect("Should parse successfully")[0];
+    assert!(try_generate_rust_code_with_target(plain, CodegenTarget::Rtic).is_ok());
+    let options = CodegenOptions::new().with_async_actions(AsyncActions::All);
+    assert!(try_generate_rust_code_with_options(plain, CodegenTarget::Rtic, &options).is_err());
+}
+
+const BOOTLOADER: &str = r#"
+    fsm Bootloader {
+        guard recovery = recovery_pin_set || !image_valid
+        [*] --> Idle : / init_clocks
+        [*] --> Recovery : [recovery]
+        [*] --> Updating : [update_pending] / log_update
+        state Idle {
+            entry / jump_to_app
+        }
+        state Recovery {
+            entry / open_console
+        }
+        Idle --> Updating : update_requested
+        Updating --> Idle : done
+        Recovery --> Idle : reboot
+    }
+"#;
+
+/// A start choosing among guarded initial transitions, in Rust (started by `start()`
+/// and by `new()`) and C, compared with `examples/snapshots/initial_guards/*`
+/// (`OXIDATE_UPDATE_SNAPSHOTS=1` rewrites them)
+#[test]
+fn test_guarded_initial_golden_files() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/snapshots/initial_guards");
+    let update = std::env::var_os("OXIDATE_UPDATE_SNAPSHOTS").is_some();
+    let fsm = &parse_fsm(BOOTLOADER).expect("Should parse successfully")[0];
+    let mut files = Vec::new();
+    for (name, options) in [
+        ("bootloader__default.rs", CodegenOptions::new()),
+        ("bootloader__entry_on_new.rs", CodegenOptions::new().with_run_entry_on_new(true).with_metrics(true)),
+    ] {
+        files.push((name.to_string(), try_generate_rust_code_with_options(fsm, CodegenTarget::Standard, &options).expect("Should generate")));
+    }
+    let c = try_generate_rust_code_with_options(fsm, CodegenTarget::C, &CodegenOptions::new()).expect("Should generate");
+    files.extend(split_c_files(&c));
+    let mut mismatches = Vec::new();
+    for (name, code) in files {
+        let file = dir.join(name);
+        if update {
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(&file, &code).unwrap();
+        } else if std::fs::read_to_string(&file).ok().as_deref() != Some(code.as_str()) {
+            mismatches.push(file.display().to_string());
+        }
+    }
+    assert!(mismatches.is_empty(), "generated code differs from {:?}", mismatches);
+
+    // Guarded branches in declaration order, the default as the fallback
+    let ir = FsmIr::new(fsm);
+    let states: Vec<&str> = ir.initial_branches.iter().map(|b| b.state.as_str()).collect();
+    assert_eq!((states, ir.initial_state.as_deref()), (vec!["Recovery", "Updating"], Some("Idle")));
+    let actions: Vec<&str> = ir.initial_branches[0].actions.iter().map(|c| c.method.as_str()).collect();
+    assert_eq!(actions, ["open_console"]);
+    // The guards the start asks about are trait methods like any other
+    let sigs: Vec<String> = crate::codegen::rust_trait_methods(fsm, &CodegenOptions::default()).into_iter().map(|m| m.signature()).collect();
+    for guard in ["fn recovery_pin_set(&self) -> bool", "fn image_valid(&self) -> bool", "fn update_pending(&self) -> bool"] {
+        assert!(sigs.iter().any(|s| s == guard), "{:?}", sigs);
+    }
+}
+
+// ============================================================================
+// SNAPSHOTS
+// ============================================================================
+
+/// Every bundled example, parsed, in a fixed order
+fn example_fsms() -> Vec<crate::fsm::FsmDefinition> {
+    let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    let mut fsms = Vec::new();
+    for dir in ["", "builtin", "templates"] {
+        let mut sources: Vec<std::path::PathBuf> = std::fs::read_dir(root.join(dir))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|e| e == "fsm"))
+            .collect();
+        sources.sort();
+        for path in sources {
+            fsms.extend(parse_fsm(&std::fs::read_to_string(&path).unwrap()).expect("Should parse successfully"));
+        }
+    }
+    fsms
+}
+
+/// The same input always yields byte-identical files, so committed output does not churn
+#[test]
+fn test_generated_output_is_deterministic() {
+    let fsms = example_fsms();
+    let options = snapshot_variants().remove(1).2.with_lenient_names(true).with_header_template("{fsm_name} {target}");
+    // Some examples share event names that differ only in case, which `events.rs` rejects
+    let options = options.with_shared_events(false);
+    let generate = || {
+        let mut outputs = Vec::new();
+        for fsm in &fsms {
+            for target in [CodegenTarget::Standard, CodegenTarget::C] {
+                outputs.push(try_generate_rust_code_with_options(fsm, target, &options).expect("Should generate"));
+            }
+        }
+        for target in [CodegenTarget::Standard, CodegenTarget::C] {
+            let files = autogen_files(&fsms, target, &options).expect("Should export");
+            outputs.extend(files.into_iter().map(|(name, contents)| name + &contents));
+        }
+        outputs
+    };
+    let first = generate();
+    for _ in 1..10 {
+        assert!(generate() == first, "generated output differs between runs");
+    }
+}
+
+/// Option sets every example is generated with in `examples/snapshots`
+fn snapshot_variants() -> Vec<(&'static str, CodegenTarget, CodegenOptions)> {
+    let everything = CodegenOptions::new()
+        .with_tracing_hook(true)
+        .with_serde_derives(true)
+        .with_visibility(Visibility::Crate)
+        .with_repr_u8(true)
+        .with_event_queue_size(Some(4))
+        .with_overflow_policy(OverflowPolicy::DropOldest)
+        .with_shared_events(true)
+        .with_metrics(true)
+        .with_process_result(true)
+        .with_doc_header("Snapshot of every option");
+    vec![
+        ("default", CodegenTarget::Standard, CodegenOptions::default()),
+        ("all_options", CodegenTarget::Standard, everything),
+        ("entry_on_new", CodegenTarget::Standard, CodegenOptions::new().with_run_entry_on_new(true).with_no_std(true)),
+        ("embassy", CodegenTarget::Embassy, CodegenOptions::default()),
+        ("rtic", CodegenTarget::Rtic, CodegenOptions::default()),
+    ]
+}
+
+/// Generated code for the bundled examples, compared with the files in
+/// `examples/snapshots` (set `OXIDATE_UPDATE_SNAPSHOTS=1` to rewrite them)
+#[test]
+fn test_codegen_snapshots() {
+    let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    let snapshots = root.join("snapshots");
+    let update = std::env::var_os("OXIDATE_UPDATE_SNAPSHOTS").is_some();
+
+    let mut mismatches = Vec::new();
+    for dir in ["", "builtin", "templates"] {
+        let mut sources: Vec<std::path::PathBuf> = std::fs::read_dir(root.join(dir))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|e| e == "fsm"))
+            .collect();
+        sources.sort();
+        for path in sources {
+            let source = std::fs::read_to_string(&path).unwrap();
+            for fsm in parse_fsm(&source).expect("Should parse successfully") {
+                for (variant, target, options) in snapshot_variants() {
+                    // The premium stubs only differ in the FSM name
+                    if target != CodegenTarget::Standard && fsm.name != "TrafficLight" {
+                        continue;
+                    }
+                    let options = options.with_lenient_names(true);
+                    let code = try_generate_rust_code_with_options(&fsm, target, &options).expect("Should generate");
+                    let name = format!("{}__{}.rs", crate::fsm::naming::to_snake_case(&fsm.name), variant);
+                    let file = snapshots.join(dir).join(name);
+                    if update {
+                        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+                        std::fs::write(&file, &code).unwrap();
+                    } else if std::fs::read_to_string(&file).ok().as_deref() != Some(code.as_str()) {
+                        mismatches.push(file.display().to_string());
+                    }
+                }
+            }
+        }
+    }
+    assert!(mismatches.is_empty(), "generated code differs from {:?}", mismatches);
+}
+
+#[test]
+fn test_code_sections_per_target() {
+    let fsm = &parse_fsm(TRAFFIC_LIGHT).expect("Should parse successfully")[0];
+    let titles = |target| {
+        let code = generate_rust_code_with_target(fsm, target);
+        let sections = code_sections(&code);
+        let lines: Vec<&str> = code.lines().collect();
+        for pair in sections.windows(2) {
+            assert!(pair[0].lines.end <= pair[1].lines.start);
+        }
+        for section in &sections {
+            assert_eq!(lines[section.lines.start], format!("// ── {} ──", section.title));
+        }
+        (sections.iter().map(|s| s.title.clone()).collect::<Vec<_>>(), sections, lines.len())
+    };
+
+    let (rust, sections, line_count) = titles(CodegenTarget::Standard);
+    assert_eq!(rust, ["States", "Events", "Dispatch", "Actions trait"]);
+    assert_eq!(sections[3].lines.end, line_count);
+
+    // A section ends where the C source file begins
+    let (c, sections, _) = titles(CodegenTarget::C);
+    assert_eq!(c, ["States", "Events", "Actions table", "Machine", "Dispatch", "Names"]);
+    let code = generate_rust_code_with_target(fsm, CodegenTarget::C);
+    let source_start = code.lines().position(|l| l.ends_with(".c ====")).unwrap();
+    assert!(sections[3].lines.end <= source_start && source_start < sections[4].lines.start);
+
+    assert!(code_sections("fn main() {}\n// ── not a marker\n").is_empty());
+}
+
+#[test]
+fn test_machines_without_transitions() {
+    // No states: the empty state enum and a constructor, nothing to dispatch
+    let fsm = &parse_fsm("fsm Empty { }").expect("Should parse successfully")[0];
+    let code = generate_rust_code(fsm);
+    assert!(code.contains("/// `Empty` has no states yet\n#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\npub enum EmptyState {\n}"));
+    assert!(code.contains("    pub fn new(context: T) -> Self {\n        Self { context }\n    }"));
+    assert!(!code.contains("fn process") && !code.contains("fn state("));
+
+    // Without `[*] -->` the machine starts in its first state, like the C target
+    let fsm = &parse_fsm("fsm Parts {\n state Idle\n state Busy\n}").expect("Should parse successfully")[0];
+    let code = generate_rust_code(fsm);
+    assert!(code.contains("state: PartsState::Idle,"));
+    // Without events there is nothing to process or to trace
+    let options = CodegenOptions::new().with_tracing_hook(true).with_process_result(true);
+    let code = try_generate_rust_code_with_options(fsm, CodegenTarget::Standard, &options).expect("Should generate");
+    assert!(!code.contains("PartsEvent") && !code.contains("PartsProcessResult"));
+    let code = generate_rust_code_with_target(fsm, CodegenTarget::C);
+    assert!(code.contains("fsm->state = PARTS_STATE_IDLE;"));
+}
+
+#[test]
+fn test_methods_for_transition_and_state() {
+    let source = r#"
+        fsm Vending {
+            events {
+                coin(cents: u16)
+                select
+                refund
+            }
+            guard can_vend = has_credit && !sold_out
+            [*] --> Idle
+            state Idle {
+                entry / show_prices
+                exit / hide_prices
+            }
+            Idle --> Paying : coin(c) / add_coin(c)
+            Paying --> Paying : coin(c) / add_coin(c); beep
+            Paying --> Dispensing : select [can_vend] / dispense
+            Paying --> Idle : refund [is_day_mode] / return_coins; show_prices
+            Dispensing --> Idle
+        }
+    "#;
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    let signatures = |index, target| -> Vec<String> {
+        methods_for_transition(fsm, index, target).into_iter().map(|m| m.signature).collect()
+    };
+
+    assert_eq!(signatures(0, CodegenTarget::Standard), ["fn add_coin(&mut self, c: u16)"]);
+    assert_eq!(signatures(0, CodegenTarget::C), ["void (*add_coin)(void *user, uint16_t c)"]);
+    assert_eq!(signatures(3, CodegenTarget::Standard), ["fn show_prices(&mut self)", "fn return_coins(&mut self)", "fn is_day_mode(&self) -> bool"]);
+
+    // A named guard brings the methods of its definition
+    let select = methods_for_transition(fsm, 2, CodegenTarget::Standard);
+    let names: Vec<(&str, MethodRole)> = select.iter().map(|m| (m.name.as_str(), m.role)).collect();
+    assert_eq!(
+        names,
+        [("dispense", MethodRole::Action), ("can_vend", MethodRole::NamedGuard), ("has_credit", MethodRole::Guard), ("sold_out", MethodRole::Guard)]
+    );
+    assert!(select.iter().all(|m| m.shared_with.is_empty()), "{select:?}");
+    let c = methods_for_transition(fsm, 2, CodegenTarget::C);
+    assert_eq!(c[1].signature, "static bool vending_guard_can_vend(const vending_t *fsm)");
+    assert_eq!(c[2].signature, "bool (*has_credit)(void *user)");
+
+    // Shared uses are the other places the method is required
+    let coin = methods_for_transition(fsm, 1, CodegenTarget::Standard);
+    assert_eq!(coin[0].shared_with, ["Idle --> Paying : coin(c) / add_coin(c)"]);
+    let refund = methods_for_transition(fsm, 3, CodegenTarget::Standard);
+    assert_eq!(refund[0].shared_with, ["entry of Idle"]);
+
+    // States list their entry and exit actions; unknown transitions and states need nothing
+    let idle: Vec<String> = methods_for_state(fsm, "Idle", CodegenTarget::Standard).into_iter().map(|m| m.signature).collect();
+    assert_eq!(idle, ["fn show_prices(&mut self)", "fn hide_prices(&mut self)"]);
+    assert!(methods_for_transition(fsm, 4, CodegenTarget::Standard).is_empty());
+    assert!(methods_for_transition(fsm, 99, CodegenTarget::Standard).is_empty());
+    assert!(methods_for_state(fsm, "Nowhere", CodegenTarget::Standard).is_empty());
+}
+
+#[test]
+fn test_bindings_bound_unbound_and_stale() {
+    let fsm = &parse_fsm(include_str!("../../examples/door_lock.fsm")).expect("Should parse")[0];
+    let bindings = Bindings::from_source(include_str!("../../examples/door_lock_hal.toml"), "door_lock_hal.toml")
+        .expect("Should load");
+    let options = CodegenOptions::new().with_bindings(bindings.clone());
+
+    let report = bindings.check(fsm, &options);
+    assert_eq!(
+        report,
+        BindingReport {
+            unbound: vec!["notify_security".to_string()],
+            stale: vec!["[actions] open_gate".to_string(), "[guards] engage_lock".to_string()],
+        }
+    );
+    let warnings = report.warnings(&fsm.name);
+    assert_eq!(warnings.len(), 3);
+    assert_eq!(warnings[0], "Binding [actions] open_gate matches nothing in DoorLockActions");
+    assert!(warnings[2].contains("1 method(s)") && warnings[2].ends_with("notify_security"), "{warnings:?}");
+
+    let code = try_generate_rust_code_with_options(fsm, CodegenTarget::Standard, &options).expect("Should generate");
+    assert!(code.contains("use crate::hal::{gpio_read, gpio_set, HIGH, KEYPAD, LOCK, LOW, SIREN};\n"), "{code}");
+    assert!(code.contains("pub struct HalActions;\n\n#[allow(unused_variables)]\nimpl DoorLockActions for HalActions {\n"), "{code}");
+    // Bound by snake_case name, a `;` added only where missing
+    assert!(code.contains("    fn engage_lock(&mut self) {\n        gpio_set(LOCK, HIGH);\n    }\n"), "{code}");
+    assert!(code.contains("    fn sound_alarm(&mut self) {\n        gpio_set(SIREN, HIGH);\n    }\n"), "{code}");
+    assert!(code.contains("    fn authorized(&self) -> bool {\n        gpio_read(KEYPAD) == HIGH\n    }\n"), "{code}");
+    assert!(code.contains("// WARNING: no binding for `notify_security`"), "{code}");
+    assert!(code.contains("/// Not bound yet (these panic): `notify_security`\n"), "{code}");
+    assert_eq!(code.matches("todo!(").count(), 1);
+    assert!(!code.contains("open_gate"));
+
+    // Nothing changes without bindings, and the C backend ignores them
+    assert!(!generate_rust_code(fsm).contains("HalActions"));
+    let c = try_generate_rust_code_with_options(fsm, CodegenTarget::C, &options).expect("Should generate");
+    assert!(!c.contains("HalActions") && !c.contains("gpio_set"));
+
+    // Imports per backend, and the struct can be renamed
+    let renamed = Bindings::from_source("struct = \"Board\"\n[imports]\nc = [\"nope\"]\nstandard = [\"board::*\"]\n", "b.toml")
+        .expect("Should load");
+    let code = try_generate_rust_code_with_options(fsm, CodegenTarget::Standard, &CodegenOptions::new().with_bindings(renamed))
+        .expect("Should generate");
+    assert!(code.contains("use board::*;\n") && !code.contains("nope"), "{code}");
+    assert!(code.contains("impl DoorLockActions for Board {"), "{code}");
+    assert_eq!(code.matches("todo!(").count(), 7);
+}
+
+#[test]
+fn test_bindings_errors() {
+    let unknown = Bindings::from_source("[action]\nengage_lock = \"x\"\n", "hal.toml").unwrap_err();
+    assert!(matches!(unknown, BindingsError::Syntax { ref file, .. } if file == "hal.toml"), "{unknown:?}");
+    assert!(unknown.to_string().starts_with("hal.toml: "), "{unknown}");
+    let not_a_snippet = Bindings::from_source("[actions]\nengage_lock = 3\n", "hal.toml");
+    assert!(matches!(not_a_snippet, Err(BindingsError::Syntax { .. })));
+    assert!(matches!(Bindings::load("/nonexistent/hal.toml"), Err(BindingsError::Io { .. })));
+    assert_eq!(Bindings::from_source("", "hal.toml").unwrap().struct_name(), "HalActions");
+}
+
+#[test]
+fn test_rate_limited_transitions() {
+    let source = "fsm Button {\n    [*] --> Up\n    Up --> Down : press @debounce(50ms) / click\n    Down --> Up : release @throttle(1s)\n}\n";
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let code = generate_rust_code(fsm);
+    assert!(code.contains("    transition_times: [Option<u32>; 2],\n"));
+    assert!(code.contains("    fn now_ms(&self) -> u32;\n"));
+    assert!(code.contains(
+        "            (ButtonState::Up, ButtonEvent::Press) => {\n                if self.rate_limited(0, 50, true) { // @debounce(50ms)\n                    return false;\n                }\n                self.context.click();\n"
+    ));
+    assert!(code.contains("if self.rate_limited(1, 1000, false) { // @throttle(1s)"));
+
+    let options = CodegenOptions::new().with_process_result(true);
+    let code = generate_rust_code_with_options(fsm, CodegenTarget::Standard, &options);
+    assert!(code.contains("    Suppressed,\n"));
+    assert!(code.contains("                    return ButtonProcessResult::Suppressed;\n"));
+    let c = generate_rust_code_with_options(fsm, CodegenTarget::C, &CodegenOptions::new());
+    assert!(c.contains("    uint32_t transition_times[2];\n    bool transition_seen[2];\n"));
+    assert!(c.contains("if (button_rate_limited(fsm, 1, 1000u, false)) { // @throttle(1s)"));
+
+    // Nothing of it without a constraint
+    let code = generate_rust_code_with_options(&parse_fsm(TRAFFIC_LIGHT).unwrap()[0], CodegenTarget::Standard, &options);
+    assert!(!code.contains("now_ms") && !code.contains("transition_times") && !code.contains("Suppressed"));
+}
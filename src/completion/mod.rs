@@ -0,0 +1,145 @@
+//! DSL Editor Completion
+//! Suggests state, event and action names from the last successful parse
+
+use crate::fsm::FsmDefinition;
+
+#[cfg(test)]
+mod tests;
+
+/// What kind of name the cursor position expects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionContext {
+    /// Transition source (start of a line) or target (after `-->`)
+    State,
+    /// Transition trigger (after `:`)
+    Event,
+    /// Action call (after `/` or `;`)
+    Action,
+}
+
+/// The identifier being typed at the end of `source_before_cursor`
+pub fn word_before_cursor(source_before_cursor: &str) -> &str {
+    let start = source_before_cursor
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| unicode_ident::is_xid_continue(*c))
+        .last()
+        .map_or(source_before_cursor.len(), |(i, _)| i);
+    &source_before_cursor[start..]
+}
+
+/// Guess what the cursor position expects from the current line
+pub fn completion_context(source_before_cursor: &str) -> Option<CompletionContext> {
+    let line = source_before_cursor.rsplit('\n').next().unwrap_or("");
+    if line.contains("//") {
+        return None;
+    }
+    let word = word_before_cursor(line);
+    let before = line[..line.len() - word.len()].trim();
+
+    // Action lists: transitions, entry/exit and internal transitions
+    if before.ends_with('/') || (before.ends_with(';') && before.contains('/')) {
+        return Some(CompletionContext::Action);
+    }
+    if ["->", "<--", "<-", "<-.-"].iter().any(|arrow| before.ends_with(arrow)) {
+        return Some(CompletionContext::State);
+    }
+    // `A --> B : ev` or `B <-- A : ev`, but not `state A : description`
+    if before.ends_with(':') && (before.contains("->") || before.contains("<-")) {
+        return Some(CompletionContext::Event);
+    }
+    // Start of a line is a transition source; an empty word there is just a blank line
+    if before.is_empty() && !word.is_empty() {
+        return Some(CompletionContext::State);
+    }
+    None
+}
+
+/// Names to offer at the cursor, filtered by the word being typed.
+///
+/// The word itself is not offered once it is complete, so the list empties
+/// after accepting a suggestion.
+pub fn suggest(source_before_cursor: &str, fsm: &FsmDefinition) -> Vec<String> {
+    let Some(context) = completion_context(source_before_cursor) else {
+        return Vec::new();
+    };
+    let word = word_before_cursor(source_before_cursor);
+
+    let candidates: Vec<String> = match context {
+        CompletionContext::State => fsm.states.iter().map(|s| s.name.clone()).collect(),
+        CompletionContext::Event => fsm.collect_events().into_iter().map(|e| e.name).collect(),
+        CompletionContext::Action => action_names(fsm),
+    };
+
+    let lower = word.to_lowercase();
+    candidates
+        .into_iter()
+        .filter(|name| name != word && name.to_lowercase().starts_with(&lower))
+        .collect()
+}
+
+/// Every action called anywhere in the FSM, sorted
+fn action_names(fsm: &FsmDefinition) -> Vec<String> {
+    let mut names: Vec<String> = fsm
+        .initial_transitions
+        .iter()
+        .flat_map(|t| &t.actions)
+        .chain(fsm.states.iter().flat_map(|s| {
+            s.entry_actions
+                .iter()
+                .chain(&s.exit_actions)
+                .chain(s.internal_transitions.iter().flat_map(|t| &t.actions))
+        }))
+        .chain(fsm.transitions.iter().flat_map(|t| &t.actions))
+        .chain(fsm.choice_points.iter().flat_map(|c| c.branches.iter().flat_map(|b| &b.actions)))
+        .map(|a| a.name.clone())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Editor popup state; the items are recomputed whenever the text changes
+#[derive(Debug, Default)]
+pub struct CompletionPopup {
+    pub items: Vec<String>,
+    pub selected: usize,
+    /// Cursor (char index) the items were computed for; moving away closes the popup
+    pub cursor: Option<usize>,
+}
+
+impl CompletionPopup {
+    pub fn is_open(&self) -> bool {
+        !self.items.is_empty()
+    }
+
+    pub fn open(&mut self, items: Vec<String>, cursor: usize) {
+        if items != self.items {
+            self.selected = 0;
+        }
+        self.items = items;
+        self.cursor = Some(cursor);
+    }
+
+    pub fn close(&mut self) {
+        self.items.clear();
+        self.selected = 0;
+        self.cursor = None;
+    }
+
+    pub fn select_next(&mut self) {
+        if self.is_open() {
+            self.selected = (self.selected + 1) % self.items.len();
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        if self.is_open() {
+            self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+        }
+    }
+
+    pub fn selected_item(&self) -> Option<&str> {
+        self.items.get(self.selected).map(String::as_str)
+    }
+}
@@ -0,0 +1,61 @@
+//! Unit tests for editor completion
+
+use crate::completion::{completion_context, suggest, word_before_cursor, CompletionContext};
+use crate::fsm::FsmDefinition;
+use crate::parser::parse_fsm;
+
+fn door() -> FsmDefinition {
+    let source = r#"
+        fsm Door {
+            [*] --> Locked
+            state Locked {
+                entry / engage_lock
+            }
+            Locked --> Unlocked : code_entered [is_valid] / log_event
+            Unlocked --> Locked : lock / engage_lock; log_event
+            Unlocked --> Open : push
+        }
+    "#;
+    parse_fsm(source).expect("Should parse successfully").remove(0)
+}
+
+#[test]
+fn test_suggest_states() {
+    let fsm = door();
+
+    assert_eq!(suggest("    Unl", &fsm), vec!["Unlocked"]);
+    assert_eq!(suggest("    Locked --> ", &fsm), vec!["Locked", "Unlocked", "Open"]);
+    assert_eq!(suggest("    Open -> u", &fsm), vec!["Unlocked"]);
+    // A finished name is not offered again
+    assert!(suggest("    Locked --> Open", &fsm).is_empty());
+}
+
+#[test]
+fn test_suggest_events() {
+    let fsm = door();
+
+    assert_eq!(suggest("    Open --> Locked : ", &fsm), vec!["code_entered", "lock", "push"]);
+    assert_eq!(suggest("    Open --> Locked : lo", &fsm), vec!["lock"]);
+    // A state description is free text
+    assert!(suggest("    state Open : lo", &fsm).is_empty());
+}
+
+#[test]
+fn test_suggest_actions() {
+    let fsm = door();
+
+    assert_eq!(suggest("    Open --> Locked : lock / ", &fsm), vec!["engage_lock", "log_event"]);
+    assert_eq!(suggest("    Open --> Locked : lock / engage_lock; l", &fsm), vec!["log_event"]);
+    assert_eq!(suggest("        exit / eng", &fsm), vec!["engage_lock"]);
+}
+
+#[test]
+fn test_no_suggestions_elsewhere() {
+    let fsm = door();
+
+    assert!(suggest("    ", &fsm).is_empty());
+    assert!(suggest("    // Lo", &fsm).is_empty());
+    assert!(suggest("    Locked --> Unlocked : code_entered [is_", &fsm).is_empty());
+    assert_eq!(completion_context("fsm Door {\n    Lo"), Some(CompletionContext::State));
+    assert_eq!(word_before_cursor("A --> Unl"), "Unl");
+}
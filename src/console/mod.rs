@@ -0,0 +1,115 @@
+//! Error Console
+//! Recent errors and warnings from parsing, layout, code generation, exports and file
+//! I/O, each with the time it was reported. New problems are added to the list, so they
+//! no longer overwrite earlier ones.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(test)]
+mod tests;
+
+/// Entries kept; the oldest are dropped first
+pub const MAX_ENTRIES: usize = 200;
+
+/// Where a problem came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Category {
+    Parse,
+    Layout,
+    Codegen,
+    Export,
+    /// Opening and saving `.fsm` files and projects
+    File,
+    /// Source edits made from the canvas and dialogs
+    Edit,
+}
+
+impl Category {
+    pub fn label(self) -> &'static str {
+        match self {
+            Category::Parse => "Parse",
+            Category::Layout => "Layout",
+            Category::Codegen => "Codegen",
+            Category::Export => "Export",
+            Category::File => "File",
+            Category::Edit => "Edit",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Clone, Debug)]
+pub struct ConsoleEntry {
+    /// When it was last reported
+    pub time: SystemTime,
+    pub category: Category,
+    pub severity: Severity,
+    pub message: String,
+    /// How often it was reported in a row (auto-parse repeats a parse error)
+    pub count: usize,
+}
+
+impl ConsoleEntry {
+    /// `12:04:31Z [Layout] error: ...`, the form copied to the clipboard
+    pub fn to_text(&self) -> String {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let repeated = if self.count > 1 { format!(" (×{})", self.count) } else { String::new() };
+        format!("{} [{}] {severity}{repeated}: {}", clock_time(self.time), self.category.label(), self.message)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ErrorConsole {
+    /// Oldest first
+    pub entries: Vec<ConsoleEntry>,
+    /// Entries reported while the console was closed (the toolbar badge)
+    pub unseen: usize,
+    /// Whether the console panel is shown
+    pub open: bool,
+}
+
+impl ErrorConsole {
+    /// Record a problem. One that repeats the latest entry only bumps its count and time.
+    pub fn push(&mut self, category: Category, severity: Severity, message: impl Into<String>) {
+        let message = message.into();
+        let time = SystemTime::now();
+        if let Some(last) = self.entries.last_mut() {
+            if last.category == category && last.severity == severity && last.message == message {
+                last.count += 1;
+                last.time = time;
+                return;
+            }
+        }
+        self.entries.push(ConsoleEntry { time, category, severity, message, count: 1 });
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        if !self.open {
+            self.unseen += 1;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.unseen = 0;
+    }
+
+    /// Every entry, one per line
+    pub fn to_text(&self) -> String {
+        self.entries.iter().map(ConsoleEntry::to_text).collect::<Vec<_>>().join("\n")
+    }
+}
+
+/// `HH:MM:SSZ` of `time` (UTC)
+pub fn clock_time(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) % 86_400;
+    format!("{:02}:{:02}:{:02}Z", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
@@ -0,0 +1,47 @@
+//! Unit tests for the error console
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::console::{clock_time, Category, ErrorConsole, Severity, MAX_ENTRIES};
+
+#[test]
+fn test_console_keeps_and_merges_entries() {
+    let mut console = ErrorConsole::default();
+    console.push(Category::Parse, Severity::Error, "expected identifier");
+    console.push(Category::Layout, Severity::Warning, "node not found");
+    assert_eq!(console.entries.len(), 2);
+    assert_eq!(console.unseen, 2);
+
+    // The same problem again is counted, not listed twice
+    console.push(Category::Layout, Severity::Warning, "node not found");
+    assert_eq!(console.entries.len(), 2);
+    assert_eq!(console.entries[1].count, 2);
+    assert_eq!(console.unseen, 2);
+    assert!(console.entries[1].to_text().ends_with("[Layout] warning (×2): node not found"));
+
+    // Nothing is unseen while the console is open
+    console.open = true;
+    console.unseen = 0;
+    console.push(Category::Export, Severity::Error, "Could not write 'a.rs'");
+    assert_eq!(console.unseen, 0);
+    assert_eq!(console.to_text().lines().count(), 3);
+
+    console.clear();
+    assert!(console.entries.is_empty());
+}
+
+#[test]
+fn test_console_drops_oldest_entries() {
+    let mut console = ErrorConsole::default();
+    for i in 0..MAX_ENTRIES + 5 {
+        console.push(Category::Codegen, Severity::Error, format!("error {i}"));
+    }
+    assert_eq!(console.entries.len(), MAX_ENTRIES);
+    assert_eq!(console.entries[0].message, "error 5");
+}
+
+#[test]
+fn test_clock_time() {
+    assert_eq!(clock_time(UNIX_EPOCH), "00:00:00Z");
+    assert_eq!(clock_time(UNIX_EPOCH + Duration::from_secs(3 * 86_400 + 13 * 3600 + 4 * 60 + 9)), "13:04:09Z");
+}
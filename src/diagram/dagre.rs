@@ -0,0 +1,145 @@
+//! Dagre Protocol
+//! The JSON exchanged with `tools/dagre-svg-demo/src/layout_json.mjs`. Both sides
+//! carry a protocol version, so a script from an older checkout (e.g. through
+//! `OXIDATE_DAGRE_DIR`) is reported as such instead of as a serde error, and the
+//! output is checked for edges the diagram could not draw.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Version of the layout JSON; the script echoes the one it implements
+pub const DAGRE_PROTOCOL: u32 = 2;
+
+/// Characters of the script's stdout and stderr quoted in errors
+const EXCERPT_CHARS: usize = 200;
+
+#[derive(Serialize)]
+pub struct JsGraphCfg {
+    pub rankdir: String,
+    pub nodesep: f32,
+    pub ranksep: f32,
+    pub edgesep: f32,
+    pub marginx: f32,
+    pub marginy: f32,
+}
+
+#[derive(Serialize)]
+pub struct JsNodeIn {
+    pub id: String,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Serialize)]
+pub struct JsEdgeIn {
+    pub v: String,
+    pub w: String,
+    pub name: Option<String>,
+    #[serde(rename = "labelWidth")]
+    pub label_width: Option<f32>,
+    #[serde(rename = "labelHeight")]
+    pub label_height: Option<f32>,
+}
+
+#[derive(Serialize)]
+pub struct JsLayoutInput {
+    pub protocol: u32,
+    pub graph: JsGraphCfg,
+    pub nodes: Vec<JsNodeIn>,
+    pub edges: Vec<JsEdgeIn>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JsPoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JsNodeOut {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JsGraphOut {
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JsEdgeOut {
+    pub v: String,
+    pub w: String,
+    pub name: Option<String>,
+    pub points: Vec<JsPoint>,
+    pub x: Option<f32>,
+    pub y: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JsLayoutOutput {
+    pub protocol: u32,
+    pub graph: JsGraphOut,
+    pub nodes: HashMap<String, JsNodeOut>,
+    pub edges: Vec<JsEdgeOut>,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum DagreError {
+    /// `found` is `None` for scripts older than the protocol field
+    #[error(
+        "The Dagre layout script at {} speaks protocol {}, but Oxidate expects protocol {expected}.\n\n\
+         Update tools/dagre-svg-demo (or point OXIDATE_DAGRE_DIR at a current checkout).\n\n{output}",
+        script.display(),
+        found.map_or("none (older than protocol 2)".to_string(), |p| p.to_string())
+    )]
+    ProtocolMismatch { script: PathBuf, expected: u32, found: Option<u32>, output: String },
+    #[error("The Dagre layout script at {} returned unexpected JSON: {message}\n\n{output}", script.display())]
+    InvalidOutput { script: PathBuf, message: String, output: String },
+    #[error("The Dagre layout script at {} returned edge {edge} to node `{node}`, which is not in its nodes", script.display())]
+    UnknownNode { script: PathBuf, edge: String, node: String },
+    #[error("The Dagre layout script at {} returned edge {edge} without points", script.display())]
+    EmptyRoute { script: PathBuf, edge: String },
+}
+
+/// Parse the stdout of a successful run of `script`, checking its protocol and that
+/// every edge joins known nodes along at least one point (`stderr` is only quoted)
+pub fn parse_layout_output(stdout: &[u8], stderr: &[u8], script: &Path) -> Result<JsLayoutOutput, DagreError> {
+    let output = || format!("stdout: {}\nstderr: {}", excerpt(stdout), excerpt(stderr));
+    let invalid = |message: String| DagreError::InvalidOutput { script: script.to_path_buf(), message, output: output() };
+
+    let value: serde_json::Value = serde_json::from_slice(stdout).map_err(|e| invalid(e.to_string()))?;
+    let found = value.get("protocol").and_then(|p| p.as_u64()).and_then(|p| u32::try_from(p).ok());
+    if found != Some(DAGRE_PROTOCOL) {
+        return Err(DagreError::ProtocolMismatch { script: script.to_path_buf(), expected: DAGRE_PROTOCOL, found, output: output() });
+    }
+    let layout: JsLayoutOutput = serde_json::from_value(value).map_err(|e| invalid(e.to_string()))?;
+
+    for edge in &layout.edges {
+        let name = || format!("{} -> {}", edge.v, edge.w);
+        if let Some(node) = [&edge.v, &edge.w].into_iter().find(|n| !layout.nodes.contains_key(n.as_str())) {
+            return Err(DagreError::UnknownNode { script: script.to_path_buf(), edge: name(), node: node.clone() });
+        }
+        if edge.points.is_empty() {
+            return Err(DagreError::EmptyRoute { script: script.to_path_buf(), edge: name() });
+        }
+    }
+    Ok(layout)
+}
+
+/// The first characters of `bytes` as text, `(empty)` if there are none
+fn excerpt(bytes: &[u8]) -> String {
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.trim();
+    match text.char_indices().nth(EXCERPT_CHARS) {
+        _ if text.is_empty() => "(empty)".to_string(),
+        Some((cut, _)) => format!("{}…", &text[..cut]),
+        None => text.to_string(),
+    }
+}
@@ -0,0 +1,67 @@
+//! egui Backend
+//! Paints a scene with an egui painter (canvas and offscreen export)
+
+use eframe::egui;
+
+use super::render_cache::RenderCache;
+use super::{Primitive, Scene, TextMeasure};
+
+impl TextMeasure for egui::Context {
+    fn text_size(&self, text: &str, font_size: f32) -> egui::Vec2 {
+        let font_id = egui::FontId::proportional(font_size);
+        self.fonts(|fonts| fonts.layout_no_wrap(text.to_owned(), font_id, egui::Color32::WHITE).size())
+    }
+}
+
+pub fn paint(painter: &egui::Painter, scene: &Scene) {
+    for item in &scene.items {
+        paint_primitive(painter, &item.primitive);
+    }
+}
+
+/// Like [`paint`], with text laid out once in `cache` and reused in later frames; the
+/// scene is consumed, so its points move into the shapes instead of being copied
+pub fn paint_with_cache(painter: &egui::Painter, scene: Scene, cache: &RenderCache) {
+    let mut shapes = Vec::with_capacity(scene.items.len());
+    for item in scene.items {
+        shapes.push(match item.primitive {
+            Primitive::Text { pos, anchor, text, font_size, color } => {
+                let galley = cache.galley(painter.ctx(), &text, font_size);
+                egui::Shape::galley(anchor.anchor_size(pos, galley.size()).min, galley, color)
+            }
+            Primitive::Polyline { points, stroke, dash: Some(dash) } => {
+                shapes.extend(egui::Shape::dashed_line(&points, stroke, dash.length, dash.gap));
+                continue;
+            }
+            Primitive::Polyline { points, stroke, dash: None } => egui::Shape::line(points, stroke),
+            Primitive::Polygon { points, fill, stroke } => egui::Shape::convex_polygon(points, fill, stroke),
+            primitive => shape(&primitive),
+        });
+    }
+    painter.extend(shapes);
+}
+
+fn paint_primitive(painter: &egui::Painter, primitive: &Primitive) {
+    match primitive {
+        Primitive::Polyline { points, stroke, dash: Some(dash) } => {
+            painter.extend(egui::Shape::dashed_line(points, *stroke, dash.length, dash.gap));
+        }
+        Primitive::Text { pos, anchor, text, font_size, color } => {
+            painter.text(*pos, *anchor, text, egui::FontId::proportional(*font_size), *color);
+        }
+        primitive => {
+            painter.add(shape(primitive));
+        }
+    }
+}
+
+/// The egui shape of a primitive that is one shape (not text or a dashed line)
+fn shape(primitive: &Primitive) -> egui::Shape {
+    match primitive {
+        Primitive::RoundedRect { rect, rounding, fill, stroke } => egui::epaint::RectShape::new(*rect, *rounding, *fill, *stroke).into(),
+        Primitive::Circle { center, radius, fill, stroke } => egui::epaint::CircleShape { center: *center, radius: *radius, fill: *fill, stroke: *stroke }.into(),
+        Primitive::Polyline { points, stroke, .. } => egui::Shape::line(points.clone(), *stroke),
+        Primitive::Polygon { points, fill, stroke } => egui::Shape::convex_polygon(points.clone(), *fill, *stroke),
+        Primitive::Text { .. } => unreachable!("text is laid out by the caller"),
+    }
+}
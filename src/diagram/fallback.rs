@@ -0,0 +1,274 @@
+//! Fallback Layout
+//! Pure-Rust node placement that needs no layout engine (Node.js + Dagre), for
+//! previews like the template gallery's thumbnails: states in rows by their
+//! distance from the initial state, pushed apart by a short force-directed pass.
+//! [`fallback_layout`] adds straight edges through label nodes, for the canvas when
+//! Dagre fails. [`grid_layout`] lays out machines without transitions, which leave
+//! the engine nothing to do.
+
+use std::collections::{HashMap, VecDeque};
+
+use eframe::egui::{self, Pos2, Rect, Vec2};
+
+use super::{
+    calculate_state_rect, estimate_state_size, initial_label_node, layout_bounds, transition_type, LayoutedDiagram, LayoutedEdge, LayoutedLabel, StateBoxContent,
+    TransitionType, LABEL_NODE_PREFIX,
+};
+use crate::fsm::{FsmDefinition, Transition, TransitionId, ANY_STATE};
+
+/// Size of the `[*]` and `*` pseudo nodes
+const PSEUDO_NODE_SIZE: f32 = 28.0;
+
+/// Node centers of `fsm`'s states, in diagram coordinates (deterministic: the same
+/// FSM always gets the same positions)
+pub fn fallback_positions(fsm: &FsmDefinition, font_size: f32, state_box: &StateBoxContent) -> HashMap<String, Pos2> {
+    if fsm.states.is_empty() {
+        return HashMap::new();
+    }
+
+    // Spacing from the largest state box keeps rows and columns from colliding
+    let sizes = fsm.states.iter().map(|s| estimate_state_size(s, font_size, state_box));
+    let max_size = sizes.fold(egui::Vec2::ZERO, |a, b| a.max(b));
+    let spacing_x = max_size.x + 280.0;
+    let spacing_y = max_size.y + 220.0;
+
+    // Rows centered on x = 0, states in declaration order within a row
+    let levels = state_levels(fsm);
+    let mut row_sizes: HashMap<i32, usize> = HashMap::new();
+    for state in &fsm.states {
+        *row_sizes.entry(levels[&state.name]).or_insert(0) += 1;
+    }
+    let mut row_filled: HashMap<i32, usize> = HashMap::new();
+    let mut positions: Vec<(&str, i32, Pos2)> = Vec::new();
+    for state in &fsm.states {
+        let level = levels[&state.name];
+        let row_width = (row_sizes[&level] - 1) as f32 * spacing_x;
+        let column = row_filled.entry(level).or_insert(0);
+        let x = -row_width / 2.0 + *column as f32 * spacing_x;
+        *column += 1;
+        positions.push((&state.name, level, egui::pos2(x, level as f32 * spacing_y)));
+    }
+
+    apply_force_layout(&mut positions, spacing_x * 0.8, spacing_y * 0.6);
+    positions.into_iter().map(|(name, _, pos)| (name.to_string(), pos)).collect()
+}
+
+/// Rows by breadth-first distance from the initial states (the first state if none is
+/// marked); states they cannot reach go in a row below all the others
+fn state_levels(fsm: &FsmDefinition) -> HashMap<String, i32> {
+    let mut levels: HashMap<String, i32> = HashMap::new();
+    let mut queue: VecDeque<(&str, i32)> = VecDeque::new();
+    let mut initial: Vec<&str> = fsm.initial_candidates().into_iter().rev().map(|t| t.target.as_str()).collect();
+    if initial.is_empty() {
+        initial.extend(fsm.states.first().map(|s| s.name.as_str()));
+    }
+    for state in initial {
+        if levels.insert(state.to_string(), 0).is_none() {
+            queue.push_back((state, 0));
+        }
+    }
+
+    let mut outgoing: HashMap<&str, Vec<&str>> = HashMap::new();
+    for transition in &fsm.transitions {
+        if transition.source != "[*]" && !transition.target.starts_with("<<") && transition.target != "[*]" {
+            outgoing.entry(transition.source.as_str()).or_default().push(transition.target.as_str());
+        }
+    }
+
+    while let Some((state, level)) = queue.pop_front() {
+        for &target in outgoing.get(state).into_iter().flatten() {
+            if !levels.contains_key(target) {
+                levels.insert(target.to_string(), level + 1);
+                queue.push_back((target, level + 1));
+            }
+        }
+    }
+
+    let unreachable = levels.values().max().copied().unwrap_or(0) + 1;
+    for state in &fsm.states {
+        levels.entry(state.name.clone()).or_insert(unreachable);
+    }
+    levels
+}
+
+/// Repel nodes from each other (more strongly within a row), pull them towards the
+/// vertical center line, then enforce `min_x` within a row and `min_y` between rows
+fn apply_force_layout(positions: &mut [(&str, i32, Pos2)], min_x: f32, min_y: f32) {
+    const ITERATIONS: usize = 100;
+    const REPULSION: f32 = 15000.0;
+    const ATTRACTION: f32 = 0.01;
+
+    for _ in 0..ITERATIONS {
+        for i in 0..positions.len() {
+            let (_, level, pos) = positions[i];
+            let mut force = egui::Vec2::ZERO;
+            for (j, &(_, other_level, other_pos)) in positions.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                let diff = pos - other_pos;
+                let dist = diff.length().max(50.0);
+                let level_factor = if level == other_level { 2.0 } else { 1.0 };
+                force += diff.normalized() * (REPULSION * level_factor / (dist * dist));
+            }
+            force.x += -pos.x * ATTRACTION;
+
+            let mut moved = pos + force * 0.5;
+            for (j, &(_, other_level, other_pos)) in positions.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                let diff = moved - other_pos;
+                if level == other_level && diff.x.abs() < min_x {
+                    let push = (min_x - diff.x.abs()) / 2.0 + 10.0;
+                    moved.x += if diff.x >= 0.0 { push } else { -push };
+                }
+                if level != other_level && diff.y.abs() < min_y {
+                    let push = (min_y - diff.y.abs()) / 2.0 + 10.0;
+                    moved.y += if diff.y >= 0.0 { push } else { -push };
+                }
+            }
+            positions[i].2 = moved;
+        }
+    }
+}
+
+/// Node centers for a machine without transitions: a near-square grid centered on the
+/// origin, filled row by row in declaration order
+pub fn grid_positions(fsm: &FsmDefinition, font_size: f32, state_box: &StateBoxContent) -> HashMap<String, Pos2> {
+    let max_size = fsm.states.iter().map(|s| estimate_state_size(s, font_size, state_box)).fold(Vec2::ZERO, |a, b| a.max(b));
+    // Room between rows for the `[*]` marker above a state
+    let spacing = max_size + Vec2::new(80.0, 120.0);
+    let columns = (fsm.states.len() as f32).sqrt().ceil().max(1.0) as usize;
+    let rows = (fsm.states.len() + columns - 1) / columns;
+    let offset = Vec2::new((columns - 1) as f32, rows.saturating_sub(1) as f32) * spacing / 2.0;
+    fsm.states
+        .iter()
+        .enumerate()
+        .map(|(i, state)| {
+            let cell = Vec2::new((i % columns) as f32, (i / columns) as f32);
+            (state.name.clone(), (cell * spacing - offset).to_pos2())
+        })
+        .collect()
+}
+
+/// A complete layout from [`grid_positions`], with only the `[*]` edge: what an FSM
+/// without transitions (or without states) shows, computed without the engine
+pub fn grid_layout(fsm: &FsmDefinition, font_size: f32, state_box: &StateBoxContent) -> (LayoutedDiagram, HashMap<String, Pos2>) {
+    straight_layout(fsm, grid_positions(fsm, font_size, state_box), font_size, state_box, &|_| String::new())
+}
+
+/// A complete layout from [`fallback_positions`]: each transition runs straight from
+/// its source's drawn box to a label node halfway to its target and on to the target's
+/// (self-transitions loop through a label above the state). `label` gives a
+/// transition's label text. Returns the diagram and every node's center, like a
+/// Dagre layout.
+pub fn fallback_layout(
+    fsm: &FsmDefinition,
+    font_size: f32,
+    state_box: &StateBoxContent,
+    label: &dyn Fn(&Transition) -> String,
+) -> (LayoutedDiagram, HashMap<String, Pos2>) {
+    straight_layout(fsm, fallback_positions(fsm, font_size, state_box), font_size, state_box, label)
+}
+
+/// Straight edges between the states at `positions`, plus the pseudo nodes
+fn straight_layout(
+    fsm: &FsmDefinition,
+    mut positions: HashMap<String, Pos2>,
+    font_size: f32,
+    state_box: &StateBoxContent,
+    label: &dyn Fn(&Transition) -> String,
+) -> (LayoutedDiagram, HashMap<String, Pos2>) {
+    let mut rects: HashMap<String, Rect> = fsm
+        .states
+        .iter()
+        .filter_map(|s| positions.get(&s.name).map(|&pos| (s.name.clone(), calculate_state_rect(s, pos, 1.0, font_size, state_box))))
+        .collect();
+    let pseudo = Vec2::splat(PSEUDO_NODE_SIZE);
+    if let Some(initial) = fsm.initial_state().and_then(|name| rects.get(name)).copied() {
+        let pos = initial.center_top() - Vec2::new(0.0, 80.0);
+        positions.insert("[*]".to_string(), pos);
+        rects.insert("[*]".to_string(), Rect::from_center_size(pos, pseudo));
+    }
+    if fsm.transitions.iter().any(|t| t.is_from_any_state()) {
+        let left = rects.values().map(|r| r.left()).fold(f32::INFINITY, f32::min);
+        let pos = egui::pos2(if left.is_finite() { left - 120.0 } else { 0.0 }, 0.0);
+        positions.insert(ANY_STATE.to_string(), pos);
+        rects.insert(ANY_STATE.to_string(), Rect::from_center_size(pos, pseudo));
+    }
+
+    let transition_ids = fsm.transition_ids();
+    let mut edges = Vec::new();
+    let mut labels = Vec::new();
+    if let Some(&start) = rects.get("[*]") {
+        for (index, initial) in fsm.initial_candidates().into_iter().enumerate() {
+            let Some(&target) = rects.get(&initial.target) else {
+                continue;
+            };
+            let Some((node, text)) = initial_label_node(index, initial) else {
+                edges.push(fallback_edge("[*]", &initial.target, None, vec![boundary_point(start, target.center()), boundary_point(target, start.center())]));
+                continue;
+            };
+            let label_pos = start.center().lerp(target.center(), 0.5);
+            positions.insert(node.clone(), label_pos);
+            edges.push(fallback_edge("[*]", &node, None, vec![boundary_point(start, label_pos), label_pos]));
+            edges.push(fallback_edge(&node, &initial.target, None, vec![label_pos, boundary_point(target, label_pos)]));
+            labels.push(LayoutedLabel { pos: label_pos, text, transition: None });
+        }
+    }
+    for (index, transition) in fsm.transitions.iter().enumerate() {
+        let (Some(&from), Some(&to)) = (rects.get(&transition.source), rects.get(&transition.target)) else {
+            continue;
+        };
+        if transition.source == "[*]" {
+            continue;
+        }
+        let id = transition_ids[index];
+        let node = format!("{LABEL_NODE_PREFIX}{id}");
+        let (into, label_pos, out_of) = if transition.source == transition.target {
+            let pos = from.center_top() - Vec2::new(0.0, 60.0);
+            (from.left_top() + Vec2::new(from.width() * 0.25, 0.0), pos, from.right_top() - Vec2::new(from.width() * 0.25, 0.0))
+        } else {
+            let pos = from.center().lerp(to.center(), 0.5);
+            (boundary_point(from, pos), pos, boundary_point(to, pos))
+        };
+        positions.insert(node.clone(), label_pos);
+
+        edges.push(fallback_edge(&transition.source, &node, Some((transition, id)), vec![into, label_pos]));
+        edges.push(fallback_edge(&node, &transition.target, Some((transition, id)), vec![label_pos, out_of]));
+        let text = label(transition);
+        if !text.is_empty() {
+            labels.push(LayoutedLabel { pos: label_pos, text, transition: Some(id) });
+        }
+    }
+
+    let bounds = layout_bounds(rects.values().copied(), edges.iter().flat_map(|e| e.points.iter().copied()));
+    let layout = LayoutedDiagram { edges, labels, bounds, fsm_name: fsm.name.clone(), states: fsm.states.clone() };
+    (layout, positions)
+}
+
+fn fallback_edge(v: &str, w: &str, transition: Option<(&Transition, TransitionId)>, points: Vec<Pos2>) -> LayoutedEdge {
+    let t = transition.map(|(t, _)| t);
+    LayoutedEdge {
+        v: v.to_string(),
+        w: w.to_string(),
+        transition: transition.map(|(_, id)| id),
+        points,
+        transition_type: t.map_or(TransitionType::Forward, transition_type),
+        is_else: t.and_then(|t| t.guard.as_ref()).is_some_and(|g| g.is_else()),
+        is_local: t.is_some_and(|t| t.is_local()),
+        is_dashed: t.is_some_and(|t| t.is_dashed()),
+    }
+}
+
+/// Where the line from the center of `rect` towards `toward` leaves it
+fn boundary_point(rect: Rect, toward: Pos2) -> Pos2 {
+    let dir = toward - rect.center();
+    if dir.length() < f32::EPSILON {
+        return rect.center();
+    }
+    let scale_x = if dir.x.abs() > f32::EPSILON { rect.width() / 2.0 / dir.x.abs() } else { f32::INFINITY };
+    let scale_y = if dir.y.abs() > f32::EPSILON { rect.height() / 2.0 / dir.y.abs() } else { f32::INFINITY };
+    rect.center() + dir * scale_x.min(scale_y).min(1.0)
+}
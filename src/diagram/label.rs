@@ -0,0 +1,112 @@
+//! Transition Labels
+//! Wraps `event [guard] / actions` labels into short lines for the diagram, and
+//! shortens lines that are still too wide so one long guard cannot blow up the layout
+
+/// Characters per label line the wrapping aims for
+pub const LABEL_WRAP_CHARS: usize = 24;
+
+/// Widest label line handed to the layout engine by default (layout px)
+pub const DEFAULT_MAX_LABEL_WIDTH: f32 = 220.0;
+
+/// Break a transition label into lines of at most `max_chars` where it can: the
+/// event at underscores, the guard before `&&`/`||` and after commas, and the
+/// actions one per line when they do not fit on one. Pieces that cannot be broken
+/// (a long identifier) stay whole; [`fit_label_width`] shortens them.
+pub fn format_label_text(label: &str, max_chars: usize) -> String {
+    // Actions go below the trigger
+    let split = label
+        .rsplit_once("/ ")
+        .filter(|(_, actions)| !actions.contains(']'));
+    let (trigger, actions) = match split {
+        Some((trigger, actions)) => (trigger.trim(), Some(actions)),
+        None => (label.trim(), None),
+    };
+
+    let mut lines = match trigger.find('[') {
+        Some(bracket) => {
+            let mut lines = wrap(split_after(trigger[..bracket].trim(), '_'), max_chars);
+            lines.extend(wrap(guard_pieces(&trigger[bracket..]), max_chars));
+            lines
+        }
+        None => wrap(split_after(trigger, '_'), max_chars),
+    };
+
+    if let Some(actions) = actions {
+        let actions: Vec<&str> = actions.split("; ").collect();
+        let one_line = format!("/ {}", actions.join("; "));
+        if actions.len() > 1 && one_line.chars().count() > max_chars {
+            for (i, action) in actions.iter().enumerate() {
+                let prefix = if i == 0 { "/ " } else { "  " };
+                let suffix = if i + 1 < actions.len() { ";" } else { "" };
+                lines.push(format!("{prefix}{action}{suffix}"));
+            }
+        } else {
+            lines.push(one_line);
+        }
+    }
+    lines.join("\n")
+}
+
+/// Cut every line wider than `max_width` (as measured by `width_of`) and end it with `…`
+pub fn fit_label_width(text: &str, max_width: f32, width_of: impl Fn(&str) -> f32) -> String {
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| {
+            if width_of(line) <= max_width {
+                return line.to_string();
+            }
+            let mut chars: Vec<char> = line.chars().collect();
+            while !chars.is_empty() {
+                chars.pop();
+                let cut = format!("{}…", chars.iter().collect::<String>().trim_end());
+                if width_of(&cut) <= max_width {
+                    return cut;
+                }
+            }
+            "…".to_string()
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// `a_b_c` → `a_`, `b_`, `c`
+fn split_after(text: &str, separator: char) -> Vec<&str> {
+    text.split_inclusive(separator).collect()
+}
+
+/// A guard split before each `&&` and `||` and after each comma
+fn guard_pieces(guard: &str) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    for (i, c) in guard.char_indices() {
+        let operator = guard[i..].starts_with("&&") || guard[i..].starts_with("||");
+        if operator && i > start && !guard[..i].ends_with(['&', '|']) {
+            pieces.push(&guard[start..i]);
+            start = i;
+        } else if c == ',' {
+            pieces.push(&guard[start..=i]);
+            start = i + 1;
+        }
+    }
+    pieces.push(&guard[start..]);
+    pieces
+}
+
+/// Join `pieces` greedily into lines of at most `max_chars` (a longer piece gets a line of its own)
+fn wrap(pieces: Vec<&str>, max_chars: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for piece in pieces {
+        let fits = line.chars().count() + piece.trim_end().chars().count() <= max_chars;
+        if line.trim().is_empty() || fits {
+            line.push_str(piece);
+        } else {
+            lines.push(line.trim_end().to_string());
+            line = piece.trim_start().to_string();
+        }
+    }
+    if !line.trim().is_empty() {
+        lines.push(line.trim().to_string());
+    }
+    lines
+}
@@ -0,0 +1,1328 @@
+//! Diagram Rendering
+//! Turns a laid-out FSM into backend-agnostic drawing primitives (a scene), so
+//! the canvas and image export share one renderer that can be tested without a GUI
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use eframe::egui::{self, Align2, Color32, Pos2, Rect, Rounding, Stroke, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::fsm::{self, FsmDefinition, StateType, TransitionId, ANY_STATE};
+use render_cache::RenderCache;
+
+pub mod dagre;
+pub mod egui_backend;
+pub mod fallback;
+pub mod quality;
+pub mod label;
+pub mod placement;
+pub mod render_cache;
+pub mod svg_backend;
+
+#[cfg(test)]
+mod tests;
+
+/// Layout nodes standing in for a transition's label are named `__tr_<id>` (its [`TransitionId`])
+pub const LABEL_NODE_PREFIX: &str = "__tr_";
+
+/// Default diagram font size (state names); actions and notes are drawn a little
+/// smaller and state boxes grow with it
+pub const DEFAULT_DIAGRAM_FONT_SIZE: f32 = 13.0;
+
+/// What a state box shows below its name (the diagram's Display popover). Node sizes
+/// follow it, so a change needs a new layout; the default shows entry/exit actions only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StateBoxContent {
+    /// `event [guard] / action` lines below a thin separator
+    pub internal_transitions: bool,
+    /// `entry/ ...` and `exit/ ...` lines
+    pub entry_exit_actions: bool,
+    /// Action arguments: `add_to_balance(coin)` instead of `add_to_balance`
+    pub action_params: bool,
+    /// The state's description above its actions
+    pub descriptions: bool,
+}
+
+impl Default for StateBoxContent {
+    fn default() -> Self {
+        Self {
+            internal_transitions: false,
+            entry_exit_actions: true,
+            action_params: true,
+            descriptions: false,
+        }
+    }
+}
+
+// ============================================================================
+// LAYOUT (engine output)
+// ============================================================================
+
+/// Determine the type of transition for rendering decisions (layout is engine-driven).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TransitionType {
+    #[default]
+    Forward,      // Main flow - straight arrows
+    Return,       // Return transitions - curved arrows
+    Conditional,  // Has guards - curved arrows
+    Timer,        // Timer events - label above
+}
+
+/// Edge style of a transition (styling only; it does not affect the layout)
+pub fn transition_type(transition: &fsm::Transition) -> TransitionType {
+    let event_name = transition.event.as_ref().map(|e| e.name.to_lowercase()).unwrap_or_default();
+    if event_name.contains("timeout") || event_name.contains("timer") || event_name.contains("expired") {
+        TransitionType::Timer
+    } else if transition.guard.is_some() {
+        TransitionType::Conditional
+    } else {
+        TransitionType::Forward
+    }
+}
+
+/// Label node of the `index`th guarded initial transition, between `[*]` and its
+/// target, and the label it shows (`[guard]`); the default arrow has no label node
+pub fn initial_label_node(index: usize, initial: &fsm::InitialTransition) -> Option<(String, String)> {
+    let guard = initial.guard.as_ref()?;
+    Some((format!("{LABEL_NODE_PREFIX}start_{index}"), format!("[{}]", guard.expression.trim())))
+}
+
+#[derive(Clone, Debug)]
+pub struct LayoutedEdge {
+    pub v: String,
+    pub w: String,
+    /// The transition this edge is part of, if any (an id rather than an index, so a layout
+    /// computed before the transitions were reordered still points at the right one)
+    pub transition: Option<TransitionId>,
+    pub points: Vec<Pos2>,
+    pub transition_type: TransitionType,
+    /// `[else]` transition (drawn dashed)
+    pub is_else: bool,
+    /// `local` transition (open arrowhead)
+    pub is_local: bool,
+    /// `-.->` transition (drawn dashed)
+    pub is_dashed: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct LayoutedLabel {
+    pub pos: Pos2,
+    pub text: String,
+    /// Transition this label belongs to
+    pub transition: Option<TransitionId>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LayoutedDiagram {
+    pub edges: Vec<LayoutedEdge>,
+    pub labels: Vec<LayoutedLabel>,
+    /// Bounding box of all nodes and edge routes (diagram coordinates)
+    pub bounds: Rect,
+    /// FSM this diagram was computed for
+    pub fsm_name: String,
+    /// States as they were when the diagram was computed (so removed states can fade out)
+    pub states: Vec<fsm::State>,
+}
+
+/// Bounding box of the given node rects and edge points (a zero rect at the origin if empty)
+pub fn layout_bounds(nodes: impl IntoIterator<Item = Rect>, points: impl IntoIterator<Item = Pos2>) -> Rect {
+    let mut bounds = Rect::NOTHING;
+    for node in nodes {
+        bounds = bounds.union(node);
+    }
+    for p in points {
+        bounds.extend_with(p);
+    }
+    if bounds.is_finite() {
+        bounds
+    } else {
+        Rect::from_min_max(Pos2::ZERO, Pos2::ZERO)
+    }
+}
+
+/// Hash of everything the layout depends on: states and what `state_box` shows in
+/// their boxes, transitions with their labels and kind, notes and the direction hint.
+/// Comments (and descriptions, unless shown) are left out, so editing them does not
+/// re-run the layout engine.
+pub fn layout_fingerprint(fsm: &FsmDefinition, state_box: &StateBoxContent) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    fsm.name.hash(&mut hasher);
+    for initial in &fsm.initial_transitions {
+        initial.target.hash(&mut hasher);
+        initial.guard.as_ref().map(|g| &g.expression).hash(&mut hasher);
+    }
+    for state in &fsm.states {
+        state.name.hash(&mut hasher);
+        std::mem::discriminant(&state.state_type).hash(&mut hasher);
+        StateBody::new(state, state_box).hash(&mut hasher);
+    }
+    for transition in &fsm.transitions {
+        transition.source.hash(&mut hasher);
+        transition.target.hash(&mut hasher);
+        transition.label().hash(&mut hasher);
+        transition.is_local().hash(&mut hasher);
+    }
+    for note in &fsm.notes {
+        note.target_state.hash(&mut hasher);
+        std::mem::discriminant(&note.position).hash(&mut hasher);
+        note.text.hash(&mut hasher);
+    }
+    fsm.layout_hint.map(|hint| std::mem::discriminant(&hint)).hash(&mut hasher);
+    hasher.finish()
+}
+
+// ============================================================================
+// SCENE
+// ============================================================================
+
+/// Dash pattern of a dashed polyline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dash {
+    pub length: f32,
+    pub gap: f32,
+}
+
+/// A drawing primitive in screen coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub enum Primitive {
+    RoundedRect {
+        rect: Rect,
+        rounding: Rounding,
+        fill: Color32,
+        stroke: Stroke,
+    },
+    Circle {
+        center: Pos2,
+        radius: f32,
+        fill: Color32,
+        stroke: Stroke,
+    },
+    Polyline {
+        points: Vec<Pos2>,
+        stroke: Stroke,
+        dash: Option<Dash>,
+    },
+    /// Convex polygon
+    Polygon {
+        points: Vec<Pos2>,
+        fill: Color32,
+        stroke: Stroke,
+    },
+    Text {
+        pos: Pos2,
+        anchor: Align2,
+        text: String,
+        font_size: f32,
+        color: Color32,
+    },
+}
+
+/// What part of the diagram a primitive belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Element {
+    /// Route of a layout edge (a transition has two: into and out of its label node)
+    Edge(Option<TransitionId>),
+    /// Glow behind a highlighted transition's edges
+    EdgeHighlight(TransitionId),
+    /// Arrowhead where a transition reaches its target
+    Arrowhead(Option<TransitionId>),
+    /// Transition label box and text
+    Label,
+    InitialMarker,
+    /// Node wildcard (`* -->`) transitions leave from
+    AnyStateMarker,
+    /// Note box, connector and text (by target state)
+    Note(String),
+    /// Outline of a state, the rect used for hit-testing
+    StateBox(String),
+    /// Header, separator and text inside a state box
+    StateContent(String),
+    /// Dot travelling along the last fired transition
+    SimToken,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneItem {
+    pub element: Element,
+    pub primitive: Primitive,
+}
+
+/// Primitives in paint order (later items are drawn on top)
+#[derive(Debug, Clone, Default)]
+pub struct Scene {
+    pub items: Vec<SceneItem>,
+}
+
+impl Scene {
+    /// Primitives belonging to `element`
+    pub fn primitives_of(&self, element: Element) -> impl Iterator<Item = &Primitive> + '_ {
+        self.items.iter().filter(move |i| i.element == element).map(|i| &i.primitive)
+    }
+}
+
+/// Colors of the diagram
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagramTheme {
+    pub background: Color32,
+    pub state_fill: Color32,
+    pub composite_fill: Color32,
+    pub final_fill: Color32,
+    pub state_header: Color32,
+    pub composite_header: Color32,
+    pub final_header: Color32,
+    pub state_stroke: Color32,
+    pub initial_stroke: Color32,
+    /// Active simulator state and the simulator token
+    pub accent: Color32,
+    pub state_name: Color32,
+    pub state_actions: Color32,
+    pub edge_forward: Color32,
+    pub edge_return: Color32,
+    pub edge_conditional: Color32,
+    pub edge_timer: Color32,
+    /// Glow behind highlighted transitions
+    pub highlight: Color32,
+    pub label_fill: Color32,
+    pub label_stroke: Color32,
+    pub label_text: Color32,
+    pub note_fill: Color32,
+    pub note_stroke: Color32,
+    pub note_text: Color32,
+    pub initial_marker_outer: Color32,
+    pub initial_marker_inner: Color32,
+    pub token_outline: Color32,
+}
+
+impl Default for DiagramTheme {
+    fn default() -> Self {
+        Self {
+            background: Color32::from_rgb(25, 28, 32),
+            state_fill: Color32::from_rgb(40, 55, 75),
+            composite_fill: Color32::from_rgb(50, 80, 120),
+            final_fill: Color32::from_rgb(100, 50, 50),
+            state_header: Color32::from_rgb(55, 75, 100),
+            composite_header: Color32::from_rgb(60, 95, 140),
+            final_header: Color32::from_rgb(120, 60, 60),
+            state_stroke: Color32::from_rgb(100, 120, 145),
+            initial_stroke: Color32::from_rgb(100, 220, 100),
+            accent: Color32::from_rgb(255, 220, 120),
+            state_name: Color32::WHITE,
+            state_actions: Color32::from_rgb(180, 200, 220),
+            edge_forward: Color32::from_rgb(150, 160, 180),
+            edge_return: Color32::from_rgb(120, 180, 140),
+            edge_conditional: Color32::from_rgb(180, 150, 120),
+            edge_timer: Color32::from_rgb(180, 180, 120),
+            highlight: Color32::from_rgba_unmultiplied(255, 220, 120, 90),
+            label_fill: Color32::from_rgb(30, 35, 45),
+            label_stroke: Color32::from_rgb(70, 80, 95),
+            label_text: Color32::from_rgb(255, 230, 120),
+            note_fill: Color32::from_rgb(250, 232, 150),
+            note_stroke: Color32::from_rgb(190, 160, 80),
+            note_text: Color32::from_rgb(60, 50, 20),
+            initial_marker_outer: Color32::WHITE,
+            initial_marker_inner: Color32::BLACK,
+            token_outline: Color32::from_rgb(40, 30, 20),
+        }
+    }
+}
+
+/// Colors a state box is drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateColors {
+    pub fill: Color32,
+    pub header: Color32,
+    /// State name on the header
+    pub name: Color32,
+}
+
+impl DiagramTheme {
+    /// Colors of `state`'s box: the theme's for its type, with the header in the
+    /// state's `#color(...)` (and dark name text on light colors)
+    pub fn state_colors(&self, state: &fsm::State) -> StateColors {
+        let (fill, header) = match state.state_type {
+            StateType::Composite => (self.composite_fill, self.composite_header),
+            StateType::Final => (self.final_fill, self.final_header),
+            _ => (self.state_fill, self.state_header),
+        };
+        match state.rgb() {
+            Some(rgb) => StateColors {
+                fill,
+                header: Color32::from_rgb(rgb[0], rgb[1], rgb[2]),
+                name: if fsm::color::is_light(rgb) { Color32::BLACK } else { self.state_name },
+            },
+            None => StateColors {
+                fill,
+                header,
+                name: self.state_name,
+            },
+        }
+    }
+
+    pub fn edge_color(&self, transition_type: TransitionType) -> Color32 {
+        match transition_type {
+            TransitionType::Forward => self.edge_forward,
+            TransitionType::Return => self.edge_return,
+            TransitionType::Conditional => self.edge_conditional,
+            TransitionType::Timer => self.edge_timer,
+        }
+    }
+}
+
+/// Maps diagram coordinates to screen coordinates: `p * zoom + offset`
+#[derive(Debug, Clone, Copy)]
+pub struct Viewport {
+    pub offset: Vec2,
+    pub zoom: f32,
+}
+
+/// Zoom limits shared by every way of zooming the canvas
+pub const MIN_ZOOM: f32 = 0.1;
+pub const MAX_ZOOM: f32 = 3.0;
+
+impl Viewport {
+    pub fn to_screen(self, p: Pos2) -> Pos2 {
+        (p.to_vec2() * self.zoom + self.offset).to_pos2()
+    }
+
+    /// Zoom (clamped) that keeps the diagram point under `anchor` where it is on screen
+    pub fn zoom_at(self, anchor: Pos2, zoom: f32) -> Self {
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        Self {
+            offset: anchor.to_vec2() - (anchor.to_vec2() - self.offset) * (zoom / self.zoom),
+            zoom,
+        }
+    }
+
+    /// Largest zoom (clamped) that shows `bounds` inside `canvas` with `margin` pixels
+    /// to spare on every side, centred
+    pub fn fit(bounds: Rect, canvas: Rect, margin: f32) -> Self {
+        let available = (canvas.size() - Vec2::splat(2.0 * margin)).max(Vec2::splat(1.0));
+        let size = bounds.size().max(Vec2::splat(1.0));
+        let zoom = (available.x / size.x).min(available.y / size.y).clamp(MIN_ZOOM, MAX_ZOOM);
+        Self {
+            offset: canvas.center().to_vec2() - bounds.center().to_vec2() * zoom,
+            zoom,
+        }
+    }
+}
+
+/// Zoom and pan of one diagram viewport; the pan is relative to the centre of the
+/// canvas rect it is shown in, so resizing the rect keeps the diagram centred
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CanvasView {
+    pub zoom: f32,
+    pub pan: Vec2,
+}
+
+impl Default for CanvasView {
+    fn default() -> Self {
+        Self { zoom: 1.0, pan: Vec2::ZERO }
+    }
+}
+
+impl CanvasView {
+    /// The transform that draws the diagram on `canvas`
+    pub fn viewport(self, canvas: Rect) -> Viewport {
+        Viewport {
+            offset: canvas.center().to_vec2() + self.pan,
+            zoom: self.zoom,
+        }
+    }
+
+    /// Diagram point shown at `p` on `canvas`
+    pub fn to_diagram(self, canvas: Rect, p: Pos2) -> Pos2 {
+        ((p - canvas.center() - self.pan) / self.zoom).to_pos2()
+    }
+
+    /// Zoom (clamped) about `anchor`, given relative to the canvas centre
+    pub fn zoom_at(&mut self, anchor: Vec2, zoom: f32) {
+        let viewport = Viewport { offset: self.pan, zoom: self.zoom }.zoom_at(anchor.to_pos2(), zoom);
+        self.zoom = viewport.zoom;
+        self.pan = viewport.offset;
+    }
+
+    /// Show all of `bounds` on `canvas` (see [`Viewport::fit`])
+    pub fn fit(&mut self, bounds: Rect, canvas: Rect, margin: f32) {
+        let fit = Viewport::fit(bounds, canvas, margin);
+        self.zoom = fit.zoom;
+        self.pan = fit.offset - canvas.center().to_vec2();
+    }
+
+    /// Pan so that the diagram point `p` is drawn `at` from the canvas centre
+    pub fn show_at(&mut self, p: Pos2, at: Vec2) {
+        self.pan = at - p.to_vec2() * self.zoom;
+    }
+}
+
+/// Size of a single line of text, as the painting backend would lay it out
+pub trait TextMeasure {
+    fn text_size(&self, text: &str, font_size: f32) -> Vec2;
+}
+
+/// Simulator and selection state drawn over the diagram
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimOverlay<'a> {
+    /// Current simulator state (drawn with the accent stroke)
+    pub active_state: Option<&'a str>,
+    /// Transitions drawn with a glow (event matrix selection)
+    pub highlighted_transitions: &'a [TransitionId],
+    pub token: Option<SimToken<'a>>,
+}
+
+/// Progress of the last fired transition's animation
+#[derive(Debug, Clone, Copy)]
+pub struct SimToken<'a> {
+    pub transition: TransitionId,
+    pub from: &'a str,
+    pub to: &'a str,
+    /// 0..=1 along the route
+    pub progress: f32,
+}
+
+/// The hovered state or transition and what it connects; the rest of the diagram is dimmed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HoverFocus {
+    /// Highlighted states (`[*]` for the initial marker)
+    pub states: HashSet<String>,
+    /// Highlighted transitions
+    pub transitions: HashSet<TransitionId>,
+}
+
+impl HoverFocus {
+    /// `name`, its incoming and outgoing transitions and the states at their other ends
+    pub fn state(fsm: &FsmDefinition, name: &str) -> Self {
+        let mut focus = Self::default();
+        focus.states.insert(name.to_string());
+        if fsm.initial_transitions.iter().any(|t| t.target == name) {
+            focus.states.insert("[*]".to_string());
+        }
+        for (transition, id) in fsm.transitions.iter().zip(fsm.transition_ids()) {
+            if transition.source == name || transition.target == name {
+                focus.transitions.insert(id);
+                focus.states.insert(transition.source.clone());
+                focus.states.insert(transition.target.clone());
+            }
+        }
+        focus
+    }
+
+    /// Transition `index` and its two ends
+    pub fn transition(fsm: &FsmDefinition, index: usize) -> Self {
+        let mut focus = Self::default();
+        if let (Some(transition), Some(id)) = (fsm.transitions.get(index), fsm.transition_id(index)) {
+            focus.transitions.insert(id);
+            focus.states.insert(transition.source.clone());
+            focus.states.insert(transition.target.clone());
+        }
+        focus
+    }
+
+    fn has_edge(&self, edge: &LayoutedEdge) -> bool {
+        match edge.transition {
+            Some(id) => self.transitions.contains(&id),
+            None => self.states.contains(&edge.v) && self.states.contains(&edge.w),
+        }
+    }
+}
+
+/// Opacity of everything outside the hover focus
+const DIMMED_OPACITY: f32 = 0.35;
+
+/// Everything `build_scene` draws
+pub struct DiagramContent<'a> {
+    pub fsm: &'a FsmDefinition,
+    pub layout: &'a LayoutedDiagram,
+    /// Node centers (diagram coordinates), including `[*]` and `*`
+    pub positions: &'a HashMap<String, Pos2>,
+    /// Diagram font size (state names), see [`DEFAULT_DIAGRAM_FONT_SIZE`]
+    pub font_size: f32,
+    pub label_font_size: f32,
+    pub state_box: StateBoxContent,
+    pub overlay: SimOverlay<'a>,
+}
+
+/// The whole diagram: edges and labels, initial marker, notes, states, then the overlay
+pub fn build_scene(content: &DiagramContent, theme: &DiagramTheme, measure: &dyn TextMeasure, viewport: Viewport) -> Scene {
+    let mut scene = SceneBuilder::new(theme, measure, viewport);
+    scene.set_font_size(content.font_size);
+    scene.set_state_box(content.state_box);
+    scene.edges(content.layout, content.label_font_size, content.overlay.highlighted_transitions);
+    if let Some(&pos) = content.positions.get("[*]") {
+        scene.initial_marker(pos);
+    }
+    if let Some(&pos) = content.positions.get(ANY_STATE) {
+        scene.any_state_marker(pos);
+    }
+    for note in &content.fsm.notes {
+        let state = content.fsm.states.iter().find(|s| s.name == note.target_state);
+        if let (Some(state), Some(&pos)) = (state, content.positions.get(&note.target_state)) {
+            scene.note(state, pos, note);
+        }
+    }
+    for state in &content.fsm.states {
+        if let Some(&pos) = content.positions.get(&state.name) {
+            let is_initial = content.fsm.initial_state() == Some(state.name.as_str());
+            let is_active = content.overlay.active_state == Some(state.name.as_str());
+            scene.state(state, pos, is_initial, is_active);
+        }
+    }
+    if let Some(token) = &content.overlay.token {
+        scene.sim_token(content.layout, token);
+    }
+    scene.finish()
+}
+
+/// Adds diagram parts to a scene one at a time (positions in diagram coordinates),
+/// for callers that interpolate or fade parts, like the animated canvas
+pub struct SceneBuilder<'a> {
+    theme: &'a DiagramTheme,
+    measure: &'a dyn TextMeasure,
+    viewport: Viewport,
+    font_size: f32,
+    state_box: StateBoxContent,
+    opacity: f32,
+    focus: Option<HoverFocus>,
+    /// Whether the part being added is outside the hover focus
+    dimmed: bool,
+    /// State boxes measured in earlier frames
+    cache: Option<&'a RenderCache>,
+    scene: Scene,
+}
+
+impl<'a> SceneBuilder<'a> {
+    pub fn new(theme: &'a DiagramTheme, measure: &'a dyn TextMeasure, viewport: Viewport) -> Self {
+        Self {
+            theme,
+            measure,
+            viewport,
+            font_size: DEFAULT_DIAGRAM_FONT_SIZE,
+            state_box: StateBoxContent::default(),
+            opacity: 1.0,
+            focus: None,
+            dimmed: false,
+            cache: None,
+            scene: Scene::default(),
+        }
+    }
+
+    /// Take state box sizes and body text from `cache` (prepared for the same FSM,
+    /// font size and state box content) instead of measuring every state again
+    pub fn set_render_cache(&mut self, cache: &'a RenderCache) {
+        self.cache = Some(cache);
+    }
+
+    /// Screen rect of `state` at `pos`
+    fn state_rect(&self, state: &fsm::State, pos: Pos2) -> Rect {
+        let center = self.viewport.to_screen(pos);
+        match self.cache {
+            Some(cache) => cache.state_rect(state, center, self.viewport.zoom, self.font_size, &self.state_box),
+            None => calculate_state_rect(state, center, self.viewport.zoom, self.font_size, &self.state_box),
+        }
+    }
+
+    /// Diagram font size of states and notes added from now on
+    pub fn set_font_size(&mut self, font_size: f32) {
+        self.font_size = font_size;
+    }
+
+    /// What the boxes of states (and the rects notes attach to) added from now on show
+    pub fn set_state_box(&mut self, state_box: StateBoxContent) {
+        self.state_box = state_box;
+    }
+
+    /// Opacity of everything added from now on
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity.clamp(0.0, 1.0);
+    }
+
+    /// Highlight `focus` (accent strokes) and dim everything else added from now on
+    pub fn set_focus(&mut self, focus: Option<HoverFocus>) {
+        self.focus = focus;
+    }
+
+    /// Whether a state (or `[*]`) is in the hover focus; `None` without a focus
+    fn state_focus(&self, name: &str) -> Option<bool> {
+        self.focus.as_ref().map(|f| f.states.contains(name))
+    }
+
+    pub fn finish(self) -> Scene {
+        self.scene
+    }
+
+    fn push(&mut self, element: Element, mut primitive: Primitive) {
+        let opacity = if self.dimmed { self.opacity * DIMMED_OPACITY } else { self.opacity };
+        if opacity < 1.0 {
+            let fade = |color: &mut Color32| *color = color.gamma_multiply(opacity);
+            match &mut primitive {
+                Primitive::RoundedRect { fill, stroke, .. }
+                | Primitive::Circle { fill, stroke, .. }
+                | Primitive::Polygon { fill, stroke, .. } => {
+                    fade(fill);
+                    fade(&mut stroke.color);
+                }
+                Primitive::Polyline { stroke, .. } => fade(&mut stroke.color),
+                Primitive::Text { color, .. } => fade(color),
+            }
+        }
+        self.scene.items.push(SceneItem { element, primitive });
+    }
+
+    /// Engine-routed edges and their labels (`highlighted` transitions get a glow)
+    pub fn edges(&mut self, layout: &LayoutedDiagram, label_font_size: f32, highlighted: &[TransitionId]) {
+        let zoom = self.viewport.zoom;
+        for edge in &layout.edges {
+            let mut route: Vec<Pos2> = edge.points.iter().map(|&p| self.viewport.to_screen(p)).collect();
+
+            // Ensure there is at least one segment
+            route.dedup_by(|a, b| (a.x - b.x).abs() < 0.01 && (a.y - b.y).abs() < 0.01);
+            if route.len() < 2 {
+                continue;
+            }
+            if let Some(id) = edge.transition.filter(|id| highlighted.contains(id)) {
+                self.push(
+                    Element::EdgeHighlight(id),
+                    Primitive::Polyline {
+                        points: route.clone(),
+                        stroke: Stroke::new(6.0 * zoom, self.theme.highlight),
+                        dash: None,
+                    },
+                );
+            }
+
+            let focused = self.focus.as_ref().map(|f| f.has_edge(edge));
+            self.dimmed = focused == Some(false);
+            let (color, width) = if focused == Some(true) {
+                (self.theme.accent, 2.5)
+            } else {
+                (self.theme.edge_color(edge.transition_type), 1.5)
+            };
+            let arrowhead = arrowhead(&route, zoom);
+            self.push(
+                Element::Edge(edge.transition),
+                Primitive::Polyline {
+                    points: route,
+                    stroke: Stroke::new(width * zoom, color),
+                    dash: (edge.is_else || edge.is_dashed).then_some(Dash {
+                        length: 8.0 * zoom,
+                        gap: 5.0 * zoom,
+                    }),
+                },
+            );
+            // The half of a transition that ends at its label continues past it
+            if !edge.w.starts_with(LABEL_NODE_PREFIX) {
+                let (fill, stroke) = if edge.is_local {
+                    (self.theme.background, Stroke::new(1.5 * zoom, color))
+                } else {
+                    (color, Stroke::NONE)
+                };
+                self.push(
+                    Element::Arrowhead(edge.transition),
+                    Primitive::Polygon {
+                        points: arrowhead.to_vec(),
+                        fill,
+                        stroke,
+                    },
+                );
+            }
+        }
+
+        // Labels are nodes placed by the engine (no edge-label proxy required).
+        for label in &layout.labels {
+            self.dimmed = self
+                .focus
+                .as_ref()
+                .is_some_and(|f| !label.transition.is_some_and(|id| f.transitions.contains(&id)));
+            let pos = self.viewport.to_screen(label.pos);
+            let font_size = label_font_size * zoom;
+            let rect = label_rect(self.measure, label, label_font_size, self.viewport);
+            self.push(
+                Element::Label,
+                Primitive::RoundedRect {
+                    rect,
+                    rounding: Rounding::same(3.0),
+                    fill: self.theme.label_fill,
+                    stroke: Stroke::new(1.0, self.theme.label_stroke),
+                },
+            );
+            self.push(
+                Element::Label,
+                Primitive::Text {
+                    pos,
+                    anchor: Align2::CENTER_CENTER,
+                    text: label.text.clone(),
+                    font_size,
+                    color: self.theme.label_text,
+                },
+            );
+        }
+        self.dimmed = false;
+    }
+
+    /// UML initial pseudo-state
+    pub fn initial_marker(&mut self, pos: Pos2) {
+        let center = self.viewport.to_screen(pos);
+        let zoom = self.viewport.zoom;
+        self.dimmed = self.state_focus("[*]") == Some(false);
+        for (radius, fill) in [(8.0, self.theme.initial_marker_outer), (4.0, self.theme.initial_marker_inner)] {
+            self.push(
+                Element::InitialMarker,
+                Primitive::Circle {
+                    center,
+                    radius: radius * zoom,
+                    fill,
+                    stroke: Stroke::NONE,
+                },
+            );
+        }
+        self.dimmed = false;
+    }
+
+    /// Small "any" circle the edges of wildcard transitions start from
+    pub fn any_state_marker(&mut self, pos: Pos2) {
+        let center = self.viewport.to_screen(pos);
+        let zoom = self.viewport.zoom;
+        self.dimmed = self.state_focus(ANY_STATE) == Some(false);
+        self.push(
+            Element::AnyStateMarker,
+            Primitive::Circle {
+                center,
+                radius: 14.0 * zoom,
+                fill: self.theme.label_fill,
+                stroke: Stroke::new(1.5, self.theme.state_stroke),
+            },
+        );
+        self.push(
+            Element::AnyStateMarker,
+            Primitive::Text {
+                pos: center,
+                anchor: Align2::CENTER_CENTER,
+                text: "any".to_string(),
+                font_size: 10.0 * zoom,
+                color: self.theme.label_text,
+            },
+        );
+        self.dimmed = false;
+    }
+
+    /// Small note box with a dashed connector to its state (at `pos`)
+    pub fn note(&mut self, state: &fsm::State, pos: Pos2, note: &fsm::Note) {
+        let zoom = self.viewport.zoom;
+        self.dimmed = self.state_focus(&state.name) == Some(false);
+        let state_rect = self.state_rect(state, pos);
+        let rect = note_rect(self.measure, state_rect, note, zoom, self.font_size);
+        let (from, to) = match note.position {
+            fsm::NotePosition::Left => (rect.right_center(), egui::pos2(state_rect.left(), rect.center().y)),
+            fsm::NotePosition::Right => (rect.left_center(), egui::pos2(state_rect.right(), rect.center().y)),
+        };
+        let element = Element::Note(note.target_state.clone());
+        let border = self.theme.note_stroke;
+        self.push(
+            element.clone(),
+            Primitive::Polyline {
+                points: vec![from, to],
+                stroke: Stroke::new(1.0 * zoom, border),
+                dash: Some(Dash {
+                    length: 4.0 * zoom,
+                    gap: 3.0 * zoom,
+                }),
+            },
+        );
+        self.push(
+            element.clone(),
+            Primitive::RoundedRect {
+                rect,
+                rounding: Rounding::same(2.0 * zoom),
+                fill: self.theme.note_fill,
+                stroke: Stroke::new(1.0 * zoom, border),
+            },
+        );
+        self.push(
+            element,
+            Primitive::Text {
+                pos: rect.min + egui::vec2(6.0, 4.0) * zoom,
+                anchor: Align2::LEFT_TOP,
+                text: note.text.clone(),
+                font_size: note_font_size(self.font_size) * zoom,
+                color: self.theme.note_text,
+            },
+        );
+        self.dimmed = false;
+    }
+
+    /// UML state box: name header over the body `set_state_box` selects (description and
+    /// entry/exit actions, then internal transitions below a separator)
+    pub fn state(&mut self, state: &fsm::State, pos: Pos2, is_initial: bool, is_active: bool) {
+        let zoom = self.viewport.zoom;
+        let rect = self.state_rect(state, pos);
+        let body = match self.cache.and_then(|cache| cache.state_body(&state.name)) {
+            Some(body) => Cow::Borrowed(body),
+            None => Cow::Owned(StateBody::new(state, &self.state_box)),
+        };
+        let header_height = header_height(self.font_size) * zoom;
+        let actions_height = rect.height() - header_height;
+
+        let focused = self.state_focus(&state.name);
+        self.dimmed = focused == Some(false);
+
+        let colors = self.theme.state_colors(state);
+        let stroke_color = if is_active || focused == Some(true) {
+            self.theme.accent
+        } else if is_initial {
+            self.theme.initial_stroke
+        } else {
+            self.theme.state_stroke
+        };
+        let stroke_width = if is_active {
+            3.5
+        } else if is_initial {
+            3.0
+        } else if focused == Some(true) {
+            2.5
+        } else {
+            1.5
+        };
+        if state.is_final() {
+            self.final_state(state, rect, Stroke::new(stroke_width * zoom, stroke_color), colors.name);
+            self.dimmed = false;
+            return;
+        }
+        let corner_radius = 8.0 * zoom;
+
+        // Main box (body)
+        self.push(
+            Element::StateBox(state.name.clone()),
+            Primitive::RoundedRect {
+                rect,
+                rounding: Rounding::same(corner_radius),
+                fill: colors.fill,
+                stroke: Stroke::new(stroke_width * zoom, stroke_color),
+            },
+        );
+
+        // Header compartment with rounded top corners only
+        let content = Element::StateContent(state.name.clone());
+        self.push(
+            content.clone(),
+            Primitive::RoundedRect {
+                rect: Rect::from_min_size(rect.min, egui::vec2(rect.width(), header_height)),
+                rounding: Rounding {
+                    nw: corner_radius,
+                    ne: corner_radius,
+                    sw: 0.0,
+                    se: 0.0,
+                },
+                fill: colors.header,
+                stroke: Stroke::NONE,
+            },
+        );
+
+        // Separator line between header and body
+        self.push(
+            content.clone(),
+            Primitive::Polyline {
+                points: vec![
+                    egui::pos2(rect.left(), rect.top() + header_height),
+                    egui::pos2(rect.right(), rect.top() + header_height),
+                ],
+                stroke: Stroke::new(1.0 * zoom, stroke_color),
+                dash: None,
+            },
+        );
+
+        // State name in header (centered)
+        self.push(
+            content.clone(),
+            Primitive::Text {
+                pos: egui::pos2(rect.center().x, rect.top() + header_height / 2.0),
+                anchor: Align2::CENTER_CENTER,
+                text: state.name.clone(),
+                font_size: self.font_size * zoom,
+                color: colors.name,
+            },
+        );
+
+        // Body: one block, or the internal transitions below a thin separator
+        let (font_size, color) = (action_font_size(self.font_size) * zoom, self.theme.state_actions);
+        let block = |lines: &[String], center_y: f32| Primitive::Text {
+            pos: egui::pos2(rect.center().x, center_y),
+            anchor: Align2::CENTER_CENTER,
+            text: lines.join("\n"),
+            font_size,
+            color,
+        };
+        let body_top = rect.top() + header_height;
+        if body.lines.is_empty() || body.internal.is_empty() {
+            let lines = if body.internal.is_empty() { &body.lines } else { &body.internal };
+            if !lines.is_empty() {
+                self.push(content, block(lines, body_top + actions_height / 2.0));
+            }
+        } else {
+            let line_height = body_line_height(self.font_size) * zoom;
+            let top = body_top + BODY_PADDING * zoom / 2.0;
+            let separator_y = top + body.lines.len() as f32 * line_height + BODY_SEPARATOR_GAP * zoom / 2.0;
+            let first = block(&body.lines, top + body.lines.len() as f32 * line_height / 2.0);
+            let second = block(
+                &body.internal,
+                separator_y + BODY_SEPARATOR_GAP * zoom / 2.0 + body.internal.len() as f32 * line_height / 2.0,
+            );
+            self.push(content.clone(), first);
+            self.push(
+                content.clone(),
+                Primitive::Polyline {
+                    points: vec![egui::pos2(rect.left(), separator_y), egui::pos2(rect.right(), separator_y)],
+                    stroke: Stroke::new(0.5 * zoom, stroke_color),
+                    dash: None,
+                },
+            );
+            self.push(content, second);
+        }
+        self.dimmed = false;
+    }
+
+    /// UML final state: a ring around a filled disc filling `rect`, the name below
+    fn final_state(&mut self, state: &fsm::State, rect: Rect, stroke: Stroke, name_color: Color32) {
+        let element = Element::StateBox(state.name.clone());
+        let radius = rect.width() / 2.0;
+        self.push(
+            element.clone(),
+            Primitive::Circle {
+                center: rect.center(),
+                radius,
+                fill: self.theme.label_fill,
+                stroke,
+            },
+        );
+        self.push(
+            element,
+            Primitive::Circle {
+                center: rect.center(),
+                radius: radius * 0.6,
+                fill: stroke.color,
+                stroke: Stroke::NONE,
+            },
+        );
+        self.push(
+            Element::StateContent(state.name.clone()),
+            Primitive::Text {
+                pos: egui::pos2(rect.center().x, rect.bottom() + 4.0 * self.viewport.zoom),
+                anchor: Align2::CENTER_TOP,
+                text: state.name.clone(),
+                font_size: action_font_size(self.font_size) * self.viewport.zoom,
+                color: name_color,
+            },
+        );
+    }
+
+    /// Dot moving along the last fired transition's route
+    pub fn sim_token(&mut self, layout: &LayoutedDiagram, token: &SimToken) {
+        let Some(route) = transition_route(layout, token.transition, token.from, token.to) else {
+            return;
+        };
+        let route: Vec<Pos2> = route.into_iter().map(|p| self.viewport.to_screen(p)).collect();
+        if let Some(center) = polyline_point_at(&route, token.progress) {
+            let zoom = self.viewport.zoom;
+            self.push(
+                Element::SimToken,
+                Primitive::Circle {
+                    center,
+                    radius: 6.0 * zoom,
+                    fill: self.theme.accent,
+                    stroke: Stroke::new(2.0 * zoom, self.theme.token_outline),
+                },
+            );
+        }
+    }
+}
+
+/// Tags of `fsm`'s states in order of first use, each with the `#color` all of its
+/// states share (`None` when they have none or different ones), for the diagram legend
+pub fn tag_legend(fsm: &FsmDefinition) -> Vec<(String, Option<Color32>)> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in fsm.states.iter().flat_map(|s| &s.tags) {
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+    tags.into_iter()
+        .map(|tag| {
+            let mut colors = fsm.states.iter().filter(|s| s.tags.contains(&tag)).map(|s| s.rgb());
+            let first = colors.next().flatten();
+            let shared = first.filter(|&rgb| colors.all(|c| c == Some(rgb)));
+            (tag, shared.map(|[r, g, b]| Color32::from_rgb(r, g, b)))
+        })
+        .collect()
+}
+
+// ============================================================================
+// GEOMETRY
+// ============================================================================
+
+/// Text of a state box below the name, as `StateBoxContent` selects it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct StateBody {
+    /// Description lines, then `entry/ ...` and `exit/ ...` lines
+    pub lines: Vec<String>,
+    /// `event [guard] / action` lines of the internal transitions
+    pub internal: Vec<String>,
+}
+
+impl StateBody {
+    pub fn new(state: &fsm::State, content: &StateBoxContent) -> Self {
+        let action = |a: &fsm::Action| match content.action_params && !a.params.is_empty() {
+            true => format!("{}({})", a.name, a.params.join(", ")),
+            false => a.name.clone(),
+        };
+        let mut lines: Vec<String> = Vec::new();
+        if content.descriptions {
+            lines.extend(state.description.iter().flat_map(|d| d.lines()).map(str::to_string));
+        }
+        if content.entry_exit_actions {
+            lines.extend(state.entry_actions.iter().map(|a| format!("entry/ {}", action(a))));
+            lines.extend(state.exit_actions.iter().map(|a| format!("exit/ {}", action(a))));
+        }
+        let internal = match content.internal_transitions {
+            true => state.internal_transitions.iter().map(|t| internal_transition_line(t, &action)).collect(),
+            false => Vec::new(),
+        };
+        Self { lines, internal }
+    }
+
+    fn line_count(&self) -> usize {
+        self.lines.len() + self.internal.len()
+    }
+
+    fn longest_line(&self) -> usize {
+        self.lines.iter().chain(&self.internal).map(|l| l.chars().count()).max().unwrap_or(0)
+    }
+
+    /// Whether the internal transitions get a separator of their own
+    fn has_separator(&self) -> bool {
+        !self.lines.is_empty() && !self.internal.is_empty()
+    }
+}
+
+/// `event [guard] / a; b`
+fn internal_transition_line(transition: &fsm::Transition, action: &dyn Fn(&fsm::Action) -> String) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    parts.extend(transition.event.as_ref().map(|e| e.name.clone()));
+    parts.extend(transition.guard.as_ref().map(|g| format!("[{}]", g.expression)));
+    if !transition.actions.is_empty() {
+        let actions: Vec<String> = transition.actions.iter().map(action).collect();
+        parts.push(format!("/ {}", actions.join("; ")));
+    }
+    parts.join(" ")
+}
+
+/// Space above and below the body lines at zoom 1
+const BODY_PADDING: f32 = 15.0;
+
+/// Space around the separator above the internal transitions at zoom 1
+const BODY_SEPARATOR_GAP: f32 = 8.0;
+
+/// Outer ring of a final state's glyph at zoom 1 and the default font size
+const FINAL_STATE_DIAMETER: f32 = 28.0;
+
+/// Body lines (actions, internal transitions) at zoom 1
+fn body_line_height(font_size: f32) -> f32 {
+    action_font_size(font_size) * 1.3
+}
+
+/// Entry/exit action lines are drawn at 10/13 of the diagram font size
+fn action_font_size(font_size: f32) -> f32 {
+    font_size * 10.0 / DEFAULT_DIAGRAM_FONT_SIZE
+}
+
+/// Notes are drawn at 11/13 of the diagram font size
+fn note_font_size(font_size: f32) -> f32 {
+    font_size * 11.0 / DEFAULT_DIAGRAM_FONT_SIZE
+}
+
+/// Height of a state's name compartment at zoom 1
+fn header_height(font_size: f32) -> f32 {
+    font_size * 22.0 / DEFAULT_DIAGRAM_FONT_SIZE
+}
+
+/// Calculate the bounding rectangle for a state (used for routing and collision)
+pub fn calculate_state_rect(state: &fsm::State, pos: Pos2, zoom: f32, font_size: f32, content: &StateBoxContent) -> Rect {
+    Rect::from_center_size(pos, state_box_size(state, font_size, content) * zoom)
+}
+
+/// Size of a state's box at zoom 1 (every part of it scales with the zoom)
+pub fn state_box_size(state: &fsm::State, font_size: f32, content: &StateBoxContent) -> Vec2 {
+    let scale = font_size / DEFAULT_DIAGRAM_FONT_SIZE;
+    if state.is_final() {
+        return Vec2::splat(FINAL_STATE_DIAMETER * scale);
+    }
+    let body = StateBody::new(state, content);
+
+    let char_width = action_font_size(font_size) * 0.55;
+    let line_height = body_line_height(font_size);
+
+    // Width based on name or body, whichever is larger
+    let name_width = state.name.len() as f32 * 9.0 * scale;
+    let action_width = body.longest_line() as f32 * char_width;
+
+    let width = name_width.max(action_width).max(80.0) + BODY_PADDING * 2.0;
+
+    // Height: header (name) + separator + body
+    let mut actions_height = if body.line_count() == 0 {
+        20.0 * scale
+    } else {
+        (body.line_count() as f32 * line_height) + BODY_PADDING
+    };
+    if body.has_separator() {
+        actions_height += BODY_SEPARATOR_GAP;
+    }
+    egui::vec2(width, header_height(font_size) + actions_height)
+}
+
+/// Size of a state box for the layout engine, with diagram font size `font_size`
+pub fn estimate_state_size(state: &fsm::State, font_size: f32, content: &StateBoxContent) -> Vec2 {
+    let scale = font_size / DEFAULT_DIAGRAM_FONT_SIZE;
+    if state.is_final() {
+        // Room for the name below the glyph
+        let width = (state.name.len() as f32 * 7.0 * scale).max(FINAL_STATE_DIAMETER * scale);
+        return egui::vec2(width, (FINAL_STATE_DIAMETER + 20.0) * scale);
+    }
+    let body = StateBody::new(state, content);
+    let max_chars = state.name.len().max(body.longest_line());
+
+    // Estimate width: chars * approximate char width + padding
+    let width = (max_chars as f32 * 8.0 * scale).max(100.0) + 30.0;
+
+    // Estimate height: header + separator + body lines + padding
+    let mut height = 30.0 * scale + (body.line_count().max(1) as f32 * 16.0 * scale) + 20.0;
+    if body.has_separator() {
+        height += BODY_SEPARATOR_GAP * scale;
+    }
+    egui::vec2(width, height)
+}
+
+/// Screen rect of a transition label's box
+pub fn label_rect(measure: &dyn TextMeasure, label: &LayoutedLabel, label_font_size: f32, viewport: Viewport) -> Rect {
+    let zoom = viewport.zoom;
+    let text_size = measure.text_size(&label.text, label_font_size * zoom);
+    Rect::from_center_size(viewport.to_screen(label.pos), text_size + egui::vec2(14.0 * zoom, 8.0 * zoom))
+}
+
+/// Transition whose label box contains the screen point `pointer` (the topmost one if labels overlap)
+pub fn label_at(
+    layout: &LayoutedDiagram,
+    measure: &dyn TextMeasure,
+    label_font_size: f32,
+    viewport: Viewport,
+    pointer: Pos2,
+) -> Option<TransitionId> {
+    layout
+        .labels
+        .iter()
+        .rev()
+        .find(|label| label_rect(measure, label, label_font_size, viewport).contains(pointer))
+        .and_then(|label| label.transition)
+}
+
+/// Gap between a note and its state
+const NOTE_GAP: f32 = 16.0;
+
+/// Where a note goes next to its (already transformed) state rect
+pub fn note_rect(measure: &dyn TextMeasure, state_rect: Rect, note: &fsm::Note, zoom: f32, font_size: f32) -> Rect {
+    let size = measure.text_size(&note.text, note_font_size(font_size) * zoom) + egui::vec2(12.0, 8.0) * zoom;
+    let top = state_rect.top();
+    match note.position {
+        fsm::NotePosition::Left => Rect::from_min_size(egui::pos2(state_rect.left() - NOTE_GAP * zoom - size.x, top), size),
+        fsm::NotePosition::Right => Rect::from_min_size(egui::pos2(state_rect.right() + NOTE_GAP * zoom, top), size),
+    }
+}
+
+/// Arrowhead triangle at the end of a route (route must have at least 2 points)
+fn arrowhead(route: &[Pos2], zoom: f32) -> [Pos2; 3] {
+    let last = route[route.len() - 1];
+    let prev = route[route.len() - 2];
+    let dir = (last - prev).normalized();
+
+    let arrow_size = 10.0 * zoom;
+    let arrow_angle = 0.4;
+
+    let perp = egui::vec2(-dir.y, dir.x);
+    [
+        last,
+        last - dir * arrow_size + perp * arrow_size * arrow_angle,
+        last - dir * arrow_size - perp * arrow_size * arrow_angle,
+    ]
+}
+
+/// Point at fraction `t` of a polyline's length
+pub fn polyline_point_at(points: &[Pos2], t: f32) -> Option<Pos2> {
+    if points.len() < 2 {
+        return None;
+    }
+    let lengths: Vec<f32> = points.windows(2).map(|s| s[0].distance(s[1])).collect();
+    let total: f32 = lengths.iter().sum();
+    if total <= 0.0001 {
+        return Some(points[0]);
+    }
+    let mut target = t.clamp(0.0, 1.0) * total;
+    for (i, &seg) in lengths.iter().enumerate() {
+        if target <= seg {
+            let alpha = if seg <= 0.0001 { 0.0 } else { target / seg };
+            return Some(points[i].lerp(points[i + 1], alpha));
+        }
+        target -= seg;
+    }
+    points.last().copied()
+}
+
+/// Shortest distance from `p` to a polyline (infinite for an empty one)
+pub fn distance_to_polyline(points: impl IntoIterator<Item = Pos2>, p: Pos2) -> f32 {
+    let points: Vec<Pos2> = points.into_iter().collect();
+    if let [only] = points.as_slice() {
+        return only.distance(p);
+    }
+    points
+        .windows(2)
+        .map(|segment| {
+            let (a, b) = (segment[0], segment[1]);
+            let ab = b - a;
+            let t = if ab.length_sq() > 0.0 { ((p - a).dot(ab) / ab.length_sq()).clamp(0.0, 1.0) } else { 0.0 };
+            (a + ab * t).distance(p)
+        })
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// Full route of a transition from `from` to `to`, through its label node
+pub fn transition_route(layout: &LayoutedDiagram, transition: TransitionId, from: &str, to: &str) -> Option<Vec<Pos2>> {
+    let tr_node = format!("{LABEL_NODE_PREFIX}{transition}");
+    let a = layout
+        .edges
+        .iter()
+        .find(|e| e.transition == Some(transition) && e.v == from && e.w == tr_node);
+    let b = layout
+        .edges
+        .iter()
+        .find(|e| e.transition == Some(transition) && e.v == tr_node && e.w == to);
+
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            let mut pts = a.points.clone();
+            if let Some(first_b) = b.points.first().copied() {
+                if pts.last().copied().is_some_and(|last| last.distance(first_b) < 0.01) {
+                    pts.pop();
+                }
+            }
+            pts.extend_from_slice(&b.points);
+            Some(pts)
+        }
+        _ => {
+            // Fallback: pick the longest segment we can find for that transition.
+            layout
+                .edges
+                .iter()
+                .filter(|e| e.transition == Some(transition))
+                .max_by(|a, b| a.points.len().cmp(&b.points.len()))
+                .map(|e| e.points.clone())
+        }
+    }
+}
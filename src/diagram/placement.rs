@@ -0,0 +1,183 @@
+//! Label Placement
+//! Post-pass over a finished layout that moves transition labels off the routes of
+//! other transitions. The layout engine keeps labels clear of nodes, but a long edge
+//! can still run straight through an unrelated label, especially left-to-right. Each
+//! label is handled on its own, so the result does not depend on label order.
+
+use eframe::egui::{Pos2, Rect, Vec2};
+
+use super::{label_rect, LayoutedDiagram, TextMeasure, Viewport};
+use crate::fsm::TransitionId;
+
+/// Space a label keeps from the routes of other transitions (layout px)
+pub const LABEL_CLEARANCE: f32 = 6.0;
+
+/// Distance a label moves per try
+const NUDGE_STEP: f32 = 4.0;
+
+/// Furthest a label is moved from where the layout put it
+const MAX_NUDGE: f32 = 60.0;
+
+/// Routes closer than this to a label count towards the crowding of its side
+const CROWDING_RADIUS: f32 = 120.0;
+
+struct Segment {
+    a: Pos2,
+    b: Pos2,
+    transition: Option<TransitionId>,
+    bounds: Rect,
+}
+
+/// Nudge every label that comes closer than [`LABEL_CLEARANCE`] to another transition's
+/// route perpendicular to the nearest such segment, in small steps and at most
+/// `MAX_NUDGE` away, trying the less crowded side first. A label is not moved into
+/// any of `nodes` (state boxes); one that cannot be cleared goes where it is clearest.
+pub fn avoid_edge_segments(layout: &mut LayoutedDiagram, measure: &dyn TextMeasure, label_font_size: f32, nodes: &[Rect]) {
+    let segments: Vec<Segment> = layout
+        .edges
+        .iter()
+        .flat_map(|edge| {
+            edge.points.windows(2).map(|s| Segment {
+                a: s[0],
+                b: s[1],
+                transition: edge.transition,
+                bounds: Rect::from_two_pos(s[0], s[1]),
+            })
+        })
+        .collect();
+    let viewport = Viewport { offset: Vec2::ZERO, zoom: 1.0 };
+
+    for label in &mut layout.labels {
+        let rect = label_rect(measure, label, label_font_size, viewport);
+        // Only segments the label could reach matter; its own route runs through it
+        let reach = rect.expand(MAX_NUDGE + LABEL_CLEARANCE);
+        let nearby: Vec<&Segment> = segments
+            .iter()
+            .filter(|s| s.transition.is_none() || s.transition != label.transition)
+            .filter(|s| s.bounds.intersects(reach))
+            .collect();
+
+        // A segment's bounds are never nearer than the segment, so most are skipped cheaply
+        let clearance = |rect: Rect| {
+            nearby.iter().fold(f32::INFINITY, |nearest, s| {
+                if rect_distance(rect, s.bounds) >= nearest {
+                    nearest
+                } else {
+                    nearest.min(rect_segment_distance(rect, s.a, s.b))
+                }
+            })
+        };
+        let current = clearance(rect);
+        if current >= LABEL_CLEARANCE {
+            continue;
+        }
+
+        let center = rect.center();
+        let Some(nearest) = nearby
+            .iter()
+            .min_by(|x, y| point_segment_distance(center, x.a, x.b).total_cmp(&point_segment_distance(center, y.a, y.b)))
+        else {
+            continue;
+        };
+        let normal = segment_normal(nearest.a, nearest.b);
+        let crowding = |side: f32| -> usize {
+            let towards_side = |p: Pos2| (p - center).dot(normal) * side > 0.0;
+            let routes = nearby.iter().filter(|s| {
+                let closest = closest_point(center, s.a, s.b);
+                closest.distance(center) < CROWDING_RADIUS && towards_side(closest)
+            });
+            let boxes = nodes.iter().filter(|n| n.distance_to_pos(center) < CROWDING_RADIUS && towards_side(n.center()));
+            routes.count() + boxes.count()
+        };
+        let sides = if crowding(-1.0) < crowding(1.0) { [-1.0, 1.0] } else { [1.0, -1.0] };
+
+        let steps = (MAX_NUDGE / NUDGE_STEP) as usize;
+        let mut best = (current, Vec2::ZERO);
+        'search: for step in 1..=steps {
+            for side in sides {
+                let offset = normal * side * step as f32 * NUDGE_STEP;
+                let moved = rect.translate(offset);
+                if nodes.iter().any(|n| n.intersects(moved)) {
+                    continue;
+                }
+                let distance = clearance(moved);
+                if distance >= LABEL_CLEARANCE {
+                    best = (distance, offset);
+                    break 'search;
+                }
+                if distance > best.0 {
+                    best = (distance, offset);
+                }
+            }
+        }
+        label.pos += best.1;
+    }
+}
+
+/// Unit normal of the segment `a`–`b`, pointing down (or right for a vertical segment)
+fn segment_normal(a: Pos2, b: Pos2) -> Vec2 {
+    let direction = b - a;
+    if direction.length_sq() <= f32::EPSILON {
+        return Vec2::Y;
+    }
+    let normal = direction.rot90().normalized();
+    if normal.y < 0.0 || (normal.y == 0.0 && normal.x < 0.0) {
+        -normal
+    } else {
+        normal
+    }
+}
+
+fn closest_point(p: Pos2, a: Pos2, b: Pos2) -> Pos2 {
+    let ab = b - a;
+    let t = if ab.length_sq() > 0.0 { ((p - a).dot(ab) / ab.length_sq()).clamp(0.0, 1.0) } else { 0.0 };
+    a + ab * t
+}
+
+fn point_segment_distance(p: Pos2, a: Pos2, b: Pos2) -> f32 {
+    closest_point(p, a, b).distance(p)
+}
+
+/// Gap between two rects (zero if they overlap)
+fn rect_distance(a: Rect, b: Rect) -> f32 {
+    let dx = (a.left() - b.right()).max(b.left() - a.right()).max(0.0);
+    let dy = (a.top() - b.bottom()).max(b.top() - a.bottom()).max(0.0);
+    dx.hypot(dy)
+}
+
+/// Shortest distance between a rect and the segment `a`–`b` (zero if they touch)
+fn rect_segment_distance(rect: Rect, a: Pos2, b: Pos2) -> f32 {
+    if segment_hits_rect(rect, a, b) {
+        return 0.0;
+    }
+    // Apart, the closest pair is an endpoint and the rect or a corner and the segment
+    let corners = [rect.left_top(), rect.right_top(), rect.left_bottom(), rect.right_bottom()];
+    corners
+        .into_iter()
+        .map(|corner| point_segment_distance(corner, a, b))
+        .chain([rect.distance_to_pos(a), rect.distance_to_pos(b)])
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// Whether the segment `a`–`b` passes through `rect` (Liang–Barsky clipping)
+fn segment_hits_rect(rect: Rect, a: Pos2, b: Pos2) -> bool {
+    let d = b - a;
+    let (mut enter, mut leave) = (0.0_f32, 1.0_f32);
+    for (p, q) in [
+        (-d.x, a.x - rect.left()),
+        (d.x, rect.right() - a.x),
+        (-d.y, a.y - rect.top()),
+        (d.y, rect.bottom() - a.y),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return false;
+            }
+        } else if p < 0.0 {
+            enter = enter.max(q / p);
+        } else {
+            leave = leave.min(q / p);
+        }
+    }
+    enter <= leave
+}
@@ -0,0 +1,106 @@
+//! Layout Quality
+//! Objective measures of a computed layout — edge crossings, labels overlapping
+//! states or each other, total edge length — so spacing can be compared by number
+//! instead of by eye (toolbar readout and the Optimize button)
+
+use std::collections::HashMap;
+
+use eframe::egui::{Pos2, Rect, Vec2};
+
+use super::{calculate_state_rect, label_rect, LayoutedDiagram, LayoutedEdge, StateBoxContent, TextMeasure, Viewport};
+
+/// Edge length one crossing is worth in [`LayoutQuality::score`]
+const CROSSING_WEIGHT: f32 = 1000.0;
+/// Edge length one overlap is worth in [`LayoutQuality::score`]
+const OVERLAP_WEIGHT: f32 = 500.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayoutQuality {
+    /// Points where routes of different transitions cross
+    pub crossings: usize,
+    /// Label boxes overlapping a state box or another label box
+    pub overlaps: usize,
+    /// Length of every edge route (layout px)
+    pub edge_length: f32,
+}
+
+impl LayoutQuality {
+    pub fn measure(edges: &[LayoutedEdge], state_rects: &[Rect], label_rects: &[Rect]) -> Self {
+        Self {
+            crossings: edge_crossings(edges),
+            overlaps: label_overlaps(state_rects, label_rects),
+            edge_length: total_edge_length(edges),
+        }
+    }
+
+    /// Lower is better: crossings first, then overlaps, then shorter edges
+    pub fn score(&self) -> f32 {
+        self.crossings as f32 * CROSSING_WEIGHT + self.overlaps as f32 * OVERLAP_WEIGHT + self.edge_length
+    }
+}
+
+/// Quality of `layout` with the state boxes at `positions` and the label boxes as drawn
+pub fn layout_quality(
+    layout: &LayoutedDiagram,
+    positions: &HashMap<String, Pos2>,
+    font_size: f32,
+    label_font_size: f32,
+    state_box: &StateBoxContent,
+    measure: &dyn TextMeasure,
+) -> LayoutQuality {
+    let state_rects: Vec<Rect> = layout
+        .states
+        .iter()
+        .filter_map(|state| positions.get(&state.name).map(|&pos| calculate_state_rect(state, pos, 1.0, font_size, state_box)))
+        .collect();
+    let identity = Viewport { offset: Vec2::ZERO, zoom: 1.0 };
+    let label_rects: Vec<Rect> = layout.labels.iter().map(|label| label_rect(measure, label, label_font_size, identity)).collect();
+    LayoutQuality::measure(&layout.edges, &state_rects, &label_rects)
+}
+
+/// Crossings between the routes of different edges. The two halves of a transition
+/// (into and out of its label node) are one route and do not count against each
+/// other; routes that only touch, e.g. at a shared node, do not cross.
+pub fn edge_crossings(edges: &[LayoutedEdge]) -> usize {
+    let mut crossings = 0;
+    for (i, a) in edges.iter().enumerate() {
+        for b in &edges[i + 1..] {
+            if a.transition.is_some() && a.transition == b.transition {
+                continue;
+            }
+            for sa in a.points.windows(2) {
+                crossings += b.points.windows(2).filter(|sb| segments_cross(sa[0], sa[1], sb[0], sb[1])).count();
+            }
+        }
+    }
+    crossings
+}
+
+/// Label boxes overlapping a state box, plus pairs of overlapping label boxes
+pub fn label_overlaps(state_rects: &[Rect], label_rects: &[Rect]) -> usize {
+    let mut overlaps = 0;
+    for (i, label) in label_rects.iter().enumerate() {
+        overlaps += state_rects.iter().filter(|state| overlap(label, state)).count();
+        overlaps += label_rects[i + 1..].iter().filter(|other| overlap(label, other)).count();
+    }
+    overlaps
+}
+
+pub fn total_edge_length(edges: &[LayoutedEdge]) -> f32 {
+    edges.iter().flat_map(|edge| edge.points.windows(2)).map(|s| s[0].distance(s[1])).sum()
+}
+
+/// Boxes sharing some area (touching edges do not count)
+fn overlap(a: &Rect, b: &Rect) -> bool {
+    let shared = a.intersect(*b);
+    shared.width() > 0.0 && shared.height() > 0.0
+}
+
+/// Whether segments `p1 p2` and `q1 q2` cross at a point inside both
+fn segments_cross(p1: Pos2, p2: Pos2, q1: Pos2, q2: Pos2) -> bool {
+    // Which side of the line through `a b` the point `c` is on
+    let side = |a: Pos2, b: Pos2, c: Pos2| (b - a).x * (c - a).y - (b - a).y * (c - a).x;
+    let (d1, d2) = (side(q1, q2, p1), side(q1, q2, p2));
+    let (d3, d4) = (side(p1, p2, q1), side(p1, p2, q2));
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
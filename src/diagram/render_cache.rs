@@ -0,0 +1,158 @@
+//! Render Cache
+//! State box sizes and laid-out text kept between canvas frames, so a frame in which
+//! nothing changed neither rebuilds every state's body text nor lays out every label
+//! again. Everything is dropped when the FSM's content, the zoom bucket, the fonts
+//! or the theme change.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::sync::Arc;
+
+use eframe::egui::{self, Galley, Pos2, Rect, Vec2};
+
+use super::{calculate_state_rect, state_box_size, DiagramTheme, StateBody, StateBoxContent, TextMeasure};
+use crate::fsm::{self, FsmDefinition};
+
+/// Zoom steps per octave that share a bucket
+const ZOOM_BUCKETS_PER_OCTAVE: f32 = 8.0;
+
+/// What the cached values were computed for
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderKey {
+    /// [`layout_fingerprint`](super::layout_fingerprint) of the FSM shown
+    fingerprint: u64,
+    /// Zoom rounded to an eighth of an octave: text laid out at the zooms of other
+    /// buckets is dropped, so zooming does not grow the cache without bound
+    zoom_bucket: i32,
+    font_size: f32,
+    pixels_per_point: f32,
+    theme: DiagramTheme,
+}
+
+impl RenderKey {
+    pub fn new(fingerprint: u64, zoom: f32, font_size: f32, pixels_per_point: f32, theme: &DiagramTheme) -> Self {
+        Self {
+            fingerprint,
+            zoom_bucket: (zoom.log2() * ZOOM_BUCKETS_PER_OCTAVE).round() as i32,
+            font_size,
+            pixels_per_point,
+            theme: theme.clone(),
+        }
+    }
+}
+
+/// State boxes and text galleys of the FSM on the canvas (see the module docs)
+#[derive(Default)]
+pub struct RenderCache {
+    key: Option<RenderKey>,
+    /// Box size at zoom 1 and body text of each state, by name
+    states: HashMap<String, (Vec2, StateBody)>,
+    /// Text laid out with a placeholder color (painting picks the color), by hash of
+    /// the text and font size
+    galleys: RefCell<HashMap<u64, Arc<Galley>, BuildHasherDefault<TextHashHasher>>>,
+    /// Font atlas fill ratio at the last `prepare`: egui rebuilds a full atlas, which
+    /// invalidates every galley laid out before
+    atlas_fill: f32,
+}
+
+impl RenderCache {
+    /// Make the cache hold `fsm` for `key`: unchanged if the key is the same, else
+    /// emptied and the state boxes measured again. Returns whether it was rebuilt.
+    pub fn prepare(&mut self, ctx: &egui::Context, key: RenderKey, fsm: &FsmDefinition, state_box: &StateBoxContent) -> bool {
+        let atlas_fill = ctx.fonts(|fonts| fonts.font_atlas_fill_ratio());
+        if atlas_fill < self.atlas_fill {
+            self.galleys.get_mut().clear();
+        }
+        self.atlas_fill = atlas_fill;
+        if self.key.as_ref() == Some(&key) {
+            return false;
+        }
+        self.galleys.get_mut().clear();
+        self.states = fsm
+            .states
+            .iter()
+            .map(|state| {
+                let size = state_box_size(state, key.font_size, state_box);
+                (state.name.clone(), (size, StateBody::new(state, state_box)))
+            })
+            .collect();
+        self.key = Some(key);
+        true
+    }
+
+    /// Body text of the state called `name`, if it was cached
+    pub fn state_body(&self, name: &str) -> Option<&StateBody> {
+        self.states.get(name).map(|(_, body)| body)
+    }
+
+    /// Screen rect of `state` centred at `center`, like [`calculate_state_rect`]
+    pub fn state_rect(&self, state: &fsm::State, center: Pos2, zoom: f32, font_size: f32, state_box: &StateBoxContent) -> Rect {
+        match self.states.get(&state.name) {
+            Some((size, _)) => Rect::from_center_size(center, *size * zoom),
+            None => calculate_state_rect(state, center, zoom, font_size, state_box),
+        }
+    }
+
+    /// `text` laid out on one line (breaking at `\n` only) at `font_size`
+    pub fn galley(&self, ctx: &egui::Context, text: &str, font_size: f32) -> Arc<Galley> {
+        let hash = text_hash(text, font_size);
+        if let Some(galley) = self.galleys.borrow().get(&hash) {
+            return galley.clone();
+        }
+        let font_id = egui::FontId::proportional(font_size);
+        let galley = ctx.fonts(|fonts| fonts.layout_no_wrap(text.to_owned(), font_id, egui::Color32::PLACEHOLDER));
+        self.galleys.borrow_mut().insert(hash, galley.clone());
+        galley
+    }
+
+    /// Text measuring through the cache, for scenes and the layout's label sizes
+    pub fn measure<'a>(&'a self, ctx: &'a egui::Context) -> CachedMeasure<'a> {
+        CachedMeasure { cache: self, ctx }
+    }
+}
+
+/// [`TextMeasure`] answered from a [`RenderCache`]
+pub struct CachedMeasure<'a> {
+    cache: &'a RenderCache,
+    ctx: &'a egui::Context,
+}
+
+impl TextMeasure for CachedMeasure<'_> {
+    fn text_size(&self, text: &str, font_size: f32) -> Vec2 {
+        self.cache.galley(self.ctx, text, font_size).size()
+    }
+}
+
+/// Key of `text` laid out at `font_size`: every text is looked up on every frame, so
+/// this is a multiply-rotate hash over 8-byte words rather than SipHash
+fn text_hash(text: &str, font_size: f32) -> u64 {
+    const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+    let mix = |hash: u64, word: u64| (hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    let mut hash = mix(0, u64::from(font_size.to_bits()));
+    let mut chunks = text.as_bytes().chunks_exact(8);
+    for chunk in &mut chunks {
+        hash = mix(hash, u64::from_le_bytes(chunk.try_into().unwrap()));
+    }
+    let mut tail = [0u8; 8];
+    tail[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+    mix(mix(hash, u64::from_le_bytes(tail)), text.len() as u64)
+}
+
+/// Hasher of the galley map, whose keys are [`text_hash`]es already
+#[derive(Default)]
+struct TextHashHasher(u64);
+
+impl Hasher for TextHashHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, _: &[u8]) {
+        unreachable!("galleys are keyed by u64")
+    }
+
+    fn write_u64(&mut self, hash: u64) {
+        self.0 = hash;
+    }
+}
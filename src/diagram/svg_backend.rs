@@ -0,0 +1,105 @@
+//! SVG Backend
+//! Writes a scene as a standalone SVG document (exports and HTML reports)
+
+use std::fmt::Write;
+
+use eframe::egui::{Align, Color32, Pos2, Stroke, Vec2};
+
+use super::{Primitive, Scene};
+use crate::report::escape;
+
+/// `scene` on a `size` canvas (screen coordinates from the origin) filled with `background`
+pub fn render(scene: &Scene, size: Vec2, background: Color32) -> String {
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}\" height=\"{h:.0}\" viewBox=\"0 0 {w:.0} {h:.0}\" font-family=\"sans-serif\">\n",
+        w = size.x,
+        h = size.y
+    );
+    let _ = writeln!(svg, "<rect width=\"100%\" height=\"100%\"{}/>", paint("fill", background));
+    for item in &scene.items {
+        write_primitive(&mut svg, &item.primitive);
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn write_primitive(svg: &mut String, primitive: &Primitive) {
+    let _ = match primitive {
+        Primitive::RoundedRect { rect, rounding, fill, stroke } => writeln!(
+            svg,
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" rx=\"{:.1}\"{}{}/>",
+            rect.min.x,
+            rect.min.y,
+            rect.width(),
+            rect.height(),
+            rounding.nw.max(rounding.ne).max(rounding.sw).max(rounding.se),
+            paint("fill", *fill),
+            outline(*stroke)
+        ),
+        Primitive::Circle { center, radius, fill, stroke } => writeln!(
+            svg,
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\"{}{}/>",
+            center.x,
+            center.y,
+            radius,
+            paint("fill", *fill),
+            outline(*stroke)
+        ),
+        Primitive::Polyline { points, stroke, dash } => {
+            let dash = dash.map(|d| format!(" stroke-dasharray=\"{:.1} {:.1}\"", d.length, d.gap)).unwrap_or_default();
+            writeln!(svg, "<polyline points=\"{}\" fill=\"none\"{}{dash}/>", coordinates(points), outline(*stroke))
+        }
+        Primitive::Polygon { points, fill, stroke } => {
+            writeln!(svg, "<polygon points=\"{}\"{}{}/>", coordinates(points), paint("fill", *fill), outline(*stroke))
+        }
+        Primitive::Text { pos, anchor, text, font_size, color } => {
+            let text_anchor = match anchor.x() {
+                Align::Min => "start",
+                Align::Center => "middle",
+                Align::Max => "end",
+            };
+            // Lines of a multi-line text are stacked from the anchor like egui lays them out
+            let lines: Vec<&str> = text.lines().collect();
+            let line_height = font_size * 1.2;
+            let height = line_height * lines.len().max(1) as f32;
+            let top = match anchor.y() {
+                Align::Min => pos.y,
+                Align::Center => pos.y - height / 2.0,
+                Align::Max => pos.y - height,
+            };
+            for (i, line) in lines.iter().enumerate() {
+                let _ = writeln!(
+                    svg,
+                    "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"{:.1}\" text-anchor=\"{text_anchor}\" dominant-baseline=\"central\"{}>{}</text>",
+                    pos.x,
+                    top + line_height * (i as f32 + 0.5),
+                    font_size,
+                    paint("fill", *color),
+                    escape(line)
+                );
+            }
+            Ok(())
+        }
+    };
+}
+
+fn coordinates(points: &[Pos2]) -> String {
+    points.iter().map(|p| format!("{:.1},{:.1}", p.x, p.y)).collect::<Vec<_>>().join(" ")
+}
+
+/// ` fill="#rrggbb"` (with an opacity if translucent), or `none` for transparent
+fn paint(attribute: &str, color: Color32) -> String {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    match a {
+        0 => format!(" {attribute}=\"none\""),
+        255 => format!(" {attribute}=\"#{r:02x}{g:02x}{b:02x}\""),
+        a => format!(" {attribute}=\"#{r:02x}{g:02x}{b:02x}\" {attribute}-opacity=\"{:.2}\"", a as f32 / 255.0),
+    }
+}
+
+fn outline(stroke: Stroke) -> String {
+    if stroke.width <= 0.0 || stroke.color.a() == 0 {
+        return " stroke=\"none\"".to_string();
+    }
+    format!("{} stroke-width=\"{:.1}\"", paint("stroke", stroke.color), stroke.width)
+}
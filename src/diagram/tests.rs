@@ -0,0 +1,1038 @@
+//! Unit tests for diagram scene building
+
+use std::collections::HashMap;
+
+use eframe::egui;
+
+use crate::diagram::dagre::{parse_layout_output, DagreError, DAGRE_PROTOCOL};
+use crate::diagram::fallback::{fallback_layout, grid_layout};
+use crate::diagram::label::{fit_label_width, format_label_text, LABEL_WRAP_CHARS};
+use crate::diagram::placement::{avoid_edge_segments, LABEL_CLEARANCE};
+use crate::diagram::egui_backend::{paint, paint_with_cache};
+use crate::diagram::quality::{edge_crossings, label_overlaps, total_edge_length, LayoutQuality};
+use crate::diagram::render_cache::{RenderCache, RenderKey};
+use crate::diagram::{
+    build_scene, calculate_state_rect, distance_to_polyline, estimate_state_size, label_at, layout_bounds, layout_fingerprint, polyline_point_at, tag_legend, DiagramContent, DiagramTheme, Element,
+    HoverFocus, LayoutedDiagram, LayoutedEdge, LayoutedLabel, Primitive, Scene, SceneBuilder, SimOverlay, SimToken, TextMeasure,
+    StateBoxContent, StateColors, TransitionType, Viewport, CanvasView, DEFAULT_DIAGRAM_FONT_SIZE, MAX_ZOOM, MIN_ZOOM,
+};
+use crate::fsm::{FsmDefinition, TransitionId};
+use crate::parser::parse_fsm;
+
+/// Fixed-width text, so the tests need no fonts
+struct Monospace;
+
+impl TextMeasure for Monospace {
+    fn text_size(&self, text: &str, font_size: f32) -> egui::Vec2 {
+        egui::vec2(text.chars().count() as f32 * font_size * 0.6, font_size)
+    }
+}
+
+/// Hand-placed layout; like the engine's, every transition runs through its label node
+fn door() -> (FsmDefinition, LayoutedDiagram, HashMap<String, egui::Pos2>) {
+    let source = r#"
+        fsm Door {
+            [*] --> Locked
+            state Locked {
+                entry / engage_lock
+            }
+            Locked --> Unlocked : valid_key
+            Unlocked --> Locked : lock [is_closed]
+            Locked --> Alarming : invalid_key
+        }
+    "#;
+    let fsm = parse_fsm(source).expect("Should parse successfully").remove(0);
+
+    let mut positions: HashMap<String, egui::Pos2> = [
+        ("[*]", egui::pos2(0.0, -120.0)),
+        ("Locked", egui::pos2(0.0, 0.0)),
+        ("Unlocked", egui::pos2(250.0, 0.0)),
+        ("Alarming", egui::pos2(0.0, 200.0)),
+    ]
+    .into_iter()
+    .map(|(name, pos)| (name.to_string(), pos))
+    .collect();
+    let label_positions = [egui::pos2(125.0, -30.0), egui::pos2(125.0, 30.0), egui::pos2(0.0, 100.0)];
+    for (id, pos) in fsm.transition_ids().into_iter().zip(label_positions) {
+        positions.insert(format!("__tr_{id}"), pos);
+    }
+
+    let edge = |v: &str, w: &str, transition: Option<TransitionId>| LayoutedEdge {
+        v: v.to_string(),
+        w: w.to_string(),
+        transition,
+        points: vec![positions[v], positions[w]],
+        transition_type: TransitionType::Forward,
+        is_else: false,
+        is_local: false,
+        is_dashed: false,
+    };
+    let mut edges = vec![edge("[*]", "Locked", None)];
+    let mut labels = Vec::new();
+    for (transition, id) in fsm.transitions.iter().zip(fsm.transition_ids()) {
+        let node = format!("__tr_{id}");
+        edges.push(edge(&transition.source, &node, Some(id)));
+        edges.push(edge(&node, &transition.target, Some(id)));
+        labels.push(LayoutedLabel {
+            pos: positions[&node],
+            text: transition.label(),
+            transition: Some(id),
+        });
+    }
+
+    let layout = LayoutedDiagram {
+        edges,
+        labels,
+        bounds: egui::Rect::from_points(&positions.values().copied().collect::<Vec<_>>()).expand(80.0),
+        fsm_name: fsm.name.clone(),
+        states: fsm.states.clone(),
+    };
+    (fsm, layout, positions)
+}
+
+/// Id of the door's transition at `index`
+fn id(index: usize) -> TransitionId {
+    door().0.transition_id(index).unwrap()
+}
+
+fn scene(overlay: SimOverlay) -> (FsmDefinition, Scene) {
+    let (fsm, layout, positions) = door();
+    let content = DiagramContent {
+        fsm: &fsm,
+        layout: &layout,
+        positions: &positions,
+        font_size: DEFAULT_DIAGRAM_FONT_SIZE,
+        label_font_size: 12.0,
+        state_box: StateBoxContent::default(),
+        overlay,
+    };
+    let viewport = Viewport {
+        offset: egui::Vec2::ZERO,
+        zoom: 1.0,
+    };
+    let scene = build_scene(&content, &DiagramTheme::default(), &Monospace, viewport);
+    (fsm, scene)
+}
+
+fn state_box<'s>(scene: &'s Scene, name: &str) -> (&'s egui::Rect, &'s egui::Stroke) {
+    let element = Element::StateBox(name.to_string());
+    match scene.primitives_of(element).collect::<Vec<_>>()[..] {
+        [Primitive::RoundedRect { rect, stroke, .. }] => (rect, stroke),
+        ref other => panic!("expected one box for {name}, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_state_boxes_do_not_overlap() {
+    let (fsm, scene) = scene(SimOverlay::default());
+    let (_, _, positions) = door();
+
+    let rects: Vec<egui::Rect> = fsm.states.iter().map(|s| *state_box(&scene, &s.name).0).collect();
+    for (state, rect) in fsm.states.iter().zip(&rects) {
+        assert_eq!(*rect, calculate_state_rect(state, positions[&state.name], 1.0, DEFAULT_DIAGRAM_FONT_SIZE, &StateBoxContent::default()));
+    }
+    for (i, a) in rects.iter().enumerate() {
+        for b in &rects[i + 1..] {
+            assert!(!a.intersects(*b), "{a:?} overlaps {b:?}");
+        }
+    }
+}
+
+#[test]
+fn test_one_arrowhead_per_transition() {
+    let (fsm, scene) = scene(SimOverlay::default());
+
+    for i in 0..fsm.transitions.len() {
+        assert_eq!(scene.primitives_of(Element::Edge(Some(id(i)))).count(), 2);
+        let arrowheads: Vec<_> = scene.primitives_of(Element::Arrowhead(Some(id(i)))).collect();
+        assert_eq!(arrowheads.len(), 1, "transition {i}");
+        // The tip is at the target, not at the label node
+        let Primitive::Polygon { points, .. } = arrowheads[0] else {
+            panic!("arrowhead is not a polygon");
+        };
+        let (_, _, positions) = door();
+        assert_eq!(points[0], positions[&fsm.transitions[i].target]);
+    }
+    // The initial transition's edge
+    assert_eq!(scene.primitives_of(Element::Arrowhead(None)).count(), 1);
+    assert_eq!(scene.primitives_of(Element::Label).count(), 2 * fsm.transitions.len());
+}
+
+#[test]
+fn test_local_transition_has_open_arrowhead() {
+    let (fsm, mut layout, positions) = door();
+    for edge in layout.edges.iter_mut().filter(|e| e.transition == Some(id(0))) {
+        edge.is_local = true;
+    }
+    let content = DiagramContent {
+        fsm: &fsm,
+        layout: &layout,
+        positions: &positions,
+        font_size: DEFAULT_DIAGRAM_FONT_SIZE,
+        label_font_size: 12.0,
+        state_box: StateBoxContent::default(),
+        overlay: SimOverlay::default(),
+    };
+    let viewport = Viewport {
+        offset: egui::Vec2::ZERO,
+        zoom: 1.0,
+    };
+    let theme = DiagramTheme::default();
+    let scene = build_scene(&content, &theme, &Monospace, viewport);
+
+    let fills: Vec<(egui::Color32, f32)> = (0..2)
+        .map(|i| match scene.primitives_of(Element::Arrowhead(Some(id(i)))).next() {
+            Some(Primitive::Polygon { fill, stroke, .. }) => (*fill, stroke.width),
+            other => panic!("unexpected arrowhead {other:?}"),
+        })
+        .collect();
+    assert_eq!(fills[0], (theme.background, 1.5));
+    assert_eq!(fills[1].1, 0.0);
+}
+
+/// A layout computed before an edit reordered the transitions still highlights the
+/// transition the user hovers in the new definition
+#[test]
+fn test_focus_follows_reordered_transitions() {
+    let (_, layout, _) = door();
+    let reordered = parse_fsm(
+        "fsm Door {\n [*] --> Locked\n Locked --> Alarming : invalid_key\n Locked --> Unlocked : valid_key\n Unlocked --> Locked : lock [is_closed]\n}",
+    )
+    .unwrap()
+    .remove(0);
+
+    let focus = HoverFocus::transition(&reordered, 0);
+    let focused: Vec<&str> = layout
+        .edges
+        .iter()
+        .filter(|e| e.transition.is_some_and(|id| focus.transitions.contains(&id)))
+        .map(|e| e.w.as_str())
+        .collect();
+    assert_eq!(focused.last(), Some(&"Alarming"));
+    assert_eq!(focused.len(), 2);
+}
+
+#[test]
+fn test_hover_focus_highlights_neighbors() {
+    let (fsm, layout, positions) = door();
+    let focus = HoverFocus::state(&fsm, "Unlocked");
+    assert_eq!(focus.transitions, [id(0), id(1)].into());
+    assert_eq!(focus.states, ["Locked".to_string(), "Unlocked".to_string()].into());
+    assert_eq!(HoverFocus::transition(&fsm, 2).states, ["Locked".to_string(), "Alarming".to_string()].into());
+
+    let theme = DiagramTheme::default();
+    let viewport = Viewport {
+        offset: egui::Vec2::ZERO,
+        zoom: 1.0,
+    };
+    let mut scene = SceneBuilder::new(&theme, &Monospace, viewport);
+    scene.set_focus(Some(focus));
+    scene.edges(&layout, 12.0, &[]);
+    for state in &fsm.states {
+        scene.state(state, positions[&state.name], false, false);
+    }
+    let scene = scene.finish();
+
+    let edge_color = |i: usize| match scene.primitives_of(Element::Edge(Some(id(i)))).next() {
+        Some(Primitive::Polyline { stroke, .. }) => stroke.color,
+        other => panic!("unexpected edge {other:?}"),
+    };
+    assert_eq!(edge_color(0), theme.accent);
+    assert_eq!(edge_color(1), theme.accent);
+    assert!(edge_color(2).a() < 255, "unrelated edges are dimmed");
+
+    assert_eq!(state_box(&scene, "Locked").1.color, theme.accent);
+    assert!(state_box(&scene, "Alarming").1.color.a() < 255);
+}
+
+#[test]
+fn test_sim_overlay() {
+    let theme = DiagramTheme::default();
+    let overlay = SimOverlay {
+        active_state: Some("Unlocked"),
+        highlighted_transitions: &[id(1)],
+        token: Some(SimToken {
+            transition: id(0),
+            from: "Locked",
+            to: "Unlocked",
+            progress: 0.5,
+        }),
+    };
+    let (_, scene) = scene(overlay);
+
+    assert_eq!(state_box(&scene, "Unlocked").1.color, theme.accent);
+    assert_eq!(state_box(&scene, "Locked").1.color, theme.initial_stroke);
+    assert_eq!(state_box(&scene, "Alarming").1.color, theme.state_stroke);
+
+    assert_eq!(scene.primitives_of(Element::EdgeHighlight(id(1))).count(), 2);
+    assert_eq!(scene.primitives_of(Element::EdgeHighlight(id(0))).count(), 0);
+
+    // Halfway along Locked -> label -> Unlocked is the label node
+    match scene.primitives_of(Element::SimToken).collect::<Vec<_>>()[..] {
+        [Primitive::Circle { center, fill, .. }] => {
+            assert!(center.distance(egui::pos2(125.0, -30.0)) < 0.01);
+            assert_eq!(*fill, theme.accent);
+        }
+        ref other => panic!("expected one token, got {other:?}"),
+    }
+    // The overlay is drawn last
+    assert_eq!(scene.items.last().unwrap().element, Element::SimToken);
+}
+
+#[test]
+fn test_state_box_follows_font_size() {
+    let (fsm, _, _) = door();
+    let theme = DiagramTheme::default();
+    let viewport = Viewport {
+        offset: egui::Vec2::ZERO,
+        zoom: 1.0,
+    };
+    let mut builder = SceneBuilder::new(&theme, &Monospace, viewport);
+    builder.set_font_size(26.0);
+    builder.state(&fsm.states[0], egui::Pos2::ZERO, false, false);
+    let scene = builder.finish();
+
+    let (rect, _) = state_box(&scene, &fsm.states[0].name);
+    assert_eq!(*rect, calculate_state_rect(&fsm.states[0], egui::Pos2::ZERO, 1.0, 26.0, &StateBoxContent::default()));
+    let default = calculate_state_rect(&fsm.states[0], egui::Pos2::ZERO, 1.0, DEFAULT_DIAGRAM_FONT_SIZE, &StateBoxContent::default());
+    assert!(rect.width() > default.width() && rect.height() > default.height());
+    let name_size = scene.items.iter().find_map(|item| match &item.primitive {
+        Primitive::Text { text, font_size, .. } if *text == fsm.states[0].name => Some(*font_size),
+        _ => None,
+    });
+    assert_eq!(name_size, Some(26.0));
+}
+
+const VENDING: &str = r#"
+    fsm VendingMachine {
+        [*] --> AcceptingCoins
+        state AcceptingCoins : "Waiting for the full price" {
+            entry / show_price(total)
+            coin_inserted / add_to_balance(coin)
+            refund [has_balance] / return_coins
+        }
+        AcceptingCoins --> Dispensing : paid
+    }
+"#;
+
+#[test]
+fn test_state_size_follows_box_content() {
+    let fsm = &parse_fsm(VENDING).expect("Should parse successfully")[0];
+    let state = &fsm.states[0];
+    let size = |content: StateBoxContent| estimate_state_size(state, DEFAULT_DIAGRAM_FONT_SIZE, &content);
+    let default = size(StateBoxContent::default());
+
+    let internal = size(StateBoxContent { internal_transitions: true, ..StateBoxContent::default() });
+    assert!(internal.y > default.y);
+    assert!(internal.x > default.x, "`refund [has_balance] / return_coins` is the longest line");
+    let names_only = size(StateBoxContent { action_params: false, ..StateBoxContent::default() });
+    assert!(names_only.x < default.x && names_only.y == default.y);
+    let descriptions = size(StateBoxContent { descriptions: true, ..StateBoxContent::default() });
+    assert!(descriptions.y > default.y);
+    let bare = size(StateBoxContent { entry_exit_actions: false, ..StateBoxContent::default() });
+    assert!(bare.x < default.x);
+
+    // The layout engine's size and the drawn box change together
+    let rect = |content: StateBoxContent| calculate_state_rect(state, egui::Pos2::ZERO, 1.0, DEFAULT_DIAGRAM_FONT_SIZE, &content);
+    let everything = StateBoxContent {
+        internal_transitions: true,
+        entry_exit_actions: true,
+        action_params: true,
+        descriptions: true,
+    };
+    assert!(rect(everything).height() > rect(StateBoxContent::default()).height());
+    let fingerprint = |content: StateBoxContent| layout_fingerprint(fsm, &content);
+    assert_ne!(fingerprint(everything), fingerprint(StateBoxContent::default()));
+}
+
+#[test]
+fn test_internal_transitions_below_separator() {
+    let fsm = &parse_fsm(VENDING).expect("Should parse successfully")[0];
+    let theme = DiagramTheme::default();
+    let viewport = Viewport {
+        offset: egui::Vec2::ZERO,
+        zoom: 1.0,
+    };
+    let mut builder = SceneBuilder::new(&theme, &Monospace, viewport);
+    builder.set_state_box(StateBoxContent {
+        internal_transitions: true,
+        action_params: true,
+        ..StateBoxContent::default()
+    });
+    builder.state(&fsm.states[0], egui::Pos2::ZERO, false, false);
+    let scene = builder.finish();
+
+    let texts: Vec<(&str, f32)> = scene
+        .items
+        .iter()
+        .filter_map(|item| match &item.primitive {
+            Primitive::Text { text, pos, .. } => Some((text.as_str(), pos.y)),
+            _ => None,
+        })
+        .collect();
+    let (_, actions_y) = texts.iter().find(|(t, _)| *t == "entry/ show_price(total)").unwrap();
+    let (_, internal_y) = texts
+        .iter()
+        .find(|(t, _)| *t == "coin_inserted / add_to_balance(coin)\nrefund [has_balance] / return_coins")
+        .unwrap();
+    // Header separator, then the thin one between actions and internal transitions
+    let separators: Vec<f32> = scene
+        .items
+        .iter()
+        .filter_map(|item| match &item.primitive {
+            Primitive::Polyline { points, .. } if item.element == Element::StateContent(fsm.states[0].name.clone()) => Some(points[0].y),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(separators.len(), 2);
+    assert!(*actions_y < separators[1] && separators[1] < *internal_y);
+}
+
+#[test]
+fn test_viewport_opacity_and_geometry() {
+    let (fsm, _, _) = door();
+    let theme = DiagramTheme::default();
+    let viewport = Viewport {
+        offset: egui::vec2(400.0, 300.0),
+        zoom: 2.0,
+    };
+    let mut builder = SceneBuilder::new(&theme, &Monospace, viewport);
+    builder.set_opacity(0.5);
+    builder.state(&fsm.states[0], egui::pos2(10.0, 20.0), false, false);
+    let scene = builder.finish();
+
+    let (rect, stroke) = state_box(&scene, &fsm.states[0].name);
+    assert_eq!(rect.center(), egui::pos2(420.0, 340.0));
+    assert_eq!(*rect, calculate_state_rect(&fsm.states[0], egui::pos2(420.0, 340.0), 2.0, DEFAULT_DIAGRAM_FONT_SIZE, &StateBoxContent::default()));
+    assert_eq!(stroke.color, theme.state_stroke.gamma_multiply(0.5));
+
+    let route = [egui::pos2(0.0, 0.0), egui::pos2(10.0, 0.0), egui::pos2(10.0, 10.0)];
+    assert_eq!(polyline_point_at(&route, 0.75), Some(egui::pos2(10.0, 5.0)));
+    assert_eq!(distance_to_polyline(route, egui::pos2(14.0, 5.0)), 4.0);
+}
+
+#[test]
+fn test_layout_fingerprint_ignores_comments() {
+    let fingerprint = |source: &str| layout_fingerprint(&parse_fsm(source).expect("Should parse successfully")[0], &StateBoxContent::default());
+    let base = fingerprint("fsm Door {\n [*] --> Locked\n Locked --> Open : unlock\n}");
+
+    let commented = fingerprint(
+        "fsm Door {\n // the door\n [*] --> Locked\n state Locked: \"Bolted\"\n Locked --> Open : unlock // ABC-1\n}",
+    );
+    assert_eq!(base, commented);
+
+    let extra_transition = fingerprint("fsm Door {\n [*] --> Locked\n Locked --> Open : unlock\n Open --> Locked : lock\n}");
+    assert_ne!(base, extra_transition);
+    let relabelled = fingerprint("fsm Door {\n [*] --> Locked\n Locked --> Open : unlock [authorised]\n}");
+    assert_ne!(base, relabelled);
+}
+
+#[test]
+fn test_fit_and_zoom_at() {
+    let (fsm, layout, positions) = door();
+    // The engine's bounds: state boxes plus every edge route
+    let bounds = layout_bounds(
+        fsm.states.iter().map(|s| calculate_state_rect(s, positions[&s.name], 1.0, DEFAULT_DIAGRAM_FONT_SIZE, &StateBoxContent::default())),
+        layout.edges.iter().flat_map(|e| e.points.iter().copied()),
+    );
+    let canvas = egui::Rect::from_min_size(egui::pos2(300.0, 50.0), egui::vec2(800.0, 600.0));
+    let fit = Viewport::fit(bounds, canvas, 40.0);
+
+    let shown = egui::Rect::from_two_pos(fit.to_screen(bounds.min), fit.to_screen(bounds.max));
+    assert!((shown.center() - canvas.center()).length() < 0.01);
+    assert!(canvas.shrink(40.0).expand(0.01).contains_rect(shown));
+    // The limiting dimension touches the margin
+    let slack = canvas.shrink(40.0).size() - shown.size();
+    assert!(slack.x.min(slack.y).abs() < 0.01, "{slack:?}");
+
+    // Tiny and huge diagrams are clamped to the zoom limits
+    let dot = egui::Rect::from_center_size(egui::Pos2::ZERO, egui::vec2(1.0, 1.0));
+    assert_eq!(Viewport::fit(dot, canvas, 40.0).zoom, MAX_ZOOM);
+    assert_eq!(Viewport::fit(dot.expand(1.0e6), canvas, 40.0).zoom, MIN_ZOOM);
+
+    // Zooming keeps the point under the anchor in place
+    let anchor = egui::pos2(500.0, 120.0);
+    let diagram_point = ((anchor.to_vec2() - fit.offset) / fit.zoom).to_pos2();
+    let zoomed = fit.zoom_at(anchor, fit.zoom * 1.5);
+    assert!(zoomed.to_screen(diagram_point).distance(anchor) < 0.01);
+
+    // A view keeps its pan relative to whichever rect it is shown in
+    let mut view = CanvasView::default();
+    view.fit(bounds, canvas, 40.0);
+    assert_eq!((view.viewport(canvas).offset, view.viewport(canvas).zoom), (fit.offset, fit.zoom));
+    let right_half = egui::Rect::from_min_max(canvas.center(), canvas.max);
+    let moved = right_half.center() - canvas.center();
+    assert_eq!(view.viewport(right_half).offset, fit.offset + moved);
+    assert!(view.to_diagram(canvas, fit.to_screen(diagram_point)).distance(diagram_point) < 0.01);
+    view.zoom_at(anchor - canvas.center(), view.zoom * 1.5);
+    assert_eq!(view.viewport(canvas).offset, zoomed.offset);
+    view.show_at(diagram_point, egui::vec2(10.0, -20.0));
+    assert!(view.viewport(canvas).to_screen(diagram_point).distance(canvas.center() + egui::vec2(10.0, -20.0)) < 0.01);
+}
+
+#[test]
+fn test_label_hit_testing() {
+    let (_, layout, _) = door();
+    let viewport = Viewport {
+        offset: egui::vec2(400.0, 300.0),
+        zoom: 2.0,
+    };
+    // `lock [is_closed]` is centred at (125, 30) in the diagram
+    let center = viewport.to_screen(egui::pos2(125.0, 30.0));
+    assert_eq!(label_at(&layout, &Monospace, 12.0, viewport, center), Some(id(1)));
+    assert_eq!(label_at(&layout, &Monospace, 12.0, viewport, center + egui::vec2(100.0, 0.0)), Some(id(1)));
+    assert_eq!(label_at(&layout, &Monospace, 12.0, viewport, center + egui::vec2(0.0, 40.0)), None);
+}
+
+fn widest_line(text: &str) -> usize {
+    text.lines().map(|line| line.chars().count()).max().unwrap_or(0)
+}
+
+#[test]
+fn test_label_event_guard_and_actions() {
+    assert_eq!(format_label_text("go", LABEL_WRAP_CHARS), "go");
+    assert_eq!(format_label_text("go [ready] / start", LABEL_WRAP_CHARS), "go\n[ready]\n/ start");
+    assert_eq!(format_label_text("[ready]", LABEL_WRAP_CHARS), "[ready]");
+    assert_eq!(format_label_text("/ beep", LABEL_WRAP_CHARS), "/ beep");
+    assert_eq!(
+        format_label_text("coin / add_coin; update_display; beep", LABEL_WRAP_CHARS),
+        "coin\n/ add_coin;\n  update_display;\n  beep"
+    );
+    assert_eq!(format_label_text("coin / add; beep", LABEL_WRAP_CHARS), "coin\n/ add; beep");
+}
+
+#[test]
+fn test_label_long_event_wraps_at_underscores() {
+    let label = format_label_text("emergency_stop_button_pressed_twice", 16);
+    assert_eq!(label, "emergency_stop_\nbutton_pressed_\ntwice");
+    assert!(widest_line(&label) <= 16);
+}
+
+#[test]
+fn test_label_guard_wraps_at_operators() {
+    let label = format_label_text("unlock [attempts > 3 && lockout_not_active && !maintenance_mode]", LABEL_WRAP_CHARS);
+    assert_eq!(label, "unlock\n[attempts > 3\n&& lockout_not_active\n&& !maintenance_mode]");
+    assert!(widest_line(&label) <= LABEL_WRAP_CHARS);
+
+    let label = format_label_text("[in_range(temperature, min_temp, max_temp) || override]", LABEL_WRAP_CHARS);
+    assert_eq!(label, "[in_range(temperature,\nmin_temp, max_temp)\n|| override]");
+    assert!(widest_line(&label) <= LABEL_WRAP_CHARS);
+}
+
+#[test]
+fn test_label_too_wide_gets_ellipsis() {
+    let text = format_label_text("[a_really_long_unbreakable_guard_expression]", 10);
+    let fitted = fit_label_width(&text, 12.0, |line| line.chars().count() as f32);
+    assert_eq!(fitted, "[a_really_l…");
+    assert_eq!(fit_label_width("short\nwaaaaaaay too long", 8.0, |l| l.chars().count() as f32), "short\nwaaaaaa…");
+}
+
+#[test]
+fn test_external_self_transitions_marked() {
+    let source = r#"
+        fsm Box {
+            [*] --> Accepting
+            state Accepting {
+                coin / add_to_balance
+            }
+            Accepting --> Accepting : coin [jammed] / eject_coin
+            Accepting --> Accepting
+            Accepting --> Done : full
+            * --> Accepting : reset
+        }
+    "#;
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let labels: Vec<String> = fsm.transitions.iter().map(|t| t.diagram_label()).collect();
+    assert_eq!(labels, ["↺ coin [jammed] / eject_coin", "↺", "full", "reset"]);
+    assert_eq!(fsm.transitions[0].label(), "coin [jammed] / eject_coin");
+
+    // The internal transition is drawn in the box only, never as an edge
+    let (layout, _) = fallback_layout(fsm, DEFAULT_DIAGRAM_FONT_SIZE, &StateBoxContent::default(), &|t| t.diagram_label());
+    assert_eq!(layout.labels.len(), fsm.transitions.len());
+    assert!(layout.labels.iter().all(|l| !l.text.contains("add_to_balance")));
+}
+
+#[test]
+fn test_state_colors_follow_color_annotation() {
+    let source = r#"
+        fsm Link {
+            [*] --> Idle
+            state Idle
+            state Fault #color(Red) @tag(safety)
+            state Estop #color(red) @tag(safety)
+            state Connected #color(#FFE020) @tag(comms)
+            state Retry #color(#123) @tag(comms)
+        }
+    "#;
+    let fsms = parse_fsm(source).unwrap();
+    let fsm = &fsms[0];
+    let theme = DiagramTheme::default();
+    let colors = |name: &str| theme.state_colors(fsm.states.iter().find(|s| s.name == name).unwrap());
+    let plain = StateColors {
+        fill: theme.state_fill,
+        header: theme.state_header,
+        name: theme.state_name,
+    };
+
+    assert_eq!(colors("Idle"), plain);
+    // Named colors are case-insensitive; dark headers keep the theme's name color
+    assert_eq!(colors("Fault"), StateColors { header: egui::Color32::from_rgb(0xD0, 0x30, 0x30), ..plain });
+    // Light headers get dark text
+    assert_eq!(
+        colors("Connected"),
+        StateColors {
+            header: egui::Color32::from_rgb(0xFF, 0xE0, 0x20),
+            name: egui::Color32::BLACK,
+            ..plain
+        }
+    );
+    // Unparseable colors fall back to the theme
+    assert_eq!(colors("Retry"), plain);
+
+    assert_eq!(
+        tag_legend(fsm),
+        vec![
+            ("safety".to_string(), Some(egui::Color32::from_rgb(0xD0, 0x30, 0x30))),
+            ("comms".to_string(), None),
+        ]
+    );
+}
+
+/// Straight or bent route of one transition half
+fn route(transition: Option<TransitionId>, points: &[(f32, f32)]) -> LayoutedEdge {
+    LayoutedEdge {
+        v: String::new(),
+        w: String::new(),
+        transition,
+        points: points.iter().map(|&(x, y)| egui::pos2(x, y)).collect(),
+        transition_type: TransitionType::Forward,
+        is_else: false,
+        is_local: false,
+        is_dashed: false,
+    }
+}
+
+#[test]
+fn test_edge_crossings_on_hand_built_routes() {
+    let ids = door().0.transition_ids();
+    let (a, b, c) = (Some(ids[0]), Some(ids[1]), Some(ids[2]));
+
+    // An X crosses once, also with the start edge (no transition)
+    assert_eq!(edge_crossings(&[route(a, &[(0.0, 0.0), (100.0, 100.0)]), route(b, &[(0.0, 100.0), (100.0, 0.0)])]), 1);
+    assert_eq!(edge_crossings(&[route(None, &[(0.0, 0.0), (100.0, 100.0)]), route(b, &[(0.0, 100.0), (100.0, 0.0)])]), 1);
+
+    // A zigzag crosses a straight route once per segment
+    let zigzag = route(a, &[(0.0, -10.0), (50.0, 10.0), (100.0, -10.0), (150.0, 10.0)]);
+    let straight = route(c, &[(-10.0, 0.0), (210.0, 0.0)]);
+    assert_eq!(edge_crossings(&[zigzag.clone(), straight.clone()]), 3);
+
+    // Halves of one transition, routes meeting at a shared point, parallel routes
+    assert_eq!(edge_crossings(&[route(a, &[(0.0, 0.0), (100.0, 100.0)]), route(a, &[(0.0, 100.0), (100.0, 0.0)])]), 0);
+    assert_eq!(edge_crossings(&[route(a, &[(0.0, 0.0), (50.0, 50.0)]), route(b, &[(50.0, 50.0), (100.0, 0.0)])]), 0);
+    assert_eq!(edge_crossings(&[route(a, &[(0.0, 0.0), (100.0, 0.0)]), route(b, &[(0.0, 20.0), (100.0, 20.0)])]), 0);
+
+    // Every pair counts
+    let across = route(b, &[(75.0, -50.0), (75.0, 50.0)]);
+    assert_eq!(edge_crossings(&[zigzag, straight, across]), 3 + 1 + 1);
+}
+
+#[test]
+fn test_label_overlaps_edge_length_and_score() {
+    let rect = |x0: f32, y0: f32, x1: f32, y1: f32| egui::Rect::from_min_max(egui::pos2(x0, y0), egui::pos2(x1, y1));
+    let states = [rect(0.0, 0.0, 100.0, 50.0), rect(300.0, 0.0, 400.0, 50.0)];
+    let labels = [
+        rect(90.0, 40.0, 130.0, 60.0),  // Into the first state
+        rect(120.0, 55.0, 160.0, 70.0), // Into the first label
+        rect(100.0, 0.0, 120.0, 20.0),  // Only touches the first state
+        rect(200.0, 0.0, 240.0, 20.0),
+    ];
+    assert_eq!(label_overlaps(&states, &labels), 2);
+    assert_eq!(label_overlaps(&states, &[]), 0);
+
+    let edges = [route(None, &[(0.0, 0.0), (30.0, 40.0), (30.0, 90.0)]), route(None, &[(0.0, 0.0), (0.0, 20.0)])];
+    assert_eq!(total_edge_length(&edges), 120.0);
+
+    let quality = LayoutQuality::measure(&edges, &states, &labels);
+    assert_eq!((quality.crossings, quality.overlaps, quality.edge_length), (0, 2, 120.0));
+    // A crossing outweighs much longer edges
+    let tangled = LayoutQuality { crossings: 1, overlaps: 0, edge_length: 100.0 };
+    let spread = LayoutQuality { crossings: 0, overlaps: 0, edge_length: 900.0 };
+    assert!(spread.score() < tangled.score());
+}
+
+// ============================================================================
+// LABEL PLACEMENT
+// ============================================================================
+
+/// A label of transition `a` sitting on a horizontal route of transition `b`; its own
+/// route runs vertically through it
+fn label_on_route(extra: Vec<LayoutedEdge>) -> LayoutedDiagram {
+    let ids = door().0.transition_ids();
+    let mut edges = vec![route(Some(ids[1]), &[(-200.0, 0.0), (200.0, 0.0)]), route(Some(ids[0]), &[(0.0, -100.0), (0.0, 100.0)])];
+    edges.extend(extra);
+    LayoutedDiagram {
+        edges,
+        labels: vec![LayoutedLabel { pos: egui::pos2(0.0, 0.0), text: "go".to_string(), transition: Some(ids[0]) }],
+        bounds: egui::Rect::from_min_max(egui::pos2(-200.0, -100.0), egui::pos2(200.0, 100.0)),
+        fsm_name: "Door".to_string(),
+        states: Vec::new(),
+    }
+}
+
+/// Gap between the placed label's box and the horizontal line at `y`
+fn gap_to(layout: &LayoutedDiagram, y: f32) -> f32 {
+    let viewport = Viewport { offset: egui::Vec2::ZERO, zoom: 1.0 };
+    let rect = crate::diagram::label_rect(&Monospace, &layout.labels[0], 12.0, viewport);
+    if rect.top() > y {
+        rect.top() - y
+    } else {
+        (y - rect.bottom()).max(0.0)
+    }
+}
+
+#[test]
+fn test_label_moves_off_unrelated_route() {
+    let mut layout = label_on_route(Vec::new());
+    avoid_edge_segments(&mut layout, &Monospace, 12.0, &[]);
+
+    // Straight off the segment, just far enough; its own route does not hold it back
+    let pos = layout.labels[0].pos;
+    assert_eq!(pos.x, 0.0);
+    assert!(gap_to(&layout, 0.0) >= LABEL_CLEARANCE, "{pos:?}");
+    assert!(gap_to(&layout, 0.0) < LABEL_CLEARANCE + 5.0, "{pos:?}");
+
+    // A clear label stays where it is
+    let before = layout.labels[0].pos;
+    avoid_edge_segments(&mut layout, &Monospace, 12.0, &[]);
+    assert_eq!(layout.labels[0].pos, before);
+}
+
+#[test]
+fn test_label_moves_to_less_crowded_side() {
+    let ids = door().0.transition_ids();
+    let below = || route(Some(ids[2]), &[(-200.0, 30.0), (200.0, 30.0)]);
+
+    let mut layout = label_on_route(vec![below()]);
+    avoid_edge_segments(&mut layout, &Monospace, 12.0, &[]);
+    assert!(layout.labels[0].pos.y < 0.0, "{:?}", layout.labels[0].pos);
+    assert!(gap_to(&layout, 0.0) >= LABEL_CLEARANCE);
+
+    // A state box above leaves only the crowded side, past the second route
+    let node = egui::Rect::from_min_max(egui::pos2(-50.0, -60.0), egui::pos2(50.0, -18.0));
+    let mut layout = label_on_route(vec![below()]);
+    avoid_edge_segments(&mut layout, &Monospace, 12.0, &[node]);
+    assert!(layout.labels[0].pos.y > 30.0, "{:?}", layout.labels[0].pos);
+    assert!(gap_to(&layout, 30.0) >= LABEL_CLEARANCE);
+}
+
+#[test]
+fn test_label_placement_is_deterministic() {
+    // A long left-to-right chain whose labels all sit on one shared horizontal route
+    let ids: Vec<TransitionId> = door().0.transition_ids();
+    let mut layout = label_on_route(Vec::new());
+    layout.edges = vec![route(None, &[(-1000.0, 0.0), (1000.0, 0.0)])];
+    layout.labels = (0..40)
+        .map(|i| LayoutedLabel { pos: egui::pos2(i as f32 * 50.0 - 1000.0, (i % 3) as f32 * 4.0), text: "tick".to_string(), transition: Some(ids[i % 3]) })
+        .collect();
+
+    let mut first = layout.clone();
+    avoid_edge_segments(&mut first, &Monospace, 12.0, &[]);
+    let mut reversed = layout.clone();
+    reversed.labels.reverse();
+    avoid_edge_segments(&mut reversed, &Monospace, 12.0, &[]);
+    reversed.labels.reverse();
+
+    let positions = |layout: &LayoutedDiagram| layout.labels.iter().map(|l| l.pos).collect::<Vec<_>>();
+    assert_eq!(positions(&first), positions(&reversed));
+    for label in &first.labels {
+        let viewport = Viewport { offset: egui::Vec2::ZERO, zoom: 1.0 };
+        let rect = crate::diagram::label_rect(&Monospace, label, 12.0, viewport);
+        assert!(rect.top() >= LABEL_CLEARANCE || rect.bottom() <= -LABEL_CLEARANCE, "{rect:?}");
+    }
+}
+
+// ============================================================================
+// DAGRE OUTPUT
+// ============================================================================
+
+const NODES: &str = r#""nodes": {"A": {"x": 0, "y": 0, "width": 10, "height": 10}, "B": {"x": 0, "y": 50, "width": 10, "height": 10}}"#;
+
+fn dagre_output(protocol: &str, edges: &str) -> String {
+    format!(r#"{{{protocol}"graph": {{"width": 10, "height": 60}}, {NODES}, "edges": [{edges}]}}"#)
+}
+
+#[test]
+fn test_dagre_output_parses_and_checks_protocol() {
+    let script = std::path::Path::new("/old/checkout/src/layout_json.mjs");
+    let edge = r#"{"v": "A", "w": "B", "name": "e", "points": [{"x": 0, "y": 5}, {"x": 0, "y": 45}], "x": null, "y": null}"#;
+    let layout = parse_layout_output(dagre_output(r#""protocol": 2, "#, edge).as_bytes(), b"", script).unwrap();
+    assert_eq!((layout.nodes.len(), layout.edges[0].points.len()), (2, 2));
+
+    // A script from before the protocol field
+    let error = parse_layout_output(dagre_output("", edge).as_bytes(), b"warn: old", script).unwrap_err();
+    assert!(matches!(error, DagreError::ProtocolMismatch { expected: DAGRE_PROTOCOL, found: None, .. }));
+    let message = error.to_string();
+    assert!(message.contains("/old/checkout/src/layout_json.mjs speaks protocol none"), "{message}");
+    assert!(message.contains("expects protocol 2") && message.contains("stderr: warn: old"), "{message}");
+
+    let error = parse_layout_output(dagre_output(r#""protocol": 3, "#, edge).as_bytes(), b"", script).unwrap_err();
+    assert!(matches!(error, DagreError::ProtocolMismatch { found: Some(3), .. }));
+}
+
+#[test]
+fn test_dagre_output_rejects_malformed_json() {
+    let script = std::path::Path::new("layout_json.mjs");
+    let invalid = |stdout: &str| match parse_layout_output(stdout.as_bytes(), b"", script) {
+        Err(DagreError::InvalidOutput { message, output, .. }) => (message, output),
+        other => panic!("expected invalid output, got {other:?}"),
+    };
+
+    // Not JSON at all: the output is quoted, cut to 200 characters
+    let (_, output) = invalid(&format!("Error: Cannot find module 'dagre' {}", "x".repeat(300)));
+    assert!(output.starts_with("stdout: Error: Cannot find module 'dagre'"));
+    assert_eq!(output.lines().next().unwrap().chars().count(), "stdout: ".len() + 200 + 1);
+    assert!(output.ends_with("stderr: (empty)"));
+
+    // The right protocol but a missing field
+    let (message, _) = invalid(&dagre_output(r#""protocol": 2, "#, r#"{"v": "A", "w": "B", "name": null, "x": null, "y": null}"#));
+    assert!(message.contains("missing field `points`"), "{message}");
+
+    // Edges the diagram could not draw
+    let unknown = r#"{"v": "A", "w": "C", "name": null, "points": [{"x": 0, "y": 0}], "x": null, "y": null}"#;
+    let error = parse_layout_output(dagre_output(r#""protocol": 2, "#, unknown).as_bytes(), b"", script).unwrap_err();
+    assert!(matches!(&error, DagreError::UnknownNode { edge, node, .. } if edge == "A -> C" && node == "C"));
+    let empty = r#"{"v": "A", "w": "B", "name": null, "points": [], "x": null, "y": null}"#;
+    let error = parse_layout_output(dagre_output(r#""protocol": 2, "#, empty).as_bytes(), b"", script).unwrap_err();
+    assert!(matches!(&error, DagreError::EmptyRoute { edge, .. } if edge == "A -> B"));
+}
+
+#[test]
+fn test_grid_layout_without_transitions() {
+    let state_box = StateBoxContent::default();
+    let layout_of = |source: &str| {
+        let fsm = parse_fsm(source).unwrap().remove(0);
+        let (layout, positions) = grid_layout(&fsm, DEFAULT_DIAGRAM_FONT_SIZE, &state_box);
+        // The scene builds without a layout engine having run
+        let content = DiagramContent {
+            fsm: &fsm,
+            layout: &layout,
+            positions: &positions,
+            font_size: DEFAULT_DIAGRAM_FONT_SIZE,
+            label_font_size: 12.0,
+            state_box,
+            overlay: SimOverlay::default(),
+        };
+        build_scene(&content, &DiagramTheme::default(), &Monospace, Viewport { offset: egui::Vec2::ZERO, zoom: 1.0 });
+        (fsm, layout, positions)
+    };
+
+    // No states: an empty diagram with finite bounds
+    let (_, layout, positions) = layout_of("fsm Empty { }");
+    assert!(layout.edges.is_empty() && positions.is_empty());
+    assert!(layout.bounds.is_finite());
+
+    // One state, centered, with the start edge into it
+    let (_, layout, positions) = layout_of("fsm Single {\n [*] --> Idle\n}");
+    assert_eq!(positions["Idle"], egui::Pos2::ZERO);
+    assert!(positions.contains_key("[*]"));
+    assert_eq!(layout.edges.len(), 1);
+
+    // Five states fill a 3-column grid row by row, without overlapping
+    let (fsm, layout, positions) = layout_of("fsm Parts {\n state A\n state B\n state C\n state D\n state E\n}");
+    assert!(layout.edges.is_empty() && !positions.contains_key("[*]"));
+    assert_eq!(positions["A"].y, positions["C"].y);
+    assert!(positions["A"].x < positions["B"].x && positions["B"].x < positions["C"].x);
+    assert_eq!(positions["D"].x, positions["A"].x);
+    assert!(positions["D"].y > positions["A"].y);
+    let rects: Vec<egui::Rect> =
+        fsm.states.iter().map(|s| calculate_state_rect(s, positions[&s.name], 1.0, DEFAULT_DIAGRAM_FONT_SIZE, &state_box)).collect();
+    for (i, a) in rects.iter().enumerate() {
+        assert!(rects[i + 1..].iter().all(|b| !a.intersects(*b)));
+        assert!(layout.bounds.contains_rect(*a));
+    }
+}
+
+#[test]
+fn test_fallback_layout_routes_every_transition() {
+    let source = r#"
+        fsm Door {
+            [*] --> Closed
+            Closed --> Open : open [unlocked]
+            Open --> Closed : close
+            Open --> Open : hold
+            * --> Closed : reset
+        }
+    "#;
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let state_box = StateBoxContent::default();
+    let (layout, positions) = fallback_layout(fsm, DEFAULT_DIAGRAM_FONT_SIZE, &state_box, &|t| t.label());
+
+    // The start edge, then two halves per transition through its label node
+    assert_eq!(layout.edges.len(), 1 + 2 * 4);
+    assert_eq!(layout.labels.len(), 4);
+    let ids = fsm.transition_ids();
+    for (index, id) in ids.iter().enumerate().skip(1) {
+        let transition = &fsm.transitions[index];
+        let route = crate::diagram::transition_route(&layout, *id, &transition.source, &transition.target).expect("a route");
+        assert!(route.len() >= 3, "{:?}", transition.label());
+        assert!(positions.contains_key(&format!("__tr_{id}")));
+    }
+    assert!(positions.contains_key("[*]") && positions.contains_key("*"));
+    assert_eq!(layout.edges[1].transition_type, TransitionType::Conditional);
+
+    // Routes start and end on the state boxes, not at their centers
+    let closed = calculate_state_rect(&fsm.states[0], positions["Closed"], 1.0, DEFAULT_DIAGRAM_FONT_SIZE, &state_box);
+    let first = layout.edges[1].points[0];
+    assert!(closed.expand(0.5).contains(first) && !closed.shrink(0.5).contains(first));
+    assert!(layout.bounds.contains(positions["Open"]));
+}
+
+/// Generated stress fixture: `count` states in a ring, each with entry/exit actions,
+/// an internal transition and two labelled transitions out, plus a note every tenth
+fn stress_fsm_source(count: usize) -> String {
+    let mut source = String::from("fsm Stress {\n    [*] --> S000\n");
+    for i in 0..count {
+        source.push_str(&format!(
+            "    state S{i:03} : Stage {i} {{\n        entry / open_stage_{i}\n        exit / close_stage_{i}\n        poll [busy_{i}] / sample_{i}\n    }}\n"
+        ));
+    }
+    for i in 0..count {
+        let (next, skip) = ((i + 1) % count, (i + 7) % count);
+        source.push_str(&format!("    S{i:03} --> S{next:03} : advance [ready_{i}] / log_step({i})\n"));
+        source.push_str(&format!("    S{i:03} --> S{skip:03} : jump_{i} / skip_ahead\n"));
+        if i % 10 == 0 {
+            source.push_str(&format!("    note right of S{i:03} : checkpoint {i}\n"));
+        }
+    }
+    source.push_str("}\n");
+    source
+}
+
+/// The canvas's scene for `fsm`, measured directly or through `cache`
+fn canvas_scene(
+    ctx: &egui::Context,
+    fsm: &FsmDefinition,
+    layout: &LayoutedDiagram,
+    positions: &HashMap<String, egui::Pos2>,
+    viewport: Viewport,
+    cache: Option<&RenderCache>,
+) -> Scene {
+    let theme = DiagramTheme::default();
+    let measure: Box<dyn TextMeasure + '_> = match cache {
+        Some(cache) => Box::new(cache.measure(ctx)),
+        None => Box::new(ctx.clone()),
+    };
+    let mut scene = SceneBuilder::new(&theme, measure.as_ref(), viewport);
+    if let Some(cache) = cache {
+        scene.set_render_cache(cache);
+    }
+    scene.edges(layout, DEFAULT_DIAGRAM_FONT_SIZE, &[]);
+    for note in &fsm.notes {
+        let state = fsm.states.iter().find(|s| s.name == note.target_state).unwrap();
+        scene.note(state, positions[&note.target_state], note);
+    }
+    for state in &fsm.states {
+        scene.state(state, positions[&state.name], false, false);
+    }
+    scene.finish()
+}
+
+#[test]
+fn test_render_cache_matches_uncached_scene() {
+    let fsm = &parse_fsm(&stress_fsm_source(12)).unwrap()[0];
+    let state_box = StateBoxContent { internal_transitions: true, ..StateBoxContent::default() };
+    let (layout, positions) = fallback_layout(fsm, DEFAULT_DIAGRAM_FONT_SIZE, &StateBoxContent::default(), &|t| t.label());
+    let theme = DiagramTheme::default();
+    let viewport = Viewport { offset: egui::vec2(40.0, -25.0), zoom: 1.7 };
+
+    let ctx = egui::Context::default();
+    let _ = ctx.run(egui::RawInput::default(), |ctx| {
+        let mut cache = RenderCache::default();
+        let key = |fingerprint| RenderKey::new(fingerprint, 1.7, DEFAULT_DIAGRAM_FONT_SIZE, 1.0, &theme);
+        let fingerprint = layout_fingerprint(fsm, &StateBoxContent::default());
+        assert!(cache.prepare(ctx, key(fingerprint), fsm, &StateBoxContent::default()));
+        assert!(!cache.prepare(ctx, key(fingerprint), fsm, &StateBoxContent::default()));
+
+        // Same primitives as measuring every frame, twice (the second time from the cache)
+        let uncached = canvas_scene(ctx, fsm, &layout, &positions, viewport, None);
+        for _ in 0..2 {
+            assert_eq!(canvas_scene(ctx, fsm, &layout, &positions, viewport, Some(&cache)).items, uncached.items);
+        }
+        for state in &fsm.states {
+            let center = egui::pos2(10.0, 20.0);
+            let expected = calculate_state_rect(state, center, 1.7, DEFAULT_DIAGRAM_FONT_SIZE, &StateBoxContent::default());
+            let cached = cache.state_rect(state, center, 1.7, DEFAULT_DIAGRAM_FONT_SIZE, &StateBoxContent::default());
+            assert!((cached.size() - expected.size()).length() < 1e-3, "{}: {cached:?} vs {expected:?}", state.name);
+        }
+
+        // A new fingerprint (here: the internal transitions shown) measures the boxes again
+        assert!(cache.prepare(ctx, key(layout_fingerprint(fsm, &state_box)), fsm, &state_box));
+        assert_eq!(cache.state_body("S001").unwrap().internal, ["poll [busy_1] / sample_1"]);
+        let other_zoom = RenderKey::new(layout_fingerprint(fsm, &state_box), 3.5, DEFAULT_DIAGRAM_FONT_SIZE, 1.0, &theme);
+        assert!(cache.prepare(ctx, other_zoom, fsm, &state_box));
+    });
+}
+
+/// Steady-state canvas frame time for the 120-state stress machine, without and
+/// with the render cache: `cargo test --release --bin oxidate -- --ignored render_cache_frame_time --nocapture`
+#[test]
+#[ignore = "timing benchmark"]
+fn bench_render_cache_frame_time() {
+    use std::time::{Duration, Instant};
+
+    const FRAMES: usize = 60;
+
+    let fsm = &parse_fsm(&stress_fsm_source(120)).unwrap()[0];
+    let state_box = StateBoxContent::default();
+    let (layout, positions) = fallback_layout(fsm, DEFAULT_DIAGRAM_FONT_SIZE, &state_box, &|t| t.label());
+    let theme = DiagramTheme::default();
+    let viewport = Viewport { offset: egui::Vec2::ZERO, zoom: 0.8 };
+    let fingerprint = layout_fingerprint(fsm, &state_box);
+
+    let ctx = egui::Context::default();
+    let mut cache = RenderCache::default();
+    let mut frame = |cached: bool| {
+        let mut elapsed = Duration::ZERO;
+        let _ = ctx.run(egui::RawInput::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let start = Instant::now();
+                // The canvas paints the scene, then hit-tests the pointer against every
+                // state box and edge label (empty space here, so nothing stops early)
+                let pointer = egui::pos2(-1.0e4, -1.0e4);
+                let hovered = if cached {
+                    cache.prepare(ctx, RenderKey::new(fingerprint, viewport.zoom, DEFAULT_DIAGRAM_FONT_SIZE, 1.0, &theme), fsm, &state_box);
+                    paint_with_cache(ui.painter(), canvas_scene(ctx, fsm, &layout, &positions, viewport, Some(&cache)), &cache);
+                    let state = fsm.states.iter().find(|state| {
+                        let center = viewport.to_screen(positions[&state.name]);
+                        cache.state_rect(state, center, viewport.zoom, DEFAULT_DIAGRAM_FONT_SIZE, &state_box).contains(pointer)
+                    });
+                    (state.is_some(), label_at(&layout, &cache.measure(ctx), DEFAULT_DIAGRAM_FONT_SIZE, viewport, pointer))
+                } else {
+                    paint(ui.painter(), &canvas_scene(ctx, fsm, &layout, &positions, viewport, None));
+                    let state = fsm.states.iter().find(|state| {
+                        let center = viewport.to_screen(positions[&state.name]);
+                        calculate_state_rect(state, center, viewport.zoom, DEFAULT_DIAGRAM_FONT_SIZE, &state_box).contains(pointer)
+                    });
+                    (state.is_some(), label_at(&layout, ctx, DEFAULT_DIAGRAM_FONT_SIZE, viewport, pointer))
+                };
+                assert_eq!(hovered, (false, None));
+                elapsed = start.elapsed();
+            });
+        });
+        elapsed
+    };
+    let mut run = |cached| {
+        // Warm up egui's own galley cache and the font atlas first
+        (0..5).for_each(|_| {
+            frame(cached);
+        });
+        // The median, so a frame the scheduler interrupted does not count
+        let mut times: Vec<Duration> = (0..FRAMES).map(|_| frame(cached)).collect();
+        times.sort();
+        times[FRAMES / 2]
+    };
+    let uncached = run(false);
+    let cached = run(true);
+    println!("central panel frame: {uncached:?} uncached, {cached:?} with the render cache");
+    assert!(cached * 2 <= uncached, "{cached:?} is not under half of {uncached:?}");
+}
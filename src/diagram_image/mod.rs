@@ -0,0 +1,255 @@
+//! Diagram Image Export
+//! Renders the laid-out diagram offscreen (headless egui context + software
+//! rasterizer) so it can be copied to the clipboard or saved as PNG, or writes it
+//! as SVG
+
+use std::collections::HashMap;
+
+use eframe::egui;
+use eframe::epaint::{ClippedPrimitive, ImageData, Primitive, TextureId, Vertex};
+
+use crate::diagram::{self, DiagramContent, DiagramTheme, LayoutedDiagram, Viewport};
+
+#[cfg(test)]
+mod tests;
+
+/// Blank space around the diagram bounding box, in diagram units
+pub const EXPORT_MARGIN: f32 = 40.0;
+
+/// Resolution multiplier for exported images
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportScale {
+    #[default]
+    X1,
+    X2,
+    X4,
+}
+
+impl ExportScale {
+    pub const ALL: [ExportScale; 3] = [ExportScale::X1, ExportScale::X2, ExportScale::X4];
+
+    pub fn factor(&self) -> f32 {
+        match self {
+            ExportScale::X1 => 1.0,
+            ExportScale::X2 => 2.0,
+            ExportScale::X4 => 4.0,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportScale::X1 => "1x",
+            ExportScale::X2 => "2x",
+            ExportScale::X4 => "4x",
+        }
+    }
+}
+
+/// Pixel size of the exported image for a layout at `scale`
+pub fn image_size(layout: &LayoutedDiagram, scale: f32) -> [usize; 2] {
+    let size = layout.bounds.expand(EXPORT_MARGIN).size() * scale;
+    [size.x.ceil().max(1.0) as usize, size.y.ceil().max(1.0) as usize]
+}
+
+/// Render the whole diagram (not just the visible viewport) into an opaque image
+pub fn render_diagram(content: &DiagramContent, theme: &DiagramTheme, scale: f32) -> egui::ColorImage {
+    let size = image_size(content.layout, scale);
+    let screen = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(size[0] as f32, size[1] as f32));
+    let origin = content.layout.bounds.expand(EXPORT_MARGIN).min;
+    let viewport = Viewport {
+        offset: -origin.to_vec2() * scale,
+        zoom: scale,
+    };
+
+    let ctx = egui::Context::default();
+    let input = egui::RawInput {
+        screen_rect: Some(screen),
+        ..Default::default()
+    };
+    let output = ctx.run(input, |ctx| {
+        let painter = egui::Painter::new(ctx.clone(), egui::LayerId::background(), screen);
+        let scene = diagram::build_scene(content, theme, ctx, viewport);
+        diagram::egui_backend::paint(&painter, &scene);
+    });
+
+    let mut textures: HashMap<TextureId, egui::ColorImage> = HashMap::new();
+    for (id, delta) in &output.textures_delta.set {
+        let image = match &delta.image {
+            ImageData::Color(image) => (**image).clone(),
+            ImageData::Font(font) => egui::ColorImage {
+                size: font.size,
+                pixels: font.srgba_pixels(None).collect(),
+            },
+        };
+        match delta.pos {
+            None => {
+                textures.insert(*id, image);
+            }
+            Some([x0, y0]) => {
+                if let Some(texture) = textures.get_mut(id) {
+                    for y in 0..image.size[1] {
+                        for x in 0..image.size[0] {
+                            texture[(x0 + x, y0 + y)] = image[(x, y)];
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let primitives = ctx.tessellate(output.shapes, output.pixels_per_point);
+    rasterize(&primitives, &textures, size, theme.background)
+}
+
+/// The whole diagram as an SVG document, one diagram unit per pixel
+pub fn render_diagram_svg(content: &DiagramContent, theme: &DiagramTheme) -> String {
+    let size = image_size(content.layout, 1.0);
+    let screen = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(size[0] as f32, size[1] as f32));
+    let viewport = Viewport {
+        offset: -content.layout.bounds.expand(EXPORT_MARGIN).min.to_vec2(),
+        zoom: 1.0,
+    };
+
+    // Text is measured with egui's fonts, which are loaded once a frame runs
+    let ctx = egui::Context::default();
+    let input = egui::RawInput {
+        screen_rect: Some(screen),
+        ..Default::default()
+    };
+    let mut scene = diagram::Scene::default();
+    let _ = ctx.run(input, |ctx| scene = diagram::build_scene(content, theme, ctx, viewport));
+    diagram::svg_backend::render(&scene, screen.size(), theme.background)
+}
+
+/// Encode an image as PNG
+pub fn encode_png(image: &egui::ColorImage) -> Result<Vec<u8>, String> {
+    use image::ImageEncoder;
+
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png)
+        .write_image(
+            image.as_raw(),
+            image.size[0] as u32,
+            image.size[1] as u32,
+            image::ExtendedColorType::Rgba8,
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(png)
+}
+
+/// Put an image on the system clipboard
+pub fn copy_to_clipboard(clipboard: &mut arboard::Clipboard, image: &egui::ColorImage) -> Result<(), String> {
+    clipboard
+        .set_image(arboard::ImageData {
+            width: image.size[0],
+            height: image.size[1],
+            bytes: std::borrow::Cow::Borrowed(image.as_raw()),
+        })
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// SOFTWARE RASTERIZER
+// ============================================================================
+
+/// Premultiplied RGBA in 0..=1
+type Rgba = [f32; 4];
+
+fn rasterize(
+    primitives: &[ClippedPrimitive],
+    textures: &HashMap<TextureId, egui::ColorImage>,
+    size: [usize; 2],
+    background: egui::Color32,
+) -> egui::ColorImage {
+    // Start from the background directly; a painted rect would be feathered at the image border
+    let background = background.to_array().map(|c| c as f32 / 255.0);
+    let mut pixels: Vec<Rgba> = vec![background; size[0] * size[1]];
+
+    for clipped in primitives {
+        let Primitive::Mesh(mesh) = &clipped.primitive else {
+            continue;
+        };
+        let texture = textures.get(&mesh.texture_id);
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| &mesh.vertices[triangle[k] as usize]);
+            fill_triangle(&mut pixels, size, clipped.clip_rect, texture, a, b, c);
+        }
+    }
+
+    let mut image = egui::ColorImage::new(size, egui::Color32::TRANSPARENT);
+    for (dst, src) in image.pixels.iter_mut().zip(&pixels) {
+        let [r, g, b, a] = src.map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8);
+        *dst = egui::Color32::from_rgba_premultiplied(r, g, b, a);
+    }
+    image
+}
+
+fn fill_triangle(
+    pixels: &mut [Rgba],
+    size: [usize; 2],
+    clip: egui::Rect,
+    texture: Option<&egui::ColorImage>,
+    a: &Vertex,
+    b: &Vertex,
+    c: &Vertex,
+) {
+    let edge = |p: egui::Pos2, q: egui::Pos2, r: egui::Pos2| (q.x - p.x) * (r.y - p.y) - (q.y - p.y) * (r.x - p.x);
+    let area = edge(a.pos, b.pos, c.pos);
+    if area.abs() < 1e-6 {
+        return;
+    }
+
+    let bounds = egui::Rect::from_points(&[a.pos, b.pos, c.pos])
+        .intersect(clip)
+        .intersect(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(size[0] as f32, size[1] as f32)));
+    if !bounds.is_positive() {
+        return;
+    }
+
+    for y in bounds.min.y.floor() as usize..bounds.max.y.ceil() as usize {
+        for x in bounds.min.x.floor() as usize..bounds.max.x.ceil() as usize {
+            let p = egui::pos2(x as f32 + 0.5, y as f32 + 0.5);
+            let wa = edge(b.pos, c.pos, p) / area;
+            let wb = edge(c.pos, a.pos, p) / area;
+            let wc = 1.0 - wa - wb;
+            if wa < 0.0 || wb < 0.0 || wc < 0.0 {
+                continue;
+            }
+
+            let vertex_color = |v: &Vertex| v.color.to_array().map(|c| c as f32 / 255.0);
+            let [ca, cb, cc] = [vertex_color(a), vertex_color(b), vertex_color(c)];
+            let uv = egui::pos2(
+                wa * a.uv.x + wb * b.uv.x + wc * c.uv.x,
+                wa * a.uv.y + wb * b.uv.y + wc * c.uv.y,
+            );
+            let texel = texture.map_or([1.0; 4], |t| sample(t, uv));
+
+            let dst = &mut pixels[y * size[0] + x];
+            let src_alpha = (wa * ca[3] + wb * cb[3] + wc * cc[3]) * texel[3];
+            for i in 0..4 {
+                let src = (wa * ca[i] + wb * cb[i] + wc * cc[i]) * texel[i];
+                dst[i] = src + dst[i] * (1.0 - src_alpha);
+            }
+        }
+    }
+}
+
+/// Bilinear texture lookup (premultiplied)
+fn sample(texture: &egui::ColorImage, uv: egui::Pos2) -> Rgba {
+    let [w, h] = texture.size;
+    let x = (uv.x * w as f32 - 0.5).clamp(0.0, (w - 1) as f32);
+    let y = (uv.y * h as f32 - 0.5).clamp(0.0, (h - 1) as f32);
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(w - 1), (y0 + 1).min(h - 1));
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+
+    let texel = |x: usize, y: usize| texture[(x, y)].to_array().map(|c| c as f32 / 255.0);
+    let (t00, t10, t01, t11) = (texel(x0, y0), texel(x1, y0), texel(x0, y1), texel(x1, y1));
+    let mut out = [0.0; 4];
+    for i in 0..4 {
+        let top = t00[i] + (t10[i] - t00[i]) * fx;
+        let bottom = t01[i] + (t11[i] - t01[i]) * fx;
+        out[i] = top + (bottom - top) * fy;
+    }
+    out
+}
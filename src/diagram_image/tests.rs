@@ -0,0 +1,131 @@
+//! Unit tests for offscreen diagram rendering
+
+use std::collections::HashMap;
+
+use eframe::egui;
+
+use crate::diagram::{
+    estimate_state_size, DiagramContent, DiagramTheme, LayoutedDiagram, LayoutedEdge, LayoutedLabel, SimOverlay,
+    StateBoxContent, TransitionType, DEFAULT_DIAGRAM_FONT_SIZE,
+};
+use crate::diagram_image::{encode_png, image_size, render_diagram, render_diagram_svg, ExportScale, EXPORT_MARGIN};
+use crate::parser::parse_fsm;
+use crate::layout_bounds;
+
+/// Hand-placed layout so the test does not need the external layout engine
+fn traffic_light() -> (crate::fsm::FsmDefinition, LayoutedDiagram, HashMap<String, egui::Pos2>) {
+    let source = r#"
+        fsm TrafficLight {
+            [*] --> Red
+            Red --> Green : timer_expired
+            Green --> Red : timer_expired
+        }
+    "#;
+    let fsm = parse_fsm(source).expect("Should parse successfully").remove(0);
+
+    let positions: HashMap<String, egui::Pos2> = [
+        ("[*]", egui::pos2(-150.0, 0.0)),
+        ("Red", egui::pos2(-60.0, 0.0)),
+        ("Green", egui::pos2(120.0, 40.0)),
+    ]
+    .into_iter()
+    .map(|(name, pos)| (name.to_string(), pos))
+    .collect();
+
+    let points = vec![egui::pos2(-10.0, 0.0), egui::pos2(70.0, 0.0), egui::pos2(70.0, 40.0)];
+    let nodes = fsm
+        .states
+        .iter()
+        .map(|s| egui::Rect::from_center_size(positions[&s.name], estimate_state_size(s, DEFAULT_DIAGRAM_FONT_SIZE, &StateBoxContent::default())));
+    let bounds = layout_bounds(nodes, points.iter().copied());
+
+    let layout = LayoutedDiagram {
+        edges: vec![LayoutedEdge {
+            v: "Red".to_string(),
+            w: "Green".to_string(),
+            transition: fsm.transition_id(0),
+            points,
+            transition_type: TransitionType::Forward,
+            is_else: false,
+            is_local: false,
+            is_dashed: false,
+        }],
+        labels: vec![LayoutedLabel {
+            pos: egui::pos2(70.0, 20.0),
+            text: "timer_expired".to_string(),
+            transition: fsm.transition_id(0),
+        }],
+        bounds,
+        fsm_name: fsm.name.clone(),
+        states: fsm.states.clone(),
+    };
+    (fsm, layout, positions)
+}
+
+#[test]
+fn test_image_matches_layout_bounds() {
+    let (fsm, layout, positions) = traffic_light();
+    let content = DiagramContent {
+        fsm: &fsm,
+        layout: &layout,
+        positions: &positions,
+        font_size: DEFAULT_DIAGRAM_FONT_SIZE,
+        label_font_size: 12.0,
+        state_box: StateBoxContent::default(),
+        overlay: SimOverlay::default(),
+    };
+    let theme = DiagramTheme::default();
+
+    for scale in ExportScale::ALL {
+        let image = render_diagram(&content, &theme, scale.factor());
+        let expected = (layout.bounds.size() + egui::Vec2::splat(2.0 * EXPORT_MARGIN)) * scale.factor();
+        assert_eq!(image.size, [expected.x.ceil() as usize, expected.y.ceil() as usize]);
+        assert_eq!(image.size, image_size(&layout, scale.factor()));
+    }
+}
+
+#[test]
+fn test_image_is_opaque_with_content() {
+    let (fsm, layout, positions) = traffic_light();
+    let content = DiagramContent {
+        fsm: &fsm,
+        layout: &layout,
+        positions: &positions,
+        font_size: DEFAULT_DIAGRAM_FONT_SIZE,
+        label_font_size: 12.0,
+        state_box: StateBoxContent::default(),
+        overlay: SimOverlay::default(),
+    };
+    let theme = DiagramTheme::default();
+    let image = render_diagram(&content, &theme, 1.0);
+
+    assert!(image.pixels.iter().all(|p| p.a() == 255));
+    assert_eq!(image[(0, 0)], theme.background);
+    assert!(image.pixels.iter().any(|&p| p != theme.background));
+
+    let png = encode_png(&image).expect("Should encode");
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+}
+
+#[test]
+fn test_svg_has_states_labels_and_size() {
+    let (fsm, layout, positions) = traffic_light();
+    let content = DiagramContent {
+        fsm: &fsm,
+        layout: &layout,
+        positions: &positions,
+        font_size: DEFAULT_DIAGRAM_FONT_SIZE,
+        label_font_size: 12.0,
+        state_box: StateBoxContent::default(),
+        overlay: SimOverlay::default(),
+    };
+    let svg = render_diagram_svg(&content, &DiagramTheme::default());
+
+    let [width, height] = image_size(&layout, 1.0);
+    assert!(svg.starts_with(&format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\"")));
+    assert!(svg.trim_end().ends_with("</svg>"));
+    for text in ["Red", "Green", "timer_expired"] {
+        assert!(svg.contains(&format!(">{text}</text>")), "{text} missing");
+    }
+    assert!(svg.contains("<polyline") && svg.contains("<polygon"));
+}
@@ -0,0 +1,362 @@
+//! DSL Reference
+//! What the Help > DSL Reference window shows: one entry per construct with a line
+//! of description and a snippet to insert. The grammar shape of each entry is read
+//! from `fsm.pest` itself, and which constructs need an entry from the alternatives
+//! of its `fsm_item` and `state_body_item` rules, so the reference cannot fall
+//! behind the grammar without a test noticing.
+
+use std::ops::Range;
+
+#[cfg(test)]
+mod tests;
+
+/// The grammar the parser is generated from
+pub const GRAMMAR: &str = include_str!("../parser/fsm.pest");
+
+/// Where a construct is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// Top level of a file
+    File,
+    /// Inside `fsm Name { }`
+    Fsm,
+    /// Inside `state Name { }`
+    State,
+}
+
+impl std::fmt::Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scope::File => write!(f, "Top level"),
+            Scope::Fsm => write!(f, "Inside fsm {{ }}"),
+            Scope::State => write!(f, "Inside state {{ }}"),
+        }
+    }
+}
+
+/// One construct of the reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DocEntry {
+    pub title: &'static str,
+    /// Grammar rule the snippet is an instance of
+    pub rule: &'static str,
+    pub scope: Scope,
+    pub description: &'static str,
+    /// Source that parses as `rule` on its own
+    pub snippet: &'static str,
+}
+
+impl DocEntry {
+    /// Right-hand side of the entry's rule in the grammar
+    pub fn shape(&self) -> String {
+        rule_shape(self.rule).unwrap_or_default()
+    }
+}
+
+/// Every entry, grouped by scope in the order the window lists them
+pub const DOC_ENTRIES: &[DocEntry] = &[
+    DocEntry {
+        title: "State machine",
+        rule: "fsm_definition",
+        scope: Scope::File,
+        description: "A named machine; everything inside fsm { } below goes between its braces",
+        snippet: "fsm Blinker {\n    [*] --> Off\n    Off --> On : toggle\n    On --> Off : toggle\n}",
+    },
+    DocEntry {
+        title: "Include",
+        rule: "include_directive",
+        scope: Scope::File,
+        description: "Pulls in the FSMs of another file, resolved relative to this one",
+        snippet: "include \"motor.fsm\"",
+    },
+    DocEntry {
+        title: "Description",
+        rule: "fsm_description",
+        scope: Scope::Fsm,
+        description: "Shown under the FSM tabs and written to the header of generated files",
+        snippet: "description: Blinks the status LED while enabled",
+    },
+    DocEntry {
+        title: "Metadata",
+        rule: "meta_def",
+        scope: Scope::Fsm,
+        description: "Free-form key and value, written to the header comments of exports",
+        snippet: "meta version = \"1.2\"",
+    },
+    DocEntry {
+        title: "File name",
+        rule: "file_name_def",
+        scope: Scope::Fsm,
+        description: "Stem of the generated files instead of the snake_case FSM name",
+        snippet: "@file_name(\"blinker\")",
+    },
+    DocEntry {
+        title: "Layout direction",
+        rule: "direction_def",
+        scope: Scope::Fsm,
+        description: "Which way the diagram flows: TB, BT, LR or RL",
+        snippet: "direction LR",
+    },
+    DocEntry {
+        title: "Note",
+        rule: "note_def",
+        scope: Scope::Fsm,
+        description: "Text drawn beside a state in the diagram",
+        snippet: "note right of Idle : Waits for the first command",
+    },
+    DocEntry {
+        title: "Initial state",
+        rule: "initial_state",
+        scope: Scope::Fsm,
+        description: "Where the machine starts; the actions run once, before the state's entry actions",
+        snippet: "[*] --> Idle : / load_config",
+    },
+    DocEntry {
+        title: "Guarded initial state",
+        rule: "initial_state",
+        scope: Scope::Fsm,
+        description: "Starts here instead when its guard holds at start; guarded ones are tried in order before the unguarded default",
+        snippet: "[*] --> Recovery : [recovery_pin_set]",
+    },
+    DocEntry {
+        title: "Events",
+        rule: "events_def",
+        scope: Scope::Fsm,
+        description: "Declares events in enum order, with /// docs, payload parameters and = N ids",
+        snippet: "events {\n    start, stop\n    set_speed(rpm: u16) = 5\n}",
+    },
+    DocEntry {
+        title: "Timer",
+        rule: "timer_def",
+        scope: Scope::Fsm,
+        description: "Sends an event after a delay (ms, or 2s), once (oneshot) or repeatedly (periodic)",
+        snippet: "timer blink = 500 -> Tick periodic",
+    },
+    DocEntry {
+        title: "Named guard",
+        rule: "guard_def",
+        scope: Scope::Fsm,
+        description: "A guard expression defined once and used by name between [ ]",
+        snippet: "guard ready = calibrated && !fault",
+    },
+    DocEntry {
+        title: "Choice point",
+        rule: "choice_def",
+        scope: Scope::Fsm,
+        description: "Picks the target of the first branch whose guard holds; transitions reach it as <<name>>",
+        snippet: "choice check_level {\n    [level_high] -> Draining / open_valve\n    [else] -> Idle\n}",
+    },
+    DocEntry {
+        title: "State",
+        rule: "state_simple",
+        scope: Scope::Fsm,
+        description: "Declares a state, optionally with a #color, @tags and a description",
+        snippet: "state Idle #color(green) @tag(safe) : Waiting for work",
+    },
+    DocEntry {
+        title: "Final state",
+        rule: "state_simple",
+        scope: Scope::Fsm,
+        description: "The machine stops there, so no transition may leave it",
+        snippet: "final state Done",
+    },
+    DocEntry {
+        title: "State with a body",
+        rule: "state_with_body",
+        scope: Scope::Fsm,
+        description: "A state with entry and exit actions, timers and internal transitions",
+        snippet: "state Heating {\n    entry / heater_on\n    exit / heater_off\n}",
+    },
+    DocEntry {
+        title: "Transition",
+        rule: "transition",
+        scope: Scope::Fsm,
+        description: "Moves to another state on an event if the guard holds, running the actions",
+        snippet: "Idle --> Running : start [ready] / spin_up; log(\"started\")",
+    },
+    DocEntry {
+        title: "Completion transition",
+        rule: "transition",
+        scope: Scope::Fsm,
+        description: "No event: taken as soon as the source is entered and the guard holds",
+        snippet: "Validating --> Submitting : [all_valid]",
+    },
+    DocEntry {
+        title: "Any-state transition",
+        rule: "transition",
+        scope: Scope::Fsm,
+        description: "Leaves every state that does not take the event itself",
+        snippet: "* --> Stopped : emergency_stop",
+    },
+    DocEntry {
+        title: "Payload binding",
+        rule: "transition",
+        scope: Scope::Fsm,
+        description: "Binds the parameters of a declared event for the guard and actions",
+        snippet: "Running --> Running : set_speed(rpm) [speed_ok(rpm)] / apply_speed(rpm)",
+    },
+    DocEntry {
+        title: "Debounce / throttle",
+        rule: "transition",
+        scope: Scope::Fsm,
+        description: "Ignores the event until it has been quiet for the window (debounce), or within the window after the last firing (throttle)",
+        snippet: "Closed --> Open : door_opened @debounce(50ms) [unlocked]",
+    },
+    DocEntry {
+        title: "Transition to a choice point",
+        rule: "transition",
+        scope: Scope::Fsm,
+        description: "Hands the decision to a choice point",
+        snippet: "Measuring --> <<check_level>> : done",
+    },
+    DocEntry {
+        title: "Async action",
+        rule: "transition",
+        scope: Scope::Fsm,
+        description: "The Embassy target awaits the action; targets that cannot await reject the machine",
+        snippet: "Ready --> Sending : frame_ready / async send_frame; count_frame",
+    },
+    DocEntry {
+        title: "Entry actions",
+        rule: "entry_action",
+        scope: Scope::State,
+        description: "Run in order each time the state is entered",
+        snippet: "entry / heater_on; reset_count",
+    },
+    DocEntry {
+        title: "Exit actions",
+        rule: "exit_action",
+        scope: Scope::State,
+        description: "Run in order each time the state is left",
+        snippet: "exit / heater_off",
+    },
+    DocEntry {
+        title: "Start a timer",
+        rule: "timer_start",
+        scope: Scope::State,
+        description: "Starts a timer when the state is entered",
+        snippet: "start_timer(blink)",
+    },
+    DocEntry {
+        title: "Stop a timer",
+        rule: "timer_stop",
+        scope: Scope::State,
+        description: "Stops a timer when the state is left",
+        snippet: "stop_timer(blink)",
+    },
+    DocEntry {
+        title: "Transition in the state block",
+        rule: "grouped_transition",
+        scope: Scope::State,
+        description: "A transition leaving the state, written in its block: the same as `State --> Target : event [guard] / action`",
+        snippet: "on timeout [held_too_long] -> Alarming / sound_alarm",
+    },
+    DocEntry {
+        title: "Internal transition",
+        rule: "internal_transition",
+        scope: Scope::State,
+        description: "Handles an event without leaving the state: no exit or entry actions run",
+        snippet: "tick [counting] / count_tick",
+    },
+    DocEntry {
+        title: "Internal action",
+        rule: "internal_action",
+        scope: Scope::State,
+        description: "An internal transition without a guard (the older form)",
+        snippet: "tick / count_tick",
+    },
+];
+
+/// Text of `rule = { ... }` between the braces, with whitespace collapsed
+pub fn rule_shape(rule: &str) -> Option<String> {
+    let mut offset = 0;
+    let start = GRAMMAR.split_inclusive('\n').find_map(|line| {
+        let start = offset;
+        offset += line.len();
+        line.strip_prefix(rule)?.trim_start().starts_with('=').then_some(start)
+    })?;
+    let open = start + GRAMMAR[start..].find('{')?;
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in GRAMMAR[open..].char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '{' if !quoted => depth += 1,
+            '}' if !quoted => {
+                depth -= 1;
+                if depth == 0 {
+                    let body = &GRAMMAR[open + 1..open + i];
+                    return Some(body.split_whitespace().collect::<Vec<_>>().join(" "));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Rules `rule` chooses between (`fsm_item` → `fsm_description`, `meta_def`, ...):
+/// the alternatives of its body, or of the group it ends with
+pub fn rule_alternatives(rule: &str) -> Vec<String> {
+    let Some(shape) = rule_shape(rule) else {
+        return Vec::new();
+    };
+    let mut expression = shape.as_str();
+    loop {
+        let alternatives = split_top_level(expression, '|');
+        if alternatives.len() > 1 {
+            return alternatives.into_iter().map(|a| a.trim().to_string()).collect();
+        }
+        match expression.trim_end().strip_suffix(')').and_then(|rest| rest.find('(').map(|i| &rest[i + 1..])) {
+            Some(group) => expression = group,
+            None => return vec![shape],
+        }
+    }
+}
+
+/// `expression` cut at each `separator` outside parentheses and quotes
+fn split_top_level(expression: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut quoted, mut start) = (0usize, false, 0);
+    for (i, c) in expression.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth = depth.saturating_sub(1),
+            c if c == separator && !quoted && depth == 0 => {
+                parts.push(&expression[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&expression[start..]);
+    parts
+}
+
+/// Insert `snippet` into `text` at byte offset `at` as whole lines, indented like
+/// the line `at` is on: at `at` if nothing but indentation comes before it on the
+/// line, else on a new line after it. Returns the byte range the snippet took.
+pub fn insert_snippet(text: &mut String, at: usize, snippet: &str) -> Range<usize> {
+    let at = at.min(text.len());
+    let line_start = text[..at].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = text[at..].find('\n').map_or(text.len(), |i| at + i);
+    let before = &text[line_start..at];
+    let (offset, indent, mut inserted) = match before.trim().is_empty() {
+        true => (at, before.to_string(), String::new()),
+        false => {
+            let indent: String = before.chars().take_while(|c| c.is_whitespace()).collect();
+            (line_end, indent.clone(), format!("\n{}", indent))
+        }
+    };
+    inserted.push_str(&snippet.lines().collect::<Vec<_>>().join(&format!("\n{}", indent)));
+    // Text after the cursor moves to a line of its own
+    if offset == at && !text[at..line_end].trim().is_empty() {
+        inserted.push_str(&format!("\n{}", indent));
+    }
+    text.insert_str(offset, &inserted);
+    offset..offset + inserted.len()
+}
@@ -0,0 +1,76 @@
+//! Unit tests for the DSL reference
+
+use pest::Parser;
+
+use crate::docs::{insert_snippet, rule_alternatives, rule_shape, Scope, DOC_ENTRIES};
+use crate::parser::{parse_fsm, FsmParser, Rule};
+
+#[test]
+fn test_every_grammar_alternative_has_an_entry() {
+    let fsm_items = rule_alternatives("fsm_item");
+    assert!(fsm_items.len() >= 14, "{fsm_items:?}");
+    assert!(fsm_items.contains(&"choice_def".to_string()));
+    let state_items = rule_alternatives("state_body_item");
+    assert_eq!(state_items, ["grouped_transition", "entry_action", "exit_action", "timer_start", "timer_stop", "internal_transition", "internal_action"]);
+
+    // `unsupported` catches Mermaid statements only to report them
+    for rule in fsm_items.iter().chain(&state_items).filter(|rule| *rule != "unsupported") {
+        assert!(DOC_ENTRIES.iter().any(|entry| entry.rule == rule), "no DSL reference entry for `{rule}`");
+    }
+}
+
+#[test]
+fn test_snippets_parse_as_their_rule() {
+    for entry in DOC_ENTRIES {
+        let rule = Rule::all_rules()
+            .iter()
+            .find(|rule| format!("{:?}", rule) == entry.rule)
+            .unwrap_or_else(|| panic!("`{}` is not a grammar rule", entry.rule));
+        let parsed = FsmParser::parse(*rule, entry.snippet).unwrap_or_else(|e| panic!("{}: {}", entry.title, e));
+        assert_eq!(parsed.as_str(), entry.snippet, "{} is more than one `{}`", entry.title, entry.rule);
+        assert!(!entry.shape().is_empty());
+
+        let source = match entry.scope {
+            Scope::File => entry.snippet.to_string(),
+            Scope::Fsm => format!("fsm Example {{\n{}\n}}", entry.snippet),
+            Scope::State => format!("fsm Example {{\n[*] --> Idle\nstate Idle {{\n{}\n}}\n}}", entry.snippet),
+        };
+        parse_fsm(&source).unwrap_or_else(|e| panic!("{}: {}", entry.title, e));
+    }
+}
+
+#[test]
+fn test_rule_shape() {
+    assert_eq!(rule_shape("timer_start").as_deref(), Some("\"start_timer\" ~ \"(\" ~ identifier ~ \")\""));
+    // Braces in string literals do not end the rule
+    assert_eq!(
+        rule_shape("events_def").as_deref(),
+        Some("\"events\" ~ \"{\" ~ (event_item | doc_comment)* ~ \"}\"")
+    );
+    assert_eq!(rule_shape("identifier").as_deref(), Some("(XID_START | \"_\") ~ XID_CONTINUE*"));
+    assert_eq!(rule_shape("no_such_rule"), None);
+}
+
+#[test]
+fn test_insert_snippet() {
+    // On a blank indented line: at the cursor, later lines indented the same
+    let mut text = "fsm A {\n    \n}".to_string();
+    let range = insert_snippet(&mut text, 12, "state B {\n    entry / b\n}");
+    assert_eq!(text, "fsm A {\n    state B {\n        entry / b\n    }\n}");
+    assert_eq!(&text[range], "state B {\n        entry / b\n    }");
+
+    // In the middle of a line: on a new line after it
+    let mut text = "fsm A {\n    [*] --> Idle\n}".to_string();
+    insert_snippet(&mut text, 15, "direction LR");
+    assert_eq!(text, "fsm A {\n    [*] --> Idle\n    direction LR\n}");
+
+    // Before the text of a line: that text moves down
+    let mut text = "fsm A {\n    [*] --> Idle\n}".to_string();
+    insert_snippet(&mut text, 12, "direction LR");
+    assert_eq!(text, "fsm A {\n    direction LR\n    [*] --> Idle\n}");
+
+    // Past the end
+    let mut text = String::new();
+    assert_eq!(insert_snippet(&mut text, 5, "fsm A { }"), 0..9);
+    assert_eq!(text, "fsm A { }");
+}
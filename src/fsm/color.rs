@@ -0,0 +1,54 @@
+//! State Colors
+//! Values of `#color(...)` state annotations: a named color or `#RRGGBB`
+
+/// Named colors accepted by `#color(name)` (case-insensitive)
+const NAMED_COLORS: &[(&str, [u8; 3])] = &[
+    ("black", [0x00, 0x00, 0x00]),
+    ("white", [0xFF, 0xFF, 0xFF]),
+    ("gray", [0x80, 0x80, 0x80]),
+    ("grey", [0x80, 0x80, 0x80]),
+    ("silver", [0xC0, 0xC0, 0xC0]),
+    ("red", [0xD0, 0x30, 0x30]),
+    ("maroon", [0x80, 0x00, 0x00]),
+    ("orange", [0xFF, 0x8C, 0x00]),
+    ("gold", [0xFF, 0xD7, 0x00]),
+    ("yellow", [0xFF, 0xE0, 0x20]),
+    ("olive", [0x80, 0x80, 0x00]),
+    ("lime", [0x32, 0xCD, 0x32]),
+    ("green", [0x2E, 0x8B, 0x57]),
+    ("teal", [0x00, 0x80, 0x80]),
+    ("cyan", [0x00, 0xB7, 0xEB]),
+    ("blue", [0x30, 0x60, 0xD0]),
+    ("navy", [0x00, 0x00, 0x80]),
+    ("purple", [0x80, 0x30, 0xA0]),
+    ("magenta", [0xFF, 0x00, 0xFF]),
+    ("pink", [0xFF, 0x69, 0xB4]),
+    ("brown", [0x8B, 0x45, 0x13]),
+];
+
+/// RGB of a named color or `#RRGGBB`; `None` for anything else
+pub fn parse_color(text: &str) -> Option<[u8; 3]> {
+    let text = text.trim();
+    if let Some(hex) = text.strip_prefix('#') {
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some([channel(0)?, channel(2)?, channel(4)?]);
+    }
+    NAMED_COLORS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(text))
+        .map(|&(_, rgb)| rgb)
+}
+
+/// `#RRGGBB`
+pub fn to_hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02X}{:02X}{:02X}", r, g, b)
+}
+
+/// Whether dark text reads better than light text on `rgb`
+pub fn is_light([r, g, b]: [u8; 3]) -> bool {
+    // Relative luminance with the usual Rec. 601 weights
+    0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32 > 150.0
+}
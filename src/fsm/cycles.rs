@@ -0,0 +1,231 @@
+//! Cycle Analysis
+//! Strongly connected components of the transition graph, for livelock detection
+
+use std::collections::HashMap;
+
+use super::{FsmDefinition, StateType, TransitionKind};
+
+/// Groups of reachable states that can cycle forever: every transition out of
+/// them stays inside the group and none of them is final.
+///
+/// Only states reachable from the initial state are considered, so dead code is
+/// left to reachability checks. The group containing the initial state is the
+/// machine's main loop (e.g. a traffic light) and is not reported. States are
+/// listed in declaration order.
+pub fn livelock_cycles(fsm: &FsmDefinition) -> Vec<Vec<String>> {
+    let graph = TransitionGraph::new(fsm);
+    let reachable = graph.reachable_from_initial(fsm);
+    let initial: Vec<usize> = fsm.initial_transitions.iter().filter_map(|t| graph.index.get(t.target.as_str()).copied()).collect();
+
+    let mut cycles = Vec::new();
+    for component in graph.strongly_connected_components() {
+        if !component.iter().all(|&s| reachable[s]) || initial.iter().any(|i| component.contains(i)) {
+            continue;
+        }
+        let cyclic = component.len() > 1 || graph.edges[component[0]].contains(&Node::State(component[0]));
+        let escapes = component.iter().any(|&s| {
+            graph.edges[s].iter().any(|node| match node {
+                Node::State(t) => !component.contains(t),
+                Node::Final => true,
+            })
+        });
+        let has_final = component.iter().any(|&s| fsm.states[s].state_type == StateType::Final);
+
+        if cyclic && !escapes && !has_final {
+            let mut states = component;
+            states.sort_unstable();
+            cycles.push(states);
+        }
+    }
+    cycles.sort();
+    cycles
+        .into_iter()
+        .map(|states| states.into_iter().map(|s| fsm.states[s].name.clone()).collect())
+        .collect()
+}
+
+/// Loops of unguarded completion transitions (`A --> B`, neither event nor guard),
+/// which would hop forever once entered. Each loop starts and ends at its
+/// first-declared state, e.g. `[A, B, A]`. Only a state's first unguarded completion
+/// transition counts, since nothing after it is ever tried.
+pub fn completion_cycles(fsm: &FsmDefinition) -> Vec<Vec<String>> {
+    let index: HashMap<&str, usize> = fsm.states.iter().enumerate().map(|(i, s)| (s.name.as_str(), i)).collect();
+    let next: Vec<Option<usize>> = fsm
+        .states
+        .iter()
+        .map(|state| {
+            fsm.transitions
+                .iter()
+                .find(|t| t.source == state.name && t.is_completion() && t.guard.is_none())
+                .and_then(|t| index.get(t.target.as_str()).copied())
+        })
+        .collect();
+
+    // Every state has at most one successor, so each walk ends in a known state or a new loop
+    let mut visited = vec![false; fsm.states.len()];
+    let mut cycles = Vec::new();
+    for start in 0..fsm.states.len() {
+        let mut path = Vec::new();
+        let mut current = Some(start);
+        while let Some(state) = current.filter(|&s| !visited[s]) {
+            visited[state] = true;
+            path.push(state);
+            current = next[state];
+        }
+        if let Some(position) = current.and_then(|s| path.iter().position(|&p| p == s)) {
+            let mut cycle = path.split_off(position);
+            let first = cycle.iter().enumerate().min_by_key(|&(_, &s)| s).map_or(0, |(i, _)| i);
+            cycle.rotate_left(first);
+            cycle.push(cycle[0]);
+            cycles.push(cycle.into_iter().map(|s| fsm.states[s].name.clone()).collect());
+        }
+    }
+    cycles
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Node {
+    State(usize),
+    /// `[*]` as a target
+    Final,
+}
+
+/// External transitions as adjacency lists over state indices (choice points resolved to their branches)
+struct TransitionGraph<'a> {
+    index: HashMap<&'a str, usize>,
+    edges: Vec<Vec<Node>>,
+}
+
+impl<'a> TransitionGraph<'a> {
+    fn new(fsm: &'a FsmDefinition) -> Self {
+        let index: HashMap<&str, usize> = fsm.states.iter().enumerate().map(|(i, s)| (s.name.as_str(), i)).collect();
+        let mut edges = vec![Vec::new(); fsm.states.len()];
+
+        let node = |target: &str| match target {
+            "[*]" => Some(Node::Final),
+            name => index.get(name).map(|&i| Node::State(i)),
+        };
+
+        for transition in &fsm.transitions {
+            if transition.kind == TransitionKind::Internal {
+                continue;
+            }
+            // A wildcard leaves every state
+            let sources: Vec<usize> = match index.get(transition.source.as_str()) {
+                Some(&source) => vec![source],
+                None if transition.is_from_any_state() => (0..fsm.states.len()).collect(),
+                None => continue,
+            };
+            let targets: Vec<&str> = match transition
+                .target
+                .strip_prefix("<<")
+                .and_then(|t| t.strip_suffix(">>"))
+            {
+                Some(choice) => fsm
+                    .choice_points
+                    .iter()
+                    .filter(|c| c.name == choice)
+                    .flat_map(|c| c.branches.iter().map(|b| b.target.as_str()))
+                    .collect(),
+                None => vec![transition.target.as_str()],
+            };
+            for target in targets.into_iter().filter_map(node) {
+                for &source in &sources {
+                    if !edges[source].contains(&target) {
+                        edges[source].push(target);
+                    }
+                }
+            }
+        }
+
+        Self { index, edges }
+    }
+
+    fn reachable_from_initial(&self, fsm: &FsmDefinition) -> Vec<bool> {
+        let mut stack: Vec<usize> = fsm.initial_transitions.iter().filter_map(|t| self.index.get(t.target.as_str()).copied()).collect();
+        if stack.is_empty() {
+            // Nothing to measure reachability against
+            return vec![true; self.edges.len()];
+        }
+        let mut reachable = vec![false; self.edges.len()];
+        while let Some(state) = stack.pop() {
+            if std::mem::replace(&mut reachable[state], true) {
+                continue;
+            }
+            for node in &self.edges[state] {
+                if let Node::State(next) = *node {
+                    stack.push(next);
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Tarjan's algorithm
+    fn strongly_connected_components(&self) -> Vec<Vec<usize>> {
+        struct Tarjan<'g> {
+            edges: &'g [Vec<Node>],
+            next_index: usize,
+            index: Vec<Option<usize>>,
+            low_link: Vec<usize>,
+            stack: Vec<usize>,
+            on_stack: Vec<bool>,
+            components: Vec<Vec<usize>>,
+        }
+
+        impl Tarjan<'_> {
+            fn visit(&mut self, v: usize) {
+                self.index[v] = Some(self.next_index);
+                self.low_link[v] = self.next_index;
+                self.next_index += 1;
+                self.stack.push(v);
+                self.on_stack[v] = true;
+
+                for node in self.edges[v].clone() {
+                    let Node::State(w) = node else {
+                        continue;
+                    };
+                    match self.index[w] {
+                        None => {
+                            self.visit(w);
+                            self.low_link[v] = self.low_link[v].min(self.low_link[w]);
+                        }
+                        Some(index) if self.on_stack[w] => {
+                            self.low_link[v] = self.low_link[v].min(index);
+                        }
+                        Some(_) => {}
+                    }
+                }
+
+                if Some(self.low_link[v]) == self.index[v] {
+                    let mut component = Vec::new();
+                    while let Some(w) = self.stack.pop() {
+                        self.on_stack[w] = false;
+                        component.push(w);
+                        if w == v {
+                            break;
+                        }
+                    }
+                    self.components.push(component);
+                }
+            }
+        }
+
+        let n = self.edges.len();
+        let mut tarjan = Tarjan {
+            edges: &self.edges,
+            next_index: 0,
+            index: vec![None; n],
+            low_link: vec![0; n],
+            stack: Vec::new(),
+            on_stack: vec![false; n],
+            components: Vec::new(),
+        };
+        for v in 0..n {
+            if tarjan.index[v].is_none() {
+                tarjan.visit(v);
+            }
+        }
+        tarjan.components
+    }
+}
@@ -0,0 +1,194 @@
+//! State Space Exploration
+//! Bounded, exhaustive run of an FSM over every event in every reachable state, with
+//! each guard treated as a free boolean: both answers are explored. Within one step a
+//! guard expression gets one answer, so a transition shadowed by an earlier one with
+//! the same (or no) guard still shows up as dead. Guards can in fact only ever take
+//! some of the answers explored, so this over-approximates what the machine can do:
+//! anything it reports as never happening cannot happen (within the depth explored),
+//! while what it reaches may still be impossible in practice. The DSL has no
+//! extended-state variables, so there are no value domains to track.
+
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+use super::interpreter::{completion, dispatch, initial_starts, Firing, StepResult};
+use super::{trigger_label, FsmDefinition, Transition};
+
+/// Events handled in a row the GUI explores by default
+pub const DEFAULT_EXPLORE_DEPTH: usize = 16;
+
+/// What [`explore`] never saw happen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exploration {
+    /// Events handled in a row at most
+    pub max_depth: usize,
+    /// Whether some path was cut off at `max_depth` (the results then only cover shorter paths)
+    pub truncated: bool,
+    /// Indices into `FsmDefinition::transitions`, in declaration order
+    pub unfired_transitions: Vec<usize>,
+    /// State name and index into its `internal_transitions`
+    pub unfired_internal_transitions: Vec<(String, usize)>,
+    /// States never entered, in declaration order
+    pub unvisited_states: Vec<String>,
+    /// Names of actions that never run, in declaration order
+    pub unexecuted_actions: Vec<String>,
+}
+
+impl Exploration {
+    pub fn is_clean(&self) -> bool {
+        self.unfired_transitions.is_empty()
+            && self.unfired_internal_transitions.is_empty()
+            && self.unvisited_states.is_empty()
+            && self.unexecuted_actions.is_empty()
+    }
+
+    /// One line per finding, for the diagnostics next to `warnings()`
+    pub fn messages(&self, fsm: &FsmDefinition) -> Vec<String> {
+        let line = |t: &Transition| t.line.map(|l| format!(" (line {l})")).unwrap_or_default();
+        let transitions = self.unfired_transitions.iter().filter_map(|&i| fsm.transitions.get(i)).map(|t| {
+            format!("Transition '{}'{} never fires, whatever its guards answer", trigger_label(t), line(t))
+        });
+        let internal = self.unfired_internal_transitions.iter().filter_map(|(state, i)| {
+            let t = fsm.states.iter().find(|s| s.name == *state)?.internal_transitions.get(*i)?;
+            let event = t.event.as_ref().map_or(String::new(), |e| e.pattern());
+            Some(format!("Internal transition '{event}' of '{state}'{} never fires, whatever its guards answer", line(t)))
+        });
+        let states = self.unvisited_states.iter().map(|s| format!("State '{s}' is never entered"));
+        let actions = self.unexecuted_actions.iter().map(|a| format!("Action '{a}' never runs"));
+        let mut messages: Vec<String> = transitions.chain(internal).chain(states).chain(actions).collect();
+        if self.truncated {
+            messages.push(format!("Paths longer than {} events were not explored", self.max_depth));
+        }
+        messages
+    }
+}
+
+/// Explore every path of at most `max_depth` events from the initial state (after the
+/// start actions and completion transitions), answering each guard both ways; with
+/// guarded initial transitions, from each state the machine can start in
+pub fn explore(fsm: &FsmDefinition, max_depth: usize) -> Exploration {
+    let events: Vec<String> = fsm.collect_events().into_iter().map(|e| e.name).collect();
+    let mut run = Run::default();
+
+    let starts = initial_starts(fsm);
+    if !starts.is_empty() {
+        // A configuration is a state and whether its completion transitions are still to
+        // be tried; a completion hop is part of the step that entered the state
+        let mut queue = VecDeque::new();
+        for (initial, actions) in starts {
+            run.actions.extend(actions.into_iter().map(|a| a.name.as_str()));
+            run.states.insert(initial.to_string());
+            queue.push_back(((initial.to_string(), true), 0));
+        }
+        let mut seen = HashSet::new();
+        while let Some(((state, entering), depth)) = queue.pop_front() {
+            if !seen.insert((state.clone(), entering)) {
+                continue;
+            }
+            if entering {
+                for hop in outcomes(|guard| completion(fsm, &state, guard)) {
+                    let next = match hop {
+                        Some(hop) => (run.record(&hop), hop.transition.enters_target()),
+                        None => (state.clone(), false),
+                    };
+                    queue.push_front((next, depth));
+                }
+                continue;
+            }
+            for event in &events {
+                let fired = outcomes(|guard| match dispatch(fsm, &state, event, guard) {
+                    StepResult::Fired(firing) => Some(firing),
+                    _ => None,
+                });
+                for firing in fired.into_iter().flatten() {
+                    let entering = !firing.is_internal() && firing.transition.enters_target();
+                    if depth == max_depth {
+                        run.truncated |= !seen.contains(&(firing.to.clone(), entering)) || run.is_new(&firing);
+                        continue;
+                    }
+                    queue.push_back(((run.record(&firing), entering), depth + 1));
+                }
+            }
+        }
+    }
+
+    run.finish(fsm, max_depth)
+}
+
+/// What the exploration has seen so far
+#[derive(Default)]
+struct Run<'a> {
+    fired: Vec<&'a Transition>,
+    states: BTreeSet<String>,
+    actions: BTreeSet<&'a str>,
+    truncated: bool,
+}
+
+impl<'a> Run<'a> {
+    /// Note what `firing` did; returns the state it ends in
+    fn record(&mut self, firing: &Firing<'a>) -> String {
+        if !self.fired.iter().any(|t| std::ptr::eq(*t, firing.transition)) {
+            self.fired.push(firing.transition);
+        }
+        self.actions.extend(firing.actions.iter().map(|a| a.name.as_str()));
+        self.states.insert(firing.to.clone());
+        firing.to.clone()
+    }
+
+    /// Whether `firing` would add a transition or action not seen yet
+    fn is_new(&self, firing: &Firing<'a>) -> bool {
+        !self.fired.iter().any(|t| std::ptr::eq(*t, firing.transition)) || firing.actions.iter().any(|a| !self.actions.contains(a.name.as_str()))
+    }
+
+    fn finish(self, fsm: &FsmDefinition, max_depth: usize) -> Exploration {
+        let fired = |t: &Transition| self.fired.iter().any(|f| std::ptr::eq(*f, t));
+        let unfired_internal_transitions = fsm
+            .states
+            .iter()
+            .flat_map(|s| s.internal_transitions.iter().enumerate().map(move |(i, t)| (s, i, t)))
+            .filter(|(_, _, t)| !fired(t))
+            .map(|(s, i, _)| (s.name.clone(), i))
+            .collect();
+        // Entering a substate counts for the composite states around it
+        let visited = |name: &str| self.states.contains(name) || fsm.states.iter().any(|s| s.name == name && self.states.iter().any(|v| s.contains_state(v)));
+
+        Exploration {
+            max_depth,
+            truncated: self.truncated,
+            unfired_transitions: (0..fsm.transitions.len()).filter(|&i| !fired(&fsm.transitions[i])).collect(),
+            unfired_internal_transitions,
+            unvisited_states: fsm.states.iter().filter(|s| !visited(&s.name)).map(|s| s.name.clone()).collect(),
+            unexecuted_actions: fsm.action_names().into_iter().filter(|a| !self.actions.contains(a)).map(str::to_string).collect(),
+        }
+    }
+}
+
+/// Every result of `step` over all answers to the guards it asks, one guard
+/// expression answered the same way throughout a run. Guards asked for the first
+/// time fail; each is then tried passing in a run of its own.
+fn outcomes<'a>(step: impl Fn(&dyn Fn(&str) -> bool) -> Option<Firing<'a>>) -> Vec<Option<Firing<'a>>> {
+    let mut results = Vec::new();
+    let mut pending: Vec<Vec<(String, bool)>> = vec![Vec::new()];
+    while let Some(fixed) = pending.pop() {
+        let asked: RefCell<Vec<String>> = RefCell::default();
+        let guard = |expression: &str| {
+            if let Some(&(_, passes)) = fixed.iter().find(|(e, _)| e == expression) {
+                return passes;
+            }
+            let mut asked = asked.borrow_mut();
+            if !asked.iter().any(|e| e == expression) {
+                asked.push(expression.to_string());
+            }
+            false
+        };
+        results.push(step(&guard));
+        let asked = asked.into_inner();
+        for (i, expression) in asked.iter().enumerate() {
+            let mut branch = fixed.clone();
+            branch.extend(asked[..i].iter().map(|e| (e.clone(), false)));
+            branch.push((expression.clone(), true));
+            pending.push(branch);
+        }
+    }
+    results
+}
@@ -0,0 +1,373 @@
+//! Guard Expressions
+//! The little boolean language of guards: terms combined with `!`, `&&`, `||` and
+//! parentheses, where a term is a guard method (`is_ready`, `speed_ok(rpm)`, or a
+//! comparison such as `attempts > 3`, asked as `attempts_gt_3`) or the name of a
+//! named guard (`guard lockout = attempts > 3 && !maintenance_mode`).
+
+use std::fmt;
+
+use super::{FsmDefinition, Guard, NamedGuard};
+
+/// A parsed guard expression whose terms are `T` (the term text after parsing)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuardExpr<T = String> {
+    Term(T),
+    Not(Box<GuardExpr<T>>),
+    /// Every operand holds (`&&`)
+    All(Vec<GuardExpr<T>>),
+    /// Some operand holds (`||`)
+    Any(Vec<GuardExpr<T>>),
+}
+
+impl GuardExpr {
+    /// Parse `expression`; text that does not parse (unbalanced parentheses, a
+    /// dangling operator) is one term, as every guard was before composition
+    pub fn parse(expression: &str) -> Self {
+        let mut parser = Parser { rest: expression.trim() };
+        match parser.any() {
+            Some(expr) if parser.rest.is_empty() => expr,
+            _ => GuardExpr::Term(expression.trim().to_string()),
+        }
+    }
+}
+
+impl<T> GuardExpr<T> {
+    /// Terms in the order written
+    pub fn terms(&self) -> Vec<&T> {
+        let mut terms = Vec::new();
+        self.collect_terms(&mut terms);
+        terms
+    }
+
+    fn collect_terms<'a>(&'a self, terms: &mut Vec<&'a T>) {
+        match self {
+            GuardExpr::Term(term) => terms.push(term),
+            GuardExpr::Not(operand) => operand.collect_terms(terms),
+            GuardExpr::All(operands) | GuardExpr::Any(operands) => {
+                operands.iter().for_each(|operand| operand.collect_terms(terms));
+            }
+        }
+    }
+
+    /// The same expression with every term replaced by `f(term)`
+    pub fn map<U>(&self, f: &mut dyn FnMut(&T) -> U) -> GuardExpr<U> {
+        match self {
+            GuardExpr::Term(term) => GuardExpr::Term(f(term)),
+            GuardExpr::Not(operand) => GuardExpr::Not(Box::new(operand.map(f))),
+            GuardExpr::All(operands) => GuardExpr::All(operands.iter().map(|o| o.map(f)).collect()),
+            GuardExpr::Any(operands) => GuardExpr::Any(operands.iter().map(|o| o.map(f)).collect()),
+        }
+    }
+
+    /// Value of the expression, asking `term` for the terms it needs (left to right,
+    /// short-circuiting like the generated code)
+    pub fn evaluate(&self, term: &mut dyn FnMut(&T) -> bool) -> bool {
+        match self {
+            GuardExpr::Term(t) => term(t),
+            GuardExpr::Not(operand) => !operand.evaluate(term),
+            GuardExpr::All(operands) => operands.iter().all(|o| o.evaluate(term)),
+            GuardExpr::Any(operands) => operands.iter().any(|o| o.evaluate(term)),
+        }
+    }
+
+    /// Source text (Rust, C or DSL) with each term written by `term`, parenthesized
+    /// where the precedence needs it
+    pub fn render(&self, term: &mut dyn FnMut(&T) -> String) -> String {
+        let operand = |expr: &GuardExpr<T>, term: &mut dyn FnMut(&T) -> String, parent: u8| {
+            let text = expr.render(term);
+            if expr.precedence() < parent {
+                format!("({})", text)
+            } else {
+                text
+            }
+        };
+        match self {
+            GuardExpr::Term(t) => term(t),
+            GuardExpr::Not(inner) => format!("!{}", operand(inner, term, 3)),
+            GuardExpr::All(operands) => {
+                operands.iter().map(|o| operand(o, term, 2)).collect::<Vec<_>>().join(" && ")
+            }
+            GuardExpr::Any(operands) => {
+                operands.iter().map(|o| operand(o, term, 1)).collect::<Vec<_>>().join(" || ")
+            }
+        }
+    }
+
+    /// Whether the expression is a single term
+    pub fn is_term(&self) -> bool {
+        matches!(self, GuardExpr::Term(_))
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            GuardExpr::Any(_) => 1,
+            GuardExpr::All(_) => 2,
+            GuardExpr::Term(_) | GuardExpr::Not(_) => 3,
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for GuardExpr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(&mut |term| term.to_string()))
+    }
+}
+
+/// Recursive descent over `||`, then `&&`, then `!` and parentheses
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+impl Parser<'_> {
+    fn any(&mut self) -> Option<GuardExpr> {
+        let mut operands = vec![self.all()?];
+        while self.eat("||") {
+            operands.push(self.all()?);
+        }
+        Some(if operands.len() == 1 { operands.remove(0) } else { GuardExpr::Any(operands) })
+    }
+
+    fn all(&mut self) -> Option<GuardExpr> {
+        let mut operands = vec![self.unary()?];
+        while self.eat("&&") {
+            operands.push(self.unary()?);
+        }
+        Some(if operands.len() == 1 { operands.remove(0) } else { GuardExpr::All(operands) })
+    }
+
+    fn unary(&mut self) -> Option<GuardExpr> {
+        // `!=` belongs to a term such as `!= 0`, which then fails to parse as a whole
+        if self.rest.starts_with('!') && !self.rest.starts_with("!=") {
+            self.eat("!");
+            return Some(GuardExpr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let inner = self.any()?;
+            return self.eat(")").then_some(inner);
+        }
+        self.term()
+    }
+
+    /// Text up to the next `&&`, `||` or unmatched `)`; parentheses inside it (the
+    /// arguments of `speed_ok(rpm)`) stay with the term
+    fn term(&mut self) -> Option<GuardExpr> {
+        let mut depth = 0usize;
+        let mut end = self.rest.len();
+        for (i, c) in self.rest.char_indices() {
+            let tail = &self.rest[i..];
+            match c {
+                '(' => depth += 1,
+                ')' if depth == 0 => {
+                    end = i;
+                    break;
+                }
+                ')' => depth -= 1,
+                _ if depth == 0 && (tail.starts_with("&&") || tail.starts_with("||")) => {
+                    end = i;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let term = self.rest[..end].trim();
+        if term.is_empty() || depth > 0 {
+            return None;
+        }
+        self.rest = self.rest[end..].trim_start();
+        Some(GuardExpr::Term(term.to_string()))
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest.trim_start();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// A term comparing a name and a value (`attempts > 3`, `rpm < MAX`), which the
+/// generated code asks a guard method about, passing the operands that are bindings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Comparison<'a> {
+    pub left: &'a str,
+    /// `<`, `<=`, `>`, `>=`, `==` or `!=`
+    pub operator: &'a str,
+    pub right: &'a str,
+}
+
+impl<'a> Comparison<'a> {
+    const OPERATORS: [(&'static str, &'static str); 6] = [("<=", "le"), (">=", "ge"), ("==", "eq"), ("!=", "ne"), ("<", "lt"), (">", "gt")];
+
+    /// `left <operator> right`, where both operands are names or numbers
+    fn parse(term: &'a str) -> Option<Self> {
+        let (at, operator) = term.char_indices().find_map(|(i, _)| {
+            Self::OPERATORS.iter().map(|(op, _)| *op).find(|op| term[i..].starts_with(op)).map(|op| (i, op))
+        })?;
+        let (left, right) = (term[..at].trim(), term[at + operator.len()..].trim());
+        (is_operand(left) && is_operand(right) && (is_name(left) || is_name(right))).then_some(Self { left, operator, right })
+    }
+
+    /// Name of the guard method: the operands joined by the operator's name
+    /// (`attempts_gt_3`, `rpm_lt_MAX`)
+    pub fn method(&self) -> String {
+        let word = Self::OPERATORS.iter().find(|(op, _)| *op == self.operator).map_or("", |(_, word)| word);
+        let operand = |o: &str| o.strip_prefix('-').map_or_else(|| o.replace('.', "_"), |n| format!("minus_{}", n.replace('.', "_")));
+        format!("{}_{}_{}", operand(self.left), word, operand(self.right))
+    }
+
+    /// The operands that are names, which a binding of the transition is passed for
+    pub fn names(&self) -> Vec<&'a str> {
+        [self.left, self.right].into_iter().filter(|o| is_name(o)).collect()
+    }
+}
+
+fn is_name(operand: &str) -> bool {
+    operand.chars().next().is_some_and(|c| unicode_ident::is_xid_start(c) || c == '_') && operand.chars().all(unicode_ident::is_xid_continue)
+}
+
+/// A name, or a decimal number (`3`, `-1`, `0.5`)
+fn is_operand(operand: &str) -> bool {
+    let number = operand.strip_prefix('-').unwrap_or(operand);
+    is_name(operand) || (number.starts_with(|c: char| c.is_ascii_digit()) && number.chars().all(|c| c.is_ascii_digit() || c == '.' || c == '_'))
+}
+
+impl Guard {
+    /// The comparison this term is, if it is one (see [`Comparison`])
+    pub fn comparison(&self) -> Option<Comparison<'_>> {
+        Comparison::parse(self.expression.trim())
+    }
+
+    /// Whether the term can become a guard method: a name, a call or a comparison
+    /// (not `a + b > 3`)
+    pub fn has_method(&self) -> bool {
+        is_name(&self.method())
+    }
+
+    /// What the guard method is passed the bindings among: a call's arguments, or a
+    /// comparison's names
+    pub fn inputs(&self) -> Vec<&str> {
+        match self.comparison() {
+            Some(comparison) => comparison.names(),
+            None => self.args(),
+        }
+    }
+
+    /// The guard parsed into its terms and operators
+    pub fn parsed(&self) -> GuardExpr {
+        GuardExpr::parse(&self.expression)
+    }
+
+    /// The terms of the expression, each as a guard of its own (a named guard's name
+    /// included)
+    pub fn terms(&self) -> Vec<Guard> {
+        self.parsed().terms().into_iter().map(|term| Guard::new(term.as_str())).collect()
+    }
+}
+
+impl FsmDefinition {
+    /// The named guard called `name`
+    pub fn named_guard(&self, name: &str) -> Option<&NamedGuard> {
+        self.guards.iter().find(|g| g.name == name)
+    }
+
+    /// `expression` with every named guard replaced by its definition, recursively
+    /// (a named guard met again inside its own definition is left as a term)
+    pub fn resolve_guard(&self, expression: &str) -> GuardExpr {
+        self.resolve(&GuardExpr::parse(expression), &mut Vec::new())
+    }
+
+    fn resolve<'a>(&'a self, expr: &GuardExpr, resolving: &mut Vec<&'a str>) -> GuardExpr {
+        match expr {
+            GuardExpr::Term(term) => match self.named_guard(term).filter(|g| !resolving.contains(&g.name.as_str())) {
+                Some(named) => {
+                    resolving.push(&named.name);
+                    let resolved = self.resolve(&GuardExpr::parse(&named.expression), resolving);
+                    resolving.pop();
+                    resolved
+                }
+                None => expr.clone(),
+            },
+            GuardExpr::Not(operand) => GuardExpr::Not(Box::new(self.resolve(operand, resolving))),
+            GuardExpr::All(operands) => GuardExpr::All(operands.iter().map(|o| self.resolve(o, resolving)).collect()),
+            GuardExpr::Any(operands) => GuardExpr::Any(operands.iter().map(|o| self.resolve(o, resolving)).collect()),
+        }
+    }
+
+    /// Value of `expression`, with named guards resolved and `term` asked for the
+    /// rest; a plain term is passed to `term` as written
+    pub fn evaluate_guard(&self, expression: &str, term: &dyn Fn(&str) -> bool) -> bool {
+        let expr = GuardExpr::parse(expression);
+        if expr.is_term() && self.named_guard(expression.trim()).is_none() {
+            return term(expression);
+        }
+        self.resolve(&expr, &mut Vec::new()).evaluate(&mut |t| term(t))
+    }
+
+    /// Named guards whose definitions reference each other in a loop, each loop once
+    /// as the names along it (`a → b → a`)
+    pub fn guard_cycles(&self) -> Vec<Vec<String>> {
+        let mut cycles: Vec<Vec<String>> = Vec::new();
+        for start in &self.guards {
+            let mut path = vec![start.name.as_str()];
+            self.find_cycles(&mut path, &mut cycles);
+        }
+        cycles
+    }
+
+    fn find_cycles<'a>(&'a self, path: &mut Vec<&'a str>, cycles: &mut Vec<Vec<String>>) {
+        let Some(current) = path.last().and_then(|name| self.named_guard(name)) else {
+            return;
+        };
+        for term in GuardExpr::parse(&current.expression).terms() {
+            let Some(next) = self.named_guard(term) else {
+                continue;
+            };
+            if next.name == path[0] {
+                // Reported once, from the definition that comes first
+                let first = self.guards.iter().position(|g| path.contains(&g.name.as_str()));
+                if first.is_some_and(|i| self.guards[i].name == path[0]) {
+                    let mut cycle: Vec<String> = path.iter().map(|n| n.to_string()).collect();
+                    cycle.push(next.name.clone());
+                    if !cycles.contains(&cycle) {
+                        cycles.push(cycle);
+                    }
+                }
+            } else if !path.contains(&next.name.as_str()) {
+                path.push(&next.name);
+                self.find_cycles(path, cycles);
+                path.pop();
+            }
+        }
+    }
+
+    /// Named guards no initial transition, transition, choice branch or used named guard
+    /// refers to
+    pub fn unused_guards(&self) -> Vec<&NamedGuard> {
+        let mut used: Vec<&str> = Vec::new();
+        let mut pending: Vec<String> = self.guard_uses().iter().map(|g| g.expression.clone()).collect();
+        while let Some(expression) = pending.pop() {
+            for term in GuardExpr::parse(&expression).terms() {
+                if let Some(named) = self.named_guard(term).filter(|g| !used.contains(&g.name.as_str())) {
+                    used.push(&named.name);
+                    pending.push(named.expression.clone());
+                }
+            }
+        }
+        self.guards.iter().filter(|g| !used.contains(&g.name.as_str())).collect()
+    }
+
+    /// Guards of initial transitions, transitions, internal transitions and choice
+    /// branches (`[else]` aside)
+    pub fn guard_uses(&self) -> Vec<&Guard> {
+        let transitions = self.transitions.iter().chain(self.states.iter().flat_map(|s| &s.internal_transitions));
+        let initial = self.initial_transitions.iter().filter_map(|t| t.guard.as_ref());
+        initial
+            .chain(transitions.filter_map(|t| t.guard.as_ref()))
+            .chain(self.choice_points.iter().flat_map(|c| c.branches.iter().map(|b| &b.guard)))
+            .filter(|g| !g.is_else())
+            .collect()
+    }
+}
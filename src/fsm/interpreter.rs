@@ -0,0 +1,618 @@
+//! FSM Interpreter
+//! Runs an FSM definition directly (scripting, property tests, the GUI simulator)
+//! with the semantics of the generated code: exit actions, transition actions,
+//! then entry actions (or transition actions first, see [`ActionOrder`]). Guards
+//! are answered by a caller-provided callback.
+//! Completion (event-less) transitions are taken right after a state is entered,
+//! hop after hop, up to a hop limit. Nothing leaves a final state.
+//! `timer` definitions run on simulated time: `start_timer`/`stop_timer` actions
+//! start and stop them, leaving a state stops them, and `advance_time` fires them.
+//! The same simulated time is the clock of `@debounce` / `@throttle` transitions.
+
+use std::collections::{HashMap, VecDeque};
+
+use super::{Action, FsmDefinition, Guard, InitialTransition, TimerMode, TimerOp, TimingConstraint, TimingKind, Transition};
+
+/// Completion transitions taken in a row before the machine stops hopping (the
+/// generated code's default too), so a loop of them cannot hang a step
+pub const DEFAULT_MAX_COMPLETION_HOPS: usize = 16;
+
+/// When a transition's own actions run relative to the exit actions of the state it
+/// leaves; the entry actions of the state it enters come last either way
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ActionOrder {
+    /// Exit actions, transition actions, entry actions (UML)
+    #[default]
+    UmlStandard,
+    /// Transition actions, exit actions, entry actions (as some C frameworks do)
+    ActionFirst,
+}
+
+impl ActionOrder {
+    pub const ALL: [ActionOrder; 2] = [ActionOrder::UmlStandard, ActionOrder::ActionFirst];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ActionOrder::UmlStandard => "UML: exit → action → entry",
+            ActionOrder::ActionFirst => "Action first: action → exit → entry",
+        }
+    }
+}
+
+/// Executes an FSM definition event by event
+pub struct Interpreter<'a, G> {
+    fsm: &'a FsmDefinition,
+    guard: G,
+    current_state: Option<String>,
+    queue: VecDeque<String>,
+    max_completion_hops: usize,
+    action_order: ActionOrder,
+    timers: Vec<RunningTimer>,
+    /// Simulated milliseconds since the interpreter was created
+    clock_ms: u64,
+    rate_limits: RateLimits,
+}
+
+/// A started timer of an [`Interpreter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunningTimer {
+    pub name: String,
+    pub event: String,
+    /// Simulated milliseconds until it fires
+    pub remaining_ms: u64,
+    /// Restart period of a periodic timer
+    pub period_ms: Option<u64>,
+}
+
+/// Outcome of one `Interpreter::step`
+#[derive(Debug, Clone)]
+pub enum StepResult<'a> {
+    /// No event was queued
+    Idle,
+    /// No transition was enabled for the event; it was dropped
+    Ignored {
+        event: String,
+        /// Guards evaluated while looking for a transition, in evaluation order
+        guards: Vec<(&'a str, bool)>,
+    },
+    /// The transition the event selected has a timing constraint that held it back;
+    /// the event was dropped
+    Suppressed {
+        event: String,
+        transition: &'a Transition,
+        constraint: TimingConstraint,
+    },
+    Fired(Firing<'a>),
+}
+
+/// A transition that was taken
+#[derive(Debug, Clone)]
+pub struct Firing<'a> {
+    pub event: String,
+    pub from: String,
+    /// State after the transition (past any choice points)
+    pub to: String,
+    /// The transition taken (an external one or one of the state's internal transitions)
+    pub transition: &'a Transition,
+    /// Index into `FsmDefinition::transitions`; `None` for an internal transition
+    pub transition_index: Option<usize>,
+    /// `[else]` transition or choice branch taken
+    pub took_else: bool,
+    /// Choice points passed through, in order
+    pub choices: Vec<&'a str>,
+    /// Actions in execution order: source exit, transition, choice branches, target entry
+    /// (only the transition's own actions for an internal transition); see
+    /// [`Firing::with_action_order`] for the other order
+    pub actions: Vec<&'a Action>,
+    /// How many of `actions` are the source's exit actions (first, in UML order)
+    pub exit_count: usize,
+    /// How many of `actions` are the transition's and its choice branches' own (right
+    /// after the exit actions, in UML order)
+    pub own_count: usize,
+    /// Guards evaluated while selecting the transition, in evaluation order
+    pub guards: Vec<(&'a str, bool)>,
+    /// Completion transitions taken after entering `to`, in order (filled in by
+    /// `Interpreter::step`; see [`completions`])
+    pub completions: Vec<Firing<'a>>,
+}
+
+impl Firing<'_> {
+    pub fn is_internal(&self) -> bool {
+        self.transition_index.is_none()
+    }
+
+    /// State the machine settles in: `to`, or where its completion transitions led
+    pub fn settled_state(&self) -> &str {
+        self.completions.last().map_or(&self.to, |c| &c.to)
+    }
+
+    /// The same firing (and completion transitions) with `actions` in `order`;
+    /// firings come in [`ActionOrder::UmlStandard`] order
+    pub fn with_action_order(mut self, order: ActionOrder) -> Self {
+        if order == ActionOrder::ActionFirst {
+            self.actions[..self.exit_count + self.own_count].rotate_left(self.exit_count);
+            (self.exit_count, self.own_count) = (0, self.exit_count + self.own_count);
+        }
+        self.completions = self.completions.into_iter().map(|c| c.with_action_order(order)).collect();
+        self
+    }
+}
+
+impl<'a, G: Fn(&str) -> bool> Interpreter<'a, G> {
+    /// Interpreter in the initial state; `guard` is called with each guard term (a
+    /// named guard resolved to its terms)
+    pub fn new(fsm: &'a FsmDefinition, guard: G) -> Self {
+        let mut interpreter = Self {
+            fsm,
+            guard,
+            current_state: None,
+            queue: VecDeque::new(),
+            max_completion_hops: DEFAULT_MAX_COMPLETION_HOPS,
+            action_order: ActionOrder::default(),
+            timers: Vec::new(),
+            clock_ms: 0,
+            rate_limits: RateLimits::default(),
+        };
+        interpreter.reset();
+        interpreter
+    }
+
+    /// Completion transitions taken in a row at most (see [`DEFAULT_MAX_COMPLETION_HOPS`]);
+    /// takes effect from the next step
+    pub fn with_max_completion_hops(mut self, hops: usize) -> Self {
+        self.max_completion_hops = hops;
+        self
+    }
+
+    /// Order of exit and transition actions in the firings from the next step (and
+    /// in what `reset` returns)
+    pub fn with_action_order(mut self, order: ActionOrder) -> Self {
+        self.action_order = order;
+        self
+    }
+
+    /// Back to the initial state (the one the guards pick among guarded initial
+    /// transitions, the first state if none is marked) with an empty
+    /// queue, no timer running and no timing constraint holding a transition back
+    /// (the clock keeps its time); returns the actions a start runs, see
+    /// [`start_actions`], followed by those of the completion transitions taken from
+    /// the initial state
+    pub fn reset(&mut self) -> Vec<&'a Action> {
+        self.queue.clear();
+        self.timers.clear();
+        self.rate_limits.clear();
+        self.current_state = initial_state(self.fsm, &self.guard).map(str::to_string);
+        let mut actions = start_actions(self.fsm, &self.guard);
+        if let Some(initial) = self.current_state.take() {
+            self.update_timers(&actions, Some(&initial));
+            let hops = completions(self.fsm, &initial, &self.guard, self.max_completion_hops);
+            let hops: Vec<Firing<'a>> = hops.into_iter().map(|c| c.with_action_order(self.action_order)).collect();
+            for hop in &hops {
+                self.fire_timers_of(hop);
+            }
+            actions.extend(hops.iter().flat_map(|c| c.actions.iter().copied()));
+            self.current_state = Some(hops.last().map_or(initial, |c| c.to.clone()));
+        }
+        actions
+    }
+
+    pub fn current_state(&self) -> Option<&str> {
+        self.current_state.as_deref()
+    }
+
+    /// Events posted but not yet stepped, oldest first
+    pub fn pending(&self) -> impl Iterator<Item = &str> {
+        self.queue.iter().map(String::as_str)
+    }
+
+    pub fn post(&mut self, event_name: impl Into<String>) {
+        self.queue.push_back(event_name.into());
+    }
+
+    /// Timers running now, in the order they were started
+    pub fn timers(&self) -> &[RunningTimer] {
+        &self.timers
+    }
+
+    /// Simulated milliseconds so far, the time `@debounce` / `@throttle` go by
+    pub fn now_ms(&self) -> u64 {
+        self.clock_ms
+    }
+
+    /// Handle the oldest queued event
+    pub fn step(&mut self) -> StepResult<'a> {
+        match self.queue.pop_front() {
+            Some(event) => self.handle(event),
+            None => StepResult::Idle,
+        }
+    }
+
+    /// Let `ms` milliseconds of simulated time pass: each timer that expires has its
+    /// event handled right away, in the order they expire (the first defined on a tie,
+    /// as in the generated code; a periodic one once per period), so a timer its own event stops does not fire again. Queued events
+    /// stay queued, so they are handled at the later time. Returns the results of the
+    /// timer events
+    pub fn advance_time(&mut self, ms: u64) -> Vec<StepResult<'a>> {
+        let mut results = Vec::new();
+        let mut left = ms;
+        // The first defined of the timers due soonest
+        let defined = |timer: &RunningTimer| self.fsm.timers.iter().position(|t| t.name == timer.name);
+        while let Some(index) = (0..self.timers.len())
+            .min_by_key(|&i| (self.timers[i].remaining_ms, defined(&self.timers[i])))
+            .filter(|&i| self.timers[i].remaining_ms <= left)
+        {
+            let due = self.timers[index].remaining_ms;
+            left -= due;
+            self.clock_ms += due;
+            for timer in &mut self.timers {
+                timer.remaining_ms -= due;
+            }
+            let event = self.timers[index].event.clone();
+            match self.timers[index].period_ms {
+                Some(period) => self.timers[index].remaining_ms = period,
+                None => {
+                    self.timers.remove(index);
+                }
+            }
+            results.push(self.handle(event));
+        }
+        for timer in &mut self.timers {
+            timer.remaining_ms -= left;
+        }
+        self.clock_ms += left;
+        results
+    }
+
+    fn handle(&mut self, event: String) -> StepResult<'a> {
+        let Some(current) = self.current_state.as_deref() else {
+            return StepResult::Ignored { event, guards: Vec::new() };
+        };
+        match dispatch(self.fsm, current, &event, &self.guard) {
+            StepResult::Fired(mut firing) => {
+                if let Some(constraint) = self.rate_limits.holds_back(self.fsm, firing.transition, self.clock_ms) {
+                    return StepResult::Suppressed { event, transition: firing.transition, constraint };
+                }
+                if !firing.is_internal() && firing.transition.enters_target() {
+                    firing.completions = completions(self.fsm, &firing.to, &self.guard, self.max_completion_hops);
+                }
+                self.current_state = Some(firing.settled_state().to_string());
+                let firing = firing.with_action_order(self.action_order);
+                for hop in std::iter::once(&firing).chain(&firing.completions) {
+                    self.fire_timers_of(hop);
+                }
+                StepResult::Fired(firing)
+            }
+            result => result,
+        }
+    }
+
+    /// Timer bookkeeping for a transition taken: leaving its source stops every timer,
+    /// then its timer actions and the timers its target auto-starts apply
+    fn fire_timers_of(&mut self, firing: &Firing<'a>) {
+        let external = !firing.is_internal();
+        if external && firing.transition.exits_source() {
+            self.timers.clear();
+        }
+        let entered = (external && firing.transition.enters_target()).then_some(firing.to.as_str());
+        self.update_timers(&firing.actions, entered);
+    }
+
+    /// Timer actions in order, then the timers that auto-start in the state
+    /// `entered`, if any
+    fn update_timers(&mut self, actions: &[&Action], entered: Option<&str>) {
+        for action in actions {
+            match action.timer_op() {
+                Some((TimerOp::Start, timer)) => self.start_timer(timer),
+                Some((TimerOp::Stop, timer)) => self.timers.retain(|t| t.name != timer),
+                None => {}
+            }
+        }
+        for timer in self.fsm.timers.iter().filter(|t| entered.is_some() && t.auto_start_state.as_deref() == entered) {
+            self.start_timer(&timer.name);
+        }
+    }
+
+    /// (Re)start `timer` with its full duration; unknown timers are ignored
+    fn start_timer(&mut self, timer: &str) {
+        let Some(definition) = self.fsm.timers.iter().find(|t| t.name == timer) else {
+            return;
+        };
+        // A zero period would expire forever at one instant
+        let duration = u64::from(definition.duration_ms.max(1));
+        self.timers.retain(|t| t.name != timer);
+        self.timers.push(RunningTimer {
+            name: definition.name.clone(),
+            event: definition.event.name.clone(),
+            remaining_ms: duration,
+            period_ms: (definition.mode == TimerMode::Periodic).then_some(duration),
+        });
+    }
+
+    /// Post `event_name` and handle everything queued; returns the last result
+    pub fn send(&mut self, event_name: impl Into<String>) -> StepResult<'a> {
+        self.post(event_name);
+        let mut last = StepResult::Idle;
+        while !self.queue.is_empty() {
+            last = self.step();
+        }
+        last
+    }
+}
+
+/// When each `@debounce` / `@throttle` transition last saw or took its event; the
+/// interpreter and the GUI simulator both decide with it whether such a transition
+/// may fire
+#[derive(Debug, Clone, Default)]
+pub struct RateLimits {
+    /// By [`timing_slot`]
+    last_ms: HashMap<usize, u64>,
+}
+
+impl RateLimits {
+    /// The constraint that holds `transition` of `fsm` back at `now_ms`, if any,
+    /// recording the attempt: a debounced transition needs its window without its
+    /// event (every attempt counts), a throttled one its window since it last fired
+    pub fn holds_back(&mut self, fsm: &FsmDefinition, transition: &Transition, now_ms: u64) -> Option<TimingConstraint> {
+        let constraint = transition.timing_constraint?;
+        let slot = timing_slot(fsm, transition)?;
+        let last = self.last_ms.get(&slot).copied();
+        let too_soon = last.is_some_and(|last| now_ms.saturating_sub(last) < u64::from(constraint.window_ms));
+        if !too_soon || constraint.kind == TimingKind::Debounce {
+            self.last_ms.insert(slot, now_ms);
+        }
+        too_soon.then_some(constraint)
+    }
+
+    /// Forget every attempt
+    pub fn clear(&mut self) {
+        self.last_ms.clear();
+    }
+}
+
+/// Position of `transition` (borrowed from `fsm`) among the FSM's transitions, then
+/// its states' internal transitions
+fn timing_slot(fsm: &FsmDefinition, transition: &Transition) -> Option<usize> {
+    let internal = fsm.states.iter().flat_map(|s| &s.internal_transitions);
+    fsm.transitions.iter().chain(internal).position(|t| std::ptr::eq(t, transition))
+}
+
+/// Initial transition taken at start: the first guarded one whose guard holds, else
+/// the unguarded default
+pub fn initial_transition<'a>(fsm: &'a FsmDefinition, guard: &dyn Fn(&str) -> bool) -> Option<&'a InitialTransition> {
+    let passes = |t: &&InitialTransition| t.guard.as_ref().map_or(true, |g| fsm.evaluate_guard(&g.expression, guard));
+    fsm.initial_candidates().into_iter().find(passes)
+}
+
+/// Target of the initial transition `guard` picks, or the first state when none is
+/// marked
+pub fn initial_state<'a>(fsm: &'a FsmDefinition, guard: &dyn Fn(&str) -> bool) -> Option<&'a str> {
+    start_of(fsm, initial_transition(fsm, guard)).0
+}
+
+/// Actions run when the machine starts, in order: the actions of the initial
+/// transition `guard` picks (`[*] --> Idle : / load_config`), then the initial
+/// state's entry actions
+pub fn start_actions<'a>(fsm: &'a FsmDefinition, guard: &dyn Fn(&str) -> bool) -> Vec<&'a Action> {
+    start_of(fsm, initial_transition(fsm, guard)).1
+}
+
+/// Every way the machine can start, whatever the guards answer: each initial
+/// transition's target with its start actions, or the first state when none is marked
+pub fn initial_starts(fsm: &FsmDefinition) -> Vec<(&str, Vec<&Action>)> {
+    let candidates = fsm.initial_candidates();
+    if candidates.is_empty() {
+        return start_of(fsm, None).0.map(|state| (state, start_of(fsm, None).1)).into_iter().collect();
+    }
+    candidates.into_iter().map(|t| (t.target.as_str(), start_of(fsm, Some(t)).1)).collect()
+}
+
+fn start_of<'a>(fsm: &'a FsmDefinition, initial: Option<&'a InitialTransition>) -> (Option<&'a str>, Vec<&'a Action>) {
+    let state = initial.map(|t| t.target.as_str()).or_else(|| fsm.states.first().map(|s| s.name.as_str()));
+    let entry = state
+        .and_then(|name| fsm.states.iter().find(|s| s.name == name))
+        .into_iter()
+        .flat_map(|s| &s.entry_actions);
+    let actions = initial.into_iter().flat_map(|t| &t.actions).chain(entry).collect();
+    (state, actions)
+}
+
+/// Whether `state` is a final state of `fsm`, where the machine has finished
+pub fn is_final(fsm: &FsmDefinition, state: &str) -> bool {
+    fsm.states.iter().any(|s| s.name == state && s.is_final())
+}
+
+/// Every guard term the FSM can ask about (from transitions, internal transitions
+/// and choice branches, with named guards resolved), sorted, without `else`
+pub fn guard_expressions(fsm: &FsmDefinition) -> Vec<String> {
+    let mut expressions: Vec<String> = fsm
+        .guard_uses()
+        .into_iter()
+        .flat_map(|g| fsm.resolve_guard(&g.expression).terms().into_iter().cloned().collect::<Vec<_>>())
+        .collect();
+    expressions.sort_unstable();
+    expressions.dedup();
+    expressions
+}
+
+/// What `event` does in `state`, without changing anything: the first enabled
+/// external transition from `state` (declaration order, `[else]` only if no guarded
+/// sibling passes), otherwise the first enabled internal transition of the state,
+/// otherwise the first enabled wildcard (`* -->`) transition. A final state ignores
+/// every event.
+pub fn dispatch<'a>(fsm: &'a FsmDefinition, state: &str, event: &str, guard: &dyn Fn(&str) -> bool) -> StepResult<'a> {
+    let guard = &|expression: &str| fsm.evaluate_guard(expression, guard);
+    let mut guards = Vec::new();
+    if is_final(fsm, state) {
+        return StepResult::Ignored { event: event.to_string(), guards };
+    }
+    let handles = |t: &Transition| t.event.as_ref().is_some_and(|e| e.name == event);
+
+    if let Some(firing) = external_firing(fsm, state, event, guard, &mut guards, |t| t.source == state && handles(t)) {
+        return StepResult::Fired(firing);
+    }
+
+    let internal: Vec<&'a Transition> = fsm
+        .states
+        .iter()
+        .filter(|s| s.name == state)
+        .flat_map(|s| s.internal_transitions.iter().filter(|t| handles(t)))
+        .collect();
+    let enabled = select(&internal, |&t| t.guard.as_ref(), guard, &mut guards, |_| Some(()));
+    if let Some((&transition, ())) = enabled {
+        return StepResult::Fired(Firing {
+            event: event.to_string(),
+            from: state.to_string(),
+            to: state.to_string(),
+            transition,
+            transition_index: None,
+            took_else: is_else(transition.guard.as_ref()),
+            choices: Vec::new(),
+            actions: transition.actions.iter().collect(),
+            exit_count: 0,
+            own_count: transition.actions.len(),
+            guards,
+            completions: Vec::new(),
+        });
+    }
+
+    let any_state = |t: &Transition| t.is_from_any_state() && handles(t);
+    if let Some(firing) = external_firing(fsm, state, event, guard, &mut guards, any_state) {
+        return StepResult::Fired(firing);
+    }
+
+    StepResult::Ignored {
+        event: event.to_string(),
+        guards,
+    }
+}
+
+/// The completion transition taken right after entering `state`, if one is enabled:
+/// the first of the state's event-less transitions whose guard passes (`[else]` last).
+/// Its `event` is empty.
+pub fn completion<'a>(fsm: &'a FsmDefinition, state: &str, guard: &dyn Fn(&str) -> bool) -> Option<Firing<'a>> {
+    if is_final(fsm, state) {
+        return None;
+    }
+    let guard = &|expression: &str| fsm.evaluate_guard(expression, guard);
+    let from_state = |t: &Transition| t.source == state && t.is_completion();
+    external_firing(fsm, state, "", guard, &mut Vec::new(), from_state)
+}
+
+/// Completion transitions taken one after the other once `state` is entered, at most
+/// `max_hops` of them (a loop of completion transitions stops there)
+pub fn completions<'a>(fsm: &'a FsmDefinition, state: &str, guard: &dyn Fn(&str) -> bool, max_hops: usize) -> Vec<Firing<'a>> {
+    let mut hops: Vec<Firing<'a>> = Vec::new();
+    while hops.len() < max_hops {
+        let from = hops.last().map_or(state, |hop| hop.to.as_str());
+        let Some(hop) = completion(fsm, from, guard) else {
+            break;
+        };
+        hops.push(hop);
+    }
+    hops
+}
+
+/// The first enabled transition among those `accepts` takes, taken from `state` on `event`
+fn external_firing<'a>(
+    fsm: &'a FsmDefinition,
+    state: &str,
+    event: &str,
+    guard: &dyn Fn(&str) -> bool,
+    guards: &mut Vec<(&'a str, bool)>,
+    accepts: impl Fn(&Transition) -> bool,
+) -> Option<Firing<'a>> {
+    let external: Vec<(usize, &'a Transition)> = fsm
+        .transitions
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| accepts(t))
+        .collect();
+    let (&(index, transition), path) = select(&external, |&(_, t)| t.guard.as_ref(), guard, guards, |&(_, t)| {
+        resolve_choices(fsm, &t.target, guard)
+    })?;
+    let source = fsm.states.iter().find(|s| s.name == state).filter(|_| transition.exits_source());
+    let target = fsm.states.iter().find(|s| s.name == path.target).filter(|_| transition.enters_target());
+    let mut actions: Vec<&'a Action> = source.map(|s| s.exit_actions.iter().collect()).unwrap_or_default();
+    let exit_count = actions.len();
+    actions.extend(&transition.actions);
+    actions.extend(path.actions.iter().copied());
+    let own_count = actions.len() - exit_count;
+    actions.extend(target.into_iter().flat_map(|s| &s.entry_actions));
+    guards.extend(path.guards);
+    Some(Firing {
+        event: event.to_string(),
+        from: state.to_string(),
+        to: path.target,
+        transition,
+        transition_index: Some(index),
+        took_else: is_else(transition.guard.as_ref()) || path.took_else,
+        choices: path.choices,
+        actions,
+        exit_count,
+        own_count,
+        guards: std::mem::take(guards),
+        completions: Vec::new(),
+    })
+}
+
+fn is_else(guard: Option<&Guard>) -> bool {
+    guard.is_some_and(|g| g.is_else())
+}
+
+/// First candidate whose guard passes and that `complete` accepts; `[else]`
+/// candidates are only tried after every other one
+fn select<'c, 'a, T, R>(
+    candidates: &'c [T],
+    guard_of: impl Fn(&'c T) -> Option<&'a Guard>,
+    guard: &dyn Fn(&str) -> bool,
+    evaluated: &mut Vec<(&'a str, bool)>,
+    complete: impl Fn(&'c T) -> Option<R>,
+) -> Option<(&'c T, R)> {
+    let (fallbacks, ordinary): (Vec<&'c T>, Vec<&'c T>) = candidates.iter().partition(|c| is_else(guard_of(c)));
+    for candidate in ordinary.into_iter().chain(fallbacks) {
+        if let Some(g) = guard_of(candidate).filter(|g| !g.is_else()) {
+            let passed = guard(&g.expression);
+            evaluated.push((g.expression.as_str(), passed));
+            if !passed {
+                continue;
+            }
+        }
+        if let Some(result) = complete(candidate) {
+            return Some((candidate, result));
+        }
+    }
+    None
+}
+
+/// Where a transition into `target` ends up after its choice points
+struct ChoicePath<'a> {
+    target: String,
+    choices: Vec<&'a str>,
+    actions: Vec<&'a Action>,
+    guards: Vec<(&'a str, bool)>,
+    took_else: bool,
+}
+
+/// Follow `<<Choice>>` targets to a state; `None` if a choice has no enabled branch
+/// (or choices loop), in which case the transition is not enabled
+fn resolve_choices<'a>(fsm: &'a FsmDefinition, target: &str, guard: &dyn Fn(&str) -> bool) -> Option<ChoicePath<'a>> {
+    let mut path = ChoicePath {
+        target: target.to_string(),
+        choices: Vec::new(),
+        actions: Vec::new(),
+        guards: Vec::new(),
+        took_else: false,
+    };
+    while let Some(name) = path.target.strip_prefix("<<").and_then(|t| t.strip_suffix(">>")) {
+        let choice = fsm.choice_points.iter().find(|c| c.name == name)?;
+        if path.choices.contains(&choice.name.as_str()) {
+            return None;
+        }
+        let (branch, ()) = select(&choice.branches, |b| Some(&b.guard), guard, &mut path.guards, |_| Some(()))?;
+        path.choices.push(&choice.name);
+        path.actions.extend(&branch.actions);
+        path.took_else |= branch.guard.is_else();
+        path.target = branch.target.clone();
+    }
+    Some(path)
+}
@@ -0,0 +1,167 @@
+//! Event Matrix
+//! Which states handle which events, for completeness reviews
+
+use serde::{Deserialize, Serialize};
+
+use super::{FsmDefinition, Transition};
+
+/// How a state reacts to an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventHandling {
+    /// An unguarded transition leaves the state
+    External,
+    /// An unguarded internal transition handles the event without leaving the state
+    Internal,
+    /// Only guarded transitions exist, so the event may be dropped at runtime
+    GuardedOnly,
+    /// The event is ignored in this state
+    Unhandled,
+}
+
+impl EventHandling {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventHandling::External => "external",
+            EventHandling::Internal => "internal",
+            EventHandling::GuardedOnly => "guarded_only",
+            EventHandling::Unhandled => "unhandled",
+        }
+    }
+}
+
+/// One (state, event) cell of the matrix
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventMatrixCell {
+    pub handling: EventHandling,
+    /// Indices into `FsmDefinition::transitions` of the external transitions handling the
+    /// event, the state's own before wildcards
+    pub transitions: Vec<usize>,
+}
+
+/// State × event table of how every event is handled
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventMatrix {
+    /// Row labels, in declaration order
+    pub states: Vec<String>,
+    /// Column labels, sorted
+    pub events: Vec<String>,
+    /// `cells[state][event]`
+    pub cells: Vec<Vec<EventMatrixCell>>,
+}
+
+impl EventMatrix {
+    /// Look up a cell by state and event name
+    pub fn get(&self, state: &str, event: &str) -> Option<&EventMatrixCell> {
+        let row = self.states.iter().position(|s| s == state)?;
+        let col = self.events.iter().position(|e| e == event)?;
+        Some(&self.cells[row][col])
+    }
+
+    /// (state, event) pairs that are not handled at all
+    pub fn unhandled(&self) -> Vec<(&str, &str)> {
+        let mut pairs = Vec::new();
+        for (state, row) in self.states.iter().zip(&self.cells) {
+            for (event, cell) in self.events.iter().zip(row) {
+                if cell.handling == EventHandling::Unhandled {
+                    pairs.push((state.as_str(), event.as_str()));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Render as CSV: a `state` column followed by one column per event
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("state");
+        for event in &self.events {
+            csv.push(',');
+            csv.push_str(&csv_field(event));
+        }
+        csv.push('\n');
+
+        for (state, row) in self.states.iter().zip(&self.cells) {
+            csv.push_str(&csv_field(state));
+            for cell in row {
+                csv.push(',');
+                csv.push_str(cell.handling.as_str());
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+/// Build the event matrix of an FSM.
+///
+/// Unguarded handlers win over guarded ones, and external transitions win over
+/// internal ones, which win over wildcard (`* -->`) transitions. The DSL has no
+/// event deferral, so there is no deferred category.
+pub fn event_matrix(fsm: &FsmDefinition) -> EventMatrix {
+    let events: Vec<String> = fsm.collect_events().into_iter().map(|e| e.name).collect();
+
+    let handles = |t: &Transition, event: &str| t.event.as_ref().is_some_and(|e| e.name == event);
+
+    let cells = fsm
+        .states
+        .iter()
+        .map(|state| {
+            events
+                .iter()
+                .map(|event| {
+                    let external = |from: &dyn Fn(&Transition) -> bool| -> Vec<usize> {
+                        fsm.transitions
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, t)| from(t) && handles(t, event))
+                            .map(|(i, _)| i)
+                            .collect()
+                    };
+                    let own = external(&|t| t.source == state.name);
+                    let wildcards = external(&Transition::is_from_any_state);
+                    let internal: Vec<&Transition> = state
+                        .internal_transitions
+                        .iter()
+                        .filter(|t| handles(t, event))
+                        .collect();
+
+                    let handling = if own.iter().any(|&i| unconditional(&fsm.transitions[i])) {
+                        EventHandling::External
+                    } else if internal.iter().any(|t| unconditional(t)) {
+                        EventHandling::Internal
+                    } else if wildcards.iter().any(|&i| unconditional(&fsm.transitions[i])) {
+                        EventHandling::External
+                    } else if !own.is_empty() || !internal.is_empty() || !wildcards.is_empty() {
+                        EventHandling::GuardedOnly
+                    } else {
+                        EventHandling::Unhandled
+                    };
+
+                    EventMatrixCell {
+                        handling,
+                        transitions: own.into_iter().chain(wildcards).collect(),
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    EventMatrix {
+        states: fsm.states.iter().map(|s| s.name.clone()).collect(),
+        events,
+        cells,
+    }
+}
+
+/// Unguarded, or an `[else]` that runs whenever its guarded siblings don't
+fn unconditional(transition: &Transition) -> bool {
+    transition.guard.as_ref().map_or(true, |g| g.is_else())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
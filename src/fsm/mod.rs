@@ -1,11 +1,33 @@
 //! FSM Data Structures
 //! Core types representing Finite State Machines
 
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
 use serde::{Deserialize, Serialize};
 
+pub mod color;
+pub mod cycles;
+pub mod explore;
+pub mod guards;
+pub mod interpreter;
+pub mod matrix;
+pub mod naming;
+
+pub use cycles::{completion_cycles, livelock_cycles};
+pub use explore::explore;
+pub use matrix::event_matrix;
+
 #[cfg(test)]
 mod tests;
 
+/// Source of a wildcard transition (`* --> Stopped : emergency_stop`), taken from
+/// any state that has no transition of its own for the event
+pub const ANY_STATE: &str = "*";
+
 /// A complete FSM definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FsmDefinition {
@@ -13,18 +35,68 @@ pub struct FsmDefinition {
     pub name: String,
     /// Optional description
     pub description: Option<String>,
-    /// Initial state name
-    pub initial_state: Option<String>,
+    /// Free-form `meta key = "value"` entries (version, author, target board, ...)
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+    /// Every `[*] -->` line, in source order (a composite state's sub-FSM has its
+    /// own). The unguarded one is the default; see [`FsmDefinition::initial_state`]
+    #[serde(default)]
+    pub initial_transitions: Vec<InitialTransition>,
     /// All states in the FSM
     pub states: Vec<State>,
     /// All transitions between states
     pub transitions: Vec<Transition>,
-    /// Events that this FSM responds to
+    /// Events declared in `events { ... }` blocks, in declaration order (transitions
+    /// may use others too; `collect_events` has them all)
     pub events: Vec<Event>,
+    /// Source line of the first `events` block
+    #[serde(default)]
+    pub events_line: Option<usize>,
     /// Choice/Decision points
     pub choice_points: Vec<ChoicePoint>,
     /// Software timers
     pub timers: Vec<Timer>,
+    /// `guard name = expression` definitions, in declaration order
+    #[serde(default)]
+    pub guards: Vec<NamedGuard>,
+    /// Notes attached to states (Mermaid `note left of X`)
+    #[serde(default)]
+    pub notes: Vec<Note>,
+    /// Preferred diagram direction (Mermaid `direction LR`)
+    #[serde(default)]
+    pub layout_hint: Option<LayoutHint>,
+    /// `@file_name("io_mgr")`: stem of the generated and exported files, instead of
+    /// the snake_case FSM name
+    #[serde(default)]
+    pub file_name: Option<String>,
+    /// File this FSM was loaded from (set by `parse_fsm_file`)
+    #[serde(default)]
+    pub source_file: Option<String>,
+    /// Source line of the `fsm` header
+    #[serde(default)]
+    pub line: Option<usize>,
+    /// `// ...` lines above the `fsm` header
+    #[serde(default)]
+    pub leading_comments: Vec<String>,
+    /// Trailing `// ...` comment on the `fsm` header line
+    #[serde(default)]
+    pub trailing_comment: Option<String>,
+    /// Comments on lines of their own that lead no state, transition or timer
+    /// (above other items, inside bodies, before the closing brace)
+    #[serde(default)]
+    pub orphan_comments: Vec<OrphanComment>,
+    /// Comments after the closing brace that lead no other `fsm` (the end of the file)
+    #[serde(default)]
+    pub following_comments: Vec<String>,
+}
+
+/// A comment on lines of its own not attached to an item, kept with its position so
+/// the DSL printer can put it back between the same items
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrphanComment {
+    /// Source line of the comment
+    pub line: usize,
+    pub text: String,
 }
 
 impl FsmDefinition {
@@ -32,59 +104,278 @@ impl FsmDefinition {
         Self {
             name: name.into(),
             description: None,
-            initial_state: None,
+            metadata: BTreeMap::new(),
+            initial_transitions: Vec::new(),
             states: Vec::new(),
             transitions: Vec::new(),
             events: Vec::new(),
+            events_line: None,
             choice_points: Vec::new(),
             timers: Vec::new(),
+            guards: Vec::new(),
+            notes: Vec::new(),
+            layout_hint: None,
+            file_name: None,
+            source_file: None,
+            line: None,
+            leading_comments: Vec::new(),
+            trailing_comment: None,
+            orphan_comments: Vec::new(),
+            following_comments: Vec::new(),
+        }
+    }
+
+    /// The default initial transition: the first unguarded `[*] -->` line, else the
+    /// first one
+    pub fn initial_transition(&self) -> Option<&InitialTransition> {
+        self.initial_transitions.iter().find(|t| t.guard.is_none()).or(self.initial_transitions.first())
+    }
+
+    /// Initial state name (the default initial transition's target)
+    pub fn initial_state(&self) -> Option<&str> {
+        self.initial_transition().map(|t| t.target.as_str())
+    }
+
+    /// Actions of the default initial transition (`[*] --> Idle : / load_config`), run
+    /// once at start before the initial state's entry actions
+    pub fn initial_actions(&self) -> &[Action] {
+        self.initial_transition().map_or(&[], |t| t.actions.as_slice())
+    }
+
+    /// Source line of the default `[*] -->` line
+    pub fn initial_line(&self) -> Option<usize> {
+        self.initial_transition().and_then(|t| t.line)
+    }
+
+    /// Make `state` the default initial state, keeping the default's actions
+    pub fn set_initial_state(&mut self, state: impl Into<String>) {
+        let state = state.into();
+        match self.initial_transitions.iter_mut().find(|t| t.guard.is_none()) {
+            Some(initial) => initial.target = state,
+            None => self.initial_transitions.push(InitialTransition::new(state)),
         }
     }
 
-    /// Get all unique events from transitions
+    /// The initial transitions in the order a start tries them: the guarded ones in
+    /// source order, then the default
+    pub fn initial_candidates(&self) -> Vec<&InitialTransition> {
+        let guarded = self.initial_transitions.iter().filter(|t| t.guard.is_some());
+        guarded.chain(self.initial_transitions.iter().find(|t| t.guard.is_none())).collect()
+    }
+
+    /// Whether some `[*] -->` line has a guard, so the start picks its state
+    pub fn has_guarded_initial(&self) -> bool {
+        self.initial_transitions.iter().any(|t| t.guard.is_some())
+    }
+
+    /// Stem of the files generated for this FSM (`io_manager.rs`, `io_manager.h`,
+    /// `io_manager.png`): the `@file_name` override, or the snake_case name.
+    /// Module names must be ASCII, so `Wächter` is transliterated to `waechter`.
+    pub fn file_stem(&self) -> String {
+        self.file_name.clone().unwrap_or_else(|| match naming::to_snake_case(&self.name) {
+            stem if stem.is_ascii() => stem,
+            _ => naming::sanitize_ident(&self.name),
+        })
+    }
+
+    /// Every event: the declared ones in declaration order, then the other events
+    /// transitions and internal transitions react to, by name
     pub fn collect_events(&self) -> Vec<Event> {
-        let mut events: Vec<Event> = self
+        self.events_in(EventOrder::Alphabetical)
+    }
+
+    /// Every event, the declared ones first in declaration order, then the other
+    /// events transitions and internal transitions react to in `order`
+    pub fn events_in(&self, order: EventOrder) -> Vec<Event> {
+        let mut triggers: Vec<&Transition> = self
             .transitions
             .iter()
-            .filter_map(|t| t.event.clone())
+            .chain(self.states.iter().flat_map(|s| &s.internal_transitions))
+            .filter(|t| t.event.is_some())
             .collect();
+        // Stable, so transitions without a line keep their place among each other
+        if order == EventOrder::Declaration {
+            triggers.sort_by_key(|t| t.line.unwrap_or(usize::MAX));
+        }
+        let mut inferred: Vec<Event> = Vec::new();
+        for event in triggers.into_iter().filter_map(|t| t.event.as_ref()) {
+            if !self.events.iter().chain(&inferred).any(|e| e.name == event.name) {
+                inferred.push(Event::new(event.name.clone()));
+            }
+        }
+        if order == EventOrder::Alphabetical {
+            inferred.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        self.events.iter().cloned().chain(inferred).collect()
+    }
 
-        // Add internal transition events
-        for state in &self.states {
-            for internal in &state.internal_transitions {
-                if let Some(event) = &internal.event {
-                    if !events.iter().any(|e| e.name == event.name) {
-                        events.push(event.clone());
-                    }
-                }
+    /// Every action call (initial, transition, entry, exit and choice actions)
+    pub fn actions(&self) -> impl Iterator<Item = &Action> {
+        let transition_actions = self
+            .transitions
+            .iter()
+            .chain(self.states.iter().flat_map(|s| &s.internal_transitions))
+            .flat_map(|t| &t.actions);
+        let state_actions = self.states.iter().flat_map(|s| s.entry_actions.iter().chain(&s.exit_actions));
+        let choice_actions = self.choice_points.iter().flat_map(|c| c.branches.iter().flat_map(|b| &b.actions));
+        let initial_actions = self.initial_transitions.iter().flat_map(|t| &t.actions);
+        initial_actions.chain(transition_actions).chain(state_actions).chain(choice_actions)
+    }
+
+    /// Names of the actions called anywhere (see [`FsmDefinition::actions`]), in order
+    /// of first mention
+    pub fn action_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for action in self.actions() {
+            if !names.contains(&action.name.as_str()) {
+                names.push(&action.name);
             }
         }
+        names
+    }
+
+    /// Names of the events transitions, internal transitions and timers use, in order
+    /// of first use
+    fn used_events(&self) -> Vec<&str> {
+        let transitions = self.transitions.iter().chain(self.states.iter().flat_map(|s| &s.internal_transitions));
+        let mut used: Vec<&str> = Vec::new();
+        let names = transitions.filter_map(|t| t.event.as_ref()).chain(self.timers.iter().map(|t| &t.event));
+        for name in names.map(|e| e.name.as_str()) {
+            if !used.contains(&name) {
+                used.push(name);
+            }
+        }
+        used
+    }
+
+    /// Declared events nothing uses
+    pub fn unused_events(&self) -> Vec<&Event> {
+        let used = self.used_events();
+        self.events.iter().filter(|e| !used.contains(&e.name.as_str())).collect()
+    }
+
+    /// Used events missing from the `events` block (none without a block)
+    pub fn undeclared_events(&self) -> Vec<&str> {
+        if self.events_line.is_none() {
+            return Vec::new();
+        }
+        self.used_events().into_iter().filter(|name| !self.events.iter().any(|e| e.name == *name)).collect()
+    }
+
+    /// Payload of the event called `name` as declared in the events block (empty if none)
+    pub fn event_params(&self, name: &str) -> &[EventParam] {
+        self.events.iter().find(|e| e.name == name).map_or(&[], |e| e.params.as_slice())
+    }
 
-        // Deduplicate
-        events.sort_by(|a, b| a.name.cmp(&b.name));
-        events.dedup_by(|a, b| a.name == b.name);
-        events
+    /// What the trigger of `transition` binds: each name with the type of its parameter
+    pub fn bound_params(&self, transition: &Transition) -> Vec<(String, String)> {
+        let Some(ref event) = transition.event else {
+            return Vec::new();
+        };
+        event
+            .bindings
+            .iter()
+            .zip(self.event_params(&event.name))
+            .map(|(binding, param)| (binding.clone(), param.ty.clone()))
+            .collect()
     }
 
     /// Validate the FSM definition
     pub fn validate(&self) -> Result<(), Vec<String>> {
+        self.validate_with(&ValidationOptions::default())
+    }
+
+    /// Validate, turning the warnings selected by `options` into errors
+    pub fn validate_with(&self, options: &ValidationOptions) -> Result<(), Vec<String>> {
         let mut errors = Vec::new();
 
-        // Check for initial state
-        if self.initial_state.is_none() {
-            errors.push("No initial state defined".to_string());
+        if options.duplicate_states_are_errors {
+            errors.extend(self.duplicate_states().into_iter().map(duplicate_state_message));
+        }
+        if options.undeclared_events_are_errors {
+            errors.extend(self.undeclared_events().into_iter().map(|name| format!("Event '{}' is not declared in the events block", name)));
+        }
+
+        if let Some(ref file_name) = self.file_name {
+            if !naming::is_rust_identifier(file_name) || !file_name.is_ascii() {
+                errors.push(format!("File name '{}' is not a valid module name", file_name));
+            }
+        }
+
+        // Check for initial state (a machine still being sketched only gets a warning)
+        if self.initial_transitions.is_empty() && !self.is_skeleton() {
+            errors.push(NO_INITIAL_STATE.to_string());
+        }
+
+        let initial_at = |t: &InitialTransition| match t.line {
+            Some(line) => format!("{} (line {})", t.target, line),
+            None => t.target.clone(),
+        };
+        let defaults: Vec<String> = self.initial_transitions.iter().filter(|t| t.guard.is_none()).map(initial_at).collect();
+        if defaults.len() > 1 {
+            errors.push(format!("Multiple initial states: {}", defaults.join(", ")));
+        }
+        if defaults.is_empty() && self.has_guarded_initial() {
+            errors.push(format!("{}: add an unguarded `[*] --> State` to start in when no guard holds", NO_DEFAULT_INITIAL));
+        }
+        let guarded: Vec<&InitialTransition> = self.initial_transitions.iter().filter(|t| t.guard.is_some()).collect();
+        for (i, first) in guarded.iter().enumerate() {
+            let expression = |t: &InitialTransition| t.guard.as_ref().map(|g| g.expression.trim().to_string());
+            if let Some(again) = guarded[i + 1..].iter().find(|t| expression(t) == expression(first)) {
+                errors.push(format!(
+                    "Initial transitions to {} and {} have the same guard [{}]: the second is never taken",
+                    initial_at(first),
+                    initial_at(again),
+                    expression(first).unwrap_or_default()
+                ));
+            }
+            // Nothing has happened yet: no event to bind from, and the default is the `else`
+            let terms = first.guard.as_ref().map(|g| g.terms()).unwrap_or_default();
+            if first.guard.as_ref().is_some_and(|g| g.is_else()) {
+                errors.push(format!("Initial transition to {} cannot be guarded by [else]; leave the default unguarded", initial_at(first)));
+            } else if let Some(term) = terms.iter().find(|t| !t.args().is_empty()) {
+                errors.push(format!("Initial transition to {} passes arguments to '{}'; its guard can only ask the context", initial_at(first), term.method()));
+            }
+        }
+
+        // Check that the initial states exist
+        for initial in &self.initial_transitions {
+            if !self.states.iter().any(|s| s.name == initial.target) {
+                errors.push(format!("Initial state '{}' not found", initial.target));
+            }
         }
 
-        // Check that initial state exists
-        if let Some(ref initial) = self.initial_state {
-            if !self.states.iter().any(|s| &s.name == initial) {
-                errors.push(format!("Initial state '{}' not found", initial));
+        // Check [else] transitions: last of their source+event group, with a guarded sibling
+        for (i, transition) in self.transitions.iter().enumerate() {
+            if !transition.guard.as_ref().is_some_and(|g| g.is_else()) {
+                continue;
+            }
+            let event = transition.event.as_ref().map(|e| e.name.as_str());
+            let siblings: Vec<(usize, &Transition)> = self
+                .transitions
+                .iter()
+                .enumerate()
+                .filter(|(j, t)| {
+                    *j != i && t.source == transition.source && t.event.as_ref().map(|e| e.name.as_str()) == event
+                })
+                .collect();
+            let trigger = match event {
+                Some(event) => format!("'{}' on '{}'", transition.source, event),
+                None => format!("'{}'", transition.source),
+            };
+
+            if siblings.iter().any(|(j, _)| *j > i) {
+                errors.push(format!("[else] transition from {} must be the last one declared", trigger));
+            }
+            if !siblings.iter().any(|(_, t)| t.guard.as_ref().is_some_and(|g| !g.is_else())) {
+                errors.push(format!("[else] transition from {} has no guarded sibling", trigger));
             }
         }
 
         // Check transition references
         for transition in &self.transitions {
             if transition.source != "[*]"
+                && !transition.is_from_any_state()
                 && !self.states.iter().any(|s| s.name == transition.source)
             {
                 errors.push(format!(
@@ -94,6 +385,7 @@ impl FsmDefinition {
             }
             if transition.target != "[*]"
                 && !self.states.iter().any(|s| s.name == transition.target)
+                && !(transition.is_local() && self.is_local_target(transition))
             {
                 errors.push(format!(
                     "Transition target state '{}' not found",
@@ -102,12 +394,451 @@ impl FsmDefinition {
             }
         }
 
+        // Unguarded completion transitions must not lead back to where they started
+        for cycle in completion_cycles(self) {
+            errors.push(format!("Completion transitions loop without a guard: {}", cycle.join(" --> ")));
+        }
+
+        // Local transitions stay inside their (composite) source
+        for transition in self.transitions.iter().filter(|t| t.is_local()) {
+            if !self.is_local_target(transition) {
+                errors.push(format!(
+                    "Local transition '{}' --> '{}' needs a composite source containing its target",
+                    transition.source, transition.target
+                ));
+            }
+        }
+
+        // Nothing leaves a final state (wildcard transitions skip it)
+        for state in self.states.iter().filter(|s| s.is_final()) {
+            let leaving: Vec<String> = self
+                .transitions
+                .iter()
+                .filter(|t| t.source == state.name)
+                .chain(&state.internal_transitions)
+                .map(|t| {
+                    let internal = if t.kind == TransitionKind::Internal { " (internal)" } else { "" };
+                    let line = t.line.map(|l| format!(" (line {})", l)).unwrap_or_default();
+                    format!("{}{}{}", trigger_label(t), internal, line)
+                })
+                .collect();
+            if !leaving.is_empty() {
+                errors.push(format!("Final state '{}' has outgoing transitions: {}", state.name, leaving.join(", ")));
+            }
+        }
+
+        // Triggers bind the whole payload or none of it, and calls only pass bound names
+        let internal = self.states.iter().flat_map(|s| &s.internal_transitions);
+        for transition in self.transitions.iter().chain(internal) {
+            errors.extend(self.binding_errors(transition));
+        }
+        for timer in &self.timers {
+            if !self.event_params(&timer.event.name).is_empty() {
+                errors.push(format!("Timer '{}' fires '{}', which carries parameters", timer.name, timer.event.name));
+            }
+        }
+        errors.extend(self.named_guard_errors());
+
         if errors.is_empty() {
             Ok(())
         } else {
             Err(errors)
         }
     }
+
+    /// Repeated and looping named guard definitions, and arguments passed to or by them
+    fn named_guard_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (i, named) in self.guards.iter().enumerate() {
+            if self.guards[..i].iter().any(|g| g.name == named.name) {
+                let lines: Vec<String> =
+                    self.guards.iter().filter(|g| g.name == named.name).filter_map(|g| g.line).map(|l| l.to_string()).collect();
+                errors.push(format!("Guard '{}' is defined more than once (lines {})", named.name, lines.join(", ")));
+            }
+            if Guard::new(named.expression.as_str()).is_else() {
+                errors.push(format!("Guard '{}' cannot be defined as [else]", named.name));
+            }
+            // A named guard is called without the event, so it has nothing to pass
+            for term in Guard::new(named.expression.as_str()).terms().iter().filter(|t| !t.args().is_empty()) {
+                errors.push(format!("Guard '{}' passes arguments to '{}'; only a transition's guard can", named.name, term.expression));
+            }
+        }
+        for cycle in self.guard_cycles() {
+            errors.push(format!("Guards refer to each other in a loop: {}", cycle.join(" → ")));
+        }
+        for guard in self.guard_uses() {
+            for term in guard.terms() {
+                if term.expression != term.method() && self.named_guard(&term.method()).is_some() {
+                    errors.push(format!("Guard '{}' takes no arguments: [{}]", term.method(), guard.expression));
+                }
+            }
+        }
+        let definitions = self.guards.iter().map(|g| Guard::new(g.expression.as_str()));
+        for guard in self.guard_uses().into_iter().cloned().chain(definitions) {
+            for term in guard.terms().iter().filter(|t| !t.has_method()) {
+                errors.push(format!(
+                    "Guard term '{}' in [{}] is not a method, a named guard or a comparison of a name with a name or number (`attempts > 3`)",
+                    term.expression, guard.expression
+                ));
+            }
+        }
+        errors
+    }
+
+    fn binding_errors(&self, transition: &Transition) -> Vec<String> {
+        let mut errors = Vec::new();
+        let label = trigger_label(transition);
+        let bindings = transition.event.as_ref().map_or(&[][..], |e| e.bindings.as_slice());
+        if let Some(ref event) = transition.event {
+            let declared = self.event_params(&event.name).len();
+            if !bindings.is_empty() && bindings.len() != declared {
+                errors.push(format!(
+                    "{} binds {} parameter(s), but '{}' declares {}",
+                    label,
+                    bindings.len(),
+                    event.name,
+                    declared
+                ));
+            }
+            for (i, binding) in bindings.iter().enumerate() {
+                if bindings[..i].contains(binding) {
+                    errors.push(format!("{} binds '{}' twice", label, binding));
+                }
+            }
+        }
+
+        let unknown = |arg: &str| !bindings.iter().any(|b| b == arg);
+        if let Some(ref guard) = transition.guard {
+            for term in guard.terms() {
+                for arg in term.args().into_iter().filter(|a| unknown(a)) {
+                    errors.push(format!("{}: guard uses unknown binding '{}'", label, arg));
+                }
+            }
+        }
+        // Without bindings, action parameters keep their old meaning (timers, `send` targets);
+        // constants (`5000`, `"door"`) are passed as written
+        if !bindings.is_empty() {
+            for action in transition.actions.iter().filter(|a| a.name != "send") {
+                for param in action.params.iter().filter(|p| unknown(p) && Action::literal_type(p).is_none()) {
+                    errors.push(format!("{}: action '{}' uses unknown binding '{}'", label, action.name, param));
+                }
+            }
+        }
+        errors
+    }
+
+    /// Whether the source of `transition` is a composite state that is, or contains, its target
+    fn is_local_target(&self, transition: &Transition) -> bool {
+        self.states
+            .iter()
+            .find(|s| s.name == transition.source)
+            .is_some_and(|s| s.is_composite() && (s.name == transition.target || s.contains_state(&transition.target)))
+    }
+
+    /// Whether nothing moves the machine yet: no transitions, internal ones included
+    pub fn is_skeleton(&self) -> bool {
+        self.transitions.is_empty() && self.states.iter().all(|s| s.internal_transitions.is_empty())
+    }
+
+    /// Guidance for a machine that is still being sketched (no states, or no
+    /// transitions), where `validate` does not insist on an initial state
+    fn skeleton_warning(&self) -> Option<String> {
+        if !self.is_skeleton() {
+            return None;
+        }
+        match (self.states.first(), self.initial_state()) {
+            (None, _) => Some(NO_STATES.to_string()),
+            (Some(first), None) => Some(format!(
+                "{}: add `[*] --> {}` to choose the initial state (until then the first state is used)",
+                NO_TRANSITIONS, first.name
+            )),
+            (Some(_), Some(initial)) => Some(format!("{}: it stays in '{}'", NO_TRANSITIONS, initial)),
+        }
+    }
+
+    /// Problems that don't prevent code generation but are probably mistakes
+    pub fn warnings(&self) -> Vec<String> {
+        let skeleton = self.skeleton_warning();
+        let duplicates = self.duplicate_states().into_iter().map(duplicate_state_message);
+        let ambiguous = self.ambiguous_transitions().into_iter().map(|group| {
+            let lines: Vec<String> = group.iter().filter_map(|t| t.line).map(|l| l.to_string()).collect();
+            format!(
+                "Transitions '{}' differ only in their actions (lines {}): which actions run is ambiguous",
+                trigger_label(group[0]),
+                lines.join(", ")
+            )
+        });
+        let wildcards = self.redundant_wildcards().into_iter().map(|wildcard| {
+            format!(
+                "Wildcard transition '{}'{} never fires: every state has the same transition of its own",
+                trigger_label(wildcard),
+                wildcard.line.map(|l| format!(" (line {})", l)).unwrap_or_default()
+            )
+        });
+        let livelocks = livelock_cycles(self)
+            .into_iter()
+            .map(|states| format!("Possible livelock: no transition leaves {{{}}}", states.join(", ")));
+        let colors = self.states.iter().filter_map(|state| {
+            let color = state.color.as_deref()?;
+            state.rgb().is_none().then(|| {
+                format!(
+                    "State '{}' has an unknown color '{}' (use a named color like red or #RRGGBB)",
+                    state.name, color
+                )
+            })
+        });
+        let unused = self.unused_events().into_iter().map(|event| {
+            let line = event.line.map(|l| format!(" (line {})", l)).unwrap_or_default();
+            format!("Event '{}' is declared{} but no transition or timer uses it", event.name, line)
+        });
+        let unused_guards = self.unused_guards().into_iter().map(|guard| {
+            let line = guard.line.map(|l| format!(" (line {})", l)).unwrap_or_default();
+            format!("Guard '{}' is defined{} but no transition uses it", guard.name, line)
+        });
+        let self_transitions = self.internal_and_self_transitions().into_iter().map(|(internal, external)| {
+            let line = |t: &Transition| t.line.map(|l| format!(" (line {})", l)).unwrap_or_default();
+            let outcome = match external.guard {
+                None => "so the internal one never runs",
+                Some(_) => "and the internal one runs only when its guard fails",
+            };
+            format!(
+                "State '{}' handles '{}' with an internal transition{} and an external self-transition{}: \
+                 the self-transition, which runs the exit and entry actions, is checked first, {}",
+                internal.source,
+                internal.event.as_ref().map_or("", |e| e.name.as_str()),
+                line(internal),
+                line(external),
+                outcome
+            )
+        });
+        skeleton
+            .into_iter()
+            .chain(duplicates)
+            .chain(ambiguous)
+            .chain(wildcards)
+            .chain(livelocks)
+            .chain(colors)
+            .chain(unused)
+            .chain(unused_guards)
+            .chain(self_transitions)
+            .collect()
+    }
+
+    /// Internal transitions of a state paired with an external self-transition of the
+    /// same state on the same event, which the dispatch checks first
+    pub fn internal_and_self_transitions(&self) -> Vec<(&Transition, &Transition)> {
+        let same_event = |a: &Transition, b: &Transition| a.event.as_ref().zip(b.event.as_ref()).is_some_and(|(a, b)| a.name == b.name);
+        self.states
+            .iter()
+            .flat_map(|state| &state.internal_transitions)
+            .filter_map(|internal| {
+                let external = self
+                    .transitions
+                    .iter()
+                    .find(|t| t.is_external_self_transition() && t.source == internal.source && same_event(t, internal))?;
+                Some((internal, external))
+            })
+            .collect()
+    }
+
+    /// Notes about the definition that are not problems by themselves
+    pub fn infos(&self) -> Vec<String> {
+        let mut infos: Vec<String> = self
+            .transitions
+            .iter()
+            .filter(|t| t.declared_at.len() > 1)
+            .map(duplicate_transition_message)
+            .collect();
+        let implicit = self.implicit_states();
+        if !implicit.is_empty() {
+            infos.push(format!("Implicitly declared states (no `state` line): {}", implicit.join(", ")));
+        }
+        let undeclared = self.undeclared_events();
+        if !undeclared.is_empty() {
+            infos.push(format!("Events not in the events block: {}", undeclared.join(", ")));
+        }
+        infos
+    }
+
+    /// Collapse transitions identical in source, target, event, guard, actions and
+    /// kind into the first one, which collects their lines in `declared_at`. The
+    /// parser runs this, so code generation never sees exact duplicates.
+    pub fn merge_duplicate_transitions(&mut self) {
+        let mut merged: Vec<Transition> = Vec::with_capacity(self.transitions.len());
+        for transition in self.transitions.drain(..) {
+            match merged.iter_mut().find(|t| same_trigger(t, &transition) && same_actions(t, &transition)) {
+                Some(existing) => {
+                    if existing.declared_at.is_empty() {
+                        existing.declared_at.extend(existing.line);
+                    }
+                    existing.declared_at.extend(transition.line);
+                    // The duplicate's comments stay where it was
+                    if let Some(line) = transition.line {
+                        let texts = transition.leading_comments.into_iter().chain(transition.trailing_comment);
+                        self.orphan_comments.extend(texts.map(|text| OrphanComment { line, text }));
+                    }
+                }
+                None => merged.push(transition),
+            }
+        }
+        self.transitions = merged;
+        self.orphan_comments.sort_by_key(|c| c.line);
+    }
+
+    /// Groups of transitions that only differ in their actions (same source, target,
+    /// event, guard and kind)
+    pub fn ambiguous_transitions(&self) -> Vec<Vec<&Transition>> {
+        let mut groups: Vec<Vec<&Transition>> = Vec::new();
+        for transition in &self.transitions {
+            match groups.iter_mut().find(|group| same_trigger(group[0], transition)) {
+                Some(group) => group.push(transition),
+                None => groups.push(vec![transition]),
+            }
+        }
+        groups.retain(|group| group.len() > 1);
+        groups
+    }
+
+    /// Wildcard transitions shadowed in every state by a transition from that state
+    /// with the same event, guard, target, actions and kind
+    pub fn redundant_wildcards(&self) -> Vec<&Transition> {
+        self.transitions
+            .iter()
+            .filter(|wildcard| wildcard.is_from_any_state() && !self.states.is_empty())
+            .filter(|wildcard| {
+                self.states.iter().all(|state| {
+                    self.transitions
+                        .iter()
+                        .any(|t| t.source == state.name && same_response(t, wildcard) && same_actions(t, wildcard))
+                })
+            })
+            .collect()
+    }
+
+    /// Id of every transition, in `transitions` order
+    pub fn transition_ids(&self) -> Vec<TransitionId> {
+        let mut seen: Vec<(&Transition, usize)> = Vec::new();
+        self.transitions
+            .iter()
+            .map(|transition| {
+                // The nth identical transition gets its own id
+                let occurrence = match seen.iter_mut().find(|(t, _)| TransitionId::same_key(t, transition)) {
+                    Some((_, count)) => {
+                        *count += 1;
+                        *count
+                    }
+                    None => {
+                        seen.push((transition, 0));
+                        0
+                    }
+                };
+                TransitionId::of(transition, occurrence)
+            })
+            .collect()
+    }
+
+    /// Id of the transition at `index`
+    pub fn transition_id(&self, index: usize) -> Option<TransitionId> {
+        self.transition_ids().get(index).copied()
+    }
+
+    /// Index in `transitions` of the transition with `id`, wherever it moved to
+    pub fn transition_index(&self, id: TransitionId) -> Option<usize> {
+        self.transition_ids().iter().position(|&i| i == id)
+    }
+
+    /// States with more than one `state` declaration (their bodies are merged)
+    pub fn duplicate_states(&self) -> Vec<&State> {
+        self.states.iter().filter(|s| s.declared_at.len() > 1).collect()
+    }
+
+    /// States that were created by being mentioned, never declared; a typo in a
+    /// transition shows up here as an extra state
+    pub fn implicit_states(&self) -> Vec<&str> {
+        self.states
+            .iter()
+            .filter(|s| !s.is_declared())
+            .map(|s| s.name.as_str())
+            .collect()
+    }
+}
+
+/// Same source, target, event, guard and kind
+fn same_trigger(a: &Transition, b: &Transition) -> bool {
+    a.source == b.source && same_response(a, b)
+}
+
+/// Same target, event, guard and kind, whatever the source
+fn same_response(a: &Transition, b: &Transition) -> bool {
+    a.target == b.target
+        && a.event == b.event
+        && a.guard.as_ref().map(|g| &g.expression) == b.guard.as_ref().map(|g| &g.expression)
+        && a.kind == b.kind
+}
+
+fn same_actions(a: &Transition, b: &Transition) -> bool {
+    a.actions.len() == b.actions.len()
+        && a.actions.iter().zip(&b.actions).all(|(x, y)| x.name == y.name && x.params == y.params)
+}
+
+/// `Source --> Target : event [guard]`, without the actions
+fn trigger_label(transition: &Transition) -> String {
+    let mut label = format!("{} --> {}", transition.source, transition.target);
+    let event = transition.event.as_ref().map(Event::pattern);
+    let guard = transition.guard.as_ref().map(|g| format!("[{}]", g.expression));
+    let trigger: Vec<String> = event.into_iter().chain(guard).collect();
+    if !trigger.is_empty() {
+        label.push_str(&format!(" : {}", trigger.join(" ")));
+    }
+    label
+}
+
+/// Error `validate` reports for an FSM without `[*] -->`
+pub const NO_INITIAL_STATE: &str = "No initial state defined";
+/// Start of the error for guarded `[*] -->` lines without an unguarded one
+pub const NO_DEFAULT_INITIAL: &str = "Guarded initial transitions need a default";
+
+/// Warning for an FSM without states (`fsm Foo { }`)
+pub const NO_STATES: &str = "Machine has no states yet: add `[*] --> Idle` to create one and start in it";
+
+/// Start of the warning for an FSM with states but no transitions
+pub const NO_TRANSITIONS: &str = "Machine has no transitions";
+
+/// Info about a transition declared more than once, with the lines it was merged from
+pub fn duplicate_transition_message(transition: &Transition) -> String {
+    let lines: Vec<String> = transition.declared_at.iter().map(|l| l.to_string()).collect();
+    let label = match transition.label() {
+        label if label.is_empty() => format!("{} --> {}", transition.source, transition.target),
+        label => format!("{} --> {} : {}", transition.source, transition.target, label),
+    };
+    format!("Duplicate transition '{}' merged (lines {})", label, lines.join(", "))
+}
+
+fn duplicate_state_message(state: &State) -> String {
+    let lines: Vec<String> = state.declared_at.iter().map(|l| l.to_string()).collect();
+    format!("State '{}' is declared more than once (lines {})", state.name, lines.join(", "))
+}
+
+/// Which `FsmDefinition::warnings` `validate_with` reports as errors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValidationOptions {
+    /// A second `state X` declaration is an error instead of being merged
+    pub duplicate_states_are_errors: bool,
+    /// With an `events` block, using an event it does not list is an error instead of
+    /// an info
+    pub undeclared_events_are_errors: bool,
+}
+
+impl ValidationOptions {
+    pub fn with_duplicate_states_as_errors(mut self, enabled: bool) -> Self {
+        self.duplicate_states_are_errors = enabled;
+        self
+    }
+
+    pub fn with_undeclared_events_as_errors(mut self, enabled: bool) -> Self {
+        self.undeclared_events_are_errors = enabled;
+        self
+    }
 }
 
 /// A state in the FSM
@@ -117,6 +848,12 @@ pub struct State {
     pub name: String,
     /// Optional description
     pub description: Option<String>,
+    /// `// ...` lines above its `state` declaration
+    #[serde(default)]
+    pub leading_comments: Vec<String>,
+    /// Trailing `// ...` comment on the declaring line
+    #[serde(default, alias = "comment")]
+    pub trailing_comment: Option<String>,
     /// State type
     pub state_type: StateType,
     /// Entry actions (can have multiple)
@@ -129,6 +866,19 @@ pub struct State {
     pub sub_fsm: Option<FsmDefinition>,
     /// Visual position in the GUI (x, y)
     pub position: Option<(f32, f32)>,
+    /// Source line of the declaration (or first mention)
+    #[serde(default)]
+    pub line: Option<usize>,
+    /// Lines of its `state` declarations; empty if the state only appears in
+    /// transitions, notes or `[*] -->`
+    #[serde(default)]
+    pub declared_at: Vec<usize>,
+    /// `#color(...)` annotation as written: a named color or `#RRGGBB`
+    #[serde(default)]
+    pub color: Option<String>,
+    /// `@tag(...)` annotations, e.g. the subsystem the state belongs to
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 impl State {
@@ -136,18 +886,46 @@ impl State {
         Self {
             name: name.into(),
             description: None,
+            leading_comments: Vec::new(),
+            trailing_comment: None,
             state_type,
             entry_actions: Vec::new(),
             exit_actions: Vec::new(),
             internal_transitions: Vec::new(),
             sub_fsm: None,
             position: None,
+            line: None,
+            declared_at: Vec::new(),
+            color: None,
+            tags: Vec::new(),
         }
     }
 
+    /// RGB of the `#color(...)` annotation, if it names a known color
+    pub fn rgb(&self) -> Option<[u8; 3]> {
+        self.color.as_deref().and_then(color::parse_color)
+    }
+
+    /// Whether the state has a `state` declaration
+    pub fn is_declared(&self) -> bool {
+        !self.declared_at.is_empty()
+    }
+
     pub fn is_composite(&self) -> bool {
         matches!(self.state_type, StateType::Composite)
     }
+
+    /// The machine stops in a final state: no transition leaves it
+    pub fn is_final(&self) -> bool {
+        self.state_type == StateType::Final
+    }
+
+    /// Whether `name` is a substate of this state, at any depth
+    pub fn contains_state(&self, name: &str) -> bool {
+        self.sub_fsm
+            .as_ref()
+            .is_some_and(|sub| sub.states.iter().any(|s| s.name == name || s.contains_state(name)))
+    }
 }
 
 /// Type of state
@@ -168,7 +946,7 @@ pub enum StateType {
 /// A transition between states
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transition {
-    /// Source state name
+    /// Source state name (`[*]` for the initial transition, [`ANY_STATE`] for a wildcard)
     pub source: String,
     /// Target state name
     pub target: String,
@@ -176,10 +954,35 @@ pub struct Transition {
     pub event: Option<Event>,
     /// Guard condition
     pub guard: Option<Guard>,
-    /// Action to execute
-    pub action: Option<Action>,
+    /// Actions to execute, in order
+    pub actions: Vec<Action>,
     /// Transition kind
     pub kind: TransitionKind,
+    /// `@debounce(50ms)` / `@throttle(1s)` after the event: how often it may fire
+    #[serde(default)]
+    pub timing_constraint: Option<TimingConstraint>,
+    /// How the arrow is drawn; it does not change the generated code
+    #[serde(default)]
+    pub style: TransitionStyle,
+    /// Written target first (`B <-- A`), which the pretty-printer keeps
+    #[serde(default)]
+    pub reversed: bool,
+    /// Documentation from `///` comments
+    #[serde(default)]
+    pub description: Option<String>,
+    /// `// ...` lines above the transition
+    #[serde(default)]
+    pub leading_comments: Vec<String>,
+    /// Trailing `// ...` comment on the transition line
+    #[serde(default, alias = "comment")]
+    pub trailing_comment: Option<String>,
+    /// Source line of the transition
+    #[serde(default)]
+    pub line: Option<usize>,
+    /// Lines of the identical declarations merged into this transition by
+    /// `FsmDefinition::merge_duplicate_transitions` (empty without duplicates)
+    #[serde(default)]
+    pub declared_at: Vec<usize>,
 }
 
 impl Transition {
@@ -189,8 +992,16 @@ impl Transition {
             target: target.into(),
             event: None,
             guard: None,
-            action: None,
+            actions: Vec::new(),
             kind: TransitionKind::External,
+            timing_constraint: None,
+            style: TransitionStyle::Solid,
+            reversed: false,
+            description: None,
+            leading_comments: Vec::new(),
+            trailing_comment: None,
+            line: None,
+            declared_at: Vec::new(),
         }
     }
 
@@ -205,51 +1016,239 @@ impl Transition {
     }
 
     pub fn with_action(mut self, action: Action) -> Self {
-        self.action = Some(action);
+        self.actions.push(action);
         self
     }
 
+    /// Wildcard transition (`* --> Target`), from whichever state the machine is in
+    pub fn is_from_any_state(&self) -> bool {
+        self.source == ANY_STATE
+    }
+
+    /// A transition from a state back to itself that leaves it, running its exit and
+    /// entry actions (unlike an internal transition in the state's body)
+    pub fn is_external_self_transition(&self) -> bool {
+        self.kind == TransitionKind::External && self.source == self.target && !self.is_from_any_state()
+    }
+
+    /// Completion transition (`Validating --> Submitting : [all_valid]`): no event, taken
+    /// as soon as its source state has been entered and its guard passes
+    pub fn is_completion(&self) -> bool {
+        self.event.is_none() && self.source != "[*]" && !self.is_from_any_state() && self.target != "[*]"
+    }
+
+    pub fn is_dashed(&self) -> bool {
+        self.style == TransitionStyle::Dashed
+    }
+
+    /// `local` transition: its composite source is not exited
+    pub fn is_local(&self) -> bool {
+        self.kind == TransitionKind::Local
+    }
+
+    /// Whether taking the transition runs the exit actions of its source
+    pub fn exits_source(&self) -> bool {
+        !self.is_local()
+    }
+
+    /// Whether taking the transition runs the entry actions of its target
+    pub fn enters_target(&self) -> bool {
+        !(self.is_local() && self.source == self.target)
+    }
+
     /// Format transition label for display
     pub fn label(&self) -> String {
+        self.label_with(TimingConstraint::to_string)
+    }
+
+    /// [`Transition::label`] with the timing constraint as a clock (`door_opened ⏱debounce 50ms`),
+    /// after a `↺` on an external self-transition
+    pub fn diagram_label(&self) -> String {
+        let label = self.label_with(TimingConstraint::glyph);
+        match self.is_external_self_transition() {
+            true => format!("↺ {}", label).trim_end().to_string(),
+            false => label,
+        }
+    }
+
+    fn label_with(&self, timing: impl Fn(&TimingConstraint) -> String) -> String {
         let mut parts = Vec::new();
 
         if let Some(ref event) = self.event {
-            parts.push(event.name.clone());
+            parts.push(event.pattern());
+        }
+
+        if let Some(ref constraint) = self.timing_constraint {
+            parts.push(timing(constraint));
         }
 
         if let Some(ref guard) = self.guard {
             parts.push(format!("[{}]", guard.expression));
         }
 
-        if let Some(ref action) = self.action {
-            parts.push(format!("/ {}", action.name));
+        if !self.actions.is_empty() {
+            let calls: Vec<String> = self.actions.iter().map(Action::to_string).collect();
+            parts.push(format!("/ {}", calls.join("; ")));
         }
 
         parts.join(" ")
     }
 }
 
+/// Identity of a transition that does not depend on its position in
+/// `FsmDefinition::transitions`: a hash of its source, target, label and kind. A
+/// layout or simulator step recorded before the transitions were reordered (or the
+/// source re-parsed) still refers to the same transition, and a changed transition
+/// refers to none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TransitionId(u64);
+
+impl TransitionId {
+    fn of(transition: &Transition, occurrence: usize) -> Self {
+        let mut hasher = DefaultHasher::new();
+        transition.source.hash(&mut hasher);
+        transition.target.hash(&mut hasher);
+        transition.label().hash(&mut hasher);
+        transition.kind.hash(&mut hasher);
+        occurrence.hash(&mut hasher);
+        Self(hasher.finish())
+    }
+
+    fn same_key(a: &Transition, b: &Transition) -> bool {
+        a.source == b.source && a.target == b.target && a.label() == b.label() && a.kind == b.kind
+    }
+}
+
+impl fmt::Display for TransitionId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
 /// Kind of transition
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum TransitionKind {
     /// External transition (exits and re-enters states)
     External,
     /// Internal transition (doesn't exit the state)
     Internal,
-    /// Local transition (stays within composite state boundary)
+    /// Local transition (stays within composite state boundary), written `local event`
     Local,
 }
 
+/// Order of the events no `events` block declares, after the declared ones
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EventOrder {
+    /// By name, so reordering transitions does not move them
+    #[default]
+    Alphabetical,
+    /// By the first transition that uses them in the source, so a new event is
+    /// added after the others instead of between them
+    Declaration,
+}
+
+impl EventOrder {
+    pub const ALL: [EventOrder; 2] = [EventOrder::Alphabetical, EventOrder::Declaration];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EventOrder::Alphabetical => "Alphabetical",
+            EventOrder::Declaration => "Declaration (first use)",
+        }
+    }
+}
+
+/// Arrow of a transition: `-->` is solid, `-.->` dashed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TransitionStyle {
+    #[default]
+    Solid,
+    Dashed,
+}
+
+impl TransitionStyle {
+    /// The arrow as written, `reversed` for target-first transitions (`B <-.- A`)
+    pub fn arrow(self, reversed: bool) -> &'static str {
+        match (self, reversed) {
+            (TransitionStyle::Solid, false) => "-->",
+            (TransitionStyle::Solid, true) => "<--",
+            (TransitionStyle::Dashed, false) => "-.->",
+            (TransitionStyle::Dashed, true) => "<-.-",
+        }
+    }
+}
+
 /// An event that triggers transitions
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Event {
     /// Event name
     pub name: String,
+    /// `///` documentation of a declared event
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Source line of its declaration in an `events` block
+    #[serde(default)]
+    pub line: Option<usize>,
+    /// Payload of a declared event, e.g. `set_speed(rpm: u16)`
+    #[serde(default)]
+    pub params: Vec<EventParam>,
+    /// Explicit discriminant of a declared event, e.g. `stop = 2`
+    #[serde(default)]
+    pub id: Option<u32>,
+    /// Names a transition's trigger gives the payload, in parameter order, e.g.
+    /// `set_speed(rpm)`; its guard and actions can pass them on
+    #[serde(default)]
+    pub bindings: Vec<String>,
 }
 
 impl Event {
     pub fn new(name: impl Into<String>) -> Self {
-        Self { name: name.into() }
+        Self {
+            name: name.into(),
+            description: None,
+            line: None,
+            params: Vec::new(),
+            id: None,
+            bindings: Vec::new(),
+        }
+    }
+
+    /// The event as a trigger: its name, with the bindings in parentheses if it has any
+    pub fn pattern(&self) -> String {
+        if self.bindings.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}({})", self.name, self.bindings.join(", "))
+        }
+    }
+}
+
+/// A parameter of an event's payload
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EventParam {
+    pub name: String,
+    /// Rust type, as written in the DSL (must be `Copy`)
+    pub ty: String,
+}
+
+/// A `[*] --> State` line. The unguarded one is the default initial transition; a
+/// guarded one (`[*] --> RecoveryMode : [recovery_pin_set]`) is taken instead when its
+/// guard holds at start
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InitialTransition {
+    pub target: String,
+    pub guard: Option<Guard>,
+    /// Run once at start, before the target's entry actions
+    #[serde(default)]
+    pub actions: Vec<Action>,
+    /// Source line of the `[*] -->` line
+    #[serde(default)]
+    pub line: Option<usize>,
+}
+
+impl InitialTransition {
+    pub fn new(target: impl Into<String>) -> Self {
+        Self { target: target.into(), guard: None, actions: Vec::new(), line: None }
     }
 }
 
@@ -261,11 +1260,77 @@ pub struct Guard {
 }
 
 impl Guard {
+    /// Guard keyword taken when no sibling guard holds
+    pub const ELSE: &'static str = "else";
+
     pub fn new(expression: impl Into<String>) -> Self {
         Self {
             expression: expression.into(),
         }
     }
+
+    /// Whether this is an `[else]` guard
+    pub fn is_else(&self) -> bool {
+        self.expression == Self::ELSE
+    }
+
+    /// What the guard checks: the method of a call like `speed_ok(rpm)`, the method a
+    /// comparison like `attempts > 3` is asked through (`attempts_gt_3`), otherwise the
+    /// whole expression
+    pub fn method(&self) -> Cow<'_, str> {
+        if let Some((method, _)) = self.call() {
+            return Cow::Borrowed(method);
+        }
+        match self.comparison() {
+            Some(comparison) => Cow::Owned(comparison.method()),
+            None => Cow::Borrowed(self.expression.as_str()),
+        }
+    }
+
+    /// Arguments of a call like `speed_ok(rpm)` (none for any other expression)
+    pub fn args(&self) -> Vec<&str> {
+        self.call()
+            .map(|(_, args)| args.split(',').map(str::trim).filter(|a| !a.is_empty()).collect())
+            .unwrap_or_default()
+    }
+
+    fn call(&self) -> Option<(&str, &str)> {
+        let (method, rest) = self.expression.split_once('(')?;
+        let args = rest.trim_end().strip_suffix(')')?;
+        let method = method.trim();
+        let is_name = !method.is_empty() && method.chars().all(unicode_ident::is_xid_continue);
+        is_name.then_some((method, args))
+    }
+}
+
+/// A guard defined once at fsm scope (`guard lockout = attempts > 3 && !maintenance_mode`)
+/// and used by name in guard expressions (`[lockout]`, `[lockout && door_closed]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamedGuard {
+    pub name: String,
+    /// Definition, which may use other named guards
+    pub expression: String,
+    /// Source line of the definition
+    #[serde(default)]
+    pub line: Option<usize>,
+    /// `// ...` lines above the definition
+    #[serde(default)]
+    pub leading_comments: Vec<String>,
+    /// Trailing `// ...` comment on the definition line
+    #[serde(default)]
+    pub trailing_comment: Option<String>,
+}
+
+impl NamedGuard {
+    pub fn new(name: impl Into<String>, expression: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            expression: expression.into(),
+            line: None,
+            leading_comments: Vec::new(),
+            trailing_comment: None,
+        }
+    }
 }
 
 /// An action to execute
@@ -275,6 +1340,18 @@ pub struct Action {
     pub name: String,
     /// Optional parameters
     pub params: Vec<String>,
+    /// Written `async name`: the Embassy target awaits it (see `codegen::AsyncActions`)
+    #[serde(default)]
+    pub is_async: bool,
+    /// Written `name()` rather than `name`
+    #[serde(default)]
+    pub parenthesized: bool,
+    /// Source line of the statement (`entry / a; b` gives both actions its line)
+    #[serde(default)]
+    pub line: Option<usize>,
+    /// Trailing `// ...` comment on an `entry /` or `exit /` line, on its last action
+    #[serde(default)]
+    pub trailing_comment: Option<String>,
 }
 
 impl Action {
@@ -282,6 +1359,10 @@ impl Action {
         Self {
             name: name.into(),
             params: Vec::new(),
+            is_async: false,
+            parenthesized: false,
+            line: None,
+            trailing_comment: None,
         }
     }
 
@@ -289,6 +1370,86 @@ impl Action {
         self.params = params;
         self
     }
+
+    pub fn with_async(mut self, is_async: bool) -> Self {
+        self.is_async = is_async;
+        self
+    }
+
+    /// Rust type of a parameter written as a constant: `u32` for `5000` (`i32` if
+    /// negative, `u64`/`i64` when it does not fit), `&str` for `"door"`, `bool` for
+    /// `true`/`false`; `None` for an identifier
+    pub fn literal_type(param: &str) -> Option<&'static str> {
+        if param == "true" || param == "false" {
+            return Some("bool");
+        }
+        if param.len() >= 2 && param.starts_with('"') && param.ends_with('"') {
+            return Some("&str");
+        }
+        if param.parse::<u32>().is_ok() {
+            Some("u32")
+        } else if param.parse::<i32>().is_ok() {
+            Some("i32")
+        } else if param.parse::<u64>().is_ok() {
+            Some("u64")
+        } else if param.parse::<i64>().is_ok() {
+            Some("i64")
+        } else {
+            None
+        }
+    }
+
+    /// Whether the constant `param` can be passed where `ty` is expected, as `-40`
+    /// for an `i16` payload
+    pub fn literal_fits(param: &str, ty: &str) -> bool {
+        let Ok(value) = param.parse::<i128>() else {
+            return Action::literal_type(param) == Some(ty);
+        };
+        let range = match ty {
+            "u8" => (0, u8::MAX as i128),
+            "u16" => (0, u16::MAX as i128),
+            "u32" => (0, u32::MAX as i128),
+            "u64" | "usize" => (0, u64::MAX as i128),
+            "i8" => (i8::MIN as i128, i8::MAX as i128),
+            "i16" => (i16::MIN as i128, i16::MAX as i128),
+            "i32" => (i32::MIN as i128, i32::MAX as i128),
+            "i64" | "isize" => (i64::MIN as i128, i64::MAX as i128),
+            _ => return false,
+        };
+        (range.0..=range.1).contains(&value)
+    }
+
+    /// `send(Machine, event)` or a timer's start/stop action: its parameters name a
+    /// machine, event or timer instead of passing values
+    pub fn is_builtin(&self) -> bool {
+        (self.name == "send" && self.params.len() == 2) || self.timer_op().is_some()
+    }
+
+    /// What a `start_timer(t)` / `stop_timer(t)` action does to which timer, including
+    /// the `start_timer_t` form a bare `start_timer(t)` in a state body is parsed into
+    pub fn timer_op(&self) -> Option<(TimerOp, &str)> {
+        let [timer] = self.params.as_slice() else {
+            return None;
+        };
+        let (op, suffix) = if let Some(suffix) = self.name.strip_prefix("start_timer") {
+            (TimerOp::Start, suffix)
+        } else {
+            (TimerOp::Stop, self.name.strip_prefix("stop_timer")?)
+        };
+        (suffix.is_empty() || suffix.strip_prefix('_') == Some(timer.as_str())).then_some((op, timer.as_str()))
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_async {
+            write!(f, "async ")?;
+        }
+        match self.params.is_empty() {
+            true => write!(f, "{}", self.name),
+            false => write!(f, "{}({})", self.name, self.params.join(", ")),
+        }
+    }
 }
 
 /// Runtime context for FSM execution
@@ -339,6 +1500,15 @@ pub struct Timer {
     pub mode: TimerMode,
     /// Optional: start automatically on state entry
     pub auto_start_state: Option<String>,
+    /// Source line of the `timer` definition
+    #[serde(default)]
+    pub line: Option<usize>,
+    /// `// ...` lines above the definition
+    #[serde(default)]
+    pub leading_comments: Vec<String>,
+    /// Trailing `// ...` comment on the definition line
+    #[serde(default)]
+    pub trailing_comment: Option<String>,
 }
 
 impl Timer {
@@ -349,6 +1519,9 @@ impl Timer {
             event,
             mode: TimerMode::OneShot,
             auto_start_state: None,
+            line: None,
+            leading_comments: Vec::new(),
+            trailing_comment: None,
         }
     }
     
@@ -372,6 +1545,70 @@ pub enum TimerMode {
     Periodic,
 }
 
+/// How often a transition may fire, written after its event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TimingConstraint {
+    pub kind: TimingKind,
+    /// Length of the window in milliseconds
+    pub window_ms: u32,
+}
+
+/// What a [`TimingConstraint`] measures its window from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TimingKind {
+    /// `@debounce`: fires only if the event was not seen within the window, so a
+    /// bouncing input fires once and then not until it has been quiet that long
+    Debounce,
+    /// `@throttle`: fires at most once per window; the events in between are ignored
+    Throttle,
+}
+
+impl TimingKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimingKind::Debounce => "debounce",
+            TimingKind::Throttle => "throttle",
+        }
+    }
+}
+
+impl TimingConstraint {
+    pub fn debounce(window_ms: u32) -> Self {
+        Self { kind: TimingKind::Debounce, window_ms }
+    }
+
+    pub fn throttle(window_ms: u32) -> Self {
+        Self { kind: TimingKind::Throttle, window_ms }
+    }
+
+    /// The window as written: whole seconds in `s`, anything else in `ms`
+    pub fn window(&self) -> String {
+        match self.window_ms {
+            ms if ms > 0 && ms % 1000 == 0 => format!("{}s", ms / 1000),
+            ms => format!("{}ms", ms),
+        }
+    }
+
+    /// How the diagram shows it: `⏱debounce 50ms`
+    pub fn glyph(&self) -> String {
+        format!("⏱{} {}", self.kind.as_str(), self.window())
+    }
+}
+
+impl fmt::Display for TimingConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "@{}({})", self.kind.as_str(), self.window())
+    }
+}
+
+/// What a timer action does to its timer (see [`Action::timer_op`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerOp {
+    /// (Re)start it with its full duration
+    Start,
+    Stop,
+}
+
 // ============================================================================
 // CHOICE/DECISION POINTS
 // ============================================================================
@@ -385,6 +1622,9 @@ pub struct ChoicePoint {
     pub branches: Vec<ChoiceBranch>,
     /// Visual position
     pub position: Option<(f32, f32)>,
+    /// Source line of the `choice` definition
+    #[serde(default)]
+    pub line: Option<usize>,
 }
 
 impl ChoicePoint {
@@ -393,6 +1633,7 @@ impl ChoicePoint {
             name: name.into(),
             branches: Vec::new(),
             position: None,
+            line: None,
         }
     }
     
@@ -400,16 +1641,16 @@ impl ChoicePoint {
         self.branches.push(ChoiceBranch {
             guard: Guard::new(guard),
             target: target.into(),
-            action: None,
+            actions: Vec::new(),
         });
         self
     }
     
     pub fn add_else(mut self, target: impl Into<String>) -> Self {
         self.branches.push(ChoiceBranch {
-            guard: Guard::new("else"),
+            guard: Guard::new(Guard::ELSE),
             target: target.into(),
-            action: None,
+            actions: Vec::new(),
         });
         self
     }
@@ -422,6 +1663,44 @@ pub struct ChoiceBranch {
     pub guard: Guard,
     /// Target state
     pub target: String,
-    /// Optional action to execute
-    pub action: Option<Action>,
+    /// Actions to execute, in order
+    pub actions: Vec<Action>,
+}
+
+// ============================================================================
+// NOTES AND LAYOUT HINTS
+// ============================================================================
+
+/// A free-text note shown next to a state in the diagram
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Note {
+    /// State the note is attached to
+    pub target_state: String,
+    /// Side of the state the note is drawn on
+    pub position: NotePosition,
+    /// Note text (may span several lines)
+    pub text: String,
+    /// Source line of the `note`
+    #[serde(default)]
+    pub line: Option<usize>,
+}
+
+/// Side of a state a note is drawn on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotePosition {
+    Left,
+    Right,
+}
+
+/// Preferred direction of the diagram's main flow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LayoutHint {
+    /// Top to bottom
+    TB,
+    /// Bottom to top
+    BT,
+    /// Left to right
+    LR,
+    /// Right to left
+    RL,
 }
@@ -0,0 +1,487 @@
+//! Identifier Naming
+//! How DSL names become Rust identifiers in generated code (PascalCase enum
+//! variants, snake_case trait methods), checks that the results compile, and
+//! sanitization for names that don't. Names may be any Unicode identifier
+//! (`Überwachung`); a method named after a keyword is emitted as a raw identifier
+//! (`r#match`).
+
+use std::collections::{HashMap, HashSet};
+
+use thiserror::Error;
+
+use super::{Action, FsmDefinition, Guard, Transition};
+
+/// Strict and reserved keywords of the 2021 edition
+pub const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn",
+    "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self",
+    "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while", "abstract", "become",
+    "box", "do", "final", "macro", "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+/// Keywords that cannot be written as raw identifiers either
+const NON_RAW_KEYWORDS: &[&str] = &["crate", "self", "Self", "super"];
+
+/// What a DSL name becomes in the generated code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NameKind {
+    /// Prefix of the generated types (`{name}State`, `{name}Actions`, ...)
+    Fsm,
+    /// Variant of the state enum
+    State,
+    /// Variant of the event enum
+    Event,
+    /// Method of the actions trait
+    Action,
+    /// `-> bool` method of the actions trait
+    Guard,
+}
+
+impl NameKind {
+    /// Identifier emitted for `name`
+    pub fn convert(self, name: &str) -> String {
+        match self {
+            NameKind::Fsm => name.to_string(),
+            NameKind::State | NameKind::Event => to_pascal_case(name),
+            NameKind::Action | NameKind::Guard => to_snake_case(name),
+        }
+    }
+
+    /// Whether `name` converts to an identifier the generated code can use
+    pub fn accepts(self, name: &str) -> bool {
+        match self {
+            // Only ever emitted with a suffix, so keywords are fine
+            NameKind::Fsm => is_identifier_syntax(name),
+            // Methods named after keywords are emitted raw (`r#match`)
+            NameKind::Action | NameKind::Guard => is_identifier_syntax(name) && can_be_raw(&self.convert(name)),
+            NameKind::State | NameKind::Event => is_identifier_syntax(name) && is_rust_identifier(&self.convert(name)),
+        }
+    }
+
+    /// Replacement DSL name for one `accepts` rejects
+    pub fn sanitize(self, name: &str) -> String {
+        match self {
+            NameKind::Fsm => to_pascal_case(&sanitize_ident(name)),
+            _ => sanitize_ident(name),
+        }
+    }
+}
+
+impl std::fmt::Display for NameKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NameKind::Fsm => write!(f, "FSM"),
+            NameKind::State => write!(f, "state"),
+            NameKind::Event => write!(f, "event"),
+            NameKind::Action => write!(f, "action"),
+            NameKind::Guard => write!(f, "guard"),
+        }
+    }
+}
+
+/// A name that cannot be emitted as written
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum NameError {
+    #[error("{kind} `{name}`{} becomes `{converted}`, which is not a valid Rust identifier (try `{suggestion}`)", at_line(*.line))]
+    Invalid {
+        kind: NameKind,
+        name: String,
+        converted: String,
+        suggestion: String,
+        line: Option<usize>,
+    },
+    /// `Self` as a variant, `self` as a method: keywords that have no raw form
+    #[error("{kind} `{name}`{} becomes the Rust keyword `{converted}`, which cannot be used even as a raw identifier (try `{suggestion}`)", at_line(*.line))]
+    Keyword {
+        kind: NameKind,
+        name: String,
+        converted: String,
+        suggestion: String,
+        line: Option<usize>,
+    },
+    #[error("{kind}s {} all become `{identifier}`", quoted(names))]
+    Collision {
+        kind: NameKind,
+        identifier: String,
+        names: Vec<String>,
+    },
+}
+
+/// A name replaced by `sanitize_ident` in lenient mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rename {
+    pub kind: NameKind,
+    /// Name as written in the DSL
+    pub from: String,
+    /// Sanitized DSL name
+    pub to: String,
+    /// First line the name appears on
+    pub line: Option<usize>,
+}
+
+impl std::fmt::Display for Rename {
+    /// `turn on LED → turn_on_led`, showing the emitted identifier
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} → {}", self.kind, self.from, self.kind.convert(&self.to))
+    }
+}
+
+fn at_line(line: Option<usize>) -> String {
+    line.map(|l| format!(" (line {})", l)).unwrap_or_default()
+}
+
+fn quoted(names: &[String]) -> String {
+    let names: Vec<String> = names.iter().map(|n| format!("`{}`", n)).collect();
+    names.join(", ")
+}
+
+/// Words of a name in their original case. Words end at `_`, `-` and whitespace,
+/// and a new one starts at a capital after a lowercase letter or digit (`ledOn`,
+/// `Motor2Controller`) or at the last capital of an acronym (`HTTPServer`). Digits
+/// stay with the word before them; other characters are kept as they are.
+pub fn split_words(name: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = name.char_indices().collect();
+    let mut words = Vec::new();
+    let mut start: Option<usize> = None;
+    for (i, &(offset, c)) in chars.iter().enumerate() {
+        if c == '_' || c == '-' || c.is_whitespace() {
+            words.extend(start.take().map(|start| &name[start..offset]));
+            continue;
+        }
+        let prev = i.checked_sub(1).map(|p| chars[p].1);
+        let next = chars.get(i + 1).map(|&(_, c)| c);
+        let boundary = c.is_uppercase()
+            && prev.is_some_and(|p| {
+                p.is_lowercase() || p.is_ascii_digit() || (p.is_uppercase() && next.is_some_and(char::is_lowercase))
+            });
+        if boundary {
+            words.extend(start.take().map(|start| &name[start..offset]));
+        }
+        start.get_or_insert(offset);
+    }
+    words.extend(start.map(|start| &name[start..]));
+    words
+}
+
+/// Any name → `PascalCase` (enum variants): each word of [`split_words`] starts with
+/// a capital and keeps the rest of its letters, so `HTTPServer` stays as it is and
+/// `http_server` becomes `HttpServer`. A word starting with a digit is joined as it
+/// is (`motor_2` → `Motor2`), PascalCase has no way to mark that boundary.
+pub fn to_pascal_case(s: &str) -> String {
+    split_words(s)
+        .into_iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                None => String::new(),
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+            }
+        })
+        .collect()
+}
+
+/// Any name → `snake_case` (trait methods, file names): the words of
+/// [`split_words`] lowercased and joined with `_` (`IOManager` → `io_manager`,
+/// `HTTPServer2` → `http_server2`, `ledOn` → `led_on`). Leading underscores are
+/// kept. The result converts back and forth unchanged:
+/// `to_snake_case(&to_pascal_case(&snake)) == snake` unless a word starts with a digit.
+pub fn to_snake_case(s: &str) -> String {
+    let underscores = s.len() - s.trim_start_matches('_').len();
+    let words: Vec<String> = split_words(s).iter().map(|word| word.to_lowercase()).collect();
+    format!("{}{}", &s[..underscores], words.join("_"))
+}
+
+/// Unicode identifier syntax (XID start or `_`, then XID continue), not `_` alone
+fn is_identifier_syntax(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| unicode_ident::is_xid_start(c) || c == '_')
+        && chars.all(unicode_ident::is_xid_continue)
+        && s != "_"
+}
+
+/// Whether `s` is a strict or reserved Rust keyword
+pub fn is_rust_keyword(s: &str) -> bool {
+    RUST_KEYWORDS.contains(&s)
+}
+
+/// Whether `s` can be used as-is as a Rust identifier (keywords and `_` cannot)
+pub fn is_rust_identifier(s: &str) -> bool {
+    is_identifier_syntax(s) && !is_rust_keyword(s)
+}
+
+/// Whether `s` is a Rust identifier, written raw if it is a keyword
+fn can_be_raw(s: &str) -> bool {
+    is_identifier_syntax(s) && !NON_RAW_KEYWORDS.contains(&s)
+}
+
+/// `ident` as written in Rust code: a keyword becomes a raw identifier (`r#match`).
+/// The keywords without a raw form are left as they are; `check_names` rejects them.
+pub fn rust_ident(ident: &str) -> String {
+    match is_rust_keyword(ident) && !NON_RAW_KEYWORDS.contains(&ident) {
+        true => format!("r#{}", ident),
+        false => ident.to_string(),
+    }
+}
+
+/// ASCII spelling of a Latin letter with diacritics (`ü` → `ue`, `é` → `e`)
+fn transliterate(c: char) -> Option<&'static str> {
+    Some(match c {
+        'ä' => "ae",
+        'ö' => "oe",
+        'ü' => "ue",
+        'Ä' => "Ae",
+        'Ö' => "Oe",
+        'Ü' => "Ue",
+        'ß' => "ss",
+        'æ' => "ae",
+        'Æ' => "Ae",
+        'ø' => "o",
+        'Ø' => "O",
+        'œ' => "oe",
+        'Œ' => "Oe",
+        'à' | 'á' | 'â' | 'ã' | 'å' | 'ā' | 'ą' => "a",
+        'À' | 'Á' | 'Â' | 'Ã' | 'Å' | 'Ā' | 'Ą' => "A",
+        'ç' | 'ć' | 'č' => "c",
+        'Ç' | 'Ć' | 'Č' => "C",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ę' | 'ě' => "e",
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ę' | 'Ě' => "E",
+        'ì' | 'í' | 'î' | 'ï' => "i",
+        'Ì' | 'Í' | 'Î' | 'Ï' => "I",
+        'ñ' | 'ń' | 'ň' => "n",
+        'Ñ' | 'Ń' | 'Ň' => "N",
+        'ò' | 'ó' | 'ô' | 'õ' => "o",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' => "O",
+        'ù' | 'ú' | 'û' | 'ů' => "u",
+        'Ù' | 'Ú' | 'Û' | 'Ů' => "U",
+        'ý' | 'ÿ' => "y",
+        'Ý' => "Y",
+        'ł' => "l",
+        'Ł' => "L",
+        'ś' | 'š' => "s",
+        'Ś' | 'Š' => "S",
+        'ź' | 'ż' | 'ž' => "z",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        'ř' => "r",
+        'Ř' => "R",
+        _ => return None,
+    })
+}
+
+/// A snake_case identifier for any name: Latin letters with diacritics are
+/// transliterated (`Überwachung` → `ueberwachung`), words are split at other
+/// non-alphanumeric characters and case changes, lowercased and joined with `_`
+/// (`turn on LED` → `turn_on_led`, `attempts > 3` → `attempts_3`). Letters of
+/// other scripts are kept. A leading digit gets an `n_` prefix and a keyword an
+/// `r_` prefix, so the result is valid in both PascalCase and snake_case.
+pub fn sanitize_ident(name: &str) -> String {
+    let mut spaced = String::new();
+    for c in name.chars() {
+        match transliterate(c) {
+            Some(ascii) => spaced.push_str(ascii),
+            None if c.is_alphanumeric() && unicode_ident::is_xid_continue(c) => spaced.push(c),
+            None => spaced.push(' '),
+        }
+    }
+    let words: Vec<String> = split_words(&spaced).iter().map(|word| word.to_lowercase()).collect();
+
+    let ident = words.join("_");
+    match ident.chars().next() {
+        None => "unnamed".to_string(),
+        Some(c) if !unicode_ident::is_xid_start(c) => format!("n_{}", ident),
+        _ if !is_rust_identifier(&ident) || !is_rust_identifier(&to_pascal_case(&ident)) => format!("r_{}", ident),
+        _ => ident,
+    }
+}
+
+/// Every name in the FSM that ends up in generated code, in order of first use,
+/// with the line it first appears on
+fn named_items(fsm: &FsmDefinition) -> Vec<(NameKind, String, Option<usize>)> {
+    let mut items = vec![(NameKind::Fsm, fsm.name.clone(), fsm.line)];
+    let mut push = |kind: NameKind, name: &str, line: Option<usize>| items.push((kind, name.to_string(), line));
+    fsm.initial_transitions.iter().flat_map(|t| &t.actions).for_each(|a| push(NameKind::Action, &a.name, fsm.line));
+    fsm.events.iter().for_each(|e| push(NameKind::Event, &e.name, e.line));
+    for state in &fsm.states {
+        push(NameKind::State, &state.name, state.line);
+        let actions = state.entry_actions.iter().chain(&state.exit_actions);
+        actions.for_each(|a| push(NameKind::Action, &a.name, state.line));
+        for t in &state.internal_transitions {
+            transition_items(&mut push, t);
+        }
+    }
+    for t in &fsm.transitions {
+        transition_items(&mut push, t);
+    }
+    for timer in &fsm.timers {
+        push(NameKind::Event, &timer.event.name, None);
+    }
+    // A named guard is a guard method too, and so is each method its definition uses
+    for named in &fsm.guards {
+        push(NameKind::Guard, &named.name, named.line);
+        for term in Guard::new(named.expression.as_str()).terms() {
+            push(NameKind::Guard, &term.method(), named.line);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    items.retain(|(kind, name, _)| seen.insert((*kind, name.clone())));
+    items
+}
+
+fn transition_items(push: &mut impl FnMut(NameKind, &str, Option<usize>), t: &Transition) {
+    if let Some(ref event) = t.event {
+        push(NameKind::Event, &event.name, t.line);
+    }
+    for term in t.guard.iter().filter(|g| !g.is_else()).flat_map(|g| g.terms()) {
+        push(NameKind::Guard, &term.method(), t.line);
+    }
+    t.actions.iter().for_each(|a| push(NameKind::Action, &a.name, t.line));
+}
+
+/// Check that every name converts to a usable identifier and that no two names
+/// of a kind convert to the same one.
+///
+/// With `lenient`, unusable names are sanitized instead of reported; the
+/// replacements are returned for `apply_renames`. Collisions (including ones
+/// created by sanitizing) are errors either way.
+pub fn check_names(fsm: &FsmDefinition, lenient: bool) -> Result<Vec<Rename>, Vec<NameError>> {
+    let mut errors = Vec::new();
+    let mut renames = Vec::new();
+    let mut emitted: Vec<(NameKind, String, Vec<String>)> = Vec::new();
+
+    for (kind, name, line) in named_items(fsm) {
+        let name = name.as_str();
+        let mut identifier = kind.convert(name);
+        if !kind.accepts(name) {
+            let sanitized = kind.sanitize(name);
+            if lenient {
+                identifier = kind.convert(&sanitized);
+                renames.push(Rename {
+                    kind,
+                    from: name.to_string(),
+                    to: sanitized,
+                    line,
+                });
+            } else {
+                let (name, converted, suggestion) = (name.to_string(), identifier.clone(), sanitized);
+                errors.push(match is_rust_keyword(&identifier) {
+                    true => NameError::Keyword { kind, name, converted, suggestion, line },
+                    false => NameError::Invalid { kind, name, converted, suggestion, line },
+                });
+                continue;
+            }
+        }
+        match emitted.iter_mut().find(|(k, id, _)| *k == kind && *id == identifier) {
+            Some((_, _, names)) => names.push(name.to_string()),
+            None => emitted.push((kind, identifier, vec![name.to_string()])),
+        }
+    }
+
+    errors.extend(
+        emitted
+            .into_iter()
+            .filter(|(_, _, names)| names.len() > 1)
+            .map(|(kind, identifier, names)| NameError::Collision { kind, identifier, names }),
+    );
+    if errors.is_empty() {
+        Ok(renames)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Copy of `fsm` with every `rename` applied wherever the name is used
+pub fn apply_renames(fsm: &FsmDefinition, renames: &[Rename]) -> FsmDefinition {
+    let lookup: HashMap<(NameKind, &str), &str> =
+        renames.iter().map(|r| ((r.kind, r.from.as_str()), r.to.as_str())).collect();
+    let rename = |kind: NameKind, name: &mut String| {
+        if let Some(to) = lookup.get(&(kind, name.as_str())) {
+            *name = to.to_string();
+        }
+    };
+    // Choice point targets are `<<Name>>`, not states
+    let rename_target = |name: &mut String| {
+        if !name.starts_with("<<") {
+            rename(NameKind::State, name);
+        }
+    };
+    let rename_actions = |actions: &mut Vec<Action>| {
+        for action in actions {
+            rename(NameKind::Action, &mut action.name);
+        }
+    };
+    // Only the method of a call like `speed_ok(rpm)` is renamed, not its arguments (nor
+    // a comparison, whose method is made from it); a composed expression is rewritten
+    // only if one of its terms is
+    let rename_guard = |guard: &mut Guard| {
+        let mut renamed = false;
+        let parsed = guard.parsed().map(&mut |term| {
+            let term = Guard::new(term.as_str());
+            if term.comparison().is_some() {
+                return term.expression;
+            }
+            let mut method = term.method().to_string();
+            rename(NameKind::Guard, &mut method);
+            renamed |= method != term.method();
+            term.expression.replacen(&*term.method(), &method, 1)
+        });
+        if renamed {
+            guard.expression = parsed.to_string();
+        }
+    };
+    let rename_transition = |t: &mut Transition| {
+        rename_target(&mut t.source);
+        rename_target(&mut t.target);
+        if let Some(ref mut event) = t.event {
+            rename(NameKind::Event, &mut event.name);
+        }
+        if let Some(guard) = t.guard.as_mut().filter(|g| !g.is_else()) {
+            rename_guard(guard);
+        }
+        rename_actions(&mut t.actions);
+    };
+
+    let mut fsm = fsm.clone();
+    rename(NameKind::Fsm, &mut fsm.name);
+    for initial in &mut fsm.initial_transitions {
+        rename(NameKind::State, &mut initial.target);
+        if let Some(guard) = initial.guard.as_mut() {
+            rename_guard(guard);
+        }
+        rename_actions(&mut initial.actions);
+    }
+    for event in &mut fsm.events {
+        rename(NameKind::Event, &mut event.name);
+    }
+    for state in &mut fsm.states {
+        rename(NameKind::State, &mut state.name);
+        rename_actions(&mut state.entry_actions);
+        rename_actions(&mut state.exit_actions);
+        state.internal_transitions.iter_mut().for_each(rename_transition);
+    }
+    fsm.transitions.iter_mut().for_each(rename_transition);
+    for choice in &mut fsm.choice_points {
+        for branch in &mut choice.branches {
+            rename_target(&mut branch.target);
+            rename_actions(&mut branch.actions);
+            if !branch.guard.is_else() {
+                rename_guard(&mut branch.guard);
+            }
+        }
+    }
+    for named in &mut fsm.guards {
+        rename(NameKind::Guard, &mut named.name);
+        let mut guard = Guard::new(named.expression.as_str());
+        rename_guard(&mut guard);
+        named.expression = guard.expression;
+    }
+    for timer in &mut fsm.timers {
+        rename(NameKind::Event, &mut timer.event.name);
+        if let Some(ref mut state) = timer.auto_start_state {
+            rename(NameKind::State, state);
+        }
+    }
+    for note in &mut fsm.notes {
+        rename(NameKind::State, &mut note.target_state);
+    }
+    fsm
+}
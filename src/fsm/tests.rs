@@ -1,12 +1,19 @@
 //! Unit tests for the FSM data structures
 
-use crate::fsm::{FsmDefinition, State, StateType, Transition, Event, Guard, Action};
+use std::cell::Cell;
+
+use crate::fsm::interpreter::{guard_expressions, ActionOrder, Firing, Interpreter, StepResult};
+use crate::fsm::matrix::EventHandling;
+use crate::fsm::naming::{
+    apply_renames, check_names, is_rust_identifier, rust_ident, sanitize_ident, split_words, to_pascal_case, to_snake_case, NameError, NameKind,
+};
+use crate::fsm::{completion_cycles, event_matrix, livelock_cycles, FsmDefinition, State, StateType, Transition, Event, Guard, Action, ValidationOptions};
 
 #[test]
 fn test_fsm_definition_new() {
     let fsm = FsmDefinition::new("TestMachine");
     assert_eq!(fsm.name, "TestMachine");
-    assert!(fsm.initial_state.is_none());
+    assert!(fsm.initial_state().is_none());
     assert!(fsm.states.is_empty());
     assert!(fsm.transitions.is_empty());
 }
@@ -24,44 +31,25 @@ fn test_state_new() {
 #[test]
 fn test_transition_label() {
     // Transition with event only
-    let t1 = Transition {
-        source: "A".to_string(),
-        target: "B".to_string(),
-        event: Some(Event { name: "Click".to_string() }),
-        guard: None,
-        action: None,
-        kind: crate::fsm::TransitionKind::External,
-    };
+    let t1 = Transition::new("A", "B").with_event(Event::new("Click"));
     assert!(t1.label().contains("Click"));
     
     // Transition with guard
-    let t2 = Transition {
-        source: "A".to_string(),
-        target: "B".to_string(),
-        event: Some(Event { name: "Submit".to_string() }),
-        guard: Some(Guard { expression: "is_valid".to_string() }),
-        action: None,
-        kind: crate::fsm::TransitionKind::External,
-    };
+    let t2 = Transition::new("A", "B").with_event(Event::new("Submit")).with_guard(Guard::new("is_valid"));
     assert!(t2.label().contains("Submit"));
     assert!(t2.label().contains("[is_valid]"));
     
     // Transition with action
-    let t3 = Transition {
-        source: "A".to_string(),
-        target: "B".to_string(),
-        event: Some(Event { name: "Go".to_string() }),
-        guard: None,
-        action: Some(Action { name: "do_it".to_string(), params: vec![] }),
-        kind: crate::fsm::TransitionKind::External,
-    };
+    let t3 = Transition::new("A", "B").with_event(Event::new("Go")).with_action(Action::new("do_it"));
     assert!(t3.label().contains("Go"));
     assert!(t3.label().contains("do_it"));
 }
 
 #[test]
 fn test_fsm_validation_no_initial_state() {
-    let fsm = FsmDefinition::new("Test");
+    let mut fsm = FsmDefinition::new("Test");
+    fsm.states.push(State::new("A", StateType::Simple));
+    fsm.transitions.push(Transition::new("A", "A"));
     let result = fsm.validate();
     assert!(result.is_err());
     let errors = result.unwrap_err();
@@ -71,7 +59,7 @@ fn test_fsm_validation_no_initial_state() {
 #[test]
 fn test_fsm_validation_missing_state() {
     let mut fsm = FsmDefinition::new("Test");
-    fsm.initial_state = Some("NonExistent".to_string());
+    fsm.set_initial_state("NonExistent".to_string());
     
     let result = fsm.validate();
     assert!(result.is_err());
@@ -79,20 +67,98 @@ fn test_fsm_validation_missing_state() {
     assert!(errors.iter().any(|e| e.contains("NonExistent")));
 }
 
+#[test]
+fn test_transitions_differing_only_in_actions_warn() {
+    let source = "fsm M {\n    [*] --> Idle\n    Idle --> Active : start [ready] / spin_up\n    Active --> Idle : stop\n    Idle --> Active : start [ready] / beep\n}\n";
+    let mut fsm = crate::parser::parse_fsm(source).unwrap().remove(0);
+    assert_eq!(fsm.transitions.len(), 3);
+    assert_eq!(
+        fsm.warnings(),
+        ["Transitions 'Idle --> Active : start [ready]' differ only in their actions (lines 3, 5): which actions run is ambiguous"]
+    );
+    assert!(!fsm.infos().iter().any(|info| info.starts_with("Duplicate transition")));
+
+    // The pass itself leaves them alone and is idempotent
+    fsm.merge_duplicate_transitions();
+    fsm.merge_duplicate_transitions();
+    assert_eq!(fsm.transitions.len(), 3);
+    assert_eq!(fsm.ambiguous_transitions().len(), 1);
+}
+
+#[test]
+fn test_duplicate_and_implicit_state_diagnostics() {
+    let source = "fsm M {\n    [*] --> Active\n    state Active\n    Active --> Activ : stop\n    state Active\n}\n";
+    let fsm = &crate::parser::parse_fsm(source).unwrap()[0];
+
+    assert_eq!(fsm.warnings(), ["State 'Active' is declared more than once (lines 3, 5)"]);
+    assert_eq!(fsm.infos(), ["Implicitly declared states (no `state` line): Activ"]);
+    assert!(fsm.validate().is_ok());
+
+    let strict = ValidationOptions::default().with_duplicate_states_as_errors(true);
+    assert_eq!(
+        fsm.validate_with(&strict).unwrap_err(),
+        ["State 'Active' is declared more than once (lines 3, 5)"]
+    );
+}
+
+#[test]
+fn test_final_state_with_outgoing_transitions() {
+    let source = "fsm M {\n    [*] --> Idle\n    Idle --> Done : finish\n    Done --> Idle : restart\n    * --> Idle : reset\n    final state Done {\n        ping / log\n    }\n}\n";
+    let fsm = &crate::parser::parse_fsm(source).unwrap()[0];
+
+    // The wildcard does not count: it skips final states
+    assert_eq!(
+        fsm.validate().unwrap_err(),
+        ["Final state 'Done' has outgoing transitions: Done --> Idle : restart (line 4), Done --> Done : ping (internal) (line 7)"]
+    );
+}
+
+#[test]
+fn test_event_binding_errors() {
+    let source = "fsm M {\n    events {\n        set(level: u8)\n        pair(a: u8, b: u8)\n        go\n    }\n    timer t = 10 -> set\n    [*] --> Idle\n    Idle --> Idle : set(v) [ok(v)] / apply(v)\n    Idle --> Idle : set / apply(3)\n    Idle --> Idle : pair(a) / apply(a)\n    Idle --> Idle : pair(a, a)\n    Idle --> Idle : go(x)\n    Idle --> Idle : set(v) [ok(w)] / apply(v); send(Other, go); store(w)\n    Idle --> Idle : [ready(v)]\n}\n";
+    let fsm = &crate::parser::parse_fsm(source).unwrap()[0];
+
+    // Binding nothing ignores the payload; parameters without bindings keep their old meaning
+    assert_eq!(
+        fsm.validate().unwrap_err(),
+        [
+            "Idle --> Idle : pair(a) binds 1 parameter(s), but 'pair' declares 2",
+            "Idle --> Idle : pair(a, a) binds 'a' twice",
+            "Idle --> Idle : go(x) binds 1 parameter(s), but 'go' declares 0",
+            "Idle --> Idle : set(v) [ok(w)]: guard uses unknown binding 'w'",
+            "Idle --> Idle : set(v) [ok(w)]: action 'store' uses unknown binding 'w'",
+            "Idle --> Idle : [ready(v)]: guard uses unknown binding 'v'",
+            "Timer 't' fires 'set', which carries parameters",
+        ]
+    );
+}
+
+#[test]
+fn test_declared_event_diagnostics() {
+    let source = "fsm M {\n    events {\n        start\n        reset\n    }\n    [*] --> Idle\n    state Idle\n    state Busy\n    Idle --> Busy : start\n    Busy --> Idle : done\n}\n";
+    let fsm = &crate::parser::parse_fsm(source).unwrap()[0];
+
+    // Declared but unused: a warning
+    assert_eq!(fsm.warnings(), ["Event 'reset' is declared (line 4) but no transition or timer uses it"]);
+    // Used but undeclared: a note, or an error when the events block must be complete
+    assert_eq!(fsm.infos(), ["Events not in the events block: done"]);
+    assert!(fsm.validate().is_ok());
+    let strict = ValidationOptions::default().with_undeclared_events_as_errors(true);
+    assert_eq!(fsm.validate_with(&strict).unwrap_err(), ["Event 'done' is not declared in the events block"]);
+
+    // Without an events block every event is inferred and nothing is reported
+    let inferred = &crate::parser::parse_fsm("fsm M {\n    [*] --> Idle\n    state Idle\n    Idle --> Idle : start\n}\n").unwrap()[0];
+    assert!(inferred.warnings().is_empty() && inferred.infos().is_empty());
+    assert!(inferred.validate_with(&strict).is_ok());
+}
+
 #[test]
 fn test_fsm_validation_valid() {
     let mut fsm = FsmDefinition::new("Test");
-    fsm.initial_state = Some("Idle".to_string());
+    fsm.set_initial_state("Idle".to_string());
     fsm.states.push(State::new("Idle", StateType::Simple));
     fsm.states.push(State::new("Active", StateType::Simple));
-    fsm.transitions.push(Transition {
-        source: "Idle".to_string(),
-        target: "Active".to_string(),
-        event: Some(Event { name: "Start".to_string() }),
-        guard: None,
-        action: None,
-        kind: crate::fsm::TransitionKind::External,
-    });
+    fsm.transitions.push(Transition::new("Idle", "Active").with_event(Event::new("Start")));
     
     let result = fsm.validate();
     assert!(result.is_ok());
@@ -101,31 +167,1050 @@ fn test_fsm_validation_valid() {
 #[test]
 fn test_fsm_collect_events() {
     let mut fsm = FsmDefinition::new("Test");
-    fsm.transitions.push(Transition {
-        source: "A".to_string(),
-        target: "B".to_string(),
-        event: Some(Event { name: "Event1".to_string() }),
-        guard: None,
-        action: None,
-        kind: crate::fsm::TransitionKind::External,
-    });
-    fsm.transitions.push(Transition {
-        source: "B".to_string(),
-        target: "C".to_string(),
-        event: Some(Event { name: "Event2".to_string() }),
-        guard: None,
-        action: None,
-        kind: crate::fsm::TransitionKind::External,
-    });
-    fsm.transitions.push(Transition {
-        source: "C".to_string(),
-        target: "A".to_string(),
-        event: Some(Event { name: "Event1".to_string() }), // Duplicate
-        guard: None,
-        action: None,
-        kind: crate::fsm::TransitionKind::External,
-    });
+    fsm.transitions.push(Transition::new("A", "B").with_event(Event::new("Event1")));
+    fsm.transitions.push(Transition::new("B", "C").with_event(Event::new("Event2")));
+    fsm.transitions.push(Transition::new("C", "A").with_event(Event::new("Event1"))); // Duplicate
     
     let events = fsm.collect_events();
     assert_eq!(events.len(), 2); // Should be deduplicated
 }
+
+#[test]
+fn test_event_matrix_categories() {
+    let source = r#"
+        fsm Test {
+            [*] --> Idle
+            state Idle {
+                ping / reply()
+            }
+            state Busy
+            Idle --> Busy : start
+            Busy --> Idle : stop [is_done]
+        }
+    "#;
+    let fsms = crate::parser::parse_fsm(source).expect("Should parse successfully");
+    let matrix = event_matrix(&fsms[0]);
+
+    assert_eq!(matrix.events, vec!["ping", "start", "stop"]);
+    assert_eq!(matrix.get("Idle", "start").unwrap().handling, EventHandling::External);
+    assert_eq!(matrix.get("Idle", "start").unwrap().transitions, vec![0]);
+    assert_eq!(matrix.get("Idle", "ping").unwrap().handling, EventHandling::Internal);
+    assert_eq!(matrix.get("Busy", "stop").unwrap().handling, EventHandling::GuardedOnly);
+    assert_eq!(matrix.unhandled(), vec![("Idle", "stop"), ("Busy", "ping"), ("Busy", "start")]);
+}
+
+#[test]
+fn test_event_matrix_csv() {
+    let mut fsm = FsmDefinition::new("Test");
+    fsm.states.push(State::new("A", StateType::Simple));
+    fsm.states.push(State::new("B", StateType::Simple));
+    fsm.transitions.push(Transition::new("A", "B").with_event(Event::new("go")));
+
+    let csv = event_matrix(&fsm).to_csv();
+    assert_eq!(csv, "state,go\nA,external\nB,unhandled\n");
+}
+
+#[test]
+fn test_fsm_validation_else_guard() {
+    let valid = r#"
+        fsm Test {
+            [*] --> Idle
+            Idle --> Fast : go [is_urgent]
+            Idle --> Slow : go [else]
+        }
+    "#;
+    let fsms = crate::parser::parse_fsm(valid).expect("Should parse successfully");
+    assert!(fsms[0].validate().is_ok());
+    assert_eq!(event_matrix(&fsms[0]).get("Idle", "go").unwrap().handling, EventHandling::External);
+
+    let not_last = valid.replace(
+        "Idle --> Fast : go [is_urgent]\n            Idle --> Slow : go [else]",
+        "Idle --> Slow : go [else]\n            Idle --> Fast : go [is_urgent]",
+    );
+    let errors = crate::parser::parse_fsm(&not_last).unwrap()[0].validate().unwrap_err();
+    assert!(errors.iter().any(|e| e.contains("must be the last")));
+
+    let no_sibling = valid.replace("Idle --> Fast : go [is_urgent]", "");
+    let errors = crate::parser::parse_fsm(&no_sibling).unwrap()[0].validate().unwrap_err();
+    assert!(errors.iter().any(|e| e.contains("no guarded sibling")));
+}
+
+/// `Machine` with entry/exit actions and a `local` self-transition, made composite
+/// (the DSL has no nested states yet) with a `Child` substate
+fn local_machine() -> FsmDefinition {
+    let source = r#"
+        fsm Test {
+            [*] --> Machine
+            state Machine {
+                entry / power_up
+                exit / power_down
+            }
+            Machine --> Machine : local reset / clear
+            Machine --> Machine : reboot
+        }
+    "#;
+    let mut fsm = crate::parser::parse_fsm(source).unwrap().remove(0);
+    let mut sub_fsm = FsmDefinition::new("Machine_sub");
+    sub_fsm.states.push(State::new("Child", StateType::Simple));
+    fsm.states[0].state_type = StateType::Composite;
+    fsm.states[0].sub_fsm = Some(sub_fsm);
+    fsm
+}
+
+/// A composite state's sub-FSM has an initial state of its own
+#[test]
+fn test_nested_initial_state_is_not_a_duplicate() {
+    let mut fsm = local_machine();
+    let sub_fsm = crate::parser::parse_fsm("fsm Machine_sub {\n [*] --> Child\n}").unwrap().remove(0);
+    fsm.states[0].sub_fsm = Some(sub_fsm);
+    assert_eq!(fsm.initial_transitions.len(), 1);
+    assert!(fsm.validate().is_ok());
+    assert!(fsm.states[0].sub_fsm.as_ref().unwrap().validate().is_ok());
+}
+
+#[test]
+fn test_local_transitions() {
+    let fsm = local_machine();
+    assert!(fsm.transitions[0].is_local());
+    assert!(fsm.validate().is_ok());
+
+    // Local transitions skip the composite's exit and entry actions, external ones re-run them
+    let no_guards = |_: &str| false;
+    let local = fired(crate::fsm::interpreter::dispatch(&fsm, "Machine", "reset", &no_guards));
+    assert_eq!(action_names(&local), ["clear"]);
+    let external = fired(crate::fsm::interpreter::dispatch(&fsm, "Machine", "reboot", &no_guards));
+    assert_eq!(action_names(&external), ["power_down", "power_up"]);
+
+    // Into a substate is fine, out of the composite or from a simple state is not
+    let mut fsm = local_machine();
+    fsm.transitions[0].target = "Child".to_string();
+    assert!(fsm.validate().is_ok());
+    fsm.transitions[0].target = "Elsewhere".to_string();
+    let errors = fsm.validate().unwrap_err();
+    assert!(errors.contains(&"Local transition 'Machine' --> 'Elsewhere' needs a composite source containing its target".to_string()));
+
+    let mut fsm = local_machine();
+    fsm.states[0].state_type = StateType::Simple;
+    assert_eq!(fsm.validate().unwrap_err().len(), 1);
+}
+
+/// FSM with the given initial state and `source -> target` transitions
+fn graph(initial: &str, edges: &[(&str, &str)]) -> FsmDefinition {
+    let mut fsm = FsmDefinition::new("Test");
+    fsm.set_initial_state(initial.to_string());
+    fsm.states.push(State::new(initial, StateType::Simple));
+    for (source, target) in edges {
+        for name in [source, target] {
+            if *name != "[*]" && !fsm.states.iter().any(|s| s.name == *name) {
+                fsm.states.push(State::new(*name, StateType::Simple));
+            }
+        }
+        fsm.transitions.push(Transition::new(*source, *target).with_event(Event::new("ev")));
+    }
+    fsm
+}
+
+#[test]
+fn test_livelock_cycle_with_exit() {
+    let fsm = graph("Idle", &[("Idle", "Error"), ("Error", "Retry"), ("Retry", "Error"), ("Retry", "Idle")]);
+    assert!(livelock_cycles(&fsm).is_empty());
+
+    // The main loop through the initial state is not a livelock
+    let fsm = graph("Red", &[("Red", "Green"), ("Green", "Yellow"), ("Yellow", "Red")]);
+    assert!(livelock_cycles(&fsm).is_empty());
+}
+
+#[test]
+fn test_livelock_cycle_without_exit() {
+    let fsm = graph("Idle", &[("Idle", "Error"), ("Error", "Retry"), ("Retry", "Error")]);
+    assert_eq!(livelock_cycles(&fsm), vec![vec!["Error".to_string(), "Retry".to_string()]]);
+    assert_eq!(fsm.warnings(), vec!["Possible livelock: no transition leaves {Error, Retry}".to_string()]);
+
+    // A self-loop-only state, and the same cycle with an exit to a final state
+    let fsm = graph("Idle", &[("Idle", "Stuck"), ("Stuck", "Stuck")]);
+    assert_eq!(livelock_cycles(&fsm), vec![vec!["Stuck".to_string()]]);
+    let fsm = graph("Idle", &[("Idle", "Error"), ("Error", "Retry"), ("Retry", "Error"), ("Retry", "[*]")]);
+    assert!(livelock_cycles(&fsm).is_empty());
+}
+
+#[test]
+fn test_livelock_nested_cycles() {
+    // A <-> B and B <-> C form one component; reported once, with all three states
+    let edges = [("Idle", "A"), ("A", "B"), ("B", "A"), ("B", "C"), ("C", "B")];
+    let fsm = graph("Idle", &edges);
+    assert_eq!(
+        livelock_cycles(&fsm),
+        vec![vec!["A".to_string(), "B".to_string(), "C".to_string()]]
+    );
+
+    let mut with_exit = edges.to_vec();
+    with_exit.push(("C", "Done"));
+    assert!(livelock_cycles(&graph("Idle", &with_exit)).is_empty());
+
+    // Unreachable cycles are dead code, not livelocks
+    let fsm = graph("Idle", &[("X", "Y"), ("Y", "X")]);
+    assert!(livelock_cycles(&fsm).is_empty());
+}
+
+#[test]
+fn test_transition_ids_survive_reordering() {
+    let before = &crate::parser::parse_fsm(
+        "fsm Door {\n [*] --> Locked\n Locked --> Open : unlock\n Open --> Locked : close / lock\n Open --> Open : tick\n Open --> Open : tick\n}",
+    )
+    .unwrap()[0];
+    let after = &crate::parser::parse_fsm(
+        "fsm Door {\n [*] --> Locked\n Open --> Open : tick\n Open --> Locked : close / lock; beep\n Locked --> Open : unlock\n}",
+    )
+    .unwrap()[0];
+
+    let ids = before.transition_ids();
+    assert_eq!(ids.len(), 3, "the repeated tick is merged");
+    // Unchanged transitions are found at their new positions, the edited one is gone
+    assert_eq!(after.transition_index(ids[0]), Some(2));
+    assert_eq!(after.transition_index(ids[1]), None);
+    assert_eq!(after.transition_index(ids[2]), Some(0));
+    assert_eq!(after.transition_id(2), Some(ids[0]));
+
+    // Identical transitions built in code still get an id each
+    let mut fsm = FsmDefinition::new("Twice");
+    fsm.transitions.push(Transition::new("A", "B"));
+    fsm.transitions.push(Transition::new("A", "B"));
+    let ids = fsm.transition_ids();
+    assert_ne!(ids[0], ids[1]);
+    assert_eq!(fsm.transition_index(ids[1]), Some(1));
+}
+
+// ============================================================================
+// INTERPRETER
+// ============================================================================
+
+/// The machine of examples/door_lock_test.rs: refused commands are internal
+/// transitions that only play the error sound
+const DOOR: &str = r#"
+    fsm Door {
+        [*] --> Locked
+        state Locked {
+            entry / on_enter_locked
+            exit / on_exit_locked
+            Unlock / play_error_sound
+        }
+        state Unlocked {
+            entry / on_enter_unlocked
+            exit / on_exit_unlocked
+        }
+        state Open {
+            entry / on_enter_open
+            exit / on_exit_open
+            Lock / play_error_sound
+        }
+        Locked --> Unlocked : Unlock [is_valid_code] / play_success_sound; log_access
+        Unlocked --> Locked : Lock
+        Unlocked --> Open : Open
+        Open --> Unlocked : Close
+        Open --> Locked : Lock [is_closed]
+    }
+"#;
+
+fn fired(result: StepResult) -> Firing {
+    match result {
+        StepResult::Fired(firing) => firing,
+        other => panic!("expected a transition, got {other:?}"),
+    }
+}
+
+fn action_names(firing: &Firing) -> Vec<String> {
+    firing.actions.iter().map(|a| a.name.clone()).collect()
+}
+
+#[test]
+fn test_interpreter_door_guards_and_action_order() {
+    let fsm = &crate::parser::parse_fsm(DOOR).unwrap()[0];
+    let valid_code = Cell::new(false);
+    let door_closed = Cell::new(false);
+    let mut door = Interpreter::new(fsm, |guard: &str| match guard {
+        "is_valid_code" => valid_code.get(),
+        "is_closed" => door_closed.get(),
+        other => panic!("unexpected guard {other}"),
+    });
+    assert_eq!(door.current_state(), Some("Locked"));
+    let entry: Vec<&str> = door.reset().iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(entry, ["on_enter_locked"]);
+
+    // Invalid code: no transition, only the error sound
+    let refused = fired(door.send("Unlock"));
+    assert!(refused.is_internal());
+    assert_eq!(action_names(&refused), ["play_error_sound"]);
+    assert_eq!(refused.guards, [("is_valid_code", false)]);
+    assert_eq!(door.current_state(), Some("Locked"));
+
+    // Valid code: exit, transition actions in order, entry
+    valid_code.set(true);
+    let unlocked = fired(door.send("Unlock"));
+    assert_eq!(unlocked.transition_index, Some(0));
+    assert_eq!(
+        action_names(&unlocked),
+        ["on_exit_locked", "play_success_sound", "log_access", "on_enter_unlocked"]
+    );
+    assert_eq!(door.current_state(), Some("Unlocked"));
+
+    // Open -> Close cycle
+    assert_eq!(fired(door.send("Open")).to, "Open");
+    assert_eq!(fired(door.send("Close")).to, "Unlocked");
+    assert_eq!(fired(door.send("Open")).to, "Open");
+
+    // Cannot lock while open
+    let refused = fired(door.send("Lock"));
+    assert!(refused.is_internal());
+    assert_eq!(door.current_state(), Some("Open"));
+    door_closed.set(true);
+    let locked = fired(door.send("Lock"));
+    assert_eq!(action_names(&locked), ["on_exit_open", "on_enter_locked"]);
+    assert_eq!(door.current_state(), Some("Locked"));
+
+    // Events the state does not handle are dropped
+    assert!(matches!(door.send("Close"), StepResult::Ignored { ref event, .. } if event == "Close"));
+    assert_eq!(door.current_state(), Some("Locked"));
+}
+
+/// Runs the actions of examples/connection_manager.fsm against a fake timer service
+#[derive(Default)]
+struct Connection {
+    timers: Vec<String>,
+    log: Vec<String>,
+}
+
+impl Connection {
+    fn run(&mut self, actions: &[&Action]) {
+        for action in actions {
+            match action.name.as_str() {
+                "start_timer" => self.timers.push(action.params[0].clone()),
+                "stop_timer" => self.timers.retain(|t| *t != action.params[0]),
+                _ => self.log.push(action.name.clone()),
+            }
+        }
+    }
+
+    /// Send `event`; true if it caused a transition or internal transition
+    fn send<G: Fn(&str) -> bool>(&mut self, fsm: &mut Interpreter<G>, event: &str) -> bool {
+        match fsm.send(event) {
+            StepResult::Fired(firing) => {
+                self.run(&firing.actions);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[test]
+fn test_interpreter_connection_manager() {
+    let source = include_str!("../../examples/connection_manager.fsm");
+    let fsm = &crate::parser::parse_fsm(source).unwrap()[0];
+    let mut fsm = Interpreter::new(fsm, |_: &str| false);
+    let mut connection = Connection::default();
+    connection.run(&fsm.reset());
+    assert_eq!(fsm.current_state(), Some("Disconnected"));
+    assert_eq!(connection.log, ["reset_connection"]);
+
+    // Invalid events are ignored
+    assert!(!connection.send(&mut fsm, "Disconnect"));
+    assert!(!connection.send(&mut fsm, "ConnectionEstablished"));
+    assert_eq!(fsm.current_state(), Some("Disconnected"));
+
+    // Happy path: the connect timeout runs only while connecting
+    assert!(connection.send(&mut fsm, "Connect"));
+    assert_eq!(fsm.current_state(), Some("Connecting"));
+    assert_eq!(connection.timers, ["connect_timeout"]);
+    assert!(connection.send(&mut fsm, "ConnectionEstablished"));
+    assert_eq!(fsm.current_state(), Some("Connected"));
+    assert_eq!(connection.timers, ["keepalive"]);
+
+    // Keepalive is an internal transition: no exit/entry, so the timer keeps running
+    for _ in 0..5 {
+        assert!(connection.send(&mut fsm, "KeepaliveTick"));
+        assert_eq!(fsm.current_state(), Some("Connected"));
+    }
+    assert_eq!(connection.log.iter().filter(|a| *a == "send_keepalive").count(), 5);
+    assert_eq!(connection.timers, ["keepalive"]);
+
+    // Three reconnection cycles
+    for cycle in 0..3 {
+        assert!(connection.send(&mut fsm, "ConnectionLost"));
+        assert_eq!(fsm.current_state(), Some("Reconnecting"), "cycle {cycle}");
+        assert_eq!(connection.timers, ["reconnect_delay"]);
+        assert!(connection.send(&mut fsm, "ReconnectTimer"));
+        assert_eq!(fsm.current_state(), Some("Connecting"), "cycle {cycle}");
+        assert!(connection.send(&mut fsm, "ConnectionEstablished"));
+        assert_eq!(fsm.current_state(), Some("Connected"), "cycle {cycle}");
+    }
+    assert_eq!(connection.log.iter().filter(|a| *a == "on_disconnected").count(), 3);
+
+    // Graceful disconnect
+    assert!(connection.send(&mut fsm, "Disconnect"));
+    assert_eq!(fsm.current_state(), Some("Disconnected"));
+    assert!(connection.timers.is_empty());
+    assert!(connection.log.ends_with(&["graceful_close".to_string(), "reset_connection".to_string()]));
+
+    // Timeout, failure and cancel all return to Disconnected with no timer left
+    for (event, logged) in [("ConnectTimeout", Some("log_timeout")), ("ConnectionFailed", Some("log_failure")), ("Cancel", None)] {
+        assert!(connection.send(&mut fsm, "Connect"));
+        assert!(connection.send(&mut fsm, event));
+        assert_eq!(fsm.current_state(), Some("Disconnected"), "{event}");
+        assert!(connection.timers.is_empty(), "{event}");
+        if let Some(logged) = logged {
+            assert!(connection.log.iter().any(|a| a == logged));
+        }
+    }
+
+    // Cancel while reconnecting
+    for event in ["Connect", "ConnectionEstablished", "ConnectionLost", "Cancel"] {
+        assert!(connection.send(&mut fsm, event));
+    }
+    assert_eq!(fsm.current_state(), Some("Disconnected"));
+    assert!(connection.timers.is_empty());
+}
+
+#[test]
+fn test_interpreter_periodic_keepalive() {
+    let source = include_str!("../../examples/connection_manager.fsm");
+    let fsm = &crate::parser::parse_fsm(source).unwrap()[0];
+    let mut machine = Interpreter::new(fsm, |_: &str| false);
+    let keepalives = |results: &[StepResult]| {
+        results.iter().filter(|r| matches!(r, StepResult::Fired(f) if f.event == "KeepaliveTick")).count()
+    };
+
+    // No timer runs while disconnected
+    assert!(machine.advance_time(60_000).is_empty());
+    machine.send("Connect");
+    machine.send("ConnectionEstablished");
+    assert_eq!(machine.current_state(), Some("Connected"));
+    assert_eq!(machine.timers().len(), 1);
+    assert_eq!(machine.timers()[0].name, "keepalive");
+
+    // Every 30 s, however the time is sliced, and the internal transition keeps it running
+    assert_eq!(keepalives(&machine.advance_time(29_999)), 0);
+    assert_eq!(keepalives(&machine.advance_time(1)), 1);
+    assert_eq!(keepalives(&machine.advance_time(95_000)), 3);
+    assert_eq!(machine.timers()[0].remaining_ms, 25_000);
+    for _ in 0..5 {
+        machine.advance_time(6_000);
+    }
+    assert_eq!(machine.current_state(), Some("Connected"));
+
+    // Leaving the state stops it; the reconnect delay fires once and connecting starts
+    // the (one-shot) connect timeout, which sends the machine back to Disconnected
+    machine.send("ConnectionLost");
+    assert_eq!(machine.timers()[0].name, "reconnect_delay");
+    let results = machine.advance_time(60_000);
+    assert_eq!(keepalives(&results), 0);
+    let fired: Vec<&str> = results
+        .iter()
+        .filter_map(|r| match r {
+            StepResult::Fired(f) => Some(f.event.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(fired, ["ReconnectTimer", "ConnectTimeout"]);
+    assert_eq!(machine.current_state(), Some("Disconnected"));
+    assert!(machine.timers().is_empty());
+
+    // Reset stops every timer
+    machine.send("Connect");
+    machine.send("ConnectionEstablished");
+    machine.reset();
+    assert!(machine.timers().is_empty());
+}
+
+#[test]
+fn test_interpreter_choice_points_and_else() {
+    let source = r#"
+        fsm Vending {
+            [*] --> Idle
+            state Paid {
+                entry / dispense
+            }
+            state Refund
+            state Idle {
+                exit / lock_coins
+            }
+            Idle --> <<CheckFunds>> : select / read_balance
+            choice CheckFunds {
+                [has_credit] -> Paid / charge
+                [else] -> Refund / release
+            }
+            Paid --> Idle : done [is_empty]
+            Paid --> Refund : done [else]
+        }
+    "#;
+    let fsm = &crate::parser::parse_fsm(source).unwrap()[0];
+    assert_eq!(guard_expressions(fsm), ["has_credit", "is_empty"]);
+
+    let mut machine = Interpreter::new(fsm, |guard: &str| guard == "has_credit");
+    let firing = fired(machine.send("select"));
+    assert_eq!(firing.to, "Paid");
+    assert_eq!(firing.choices, ["CheckFunds"]);
+    assert_eq!(action_names(&firing), ["lock_coins", "read_balance", "charge", "dispense"]);
+    assert_eq!(firing.guards, [("has_credit", true)]);
+    assert!(!firing.took_else);
+
+    // [else] after its guarded sibling fails
+    let firing = fired(machine.send("done"));
+    assert_eq!((firing.to.as_str(), firing.took_else), ("Refund", true));
+    assert_eq!(firing.guards, [("is_empty", false)]);
+
+    let mut machine = Interpreter::new(fsm, |_: &str| false);
+    let firing = fired(machine.send("select"));
+    assert_eq!(firing.to, "Refund");
+    assert_eq!(action_names(&firing), ["lock_coins", "read_balance", "release"]);
+    assert!(firing.took_else);
+}
+
+#[test]
+fn test_interpreter_queue_and_reset() {
+    let fsm = &crate::parser::parse_fsm(DOOR).unwrap()[0];
+    let mut door = Interpreter::new(fsm, |_: &str| true);
+    door.post("Unlock");
+    door.post("Open");
+    door.post("Close");
+    assert_eq!(door.pending().collect::<Vec<_>>(), ["Unlock", "Open", "Close"]);
+
+    assert_eq!(fired(door.step()).to, "Unlocked");
+    assert_eq!(fired(door.step()).to, "Open");
+    assert_eq!(door.pending().collect::<Vec<_>>(), ["Close"]);
+
+    door.reset();
+    assert_eq!(door.current_state(), Some("Locked"));
+    assert!(matches!(door.step(), StepResult::Idle));
+}
+
+#[test]
+fn test_interpreter_wildcard_after_own_transitions() {
+    let source = r#"
+        fsm Press {
+            [*] --> Idle
+            state Idle
+            state Running {
+                exit / stop_motor
+            }
+            state Stopped
+            Idle --> Running : start
+            Running --> Idle : emergency_stop [armed] / brake
+            * --> Stopped : emergency_stop / kill_power
+        }
+    "#;
+    let fsm = &crate::parser::parse_fsm(source).unwrap()[0];
+
+    let mut press = Interpreter::new(fsm, |_: &str| true);
+    press.send("start");
+    let firing = fired(press.send("emergency_stop"));
+    assert_eq!((firing.to.as_str(), firing.transition_index), ("Idle", Some(1)));
+
+    // No transition of Idle's own, and Running's own guard failing, fall back to the wildcard
+    let firing = fired(press.send("emergency_stop"));
+    assert_eq!((firing.from.as_str(), firing.to.as_str()), ("Idle", "Stopped"));
+    let mut press = Interpreter::new(fsm, |_: &str| false);
+    press.send("start");
+    let firing = fired(press.send("emergency_stop"));
+    assert_eq!(action_names(&firing), ["stop_motor", "kill_power"]);
+    assert_eq!(firing.guards, [("armed", false)]);
+
+    let matrix = event_matrix(fsm);
+    assert_eq!(matrix.get("Idle", "emergency_stop").unwrap().handling, EventHandling::External);
+    assert_eq!(matrix.get("Running", "emergency_stop").unwrap().transitions, [1, 2]);
+}
+
+const FORM: &str = r#"
+    fsm Form {
+        [*] --> Editing
+        state Validating {
+            entry / check_fields
+        }
+        Editing --> Validating : submit
+        Validating --> Submitting : [all_valid]
+        Validating --> Rejected : [else] / show_errors
+        Rejected --> Editing
+        Submitting --> Editing : done
+    }
+"#;
+
+#[test]
+fn test_interpreter_takes_completion_transitions() {
+    let fsm = &crate::parser::parse_fsm(FORM).unwrap()[0];
+    assert!(fsm.validate().is_ok());
+    let valid = Cell::new(true);
+    let mut form = Interpreter::new(fsm, |g: &str| g == "all_valid" && valid.get());
+
+    let firing = fired(form.send("submit"));
+    assert_eq!(firing.to, "Validating");
+    assert_eq!(action_names(&firing), ["check_fields"]);
+    let hops: Vec<&str> = firing.completions.iter().map(|c| c.to.as_str()).collect();
+    assert_eq!(hops, ["Submitting"]);
+    assert_eq!(form.current_state(), Some("Submitting"));
+
+    // Failing the guard takes [else], and the bare transition after it
+    form.send("done");
+    valid.set(false);
+    let firing = fired(form.send("submit"));
+    let hops: Vec<&str> = firing.completions.iter().map(|c| c.to.as_str()).collect();
+    assert_eq!(hops, ["Rejected", "Editing"]);
+    assert!(firing.completions[0].took_else);
+    assert_eq!(action_names(&firing.completions[0]), ["show_errors"]);
+    assert_eq!(firing.settled_state(), "Editing");
+    assert_eq!(form.current_state(), Some("Editing"));
+}
+
+#[test]
+fn test_interpreter_action_order() {
+    let source = "fsm Valve {
+        [*] --> Closed
+        state Closed {
+            exit / leave_closed
+        }
+        state Open {
+            entry / enter_open
+            exit / leave_open
+        }
+        Closed --> Open : open / turn
+        Open --> Closed : / settle
+    }";
+    let fsm = &crate::parser::parse_fsm(source).unwrap()[0];
+
+    let mut uml = Interpreter::new(fsm, |_: &str| true);
+    let firing = fired(uml.send("open"));
+    assert_eq!(action_names(&firing), ["leave_closed", "turn", "enter_open"]);
+    assert_eq!(action_names(&firing.completions[0]), ["leave_open", "settle"]);
+
+    let mut first = Interpreter::new(fsm, |_: &str| true).with_action_order(ActionOrder::ActionFirst);
+    let firing = fired(first.send("open"));
+    assert_eq!(action_names(&firing), ["turn", "leave_closed", "enter_open"]);
+    assert_eq!(action_names(&firing.completions[0]), ["settle", "leave_open"]);
+    assert_eq!(first.current_state(), Some("Closed"));
+}
+
+#[test]
+fn test_completion_loop_rejected_and_capped() {
+    let source = "fsm Spin {\n [*] --> Idle\n Idle --> A : go\n A --> B\n B --> A\n}";
+    let fsm = &crate::parser::parse_fsm(source).unwrap()[0];
+    assert_eq!(completion_cycles(fsm), [vec!["A", "B", "A"]]);
+    let errors = fsm.validate().unwrap_err();
+    assert!(errors.contains(&"Completion transitions loop without a guard: A --> B --> A".to_string()));
+
+    // The interpreter stops hopping at the limit instead of hanging
+    let mut spin = Interpreter::new(fsm, |_: &str| true).with_max_completion_hops(3);
+    assert_eq!(fired(spin.send("go")).completions.len(), 3);
+    assert_eq!(spin.current_state(), Some("B"));
+
+    // A guard anywhere in the loop lets it end
+    let source = "fsm Spin {\n [*] --> Idle\n Idle --> A : go\n A --> B\n B --> A : [again]\n}";
+    let fsm = &crate::parser::parse_fsm(source).unwrap()[0];
+    assert!(completion_cycles(fsm).is_empty() && fsm.validate().is_ok());
+}
+
+#[test]
+fn test_redundant_wildcard_warning() {
+    let source = "fsm Lamp {\n [*] --> Off\n Off --> Off : reset\n On --> Off : reset\n * --> Off : reset\n}";
+    let fsm = &crate::parser::parse_fsm(source).unwrap()[0];
+    assert_eq!(fsm.redundant_wildcards().len(), 1);
+    assert!(fsm
+        .warnings()
+        .contains(&"Wildcard transition '* --> Off : reset' (line 5) never fires: every state has the same transition of its own".to_string()));
+
+    // Useful as soon as one state lacks its own
+    let source = "fsm Lamp {\n [*] --> Off\n Off --> Off : reset\n On --> Off : toggle\n * --> Off : reset\n}";
+    let fsm = &crate::parser::parse_fsm(source).unwrap()[0];
+    assert!(fsm.redundant_wildcards().is_empty());
+}
+
+#[test]
+fn test_internal_and_self_transition_warning() {
+    let fsm = &crate::parser::parse_fsm(include_str!("../../examples/builtin/vending_machine.fsm")).unwrap()[0];
+    assert_eq!(fsm.internal_and_self_transitions().len(), 1);
+    assert!(fsm.warnings().contains(
+        &"State 'AcceptingCoins' handles 'coin_inserted' with an internal transition (line 12) and an external \
+          self-transition (line 26): the self-transition, which runs the exit and entry actions, is checked first, \
+          so the internal one never runs"
+            .to_string()
+    ));
+
+    let source = "fsm Box {\n [*] --> A\n state A {\n  coin / count\n  tap / blink\n }\n A --> A : coin [jammed] / eject\n A --> A : local_tap\n}";
+    let fsm = &crate::parser::parse_fsm(source).unwrap()[0];
+    let warnings = fsm.warnings();
+    assert_eq!(fsm.internal_and_self_transitions().len(), 1, "{warnings:?}");
+    assert!(warnings.iter().any(|w| w.ends_with("is checked first, and the internal one runs only when its guard fails")));
+}
+
+// ============================================================================
+// NAMING
+// ============================================================================
+
+#[test]
+fn test_case_conversion_table() {
+    // (name, snake_case, PascalCase, whether the snake_case survives a trip through PascalCase)
+    let table = [
+        ("IOManager", "io_manager", "IOManager", true),
+        ("HTTPServer", "http_server", "HTTPServer", true),
+        ("HTTPServer2", "http_server2", "HTTPServer2", true),
+        ("Motor2Controller", "motor2_controller", "Motor2Controller", true),
+        ("ledOn", "led_on", "LedOn", true),
+        ("turnOnLED", "turn_on_led", "TurnOnLED", true),
+        ("getHTTPResponseCode", "get_http_response_code", "GetHTTPResponseCode", true),
+        ("already_snake", "already_snake", "AlreadySnake", true),
+        ("io_manager", "io_manager", "IoManager", true),
+        ("SCREAMING_CASE", "screaming_case", "SCREAMINGCASE", true),
+        ("Motor_Controller", "motor_controller", "MotorController", true),
+        ("v2Api", "v2_api", "V2Api", true),
+        ("sensor_2", "sensor_2", "Sensor2", false),
+        ("ABC", "abc", "ABC", true),
+        ("A", "a", "A", true),
+        ("turn on-led", "turn_on_led", "TurnOnLed", true),
+        ("_private", "_private", "Private", false),
+        ("power > 0", "power_>_0", "Power>0", false),
+        ("", "", "", true),
+    ];
+    for (name, snake, pascal, round_trips) in table {
+        assert_eq!(to_snake_case(name), snake, "snake_case of {name:?}");
+        assert_eq!(to_pascal_case(name), pascal, "PascalCase of {name:?}");
+        // Converting again changes nothing
+        assert_eq!(to_snake_case(snake), snake, "snake_case of {snake:?}");
+        assert_eq!(to_pascal_case(pascal), pascal, "PascalCase of {pascal:?}");
+        // PascalCase loses the `_` before a digit and leading underscores
+        assert_eq!(to_snake_case(&to_pascal_case(snake)) == snake, round_trips, "round trip of {snake:?}");
+    }
+    assert_eq!(split_words("HTTPServer2-ledOn"), ["HTTP", "Server2", "led", "On"]);
+}
+
+#[test]
+fn test_sanitize_ident() {
+    assert_eq!(sanitize_ident("turn on LED"), "turn_on_led");
+    assert_eq!(sanitize_ident("attempts > 3"), "attempts_3");
+    assert_eq!(sanitize_ident("fooBar-baz"), "foo_bar_baz");
+    assert_eq!(sanitize_ident("HTTPServer"), "http_server");
+    assert_eq!(sanitize_ident("3 way"), "n_3_way");
+    assert_eq!(sanitize_ident("move"), "r_move");
+    assert_eq!(sanitize_ident("self"), "r_self");
+    assert_eq!(sanitize_ident("!?"), "unnamed");
+    // Latin diacritics are transliterated, other scripts kept
+    assert_eq!(sanitize_ident("Über wachung"), "ueber_wachung");
+    assert_eq!(sanitize_ident("señal roja"), "senal_roja");
+    assert_eq!(sanitize_ident("温度 高"), "温度_高");
+}
+
+#[test]
+fn test_unicode_and_keyword_names() {
+    let source = "fsm Wächter {\n [*] --> Überwachung\n Überwachung --> Loop : match [loop] / match\n Loop --> Self : ruhe / self\n}";
+    let fsm = &crate::parser::parse_fsm(source).expect("Should parse successfully")[0];
+    assert_eq!(fsm.initial_state(), Some("Überwachung"));
+
+    // Keywords are fine where they can be written raw, `Self` and `self` are not
+    let errors = check_names(fsm, false).unwrap_err();
+    assert_eq!(
+        errors,
+        [
+            NameError::Keyword {
+                kind: NameKind::State,
+                name: "Self".to_string(),
+                converted: "Self".to_string(),
+                suggestion: "r_self".to_string(),
+                line: Some(4),
+            },
+            NameError::Keyword {
+                kind: NameKind::Action,
+                name: "self".to_string(),
+                converted: "self".to_string(),
+                suggestion: "r_self".to_string(),
+                line: Some(4),
+            },
+        ]
+    );
+    let renames = check_names(fsm, true).unwrap();
+    let notes: Vec<String> = renames.iter().map(|r| r.to_string()).collect();
+    assert_eq!(notes, ["state Self → RSelf", "action self → r_self"]);
+
+    assert_eq!(rust_ident("match"), "r#match");
+    assert_eq!(rust_ident("Loop"), "Loop");
+    assert_eq!(rust_ident("überwachung"), "überwachung");
+    assert_eq!(rust_ident("self"), "self");
+    assert!(is_rust_identifier("Überwachung"));
+    assert!(!is_rust_identifier("loop"));
+    assert!(!is_rust_identifier("a-b"));
+}
+
+#[test]
+fn test_check_names_reports_and_sanitizes() {
+    let source = "fsm Robot {\n [*] --> Idle\n Idle --> Moving : go [battery - 5 > 20] / self\n Moving --> Idle : ValidKey\n Moving --> Idle : valid_key\n}";
+    let fsm = &crate::parser::parse_fsm(source).expect("Should parse successfully")[0];
+
+    let errors = check_names(fsm, false).unwrap_err();
+    assert!(errors.contains(&NameError::Invalid {
+        kind: NameKind::Guard,
+        name: "battery - 5 > 20".to_string(),
+        converted: "battery_5_>_20".to_string(),
+        suggestion: "battery_5_20".to_string(),
+        line: Some(3),
+    }));
+    assert!(errors.iter().any(|e| matches!(e, NameError::Keyword { name, .. } if name == "self")));
+    let collision = NameError::Collision {
+        kind: NameKind::Event,
+        identifier: "ValidKey".to_string(),
+        names: vec!["ValidKey".to_string(), "valid_key".to_string()],
+    };
+    assert!(errors.contains(&collision));
+    assert_eq!(collision.to_string(), "events `ValidKey`, `valid_key` all become `ValidKey`");
+
+    // Lenient mode sanitizes, but collisions stay errors
+    assert_eq!(check_names(fsm, true).unwrap_err(), vec![collision]);
+
+    let source = "fsm Robot {\n [*] --> Idle\n Idle --> Moving : go [battery - 5 > 20] / self\n}";
+    let fsm = &crate::parser::parse_fsm(source).expect("Should parse successfully")[0];
+    let renames = check_names(fsm, true).unwrap();
+    let notes: Vec<String> = renames.iter().map(|r| r.to_string()).collect();
+    assert_eq!(notes, ["guard battery - 5 > 20 → battery_5_20", "action self → r_self"]);
+
+    let renamed = apply_renames(fsm, &renames);
+    assert_eq!(renamed.transitions[0].guard.as_ref().unwrap().expression, "battery_5_20");
+    assert_eq!(renamed.transitions[0].actions[0].name, "r_self");
+    assert_eq!(check_names(&renamed, false), Ok(Vec::new()));
+
+    // Sanitizing two guards to the same identifier is a collision
+    let source = "fsm Heater {\n [*] --> Off\n Off --> On : tick [t + 1 < 18]\n On --> Off : tick [t - 1 > 18]\n}";
+    let fsm = &crate::parser::parse_fsm(source).expect("Should parse successfully")[0];
+    assert!(matches!(&check_names(fsm, true).unwrap_err()[..], [NameError::Collision { identifier, .. }] if identifier == "t_1_18"));
+}
+
+#[test]
+fn test_start_actions_before_initial_entry() {
+    let source = r#"
+        fsm Pump {
+            [*] --> Idle : / load_config; self_test(1)
+            state Idle {
+                entry / close_valve
+            }
+            Idle --> Idle : reset
+        }
+    "#;
+    let fsm = &crate::parser::parse_fsm(source).unwrap()[0];
+    assert_eq!(fsm.initial_state(), Some("Idle"));
+    assert_eq!(fsm.initial_actions()[1].params, ["1"]);
+
+    let mut pump = Interpreter::new(fsm, |_: &str| true);
+    let start: Vec<&str> = pump.reset().iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(start, ["load_config", "self_test", "close_valve"]);
+
+    // A self-transition re-enters the state but does not start the machine again
+    assert_eq!(action_names(&fired(pump.send("reset"))), ["close_valve"]);
+    assert!(crate::parser::parse_fsm("fsm Pump { [*] --> Idle : boot }").is_err());
+}
+
+// ============================================================================
+// EXPLORATION
+// ============================================================================
+
+#[test]
+fn test_explore_finds_shadowed_transitions() {
+    use crate::fsm::explore;
+
+    let source = r#"
+        fsm Valve {
+            [*] --> Closed
+            Closed --> Open : open [pressure_ok] / vent
+            Closed --> Locked : open [pressure_ok] / lock_out
+            Closed --> Closed : open / retry
+            Closed --> Stuck : open / jam
+            Open --> Closed : close [drained] / seal
+            Open --> Open : close [else]
+            Locked --> Closed : unlock
+            state Stuck {
+                entry / alarm
+            }
+        }
+    "#;
+    let fsm = &crate::parser::parse_fsm(source).expect("Should parse successfully")[0];
+    let exploration = explore(fsm, 8);
+
+    // `pressure_ok` passing takes the first transition, failing takes the retry
+    let dead: Vec<String> = exploration.unfired_transitions.iter().map(|&i| fsm.transitions[i].target.clone()).collect();
+    assert_eq!(dead, ["Locked", "Stuck", "Closed"]);
+    assert_eq!(fsm.transitions[exploration.unfired_transitions[2]].source, "Locked");
+    assert_eq!(exploration.unvisited_states, ["Locked", "Stuck"]);
+    assert_eq!(exploration.unexecuted_actions, ["lock_out", "jam", "alarm"]);
+    assert!(!exploration.truncated);
+
+    let messages = exploration.messages(fsm);
+    assert_eq!(messages[0], "Transition 'Closed --> Locked : open [pressure_ok]' (line 5) never fires, whatever its guards answer");
+    assert!(messages.contains(&"Action 'alarm' never runs".to_string()));
+}
+
+#[test]
+fn test_explore_depth_and_internal_transitions() {
+    use crate::fsm::explore;
+
+    let source = r#"
+        fsm Line {
+            [*] --> A
+            A --> B : next
+            B --> C : next [ready]
+            C --> D : next
+            state A {
+                ping / pong
+                ping [quiet] / hush
+            }
+        }
+    "#;
+    let fsm = &crate::parser::parse_fsm(source).expect("Should parse successfully")[0];
+
+    let shallow = explore(fsm, 2);
+    assert!(shallow.truncated);
+    assert_eq!(shallow.unvisited_states, ["D"]);
+    assert_eq!(shallow.unfired_transitions, [2]);
+    assert_eq!(shallow.messages(fsm).last().unwrap(), "Paths longer than 2 events were not explored");
+
+    let deep = explore(fsm, 3);
+    assert!(!deep.truncated);
+    assert!(deep.unvisited_states.is_empty());
+    // The unguarded internal transition always answers `ping` first
+    assert_eq!(deep.unfired_internal_transitions, [("A".to_string(), 1)]);
+    assert_eq!(deep.unexecuted_actions, ["hush"]);
+    assert!(!deep.is_clean());
+}
+
+#[test]
+fn test_guard_expression_parsing() {
+    use crate::fsm::guards::GuardExpr;
+
+    let term = |t: &str| GuardExpr::Term(t.to_string());
+    let expr = GuardExpr::parse("ready && !(speed_ok(rpm) || count != 0)");
+    assert_eq!(expr.terms(), ["ready", "speed_ok(rpm)", "count != 0"]);
+    assert_eq!(expr.to_string(), "ready && !(speed_ok(rpm) || count != 0)");
+    assert_eq!(GuardExpr::parse("a || b && c"), GuardExpr::Any(vec![term("a"), GuardExpr::All(vec![term("b"), term("c")])]));
+    assert_eq!(GuardExpr::parse(" (a || b)&&c ").to_string(), "(a || b) && c");
+    assert_eq!(GuardExpr::parse("!!a").to_string(), "!!a");
+
+    // What is not a composition stays one term, as before
+    assert_eq!(GuardExpr::parse("count != 0"), term("count != 0"));
+    assert_eq!(GuardExpr::parse("a &&"), term("a &&"));
+    assert_eq!(GuardExpr::parse("(a || b"), term("(a || b"));
+}
+
+const KEYPAD: &str = r#"
+    fsm Keypad {
+        guard lockout = attempts > 3 && !maintenance_mode
+        guard alarm = lockout || tampered
+
+        [*] --> Idle
+        Idle --> Locked : key [lockout]
+        Idle --> Alarm : key [alarm && door_closed]
+        Locked --> Idle : reset
+        Alarm --> Idle : reset
+    }
+"#;
+
+#[test]
+fn test_named_guards_resolve_nested_references() {
+    let fsm = &crate::parser::parse_fsm(KEYPAD).unwrap()[0];
+    assert_eq!(fsm.guards.len(), 2);
+    assert_eq!(
+        fsm.resolve_guard("alarm && door_closed").to_string(),
+        "(attempts > 3 && !maintenance_mode || tampered) && door_closed"
+    );
+    assert!(fsm.validate().is_ok());
+    assert!(fsm.warnings().is_empty());
+    assert_eq!(guard_expressions(fsm), ["attempts > 3", "door_closed", "maintenance_mode", "tampered"]);
+
+    let attempts_exceeded = Cell::new(false);
+    let tampered = Cell::new(true);
+    let mut keypad = Interpreter::new(fsm, |guard: &str| match guard {
+        "attempts > 3" => attempts_exceeded.get(),
+        "maintenance_mode" => false,
+        "tampered" => tampered.get(),
+        "door_closed" => true,
+        other => panic!("unexpected guard {other}"),
+    });
+    // The guard is reported as written, with its value
+    let alarm = fired(keypad.send("key"));
+    assert_eq!(alarm.to, "Alarm");
+    assert_eq!(alarm.guards, [("lockout", false), ("alarm && door_closed", true)]);
+    keypad.send("reset");
+    attempts_exceeded.set(true);
+    assert_eq!(fired(keypad.send("key")).to, "Locked");
+
+    let mut unused = fsm.clone();
+    unused.guards.push(crate::fsm::NamedGuard::new("never", "door_closed"));
+    assert_eq!(unused.warnings(), ["Guard 'never' is defined but no transition uses it"]);
+}
+
+#[test]
+fn test_interpreter_debounce_and_throttle() {
+    let source = "fsm Door {\n    [*] --> Closed\n    Closed --> Open : door_opened @debounce(50ms)\n    Open --> Closed : door_closed\n    state Open {\n        sample @throttle(1s) / log_sample\n    }\n}\n";
+    let fsm = &crate::parser::parse_fsm(source).unwrap()[0];
+    let mut door = Interpreter::new(fsm, |_: &str| false);
+    let suppressed = |result: StepResult| match result {
+        StepResult::Suppressed { constraint, .. } => Some(constraint.kind.as_str()),
+        _ => None,
+    };
+
+    // The first event fires; bounces keep it quiet until 50 ms pass without one
+    assert_eq!(fired(door.send("door_opened")).to, "Open");
+    door.send("door_closed");
+    door.advance_time(30);
+    assert_eq!(suppressed(door.send("door_opened")), Some("debounce"));
+    door.advance_time(30);
+    assert_eq!(suppressed(door.send("door_opened")), Some("debounce"), "the bounce restarted the window");
+    door.advance_time(50);
+    assert_eq!(door.now_ms(), 110);
+    assert_eq!(fired(door.send("door_opened")).to, "Open");
+
+    // At most one sample per second, counted from the one that fired
+    assert!(fired(door.send("sample")).is_internal());
+    door.advance_time(600);
+    assert_eq!(suppressed(door.send("sample")), Some("throttle"));
+    door.advance_time(400);
+    assert!(fired(door.send("sample")).is_internal());
+    assert_eq!(suppressed(door.send("sample")), Some("throttle"));
+
+    // A reset forgets the windows
+    door.reset();
+    assert_eq!(fired(door.send("door_opened")).to, "Open");
+    assert!(fired(door.send("sample")).is_internal());
+}
+
+#[test]
+fn test_named_guard_cycles_and_definition_errors() {
+    let source = r#"
+        fsm Loop {
+            guard first = second && ready
+            guard second = !first
+            guard own = own || ready
+            guard twice = ready
+            guard twice = !ready
+            guard fast = speed_ok(rpm)
+            guard lockout = attempts > 3 && !maintenance_mode
+            guard busy = queue + 1 > limit
+
+            [*] --> A
+            A --> B : go [first]
+            A --> B : go [own && twice]
+            A --> B : go [fast]
+            A --> B : go [twice(rpm)]
+            A --> B : go [lockout || busy]
+        }
+    "#;
+    let fsm = &crate::parser::parse_fsm(source).unwrap()[0];
+    assert_eq!(fsm.guard_cycles(), [vec!["first", "second", "first"], vec!["own", "own"]]);
+    // Resolution stops where a loop closes
+    assert_eq!(fsm.resolve_guard("first").to_string(), "!first && ready");
+    assert!(!fsm.evaluate_guard("own", &|_| false));
+
+    let errors = fsm.validate().unwrap_err();
+    for expected in [
+        "Guard 'twice' is defined more than once (lines 6, 7)",
+        "Guard 'fast' passes arguments to 'speed_ok(rpm)'; only a transition's guard can",
+        "Guards refer to each other in a loop: first → second → first",
+        "Guards refer to each other in a loop: own → own",
+        "Guard 'twice' takes no arguments: [twice(rpm)]",
+        // A comparison of names and numbers is asked of the context; anything else has no method to ask
+        "Guard term 'queue + 1 > limit' in [queue + 1 > limit] is not a method, a named guard or a comparison of a name with a name or number (`attempts > 3`)",
+    ] {
+        assert!(errors.iter().any(|e| e == expected), "missing {expected:?} in {errors:?}");
+    }
+    assert!(!errors.iter().any(|e| e.contains("in [attempts > 3")), "{errors:?}");
+}
@@ -0,0 +1,100 @@
+//! Layout Cache
+//! Keeps recent engine layouts so switching between FSM tabs, or back to an
+//! earlier layout configuration, does not run the layout engine again
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+use eframe::egui::Pos2;
+
+use crate::diagram::{layout_fingerprint, LayoutedDiagram, StateBoxContent};
+use crate::fsm::FsmDefinition;
+
+#[cfg(test)]
+mod tests;
+
+/// Layouts kept before the least recently used one is dropped
+pub const LAYOUT_CACHE_CAPACITY: usize = 16;
+
+/// Cache key: the FSM's `layout_fingerprint` with the state box content shown (so
+/// comment-only edits keep their layout) together with the layout engine configuration
+pub fn layout_key(fsm: &FsmDefinition, state_box: &StateBoxContent, config: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    layout_fingerprint(fsm, state_box).hash(&mut hasher);
+    config.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// An engine layout and the node positions computed with it
+#[derive(Clone, Debug)]
+pub struct CachedLayout {
+    pub layout: LayoutedDiagram,
+    pub state_positions: HashMap<String, Pos2>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LayoutCache {
+    /// Least recently used first
+    entries: VecDeque<(u64, CachedLayout)>,
+    capacity: usize,
+    pub hits: usize,
+    pub misses: usize,
+    /// Whether the latest lookup was a hit
+    pub last_hit: Option<bool>,
+}
+
+impl Default for LayoutCache {
+    fn default() -> Self {
+        Self::new(LAYOUT_CACHE_CAPACITY)
+    }
+}
+
+impl LayoutCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity: capacity.max(1),
+            hits: 0,
+            misses: 0,
+            last_hit: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The layout stored under `key`, which becomes the most recently used
+    pub fn get(&mut self, key: u64) -> Option<&CachedLayout> {
+        let found = self.entries.iter().position(|(k, _)| *k == key);
+        self.last_hit = Some(found.is_some());
+        match found {
+            Some(index) => {
+                self.hits += 1;
+                let entry = self.entries.remove(index)?;
+                self.entries.push_back(entry);
+                self.entries.back().map(|(_, layout)| layout)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Store a layout, dropping the least recently used one when full
+    pub fn insert(&mut self, key: u64, layout: CachedLayout) {
+        self.entries.retain(|(k, _)| *k != key);
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((key, layout));
+    }
+
+    /// Drop the layouts of FSMs that no longer exist (e.g. after a parse)
+    pub fn retain_fsms<'a>(&mut self, names: impl IntoIterator<Item = &'a str>) {
+        let names: Vec<&str> = names.into_iter().collect();
+        self.entries.retain(|(_, cached)| names.contains(&cached.layout.fsm_name.as_str()));
+    }
+}
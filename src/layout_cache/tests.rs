@@ -0,0 +1,66 @@
+//! Unit tests for the layout cache
+
+use std::collections::HashMap;
+
+use eframe::egui;
+
+use crate::diagram::{LayoutedDiagram, StateBoxContent};
+use crate::layout_cache::{layout_key, CachedLayout, LayoutCache};
+use crate::parser::parse_fsm;
+
+fn key(source: &str, direction: &str) -> u64 {
+    layout_key(&parse_fsm(source).expect("Should parse successfully")[0], &StateBoxContent::default(), &direction)
+}
+
+fn cached(fsm_name: &str) -> CachedLayout {
+    CachedLayout {
+        layout: LayoutedDiagram {
+            edges: Vec::new(),
+            labels: Vec::new(),
+            bounds: egui::Rect::NOTHING,
+            fsm_name: fsm_name.to_string(),
+            states: Vec::new(),
+        },
+        state_positions: HashMap::new(),
+    }
+}
+
+#[test]
+fn test_layout_key_follows_labels_not_comments() {
+    let base = key("fsm Door {\n [*] --> Locked\n Locked --> Open : unlock\n}", "TB");
+
+    let commented = key(
+        "fsm Door {\n // the door\n [*] --> Locked\n /// Opens it\n Locked --> Open : unlock // ABC-1\n}",
+        "TB",
+    );
+    assert_eq!(base, commented);
+
+    let relabelled = key("fsm Door {\n [*] --> Locked\n Locked --> Open : unlock_door\n}", "TB");
+    assert_ne!(base, relabelled);
+    let guarded = key("fsm Door {\n [*] --> Locked\n Locked --> Open : unlock [ok]\n}", "TB");
+    assert_ne!(base, guarded);
+    let sideways = key("fsm Door {\n [*] --> Locked\n Locked --> Open : unlock\n}", "LR");
+    assert_ne!(base, sideways);
+}
+
+#[test]
+fn test_layout_cache_evicts_least_recently_used() {
+    let mut cache = LayoutCache::new(2);
+    cache.insert(1, cached("A"));
+    cache.insert(2, cached("B"));
+    assert!(cache.get(1).is_some());
+    assert_eq!(cache.last_hit, Some(true));
+
+    // 2 is now the least recently used
+    cache.insert(3, cached("C"));
+    assert_eq!(cache.len(), 2);
+    assert!(cache.get(2).is_none());
+    assert_eq!(cache.last_hit, Some(false));
+    assert_eq!(cache.get(1).map(|c| c.layout.fsm_name.as_str()), Some("A"));
+    assert_eq!((cache.hits, cache.misses), (2, 1));
+
+    cache.retain_fsms(["C"]);
+    assert_eq!(cache.len(), 1);
+    assert!(cache.get(1).is_none());
+    assert!(cache.get(3).is_some());
+}
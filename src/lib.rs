@@ -4,7 +4,11 @@
 pub mod fsm;
 pub mod parser;
 pub mod codegen;
+pub mod project;
+pub mod report;
+pub mod docs;
 
 pub use fsm::*;
 pub use parser::parse_fsm;
+pub use parser::loader::parse_fsm_file;
 pub use codegen::generate_rust_code;
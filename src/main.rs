@@ -6,17 +6,76 @@ use std::collections::HashMap;
 use std::io::Write;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
+use std::sync::mpsc;
 use std::time::{Duration, Instant};
 
 mod fsm;
 mod parser;
 mod codegen;
+mod project;
+mod workspace;
+mod sim;
+mod diagram;
+mod diagram_image;
+mod completion;
+mod layout_cache;
+mod settings;
+mod templates;
+mod console;
+mod report;
+mod code_view;
+mod docs;
+mod staleness;
+mod split_view;
+mod tutorial;
+
+use fsm::naming::to_pascal_case;
+use fsm::{FsmDefinition, ANY_STATE};
+use parser::blocks::{Reparse, SourceBlocks};
+use parser::diagnostics::{diagnostics, Diagnostic, Severity as DiagnosticSeverity};
+use parser::edit::{
+    add_entry_action, add_events_block, add_state, add_transition, color_states, delete_states, extract_states, is_identifier, rename, rename_event,
+    rewrite_transition, set_initial_state, state_definition, tag_states, LabelEdit, RenameKind,
+};
+use parser::format::{format_fsm, format_fsms};
+use parser::loader::parse_fsm_source;
+use parser::{import_events, import_scxml, parse_fsm};
+use codegen::autogen::{failure_report, remove_stale_files, write_autogen_folder, AutogenError, ExportSummary};
+use codegen::bindings::Bindings;
+use codegen::check::{cargo_available, check_generated_code, CompileReport, DiagnosticLevel};
+use codegen::dot::generate_dot_with_direction;
+use codegen::generate_plantuml;
+use codegen::{
+    try_generate_rust_code_with_options, ActionOrder, AsyncActions, CodegenOptions, CodegenTarget, EventOrder, OverflowPolicy,
+    Visibility,
+};
+use report::{html_report, ReportSection};
+use sim::bus::SimBus;
+use sim::profiles::{GuardProfile, GuardProfiles, PROFILES_KEY};
+use sim::Simulator;
+use docs::{Scope, DOC_ENTRIES};
+use workspace::Workspace;
+use diagram::{
+    calculate_state_rect, distance_to_polyline, estimate_state_size, label_at, layout_bounds, note_rect, CanvasView, DiagramContent, DiagramTheme, HoverFocus, LayoutedDiagram, LayoutedEdge,
+    LayoutedLabel, SceneBuilder, SimOverlay, SimToken, StateBoxContent, TextMeasure, TransitionType, DEFAULT_DIAGRAM_FONT_SIZE,
+};
+use diagram::dagre::{parse_layout_output, JsEdgeIn, JsGraphCfg, JsLayoutInput, JsNodeIn, DAGRE_PROTOCOL};
+use diagram::fallback::{fallback_layout, grid_layout};
+use diagram::quality::{layout_quality, LayoutQuality};
+use diagram::placement::avoid_edge_segments;
+use diagram::render_cache::{RenderCache, RenderKey};
+use diagram::label::{fit_label_width, format_label_text, DEFAULT_MAX_LABEL_WIDTH, LABEL_WRAP_CHARS};
+use diagram_image::ExportScale;
+use completion::CompletionPopup;
+use code_view::CodeFolds;
+use layout_cache::{layout_key, CachedLayout, LayoutCache};
+use templates::{thumbnail_svg, Template, TEMPLATES};
+use console::{Category, ErrorConsole, Severity};
+use staleness::Staleness;
+use split_view::{split_rect, Pane, SplitView};
+use tutorial::{Spotlight, Tutorial, TutorialApp, TutorialRecord, TutorialStage, TUTORIAL_KEY, TUTORIAL_SOURCE};
+use settings::{Settings, DIAGRAM_FONT_RANGE, EDGE_LABEL_FONT_RANGE, EDITOR_FONT_RANGE, SETTINGS_KEY, UI_SCALE_RANGE};
 
-use fsm::{FsmDefinition, StateType};
-use parser::parse_fsm;
-use codegen::{generate_rust_code_with_target, CodegenTarget};
-
-use serde::{Deserialize, Serialize};
 
 fn oxidate_icon() -> egui::IconData {
     // Simple generated icon (64x64): dark background + orange "oxidation" ring.
@@ -176,10 +235,47 @@ fn node_binary() -> PathBuf {
     PathBuf::from("node")
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 enum LayoutDirection {
     TB,
+    BT,
     LR,
+    RL,
+}
+
+impl LayoutDirection {
+    const ALL: [LayoutDirection; 4] = [LayoutDirection::TB, LayoutDirection::BT, LayoutDirection::LR, LayoutDirection::RL];
+
+    fn label(&self) -> &'static str {
+        match self {
+            LayoutDirection::TB => "TB",
+            LayoutDirection::BT => "BT",
+            LayoutDirection::LR => "LR",
+            LayoutDirection::RL => "RL",
+        }
+    }
+}
+
+impl From<fsm::LayoutHint> for LayoutDirection {
+    fn from(hint: fsm::LayoutHint) -> Self {
+        match hint {
+            fsm::LayoutHint::TB => LayoutDirection::TB,
+            fsm::LayoutHint::BT => LayoutDirection::BT,
+            fsm::LayoutHint::LR => LayoutDirection::LR,
+            fsm::LayoutHint::RL => LayoutDirection::RL,
+        }
+    }
+}
+
+impl From<LayoutDirection> for fsm::LayoutHint {
+    fn from(direction: LayoutDirection) -> Self {
+        match direction {
+            LayoutDirection::TB => fsm::LayoutHint::TB,
+            LayoutDirection::BT => fsm::LayoutHint::BT,
+            LayoutDirection::LR => fsm::LayoutHint::LR,
+            LayoutDirection::RL => fsm::LayoutHint::RL,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -190,7 +286,24 @@ struct LayoutConfig {
     edgesep: f32,
     marginx: f32,
     marginy: f32,
+    /// Diagram font size (state names); node sizes depend on it
+    font_size: f32,
     edge_label_font_size: f32,
+    /// Label lines wider than this are cut with an ellipsis (layout px)
+    max_label_width: f32,
+    /// What state boxes show; node sizes depend on it
+    state_box: StateBoxContent,
+}
+
+/// Layout cache key part; the spacings hash by their bits
+impl std::hash::Hash for LayoutConfig {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.direction.hash(state);
+        for value in [self.nodesep, self.ranksep, self.edgesep, self.marginx, self.marginy, self.font_size, self.edge_label_font_size, self.max_label_width] {
+            value.to_bits().hash(state);
+        }
+        self.state_box.hash(state);
+    }
 }
 
 impl Default for LayoutConfig {
@@ -202,31 +315,195 @@ impl Default for LayoutConfig {
             edgesep: 20.0,
             marginx: 40.0,
             marginy: 40.0,
+            font_size: DEFAULT_DIAGRAM_FONT_SIZE,
             edge_label_font_size: 12.0,
+            max_label_width: DEFAULT_MAX_LABEL_WIDTH,
+            state_box: StateBoxContent::default(),
         }
     }
 }
 
+/// Transition from a previous layout to the current one
 #[derive(Clone, Debug)]
-struct LayoutedEdge {
-    v: String,
-    w: String,
-    /// If this edge is part of a concrete transition, this is that transition's index in `FsmDefinition::transitions`.
-    transition_index: Option<usize>,
-    points: Vec<egui::Pos2>,
-    transition_type: TransitionType,
+struct LayoutAnimation {
+    from_layout: LayoutedDiagram,
+    from_positions: HashMap<String, egui::Pos2>,
+    started_at: Instant,
+}
+
+/// What a diagram viewport paints: an FSM with its layout, and the overlays on it
+struct PaneContent<'a> {
+    fsm: &'a FsmDefinition,
+    layout: &'a LayoutedDiagram,
+    positions: &'a HashMap<String, egui::Pos2>,
+    render_cache: &'a RenderCache,
+    /// Layout change being animated, and its eased progress
+    animation: Option<(&'a LayoutAnimation, f32)>,
+    focus: Option<HoverFocus>,
+    sim: Option<&'a Simulator>,
+}
+
+/// Transition label being edited from the diagram
+struct LabelEditDialog {
+    fsm_index: usize,
+    transition_index: usize,
+    /// `Source --> Target` of the transition, for the dialog title
+    route: String,
+    edit: LabelEdit,
+    /// Event before editing
+    old_event: String,
+    /// Transitions, internal transitions and timers reacting to `old_event`
+    event_uses: usize,
+    rename_everywhere: bool,
+    /// Why the last apply was rejected
+    error: Option<String>,
+}
+
+/// Edit mode: a transition being drawn from a state (or the start node) to the pointer
+struct EdgeDrag {
+    /// State name, or `[*]` for the start node
+    from: String,
+    /// Escape was pressed: the drag ends without adding anything
+    cancelled: bool,
+}
+
+/// Edit mode: transition (and possibly a new state) to add after a drag on the canvas
+struct NewTransitionDialog {
+    fsm_index: usize,
+    /// Source state, or `[*]` to make the target the initial state
+    from: String,
+    /// Target state; `None` adds a state named `new_state` (dropped on empty canvas)
+    to: Option<String>,
+    /// Opened from a state's context menu: the target is picked from the FSM's states
+    pick_target: bool,
+    new_state: String,
+    edit: LabelEdit,
+    /// Why the last apply was rejected
+    error: Option<String>,
+}
+
+/// What the canvas context menu was opened on
+#[derive(Clone)]
+enum CanvasTarget {
+    State(String),
+    /// Empty canvas at this screen position
+    Empty(egui::Pos2),
+}
+
+/// Item picked in the canvas context menu
+enum CanvasAction {
+    SetInitial(String),
+    AddTransition(String),
+    AddEntryAction(String),
+    Rename(String),
+    Delete(String),
+    GoToDefinition(String),
+    SimulateFrom(String),
+    AddState(egui::Pos2),
+    Fit,
+}
+
+/// "Add state here" dialog of the canvas context menu
+struct NewStateDialog {
+    fsm_index: usize,
+    name: String,
+    /// Where the canvas was right-clicked, from its centre
+    at: egui::Vec2,
+    /// Why the last apply was rejected
+    error: Option<String>,
+}
+
+/// States selected on the canvas, in the order they were selected
+#[derive(Default)]
+struct StateSelection {
+    /// FSM they belong to: the selection does not carry over to another FSM
+    fsm: String,
+    states: Vec<String>,
+}
+
+impl StateSelection {
+    /// Selected states of `fsm`
+    fn of(&self, fsm: &str) -> &[String] {
+        if self.fsm == fsm {
+            &self.states
+        } else {
+            &[]
+        }
+    }
+
+    fn set(&mut self, fsm: &str, states: Vec<String>) {
+        self.fsm = fsm.to_string();
+        self.states = states;
+    }
+
+    /// Add `state` to the selection of `fsm`, or remove it if it is selected
+    fn toggle(&mut self, fsm: &str, state: &str) {
+        let mut states = self.of(fsm).to_vec();
+        match states.iter().position(|s| s == state) {
+            Some(index) => {
+                states.remove(index);
+            }
+            None => states.push(state.to_string()),
+        }
+        self.set(fsm, states);
+    }
+
+    fn clear(&mut self) {
+        self.states.clear();
+    }
+}
+
+/// What the selection dialog does to the selected states
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SelectionAction {
+    Tag,
+    Color,
+    /// Group them into a composite state
+    Extract,
+    /// Add `entry / <value>` to each
+    EntryAction,
+}
+
+/// Tag, color or extract dialog for the states selected on the canvas
+struct SelectionDialog {
+    fsm_index: usize,
+    action: SelectionAction,
+    states: Vec<String>,
+    /// The tag, color, composite state name or entry actions
+    value: String,
+    /// Why the last apply was rejected
+    error: Option<String>,
+}
+
+/// Refactor → Rename dialog
+struct RenameDialog {
+    fsm_index: usize,
+    kind: RenameKind,
+    old_name: String,
+    new_name: String,
+    /// Why the last apply was rejected
+    error: Option<String>,
 }
 
+/// File action that discards the editor content, held back while "Save changes?" is asked
 #[derive(Clone, Debug)]
-struct LayoutedLabel {
-    pos: egui::Pos2,
-    text: String,
+enum FileAction {
+    /// Pick an `.fsm` file and open it
+    Open,
+    /// Pick a project manifest and open it
+    OpenProject,
+    /// Pick an SCXML file and replace the editor content with its DSL
+    ImportScxml,
+    /// Replace the editor content with a template or example
+    Load(&'static str),
+    /// Replace the editor content with new FSMs (New FSM dialog, names as typed)
+    CreateFsms(String),
+    Quit,
 }
 
-#[derive(Clone, Debug, Default)]
-struct LayoutedDiagram {
-    edges: Vec<LayoutedEdge>,
-    labels: Vec<LayoutedLabel>,
+/// `● door_lock.fsm — Oxidate` (the dot marks unsaved changes)
+fn window_title(name: &str, dirty: bool) -> String {
+    format!("{}{} — Oxidate", if dirty { "● " } else { "" }, name)
 }
 
 fn main() -> eframe::Result<()> {
@@ -235,7 +512,7 @@ fn main() -> eframe::Result<()> {
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1400.0, 900.0])
-            .with_title("Oxidate - FSM Visualizer")
+            .with_title(window_title("Untitled", false))
             .with_icon(oxidate_icon()),
         ..Default::default()
     };
@@ -250,14 +527,29 @@ fn main() -> eframe::Result<()> {
 struct OxidateApp {
     /// Source code editor content (all FSMs combined)
     source_code: String,
-    /// Individual FSM source codes (extracted from source_code)
-    fsm_sources: Vec<String>,
+    /// File the editor content was opened from or saved to (includes resolve relative to it)
+    current_file: Option<PathBuf>,
+    /// Editor content as last opened or saved; the buffer is dirty while `source_code` differs
+    saved_source: String,
+    /// Editor tabs: `source_code` cut into one block per FSM
+    fsm_sources: SourceBlocks,
+    /// Hash of each `fsm_sources` block as its FSM was last parsed, so that an edit
+    /// parses only the tabs it changed (empty: the next parse is a full one)
+    parsed_block_hashes: Vec<u64>,
+    /// Open project: one editor tab per member file instead of `fsm_sources`
+    workspace: Option<Workspace>,
+    /// What the editor buffers held when they last parsed, to tell when the diagram is out of date
+    staleness: Staleness,
     /// Generated Rust code
     generated_code: String,
+    /// Folded sections of the generated code, per target and FSM
+    code_folds: CodeFolds,
     /// Parsed FSM definitions
     fsms: Vec<FsmDefinition>,
-    /// Parse error message
-    error_message: Option<String>,
+    /// Why the editor content does not parse, shown above the editor
+    parse_error: Option<String>,
+    /// Recent errors and warnings of every kind
+    console: ErrorConsole,
     /// Selected FSM index
     selected_fsm: usize,
     /// State positions for visualization (calculated automatically)
@@ -268,362 +560,1047 @@ struct OxidateApp {
     layout_config: LayoutConfig,
     /// Whether we must recompute layout using the engine
     layout_dirty: bool,
+    /// Animate between layouts when the layout changes
+    animate_layout: bool,
+    /// Duration of the layout change animation
+    layout_anim_duration_ms: f32,
+    /// In-flight layout change animation
+    layout_anim: Option<LayoutAnimation>,
     /// Show code panel
     show_code_panel: bool,
     /// Show generated code panel
     show_generated_panel: bool,
-    /// Zoom level
-    zoom: f32,
-    /// Pan offset
-    pan_offset: egui::Vec2,
+    /// Diagram colors (canvas and exported images)
+    theme: DiagramTheme,
+    /// Re-parse automatically once the editor has been idle for `AUTO_PARSE_DELAY`
+    auto_parse: bool,
+    /// Time of the last editor change not yet parsed
+    last_edit: Option<Instant>,
+    /// `layout_fingerprint` of the FSM the current layout was computed for
+    layout_fingerprint: Option<u64>,
+    /// Crossings, overlaps and edge length of the current layout
+    layout_quality: Option<LayoutQuality>,
+    /// Recent engine layouts by FSM structure and layout configuration
+    layout_cache: LayoutCache,
+    /// State boxes and text galleys reused between canvas frames
+    render_cache: RenderCache,
+    /// The second viewport of View > Split Diagram
+    split: SplitView,
+    /// Show the minimap overlay in the diagram
+    show_minimap: bool,
+    /// Show the event matrix window
+    show_event_matrix: bool,
+    /// Show the Settings window
+    show_settings: bool,
+    /// UI scale and font sizes (persisted)
+    settings: Settings,
+    /// Events per path the Analyze button explores
+    analysis_depth: usize,
+    /// Findings of the last Analyze run (FSM name, messages), until the source changes
+    analysis: Option<(String, Vec<String>)>,
+    /// Transitions highlighted from the event matrix (FSM name, transitions)
+    highlighted_transitions: Option<(String, Vec<fsm::TransitionId>)>,
+    /// Resolution of copied/saved diagram images
+    export_scale: ExportScale,
+    /// Kept alive so copied images survive on platforms where the owner serves the clipboard
+    clipboard: Option<arboard::Clipboard>,
+    /// Zoom and pan of the diagram canvas (the left viewport in split view)
+    view: CanvasView,
+    /// Zoom-to-fit on the next canvas frame (the canvas size is only known there)
+    fit_requested: bool,
     /// Code generation target
     codegen_target: CodegenTarget,
+    /// Optional code generation features
+    codegen_options: CodegenOptions,
+    /// Problems that prevented code generation for the selected FSM
+    codegen_errors: Vec<String>,
+    /// Names sanitized by lenient code generation, e.g. `action turn on LED → turn_on_led`
+    codegen_notes: Vec<String>,
+    /// Bindings file the HAL actions struct comes from (see `codegen::bindings`)
+    bindings_path: Option<PathBuf>,
+    /// Unbound methods and bindings matching nothing in the selected FSM
+    binding_warnings: Vec<String>,
+    /// Whether `cargo` was found at startup (needed by the Check button)
+    cargo_available: bool,
+    /// `cargo check` running in the background, and the code it checks
+    compile_check: Option<(String, mpsc::Receiver<Result<CompileReport, String>>)>,
+    /// Last compile check result and the code it checked
+    compile_report: Option<(String, Result<CompileReport, String>)>,
+    /// Name completion in the DSL editor
+    completion: CompletionPopup,
+    /// Transition label edit dialog (opened by double-clicking a label)
+    label_edit: Option<LabelEditDialog>,
+    /// Rename state/event dialog (Refactor menu)
+    rename: Option<RenameDialog>,
+    /// Result of the last folder export, shown until dismissed (offers to delete stale files)
+    export_summary: Option<ExportSummary>,
+    /// Canvas edit mode: dragging between states adds transitions
+    edit_mode: bool,
+    edge_drag: Option<EdgeDrag>,
+    new_transition: Option<NewTransitionDialog>,
+    /// Canvas select mode: dragging draws a rubber band that selects states
+    select_mode: bool,
+    /// Select mode: screen position where the rubber band drag started
+    rubber_band: Option<egui::Pos2>,
+    /// States selected on the canvas (click, Ctrl+click or rubber band)
+    selection: StateSelection,
+    selection_dialog: Option<SelectionDialog>,
+    /// What the open canvas context menu is for (set by the right-click)
+    canvas_target: Option<CanvasTarget>,
+    new_state: Option<NewStateDialog>,
+    /// A state added from the context menu and where to show it (from the canvas
+    /// centre) once the layout places it
+    reveal_state: Option<(String, egui::Vec2)>,
+    /// Byte range of the editor's text to select and scroll to (Go to definition)
+    editor_jump: Option<std::ops::Range<usize>>,
+    /// Help > DSL Reference window
+    show_dsl_reference: bool,
+    /// Snippet from the DSL reference to insert at the editor's cursor
+    pending_snippet: Option<&'static str>,
     /// New FSM dialog state
     show_new_fsm_dialog: bool,
     /// New FSM name input
     new_fsm_name: String,
+    /// Template gallery window
+    show_template_gallery: bool,
+    /// Category shown in the template gallery (`None` = all)
+    template_category: Option<&'static str>,
+    /// FSM name and SVG thumbnail of each template, built when the gallery first opens
+    template_previews: Vec<(String, egui::load::Bytes)>,
+    /// File action waiting for the "Save changes?" prompt
+    unsaved_prompt: Option<FileAction>,
+    /// Quitting with unsaved changes was confirmed; the next close request goes through
+    close_confirmed: bool,
+    /// Title last sent to the viewport
+    window_title: String,
 
     /// Debug/simulation mode
     sim: Simulator,
-}
-
-#[derive(Clone, Debug)]
-struct Simulator {
-    enabled: bool,
-    running: bool,
-    speed: f32,
-
-    current_state: Option<String>,
-    queued_events: std::collections::VecDeque<String>,
-    event_input: String,
-
-    auto_tick: bool,
-    auto_event: String,
-    auto_period_s: f32,
-    auto_accum_s: f32,
-
-    last_frame: Option<Instant>,
-    last_fired: Option<SimFired>,
-    log: Vec<String>,
-}
-
-#[derive(Clone, Debug)]
-struct SimFired {
-    transition_index: Option<usize>,
-    from: String,
-    to: String,
-    label: String,
-    started_at: Instant,
-    duration_s: f32,
-}
-
-impl Default for Simulator {
-    fn default() -> Self {
-        Self {
-            enabled: false,
-            running: false,
-            speed: 1.0,
-            current_state: None,
-            queued_events: std::collections::VecDeque::new(),
-            event_input: String::new(),
-            auto_tick: false,
-            auto_event: "timer_expired".to_string(),
-            auto_period_s: 1.0,
-            auto_accum_s: 0.0,
-            last_frame: None,
-            last_fired: None,
-            log: Vec::new(),
-        }
-    }
+    /// Saved guard values per FSM (persisted)
+    guard_profiles: GuardProfiles,
+    /// FSM and name of the guard profile applied last
+    active_profile: Option<(String, String)>,
+    /// Name the sim panel saves the current guard values under
+    profile_name: String,
+    /// Several FSMs simulated together (replaces `sim` while enabled)
+    bus: SimBus,
+    /// Help > Tutorial (offered at startup until finished or turned down)
+    tutorial: Tutorial<OxidateApp>,
+    /// Where the regions a tutorial step can point at were drawn this frame
+    spotlights: HashMap<Spotlight, egui::Rect>,
 }
 
 impl OxidateApp {
-    fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+    fn new(cc: &eframe::CreationContext<'_>) -> Self {
         let mut app = Self {
             source_code: DEFAULT_FSM_CODE.to_string(),
-            fsm_sources: Vec::new(),
+            current_file: None,
+            saved_source: DEFAULT_FSM_CODE.to_string(),
+            fsm_sources: SourceBlocks::default(),
+            parsed_block_hashes: Vec::new(),
+            workspace: None,
+            staleness: Staleness::default(),
             generated_code: String::new(),
+            code_folds: CodeFolds::default(),
             fsms: Vec::new(),
-            error_message: None,
+            parse_error: None,
+            console: ErrorConsole::default(),
             selected_fsm: 0,
             state_positions: HashMap::new(),
             layout: None,
             layout_config: LayoutConfig::default(),
             layout_dirty: true,
+            animate_layout: true,
+            layout_anim_duration_ms: 250.0,
+            layout_anim: None,
             show_code_panel: true,
             show_generated_panel: true,
-            zoom: 1.0,
-            pan_offset: egui::Vec2::ZERO,
+            theme: DiagramTheme::default(),
+            auto_parse: true,
+            last_edit: None,
+            layout_fingerprint: None,
+            layout_quality: None,
+            layout_cache: LayoutCache::default(),
+            render_cache: RenderCache::default(),
+            split: SplitView::default(),
+            show_minimap: true,
+            show_event_matrix: false,
+            show_settings: false,
+            settings: Settings::default(),
+            analysis_depth: fsm::explore::DEFAULT_EXPLORE_DEPTH,
+            analysis: None,
+            highlighted_transitions: None,
+            export_scale: ExportScale::default(),
+            clipboard: None,
+            view: CanvasView::default(),
+            fit_requested: false,
             codegen_target: CodegenTarget::Embassy, // Default to Embassy for embedded
+            codegen_options: CodegenOptions::default(),
+            codegen_errors: Vec::new(),
+            codegen_notes: Vec::new(),
+            bindings_path: None,
+            binding_warnings: Vec::new(),
+            cargo_available: cargo_available(),
+            compile_check: None,
+            compile_report: None,
+            completion: CompletionPopup::default(),
+            label_edit: None,
+            rename: None,
+            export_summary: None,
+            edit_mode: false,
+            edge_drag: None,
+            select_mode: false,
+            rubber_band: None,
+            selection: StateSelection::default(),
+            selection_dialog: None,
+            canvas_target: None,
+            new_state: None,
+            reveal_state: None,
+            editor_jump: None,
+            show_dsl_reference: false,
+            pending_snippet: None,
+            new_transition: None,
             show_new_fsm_dialog: false,
             new_fsm_name: String::new(),
+            show_template_gallery: false,
+            template_category: None,
+            template_previews: Vec::new(),
+            unsaved_prompt: None,
+            close_confirmed: false,
+            window_title: String::new(),
             sim: Simulator::default(),
+            guard_profiles: GuardProfiles::default(),
+            active_profile: None,
+            profile_name: String::new(),
+            bus: SimBus::default(),
+            tutorial: Tutorial::new(tutorial::first_fsm_steps()),
+            spotlights: HashMap::new(),
         };
+        if let Some(settings) = cc.storage.and_then(|s| s.get_string(SETTINGS_KEY)).and_then(|json| Settings::from_json(&json)) {
+            app.settings = settings;
+        }
+        if let Some(profiles) = cc.storage.and_then(|s| s.get_string(PROFILES_KEY)).and_then(|json| GuardProfiles::from_json(&json)) {
+            app.guard_profiles = profiles;
+        }
+        if let Some(record) = cc.storage.and_then(|s| s.get_string(TUTORIAL_KEY)).and_then(|json| TutorialRecord::from_json(&json)) {
+            app.tutorial.record = record;
+        }
+        app.tutorial.offer();
+        app.apply_settings(&cc.egui_ctx);
+        // SVG template thumbnails
+        egui_extras::install_image_loaders(&cc.egui_ctx);
         // Parse the default example on startup
         app.parse_source();
         app
     }
 
-    fn parse_source(&mut self) {
-        // Extract individual FSM source blocks
-        self.extract_fsm_sources();
-        
-        match parse_fsm(&self.source_code) {
-            Ok(fsms) => {
-                self.fsms = fsms;
-                self.error_message = None;
-                if !self.fsms.is_empty() {
-                    self.selected_fsm = 0; // Reset to first FSM
-                    // IMPORTANT: layout is engine-driven. Defer computation to `update()`
-                    // so we can measure fonts for accurate label sizes.
-                    self.layout_dirty = true;
-                    // Generate code for the selected FSM
-                    self.regenerate_code();
-
-                    // Reset simulator to align with the newly parsed FSM.
-                    self.sim.current_state = None;
-                    self.sim.queued_events.clear();
-                    self.sim.last_fired = None;
-                    self.sim.log.clear();
-                } else {
-                    self.generated_code = "// No FSMs parsed".to_string();
-                }
-            }
-            Err(e) => {
-                self.error_message = Some(e.to_string());
-                self.generated_code = format!("// Parse error: {}", e);
-            }
+    /// Apply the (clamped) settings: UI scale, and the diagram fonts the layout depends on
+    fn apply_settings(&mut self, ctx: &egui::Context) {
+        self.settings = self.settings.clone().clamped();
+        if ctx.zoom_factor() != self.settings.ui_scale {
+            ctx.set_zoom_factor(self.settings.ui_scale);
+        }
+        let config = &mut self.layout_config;
+        if config.font_size != self.settings.diagram_font_size
+            || config.edge_label_font_size != self.settings.edge_label_font_size
+            || config.state_box != self.settings.state_box
+        {
+            config.font_size = self.settings.diagram_font_size;
+            config.edge_label_font_size = self.settings.edge_label_font_size;
+            config.state_box = self.settings.state_box;
+            self.mark_layout_dirty();
         }
     }
-    
-    /// Extract individual FSM source code blocks from the combined source
-    fn extract_fsm_sources(&mut self) {
-        self.fsm_sources.clear();
-        
-        let mut current_block = String::new();
-        let mut brace_count = 0;
-        let mut in_fsm = false;
-        let mut pending_comments = String::new();
-        
-        for line in self.source_code.lines() {
-            let trimmed = line.trim();
-            
-            // Collect comments before FSM
-            if !in_fsm && (trimmed.starts_with("//") || trimmed.is_empty()) {
-                pending_comments.push_str(line);
-                pending_comments.push('\n');
-                continue;
-            }
-            
-            // Start of FSM block
-            if trimmed.starts_with("fsm ") {
-                in_fsm = true;
-                current_block = pending_comments.clone();
-                pending_comments.clear();
+
+    fn show_settings_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_settings;
+        let mut changed = false;
+        egui::Window::new("⚙ Settings").open(&mut open).resizable(false).show(ctx, |ui| {
+            let settings = &mut self.settings;
+            egui::Grid::new("settings_grid").num_columns(2).spacing([12.0, 6.0]).show(ui, |ui| {
+                ui.label("UI scale");
+                changed |= ui.add(egui::Slider::new(&mut settings.ui_scale, UI_SCALE_RANGE).step_by(0.05)).changed();
+                ui.end_row();
+                ui.label("Editor font size");
+                changed |= ui.add(egui::Slider::new(&mut settings.editor_font_size, EDITOR_FONT_RANGE).step_by(0.5)).changed();
+                ui.end_row();
+                ui.label("Diagram font size");
+                changed |= ui.add(egui::Slider::new(&mut settings.diagram_font_size, DIAGRAM_FONT_RANGE).step_by(0.5)).changed();
+                ui.end_row();
+                ui.label("Transition label font size");
+                changed |= ui.add(egui::Slider::new(&mut settings.edge_label_font_size, EDGE_LABEL_FONT_RANGE).step_by(0.5)).changed();
+                ui.end_row();
+            });
+            ui.separator();
+            if ui.button("Reset to defaults").clicked() {
+                *settings = Settings::default();
+                changed = true;
             }
-            
-            if in_fsm {
-                current_block.push_str(line);
-                current_block.push('\n');
-                
-                // Count braces
-                brace_count += line.chars().filter(|&c| c == '{').count() as i32;
-                brace_count -= line.chars().filter(|&c| c == '}').count() as i32;
-                
-                // End of FSM block
-                if brace_count == 0 && current_block.contains('{') {
-                    self.fsm_sources.push(current_block.trim().to_string());
-                    current_block.clear();
-                    in_fsm = false;
+        });
+        self.show_settings = open;
+        if changed {
+            self.apply_settings(ctx);
+        }
+    }
+
+    /// Replace the editor content with a single buffer (closes any open project)
+    fn load_source(&mut self, source: impl Into<String>, path: Option<PathBuf>) {
+        self.workspace = None;
+        self.staleness.clear();
+        // A whole new text: parsed in full
+        self.parsed_block_hashes.clear();
+        self.source_code = source.into();
+        self.saved_source = self.source_code.clone();
+        self.current_file = path;
+        self.parse_source();
+    }
+
+    /// Load the DSL of an SCXML file as a new, unsaved buffer; what could not be
+    /// imported goes to the console as warnings
+    fn import_scxml_file(&mut self, path: &std::path::Path) {
+        let imported = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read '{}': {}", path.display(), e))
+            .and_then(|xml| import_scxml(&xml).map_err(|e| format!("Could not import '{}': {}", path.display(), e)));
+        match imported {
+            Ok(import) => {
+                self.load_source(format_fsms(&import.fsms), None);
+                for warning in import.warnings {
+                    self.push_warning(Category::File, format!("SCXML import: {}", warning));
                 }
-            } else {
-                pending_comments.clear();
             }
+            Err(message) => self.push_error(Category::File, message),
         }
-        
-        // Handle any remaining block
-        if !current_block.trim().is_empty() {
-            self.fsm_sources.push(current_block.trim().to_string());
+    }
+
+    /// Declare the enumerators of the C header or Rust file at `path` in an events
+    /// block of the selected FSM; those it declares already go to the console
+    fn import_events_file(&mut self, path: &std::path::Path) {
+        let origin = path.file_name().map_or_else(|| path.display().to_string(), |f| f.to_string_lossy().into_owned());
+        let mut conflicts = Vec::new();
+        let edited = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read '{}': {}", path.display(), e))
+            .and_then(|text| {
+                self.edit_fsm_source(
+                    self.selected_fsm,
+                    |source, fsm| {
+                        let import = import_events(&text, None, fsm).map_err(|e| format!("Could not import events from '{origin}': {e}"))?;
+                        conflicts = import.conflicts.clone();
+                        match import.events_block(&origin) {
+                            Some(block) => add_events_block(source, fsm, &block).ok_or_else(|| format!("No line to add the events block on in {}", fsm.name)),
+                            None => Ok(source.to_string()),
+                        }
+                    },
+                    |_, _| Ok(()),
+                )
+            });
+        for conflict in conflicts {
+            self.push_warning(Category::Edit, format!("Event import: {}", conflict));
+        }
+        if let Err(error) = edited {
+            self.push_error(Category::Edit, error);
         }
     }
-    
-    /// Update source_code from individual fsm_sources
-    fn rebuild_source_code(&mut self) {
-        self.source_code = self.fsm_sources.join("\n\n");
+
+    /// Whether the editor or any project member has unsaved edits
+    fn is_dirty(&self) -> bool {
+        match self.workspace {
+            Some(ref workspace) => workspace.members.iter().any(|m| m.is_dirty()),
+            None => self.source_code != self.saved_source,
+        }
     }
-    
-    fn regenerate_code(&mut self) {
-        if let Some(fsm) = self.fsms.get(self.selected_fsm) {
-            self.generated_code = generate_rust_code_with_target(fsm, self.codegen_target);
+
+    /// Run `action`, first asking "Save changes?" when it would discard unsaved edits
+    fn request_file_action(&mut self, ctx: &egui::Context, action: FileAction) {
+        if self.is_dirty() {
+            self.unsaved_prompt = Some(action);
         } else {
-            self.generated_code = format!("// No FSM at index {}", self.selected_fsm);
+            self.run_file_action(ctx, action);
         }
     }
 
-    fn mark_layout_dirty(&mut self) {
-        self.layout_dirty = true;
+    fn run_file_action(&mut self, ctx: &egui::Context, action: FileAction) {
+        match action {
+            FileAction::Open => {
+                if let Some(path) = rfd::FileDialog::new().add_filter("FSM", &["fsm", "txt"]).pick_file() {
+                    match std::fs::read_to_string(&path) {
+                        Ok(content) => self.load_source(content, Some(path)),
+                        Err(e) => self.push_error(Category::File, format!("Could not read '{}': {}", path.display(), e)),
+                    }
+                }
+            }
+            FileAction::OpenProject => {
+                if let Some(path) = rfd::FileDialog::new().add_filter("Oxidate project", &["toml"]).pick_file() {
+                    self.open_project(&path);
+                }
+            }
+            FileAction::ImportScxml => {
+                if let Some(path) = rfd::FileDialog::new().add_filter("SCXML", &["scxml", "xml"]).pick_file() {
+                    self.import_scxml_file(&path);
+                }
+            }
+            FileAction::Load(source) => self.load_source(source, None),
+            FileAction::CreateFsms(names) => self.create_new_fsms(&names),
+            FileAction::Quit => {
+                self.close_confirmed = true;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+        }
     }
 
-    fn measure_text(ctx: &egui::Context, text: &str, font_size: f32) -> egui::Vec2 {
-        let font_id = egui::FontId::proportional(font_size);
-        ctx.fonts(|fonts| {
-            let galley = fonts.layout_no_wrap(text.to_owned(), font_id, egui::Color32::WHITE);
-            galley.size()
-        })
+    /// Save the editor to its file (Save As without one), or the selected project member;
+    /// returns whether it was saved
+    fn save_current(&mut self) -> bool {
+        if let Some(ref mut workspace) = self.workspace {
+            let member = workspace.selected_member_mut();
+            if let Err(e) = member.save() {
+                let message = format!("Could not save '{}': {}", member.path.display(), e);
+                self.push_error(Category::File, message);
+                return false;
+            }
+            return true;
+        }
+        match self.current_file.clone() {
+            Some(path) => self.write_source(path),
+            None => self.save_as(),
+        }
     }
 
-    fn compute_layout_with_dagre(&mut self, ctx: &egui::Context, fsm: &FsmDefinition) -> Result<(), String> {
-        #[derive(Serialize)]
-        struct JsGraphCfg {
-            rankdir: String,
-            nodesep: f32,
-            ranksep: f32,
-            edgesep: f32,
-            marginx: f32,
-            marginy: f32,
+    /// Save the editor to a file picked by the user; returns whether it was saved
+    fn save_as(&mut self) -> bool {
+        let mut dialog = rfd::FileDialog::new().add_filter("FSM", &["fsm"]);
+        if let Some(name) = self.current_file.as_ref().and_then(|p| p.file_name()) {
+            dialog = dialog.set_file_name(name.to_string_lossy());
         }
-
-        #[derive(Serialize)]
-        struct JsNodeIn {
-            id: String,
-            width: f32,
-            height: f32,
+        match dialog.save_file() {
+            Some(path) => self.write_source(path),
+            None => false,
         }
+    }
 
-        #[derive(Serialize)]
-        struct JsEdgeIn {
-            v: String,
-            w: String,
-            name: Option<String>,
-            #[serde(rename = "labelWidth")]
-            label_width: Option<f32>,
-            #[serde(rename = "labelHeight")]
-            label_height: Option<f32>,
+    fn write_source(&mut self, path: PathBuf) -> bool {
+        match std::fs::write(&path, &self.source_code) {
+            Ok(()) => {
+                self.saved_source = self.source_code.clone();
+                self.current_file = Some(path);
+                true
+            }
+            Err(e) => {
+                self.push_error(Category::File, format!("Could not save '{}': {}", path.display(), e));
+                false
+            }
         }
+    }
 
-        #[derive(Serialize)]
-        struct JsLayoutInput {
-            graph: JsGraphCfg,
-            nodes: Vec<JsNodeIn>,
-            edges: Vec<JsEdgeIn>,
+    /// Save before a prompted action: every dirty project member, or the editor
+    fn save_all_changes(&mut self) -> bool {
+        match self.workspace {
+            Some(ref mut workspace) => {
+                let errors = workspace.save_all();
+                let saved = errors.is_empty();
+                for error in errors {
+                    self.push_error(Category::File, error);
+                }
+                saved
+            }
+            None => self.save_current(),
         }
+    }
 
-        #[derive(Deserialize)]
-        struct JsPoint {
-            x: f32,
-            y: f32,
+    /// "Save changes?" before an action that would discard unsaved edits
+    fn show_unsaved_prompt(&mut self, ctx: &egui::Context) {
+        let Some(ref action) = self.unsaved_prompt else {
+            return;
+        };
+        let what = match action {
+            FileAction::Quit => "before quitting",
+            _ => "before continuing",
+        };
+        let mut choice = None;
+        egui::Window::new("Save changes?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("{} has unsaved changes. Save them {}?", self.document_name(), what));
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    if ui.button("💾 Save").clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button("Discard").clicked() {
+                        choice = Some(false);
+                    }
+                    if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                        self.unsaved_prompt = None;
+                    }
+                });
+            });
+        let Some(save) = choice else {
+            return;
+        };
+        let action = self.unsaved_prompt.take().expect("prompt is open");
+        // A cancelled Save As or a failed write cancels the action too
+        if !save || self.save_all_changes() {
+            self.run_file_action(ctx, action);
         }
+    }
 
-        #[derive(Deserialize)]
-        struct JsNodeOut {
-            x: f32,
-            y: f32,
-            width: f32,
-            height: f32,
+    /// Open project's name, the editor's file name, or `Untitled`
+    fn document_name(&self) -> String {
+        match (&self.workspace, &self.current_file) {
+            (Some(workspace), _) => workspace.project.name().to_string(),
+            (None, Some(path)) => path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned()),
+            (None, None) => "Untitled".to_string(),
         }
+    }
 
-        #[derive(Deserialize)]
-        struct JsGraphOut {
-            width: f32,
-            height: f32,
+    /// Keep the viewport title in sync with the document and its dirty state
+    fn update_window_title(&mut self, ctx: &egui::Context) {
+        let title = window_title(&self.document_name(), self.is_dirty());
+        if title != self.window_title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.window_title = title;
         }
+    }
 
-        #[derive(Deserialize)]
-        struct JsEdgeOut {
-            v: String,
-            w: String,
-            name: Option<String>,
-            points: Vec<JsPoint>,
-            x: Option<f32>,
-            y: Option<f32>,
+    fn open_project(&mut self, path: &std::path::Path) {
+        match Workspace::open(path) {
+            Ok(workspace) => {
+                // Members parse as they load
+                self.staleness.clear();
+                for (i, member) in workspace.members.iter().enumerate().filter(|(_, m)| m.error.is_none()) {
+                    self.staleness.record(i, &member.source);
+                }
+                self.workspace = Some(workspace);
+                self.fsm_sources = SourceBlocks::default();
+                self.parsed_block_hashes.clear();
+                self.current_file = None;
+                self.selected_fsm = 0;
+                self.show_workspace();
+            }
+            Err(e) => self.push_error(Category::File, e.to_string()),
         }
+    }
 
-        #[derive(Deserialize)]
-        struct JsLayoutOutput {
-            graph: JsGraphOut,
-            nodes: std::collections::HashMap<String, JsNodeOut>,
-            edges: Vec<JsEdgeOut>,
+    fn parse_source(&mut self) {
+        if let Some(ref mut workspace) = self.workspace {
+            if workspace.selected_member_mut().parse() {
+                self.staleness.record(workspace.selected, &workspace.selected_member().source);
+            }
+            self.show_workspace();
+            return;
+        }
+        if self.parse_changed_blocks() {
+            return;
+        }
+        // Extract individual FSM source blocks
+        self.extract_fsm_sources();
+        
+        let result = self.parse_current_source();
+        if result.is_ok() {
+            self.staleness.record(0, &self.source_code);
+            self.parsed_block_hashes = self.fsm_sources.hashes();
         }
+        self.apply_parse_result(result);
+    }
 
-        // Graph config to send to JS Dagre.
-        let graph_cfg = JsGraphCfg {
-            rankdir: match self.layout_config.direction {
-                LayoutDirection::TB => "tb".to_string(),
-                LayoutDirection::LR => "lr".to_string(),
-            },
-            nodesep: self.layout_config.nodesep,
-            ranksep: self.layout_config.ranksep,
-            edgesep: self.layout_config.edgesep,
-            marginx: self.layout_config.marginx,
-            marginy: self.layout_config.marginy,
+    /// Parse again only the FSM tabs edited since the last parse and swap their FSMs in,
+    /// leaving the others (and their layouts and simulation) alone. False if the whole
+    /// source has to be parsed instead: it is not what the tabs hold, or tabs were
+    /// added, removed or merged. A tab that fails to parse only reports the error.
+    fn parse_changed_blocks(&mut self) -> bool {
+        if self.workspace.is_some() || self.fsm_sources.join() != self.source_code {
+            return false;
+        }
+        let old_fsm = self.fsms.get(self.selected_fsm).cloned();
+        let sim_index = self.pane_fsm_index(self.split.sim_pane());
+        let old_sim_fsm = self.pane_fsm(self.split.sim_pane()).cloned();
+        let path = self.current_file.clone();
+        let parse = |text: &str| match path {
+            Some(ref path) => parse_fsm_source(text, path),
+            None => parse_fsm(text),
         };
+        let changed = match self.fsm_sources.reparse(&mut self.fsms, &mut self.parsed_block_hashes, parse) {
+            Ok(Reparse::Blocks(changed)) => changed,
+            Ok(Reparse::Full) => return false,
+            Err(e) => {
+                self.set_parse_error(e.to_string());
+                return true;
+            }
+        };
+        self.staleness.record(0, &self.source_code);
+        self.parse_error = None;
+        if changed.is_empty() {
+            return true;
+        }
 
-        // Nodes.
-        let mut nodes_in: Vec<JsNodeIn> = Vec::new();
-        for state in &fsm.states {
-            let size = estimate_state_size(state);
-            nodes_in.push(JsNodeIn {
-                id: state.name.clone(),
-                width: size.x,
-                height: size.y,
-            });
+        // Findings and highlights of an FSM that changed are out of date
+        let unchanged = |name: &String| self.fsms.iter().enumerate().any(|(i, f)| !changed.contains(&i) && f.name == *name);
+        if !self.analysis.as_ref().is_some_and(|(name, _)| unchanged(name)) {
+            self.analysis = None;
         }
+        if !self.highlighted_transitions.as_ref().is_some_and(|(name, _)| unchanged(name)) {
+            self.highlighted_transitions = None;
+        }
+        self.layout_cache.retain_fsms(self.fsms.iter().map(|f| f.name.as_str()));
+        if changed.contains(&self.selected_fsm) {
+            self.refresh_selected_fsm(old_fsm.as_ref());
+        }
+        if sim_index.is_some_and(|i| changed.contains(&i)) {
+            self.reattach_sim(old_sim_fsm.as_ref());
+        }
+        self.bus.reconcile(&self.fsms);
+        true
+    }
 
-        // Pseudo start node.
-        let start_id = "[*]".to_string();
-        let has_start = fsm.transitions.iter().any(|t| t.source == "[*]");
-        if has_start {
-            nodes_in.push(JsNodeIn {
-                id: start_id.clone(),
-                width: 16.0,
-                height: 16.0,
-            });
+    /// Parse what the editor shows (Parse & Visualize, Ctrl+R)
+    fn parse_editor(&mut self) {
+        if self.workspace.is_none() && self.selected_fsm < self.fsm_sources.blocks.len() {
+            self.rebuild_source_code();
         }
+        self.parse_source();
+    }
 
-        // Represent every transition as an intermediate node (optionally sized to the label).
-        let mut transition_node_type: std::collections::HashMap<String, TransitionType> = std::collections::HashMap::new();
-        let mut label_node_text: std::collections::HashMap<String, String> = std::collections::HashMap::new();
-        let mut edges_in: Vec<JsEdgeIn> = Vec::new();
+    /// Whether the editor holds text the diagram was not parsed from
+    fn diagram_is_stale(&self) -> bool {
+        match self.workspace {
+            Some(ref workspace) => self.staleness.is_stale(workspace.members.iter().map(|m| m.source.as_str())),
+            None => self.staleness.is_stale([self.source_code.as_str()]),
+        }
+    }
 
-        for (t_idx, transition) in fsm.transitions.iter().enumerate() {
-            if transition.source == "[*]" {
-                edges_in.push(JsEdgeIn {
-                    v: start_id.clone(),
-                    w: transition.target.clone(),
-                    name: Some(format!("start_{t_idx}")),
-                    label_width: Some(0.0),
-                    label_height: Some(0.0),
-                });
-                continue;
+    /// Re-parse after the editor went idle. A failed parse only reports the error:
+    /// the last good FSMs stay rendered and the editor's FSM blocks are not re-split.
+    fn auto_parse(&mut self) {
+        self.last_edit = None;
+        if self.workspace.is_some() {
+            // Members parse on their own and keep their last good FSMs
+            self.parse_source();
+            return;
+        }
+        if self.parse_changed_blocks() {
+            return;
+        }
+        match self.parse_current_source() {
+            Ok(fsms) => {
+                self.staleness.record(0, &self.source_code);
+                self.extract_fsm_sources();
+                self.parsed_block_hashes = self.fsm_sources.hashes();
+                self.apply_parse_result(Ok(fsms));
             }
+            Err(e) => self.set_parse_error(e.to_string()),
+        }
+    }
 
-            let transition_node_id = format!("__tr_{t_idx}");
-
-            let raw_label = transition.label();
-            let label = format_label_text(&raw_label);
+    fn parse_current_source(&self) -> Result<Vec<FsmDefinition>, parser::ParseError> {
+        match &self.current_file {
+            Some(path) => parse_fsm_source(&self.source_code, path),
+            None => parse_fsm(&self.source_code),
+        }
+    }
 
-            // Styling only (does NOT affect layout/routing)
-            let transition_type = {
-                let event_name = transition.event.as_ref().map(|e| e.name.to_lowercase()).unwrap_or_default();
-                if event_name.contains("timeout") || event_name.contains("timer") || event_name.contains("expired") {
-                    TransitionType::Timer
-                } else if transition.guard.is_some() {
-                    TransitionType::Conditional
+    fn apply_parse_result(&mut self, result: Result<Vec<FsmDefinition>, parser::ParseError>) {
+        match result {
+            Ok(fsms) => {
+                let old_fsm = self.fsms.get(self.selected_fsm).cloned();
+                let old_sim_fsm = self.pane_fsm(self.split.sim_pane()).cloned();
+                self.fsms = fsms;
+                self.parse_error = None;
+                self.highlighted_transitions = None;
+                self.analysis = None;
+                self.layout_cache.retain_fsms(self.fsms.iter().map(|f| f.name.as_str()));
+                if !self.fsms.is_empty() {
+                    // Stay on the same FSM if it still exists, otherwise fall back to the first one
+                    self.selected_fsm = old_fsm
+                        .as_ref()
+                        .and_then(|old| self.fsms.iter().position(|f| f.name == old.name))
+                        .unwrap_or(0);
+                    self.refresh_selected_fsm(old_fsm.as_ref());
+
+                    // Keep the simulation going if the edit left it meaningful.
+                    self.reattach_sim(old_sim_fsm.as_ref());
+                    self.bus.reconcile(&self.fsms);
                 } else {
-                    TransitionType::Forward
+                    self.generated_code = "// Nothing to generate yet: add an `fsm Name { ... }` block".to_string();
                 }
-            };
-
-            transition_node_type.insert(transition_node_id.clone(), transition_type);
-
-            if label.is_empty() {
-                nodes_in.push(JsNodeIn {
-                    id: transition_node_id.clone(),
-                    width: 1.0,
-                    height: 1.0,
+            }
+            Err(e) => {
+                self.set_parse_error(e.to_string());
+                self.generated_code = format!("// Parse error: {}", e);
+            }
+        }
+    }
+    
+    /// Layout, layout hint and generated code after the selected FSM was parsed again
+    /// (it was `old_fsm` before)
+    fn refresh_selected_fsm(&mut self, old_fsm: Option<&FsmDefinition>) {
+        // IMPORTANT: layout is engine-driven. Defer computation to `update()`
+        // so we can measure fonts for accurate label sizes. Edits that leave
+        // the structure alone (comments, descriptions) keep the current layout.
+        let fingerprint = diagram::layout_fingerprint(&self.fsms[self.selected_fsm], &self.layout_config.state_box);
+        if self.layout.is_none() || self.layout_fingerprint != Some(fingerprint) {
+            self.layout_dirty = true;
+        }
+        self.apply_layout_hint(old_fsm);
+        // Generate code for the selected FSM
+        self.regenerate_code();
+    }
+
+    /// Show every member's FSMs, staying on the selected member's machines, with its error
+    fn show_workspace(&mut self) {
+        let Some(ref workspace) = self.workspace else {
+            return;
+        };
+        let range = workspace.fsm_range(workspace.selected);
+        let error = workspace.selected_member().error.clone();
+        self.apply_parse_result(Ok(workspace.fsms()));
+        if !range.is_empty() && !range.contains(&self.selected_fsm) {
+            self.select_fsm(range.start);
+        }
+        match error {
+            Some(error) => self.set_parse_error(error),
+            None => self.parse_error = None,
+        }
+    }
+
+    /// Show `error` above the editor and log it to the console
+    fn set_parse_error(&mut self, error: String) {
+        self.push_error(Category::Parse, error.clone());
+        self.parse_error = Some(error);
+    }
+
+    fn push_error(&mut self, category: Category, message: impl Into<String>) {
+        self.console.push(category, Severity::Error, message);
+    }
+
+    fn push_warning(&mut self, category: Category, message: impl Into<String>) {
+        self.console.push(category, Severity::Warning, message);
+    }
+
+    /// Switch the diagram and generated code to another FSM
+    fn select_fsm(&mut self, index: usize) {
+        self.selected_fsm = index;
+        self.apply_layout_hint(None);
+        self.mark_layout_dirty();
+        self.regenerate_code();
+    }
+
+    /// Text of the buffer shown in the editor
+    fn editor_text(&self) -> &str {
+        match self.workspace {
+            Some(ref workspace) => &workspace.selected_member().source,
+            None => &self.fsm_sources.blocks[self.selected_fsm],
+        }
+    }
+
+    fn editor_text_mut(&mut self) -> &mut String {
+        match self.workspace {
+            Some(ref mut workspace) => &mut workspace.selected_member_mut().source,
+            None => &mut self.fsm_sources.blocks[self.selected_fsm],
+        }
+    }
+
+    /// Pre-select the direction the selected FSM asks for (`direction LR`), unless it was already applied
+    fn apply_layout_hint(&mut self, previous: Option<&FsmDefinition>) {
+        let Some(fsm) = self.fsms.get(self.selected_fsm) else {
+            return;
+        };
+        let Some(hint) = fsm.layout_hint else {
+            return;
+        };
+        if previous.is_some_and(|p| p.name == fsm.name && p.layout_hint == Some(hint)) {
+            return;
+        }
+        self.layout_config.direction = hint.into();
+    }
+
+    /// Cut the combined source into the editor's FSM tabs. Source that does not parse
+    /// keeps the tabs it was joined from (an edit in progress), or else opens as one tab.
+    fn extract_fsm_sources(&mut self) {
+        match SourceBlocks::split(&self.source_code) {
+            Ok(blocks) => self.fsm_sources = blocks,
+            Err(_) if self.fsm_sources.join() == self.source_code => {}
+            Err(_) => self.fsm_sources = SourceBlocks::whole(&self.source_code),
+        }
+    }
+
+    /// Update source_code from the edited FSM tabs
+    fn rebuild_source_code(&mut self) {
+        self.source_code = self.fsm_sources.join();
+    }
+
+    fn regenerate_code(&mut self) {
+        self.codegen_errors.clear();
+        self.codegen_notes.clear();
+        self.binding_warnings.clear();
+        if let Some(fsm) = self.fsms.get(self.selected_fsm) {
+            if self.codegen_options.lenient_names {
+                let renames = fsm::naming::check_names(fsm, true).unwrap_or_default();
+                self.codegen_notes = renames.iter().map(|r| r.to_string()).collect();
+            }
+            if let Some(ref bindings) = self.codegen_options.bindings {
+                if self.codegen_target != CodegenTarget::C {
+                    self.binding_warnings = bindings.check(fsm, &self.codegen_options).warnings(&fsm.name);
+                }
+            }
+            match try_generate_rust_code_with_options(fsm, self.codegen_target, &self.codegen_options) {
+                Ok(code) => self.generated_code = code,
+                Err(errors) => {
+                    self.codegen_errors = errors.iter().map(|e| e.to_string()).collect();
+                    self.generated_code = failure_report(&fsm.name, &self.codegen_errors);
+                    for error in &self.codegen_errors {
+                        self.console.push(Category::Codegen, Severity::Error, format!("{}: {}", fsm.name, error));
+                    }
+                }
+            }
+        } else {
+            self.generated_code = format!("// No FSM at index {}", self.selected_fsm);
+        }
+    }
+
+    /// Pick a bindings file and implement the actions trait with its snippets
+    fn load_bindings(&mut self) {
+        let Some(path) = rfd::FileDialog::new().add_filter("Bindings", &["toml"]).pick_file() else {
+            return;
+        };
+        match Bindings::load(&path) {
+            Ok(bindings) => {
+                self.codegen_options.bindings = Some(bindings);
+                self.bindings_path = Some(path);
+                self.regenerate_code();
+                for warning in self.binding_warnings.clone() {
+                    self.push_warning(Category::Codegen, warning);
+                }
+            }
+            Err(e) => self.push_error(Category::Codegen, e.to_string()),
+        }
+    }
+
+    /// `cargo check` the generated code on a background thread
+    fn start_compile_check(&mut self) {
+        let (sender, receiver) = mpsc::channel();
+        let (target, code) = (self.codegen_target, self.generated_code.clone());
+        std::thread::spawn(move || {
+            let _ = sender.send(check_generated_code(target, &code).map_err(|e| e.to_string()));
+        });
+        self.compile_check = Some((self.generated_code.clone(), receiver));
+    }
+
+    /// Diagnostic level per 1-based line of the generated code, if the last check is for the code shown
+    fn compile_marks(&self) -> HashMap<usize, DiagnosticLevel> {
+        let mut marks = HashMap::new();
+        if let Some((code, Ok(report))) = &self.compile_report {
+            if *code == self.generated_code {
+                for diagnostic in &report.diagnostics {
+                    if let Some(line) = diagnostic.line {
+                        let mark = marks.entry(line).or_insert(diagnostic.level);
+                        if diagnostic.level == DiagnosticLevel::Error {
+                            *mark = DiagnosticLevel::Error;
+                        }
+                    }
+                }
+            }
+        }
+        marks
+    }
+
+    /// Collapsible list of the last compile check's errors and warnings
+    fn show_compile_report(&self, ui: &mut egui::Ui) {
+        let Some((code, result)) = &self.compile_report else {
+            return;
+        };
+        let outdated = if *code == self.generated_code { "" } else { " (outdated)" };
+        let report = match result {
+            Ok(report) => report,
+            Err(e) => {
+                ui.colored_label(egui::Color32::LIGHT_RED, format!("❌ Check failed{}: {}", outdated, e));
+                return;
+            }
+        };
+        let (errors, warnings) = (report.count(DiagnosticLevel::Error), report.count(DiagnosticLevel::Warning));
+        let title = if report.success {
+            format!("✅ Compiles, {} warning(s){}", warnings, outdated)
+        } else {
+            format!("❌ {} error(s), {} warning(s){}", errors, warnings, outdated)
+        };
+        egui::CollapsingHeader::new(title)
+            .id_salt("compile_report")
+            .default_open(!report.diagnostics.is_empty())
+            .show(ui, |ui| {
+                for diagnostic in &report.diagnostics {
+                    let color = match diagnostic.level {
+                        DiagnosticLevel::Error => egui::Color32::LIGHT_RED,
+                        DiagnosticLevel::Warning => egui::Color32::YELLOW,
+                    };
+                    let line = diagnostic.line.map(|l| format!("line {}: ", l)).unwrap_or_default();
+                    ui.colored_label(color, format!("{}{}", line, diagnostic.message))
+                        .on_hover_text(egui::RichText::new(&diagnostic.rendered).monospace());
+                }
+            });
+    }
+
+    fn mark_layout_dirty(&mut self) {
+        self.layout_dirty = true;
+        self.split.layout_dirty = true;
+    }
+
+    /// Start animating from the previous layout, if it was for the same FSM
+    fn start_layout_animation(
+        &mut self,
+        previous_layout: Option<LayoutedDiagram>,
+        previous_positions: HashMap<String, egui::Pos2>,
+    ) {
+        self.layout_anim = None;
+        if !self.animate_layout {
+            return;
+        }
+        let same_fsm = |from: &LayoutedDiagram| {
+            self.layout.as_ref().is_some_and(|to| to.fsm_name == from.fsm_name)
+        };
+        if let Some(from_layout) = previous_layout.filter(same_fsm) {
+            self.layout_anim = Some(LayoutAnimation {
+                from_layout,
+                from_positions: previous_positions,
+                started_at: Instant::now(),
+            });
+        }
+    }
+
+    /// Eased progress (0..1) of the layout animation, or `None` when not animating
+    fn layout_anim_progress(&self) -> Option<f32> {
+        let anim = self.layout_anim.as_ref()?;
+        let duration_s = (self.layout_anim_duration_ms / 1000.0).max(0.001);
+        let t = (anim.started_at.elapsed().as_secs_f32() / duration_s).clamp(0.0, 1.0);
+        Some(t * t * (3.0 - 2.0 * t))
+    }
+
+    fn compute_layout_with_dagre(&mut self, ctx: &egui::Context, fsm: &FsmDefinition) -> Result<(), String> {
+        // Without transitions (or states) there is nothing to route: no need for Node.js
+        if fsm.transitions.is_empty() {
+            let (layout, positions) = grid_layout(fsm, self.layout_config.font_size, &self.layout_config.state_box);
+            self.layout = Some(layout);
+            self.state_positions = positions;
+            return Ok(());
+        }
+
+        let cache_key = layout_key(fsm, &self.layout_config.state_box, &self.layout_config);
+        if let Some(cached) = self.layout_cache.get(cache_key) {
+            let mut layout = cached.layout.clone();
+            // Same structure, but descriptions and comments may have changed
+            layout.states = fsm.states.clone();
+            self.state_positions = cached.state_positions.clone();
+            self.layout = Some(layout);
+            return Ok(());
+        }
+
+        // Graph config to send to JS Dagre.
+        let graph_cfg = JsGraphCfg {
+            rankdir: match self.layout_config.direction {
+                LayoutDirection::TB => "tb".to_string(),
+                LayoutDirection::BT => "bt".to_string(),
+                LayoutDirection::LR => "lr".to_string(),
+                LayoutDirection::RL => "rl".to_string(),
+            },
+            nodesep: self.layout_config.nodesep,
+            ranksep: self.layout_config.ranksep,
+            edgesep: self.layout_config.edgesep,
+            marginx: self.layout_config.marginx,
+            marginy: self.layout_config.marginy,
+        };
+
+        // Nodes.
+        let mut nodes_in: Vec<JsNodeIn> = Vec::new();
+        for state in &fsm.states {
+            let size = estimate_state_size(state, self.layout_config.font_size, &self.layout_config.state_box);
+            nodes_in.push(JsNodeIn {
+                id: state.name.clone(),
+                width: size.x,
+                height: size.y,
+            });
+        }
+
+        // Pseudo start node: one per FSM, into its initial state (extra unguarded `[*] -->`
+        // lines are validation errors and not drawn), and through a label node into the
+        // target of each guarded initial transition.
+        let start_id = "[*]".to_string();
+        let mut edges_in: Vec<JsEdgeIn> = Vec::new();
+        let mut label_node_text: std::collections::HashMap<String, String> = std::collections::HashMap::new();
+        if !fsm.initial_transitions.is_empty() {
+            nodes_in.push(JsNodeIn {
+                id: start_id.clone(),
+                width: 16.0,
+                height: 16.0,
+            });
+        }
+        for (index, initial) in fsm.initial_candidates().into_iter().enumerate() {
+            let Some((node_id, label)) = diagram::initial_label_node(index, initial) else {
+                edges_in.push(JsEdgeIn {
+                    v: start_id.clone(),
+                    w: initial.target.clone(),
+                    name: Some("start".to_string()),
+                    label_width: Some(0.0),
+                    label_height: Some(0.0),
+                });
+                continue;
+            };
+            let label_size = self.render_cache.measure(ctx).text_size(&label, self.layout_config.edge_label_font_size);
+            nodes_in.push(JsNodeIn {
+                id: node_id.clone(),
+                width: label_size.x + 14.0,
+                height: label_size.y + 8.0,
+            });
+            edges_in.push(JsEdgeIn {
+                v: start_id.clone(),
+                w: node_id.clone(),
+                name: Some(format!("start_{index}_a")),
+                label_width: Some(0.0),
+                label_height: Some(0.0),
+            });
+            edges_in.push(JsEdgeIn {
+                v: node_id.clone(),
+                w: initial.target.clone(),
+                name: Some(format!("start_{index}_b")),
+                label_width: Some(0.0),
+                label_height: Some(0.0),
+            });
+            label_node_text.insert(node_id, label);
+        }
+
+        // Pseudo node wildcard (`* -->`) transitions leave from.
+        if fsm.transitions.iter().any(|t| t.is_from_any_state()) {
+            nodes_in.push(JsNodeIn {
+                id: ANY_STATE.to_string(),
+                width: 28.0,
+                height: 28.0,
+            });
+        }
+
+        // Represent every transition as an intermediate node (optionally sized to the label).
+        let mut transition_node_type: std::collections::HashMap<String, TransitionType> = std::collections::HashMap::new();
+        // Label nodes are named after the transition's id, which survives reordering
+        let transition_ids = fsm.transition_ids();
+        let mut label_node_transition: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+
+        for (t_idx, transition) in fsm.transitions.iter().enumerate() {
+            if transition.source == "[*]" {
+                continue;
+            }
+
+            let transition_node_id = format!("__tr_{}", transition_ids[t_idx]);
+            label_node_transition.insert(transition_node_id.clone(), t_idx);
+
+            let label = self.transition_label_text(ctx, transition);
+
+            transition_node_type.insert(transition_node_id.clone(), diagram::transition_type(transition));
+
+            if label.is_empty() {
+                nodes_in.push(JsNodeIn {
+                    id: transition_node_id.clone(),
+                    width: 1.0,
+                    height: 1.0,
                 });
             } else {
-                let label_size = Self::measure_text(ctx, &label, self.layout_config.edge_label_font_size);
+                let label_size = self.render_cache.measure(ctx).text_size(&label, self.layout_config.edge_label_font_size);
                 nodes_in.push(JsNodeIn {
                     id: transition_node_id.clone(),
                     width: label_size.x + 14.0,
@@ -649,6 +1626,7 @@ impl OxidateApp {
         }
 
         let input = JsLayoutInput {
+            protocol: DAGRE_PROTOCOL,
             graph: graph_cfg,
             nodes: nodes_in,
             edges: edges_in,
@@ -668,7 +1646,7 @@ impl OxidateApp {
         let node = node_binary();
         let mut child = Command::new(&node)
             .current_dir(&demo_dir)
-            .arg(script)
+            .arg(&script)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
@@ -694,41 +1672,23 @@ impl OxidateApp {
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
             return Err(format!(
-                "Dagre (Node.js) layout failed.\n\nIf you haven't yet, run: `cd tools/dagre-svg-demo && npm install`\n\nError:\n{}",
+                "Dagre (Node.js) layout failed running {}.\n\nIf you haven't yet, run: `cd tools/dagre-svg-demo && npm install`\n\nError:\n{}",
+                script.display(),
                 stderr.trim()
             ));
         }
 
-        let js_layout: JsLayoutOutput = serde_json::from_slice(&output.stdout)
-            .map_err(|e| format!("Failed to parse Dagre output JSON: {e}"))?;
+        let js_layout = parse_layout_output(&output.stdout, &output.stderr, &script).map_err(|e| e.to_string())?;
 
         // Compute center using returned nodes/edge points.
-        let mut min_x = f32::INFINITY;
-        let mut min_y = f32::INFINITY;
-        let mut max_x = f32::NEG_INFINITY;
-        let mut max_y = f32::NEG_INFINITY;
-
-        for n in js_layout.nodes.values() {
-            min_x = min_x.min(n.x - n.width * 0.5);
-            max_x = max_x.max(n.x + n.width * 0.5);
-            min_y = min_y.min(n.y - n.height * 0.5);
-            max_y = max_y.max(n.y + n.height * 0.5);
-        }
-        for e in &js_layout.edges {
-            for p in &e.points {
-                min_x = min_x.min(p.x);
-                max_x = max_x.max(p.x);
-                min_y = min_y.min(p.y);
-                max_y = max_y.max(p.y);
-            }
-        }
-        if !min_x.is_finite() {
-            min_x = 0.0;
-            min_y = 0.0;
-            max_x = 0.0;
-            max_y = 0.0;
-        }
-        let center = egui::vec2((min_x + max_x) * 0.5, (min_y + max_y) * 0.5);
+        let bounds = layout_bounds(
+            js_layout
+                .nodes
+                .values()
+                .map(|n| egui::Rect::from_center_size(egui::pos2(n.x, n.y), egui::vec2(n.width, n.height))),
+            js_layout.edges.iter().flat_map(|e| e.points.iter().map(|p| egui::pos2(p.x, p.y))),
+        );
+        let center = bounds.center().to_vec2();
 
         self.state_positions.clear();
         for (id, n) in js_layout.nodes.iter() {
@@ -745,19 +1705,18 @@ impl OxidateApp {
                 TransitionType::Forward
             };
 
-            let transition_index = e
-                .name
-                .as_deref()
-                .and_then(|name| name.strip_prefix("tr_"))
-                .and_then(|rest| rest.split('_').next())
-                .and_then(|n| n.parse::<usize>().ok());
+            let transition_index = label_node_transition.get(&e.v).or_else(|| label_node_transition.get(&e.w)).copied();
+            let transition = transition_index.and_then(|i| fsm.transitions.get(i));
 
             layout_edges.push(LayoutedEdge {
                 v: e.v.clone(),
                 w: e.w.clone(),
-                transition_index,
+                transition: transition_index.map(|i| transition_ids[i]),
                 points: e.points.iter().map(|p| egui::pos2(p.x - center.x, p.y - center.y)).collect(),
                 transition_type,
+                is_else: transition.and_then(|t| t.guard.as_ref()).is_some_and(|g| g.is_else()),
+                is_local: transition.is_some_and(|t| t.is_local()),
+                is_dashed: transition.is_some_and(|t| t.is_dashed()),
             });
         }
 
@@ -767,158 +1726,153 @@ impl OxidateApp {
                 layout_labels.push(LayoutedLabel {
                     pos: egui::pos2(n.x - center.x, n.y - center.y),
                     text: text.clone(),
+                    transition: label_node_transition.get(label_node_id).map(|&i| transition_ids[i]),
                 });
             }
         }
 
-        self.layout = Some(LayoutedDiagram { edges: layout_edges, labels: layout_labels });
+        let mut bounds = bounds.translate(-center);
+        for note in &fsm.notes {
+            let state = fsm.states.iter().find(|s| s.name == note.target_state);
+            if let (Some(state), Some(&pos)) = (state, self.state_positions.get(&note.target_state)) {
+                let font_size = self.layout_config.font_size;
+                bounds = bounds.union(note_rect(ctx, calculate_state_rect(state, pos, 1.0, font_size, &self.layout_config.state_box), note, 1.0, font_size));
+            }
+        }
+
+        let mut layout = LayoutedDiagram {
+            edges: layout_edges,
+            labels: layout_labels,
+            bounds,
+            fsm_name: fsm.name.clone(),
+            states: fsm.states.clone(),
+        };
+        // Dagre keeps labels off nodes only; move them off unrelated edges too
+        let node_rects: Vec<egui::Rect> = js_layout
+            .nodes
+            .iter()
+            .filter(|(id, _)| !id.starts_with(diagram::LABEL_NODE_PREFIX))
+            .map(|(_, n)| egui::Rect::from_center_size(egui::pos2(n.x, n.y) - center, egui::vec2(n.width, n.height)))
+            .collect();
+        avoid_edge_segments(&mut layout, &self.render_cache.measure(ctx), self.layout_config.edge_label_font_size, &node_rects);
+        self.layout = Some(layout);
+        if let Some(layout) = self.layout.clone() {
+            let state_positions = self.state_positions.clone();
+            self.layout_cache.insert(cache_key, CachedLayout { layout, state_positions });
+        }
         Ok(())
     }
 
-    fn calculate_state_positions(&mut self) {
-        self.state_positions.clear();
-        
-        if let Some(fsm) = self.fsms.get(self.selected_fsm) {
-            let num_states = fsm.states.len();
-            if num_states == 0 {
-                return;
-            }
+    /// A transition's label as laid out: wrapped, and cut to `max_label_width` so one
+    /// long guard cannot stretch the whole layout (hovering the label shows the full text)
+    fn transition_label_text(&self, ctx: &egui::Context, transition: &fsm::Transition) -> String {
+        let font_size = self.layout_config.edge_label_font_size;
+        fit_label_width(
+            &format_label_text(&transition.diagram_label(), LABEL_WRAP_CHARS),
+            self.layout_config.max_label_width,
+            |line| self.render_cache.measure(ctx).text_size(line, font_size).x,
+        )
+    }
 
-            // Calculate state sizes first for proper spacing
-            let state_sizes: Vec<(String, egui::Vec2)> = fsm.states.iter()
-                .map(|s| (s.name.clone(), estimate_state_size(s)))
-                .collect();
-            
-            // Find max dimensions
-            let max_width = state_sizes.iter().map(|(_, sz)| sz.x).fold(0.0f32, |a, b| a.max(b));
-            let max_height = state_sizes.iter().map(|(_, sz)| sz.y).fold(0.0f32, |a, b| a.max(b));
-            
-            // Use much larger spacing to avoid collisions - significantly increased
-            let base_spacing_x = max_width + 280.0;  // Horizontal spacing
-            let base_spacing_y = max_height + 220.0; // Vertical spacing
-            
-            // Try to arrange in a grid that accommodates the FSM structure
-            // Analyze transitions to find levels
-            let levels = calculate_state_levels(fsm);
-            
-            if levels.is_empty() {
-                // Fallback: simple circle layout with large radius
-                let radius = (num_states as f32 * 50.0).max(200.0);
-                let center = egui::Pos2::new(0.0, 0.0);
-                
-                for (i, state) in fsm.states.iter().enumerate() {
-                    let angle = (i as f32 / num_states as f32) * 2.0 * std::f32::consts::PI - std::f32::consts::FRAC_PI_2;
-                    let x = center.x + radius * angle.cos();
-                    let y = center.y + radius * angle.sin();
-                    self.state_positions.insert(state.name.clone(), egui::Pos2::new(x, y));
-                }
-            } else {
-                // Use hierarchical layout based on levels
-                let mut level_counts: std::collections::HashMap<i32, i32> = std::collections::HashMap::new();
-                
-                for (_, level) in &levels {
-                    *level_counts.entry(*level).or_insert(0) += 1;
-                }
-                
-                let max_level = levels.values().max().copied().unwrap_or(0);
-                let mut level_current: std::collections::HashMap<i32, i32> = std::collections::HashMap::new();
-                
-                for (state_name, level) in &levels {
-                    let count_in_level = level_counts.get(level).copied().unwrap_or(1);
-                    let idx_in_level = *level_current.entry(*level).or_insert(0);
-                    *level_current.get_mut(level).unwrap() += 1;
-                    
-                    // Center the states in each level
-                    let level_width = (count_in_level - 1) as f32 * base_spacing_x;
-                    let start_x = -level_width / 2.0;
-                    
-                    let x = start_x + idx_in_level as f32 * base_spacing_x;
-                    let y = *level as f32 * base_spacing_y;
-                    
-                    self.state_positions.insert(state_name.clone(), egui::Pos2::new(x, y));
-                }
-                
-                // Apply force-directed adjustment to reduce overlaps
-                self.apply_force_layout(&levels, base_spacing_x * 0.8, base_spacing_y * 0.6);
-            }
+    /// Lay out `fsm` without Dagre (straight edges), so a failing layout engine still
+    /// leaves a usable diagram; not cached, so the next layout tries Dagre again
+    fn apply_fallback_layout(&mut self, ctx: &egui::Context, fsm: &FsmDefinition) {
+        let label = |transition: &fsm::Transition| self.transition_label_text(ctx, transition);
+        let (mut layout, positions) = fallback_layout(fsm, self.layout_config.font_size, &self.layout_config.state_box, &label);
+        let node_rects: Vec<egui::Rect> = fsm
+            .states
+            .iter()
+            .filter_map(|s| positions.get(&s.name).map(|&pos| calculate_state_rect(s, pos, 1.0, self.layout_config.font_size, &self.layout_config.state_box)))
+            .collect();
+        avoid_edge_segments(&mut layout, &self.render_cache.measure(ctx), self.layout_config.edge_label_font_size, &node_rects);
+        self.layout = Some(layout);
+        self.state_positions = positions;
+    }
+
+    /// Lay out the FSM of the split view's right viewport when it is out of date:
+    /// through the layout cache like the main canvas, whose layout is left alone
+    /// (or shared, when both show the same FSM)
+    fn layout_split_pane(&mut self, ctx: &egui::Context) {
+        let Some(fsm) = self.pane_fsm(Pane::Right) else {
+            return;
+        };
+        let fingerprint = diagram::layout_fingerprint(fsm, &self.layout_config.state_box);
+        if !self.split.needs_layout(fsm, fingerprint) {
+            return;
+        }
+        if self.layout_fingerprint == Some(fingerprint) && self.layout.as_ref().is_some_and(|l| l.fsm_name == fsm.name) {
+            self.split.set_layout(self.layout.clone(), self.state_positions.clone(), fingerprint);
+            return;
         }
+        let fsm = fsm.clone();
+        let saved = (self.layout.take(), std::mem::take(&mut self.state_positions));
+        if let Err(e) = self.compute_layout_with_dagre(ctx, &fsm) {
+            self.push_warning(Category::Layout, format!("{e}\n\nShowing a simple built-in layout instead."));
+            self.apply_fallback_layout(ctx, &fsm);
+        }
+        let computed = (self.layout.take(), std::mem::take(&mut self.state_positions));
+        (self.layout, self.state_positions) = saved;
+        self.split.set_layout(computed.0, computed.1, fingerprint);
     }
-    
-    /// Apply force-directed layout adjustment
-    fn apply_force_layout(&mut self, levels: &std::collections::HashMap<String, i32>, min_x: f32, min_y: f32) {
-        let iterations = 100;
-        let repulsion = 15000.0;
-        let attraction = 0.01;
-        
-        for _ in 0..iterations {
-            let positions_copy: Vec<(String, egui::Pos2)> = self.state_positions.iter()
-                .map(|(k, v)| (k.clone(), *v))
-                .collect();
-            
-            for (name, pos) in positions_copy.iter() {
-                let mut force = egui::Vec2::ZERO;
-                let my_level = levels.get(name).copied().unwrap_or(0);
-                
-                // Repulsion from other nodes
-                for (other_name, other_pos) in positions_copy.iter() {
-                    if name == other_name {
-                        continue;
-                    }
-                    
-                    let diff = *pos - *other_pos;
-                    let dist = diff.length().max(50.0);
-                    
-                    // Stronger repulsion for same level
-                    let other_level = levels.get(other_name).copied().unwrap_or(0);
-                    let level_factor = if my_level == other_level { 2.0 } else { 1.0 };
-                    
-                    force += diff.normalized() * (repulsion * level_factor / (dist * dist));
-                }
-                
-                // Attraction to center of level (horizontal only)
-                force.x += -pos.x * attraction;
-                
-                // Apply force with damping
-                let new_pos = *pos + force * 0.5;
-                
-                // Enforce minimum distances
-                let mut final_pos = new_pos;
-                for (other_name, other_pos) in positions_copy.iter() {
-                    if name == other_name {
-                        continue;
-                    }
-                    
-                    let diff = final_pos - *other_pos;
-                    let dx = diff.x.abs();
-                    let dy = diff.y.abs();
-                    
-                    let other_level = levels.get(other_name).copied().unwrap_or(0);
-                    
-                    // Enforce minimum distances
-                    if my_level == other_level && dx < min_x {
-                        let push = (min_x - dx) / 2.0 + 10.0;
-                        if diff.x >= 0.0 {
-                            final_pos.x += push;
-                        } else {
-                            final_pos.x -= push;
-                        }
-                    }
-                    
-                    if my_level != other_level && dy < min_y {
-                        let push = (min_y - dy) / 2.0 + 10.0;
-                        if diff.y >= 0.0 {
-                            final_pos.y += push;
-                        } else {
-                            final_pos.y -= push;
-                        }
-                    }
+
+    /// Quality of the current layout, with label boxes measured like the canvas draws them
+    fn measure_layout_quality(&self, ctx: &egui::Context) -> Option<LayoutQuality> {
+        let config = &self.layout_config;
+        let layout = self.layout.as_ref()?;
+        let measure = self.render_cache.measure(ctx);
+        Some(layout_quality(layout, &self.state_positions, config.font_size, config.edge_label_font_size, &config.state_box, &measure))
+    }
+
+    /// Lay out the selected FSM with every spacing combination of a small grid (and
+    /// the current one) and keep the best scoring; the layouts land in the cache, so
+    /// switching to the winner is instant
+    fn optimize_layout(&mut self, ctx: &egui::Context) {
+        const NODESEP: [f32; 3] = [30.0, 60.0, 100.0];
+        const RANKSEP: [f32; 3] = [50.0, 90.0, 140.0];
+        const EDGESEP: [f32; 2] = [10.0, 25.0];
+
+        let Some(fsm) = self.fsms.get(self.selected_fsm).cloned() else {
+            return;
+        };
+        // Trying layouts replaces the current one; the winner is animated to from it
+        let previous_layout = self.layout.clone();
+        let previous_positions = self.state_positions.clone();
+        let current = (self.layout_config.nodesep, self.layout_config.ranksep, self.layout_config.edgesep);
+
+        let mut candidates = vec![current];
+        for nodesep in NODESEP {
+            for ranksep in RANKSEP {
+                for edgesep in EDGESEP {
+                    candidates.push((nodesep, ranksep, edgesep));
                 }
-                
-                self.state_positions.insert(name.clone(), final_pos);
             }
         }
+        let mut best: Option<((f32, f32, f32), f32)> = None;
+        for (nodesep, ranksep, edgesep) in candidates {
+            self.layout_config.nodesep = nodesep;
+            self.layout_config.ranksep = ranksep;
+            self.layout_config.edgesep = edgesep;
+            if self.compute_layout_with_dagre(ctx, &fsm).is_err() {
+                continue;
+            }
+            let Some(score) = self.measure_layout_quality(ctx).map(|q| q.score()) else {
+                continue;
+            };
+            // Ties keep the earlier candidate, so the current spacing wins unless beaten
+            if best.map_or(true, |(_, best_score)| score < best_score) {
+                best = Some(((nodesep, ranksep, edgesep), score));
+            }
+        }
+
+        let (nodesep, ranksep, edgesep) = best.map_or(current, |(spacing, _)| spacing);
+        self.layout_config.nodesep = nodesep;
+        self.layout_config.ranksep = ranksep;
+        self.layout_config.edgesep = edgesep;
+        self.layout = previous_layout;
+        self.state_positions = previous_positions;
+        self.mark_layout_dirty();
     }
-    
+
     /// Create new FSMs with the given names (comma or space separated)
     fn create_new_fsms(&mut self, names_input: &str) {
         // Parse names - split by comma, semicolon, or newline
@@ -935,25 +1889,16 @@ impl OxidateApp {
         let mut all_fsms = String::new();
         
         for (idx, name) in names.iter().enumerate() {
-            // Clean name - remove spaces, keep only valid identifier chars
-            let clean_name: String = name
+            // PascalCase, keeping only valid identifier chars
+            let pascal_name: String = to_pascal_case(name)
                 .chars()
                 .filter(|c| c.is_alphanumeric() || *c == '_')
                 .collect();
             
-            if clean_name.is_empty() {
+            if pascal_name.is_empty() {
                 continue;
             }
             
-            // Make first char uppercase (PascalCase)
-            let pascal_name = {
-                let mut chars = clean_name.chars();
-                match chars.next() {
-                    None => continue,
-                    Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
-                }
-            };
-            
             if idx > 0 {
                 all_fsms.push_str("\n\n");
             }
@@ -985,30 +1930,21 @@ fsm {name} {{
         
         if !all_fsms.is_empty() {
             all_fsms.insert_str(0, "// State Machines - Created with Oxidate FSM Visualizer\n\n");
-            self.source_code = all_fsms;
-            self.parse_source();
+            self.load_source(all_fsms, None);
         }
     }
     
     /// Add a new FSM to existing code
     fn add_new_fsm(&mut self, name: &str) {
-        let clean_name: String = name
+        let pascal_name: String = to_pascal_case(name)
             .chars()
             .filter(|c| c.is_alphanumeric() || *c == '_')
             .collect();
         
-        if clean_name.is_empty() {
+        if pascal_name.is_empty() {
             return;
         }
         
-        let pascal_name = {
-            let mut chars = clean_name.chars();
-            match chars.next() {
-                None => return,
-                Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
-            }
-        };
-        
         let new_fsm = format!(r#"
 
 // {name} State Machine
@@ -1029,282 +1965,2148 @@ fsm {name} {{
     Active -> Idle : stop
 }}"#, name = pascal_name);
         
-        self.source_code.push_str(&new_fsm);
+        self.append_source(&new_fsm);
+    }
+
+    /// Append DSL text to the editor content (in a project, to the member shown in the editor)
+    fn append_source(&mut self, text: &str) {
+        match self.workspace {
+            // Goes into the member shown in the editor
+            Some(ref mut workspace) => workspace.selected_member_mut().source.push_str(text),
+            None => self.source_code.push_str(text),
+        }
         self.parse_source();
     }
+
+    /// Template gallery: a card per template with its thumbnail, and Insert (append
+    /// to the editor content) or Replace (the editor content) buttons
+    fn show_template_gallery_window(&mut self, ctx: &egui::Context) {
+        if self.template_previews.is_empty() {
+            self.template_previews = TEMPLATES
+                .iter()
+                .map(|template| {
+                    // Every template parses (templates::tests)
+                    let fsm = &parse_fsm(template.source).expect("template parses")[0];
+                    (fsm.name.clone(), egui::load::Bytes::from(thumbnail_svg(fsm).into_bytes()))
+                })
+                .collect();
+        }
+
+        let mut open = self.show_template_gallery;
+        let mut chosen: Option<(&'static Template, bool)> = None;
+        egui::Window::new("📚 Templates").open(&mut open).default_size([640.0, 520.0]).show(ctx, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                ui.selectable_value(&mut self.template_category, None, "All");
+                for category in templates::categories() {
+                    ui.selectable_value(&mut self.template_category, Some(category), category);
+                }
+            });
+            ui.separator();
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("template_gallery").num_columns(3).spacing([12.0, 12.0]).show(ui, |ui| {
+                    let shown = TEMPLATES
+                        .iter()
+                        .zip(&self.template_previews)
+                        .filter(|(t, _)| self.template_category.map_or(true, |c| c == t.category));
+                    for (i, (template, (fsm_name, thumbnail))) in shown.enumerate() {
+                        ui.group(|ui| {
+                            ui.set_width(180.0);
+                            ui.vertical(|ui| {
+                                let uri = format!("bytes://template/{}.svg", fsm_name);
+                                ui.add(egui::Image::from_bytes(uri, thumbnail.clone()).fit_to_exact_size(egui::vec2(180.0, 120.0)));
+                                ui.strong(template.name);
+                                ui.small(template.category);
+                                ui.add(egui::Label::new(egui::RichText::new(template.description).small()).wrap());
+                                ui.horizontal(|ui| {
+                                    // Two FSMs with one name cannot coexist in a file
+                                    let taken = self.fsms.iter().any(|f| &f.name == fsm_name);
+                                    let insert = ui
+                                        .add_enabled(!taken, egui::Button::new("➕ Insert"))
+                                        .on_hover_text("Append to the current file")
+                                        .on_disabled_hover_text(format!("An FSM named {} already exists", fsm_name));
+                                    if insert.clicked() {
+                                        chosen = Some((template, false));
+                                    }
+                                    if ui.button("📄 Replace").on_hover_text("Replace the current file").clicked() {
+                                        chosen = Some((template, true));
+                                    }
+                                });
+                            });
+                        });
+                        if i % 3 == 2 {
+                            ui.end_row();
+                        }
+                    }
+                });
+            });
+        });
+
+        self.show_template_gallery = open;
+        match chosen {
+            Some((template, true)) => {
+                self.request_file_action(ctx, FileAction::Load(template.source));
+                self.show_template_gallery = false;
+            }
+            Some((template, false)) => {
+                self.append_source(&format!("\n\n{}", template.source.trim_end()));
+                self.show_template_gallery = false;
+            }
+            None => {}
+        }
+    }
     
     /// Export all FSMs to a folder with autogen files
-    fn export_all_fsms_to_folder(&self, folder: &std::path::Path) {
-        use std::io::Write;
-        
-        // Create autogen subfolder
-        let autogen_folder = folder.join("autogen");
-        let _ = std::fs::create_dir_all(&autogen_folder);
-        
-        // Generate mod.rs for autogen
-        let mut mod_content = String::from("//! Auto-generated FSM code\n//! DO NOT EDIT - Generated by Oxidate\n\n");
-        
-        for fsm in &self.fsms {
-            let snake_name = to_snake_case(&fsm.name);
-            
-            // Generate code for each target
-            let code = generate_rust_code_with_target(fsm, self.codegen_target);
-            
-            // Write the FSM file
-            let filename = format!("{}.rs", snake_name);
-            let filepath = autogen_folder.join(&filename);
-            
-            if let Ok(mut file) = std::fs::File::create(&filepath) {
-                let header = format!(
-                    "//! Auto-generated code for {} FSM\n//! DO NOT EDIT - Generated by Oxidate\n//! Target: {:?}\n\n",
-                    fsm.name, self.codegen_target
-                );
-                let _ = file.write_all(header.as_bytes());
-                let _ = file.write_all(code.as_bytes());
+    fn export_all_fsms_to_folder(&mut self, folder: &std::path::Path) {
+        match write_autogen_folder(folder, &self.fsms, self.codegen_target, &self.codegen_options) {
+            Ok(summary) => self.export_summary = Some(summary),
+            Err(AutogenError::SharedEvents(errors)) => {
+                for error in errors {
+                    self.push_error(Category::Export, format!("Shared events: {error}"));
+                }
             }
-            
-            // Add to mod.rs
-            mod_content.push_str(&format!("pub mod {};\n", snake_name));
+            Err(e) => self.push_error(Category::Export, format!("Could not export to '{}': {}", folder.display(), e)),
         }
-        
-        // Also export pub use statements
-        mod_content.push_str("\n// Re-exports\n");
-        for fsm in &self.fsms {
-            let snake_name = to_snake_case(&fsm.name);
-            mod_content.push_str(&format!("pub use {}::*;\n", snake_name));
+    }
+
+    /// What the last export wrote and skipped, and its stale files with a button to delete them
+    fn show_export_summary_window(&mut self, ctx: &egui::Context) {
+        let Some(mut summary) = self.export_summary.take() else {
+            return;
+        };
+        let mut open = true;
+        let (mut close, mut remove) = (false, false);
+        egui::Window::new("📁 Export")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("{}: {}", summary.folder.display(), summary.describe()));
+                for (names, icon, hover) in [
+                    (&summary.written, "✏", "Written"),
+                    (&summary.unchanged, "✔", "Unchanged, not rewritten"),
+                    (&summary.removed, "🗑", "Removed"),
+                ] {
+                    for name in names {
+                        ui.monospace(format!("{icon} {name}")).on_hover_text(hover);
+                    }
+                }
+                if !summary.stale.is_empty() {
+                    ui.separator();
+                    ui.label("Generated files of FSMs that are no longer exported:");
+                    for name in &summary.stale {
+                        ui.colored_label(egui::Color32::YELLOW, format!("⚠ {name}"));
+                    }
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if !summary.stale.is_empty() {
+                        remove = ui.button(format!("🗑 Delete {} stale file(s)", summary.stale.len())).clicked();
+                    }
+                    close = ui.button("Close").clicked();
+                });
+            });
+
+        if remove {
+            if let Err(e) = remove_stale_files(&mut summary) {
+                self.push_error(Category::Export, format!("Could not remove stale files: {e}"));
+            }
         }
-        
-        // Write mod.rs
-        let mod_path = autogen_folder.join("mod.rs");
-        let _ = std::fs::write(&mod_path, mod_content);
-        
-        // Write a README
-        let readme = format!(
-            "# Auto-generated FSM Code\n\n\
-            Generated by Oxidate FSM Visualizer\n\n\
-            ## Files\n\n\
-            - `mod.rs` - Module declarations\n\
-            {}\n\n\
-            ## Usage\n\n\
-            Add to your `lib.rs` or `main.rs`:\n\n\
-            ```rust\n\
-            mod autogen;\n\
-            use autogen::*;\n\
-            ```\n\n\
-            ## Target: {:?}\n",
-            self.fsms.iter()
-                .map(|f| format!("- `{}.rs` - {} state machine", to_snake_case(&f.name), f.name))
-                .collect::<Vec<_>>()
-                .join("\n"),
-            self.codegen_target
-        );
-        let readme_path = autogen_folder.join("README.md");
-        let _ = std::fs::write(&readme_path, readme);
-    }
-
-    fn sim_reset_to_initial(&mut self, fsm: &FsmDefinition) {
-        self.sim.queued_events.clear();
-        self.sim.auto_accum_s = 0.0;
-        self.sim.last_fired = None;
-        self.sim.last_frame = None;
-
-        if let Some(initial) = &fsm.initial_state {
-            self.sim.current_state = Some(initial.clone());
-            self.sim.log.push(format!("reset → {initial}"));
-        } else if let Some(first) = fsm.states.first() {
-            self.sim.current_state = Some(first.name.clone());
-            self.sim.log.push(format!("reset → {} (fallback)", first.name));
-        } else {
-            self.sim.current_state = None;
-            self.sim.log.push("reset → <no states>".to_string());
+        if open && !close {
+            self.export_summary = Some(summary);
+        }
+    }
+
+    /// Bottom panel listing the console's entries, newest first, each with a copy button
+    fn show_error_console(&mut self, ctx: &egui::Context) {
+        if !self.console.open {
+            return;
+        }
+        self.console.unseen = 0;
+        let (mut close, mut clear) = (false, false);
+        egui::TopBottomPanel::bottom("error_console").resizable(true).default_height(160.0).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.strong("Console");
+                ui.weak(format!("{} entries, times in UTC", self.console.entries.len()));
+                if ui.add_enabled(!self.console.entries.is_empty(), egui::Button::new("📋 Copy All")).clicked() {
+                    ui.output_mut(|o| o.copied_text = self.console.to_text());
+                }
+                clear = ui.add_enabled(!self.console.entries.is_empty(), egui::Button::new("🗑 Clear")).clicked();
+                close = ui.button("✖").on_hover_text("Close the console").clicked();
+            });
+            ui.separator();
+            egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+                if self.console.entries.is_empty() {
+                    ui.weak("No errors or warnings");
+                }
+                for entry in self.console.entries.iter().rev() {
+                    ui.horizontal(|ui| {
+                        if ui.small_button("📋").on_hover_text("Copy").clicked() {
+                            ui.output_mut(|o| o.copied_text = entry.to_text());
+                        }
+                        ui.monospace(console::clock_time(entry.time));
+                        let (icon, color) = match entry.severity {
+                            Severity::Error => ("❌", egui::Color32::RED),
+                            Severity::Warning => ("⚠", egui::Color32::from_rgb(230, 190, 100)),
+                        };
+                        ui.colored_label(color, format!("{icon} {}", entry.category.label()));
+                        if entry.count > 1 {
+                            ui.weak(format!("×{}", entry.count));
+                        }
+                        ui.add(egui::Label::new(&entry.message).selectable(true).wrap());
+                    });
+                }
+            });
+        });
+        if clear {
+            self.console.clear();
+        }
+        if close {
+            self.console.open = false;
+        }
+    }
+
+    /// Whether a state box of the selected FSM is under `pointer` on the canvas at `canvas`
+    /// The state drawn under `pointer` (the topmost one if states overlap)
+    fn state_under_pointer(&self, canvas: egui::Rect, pointer: egui::Pos2) -> Option<&fsm::State> {
+        let fsm = self.fsms.get(self.selected_fsm)?;
+        fsm.states.iter().rev().find(|state| self.state_screen_rect(canvas, state).is_some_and(|r| r.contains(pointer)))
+    }
+
+    /// Where `state` of the selected FSM is drawn on the canvas at `canvas`
+    fn state_screen_rect(&self, canvas: egui::Rect, state: &fsm::State) -> Option<egui::Rect> {
+        let viewport = self.view.viewport(canvas);
+        let pos = *self.state_positions.get(&state.name)?;
+        let config = &self.layout_config;
+        Some(self.render_cache.state_rect(state, viewport.to_screen(pos), self.view.zoom, config.font_size, &config.state_box))
+    }
+
+    /// Edit mode: the state a transition can be drawn from or to at `pointer` (its
+    /// border counts from a few pixels outside the box), or `[*]` for the start node
+    fn edge_end_under_pointer(&self, canvas: egui::Rect, pointer: egui::Pos2) -> Option<String> {
+        let fsm = self.fsms.get(self.selected_fsm)?;
+        let viewport = self.view.viewport(canvas);
+        let on_start = self
+            .state_positions
+            .get("[*]")
+            .is_some_and(|&pos| viewport.to_screen(pos).distance(pointer) <= INITIAL_MARKER_RADIUS * self.view.zoom + EDGE_DRAG_MARGIN);
+        if on_start {
+            return Some("[*]".to_string());
         }
+        fsm.states
+            .iter()
+            .rev()
+            .find(|state| self.state_screen_rect(canvas, state).is_some_and(|r| r.expand(EDGE_DRAG_MARGIN).contains(pointer)))
+            .map(|state| state.name.clone())
     }
 
-    fn sim_post_event(&mut self, event_name: impl Into<String>) {
-        let name = event_name.into();
-        if name.trim().is_empty() {
+    /// Edit mode: a dashed line from the border of the state being dragged from to
+    /// `pointer`, and an outline around the state it would connect to
+    fn paint_edge_drag(&self, painter: &egui::Painter, canvas: egui::Rect, from: &str, pointer: egui::Pos2) {
+        let Some(fsm) = self.fsms.get(self.selected_fsm) else {
+            return;
+        };
+        let viewport = self.view.viewport(canvas);
+        let source = match fsm.states.iter().find(|s| s.name == from) {
+            Some(state) => self.state_screen_rect(canvas, state),
+            None => self.state_positions.get(from).map(|&pos| egui::Rect::from_center_size(viewport.to_screen(pos), egui::Vec2::ZERO)),
+        };
+        let Some(source) = source else {
             return;
+        };
+        let stroke = egui::Stroke::new(2.0, self.theme.accent);
+
+        // Leave the source box where the line to the pointer crosses its border
+        let direction = pointer - source.center();
+        if !source.contains(pointer) && direction != egui::Vec2::ZERO {
+            let half = source.size() / 2.0;
+            let t = (half.x / direction.x.abs()).min(half.y / direction.y.abs()).min(1.0);
+            let start = source.center() + direction * t;
+            painter.extend(egui::Shape::dashed_line(&[start, pointer], stroke, 6.0, 4.0));
+            let back = -direction.normalized() * 10.0;
+            let side = egui::vec2(-back.y, back.x) * 0.5;
+            painter.add(egui::Shape::convex_polygon(vec![pointer, pointer + back + side, pointer + back - side], self.theme.accent, egui::Stroke::NONE));
+        }
+
+        match self.edge_end_under_pointer(canvas, pointer).filter(|end| end != "[*]") {
+            Some(target) => {
+                if let Some(rect) = fsm.states.iter().find(|s| s.name == target).and_then(|s| self.state_screen_rect(canvas, s)) {
+                    painter.rect_stroke(rect.expand(3.0), 6.0, stroke);
+                }
+            }
+            // Dropping here adds a state
+            None => {
+                let hint = egui::Rect::from_center_size(pointer + egui::vec2(0.0, 18.0), egui::vec2(36.0, 20.0));
+                painter.rect_stroke(hint, 4.0, egui::Stroke::new(1.0, self.theme.accent));
+                painter.text(hint.center(), egui::Align2::CENTER_CENTER, "+", egui::FontId::proportional(14.0), self.theme.accent);
+            }
         }
-        self.sim.queued_events.push_back(name);
     }
 
-    fn sim_step(&mut self, fsm: &FsmDefinition) {
-        if self.sim.current_state.is_none() {
-            self.sim_reset_to_initial(fsm);
+    /// Edit mode: a drag from `from` ended on `to` (`None`: empty canvas). The start
+    /// node dropped on a state makes it the initial state; anything else opens the
+    /// new transition dialog.
+    fn finish_edge_drag(&mut self, from: String, to: Option<String>) {
+        match to {
+            // Transitions into `[*]` are not drawn from the canvas
+            Some(to) if to == "[*]" => {}
+            Some(to) if from == "[*]" => {
+                let fsm_index = self.selected_fsm;
+                let result = self.edit_fsm_source(
+                    fsm_index,
+                    |source, fsm| set_initial_state(source, fsm, &to).ok_or_else(|| format!("No line to add `[*] --> {to}` on in {}", fsm.name)),
+                    |_, _| Ok(()),
+                );
+                if let Err(error) = result {
+                    self.push_error(Category::Edit, error);
+                }
+            }
+            to => {
+                self.new_transition = Some(NewTransitionDialog {
+                    fsm_index: self.selected_fsm,
+                    from,
+                    to,
+                    pick_target: false,
+                    new_state: String::new(),
+                    edit: LabelEdit::default(),
+                    error: None,
+                });
+            }
         }
-        let Some(event) = self.sim.queued_events.pop_front() else {
+    }
+
+    /// Transition whose label or route is under `pointer`
+    fn transition_under_pointer(&self, ctx: &egui::Context, canvas: egui::Rect, pointer: egui::Pos2) -> Option<usize> {
+        let layout = self.layout.as_ref()?;
+        let viewport = self.view.viewport(canvas);
+        self.label_under_pointer(ctx, canvas, pointer).or_else(|| {
+            layout
+                .edges
+                .iter()
+                .filter_map(|edge| {
+                    let distance = distance_to_polyline(edge.points.iter().map(|&p| viewport.to_screen(p)), pointer);
+                    Some((distance, edge.transition?))
+                })
+                .filter(|(distance, _)| *distance <= EDGE_HOVER_DISTANCE)
+                .min_by(|a, b| a.0.total_cmp(&b.0))
+                .and_then(|(_, id)| self.fsms.get(self.selected_fsm)?.transition_index(id))
+        })
+    }
+
+    /// Transition whose label is under `pointer`
+    fn label_under_pointer(&self, ctx: &egui::Context, canvas: egui::Rect, pointer: egui::Pos2) -> Option<usize> {
+        let layout = self.layout.as_ref()?;
+        let viewport = self.view.viewport(canvas);
+        let id = label_at(layout, &self.render_cache.measure(ctx), self.layout_config.edge_label_font_size, viewport, pointer)?;
+        self.fsms.get(self.selected_fsm)?.transition_index(id)
+    }
+
+    fn open_label_edit(&mut self, transition_index: usize) {
+        let Some(fsm) = self.fsms.get(self.selected_fsm) else {
             return;
         };
-        let Some(current) = self.sim.current_state.clone() else {
+        let Some(transition) = fsm.transitions.get(transition_index) else {
             return;
         };
+        let old_event = transition.event.as_ref().map(|e| e.name.clone()).unwrap_or_default();
+        let reacts = |t: &&fsm::Transition| t.event.as_ref().is_some_and(|e| e.name == old_event);
+        let internal = fsm.states.iter().flat_map(|s| &s.internal_transitions);
+        let event_uses = fsm.transitions.iter().chain(internal).filter(reacts).count()
+            + fsm.timers.iter().filter(|t| t.event.name == old_event).count();
+        self.label_edit = Some(LabelEditDialog {
+            fsm_index: self.selected_fsm,
+            transition_index,
+            route: format!("{} --> {}", transition.source, transition.target),
+            edit: LabelEdit::from_transition(transition),
+            old_event,
+            event_uses,
+            rename_everywhere: false,
+            error: None,
+        });
+    }
 
-        // Try external transitions first (from the FSM transition list).
-        if let Some((t_idx, t)) = fsm
-            .transitions
-            .iter()
-            .enumerate()
-            .find(|(_, t)| t.source == current && t.event.as_ref().is_some_and(|e| e.name == event))
-        {
-            let label = t.label();
-            self.sim.log.push(format!("{current} --{event}--> {}", t.target));
-            let started_at = Instant::now();
-            self.sim.last_fired = Some(SimFired {
-                transition_index: Some(t_idx),
-                from: current.clone(),
-                to: t.target.clone(),
-                label,
-                started_at,
-                duration_s: (0.7 / self.sim.speed.max(0.05)).clamp(0.15, 3.0),
+    fn show_label_edit_window(&mut self, ctx: &egui::Context) {
+        let Some(mut dialog) = self.label_edit.take() else {
+            return;
+        };
+        let mut open = true;
+        let (mut apply, mut cancel) = (false, false);
+        egui::Window::new("✏ Edit Transition")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.monospace(&dialog.route);
+                ui.add_space(5.0);
+                egui::Grid::new("label_edit_grid").num_columns(2).show(ui, |ui| {
+                    ui.label("Event:");
+                    ui.text_edit_singleline(&mut dialog.edit.event);
+                    ui.end_row();
+                    ui.label("Guard:");
+                    ui.text_edit_singleline(&mut dialog.edit.guard);
+                    ui.end_row();
+                    ui.label("Actions:");
+                    ui.add(egui::TextEdit::singleline(&mut dialog.edit.actions).hint_text("open_door; beep(2)"));
+                    ui.end_row();
+                });
+
+                let new_event = dialog.edit.event.trim();
+                if dialog.event_uses > 1 && !new_event.is_empty() && new_event != dialog.old_event {
+                    let text = format!("Rename `{}` everywhere ({} uses)", dialog.old_event, dialog.event_uses);
+                    ui.checkbox(&mut dialog.rename_everywhere, text);
+                }
+                ui.small(format!("{} : {}", dialog.route, dialog.edit.label()));
+                if let Some(ref error) = dialog.error {
+                    ui.colored_label(egui::Color32::RED, format!("❌ {}", error));
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    apply = ui.button("✓ Apply").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
             });
-            self.sim.current_state = Some(t.target.clone());
+
+        if apply {
+            match self.apply_label_edit(&dialog) {
+                Ok(()) => return,
+                Err(error) => dialog.error = Some(error),
+            }
+        }
+        if open && !cancel {
+            self.label_edit = Some(dialog);
+        }
+    }
+
+    /// Rewrite the edited transition's DSL line and re-parse. Nothing changes if the
+    /// line cannot be found or the edited source does not parse.
+    fn apply_label_edit(&mut self, dialog: &LabelEditDialog) -> Result<(), String> {
+        self.edit_fsm_source(
+            dialog.fsm_index,
+            |source, fsm| {
+                let mut edited = rewrite_transition(source, fsm, dialog.transition_index, &dialog.edit)
+                    .ok_or("The transition is no longer in the source")?;
+                let new_event = dialog.edit.event.trim();
+                if dialog.rename_everywhere && !dialog.old_event.is_empty() && !new_event.is_empty() {
+                    edited = rename_event(&edited, fsm, &dialog.old_event, new_event);
+                }
+                Ok(edited)
+            },
+            |_, _| Ok(()),
+        )
+    }
+
+    fn show_new_transition_window(&mut self, ctx: &egui::Context) {
+        let Some(mut dialog) = self.new_transition.take() else {
             return;
+        };
+        let mut open = true;
+        let (mut apply, mut cancel) = (false, ctx.input(|i| i.key_pressed(egui::Key::Escape)));
+        let states: Vec<String> = match dialog.pick_target {
+            true => self.fsms.get(dialog.fsm_index).map(|fsm| fsm.states.iter().map(|s| s.name.clone()).collect()).unwrap_or_default(),
+            false => Vec::new(),
+        };
+        let title = match (dialog.from.as_str(), &dialog.to) {
+            ("[*]", _) => "➕ New Initial State",
+            (_, None) => "➕ New State",
+            (_, Some(_)) => "➕ New Transition",
+        };
+        egui::Window::new(title)
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let target = dialog.to.clone().unwrap_or_else(|| match dialog.new_state.trim() {
+                    "" => "?".to_string(),
+                    name => name.to_string(),
+                });
+                let route = format!("{} --> {}", dialog.from, target);
+                ui.monospace(&route);
+                ui.add_space(5.0);
+                egui::Grid::new("new_transition_grid").num_columns(2).show(ui, |ui| {
+                    if dialog.pick_target {
+                        ui.label("To:");
+                        egui::ComboBox::from_id_salt("new_transition_target")
+                            .selected_text(dialog.to.as_deref().unwrap_or("New state"))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut dialog.to, None, "New state");
+                                for state in &states {
+                                    ui.selectable_value(&mut dialog.to, Some(state.clone()), state);
+                                }
+                            });
+                        ui.end_row();
+                    }
+                    if dialog.to.is_none() {
+                        ui.label("New state:");
+                        ui.text_edit_singleline(&mut dialog.new_state);
+                        ui.end_row();
+                    }
+                    if dialog.from != "[*]" {
+                        ui.label("Event:");
+                        ui.text_edit_singleline(&mut dialog.edit.event);
+                        ui.end_row();
+                        ui.label("Guard:");
+                        ui.text_edit_singleline(&mut dialog.edit.guard);
+                        ui.end_row();
+                        ui.label("Actions:");
+                        ui.add(egui::TextEdit::singleline(&mut dialog.edit.actions).hint_text("open_door; beep(2)"));
+                        ui.end_row();
+                    }
+                });
+                if dialog.from != "[*]" && !dialog.edit.label().is_empty() {
+                    ui.small(format!("{} : {}", route, dialog.edit.label()));
+                }
+                if let Some(ref error) = dialog.error {
+                    ui.colored_label(egui::Color32::RED, format!("❌ {}", error));
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    apply = ui.button("✓ Add").clicked();
+                    cancel |= ui.button("Cancel").clicked();
+                });
+            });
+
+        if apply {
+            match self.apply_new_transition(&dialog) {
+                Ok(()) => return,
+                Err(error) => dialog.error = Some(error),
+            }
+        }
+        if open && !cancel {
+            self.new_transition = Some(dialog);
         }
+    }
 
-        // Then internal transitions (stay in state; no edge animation).
-        if let Some(state) = fsm.states.iter().find(|s| s.name == current) {
-            if let Some(internal) = state
-                .internal_transitions
-                .iter()
-                .find(|t| t.event.as_ref().is_some_and(|e| e.name == event))
-            {
-                let label = internal.label();
-                self.sim.log.push(format!("{current} --{event}--> {current} (internal)"));
-                let started_at = Instant::now();
-                self.sim.last_fired = Some(SimFired {
-                    transition_index: None,
-                    from: current.clone(),
-                    to: current.clone(),
-                    label,
-                    started_at,
-                    duration_s: (0.4 / self.sim.speed.max(0.05)).clamp(0.10, 2.0),
+    /// Add the dialog's lines to the FSM's DSL (a `state` declaration for a new target,
+    /// then the transition or `[*] -->` line) and re-parse
+    fn apply_new_transition(&mut self, dialog: &NewTransitionDialog) -> Result<(), String> {
+        let new_state = dialog.new_state.trim();
+        if dialog.to.is_none() {
+            if !is_identifier(new_state) {
+                return Err(format!("'{new_state}' is not a valid state name"));
+            }
+            if self.fsms.get(dialog.fsm_index).is_some_and(|fsm| fsm.states.iter().any(|s| s.name == new_state)) {
+                return Err(format!("State '{new_state}' already exists"));
+            }
+        }
+        self.edit_fsm_source(
+            dialog.fsm_index,
+            |source, fsm| {
+                let no_line = || format!("No line to add to in {}'s block", fsm.name);
+                let (edited, target) = match dialog.to {
+                    Some(ref to) => (source.to_string(), to.as_str()),
+                    None => (add_state(source, fsm, new_state).ok_or_else(no_line)?, new_state),
+                };
+                match dialog.from.as_str() {
+                    "[*]" => set_initial_state(&edited, fsm, target),
+                    from => add_transition(&edited, fsm, from, target, &dialog.edit),
+                }
+                .ok_or_else(no_line)
+            },
+            |_, _| Ok(()),
+        )
+    }
+
+    fn open_rename(&mut self, kind: RenameKind) {
+        let Some(fsm) = self.fsms.get(self.selected_fsm) else {
+            return;
+        };
+        let old_name = kind.names(fsm).into_iter().next().unwrap_or_default();
+        self.rename = Some(RenameDialog {
+            fsm_index: self.selected_fsm,
+            kind,
+            new_name: old_name.clone(),
+            old_name,
+            error: None,
+        });
+    }
+
+    fn show_rename_window(&mut self, ctx: &egui::Context) {
+        let Some(mut dialog) = self.rename.take() else {
+            return;
+        };
+        let Some(names) = self.fsms.get(dialog.fsm_index).map(|fsm| dialog.kind.names(fsm)) else {
+            return;
+        };
+        let mut open = true;
+        let (mut apply, mut cancel) = (false, false);
+        let title = match dialog.kind {
+            RenameKind::State => "✏ Rename State",
+            RenameKind::Event => "✏ Rename Event",
+        };
+        egui::Window::new(title)
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                egui::Grid::new("rename_grid").num_columns(2).show(ui, |ui| {
+                    ui.label("Rename:");
+                    egui::ComboBox::from_id_salt("rename_old_name")
+                        .selected_text(&dialog.old_name)
+                        .show_ui(ui, |ui| {
+                            for name in &names {
+                                if ui.selectable_value(&mut dialog.old_name, name.clone(), name).changed() {
+                                    dialog.new_name = name.clone();
+                                }
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("To:");
+                    ui.text_edit_singleline(&mut dialog.new_name);
+                    ui.end_row();
                 });
-                return;
+                if let Some(ref error) = dialog.error {
+                    ui.colored_label(egui::Color32::RED, format!("❌ {}", error));
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    apply = ui.button("✓ Rename").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+
+        if apply {
+            match self.apply_rename(&dialog) {
+                Ok(()) => return,
+                Err(error) => dialog.error = Some(error),
             }
         }
+        if open && !cancel {
+            self.rename = Some(dialog);
+        }
+    }
 
-        self.sim.log.push(format!("{current}: no transition for event '{event}'"));
+    /// Rename structurally (every reference in the FSM's block) and re-parse; refused if
+    /// the renamed FSM would have a different number of problems
+    fn apply_rename(&mut self, dialog: &RenameDialog) -> Result<(), String> {
+        let new_name = dialog.new_name.trim();
+        self.edit_fsm_source(
+            dialog.fsm_index,
+            |source, fsm| rename(source, fsm, dialog.kind, &dialog.old_name, new_name).map_err(|e| e.to_string()),
+            |before, after| {
+                let issues = |fsm: &FsmDefinition| fsm.validate().err().map_or(0, |e| e.len()) + fsm.warnings().len();
+                if issues(before) == issues(after) {
+                    Ok(())
+                } else {
+                    Err(format!("Renaming would change the problems reported for {}", before.name))
+                }
+            },
+        )
     }
 
-    fn polyline_point_at(points: &[egui::Pos2], t: f32) -> Option<egui::Pos2> {
-        if points.len() < 2 {
-            return None;
+    /// A click at `pointer` selects the state under it, or with `add` (Ctrl) adds or
+    /// removes it; a click on empty canvas clears the selection unless `add`
+    fn click_select(&mut self, canvas: egui::Rect, pointer: egui::Pos2, add: bool) {
+        let Some(fsm) = self.fsms.get(self.selected_fsm) else {
+            return;
+        };
+        let fsm_name = fsm.name.clone();
+        match (self.state_under_pointer(canvas, pointer).map(|s| s.name.clone()), add) {
+            (Some(state), true) => self.selection.toggle(&fsm_name, &state),
+            (Some(state), false) => self.selection.set(&fsm_name, vec![state]),
+            (None, true) => {}
+            (None, false) => self.selection.clear(),
         }
-        let mut lengths: Vec<f32> = Vec::with_capacity(points.len() - 1);
-        let mut total = 0.0f32;
-        for i in 0..points.len() - 1 {
-            let d = points[i].distance(points[i + 1]);
-            lengths.push(d);
-            total += d;
-        }
-        if total <= 0.0001 {
-            return Some(points[0]);
-        }
-        let mut target = (t.clamp(0.0, 1.0)) * total;
-        for i in 0..lengths.len() {
-            let seg = lengths[i];
-            if target <= seg {
-                let a = points[i];
-                let b = points[i + 1];
-                let alpha = if seg <= 0.0001 { 0.0 } else { target / seg };
-                return Some(egui::pos2(
-                    a.x + (b.x - a.x) * alpha,
-                    a.y + (b.y - a.y) * alpha,
-                ));
-            }
-            target -= seg;
-        }
-        Some(*points.last().unwrap())
-    }
-
-    fn sim_route_for_transition(layout: &LayoutedDiagram, transition_index: usize, from: &str, to: &str) -> Option<Vec<egui::Pos2>> {
-        let tr_node = format!("__tr_{transition_index}");
-        let a = layout
-            .edges
-            .iter()
-            .find(|e| e.transition_index == Some(transition_index) && e.v == from && e.w == tr_node);
-        let b = layout
-            .edges
-            .iter()
-            .find(|e| e.transition_index == Some(transition_index) && e.v == tr_node && e.w == to);
+    }
 
-        match (a, b) {
-            (Some(a), Some(b)) => {
-                let mut pts = a.points.clone();
-                if let Some(first_b) = b.points.first().copied() {
-                    if pts.last().copied().is_some_and(|last| last.distance(first_b) < 0.01) {
-                        pts.pop();
-                    }
+    /// Select the states the rubber band `band` touches, added to the selection with `add`
+    fn select_in_rect(&mut self, canvas: egui::Rect, band: egui::Rect, add: bool) {
+        let Some(fsm) = self.fsms.get(self.selected_fsm) else {
+            return;
+        };
+        let mut states = if add { self.selection.of(&fsm.name).to_vec() } else { Vec::new() };
+        for state in &fsm.states {
+            let touched = self.state_screen_rect(canvas, state).is_some_and(|r| r.intersects(band));
+            if touched && !states.contains(&state.name) {
+                states.push(state.name.clone());
+            }
+        }
+        let fsm_name = fsm.name.clone();
+        self.selection.set(&fsm_name, states);
+    }
+
+    /// Delete the selected states and everything referring to them, as one edit
+    fn delete_selected_states(&mut self) {
+        let Some(fsm) = self.fsms.get(self.selected_fsm) else {
+            return;
+        };
+        let states = self.selection.of(&fsm.name).to_vec();
+        if states.is_empty() {
+            return;
+        }
+        let result = self.edit_fsm_source(
+            self.selected_fsm,
+            |source, fsm| delete_states(source, fsm, &states).ok_or_else(|| format!("{}'s block could not be located", fsm.name)),
+            |_, _| Ok(()),
+        );
+        match result {
+            Ok(()) => self.selection.clear(),
+            Err(error) => self.push_error(Category::Edit, error),
+        }
+    }
+
+    /// The canvas context menu for `canvas_target`. Items that edit the DSL are disabled
+    /// for FSMs that cannot be edited here (included from another file).
+    fn canvas_menu(&self, ui: &mut egui::Ui) -> Option<CanvasAction> {
+        let fsm = self.fsms.get(self.selected_fsm)?;
+        let target = self.canvas_target.clone()?;
+        let read_only = self.source_of(self.selected_fsm).err();
+        let item = |ui: &mut egui::Ui, text: &str, enabled: bool| {
+            ui.add_enabled(enabled && read_only.is_none(), egui::Button::new(text))
+                .on_disabled_hover_text(read_only.as_deref().unwrap_or(""))
+                .clicked()
+        };
+        let mut picked = None;
+        match target {
+            CanvasTarget::State(state) => {
+                ui.label(egui::RichText::new(&state).strong());
+                ui.separator();
+                let initial = fsm.initial_state() == Some(state.as_str());
+                if item(ui, "Set as initial state", !initial) {
+                    picked = Some(CanvasAction::SetInitial(state.clone()));
+                }
+                if item(ui, "Add outgoing transition…", true) {
+                    picked = Some(CanvasAction::AddTransition(state.clone()));
+                }
+                if item(ui, "Add entry action…", true) {
+                    picked = Some(CanvasAction::AddEntryAction(state.clone()));
+                }
+                if item(ui, "Rename…", true) {
+                    picked = Some(CanvasAction::Rename(state.clone()));
+                }
+                if item(ui, "🗑 Delete state", true) {
+                    picked = Some(CanvasAction::Delete(state.clone()));
+                }
+                ui.separator();
+                if item(ui, "Go to definition", true) {
+                    picked = Some(CanvasAction::GoToDefinition(state.clone()));
+                }
+                if ui.button("▶ Start simulation here").clicked() {
+                    picked = Some(CanvasAction::SimulateFrom(state));
                 }
-                pts.extend_from_slice(&b.points);
-                Some(pts)
             }
-            _ => {
-                // Fallback: pick the longest segment we can find for that transition.
-                layout
-                    .edges
-                    .iter()
-                    .filter(|e| e.transition_index == Some(transition_index))
-                    .max_by(|a, b| a.points.len().cmp(&b.points.len()))
-                    .map(|e| e.points.clone())
+            CanvasTarget::Empty(at) => {
+                if item(ui, "Add state here…", true) {
+                    picked = Some(CanvasAction::AddState(at));
+                }
+                if ui.button("Fit view").clicked() {
+                    picked = Some(CanvasAction::Fit);
+                }
             }
         }
+        if picked.is_some() {
+            ui.close_menu();
+        }
+        picked
     }
-}
 
-/// Convert PascalCase to snake_case
-fn to_snake_case(s: &str) -> String {
-    let mut result = String::new();
-    for (i, c) in s.chars().enumerate() {
-        if c.is_uppercase() {
-            if i > 0 {
-                result.push('_');
+    /// Carry out the context menu's `action` on the canvas at `canvas`
+    fn run_canvas_action(&mut self, action: CanvasAction, canvas: egui::Rect) {
+        let fsm_index = self.selected_fsm;
+        let Some(fsm_name) = self.fsms.get(fsm_index).map(|fsm| fsm.name.clone()) else {
+            return;
+        };
+        let result = match action {
+            CanvasAction::SetInitial(state) => self.edit_fsm_source(
+                fsm_index,
+                |source, fsm| set_initial_state(source, fsm, &state).ok_or_else(|| format!("No line to add `[*] --> {state}` on in {}", fsm.name)),
+                |_, _| Ok(()),
+            ),
+            CanvasAction::AddTransition(from) => {
+                self.new_transition = Some(NewTransitionDialog {
+                    fsm_index,
+                    from,
+                    to: None,
+                    pick_target: true,
+                    new_state: String::new(),
+                    edit: LabelEdit::default(),
+                    error: None,
+                });
+                Ok(())
             }
-            result.push(c.to_lowercase().next().unwrap());
-        } else {
-            result.push(c);
+            CanvasAction::AddEntryAction(state) => {
+                self.open_selection_dialog(SelectionAction::EntryAction, vec![state]);
+                Ok(())
+            }
+            CanvasAction::Rename(state) => {
+                self.open_rename(RenameKind::State);
+                if let Some(ref mut dialog) = self.rename {
+                    dialog.old_name = state.clone();
+                    dialog.new_name = state;
+                }
+                Ok(())
+            }
+            CanvasAction::Delete(state) => {
+                let states = [state.clone()];
+                let deleted = self.edit_fsm_source(
+                    fsm_index,
+                    |source, fsm| delete_states(source, fsm, &states).ok_or_else(|| format!("{}'s block could not be located", fsm.name)),
+                    |_, _| Ok(()),
+                );
+                if deleted.is_ok() {
+                    let remaining = self.selection.of(&fsm_name).iter().filter(|s| **s != state).cloned().collect();
+                    self.selection.set(&fsm_name, remaining);
+                }
+                deleted
+            }
+            CanvasAction::GoToDefinition(state) => self.go_to_definition(&state),
+            CanvasAction::SimulateFrom(state) => {
+                if let Some(fsm) = self.fsms.get(fsm_index) {
+                    self.sim.enabled = true;
+                    self.sim.start_at(fsm, &state);
+                }
+                Ok(())
+            }
+            CanvasAction::AddState(at) => {
+                self.new_state = Some(NewStateDialog { fsm_index, name: String::new(), at: at - canvas.center(), error: None });
+                Ok(())
+            }
+            CanvasAction::Fit => {
+                self.fit_requested = true;
+                Ok(())
+            }
+        };
+        if let Err(error) = result {
+            self.push_error(Category::Edit, error);
         }
     }
-    result
-}
 
-impl eframe::App for OxidateApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    /// Show the declaration of `state` (else its first mention) in the editor
+    fn go_to_definition(&mut self, state: &str) -> Result<(), String> {
+        let (source, _, member) = self.source_of(self.selected_fsm)?;
+        let fsm = &self.fsms[self.selected_fsm];
+        let range = state_definition(&source, fsm, state).ok_or_else(|| format!("'{state}' is not in {}'s source", fsm.name))?;
+        // The editor shows the workspace member, or else the FSM's own block
+        let offset = match member {
+            Some(_) => 0,
+            None if self.selected_fsm < self.fsm_sources.blocks.len() => self.fsm_sources.offset(self.selected_fsm),
+            None => return Err("The editor is not showing the FSM's block".to_string()),
+        };
+        self.editor_jump = Some(range.start.saturating_sub(offset)..range.end.saturating_sub(offset));
+        self.show_code_panel = true;
+        Ok(())
+    }
+
+    /// Select the text of `editor_jump` in the editor that produced `output` and scroll to it
+    fn reveal_in_editor(&mut self, ui: &mut egui::Ui, output: &egui::text_edit::TextEditOutput) {
+        let Some(range) = self.editor_jump.take() else {
+            return;
+        };
+        let text = self.editor_text();
+        // Stale (the text changed since)
+        let Some(selected) = text.get(range.clone()) else {
+            return;
+        };
+        let start = text[..range.start].chars().count();
+        let (from, to) = (egui::text::CCursor::new(start), egui::text::CCursor::new(start + selected.chars().count()));
+        let mut state = output.state.clone();
+        state.cursor.set_char_range(Some(egui::text::CCursorRange::two(from, to)));
+        state.store(ui.ctx(), output.response.id);
+        output.response.request_focus();
+        let cursor = output.galley.pos_from_cursor(&output.galley.from_ccursor(from));
+        ui.scroll_to_rect(cursor.translate(output.galley_pos.to_vec2()), Some(egui::Align::Center));
+    }
+
+    /// Help > Tutorial: open the panels it points at and start from its blank machine
+    fn start_tutorial(&mut self, ctx: &egui::Context) {
+        self.show_code_panel = true;
+        self.show_generated_panel = true;
+        self.sim.enabled = false;
+        self.sim.running = false;
+        self.bus.enabled = false;
+        self.request_file_action(ctx, FileAction::Load(TUTORIAL_SOURCE));
+        self.tutorial.start();
+    }
+
+    /// The tutorial's offer, its current step as a card beside the region it points
+    /// at (the rest of the window dimmed), or its last card
+    fn show_tutorial(&mut self, ctx: &egui::Context) {
+        if self.tutorial.step_done(self) {
+            self.tutorial.next();
+            ctx.request_repaint();
+        }
+        let (title, text, spotlight) = match self.tutorial.stage {
+            TutorialStage::Off => return,
+            TutorialStage::Offered => (
+                "🎓 New to Oxidate?".to_string(),
+                "Take the tutorial: write a first FSM, see its diagram, generate its code and simulate it. \
+                 Help > Tutorial opens it any time."
+                    .to_string(),
+                None,
+            ),
+            TutorialStage::Step(_) => {
+                let Some((index, step)) = self.tutorial.current() else {
+                    return;
+                };
+                let title = format!("{} ({}/{})", step.title, index + 1, self.tutorial.steps.len());
+                (title, step.instruction.to_string(), self.spotlights.get(&step.spotlight).copied())
+            }
+            TutorialStage::Finished => (
+                "🎉 Your first FSM runs".to_string(),
+                "That is the whole loop: edit the DSL, check the diagram, take the generated code and try \
+                 the machine in the simulator. Help > DSL Reference lists everything else the DSL can do."
+                    .to_string(),
+                None,
+            ),
+        };
+
+        let screen = ctx.screen_rect();
+        if let Some(spot) = spotlight.map(|rect| rect.expand(4.0)) {
+            let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Middle, egui::Id::new("tutorial_dim")));
+            for rect in tutorial::dim_rects(screen, spot) {
+                painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(150));
+            }
+            painter.rect_stroke(spot, 4.0, egui::Stroke::new(2.0, egui::Color32::GOLD));
+        }
+        let id = egui::Id::new("tutorial_card");
+        let size = ctx.memory(|m| m.area_rect(id)).map_or(egui::vec2(320.0, 140.0), |r| r.size());
+        let pos = match spotlight {
+            Some(spot) => tutorial::card_position(screen, spot, size),
+            None => screen.center() - size / 2.0,
+        };
+
+        let (mut start, mut skip, mut exit) = (false, false, false);
+        egui::Area::new(id).order(egui::Order::Foreground).fixed_pos(pos).show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.set_max_width(320.0);
+                ui.strong(&title);
+                ui.add_space(4.0);
+                ui.label(&text);
+                ui.add_space(6.0);
+                ui.horizontal(|ui| match self.tutorial.stage {
+                    TutorialStage::Offered => {
+                        start = ui.button("Start the tutorial").clicked();
+                        exit = ui.button("No thanks").clicked();
+                    }
+                    TutorialStage::Finished => exit = ui.button("Close").clicked(),
+                    _ => {
+                        skip = ui.button("Skip step").clicked();
+                        exit = ui.button("Exit tutorial").clicked();
+                    }
+                });
+            });
+        });
+        if start {
+            self.start_tutorial(ctx);
+        } else if skip {
+            self.tutorial.next();
+        } else if exit {
+            self.tutorial.exit();
+        }
+    }
+
+    /// Help > DSL Reference: each construct with its grammar rule and a snippet to
+    /// insert at the editor's cursor
+    fn show_dsl_reference_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_dsl_reference;
+        let editable = self.workspace.is_some() || self.selected_fsm < self.fsm_sources.blocks.len();
+        egui::Window::new("📖 DSL Reference").open(&mut open).default_size([560.0, 600.0]).show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for scope in [Scope::File, Scope::Fsm, Scope::State] {
+                    ui.heading(scope.to_string());
+                    for entry in DOC_ENTRIES.iter().filter(|e| e.scope == scope) {
+                        ui.group(|ui| {
+                            ui.set_width(ui.available_width());
+                            ui.horizontal(|ui| {
+                                ui.strong(entry.title);
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    let insert = ui
+                                        .add_enabled(editable, egui::Button::new("Insert at cursor"))
+                                        .on_disabled_hover_text("Open an FSM in the editor first");
+                                    if insert.clicked() {
+                                        self.pending_snippet = Some(entry.snippet);
+                                        self.show_code_panel = true;
+                                    }
+                                });
+                            });
+                            ui.label(entry.description);
+                            ui.code(entry.snippet);
+                            ui.label(egui::RichText::new(format!("{} = {}", entry.rule, entry.shape())).monospace().small().weak());
+                        });
+                    }
+                    ui.add_space(8.0);
+                }
+            });
+        });
+        self.show_dsl_reference = open;
+    }
+
+    /// Insert the snippet picked in the DSL reference at the cursor of the editor
+    /// that produced `output` (the end of the text if it never had one)
+    fn insert_pending_snippet(&mut self, ui: &mut egui::Ui, output: &egui::text_edit::TextEditOutput) -> bool {
+        let Some(snippet) = self.pending_snippet.take() else {
+            return false;
+        };
+        // The reference window has the focus, so this is where the editor's cursor was left
+        let index = output.state.cursor.char_range().map_or(usize::MAX, |c| c.primary.index);
+        let text = self.editor_text_mut();
+        let range = docs::insert_snippet(text, char_to_byte_index(text, index), snippet);
+        let cursor = text[..range.end].chars().count();
+        let mut state = output.state.clone();
+        state
+            .cursor
+            .set_char_range(Some(egui::text::CCursorRange::one(egui::text::CCursor::new(cursor))));
+        state.store(ui.ctx(), output.response.id);
+        output.response.request_focus();
+        true
+    }
+
+    fn show_new_state_window(&mut self, ctx: &egui::Context) {
+        let Some(mut dialog) = self.new_state.take() else {
+            return;
+        };
+        let mut open = true;
+        let (mut apply, mut cancel) = (false, ctx.input(|i| i.key_pressed(egui::Key::Escape)));
+        egui::Window::new("➕ New State")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Name:");
+                    let response = ui.text_edit_singleline(&mut dialog.name);
+                    apply = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                });
+                if let Some(ref error) = dialog.error {
+                    ui.colored_label(egui::Color32::RED, format!("❌ {}", error));
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    apply |= ui.button("✓ Add").clicked();
+                    cancel |= ui.button("Cancel").clicked();
+                });
+            });
+
+        if apply {
+            match self.apply_new_state(&dialog) {
+                Ok(()) => return,
+                Err(error) => dialog.error = Some(error),
+            }
+        }
+        if open && !cancel {
+            self.new_state = Some(dialog);
+        }
+    }
+
+    /// Declare the dialog's state, re-parse, and show it where the canvas was clicked
+    fn apply_new_state(&mut self, dialog: &NewStateDialog) -> Result<(), String> {
+        let name = dialog.name.trim();
+        if !is_identifier(name) {
+            return Err(format!("'{name}' is not a valid state name"));
+        }
+        if self.fsms.get(dialog.fsm_index).is_some_and(|fsm| fsm.states.iter().any(|s| s.name == name)) {
+            return Err(format!("State '{name}' already exists"));
+        }
+        self.edit_fsm_source(
+            dialog.fsm_index,
+            |source, fsm| add_state(source, fsm, name).ok_or_else(|| format!("No line to add to in {}'s block", fsm.name)),
+            |_, _| Ok(()),
+        )?;
+        self.reveal_state = Some((name.to_string(), dialog.at));
+        Ok(())
+    }
+
+    fn open_selection_dialog(&mut self, action: SelectionAction, states: Vec<String>) {
+        self.selection_dialog = Some(SelectionDialog {
+            fsm_index: self.selected_fsm,
+            action,
+            states,
+            value: String::new(),
+            error: None,
+        });
+    }
+
+    fn show_selection_window(&mut self, ctx: &egui::Context) {
+        let Some(mut dialog) = self.selection_dialog.take() else {
+            return;
+        };
+        let mut open = true;
+        let (mut apply, mut cancel) = (false, ctx.input(|i| i.key_pressed(egui::Key::Escape)));
+        let (title, field, hint) = match dialog.action {
+            SelectionAction::Tag => ("Tag States", "Tag:", "safety"),
+            SelectionAction::Color => ("Color States", "Color:", "red or #3060D0"),
+            SelectionAction::Extract => ("Extract Composite State", "Name:", "Operating"),
+            SelectionAction::EntryAction => ("Add Entry Action", "Actions:", "start_timer(t); beep(2)"),
+        };
+        egui::Window::new(title)
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(dialog.states.join(", "));
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    ui.label(field);
+                    ui.add(egui::TextEdit::singleline(&mut dialog.value).hint_text(hint));
+                });
+                if dialog.action == SelectionAction::Extract {
+                    ui.small("They move into `fsm <Name>` with the transitions between them; a state <Name> replaces them here");
+                }
+                if let Some(ref error) = dialog.error {
+                    ui.colored_label(egui::Color32::RED, format!("❌ {}", error));
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    apply = ui.button("✓ Apply").clicked();
+                    cancel |= ui.button("Cancel").clicked();
+                });
+            });
+
+        if apply {
+            match self.apply_selection_edit(&dialog) {
+                Ok(()) => return,
+                Err(error) => dialog.error = Some(error),
+            }
+        }
+        if open && !cancel {
+            self.selection_dialog = Some(dialog);
+        }
+    }
+
+    /// Tag, color or extract the dialog's states in the DSL and re-parse
+    fn apply_selection_edit(&mut self, dialog: &SelectionDialog) -> Result<(), String> {
+        let value = dialog.value.trim();
+        match dialog.action {
+            SelectionAction::Tag if !is_identifier(value) => return Err(format!("'{value}' is not a valid tag")),
+            SelectionAction::Color if fsm::color::parse_color(value).is_none() => {
+                return Err(format!("'{value}' is not a color name or #RRGGBB"));
+            }
+            SelectionAction::EntryAction if value.is_empty() => return Err("Enter the actions to run on entry".to_string()),
+            _ => {}
+        }
+        self.edit_fsm_source(
+            dialog.fsm_index,
+            |source, fsm| {
+                let no_block = || format!("{}'s block could not be located", fsm.name);
+                match dialog.action {
+                    SelectionAction::Tag => tag_states(source, fsm, &dialog.states, value).ok_or_else(no_block),
+                    SelectionAction::Color => color_states(source, fsm, &dialog.states, value).ok_or_else(no_block),
+                    SelectionAction::Extract => extract_states(source, fsm, &dialog.states, value).map_err(|e| e.to_string()),
+                    SelectionAction::EntryAction => dialog
+                        .states
+                        .iter()
+                        .try_fold(source.to_string(), |edited, state| add_entry_action(&edited, fsm, state, value).ok_or_else(no_block)),
+                }
+            },
+            |_, _| Ok(()),
+        )?;
+        // The composite state stands for the group
+        if let (SelectionAction::Extract, Some(fsm)) = (dialog.action, self.fsms.get(dialog.fsm_index)) {
+            let fsm_name = fsm.name.clone();
+            self.selection.set(&fsm_name, vec![value.to_string()]);
+        }
+        Ok(())
+    }
+
+    /// The buffer the FSM at `fsm_index` was parsed from, its file and its workspace member.
+    /// Fails for FSMs included from another file.
+    fn source_of(&self, fsm_index: usize) -> Result<(String, Option<PathBuf>, Option<usize>), String> {
+        let fsm = self.fsms.get(fsm_index).ok_or("The state machine no longer exists")?;
+        let (source, path, member) = match self.workspace {
+            Some(ref workspace) => {
+                let member = workspace.member_of_fsm(fsm_index).ok_or("The state machine no longer exists")?;
+                let buffer = &workspace.members[member];
+                (buffer.source.clone(), Some(buffer.path.clone()), Some(member))
+            }
+            None => (self.source_code.clone(), self.current_file.clone(), None),
+        };
+        if fsm.source_file != path.as_ref().map(|p| p.display().to_string()) {
+            let file = fsm.source_file.clone().unwrap_or_default();
+            return Err(format!("{} is defined in '{}'; edit it there", fsm.name, file));
+        }
+        Ok((source, path, member))
+    }
+
+    /// Replace the DSL text of the FSM at `fsm_index` with `edit(source, fsm)` and re-parse,
+    /// as one change to the editor buffer. `check` compares the FSM before and after the
+    /// edit; nothing changes if the edit, the parse or the check fails.
+    fn edit_fsm_source(
+        &mut self,
+        fsm_index: usize,
+        edit: impl FnOnce(&str, &FsmDefinition) -> Result<String, String>,
+        check: impl FnOnce(&FsmDefinition, &FsmDefinition) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let fsm = self.fsms.get(fsm_index).cloned().ok_or("The state machine no longer exists")?;
+        let (source, path, member) = self.source_of(fsm_index)?;
+
+        let edited = edit(&source, &fsm)?;
+        let parsed = match path {
+            Some(ref path) => parse_fsm_source(&edited, path),
+            None => parse_fsm(&edited),
+        }
+        .map_err(|e| e.to_string())?;
+        if let Some(after) = parsed.iter().find(|f| f.name == fsm.name) {
+            check(&fsm, after)?;
+        }
+
+        match (member, self.workspace.as_mut()) {
+            (Some(member), Some(workspace)) => {
+                workspace.members[member].source = edited;
+                if workspace.members[member].parse() {
+                    self.staleness.record(member, &workspace.members[member].source);
+                }
+                self.show_workspace();
+            }
+            _ => {
+                self.source_code = edited;
+                self.parse_source();
+            }
+        }
+        self.last_edit = None;
+        Ok(())
+    }
+
+    /// Faint diagonal notice across the canvas that the diagram predates the editor's text
+    fn draw_stale_watermark(&self, ui: &egui::Ui, canvas: egui::Rect) {
+        let shortcut = ui.ctx().format_shortcut(&egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::R));
+        let text = format!("OUT OF DATE — press Parse ({shortcut})");
+        let color = self.theme.state_name.gamma_multiply(0.15);
+        // Sized to span most of the diagonal
+        let diagonal = canvas.width().hypot(canvas.height());
+        let measured = ui.fonts(|f| f.layout_no_wrap(text.clone(), egui::FontId::proportional(32.0), color).size().x);
+        let size = (32.0 * 0.7 * diagonal / measured.max(1.0)).clamp(14.0, 96.0);
+        let galley = ui.fonts(|f| f.layout_no_wrap(text, egui::FontId::proportional(size), color));
+        let angle = -canvas.height().atan2(canvas.width());
+        // Text shapes rotate about their top-left corner
+        let offset = egui::emath::Rot2::from_angle(angle) * (galley.size() / 2.0);
+        ui.painter_at(canvas).add(egui::epaint::TextShape::new(canvas.center() - offset, galley, color).with_angle(angle));
+    }
+
+    /// Legend of the state tags in the canvas's bottom-left corner, with the color their states share
+    fn draw_tag_legend(&self, ui: &mut egui::Ui, canvas: egui::Rect) {
+        const MARGIN: f32 = 10.0;
+        const ROW_HEIGHT: f32 = 18.0;
+
+        let Some(fsm) = self.fsms.get(self.selected_fsm) else {
+            return;
+        };
+        let legend = diagram::tag_legend(fsm);
+        if legend.is_empty() {
+            return;
+        }
+        let font = egui::FontId::proportional(12.0);
+        let text_width = legend
+            .iter()
+            .map(|(tag, _)| ui.fonts(|f| f.layout_no_wrap(tag.clone(), font.clone(), egui::Color32::WHITE).size().x))
+            .fold(0.0, f32::max);
+        let size = egui::vec2(text_width + 36.0, legend.len() as f32 * ROW_HEIGHT + 8.0);
+        let rect = egui::Rect::from_min_size(egui::pos2(canvas.left() + MARGIN, canvas.bottom() - MARGIN - size.y), size);
+        if !canvas.contains_rect(rect) {
+            return;
+        }
+
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 4.0, egui::Color32::from_rgba_unmultiplied(15, 17, 20, 220));
+        painter.rect_stroke(rect, 4.0, egui::Stroke::new(1.0, egui::Color32::from_rgb(70, 80, 95)));
+        for (i, (tag, color)) in legend.iter().enumerate() {
+            let center_y = rect.top() + 4.0 + (i as f32 + 0.5) * ROW_HEIGHT;
+            let swatch = egui::Rect::from_center_size(egui::pos2(rect.left() + 14.0, center_y), egui::vec2(10.0, 10.0));
+            match color {
+                Some(color) => painter.rect_filled(swatch, 2.0, *color),
+                None => painter.rect_stroke(swatch, 2.0, egui::Stroke::new(1.0, egui::Color32::GRAY)),
+            };
+            painter.text(
+                egui::pos2(rect.left() + 26.0, center_y),
+                egui::Align2::LEFT_CENTER,
+                tag,
+                font.clone(),
+                egui::Color32::from_rgb(200, 210, 225),
+            );
+        }
+    }
+
+    /// Paint `content` on the canvas through `viewport`: edges, markers, notes,
+    /// states and the simulation token
+    fn paint_diagram(&self, ctx: &egui::Context, painter: &egui::Painter, viewport: diagram::Viewport, content: PaneContent) {
+        let PaneContent { fsm, layout, .. } = content;
+        let anim_t = content.animation.map(|(_, t)| t);
+        let anim_from = content.animation.map(|(anim, _)| anim);
+        let label_font_size = self.layout_config.edge_label_font_size;
+        let measure = content.render_cache.measure(ctx);
+        let mut scene = SceneBuilder::new(&self.theme, &measure, viewport);
+        scene.set_render_cache(content.render_cache);
+        scene.set_font_size(self.layout_config.font_size);
+        scene.set_state_box(self.layout_config.state_box);
+        scene.set_focus(content.focus);
+
+        // Edges can't be interpolated meaningfully, so crossfade old and new routes.
+        if let (Some(t), Some(anim)) = (anim_t, anim_from) {
+            scene.set_opacity(1.0 - t);
+            scene.edges(&anim.from_layout, label_font_size, self.highlighted_in(&anim.from_layout));
+            scene.set_opacity(t);
+        }
+        scene.edges(layout, label_font_size, self.highlighted_in(layout));
+
+        // Node position (interpolated while animating) and opacity (new nodes fade in).
+        let node_pos = |name: &str| -> Option<(egui::Pos2, f32)> {
+            let to = content.positions.get(name).copied()?;
+            match (anim_t, anim_from.and_then(|a| a.from_positions.get(name))) {
+                (Some(t), Some(&from)) => Some((from.lerp(to, t), 1.0)),
+                (Some(t), None) => Some((to, t)),
+                _ => Some((to, 1.0)),
+            }
+        };
+
+        // Draw the initial pseudo-state if present.
+        if let Some((pos, alpha)) = node_pos("[*]") {
+            scene.set_opacity(alpha);
+            scene.initial_marker(pos);
+        }
+        if let Some((pos, alpha)) = node_pos(ANY_STATE) {
+            scene.set_opacity(alpha);
+            scene.any_state_marker(pos);
+        }
+
+        // Fade out states that no longer exist.
+        if let (Some(t), Some(anim)) = (anim_t, anim_from) {
+            scene.set_opacity(1.0 - t);
+            for state in &anim.from_layout.states {
+                if fsm.states.iter().any(|s| s.name == state.name) {
+                    continue;
+                }
+                if let Some(&pos) = anim.from_positions.get(&state.name) {
+                    scene.state(state, pos, false, false);
+                }
+            }
+        }
+
+        // Notes sit beside their state, under it if they overlap
+        for note in &fsm.notes {
+            let state = fsm.states.iter().find(|s| s.name == note.target_state);
+            if let (Some(state), Some((pos, alpha))) = (state, node_pos(&note.target_state)) {
+                scene.set_opacity(alpha);
+                scene.note(state, pos, note);
+            }
+        }
+
+        // Draw states (on top)
+        for state in &fsm.states {
+            if let Some((pos, alpha)) = node_pos(&state.name) {
+                let is_active = content.sim.is_some_and(|sim| sim.displayed_state() == Some(state.name.as_str()));
+                scene.set_opacity(alpha);
+                scene.state(state, pos, fsm.initial_state() == Some(state.name.as_str()), is_active);
+            }
+        }
+        scene.set_opacity(1.0);
+
+        // While scrubbing, flash the transition fired at that step by looping its token.
+        if let Some(entry) = content.sim.and_then(|sim| sim.scrubbed()) {
+            if let Some(transition) = entry.transition {
+                let current = fsm.transition_index(transition).and_then(|i| fsm.transitions.get(i));
+                let to = current.map_or(entry.to.as_str(), |t| t.target.as_str());
+                let progress = (ctx.input(|i| i.time) % SCRUB_FLASH_PERIOD_S) / SCRUB_FLASH_PERIOD_S;
+                scene.sim_token(
+                    layout,
+                    &SimToken {
+                        transition,
+                        from: &entry.from,
+                        to,
+                        progress: progress as f32,
+                    },
+                );
+                ctx.request_repaint();
+            }
+        } else if let Some(fired) = content.sim.and_then(|sim| sim.last_fired.as_ref()) {
+            // Animate last fired transition as a moving dot along the engine route.
+            let elapsed = fired.started_at.elapsed().as_secs_f32();
+            if let Some(transition) = fired.transition.filter(|_| elapsed <= fired.duration_s) {
+                scene.sim_token(
+                    layout,
+                    &SimToken {
+                        transition,
+                        from: &fired.from,
+                        to: &fired.to,
+                        progress: elapsed / fired.duration_s,
+                    },
+                );
+            }
+        }
+        diagram::egui_backend::paint_with_cache(painter, scene.finish(), content.render_cache);
+    }
+
+    /// The right viewport of the split view: pans, zooms and fits like the main
+    /// canvas (editing and selection stay there)
+    fn show_split_pane(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, canvas: egui::Rect) {
+        let response = ui.interact(canvas, ui.id().with("split_canvas"), egui::Sense::click_and_drag());
+        if response.dragged() {
+            self.split.view.pan += response.drag_delta();
+        }
+        let (scroll_delta, fine) = ctx.input(|i| (i.raw_scroll_delta, i.modifiers.command));
+        if let Some(pointer) = response.hover_pos().filter(|_| scroll_delta.y != 0.0) {
+            let step = if fine { 0.00025 } else { 0.001 };
+            self.split.view.zoom_at(pointer - canvas.center(), self.split.view.zoom + scroll_delta.y * step);
+        }
+        self.split.fit_requested |= response.double_clicked();
+        if let Some(bounds) = self.split.layout.as_ref().map(|l| l.bounds).filter(|_| self.split.fit_requested) {
+            self.split.view.fit(bounds, canvas, FIT_MARGIN);
+            self.split.fit_requested = false;
+        }
+
+        let painter = ui.painter_at(canvas);
+        painter.rect_filled(canvas, 0.0, self.theme.background);
+        draw_grid(&painter, canvas, self.split.view.zoom, self.split.view.pan);
+        let Some(fsm) = self.pane_fsm(Pane::Right) else {
+            return;
+        };
+        if let Some(layout) = self.split.layout.as_ref().filter(|l| l.fsm_name == fsm.name) {
+            let content = PaneContent {
+                fsm,
+                layout,
+                positions: &self.split.state_positions,
+                render_cache: &self.split.render_cache,
+                animation: None,
+                focus: None,
+                sim: self.shown_sim(Pane::Right),
+            };
+            self.paint_diagram(ctx, &painter, self.split.view.viewport(canvas), content);
+        }
+    }
+
+    /// The FSM picker in the top-left corner of a split view viewport
+    fn pane_fsm_picker(&mut self, ui: &mut egui::Ui, canvas: egui::Rect, pane: Pane) {
+        let Some(shown) = self.pane_fsm(pane).map(|f| f.name.clone()) else {
+            return;
+        };
+        let mut picked = None;
+        let mut corner = ui.new_child(egui::UiBuilder::new().max_rect(canvas.shrink(8.0)));
+        egui::ComboBox::from_id_salt(("split_fsm", pane == Pane::Left))
+            .selected_text(&shown)
+            .show_ui(&mut corner, |ui| {
+                for (index, fsm) in self.fsms.iter().enumerate() {
+                    if ui.selectable_label(fsm.name == shown, &fsm.name).clicked() {
+                        picked = Some(index);
+                    }
+                }
+            });
+        let Some(index) = picked.filter(|&i| self.fsms[i].name != shown) else {
+            return;
+        };
+        let previous = self.pane_fsm(self.split.sim_pane()).cloned();
+        match pane {
+            Pane::Left => self.select_fsm(index),
+            Pane::Right => {
+                let name = self.fsms[index].name.clone();
+                self.split.show(&name);
+            }
+        }
+        if self.split.sim_pane() == pane {
+            self.reattach_sim(previous.as_ref());
+        }
+    }
+
+    /// Simulator whose state `pane` shows: the bus machine of its FSM in multi-FSM
+    /// mode, otherwise the single one if it is attached to `pane` (`None` outside
+    /// the debug mode)
+    fn shown_sim(&self, pane: Pane) -> Option<&Simulator> {
+        if !self.sim.enabled {
+            return None;
+        }
+        if !self.bus.enabled {
+            return (self.split.sim_pane() == pane).then_some(&self.sim);
+        }
+        let fsm = self.pane_fsm(pane)?;
+        self.bus.machine(&fsm.name).map(|machine| &machine.sim)
+    }
+
+    /// FSM shown in `pane`: the selected one on the left, the split view's on the right
+    fn pane_fsm(&self, pane: Pane) -> Option<&FsmDefinition> {
+        self.fsms.get(self.pane_fsm_index(pane)?)
+    }
+
+    /// Index in `fsms` of the FSM `pane` shows
+    fn pane_fsm_index(&self, pane: Pane) -> Option<usize> {
+        match pane {
+            Pane::Left => Some(self.selected_fsm),
+            Pane::Right if self.split.enabled => self.split.fsm_index(&self.fsms, self.selected_fsm),
+            Pane::Right => None,
+        }
+    }
+
+    /// Carry the single simulator over after the FSM of its pane changed from `previous`
+    fn reattach_sim(&mut self, previous: Option<&FsmDefinition>) {
+        if let Some(fsm) = self.pane_fsm(self.split.sim_pane()).cloned() {
+            self.sim.reconcile(previous, &fsm);
+        }
+    }
+
+    /// Saved guard values of the FSM called `fsm` (whose guard expressions are
+    /// `guards`): pick one to apply it, save the current checkboxes under a name,
+    /// and export or import them as JSON
+    fn draw_guard_profiles(&mut self, ui: &mut egui::Ui, fsm: &str, guards: &[String]) {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Profile:").on_hover_text("Named sets of guard values, kept per FSM");
+            let profiles = self.guard_profiles.of(fsm);
+            let active = self
+                .active_profile
+                .as_ref()
+                .filter(|(of, _)| of == fsm)
+                .and_then(|(_, name)| profiles.iter().find(|p| &p.name == name));
+            let mut apply = None;
+            egui::ComboBox::from_id_salt("guard_profile")
+                .selected_text(active.map_or("<none>", |p| p.name.as_str()))
+                .show_ui(ui, |ui| {
+                    if profiles.is_empty() {
+                        ui.weak("no profiles saved");
+                    }
+                    for profile in profiles {
+                        let unmatched = profile.reconcile(guards).unmatched;
+                        let text = if unmatched.is_empty() { profile.name.clone() } else { format!("⚠ {}", profile.name) };
+                        let item = ui.selectable_label(active.is_some_and(|a| a.name == profile.name), text);
+                        let item = if unmatched.is_empty() {
+                            item
+                        } else {
+                            item.on_hover_text(format!("No guard matches: {}", unmatched.join(", ")))
+                        };
+                        if item.clicked() {
+                            apply = Some(profile.clone());
+                        }
+                    }
+                });
+            if let Some(profile) = active {
+                let unmatched = profile.reconcile(guards).unmatched;
+                if !unmatched.is_empty() {
+                    ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {} unmatched", unmatched.len())).on_hover_text(format!(
+                        "Saved for guards the FSM no longer has: {}",
+                        unmatched.join(", ")
+                    ));
+                }
+            }
+            if let Some(profile) = apply {
+                let reconciled = profile.reconcile(guards);
+                self.sim.guard_values.extend(reconciled.values);
+                self.active_profile = Some((fsm.to_string(), profile.name));
+            }
+
+            ui.separator();
+            ui.add(egui::TextEdit::singleline(&mut self.profile_name).hint_text("profile name").desired_width(110.0));
+            let name = self.profile_name.trim().to_string();
+            if ui.add_enabled(!name.is_empty(), egui::Button::new("Save")).on_hover_text("Save the guard values above under this name").clicked() {
+                self.guard_profiles.save(fsm, GuardProfile::capture(&name, &self.sim.guard_values, guards));
+                self.active_profile = Some((fsm.to_string(), name));
+                self.profile_name.clear();
+            }
+            let active = self.active_profile.clone().filter(|(of, name)| of == fsm && self.guard_profiles.get(fsm, name).is_some());
+            if ui.add_enabled(active.is_some(), egui::Button::new("Delete")).on_hover_text("Delete the selected profile").clicked() {
+                if let Some((_, name)) = active {
+                    self.guard_profiles.remove(fsm, &name);
+                    self.active_profile = None;
+                }
+            }
+            ui.separator();
+            let exportable = !self.guard_profiles.of(fsm).is_empty();
+            if ui.add_enabled(exportable, egui::Button::new("Export...")).on_hover_text("Save this FSM's profiles as JSON").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("JSON", &["json"])
+                    .set_file_name(format!("{}_guard_profiles.json", fsm))
+                    .save_file()
+                {
+                    if let Err(e) = std::fs::write(&path, self.guard_profiles.export(fsm)) {
+                        self.push_error(Category::Export, format!("Could not write '{}': {}", path.display(), e));
+                    }
+                }
+            }
+            if ui.button("Import...").on_hover_text("Add profiles from a JSON file (same names are replaced)").clicked() {
+                if let Some(path) = rfd::FileDialog::new().add_filter("JSON", &["json"]).pick_file() {
+                    let imported = std::fs::read_to_string(&path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|json| self.guard_profiles.import(fsm, &json).map_err(|e| e.to_string()));
+                    if let Err(e) = imported {
+                        self.push_error(Category::File, format!("Could not import '{}': {}", path.display(), e));
+                    }
+                }
+            }
+        });
+    }
+
+    /// Controls of the multi-FSM simulation: the machines on the bus, a tab per machine
+    /// with its current state, the shared event input and the prefixed log
+    fn draw_bus_panel(&mut self, ui: &mut egui::Ui) {
+        let fsms = self.fsms.clone();
+        ui.separator();
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Machines:");
+            for fsm in &fsms {
+                let mut on_bus = self.bus.machine(&fsm.name).is_some();
+                if ui.checkbox(&mut on_bus, &fsm.name).changed() {
+                    if on_bus {
+                        self.bus.add_machine(fsm);
+                    } else {
+                        self.bus.remove_machine(&fsm.name);
+                    }
+                }
+            }
+        });
+
+        // Tab strip: each machine with its current state; a click shows its FSM
+        let mut show = None;
+        ui.horizontal_wrapped(|ui| {
+            for machine in &self.bus.machines {
+                let shown = fsms.get(self.selected_fsm).is_some_and(|f| f.name == machine.fsm);
+                let state = machine.sim.current_state.as_deref().unwrap_or("<not started>");
+                let busy = if machine.sim.queued_events.is_empty() { "" } else { " ⏳" };
+                if ui.selectable_label(shown, format!("{} ● {state}{busy}", machine.fsm)).clicked() {
+                    show = fsms.iter().position(|f| f.name == machine.fsm);
+                }
+            }
+        });
+        if let Some(index) = show.filter(|&i| i != self.selected_fsm) {
+            self.select_fsm(index);
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Reset all").clicked() {
+                self.bus.reset_all(&fsms);
+            }
+            if ui.button(if self.bus.running { "Pause" } else { "Run" }).clicked() {
+                self.bus.running = !self.bus.running;
+            }
+            if ui.button("Step").on_hover_text("Every machine with queued events handles one").clicked() {
+                self.bus.step(&fsms);
+            }
+            ui.add(egui::Slider::new(&mut self.bus.speed, 0.1..=5.0).text("speed"));
+            if fsms.iter().any(|f| !f.timers.is_empty()) {
+                ui.checkbox(&mut self.bus.use_fsm_timers, "Use FSM timers");
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Event:");
+            ui.text_edit_singleline(&mut self.bus.event_input);
+            egui::ComboBox::from_id_salt("bus_target")
+                .selected_text(self.bus.target.as_deref().unwrap_or("every machine handling it"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.bus.target, None, "every machine handling it");
+                    let names: Vec<String> = self.bus.machines.iter().map(|m| m.fsm.clone()).collect();
+                    for name in names {
+                        ui.selectable_value(&mut self.bus.target, Some(name.clone()), name);
+                    }
+                });
+            if ui.button("Post").clicked() {
+                let event = std::mem::take(&mut self.bus.event_input);
+                self.bus.post_event(event, &fsms);
+            }
+            if ui.button("Clear log").clicked() {
+                self.bus.log.clear();
+            }
+        });
+
+        // Guards of the shown machine
+        if let Some(fsm) = fsms.get(self.selected_fsm) {
+            let guards = fsm::interpreter::guard_expressions(fsm);
+            if let (Some(machine), false) = (self.bus.machine_mut(&fsm.name), guards.is_empty()) {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(format!("{} guards:", fsm.name))
+                        .on_hover_text("Unchecked guards are false, so [else] branches are taken");
+                    for guard in guards {
+                        let value = machine.sim.guard_values.entry(guard.to_string()).or_default();
+                        ui.checkbox(value, guard);
+                    }
+                });
+            }
+        }
+
+        self.bus.action_order = self.codegen_options.action_order;
+        self.bus.tick(&fsms);
+
+        egui::ScrollArea::vertical()
+            .max_height(80.0)
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                let start = self.bus.log.len().saturating_sub(30);
+                for line in self.bus.log[start..].iter() {
+                    ui.label(line);
+                }
+            });
+    }
+
+    /// Draw the minimap overlay in the bottom-right corner of `canvas` and handle
+    /// click/drag navigation inside it
+    fn draw_minimap(&mut self, ui: &mut egui::Ui, canvas: egui::Rect) {
+        const MINIMAP_SIZE: egui::Vec2 = egui::vec2(200.0, 150.0);
+        const MARGIN: f32 = 10.0;
+
+        let Some(layout) = &self.layout else {
+            return;
+        };
+        let Some(fsm) = self.fsms.get(self.selected_fsm) else {
+            return;
+        };
+        if canvas.width() < MINIMAP_SIZE.x + 2.0 * MARGIN || canvas.height() < MINIMAP_SIZE.y + 2.0 * MARGIN {
+            return;
+        }
+
+        let mini = egui::Rect::from_min_size(canvas.max - MINIMAP_SIZE - egui::vec2(MARGIN, MARGIN), MINIMAP_SIZE);
+        let inner = mini.shrink(6.0);
+        let bounds = layout.bounds.expand(20.0);
+        let scale = (inner.width() / bounds.width().max(1.0)).min(inner.height() / bounds.height().max(1.0));
+        let to_mini = |p: egui::Pos2| inner.center() + (p - bounds.center()) * scale;
+        let from_mini = |p: egui::Pos2| bounds.center() + (p - inner.center()) / scale;
+
+        let painter = ui.painter_at(mini);
+        painter.rect_filled(mini, 4.0, egui::Color32::from_rgba_unmultiplied(15, 17, 20, 220));
+        painter.rect_stroke(mini, 4.0, egui::Stroke::new(1.0, egui::Color32::from_rgb(70, 80, 95)));
+
+        let edge_stroke = egui::Stroke::new(1.0, egui::Color32::from_rgb(120, 130, 150));
+        for edge in &layout.edges {
+            let points: Vec<egui::Pos2> = edge.points.iter().copied().map(to_mini).collect();
+            painter.add(egui::Shape::line(points, edge_stroke));
+        }
+        for state in &fsm.states {
+            if let Some(&pos) = self.state_positions.get(&state.name) {
+                let r = egui::Rect::from_center_size(to_mini(pos), estimate_state_size(state, self.layout_config.font_size, &self.layout_config.state_box) * scale);
+                let color = state.rgb().map_or(egui::Color32::from_rgb(60, 90, 130), |[r, g, b]| egui::Color32::from_rgb(r, g, b));
+                painter.rect_filled(r, 1.0, color);
+            }
+        }
+
+        // Visible part of the diagram, mapped back from screen space through zoom and pan
+        let to_diagram = |p: egui::Pos2| self.view.to_diagram(canvas, p);
+        let viewport = egui::Rect::from_min_max(to_diagram(canvas.min), to_diagram(canvas.max));
+        painter.rect_stroke(
+            egui::Rect::from_min_max(to_mini(viewport.min), to_mini(viewport.max)),
+            0.0,
+            egui::Stroke::new(1.5, egui::Color32::from_rgb(255, 220, 120)),
+        );
+
+        // Clicking or dragging centres the main view on the point under the pointer
+        let response = ui.interact(mini, ui.id().with("minimap"), egui::Sense::click_and_drag());
+        if response.clicked() || response.dragged() {
+            if let Some(pointer) = response.interact_pointer_pos() {
+                let target = from_mini(inner.clamp(pointer));
+                self.view.show_at(target, egui::Vec2::ZERO);
+            }
+        }
+    }
+
+    /// Event matrix of the selected FSM: states as rows, events as columns.
+    /// Clicking a cell handled by transitions highlights them in the diagram.
+    fn show_event_matrix_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_event_matrix;
+        egui::Window::new("Event Matrix")
+            .open(&mut open)
+            .resizable(true)
+            .default_width(500.0)
+            .show(ctx, |ui| {
+                let Some(fsm) = self.fsms.get(self.selected_fsm) else {
+                    ui.label("No FSM loaded.");
+                    return;
+                };
+                let matrix = fsm::event_matrix(fsm);
+                if matrix.events.is_empty() {
+                    ui.label("This FSM has no events.");
+                    return;
+                }
+
+                ui.horizontal_wrapped(|ui| {
+                    for handling in [
+                        fsm::matrix::EventHandling::External,
+                        fsm::matrix::EventHandling::Internal,
+                        fsm::matrix::EventHandling::GuardedOnly,
+                        fsm::matrix::EventHandling::Unhandled,
+                    ] {
+                        ui.colored_label(event_handling_color(handling), handling.as_str());
+                    }
+                });
+                ui.separator();
+
+                let transition_ids = fsm.transition_ids();
+                let cell_ids = |cell: &fsm::matrix::EventMatrixCell| -> Vec<fsm::TransitionId> {
+                    cell.transitions.iter().filter_map(|&i| transition_ids.get(i).copied()).collect()
+                };
+                let mut clicked: Option<Vec<fsm::TransitionId>> = None;
+                egui::ScrollArea::both().show(ui, |ui| {
+                    egui::Grid::new("event_matrix_grid").striped(true).show(ui, |ui| {
+                        ui.label("");
+                        for event in &matrix.events {
+                            ui.strong(event);
+                        }
+                        ui.end_row();
+
+                        for (state, row) in matrix.states.iter().zip(&matrix.cells) {
+                            ui.strong(state);
+                            for (event, cell) in matrix.events.iter().zip(row) {
+                                let selected = matches!(
+                                    &self.highlighted_transitions,
+                                    Some((name, ids)) if *name == fsm.name && !cell.transitions.is_empty() && *ids == cell_ids(cell)
+                                );
+                                let button = egui::Button::new(
+                                    egui::RichText::new(cell.handling.as_str()).color(egui::Color32::BLACK),
+                                )
+                                .fill(event_handling_color(cell.handling))
+                                .selected(selected);
+                                let response = ui.add(button).on_hover_text(format!("{} / {}", state, event));
+                                if response.clicked() && !cell.transitions.is_empty() {
+                                    clicked = Some(cell_ids(cell));
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+
+                if let Some(ids) = clicked {
+                    let already = matches!(&self.highlighted_transitions, Some((_, current)) if *current == ids);
+                    self.highlighted_transitions = (!already).then(|| (fsm.name.clone(), ids));
+                }
+            });
+        self.show_event_matrix = open;
+    }
+
+    /// Popup navigation keys, taken before the editor sees them; returns an accepted item
+    fn completion_keys(&mut self, ui: &mut egui::Ui) -> Option<String> {
+        if !self.completion.is_open() {
+            return None;
+        }
+        let mut accepted = None;
+        ui.input_mut(|i| {
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown) {
+                self.completion.select_next();
+            }
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp) {
+                self.completion.select_previous();
+            }
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::Tab) {
+                accepted = self.completion.selected_item().map(str::to_string);
+            }
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::Escape) {
+                self.completion.close();
+            }
+        });
+        accepted
+    }
+
+    /// Replace the word before the cursor with `item`; returns whether the text changed
+    fn accept_completion(&mut self, ctx: &egui::Context, output: &egui::text_edit::TextEditOutput, item: &str) -> bool {
+        self.completion.close();
+        let Some(index) = output.cursor_range.map(|c| c.primary.ccursor.index) else {
+            return false;
+        };
+        let text = self.editor_text_mut();
+        let end = char_to_byte_index(text, index);
+        let start = end - completion::word_before_cursor(&text[..end]).len();
+        text.replace_range(start..end, item);
+
+        let cursor = text[..start].chars().count() + item.chars().count();
+        let mut state = output.state.clone();
+        state
+            .cursor
+            .set_char_range(Some(egui::text::CCursorRange::one(egui::text::CCursor::new(cursor))));
+        state.store(ctx, output.response.id);
+        true
+    }
+
+    /// Recompute suggestions after typing, close the popup when the cursor moves away, and draw it
+    fn update_completion(&mut self, ctx: &egui::Context, output: &egui::text_edit::TextEditOutput) {
+        let cursor = output
+            .cursor_range
+            .filter(|_| output.response.has_focus())
+            .map(|c| c.primary.ccursor.index);
+        let Some(index) = cursor else {
+            self.completion.close();
+            return;
+        };
+        if output.response.changed() {
+            let text = self.editor_text();
+            let before = &text[..char_to_byte_index(text, index)];
+            let items = self
+                .fsms
+                .get(self.selected_fsm)
+                .map(|fsm| completion::suggest(before, fsm))
+                .unwrap_or_default();
+            self.completion.open(items, index);
+        } else if self.completion.cursor != Some(index) {
+            self.completion.close();
+        }
+        if !self.completion.is_open() {
+            return;
+        }
+
+        const VISIBLE: usize = 8;
+        let pos = output.galley_pos + output.galley.pos_from_ccursor(egui::text::CCursor::new(index)).left_bottom().to_vec2();
+        let first = self.completion.selected.saturating_sub(VISIBLE - 1);
+        egui::Area::new(egui::Id::new("dsl_completion"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(pos)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    for (i, item) in self.completion.items.iter().enumerate().skip(first).take(VISIBLE) {
+                        ui.add(egui::SelectableLabel::new(
+                            i == self.completion.selected,
+                            egui::RichText::new(item).monospace(),
+                        ));
+                    }
+                    if self.completion.items.len() > VISIBLE {
+                        ui.weak(format!("{}/{}", self.completion.selected + 1, self.completion.items.len()));
+                    }
+                });
+            });
+    }
+
+    /// Render the selected FSM's full diagram offscreen
+    fn render_diagram_image(&self) -> Option<egui::ColorImage> {
+        let fsm = self.fsms.get(self.selected_fsm)?;
+        let layout = self.layout.as_ref().filter(|l| l.fsm_name == fsm.name)?;
+        let content = DiagramContent {
+            fsm,
+            layout,
+            positions: &self.state_positions,
+            font_size: self.layout_config.font_size,
+            label_font_size: self.layout_config.edge_label_font_size,
+            state_box: self.layout_config.state_box,
+            overlay: SimOverlay::default(),
+        };
+        Some(diagram_image::render_diagram(&content, &self.theme, self.export_scale.factor()))
+    }
+
+    /// Transitions highlighted from the event matrix, if they belong to `layout`'s FSM
+    fn highlighted_in(&self, layout: &LayoutedDiagram) -> &[fsm::TransitionId] {
+        match &self.highlighted_transitions {
+            Some((fsm_name, ids)) if *fsm_name == layout.fsm_name => ids,
+            _ => &[],
+        }
+    }
+
+    fn copy_diagram_image(&mut self) {
+        let Some(image) = self.render_diagram_image() else {
+            return;
+        };
+        if self.clipboard.is_none() {
+            match arboard::Clipboard::new() {
+                Ok(clipboard) => self.clipboard = Some(clipboard),
+                Err(e) => {
+                    self.push_error(Category::Export, format!("Clipboard unavailable: {e}"));
+                    return;
+                }
+            }
+        }
+        if let Some(clipboard) = self.clipboard.as_mut() {
+            if let Err(e) = diagram_image::copy_to_clipboard(clipboard, &image) {
+                self.push_error(Category::Export, format!("Could not copy diagram: {e}"));
+            }
+        }
+    }
+
+    fn save_diagram_png(&mut self) {
+        let Some(image) = self.render_diagram_image() else {
+            return;
+        };
+        let file_name = format!("{}.png", self.fsms[self.selected_fsm].file_stem());
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("PNG", &["png"])
+            .set_file_name(file_name)
+            .save_file()
+        {
+            let result = diagram_image::encode_png(&image)
+                .and_then(|png| std::fs::write(&path, png).map_err(|e| e.to_string()));
+            if let Err(e) = result {
+                self.push_error(Category::Export, format!("Could not save '{}': {}", path.display(), e));
+            }
+        }
+    }
+
+    /// Write every FSM's diagram, source, code for the current target, diagnostics and
+    /// statistics into one HTML file
+    fn export_report(&mut self, ctx: &egui::Context) {
+        let title = self.current_file.as_ref().and_then(|p| p.file_stem()).map_or("fsm".to_string(), |s| s.to_string_lossy().into_owned());
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("HTML", &["html", "htm"])
+            .set_file_name(format!("{title}_report.html"))
+            .save_file()
+        else {
+            return;
+        };
+
+        // Lay out each FSM in turn; the canvas keeps its own layout
+        let saved = (self.layout.take(), std::mem::take(&mut self.state_positions));
+        let fsms = self.fsms.clone();
+        let mut svgs = Vec::new();
+        for fsm in &fsms {
+            if self.compute_layout_with_dagre(ctx, fsm).is_err() {
+                self.apply_fallback_layout(ctx, fsm);
+            }
+            svgs.push(self.layout.as_ref().map(|layout| {
+                let content = DiagramContent {
+                    fsm,
+                    layout,
+                    positions: &self.state_positions,
+                    font_size: self.layout_config.font_size,
+                    label_font_size: self.layout_config.edge_label_font_size,
+                    state_box: self.layout_config.state_box,
+                    overlay: SimOverlay::default(),
+                };
+                diagram_image::render_diagram_svg(&content, &self.theme)
+            }));
+        }
+        (self.layout, self.state_positions) = saved;
+
+        let sections: Vec<ReportSection> = fsms
+            .iter()
+            .zip(svgs)
+            .enumerate()
+            .map(|(i, (fsm, svg))| {
+                let source = self.source_of(i).ok().map(|(source, ..)| source);
+                let block = self.fsm_sources.blocks.get(i).filter(|_| self.workspace.is_none() && source.is_some());
+                let code = match try_generate_rust_code_with_options(fsm, self.codegen_target, &self.codegen_options) {
+                    Ok(code) => code,
+                    Err(errors) => failure_report(&fsm.name, &errors.iter().map(|e| e.to_string()).collect::<Vec<_>>()),
+                };
+                ReportSection {
+                    fsm,
+                    source: block.cloned().unwrap_or_else(|| format_fsm(fsm)),
+                    svg,
+                    code,
+                    diagnostics: diagnostics(source.as_deref().unwrap_or_default(), fsm),
+                }
+            })
+            .collect();
+        let target = match self.codegen_target {
+            CodegenTarget::Standard => "Rust, std",
+            CodegenTarget::Embassy => "Rust, Embassy",
+            CodegenTarget::Rtic => "Rust, RTIC",
+            CodegenTarget::C => "C",
+        };
+        let html = html_report(&format!("{title} report"), target, &sections);
+        if let Err(e) = std::fs::write(&path, html) {
+            self.push_error(Category::Export, format!("Could not write '{}': {}", path.display(), e));
+        }
+    }
+}
+
+/// Dropdown over the action orders; whether the choice changed
+fn action_order_combo(ui: &mut egui::Ui, id: &str, order: &mut ActionOrder) -> bool {
+    let before = *order;
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(order.label())
+        .show_ui(ui, |ui| {
+            for option in ActionOrder::ALL {
+                ui.selectable_value(order, option, option.label());
+            }
+        })
+        .response
+        .on_hover_text("When a transition's own actions run relative to the exit actions of the state it leaves");
+    *order != before
+}
+
+/// Byte offset of a char index (egui cursors count chars)
+fn char_to_byte_index(text: &str, char_index: usize) -> usize {
+    text.char_indices().nth(char_index).map_or(text.len(), |(i, _)| i)
+}
+
+/// Placeholder emitted instead of code that would not compile
+impl eframe::App for OxidateApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(SETTINGS_KEY, self.settings.to_json());
+        storage.set_string(PROFILES_KEY, self.guard_profiles.to_json());
+        storage.set_string(TUTORIAL_KEY, self.tutorial.record.to_json());
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // egui's Ctrl +/- zoom changes the UI scale too
+        self.settings.ui_scale = ctx.zoom_factor();
+        self.spotlights.clear();
+
+        // Closing the window with unsaved changes asks first
+        if ctx.input(|i| i.viewport().close_requested()) && !self.close_confirmed && self.is_dirty() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.unsaved_prompt = Some(FileAction::Quit);
+        }
+
+        // File shortcuts
+        let command = |key| egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, key);
+        if ctx.input_mut(|i| i.consume_shortcut(&command(egui::Key::S))) {
+            self.save_current();
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&command(egui::Key::O))) {
+            self.request_file_action(ctx, FileAction::Open);
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&command(egui::Key::N))) {
+            self.show_new_fsm_dialog = true;
+            self.new_fsm_name = "MyStateMachine".to_string();
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&command(egui::Key::R))) {
+            self.parse_editor();
+        }
+
+        if let Some((code, receiver)) = &self.compile_check {
+            match receiver.try_recv() {
+                Ok(result) => {
+                    if let Err(ref e) = result {
+                        self.console.push(Category::Codegen, Severity::Error, format!("cargo check failed: {e}"));
+                    }
+                    self.compile_report = Some((code.clone(), result));
+                    self.compile_check = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => ctx.request_repaint_after(Duration::from_millis(100)),
+                Err(mpsc::TryRecvError::Disconnected) => self.compile_check = None,
+            }
+        }
+
         // Top menu bar
         egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("File", |ui| {
-                    if ui.button("➕ New FSM...").clicked() {
+                    let shortcut = |ui: &egui::Ui, key| ui.ctx().format_shortcut(&egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, key));
+                    if ui.add(egui::Button::new("➕ New FSM...").shortcut_text(shortcut(ui, egui::Key::N))).clicked() {
                         self.show_new_fsm_dialog = true;
                         self.new_fsm_name = "MyStateMachine".to_string();
                         ui.close_menu();
                     }
-                    if ui.button("New from Template").clicked() {
-                        self.source_code = DEFAULT_FSM_CODE.to_string();
-                        self.parse_source();
+                    if ui.button("📚 New from Template...").clicked() {
+                        self.show_template_gallery = true;
                         ui.close_menu();
                     }
                     ui.separator();
-                    if ui.button("📂 Open...").clicked() {
-                        if let Some(path) = rfd::FileDialog::new()
-                            .add_filter("FSM", &["fsm", "txt"])
-                            .pick_file()
-                        {
-                            if let Ok(content) = std::fs::read_to_string(&path) {
-                                self.source_code = content;
-                                self.parse_source();
+                    if ui.add(egui::Button::new("📂 Open...").shortcut_text(shortcut(ui, egui::Key::O))).clicked() {
+                        self.request_file_action(ctx, FileAction::Open);
+                        ui.close_menu();
+                    }
+                    if ui.button("🗂 Open Project...").clicked() {
+                        self.request_file_action(ctx, FileAction::OpenProject);
+                        ui.close_menu();
+                    }
+                    ui.menu_button("📥 Import", |ui| {
+                        if ui.button("SCXML...").clicked() {
+                            self.request_file_action(ctx, FileAction::ImportScxml);
+                            ui.close_menu();
+                        }
+                        let events = ui.add_enabled(!self.fsms.is_empty(), egui::Button::new("Events from header..."));
+                        if events.on_hover_text("Declare the enumerators of a C or Rust enum in the selected FSM").clicked() {
+                            if let Some(path) = rfd::FileDialog::new().add_filter("C header or Rust", &["h", "hpp", "rs"]).pick_file() {
+                                self.import_events_file(&path);
                             }
+                            ui.close_menu();
+                        }
+                    });
+                    if let Some(ref workspace) = self.workspace {
+                        let label = workspace.selected_member().label(workspace.project.root());
+                        if ui.add(egui::Button::new(format!("💾 Save {}", label)).shortcut_text(shortcut(ui, egui::Key::S))).clicked() {
+                            self.save_current();
+                            ui.close_menu();
+                        }
+                        if ui.button("💾 Save All").clicked() {
+                            self.save_all_changes();
+                            ui.close_menu();
                         }
-                        ui.close_menu();
-                    }
-                    if ui.button("💾 Save...").clicked() {
-                        if let Some(path) = rfd::FileDialog::new()
-                            .add_filter("FSM", &["fsm"])
-                            .save_file()
-                        {
-                            let _ = std::fs::write(&path, &self.source_code);
+                    } else {
+                        if ui.add(egui::Button::new("💾 Save").shortcut_text(shortcut(ui, egui::Key::S))).clicked() {
+                            self.save_current();
+                            ui.close_menu();
+                        }
+                        if ui.button("💾 Save As...").clicked() {
+                            self.save_as();
+                            ui.close_menu();
                         }
-                        ui.close_menu();
                     }
                     ui.separator();
                     ui.menu_button("📤 Export Code", |ui| {
@@ -1313,16 +4115,62 @@ impl eframe::App for OxidateApp {
                                 .add_filter("Rust", &["rs"])
                                 .save_file()
                             {
-                                let _ = std::fs::write(&path, &self.generated_code);
+                                if let Err(e) = std::fs::write(&path, &self.generated_code) {
+                                    self.console.push(Category::Export, Severity::Error, format!("Could not write '{}': {}", path.display(), e));
+                                }
+                            }
+                            ui.close_menu();
+                        }
+                        let selected = self.fsms.get(self.selected_fsm);
+                        if ui.add_enabled(selected.is_some(), egui::Button::new("🕸 Export DOT...")).clicked() {
+                            if let Some(fsm) = selected {
+                                if let Some(path) = rfd::FileDialog::new()
+                                    .add_filter("Graphviz", &["dot", "gv"])
+                                    .set_file_name(format!("{}.dot", fsm.file_stem()))
+                                    .save_file()
+                                {
+                                    let dot = generate_dot_with_direction(fsm, self.layout_config.direction.into());
+                                    if let Err(e) = std::fs::write(&path, dot) {
+                                        self.console.push(Category::Export, Severity::Error, format!("Could not write '{}': {}", path.display(), e));
+                                    }
+                                }
+                            }
+                            ui.close_menu();
+                        }
+                        if ui.add_enabled(selected.is_some(), egui::Button::new("🌱 Export PlantUML...")).clicked() {
+                            if let Some(fsm) = selected {
+                                if let Some(path) = rfd::FileDialog::new()
+                                    .add_filter("PlantUML", &["puml", "plantuml"])
+                                    .set_file_name(format!("{}.puml", fsm.file_stem()))
+                                    .save_file()
+                                {
+                                    if let Err(e) = std::fs::write(&path, generate_plantuml(fsm)) {
+                                        self.console.push(Category::Export, Severity::Error, format!("Could not write '{}': {}", path.display(), e));
+                                    }
+                                }
                             }
                             ui.close_menu();
                         }
+                        if ui.add_enabled(!self.fsms.is_empty(), egui::Button::new("📑 Export Report...")).clicked() {
+                            self.export_report(ctx);
+                            ui.close_menu();
+                        }
                         if ui.button("📁 Export All FSMs to Folder...").clicked() {
                             if let Some(folder) = rfd::FileDialog::new().pick_folder() {
                                 self.export_all_fsms_to_folder(&folder);
                             }
                             ui.close_menu();
                         }
+                        if let Some(output) = self.workspace.as_ref().map(|w| w.project.output_dir()) {
+                            if ui
+                                .button("📦 Export Project")
+                                .on_hover_text(format!("Write every member's FSMs to {}", output.join("autogen").display()))
+                                .clicked()
+                            {
+                                self.export_all_fsms_to_folder(&output);
+                                ui.close_menu();
+                            }
+                        }
                     });
                     ui.separator();
                     if ui.button("Quit").clicked() {
@@ -1338,30 +4186,89 @@ impl eframe::App for OxidateApp {
                         ui.close_menu();
                     }
                     ui.separator();
+                    if ui.checkbox(&mut self.show_event_matrix, "Event Matrix").clicked() {
+                        ui.close_menu();
+                    }
+                    ui.checkbox(&mut self.auto_parse, "Auto-parse while typing");
+                    ui.checkbox(&mut self.show_minimap, "Minimap");
+                    let split_before = self.split.enabled;
+                    ui.checkbox(&mut self.split.enabled, "Split Diagram")
+                        .on_hover_text("Show a second FSM beside the selected one, with its own zoom and pan");
+                    self.split.fit_requested |= self.split.enabled && !split_before;
+                    // The simulator moves back to the main canvas with the right viewport
+                    if split_before && !self.split.enabled && self.split.sim_pane == Pane::Right {
+                        self.split.sim_pane = Pane::Left;
+                        let previous = self.split.fsm_index(&self.fsms, self.selected_fsm).map(|i| self.fsms[i].clone());
+                        self.reattach_sim(previous.as_ref());
+                    }
+                    ui.checkbox(&mut self.animate_layout, "Animate layout changes");
+                    ui.add_enabled(
+                        self.animate_layout,
+                        egui::DragValue::new(&mut self.layout_anim_duration_ms)
+                            .range(50.0..=2000.0)
+                            .speed(10.0)
+                            .prefix("duration ")
+                            .suffix(" ms"),
+                    );
+                    ui.separator();
                     if ui.button("Reset Zoom").clicked() {
-                        self.zoom = 1.0;
-                        self.pan_offset = egui::Vec2::ZERO;
+                        self.view = CanvasView::default();
+                        ui.close_menu();
+                    }
+                    if ui.button("Zoom to Fit (F)").clicked() {
+                        self.fit_requested = true;
+                        ui.close_menu();
+                    }
+                });
+
+                ui.menu_button("Refactor", |ui| {
+                    let has_fsm = self.fsms.get(self.selected_fsm).is_some();
+                    if ui.add_enabled(has_fsm, egui::Button::new("Rename state…")).clicked() {
+                        self.open_rename(RenameKind::State);
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(has_fsm, egui::Button::new("Rename event…")).clicked() {
+                        self.open_rename(RenameKind::Event);
                         ui.close_menu();
                     }
                 });
 
                 ui.menu_button("Examples", |ui| {
                     if ui.button("Traffic Light").clicked() {
-                        self.source_code = TRAFFIC_LIGHT_EXAMPLE.to_string();
-                        self.parse_source();
+                        self.request_file_action(ctx, FileAction::Load(TRAFFIC_LIGHT_EXAMPLE));
                         ui.close_menu();
                     }
                     if ui.button("Door Lock").clicked() {
-                        self.source_code = DOOR_LOCK_EXAMPLE.to_string();
-                        self.parse_source();
+                        self.request_file_action(ctx, FileAction::Load(DOOR_LOCK_EXAMPLE));
                         ui.close_menu();
                     }
                     if ui.button("Vending Machine").clicked() {
-                        self.source_code = VENDING_MACHINE_EXAMPLE.to_string();
-                        self.parse_source();
+                        self.request_file_action(ctx, FileAction::Load(VENDING_MACHINE_EXAMPLE));
+                        ui.close_menu();
+                    }
+                });
+
+                ui.menu_button("Help", |ui| {
+                    if ui.button("📖 DSL Reference").clicked() {
+                        self.show_dsl_reference = true;
+                        ui.close_menu();
+                    }
+                    if ui.button("🎓 Tutorial").on_hover_text("Build, generate and simulate a first FSM step by step").clicked() {
+                        self.start_tutorial(ctx);
                         ui.close_menu();
                     }
                 });
+
+                if ui.button("⚙").on_hover_text("Settings: UI scale and font sizes").clicked() {
+                    self.show_settings = !self.show_settings;
+                }
+                let badge = match self.console.unseen {
+                    0 => egui::RichText::new("🗒"),
+                    unseen => egui::RichText::new(format!("🗒 {unseen}")).color(egui::Color32::WHITE).background_color(egui::Color32::DARK_RED),
+                };
+                if ui.button(badge).on_hover_text("Error console: recent errors and warnings").clicked() {
+                    self.console.open = !self.console.open;
+                }
             });
         });
 
@@ -1393,7 +4300,7 @@ impl eframe::App for OxidateApp {
                         if ui.button("✓ Create New (Replace)").clicked() {
                             if !self.new_fsm_name.is_empty() {
                                 let names = self.new_fsm_name.clone();
-                                self.create_new_fsms(&names);
+                                self.request_file_action(ctx, FileAction::CreateFsms(names));
                                 self.show_new_fsm_dialog = false;
                             }
                         }
@@ -1416,29 +4323,98 @@ impl eframe::App for OxidateApp {
                             self.show_new_fsm_dialog = false;
                         }
                     });
+                    ui.separator();
+                    if ui.button("📚 Start from Template...").on_hover_text("Pick a ready-made FSM instead of the blank skeleton").clicked() {
+                        self.show_new_fsm_dialog = false;
+                        self.show_template_gallery = true;
+                    }
                 });
         }
 
+        if self.show_template_gallery {
+            self.show_template_gallery_window(ctx);
+        }
+
+        // Event matrix window
+        if self.show_event_matrix {
+            self.show_event_matrix_window(ctx);
+        }
+
+        if self.show_settings {
+            self.show_settings_window(ctx);
+        }
+
+        self.show_label_edit_window(ctx);
+        self.show_rename_window(ctx);
+        self.show_new_transition_window(ctx);
+        self.show_new_state_window(ctx);
+        self.show_selection_window(ctx);
+        if self.show_dsl_reference {
+            self.show_dsl_reference_window(ctx);
+        }
+        self.show_export_summary_window(ctx);
+        self.show_unsaved_prompt(ctx);
+        self.update_window_title(ctx);
+
+        // Debounced auto-parse: wait until typing pauses
+        if let Some(last_edit) = self.last_edit {
+            let idle = last_edit.elapsed();
+            if !self.auto_parse {
+                self.last_edit = None;
+            } else if idle >= AUTO_PARSE_DELAY {
+                self.auto_parse();
+            } else {
+                ctx.request_repaint_after(AUTO_PARSE_DELAY - idle);
+            }
+        }
+
         // Engine-driven layout recomputation (FSM → Graph → Dagre → Renderer)
         if self.layout_dirty {
             if let Some(fsm) = self.fsms.get(self.selected_fsm).cloned() {
+                let previous_layout = self.layout.clone();
+                let previous_positions = self.state_positions.clone();
                 match self.compute_layout_with_dagre(ctx, &fsm) {
                     Ok(()) => {
-                        // Keep parse errors (if any) intact; only clear layout-related errors.
-                        if let Some(msg) = &self.error_message {
-                            if msg.starts_with("Layout error:") {
-                                self.error_message = None;
-                            }
-                        }
+                        self.layout_fingerprint = Some(diagram::layout_fingerprint(&fsm, &self.layout_config.state_box));
+                        self.layout_quality = self.measure_layout_quality(ctx);
+                        self.start_layout_animation(previous_layout, previous_positions);
                     }
                     Err(e) => {
-                        self.error_message = Some(format!("Layout error: {e}"));
-                        self.layout = None;
+                        self.push_warning(Category::Layout, format!("{e}\n\nShowing a simple built-in layout instead."));
+                        self.apply_fallback_layout(ctx, &fsm);
+                        self.layout_fingerprint = Some(diagram::layout_fingerprint(&fsm, &self.layout_config.state_box));
+                        self.layout_quality = self.measure_layout_quality(ctx);
+                        self.start_layout_animation(previous_layout, previous_positions);
                     }
                 }
             }
             self.layout_dirty = false;
         }
+        self.layout_split_pane(ctx);
+
+        // State boxes and text measured in earlier frames stay valid until the FSM, zoom bucket, fonts or theme change
+        if let Some(fsm) = self.fsms.get(self.selected_fsm) {
+            let key = RenderKey::new(
+                self.layout_fingerprint.unwrap_or_default(),
+                self.view.zoom,
+                self.layout_config.font_size,
+                ctx.pixels_per_point(),
+                &self.theme,
+            );
+            self.render_cache.prepare(ctx, key, fsm, &self.layout_config.state_box);
+        }
+        if let Some(index) = self.split.enabled.then(|| self.split.fsm_index(&self.fsms, self.selected_fsm)).flatten() {
+            let key = RenderKey::new(
+                self.split.fingerprint.unwrap_or_default(),
+                self.split.view.zoom,
+                self.layout_config.font_size,
+                ctx.pixels_per_point(),
+                &self.theme,
+            );
+            self.split.render_cache.prepare(ctx, key, &self.fsms[index], &self.layout_config.state_box);
+        }
+
+        self.show_error_console(ctx);
 
         // Left panel: Code editor
         if self.show_code_panel {
@@ -1449,27 +4425,69 @@ impl eframe::App for OxidateApp {
                     ui.heading("FSM Definition");
                     
                     ui.horizontal(|ui| {
-                        if ui.button("▶ Parse & Visualize").clicked() {
-                            // Before parsing, update source_code from current fsm_source
-                            if self.selected_fsm < self.fsm_sources.len() {
-                                self.rebuild_source_code();
-                            }
-                            self.parse_source();
+                        let shortcut = ui.ctx().format_shortcut(&egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::R));
+                        let mut parse = egui::Button::new("▶ Parse & Visualize");
+                        if self.diagram_is_stale() {
+                            parse = parse.fill(ui.visuals().warn_fg_color.gamma_multiply(0.35));
+                        }
+                        let parse = ui.add(parse).on_hover_text(format!("Parse the editor and redraw the diagram ({shortcut})"));
+                        self.spotlights.insert(Spotlight::ParseButton, parse.rect);
+                        if parse.clicked() {
+                            self.parse_editor();
                         }
                         
                         if ui.button("➕ Add FSM").clicked() {
                             self.show_new_fsm_dialog = true;
                             self.new_fsm_name = "NewMachine".to_string();
                         }
+
+                        let analyze = ui
+                            .add_enabled(self.fsms.get(self.selected_fsm).is_some(), egui::Button::new("🔬 Analyze"))
+                            .on_hover_text("Run every event in every reachable state, with each guard both passing and failing, and list transitions, states and actions that never come up");
+                        if analyze.clicked() {
+                            let fsm = &self.fsms[self.selected_fsm];
+                            let exploration = fsm::explore(fsm, self.analysis_depth);
+                            self.analysis = Some((fsm.name.clone(), exploration.messages(fsm)));
+                        }
+                        ui.add(egui::DragValue::new(&mut self.analysis_depth).range(1..=64).prefix("depth "))
+                            .on_hover_text("Events per path at most");
                     });
                     
-                    // FSM tabs
-                    if !self.fsm_sources.is_empty() {
+                    // Project tabs: one per member file
+                    if let Some(ref workspace) = self.workspace {
+                        ui.separator();
+                        ui.label(format!("🗂 {}", workspace.project.name()));
+                        let root = workspace.project.root();
+                        let mut new_selection: Option<usize> = None;
+                        ui.horizontal_wrapped(|ui| {
+                            for (i, member) in workspace.members.iter().enumerate() {
+                                let mut label = member.label(root);
+                                if member.is_dirty() {
+                                    label.push_str(" •");
+                                }
+                                let mut text = egui::RichText::new(label);
+                                if member.error.is_some() {
+                                    text = text.color(egui::Color32::RED);
+                                }
+                                if ui.selectable_label(i == workspace.selected, text).clicked() {
+                                    new_selection = Some(i);
+                                }
+                            }
+                        });
+                        if let Some(i) = new_selection.filter(|&i| i != workspace.selected) {
+                            self.completion.close();
+                            if let Some(ref mut workspace) = self.workspace {
+                                workspace.selected = i;
+                            }
+                            self.show_workspace();
+                        }
+                    } else if !self.fsm_sources.blocks.is_empty() {
+                        // FSM tabs
                         ui.separator();
                         let mut new_selection: Option<usize> = None;
                         
                         // Collect names first to avoid borrow issues
-                        let tab_names: Vec<String> = (0..self.fsm_sources.len())
+                        let tab_names: Vec<String> = (0..self.fsm_sources.blocks.len())
                             .map(|i| {
                                 self.fsms.get(i)
                                     .map(|f| f.name.clone())
@@ -1491,9 +4509,7 @@ impl eframe::App for OxidateApp {
                                 // Save current edit before switching
                                 // Update source_code from all fsm_sources
                                 self.rebuild_source_code();
-                                self.selected_fsm = i;
-                                self.mark_layout_dirty();
-                                self.regenerate_code();
+                                self.select_fsm(i);
                             }
                         }
                     }
@@ -1501,47 +4517,119 @@ impl eframe::App for OxidateApp {
                     ui.separator();
 
                     // Error display
-                    if let Some(ref error) = self.error_message {
+                    if let Some(ref error) = self.parse_error {
                         ui.colored_label(egui::Color32::RED, format!("❌ {}", error));
                         ui.separator();
                     }
+                    let mut quick_fix = None;
+                    if let Some(fsm) = self.fsms.get(self.selected_fsm) {
+                        // Fixes edit the buffer: none while it is ahead of the parsed FSMs or
+                        // for FSMs included from elsewhere
+                        let current = self.last_edit.is_none() && self.parse_error.is_none();
+                        let found = match self.source_of(self.selected_fsm) {
+                            Ok((source, ..)) if current => diagnostics(&source, fsm),
+                            _ => diagnostics("", fsm).into_iter().map(|d| Diagnostic { fixes: Vec::new(), ..d }).collect(),
+                        };
+                        for diagnostic in &found {
+                            let (color, icon) = match diagnostic.severity {
+                                DiagnosticSeverity::Error => (egui::Color32::RED, "❌"),
+                                DiagnosticSeverity::Warning => (egui::Color32::from_rgb(230, 190, 100), "⚠"),
+                                DiagnosticSeverity::Info => (egui::Color32::LIGHT_BLUE, "ℹ"),
+                            };
+                            ui.horizontal_wrapped(|ui| {
+                                ui.colored_label(color, format!("{icon} {}", diagnostic.message));
+                                for fix in &diagnostic.fixes {
+                                    if ui.small_button("💡").on_hover_text(&fix.title).clicked() {
+                                        quick_fix = Some(fix.clone());
+                                    }
+                                }
+                            });
+                        }
+                        let analysis = self.analysis.as_ref().filter(|(name, _)| *name == fsm.name).map(|(_, findings)| findings);
+                        if let Some(findings) = analysis {
+                            ui.label("🔬 Analysis (guards taken as free booleans, an over-approximation):")
+                                .on_hover_text("Every guard may pass or fail, so what is listed can never happen; what is not listed may still be impossible");
+                            if findings.is_empty() {
+                                ui.colored_label(egui::Color32::LIGHT_GREEN, "✔ Every transition, state and action comes up");
+                            }
+                            for finding in findings {
+                                ui.colored_label(egui::Color32::from_rgb(230, 190, 100), format!("⚠ {}", finding));
+                            }
+                        }
+                        if !found.is_empty() || analysis.is_some() {
+                            ui.separator();
+                        }
+                    }
+                    if let Some(fix) = quick_fix {
+                        let result = self.edit_fsm_source(
+                            self.selected_fsm,
+                            |source, _| fix.apply(source).ok_or_else(|| format!("Could not apply '{}': the source changed", fix.title)),
+                            |_, _| Ok(()),
+                        );
+                        if let Err(error) = result {
+                            self.push_error(Category::Edit, error);
+                        }
+                    }
 
-                    // Code editor - show only selected FSM
-                    if self.selected_fsm < self.fsm_sources.len() {
+                    // Code editor - show only the selected member file or FSM
+                    if let Some(ref workspace) = self.workspace {
+                        let member = workspace.selected_member();
+                        ui.horizontal(|ui| {
+                            ui.colored_label(egui::Color32::LIGHT_BLUE, "📝");
+                            ui.label(member.path.display().to_string());
+                        });
+                    }
+                    if self.workspace.is_some() || self.selected_fsm < self.fsm_sources.blocks.len() {
                         // Show file indicator
-                        if let Some(fsm) = self.fsms.get(self.selected_fsm) {
+                        if let Some(fsm) = self.fsms.get(self.selected_fsm).filter(|_| self.workspace.is_none()) {
                             ui.horizontal(|ui| {
                                 ui.colored_label(egui::Color32::LIGHT_BLUE, "📝");
-                                ui.label(format!("{}.fsm", to_snake_case(&fsm.name)));
-                                ui.colored_label(egui::Color32::GRAY, format!("({}/{})", self.selected_fsm + 1, self.fsm_sources.len()));
+                                ui.label(format!("{}.fsm", fsm.file_stem()));
+                                ui.colored_label(egui::Color32::GRAY, format!("({}/{})", self.selected_fsm + 1, self.fsm_sources.blocks.len()));
                             });
                         }
                         
                         egui::ScrollArea::vertical().show(ui, |ui| {
-                            let response = ui.add(
-                                egui::TextEdit::multiline(&mut self.fsm_sources[self.selected_fsm])
-                                    .font(egui::TextStyle::Monospace)
-                                    .code_editor()
-                                    .desired_width(f32::INFINITY)
-                                    .desired_rows(30)
-                            );
+                            let accepted = self.completion_keys(ui);
+                            let font = egui::FontId::monospace(self.settings.editor_font_size);
+                            let output = egui::TextEdit::multiline(self.editor_text_mut())
+                                .id_salt("dsl_editor")
+                                .font(font)
+                                .code_editor()
+                                .desired_width(f32::INFINITY)
+                                .desired_rows(30)
+                                .show(ui);
+                            self.spotlights.insert(Spotlight::Editor, output.response.rect);
+                            self.reveal_in_editor(ui, &output);
+                            let mut changed = output.response.changed() | self.insert_pending_snippet(ui, &output);
+                            if let Some(item) = accepted {
+                                changed |= self.accept_completion(ui.ctx(), &output, &item);
+                            } else {
+                                self.update_completion(ui.ctx(), &output);
+                            }
                             
-                            // Auto-parse on edit (with delay would be better, but this works)
-                            if response.changed() {
-                                // Update the combined source
-                                self.rebuild_source_code();
+                            if changed {
+                                // Update the combined source; parsed once typing pauses
+                                if self.workspace.is_none() {
+                                    self.rebuild_source_code();
+                                }
+                                self.last_edit = Some(Instant::now());
                             }
                         });
                     } else {
                         // Fallback: edit full source
                         egui::ScrollArea::vertical().show(ui, |ui| {
-                            ui.add(
+                            let response = ui.add(
                                 egui::TextEdit::multiline(&mut self.source_code)
-                                    .font(egui::TextStyle::Monospace)
+                                    .font(egui::FontId::monospace(self.settings.editor_font_size))
                                     .code_editor()
                                     .desired_width(f32::INFINITY)
                                     .desired_rows(30)
                             );
+                            self.spotlights.insert(Spotlight::Editor, response.rect);
+                            if response.changed() {
+                                self.last_edit = Some(Instant::now());
+                            }
                         });
                     }
                 });
@@ -1553,10 +4641,10 @@ impl eframe::App for OxidateApp {
                 .default_width(450.0)
                 .resizable(true)
                 .show(ctx, |ui| {
-                    ui.heading("Generated Rust Code");
+                    ui.heading("Generated Code");
                     
                     // Target selector
-                    ui.horizontal(|ui| {
+                    let target_row = ui.horizontal(|ui| {
                         ui.label("Target:");
                         let prev_target = self.codegen_target;
                         egui::ComboBox::from_id_salt("target_selector")
@@ -1564,16 +4652,166 @@ impl eframe::App for OxidateApp {
                                 CodegenTarget::Standard => "🖥 Standard (std)",
                                 CodegenTarget::Embassy => "🔌 Embassy (async embedded)",
                                 CodegenTarget::Rtic => "⚡ RTIC (interrupt-driven)",
+                                CodegenTarget::C => "🔧 C (header + source)",
                             })
                             .show_ui(ui, |ui| {
                                 ui.selectable_value(&mut self.codegen_target, CodegenTarget::Standard, "🖥 Standard (std)");
                                 ui.selectable_value(&mut self.codegen_target, CodegenTarget::Embassy, "🔌 Embassy (async embedded)");
                                 ui.selectable_value(&mut self.codegen_target, CodegenTarget::Rtic, "⚡ RTIC (interrupt-driven)");
+                                ui.selectable_value(&mut self.codegen_target, CodegenTarget::C, "🔧 C (header + source)");
                             });
                         if self.codegen_target != prev_target {
                             self.regenerate_code();
                         }
                     });
+                    self.spotlights.insert(Spotlight::TargetSelector, target_row.response.rect);
+
+                    let mut load_bindings = false;
+                    egui::CollapsingHeader::new("Codegen options")
+                        .id_salt("codegen_options")
+                        .show(ui, |ui| {
+                            let before = self.codegen_options.clone();
+                            let options = &mut self.codegen_options;
+
+                            ui.checkbox(&mut options.tracing_hook, "on_transition hook")
+                                .on_hover_text("Call an on_transition(from, event, to) trait method after every transition");
+
+                            let mut serde = options.has_serde_derives();
+                            if ui
+                                .checkbox(&mut serde, "serde derives")
+                                .on_hover_text("Derive serde::Serialize/Deserialize on the state and event enums")
+                                .changed()
+                            {
+                                *options = std::mem::take(options).with_serde_derives(serde);
+                            }
+
+                            let mut crate_visible = options.visibility == Visibility::Crate;
+                            if ui.checkbox(&mut crate_visible, "pub(crate) items").changed() {
+                                options.visibility = if crate_visible { Visibility::Crate } else { Visibility::Public };
+                            }
+
+                            ui.checkbox(&mut options.no_std, "#![no_std]")
+                                .on_hover_text("For using the generated file as a crate root");
+                            ui.checkbox(&mut options.repr_u8, "#[repr(u8)] enums")
+                                .on_hover_text("Also numbers the event variants and adds an EVENT_NAMES table to decode them");
+                            ui.checkbox(&mut options.lenient_names, "Lenient names")
+                                .on_hover_text("Sanitize names that are not valid Rust identifiers (turn on LED → turn_on_led)");
+                            ui.checkbox(&mut options.run_entry_on_new, "Entry actions in new()")
+                                .on_hover_text("Run the start and initial entry actions in new() instead of generating start()");
+                            ui.checkbox(&mut options.metrics, "Dispatch metrics")
+                                .on_hover_text("Count state entries, processed events and the queue high watermark behind the fsm-metrics feature");
+                            ui.checkbox(&mut options.process_result, "Rich process() result")
+                                .on_hover_text("process() returns a ProcessResult (Transitioned, InternalHandled, GuardRejected, Ignored) instead of bool");
+                            ui.checkbox(&mut options.shared_events, "Shared events")
+                                .on_hover_text("Folder exports add events.rs with one SharedEvent enum for all FSMs; each FSM gets process_shared() behind the shared-events feature");
+
+                            ui.horizontal(|ui| {
+                                let mut queue = options.event_queue_size.is_some();
+                                if ui.checkbox(&mut queue, "Event queue").changed() {
+                                    options.event_queue_size = queue.then_some(8);
+                                }
+                                if let Some(size) = options.event_queue_size.as_mut() {
+                                    ui.add(egui::DragValue::new(size).range(1..=1024).suffix(" events"));
+                                    egui::ComboBox::from_id_salt("overflow_policy")
+                                        .selected_text(options.overflow_policy.label())
+                                        .show_ui(ui, |ui| {
+                                            for policy in OverflowPolicy::ALL {
+                                                ui.selectable_value(&mut options.overflow_policy, policy, policy.label());
+                                            }
+                                        })
+                                        .response
+                                        .on_hover_text("What post() does when the queue is full");
+                                }
+                            });
+
+                            ui.horizontal(|ui| {
+                                ui.label("Action order:");
+                                action_order_combo(ui, "codegen_action_order", &mut options.action_order);
+                            });
+
+                            ui.horizontal(|ui| {
+                                ui.label("Event order:");
+                                egui::ComboBox::from_id_salt("codegen_event_order")
+                                    .selected_text(options.event_order.label())
+                                    .show_ui(ui, |ui| {
+                                        for order in EventOrder::ALL {
+                                            ui.selectable_value(&mut options.event_order, order, order.label());
+                                        }
+                                    })
+                                    .response
+                                    .on_hover_text("Order of the events no events block declares, after the declared ones");
+                            });
+
+                            ui.horizontal(|ui| {
+                                ui.label("Async actions:");
+                                egui::ComboBox::from_id_salt("codegen_async_actions")
+                                    .selected_text(options.async_actions.label())
+                                    .show_ui(ui, |ui| {
+                                        for mode in AsyncActions::ALL {
+                                            ui.selectable_value(&mut options.async_actions, mode, mode.label());
+                                        }
+                                    })
+                                    .response
+                                    .on_hover_text("Actions the Embassy machine awaits (async fn in the actions trait); other targets need None when the DSL marks actions async");
+                            });
+
+                            ui.label("Doc header:");
+                            let mut header = options.doc_header.clone().unwrap_or_default();
+                            if ui
+                                .add(egui::TextEdit::multiline(&mut header).desired_rows(2).desired_width(f32::INFINITY))
+                                .changed()
+                            {
+                                options.doc_header = (!header.trim().is_empty()).then_some(header);
+                            }
+
+                            ui.label("File header:").on_hover_text(
+                                "Comment lines at the top of every generated file, e.g. an SPDX license header; \
+                                 {fsm_name}, {target} and {tool_version} are filled in",
+                            );
+                            let mut template = options.header_template.clone().unwrap_or_default();
+                            if ui
+                                .add(
+                                    egui::TextEdit::multiline(&mut template)
+                                        .desired_rows(2)
+                                        .desired_width(f32::INFINITY)
+                                        .hint_text("SPDX-License-Identifier: MIT"),
+                                )
+                                .changed()
+                            {
+                                options.header_template = (!template.trim().is_empty()).then_some(template);
+                            }
+
+                            ui.horizontal(|ui| {
+                                ui.label("HAL bindings:");
+                                match self.bindings_path {
+                                    Some(ref path) => {
+                                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                                        ui.label(name).on_hover_text(path.display().to_string());
+                                        if ui.small_button("✖").on_hover_text("Stop generating the bindings struct").clicked() {
+                                            self.codegen_options.bindings = None;
+                                            self.bindings_path = None;
+                                        }
+                                    }
+                                    None => {
+                                        ui.weak("none");
+                                    }
+                                }
+                                if ui
+                                    .button("Load bindings…")
+                                    .on_hover_text("A TOML file mapping actions, guards and getters to HAL code; generates a HalActions struct implementing the trait")
+                                    .clicked()
+                                {
+                                    load_bindings = true;
+                                }
+                            });
+
+                            if self.codegen_options != before {
+                                self.regenerate_code();
+                            }
+                        });
+                    if load_bindings {
+                        self.load_bindings();
+                    }
                     
                     ui.separator();
                     
@@ -1581,15 +4819,35 @@ impl eframe::App for OxidateApp {
                         if ui.button("📋 Copy").clicked() {
                             ui.output_mut(|o| o.copied_text = self.generated_code.clone());
                         }
+                        let c_target = self.codegen_target == CodegenTarget::C;
                         if ui.button("💾 Save...").clicked() {
+                            let (kind, extensions) = if c_target { ("C", &["h", "c"][..]) } else { ("Rust", &["rs"][..]) };
                             if let Some(path) = rfd::FileDialog::new()
-                                .add_filter("Rust", &["rs"])
+                                .add_filter(kind, extensions)
                                 .save_file()
                             {
-                                let _ = std::fs::write(&path, &self.generated_code);
+                                if let Err(e) = std::fs::write(&path, &self.generated_code) {
+                                    self.console.push(Category::Export, Severity::Error, format!("Could not write '{}': {}", path.display(), e));
+                                }
                             }
                         }
+                        let checking = self.compile_check.is_some();
+                        let check = ui.add_enabled(
+                            self.cargo_available && !checking && !c_target,
+                            egui::Button::new(if checking { "⏳ Checking..." } else { "🔍 Check" }),
+                        );
+                        let check = if c_target {
+                            check.on_disabled_hover_text("cargo check only covers the Rust targets")
+                        } else if self.cargo_available {
+                            check.on_hover_text("Run cargo check on the generated code in a scratch crate")
+                        } else {
+                            check.on_disabled_hover_text("cargo was not found on PATH")
+                        };
+                        if check.clicked() {
+                            self.start_compile_check();
+                        }
                     });
+                    self.show_compile_report(ui);
                     
                     // Target info
                     ui.separator();
@@ -1609,6 +4867,12 @@ impl eframe::App for OxidateApp {
                             ui.small("• Zero-cost abstractions");
                             ui.small("• heapless queue");
                         }
+                        CodegenTarget::C => {
+                            ui.colored_label(egui::Color32::LIGHT_BLUE, "🔧 C99 for non-Rust toolchains");
+                            ui.small("• Actions and guards as a function-pointer table");
+                            ui.small("• <fsm>_init() and <fsm>_dispatch()");
+                            ui.small("• Folder export writes the .h/.c pair");
+                        }
                     }
                     
                     ui.separator();
@@ -1634,13 +4898,45 @@ impl eframe::App for OxidateApp {
                         }
                         ui.separator();
                     }
+                    // Description and `meta` entries of the selected FSM
+                    if let Some(fsm) = self.fsms.get(self.selected_fsm) {
+                        if fsm.description.is_some() || !fsm.metadata.is_empty() {
+                            let description = fsm.description.as_deref().unwrap_or("(no description)");
+                            let label = ui.weak(description);
+                            if !fsm.metadata.is_empty() {
+                                let metadata: Vec<String> =
+                                    fsm.metadata.iter().map(|(key, value)| format!("{key} = {value}")).collect();
+                                label.on_hover_text(metadata.join("\n"));
+                            }
+                            ui.separator();
+                        }
+                    }
                     
                     // Regenerate code if tab changed
                     if tab_changed {
                         self.mark_layout_dirty();
-                        if let Some(fsm) = self.fsms.get(self.selected_fsm) {
-                            self.generated_code = generate_rust_code_with_target(fsm, self.codegen_target);
+                        self.regenerate_code();
+                    }
+                    
+                    // Names and collisions that would make the generated code uncompilable
+                    if !self.codegen_errors.is_empty() {
+                        for error in &self.codegen_errors {
+                            ui.colored_label(egui::Color32::RED, format!("❌ {}", error));
+                        }
+                        ui.separator();
+                    }
+                    if !self.binding_warnings.is_empty() {
+                        for warning in &self.binding_warnings {
+                            ui.colored_label(egui::Color32::YELLOW, format!("⚠ {}", warning));
+                        }
+                        ui.separator();
+                    }
+                    if !self.codegen_notes.is_empty() {
+                        ui.label("Sanitized names:");
+                        for note in &self.codegen_notes {
+                            ui.colored_label(egui::Color32::LIGHT_BLUE, format!("ℹ {}", note));
                         }
+                        ui.separator();
                     }
                     
                     if self.generated_code.is_empty() {
@@ -1650,29 +4946,29 @@ impl eframe::App for OxidateApp {
                         if let Some(fsm) = self.fsms.get(self.selected_fsm) {
                             ui.horizontal(|ui| {
                                 ui.colored_label(egui::Color32::LIGHT_GREEN, "📄");
-                                ui.colored_label(egui::Color32::WHITE, format!("{}.rs", to_snake_case(&fsm.name)));
+                                let file = match self.codegen_target {
+                                    CodegenTarget::C => format!("{0}.h + {0}.c", fsm.file_stem()),
+                                    _ => format!("{}.rs", fsm.file_stem()),
+                                };
+                                ui.colored_label(egui::Color32::WHITE, file);
                                 ui.colored_label(egui::Color32::GRAY, format!("({} of {})", self.selected_fsm + 1, self.fsms.len()));
                             });
                         }
                         
                         // Check if the generated code header matches the selected FSM
-                        let expected_header = format!("//! Auto-generated FSM: {}", 
+                        let expected_header = format!("Auto-generated FSM: {}", 
                             self.fsms.get(self.selected_fsm).map(|f| f.name.as_str()).unwrap_or(""));
                         if !self.generated_code.contains(&expected_header) {
                             // Force regenerate if mismatch
-                            if let Some(fsm) = self.fsms.get(self.selected_fsm) {
-                                self.generated_code = generate_rust_code_with_target(fsm, self.codegen_target);
-                            }
+                            self.regenerate_code();
                         }
                         
+                        // Lines with compile check diagnostics are highlighted
+                        let marks = self.compile_marks();
+                        let fsm_name = self.fsms.get(self.selected_fsm).map(|f| f.name.clone()).unwrap_or_default();
+                        let folded = self.code_folds.of(self.codegen_target, &fsm_name);
                         egui::ScrollArea::vertical().show(ui, |ui| {
-                            ui.add(
-                                egui::TextEdit::multiline(&mut self.generated_code.as_str())
-                                    .font(egui::TextStyle::Monospace)
-                                    .code_editor()
-                                    .desired_width(f32::INFINITY)
-                                    .desired_rows(40)
-                            );
+                            code_view::show(ui, &self.generated_code, self.settings.editor_font_size, &marks, folded);
                         });
                     }
                 });
@@ -1683,24 +4979,77 @@ impl eframe::App for OxidateApp {
             ui.heading("State Diagram");
             
             // Toolbar (wrapped so it doesn't disappear when panels are narrow)
+            let right_shown = self.pane_fsm(Pane::Right).is_some();
             ui.horizontal_wrapped(|ui| {
                 let sim_enabled_before = self.sim.enabled;
-                ui.checkbox(&mut self.sim.enabled, "Debug sim");
+                let sim_toggle = ui.checkbox(&mut self.sim.enabled, "Debug sim");
+                self.spotlights.insert(Spotlight::SimToggle, sim_toggle.rect);
+                if self.sim.enabled && !self.bus.enabled && right_shown {
+                    let pane_before = self.split.sim_pane;
+                    ui.label("on").on_hover_text("Viewport whose FSM the simulator runs");
+                    ui.radio_value(&mut self.split.sim_pane, Pane::Left, "left");
+                    ui.radio_value(&mut self.split.sim_pane, Pane::Right, "right");
+                    if self.split.sim_pane != pane_before {
+                        let previous = self.pane_fsm(pane_before).cloned();
+                        self.reattach_sim(previous.as_ref());
+                    }
+                }
+                ui.toggle_value(&mut self.edit_mode, "✏ Edit").on_hover_text(
+                    "Drag from a state to another to add a transition, from the start node to change the initial state, \
+                     or onto empty canvas to add a state (Esc cancels)",
+                );
+                let select_before = self.select_mode;
+                ui.toggle_value(&mut self.select_mode, "⬚ Select").on_hover_text(
+                    "Drag to select the states a rectangle touches (Ctrl: add to the selection). \
+                     Outside this mode too, click a state to select it and Ctrl+click to add or remove one",
+                );
+                // Both modes use the drag
+                match (self.edit_mode, self.select_mode && !select_before) {
+                    (true, true) => self.edit_mode = false,
+                    (true, false) => self.select_mode = false,
+                    _ => {}
+                }
+                if !self.edit_mode {
+                    self.edge_drag = None;
+                }
+                if !self.select_mode {
+                    self.rubber_band = None;
+                }
                 if sim_enabled_before != self.sim.enabled {
                     self.sim.running = false;
                     self.sim.last_frame = None;
                     self.sim.last_fired = None;
+                    self.bus.running = false;
+                }
+                if self.sim.enabled && self.fsms.len() >= 2 {
+                    let toggled = ui
+                        .checkbox(&mut self.bus.enabled, "Multi-FSM")
+                        .on_hover_text("Simulate several FSMs together, connected by an event bus")
+                        .changed();
+                    if toggled && self.bus.enabled && self.bus.machines.is_empty() {
+                        // Start with the shown FSM and the next one
+                        for offset in 0..2 {
+                            let fsm = &self.fsms[(self.selected_fsm + offset) % self.fsms.len()];
+                            self.bus.add_machine(fsm);
+                        }
+                    }
                 }
 
                 ui.separator();
 
-                // Zoom controls
+                // Zoom controls (about the canvas centre)
                 if ui.button("➖").clicked() {
-                    self.zoom = (self.zoom - 0.1).max(0.3);
+                    self.view.zoom_at(egui::Vec2::ZERO, self.view.zoom - 0.1);
                 }
-                ui.label(format!("{:.0}%", self.zoom * 100.0));
+                ui.label(format!("{:.0}%", self.view.zoom * 100.0));
                 if ui.button("➕").clicked() {
-                    self.zoom = (self.zoom + 0.1).min(3.0);
+                    self.view.zoom_at(egui::Vec2::ZERO, self.view.zoom + 0.1);
+                }
+                if ui.button("100%").clicked() {
+                    self.view.zoom_at(egui::Vec2::ZERO, 1.0);
+                }
+                if ui.button("Fit").on_hover_text("Fit the whole diagram (F, or double-click the canvas)").clicked() {
+                    self.fit_requested = true;
                 }
                 ui.separator();
                 
@@ -1716,25 +5065,35 @@ impl eframe::App for OxidateApp {
                 ui.label("Layout:");
                 let mut dir_changed = false;
                 egui::ComboBox::from_id_source("layout_direction")
-                    .selected_text(match self.layout_config.direction {
-                        LayoutDirection::TB => "TB",
-                        LayoutDirection::LR => "LR",
-                    })
+                    .selected_text(self.layout_config.direction.label())
                     .show_ui(ui, |ui| {
-                        dir_changed |= ui
-                            .selectable_value(&mut self.layout_config.direction, LayoutDirection::TB, "TB")
-                            .changed();
-                        dir_changed |= ui
-                            .selectable_value(&mut self.layout_config.direction, LayoutDirection::LR, "LR")
-                            .changed();
+                        for direction in LayoutDirection::ALL {
+                            dir_changed |= ui
+                                .selectable_value(&mut self.layout_config.direction, direction, direction.label())
+                                .changed();
+                        }
                     });
                 if dir_changed {
                     self.mark_layout_dirty();
                 }
+                let mut box_changed = false;
+                ui.menu_button("Display", |ui| {
+                    let state_box = &mut self.settings.state_box;
+                    ui.label("State boxes show");
+                    box_changed |= ui.checkbox(&mut state_box.internal_transitions, "Internal transitions").changed();
+                    box_changed |= ui.checkbox(&mut state_box.entry_exit_actions, "Entry/exit actions").changed();
+                    box_changed |= ui.checkbox(&mut state_box.action_params, "Action parameters").changed();
+                    box_changed |= ui.checkbox(&mut state_box.descriptions, "State descriptions").changed();
+                });
+                if box_changed {
+                    self.apply_settings(ctx);
+                }
             });
 
-            if self.sim.enabled {
-                let fsm_for_sim = self.fsms.get(self.selected_fsm).cloned();
+            if self.sim.enabled && self.bus.enabled {
+                self.draw_bus_panel(ui);
+            } else if self.sim.enabled {
+                let fsm_for_sim = self.pane_fsm(self.split.sim_pane()).cloned();
                 if let Some(fsm) = fsm_for_sim {
                     ui.separator();
                     ui.horizontal(|ui| {
@@ -1745,24 +5104,26 @@ impl eframe::App for OxidateApp {
                             .unwrap_or("<not started>");
                         ui.label(format!("Current: {current}"));
                         if ui.button("Reset").clicked() {
-                            self.sim_reset_to_initial(&fsm);
+                            self.sim.reset_to_initial(&fsm);
                         }
                         if ui.button(if self.sim.running { "Pause" } else { "Run" }).clicked() {
                             self.sim.running = !self.sim.running;
                             self.sim.last_frame = Some(Instant::now());
+                            self.sim.scrub = None;
                         }
                         if ui.button("Step").clicked() {
-                            self.sim_step(&fsm);
+                            self.sim.scrub = None;
+                            self.sim.step(&fsm);
                         }
                         ui.add(egui::Slider::new(&mut self.sim.speed, 0.1..=5.0).text("speed"));
                     });
 
-                    ui.horizontal(|ui| {
+                    let event_row = ui.horizontal(|ui| {
                         ui.label("Event:");
                         ui.text_edit_singleline(&mut self.sim.event_input);
                         if ui.button("Post").clicked() {
                             let ev = self.sim.event_input.trim().to_string();
-                            self.sim_post_event(ev);
+                            self.sim.post_event(ev);
                             self.sim.event_input.clear();
                         }
                         ui.separator();
@@ -1774,33 +5135,173 @@ impl eframe::App for OxidateApp {
                             self.sim.log.clear();
                         }
                     });
+                    self.spotlights.insert(Spotlight::SimEvents, event_row.response.rect);
+
+                    // Event queue, head first; redrawn every frame, so it follows a running simulation
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label(format!("Queue ({}):", self.sim.queued_events.len()));
+                        let last = self.sim.queued_events.len();
+                        ui.add(egui::DragValue::new(&mut self.sim.insert_position).range(0..=last).prefix("at "));
+                        let insert = ui
+                            .add_enabled(!self.sim.event_input.trim().is_empty(), egui::Button::new("Insert"))
+                            .on_hover_text("Queue the event above at this position, e.g. to model preemption");
+                        if insert.clicked() {
+                            let ev = self.sim.event_input.trim().to_string();
+                            self.sim.insert_event(self.sim.insert_position, ev);
+                            self.sim.event_input.clear();
+                        }
+                        if ui.add_enabled(last > 0, egui::Button::new("Clear queue")).clicked() {
+                            self.sim.queued_events.clear();
+                        }
+                    });
+                    if !self.sim.queued_events.is_empty() {
+                        enum QueueEdit {
+                            Remove(usize),
+                            Duplicate(usize),
+                            Move(usize, bool),
+                        }
+                        let mut edit = None;
+                        let last = self.sim.queued_events.len() - 1;
+                        egui::ScrollArea::vertical().id_salt("sim_queue").max_height(120.0).show(ui, |ui| {
+                            for (index, event) in self.sim.queued_events.iter().enumerate() {
+                                ui.horizontal(|ui| {
+                                    let text = egui::RichText::new(format!("{}. {event}", index + 1)).monospace();
+                                    // The head event: will the current state take it, or drop it?
+                                    match (index, self.sim.current_state.as_deref()) {
+                                        (0, Some(current)) => match self.sim.find_transition(&fsm, current, event) {
+                                            Some(firing) => ui
+                                                .label(text.color(egui::Color32::from_rgb(80, 180, 80)))
+                                                .on_hover_text(format!("{current} handles it → {}", firing.to)),
+                                            None => ui
+                                                .label(text.color(egui::Color32::from_rgb(220, 70, 70)))
+                                                .on_hover_text(format!("{current} drops it")),
+                                        },
+                                        _ => ui.label(text),
+                                    };
+                                    if ui.add_enabled(index > 0, egui::Button::new("⏶").small()).on_hover_text("Move up").clicked() {
+                                        edit = Some(QueueEdit::Move(index, true));
+                                    }
+                                    if ui.add_enabled(index < last, egui::Button::new("⏷").small()).on_hover_text("Move down").clicked() {
+                                        edit = Some(QueueEdit::Move(index, false));
+                                    }
+                                    if ui.small_button("⧉").on_hover_text("Duplicate").clicked() {
+                                        edit = Some(QueueEdit::Duplicate(index));
+                                    }
+                                    if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                                        edit = Some(QueueEdit::Remove(index));
+                                    }
+                                });
+                            }
+                        });
+                        match edit {
+                            Some(QueueEdit::Remove(index)) => self.sim.remove_queued(index),
+                            Some(QueueEdit::Duplicate(index)) => self.sim.duplicate_queued(index),
+                            Some(QueueEdit::Move(index, up)) => self.sim.move_queued(index, up),
+                            None => {}
+                        }
+                    }
 
-                    // Per-frame sim update (auto event + stepping).
-                    let now = Instant::now();
-                    let dt_s = if let Some(last) = self.sim.last_frame {
-                        (now - last).as_secs_f32()
-                    } else {
-                        0.0
-                    };
-                    self.sim.last_frame = Some(now);
-
-                    if self.sim.running {
-                        if self.sim.auto_tick {
-                            self.sim.auto_accum_s += dt_s;
-                            while self.sim.auto_accum_s >= self.sim.auto_period_s {
-                                self.sim.auto_accum_s -= self.sim.auto_period_s;
-                                let ev = self.sim.auto_event.trim().to_string();
-                                if !ev.is_empty() {
-                                    self.sim_post_event(ev);
+                    // Timeline scrubber: shows the machine at an earlier step without touching the live run
+                    ui.horizontal(|ui| {
+                        match (self.sim.history.front(), self.sim.history.back()) {
+                            (Some(first), Some(last)) => {
+                                let (first, last) = (first.step, last.step);
+                                let mut step = self.sim.scrub.unwrap_or(last);
+                                let slider = egui::Slider::new(&mut step, first..=last).text("step");
+                                if ui.add_enabled(first < last, slider).changed() {
+                                    self.sim.scrub = Some(step);
+                                }
+                                if let Some(entry) = self.sim.scrubbed() {
+                                    ui.label(format!(
+                                        "{} --{}--> {} at {:.1}s",
+                                        entry.from,
+                                        entry.event,
+                                        entry.to,
+                                        entry.timestamp.as_secs_f32()
+                                    ));
+                                    if ui.button("Live").clicked() {
+                                        self.sim.scrub = None;
+                                    }
                                 }
                             }
+                            _ => {
+                                ui.weak("History: no transitions yet");
+                            }
+                        }
+                        ui.separator();
+                        if ui
+                            .add(egui::DragValue::new(&mut self.sim.history_cap).range(1..=100_000).prefix("keep "))
+                            .on_hover_text("Maximum history length; the oldest steps are dropped")
+                            .changed()
+                        {
+                            self.sim.trim_history();
                         }
-                        // Consume at most one event per frame to keep animation readable.
-                        if !self.sim.queued_events.is_empty() {
-                            self.sim_step(&fsm);
+                        if ui.add_enabled(!self.sim.history.is_empty(), egui::Button::new("Export CSV...")).clicked() {
+                            if let Some(path) = rfd::FileDialog::new()
+                                .add_filter("CSV", &["csv"])
+                                .set_file_name(format!("{}_history.csv", fsm.name))
+                                .save_file()
+                            {
+                                if let Err(e) = std::fs::write(&path, self.sim.history_csv()) {
+                                    self.console.push(Category::Export, Severity::Error, format!("Could not write '{}': {}", path.display(), e));
+                                }
+                            }
                         }
+                    });
+
+                    let guards = fsm::interpreter::guard_expressions(&fsm);
+                    if !guards.is_empty() {
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label("Guards:").on_hover_text("Unchecked guards are false, so [else] branches are taken");
+                            for guard in &guards {
+                                let value = self.sim.guard_values.entry(guard.to_string()).or_default();
+                                ui.checkbox(value, guard);
+                            }
+                        });
+                        self.draw_guard_profiles(ui, &fsm.name, &guards);
+                    }
+
+                    if !fsm.timers.is_empty() {
+                        ui.horizontal_wrapped(|ui| {
+                            ui.checkbox(&mut self.sim.use_fsm_timers, "Use FSM timers").on_hover_text(
+                                "While running, timers started on state entry fire their events after their duration \
+                                 (scaled by the speed slider); leaving the state cancels them",
+                            );
+                            if self.sim.use_fsm_timers {
+                                let mut cancel = None;
+                                for pending in &self.sim.pending_timers {
+                                    let periodic = if pending.period.is_some() { " (periodic)" } else { "" };
+                                    ui.label(format!(
+                                        "⏱ {} → {} in {:.1}s{}",
+                                        pending.timer,
+                                        pending.event,
+                                        pending.remaining.as_secs_f32(),
+                                        periodic
+                                    ));
+                                    if ui.small_button("✖").on_hover_text("Cancel this timer").clicked() {
+                                        cancel = Some(pending.timer.clone());
+                                    }
+                                }
+                                if self.sim.pending_timers.is_empty() {
+                                    ui.weak("no timers pending");
+                                }
+                                if let Some(timer) = cancel {
+                                    self.sim.cancel_timer(&timer);
+                                }
+                            }
+                        });
                     }
 
+                    ui.horizontal(|ui| {
+                        ui.label("Action order:");
+                        if action_order_combo(ui, "sim_action_order", &mut self.codegen_options.action_order) {
+                            self.regenerate_code();
+                        }
+                    });
+
+                    self.sim.action_order = self.codegen_options.action_order;
+                    self.sim.tick(&fsm);
+
                     egui::ScrollArea::vertical()
                         .max_height(80.0)
                         .auto_shrink([false; 2])
@@ -1818,137 +5319,301 @@ impl eframe::App for OxidateApp {
                 changed |= ui.add(egui::DragValue::new(&mut self.layout_config.nodesep).speed(1.0).prefix("nodesep ")).changed();
                 changed |= ui.add(egui::DragValue::new(&mut self.layout_config.ranksep).speed(1.0).prefix("ranksep ")).changed();
                 changed |= ui.add(egui::DragValue::new(&mut self.layout_config.edgesep).speed(1.0).prefix("edgesep ")).changed();
+                changed |= ui
+                    .add(egui::DragValue::new(&mut self.layout_config.max_label_width).speed(2.0).range(60.0..=800.0).prefix("max label "))
+                    .on_hover_text("Wider transition label lines are cut with … (hover the label for the full text)")
+                    .changed();
                 if changed {
                     self.mark_layout_dirty();
                 }
+                if ui
+                    .add_enabled(self.layout.is_some(), egui::Button::new("Optimize"))
+                    .on_hover_text("Try a grid of nodesep/ranksep/edgesep values and keep the layout with the fewest crossings and overlaps, then the shortest edges")
+                    .clicked()
+                {
+                    self.optimize_layout(ctx);
+                }
+                if let Some(quality) = self.layout_quality {
+                    ui.weak(format!("crossings: {}, overlaps: {}", quality.crossings, quality.overlaps))
+                        .on_hover_text(format!("Edge crossings, label boxes overlapping states or labels; total edge length {:.0} px", quality.edge_length));
+                }
+
+                ui.separator();
+                egui::ComboBox::from_id_salt("export_scale")
+                    .selected_text(self.export_scale.label())
+                    .width(48.0)
+                    .show_ui(ui, |ui| {
+                        for scale in ExportScale::ALL {
+                            ui.selectable_value(&mut self.export_scale, scale, scale.label());
+                        }
+                    });
+                if ui.button("📷 Copy diagram as image").clicked() {
+                    self.copy_diagram_image();
+                }
+                if ui.button("Save as PNG…").clicked() {
+                    self.save_diagram_png();
+                }
             });
+
+            // Bulk operations on the selected states
+            let selected = self.fsms.get(self.selected_fsm).map(|fsm| self.selection.of(&fsm.name).to_vec()).unwrap_or_default();
+            if !selected.is_empty() {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(format!("Selected: {}", selected.join(", ")));
+                    if ui.button("🗑 Delete").on_hover_text("Remove them with their transitions, branches and notes (Del)").clicked() {
+                        self.delete_selected_states();
+                    }
+                    if ui.button("Tag…").clicked() {
+                        self.open_selection_dialog(SelectionAction::Tag, selected.clone());
+                    }
+                    if ui.button("Color…").clicked() {
+                        self.open_selection_dialog(SelectionAction::Color, selected.clone());
+                    }
+                    if ui
+                        .button("Extract composite…")
+                        .on_hover_text("Move them and the transitions between them into an FSM of their own, standing in as one state here")
+                        .clicked()
+                    {
+                        self.open_selection_dialog(SelectionAction::Extract, selected.clone());
+                    }
+                    if ui.button("Clear").clicked() {
+                        self.selection.clear();
+                    }
+                });
+            }
             
             ui.separator();
 
             // Drawing area
-            let (response, painter) = ui.allocate_painter(
-                ui.available_size(),
-                egui::Sense::drag(),
-            );
+            // The main canvas, on the left when the split view shows a second FSM beside it
+            let area = ui.available_rect_before_wrap();
+            ui.advance_cursor_after_rect(area);
+            let (rect, right) = match self.split.enabled && !self.fsms.is_empty() {
+                true => {
+                    let (left, right) = split_rect(area);
+                    (left, Some(right))
+                }
+                false => (area, None),
+            };
+            let response = ui.interact(rect, ui.id().with("diagram_canvas"), egui::Sense::click_and_drag());
+            let painter = ui.painter_at(rect);
+            self.spotlights.insert(Spotlight::Diagram, rect);
+
+            // Edit mode: a drag from a state or the start node draws a transition instead of panning
+            if self.edit_mode && response.drag_started() {
+                let origin = ctx.input(|i| i.pointer.press_origin());
+                self.edge_drag = origin
+                    .and_then(|p| self.edge_end_under_pointer(rect, p))
+                    .map(|from| EdgeDrag { from, cancelled: false });
+            }
+            if let Some(ref mut drag) = self.edge_drag {
+                drag.cancelled |= ctx.input(|i| i.key_pressed(egui::Key::Escape));
+            }
+            // Select mode: a drag draws a rubber band instead of panning
+            if self.select_mode && response.drag_started() {
+                self.rubber_band = ctx.input(|i| i.pointer.press_origin());
+            }
+            if response.drag_stopped() {
+                if let (Some(start), Some(end)) = (self.rubber_band.take(), ctx.input(|i| i.pointer.latest_pos())) {
+                    let add = ctx.input(|i| i.modifiers.command);
+                    self.select_in_rect(rect, egui::Rect::from_two_pos(start, end), add);
+                }
+                if let Some(drag) = self.edge_drag.take().filter(|d| !d.cancelled) {
+                    // Released outside the canvas: nothing to add
+                    if let Some(drop) = ctx.input(|i| i.pointer.latest_pos()).filter(|p| rect.contains(*p)) {
+                        let to = self.edge_end_under_pointer(rect, drop);
+                        self.finish_edge_drag(drag.from, to);
+                    }
+                }
+            }
+            let pointer = ctx.input(|i| i.pointer.latest_pos());
+            let over_edge_end = self.edit_mode && response.hovered() && pointer.is_some_and(|p| self.edge_end_under_pointer(rect, p).is_some());
+            if self.edge_drag.is_some() || over_edge_end {
+                ctx.set_cursor_icon(egui::CursorIcon::Crosshair);
+            }
 
             // Handle panning
-            if response.dragged() {
-                self.pan_offset += response.drag_delta();
+            if response.dragged() && self.edge_drag.is_none() && self.rubber_band.is_none() {
+                self.view.pan += response.drag_delta();
+            }
+
+            // Handle zoom with scroll (Ctrl: fine steps), centred on the pointer
+            let (scroll_delta, fine) = ctx.input(|i| (i.raw_scroll_delta, i.modifiers.command));
+            if let Some(pointer) = response.hover_pos().filter(|_| scroll_delta.y != 0.0) {
+                let step = if fine { 0.00025 } else { 0.001 };
+                self.view.zoom_at(pointer - rect.center(), self.view.zoom + scroll_delta.y * step);
             }
 
-            // Handle zoom with scroll
-            let scroll_delta = ctx.input(|i| i.raw_scroll_delta);
-            if response.hovered() && scroll_delta.y != 0.0 {
-                self.zoom = (self.zoom + scroll_delta.y * 0.001).clamp(0.3, 3.0);
+            // A click selects the state under it (Ctrl: adds or removes it), on empty canvas clears the selection
+            if let Some(pointer) = response.interact_pointer_pos().filter(|_| response.clicked()) {
+                let add = ctx.input(|i| i.modifiers.command);
+                self.click_select(rect, pointer, add);
+            }
+
+            // A right-click opens quick actions for the state under the pointer, or for the canvas
+            if let Some(pointer) = response.interact_pointer_pos().filter(|_| response.secondary_clicked()) {
+                self.canvas_target = Some(match self.state_under_pointer(rect, pointer) {
+                    Some(state) => CanvasTarget::State(state.name.clone()),
+                    None => CanvasTarget::Empty(pointer),
+                });
+            }
+            let mut picked = None;
+            response.context_menu(|ui| picked = self.canvas_menu(ui));
+            if let Some(action) = picked {
+                self.run_canvas_action(action, rect);
+            }
+
+            // Keyboard navigation once the canvas has been clicked: arrows pan, F fits, Del deletes the selection
+            if response.clicked() || response.drag_started() {
+                response.request_focus();
+            }
+            if response.has_focus() {
+                let arrows = egui::EventFilter {
+                    horizontal_arrows: true,
+                    vertical_arrows: true,
+                    ..Default::default()
+                };
+                ctx.memory_mut(|m| m.set_focus_lock_filter(response.id, arrows));
+                let (direction, dt, fit, delete) = ctx.input(|i| {
+                    let axis = |negative, positive| i.key_down(positive) as i8 as f32 - i.key_down(negative) as i8 as f32;
+                    let direction = egui::vec2(
+                        axis(egui::Key::ArrowRight, egui::Key::ArrowLeft),
+                        axis(egui::Key::ArrowDown, egui::Key::ArrowUp),
+                    );
+                    (direction, i.stable_dt, i.key_pressed(egui::Key::F), i.key_pressed(egui::Key::Delete))
+                });
+                if delete {
+                    self.delete_selected_states();
+                }
+                if direction != egui::Vec2::ZERO {
+                    self.view.pan += direction * KEYBOARD_PAN_SPEED * dt;
+                    ctx.request_repaint();
+                }
+                self.fit_requested |= fit;
             }
 
-            let rect = response.rect;
+            // Double-click on a transition label edits it, on empty canvas fits the diagram
+            if let Some(pointer) = response.interact_pointer_pos().filter(|_| response.double_clicked()) {
+                match self.label_under_pointer(ctx, rect, pointer) {
+                    Some(transition_index) => self.open_label_edit(transition_index),
+                    None => self.fit_requested |= self.state_under_pointer(rect, pointer).is_none(),
+                }
+            }
+            if std::mem::take(&mut self.fit_requested) {
+                if let Some(layout) = &self.layout {
+                    self.view.fit(layout.bounds, rect, FIT_MARGIN);
+                }
+            }
+            // Pan so that a state added with "Add state here" is where the canvas was clicked
+            if let Some((name, at)) = self.reveal_state.take() {
+                match self.state_positions.get(&name) {
+                    Some(&pos) => self.view.show_at(pos, at),
+                    None if self.layout_dirty => self.reveal_state = Some((name, at)),
+                    None => {}
+                }
+            }
             
             // Draw background
-            painter.rect_filled(rect, 0.0, egui::Color32::from_rgb(25, 28, 32));
+            painter.rect_filled(rect, 0.0, self.theme.background);
 
             // Draw grid
-            draw_grid(&painter, rect, self.zoom, self.pan_offset);
+            draw_grid(&painter, rect, self.view.zoom, self.view.pan);
 
             if let Some(fsm) = self.fsms.get(self.selected_fsm) {
-                // Transform helper
-                let transform = |pos: egui::Pos2| -> egui::Pos2 {
-                    let centered = pos.to_vec2() * self.zoom;
-                    rect.center() + centered + self.pan_offset
-                };
+                let viewport = self.view.viewport(rect);
 
                 if let Some(layout) = &self.layout {
-                    // Draw edges from engine-provided points.
-                    for edge in &layout.edges {
-                        if edge.points.len() >= 2 {
-                            let mut route: Vec<egui::Pos2> = edge.points.iter().copied().map(transform).collect();
-
-                            // Ensure there is at least one segment
-                            route.dedup_by(|a, b| (a.x - b.x).abs() < 0.01 && (a.y - b.y).abs() < 0.01);
-                            if route.len() >= 2 {
-                                let color = match edge.transition_type {
-                                    TransitionType::Forward => egui::Color32::from_rgb(150, 160, 180),
-                                    TransitionType::Return => egui::Color32::from_rgb(120, 180, 140),
-                                    TransitionType::Conditional => egui::Color32::from_rgb(180, 150, 120),
-                                    TransitionType::Timer => egui::Color32::from_rgb(180, 180, 120),
-                                };
-                                draw_orthogonal_arrow_colored(&painter, &route, self.zoom, color);
-                            }
-                        }
-                    }
-
-                    // Draw labels as nodes produced by the engine (no edge-label proxy required).
-                    for label in &layout.labels {
-                        let label_pos = transform(label.pos);
-                        let font_size = self.layout_config.edge_label_font_size * self.zoom;
-                        let text_size = Self::measure_text(ctx, &label.text, font_size);
-                        let rect = egui::Rect::from_center_size(
-                            label_pos,
-                            text_size + egui::vec2(14.0 * self.zoom, 8.0 * self.zoom),
-                        );
-                        draw_label(
-                            &painter,
-                            &LabelInfo {
-                                pos: label_pos,
-                                rect,
-                                text: label.text.clone(),
-                                font_size,
-                            },
-                        );
+                    // Hovering a state or transition highlights what it connects (draw-time only)
+                    let hover_pos = response.hover_pos();
+                    let hovered_state = hover_pos.and_then(|p| self.state_under_pointer(rect, p));
+                    let hovered_transition = hover_pos
+                        .filter(|_| hovered_state.is_none())
+                        .and_then(|p| self.transition_under_pointer(ctx, rect, p));
+                    let focus = match (hovered_state, hovered_transition) {
+                        (Some(state), _) => Some(HoverFocus::state(fsm, &state.name)),
+                        (None, Some(index)) => Some(HoverFocus::transition(fsm, index)),
+                        (None, None) => None,
+                    };
+                    let content = PaneContent {
+                        fsm,
+                        layout,
+                        positions: &self.state_positions,
+                        render_cache: &self.render_cache,
+                        animation: self.layout_anim.as_ref().zip(self.layout_anim_progress()),
+                        focus,
+                        sim: self.shown_sim(Pane::Left),
+                    };
+                    self.paint_diagram(ctx, &painter, viewport, content);
+                    if let (Some(drag), Some(pointer)) = (self.edge_drag.as_ref().filter(|d| !d.cancelled), pointer) {
+                        self.paint_edge_drag(&painter, rect, &drag.from, pointer);
                     }
 
-                    // Draw the initial pseudo-state if present.
-                    if let Some(&pos) = self.state_positions.get("[*]") {
-                        let p = transform(pos);
-                        painter.circle_filled(p, 8.0 * self.zoom, egui::Color32::WHITE);
-                        painter.circle_filled(p, 4.0 * self.zoom, egui::Color32::BLACK);
-                    }
-
-                    // Draw states (on top)
-                    for state in &fsm.states {
-                        if let Some(&pos) = self.state_positions.get(&state.name) {
-                            let transformed_pos = transform(pos);
-                            let is_active = self
-                                .sim
-                                .enabled
-                                .then(|| self.sim.current_state.as_deref() == Some(state.name.as_str()))
-                                .unwrap_or(false);
-                            draw_state(
-                                &painter,
-                                transformed_pos,
-                                state,
-                                fsm.initial_state.as_deref() == Some(&state.name),
-                                is_active,
-                                self.zoom,
-                            );
+                    // Selected states, and the rubber band selecting more
+                    let selection_stroke = egui::Stroke::new(2.0, self.theme.accent);
+                    for state in fsm.states.iter().filter(|s| self.selection.of(&fsm.name).contains(&s.name)) {
+                        if let Some(state_rect) = self.state_screen_rect(rect, state) {
+                            painter.rect_stroke(state_rect.expand(4.0), 6.0, selection_stroke);
                         }
                     }
+                    if let (Some(start), Some(pointer)) = (self.rubber_band, pointer) {
+                        let band = egui::Rect::from_two_pos(start, pointer);
+                        painter.rect_filled(band, 0.0, self.theme.accent.gamma_multiply(0.12));
+                        painter.rect_stroke(band, 0.0, egui::Stroke::new(1.0, self.theme.accent));
+                    }
 
-                    // Animate last fired transition as a moving dot along the engine route.
-                    if self.sim.enabled {
-                        if let Some(fired) = &self.sim.last_fired {
-                            let elapsed = fired.started_at.elapsed().as_secs_f32();
-                            if elapsed <= fired.duration_s {
-                                if let Some(t_idx) = fired.transition_index {
-                                    if let Some(route) = Self::sim_route_for_transition(layout, t_idx, &fired.from, &fired.to) {
-                                        let route_screen: Vec<egui::Pos2> = route.into_iter().map(transform).collect();
-                                        let t = (elapsed / fired.duration_s).clamp(0.0, 1.0);
-                                        if let Some(p) = Self::polyline_point_at(&route_screen, t) {
-                                            painter.circle_filled(
-                                                p,
-                                                6.0 * self.zoom,
-                                                egui::Color32::from_rgb(255, 220, 120),
-                                            );
-                                            painter.circle_stroke(
-                                                p,
-                                                6.0 * self.zoom,
-                                                egui::Stroke::new(2.0 * self.zoom, egui::Color32::from_rgb(40, 30, 20)),
-                                            );
+                    // Full label (with action parameters), description and trailing comment
+                    let hovered = hovered_transition.and_then(|i| fsm.transitions.get(i));
+                    let full_label = hovered.map(|t| {
+                        let label = LabelEdit::from_transition(t).label();
+                        let label = if t.is_local() { format!("local {label}") } else { label };
+                        format!("{} --> {} : {}", t.source, t.target, label.trim_end())
+                    });
+                    // A guard using named guards, with their definitions filled in
+                    let expanded_guard = hovered
+                        .and_then(|t| t.guard.as_ref())
+                        .filter(|g| g.terms().iter().any(|term| fsm.named_guard(&term.expression).is_some()))
+                        .map(|g| format!("[{}] = {}", g.expression, fsm.resolve_guard(&g.expression)));
+                    // What the generated code will need implemented for it, as the selected target declares it
+                    let methods = match (hovered_state, hovered_transition) {
+                        (Some(state), _) => codegen::ir::methods_for_state(fsm, &state.name, self.codegen_target),
+                        (None, Some(index)) => codegen::ir::methods_for_transition(fsm, index, self.codegen_target),
+                        (None, None) => Vec::new(),
+                    };
+                    let notes = match (hovered_state, hovered) {
+                        (Some(state), _) => Some((state.description.as_deref(), state.trailing_comment.as_deref())),
+                        (None, Some(t)) => Some((t.description.as_deref(), t.trailing_comment.as_deref())),
+                        (None, None) => None,
+                    };
+                    let has_card = |(d, c): &(Option<&str>, Option<&str>)| full_label.is_some() || d.is_some() || c.is_some() || !methods.is_empty();
+                    if let Some((description, comment)) = notes.filter(has_card) {
+                        response.clone().on_hover_ui_at_pointer(|ui| {
+                            if let Some(ref label) = full_label {
+                                ui.monospace(label);
+                            }
+                            if let Some(ref guard) = expanded_guard {
+                                ui.monospace(guard);
+                            }
+                            if let Some(description) = description {
+                                ui.label(description);
+                            }
+                            if let Some(comment) = comment {
+                                ui.label(egui::RichText::new(format!("// {comment}")).monospace().weak());
+                            }
+                            if !methods.is_empty() {
+                                ui.separator();
+                                ui.weak("Generated code requires:");
+                                for method in &methods {
+                                    ui.horizontal(|ui| {
+                                        ui.monospace(&method.signature);
+                                        let shared = method.shared_with.len();
+                                        if shared > 0 {
+                                            let uses = if shared == 1 { "use" } else { "uses" };
+                                            ui.weak(format!("shared with {shared} other {uses}"));
                                         }
-                                    }
+                                    });
                                 }
                             }
-                        }
+                        });
                     }
                 } else {
                     painter.text(
@@ -1969,6 +5634,24 @@ impl eframe::App for OxidateApp {
                     egui::Color32::GRAY,
                 );
             }
+
+            if let Some(right) = right {
+                self.show_split_pane(ctx, ui, right);
+            }
+
+            // Not while typing pauses before an auto-parse
+            let parse_pending = self.auto_parse && self.last_edit.is_some();
+            if !self.fsms.is_empty() && !parse_pending && self.diagram_is_stale() {
+                self.draw_stale_watermark(ui, area);
+            }
+            if self.show_minimap {
+                self.draw_minimap(ui, rect);
+            }
+            self.draw_tag_legend(ui, rect);
+            if let Some(right) = right {
+                self.pane_fsm_picker(ui, rect, Pane::Left);
+                self.pane_fsm_picker(ui, right, Pane::Right);
+            }
         });
 
         // Bottom panel: Info
@@ -1976,32 +5659,87 @@ impl eframe::App for OxidateApp {
             ui.horizontal(|ui| {
                 ui.label("Oxidate v0.1.0");
                 ui.separator();
-                ui.label("Scroll to zoom | Drag to pan");
+                match (self.edit_mode, self.select_mode) {
+                    (true, _) => ui.label("Edit mode: drag between states to add a transition, from [*] to set the initial state, onto empty canvas to add a state | Esc: cancel"),
+                    (false, true) => ui.label("Select mode: drag a rectangle to select states (Ctrl: add) | Ctrl+click: add or remove a state | Del: delete them"),
+                    (false, false) => ui.label("Scroll to zoom (Ctrl: fine) | Drag or arrow keys to pan | F / double-click: fit | Click / Ctrl+click: select states"),
+                };
                 
                 if let Some(fsm) = self.fsms.get(self.selected_fsm) {
                     ui.separator();
-                    if let Some(ref initial) = fsm.initial_state {
-                        ui.label(format!("Initial: {}", initial));
+                    if let Some(initial) = fsm.initial_state() {
+                        match fsm.has_guarded_initial() {
+                            true => ui.label(format!("Initial: {} (or a guarded one)", initial)),
+                            false => ui.label(format!("Initial: {}", initial)),
+                        };
                     }
                 }
+
+                if let Some(hit) = self.layout_cache.last_hit {
+                    ui.separator();
+                    let cache = &self.layout_cache;
+                    ui.weak(if hit { "Layout: cached" } else { "Layout: computed" }).on_hover_text(format!(
+                        "{} layout(s) cached, {} hit(s), {} miss(es)",
+                        cache.len(),
+                        cache.hits,
+                        cache.misses
+                    ));
+                }
             });
         });
 
+        self.show_tutorial(ctx);
+
+        // Keep repainting until the layout change animation has finished.
+        if self.layout_anim.is_some() {
+            let done = self.layout_anim_progress().is_some_and(|t| t >= 1.0);
+            if done {
+                self.layout_anim = None;
+            } else {
+                ctx.request_repaint_after(Duration::from_millis(16));
+            }
+        }
+
         // eframe/egui only repaints on input by default. The simulator needs continuous
         // repainting for Auto stepping + transition animation, even when the mouse is idle.
-        if self.sim.enabled {
-            let animating = self
-                .sim
+        for sim in [Pane::Left, Pane::Right].into_iter().filter_map(|pane| self.shown_sim(pane)) {
+            let animating = sim
                 .last_fired
                 .as_ref()
                 .is_some_and(|f| f.started_at.elapsed().as_secs_f32() < f.duration_s);
-            if self.sim.running || animating {
+            let running = if self.bus.enabled { self.bus.running } else { sim.running };
+            if running || animating {
                 ctx.request_repaint_after(Duration::from_millis(16));
             }
         }
     }
 }
 
+impl TutorialApp for OxidateApp {
+    fn editor_source(&self) -> &str {
+        match self.workspace {
+            Some(ref workspace) => &workspace.selected_member().source,
+            None => &self.source_code,
+        }
+    }
+
+    fn parsed_fsms(&self) -> &[FsmDefinition] {
+        &self.fsms
+    }
+
+    fn codegen_target(&self) -> CodegenTarget {
+        self.codegen_target
+    }
+
+    fn sim_enabled(&self) -> bool {
+        self.sim.enabled
+    }
+
+    fn sim_state(&self) -> Option<&str> {
+        self.sim.current_state.as_deref()
+    }
+}
+
 /// Information about a label for overlap detection
 #[derive(Clone)]
 struct LabelInfo {
@@ -2278,21 +6016,6 @@ fn calculate_orthogonal_route_with_lanes(
     points
 }
 
-/// Determine the type of transition for rendering decisions (layout is engine-driven).
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum TransitionType {
-    Forward,      // Main flow - straight arrows
-    Return,       // Return transitions - curved arrows
-    Conditional,  // Has guards - curved arrows
-    Timer,        // Timer events - label above
-}
-
-impl Default for TransitionType {
-    fn default() -> Self {
-        TransitionType::Forward
-    }
-}
-
 fn classify_transition(transition: &fsm::Transition, is_reverse: bool) -> TransitionType {
     // Get event name if present
     let event_name = transition.event.as_ref()
@@ -2389,68 +6112,6 @@ fn calculate_label_position(
     }
 }
 
-/// Format label text - break into multiple SHORT lines for better readability
-fn format_label_text(label: &str) -> String {
-    let mut result = String::new();
-    
-    // Split event and guard
-    if let Some(bracket_start) = label.find('[') {
-        let event = label[..bracket_start].trim();
-        let guard_part = &label[bracket_start..];
-        
-        // Add event (may need to break if long)
-        if event.len() > 15 {
-            // Break long event names at underscores
-            let parts: Vec<&str> = event.split('_').collect();
-            let mut line = String::new();
-            for (i, part) in parts.iter().enumerate() {
-                if line.len() + part.len() > 12 && !line.is_empty() {
-                    result.push_str(&line);
-                    result.push('\n');
-                    line = part.to_string();
-                } else {
-                    if !line.is_empty() {
-                        line.push('_');
-                    }
-                    line.push_str(part);
-                }
-                if i == parts.len() - 1 {
-                    result.push_str(&line);
-                }
-            }
-        } else {
-            result.push_str(event);
-        }
-        
-        // Add guard on new line
-        result.push('\n');
-        result.push_str(guard_part);
-    } else if label.len() > 15 {
-        // Long label without guard - break at underscores
-        let parts: Vec<&str> = label.split('_').collect();
-        let mut line = String::new();
-        for (i, part) in parts.iter().enumerate() {
-            if line.len() + part.len() > 12 && !line.is_empty() {
-                result.push_str(&line);
-                result.push('\n');
-                line = part.to_string();
-            } else {
-                if !line.is_empty() {
-                    line.push('_');
-                }
-                line.push_str(part);
-            }
-            if i == parts.len() - 1 {
-                result.push_str(&line);
-            }
-        }
-    } else {
-        result = label.to_string();
-    }
-    
-    result
-}
-
 /// Calculate label info for orthogonal transition
 fn calculate_label_info_orthogonal(
     route: &[egui::Pos2],
@@ -2461,13 +6122,13 @@ fn calculate_label_info_orthogonal(
     from_rect: egui::Rect,
     to_rect: egui::Rect,
 ) -> Option<LabelInfo> {
-    let raw_label = transition.label();
+    let raw_label = transition.diagram_label();
     if raw_label.is_empty() {
         return None;
     }
     
     // Format label - break into multiple SHORT lines
-    let label = format_label_text(&raw_label);
+    let label = format_label_text(&raw_label, LABEL_WRAP_CHARS);
     let lines: Vec<&str> = label.lines().collect();
     let num_lines = lines.len();
     
@@ -2604,58 +6265,35 @@ fn resolve_label_overlaps(labels: &mut [LabelInfo], state_boxes: &[StateBox]) {
     }
 }
 
-/// Draw orthogonal arrow with arrowhead
-fn draw_orthogonal_arrow(painter: &egui::Painter, route: &[egui::Pos2], zoom: f32) {
-    draw_orthogonal_arrow_colored(painter, route, zoom, egui::Color32::from_rgb(160, 175, 195));
-}
-
-/// Draw orthogonal arrow with custom color
-fn draw_orthogonal_arrow_colored(painter: &egui::Painter, route: &[egui::Pos2], zoom: f32, color: egui::Color32) {
-    if route.len() < 2 {
-        return;
-    }
-    
-    let stroke = egui::Stroke::new(1.5 * zoom, color);
-    
-    // Draw line segments
-    for i in 0..route.len() - 1 {
-        painter.line_segment([route[i], route[i + 1]], stroke);
+fn event_handling_color(handling: fsm::matrix::EventHandling) -> egui::Color32 {
+    match handling {
+        fsm::matrix::EventHandling::External => egui::Color32::from_rgb(120, 200, 140),
+        fsm::matrix::EventHandling::Internal => egui::Color32::from_rgb(120, 170, 220),
+        fsm::matrix::EventHandling::GuardedOnly => egui::Color32::from_rgb(230, 190, 100),
+        fsm::matrix::EventHandling::Unhandled => egui::Color32::from_rgb(110, 110, 115),
     }
-    
-    // Draw arrowhead at the end
-    let last = route[route.len() - 1];
-    let prev = route[route.len() - 2];
-    let dir = (last - prev).normalized();
-    
-    let arrow_size = 10.0 * zoom;
-    let arrow_angle = 0.4;
-    
-    let perp = egui::vec2(-dir.y, dir.x);
-    let arrow_p1 = last - dir * arrow_size + perp * arrow_size * arrow_angle;
-    let arrow_p2 = last - dir * arrow_size - perp * arrow_size * arrow_angle;
-    
-    painter.add(egui::Shape::convex_polygon(
-        vec![last, arrow_p1, arrow_p2],
-        color,
-        egui::Stroke::NONE,
-    ));
 }
 
-/// Draw a transition label
-fn draw_label(painter: &egui::Painter, info: &LabelInfo) {
-    // Background
-    painter.rect_filled(info.rect, 3.0, egui::Color32::from_rgb(30, 35, 45));
-    painter.rect_stroke(info.rect, 3.0, egui::Stroke::new(1.0, egui::Color32::from_rgb(70, 80, 95)));
-    
-    // Text
-    painter.text(
-        info.pos,
-        egui::Align2::CENTER_CENTER,
-        &info.text,
-        egui::FontId::proportional(info.font_size),
-        egui::Color32::from_rgb(255, 230, 120),
-    );
-}
+/// Space left around the diagram by zoom-to-fit (screen pixels)
+const FIT_MARGIN: f32 = 40.0;
+
+/// Arrow-key panning speed (screen pixels per second)
+const KEYBOARD_PAN_SPEED: f32 = 600.0;
+
+/// Editor idle time before an automatic re-parse
+const AUTO_PARSE_DELAY: Duration = Duration::from_millis(500);
+
+/// How close (screen pixels) the pointer must be to an edge route to hover it
+const EDGE_HOVER_DISTANCE: f32 = 6.0;
+
+/// Edit mode: how far outside a state box (screen pixels) a drag still starts or ends on it
+const EDGE_DRAG_MARGIN: f32 = 6.0;
+
+/// Radius of the start node's outer circle at 100% zoom (as `SceneBuilder::initial_marker` draws it)
+const INITIAL_MARKER_RADIUS: f32 = 8.0;
+
+/// Loop time of the token flashing the transition at the scrubbed step
+const SCRUB_FLASH_PERIOD_S: f64 = 1.2;
 
 fn draw_grid(painter: &egui::Painter, rect: egui::Rect, zoom: f32, offset: egui::Vec2) {
     let grid_size = 50.0 * zoom;
@@ -2683,254 +6321,6 @@ fn draw_grid(painter: &egui::Painter, rect: egui::Rect, zoom: f32, offset: egui:
     }
 }
 
-/// Estimate the visual size of a state box
-fn estimate_state_size(state: &fsm::State) -> egui::Vec2 {
-    let mut action_lines = 0;
-    let mut max_action_len = 0;
-    
-    for entry in &state.entry_actions {
-        action_lines += 1;
-        max_action_len = max_action_len.max(entry.name.len() + 7); // "entry/ "
-    }
-    for exit in &state.exit_actions {
-        action_lines += 1;
-        max_action_len = max_action_len.max(exit.name.len() + 6); // "exit/ "
-    }
-    
-    // Add internal transitions
-    action_lines += state.internal_transitions.len();
-    for internal in &state.internal_transitions {
-        let line_len = internal.label().len();
-        max_action_len = max_action_len.max(line_len);
-    }
-    
-    let name_len = state.name.len();
-    let max_chars = name_len.max(max_action_len);
-    
-    // Estimate width: chars * approximate char width + padding
-    let width = (max_chars as f32 * 8.0).max(100.0) + 30.0;
-    
-    // Estimate height: header + separator + action lines + padding
-    let height = 30.0 + (action_lines.max(1) as f32 * 16.0) + 20.0;
-    
-    egui::vec2(width, height)
-}
-
-/// Calculate hierarchical levels for states based on transitions
-fn calculate_state_levels(fsm: &fsm::FsmDefinition) -> std::collections::HashMap<String, i32> {
-    let mut levels: std::collections::HashMap<String, i32> = std::collections::HashMap::new();
-    
-    // Find initial state
-    let initial = fsm.initial_state.as_ref();
-    
-    // BFS to assign levels
-    let mut queue: std::collections::VecDeque<(String, i32)> = std::collections::VecDeque::new();
-    
-    if let Some(init) = initial {
-        levels.insert(init.clone(), 0);
-        queue.push_back((init.clone(), 0));
-    } else if let Some(first_state) = fsm.states.first() {
-        levels.insert(first_state.name.clone(), 0);
-        queue.push_back((first_state.name.clone(), 0));
-    }
-    
-    // Build adjacency from transitions
-    let mut outgoing: std::collections::HashMap<String, Vec<String>> = std::collections::HashMap::new();
-    for transition in &fsm.transitions {
-        if transition.source != "[*]" && !transition.target.starts_with("<<") && transition.target != "[*]" {
-            outgoing.entry(transition.source.clone())
-                .or_insert_with(Vec::new)
-                .push(transition.target.clone());
-        }
-    }
-    
-    // BFS
-    while let Some((state, level)) = queue.pop_front() {
-        if let Some(targets) = outgoing.get(&state) {
-            for target in targets {
-                if !levels.contains_key(target) {
-                    // Self-loops stay at same level
-                    let new_level = if target == &state { level } else { level + 1 };
-                    levels.insert(target.clone(), new_level);
-                    queue.push_back((target.clone(), new_level));
-                }
-            }
-        }
-    }
-    
-    // Assign remaining states that weren't reached
-    let max_level = levels.values().max().copied().unwrap_or(0);
-    for state in &fsm.states {
-        if !levels.contains_key(&state.name) {
-            levels.insert(state.name.clone(), max_level + 1);
-        }
-    }
-    
-    levels
-}
-
-/// Calculate the bounding rectangle for a state (used for routing and collision)
-fn calculate_state_rect(state: &fsm::State, pos: egui::Pos2, zoom: f32) -> egui::Rect {
-    let mut action_lines = Vec::new();
-    for entry in &state.entry_actions {
-        action_lines.push(format!("entry/ {}", entry.name));
-    }
-    for exit in &state.exit_actions {
-        action_lines.push(format!("exit/ {}", exit.name));
-    }
-    
-    let font_size = 10.0 * zoom;
-    let char_width = font_size * 0.55;
-    let line_height = font_size * 1.3;
-    
-    // Width based on name or actions, whichever is larger
-    let name_width = state.name.len() as f32 * 9.0 * zoom;
-    let action_width = action_lines.iter()
-        .map(|line| line.len() as f32 * char_width)
-        .max_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap_or(0.0);
-    
-    let padding = 15.0 * zoom;
-    let width = name_width.max(action_width).max(80.0 * zoom) + padding * 2.0;
-    
-    // Height: header (name) + separator + actions area
-    let header_height = 22.0 * zoom;
-    let actions_height = if action_lines.is_empty() {
-        20.0 * zoom
-    } else {
-        (action_lines.len() as f32 * line_height) + padding
-    };
-    let height = header_height + actions_height;
-    
-    egui::Rect::from_center_size(pos, egui::vec2(width, height))
-}
-
-fn draw_state(
-    painter: &egui::Painter,
-    pos: egui::Pos2,
-    state: &fsm::State,
-    is_initial: bool,
-    is_active: bool,
-    zoom: f32,
-) {
-    // Calculate content for dynamic sizing
-    let mut action_lines = Vec::new();
-    for entry in &state.entry_actions {
-        action_lines.push(format!("entry/ {}", entry.name));
-    }
-    for exit in &state.exit_actions {
-        action_lines.push(format!("exit/ {}", exit.name));
-    }
-    
-    // Dynamic sizing based on content
-    let font_size = 10.0 * zoom;
-    let char_width = font_size * 0.55;
-    let line_height = font_size * 1.3;
-    
-    // Width based on name or actions
-    let name_width = state.name.len() as f32 * 9.0 * zoom;
-    let action_width = action_lines.iter()
-        .map(|line| line.len() as f32 * char_width)
-        .max_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap_or(0.0);
-    
-    let padding = 15.0 * zoom;
-    let width = name_width.max(action_width).max(80.0 * zoom) + padding * 2.0;
-    
-    // Height: header + actions
-    let header_height = 22.0 * zoom;
-    let actions_height = if action_lines.is_empty() {
-        20.0 * zoom
-    } else {
-        (action_lines.len() as f32 * line_height) + padding
-    };
-    let total_height = header_height + actions_height;
-    
-    let rect = egui::Rect::from_center_size(pos, egui::vec2(width, total_height));
-    
-    // Colors
-    let fill_color = match state.state_type {
-        StateType::Composite => egui::Color32::from_rgb(50, 80, 120),
-        StateType::Final => egui::Color32::from_rgb(100, 50, 50),
-        _ => egui::Color32::from_rgb(40, 55, 75),
-    };
-    
-    let header_color = match state.state_type {
-        StateType::Composite => egui::Color32::from_rgb(60, 95, 140),
-        StateType::Final => egui::Color32::from_rgb(120, 60, 60),
-        _ => egui::Color32::from_rgb(55, 75, 100),
-    };
-    
-    let stroke_color = if is_active {
-        egui::Color32::from_rgb(255, 220, 120)
-    } else if is_initial {
-        egui::Color32::from_rgb(100, 220, 100)
-    } else {
-        egui::Color32::from_rgb(100, 120, 145)
-    };
-
-    let stroke_width = if is_active { 3.5 } else if is_initial { 3.0 } else { 1.5 };
-    let corner_radius = 8.0 * zoom;
-    
-    // Draw main box (body)
-    painter.rect(
-        rect,
-        corner_radius,
-        fill_color,
-        egui::Stroke::new(stroke_width * zoom, stroke_color),
-    );
-    
-    // Draw header compartment (name area)
-    let header_rect = egui::Rect::from_min_size(
-        rect.min,
-        egui::vec2(width, header_height),
-    );
-    
-    // Header with rounded top corners only
-    painter.rect_filled(
-        header_rect,
-        egui::Rounding {
-            nw: corner_radius,
-            ne: corner_radius,
-            sw: 0.0,
-            se: 0.0,
-        },
-        header_color,
-    );
-    
-    // Separator line between header and body
-    painter.line_segment(
-        [
-            egui::pos2(rect.left(), rect.top() + header_height),
-            egui::pos2(rect.right(), rect.top() + header_height),
-        ],
-        egui::Stroke::new(1.0 * zoom, stroke_color),
-    );
-    
-    // State name in header (centered)
-    let name_pos = egui::pos2(rect.center().x, rect.top() + header_height / 2.0);
-    painter.text(
-        name_pos,
-        egui::Align2::CENTER_CENTER,
-        &state.name,
-        egui::FontId::proportional(13.0 * zoom),
-        egui::Color32::WHITE,
-    );
-    
-    // Entry/exit actions in body
-    if !action_lines.is_empty() {
-        let body_center_y = rect.top() + header_height + actions_height / 2.0;
-        let actions = action_lines.join("\n");
-        painter.text(
-            egui::pos2(rect.center().x, body_center_y),
-            egui::Align2::CENTER_CENTER,
-            actions,
-            egui::FontId::proportional(font_size),
-            egui::Color32::from_rgb(180, 200, 220),
-        );
-    }
-}
-
 // Default FSM code shown on startup
 const DEFAULT_FSM_CODE: &str = r#"// Oxidate - FSM Definition Example
 // Syntax: Mermaid-like state diagram DSL
@@ -2951,95 +6341,9 @@ fsm TrafficLight {
 }
 "#;
 
-const TRAFFIC_LIGHT_EXAMPLE: &str = r#"// Traffic Light State Machine
-fsm TrafficLight {
-    [*] --> Red
-    
-    state Red : Stop - Wait for green {
-        entry / activate_red_light
-        exit / deactivate_red_light
-    }
-    
-    state Yellow : Caution {
-        entry / activate_yellow_light
-        exit / deactivate_yellow_light
-    }
-    
-    state Green : Go! {
-        entry / activate_green_light
-        exit / deactivate_green_light
-    }
-    
-    Red --> Green : timer_expired [day_mode]
-    Red --> Yellow : timer_expired [night_mode]
-    Green --> Yellow : timer_expired
-    Yellow --> Red : timer_expired
-}
-"#;
+// Built-in examples (Examples menu); also the inputs of the DOT export golden tests
+const TRAFFIC_LIGHT_EXAMPLE: &str = include_str!("../examples/builtin/traffic_light.fsm");
 
-const DOOR_LOCK_EXAMPLE: &str = r#"// Smart Door Lock State Machine
-fsm DoorLock {
-    [*] --> Locked
-    
-    state Locked : Door is secured {
-        entry / engage_lock
-        exit / disengage_lock
-    }
-    
-    state Unlocked : Door can be opened {
-        entry / notify_unlocked
-    }
-    
-    state Open : Door is open {
-        entry / start_open_timer
-        exit / stop_open_timer
-    }
-    
-    state Alarming : Security alert! {
-        entry / sound_alarm
-        exit / silence_alarm
-    }
-    
-    Locked --> Unlocked : valid_key
-    Locked --> Alarming : invalid_key [attempts > 3]
-    Unlocked --> Locked : lock_cmd
-    Unlocked --> Open : door_opened
-    Open --> Unlocked : door_closed
-    Open --> Alarming : timeout [held_too_long]
-    Alarming --> Locked : reset_alarm
-}
-"#;
+const DOOR_LOCK_EXAMPLE: &str = include_str!("../examples/builtin/door_lock.fsm");
 
-const VENDING_MACHINE_EXAMPLE: &str = r#"// Vending Machine State Machine
-fsm VendingMachine {
-    [*] --> Idle
-    
-    state Idle : Insert coins {
-        entry / display_welcome
-        exit / clear_display
-    }
-    
-    state AcceptingCoins : Accepting payment {
-        entry / show_balance
-        coin_inserted / add_to_balance
-    }
-    
-    state Dispensing : Delivering product {
-        entry / dispense_product
-        exit / update_inventory
-    }
-    
-    state ReturningChange : Giving change {
-        entry / calculate_change
-        exit / dispense_change
-    }
-    
-    Idle --> AcceptingCoins : coin_inserted
-    AcceptingCoins --> AcceptingCoins : coin_inserted / add_coin
-    AcceptingCoins --> Dispensing : select_product [sufficient_funds]
-    AcceptingCoins --> Idle : cancel / return_coins
-    Dispensing --> ReturningChange : dispensed [has_change]
-    Dispensing --> Idle : dispensed [no_change]
-    ReturningChange --> Idle : change_returned
-}
-"#;
+const VENDING_MACHINE_EXAMPLE: &str = include_str!("../examples/builtin/vending_machine.fsm");
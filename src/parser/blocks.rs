@@ -0,0 +1,149 @@
+//! Source Blocks
+//! Cuts DSL text into one block per `fsm` definition for the editor's FSM tabs,
+//! using the spans the grammar parsed (so braces in comments, doc comments and notes
+//! do not count), and joins the blocks back into exactly the original text.
+//!
+//! Blocks also let an edit be parsed incrementally: [`SourceBlocks::reparse`] parses
+//! again only the blocks whose text changed since the FSMs were parsed and puts the
+//! results in place, leaving every other FSM as it was.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use pest::Parser;
+
+use super::{FsmParser, ParseResult, Rule};
+use crate::fsm::FsmDefinition;
+
+/// What [`SourceBlocks::reparse`] did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reparse {
+    /// The FSMs of these blocks were parsed again and replaced (none if no block changed)
+    Blocks(Vec<usize>),
+    /// The blocks no longer match the FSMs one to one (a block was added or removed,
+    /// or now holds no FSM or several): nothing was touched, parse the whole source
+    Full,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceBlocks {
+    /// One per FSM: its definition with the comments and `include` lines above it;
+    /// text after the last definition belongs to the last block
+    pub blocks: Vec<String>,
+    /// Whitespace before the first block, between each pair and after the last (one
+    /// more than `blocks`; with no blocks, the whole text)
+    pub gaps: Vec<String>,
+}
+
+impl SourceBlocks {
+    /// Split `source` at the end of each `fsm` definition; fails if it does not parse
+    pub fn split(source: &str) -> ParseResult<Self> {
+        let file = FsmParser::parse(Rule::file, source)?;
+        let ends: Vec<usize> = file
+            .flat_map(|pair| pair.into_inner())
+            .filter(|pair| pair.as_rule() == Rule::fsm_definition)
+            .map(|pair| pair.as_span().end())
+            .collect();
+        let Some((_, ends)) = ends.split_last() else {
+            return Ok(Self { blocks: Vec::new(), gaps: vec![source.to_string()] });
+        };
+
+        let mut split = Self::default();
+        let mut start = 0;
+        for &end in ends {
+            split.push(&source[start..end]);
+            start = end;
+        }
+        // Trailing comments stay with the last FSM
+        split.push(&source[start..]);
+        Ok(split)
+    }
+
+    /// All of `source` as a single block (for text that does not parse yet)
+    pub fn whole(source: &str) -> Self {
+        let mut split = Self::default();
+        split.push(source);
+        split
+    }
+
+    /// The text the blocks were cut from, with the blocks' current contents
+    pub fn join(&self) -> String {
+        let mut source = String::new();
+        for (gap, block) in self.gaps.iter().zip(&self.blocks) {
+            source.push_str(gap);
+            source.push_str(block);
+        }
+        source.push_str(self.gaps.last().map_or("", String::as_str));
+        source
+    }
+
+    /// Byte offset of block `index` in the joined text
+    pub fn offset(&self, index: usize) -> usize {
+        let gaps: usize = self.gaps.iter().take(index + 1).map(String::len).sum();
+        gaps + self.blocks.iter().take(index).map(String::len).sum::<usize>()
+    }
+
+    /// Hash of each block's text, to tell later which blocks were edited
+    pub fn hashes(&self) -> Vec<u64> {
+        self.blocks.iter().map(|block| text_hash(block)).collect()
+    }
+
+    /// Block `index` after as many line breaks as come before it in the joined text,
+    /// so that what it parses to carries the lines of the whole source
+    pub fn padded(&self, index: usize) -> String {
+        let before = self.gaps.iter().take(index + 1).chain(self.blocks.iter().take(index));
+        let lines: usize = before.map(|text| text.matches('\n').count()).sum();
+        let mut padded = "\n".repeat(lines);
+        padded.push_str(self.blocks.get(index).map_or("", String::as_str));
+        padded
+    }
+
+    /// Parse with `parse` only the blocks whose hash differs from `hashes`, the hashes
+    /// of the blocks `fsms` (one FSM per block) were parsed from, and replace those
+    /// FSMs and hashes in place. A block that fails to parse fails the whole call
+    /// with nothing replaced, so the other FSMs stay as they were.
+    pub fn reparse(
+        &self,
+        fsms: &mut [FsmDefinition],
+        hashes: &mut [u64],
+        parse: impl Fn(&str) -> ParseResult<Vec<FsmDefinition>>,
+    ) -> ParseResult<Reparse> {
+        if fsms.len() != self.blocks.len() || hashes.len() != self.blocks.len() {
+            return Ok(Reparse::Full);
+        }
+        let current = self.hashes();
+        let mut parsed = Vec::new();
+        for index in (0..self.blocks.len()).filter(|&i| current[i] != hashes[i]) {
+            let mut found = parse(&self.padded(index))?;
+            match (found.pop(), found.is_empty()) {
+                (Some(fsm), true) => parsed.push((index, fsm)),
+                _ => return Ok(Reparse::Full),
+            }
+        }
+        let indices = parsed.iter().map(|(index, _)| *index).collect();
+        for (index, fsm) in parsed {
+            fsms[index] = fsm;
+            hashes[index] = current[index];
+        }
+        Ok(Reparse::Blocks(indices))
+    }
+
+    /// Add a block from `chunk`, keeping its surrounding whitespace as gaps
+    fn push(&mut self, chunk: &str) {
+        let body = chunk.trim();
+        let leading = &chunk[..chunk.len() - chunk.trim_start().len()];
+        let trailing = &chunk[leading.len() + body.len()..];
+        match self.gaps.last_mut() {
+            Some(gap) => gap.push_str(leading),
+            None => self.gaps.push(leading.to_string()),
+        }
+        self.blocks.push(body.to_string());
+        self.gaps.push(trailing.to_string());
+    }
+}
+
+fn text_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,201 @@
+//! Diagnostics
+//! What validation reports about an FSM, tied to the source it was parsed from, with
+//! quick fixes where the intent is clear: a misspelled state name, a missing initial
+//! state, a repeated transition. A fix is one text edit, so applying it and parsing
+//! again is a single change to the buffer.
+
+use std::ops::Range;
+
+use super::edit::{set_initial_state, state_references};
+use super::line_at;
+use crate::fsm::{duplicate_transition_message, FsmDefinition, NO_INITIAL_STATE, NO_TRANSITIONS};
+
+/// Largest edit distance at which a declared state is suggested for an undeclared one
+/// (short names also need at least twice as many characters as edits: `Off` is not a typo
+/// of `On`)
+pub const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// Replace `range` of the source with `replacement`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuickFix {
+    /// What the fix does, e.g. `Change to 'Active'`
+    pub title: String,
+    /// Byte range in the source the diagnostics were made for
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+impl QuickFix {
+    /// `source` with the edit made; `None` if the range does not fit it
+    pub fn apply(&self, source: &str) -> Option<String> {
+        source.get(self.range.clone())?;
+        let mut fixed = source.to_string();
+        fixed.replace_range(self.range.clone(), &self.replacement);
+        Some(fixed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub severity: Severity,
+    /// Byte range of the source it is about, if it points at one place
+    pub span: Option<Range<usize>>,
+    pub fixes: Vec<QuickFix>,
+}
+
+impl Diagnostic {
+    fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            severity,
+            span: None,
+            fixes: Vec::new(),
+        }
+    }
+}
+
+/// The errors of `validate`, the `warnings` and the `infos` of `fsm`, with the same
+/// messages and in that order, plus a warning wherever a state that is never declared
+/// is named like one that is. `source` is the text `fsm` was parsed from.
+pub fn diagnostics(source: &str, fsm: &FsmDefinition) -> Vec<Diagnostic> {
+    let errors = fsm.validate().err().unwrap_or_default().into_iter().map(|message| {
+        let mut diagnostic = Diagnostic::new(Severity::Error, message);
+        if diagnostic.message == NO_INITIAL_STATE {
+            diagnostic.fixes.extend(initial_state_fix(source, fsm));
+        }
+        diagnostic
+    });
+    let warnings = fsm.warnings().into_iter().map(|message| {
+        let mut diagnostic = Diagnostic::new(Severity::Warning, message);
+        if diagnostic.message.starts_with(NO_TRANSITIONS) && fsm.initial_state().is_none() {
+            diagnostic.fixes.extend(initial_state_fix(source, fsm));
+        }
+        diagnostic
+    });
+    let infos = fsm.infos().into_iter().map(|message| {
+        let mut diagnostic = Diagnostic::new(Severity::Info, message);
+        if let Some(transition) = fsm.transitions.iter().find(|t| t.declared_at.len() > 1 && duplicate_transition_message(t) == diagnostic.message) {
+            diagnostic.fixes = transition.declared_at[1..].iter().filter_map(|&line| remove_line_fix(source, line)).collect();
+        }
+        diagnostic
+    });
+    errors.chain(warnings).chain(misspelled_states(source, fsm)).chain(infos).collect()
+}
+
+/// `[*] --> ` the first declared state (the first state if none is declared)
+fn initial_state_fix(source: &str, fsm: &FsmDefinition) -> Option<QuickFix> {
+    let state = fsm.states.iter().find(|s| s.is_declared()).or(fsm.states.first())?;
+    let edited = set_initial_state(source, fsm, &state.name)?;
+    let (range, replacement) = difference(source, &edited);
+    Some(QuickFix {
+        title: format!("Set initial state to '{}'", state.name),
+        range,
+        replacement,
+    })
+}
+
+/// Delete 1-based `line` of `source`, line break included; none for a line that
+/// also opens or closes a block (a grouped transition on its state's line)
+fn remove_line_fix(source: &str, line: usize) -> Option<QuickFix> {
+    let start = match line {
+        0 => return None,
+        1 => 0,
+        _ => source.match_indices('\n').nth(line - 2)?.0 + 1,
+    };
+    let end = source[start..].find('\n').map_or(source.len(), |i| start + i + 1);
+    if source[start..end].contains(['{', '}']) {
+        return None;
+    }
+    Some(QuickFix {
+        title: format!("Remove duplicate at line {line}"),
+        range: start..end,
+        replacement: String::new(),
+    })
+}
+
+/// A warning at each mention of an undeclared state within `MAX_SUGGESTION_DISTANCE`
+/// of a declared one, offering the closest declared names
+fn misspelled_states(source: &str, fsm: &FsmDefinition) -> Vec<Diagnostic> {
+    let declared: Vec<&str> = fsm.states.iter().filter(|s| s.is_declared()).map(|s| s.name.as_str()).collect();
+    let close_to = |name: &str, distance: usize| {
+        (1..=MAX_SUGGESTION_DISTANCE).contains(&distance) && 2 * distance <= name.chars().count()
+    };
+    let suggestions: Vec<(&str, Vec<&str>)> = fsm
+        .implicit_states()
+        .into_iter()
+        .map(|name| {
+            let mut close: Vec<(usize, &str)> = declared
+                .iter()
+                .map(|d| (edit_distance(name, d), *d))
+                .filter(|(distance, _)| close_to(name, *distance))
+                .collect();
+            close.sort_by_key(|(distance, _)| *distance);
+            (name, close.into_iter().map(|(_, d)| d).collect::<Vec<_>>())
+        })
+        .filter(|(_, close)| !close.is_empty())
+        .collect();
+    if suggestions.is_empty() {
+        return Vec::new();
+    }
+
+    state_references(source, fsm)
+        .into_iter()
+        .filter_map(|span| {
+            let (name, close) = suggestions.iter().find(|(name, _)| source[span.clone()] == **name)?;
+            let message = format!(
+                "State '{}' (line {}) is never declared: did you mean '{}'?",
+                name,
+                line_at(source, span.start),
+                close.join("' or '")
+            );
+            let fixes = close
+                .iter()
+                .map(|d| QuickFix {
+                    title: format!("Change to '{d}'"),
+                    range: span.clone(),
+                    replacement: d.to_string(),
+                })
+                .collect();
+            Some(Diagnostic {
+                message,
+                severity: Severity::Warning,
+                span: Some(span),
+                fixes,
+            })
+        })
+        .collect()
+}
+
+/// The one range of `old` that `new` replaces, and what it puts there
+fn difference(old: &str, new: &str) -> (Range<usize>, String) {
+    let prefix = old.bytes().zip(new.bytes()).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].bytes().rev().zip(new[prefix..].bytes().rev()).take_while(|(a, b)| a == b).count();
+    // Stay on character boundaries
+    let start = (0..=prefix).rev().find(|&i| old.is_char_boundary(i) && new.is_char_boundary(i)).unwrap_or(0);
+    let end = (0..=suffix).rev().find(|&i| old.is_char_boundary(old.len() - i) && new.is_char_boundary(new.len() - i)).unwrap_or(0);
+    (start..old.len() - end, new[start..new.len() - end].to_string())
+}
+
+/// Levenshtein distance between `a` and `b`, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substituted = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substituted.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
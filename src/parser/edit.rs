@@ -0,0 +1,759 @@
+//! Source Edits
+//! Rewrites transition labels, renames states and events, adds states, transitions,
+//! entry actions, events blocks and the initial state, and deletes, tags, colors or extracts groups
+//! of states in DSL text, so edits made in the GUI go through the source (which stays the single
+//! source of truth) and a re-parse
+
+use std::ops::Range;
+
+use pest::iterators::Pair;
+use pest::Parser;
+use thiserror::Error;
+
+use super::{last_token_end, line_at, line_of, parse_transition, FsmParser, Rule};
+use crate::fsm::{Action, FsmDefinition, Transition};
+
+/// Label of a transition as typed in the edit dialog; empty fields are left out
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelEdit {
+    pub event: String,
+    pub guard: String,
+    /// Action calls separated by `;`, e.g. `open_door; beep(2)`
+    pub actions: String,
+}
+
+impl LabelEdit {
+    pub fn from_transition(transition: &Transition) -> Self {
+        Self {
+            // The timing constraint goes with the event it limits
+            event: transition
+                .event
+                .as_ref()
+                .map(|event| match transition.timing_constraint {
+                    Some(constraint) => format!("{} {}", event.pattern(), constraint),
+                    None => event.pattern(),
+                })
+                .unwrap_or_default(),
+            guard: transition.guard.as_ref().map(|g| g.expression.clone()).unwrap_or_default(),
+            actions: transition.actions.iter().map(action_call).collect::<Vec<_>>().join("; "),
+        }
+    }
+
+    /// The text after the `:` of a transition line
+    pub fn label(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.event.trim().is_empty() {
+            parts.push(self.event.trim().to_string());
+        }
+        if !self.guard.trim().is_empty() {
+            parts.push(format!("[{}]", self.guard.trim()));
+        }
+        let actions: Vec<&str> = self.actions.split(';').map(str::trim).filter(|a| !a.is_empty()).collect();
+        if !actions.is_empty() {
+            parts.push(format!("/ {}", actions.join("; ")));
+        }
+        parts.join(" ")
+    }
+}
+
+/// `action` as written: `name()` keeps its parentheses
+pub(super) fn action_call(action: &Action) -> String {
+    match action.parenthesized && action.params.is_empty() {
+        true => format!("{}()", action),
+        false => action.to_string(),
+    }
+}
+
+/// Replace the label of `fsm.transitions[index]` in `source`, keeping the arrow,
+/// indentation, a `local` keyword and any trailing comment. `None` if the transition's
+/// line cannot be found.
+pub fn rewrite_transition(source: &str, fsm: &FsmDefinition, index: usize, edit: &LabelEdit) -> Option<String> {
+    let transition = fsm.transitions.get(index)?;
+    let mut lines = split_lines(source);
+    let line = find_transition_line(&lines, fsm_lines(source, fsm), transition)?;
+    let (indent, body) = split_indent(lines[line].0);
+    let pair = FsmParser::parse(Rule::transition, body).ok()?.next()?;
+    let target_end = pair.clone().into_inner().nth(2)?.as_span().end();
+    let label = match (transition.is_local(), edit.label()) {
+        (true, label) => format!("local {label}").trim_end().to_string(),
+        (false, label) => label,
+    };
+    let label = if label.is_empty() { String::new() } else { format!(" : {label}") };
+    // Keep a trailing comment with the spacing before it
+    let rest = &body[last_token_end(&pair)..];
+    let comment = rest.find("//").map_or("", |i| &rest[rest[..i].trim_end().len()..]);
+    let rewritten = format!("{indent}{}{label}{comment}", &body[..target_end]);
+    lines[line].0 = &rewritten;
+    Some(join_lines(&lines))
+}
+
+/// Rename event `old` to `new` in every transition, internal transition and timer of `fsm`
+pub fn rename_event(source: &str, fsm: &FsmDefinition, old: &str, new: &str) -> String {
+    let mut lines = split_lines(source);
+    let range = fsm_lines(source, fsm);
+    let renamed: Vec<(usize, String)> = lines[range.clone()]
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (line, _))| {
+            let (indent, body) = split_indent(line);
+            let span = event_span(body).filter(|span| &body[span.clone()] == old)?;
+            Some((range.start + i, format!("{indent}{}{new}{}", &body[..span.start], &body[span.end..])))
+        })
+        .collect();
+    for (i, line) in &renamed {
+        lines[*i].0 = line;
+    }
+    join_lines(&lines)
+}
+
+/// What `rename` renames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenameKind {
+    State,
+    Event,
+}
+
+impl RenameKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RenameKind::State => "state",
+            RenameKind::Event => "event",
+        }
+    }
+
+    /// Names of this kind in `fsm`, in declaration order
+    pub fn names(&self, fsm: &FsmDefinition) -> Vec<String> {
+        match self {
+            RenameKind::State => fsm.states.iter().map(|s| s.name.clone()).collect(),
+            RenameKind::Event => fsm.collect_events().into_iter().map(|e| e.name).collect(),
+        }
+    }
+}
+
+/// Why `rename` refused
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum RenameError {
+    #[error("'{0}' is not a valid name")]
+    InvalidName(String),
+    #[error("{} '{name}' not found", kind.as_str())]
+    NotFound { kind: RenameKind, name: String },
+    #[error("{} '{name}' already exists", kind.as_str())]
+    AlreadyExists { kind: RenameKind, name: String },
+}
+
+/// Rename a state or event of `fsm` everywhere in its block; refuses names that
+/// would not parse or would merge two states or events
+pub fn rename(source: &str, fsm: &FsmDefinition, kind: RenameKind, old: &str, new: &str) -> Result<String, RenameError> {
+    if !is_identifier(new) {
+        return Err(RenameError::InvalidName(new.to_string()));
+    }
+    let names = kind.names(fsm);
+    if !names.iter().any(|n| n == old) {
+        return Err(RenameError::NotFound { kind, name: old.to_string() });
+    }
+    if old != new && names.iter().any(|n| n == new) {
+        return Err(RenameError::AlreadyExists { kind, name: new.to_string() });
+    }
+    Ok(match kind {
+        RenameKind::State => rename_state(source, fsm, old, new),
+        RenameKind::Event => rename_event(source, fsm, old, new),
+    })
+}
+
+/// Rename state `old` to `new` in the declarations, transitions, initial state, choice
+/// branches and notes of `fsm` (timers refer to events, not states)
+pub fn rename_state(source: &str, fsm: &FsmDefinition, old: &str, new: &str) -> String {
+    let mut renamed = source.to_string();
+    for span in state_references(source, fsm).into_iter().filter(|span| &source[span.clone()] == old).rev() {
+        renamed.replace_range(span, new);
+    }
+    renamed
+}
+
+/// Byte ranges of every state name in the block of `fsm`, in source order (none if
+/// the block cannot be located)
+pub(super) fn state_references(source: &str, fsm: &FsmDefinition) -> Vec<Range<usize>> {
+    let Ok(pairs) = FsmParser::parse(Rule::file, source) else {
+        return Vec::new();
+    };
+    let Some(block) = pairs.flat_map(|p| p.into_inner()).find(|p| is_fsm_block(p, fsm)) else {
+        return Vec::new();
+    };
+    let mut spans: Vec<Range<usize>> = block.into_inner().flatten().filter_map(state_reference).collect();
+    spans.sort_by_key(|span| span.start);
+    spans.dedup();
+    spans
+}
+
+/// Whether `name` parses as a state or event name
+pub fn is_identifier(name: &str) -> bool {
+    FsmParser::parse(Rule::identifier, name).is_ok_and(|mut p| p.next().is_some_and(|p| p.as_str() == name))
+}
+
+/// Declare `state name` after the last state of `fsm`. `None` if the block cannot be
+/// located or has no line to add it on.
+pub fn add_state(source: &str, fsm: &FsmDefinition, name: &str) -> Option<String> {
+    insert_item(source, fsm, Section::State, &format!("state {name}"))
+}
+
+/// Add the `events { ... }` block `block` after the last events block of `fsm`, or
+/// after its header if it has none
+pub fn add_events_block(source: &str, fsm: &FsmDefinition, block: &str) -> Option<String> {
+    insert_item(source, fsm, Section::Events, block)
+}
+
+/// Add `from --> to : label` after the last transition of `fsm`
+pub fn add_transition(source: &str, fsm: &FsmDefinition, from: &str, to: &str, edit: &LabelEdit) -> Option<String> {
+    let label = edit.label();
+    let line = match label.is_empty() {
+        true => format!("{from} --> {to}"),
+        false => format!("{from} --> {to} : {label}"),
+    };
+    insert_item(source, fsm, Section::Transition, &line)
+}
+
+/// Point the unguarded `[*] -->` line of `fsm` at `state` (keeping its initial
+/// actions), or add one after the header and timers if there is none
+pub fn set_initial_state(source: &str, fsm: &FsmDefinition, state: &str) -> Option<String> {
+    let (items, _) = fsm_block(source, fsm)?;
+    let Some(initial) = items.iter().find(|item| item.rule == Rule::initial_state && item.name.is_some()) else {
+        return insert_item(source, fsm, Section::Initial, &format!("[*] --> {state}"));
+    };
+    let mut edited = source.to_string();
+    edited.replace_range(initial.name.clone()?, state);
+    Some(edited)
+}
+
+/// Add `entry / actions` to the body of `state`'s first declaration with a body, after
+/// its entry actions; a state declared without a body gets one, and an undeclared
+/// state a `state` block after the last state. `None` if the block cannot be located.
+pub fn add_entry_action(source: &str, fsm: &FsmDefinition, state: &str, actions: &str) -> Option<String> {
+    let line = format!("entry / {actions}");
+    let declarations: Vec<Pair<Rule>> = fsm_pair(source, fsm)?
+        .into_inner()
+        .flatten()
+        .filter(|p| matches!(p.as_rule(), Rule::state_simple | Rule::state_with_body))
+        .filter(|p| state_reference(p.clone()).is_some_and(|name| source[name] == *state))
+        .collect();
+    let lines = split_lines(source);
+    let indent_of = |offset: usize| split_indent(lines[line_at(source, offset) - 1].0).0.to_string();
+
+    if let Some(declaration) = declarations.iter().find(|p| p.as_rule() == Rule::state_with_body) {
+        let span = declaration.as_span();
+        let body: Vec<Pair<Rule>> =
+            declaration.clone().into_inner().filter(|p| matches!(p.as_rule(), Rule::state_body_item | Rule::doc_comment)).collect();
+        let close = span.end() - 1;
+        let open = span.start() + source[span.start()..body.first().map_or(close, |p| p.as_span().start())].rfind('{')?;
+        let mut edited = source.to_string();
+        // `state Idle { entry / a }` stays on one line
+        if line_at(source, open) == line_at(source, close) {
+            let space = if source[..close].ends_with(char::is_whitespace) { "" } else { " " };
+            edited.insert_str(close, &format!("{space}{line} "));
+            return Some(edited);
+        }
+        let is_entry = |p: &&Pair<Rule>| (*p).clone().into_inner().next().is_some_and(|i| i.as_rule() == Rule::entry_action);
+        let after = match body.iter().rev().find(is_entry) {
+            Some(entry) => line_at(source, last_token_end(entry)) - 1,
+            None => line_at(source, open) - 1,
+        };
+        let indent = match body.first() {
+            Some(item) => indent_of(item.as_span().start()),
+            None => format!("{}    ", indent_of(span.start())),
+        };
+        // The closing brace is on a later line, so the line after which it goes ends in a newline
+        let mut lines = lines.clone();
+        let text = format!("{indent}{line}");
+        let ending = lines[after].1;
+        lines.insert(after + 1, (&text, ending));
+        return Some(join_lines(&lines));
+    }
+
+    if let Some(declaration) = declarations.first() {
+        let indent = indent_of(declaration.as_span().start());
+        let end = last_token_end(declaration);
+        let mut edited = source.to_string();
+        edited.insert_str(end, &format!(" {{\n{indent}    {line}\n{indent}}}"));
+        return Some(edited);
+    }
+    insert_item(source, fsm, Section::State, &format!("state {state} {{\n    {line}\n}}"))
+}
+
+/// Byte range of `state`'s name in its first declaration, else where the block
+/// first refers to it (a transition, the initial state, a branch or a note)
+pub fn state_definition(source: &str, fsm: &FsmDefinition, state: &str) -> Option<Range<usize>> {
+    let references: Vec<(Rule, Range<usize>)> = fsm_pair(source, fsm)?
+        .into_inner()
+        .flatten()
+        .filter_map(|p| Some((p.as_rule(), state_reference(p)?)))
+        .filter(|(_, name)| source[name.clone()] == *state)
+        .collect();
+    let declaration = references.iter().find(|(rule, _)| matches!(rule, Rule::state_simple | Rule::state_with_body));
+    declaration.or(references.first()).map(|(_, name)| name.clone())
+}
+
+/// Remove the declarations of `states` from `fsm` with every transition, initial
+/// state line, choice branch and note that refers to them (a choice left without
+/// branches goes too). `None` if the block cannot be located.
+pub fn delete_states(source: &str, fsm: &FsmDefinition, states: &[String]) -> Option<String> {
+    let (items, _) = fsm_block(source, fsm)?;
+    let mut doomed = Vec::new();
+    for item in &items {
+        let hits: Vec<usize> = item.states.iter().filter(|(name, _)| states.contains(name)).map(|(_, line)| *line).collect();
+        // A kept state only loses the lines of the grouped transitions into deleted ones
+        let kept_state = item.rule == Rule::state_with_body && !item.states.first().is_some_and(|(name, _)| states.contains(name));
+        if kept_state {
+            doomed.extend(hits.into_iter().filter(|line| item.lines.start < *line && *line < item.lines.end));
+        } else if item.rule == Rule::choice_def && hits.len() < item.states.len() {
+            doomed.extend(hits);
+        } else if !hits.is_empty() {
+            doomed.extend(item.lines.start..=item.lines.end);
+        }
+    }
+    Some(remove_lines(source, &doomed))
+}
+
+/// Add `@tag(tag)` to each of `states` that does not have it yet, on its first
+/// declaration (a state without one gets a `state` line)
+pub fn tag_states(source: &str, fsm: &FsmDefinition, states: &[String], tag: &str) -> Option<String> {
+    let annotation = format!("@tag({tag})");
+    annotate_states(source, fsm, states, &annotation, |declarations| {
+        if declarations.iter().any(|d| d.tags.iter().any(|t| t == tag)) {
+            return Vec::new();
+        }
+        vec![(declarations[0].name_end..declarations[0].name_end, format!(" {annotation}"))]
+    })
+}
+
+/// Set the `#color(...)` of each of `states` to `color`, replacing the color of every
+/// declaration that has one, else adding it to the first
+pub fn color_states(source: &str, fsm: &FsmDefinition, states: &[String], color: &str) -> Option<String> {
+    let annotation = format!("#color({color})");
+    annotate_states(source, fsm, states, &annotation, |declarations| {
+        let replaced: Vec<_> = declarations.iter().filter_map(|d| d.color.clone()).map(|span| (span, color.to_string())).collect();
+        if !replaced.is_empty() {
+            return replaced;
+        }
+        vec![(declarations[0].name_end..declarations[0].name_end, format!(" {annotation}"))]
+    })
+}
+
+/// Why `extract_states` refused
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum ExtractError {
+    #[error("no states selected")]
+    NothingSelected,
+    #[error("'{0}' is not a valid name")]
+    InvalidName(String),
+    #[error("state '{0}' not found")]
+    NotFound(String),
+    #[error("a state or FSM named '{0}' already exists")]
+    AlreadyExists(String),
+    #[error("the FSM's block could not be located")]
+    NoBlock,
+}
+
+/// Group `states` into a composite state `name`. The DSL has no nested state blocks,
+/// so the group becomes an `fsm name { ... }` block after the block of `fsm`, holding
+/// their declarations, the transitions between them and their notes, and a `state
+/// name` takes their place. Transitions crossing the boundary keep their other end
+/// and now start or end at `name` (identical ones merge). The new block starts in
+/// the FSM's initial state if it moves, else in the first state a transition from
+/// outside enters, else in the first moved state.
+pub fn extract_states(source: &str, fsm: &FsmDefinition, states: &[String], name: &str) -> Result<String, ExtractError> {
+    if states.is_empty() {
+        return Err(ExtractError::NothingSelected);
+    }
+    if !is_identifier(name) {
+        return Err(ExtractError::InvalidName(name.to_string()));
+    }
+    if let Some(missing) = states.iter().find(|s| !fsm.states.iter().any(|state| &state.name == *s)) {
+        return Err(ExtractError::NotFound(missing.clone()));
+    }
+    if fsm.states.iter().any(|s| s.name == name) || fsm_names(source).iter().any(|n| n == name) {
+        return Err(ExtractError::AlreadyExists(name.to_string()));
+    }
+    let (items, _) = fsm_block(source, fsm).ok_or(ExtractError::NoBlock)?;
+    let selected = |state: &str| states.iter().any(|s| s == state);
+    let moves = |item: &BodyItem| match item.rule {
+        Rule::state_simple | Rule::state_with_body | Rule::note_def => item.states.iter().any(|(s, _)| selected(s)),
+        // Both ends inside (`[*]` and `*` ends are outside)
+        Rule::transition => item.states.len() == 2 && item.states.iter().all(|(s, _)| selected(s)),
+        _ => false,
+    };
+    let moved: Vec<&BodyItem> = items.iter().filter(|item| moves(item)).collect();
+    let entry = fsm
+        .initial_state()
+        .map(str::to_string)
+        .filter(|s| selected(s))
+        .or_else(|| fsm.transitions.iter().find(|t| selected(&t.target) && !selected(&t.source)).map(|t| t.target.clone()))
+        .or_else(|| fsm.states.iter().find(|s| selected(&s.name)).map(|s| s.name.clone()))
+        .ok_or(ExtractError::NothingSelected)?;
+
+    // The new block, its items grouped as `format` prints them
+    let lines = split_lines(source);
+    let text = |item: &BodyItem| lines[item.lines.start..=item.lines.end].iter().map(|(l, _)| *l).collect::<Vec<_>>();
+    let indent = moved.first().map_or("    ", |item| split_indent(lines[item.lines.start].0).0);
+    let mut block = vec![format!("{indent}[*] --> {entry}")];
+    for rules in [&[Rule::state_simple, Rule::state_with_body][..], &[Rule::transition], &[Rule::note_def]] {
+        let mut previous_multiline = true;
+        for item in moved.iter().filter(|item| rules.contains(&item.rule)) {
+            let item_lines = text(item);
+            if previous_multiline || item_lines.len() > 1 {
+                block.push(String::new());
+            }
+            previous_multiline = item_lines.len() > 1;
+            block.extend(item_lines.into_iter().map(str::to_string));
+        }
+    }
+
+    // `state name` where the first moved declaration was, the moved lines gone
+    let placeholder = moved.iter().find(|item| matches!(item.rule, Rule::state_simple | Rule::state_with_body)).map(|item| item.lines.start);
+    let mut doomed: Vec<usize> = moved.iter().flat_map(|item| item.lines.start..=item.lines.end).collect();
+    let mut edited = match placeholder {
+        Some(at) => {
+            let mut lines = lines.clone();
+            let line = format!("{indent}state {name}");
+            lines.insert(at, (&line, lines[at].1));
+            doomed.iter_mut().filter(|line| **line >= at).for_each(|line| *line += 1);
+            remove_lines(&join_lines(&lines), &doomed)
+        }
+        None => remove_lines(source, &doomed),
+    };
+    for state in states {
+        edited = rename_state(&edited, fsm, state, name);
+    }
+    if placeholder.is_none() {
+        edited = insert_item(&edited, fsm, Section::State, &format!("state {name}")).ok_or(ExtractError::NoBlock)?;
+    }
+
+    // Boundary transitions that became the same line
+    let (items, _) = fsm_block(&edited, fsm).ok_or(ExtractError::NoBlock)?;
+    let lines = split_lines(&edited);
+    let mut seen = Vec::new();
+    let mut duplicates = Vec::new();
+    for item in items.iter().filter(|item| item.rule == Rule::transition) {
+        let line = lines[item.lines.start].0.trim();
+        match seen.contains(&line) {
+            true => duplicates.push(item.lines.start),
+            false => seen.push(line),
+        }
+    }
+    let edited = remove_lines(&edited, &duplicates);
+
+    // The new block after the closing brace of the FSM's block
+    let closing = fsm_lines(&edited, fsm).end;
+    let at: usize = edited.split_inclusive('\n').take(closing).map(str::len).sum();
+    let newline = if edited[..at].ends_with('\n') { "" } else { "\n" };
+    let mut extracted = edited;
+    extracted.insert_str(at, &format!("{newline}\nfsm {name} {{\n{}\n}}\n", block.join("\n")));
+    Ok(extracted)
+}
+
+/// Parts of an FSM body in the order `format` prints them, where added lines go
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Section {
+    Header,
+    Events,
+    Timer,
+    Guard,
+    Initial,
+    State,
+    Transition,
+}
+
+impl Section {
+    fn of(rule: Rule) -> Option<Self> {
+        match rule {
+            Rule::fsm_description | Rule::meta_def | Rule::file_name_def | Rule::direction_def => Some(Section::Header),
+            Rule::events_def => Some(Section::Events),
+            Rule::timer_def => Some(Section::Timer),
+            Rule::guard_def => Some(Section::Guard),
+            Rule::initial_state => Some(Section::Initial),
+            Rule::state_with_body | Rule::state_simple => Some(Section::State),
+            Rule::transition => Some(Section::Transition),
+            _ => None,
+        }
+    }
+}
+
+/// An item of an FSM body
+struct BodyItem {
+    rule: Rule,
+    /// 0-based lines of its first token (doc comments included) and its last
+    lines: Range<usize>,
+    /// Byte range of the state an unguarded initial state line names
+    name: Option<Range<usize>>,
+    /// States it refers to, each with the 0-based line of the reference
+    states: Vec<(String, usize)>,
+}
+
+/// The `fsm_definition` of `fsm` in `source`
+fn fsm_pair<'a>(source: &'a str, fsm: &FsmDefinition) -> Option<Pair<'a, Rule>> {
+    FsmParser::parse(Rule::file, source).ok()?.flat_map(|p| p.into_inner()).find(|p| is_fsm_block(p, fsm))
+}
+
+/// The items of `fsm`'s block, and the 0-based lines of its name and closing brace
+fn fsm_block(source: &str, fsm: &FsmDefinition) -> Option<(Vec<BodyItem>, Range<usize>)> {
+    let line = |pos: pest::Position| pos.line_col().0 - 1;
+    let block = fsm_pair(source, fsm)?;
+    let header = block.clone().into_inner().find(|p| p.as_rule() == Rule::identifier)?;
+    let block_lines = line(header.as_span().start_pos())..line(block.as_span().end_pos());
+    let body = block.into_inner().find(|p| p.as_rule() == Rule::fsm_body)?;
+    let items = body
+        .into_inner()
+        .filter(|item| item.as_rule() == Rule::fsm_item)
+        .filter_map(|item| {
+            let inner = item.clone().into_inner().find(|p| p.as_rule() != Rule::doc_comment)?;
+            // Spans run on over the whitespace and comments after optional parts; a body ends at its `}`
+            let end = match inner.as_rule() {
+                Rule::state_with_body | Rule::choice_def | Rule::events_def => inner.as_span().end_pos(),
+                _ => pest::Position::new(source, last_token_end(&inner))?,
+            };
+            let lines = line(item.as_span().start_pos())..line(end);
+            // `Idle <-- [*]` and `[*] -.-> Idle` declare initial transitions too
+            let from_start = |p: &Pair<Rule>| p.clone().into_inner().any(|p| p.as_rule() == Rule::source && p.as_str() == "[*]");
+            let rule = match inner.as_rule() {
+                Rule::transition if from_start(&inner) => Rule::initial_state,
+                rule => rule,
+            };
+            let guarded = inner.clone().into_inner().flatten().any(|p| p.as_rule() == Rule::guard);
+            let name = inner.clone().into_inner().flatten().find(|p| p.as_rule() == Rule::identifier).filter(|_| rule == Rule::initial_state && !guarded);
+            let states = std::iter::once(inner.clone())
+                .chain(inner.clone().into_inner().flatten())
+                .filter_map(state_reference)
+                .map(|span| (source[span.clone()].to_string(), line_at(source, span.start) - 1))
+                .collect();
+            Some(BodyItem {
+                rule,
+                lines,
+                name: name.map(|n| n.as_span().start()..n.as_span().end()),
+                states,
+            })
+        })
+        .collect();
+    Some((items, block_lines))
+}
+
+/// Add `text` on lines of its own after the last item of `section`, else of the
+/// closest section before it, else at the top of the block, indented like that item
+fn insert_item(source: &str, fsm: &FsmDefinition, section: Section, text: &str) -> Option<String> {
+    let (items, block) = fsm_block(source, fsm)?;
+    let mut lines = split_lines(source);
+    let anchor = items.iter().filter(|item| Section::of(item.rule).is_some_and(|s| s <= section)).max_by_key(|item| {
+        // Latest section first, then the latest item in it
+        (Section::of(item.rule), item.lines.end)
+    });
+    let (after, indent) = match anchor {
+        Some(item) => (item.lines.end, split_indent(lines[item.lines.start].0).0.to_string()),
+        None => (block.start, format!("{}    ", split_indent(lines[block.start].0).0)),
+    };
+    // The new line must land inside the braces
+    if after >= block.end {
+        return None;
+    }
+    let ending = lines[after].1;
+    let line = text.lines().map(|line| format!("{indent}{line}")).collect::<Vec<_>>().join(if ending.is_empty() { "\n" } else { ending });
+    lines.insert(after + 1, (&line, ending));
+    Some(join_lines(&lines))
+}
+
+/// A `state` declaration of a group being annotated
+#[derive(Clone)]
+struct Declaration {
+    name: String,
+    /// Byte offset just after the state's name
+    name_end: usize,
+    /// Byte range of the color inside `#color(...)`
+    color: Option<Range<usize>>,
+    tags: Vec<String>,
+}
+
+/// Apply `edits(declarations)` (byte ranges and their replacements) to the
+/// declarations of each of `states`; a state without any gets `state <name>
+/// <annotation>` after the last state
+fn annotate_states(
+    source: &str,
+    fsm: &FsmDefinition,
+    states: &[String],
+    annotation: &str,
+    edits: impl Fn(&[Declaration]) -> Vec<(Range<usize>, String)>,
+) -> Option<String> {
+    let declarations: Vec<Declaration> = fsm_pair(source, fsm)?
+        .into_inner()
+        .flatten()
+        .filter(|p| matches!(p.as_rule(), Rule::state_simple | Rule::state_with_body))
+        .filter_map(|pair| {
+            let name = state_reference(pair.clone())?;
+            let annotations: Vec<Pair<Rule>> = pair.into_inner().collect();
+            let color = annotations
+                .iter()
+                .filter(|p| p.as_rule() == Rule::color_annotation)
+                .find_map(|p| p.clone().into_inner().next())
+                .map(|value| value.as_span().start()..value.as_span().end());
+            let tags = annotations
+                .iter()
+                .filter(|p| p.as_rule() == Rule::tag_annotation)
+                .flat_map(|p| p.clone().into_inner().map(|tag| tag.as_str().to_string()))
+                .collect();
+            Some(Declaration { name: source[name.clone()].to_string(), name_end: name.end, color, tags })
+        })
+        .collect();
+
+    let mut replacements = Vec::new();
+    let mut undeclared = Vec::new();
+    for state in states {
+        let own: Vec<Declaration> = declarations.iter().filter(|d| &d.name == state).cloned().collect();
+        match own.is_empty() {
+            true => undeclared.push(state),
+            false => replacements.extend(edits(&own)),
+        }
+    }
+    replacements.sort_by_key(|(span, _)| span.start);
+    let mut edited = source.to_string();
+    for (span, text) in replacements.into_iter().rev() {
+        edited.replace_range(span, &text);
+    }
+    for state in undeclared {
+        edited = insert_item(&edited, fsm, Section::State, &format!("state {state} {annotation}"))?;
+    }
+    Some(edited)
+}
+
+/// Names of the FSMs declared in `source`
+fn fsm_names(source: &str) -> Vec<String> {
+    let Ok(pairs) = FsmParser::parse(Rule::file, source) else {
+        return Vec::new();
+    };
+    pairs
+        .flat_map(|p| p.into_inner())
+        .filter(|p| p.as_rule() == Rule::fsm_definition)
+        .filter_map(|p| p.into_inner().find(|p| p.as_rule() == Rule::identifier))
+        .map(|name| name.as_str().to_string())
+        .collect()
+}
+
+fn is_fsm_block(pair: &Pair<Rule>, fsm: &FsmDefinition) -> bool {
+    pair.as_rule() == Rule::fsm_definition && Some(line_of(pair)) == fsm.line
+}
+
+/// Byte range of the state name a declaration, transition end, initial state,
+/// choice branch or note refers to
+fn state_reference(pair: Pair<Rule>) -> Option<Range<usize>> {
+    fn identifier(pair: Pair<Rule>) -> Option<Pair<Rule>> {
+        pair.into_inner().find(|p| p.as_rule() == Rule::identifier)
+    }
+    let name = match pair.as_rule() {
+        Rule::state_simple | Rule::state_with_body => {
+            let name = pair.into_inner().find(|p| p.as_rule() != Rule::final_keyword)?;
+            match name.as_rule() {
+                Rule::state_alias => identifier(name)?,
+                _ => name,
+            }
+        }
+        Rule::source | Rule::target | Rule::initial_state | Rule::choice_branch | Rule::note_def => identifier(pair)?,
+        _ => return None,
+    };
+    Some(name.as_span().start()..name.as_span().end())
+}
+
+/// Lines without their terminators, paired with the terminator
+fn split_lines(source: &str) -> Vec<(&str, &str)> {
+    source
+        .split_inclusive('\n')
+        .map(|line| {
+            let content = line.trim_end_matches(['\r', '\n']);
+            (content, &line[content.len()..])
+        })
+        .collect()
+}
+
+fn join_lines(lines: &[(&str, &str)]) -> String {
+    lines.iter().flat_map(|(content, end)| [*content, *end]).collect()
+}
+
+/// `source` without its 0-based `doomed` lines, or the blank line a removal would
+/// leave doubled or before a closing brace
+fn remove_lines(source: &str, doomed: &[usize]) -> String {
+    let is_blank = |line: &(&str, &str)| line.0.trim().is_empty();
+    let mut kept: Vec<(&str, &str)> = Vec::new();
+    let mut removed = false;
+    for (i, line) in split_lines(source).into_iter().enumerate() {
+        if doomed.contains(&i) {
+            removed = true;
+            continue;
+        }
+        if removed && kept.last().is_some_and(is_blank) {
+            if is_blank(&line) {
+                continue;
+            }
+            if line.0.trim_start().starts_with('}') {
+                kept.pop();
+            }
+        }
+        removed = false;
+        kept.push(line);
+    }
+    join_lines(&kept)
+}
+
+fn split_indent(line: &str) -> (&str, &str) {
+    line.split_at(line.len() - line.trim_start().len())
+}
+
+/// 0-based line range of the `fsm` block in `source` (everything if it cannot be located)
+fn fsm_lines(source: &str, fsm: &FsmDefinition) -> Range<usize> {
+    let everything = 0..split_lines(source).len();
+    let Ok(pairs) = FsmParser::parse(Rule::file, source) else {
+        return everything;
+    };
+    pairs
+        .flat_map(|p| p.into_inner())
+        .find(|p| is_fsm_block(p, fsm))
+        .map_or(everything, |p| {
+            let (start, end) = (p.as_span().start_pos(), p.as_span().end_pos());
+            start.line_col().0 - 1..end.line_col().0
+        })
+}
+
+/// The recorded line of `transition` if it still declares it, otherwise the first
+/// line in `range` declaring the same source, target and label
+fn find_transition_line(lines: &[(&str, &str)], range: Range<usize>, transition: &Transition) -> Option<usize> {
+    let declares = |line: usize| {
+        lines.get(line).is_some_and(|(text, _)| {
+            FsmParser::parse(Rule::transition, text.trim())
+                .ok()
+                .and_then(|mut pairs| pairs.next())
+                .and_then(|pair| parse_transition(pair).ok())
+                .is_some_and(|found| same_transition(&found, transition))
+        })
+    };
+    let recorded = transition.line.map(|line| line - 1).filter(|&line| declares(line));
+    recorded.or_else(|| range.into_iter().find(|&line| declares(line)))
+}
+
+fn same_transition(a: &Transition, b: &Transition) -> bool {
+    let calls = |t: &Transition| t.actions.iter().map(action_call).collect::<Vec<_>>();
+    a.source == b.source && a.target == b.target && a.label() == b.label() && calls(a) == calls(b)
+}
+
+/// Byte range of the event a transition, internal transition or timer line reacts to
+fn event_span(body: &str) -> Option<Range<usize>> {
+    let span = |pair: pest::iterators::Pair<Rule>| pair.as_span().start()..pair.as_span().end();
+    if let Ok(mut pairs) = FsmParser::parse(Rule::transition, body) {
+        let label = pairs.next()?.into_inner().find(|p| p.as_rule() == Rule::transition_label)?;
+        let event = label.into_inner().find(|p| p.as_rule() == Rule::event)?;
+        return event.into_inner().next().map(span);
+    }
+    if let Ok(mut pairs) = FsmParser::parse(Rule::grouped_transition, body) {
+        let event = pairs.next()?.into_inner().find(|p| p.as_rule() == Rule::event)?;
+        return event.into_inner().next().map(span);
+    }
+    if let Ok(mut pairs) = FsmParser::parse(Rule::timer_def, body) {
+        return pairs.next()?.into_inner().filter(|p| p.as_rule() == Rule::identifier).nth(1).map(span);
+    }
+    [Rule::internal_transition, Rule::internal_action]
+        .into_iter()
+        .find_map(|rule| FsmParser::parse(rule, body).ok())
+        .and_then(|mut pairs| pairs.next()?.into_inner().next())
+        .map(span)
+}
@@ -0,0 +1,306 @@
+//! Event Import
+//! Seeds an FSM's `events` block from an event list that already exists as a C
+//! `enum { ... }` (`typedef`, `enum class` and fixed underlying types included) or a
+//! Rust `enum`. This is not a C or Rust parser: comments, preprocessor lines and
+//! attributes are blanked out, then every `enum` body is split at its top-level
+//! commas. Enumerators inside `#ifdef` blocks are all kept, since the conditions
+//! cannot be evaluated. Names become the DSL's snake_case, after dropping a prefix that
+//! ends in `_` and is shared by the whole enum (`EV_START`, `EV_STOP` → `start`,
+//! `stop`); integer values are carried over as the events' discriminants.
+
+use std::collections::HashSet;
+
+use thiserror::Error;
+
+use super::edit::is_identifier;
+use crate::fsm::naming::to_snake_case;
+use crate::fsm::FsmDefinition;
+
+/// Why no events could be imported
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EventImportError {
+    #[error("no enum found")]
+    NoEnum,
+    #[error("no enum named '{0}' (found: {1})")]
+    EnumNotFound(String, String),
+}
+
+/// An `enum` of the imported file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceEnum {
+    /// Its name, or the `typedef` name of an anonymous C enum
+    pub name: Option<String>,
+    pub variants: Vec<EnumVariant>,
+}
+
+/// One enumerator or variant, as written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumVariant {
+    pub name: String,
+    /// Explicit value, if it is an integer literal (expressions are dropped)
+    pub value: Option<u32>,
+    /// 1-based line in the imported file
+    pub line: usize,
+}
+
+/// An event to declare
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedEvent {
+    /// DSL name
+    pub name: String,
+    /// Enumerator it comes from
+    pub source_name: String,
+    pub value: Option<u32>,
+}
+
+/// Events to declare in an FSM, and the enumerators left out
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventImport {
+    pub events: Vec<ImportedEvent>,
+    /// Why each left out enumerator was left out
+    pub conflicts: Vec<String>,
+}
+
+impl EventImport {
+    /// `events { ... }` block declaring [`EventImport::events`], after a comment naming
+    /// `origin`; `None` if there is nothing to declare
+    pub fn events_block(&self, origin: &str) -> Option<String> {
+        if self.events.is_empty() {
+            return None;
+        }
+        let mut block = format!("// Imported from {origin}\nevents {{\n");
+        for event in &self.events {
+            match event.value {
+                Some(value) => block.push_str(&format!("    {} = {}\n", event.name, value)),
+                None => block.push_str(&format!("    {}\n", event.name)),
+            }
+        }
+        block.push('}');
+        Some(block)
+    }
+}
+
+/// Events of the enums of `text` to declare in `fsm`: all of them, or only those of the
+/// enum called `enum_name`. An event the FSM already declares, or that two enumerators
+/// would both become, is reported instead of declared twice.
+pub fn import_events(text: &str, enum_name: Option<&str>, fsm: &FsmDefinition) -> Result<EventImport, EventImportError> {
+    let enums = find_enums(text);
+    if enums.is_empty() {
+        return Err(EventImportError::NoEnum);
+    }
+    let enums: Vec<&SourceEnum> = match enum_name {
+        None => enums.iter().collect(),
+        Some(wanted) => match enums.iter().find(|e| e.name.as_deref() == Some(wanted)) {
+            Some(found) => vec![found],
+            None => {
+                let names: Vec<&str> = enums.iter().map(|e| e.name.as_deref().unwrap_or("(anonymous)")).collect();
+                return Err(EventImportError::EnumNotFound(wanted.to_string(), names.join(", ")));
+            }
+        },
+    };
+
+    let mut import = EventImport::default();
+    let mut taken: HashSet<String> = HashSet::new();
+    for source_enum in enums {
+        let prefix = common_prefix(&source_enum.variants);
+        for variant in &source_enum.variants {
+            let name = to_snake_case(&variant.name[prefix..]);
+            let described = match name == variant.name {
+                true => format!("'{}' (line {})", variant.name, variant.line),
+                false => format!("'{}' (line {}, as '{}')", variant.name, variant.line, name),
+            };
+            if !is_identifier(&name) {
+                import.conflicts.push(format!("{described} is not a valid event name"));
+            } else if fsm.events.iter().any(|e| e.name == name) {
+                import.conflicts.push(format!("{described} is already declared in {}", fsm.name));
+            } else if !taken.insert(name.clone()) {
+                import.conflicts.push(format!("{described} is imported already"));
+            } else {
+                import.events.push(ImportedEvent { name, source_name: variant.name.clone(), value: variant.value });
+            }
+        }
+    }
+    Ok(import)
+}
+
+/// Every `enum` of the C header or Rust file `text`, in file order
+pub fn find_enums(text: &str) -> Vec<SourceEnum> {
+    let code = blank_noise(text);
+    let bytes = code.as_bytes();
+    let mut enums = Vec::new();
+    let mut search = 0;
+    while let Some(found) = code[search..].find("enum") {
+        let start = search + found;
+        search = start + "enum".len();
+        let boundary = |i: Option<&u8>| !i.is_some_and(|c| c.is_ascii_alphanumeric() || *c == b'_');
+        if !boundary(start.checked_sub(1).and_then(|i| bytes.get(i))) || !boundary(bytes.get(search)) {
+            continue;
+        }
+        // `enum [class] [Name] [: type] {`; a `;` first is a declaration, not a definition
+        let Some(open) = code[search..].find(['{', ';']).map(|i| search + i).filter(|&i| bytes[i] == b'{') else {
+            continue;
+        };
+        // Not `enum` in a parameter list or a generic enum
+        if !code[search..open].chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':' || c.is_whitespace()) {
+            continue;
+        }
+        let Some(close) = matching_brace(bytes, open) else {
+            break;
+        };
+        let head = code[search..open].split(':').next().unwrap_or_default();
+        let mut name = head.split_whitespace().rfind(|word| !matches!(*word, "class" | "struct")).map(str::to_string);
+        if name.is_none() {
+            // `typedef enum { ... } name_t;`
+            let tail = code[close + 1..].split(';').next().unwrap_or_default();
+            name = tail.split([',', ' ', '\t', '\n', '\r']).rfind(|word| is_c_identifier(word)).map(str::to_string);
+        }
+        let variants = split_top_level(&code[open + 1..close])
+            .into_iter()
+            .filter_map(|(offset, entry)| variant(text, open + 1 + offset, entry))
+            .collect();
+        enums.push(SourceEnum { name, variants });
+        search = close + 1;
+    }
+    enums
+}
+
+/// `text` with comments, string literals, preprocessor lines and `#[...]` attributes
+/// replaced by spaces, so that byte offsets and lines stay the same
+fn blank_noise(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = bytes.to_vec();
+    let blank = |out: &mut Vec<u8>, range: std::ops::Range<usize>| {
+        for byte in &mut out[range] {
+            if *byte != b'\n' {
+                *byte = b' ';
+            }
+        }
+    };
+    let mut i = 0;
+    let mut line_start = true;
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        let end = if rest.starts_with(b"//") {
+            i + rest.iter().position(|&c| c == b'\n').unwrap_or(rest.len())
+        } else if rest.starts_with(b"/*") {
+            i + 2 + rest[2..].windows(2).position(|w| w == b"*/").map_or(rest.len() - 2, |p| p + 2)
+        } else if rest[0] == b'"' {
+            let mut j = 1;
+            while j < rest.len() && rest[j] != b'"' && rest[j] != b'\n' {
+                j += if rest[j] == b'\\' { 2 } else { 1 };
+            }
+            i + (j + 1).min(rest.len())
+        } else if rest.starts_with(b"#[") || rest.starts_with(b"#![") {
+            let open = i + rest.iter().position(|&c| c == b'[').unwrap_or(0);
+            matching_brace(bytes, open).map_or(bytes.len(), |close| close + 1)
+        } else if rest[0] == b'#' && line_start {
+            // A directive runs to the end of its line, and on after a trailing `\`
+            let mut j = i;
+            loop {
+                let eol = j + bytes[j..].iter().position(|&c| c == b'\n').unwrap_or(bytes.len() - j);
+                if eol > 0 && eol < bytes.len() && text[..eol].trim_end_matches('\r').ends_with('\\') {
+                    j = eol + 1;
+                } else {
+                    break eol;
+                }
+            }
+        } else {
+            if !rest[0].is_ascii_whitespace() {
+                line_start = false;
+            } else if rest[0] == b'\n' {
+                line_start = true;
+            }
+            i += 1;
+            continue;
+        };
+        blank(&mut out, i..end);
+        i = end.max(i + 1);
+        line_start = false;
+    }
+    // Whole characters were replaced, each byte by a space
+    String::from_utf8(out).unwrap_or_default()
+}
+
+/// Index of the bracket closing the one at `open`
+fn matching_brace(bytes: &[u8], open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, &c) in bytes.iter().enumerate().skip(open) {
+        match c {
+            b'{' | b'(' | b'[' => depth += 1,
+            b'}' | b')' | b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The comma separated entries of an enum body, with their offsets in it
+fn split_top_level(body: &str) -> Vec<(usize, &str)> {
+    let mut entries = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    for (i, c) in body.char_indices() {
+        match c {
+            '{' | '(' | '[' => depth += 1,
+            '}' | ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                entries.push((start, &body[start..i]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    entries.push((start, &body[start..]));
+    entries
+}
+
+/// The enumerator of `entry` (`NAME`, `NAME = 3`, `Name(u8)`, `Name { .. }`), which
+/// starts at byte `offset` of `text`
+fn variant(text: &str, offset: usize, entry: &str) -> Option<EnumVariant> {
+    let leading = entry.len() - entry.trim_start().len();
+    let entry = entry.trim();
+    let name_len = entry.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(entry.len());
+    let name = &entry[..name_len];
+    if !is_c_identifier(name) {
+        return None;
+    }
+    let value = entry[name_len..].trim_start().strip_prefix('=').and_then(|value| parse_integer(value.trim()));
+    let line = text[..offset + leading].matches('\n').count() + 1;
+    Some(EnumVariant { name: name.to_string(), value, line })
+}
+
+/// A decimal or `0x` literal, with any C integer suffix
+fn parse_integer(value: &str) -> Option<u32> {
+    let value = value.trim_end_matches(['u', 'U', 'l', 'L']);
+    match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+fn is_c_identifier(word: &str) -> bool {
+    word.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') && word.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Length of the longest prefix ending in `_` that every variant shares and that
+/// leaves each of them a name starting with a letter
+fn common_prefix(variants: &[EnumVariant]) -> usize {
+    let [first, rest @ ..] = variants else {
+        return 0;
+    };
+    if rest.is_empty() {
+        return 0;
+    }
+    let shared = rest.iter().fold(first.name.len(), |len, v| {
+        first.name.bytes().zip(v.name.bytes()).take(len).take_while(|(a, b)| a == b).count()
+    });
+    first.name[..shared]
+        .rmatch_indices('_')
+        .map(|(i, _)| i + 1)
+        .find(|&len| variants.iter().all(|v| v.name[len..].starts_with(|c: char| c.is_ascii_alphabetic())))
+        .unwrap_or(0)
+}
@@ -0,0 +1,515 @@
+//! DSL Printer
+//! Writes FSM definitions back as DSL text, comments included, so features that
+//! generate or rewrite whole definitions keep what the user wrote around them.
+//!
+//! Parsed items come out in source order, with comments on lines of their own back
+//! above the item they were above (comments inside a state or choice body come back
+//! below it); items built in code (no source line) follow in the order events,
+//! timers, `[*]`, states, transitions, choices, notes.
+//!
+//! Transitions come out flat (`Source --> Target : label` at fsm scope) unless
+//! [`FormatOptions`] asks for them grouped into their source state's block as
+//! `on event -> Target` lines.
+
+use super::edit::action_call;
+use crate::fsm::{
+    Action, ChoicePoint, Event, FsmDefinition, LayoutHint, NamedGuard, Note, NotePosition, State, StateType, Timer, TimerMode, Transition,
+    TransitionKind, TransitionStyle,
+};
+
+const INDENT: &str = "    ";
+
+/// Where the printer writes a state's outgoing transitions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TransitionLayout {
+    /// `Source --> Target : event [guard] / action` lines at fsm scope
+    #[default]
+    Flat,
+    /// `on event [guard] -> Target / action` lines in the source state's block.
+    /// Transitions the block form cannot hold (from `[*]` or `*`, local, without an
+    /// event, or with comments above them) stay flat.
+    Grouped,
+}
+
+/// How the printer lays out what it writes
+#[derive(Debug, Clone, Default)]
+pub struct FormatOptions {
+    pub transition_layout: TransitionLayout,
+}
+
+impl FormatOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_transition_layout(mut self, layout: TransitionLayout) -> Self {
+        self.transition_layout = layout;
+        self
+    }
+}
+
+/// Every FSM, separated by a blank line
+pub fn format_fsms(fsms: &[FsmDefinition]) -> String {
+    format_fsms_with_options(fsms, &FormatOptions::default())
+}
+
+/// [`format_fsms`] laid out as `options` asks
+pub fn format_fsms_with_options(fsms: &[FsmDefinition], options: &FormatOptions) -> String {
+    fsms.iter().map(|fsm| format_fsm_with_options(fsm, options)).collect::<Vec<_>>().join("\n")
+}
+
+/// One `fsm Name { ... }` block, ending in a newline
+pub fn format_fsm(fsm: &FsmDefinition) -> String {
+    format_fsm_with_options(fsm, &FormatOptions::default())
+}
+
+/// [`format_fsm`] laid out as `options` asks
+pub fn format_fsm_with_options(fsm: &FsmDefinition, options: &FormatOptions) -> String {
+    let mut out = String::new();
+    for comment in &fsm.leading_comments {
+        out.push_str(&comment_line(comment));
+        out.push('\n');
+    }
+    push_docs(&mut out, "", fsm.description.as_deref());
+    out.push_str(&with_comment(format!("fsm {} {{", fsm.name), fsm.trailing_comment.as_deref()));
+    out.push('\n');
+
+    let mut orphans = fsm.orphan_comments.iter().peekable();
+    let mut previous: Option<Block> = None;
+    for block in blocks(fsm, options) {
+        if previous.as_ref().is_some_and(|p| p.needs_gap_before(&block)) {
+            out.push('\n');
+        }
+        if let Some(line) = block.line {
+            while let Some(orphan) = orphans.next_if(|c| c.line < line) {
+                push_line(&mut out, INDENT, &comment_line(&orphan.text));
+            }
+        }
+        let mut lines = block.lines.clone();
+        // A comment on a one-line item's own line was written after it
+        if let (Some(line), Some(last)) = (block.line.filter(|_| !block.multiline), lines.last_mut()) {
+            while let Some(orphan) = orphans.next_if(|c| c.line == line) {
+                last.push(' ');
+                last.push_str(&comment_line(&orphan.text));
+            }
+        }
+        for line in &lines {
+            push_line(&mut out, INDENT, line);
+        }
+        previous = Some(block);
+    }
+    for orphan in orphans {
+        push_line(&mut out, INDENT, &comment_line(&orphan.text));
+    }
+    out.push_str("}\n");
+    for comment in &fsm.following_comments {
+        out.push_str(&comment_line(comment));
+        out.push('\n');
+    }
+    out
+}
+
+// ============================================================================
+// BLOCKS
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Header,
+    Events,
+    Timer,
+    Guard,
+    Initial,
+    State,
+    Transition,
+    Choice,
+    Note,
+}
+
+/// The lines of one item, relative to the body's indentation, with its comments
+struct Block {
+    line: Option<usize>,
+    section: Section,
+    lines: Vec<String>,
+    /// Spans more than one line besides its leading comments (e.g. a state body)
+    multiline: bool,
+}
+
+impl Block {
+    fn new(line: Option<usize>, section: Section, leading_comments: &[String]) -> Self {
+        Self {
+            line,
+            section,
+            lines: leading_comments.iter().map(|c| comment_line(c)).collect(),
+            multiline: false,
+        }
+    }
+
+    fn push(&mut self, line: String) {
+        self.lines.push(line);
+    }
+
+    /// Blank line between sections and around items with a body
+    fn needs_gap_before(&self, next: &Block) -> bool {
+        self.section != next.section || self.multiline || next.multiline
+    }
+}
+
+/// Every item of `fsm` in print order
+fn blocks(fsm: &FsmDefinition, options: &FormatOptions) -> Vec<Block> {
+    let mut blocks = Vec::new();
+
+    for (key, value) in &fsm.metadata {
+        let mut block = Block::new(None, Section::Header, &[]);
+        block.push(format!("meta {} = {}", key, quote(value)));
+        blocks.push(block);
+    }
+    if let Some(ref file_name) = fsm.file_name {
+        let mut block = Block::new(None, Section::Header, &[]);
+        block.push(format!("@file_name({})", quote(file_name)));
+        blocks.push(block);
+    }
+    if let Some(hint) = fsm.layout_hint {
+        let mut block = Block::new(None, Section::Header, &[]);
+        let direction = match hint {
+            LayoutHint::TB => "TB",
+            LayoutHint::BT => "BT",
+            LayoutHint::LR => "LR",
+            LayoutHint::RL => "RL",
+        };
+        block.push(format!("direction {}", direction));
+        blocks.push(block);
+    }
+    if !fsm.events.is_empty() {
+        blocks.push(events_block(fsm));
+    }
+    blocks.extend(fsm.timers.iter().map(timer_block));
+    blocks.extend(fsm.guards.iter().map(guard_block));
+    // Rejected extra `[*] -->` lines stay in the source until the user removes them
+    for initial in &fsm.initial_transitions {
+        let mut block = Block::new(initial.line, Section::Initial, &[]);
+        let mut label = Vec::new();
+        label.extend(initial.guard.as_ref().map(|g| format!("[{}]", g.expression)));
+        if !initial.actions.is_empty() {
+            label.push(format!("/ {}", action_list(&initial.actions)));
+        }
+        match label.is_empty() {
+            true => block.push(format!("[*] --> {}", initial.target)),
+            false => block.push(format!("[*] --> {} : {}", initial.target, label.join(" "))),
+        }
+        blocks.push(block);
+    }
+    let grouped = |t: &&Transition| options.transition_layout == TransitionLayout::Grouped && fits_state_block(t);
+    // States only mentioned in transitions, notes or `[*] -->` have no line of their own,
+    // unless their block holds grouped transitions
+    for state in &fsm.states {
+        let outgoing: Vec<&Transition> = fsm.transitions.iter().filter(grouped).filter(|t| t.source == state.name).collect();
+        if state.is_declared() || !outgoing.is_empty() {
+            blocks.push(state_block(state, &outgoing));
+        }
+    }
+    blocks.extend(fsm.transitions.iter().filter(|t| !grouped(t)).map(transition_block));
+    blocks.extend(fsm.choice_points.iter().map(choice_block));
+    blocks.extend(fsm.notes.iter().map(note_block));
+
+    // Source order; the header first and items without a line last
+    blocks.sort_by_key(|block| match (block.line, block.section) {
+        (Some(line), _) => line,
+        (None, Section::Header | Section::Events) => 0,
+        (None, _) => usize::MAX,
+    });
+    blocks
+}
+
+/// The `events { ... }` block, one declared event per line below its docs
+fn events_block(fsm: &FsmDefinition) -> Block {
+    let mut block = Block::new(fsm.events_line, Section::Events, &[]);
+    block.push("events {".to_string());
+    let mut docs = String::new();
+    for event in &fsm.events {
+        push_docs(&mut docs, INDENT, event.description.as_deref());
+        push_line(&mut docs, INDENT, &event_declaration(event));
+    }
+    block.lines.extend(docs.lines().map(str::to_string));
+    block.push("}".to_string());
+    block.multiline = true;
+    block
+}
+
+fn timer_block(timer: &Timer) -> Block {
+    let mut block = Block::new(timer.line, Section::Timer, &timer.leading_comments);
+    let mode = match timer.mode {
+        TimerMode::Periodic => " periodic",
+        TimerMode::OneShot => "",
+    };
+    let definition = format!("timer {} = {} -> {}{}", timer.name, timer.duration_ms, timer.event.name, mode);
+    block.push(with_comment(definition, timer.trailing_comment.as_deref()));
+    block
+}
+
+fn guard_block(guard: &NamedGuard) -> Block {
+    let mut block = Block::new(guard.line, Section::Guard, &guard.leading_comments);
+    let definition = format!("guard {} = {}", guard.name, guard.expression);
+    block.push(with_comment(definition, guard.trailing_comment.as_deref()));
+    block
+}
+
+/// A `state` line, or block with its body and the `grouped` transitions leaving it
+fn state_block(state: &State, grouped: &[&Transition]) -> Block {
+    let line = state.declared_at.first().copied().or_else(|| grouped.iter().filter_map(|t| t.line).min());
+    let mut block = Block::new(line, Section::State, &state.leading_comments);
+
+    let mut header = match state.state_type {
+        StateType::Final => format!("final state {}", state.name),
+        _ => format!("state {}", state.name),
+    };
+    if let Some(ref color) = state.color {
+        header.push_str(&format!(" #color({})", color));
+    }
+    if !state.tags.is_empty() {
+        header.push_str(&format!(" @tag({})", state.tags.join(", ")));
+    }
+    match state.description.as_deref() {
+        Some(description) if is_inline_description(description) => header.push_str(&format!(" : {}", description)),
+        description => {
+            let mut docs = String::new();
+            push_docs(&mut docs, "", description);
+            block.lines.extend(docs.lines().map(str::to_string));
+        }
+    }
+
+    let mut body: Vec<String> = Vec::new();
+    body.extend(action_lines(&state.entry_actions, "entry", "start_timer"));
+    body.extend(action_lines(&state.exit_actions, "exit", "stop_timer"));
+    body.extend(state.internal_transitions.iter().map(|t| with_comment(transition_label(t), t.trailing_comment.as_deref())));
+    for transition in grouped {
+        let mut docs = String::new();
+        push_docs(&mut docs, "", transition.description.as_deref());
+        body.extend(docs.lines().map(str::to_string));
+        body.push(with_comment(grouped_transition(transition), transition.trailing_comment.as_deref()));
+    }
+
+    if body.is_empty() {
+        block.push(with_comment(header, state.trailing_comment.as_deref()));
+    } else {
+        block.multiline = true;
+        block.push(with_comment(format!("{} {{", header), state.trailing_comment.as_deref()));
+        block.lines.extend(body.into_iter().map(|line| format!("{}{}", INDENT, line)));
+        block.push("}".to_string());
+    }
+    block
+}
+
+fn transition_block(transition: &Transition) -> Block {
+    let mut block = Block::new(transition.line, Section::Transition, &transition.leading_comments);
+    let mut docs = String::new();
+    push_docs(&mut docs, "", transition.description.as_deref());
+    block.lines.extend(docs.lines().map(str::to_string));
+
+    let label = transition_label(transition);
+    let arrow = transition.style.arrow(transition.reversed);
+    let mut line = match transition.reversed {
+        true => format!("{} {} {}", transition.target, arrow, transition.source),
+        false => format!("{} {} {}", transition.source, arrow, transition.target),
+    };
+    if !label.is_empty() {
+        line.push_str(&format!(" : {}", label));
+    }
+    block.push(with_comment(line, transition.trailing_comment.as_deref()));
+    block
+}
+
+/// Whether `transition` can be written in its source state's block
+fn fits_state_block(transition: &Transition) -> bool {
+    transition.kind == TransitionKind::External
+        && transition.event.is_some()
+        && transition.leading_comments.is_empty()
+        && transition.source != "[*]"
+        && !transition.is_from_any_state()
+}
+
+/// `on event [guard] -> Target / a; b`, a transition in its source state's block
+fn grouped_transition(transition: &Transition) -> String {
+    let mut parts = vec!["on".to_string()];
+    parts.extend(transition.event.as_ref().map(Event::pattern));
+    parts.extend(transition.timing_constraint.as_ref().map(ToString::to_string));
+    parts.extend(transition.guard.as_ref().map(|guard| format!("[{}]", guard.expression)));
+    parts.push(match transition.style {
+        TransitionStyle::Solid => "->".to_string(),
+        TransitionStyle::Dashed => "-.->".to_string(),
+    });
+    parts.push(transition.target.clone());
+    if !transition.actions.is_empty() {
+        parts.push(format!("/ {}", action_list(&transition.actions)));
+    }
+    parts.join(" ")
+}
+
+fn choice_block(choice: &ChoicePoint) -> Block {
+    let mut block = Block::new(choice.line, Section::Choice, &[]);
+    block.multiline = true;
+    block.push(format!("choice {} {{", choice.name));
+    for branch in &choice.branches {
+        let mut line = format!("{}[{}] -> {}", INDENT, branch.guard.expression, branch.target);
+        if !branch.actions.is_empty() {
+            line.push_str(&format!(" / {}", action_list(&branch.actions)));
+        }
+        block.push(line);
+    }
+    block.push("}".to_string());
+    block
+}
+
+fn note_block(note: &Note) -> Block {
+    let mut block = Block::new(note.line, Section::Note, &[]);
+    let side = match note.position {
+        NotePosition::Left => "left",
+        NotePosition::Right => "right",
+    };
+    if note.text.contains('\n') {
+        block.multiline = true;
+        block.push(format!("note {} of {}", side, note.target_state));
+        block.lines.extend(note.text.lines().map(|line| format!("{}{}", INDENT, line).trim_end().to_string()));
+        block.push("end note".to_string());
+    } else {
+        block.push(format!("note {} of {} : {}", side, note.target_state, note.text));
+    }
+    block
+}
+
+// ============================================================================
+// PIECES
+// ============================================================================
+
+/// `set_speed(rpm: u16) = 3`, an event as the events block declares it
+fn event_declaration(event: &Event) -> String {
+    let mut declaration = event.name.clone();
+    if !event.params.is_empty() {
+        let params: Vec<String> = event.params.iter().map(|p| format!("{}: {}", p.name, p.ty)).collect();
+        declaration.push_str(&format!("({})", params.join(", ")));
+    }
+    if let Some(id) = event.id {
+        declaration.push_str(&format!(" = {}", id));
+    }
+    declaration
+}
+
+/// `local event [guard] / a; b(1)`, the text after a transition's `:` (or an internal
+/// transition's whole line)
+fn transition_label(transition: &Transition) -> String {
+    let mut parts = Vec::new();
+    if transition.kind == TransitionKind::Local {
+        parts.push("local".to_string());
+    }
+    if let Some(ref event) = transition.event {
+        parts.push(event.pattern());
+    }
+    if let Some(ref constraint) = transition.timing_constraint {
+        parts.push(constraint.to_string());
+    }
+    if let Some(ref guard) = transition.guard {
+        parts.push(format!("[{}]", guard.expression));
+    }
+    if !transition.actions.is_empty() {
+        parts.push(format!("/ {}", action_list(&transition.actions)));
+    }
+    parts.join(" ")
+}
+
+fn action_list(actions: &[Action]) -> String {
+    actions.iter().map(action_call).collect::<Vec<_>>().join("; ")
+}
+
+/// `entry / a; b` (`keyword`) lines for `actions`, those written on one line kept
+/// together, and `start_timer(t)` (`timer_keyword`) lines
+fn action_lines(actions: &[Action], keyword: &str, timer_keyword: &str) -> Vec<String> {
+    let mut statements: Vec<Vec<&Action>> = Vec::new();
+    for action in actions {
+        let continues = |last: &&Action| {
+            action.line.is_some()
+                && last.line == action.line
+                && last.trailing_comment.is_none()
+                && timer_control(last, timer_keyword).is_none()
+                && timer_control(action, timer_keyword).is_none()
+        };
+        match statements.last_mut() {
+            Some(statement) if statement.last().is_some_and(continues) => statement.push(action),
+            _ => statements.push(vec![action]),
+        }
+    }
+    statements
+        .into_iter()
+        .filter_map(|statement| {
+            let last = *statement.last()?;
+            let line = match timer_control(last, timer_keyword) {
+                Some(control) => control,
+                None => format!("{} / {}", keyword, statement.iter().map(|a| action_call(a)).collect::<Vec<_>>().join("; ")),
+            };
+            Some(with_comment(line, last.trailing_comment.as_deref()))
+        })
+        .collect()
+}
+
+/// `start_timer(t)` / `stop_timer(t)` for the actions those state body items become
+fn timer_control(action: &Action, keyword: &str) -> Option<String> {
+    let [timer] = action.params.as_slice() else {
+        return None;
+    };
+    (action.name == format!("{}_{}", keyword, timer)).then(|| format!("{}({})", keyword, timer))
+}
+
+/// Whether a state description fits after `state Name :` (one line that the
+/// grammar's `description` reads back unchanged)
+fn is_inline_description(description: &str) -> bool {
+    !description.is_empty()
+        && description.trim() == description
+        && !description.contains('\n')
+        && !description.ends_with('{')
+        && !description.contains("//")
+}
+
+/// `///` lines of a description
+fn push_docs(out: &mut String, indent: &str, description: Option<&str>) {
+    for line in description.iter().flat_map(|d| d.lines()) {
+        push_line(out, indent, format!("/// {}", line).trim_end());
+    }
+}
+
+fn push_line(out: &mut String, indent: &str, line: &str) {
+    if !line.is_empty() {
+        out.push_str(indent);
+        out.push_str(line);
+    }
+    out.push('\n');
+}
+
+/// A captured comment as written back: `/* ... */` ones kept whole
+fn comment_line(text: &str) -> String {
+    if text.starts_with("/*") {
+        return text.to_string();
+    }
+    format!("// {}", text).trim_end().to_string()
+}
+
+fn with_comment(line: String, comment: Option<&str>) -> String {
+    match comment {
+        Some(comment) => format!("{} {}", line, comment_line(comment)),
+        None => line,
+    }
+}
+
+/// `"..."` with the escapes `quoted_string` reads back
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,130 @@
+//! Parse Limits
+//! Bounds a source must stay within, checked before and after parsing, so a malformed
+//! or hostile file fails with [`ParseError::LimitExceeded`] instead of exhausting the
+//! stack or memory. `parse_fsm` and the loader apply the defaults.
+
+use super::loader::MAX_INCLUDE_DEPTH;
+use super::{ParseError, ParseResult};
+use crate::fsm::FsmDefinition;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Bytes of source text, per file
+    pub max_source_len: usize,
+    /// FSMs in a source, included files counted
+    pub max_fsms: usize,
+    /// States of one FSM, substates included
+    pub max_states: usize,
+    /// Transitions of one FSM, internal ones and those of substates included
+    pub max_transitions: usize,
+    /// Deepest `{ }` nesting: an FSM's body is 1, a state body inside it 2, and so on
+    pub max_nesting_depth: usize,
+    /// Files included within each other
+    pub max_include_depth: usize,
+    /// Characters in a name (FSMs, states, events, parameters, actions, timers)
+    pub max_identifier_len: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            max_source_len: 4 * 1024 * 1024,
+            max_fsms: 256,
+            max_states: 10_000,
+            max_transitions: 50_000,
+            max_nesting_depth: 32,
+            max_include_depth: MAX_INCLUDE_DEPTH,
+            max_identifier_len: 256,
+        }
+    }
+}
+
+fn exceeded(what: impl Into<String>, limit: usize, found: usize) -> ParseResult<()> {
+    match found > limit {
+        true => Err(ParseError::LimitExceeded { what: what.into(), limit, found }),
+        false => Ok(()),
+    }
+}
+
+/// Length and nesting of `source`, checked before the grammar (which recurses once per
+/// level) sees it
+pub(super) fn check_source(source: &str, limits: &ParseLimits) -> ParseResult<()> {
+    exceeded("source length", limits.max_source_len, source.len())?;
+    exceeded("nesting depth", limits.max_nesting_depth, nesting_depth(source))
+}
+
+/// Deepest `{` nesting outside comments and strings (unclosed blocks count as open)
+fn nesting_depth(source: &str) -> usize {
+    let bytes = source.as_bytes();
+    let (mut depth, mut deepest, mut i) = (0usize, 0usize, 0);
+    while i < bytes.len() {
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = memchr(bytes, i, b"\n").unwrap_or(bytes.len());
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = memchr(bytes, i + 2, b"*/").map_or(bytes.len(), |end| end + 2);
+            }
+            // Strings end at their quote or the end of the line, like the grammar's
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && !matches!(bytes[i], b'"' | b'\n') {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+            }
+            b'{' => {
+                depth += 1;
+                deepest = deepest.max(depth);
+                i += 1;
+            }
+            b'}' => {
+                depth = depth.saturating_sub(1);
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    deepest
+}
+
+/// Position of the first `needle` in `bytes` at or after `from`
+fn memchr(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    bytes.get(from..)?.windows(needle.len()).position(|w| w == needle).map(|i| from + i)
+}
+
+/// What `fsm` declares, checked once it is parsed
+pub(super) fn check_fsm(fsm: &FsmDefinition, limits: &ParseLimits) -> ParseResult<()> {
+    let mut states = 0;
+    let mut transitions = 0;
+    let mut names: Vec<&str> = vec![&fsm.name];
+    let mut pending = vec![fsm];
+    while let Some(fsm) = pending.pop() {
+        states += fsm.states.len();
+        transitions += fsm.transitions.len();
+        for state in &fsm.states {
+            transitions += state.internal_transitions.len();
+            names.push(&state.name);
+            pending.extend(state.sub_fsm.as_ref());
+        }
+        let events = fsm.events.iter().flat_map(|e| std::iter::once(&e.name).chain(e.params.iter().map(|p| &p.name)));
+        let triggers = fsm.transitions.iter().filter_map(|t| t.event.as_ref()).map(|e| &e.name);
+        names.extend(events.chain(triggers).chain(fsm.timers.iter().map(|t| &t.name)).map(String::as_str));
+        names.extend(fsm.action_names());
+    }
+    exceeded(format!("states in '{}'", fsm.name), limits.max_states, states)?;
+    exceeded(format!("transitions in '{}'", fsm.name), limits.max_transitions, transitions)?;
+    match names.into_iter().max_by_key(|name| name.chars().count()) {
+        Some(longest) => exceeded(
+            format!("identifier length ('{}…')", longest.chars().take(16).collect::<String>()),
+            limits.max_identifier_len,
+            longest.chars().count(),
+        ),
+        None => Ok(()),
+    }
+}
+
+/// How many FSMs a source (with its includes) defines
+pub(super) fn check_fsm_count(count: usize, limits: &ParseLimits) -> ParseResult<()> {
+    exceeded("FSMs", limits.max_fsms, count)
+}
@@ -0,0 +1,157 @@
+//! FSM File Loader
+//! Loads FSM definitions from files and resolves `include` directives
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use pest::Parser;
+
+use super::limits::{self, ParseLimits};
+use super::{parse_fsm_definition, unsupported_construct, FsmParser, LineComments, ParseError, ParseResult, Rule};
+use crate::fsm::FsmDefinition;
+
+/// Default maximum nesting depth of `include` directives, see [`ParseLimits`]
+pub const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Parse an FSM file, resolving `include` directives relative to the including file.
+///
+/// The file's own machines come first, followed by the machines of each included
+/// file in include order. Every file is loaded at most once, so a fragment included
+/// from several places does not produce duplicate machines.
+pub fn parse_fsm_file(path: impl AsRef<Path>) -> ParseResult<Vec<FsmDefinition>> {
+    parse_fsm_file_with_limits(path, &ParseLimits::default())
+}
+
+/// [`parse_fsm_file`] within `limits`, applied to every included file and to the
+/// machines of all of them together
+pub fn parse_fsm_file_with_limits(path: impl AsRef<Path>, limits: &ParseLimits) -> ParseResult<Vec<FsmDefinition>> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).map_err(|e| ParseError::Io {
+        file: path.display().to_string(),
+        message: e.to_string(),
+    })?;
+    parse_fsm_source_with_limits(&source, path, limits)
+}
+
+/// Parse in-memory FSM source as if it were the contents of `path`.
+///
+/// Useful for editor buffers: includes still resolve relative to the file's location.
+pub fn parse_fsm_source(source: &str, path: impl AsRef<Path>) -> ParseResult<Vec<FsmDefinition>> {
+    parse_fsm_source_with_limits(source, path, &ParseLimits::default())
+}
+
+/// [`parse_fsm_source`] within `limits`
+pub fn parse_fsm_source_with_limits(
+    source: &str,
+    path: impl AsRef<Path>,
+    limits: &ParseLimits,
+) -> ParseResult<Vec<FsmDefinition>> {
+    let path = path.as_ref();
+    let mut loader = Loader { limits: *limits, ..Loader::default() };
+    loader.visited.insert(canonical(path));
+    loader.load(source, path, 0)?;
+    Ok(loader.fsms)
+}
+
+#[derive(Default)]
+struct Loader {
+    /// Files currently being loaded, outermost first (for cycle detection)
+    stack: Vec<PathBuf>,
+    /// Files already loaded
+    visited: HashSet<PathBuf>,
+    /// Machines collected so far
+    fsms: Vec<FsmDefinition>,
+    limits: ParseLimits,
+}
+
+impl Loader {
+    fn load(&mut self, source: &str, path: &Path, depth: usize) -> ParseResult<()> {
+        let file = path.display().to_string();
+        let in_file = |e: ParseError| ParseError::InFile {
+            file: file.clone(),
+            source: Box::new(e),
+        };
+
+        limits::check_source(source, &self.limits).map_err(in_file)?;
+        let pairs = FsmParser::parse(Rule::file, source).map_err(|e| in_file(e.into()))?;
+        let mut comments = LineComments::scan(source);
+
+        // (line, relative path) of every include, resolved after the file's own machines
+        let mut includes: Vec<(usize, String)> = Vec::new();
+        let loaded = self.fsms.len();
+
+        for pair in pairs.flat_map(|p| p.into_inner()) {
+            match pair.as_rule() {
+                Rule::include_directive => {
+                    let line = pair.as_span().start_pos().line_col().0;
+                    let target = pair
+                        .into_inner()
+                        .next()
+                        .and_then(|lit| lit.into_inner().next())
+                        .map(|inner| inner.as_str().to_string())
+                        .unwrap_or_default();
+                    includes.push((line, target));
+                }
+                Rule::unsupported => {
+                    return Err(in_file(unsupported_construct(&pair)));
+                }
+                Rule::fsm_definition => {
+                    let mut fsm = parse_fsm_definition(pair, &mut comments).map_err(in_file)?;
+                    limits::check_fsm(&fsm, &self.limits).map_err(in_file)?;
+                    limits::check_fsm_count(self.fsms.len() + 1, &self.limits).map_err(in_file)?;
+                    fsm.source_file = Some(file.clone());
+                    self.fsms.push(fsm);
+                }
+                _ => {}
+            }
+        }
+        if let Some(last) = self.fsms[loaded..].last_mut() {
+            last.following_comments = comments.take_rest();
+        }
+
+        self.stack.push(canonical(path));
+
+        for (line, target) in includes {
+            let include_error = |message: String| ParseError::Include {
+                file: file.clone(),
+                line,
+                message,
+            };
+
+            if target.trim().is_empty() {
+                return Err(include_error("empty include path".to_string()));
+            }
+            if depth + 1 > self.limits.max_include_depth {
+                let exceeded = ParseError::LimitExceeded {
+                    what: "include depth".to_string(),
+                    limit: self.limits.max_include_depth,
+                    found: depth + 1,
+                };
+                return Err(include_error(format!("{exceeded} at \"{target}\"")));
+            }
+
+            let include_path = path.parent().unwrap_or_else(|| Path::new("")).join(&target);
+            let key = canonical(&include_path);
+
+            if self.stack.contains(&key) {
+                return Err(include_error(format!("include cycle: \"{}\" is already being loaded", target)));
+            }
+            if !self.visited.insert(key) {
+                continue; // Already loaded through another include
+            }
+
+            let text = fs::read_to_string(&include_path).map_err(|e| {
+                include_error(format!("cannot read \"{}\": {}", include_path.display(), e))
+            })?;
+            self.load(&text, &include_path, depth + 1)?;
+        }
+
+        self.stack.pop();
+        Ok(())
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -1,15 +1,30 @@
 //! FSM Parser Module
 //! Parses Mermaid-like DSL into FSM data structures
 
+use std::collections::BTreeMap;
+
 use pest::Parser;
 use pest_derive::Parser;
 use thiserror::Error;
 
 use crate::fsm::{
-    Action, ChoiceBranch, ChoicePoint, Event, FsmDefinition, Guard, State, StateType, Timer,
-    TimerMode, Transition, TransitionKind,
+    Action, ChoiceBranch, ChoicePoint, Event, EventParam, FsmDefinition, Guard, InitialTransition, LayoutHint, NamedGuard, Note,
+    NotePosition, OrphanComment, State, StateType, Timer, TimerMode, TimingConstraint, TimingKind, Transition, TransitionKind, TransitionStyle,
 };
 
+pub mod blocks;
+pub mod diagnostics;
+pub mod edit;
+pub mod event_import;
+pub mod format;
+pub mod limits;
+pub mod loader;
+pub mod scxml;
+
+pub use event_import::import_events;
+pub use limits::ParseLimits;
+pub use scxml::import_scxml;
+
 #[cfg(test)]
 mod tests;
 
@@ -25,58 +40,112 @@ pub enum ParseError {
     SyntaxError { line: usize, message: String },
     #[error("Unknown state reference: {0}")]
     UnknownState(String),
+    #[error("Unsupported construct at line {line}: {construct}")]
+    Unsupported { line: usize, construct: String },
+    #[error("Could not read '{file}': {message}")]
+    Io { file: String, message: String },
+    #[error("{file}:{line}: {message}")]
+    Include {
+        file: String,
+        line: usize,
+        message: String,
+    },
+    #[error("{what}: {found} exceeds the limit of {limit}")]
+    LimitExceeded { what: String, limit: usize, found: usize },
+    #[error("{file}: {source}")]
+    InFile {
+        file: String,
+        source: Box<ParseError>,
+    },
 }
 
 pub type ParseResult<T> = Result<T, ParseError>;
 
 /// Parse FSM DSL source code into FSM definitions
+///
+/// `include` directives are accepted but not resolved here, since a raw string
+/// has no location to resolve them against. Use [`loader::parse_fsm_file`] for that.
 pub fn parse_fsm(source: &str) -> ParseResult<Vec<FsmDefinition>> {
+    parse_fsm_with_limits(source, &ParseLimits::default())
+}
+
+/// [`parse_fsm`] for untrusted input: fails with [`ParseError::LimitExceeded`] once the
+/// source outgrows `limits`
+pub fn parse_fsm_with_limits(source: &str, limits: &ParseLimits) -> ParseResult<Vec<FsmDefinition>> {
+    limits::check_source(source, limits)?;
+    let fsms = parse_unchecked(source)?;
+    limits::check_fsm_count(fsms.len(), limits)?;
+    for fsm in &fsms {
+        limits::check_fsm(fsm, limits)?;
+    }
+    Ok(fsms)
+}
+
+fn parse_unchecked(source: &str) -> ParseResult<Vec<FsmDefinition>> {
     let pairs = FsmParser::parse(Rule::file, source)?;
+    let mut comments = LineComments::scan(source);
     let mut fsms = Vec::new();
 
     for pair in pairs {
         match pair.as_rule() {
             Rule::file => {
                 for inner in pair.into_inner() {
-                    if inner.as_rule() == Rule::fsm_definition {
-                        fsms.push(parse_fsm_definition(inner)?);
+                    match inner.as_rule() {
+                        Rule::fsm_definition => fsms.push(parse_fsm_definition(inner, &mut comments)?),
+                        Rule::unsupported => return Err(unsupported_construct(&inner)),
+                        _ => {}
                     }
                 }
             }
             Rule::fsm_definition => {
-                fsms.push(parse_fsm_definition(pair)?);
+                fsms.push(parse_fsm_definition(pair, &mut comments)?);
             }
             Rule::EOI => {}
             _ => {}
         }
     }
+    if let Some(last) = fsms.last_mut() {
+        last.following_comments = comments.take_rest();
+    }
 
     Ok(fsms)
 }
 
-fn parse_fsm_definition(pair: pest::iterators::Pair<Rule>) -> ParseResult<FsmDefinition> {
-    let mut inner = pair.into_inner();
-    let name = inner.next().unwrap().as_str().to_string();
+pub(crate) fn parse_fsm_definition(pair: pest::iterators::Pair<Rule>, comments: &mut LineComments) -> ParseResult<FsmDefinition> {
+    let input = pair.as_span().get_input();
+    let line = line_of(&pair);
+    let closing_line = pair.as_span().end_pos().line_col().0;
+    let leading = comments.take_before(line);
+    let mut inner = pair.into_inner().peekable();
+    let docs = parse_doc_comments(&mut inner);
+    let name_pair = next_pair(&mut inner, line, "an FSM name")?;
 
-    let mut fsm = FsmDefinition::new(name);
+    let mut fsm = FsmDefinition::new(name_pair.as_str());
+    fsm.description = docs;
+    fsm.line = Some(line);
+    fsm.leading_comments = leading.into_values().collect();
+    fsm.trailing_comment = trailing_comment(input, name_pair.as_span().end());
 
     for item in inner {
         match item.as_rule() {
             Rule::fsm_body => {
-                parse_fsm_body(item, &mut fsm)?;
+                parse_fsm_body(item, &mut fsm, comments)?;
             }
             _ => {}
         }
     }
+    // Comments after the last item
+    fsm.orphan_comments.extend(orphans(comments.take_before(closing_line)));
+    fsm.merge_duplicate_transitions();
 
     Ok(fsm)
 }
 
-fn parse_fsm_body(pair: pest::iterators::Pair<Rule>, fsm: &mut FsmDefinition) -> ParseResult<()> {
+fn parse_fsm_body(pair: pest::iterators::Pair<Rule>, fsm: &mut FsmDefinition, comments: &mut LineComments) -> ParseResult<()> {
     for item in pair.into_inner() {
         match item.as_rule() {
             Rule::fsm_item => {
-                parse_fsm_item(item, fsm)?;
+                parse_fsm_item(item, fsm, comments)?;
             }
             _ => {}
         }
@@ -84,64 +153,490 @@ fn parse_fsm_body(pair: pest::iterators::Pair<Rule>, fsm: &mut FsmDefinition) ->
     Ok(())
 }
 
-fn parse_fsm_item(pair: pest::iterators::Pair<Rule>, fsm: &mut FsmDefinition) -> ParseResult<()> {
-    let inner = pair.into_inner().next().unwrap();
+fn parse_fsm_item(pair: pest::iterators::Pair<Rule>, fsm: &mut FsmDefinition, comments: &mut LineComments) -> ParseResult<()> {
+    let input = pair.as_span().get_input();
+    let end = last_token_end(&pair);
+    let end_line = line_at(input, end);
+    let line = line_of(&pair);
+    let mut leading = comments.take_before(line);
+    let mut items = pair.into_inner().peekable();
+    let docs = parse_doc_comments(&mut items);
+    let inner = next_pair(&mut items, line, "an FSM item")?;
+    let attaches_comments = matches!(
+        inner.as_rule(),
+        Rule::state_simple | Rule::state_with_body | Rule::transition | Rule::timer_def | Rule::guard_def
+    );
+    if !attaches_comments {
+        // Kept as orphans on the item's line, so the printer puts them back below it
+        if let Some(text) = trailing_comment(input, end) {
+            fsm.orphan_comments.push(OrphanComment { line: end_line, text });
+        }
+    }
+    let mut leading_comments = || std::mem::take(&mut leading).into_values().collect::<Vec<_>>();
 
     match inner.as_rule() {
+        Rule::fsm_description => {
+            let text = first_pair(inner, "a description")?;
+            let text = match text.as_rule() {
+                Rule::quoted_string => unquote(text),
+                _ => text.as_str().trim().to_string(),
+            };
+            fsm.description = join_description(fsm.description.take(), Some(text));
+        }
+        Rule::meta_def => {
+            let line = line_of(&inner);
+            let mut parts = inner.into_inner();
+            let key = next_pair(&mut parts, line, "a metadata key")?.as_str().to_string();
+            fsm.metadata.insert(key, unquote(next_pair(&mut parts, line, "a metadata value")?));
+        }
+        Rule::file_name_def => {
+            fsm.file_name = Some(unquote(first_pair(inner, "a file name")?));
+        }
+        Rule::direction_def => {
+            fsm.layout_hint = Some(match first_pair(inner, "a direction")?.as_str() {
+                "BT" => LayoutHint::BT,
+                "LR" => LayoutHint::LR,
+                "RL" => LayoutHint::RL,
+                _ => LayoutHint::TB,
+            });
+        }
+        Rule::note_def => {
+            let line = line_of(&inner);
+            let note = parse_note(inner)?;
+            if !fsm.states.iter().any(|s| s.name == note.target_state) {
+                fsm.states.push(implicit_state(&note.target_state, Some(line)));
+            }
+            fsm.notes.push(note);
+        }
+        Rule::unsupported => {
+            return Err(unsupported_construct(&inner));
+        }
         Rule::initial_state => {
-            let mut inner_iter = inner.into_inner();
-            // Skip arrow, get identifier
-            let state_name = inner_iter.last().unwrap().as_str();
-            fsm.initial_state = Some(state_name.to_string());
-
-            // Ensure the initial state exists
-            if !fsm.states.iter().any(|s| s.name == state_name) {
-                fsm.states.push(State::new(state_name, StateType::Simple));
+            let mut initial = InitialTransition::new("");
+            initial.line = Some(line_of(&inner));
+            for part in inner.into_inner() {
+                match part.as_rule() {
+                    Rule::identifier => initial.target = part.as_str().to_string(),
+                    Rule::guard => {
+                        let expr = first_pair(part, "a guard expression")?.as_str().trim();
+                        initial.guard = Some(Guard { expression: expr.to_string() });
+                    }
+                    Rule::action => initial.actions = parse_action_list(first_pair(part, "an action list")?)?,
+                    _ => {}
+                }
+            }
+            add_initial_transition(fsm, initial);
+        }
+        Rule::events_def => {
+            fsm.events_line.get_or_insert(line_of(&inner));
+            for item in inner.into_inner().filter(|p| p.as_rule() == Rule::event_item) {
+                let line = line_of(&item);
+                let mut parts = item.into_inner().peekable();
+                let docs = parse_doc_comments(&mut parts);
+                let declaration = next_pair(&mut parts, line, "an event declaration")?;
+                let line = line_of(&declaration);
+                let mut declaration = declaration.into_inner().peekable();
+                let name = next_pair(&mut declaration, line, "an event name")?.as_str().to_string();
+                // A second declaration of an event adds nothing
+                if fsm.events.iter().any(|e| e.name == name) {
+                    continue;
+                }
+                let mut event = Event::new(name);
+                if let Some(params) = declaration.next_if(|p| p.as_rule() == Rule::event_params) {
+                    event.params = params
+                        .into_inner()
+                        .map(|param| {
+                            let mut parts = param.into_inner();
+                            let name = next_pair(&mut parts, line, "a parameter name")?.as_str().to_string();
+                            let ty = next_pair(&mut parts, line, "a parameter type")?.as_str().to_string();
+                            Ok(EventParam { name, ty })
+                        })
+                        .collect::<ParseResult<_>>()?;
+                }
+                if let Some(id) = declaration.next_if(|p| p.as_rule() == Rule::event_id) {
+                    event.id = Some(id.as_str().parse().map_err(|_| ParseError::SyntaxError {
+                        line,
+                        message: format!("event id {} is too large", id.as_str()),
+                    })?);
+                }
+                event.description = join_description(docs, parse_doc_comments(&mut declaration));
+                event.line = Some(line);
+                fsm.events.push(event);
             }
         }
         Rule::timer_def => {
-            let timer = parse_timer_def(inner)?;
+            let mut timer = parse_timer_def(inner)?;
+            timer.leading_comments = leading_comments();
             fsm.timers.push(timer);
         }
+        Rule::guard_def => {
+            let mut guard = parse_guard_def(inner)?;
+            guard.leading_comments = leading_comments();
+            fsm.guards.push(guard);
+        }
         Rule::choice_def => {
             let choice = parse_choice_def(inner)?;
             fsm.choice_points.push(choice);
         }
         Rule::state_simple | Rule::state_with_body => {
-            let state = parse_state_definition(inner)?;
-            // Update existing or add new
-            if let Some(existing) = fsm.states.iter_mut().find(|s| s.name == state.name) {
-                existing.description = state.description;
-                existing.entry_actions.extend(state.entry_actions);
-                existing.exit_actions.extend(state.exit_actions);
-                existing.internal_transitions = state.internal_transitions;
-            } else {
-                fsm.states.push(state);
+            let (mut state, grouped) = parse_state_definition(inner)?;
+            state.description = join_description(docs, state.description);
+            state.leading_comments = leading_comments();
+            match fsm.states.iter_mut().find(|s| s.name == state.name) {
+                Some(existing) => merge_declaration(existing, state),
+                None => fsm.states.push(state),
+            }
+            for transition in grouped {
+                add_transition(fsm, transition);
             }
         }
         Rule::transition => {
-            let transition = parse_transition(inner)?;
+            let mut transition = parse_transition(inner)?;
+            // `Idle <-- [*]` and `[*] -.-> Idle` are initial transitions too
+            if transition.source == "[*]" {
+                add_initial_transition(fsm, initial_from_transition(transition)?);
+            } else {
+                transition.description = docs;
+                transition.leading_comments = leading_comments();
+                add_transition(fsm, transition);
+            }
+        }
+        _ => {}
+    }
+
+    // Comments above items that take none, and inside bodies
+    let inside = comments.take_before(end_line + 1);
+    fsm.orphan_comments.extend(orphans(leading.into_iter().chain(inside).collect()));
+    Ok(())
+}
+
+/// Add `initial` to `fsm`, creating its target if it does not exist yet; the first
+/// unguarded declaration is the default, validation reports the others
+fn add_initial_transition(fsm: &mut FsmDefinition, initial: InitialTransition) {
+    if !fsm.states.iter().any(|s| s.name == initial.target) {
+        fsm.states.push(implicit_state(&initial.target, initial.line));
+    }
+    fsm.initial_transitions.push(initial);
+}
+
+/// The initial transition a transition out of `[*]` declares, which can have a guard
+/// and actions but nothing else
+fn initial_from_transition(transition: Transition) -> ParseResult<InitialTransition> {
+    let line = transition.line.unwrap_or_default();
+    let error = |message: &str| ParseError::SyntaxError { line, message: message.to_string() };
+    if transition.target == "[*]" || transition.target.starts_with("<<") {
+        return Err(error("an initial transition must enter a state"));
+    }
+    if transition.event.is_some() || transition.timing_constraint.is_some() {
+        return Err(error("an initial transition takes no event: it is taken when the machine starts"));
+    }
+    let mut initial = InitialTransition::new(transition.target);
+    initial.guard = transition.guard;
+    initial.actions = transition.actions;
+    initial.line = transition.line;
+    Ok(initial)
+}
 
-            // Ensure source and target states exist (unless it's a choice point target)
-            if transition.source != "[*]" && !transition.source.starts_with("<<") {
-                if !fsm.states.iter().any(|s| s.name == transition.source) {
-                    fsm.states
-                        .push(State::new(&transition.source, StateType::Simple));
+/// Add `transition` to `fsm`, creating the states it mentions that do not exist yet
+fn add_transition(fsm: &mut FsmDefinition, transition: Transition) {
+    // Ensure source and target states exist (unless it's a choice point target)
+    if transition.source != "[*]" && !transition.is_from_any_state() && !transition.source.starts_with("<<") {
+        if !fsm.states.iter().any(|s| s.name == transition.source) {
+            fsm.states.push(implicit_state(&transition.source, transition.line));
+        }
+    }
+    if transition.target != "[*]" && !transition.target.starts_with("<<") {
+        if !fsm.states.iter().any(|s| s.name == transition.target) {
+            fsm.states.push(implicit_state(&transition.target, transition.line));
+        }
+    }
+
+    fsm.transitions.push(transition);
+}
+
+/// 1-based source line a pair starts on
+fn line_of(pair: &pest::iterators::Pair<Rule>) -> usize {
+    pair.as_span().start_pos().line_col().0
+}
+
+/// 1-based source line of byte `offset`
+fn line_at(input: &str, offset: usize) -> usize {
+    input[..offset].matches('\n').count() + 1
+}
+
+/// The next child the grammar promises, as an error on `line` (naming `what` was
+/// expected) rather than a panic should it be missing
+fn next_pair<'i>(
+    pairs: &mut impl Iterator<Item = pest::iterators::Pair<'i, Rule>>,
+    line: usize,
+    what: &str,
+) -> ParseResult<pest::iterators::Pair<'i, Rule>> {
+    pairs.next().ok_or_else(|| ParseError::SyntaxError { line, message: format!("expected {what}") })
+}
+
+/// The first child of `pair`, see [`next_pair`]
+fn first_pair<'i>(pair: pest::iterators::Pair<'i, Rule>, what: &str) -> ParseResult<pest::iterators::Pair<'i, Rule>> {
+    let line = line_of(&pair);
+    next_pair(&mut pair.into_inner(), line, what)
+}
+
+// ============================================================================
+// COMMENT CAPTURE
+// ============================================================================
+
+/// `// ...` and `/* ... */` comments on lines of their own, by (first) line. The
+/// grammar skips comments, so they are scanned from the input and handed out to the
+/// items below them as the parser walks the file in order.
+#[derive(Debug, Default)]
+pub(crate) struct LineComments {
+    lines: BTreeMap<usize, String>,
+}
+
+impl LineComments {
+    /// Every full-line `//` comment of `source`, except `///` documentation and lines
+    /// inside note blocks, and every `/* */` comment that starts and ends its lines,
+    /// kept whole with its delimiters (its later lines as written)
+    pub(crate) fn scan(source: &str) -> Self {
+        let mut lines = BTreeMap::new();
+        let mut block_comment: Option<(usize, Vec<&str>)> = None;
+        let mut in_note = false;
+        for (i, line) in source.lines().enumerate() {
+            let trimmed = line.trim();
+            if let Some((start, mut text)) = block_comment.take() {
+                text.push(line.trim_end());
+                match trimmed.find("*/") {
+                    Some(close) if only_comments(&trimmed[close + 2..]) => {
+                        lines.insert(start, text.join("\n"));
+                    }
+                    Some(_) => {}
+                    None => block_comment = Some((start, text)),
                 }
-            }
-            if transition.target != "[*]" && !transition.target.starts_with("<<") {
-                if !fsm.states.iter().any(|s| s.name == transition.target) {
-                    fsm.states
-                        .push(State::new(&transition.target, StateType::Simple));
+            } else if in_note {
+                in_note = !trimmed.split_whitespace().eq(["end", "note"]);
+            } else if trimmed.starts_with("/*") {
+                if only_comments(trimmed) {
+                    lines.insert(i + 1, trimmed.to_string());
+                } else if !trimmed.contains("*/") {
+                    block_comment = Some((i + 1, vec![trimmed]));
+                }
+            } else if trimmed.starts_with("note ") && !trimmed.contains(':') {
+                in_note = true;
+            } else if let Some(comment) = trimmed.strip_prefix("//") {
+                let doc = comment.starts_with('/') && !comment.starts_with("//");
+                if !doc {
+                    lines.insert(i + 1, comment.trim_start_matches('/').trim().to_string());
                 }
             }
+        }
+        Self { lines }
+    }
 
-            fsm.transitions.push(transition);
+    /// Comments above `line` not handed out yet
+    fn take_before(&mut self, line: usize) -> BTreeMap<usize, String> {
+        let rest = self.lines.split_off(&line);
+        std::mem::replace(&mut self.lines, rest)
+    }
+
+    /// Comments not handed out yet: those after the last item of the file
+    pub(crate) fn take_rest(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.lines).into_values().collect()
+    }
+}
+
+/// Whether `text` is nothing but whole comments and whitespace
+fn only_comments(text: &str) -> bool {
+    let text = text.trim_start();
+    if text.is_empty() || text.starts_with("//") {
+        return true;
+    }
+    match text.strip_prefix("/*").and_then(|rest| rest.find("*/").map(|close| &rest[close + 2..])) {
+        Some(rest) => only_comments(rest),
+        None => false,
+    }
+}
+
+fn orphans(comments: BTreeMap<usize, String>) -> impl Iterator<Item = OrphanComment> {
+    comments.into_iter().map(|(line, text)| OrphanComment { line, text })
+}
+
+/// Fold a `state` declaration into a state that already exists. An implicitly created
+/// state just takes the declaration, so the result does not depend on whether the
+/// state was mentioned before or after being declared; further declarations of the
+/// same state add their actions, internal transitions and tags (and a differing
+/// description) in declaration order; the first `#color` wins, and declaring the
+/// state final once makes it final.
+fn merge_declaration(existing: &mut State, declared: State) {
+    if !existing.is_declared() {
+        let position = existing.position;
+        *existing = declared;
+        existing.position = position;
+        return;
+    }
+    if declared.state_type == StateType::Final {
+        existing.state_type = StateType::Final;
+    }
+    if declared.description != existing.description {
+        existing.description = join_description(existing.description.take(), declared.description);
+    }
+    existing.leading_comments.extend(declared.leading_comments);
+    existing.trailing_comment = existing.trailing_comment.take().or(declared.trailing_comment);
+    existing.color = existing.color.take().or(declared.color);
+    for tag in declared.tags {
+        if !existing.tags.contains(&tag) {
+            existing.tags.push(tag);
         }
-        _ => {}
     }
+    existing.entry_actions.extend(declared.entry_actions);
+    existing.exit_actions.extend(declared.exit_actions);
+    existing.internal_transitions.extend(declared.internal_transitions);
+    existing.declared_at.extend(declared.declared_at);
+}
 
-    Ok(())
+/// A state created by its first mention outside a `state` declaration
+fn implicit_state(name: &str, line: Option<usize>) -> State {
+    let mut state = State::new(name, StateType::Simple);
+    state.line = line;
+    state
+}
+
+/// Consume leading `///` lines, one per line (a single space after `///` is dropped)
+fn parse_doc_comments<'i>(
+    pairs: &mut std::iter::Peekable<pest::iterators::Pairs<'i, Rule>>,
+) -> Option<String> {
+    let mut lines = Vec::new();
+    while let Some(doc) = pairs.next_if(|p| p.as_rule() == Rule::doc_comment) {
+        lines.push(doc_line(doc));
+    }
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// Text of one `///` line
+fn doc_line(doc: pest::iterators::Pair<Rule>) -> String {
+    let text = doc.into_inner().next().map_or("", |t| t.as_str());
+    text.strip_prefix(' ').unwrap_or(text).trim_end().to_string()
+}
+
+/// Text of a `// ...` comment after byte `end` on the same source line, or the whole
+/// `/* ... */` comment ending that line.
+///
+/// The grammar skips comments, so they are recovered from the input instead;
+/// a comment on a line of its own never follows an item's last token.
+fn trailing_comment(input: &str, end: usize) -> Option<String> {
+    let line = input[end..].lines().next()?;
+    if let Some(start) = line.find("/*").filter(|&start| !line[..start].contains("//")) {
+        let comment = line[start..].trim_end();
+        if only_comments(comment) {
+            return Some(comment.to_string());
+        }
+    }
+    let comment = &line[line.find("//")?..];
+    // `///` documents the next item
+    if comment.starts_with("///") && !comment.starts_with("////") {
+        return None;
+    }
+    let text = comment.trim_start_matches('/').trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// End of the last token inside `pair` (its own span also covers skipped whitespace and comments)
+fn last_token_end(pair: &pest::iterators::Pair<Rule>) -> usize {
+    pair.clone()
+        .into_inner()
+        .flatten()
+        .filter(|p| p.clone().into_inner().next().is_none())
+        .last()
+        .map_or(pair.as_span().end(), |p| p.as_span().end())
+}
+
+/// Trailing comment of a state body item: only a comment ending the line right after
+/// it is its own, not one after a later item on the same line
+fn own_trailing_comment(pair: &pest::iterators::Pair<Rule>) -> Option<String> {
+    let input = pair.as_span().get_input();
+    let end = last_token_end(pair);
+    // Past the `)` of a last `name()` or `start_timer(t)`, and a `;` separator
+    let rest = input[end..].lines().next().unwrap_or("").trim_start_matches(|c: char| c.is_whitespace() || "();".contains(c));
+    (rest.starts_with("//") || rest.starts_with("/*")).then(|| trailing_comment(input, end)).flatten()
+}
+
+/// Contents of a `quoted_string` with its `\"`, `\\`, `\n` and `\t` escapes resolved
+fn unquote(pair: pest::iterators::Pair<Rule>) -> String {
+    let mut text = String::new();
+    let mut chars = pair.into_inner().next().map_or("", |p| p.as_str()).chars();
+    while let Some(c) = chars.next() {
+        text.push(match c {
+            '\\' => match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some(escaped) => escaped,
+                None => break,
+            },
+            c => c,
+        });
+    }
+    text
+}
+
+/// Doc comments first, then an inline description
+fn join_description(docs: Option<String>, inline: Option<String>) -> Option<String> {
+    match (docs, inline) {
+        (Some(docs), Some(inline)) => Some(format!("{docs}\n{inline}")),
+        (docs, inline) => docs.or(inline),
+    }
+}
+
+/// Error for a recognised Mermaid statement Oxidate has no equivalent for
+pub(crate) fn unsupported_construct(pair: &pest::iterators::Pair<Rule>) -> ParseError {
+    ParseError::Unsupported {
+        line: pair.as_span().start_pos().line_col().0,
+        construct: pair.as_str().trim().to_string(),
+    }
+}
+
+fn parse_note(pair: pest::iterators::Pair<Rule>) -> ParseResult<Note> {
+    let line = line_of(&pair);
+    let mut inner = pair.into_inner();
+    let position = match next_pair(&mut inner, line, "a note position")?.as_str() {
+        "left" => NotePosition::Left,
+        _ => NotePosition::Right,
+    };
+    let target_state = next_pair(&mut inner, line, "a note's state")?.as_str().to_string();
+    let body = next_pair(&mut inner, line, "a note's text")?;
+    let text = match body.as_rule() {
+        // Block form: drop the indentation and surrounding blank lines
+        Rule::note_block => body
+            .into_inner()
+            .next()
+            .map_or("", |t| t.as_str())
+            .lines()
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string(),
+        _ => body.as_str().trim().to_string(),
+    };
+    Ok(Note {
+        target_state,
+        position,
+        text,
+        line: Some(line),
+    })
+}
+
+// ============================================================================
+// NAMED GUARD PARSING
+// ============================================================================
+
+fn parse_guard_def(pair: pest::iterators::Pair<Rule>) -> ParseResult<NamedGuard> {
+    let line = line_of(&pair);
+    let comment = trailing_comment(pair.as_span().get_input(), last_token_end(&pair));
+    let mut inner = pair.into_inner();
+    let name = next_pair(&mut inner, line, "a guard name")?.as_str();
+    let expression = next_pair(&mut inner, line, "a guard expression")?.as_str().trim();
+    let mut guard = NamedGuard::new(name, expression);
+    guard.line = Some(line);
+    guard.trailing_comment = comment;
+    Ok(guard)
 }
 
 // ============================================================================
@@ -149,15 +644,17 @@ fn parse_fsm_item(pair: pest::iterators::Pair<Rule>, fsm: &mut FsmDefinition) ->
 // ============================================================================
 
 fn parse_timer_def(pair: pest::iterators::Pair<Rule>) -> ParseResult<Timer> {
+    let line = line_of(&pair);
+    let comment = trailing_comment(pair.as_span().get_input(), last_token_end(&pair));
     let mut inner = pair.into_inner();
 
-    let name = inner.next().unwrap().as_str().to_string();
-    let duration_ms: u32 = inner.next().unwrap().as_str().parse().unwrap_or(1000);
+    let name = next_pair(&mut inner, line, "a timer name")?.as_str().to_string();
+    let duration_ms = parse_duration(next_pair(&mut inner, line, "a timer duration")?, "timer duration")?;
     
     // Skip arrow token if present
-    let mut event_name = inner.next().unwrap().as_str().to_string();
+    let mut event_name = next_pair(&mut inner, line, "a timer event")?.as_str().to_string();
     if event_name == "->" || event_name == "-->" {
-        event_name = inner.next().unwrap().as_str().to_string();
+        event_name = next_pair(&mut inner, line, "a timer event")?.as_str().to_string();
     }
 
     let mode = if let Some(mode_pair) = inner.next() {
@@ -172,21 +669,55 @@ fn parse_timer_def(pair: pest::iterators::Pair<Rule>) -> ParseResult<Timer> {
     Ok(Timer {
         name,
         duration_ms,
-        event: Event { name: event_name },
+        event: Event::new(event_name),
         mode,
         auto_start_state: None,
+        line: Some(line),
+        leading_comments: Vec::new(),
+        trailing_comment: comment,
+    })
+}
+
+/// Milliseconds of a `timer_duration` (`500`, `500ms`, `2s`); `what` names it in the
+/// error for one that does not fit a `u32`
+fn parse_duration(pair: pest::iterators::Pair<Rule>, what: &str) -> ParseResult<u32> {
+    let line = line_of(&pair);
+    let written = pair.as_str();
+    let mut duration = pair.into_inner();
+    let number = next_pair(&mut duration, line, "a duration")?.as_str();
+    let scale = match duration.next().map(|unit| unit.as_str()) {
+        Some("s") => 1000,
+        _ => 1,
+    };
+    number.parse::<u32>().ok().and_then(|n| n.checked_mul(scale)).ok_or_else(|| ParseError::SyntaxError {
+        line,
+        message: format!("{what} {written} is out of range (at most {} ms)", u32::MAX),
     })
 }
 
+/// `@debounce(50ms)` / `@throttle(1s)`
+fn parse_timing_constraint(pair: pest::iterators::Pair<Rule>) -> ParseResult<TimingConstraint> {
+    let line = line_of(&pair);
+    let mut inner = pair.into_inner();
+    let kind = match next_pair(&mut inner, line, "@debounce or @throttle")?.as_str() {
+        "@throttle" => TimingKind::Throttle,
+        _ => TimingKind::Debounce,
+    };
+    let window_ms = parse_duration(next_pair(&mut inner, line, "a duration")?, "window")?;
+    Ok(TimingConstraint { kind, window_ms })
+}
+
 // ============================================================================
 // CHOICE POINT PARSING
 // ============================================================================
 
 fn parse_choice_def(pair: pest::iterators::Pair<Rule>) -> ParseResult<ChoicePoint> {
+    let line = line_of(&pair);
     let mut inner = pair.into_inner();
 
-    let name = inner.next().unwrap().as_str().to_string();
+    let name = next_pair(&mut inner, line, "a choice name")?.as_str().to_string();
     let mut choice = ChoicePoint::new(&name);
+    choice.line = Some(line);
 
     for branch_pair in inner {
         if branch_pair.as_rule() == Rule::choice_branch {
@@ -199,43 +730,38 @@ fn parse_choice_def(pair: pest::iterators::Pair<Rule>) -> ParseResult<ChoicePoin
 }
 
 fn parse_choice_branch(pair: pest::iterators::Pair<Rule>) -> ParseResult<ChoiceBranch> {
+    let line = line_of(&pair);
     let mut inner = pair.into_inner();
 
     // Guard
-    let guard_pair = inner.next().unwrap();
-    let guard_expr = guard_pair
-        .into_inner()
-        .next()
-        .unwrap()
-        .as_str()
-        .trim()
-        .to_string();
+    let guard_pair = next_pair(&mut inner, line, "a guard")?;
+    let guard_expr = first_pair(guard_pair, "a guard expression")?.as_str().trim().to_string();
 
     // Skip arrow if present
-    let mut next = inner.next().unwrap();
+    let mut next = next_pair(&mut inner, line, "a target state")?;
     if next.as_str() == "->" || next.as_str() == "-->" {
-        next = inner.next().unwrap();
+        next = next_pair(&mut inner, line, "a target state")?;
     }
     
     // Target state
     let target = next.as_str().to_string();
 
-    // Optional action
-    let action = if let Some(action_pair) = inner.next() {
+    // Optional actions
+    let actions = if let Some(action_pair) = inner.next() {
         // Skip arrow if it appears before action
         if action_pair.as_str() == "->" || action_pair.as_str() == "-->" {
             if let Some(real_action) = inner.next() {
-                let action_body = real_action.into_inner().next().unwrap();
-                Some(parse_action_call(action_body)?)
+                let action_body = first_pair(real_action, "an action list")?;
+                parse_action_list(action_body)?
             } else {
-                None
+                Vec::new()
             }
         } else {
-            let action_body = action_pair.into_inner().next().unwrap();
-            Some(parse_action_call(action_body)?)
+            let action_body = first_pair(action_pair, "an action list")?;
+            parse_action_list(action_body)?
         }
     } else {
-        None
+        Vec::new()
     };
 
     Ok(ChoiceBranch {
@@ -243,101 +769,168 @@ fn parse_choice_branch(pair: pest::iterators::Pair<Rule>) -> ParseResult<ChoiceB
             expression: guard_expr,
         },
         target,
-        action,
+        actions,
     })
 }
 
-fn parse_state_definition(pair: pest::iterators::Pair<Rule>) -> ParseResult<State> {
-    let mut inner = pair.into_inner();
-    let name = inner.next().unwrap().as_str().to_string();
+/// A `state` declaration, and the transitions written in its block
+fn parse_state_definition(pair: pest::iterators::Pair<Rule>) -> ParseResult<(State, Vec<Transition>)> {
+    let input = pair.as_span().get_input();
+    let line = line_of(&pair);
+    let mut inner = pair.into_inner().peekable();
+    let is_final = inner.next_if(|p| p.as_rule() == Rule::final_keyword).is_some();
+    let name_pair = next_pair(&mut inner, line, "a state name")?;
+    // The comment belongs to the `state` line, not the body
+    let mut header_end = last_token_end(&name_pair);
 
-    let mut state = State::new(&name, StateType::Simple);
+    let mut state = if name_pair.as_rule() == Rule::state_alias {
+        // state "Long Name" as Alias
+        let mut alias = name_pair.into_inner();
+        let long_name = next_pair(&mut alias, line, "a state's long name")?.into_inner().next().map_or("", |t| t.as_str());
+        let mut state = State::new(next_pair(&mut alias, line, "a state alias")?.as_str(), StateType::Simple);
+        state.description = Some(long_name.to_string());
+        state
+    } else {
+        State::new(name_pair.as_str(), StateType::Simple)
+    };
+    if is_final {
+        state.state_type = StateType::Final;
+    }
 
+    let mut grouped = Vec::new();
+    let mut docs = Vec::new();
     for item in inner {
         match item.as_rule() {
+            Rule::final_annotation => {
+                header_end = item.as_span().end();
+                state.state_type = StateType::Final;
+            }
+            Rule::color_annotation => {
+                header_end = item.as_span().end();
+                let value = item.into_inner().next().map_or("", |v| v.as_str().trim());
+                state.color = Some(value.to_string());
+            }
+            Rule::tag_annotation => {
+                header_end = item.as_span().end();
+                for tag in item.into_inner().map(|t| t.as_str().to_string()) {
+                    if !state.tags.contains(&tag) {
+                        state.tags.push(tag);
+                    }
+                }
+            }
             Rule::description => {
-                state.description = Some(item.as_str().trim().to_string());
+                header_end = item.as_span().end();
+                let text = item.as_str().trim().to_string();
+                state.description = join_description(state.description.take(), Some(text));
             }
+            Rule::doc_comment => docs.push(doc_line(item)),
             Rule::state_body_item => {
-                parse_state_body_item(item, &mut state)?;
+                let count = grouped.len();
+                parse_state_body_item(item, &mut state, &mut grouped)?;
+                // `///` lines document the grouped transition below them
+                let docs = std::mem::take(&mut docs);
+                if let Some(transition) = grouped[count..].first_mut() {
+                    transition.description = (!docs.is_empty()).then(|| docs.join("\n"));
+                }
             }
             Rule::entry_action => {
-                let action = parse_action_call(item.into_inner().next().unwrap())?;
-                state.entry_actions.push(action);
+                state.entry_actions.extend(parse_action_list(first_pair(item, "an action list")?)?);
             }
             Rule::exit_action => {
-                let action = parse_action_call(item.into_inner().next().unwrap())?;
-                state.exit_actions.push(action);
+                state.exit_actions.extend(parse_action_list(first_pair(item, "an action list")?)?);
             }
             Rule::internal_action => {
+                let line = line_of(&item);
                 let mut action_inner = item.into_inner();
-                let event_name = action_inner.next().unwrap().as_str().to_string();
-                let action = parse_action_call(action_inner.next().unwrap())?;
+                let event_name = next_pair(&mut action_inner, line, "an event")?.as_str().to_string();
+                let actions = parse_action_list(next_pair(&mut action_inner, line, "an action list")?)?;
 
                 let transition = Transition {
                     source: state.name.clone(),
                     target: state.name.clone(),
-                    event: Some(Event { name: event_name }),
+                    event: Some(Event::new(event_name)),
                     guard: None,
-                    action: Some(action),
+                    actions,
                     kind: TransitionKind::Internal,
+                    timing_constraint: None,
+                    style: TransitionStyle::Solid,
+                    reversed: false,
+                    description: None,
+                    leading_comments: Vec::new(),
+                    trailing_comment: None,
+                    line: Some(line),
+                    declared_at: Vec::new(),
                 };
                 state.internal_transitions.push(transition);
             }
             _ => {}
         }
     }
+    state.trailing_comment = trailing_comment(input, header_end);
+    state.line = Some(line);
+    state.declared_at = vec![line];
 
-    Ok(state)
+    Ok((state, grouped))
 }
 
-fn parse_state_body_item(pair: pest::iterators::Pair<Rule>, state: &mut State) -> ParseResult<()> {
-    let action_item = pair.into_inner().next().unwrap();
+/// One item of `state`'s body; a grouped transition goes to `grouped`
+fn parse_state_body_item(pair: pest::iterators::Pair<Rule>, state: &mut State, grouped: &mut Vec<Transition>) -> ParseResult<()> {
+    let action_item = first_pair(pair, "a state body item")?;
+    let comment = own_trailing_comment(&action_item);
+    let line = line_of(&action_item);
     match action_item.as_rule() {
+        Rule::grouped_transition => grouped.push(parse_grouped_transition(action_item, &state.name)?),
         Rule::entry_action => {
-            let action = parse_action_call(action_item.into_inner().next().unwrap())?;
-            state.entry_actions.push(action);
+            let mut actions = parse_action_list(first_pair(action_item, "an action list")?)?;
+            if let Some(last) = actions.last_mut() {
+                last.trailing_comment = comment;
+            }
+            state.entry_actions.extend(actions);
         }
         Rule::exit_action => {
-            let action = parse_action_call(action_item.into_inner().next().unwrap())?;
-            state.exit_actions.push(action);
+            let mut actions = parse_action_list(first_pair(action_item, "an action list")?)?;
+            if let Some(last) = actions.last_mut() {
+                last.trailing_comment = comment;
+            }
+            state.exit_actions.extend(actions);
         }
         Rule::timer_start => {
             // Add timer start to entry actions
-            let timer_name = action_item.into_inner().next().unwrap().as_str().to_string();
-            let action = Action {
-                name: format!("start_timer_{}", timer_name),
-                params: vec![timer_name],
-            };
+            let timer_name = first_pair(action_item, "a timer name")?.as_str().to_string();
+            let mut action = Action::new(format!("start_timer_{}", timer_name)).with_params(vec![timer_name]);
+            action.line = Some(line);
+            action.trailing_comment = comment;
             state.entry_actions.push(action);
         }
         Rule::timer_stop => {
             // Add timer stop to exit actions
-            let timer_name = action_item.into_inner().next().unwrap().as_str().to_string();
-            let action = Action {
-                name: format!("stop_timer_{}", timer_name),
-                params: vec![timer_name],
-            };
+            let timer_name = first_pair(action_item, "a timer name")?.as_str().to_string();
+            let mut action = Action::new(format!("stop_timer_{}", timer_name)).with_params(vec![timer_name]);
+            action.line = Some(line);
+            action.trailing_comment = comment;
             state.exit_actions.push(action);
         }
         Rule::internal_transition => {
             // Internal transition with optional guard: event [guard] / action
             let mut inner = action_item.into_inner();
-            let event_name = inner.next().unwrap().as_str().to_string();
+            let mut event = Event::new(next_pair(&mut inner, line, "an event")?.as_str());
 
             let mut guard: Option<Guard> = None;
-            let mut action: Option<Action> = None;
+            let mut actions: Vec<Action> = Vec::new();
+            let mut timing_constraint = None;
 
             for item in inner {
                 match item.as_rule() {
+                    Rule::event_bindings => event.bindings = parse_bindings(item),
+                    Rule::timing_constraint => timing_constraint = Some(parse_timing_constraint(item)?),
                     Rule::guard => {
-                        let expr = item.into_inner().next().unwrap().as_str().trim();
+                        let expr = first_pair(item, "a guard expression")?.as_str().trim();
                         guard = Some(Guard {
                             expression: expr.to_string(),
                         });
                     }
-                    Rule::action_call => {
-                        action = Some(parse_action_call(item)?);
+                    Rule::action_list => {
+                        actions = parse_action_list(item)?;
                     }
                     _ => {}
                 }
@@ -346,25 +939,41 @@ fn parse_state_body_item(pair: pest::iterators::Pair<Rule>, state: &mut State) -
             let transition = Transition {
                 source: state.name.clone(),
                 target: state.name.clone(),
-                event: Some(Event { name: event_name }),
+                event: Some(event),
                 guard,
-                action,
+                actions,
                 kind: TransitionKind::Internal,
+                timing_constraint,
+                style: TransitionStyle::Solid,
+                reversed: false,
+                description: None,
+                leading_comments: Vec::new(),
+                trailing_comment: comment,
+                line: Some(line),
+                declared_at: Vec::new(),
             };
             state.internal_transitions.push(transition);
         }
         Rule::internal_action => {
             let mut inner = action_item.into_inner();
-            let event_name = inner.next().unwrap().as_str().to_string();
-            let action = parse_action_call(inner.next().unwrap())?;
+            let event_name = next_pair(&mut inner, line, "an event")?.as_str().to_string();
+            let actions = parse_action_list(next_pair(&mut inner, line, "an action list")?)?;
 
             let transition = Transition {
                 source: state.name.clone(),
                 target: state.name.clone(),
-                event: Some(Event { name: event_name }),
+                event: Some(Event::new(event_name)),
                 guard: None,
-                action: Some(action),
+                actions,
                 kind: TransitionKind::Internal,
+                timing_constraint: None,
+                style: TransitionStyle::Solid,
+                reversed: false,
+                description: None,
+                leading_comments: Vec::new(),
+                trailing_comment: comment,
+                line: Some(line),
+                declared_at: Vec::new(),
             };
             state.internal_transitions.push(transition);
         }
@@ -378,7 +987,7 @@ fn parse_state_body(pair: pest::iterators::Pair<Rule>, state: &mut State) -> Par
     for item in pair.into_inner() {
         match item.as_rule() {
             Rule::state_body_item => {
-                parse_state_body_item(item, state)?;
+                parse_state_body_item(item, state, &mut Vec::new())?;
             }
             _ => {}
         }
@@ -388,8 +997,9 @@ fn parse_state_body(pair: pest::iterators::Pair<Rule>, state: &mut State) -> Par
 
 #[allow(dead_code)]
 fn parse_hierarchical_state(pair: pest::iterators::Pair<Rule>) -> ParseResult<State> {
+    let line = line_of(&pair);
     let mut inner = pair.into_inner();
-    let name = inner.next().unwrap().as_str().to_string();
+    let name = next_pair(&mut inner, line, "a state name")?.as_str().to_string();
 
     let mut state = State::new(&name, StateType::Composite);
     state.sub_fsm = Some(FsmDefinition::new(format!("{}_sub", name)));
@@ -398,7 +1008,7 @@ fn parse_hierarchical_state(pair: pest::iterators::Pair<Rule>) -> ParseResult<St
         match item.as_rule() {
             Rule::fsm_item => {
                 if let Some(ref mut sub_fsm) = state.sub_fsm {
-                    parse_fsm_item(item, sub_fsm)?;
+                    parse_fsm_item(item, sub_fsm, &mut LineComments::default())?;
                 }
             }
             _ => {}
@@ -409,60 +1019,143 @@ fn parse_hierarchical_state(pair: pest::iterators::Pair<Rule>) -> ParseResult<St
 }
 
 fn parse_transition(pair: pest::iterators::Pair<Rule>) -> ParseResult<Transition> {
+    let comment = trailing_comment(pair.as_span().get_input(), last_token_end(&pair));
+    let line = line_of(&pair);
     let mut inner = pair.into_inner();
 
-    let source = inner.next().unwrap().as_str().to_string();
-    let _arrow = inner.next(); // Skip arrow
-
-    // Parse target - may be a choice target <<choice_name>>
-    let target_pair = inner.next().unwrap();
-    let target = if target_pair.as_rule() == Rule::choice_target {
-        // Extract name from <<name>>
-        let choice_name = target_pair.into_inner().next().unwrap().as_str();
-        format!("<<{}>>", choice_name)
-    } else {
-        target_pair.as_str().to_string()
-    };
+    // `B <-- A` declares the same transition as `A --> B`
+    let first = next_pair(&mut inner, line, "a state")?;
+    let arrow = next_pair(&mut inner, line, "an arrow")?;
+    let second = next_pair(&mut inner, line, "a state")?;
+    let reversed = arrow.as_rule() == Rule::reverse_arrow;
+    let (source, target_pair) = if reversed { (second, first) } else { (first, second) };
+    let source = source.as_str().to_string();
+    let style = arrow_style(&arrow);
+    let target = parse_target(target_pair)?;
 
     let mut transition = Transition {
         source,
         target,
         event: None,
         guard: None,
-        action: None,
+        actions: Vec::new(),
         kind: TransitionKind::External,
+        timing_constraint: None,
+        style,
+        reversed,
+        description: None,
+        leading_comments: Vec::new(),
+        trailing_comment: comment,
+        line: Some(line),
+        declared_at: Vec::new(),
     };
 
     // Parse optional transition label
     if let Some(label) = inner.next() {
         for item in label.into_inner() {
-            match item.as_rule() {
-                Rule::event => {
-                    transition.event = Some(Event {
-                        name: item.as_str().to_string(),
-                    });
-                }
-                Rule::guard => {
-                    let expr = item.into_inner().next().unwrap().as_str().trim();
-                    transition.guard = Some(Guard {
-                        expression: expr.to_string(),
-                    });
-                }
-                Rule::action => {
-                    let action_body = item.into_inner().next().unwrap();
-                    transition.action = Some(parse_action_call(action_body)?);
-                }
-                _ => {}
-            }
+            parse_label_part(item, &mut transition)?;
         }
     }
 
     Ok(transition)
 }
 
+/// `on event [guard] -> Target / action` in the block of state `source`: an ordinary
+/// external transition from it
+fn parse_grouped_transition(pair: pest::iterators::Pair<Rule>, source: &str) -> ParseResult<Transition> {
+    let comment = own_trailing_comment(&pair);
+    let line = line_of(&pair);
+
+    let mut transition = Transition {
+        source: source.to_string(),
+        target: String::new(),
+        event: None,
+        guard: None,
+        actions: Vec::new(),
+        kind: TransitionKind::External,
+        timing_constraint: None,
+        style: TransitionStyle::Solid,
+        reversed: false,
+        description: None,
+        leading_comments: Vec::new(),
+        trailing_comment: comment,
+        line: Some(line),
+        declared_at: Vec::new(),
+    };
+    for item in pair.into_inner() {
+        match item.as_rule() {
+            Rule::transition_arrow => transition.style = arrow_style(&item),
+            Rule::target => transition.target = parse_target(item)?,
+            _ => parse_label_part(item, &mut transition)?,
+        }
+    }
+    Ok(transition)
+}
+
+fn arrow_style(arrow: &pest::iterators::Pair<Rule>) -> TransitionStyle {
+    if arrow.as_str().contains('.') { TransitionStyle::Dashed } else { TransitionStyle::Solid }
+}
+
+/// A state name, `[*]`, or `<<choice>>` for a choice point
+fn parse_target(pair: pest::iterators::Pair<Rule>) -> ParseResult<String> {
+    match pair.clone().into_inner().next().filter(|p| p.as_rule() == Rule::choice_target) {
+        Some(choice) => Ok(format!("<<{}>>", first_pair(choice, "a choice name")?.as_str())),
+        None => Ok(pair.as_str().to_string()),
+    }
+}
+
+/// One part of a transition's label (event, timing, guard or actions) into `transition`
+fn parse_label_part(item: pest::iterators::Pair<Rule>, transition: &mut Transition) -> ParseResult<()> {
+    let line = line_of(&item);
+    match item.as_rule() {
+        Rule::local_kind => transition.kind = TransitionKind::Local,
+        Rule::event => {
+            let mut parts = item.into_inner();
+            let mut event = Event::new(next_pair(&mut parts, line, "an event")?.as_str());
+            if let Some(bindings) = parts.next() {
+                event.bindings = parse_bindings(bindings);
+            }
+            transition.event = Some(event);
+        }
+        Rule::timing_constraint => transition.timing_constraint = Some(parse_timing_constraint(item)?),
+        Rule::guard => {
+            let expr = first_pair(item, "a guard expression")?.as_str().trim();
+            transition.guard = Some(Guard {
+                expression: expr.to_string(),
+            });
+        }
+        Rule::action | Rule::grouped_action => {
+            let action_body = first_pair(item, "an action list")?;
+            transition.actions = parse_action_list(action_body)?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// The names in `(a, b)` after a trigger's event
+fn parse_bindings(pair: pest::iterators::Pair<Rule>) -> Vec<String> {
+    pair.into_inner().map(|name| name.as_str().to_string()).collect()
+}
+
+/// The actions of one `a; b` list, each with the list's line
+fn parse_action_list(pair: pest::iterators::Pair<Rule>) -> ParseResult<Vec<Action>> {
+    let line = line_of(&pair);
+    pair.into_inner()
+        .map(|call| {
+            let mut action = parse_action_call(call)?;
+            action.line = Some(line);
+            Ok(action)
+        })
+        .collect()
+}
+
 fn parse_action_call(pair: pest::iterators::Pair<Rule>) -> ParseResult<Action> {
-    let mut inner = pair.into_inner();
-    let name = inner.next().unwrap().as_str().to_string();
+    let line = line_of(&pair);
+    let parenthesized = pair.as_str().trim_end().ends_with(')');
+    let mut inner = pair.into_inner().peekable();
+    let is_async = inner.next_if(|p| p.as_rule() == Rule::async_keyword).is_some();
+    let name = next_pair(&mut inner, line, "an action name")?.as_str().to_string();
 
     let mut params = Vec::new();
     if let Some(params_pair) = inner.next() {
@@ -471,7 +1164,14 @@ fn parse_action_call(pair: pest::iterators::Pair<Rule>) -> ParseResult<Action> {
         }
     }
 
-    Ok(Action { name, params })
+    Ok(Action {
+        name,
+        params,
+        is_async,
+        parenthesized,
+        line: None,
+        trailing_comment: None,
+    })
 }
 
 #[allow(dead_code)]
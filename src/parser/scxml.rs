@@ -0,0 +1,363 @@
+//! SCXML Import
+//! Converts the core of W3C SCXML into an FSM definition: states, finals, initial
+//! states, transitions with `event`/`cond`/`target`, and `onentry`/`onexit` content
+//! as action names. Nested states are flattened: a compound state's transitions are
+//! copied onto its leaf states (a leaf's own transitions for the same event win), a
+//! transition into it enters its initial leaf, and its entry/exit content runs on the
+//! transitions that cross its boundary. Whatever has no DSL equivalent (parallel
+//! regions, history, the datamodel, ...) is skipped and listed in the warnings.
+
+use std::collections::HashMap;
+
+use roxmltree::{Document, Node};
+
+use super::{ParseError, ParseResult};
+use crate::fsm::{Action, Event, FsmDefinition, Guard, InitialTransition, State, StateType, Transition, TransitionKind};
+
+/// Definitions imported from an SCXML document, and what could not be carried over
+#[derive(Debug, Clone)]
+pub struct ScxmlImport {
+    pub fsms: Vec<FsmDefinition>,
+    pub warnings: Vec<String>,
+}
+
+/// FSM name when the `<scxml>` element has no `name`
+pub const DEFAULT_SCXML_NAME: &str = "Statechart";
+
+/// Import the `<scxml>` document `xml`; malformed XML or a different root element is an
+/// error, unsupported content only a warning
+pub fn import_scxml(xml: &str) -> ParseResult<ScxmlImport> {
+    let doc = Document::parse(xml).map_err(|e| ParseError::SyntaxError {
+        line: e.pos().row as usize,
+        message: e.to_string(),
+    })?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "scxml" {
+        return Err(ParseError::SyntaxError {
+            line: line_of(&root),
+            message: format!("expected an <scxml> document, found <{}>", root.tag_name().name()),
+        });
+    }
+
+    let mut importer = Importer::default();
+    let name = root.attribute("name").unwrap_or(DEFAULT_SCXML_NAME);
+    let name = importer.identifier(name, "FSM");
+    importer.collect(root, None);
+    let fsm = importer.build(root, name);
+    Ok(ScxmlImport { fsms: vec![fsm], warnings: importer.warnings })
+}
+
+/// A `<state>`, `<final>` or `<parallel>` of the document
+struct StateNode<'a, 'input> {
+    node: Node<'a, 'input>,
+    name: String,
+    /// Enclosing compound state
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+#[derive(Default)]
+struct Importer<'a, 'input> {
+    states: Vec<StateNode<'a, 'input>>,
+    /// State index by SCXML id
+    ids: HashMap<&'a str, usize>,
+    /// Identifiers given for names that are not valid ones
+    renamed: HashMap<String, String>,
+    warnings: Vec<String>,
+}
+
+impl<'a, 'input> Importer<'a, 'input> {
+    /// Index the states below `node`, warning about elements that are skipped
+    fn collect(&mut self, node: Node<'a, 'input>, parent: Option<usize>) {
+        for child in node.children().filter(Node::is_element) {
+            match child.tag_name().name() {
+                tag @ ("state" | "final" | "parallel") => {
+                    let id = child.attribute("id").unwrap_or_default();
+                    if id.is_empty() || self.ids.contains_key(id) {
+                        let problem = if id.is_empty() { "without an id" } else { "with a duplicate id" };
+                        self.warn(&child, format!("<{tag}> {problem} skipped"));
+                        continue;
+                    }
+                    let index = self.states.len();
+                    let name = self.identifier(id, "state");
+                    self.states.push(StateNode { node: child, name, parent, children: Vec::new() });
+                    self.ids.insert(id, index);
+                    if let Some(parent) = parent {
+                        self.states[parent].children.push(index);
+                    }
+                    if tag == "parallel" {
+                        self.warn(&child, format!("<parallel> '{id}' imported as a simple state; its regions were skipped"));
+                    } else {
+                        self.collect(child, Some(index));
+                    }
+                }
+                "transition" | "onentry" | "onexit" | "initial" if parent.is_some() => {}
+                tag => self.warn(&child, format!("<{tag}> is not supported and was skipped")),
+            }
+        }
+    }
+
+    fn build(&mut self, root: Node<'a, 'input>, name: String) -> FsmDefinition {
+        let mut fsm = FsmDefinition::new(name);
+        fsm.line = Some(line_of(&root));
+
+        for index in 0..self.states.len() {
+            if !self.states[index].children.is_empty() {
+                continue;
+            }
+            let node = self.states[index].node;
+            let state_type = match node.tag_name().name() {
+                "final" => StateType::Final,
+                _ => StateType::Simple,
+            };
+            let mut state = State::new(self.states[index].name.clone(), state_type);
+            state.line = Some(line_of(&node));
+            state.declared_at = vec![line_of(&node)];
+            state.entry_actions = self.content(node, "onentry");
+            state.exit_actions = self.content(node, "onexit");
+            if let Some(parent) = self.states[index].parent {
+                state.tags.push(self.states[parent].name.clone());
+            }
+            fsm.states.push(state);
+        }
+
+        // Document initial state, entering the compounds around it
+        let initial = match root.attribute("initial") {
+            Some(ids) => self.lookup(&root, ids),
+            None => self.states.iter().position(|s| s.parent.is_none()),
+        };
+        if let Some(leaf) = initial.map(|index| self.enter(index)) {
+            let mut initial = InitialTransition::new(self.states[leaf].name.clone());
+            initial.line = fsm.line;
+            initial.actions = self.entered(None, leaf);
+            fsm.initial_transitions.push(initial);
+        }
+
+        let leaves: Vec<usize> = (0..self.states.len()).filter(|&i| self.states[i].children.is_empty()).collect();
+        for leaf in leaves {
+            self.transitions_of(leaf, &mut fsm);
+        }
+        fsm
+    }
+
+    /// Transitions of `leaf` and of the compounds around it, innermost first
+    fn transitions_of(&mut self, leaf: usize, fsm: &mut FsmDefinition) {
+        let mut taken: Vec<Option<String>> = Vec::new();
+        let mut owner = Some(leaf);
+        while let Some(index) = owner {
+            let node = self.states[index].node;
+            let transitions: Vec<Node> = node.children().filter(|n| n.has_tag_name("transition")).collect();
+            let mut unguarded: Vec<Option<String>> = Vec::new();
+            for element in transitions {
+                let events = match element.attribute("event") {
+                    Some(events) => events.split_whitespace().map(|e| self.event(&element, e)).collect(),
+                    None => vec![Some(String::new())],
+                };
+                let guard = element.attribute("cond").map(|cond| self.guard(&element, cond));
+                for event in events.into_iter().flatten() {
+                    let event = (!event.is_empty()).then_some(event);
+                    // A nearer state's unguarded transition for the event is taken first
+                    if index != leaf && taken.contains(&event) {
+                        continue;
+                    }
+                    if guard.is_none() {
+                        unguarded.push(event.clone());
+                    }
+                    self.transition(leaf, &element, event, guard.clone(), index != leaf, fsm);
+                }
+            }
+            taken.extend(unguarded);
+            owner = self.states[index].parent;
+        }
+    }
+
+    fn transition(
+        &mut self,
+        leaf: usize,
+        element: &Node<'a, 'input>,
+        event: Option<String>,
+        guard: Option<Guard>,
+        inherited: bool,
+        fsm: &mut FsmDefinition,
+    ) {
+        let source = self.states[leaf].name.clone();
+        let content = self.executable(*element, "transition");
+        let target = match element.attribute("target") {
+            Some(ids) => match self.lookup(element, ids) {
+                Some(index) => Some(self.enter(index)),
+                None => return,
+            },
+            None => None,
+        };
+        let mut transition = Transition::new(source.clone(), source);
+        transition.event = event.map(Event::new);
+        transition.guard = guard;
+        // A compound's transition is listed with each leaf it was copied to
+        transition.line = Some(line_of(if inherited { &self.states[leaf].node } else { element }));
+        match target {
+            Some(target) => {
+                transition.target = self.states[target].name.clone();
+                transition.actions = self.exited(leaf, target);
+                transition.actions.extend(content);
+                transition.actions.extend(self.entered(Some(leaf), target));
+                fsm.transitions.push(transition);
+            }
+            // Targetless: the content runs without leaving the state
+            None => {
+                transition.kind = TransitionKind::Internal;
+                transition.actions = content;
+                if let Some(state) = fsm.states.iter_mut().find(|s| s.name == transition.source) {
+                    state.internal_transitions.push(transition);
+                }
+            }
+        }
+    }
+
+    /// The leaf state entering `index` ends in, following initial children
+    fn enter(&mut self, index: usize) -> usize {
+        let mut current = index;
+        // Bounded by the number of states, since every step goes one level deeper
+        for _ in 0..self.states.len() {
+            let state = &self.states[current];
+            if state.children.is_empty() {
+                break;
+            }
+            let node = state.node;
+            let first = state.children[0];
+            let initial = match node.attribute("initial") {
+                Some(ids) => self.lookup(&node, ids),
+                None => node
+                    .children()
+                    .find(|n| n.has_tag_name("initial"))
+                    .and_then(|initial| initial.children().find(|n| n.has_tag_name("transition")))
+                    .and_then(|t| t.attribute("target").map(|ids| (t, ids)))
+                    .and_then(|(t, ids)| self.lookup(&t, ids)),
+            };
+            current = initial.filter(|i| self.is_within(*i, current)).unwrap_or(first);
+        }
+        current
+    }
+
+    /// Compounds around `index`, innermost first
+    fn ancestors(&self, index: usize) -> Vec<usize> {
+        std::iter::successors(self.states[index].parent, |&i| self.states[i].parent).collect()
+    }
+
+    fn is_within(&self, index: usize, ancestor: usize) -> bool {
+        self.ancestors(index).contains(&ancestor)
+    }
+
+    /// Exit content of the compounds left going from `from` to `to`, innermost first
+    fn exited(&mut self, from: usize, to: usize) -> Vec<Action> {
+        let kept = self.ancestors(to);
+        let left: Vec<usize> = self.ancestors(from).into_iter().filter(|a| !kept.contains(a)).collect();
+        left.into_iter().flat_map(|a| self.content(self.states[a].node, "onexit")).collect()
+    }
+
+    /// Entry content of the compounds entered going from `from` to `to`, outermost first
+    fn entered(&mut self, from: Option<usize>, to: usize) -> Vec<Action> {
+        let kept = from.map(|f| self.ancestors(f)).unwrap_or_default();
+        let mut entered: Vec<usize> = self.ancestors(to).into_iter().filter(|a| !kept.contains(a)).collect();
+        entered.reverse();
+        entered.into_iter().flat_map(|a| self.content(self.states[a].node, "onentry")).collect()
+    }
+
+    /// Actions of the `<onentry>` or `<onexit>` elements of `node`
+    fn content(&mut self, node: Node<'a, 'input>, tag: &str) -> Vec<Action> {
+        node.children()
+            .filter(|n| n.has_tag_name(tag))
+            .flat_map(|block| self.executable(block, tag))
+            .collect()
+    }
+
+    /// Executable content of `node` as action names: `<raise event="go">` is `raise_go`,
+    /// `<send event="go">` `send_go`, `<log label="x">` `log_x`, `<assign location="x">`
+    /// `assign_x`, `<cancel sendid="x">` `cancel_x` and `<script>` `script`
+    fn executable(&mut self, node: Node<'a, 'input>, within: &str) -> Vec<Action> {
+        let mut actions = Vec::new();
+        for element in node.children().filter(Node::is_element) {
+            let (prefix, attribute) = match element.tag_name().name() {
+                "raise" | "send" => (element.tag_name().name(), "event"),
+                "log" => ("log", "label"),
+                "assign" => ("assign", "location"),
+                "cancel" => ("cancel", "sendid"),
+                "script" => ("script", ""),
+                tag => {
+                    self.warn(&element, format!("<{tag}> in <{within}> is not supported and was skipped"));
+                    continue;
+                }
+            };
+            let name = match element.attribute(attribute).filter(|v| !v.trim().is_empty()) {
+                Some(value) => format!("{prefix}_{value}"),
+                None => prefix.to_string(),
+            };
+            actions.push(Action::new(self.identifier(&name, "action")));
+        }
+        actions
+    }
+
+    /// The state named by the first id of `ids` (SCXML allows several)
+    fn lookup(&mut self, node: &Node, ids: &str) -> Option<usize> {
+        let first = ids.split_whitespace().next()?;
+        if ids.split_whitespace().nth(1).is_some() {
+            self.warn(node, format!("only the first of the states '{}' was imported", ids.trim()));
+        }
+        let index = self.ids.get(first).copied();
+        if index.is_none() {
+            self.warn(node, format!("unknown state '{first}'; the transition was skipped"));
+        }
+        index
+    }
+
+    /// An event descriptor as an event name; wildcards have none
+    fn event(&mut self, node: &Node, descriptor: &str) -> Option<String> {
+        let descriptor = descriptor.trim_end_matches(".*");
+        if descriptor.contains('*') {
+            self.warn(node, format!("wildcard event '{descriptor}' is not supported; the transition was skipped"));
+            return None;
+        }
+        Some(self.identifier(descriptor, "event"))
+    }
+
+    /// A `cond` expression as a guard; brackets would end the DSL guard early
+    fn guard(&mut self, node: &Node, cond: &str) -> Guard {
+        let expression = cond.split_whitespace().collect::<Vec<_>>().join(" ");
+        if expression.contains(['[', ']']) {
+            self.warn(node, format!("brackets in condition '{expression}' replaced by parentheses"));
+        }
+        Guard::new(expression.replace('[', "(").replace(']', ")"))
+    }
+
+    /// `name` as a DSL identifier; other characters become `_`
+    fn identifier(&mut self, name: &str, what: &str) -> String {
+        if let Some(renamed) = self.renamed.get(name) {
+            return renamed.clone();
+        }
+        let mut identifier: String =
+            name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect();
+        if !identifier.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            identifier.insert(0, '_');
+        }
+        if identifier != name {
+            self.push_warning(format!("{what} '{name}' renamed to '{identifier}'"));
+            self.renamed.insert(name.to_string(), identifier.clone());
+        }
+        identifier
+    }
+
+    fn warn(&mut self, node: &Node, message: String) {
+        self.push_warning(format!("line {}: {}", line_of(node), message));
+    }
+
+    /// Content shared by several leaf states is read once per leaf; it is reported once
+    fn push_warning(&mut self, warning: String) {
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+    }
+}
+
+/// 1-based line `node` starts on
+fn line_of(node: &Node) -> usize {
+    node.document().text_pos_at(node.range().start).row as usize
+}
@@ -1,6 +1,7 @@
 //! Unit tests for the FSM parser
 
-use crate::parser::parse_fsm;
+use crate::fsm::{Action, EventParam, LayoutHint, Note, NotePosition, StateType, TimingConstraint};
+use crate::parser::{parse_fsm, ParseError};
 
 #[test]
 fn test_parse_simple_fsm() {
@@ -18,7 +19,7 @@ fn test_parse_simple_fsm() {
 
     let fsm = &fsms[0];
     assert_eq!(fsm.name, "Simple");
-    assert_eq!(fsm.initial_state, Some("Idle".to_string()));
+    assert_eq!(fsm.initial_state(), Some("Idle"));
     assert_eq!(fsm.states.len(), 2);
     // Only external transitions count (initial is a pseudo-transition)
     assert_eq!(fsm.transitions.len(), 1); // Idle->Running
@@ -145,8 +146,8 @@ fn test_parse_transition_with_action() {
         .find(|t| t.source == "A" && t.target == "B")
         .unwrap();
     
-    assert!(transition.action.is_some());
-    assert_eq!(transition.action.as_ref().unwrap().name, "do_something");
+    assert_eq!(transition.actions.len(), 1);
+    assert_eq!(transition.actions[0].name, "do_something");
 }
 
 #[test]
@@ -167,7 +168,94 @@ fn test_parse_full_transition() {
     
     assert!(transition.event.is_some());
     assert!(transition.guard.is_some());
-    assert!(transition.action.is_some());
+    assert!(!transition.actions.is_empty());
+}
+
+#[test]
+fn test_parse_transition_with_action_list() {
+    let source = r#"
+        fsm Test {
+            [*] --> A
+            A --> B : Halt [is_safe] / set_speed(0); disable_power; log_event(reason, 2)
+        }
+    "#;
+
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let transition = fsms[0].transitions.iter().find(|t| t.source == "A").unwrap();
+
+    let names: Vec<&str> = transition.actions.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(names, vec!["set_speed", "disable_power", "log_event"]);
+    assert_eq!(transition.actions[0].params, vec!["0"]);
+    assert!(transition.actions[1].params.is_empty());
+    assert_eq!(transition.actions[2].params, vec!["reason", "2"]);
+    assert_eq!(transition.label(), "Halt [is_safe] / set_speed(0); disable_power; log_event(reason, 2)");
+}
+
+#[test]
+fn test_parse_state_action_lists() {
+    let source = r#"
+        fsm Test {
+            [*] --> Active
+            state Active {
+                entry / power_on; start_clock(1)
+                exit / stop_clock
+                tick [is_ready] / sample; publish
+            }
+        }
+    "#;
+
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let active = &fsms[0].states[0];
+
+    let entry: Vec<&str> = active.entry_actions.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(entry, vec!["power_on", "start_clock"]);
+    assert_eq!(active.exit_actions.len(), 1);
+    let internal: Vec<&str> = active.internal_transitions[0].actions.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(internal, vec!["sample", "publish"]);
+}
+
+#[test]
+fn test_parse_final_states() {
+    let source = "fsm Job {\n    [*] --> Running\n    Running --> Done : finish\n    Running --> Failed : crash\n    final state Done\n    state Failed <<final>>\n}\n";
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+
+    let state_type = |name: &str| fsm.states.iter().find(|s| s.name == name).unwrap().state_type;
+    assert_eq!(state_type("Done"), StateType::Final);
+    assert_eq!(state_type("Failed"), StateType::Final);
+    assert_eq!(state_type("Running"), StateType::Simple);
+
+    // Both spellings print as the keyword
+    let printed = crate::parser::format::format_fsm(fsm);
+    assert!(printed.contains("    final state Done\n    final state Failed\n"), "{}", printed);
+    let reparsed = &parse_fsm(&printed).unwrap()[0];
+    assert!(reparsed.states.iter().filter(|s| s.is_final()).map(|s| &s.name).eq(["Done", "Failed"]));
+}
+
+#[test]
+fn test_parse_event_params_and_bindings() {
+    let source = "fsm Motor {\n    events {\n        set_speed(rpm: u16), stop\n        move_to(x: i32, y: core::num::NonZeroU8)\n    }\n    [*] --> Idle\n    state Idle {\n        set_speed(rpm) / log(rpm)\n    }\n    Idle --> Idle : set_speed(rpm) [speed_ok(rpm)] / apply_speed(rpm)\n    Idle --> Idle : move_to(x, y)\n}\n";
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+
+    let param = |name: &str, ty: &str| EventParam { name: name.to_string(), ty: ty.to_string() };
+    assert_eq!(fsm.events[0].params, [param("rpm", "u16")]);
+    assert!(fsm.events[1].params.is_empty());
+    assert_eq!(fsm.events[2].params, [param("x", "i32"), param("y", "core::num::NonZeroU8")]);
+
+    let transition = &fsm.transitions[0];
+    let event = transition.event.as_ref().unwrap();
+    assert_eq!((event.name.as_str(), event.bindings.as_slice()), ("set_speed", &["rpm".to_string()][..]));
+    assert_eq!(transition.guard.as_ref().map(|g| (g.method().into_owned(), g.args())), Some(("speed_ok".to_string(), vec!["rpm"])));
+    assert_eq!(fsm.transitions[1].event.as_ref().unwrap().pattern(), "move_to(x, y)");
+    assert_eq!(fsm.states[0].internal_transitions[0].event.as_ref().unwrap().bindings, ["rpm"]);
+    assert_eq!(fsm.bound_params(transition), [("rpm".to_string(), "u16".to_string())]);
+
+    // The formatter prints declarations and bindings back
+    let printed = crate::parser::format::format_fsm(fsm);
+    assert!(printed.contains("        set_speed(rpm: u16)\n        stop\n        move_to(x: i32, y: core::num::NonZeroU8)\n"), "{}", printed);
+    assert!(printed.contains("    Idle --> Idle : set_speed(rpm) [speed_ok(rpm)] / apply_speed(rpm)\n"), "{}", printed);
+    assert!(printed.contains("        set_speed(rpm) / log(rpm)\n"), "{}", printed);
+    let reparsed = &parse_fsm(&printed).unwrap()[0];
+    assert!(reparsed.events.iter().map(|e| &e.params).eq(fsm.events.iter().map(|e| &e.params)));
 }
 
 #[test]
@@ -193,6 +281,39 @@ fn test_parse_timer() {
     let heartbeat = fsm.timers.iter().find(|t| t.name == "heartbeat").unwrap();
     assert_eq!(heartbeat.duration_ms, 1000);
     assert_eq!(heartbeat.mode, crate::fsm::TimerMode::Periodic);
+
+    // Durations in seconds or milliseconds, with or without `=`
+    let source = "fsm Test {\n    timer keepalive 2s -> Ping periodic\n    timer debounce = 20ms -> Settled\n    [*] --> Idle\n}\n";
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    let durations: Vec<(&str, u32)> = fsm.timers.iter().map(|t| (t.name.as_str(), t.duration_ms)).collect();
+    assert_eq!(durations, [("keepalive", 2000), ("debounce", 20)]);
+    assert_eq!(fsm.timers[0].mode, crate::fsm::TimerMode::Periodic);
+
+    // Seconds are checked after scaling
+    let err = parse_fsm("fsm Test {\n    timer t = 5000000s -> tick\n}\n").unwrap_err();
+    assert!(err.to_string().contains("timer duration 5000000s is out of range"), "{err}");
+}
+
+#[test]
+fn test_parse_timing_constraints() {
+    let source = "fsm Door {\n    [*] --> Closed\n\n    Closed --> Open : door_opened @debounce(50ms) [unlocked] / beep\n    Open --> Closed : door_closed\n\n    state Open {\n        sample @throttle(1s) / log_sample\n    }\n}\n";
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    let opened = &fsm.transitions[0];
+    assert_eq!(opened.timing_constraint, Some(TimingConstraint::debounce(50)));
+    assert_eq!(opened.guard.as_ref().map(|g| g.expression.as_str()), Some("unlocked"));
+    assert_eq!(opened.label(), "door_opened @debounce(50ms) [unlocked] / beep");
+    assert_eq!(opened.diagram_label(), "door_opened ⏱debounce 50ms [unlocked] / beep");
+    assert_eq!(fsm.transitions[1].timing_constraint, None);
+    let sample = &fsm.states.iter().find(|s| s.name == "Open").unwrap().internal_transitions[0];
+    assert_eq!(sample.timing_constraint, Some(TimingConstraint::throttle(1000)));
+
+    // The printer writes them back after the event
+    assert_eq!(crate::parser::format::format_fsm(fsm), source);
+
+    // Only after an event, and with a window that fits
+    assert!(parse_fsm("fsm Door {\n    A --> B : @debounce(50ms)\n}\n").is_err());
+    let err = parse_fsm("fsm Door {\n    A --> B : go @throttle(5000000s)\n}\n").unwrap_err();
+    assert!(err.to_string().contains("window 5000000s is out of range"), "{err}");
 }
 
 #[test]
@@ -295,6 +416,41 @@ fn test_parse_empty_fsm() {
     assert!(fsms[0].states.is_empty());
 }
 
+#[test]
+fn test_parse_fsms_without_transitions() {
+    use crate::fsm::{NO_INITIAL_STATE, NO_STATES};
+    use crate::parser::diagnostics::{diagnostics, Severity};
+
+    // Sketches are valid, with a warning saying what to add next
+    let empty = &parse_fsm("fsm Empty { }").unwrap()[0];
+    assert_eq!(empty.validate(), Ok(()));
+    assert_eq!(empty.warnings(), [NO_STATES]);
+
+    let single = &parse_fsm("fsm Single {\n    [*] --> Idle\n}").unwrap()[0];
+    assert_eq!((single.states.len(), single.initial_state()), (1, Some("Idle")));
+    assert_eq!(single.validate(), Ok(()));
+    assert_eq!(single.warnings(), ["Machine has no transitions: it stays in 'Idle'"]);
+
+    let source = "fsm Parts {\n    state Idle\n    state Busy\n}\n";
+    let parts = &parse_fsm(source).unwrap()[0];
+    assert!(parts.transitions.is_empty() && parts.initial_state().is_none());
+    assert_eq!(parts.validate(), Ok(()));
+    let found = diagnostics(source, parts);
+    assert_eq!(found[0].severity, Severity::Warning);
+    assert_eq!(
+        found[0].message,
+        "Machine has no transitions: add `[*] --> Idle` to choose the initial state (until then the first state is used)"
+    );
+    let fixed = found[0].fixes[0].apply(source).unwrap();
+    assert!(parse_fsm(&fixed).unwrap()[0].initial_state() == Some("Idle"));
+
+    // Once something handles events, the initial state is required again
+    let busy = &parse_fsm("fsm Busy {\n    state Idle {\n        poke / react\n    }\n}").unwrap()[0];
+    assert!(!busy.is_skeleton());
+    assert_eq!(busy.validate(), Err(vec![NO_INITIAL_STATE.to_string()]));
+    assert!(busy.warnings().is_empty());
+}
+
 #[test]
 fn test_implicit_state_creation() {
     let source = r#"
@@ -313,3 +469,1836 @@ fn test_implicit_state_creation() {
     assert!(fsm.states.iter().any(|s| s.name == "B"));
     assert!(fsm.states.iter().any(|s| s.name == "C"));
 }
+
+#[test]
+fn test_state_merge_is_order_independent() {
+    let declared_first = "fsm M {\n    state Idle : Waiting\n    [*] --> Idle\n    Idle --> Busy : go\n}\n";
+    let mentioned_first = "fsm M {\n    [*] --> Idle\n    Idle --> Busy : go\n    state Idle : Waiting\n}\n";
+
+    let a = &parse_fsm(declared_first).unwrap()[0];
+    let b = &parse_fsm(mentioned_first).unwrap()[0];
+    for fsm in [a, b] {
+        let idle = fsm.states.iter().find(|s| s.name == "Idle").unwrap();
+        assert_eq!(idle.description.as_deref(), Some("Waiting"));
+        assert!(idle.is_declared());
+        assert_eq!(fsm.implicit_states(), ["Busy"]);
+    }
+    assert_eq!(a.states[0].line, Some(2));
+    assert_eq!(b.states[0].line, Some(4));
+}
+
+#[test]
+fn test_duplicate_transitions_merge() {
+    let source = r#"
+        fsm M {
+            [*] --> Idle
+            Idle --> Active : start / spin_up
+            Active --> Idle : stop
+            Idle --> Active : start / spin_up
+            Idle --> Active : start [ready] / spin_up
+            Idle --> Active : start / spin_up
+        }
+    "#;
+    let fsm = &parse_fsm(source).unwrap()[0];
+    assert_eq!(fsm.transitions.len(), 3);
+    assert_eq!(fsm.transitions[0].line, Some(4));
+    assert_eq!(fsm.transitions[0].declared_at, [4, 6, 8]);
+    // A different guard is a different transition
+    assert!(fsm.transitions[2].declared_at.is_empty());
+    assert_eq!(fsm.infos()[0], "Duplicate transition 'Idle --> Active : start / spin_up' merged (lines 4, 6, 8)");
+    assert!(fsm.warnings().is_empty());
+
+    // One match arm per transition
+    let code = crate::codegen::generate_rust_code(fsm);
+    assert_eq!(code.matches("spin_up(").count(), code.matches("fn spin_up").count() + 2);
+}
+
+#[test]
+fn test_duplicate_state_declarations_merge() {
+    let source = r#"
+        fsm M {
+            state Idle {
+                entry / led_on
+                ping / pong
+            }
+            [*] --> Idle
+            state Idle : Waiting {
+                entry / beep
+                poke / pong
+            }
+        }
+    "#;
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let idle = &fsm.states[0];
+    assert_eq!(fsm.states.len(), 1);
+    assert_eq!(idle.declared_at, [3, 8]);
+    let entries: Vec<&str> = idle.entry_actions.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(entries, ["led_on", "beep"]);
+    assert_eq!(idle.internal_transitions.len(), 2);
+    assert_eq!(idle.description.as_deref(), Some("Waiting"));
+    assert_eq!(fsm.duplicate_states().len(), 1);
+}
+
+#[test]
+fn test_parse_fsm_ignores_include_directive() {
+    let source = r#"
+        include "common.fsm"
+        fsm Test {
+            [*] --> Idle
+        }
+    "#;
+
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    assert_eq!(fsms.len(), 1);
+    assert!(fsms[0].source_file.is_none());
+}
+
+fn include_test_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("oxidate_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("common")).unwrap();
+    dir
+}
+
+#[test]
+fn test_parse_fsm_file_resolves_includes() {
+    let dir = include_test_dir("includes");
+    std::fs::write(
+        dir.join("main.fsm"),
+        "include \"common/fault.fsm\"\nfsm Motor {\n    [*] --> Off\n    Off --> On : start\n}\n",
+    )
+    .unwrap();
+    // The shared fragment is included twice but only loaded once
+    std::fs::write(
+        dir.join("common/fault.fsm"),
+        "include \"shared.fsm\"\ninclude \"shared.fsm\"\nfsm Fault {\n    [*] --> Ok\n    Ok --> Failed : fault\n}\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("common/shared.fsm"), "fsm Shared {\n    [*] --> A\n}\n").unwrap();
+
+    let fsms = crate::parser::loader::parse_fsm_file(dir.join("main.fsm")).expect("Should load successfully");
+    let names: Vec<&str> = fsms.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, vec!["Motor", "Fault", "Shared"]);
+    assert!(fsms[0].source_file.as_ref().unwrap().ends_with("main.fsm"));
+    assert!(fsms[1].source_file.as_ref().unwrap().ends_with("fault.fsm"));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_parse_fsm_file_detects_include_cycle() {
+    let dir = include_test_dir("include_cycle");
+    std::fs::write(dir.join("a.fsm"), "include \"b.fsm\"\nfsm A {\n    [*] --> X\n}\n").unwrap();
+    std::fs::write(dir.join("b.fsm"), "fsm B {\n    [*] --> Y\n}\n\ninclude \"a.fsm\"\n").unwrap();
+
+    let err = crate::parser::loader::parse_fsm_file(dir.join("a.fsm")).unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("b.fsm:5"), "unexpected error: {}", message);
+    assert!(message.contains("cycle"), "unexpected error: {}", message);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_parse_fsm_file_missing_include() {
+    let dir = include_test_dir("include_missing");
+    std::fs::write(dir.join("main.fsm"), "\ninclude \"nope.fsm\"\n").unwrap();
+
+    let err = crate::parser::loader::parse_fsm_file(dir.join("main.fsm")).unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("main.fsm:2"), "unexpected error: {}", message);
+    assert!(message.contains("nope.fsm"), "unexpected error: {}", message);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_parse_doc_comments() {
+    let source = r#"
+        /// Controls the pump
+        /// Second line
+        fsm Pump {
+            //// banner, not documentation
+            [*] --> Idle
+            /// Waiting for demand
+            state Idle : Pump off
+            /// Start when pressure drops
+            Idle --> Running : low_pressure / start_motor
+            Running --> Idle : high_pressure
+            state Running {
+                /// stray docs inside a body are ignored
+                entry / log_start
+            }
+            /// dangling docs are ignored too
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let fsm = &fsms[0];
+
+    assert_eq!(fsm.description.as_deref(), Some("Controls the pump\nSecond line"));
+    let idle = fsm.states.iter().find(|s| s.name == "Idle").unwrap();
+    assert_eq!(idle.description.as_deref(), Some("Waiting for demand\nPump off"));
+    assert_eq!(fsm.transitions[0].description.as_deref(), Some("Start when pressure drops"));
+    assert!(fsm.transitions[1].description.is_none());
+}
+
+#[test]
+fn test_parse_trailing_comments() {
+    let source = r#"
+        fsm Door {
+            [*] --> Locked
+            // standalone comments stay out of the model
+            state Locked : Bolted // checked nightly
+            state Alarming { // siren on
+                entry / sound_siren
+            }
+            state Manual : see http://example.com/door
+            Locked --> Alarming : invalid_key [attempts > 3] // ticket ABC-123
+            // not a comment of the transition above
+            Alarming --> Locked : reset
+            Locked --> Manual : override / log(code) // audited
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let fsm = &fsms[0];
+
+    assert_eq!(fsm.transitions[0].trailing_comment.as_deref(), Some("ticket ABC-123"));
+    assert_eq!(fsm.transitions[0].guard.as_ref().unwrap().expression, "attempts > 3");
+    assert!(fsm.transitions[1].trailing_comment.is_none());
+    assert_eq!(fsm.transitions[2].trailing_comment.as_deref(), Some("audited"));
+
+    let state = |name: &str| fsm.states.iter().find(|s| s.name == name).unwrap();
+    assert_eq!(state("Locked").description.as_deref(), Some("Bolted"));
+    assert_eq!(state("Locked").trailing_comment.as_deref(), Some("checked nightly"));
+    assert_eq!(state("Alarming").trailing_comment.as_deref(), Some("siren on"));
+    assert_eq!(state("Manual").description.as_deref(), Some("see http://example.com/door"));
+    assert!(state("Manual").trailing_comment.is_none());
+}
+
+#[test]
+fn test_state_description_with_braces() {
+    use crate::parser::format::format_fsm;
+
+    // A `{` opens the body only where it ends the line
+    let source = r#"
+        fsm Lexer {
+            [*] --> Parsing
+            state Parsing : handles { and } tokens
+            state Nesting : counts { depth { // per block
+                entry / push
+            }
+            state Closing : emits } {
+            }
+        }
+    "#;
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    let state = |name: &str| fsm.states.iter().find(|s| s.name == name).unwrap();
+    assert_eq!(state("Parsing").description.as_deref(), Some("handles { and } tokens"));
+    assert_eq!(state("Nesting").description.as_deref(), Some("counts { depth"));
+    assert_eq!(state("Nesting").trailing_comment.as_deref(), Some("per block"));
+    assert_eq!(state("Nesting").entry_actions[0].name, "push");
+    assert_eq!(state("Closing").description.as_deref(), Some("emits }"));
+
+    let printed = format_fsm(fsm);
+    assert!(printed.contains("    state Parsing : handles { and } tokens\n"), "{}", printed);
+    assert_eq!(format_fsm(&parse_fsm(&printed).unwrap()[0]), printed);
+}
+
+#[test]
+fn test_parse_fsm_description_line() {
+    let source = r#"
+        fsm Pump {
+            description: Controls the pump
+            [*] --> Idle
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+
+    assert_eq!(fsms[0].description.as_deref(), Some("Controls the pump"));
+    assert_eq!(fsms[0].states.len(), 1);
+}
+
+#[test]
+fn test_parse_events_block() {
+    let source = r#"
+        fsm Door {
+            events {
+                /// Someone pushed the button
+                open, close   /// the door reached its end stop
+                /// Declared twice: the first one counts
+                open
+                fault
+            }
+            [*] --> Closed
+            Closed --> Opened : open
+        }
+    "#;
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+
+    let names: Vec<&str> = fsm.events.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["open", "close", "fault"]);
+    assert_eq!(fsm.events[0].description.as_deref(), Some("Someone pushed the button"));
+    assert_eq!(fsm.events[1].description.as_deref(), Some("the door reached its end stop"));
+    assert!(fsm.events[2].description.is_none());
+    assert_eq!(fsm.events_line, Some(3));
+    assert_eq!(fsm.events[0].line, Some(5));
+
+    // The printer writes the block back, one event per line
+    let printed = crate::parser::format::format_fsm(fsm);
+    assert!(printed.contains("    events {\n        /// Someone pushed the button\n        open\n        /// the door reached its end stop\n        close\n        fault\n    }\n"), "{}", printed);
+    let reparsed = &parse_fsm(&printed).unwrap()[0];
+    let described = |fsm: &crate::fsm::FsmDefinition| -> Vec<(String, Option<String>)> {
+        fsm.events.iter().map(|e| (e.name.clone(), e.description.clone())).collect()
+    };
+    assert_eq!(described(reparsed), described(fsm));
+}
+
+#[test]
+fn test_parse_event_ids() {
+    let source = r#"
+        fsm Logger {
+            events {
+                start = 1, stop=2   /// stops logging
+                set_rate(hz: u16) = 5
+                reset
+            }
+            [*] --> Idle
+        }
+    "#;
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    let ids = |fsm: &crate::fsm::FsmDefinition| -> Vec<(String, Option<u32>)> {
+        fsm.events.iter().map(|e| (e.name.clone(), e.id)).collect()
+    };
+    let expected = [("start", Some(1)), ("stop", Some(2)), ("set_rate", Some(5)), ("reset", None)];
+    assert_eq!(ids(fsm), expected.map(|(name, id)| (name.to_string(), id)));
+    assert_eq!(fsm.events[1].description.as_deref(), Some("stops logging"));
+    assert_eq!(fsm.events[2].params.len(), 1);
+
+    let printed = crate::parser::format::format_fsm(fsm);
+    assert!(printed.contains("        stop = 2\n        set_rate(hz: u16) = 5\n        reset\n"), "{}", printed);
+    assert_eq!(ids(&parse_fsm(&printed).unwrap()[0]), ids(fsm));
+
+    assert!(parse_fsm("fsm A { events { start = } }").is_err());
+    assert!(parse_fsm("fsm A { events { start = -1 } }").is_err());
+    assert!(parse_fsm("fsm A { events { start = 4294967296 } }").is_err());
+}
+
+#[test]
+fn test_parse_quoted_description_and_metadata() {
+    let source = r#"
+        fsm Pump {
+            description "Controls the \"coolant\" pump\nC:\\pumps"
+            meta version = "1.2"
+            meta target.board = "stm32-f4 \t rev B"
+            meta version = "1.3"
+            meta empty = ""
+            [*] --> Idle
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let fsm = &fsms[0];
+
+    assert_eq!(fsm.description.as_deref(), Some("Controls the \"coolant\" pump\nC:\\pumps"));
+    let metadata: Vec<(&str, &str)> = fsm.metadata.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    assert_eq!(metadata, [("empty", ""), ("target.board", "stm32-f4 \t rev B"), ("version", "1.3")]);
+    assert_eq!(fsm.states.len(), 1);
+
+    // Keys the tooling knows nothing about survive a save/load of the definition
+    let json = serde_json::to_string(fsm).unwrap();
+    let loaded: crate::fsm::FsmDefinition = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.metadata, fsm.metadata);
+
+    assert!(parse_fsm("fsm Pump { meta version = \"1.2 }").is_err());
+    assert!(parse_fsm("fsm Pump { description \"bad \\q escape\" }").is_err());
+}
+
+#[test]
+fn test_parse_file_name_override() {
+    let source = "fsm IOManager {\n    @file_name(\"io_mgr\")\n\n    [*] --> Idle\n}\n";
+    let fsm = &parse_fsm(source).unwrap()[0];
+    assert_eq!(fsm.file_name.as_deref(), Some("io_mgr"));
+    assert_eq!(fsm.file_stem(), "io_mgr");
+    assert!(fsm.validate().is_ok());
+    assert_eq!(crate::parser::format::format_fsm(fsm), source);
+
+    let fsm = &parse_fsm("fsm IOManager {\n [*] --> Idle\n}").unwrap()[0];
+    assert_eq!(fsm.file_stem(), "io_manager");
+
+    let fsm = &parse_fsm("fsm IOManager {\n @file_name(\"io-mgr\")\n [*] --> Idle\n}").unwrap()[0];
+    assert_eq!(fsm.validate().unwrap_err(), ["File name 'io-mgr' is not a valid module name"]);
+
+    // Module names are ASCII only
+    let fsm = &parse_fsm("fsm Wächter {\n [*] --> Idle\n}").unwrap()[0];
+    assert_eq!(fsm.file_stem(), "waechter");
+    let fsm = &parse_fsm("fsm Wächter {\n @file_name(\"wächter\")\n [*] --> Idle\n}").unwrap()[0];
+    assert_eq!(fsm.validate().unwrap_err(), ["File name 'wächter' is not a valid module name"]);
+}
+
+#[test]
+fn test_parse_named_guards() {
+    let source = "fsm Keypad {\n    // Too many tries, unless someone is servicing it\n    guard lockout = attempts_exceeded && !maintenance_mode // see #12\n    guard alarm = lockout || (tampered && armed(zone))\n\n    [*] --> Idle\n\n    Idle --> Locked : key [lockout && door_closed]\n}\n";
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let lockout = &fsm.guards[0];
+    assert_eq!((lockout.name.as_str(), lockout.line), ("lockout", Some(3)));
+    assert_eq!(lockout.expression, "attempts_exceeded && !maintenance_mode");
+    assert_eq!(lockout.leading_comments, ["Too many tries, unless someone is servicing it"]);
+    assert_eq!(lockout.trailing_comment.as_deref(), Some("see #12"));
+    assert_eq!(fsm.guards[1].expression, "lockout || (tampered && armed(zone))");
+    assert_eq!(crate::parser::format::format_fsm(fsm), source);
+
+    // A state may still be called `guard`
+    let fsm = &parse_fsm("fsm Gate {\n [*] --> guard\n guard --> open : lift\n}").unwrap()[0];
+    assert!(fsm.guards.is_empty());
+    assert_eq!(fsm.transitions[0].source, "guard");
+}
+
+#[test]
+fn test_parse_mermaid_notes_alias_and_direction() {
+    let source = r#"
+        fsm Player {
+            direction LR
+            [*] --> Still
+            state "Waiting for input" as Still
+            Still -> Moving : push
+            note right of Still : Idle most of the time
+            note left of Moving
+                Speed is capped
+                at 10 m/s
+            end note
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let fsm = &fsms[0];
+
+    assert_eq!(fsm.layout_hint, Some(LayoutHint::LR));
+    let still = fsm.states.iter().find(|s| s.name == "Still").unwrap();
+    assert_eq!(still.description.as_deref(), Some("Waiting for input"));
+    assert_eq!(
+        fsm.notes,
+        vec![
+            Note {
+                target_state: "Still".to_string(),
+                position: NotePosition::Right,
+                text: "Idle most of the time".to_string(),
+                line: Some(7),
+            },
+            Note {
+                target_state: "Moving".to_string(),
+                position: NotePosition::Left,
+                text: "Speed is capped\nat 10 m/s".to_string(),
+                line: Some(8),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_parse_mermaid_unsupported_construct() {
+    let source = "fsm Player {\n    [*] --> Still\n    classDef hot fill:#f00\n}\n";
+    let err = parse_fsm(source).unwrap_err();
+    assert!(matches!(&err, ParseError::Unsupported { line: 3, construct } if construct == "classDef hot fill:#f00"));
+    assert_eq!(err.to_string(), "Unsupported construct at line 3: classDef hot fill:#f00");
+
+    let err = parse_fsm("stateDiagram-v2\nfsm A {\n    [*] --> X\n}\n").unwrap_err();
+    assert!(matches!(err, ParseError::Unsupported { line: 1, .. }));
+
+    let err = parse_fsm("fsm A {\n    state Split <<fork>>\n}\n").unwrap_err();
+    assert!(matches!(err, ParseError::Unsupported { line: 2, .. }));
+
+    // Keywords remain usable as state names in transitions
+    let fsms = parse_fsm("fsm A {\n    [*] --> style\n    style --> class : go\n}\n").expect("Should parse successfully");
+    assert_eq!(fsms[0].transitions.len(), 1);
+}
+
+#[test]
+fn test_local_transition_keyword() {
+    use crate::fsm::TransitionKind;
+    use crate::parser::edit::{rewrite_transition, LabelEdit};
+
+    let source = "fsm M {\n    [*] --> A\n    A --> A : local reset / clear\n    A --> A : local_timer\n    A --> A : local\n}\n";
+    let fsms = parse_fsm(source).unwrap();
+    let kinds: Vec<TransitionKind> = fsms[0].transitions.iter().map(|t| t.kind).collect();
+    assert_eq!(kinds, [TransitionKind::Local, TransitionKind::External, TransitionKind::Local]);
+    assert_eq!(fsms[0].transitions[0].event.as_ref().unwrap().name, "reset");
+    assert_eq!(fsms[0].transitions[1].event.as_ref().unwrap().name, "local_timer");
+    assert!(fsms[0].transitions[2].event.is_none());
+
+    // Editing the label keeps the transition local
+    let edit = LabelEdit {
+        event: "restart".to_string(),
+        ..LabelEdit::default()
+    };
+    let rewritten = rewrite_transition(source, &fsms[0], 0, &edit).unwrap();
+    assert!(rewritten.contains("\n    A --> A : local restart\n"));
+}
+
+#[test]
+fn test_parse_reversed_arrow() {
+    use crate::parser::format::format_fsm;
+
+    let source = "fsm Door {
+    [*] --> Closed
+    Open <-- Closed : open [unlocked] / swing
+    Closed <- Open : close
+    <<check>> <-- Open : lock
+}
+";
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let ends: Vec<(&str, &str)> = fsm.transitions.iter().map(|t| (t.source.as_str(), t.target.as_str())).collect();
+    assert_eq!(ends, [("Closed", "Open"), ("Open", "Closed"), ("Open", "<<check>>")]);
+    assert!(fsm.transitions.iter().all(|t| t.reversed && !t.is_dashed()));
+    assert_eq!(fsm.transitions[0].label(), "open [unlocked] / swing");
+    assert_eq!(fsm.transitions[0].line, Some(3));
+
+    // Printed target first again
+    let printed = format_fsm(fsm);
+    assert!(printed.contains("    Open <-- Closed : open [unlocked] / swing\n"), "{printed}");
+    assert!(printed.contains("    Closed <-- Open : close\n"), "{printed}");
+}
+
+#[test]
+fn test_parse_dashed_arrow() {
+    use crate::parser::format::format_fsm;
+    use crate::parser::edit::{rewrite_transition, LabelEdit};
+
+    let source = "fsm Link {
+    [*] --> Up
+    Up -.-> Down : lost
+    Up <-.- Down : found / resync
+}
+";
+    let fsm = &parse_fsm(source).unwrap()[0];
+    assert!(fsm.transitions.iter().all(|t| t.is_dashed()));
+    assert!(!fsm.transitions[0].reversed);
+    assert_eq!((fsm.transitions[1].source.as_str(), fsm.transitions[1].target.as_str()), ("Down", "Up"));
+    // The arrow is not part of the label
+    assert_eq!(fsm.transitions[0].label(), "lost");
+
+    let printed = format_fsm(fsm);
+    assert!(printed.contains("    Up -.-> Down : lost\n"), "{printed}");
+    assert!(printed.contains("    Up <-.- Down : found / resync\n"), "{printed}");
+
+    let edit = LabelEdit {
+        event: "dropped".to_string(),
+        ..LabelEdit::default()
+    };
+    let rewritten = rewrite_transition(source, fsm, 0, &edit).unwrap();
+    assert!(rewritten.contains("\n    Up -.-> Down : dropped\n"));
+}
+
+#[test]
+fn test_parse_mixed_arrows_round_trip() {
+    use crate::fsm::TransitionStyle;
+    use crate::parser::format::format_fsm;
+
+    let source = "fsm Pump {
+    [*] --> Idle
+    Idle --> Running : start
+    Idle <-- Running : stop
+    Running -.-> Fault : overheat
+    Idle <-.- Fault : reset
+    * -.-> Fault : panic
+}
+";
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let arrows: Vec<(TransitionStyle, bool)> = fsm.transitions.iter().map(|t| (t.style, t.reversed)).collect();
+    assert_eq!(
+        arrows,
+        [
+            (TransitionStyle::Solid, false),
+            (TransitionStyle::Solid, true),
+            (TransitionStyle::Dashed, false),
+            (TransitionStyle::Dashed, true),
+            (TransitionStyle::Dashed, false),
+        ]
+    );
+    assert!(fsm.transitions[4].is_from_any_state());
+    let reparsed = &parse_fsm(&format_fsm(fsm)).unwrap()[0];
+    assert_eq!(without_whitespace(&format_fsm(reparsed)), without_whitespace(&format_fsm(fsm)));
+    let arrows_again: Vec<(TransitionStyle, bool)> = reparsed.transitions.iter().map(|t| (t.style, t.reversed)).collect();
+    assert_eq!(arrows_again, arrows);
+}
+
+#[test]
+fn test_every_arrow_from_the_start_node_is_an_initial_transition() {
+    use crate::parser::edit::set_initial_state;
+
+    for arrow in ["[*] --> Idle", "[*] -> Idle", "[*] -.-> Idle", "Idle <-- [*]", "Idle <- [*]", "Idle <-.- [*]"] {
+        let source = format!("fsm Pump {{\n    {arrow}\n    Idle --> Running : start\n}}\n");
+        let fsm = &parse_fsm(&source).unwrap()[0];
+        assert_eq!((fsm.initial_state(), fsm.initial_line()), (Some("Idle"), Some(2)), "{arrow}");
+        assert_eq!(fsm.transitions.len(), 1, "{arrow}");
+        assert!(fsm.validate().is_ok(), "{arrow}: {:?}", fsm.validate());
+        // Setting the initial state retargets the line rather than adding another
+        let retargeted = set_initial_state(&source, fsm, "Running").unwrap();
+        assert_eq!(parse_fsm(&retargeted).unwrap()[0].initial_transitions.len(), 1, "{retargeted}");
+        assert_eq!(parse_fsm(&retargeted).unwrap()[0].initial_state(), Some("Running"), "{retargeted}");
+    }
+
+    // Guards and actions carry over; an event cannot, as nothing has happened yet
+    let fsm = &parse_fsm("fsm Pump {\n    Idle <-- [*]\n    Fault <-.- [*] : [sensor_failed] / alarm\n}\n").unwrap()[0];
+    let guarded = &fsm.initial_transitions[1];
+    assert_eq!((guarded.target.as_str(), guarded.guard.as_ref().map(|g| g.expression.as_str())), ("Fault", Some("sensor_failed")));
+    assert_eq!(guarded.actions[0].name, "alarm");
+    for source in ["fsm Pump {\n    Idle <-- [*] : boot\n}\n", "fsm Pump {\n    [*] -.-> Idle : boot\n}\n", "fsm Pump {\n    [*] -.-> [*]\n}\n"] {
+        assert!(matches!(parse_fsm(source), Err(ParseError::SyntaxError { line: 2, .. })), "{source}");
+    }
+}
+
+// ============================================================================
+// SOURCE EDITS
+// ============================================================================
+
+#[test]
+fn test_rewrite_transition_label() {
+    use crate::parser::edit::{rewrite_transition, LabelEdit};
+
+    let source = "fsm Door {\n    Closed --> Open : open [unlocked] / swing(2) // by hand\n    Open -> Closed : close\n}\n";
+    let fsms = parse_fsm(source).unwrap();
+    let edit = LabelEdit::from_transition(&fsms[0].transitions[0]);
+    assert_eq!(edit.actions, "swing(2)");
+    assert_eq!(edit.label(), "open [unlocked] / swing(2)");
+
+    let edit = LabelEdit {
+        event: "push".to_string(),
+        guard: String::new(),
+        actions: "swing(2); beep".to_string(),
+    };
+    let rewritten = rewrite_transition(source, &fsms[0], 0, &edit).unwrap();
+    assert_eq!(
+        rewritten,
+        "fsm Door {\n    Closed --> Open : push / swing(2); beep // by hand\n    Open -> Closed : close\n}\n"
+    );
+
+    // A stale line number falls back to matching source, target and label
+    let shifted = format!("\n\n{source}");
+    let cleared = rewrite_transition(&shifted, &fsms[0], 1, &LabelEdit::default()).unwrap();
+    assert!(cleared.contains("\n    Open -> Closed\n"));
+
+    let edited = "fsm Door {\n    Closed --> Open : open\n}\n";
+    assert!(rewrite_transition(edited, &fsms[0], 0, &edit).is_none());
+}
+
+#[test]
+fn test_rename_event_everywhere() {
+    use crate::parser::edit::rename_event;
+
+    let source = "fsm A {\n    timer t = 100 -> tick periodic\n    state Run {\n        tick / count\n    }\n    Run --> Run : tick [full] / flush\n    Run --> Idle : ticket\n}\nfsm B {\n    X --> Y : tick\n}\n";
+    let fsms = parse_fsm(source).unwrap();
+    let renamed = rename_event(source, &fsms[0], "tick", "pulse");
+    assert_eq!(
+        renamed,
+        "fsm A {\n    timer t = 100 -> pulse periodic\n    state Run {\n        pulse / count\n    }\n    Run --> Run : pulse [full] / flush\n    Run --> Idle : ticket\n}\nfsm B {\n    X --> Y : tick\n}\n"
+    );
+}
+
+#[test]
+fn test_rename_state_structurally() {
+    use crate::parser::edit::{rename, RenameError, RenameKind};
+
+    let source = r#"fsm A {
+    [*] --> Idle
+    state "Idle and waiting" as Idle
+    state Busy {
+        entry / Idle_led
+    }
+    Idle --> Busy : go
+    Busy --> <<Check>> : done
+    choice Check {
+        [ok] -> Idle
+        [else] -> Busy
+    }
+    note left of Idle : IdleTime is not a state
+}
+fsm B {
+    [*] --> Idle
+}
+"#;
+    let fsms = parse_fsm(source).unwrap();
+    let renamed = rename(source, &fsms[0], RenameKind::State, "Idle", "Ready").unwrap();
+    assert_eq!(
+        renamed,
+        source
+            .replacen("--> Idle\n", "--> Ready\n", 1)
+            .replace("as Idle\n", "as Ready\n")
+            .replace("    Idle --> Busy", "    Ready --> Busy")
+            .replace("[ok] -> Idle", "[ok] -> Ready")
+            .replace("of Idle :", "of Ready :")
+    );
+
+    // Same problems before and after
+    let before = &fsms[0];
+    let after = &parse_fsm(&renamed).unwrap()[0];
+    assert_eq!(before.validate().is_ok(), after.validate().is_ok());
+    assert_eq!(before.warnings().len(), after.warnings().len());
+    assert_eq!(after.initial_state(), Some("Ready"));
+
+    assert_eq!(
+        rename(source, &fsms[0], RenameKind::State, "Idle", "Busy"),
+        Err(RenameError::AlreadyExists { kind: RenameKind::State, name: "Busy".to_string() })
+    );
+    assert_eq!(
+        rename(source, &fsms[0], RenameKind::State, "Idle", "two words"),
+        Err(RenameError::InvalidName("two words".to_string()))
+    );
+    assert_eq!(
+        rename(source, &fsms[0], RenameKind::Event, "stop", "halt").unwrap_err().to_string(),
+        "event 'stop' not found"
+    );
+}
+
+#[test]
+fn test_add_state_transition_and_initial_state() {
+    use crate::parser::edit::{add_state, add_transition, set_initial_state, LabelEdit};
+
+    let source = "fsm A {\n    timer t = 10 -> tick\n    state Idle {\n        entry / led\n    }\n    Idle --> Busy : go // first\n\n    note left of Idle : waits\n}\nfsm B {\n}\n";
+    let fsms = parse_fsm(source).unwrap();
+
+    // Each line goes after the last item of its section
+    let added = add_state(source, &fsms[0], "Done").unwrap();
+    assert_eq!(added, source.replace("    }\n    Idle", "    }\n    state Done\n    Idle"));
+    let edit = LabelEdit { event: "finish".to_string(), actions: "log; beep(2)".to_string(), ..LabelEdit::default() };
+    let added = add_transition(&added, &fsms[0], "Busy", "Done", &edit).unwrap();
+    assert!(added.contains("    Idle --> Busy : go // first\n    Busy --> Done : finish / log; beep(2)\n\n    note"));
+    let parsed = &parse_fsm(&added).unwrap()[0];
+    assert_eq!(parsed.transitions.len(), 2);
+    assert!(parsed.states.iter().any(|s| s.name == "Done" && s.is_declared()));
+
+    // Without an initial state the `[*]` line goes after the timers; with one, it is retargeted
+    let initial = set_initial_state(&added, &fsms[0], "Idle").unwrap();
+    assert!(initial.contains("    timer t = 10 -> tick\n    [*] --> Idle\n    state Idle {"));
+    let retargeted = set_initial_state(&initial.replace("[*] --> Idle", "[*] --> Idle : / boot"), &fsms[0], "Busy").unwrap();
+    assert!(retargeted.contains("    [*] --> Busy : / boot\n"));
+
+    // An empty block gets its first line below the header
+    let empty = add_transition(source, &fsms[1], "X", "Y", &LabelEdit::default()).unwrap();
+    assert!(empty.ends_with("fsm B {\n    X --> Y\n}\n"));
+    let one_line = "fsm C { }\n";
+    assert_eq!(add_state(one_line, &parse_fsm(one_line).unwrap()[0], "X"), None);
+}
+
+#[test]
+fn test_add_entry_action_and_state_definition() {
+    use crate::parser::edit::{add_entry_action, state_definition};
+
+    let source = "fsm A {\n    [*] --> Idle\n    state Idle {\n        entry / led\n        exit / beep\n    }\n    state Busy : working\n    state Done { }\n    Idle --> Busy : go\n    Busy --> Lost : drop\n}\n";
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let entry_actions = |edited: &str, state: &str| {
+        let fsm = &parse_fsm(edited).unwrap()[0];
+        let state = fsm.states.iter().find(|s| s.name == state).unwrap();
+        state.entry_actions.iter().map(|a| a.to_string()).collect::<Vec<_>>()
+    };
+
+    // After the entry actions of a body
+    let edited = add_entry_action(source, fsm, "Idle", "set_timeout(5000)").unwrap();
+    assert!(edited.contains("        entry / led\n        entry / set_timeout(5000)\n        exit / beep\n"));
+    assert_eq!(entry_actions(&edited, "Idle"), ["led", "set_timeout(5000)"]);
+    // A declaration without a body gets one; a one-line body stays on its line
+    let edited = add_entry_action(source, fsm, "Busy", "start; log(1)").unwrap();
+    assert!(edited.contains("    state Busy : working {\n        entry / start; log(1)\n    }\n"));
+    assert_eq!(entry_actions(&edited, "Busy"), ["start", "log(1)"]);
+    let edited = add_entry_action(source, fsm, "Done", "stop").unwrap();
+    assert!(edited.contains("    state Done { entry / stop }\n"));
+    // An undeclared state is declared with its entry action
+    let edited = add_entry_action(source, fsm, "Lost", "alarm").unwrap();
+    assert!(edited.contains("    state Done { }\n    state Lost {\n        entry / alarm\n    }\n    Idle --> Busy"));
+    assert_eq!(entry_actions(&edited, "Lost"), ["alarm"]);
+
+    // The declaration, else the first mention
+    assert_eq!(state_definition(source, fsm, "Busy").map(|r| &source[r.start - 6..r.end]), Some("state Busy"));
+    assert_eq!(state_definition(source, fsm, "Lost").map(|r| &source[r.start - 4..r.end]), Some("--> Lost"));
+    assert_eq!(state_definition(source, fsm, "Nowhere"), None);
+}
+
+#[test]
+fn test_quick_fix_misspelled_state() {
+    use crate::parser::diagnostics::{diagnostics, Severity};
+
+    let source = "fsm Pump {\n    [*] --> Idle\n    state Idle\n    state Active\n    state Activity\n    state Action\n    Idle --> Activ : start\n    Activ --> Idle : stop\n    Idle --> Drained : drain\n}\n";
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let found = diagnostics(source, fsm);
+    let typos: Vec<_> = found.iter().filter(|d| !d.fixes.is_empty()).collect();
+    // One per mention, closest first; `Drained` is too far from every declared state
+    assert_eq!(typos.len(), 2);
+    assert_eq!(typos[0].severity, Severity::Warning);
+    assert_eq!(typos[0].message, "State 'Activ' (line 7) is never declared: did you mean 'Active' or 'Action'?");
+    assert_eq!(&source[typos[0].span.clone().unwrap()], "Activ");
+    assert_eq!(typos[0].fixes[0].title, "Change to 'Active'");
+
+    let fixed = typos[0].fixes[0].apply(source).unwrap();
+    let fsm = &parse_fsm(&fixed).unwrap()[0];
+    let fixed = diagnostics(&fixed, fsm).iter().find(|d| !d.fixes.is_empty()).unwrap().fixes[0].apply(&fixed).unwrap();
+    assert_eq!(fixed, source.replace("Activ ", "Active "));
+    let fsm = &parse_fsm(&fixed).unwrap()[0];
+    assert_eq!(fsm.validate(), Ok(()));
+    assert!(diagnostics(&fixed, fsm).iter().all(|d| d.fixes.is_empty()));
+}
+
+#[test]
+fn test_quick_fix_initial_state_and_duplicates() {
+    use crate::fsm::NO_INITIAL_STATE;
+    use crate::parser::diagnostics::{diagnostics, Severity};
+
+    let source = "fsm Fan {\n    Off --> On : toggle\n    state On\n    On --> Off : toggle\n    Off --> On : toggle\n}\n";
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let found = diagnostics(source, fsm);
+    assert_eq!(found[0].severity, Severity::Error);
+    assert_eq!(found[0].message, NO_INITIAL_STATE);
+    // The first declared state, not the first one mentioned
+    assert_eq!(found[0].fixes[0].title, "Set initial state to 'On'");
+    let duplicate = found.iter().find(|d| d.message.starts_with("Duplicate transition")).unwrap();
+    assert_eq!(duplicate.severity, Severity::Info);
+    assert_eq!(duplicate.fixes[0].title, "Remove duplicate at line 5");
+
+    let fixed = found[0].fixes[0].apply(source).unwrap();
+    assert!(fixed.starts_with("fsm Fan {\n    [*] --> On\n"));
+    let fsm = &parse_fsm(&fixed).unwrap()[0];
+    let duplicate = diagnostics(&fixed, fsm).into_iter().find(|d| d.message.starts_with("Duplicate transition")).unwrap();
+    assert_eq!(duplicate.fixes[0].title, "Remove duplicate at line 6");
+    let fixed = duplicate.fixes[0].apply(&fixed).unwrap();
+    assert_eq!(fixed, "fsm Fan {\n    [*] --> On\n    Off --> On : toggle\n    state On\n    On --> Off : toggle\n}\n");
+    let fsm = &parse_fsm(&fixed).unwrap()[0];
+    assert_eq!(fsm.validate(), Ok(()));
+    let left: Vec<String> = diagnostics(&fixed, fsm).into_iter().map(|d| d.message).collect();
+    assert_eq!(left, ["Implicitly declared states (no `state` line): Off"]);
+}
+
+#[test]
+fn test_state_color_and_tag_annotations() {
+    let source = r#"
+        fsm Link {
+            [*] --> Idle
+            state Fault #color(red) @tag(safety) // latched until reset
+            state Connected #color(#3060d0) @tag(comms, network) : link up {
+                entry / notify
+            }
+            state Connected @tag(comms, diagnostics) #color(green)
+            state Odd #color(reddish)
+            Idle --> Connected : connect
+        }
+    "#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let fsm = &fsms[0];
+    let state = |name: &str| fsm.states.iter().find(|s| s.name == name).unwrap();
+
+    let fault = state("Fault");
+    assert_eq!(fault.color.as_deref(), Some("red"));
+    assert_eq!(fault.rgb(), Some([0xD0, 0x30, 0x30]));
+    assert_eq!(fault.tags, vec!["safety"]);
+    assert_eq!(fault.trailing_comment.as_deref(), Some("latched until reset"));
+
+    // The first color wins, tags are collected from every declaration
+    let connected = state("Connected");
+    assert_eq!(connected.color.as_deref(), Some("#3060d0"));
+    assert_eq!(connected.rgb(), Some([0x30, 0x60, 0xD0]));
+    assert_eq!(connected.tags, vec!["comms", "network", "diagnostics"]);
+    assert_eq!(connected.description.as_deref(), Some("link up"));
+    assert_eq!(connected.entry_actions.len(), 1);
+
+    assert_eq!(state("Idle").color, None);
+    assert_eq!(state("Odd").rgb(), None);
+    assert_eq!(
+        fsm.warnings().last().map(String::as_str),
+        Some("State 'Odd' has an unknown color 'reddish' (use a named color like red or #RRGGBB)")
+    );
+}
+
+/// Text without any whitespace, so printed DSL can be compared with what it was parsed from
+fn without_whitespace(text: &str) -> String {
+    text.split_whitespace().collect()
+}
+
+#[test]
+fn test_format_round_trips_examples() {
+    use crate::parser::format::format_fsms;
+
+    let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    let mut checked = 0;
+    for dir in [root.clone(), root.join("templates"), root.join("builtin")] {
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().map_or(true, |e| e != "fsm") {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path).unwrap();
+            let printed = format_fsms(&parse_fsm(&source).expect("Should parse successfully"));
+            assert_eq!(without_whitespace(&printed), without_whitespace(&source), "{}:\n{}", path.display(), printed);
+            checked += 1;
+        }
+    }
+    assert!(checked >= 19, "only {} examples found", checked);
+}
+
+#[test]
+fn test_format_keeps_comments_in_place() {
+    use crate::parser::format::format_fsm;
+
+    let source = r#"// Link supervision
+// (two lines)
+
+fsm Link { // one per port
+    meta version = "1.0"
+    // Timers
+    timer retry = 500 -> Retry periodic // every half second
+
+    // Initial state
+    [*] --> Idle // start here
+
+    // Idle waits
+    state Idle : Waiting {
+        entry / reset(1)
+        exit / log
+    }
+    state Busy #color(red) @tag(comms) // red while busy
+
+    /// The main path
+    Idle --> Busy : go [ready] / start // fast
+    // free-floating
+
+    Busy --> <<Check>> : done
+    choice Check {
+        [ok] -> Idle
+        [else] -> Busy / fail
+    }
+    // before the note
+    note left of Idle : waits here
+    // at the end
+}
+"#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let fsm = &fsms[0];
+    assert_eq!(fsm.leading_comments, vec!["Link supervision", "(two lines)"]);
+    assert_eq!(fsm.trailing_comment.as_deref(), Some("one per port"));
+    assert_eq!(fsm.timers[0].leading_comments, vec!["Timers"]);
+    assert_eq!(fsm.timers[0].trailing_comment.as_deref(), Some("every half second"));
+    let idle = fsm.states.iter().find(|s| s.name == "Idle").unwrap();
+    assert_eq!(idle.leading_comments, vec!["Idle waits"]);
+    assert_eq!(fsm.transitions[0].leading_comments, Vec::<String>::new());
+    assert_eq!(fsm.transitions[0].trailing_comment.as_deref(), Some("fast"));
+    assert_eq!(fsm.transitions[1].leading_comments, vec!["free-floating"]);
+    let orphans: Vec<(usize, &str)> = fsm.orphan_comments.iter().map(|c| (c.line, c.text.as_str())).collect();
+    assert_eq!(
+        orphans,
+        vec![(9, "Initial state"), (10, "start here"), (28, "before the note"), (30, "at the end")]
+    );
+
+    let printed = format_fsm(fsm);
+    assert_eq!(without_whitespace(&printed), without_whitespace(source), "{}", printed);
+    assert!(printed.contains("\n    [*] --> Idle // start here\n"));
+    // Printing what was printed changes nothing
+    assert_eq!(format_fsm(&parse_fsm(&printed).unwrap()[0]), printed);
+}
+
+#[test]
+fn test_format_keeps_comments_in_state_bodies_and_after_the_last_fsm() {
+    use crate::parser::format::format_fsms;
+
+    let source = r#"fsm Door {
+    timer hold = 100 -> Expired
+
+    [*] --> Closed
+    state Closed {
+        entry / lock() // x
+        entry / beep; flash(2) /* both */
+        start_timer(hold) // arm
+        exit / unlock // y
+        go / x // z
+        poke [ready] / nudge() // w
+    }
+}
+
+fsm Other {
+    [*] --> A
+}
+// after everything
+/* and a block */
+"#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let closed = fsms[0].states.iter().find(|s| s.name == "Closed").unwrap();
+    let comments = |actions: &[Action]| actions.iter().map(|a| a.trailing_comment.clone()).collect::<Vec<_>>();
+    assert_eq!(
+        comments(&closed.entry_actions),
+        vec![Some("x".to_string()), None, Some("/* both */".to_string()), Some("arm".to_string())]
+    );
+    assert_eq!(comments(&closed.exit_actions), vec![Some("y".to_string())]);
+    let internal: Vec<_> = closed.internal_transitions.iter().map(|t| t.trailing_comment.as_deref()).collect();
+    assert_eq!(internal, vec![Some("z"), Some("w")]);
+    assert!(fsms[0].following_comments.is_empty());
+    assert_eq!(fsms[1].following_comments, vec!["after everything", "/* and a block */"]);
+
+    let printed = format_fsms(&fsms);
+    assert_eq!(without_whitespace(&printed), without_whitespace(source), "{}", printed);
+    assert!(printed.contains("\n        entry / beep; flash(2) /* both */\n"), "{}", printed);
+    assert!(printed.ends_with("}\n// after everything\n/* and a block */\n"), "{}", printed);
+    assert_eq!(format_fsms(&parse_fsm(&printed).unwrap()), printed);
+}
+
+#[test]
+fn test_format_keeps_block_comments() {
+    use crate::parser::format::format_fsm;
+
+    let source = r#"/* Link supervision */
+fsm Link { /* one per port */
+    /*
+     * Timers
+     */
+    timer retry = 500 -> Retry periodic /* every half second */
+
+    [*] --> Idle
+
+    state Idle {
+        entry / reset
+    }
+    /* the main path */
+    Idle --> Busy : go // fast
+    /* free-floating */ /* twice */
+}
+"#;
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    assert_eq!(fsm.leading_comments, vec!["/* Link supervision */"]);
+    assert_eq!(fsm.trailing_comment.as_deref(), Some("/* one per port */"));
+    assert_eq!(fsm.timers[0].leading_comments, vec!["/*\n     * Timers\n     */"]);
+    assert_eq!(fsm.timers[0].trailing_comment.as_deref(), Some("/* every half second */"));
+    let go = fsm.transitions.iter().find(|t| t.event.as_ref().is_some_and(|e| e.name == "go")).unwrap();
+    assert_eq!((go.leading_comments.as_slice(), go.trailing_comment.as_deref()), (&["/* the main path */".to_string()][..], Some("fast")));
+
+    let printed = format_fsm(fsm);
+    assert_eq!(without_whitespace(&printed), without_whitespace(source), "{}", printed);
+    assert!(printed.contains("\n    Idle --> Busy : go // fast\n    /* free-floating */ /* twice */\n}\n"), "{}", printed);
+    assert_eq!(format_fsm(&parse_fsm(&printed).unwrap()[0]), printed);
+}
+
+#[test]
+fn test_parse_completion_transitions() {
+    use crate::parser::format::format_fsm;
+
+    let source = r#"
+fsm Form {
+    [*] --> Editing
+    Editing --> Validating : submit
+    Validating --> Submitting : [all_valid] / log_submit
+    Rejected --> Editing
+}
+"#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let fsm = &fsms[0];
+    let [submit, guarded, bare] = &fsm.transitions[..] else {
+        panic!("expected three transitions, got {:?}", fsm.transitions);
+    };
+    assert!(!submit.is_completion());
+    assert!(guarded.event.is_none() && guarded.is_completion());
+    assert_eq!(guarded.guard.as_ref().unwrap().expression, "all_valid");
+    assert_eq!(guarded.actions[0].name, "log_submit");
+    assert!(bare.event.is_none() && bare.guard.is_none() && bare.is_completion());
+    assert!(fsm.validate().is_ok());
+
+    let printed = format_fsm(fsm);
+    assert!(printed.contains("\n    Validating --> Submitting : [all_valid] / log_submit\n"));
+    assert!(printed.contains("\n    Rejected --> Editing\n"));
+}
+
+#[test]
+fn test_parse_wildcard_transition() {
+    use crate::parser::format::format_fsm;
+
+    let source = r#"
+fsm Press {
+    [*] --> Idle
+    state Idle
+    state Stopped
+    * --> Stopped : emergency_stop / kill_power
+}
+"#;
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let fsm = &fsms[0];
+    let wildcard = &fsm.transitions[0];
+    assert!(wildcard.is_from_any_state());
+    assert_eq!(wildcard.event.as_ref().unwrap().name, "emergency_stop");
+    assert_eq!(wildcard.actions[0].name, "kill_power");
+    // `*` is not a state, so it needs no declaration
+    assert_eq!(fsm.states.len(), 2);
+    assert!(fsm.validate().is_ok());
+    assert!(format_fsm(fsm).contains("\n    * --> Stopped : emergency_stop / kill_power\n"));
+
+    assert!(parse_fsm("fsm Press {\n [*] --> Idle\n Idle --> * : stop\n}").is_err());
+}
+
+#[test]
+fn test_parse_guarded_initial_transitions() {
+    use crate::parser::format::format_fsm;
+
+    let source = "fsm Boot {\n    [*] --> Idle : / init\n    [*] --> Recovery : [recovery_pin_set] / log_recovery\n    [*] --> Updating : [update_pending]\n    Idle --> Updating : go\n}\n";
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    let initial: Vec<(&str, Option<&str>, usize)> = fsm
+        .initial_transitions
+        .iter()
+        .map(|t| (t.target.as_str(), t.guard.as_ref().map(|g| g.expression.as_str()), t.actions.len()))
+        .collect();
+    assert_eq!(initial, [("Idle", None, 1), ("Recovery", Some("recovery_pin_set"), 1), ("Updating", Some("update_pending"), 0)]);
+    // The unguarded one is the default, whatever its position
+    assert_eq!((fsm.initial_state(), fsm.initial_actions()[0].name.as_str()), (Some("Idle"), "init"));
+    let candidates: Vec<&str> = fsm.initial_candidates().iter().map(|t| t.target.as_str()).collect();
+    assert_eq!(candidates, ["Recovery", "Updating", "Idle"]);
+    assert!(fsm.validate().is_ok(), "{:?}", fsm.validate());
+    assert!(fsm.unused_guards().is_empty());
+
+    // Formatting keeps every line, guards and actions included
+    let formatted = format_fsm(fsm);
+    assert!(formatted.contains("    [*] --> Recovery : [recovery_pin_set] / log_recovery\n    [*] --> Updating : [update_pending]\n"), "{formatted}");
+    let again = &parse_fsm(&formatted).expect("Should parse successfully")[0];
+    assert_eq!(format_fsm(again), formatted);
+}
+
+#[test]
+fn test_guarded_initial_transitions_validated() {
+    let errors = |body: &str| parse_fsm(&format!("fsm Boot {{\n{body}\n    state A\n    state B\n}}\n")).unwrap()[0].validate().err().unwrap_or_default();
+
+    // A default to fall back on is required
+    let missing = errors("    [*] --> A : [pin]");
+    assert_eq!(missing.len(), 1);
+    assert!(missing[0].starts_with(crate::fsm::NO_DEFAULT_INITIAL), "{}", missing[0]);
+    // The second of two identical guards could never be taken
+    assert_eq!(
+        errors("    [*] --> A\n    [*] --> B : [pin]\n    [*] --> A : [ pin ]"),
+        ["Initial transitions to B (line 3) and A (line 4) have the same guard [pin]: the second is never taken"]
+    );
+    // There is no event whose parameters a guard could take, and no `else` apart from the default
+    assert_eq!(
+        errors("    [*] --> A\n    [*] --> B : [level(3)]"),
+        ["Initial transition to B (line 3) passes arguments to 'level'; its guard can only ask the context"]
+    );
+    assert_eq!(
+        errors("    [*] --> A\n    [*] --> B : [else]"),
+        ["Initial transition to B (line 3) cannot be guarded by [else]; leave the default unguarded"]
+    );
+    // Guarded transitions do not count as extra initial states
+    assert!(errors("    [*] --> A\n    [*] --> B : [pin]\n    [*] --> B : [other]").is_empty());
+}
+
+#[test]
+fn test_multiple_initial_states_rejected() {
+    use crate::parser::format::format_fsm;
+
+    let source = "fsm Boot {\n    [*] --> Idle : / load\n    Idle --> Ready : go / prepare\n    state Idle\n    state Ready\n\n    [*] --> Ready : / skip\n}\n";
+    let fsms = parse_fsm(source).expect("Should parse successfully");
+    let fsm = &fsms[0];
+    // The first declaration stays the initial state
+    assert_eq!(fsm.initial_state(), Some("Idle"));
+    assert_eq!(fsm.initial_actions()[0].name, "load");
+    assert_eq!(
+        fsm.validate().unwrap_err(),
+        vec!["Multiple initial states: Idle (line 2), Ready (line 7)".to_string()]
+    );
+    // Rewriting the source keeps the rejected line for the user to remove
+    assert!(format_fsm(fsm).contains("\n    [*] --> Ready : / skip\n"));
+}
+
+// ============================================================================
+// SOURCE BLOCKS
+// ============================================================================
+
+#[test]
+fn test_source_blocks_ignore_braces_in_comments() {
+    use crate::parser::blocks::SourceBlocks;
+
+    let source = "// Header { not a block\ninclude \"common.fsm\"\nfsm A { // opens {{ twice\n    X --> Y : go /* } */\n}\n\n\n// About B: ends with }\nfsm B {\n    /// Docs { with braces }}\n    Y --> Z\n}\n// trailing {\n";
+    let split = SourceBlocks::split(source).unwrap();
+    assert_eq!(
+        split.blocks,
+        vec![
+            "// Header { not a block\ninclude \"common.fsm\"\nfsm A { // opens {{ twice\n    X --> Y : go /* } */\n}",
+            "// About B: ends with }\nfsm B {\n    /// Docs { with braces }}\n    Y --> Z\n}\n// trailing {",
+        ]
+    );
+    assert_eq!(split.gaps, vec!["", "\n\n\n", "\n"]);
+    // Joining gives back the exact text
+    assert_eq!(split.join(), source);
+    assert!(source[split.offset(1)..].starts_with(&split.blocks[1]));
+}
+
+#[test]
+fn test_source_blocks_ignore_braces_in_descriptions_and_notes() {
+    use crate::parser::blocks::SourceBlocks;
+
+    let source = "fsm Lexer {\n    state Parsing : handles { and } tokens\n    note right of Parsing\n        matches { and }\n    end note\n}\nfsm Other {\n    [*] --> Idle\n}\n";
+    assert_eq!(parse_fsm(source).unwrap()[0].states[0].description.as_deref(), Some("handles { and } tokens"));
+    let mut split = SourceBlocks::split(source).unwrap();
+    assert_eq!(split.blocks.len(), 2);
+    assert!(split.blocks[0].ends_with("    end note\n}"));
+    assert_eq!(split.blocks[1], "fsm Other {\n    [*] --> Idle\n}");
+
+    // An edited tab goes back in place, the rest of the text unchanged
+    split.blocks[1] = "fsm Other {\n    [*] --> Busy\n}".to_string();
+    assert_eq!(split.join(), source.replace("Idle", "Busy"));
+}
+
+#[test]
+fn test_source_blocks_without_fsms_or_parse() {
+    use crate::parser::blocks::SourceBlocks;
+
+    // Nothing to cut: the text is kept as the only gap
+    let split = SourceBlocks::split("// just a comment\n").unwrap();
+    assert!(split.blocks.is_empty());
+    assert_eq!(split.join(), "// just a comment\n");
+
+    // Half-typed source does not split; as a whole it is one block
+    let source = "\nfsm A {\n    X --> \n";
+    assert!(SourceBlocks::split(source).is_err());
+    let whole = SourceBlocks::whole(source);
+    assert_eq!(whole.blocks, vec!["fsm A {\n    X -->"]);
+    assert_eq!(whole.join(), source);
+}
+
+#[test]
+fn test_parse_async_actions() {
+    let source = "fsm Link {\n    [*] --> Idle : / async power_up\n    state Idle {\n        entry / async_reset; async\n        poll / async read(3)\n    }\n    Idle --> Idle : go / async(1); async send_frame\n}\n";
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let calls = |actions: &[Action]| actions.iter().map(|a| (a.name.clone(), a.is_async)).collect::<Vec<_>>();
+    assert_eq!(calls(fsm.initial_actions()), [("power_up".to_string(), true)]);
+    // `async_reset` and a lone `async` are names, not annotations
+    assert_eq!(calls(&fsm.states[0].entry_actions), [("async_reset".to_string(), false), ("async".to_string(), false)]);
+    assert_eq!(calls(&fsm.states[0].internal_transitions[0].actions), [("read".to_string(), true)]);
+    assert_eq!(calls(&fsm.transitions[0].actions), [("async".to_string(), false), ("send_frame".to_string(), true)]);
+    assert_eq!(fsm.transitions[0].label(), "go / async(1); async send_frame");
+
+    // The formatter keeps the annotations
+    let formatted = crate::parser::format::format_fsm(fsm);
+    assert!(formatted.contains("[*] --> Idle : / async power_up"), "{}", formatted);
+    assert!(formatted.contains("poll / async read(3)"), "{}", formatted);
+    let again = &parse_fsm(&formatted).unwrap()[0];
+    assert_eq!(calls(&again.transitions[0].actions), calls(&fsm.transitions[0].actions));
+}
+
+#[test]
+fn test_source_blocks_reparse_only_edited_blocks() {
+    use crate::parser::blocks::{Reparse, SourceBlocks};
+
+    let source = "// Lamp\nfsm A {\n    [*] --> Off\n    Off --> On : toggle\n}\n\nfsm B {\n    [*] --> Idle\n    Idle --> Busy : go\n}\nfsm C {\n    [*] --> X\n}\n";
+    let mut split = SourceBlocks::split(source).unwrap();
+    let mut fsms = parse_fsm(source).unwrap();
+    let mut hashes = split.hashes();
+    let first: *const _ = &fsms[0];
+    let before = format!("{:?}", fsms[0]);
+
+    // Nothing edited: nothing parsed
+    assert_eq!(split.reparse(&mut fsms, &mut hashes, parse_fsm).unwrap(), Reparse::Blocks(Vec::new()));
+
+    split.blocks[1] = "fsm B {\n    [*] --> Idle\n    state Busy\n    Idle --> Busy : go\n    Busy --> Idle : done\n}".to_string();
+    assert_eq!(split.reparse(&mut fsms, &mut hashes, parse_fsm).unwrap(), Reparse::Blocks(vec![1]));
+    assert_eq!(hashes, split.hashes());
+    // Block 1's FSM was not touched, and block 2's is what a full parse gives, lines included
+    assert!(std::ptr::eq(first, &fsms[0]));
+    assert_eq!(format!("{:?}", fsms[0]), before);
+    let full = parse_fsm(&split.join()).unwrap();
+    assert_eq!(format!("{:?}", fsms[1]), format!("{:?}", full[1]));
+    assert_eq!(fsms[1].transitions[1].line, Some(11));
+
+    // A block that does not parse leaves every FSM as it was
+    let parsed = format!("{:?}", fsms);
+    split.blocks[2] = "fsm C {\n    [*] --> \n}".to_string();
+    match split.reparse(&mut fsms, &mut hashes, parse_fsm) {
+        Err(ParseError::PestError(e)) => assert_eq!(e.line_col, pest::error::LineColLocation::Pos((15, 1))),
+        other => panic!("expected a syntax error, got {:?}", other),
+    }
+    assert_eq!(format!("{:?}", fsms), parsed);
+
+    // A block with a second FSM no longer maps one to one
+    split.blocks[2] = "fsm C {\n    [*] --> X\n}\nfsm D {\n    [*] --> Y\n}".to_string();
+    assert_eq!(split.reparse(&mut fsms, &mut hashes, parse_fsm).unwrap(), Reparse::Full);
+    assert_eq!(format!("{:?}", fsms), parsed);
+    let mut hashes = Vec::new();
+    assert_eq!(split.reparse(&mut fsms, &mut hashes, parse_fsm).unwrap(), Reparse::Full);
+}
+
+/// `count` FSMs of `states` states each, with entry/exit actions, guards and notes
+fn multi_fsm_source(count: usize, states: usize) -> String {
+    let mut source = String::new();
+    for m in 0..count {
+        source.push_str(&format!("// Machine {m}\nfsm Machine{m} {{\n    [*] --> S00\n"));
+        for i in 0..states {
+            source.push_str(&format!("    state S{i:02} : Stage {i} {{\n        entry / open_{i}\n        exit / close_{i}\n    }}\n"));
+        }
+        for i in 0..states {
+            let (next, skip) = ((i + 1) % states, (i + 7) % states);
+            source.push_str(&format!("    S{i:02} --> S{next:02} : advance [ready_{i}] / log_step({i})\n"));
+            source.push_str(&format!("    S{i:02} --> S{skip:02} : jump_{i} / skip_ahead\n"));
+            if i % 10 == 0 {
+                source.push_str(&format!("    note right of S{i:02} : checkpoint {i}\n"));
+            }
+        }
+        source.push_str("}\n\n");
+    }
+    source
+}
+
+/// Editing one FSM of an 8-FSM / 400-state source, parsing everything again against
+/// parsing the edited block only:
+/// `cargo test --release --lib -- --ignored incremental_reparse --nocapture`
+#[test]
+#[ignore = "timing benchmark"]
+fn bench_incremental_reparse() {
+    use std::time::{Duration, Instant};
+
+    use crate::parser::blocks::{Reparse, SourceBlocks};
+
+    const EDITS: usize = 40;
+
+    let source = multi_fsm_source(8, 50);
+    let mut split = SourceBlocks::split(&source).unwrap();
+    let mut fsms = parse_fsm(&source).unwrap();
+    assert_eq!(fsms.iter().map(|fsm| fsm.states.len()).sum::<usize>(), 400);
+    let mut hashes = split.hashes();
+    let original = split.blocks[1].clone();
+    let edited = original.replacen("S00 --> S01 : advance", "S00 --> S02 : advance", 1);
+
+    // Each edit swaps the second FSM between two versions, so it always changed
+    let edit = |i: usize, split: &mut SourceBlocks| {
+        split.blocks[1] = if i % 2 == 0 { edited.clone() } else { original.clone() };
+    };
+    // The median, so a run the scheduler interrupted does not count
+    let median = |mut times: Vec<Duration>| {
+        times.sort();
+        times[times.len() / 2]
+    };
+    let full = median(
+        (0..EDITS)
+            .map(|i| {
+                edit(i, &mut split);
+                let start = Instant::now();
+                let joined = split.join();
+                fsms = parse_fsm(&joined).unwrap();
+                start.elapsed()
+            })
+            .collect(),
+    );
+    let incremental = median(
+        (0..EDITS)
+            .map(|i| {
+                edit(i, &mut split);
+                let start = Instant::now();
+                assert_eq!(split.reparse(&mut fsms, &mut hashes, parse_fsm).unwrap(), Reparse::Blocks(vec![1]));
+                start.elapsed()
+            })
+            .collect(),
+    );
+    println!("one FSM of 8 edited: {full:?} for a full parse, {incremental:?} re-parsing its block");
+    assert!(incremental * 4 <= full, "{incremental:?} is not under a quarter of {full:?}");
+}
+
+#[test]
+fn test_parse_limits() {
+    use crate::parser::{parse_fsm_with_limits, ParseLimits};
+
+    let source = "fsm F {\n    [*] --> A\n    A --> B : go\n    B --> C : go\n    // { in a comment\n    meta owner = \"{{{\"\n    state C\n}\n";
+    let limits = ParseLimits { max_nesting_depth: 1, ..ParseLimits::default() };
+    assert_eq!(parse_fsm_with_limits(source, &limits).unwrap()[0].states.len(), 3);
+
+    let limits = ParseLimits { max_states: 2, ..ParseLimits::default() };
+    match parse_fsm_with_limits(source, &limits) {
+        Err(ParseError::LimitExceeded { what, limit: 2, found: 3 }) => assert_eq!(what, "states in 'F'"),
+        other => panic!("expected a state limit error, got {other:?}"),
+    }
+    let limits = ParseLimits { max_transitions: 1, ..ParseLimits::default() };
+    assert!(matches!(parse_fsm_with_limits(source, &limits), Err(ParseError::LimitExceeded { found: 2, .. })));
+
+    let long = format!("fsm F {{\n    [*] --> {}\n}}\n", "S".repeat(300));
+    let err = parse_fsm(&long).unwrap_err();
+    assert_eq!(err.to_string(), "identifier length ('SSSSSSSSSSSSSSSS…'): 300 exceeds the limit of 256");
+
+    // A duration that does not fit is an error on its line, not a silent default
+    let timer = "fsm F {\n    [*] --> A\n    timer t = 99999999999 -> tick\n}\n";
+    match parse_fsm(timer) {
+        Err(ParseError::SyntaxError { line: 3, message }) => assert!(message.contains("out of range")),
+        other => panic!("expected a duration error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_import_scxml_flattens_a_compound_state() {
+    use crate::parser::format::format_fsm;
+    use crate::parser::import_scxml;
+
+    let import = import_scxml(include_str!("../../examples/scxml/microwave.scxml")).expect("Should import");
+    let fsm = &import.fsms[0];
+    assert_eq!(fsm.name, "Microwave");
+    let states: Vec<(&str, StateType, &[String])> =
+        fsm.states.iter().map(|s| (s.name.as_str(), s.state_type, s.tags.as_slice())).collect();
+    let on = ["on".to_string()];
+    assert_eq!(
+        states,
+        [("off", StateType::Simple, &[][..]), ("idle", StateType::Simple, &on[..]), ("cooking", StateType::Simple, &on[..]), ("done", StateType::Final, &[][..])]
+    );
+    assert_eq!(fsm.initial_state(), Some("off"));
+
+    // Leaving or entering "on" runs its exit or entry content on the transition
+    let transitions: Vec<String> = fsm.transitions.iter().map(|t| format!("{} --> {} : {}", t.source, t.target, t.label())).collect();
+    assert_eq!(
+        transitions,
+        [
+            "off --> idle : turn_on / log_powered",
+            "idle --> cooking : door_close [timer_set]",
+            "idle --> off : turn_off / raise_power_down",
+            "cooking --> idle : door_open",
+            "cooking --> done : time_up / raise_power_down",
+            "cooking --> off : turn_off / raise_power_down",
+        ]
+    );
+    let cooking = fsm.states.iter().find(|s| s.name == "cooking").unwrap();
+    assert_eq!(cooking.entry_actions[0].name, "send_start_magnetron");
+    assert_eq!(cooking.exit_actions[0].name, "script");
+    assert_eq!(cooking.internal_transitions[0].actions[0].name, "log_beep");
+    assert!(import.warnings.iter().all(|w| w.contains("renamed")), "{:?}", import.warnings);
+
+    // The printed DSL parses back to the same machine
+    let reparsed = &parse_fsm(&format_fsm(fsm)).expect("Should parse successfully")[0];
+    assert_eq!(reparsed.states.len(), 4);
+    assert_eq!(reparsed.transitions.len(), 6);
+    assert!(reparsed.validate().is_ok());
+}
+
+#[test]
+fn test_import_scxml_warns_about_unsupported_content() {
+    use crate::parser::import_scxml;
+
+    let import = import_scxml(include_str!("../../examples/scxml/parallel.scxml")).expect("Should import");
+    let fsm = &import.fsms[0];
+    assert_eq!(fsm.name, "Statechart");
+    assert_eq!(fsm.states.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), ["boot", "running"]);
+    assert_eq!(fsm.states[0].entry_actions.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(), ["assign_retries"]);
+    assert_eq!(fsm.transitions[1].event.as_ref().map(|e| e.name.as_str()), Some("error"));
+
+    let warnings = import.warnings.join("\n");
+    for expected in ["line 4: <datamodel>", "line 10: <if>", "line 14: <parallel> 'running'"] {
+        assert!(warnings.contains(expected), "missing {expected:?} in:\n{warnings}");
+    }
+
+    assert!(matches!(import_scxml("<scxml><state id=\"a\">"), Err(ParseError::SyntaxError { .. })));
+    assert!(matches!(import_scxml("<statechart/>"), Err(ParseError::SyntaxError { line: 1, .. })));
+}
+
+#[test]
+fn test_extract_states_into_composite() {
+    use crate::fsm::FsmDefinition;
+    use crate::parser::edit::{extract_states, ExtractError};
+    use crate::parser::format::format_fsms;
+
+    let source = include_str!("../../examples/builtin/door_lock.fsm");
+    let fsms = parse_fsm(source).unwrap();
+    let group = ["Unlocked".to_string(), "Open".to_string()];
+    let extracted = extract_states(source, &fsms[0], &group, "Access").unwrap();
+
+    // The boundary transitions now start or end at the group's state
+    let outer = &extracted[..extracted.find("fsm Access").unwrap()];
+    assert!(outer.contains("    }\n    \n    state Access\n    \n    state Alarming : Security alert! {"));
+    assert!(outer.ends_with(
+        "    Locked --> Access : valid_key\n    Locked --> Alarming : invalid_key [too_many_attempts]\n    Access --> Locked : lock_cmd\n    \
+         Access --> Alarming : timeout [held_too_long]\n    Alarming --> Locked : reset_alarm\n}\n\n"
+    ));
+    assert!(!outer.contains("Unlocked") && !outer.contains("Open "));
+    assert!(extracted.ends_with(
+        "fsm Access {
+    [*] --> Unlocked
+
+    state Unlocked : Door can be opened {
+        entry / notify_unlocked
+    }
+
+    state Open : Door is open {
+        entry / start_open_timer
+        exit / stop_open_timer
+    }
+
+    Unlocked --> Open : door_opened
+    Open --> Unlocked : door_closed
+}
+"
+    ));
+
+    // Both blocks parse, validate and survive a format round trip
+    let parsed = parse_fsm(&extracted).unwrap();
+    let names = |fsm: &FsmDefinition| fsm.states.iter().map(|s| s.name.clone()).collect::<Vec<_>>();
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(names(&parsed[0]), ["Locked", "Access", "Alarming"]);
+    assert_eq!(names(&parsed[1]), ["Unlocked", "Open"]);
+    assert_eq!(parsed[1].initial_state(), Some("Unlocked"));
+    assert_eq!(parsed[0].transitions.len(), 5);
+    assert_eq!(parsed[1].transitions.len(), 2);
+    assert_eq!(parsed[1].states[1].exit_actions[0].name, "stop_open_timer");
+    for fsm in &parsed {
+        assert_eq!(fsm.validate(), Ok(()));
+    }
+    let reparsed = parse_fsm(&format_fsms(&parsed)).unwrap();
+    for (before, after) in parsed.iter().zip(&reparsed) {
+        assert_eq!(names(before), names(after));
+        assert_eq!(before.initial_state(), after.initial_state());
+        let routes = |fsm: &FsmDefinition| fsm.transitions.iter().map(|t| (t.source.clone(), t.target.clone(), t.label())).collect::<Vec<_>>();
+        assert_eq!(routes(before), routes(after));
+    }
+
+    // The initial state moving makes the group the initial state; identical boundary transitions merge
+    let source = "fsm M {\n    [*] --> A\n    state A\n    state B\n    A --> B : go\n    A --> C : stop\n    B --> C : stop\n    C --> A : back\n}\n";
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let extracted = extract_states(source, fsm, &["A".to_string(), "B".to_string()], "Run").unwrap();
+    assert_eq!(
+        extracted,
+        "fsm M {\n    [*] --> Run\n    state Run\n    Run --> C : stop\n    C --> Run : back\n}\n\nfsm Run {\n    [*] --> A\n\n    state A\n    state B\n\n    A --> B : go\n}\n"
+    );
+
+    let group = ["A".to_string()];
+    assert_eq!(extract_states(source, fsm, &group, "M"), Err(ExtractError::AlreadyExists("M".to_string())));
+    assert_eq!(extract_states(source, fsm, &group, "C"), Err(ExtractError::AlreadyExists("C".to_string())));
+    assert_eq!(extract_states(source, fsm, &group, "two words"), Err(ExtractError::InvalidName("two words".to_string())));
+    assert_eq!(extract_states(source, fsm, &["Z".to_string()], "Run"), Err(ExtractError::NotFound("Z".to_string())));
+    assert_eq!(extract_states(source, fsm, &[], "Run"), Err(ExtractError::NothingSelected));
+}
+
+#[test]
+fn test_delete_tag_and_color_states() {
+    use crate::parser::edit::{color_states, delete_states, tag_states};
+
+    let source = r#"fsm A {
+    [*] --> Idle
+    state Idle #color(red)
+
+    /// Doing the work
+    state Busy {
+        entry / start
+    }
+
+    state Idle @tag(ui)
+    Idle --> Busy : go
+    Busy --> Done : finish
+    Busy --> <<Check>> : check
+    choice Check {
+        [ok] -> Done
+        [else] -> Idle
+    }
+    note left of Busy : works
+}
+"#;
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let group = ["Busy".to_string(), "Done".to_string()];
+
+    // Their declarations, transitions, branches and notes go; the rest stays
+    let deleted = delete_states(source, fsm, &group).unwrap();
+    assert_eq!(
+        deleted,
+        "fsm A {\n    [*] --> Idle\n    state Idle #color(red)\n\n    state Idle @tag(ui)\n    choice Check {\n        [else] -> Idle\n    }\n}\n"
+    );
+    let parsed = &parse_fsm(&deleted).unwrap()[0];
+    assert_eq!(parsed.states.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), ["Idle"]);
+
+    // The first declaration gets a tag unless one has it already; undeclared states get a line
+    let everything = ["Idle".to_string(), "Busy".to_string(), "Done".to_string()];
+    let tagged = tag_states(source, fsm, &everything, "core").unwrap();
+    assert!(tagged.contains("    state Idle @tag(core) #color(red)\n"));
+    assert!(tagged.contains("    state Busy @tag(core) {\n"));
+    assert!(tagged.contains("    state Idle @tag(ui)\n    state Done @tag(core)\n    Idle --> Busy"));
+    let retagged = tag_states(&tagged, fsm, &everything, "ui").unwrap();
+    assert!(retagged.contains("    state Idle @tag(core) #color(red)\n"));
+    let parsed = &parse_fsm(&retagged).unwrap()[0];
+    assert!(parsed.states.iter().all(|s| s.tags.contains(&"core".to_string()) && s.tags.contains(&"ui".to_string())));
+
+    // A color is replaced where there is one, added after the name otherwise
+    let colored = color_states(source, fsm, &everything, "#3060D0").unwrap();
+    assert!(colored.contains("    state Idle #color(#3060D0)\n"));
+    assert!(colored.contains("    state Busy #color(#3060D0) {\n"));
+    let parsed = &parse_fsm(&colored).unwrap()[0];
+    assert!(parsed.states.iter().all(|s| s.rgb() == Some([0x30, 0x60, 0xD0])));
+}
+
+#[test]
+fn test_parse_unicode_identifiers() {
+    let source = r#"
+        fsm Wächter {
+            events {
+                Störung(stufe: u8)
+            }
+            [*] --> Überwachung
+            state Überwachung {
+                entry / zählen
+            }
+            Überwachung --> Alarm : Störung(stufe) [stufe_hoch(stufe)] / melde(stufe)
+            Alarm --> Überwachung : local quittiert
+            Alarm --> Alarm : localé
+        }
+    "#;
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    assert_eq!(fsm.name, "Wächter");
+    assert_eq!(fsm.states[0].name, "Überwachung");
+    assert_eq!(fsm.states[0].entry_actions[0].name, "zählen");
+    assert_eq!(fsm.events[0].name, "Störung");
+    assert_eq!(fsm.transitions[0].guard.as_ref().unwrap().method(), "stufe_hoch");
+    assert!(fsm.transitions[1].is_local());
+    // `local` followed by a non-ASCII letter is part of the event name
+    assert!(!fsm.transitions[2].is_local());
+    assert_eq!(fsm.transitions[2].event.as_ref().unwrap().name, "localé");
+
+    // Still identifiers only: no symbols, no leading digit
+    assert!(parse_fsm("fsm A {\n [*] --> Temp°\n}").is_err());
+    assert!(parse_fsm("fsm A {\n [*] --> 2nd\n}").is_err());
+}
+
+#[test]
+fn test_parse_grouped_transitions() {
+    let source = r#"
+        fsm Door {
+            [*] --> Open
+            events {
+                set(level: u8)
+            }
+            state Open { on door_closed -> Unlocked; on timeout [held_too_long] -> Alarming / sound_alarm }
+            state Unlocked {
+                entry / unlock
+                /// Locks by itself
+                on lock_timer -.-> Locked / latch; beep // auto
+                on set(level) @debounce(20ms) [ready] -> <<check>>
+                on shutdown -> [*]
+            }
+            choice check {
+                [ok] -> Locked
+                [else] -> Open
+            }
+        }
+    "#;
+    let flat = r#"
+        fsm Door {
+            [*] --> Open
+            events {
+                set(level: u8)
+            }
+            Open --> Unlocked : door_closed
+            Open --> Alarming : timeout [held_too_long] / sound_alarm
+            state Unlocked {
+                entry / unlock
+            }
+            /// Locks by itself
+            Unlocked -.-> Locked : lock_timer / latch; beep // auto
+            Unlocked --> <<check>> : set(level) @debounce(20ms) [ready]
+            Unlocked --> [*] : shutdown
+            choice check {
+                [ok] -> Locked
+                [else] -> Open
+            }
+        }
+    "#;
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    let labels: Vec<String> = fsm.transitions.iter().map(|t| format!("{} {} {}", t.source, t.target, t.label())).collect();
+    assert_eq!(
+        labels,
+        [
+            "Open Unlocked door_closed",
+            "Open Alarming timeout [held_too_long] / sound_alarm",
+            "Unlocked Locked lock_timer / latch; beep",
+            "Unlocked <<check>> set(level) @debounce(20ms) [ready]",
+            "Unlocked [*] shutdown",
+        ]
+    );
+    assert!(fsm.transitions.iter().all(|t| t.kind == crate::fsm::TransitionKind::External));
+    assert_eq!(fsm.transitions[1].line, Some(7));
+    assert_eq!(fsm.transitions[2].description.as_deref(), Some("Locks by itself"));
+    assert_eq!(fsm.transitions[2].trailing_comment.as_deref(), Some("auto"));
+    assert_eq!(fsm.transitions[0].trailing_comment, None);
+    assert_eq!(fsm.transitions[2].style, crate::fsm::TransitionStyle::Dashed);
+    assert_eq!(fsm.states.iter().find(|s| s.name == "Unlocked").unwrap().entry_actions.len(), 1);
+    assert!(fsm.states.iter().any(|s| s.name == "Alarming" && !s.is_declared()));
+
+    // Nothing downstream can tell the two forms apart
+    let flat = &parse_fsm(flat).expect("Should parse successfully")[0];
+    assert_eq!(crate::codegen::generate_rust_code(fsm), crate::codegen::generate_rust_code(flat));
+    assert_eq!(fsm.validate(), flat.validate());
+    assert_eq!(fsm.warnings(), flat.warnings());
+
+    // `on` is still an event name for internal transitions
+    let fsm = &parse_fsm("fsm M {\n    state A {\n        on / blink\n        on [lit] / dim\n    }\n}\n").unwrap()[0];
+    assert!(fsm.transitions.is_empty());
+    assert_eq!(fsm.states[0].internal_transitions.len(), 2);
+}
+
+#[test]
+fn test_grouped_and_flat_transitions_mix() {
+    use crate::parser::diagnostics::diagnostics;
+
+    let source = "fsm Fan {\n    [*] --> Off\n    state Off {\n        on toggle -> On\n        on fault -> Broken / alarm\n    }\n    Off --> On : toggle\n    state On { on toggle -> Off }\n    On --> Off : toggle\n    Off --> Broken : fault / alarm\n}\n";
+    let fsm = &parse_fsm(source).unwrap()[0];
+    assert_eq!(fsm.transitions.len(), 3);
+    assert_eq!(fsm.transitions[0].declared_at, [4, 7]);
+    assert_eq!(fsm.transitions[1].declared_at, [5, 10]);
+    assert_eq!(fsm.transitions[2].declared_at, [8, 9]);
+    assert_eq!(fsm.infos()[0], "Duplicate transition 'Off --> On : toggle' merged (lines 4, 7)");
+
+    // The fix removes a whole line, so only where that leaves the state's block intact
+    let found = diagnostics(source, fsm);
+    let fixes = |message: &str| {
+        let duplicate = found.iter().find(|d| d.message.starts_with(message)).unwrap();
+        duplicate.fixes.iter().map(|f| f.title.clone()).collect::<Vec<_>>()
+    };
+    assert_eq!(fixes("Duplicate transition 'Off --> On"), ["Remove duplicate at line 7"]);
+    assert_eq!(fixes("Duplicate transition 'On --> Off"), ["Remove duplicate at line 9"]);
+
+    // A transition that differs only in its actions is ambiguous in either form
+    let fsm = &parse_fsm("fsm M {\n    [*] --> A\n    state A {\n        on go -> B / one\n    }\n    A --> B : go / two\n}\n").unwrap()[0];
+    assert_eq!(fsm.ambiguous_transitions().len(), 1);
+}
+
+#[test]
+fn test_format_transition_layout() {
+    use crate::parser::format::{format_fsm, format_fsm_with_options, FormatOptions, TransitionLayout};
+
+    let grouped = "fsm Door {\n    [*] --> Open\n\n    state Open {\n        entry / chime\n        /// Shut by hand\n        on door_closed [latched] -> Unlocked / click // quiet\n        on timeout @throttle(1s) -.-> <<check>>\n    }\n\n    state Unlocked {\n        on lock -> Locked\n        on reset -> [*]\n    }\n\n    // Stays flat\n    Unlocked --> Open : open\n    Locked --> Unlocked : local unlock\n    * --> Open : panic\n\n    choice check {\n        [ok] -> Open\n        [else] -> Locked\n    }\n}\n";
+    let options = FormatOptions::new().with_transition_layout(TransitionLayout::Grouped);
+    let fsm = &parse_fsm(grouped).expect("Should parse successfully")[0];
+    assert_eq!(format_fsm_with_options(fsm, &options), grouped);
+
+    let flat = format_fsm(fsm);
+    assert!(flat.contains("    state Open {\n        entry / chime\n    }\n\n    /// Shut by hand\n    Open --> Unlocked : door_closed [latched] / click // quiet\n"));
+    assert!(flat.contains("    Open -.-> <<check>> : timeout @throttle(1s)\n"));
+    assert!(flat.contains("    Unlocked --> [*] : reset\n"));
+    assert!(!flat.contains(" on "));
+
+    // Back and forth between the forms loses nothing
+    let from_flat = &parse_fsm(&flat).unwrap()[0];
+    assert_eq!(format_fsm_with_options(from_flat, &options), grouped);
+    // A state only mentioned in transitions gets a block to hold its own
+    let fsm = &parse_fsm("fsm M {\n    [*] --> A\n    A --> B : go\n    B --> A : back\n}\n").unwrap()[0];
+    assert_eq!(
+        format_fsm_with_options(fsm, &options),
+        "fsm M {\n    [*] --> A\n\n    state A {\n        on go -> B\n    }\n\n    state B {\n        on back -> A\n    }\n}\n"
+    );
+}
+
+#[test]
+fn test_edit_grouped_transitions() {
+    use crate::parser::edit::{delete_states, rename_event, rename_state};
+
+    let source = "fsm M {\n    [*] --> Idle\n    state Idle {\n        on start -> Busy / spin\n        on fault -> Broken\n    }\n    state Busy { on stop -> Idle }\n}\n";
+    let fsm = &parse_fsm(source).unwrap()[0];
+    assert_eq!(rename_event(source, fsm, "start", "go"), source.replace("on start ->", "on go ->"));
+    assert_eq!(rename_state(source, fsm, "Busy", "Running"), source.replace("Busy", "Running"));
+
+    // Deleting a target takes the grouped transition into it, not the state holding it
+    let deleted = delete_states(source, fsm, &["Broken".to_string()]).unwrap();
+    assert_eq!(deleted, source.replace("        on fault -> Broken\n", ""));
+    let deleted = delete_states(source, fsm, &["Busy".to_string()]).unwrap();
+    assert!(!deleted.contains("Busy") && deleted.contains("on fault -> Broken"));
+}
+
+// ============================================================================
+// EVENT IMPORT
+// ============================================================================
+
+const EVENTS_HEADER: &str = r#"
+#ifndef EVENTS_H
+#define EVENTS_H
+
+#include <stdint.h>
+
+/* Not an enum { A, B } either */
+#define EVENT_MASK(x) \
+    ((x) & 0xFF)
+
+typedef enum {
+    EV_POWER_ON = 1,      /* after reset */
+    EV_POWER_OFF,         // by the user
+#ifdef HAS_BUTTON
+    EV_BUTTON_PRESSED = 0x10,
+#endif
+    EV_TIMEOUT = (1 << 5),
+    EV_FAULT,
+} app_event_t;
+
+enum led_mode { LED_OFF, LED_ON };
+
+void post(enum led_mode mode);
+#endif
+"#;
+
+#[test]
+fn test_find_enums_in_a_c_header() {
+    use crate::parser::event_import::{find_enums, EnumVariant};
+
+    let enums = find_enums(EVENTS_HEADER);
+    assert_eq!(enums.len(), 2);
+    assert_eq!(enums[0].name.as_deref(), Some("app_event_t"));
+    let variant = |name: &str, value, line| EnumVariant { name: name.to_string(), value, line };
+    assert_eq!(
+        enums[0].variants,
+        [
+            variant("EV_POWER_ON", Some(1), 12),
+            variant("EV_POWER_OFF", None, 13),
+            variant("EV_BUTTON_PRESSED", Some(16), 15),
+            // An expression is no discriminant
+            variant("EV_TIMEOUT", None, 17),
+            variant("EV_FAULT", None, 18),
+        ]
+    );
+    assert_eq!(enums[1].name.as_deref(), Some("led_mode"));
+    assert_eq!(enums[1].variants.len(), 2);
+}
+
+#[test]
+fn test_find_enums_in_rust() {
+    use crate::parser::event_import::find_enums;
+
+    let source = r#"
+        /// Events of the controller
+        #[derive(Debug, Clone, Copy)]
+        #[repr(u8)]
+        pub enum ControllerEvent {
+            /// Power came up
+            PowerOn = 3,
+            #[cfg(feature = "button")]
+            ButtonPressed,
+            SetSpeed(u16),
+            Fault { code: u32, fatal: bool },
+        }
+    "#;
+    let enums = find_enums(source);
+    assert_eq!(enums.len(), 1);
+    assert_eq!(enums[0].name.as_deref(), Some("ControllerEvent"));
+    let names: Vec<(&str, Option<u32>)> = enums[0].variants.iter().map(|v| (v.name.as_str(), v.value)).collect();
+    assert_eq!(names, [("PowerOn", Some(3)), ("ButtonPressed", None), ("SetSpeed", None), ("Fault", None)]);
+}
+
+#[test]
+fn test_import_events_into_an_fsm() {
+    use crate::parser::edit::add_events_block;
+    use crate::parser::event_import::{import_events, EventImportError};
+
+    let source = "fsm Lamp {\n    events {\n        fault\n    }\n    [*] --> Off\n    Off --> On : power_on\n}\n";
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let import = import_events(EVENTS_HEADER, Some("app_event_t"), fsm).unwrap();
+    let names: Vec<&str> = import.events.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["power_on", "power_off", "button_pressed", "timeout"]);
+    assert_eq!(import.conflicts, ["'EV_FAULT' (line 18, as 'fault') is already declared in Lamp"]);
+
+    let block = import.events_block("events.h").unwrap();
+    let edited = add_events_block(source, fsm, &block).unwrap();
+    assert!(edited.contains(
+        "    }\n    // Imported from events.h\n    events {\n        power_on = 1\n        power_off\n        button_pressed = 16\n        timeout\n    }\n    [*] --> Off\n"
+    ));
+    let fsm = &parse_fsm(&edited).unwrap()[0];
+    let declared: Vec<(&str, Option<u32>)> = fsm.events.iter().map(|e| (e.name.as_str(), e.id)).collect();
+    assert_eq!(declared, [("fault", None), ("power_on", Some(1)), ("power_off", None), ("button_pressed", Some(16)), ("timeout", None)]);
+
+    // Importing again declares nothing twice; names two enumerators share are reported
+    let again = import_events(EVENTS_HEADER, Some("app_event_t"), fsm).unwrap();
+    assert!(again.events.is_empty() && again.events_block("events.h").is_none());
+    assert_eq!(again.conflicts.len(), 5);
+    let clash = import_events("enum E { StartMotor, START_MOTOR };", None, fsm).unwrap();
+    assert_eq!(clash.events.len(), 1);
+    assert_eq!(clash.conflicts, ["'START_MOTOR' (line 1, as 'start_motor') is imported already"]);
+
+    // Without an events block, the new one goes after the header
+    let source = "fsm Lamp {\n    [*] --> Off\n}\n";
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let import = import_events(EVENTS_HEADER, Some("led_mode"), fsm).unwrap();
+    let edited = add_events_block(source, fsm, &import.events_block("events.h").unwrap()).unwrap();
+    assert_eq!(edited, "fsm Lamp {\n    // Imported from events.h\n    events {\n        off\n        on\n    }\n    [*] --> Off\n}\n");
+
+    assert_eq!(import_events("#define EV_START 1\n", None, fsm), Err(EventImportError::NoEnum));
+    assert_eq!(
+        import_events(EVENTS_HEADER, Some("event_t"), fsm).unwrap_err().to_string(),
+        "no enum named 'event_t' (found: app_event_t, led_mode)"
+    );
+}
@@ -0,0 +1,142 @@
+//! Project Manifests
+//! An `oxidate.toml` lists the member `.fsm` files of a project, one machine
+//! (or a few related ones) per file. Members are parsed independently, so a
+//! broken file does not affect the others.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::fsm::FsmDefinition;
+use crate::parser::loader::parse_fsm_file;
+use crate::parser::ParseResult;
+
+#[cfg(test)]
+mod tests;
+
+/// File name looked for when a folder is opened as a project
+pub const MANIFEST_FILE_NAME: &str = "oxidate.toml";
+
+/// Contents of `oxidate.toml`
+///
+/// ```toml
+/// [project]
+/// name = "Robot"
+/// members = ["machines/door.fsm", "machines/motor.fsm"]
+/// output = "src"  # optional: folder the `autogen` module is exported into
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub project: ProjectSection,
+}
+
+/// The `[project]` table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectSection {
+    pub name: String,
+    /// Member `.fsm` files, relative to the manifest
+    pub members: Vec<PathBuf>,
+    /// Export folder relative to the manifest; the manifest's folder if not set
+    #[serde(default)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Error, Debug)]
+pub enum ProjectError {
+    #[error("Could not read '{file}': {message}")]
+    Io { file: String, message: String },
+    #[error("{file}: {message}")]
+    Manifest { file: String, message: String },
+}
+
+/// A loaded manifest and where it lives
+#[derive(Debug, Clone)]
+pub struct Project {
+    /// Path of `oxidate.toml`
+    pub manifest_path: PathBuf,
+    pub manifest: Manifest,
+}
+
+/// One member file and the result of parsing it
+#[derive(Debug)]
+pub struct Member {
+    pub path: PathBuf,
+    pub result: ParseResult<Vec<FsmDefinition>>,
+}
+
+impl Project {
+    /// Load a manifest, given its path or the folder containing it
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ProjectError> {
+        let path = path.as_ref();
+        let manifest_path = if path.is_dir() {
+            path.join(MANIFEST_FILE_NAME)
+        } else {
+            path.to_path_buf()
+        };
+        let source = std::fs::read_to_string(&manifest_path).map_err(|e| ProjectError::Io {
+            file: manifest_path.display().to_string(),
+            message: e.to_string(),
+        })?;
+        Self::from_manifest_source(&source, manifest_path)
+    }
+
+    /// Parse manifest text as if it were the contents of `manifest_path`
+    pub fn from_manifest_source(source: &str, manifest_path: impl Into<PathBuf>) -> Result<Self, ProjectError> {
+        let manifest_path = manifest_path.into();
+        let manifest_error = |message: String| ProjectError::Manifest {
+            file: manifest_path.display().to_string(),
+            message,
+        };
+        let manifest: Manifest = toml::from_str(source).map_err(|e| manifest_error(e.message().to_string()))?;
+        if manifest.project.members.is_empty() {
+            return Err(manifest_error("`members` lists no .fsm files".to_string()));
+        }
+        Ok(Self { manifest_path, manifest })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.manifest.project.name
+    }
+
+    /// Folder containing the manifest; member paths are relative to it
+    pub fn root(&self) -> &Path {
+        self.manifest_path.parent().unwrap_or(Path::new(""))
+    }
+
+    /// Member files in manifest order
+    pub fn member_paths(&self) -> Vec<PathBuf> {
+        self.manifest.project.members.iter().map(|m| self.root().join(m)).collect()
+    }
+
+    /// Folder the `autogen` module is exported into
+    pub fn output_dir(&self) -> PathBuf {
+        match self.manifest.project.output {
+            Some(ref output) => self.root().join(output),
+            None => self.root().to_path_buf(),
+        }
+    }
+
+    /// Parse every member on its own (includes resolve relative to each member)
+    pub fn parse_members(&self) -> Vec<Member> {
+        self.member_paths()
+            .into_iter()
+            .map(|path| Member {
+                result: parse_fsm_file(&path),
+                path,
+            })
+            .collect()
+    }
+}
+
+/// Names of FSMs defined by more than one member; their generated files would overwrite each other
+pub fn duplicate_fsm_names<'a>(fsms: impl IntoIterator<Item = &'a FsmDefinition>) -> Vec<&'a str> {
+    let mut seen = std::collections::HashSet::new();
+    let mut duplicates = Vec::new();
+    for fsm in fsms {
+        if !seen.insert(fsm.name.as_str()) && !duplicates.contains(&fsm.name.as_str()) {
+            duplicates.push(fsm.name.as_str());
+        }
+    }
+    duplicates
+}
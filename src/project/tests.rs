@@ -0,0 +1,71 @@
+//! Unit tests for project manifests
+
+use std::path::{Path, PathBuf};
+
+use crate::parser::ParseError;
+use crate::project::{duplicate_fsm_names, Project, ProjectError, MANIFEST_FILE_NAME};
+
+fn project_test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("oxidate_project_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("machines")).unwrap();
+    dir
+}
+
+#[test]
+fn test_manifest_paths() {
+    let source = "[project]\nname = \"Robot\"\nmembers = [\"machines/door.fsm\", \"motor.fsm\"]\noutput = \"src\"\n";
+    let project = Project::from_manifest_source(source, "/work/robot/oxidate.toml").expect("valid manifest");
+
+    assert_eq!(project.name(), "Robot");
+    assert_eq!(project.root(), Path::new("/work/robot"));
+    assert_eq!(
+        project.member_paths(),
+        vec![PathBuf::from("/work/robot/machines/door.fsm"), PathBuf::from("/work/robot/motor.fsm")]
+    );
+    assert_eq!(project.output_dir(), PathBuf::from("/work/robot/src"));
+
+    let without_output = Project::from_manifest_source("[project]\nname = \"R\"\nmembers = [\"a.fsm\"]\n", "/r/oxidate.toml").unwrap();
+    assert_eq!(without_output.output_dir(), PathBuf::from("/r"));
+}
+
+#[test]
+fn test_invalid_manifests() {
+    let missing_members = Project::from_manifest_source("[project]\nname = \"Robot\"\n", "oxidate.toml").unwrap_err();
+    assert!(matches!(missing_members, ProjectError::Manifest { ref message, .. } if message.contains("members")));
+
+    let empty = Project::from_manifest_source("[project]\nname = \"Robot\"\nmembers = []\n", "oxidate.toml").unwrap_err();
+    assert_eq!(empty.to_string(), "oxidate.toml: `members` lists no .fsm files");
+
+    let missing_file = Project::load("/nonexistent/oxidate.toml").unwrap_err();
+    assert!(matches!(missing_file, ProjectError::Io { .. }));
+}
+
+#[test]
+fn test_members_parse_independently() {
+    let dir = project_test_dir("members");
+    std::fs::write(
+        dir.join(MANIFEST_FILE_NAME),
+        "[project]\nname = \"Robot\"\nmembers = [\"machines/door.fsm\", \"machines/motor.fsm\", \"machines/arm.fsm\"]\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("machines/door.fsm"), "fsm Door {\n    [*] --> Closed\n    Closed --> Open : open\n}\n").unwrap();
+    // Missing closing brace
+    std::fs::write(dir.join("machines/motor.fsm"), "fsm Motor {\n    [*] --> Off\n    Off --> On : start\n").unwrap();
+    std::fs::write(dir.join("machines/arm.fsm"), "fsm Arm {\n    [*] --> Parked\n}\nfsm Door {\n    [*] --> A\n}\n").unwrap();
+
+    // Opening the folder finds the manifest
+    let project = Project::load(&dir).expect("manifest loads");
+    let members = project.parse_members();
+    assert_eq!(members.len(), 3);
+
+    let names = |i: usize| -> Vec<String> { members[i].result.as_ref().unwrap().iter().map(|f| f.name.clone()).collect() };
+    assert_eq!(names(0), ["Door"]);
+    assert!(matches!(members[1].result, Err(ParseError::InFile { ref file, .. }) if file.ends_with("motor.fsm")));
+    assert_eq!(names(2), ["Arm", "Door"]);
+
+    let fsms: Vec<_> = members.iter().filter_map(|m| m.result.as_ref().ok()).flatten().collect();
+    assert_eq!(duplicate_fsm_names(fsms), ["Door"]);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,213 @@
+//! HTML Report
+//! One self-contained HTML file for design reviews: per FSM its diagram, DSL source,
+//! generated code, diagnostics and statistics. Styles are inlined and there is no
+//! script, so the file can be mailed around and opened anywhere.
+
+use std::fmt::Write;
+
+use crate::codegen::highlight::{tokens, TokenClass};
+use crate::fsm::FsmDefinition;
+use crate::parser::diagnostics::{Diagnostic, Severity};
+
+#[cfg(test)]
+mod tests;
+
+/// What the report shows for one FSM
+#[derive(Debug, Clone)]
+pub struct ReportSection<'a> {
+    pub fsm: &'a FsmDefinition,
+    /// DSL text of the FSM
+    pub source: String,
+    /// The diagram as an SVG document, if one could be drawn
+    pub svg: Option<String>,
+    /// Generated code for the report's target
+    pub code: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Counts shown in the statistics table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Statistics {
+    pub states: usize,
+    pub transitions: usize,
+    pub internal_transitions: usize,
+    pub events: usize,
+    pub guards: usize,
+    pub actions: usize,
+    pub timers: usize,
+    pub choice_points: usize,
+}
+
+impl Statistics {
+    pub fn of(fsm: &FsmDefinition) -> Self {
+        let internal: Vec<_> = fsm.states.iter().flat_map(|s| &s.internal_transitions).collect();
+        let mut guards: Vec<&str> = fsm
+            .transitions
+            .iter()
+            .chain(internal.iter().copied())
+            .filter_map(|t| t.guard.as_ref())
+            .chain(fsm.choice_points.iter().flat_map(|c| c.branches.iter().map(|b| &b.guard)))
+            .filter(|g| !g.is_else())
+            .map(|g| g.expression.as_str())
+            .collect();
+        guards.sort_unstable();
+        guards.dedup();
+        Self {
+            states: fsm.states.len(),
+            transitions: fsm.transitions.len(),
+            internal_transitions: internal.len(),
+            events: fsm.collect_events().len(),
+            guards: guards.len(),
+            actions: fsm.action_names().len(),
+            timers: fsm.timers.len(),
+            choice_points: fsm.choice_points.len(),
+        }
+    }
+
+    fn rows(&self) -> [(&'static str, usize); 8] {
+        [
+            ("States", self.states),
+            ("Transitions", self.transitions),
+            ("Internal transitions", self.internal_transitions),
+            ("Events", self.events),
+            ("Guards", self.guards),
+            ("Actions", self.actions),
+            ("Timers", self.timers),
+            ("Choice points", self.choice_points),
+        ]
+    }
+}
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em auto; max-width: 70em; color: #202020; }
+h1 { border-bottom: 2px solid #3060a0; }
+section { margin-top: 3em; }
+h2 { border-bottom: 1px solid #c0c8d0; }
+.diagram { overflow: auto; border: 1px solid #c0c8d0; padding: 0.5em; }
+pre { background: #f6f8fa; border: 1px solid #d0d7de; padding: 0.8em; overflow: auto; font-size: 0.85em; }
+table { border-collapse: collapse; }
+td { border: 1px solid #d0d7de; padding: 0.2em 0.8em; }
+td.count { text-align: right; }
+.error { color: #b00020; }
+.warning { color: #8a6100; }
+.info { color: #305080; }
+.ok { color: #207020; }
+.kw { color: #a0308a; font-weight: bold; }
+.str { color: #207020; }
+.num { color: #1060a0; }
+.com { color: #707070; font-style: italic; }
+";
+
+/// The report as one HTML document; `target` names what the code was generated for
+pub fn html_report(title: &str, target: &str, sections: &[ReportSection]) -> String {
+    let mut html = String::new();
+    let _ = writeln!(html, "<!DOCTYPE html>");
+    let _ = writeln!(html, "<html lang=\"en\">");
+    let _ = writeln!(html, "<head>");
+    let _ = writeln!(html, "<meta charset=\"utf-8\"/>");
+    let _ = writeln!(html, "<title>{}</title>", escape(title));
+    let _ = writeln!(html, "<style>{STYLE}</style>");
+    let _ = writeln!(html, "</head>");
+    let _ = writeln!(html, "<body>");
+    let _ = writeln!(html, "<h1>{}</h1>", escape(title));
+    let _ = writeln!(html, "<ul>");
+    for (i, section) in sections.iter().enumerate() {
+        let _ = writeln!(html, "<li><a href=\"#fsm-{i}\">{}</a></li>", escape(&section.fsm.name));
+    }
+    let _ = writeln!(html, "</ul>");
+    for (i, section) in sections.iter().enumerate() {
+        write_section(&mut html, i, target, section);
+    }
+    let _ = writeln!(html, "</body>");
+    let _ = writeln!(html, "</html>");
+    html
+}
+
+fn write_section(html: &mut String, index: usize, target: &str, section: &ReportSection) {
+    let fsm = section.fsm;
+    let _ = writeln!(html, "<section id=\"fsm-{index}\">");
+    let _ = writeln!(html, "<h2>{}</h2>", escape(&fsm.name));
+    if let Some(ref description) = fsm.description {
+        let _ = writeln!(html, "<p>{}</p>", escape(description));
+    }
+
+    let _ = writeln!(html, "<h3>Diagram</h3>");
+    match section.svg {
+        Some(ref svg) => {
+            let _ = writeln!(html, "<div class=\"diagram\">\n{}</div>", svg.trim_end());
+        }
+        None => {
+            let _ = writeln!(html, "<p>No diagram could be drawn.</p>");
+        }
+    }
+
+    let _ = writeln!(html, "<h3>Diagnostics</h3>");
+    if section.diagnostics.is_empty() {
+        let _ = writeln!(html, "<p class=\"ok\">No problems found.</p>");
+    } else {
+        let _ = writeln!(html, "<ul>");
+        for diagnostic in &section.diagnostics {
+            let (class, label) = match diagnostic.severity {
+                Severity::Error => ("error", "Error"),
+                Severity::Warning => ("warning", "Warning"),
+                Severity::Info => ("info", "Note"),
+            };
+            let _ = writeln!(html, "<li class=\"{class}\">{label}: {}</li>", escape(&diagnostic.message));
+        }
+        let _ = writeln!(html, "</ul>");
+    }
+
+    let _ = writeln!(html, "<h3>Statistics</h3>");
+    let _ = writeln!(html, "<table>");
+    for (name, count) in Statistics::of(fsm).rows() {
+        let _ = writeln!(html, "<tr><td>{name}</td><td class=\"count\">{count}</td></tr>");
+    }
+    let _ = writeln!(html, "</table>");
+
+    let _ = writeln!(html, "<h3>Source</h3>");
+    let _ = writeln!(html, "<pre class=\"dsl\">{}</pre>", escape(section.source.trim_end()));
+
+    let _ = writeln!(html, "<h3>Generated code ({})</h3>", escape(target));
+    let _ = writeln!(html, "<pre class=\"code\">{}</pre>", highlight(section.code.trim_end()));
+    let _ = writeln!(html, "</section>");
+}
+
+/// Text escaped for HTML and XML content and attribute values
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// ============================================================================
+// CODE HIGHLIGHTING
+// ============================================================================
+
+/// `code` escaped, with comments, strings, numbers and keywords wrapped in spans of
+/// classes `com`, `str`, `num` and `kw`
+fn highlight(code: &str) -> String {
+    let mut html = String::with_capacity(code.len() * 2);
+    for (class, text) in tokens(code) {
+        let class = match class {
+            TokenClass::Plain => {
+                html.push_str(&escape(text));
+                continue;
+            }
+            TokenClass::Keyword => "kw",
+            TokenClass::String => "str",
+            TokenClass::Number => "num",
+            TokenClass::Comment => "com",
+        };
+        let _ = write!(html, "<span class=\"{class}\">{}</span>", escape(text));
+    }
+    html
+}
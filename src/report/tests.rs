@@ -0,0 +1,83 @@
+//! Unit tests for the HTML report
+
+use crate::codegen::generate_rust_code;
+use crate::parser::diagnostics::diagnostics;
+use crate::parser::parse_fsm;
+use crate::report::{highlight, html_report, ReportSection, Statistics};
+
+const SOURCE: &str = r#"
+fsm Door {
+    [*] --> Closed
+    state Closed {
+        entry / lock
+    }
+    Closed --> Open : open [authorized] / unlock
+    Open --> Closed : close
+}
+fsm Lamp {
+    Off --> On : toggle
+    On --> Off : toggle
+}
+"#;
+
+/// Every tag closed in order; void elements must be written self-closing
+fn assert_well_formed(html: &str) {
+    let mut open: Vec<&str> = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        let end = start + rest[start..].find('>').expect("unterminated tag");
+        let tag = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+        if tag.starts_with('!') || tag.ends_with('/') {
+            continue;
+        }
+        // Text inside <style> and <pre> is escaped, so a `<` is always markup
+        let name = tag.trim_start_matches('/').split_whitespace().next().unwrap();
+        if tag.starts_with('/') {
+            assert_eq!(open.pop(), Some(name), "unbalanced </{name}>");
+        } else {
+            open.push(name);
+        }
+    }
+    assert!(open.is_empty(), "unclosed {open:?}");
+}
+
+#[test]
+fn test_report_has_a_diagram_and_code_per_fsm() {
+    let fsms = parse_fsm(SOURCE).expect("Should parse successfully");
+    let sections: Vec<ReportSection> = fsms
+        .iter()
+        .map(|fsm| ReportSection {
+            fsm,
+            source: SOURCE.to_string(),
+            svg: Some(format!("<svg xmlns=\"http://www.w3.org/2000/svg\"><text x=\"0\" y=\"0\">{}</text></svg>\n", fsm.name)),
+            code: generate_rust_code(fsm),
+            diagnostics: diagnostics(SOURCE, fsm),
+        })
+        .collect();
+    let html = html_report("Doors & lamps", "Rust", &sections);
+
+    assert_well_formed(&html);
+    assert_eq!(html.matches("<svg").count(), 2);
+    assert_eq!(html.matches("<pre class=\"code\">").count(), 2);
+    assert_eq!(html.matches("<pre class=\"dsl\">").count(), 2);
+    assert!(html.contains("<title>Doors &amp; lamps</title>"));
+    assert!(!html.contains("<script") && !html.contains("<link"));
+    // Lamp has no initial state
+    assert!(html.contains("<li class=\"error\">Error: No initial state defined</li>"));
+    assert!(html.contains("<tr><td>Guards</td><td class=\"count\">1</td></tr>"));
+}
+
+#[test]
+fn test_statistics_and_highlighting() {
+    let fsm = &parse_fsm(SOURCE).expect("Should parse successfully")[0];
+    let stats = Statistics::of(fsm);
+    assert_eq!((stats.states, stats.transitions, stats.events, stats.actions), (2, 2, 2, 2));
+
+    assert_eq!(
+        highlight("fn a() -> u8 { 42 } // \"x\" <y>\nlet s = \"a\\\"<b\";"),
+        "<span class=\"kw\">fn</span> a() -&gt; u8 { <span class=\"num\">42</span> } \
+         <span class=\"com\">// &quot;x&quot; &lt;y&gt;</span>\n<span class=\"kw\">let</span> s = \
+         <span class=\"str\">&quot;a\\&quot;&lt;b&quot;</span>;"
+    );
+}
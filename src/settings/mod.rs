@@ -0,0 +1,75 @@
+//! Display Settings
+//! UI scale and font sizes from the Settings window, stored with the app's
+//! eframe storage so they survive restarts
+
+use serde::{Deserialize, Serialize};
+
+use crate::diagram::{StateBoxContent, DEFAULT_DIAGRAM_FONT_SIZE};
+
+#[cfg(test)]
+mod tests;
+
+/// eframe storage key of the serialized settings
+pub const SETTINGS_KEY: &str = "oxidate_settings";
+
+pub const UI_SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=3.0;
+pub const EDITOR_FONT_RANGE: std::ops::RangeInclusive<f32> = 8.0..=32.0;
+pub const DIAGRAM_FONT_RANGE: std::ops::RangeInclusive<f32> = 8.0..=32.0;
+pub const EDGE_LABEL_FONT_RANGE: std::ops::RangeInclusive<f32> = 7.0..=28.0;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Zoom of the whole UI on top of the display's own scale factor
+    pub ui_scale: f32,
+    /// Monospace size of the DSL editor and the generated code
+    pub editor_font_size: f32,
+    /// Diagram font size (state names)
+    pub diagram_font_size: f32,
+    /// Transition label font size
+    pub edge_label_font_size: f32,
+    /// What the diagram's state boxes show (the Display popover)
+    pub state_box: StateBoxContent,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            ui_scale: 1.0,
+            editor_font_size: 13.0,
+            diagram_font_size: DEFAULT_DIAGRAM_FONT_SIZE,
+            edge_label_font_size: 12.0,
+            state_box: StateBoxContent::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Settings stored as JSON; missing fields take their defaults and every value is clamped
+    pub fn from_json(json: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(json).ok().map(Self::clamped)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Every value within its range (a non-finite value becomes the default)
+    pub fn clamped(self) -> Self {
+        let defaults = Self::default();
+        let clamp = |value: f32, range: std::ops::RangeInclusive<f32>, default: f32| {
+            if value.is_finite() {
+                value.clamp(*range.start(), *range.end())
+            } else {
+                default
+            }
+        };
+        Self {
+            ui_scale: clamp(self.ui_scale, UI_SCALE_RANGE, defaults.ui_scale),
+            editor_font_size: clamp(self.editor_font_size, EDITOR_FONT_RANGE, defaults.editor_font_size),
+            diagram_font_size: clamp(self.diagram_font_size, DIAGRAM_FONT_RANGE, defaults.diagram_font_size),
+            edge_label_font_size: clamp(self.edge_label_font_size, EDGE_LABEL_FONT_RANGE, defaults.edge_label_font_size),
+            state_box: self.state_box,
+        }
+    }
+}
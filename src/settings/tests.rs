@@ -0,0 +1,37 @@
+//! Unit tests for the display settings
+
+use crate::diagram::StateBoxContent;
+use crate::settings::{Settings, DIAGRAM_FONT_RANGE, UI_SCALE_RANGE};
+
+#[test]
+fn test_settings_round_trip_and_defaults() {
+    let settings = Settings {
+        ui_scale: 1.5,
+        editor_font_size: 16.0,
+        diagram_font_size: 18.0,
+        edge_label_font_size: 14.0,
+        state_box: StateBoxContent {
+            internal_transitions: true,
+            ..StateBoxContent::default()
+        },
+    };
+    assert_eq!(Settings::from_json(&settings.to_json()), Some(settings));
+
+    // Fields added later fall back to their defaults
+    let partial = Settings::from_json(r#"{"ui_scale": 2.0}"#).expect("partial settings load");
+    assert_eq!(partial.ui_scale, 2.0);
+    assert_eq!(partial.diagram_font_size, Settings::default().diagram_font_size);
+    assert_eq!(Settings::from_json("not json"), None);
+}
+
+#[test]
+fn test_settings_clamped() {
+    let settings = Settings::from_json(r#"{"ui_scale": 0.01, "diagram_font_size": 1.0, "editor_font_size": 500.0}"#)
+        .expect("settings load");
+    assert_eq!(settings.ui_scale, *UI_SCALE_RANGE.start());
+    assert_eq!(settings.diagram_font_size, *DIAGRAM_FONT_RANGE.start());
+    assert_eq!(settings.editor_font_size, 32.0);
+
+    let broken = Settings { ui_scale: f32::NAN, ..Settings::default() }.clamped();
+    assert_eq!(broken.ui_scale, 1.0);
+}
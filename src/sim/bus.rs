@@ -0,0 +1,204 @@
+//! Event Bus
+//! Runs several FSMs side by side, each in its own `Simulator`. A posted event goes
+//! to every machine that handles it (or to one chosen machine), and a fired
+//! `send(Machine, event)` action queues `event` on the machine named `Machine`.
+
+use crate::fsm::interpreter::ActionOrder;
+use crate::fsm::FsmDefinition;
+use crate::sim::{Clock, Simulator, SystemClock};
+
+/// One FSM on the bus
+#[derive(Clone, Debug)]
+pub struct BusMachine<C: Clock = SystemClock> {
+    /// Name of the FSM it runs
+    pub fsm: String,
+    pub sim: Simulator<C>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SimBus<C: Clock + Clone = SystemClock> {
+    pub enabled: bool,
+    pub running: bool,
+    /// Applied to every machine
+    pub speed: f32,
+    pub use_fsm_timers: bool,
+    pub action_order: ActionOrder,
+    /// Machines in the order they were added
+    pub machines: Vec<BusMachine<C>>,
+    pub event_input: String,
+    /// Machine posted events go to; `None` delivers them to every machine that handles them
+    pub target: Option<String>,
+    /// Lines of every machine, prefixed with its FSM name
+    pub log: Vec<String>,
+    clock: C,
+}
+
+impl Default for SimBus {
+    fn default() -> Self {
+        Self::with_clock(SystemClock)
+    }
+}
+
+impl<C: Clock + Clone> SimBus<C> {
+    pub fn with_clock(clock: C) -> Self {
+        Self {
+            enabled: false,
+            running: false,
+            speed: 1.0,
+            use_fsm_timers: false,
+            action_order: ActionOrder::default(),
+            machines: Vec::new(),
+            event_input: String::new(),
+            target: None,
+            log: Vec::new(),
+            clock,
+        }
+    }
+
+    pub fn machine(&self, fsm: &str) -> Option<&BusMachine<C>> {
+        self.machines.iter().find(|m| m.fsm == fsm)
+    }
+
+    pub fn machine_mut(&mut self, fsm: &str) -> Option<&mut BusMachine<C>> {
+        self.machines.iter_mut().find(|m| m.fsm == fsm)
+    }
+
+    /// Put `fsm` on the bus, started in its initial state; a machine already on it is kept
+    pub fn add_machine(&mut self, fsm: &FsmDefinition) {
+        if self.machine(&fsm.name).is_some() {
+            return;
+        }
+        let mut sim = Simulator::with_clock(self.clock.clone());
+        sim.route_sends = true;
+        sim.reset_to_initial(fsm);
+        self.machines.push(BusMachine { fsm: fsm.name.clone(), sim });
+        self.route();
+    }
+
+    pub fn remove_machine(&mut self, fsm: &str) {
+        self.machines.retain(|m| m.fsm != fsm);
+        if self.target.as_deref() == Some(fsm) {
+            self.target = None;
+        }
+    }
+
+    /// Restart every machine, then deliver what their start actions sent
+    pub fn reset_all(&mut self, fsms: &[FsmDefinition]) {
+        for machine in &mut self.machines {
+            if let Some(fsm) = fsms.iter().find(|f| f.name == machine.fsm) {
+                machine.sim.reset_to_initial(fsm);
+            }
+        }
+        self.route();
+    }
+
+    /// Queue `event` on the target machine, or on every machine whose FSM uses it
+    pub fn post_event(&mut self, event: impl Into<String>, fsms: &[FsmDefinition]) {
+        let event = event.into();
+        let event = event.trim();
+        if event.is_empty() {
+            return;
+        }
+        let mut delivered = Vec::new();
+        for machine in &mut self.machines {
+            let handles = match self.target {
+                Some(ref target) => *target == machine.fsm,
+                None => fsms
+                    .iter()
+                    .find(|f| f.name == machine.fsm)
+                    .is_some_and(|f| f.collect_events().iter().any(|e| e.name == event)),
+            };
+            if handles {
+                machine.sim.post_event(event);
+                delivered.push(machine.fsm.as_str());
+            }
+        }
+        if delivered.is_empty() {
+            self.log.push(format!("bus: no machine handles '{event}'"));
+        } else {
+            self.log.push(format!("bus: '{event}' → {}", delivered.join(", ")));
+        }
+    }
+
+    /// Let every machine with queued events handle one, then deliver what they sent
+    pub fn step(&mut self, fsms: &[FsmDefinition]) {
+        for machine in &mut self.machines {
+            if machine.sim.queued_events.is_empty() {
+                continue;
+            }
+            if let Some(fsm) = fsms.iter().find(|f| f.name == machine.fsm) {
+                machine.sim.scrub = None;
+                machine.sim.action_order = self.action_order;
+                machine.sim.step(fsm);
+            }
+        }
+        self.route();
+    }
+
+    /// Whether some machine still has events to handle
+    pub fn is_busy(&self) -> bool {
+        self.machines.iter().any(|m| !m.sim.queued_events.is_empty())
+    }
+
+    /// Step until no machine has events queued, at most `max_steps` rounds (sends can ping-pong forever)
+    pub fn run_until_idle(&mut self, fsms: &[FsmDefinition], max_steps: usize) {
+        for _ in 0..max_steps {
+            if !self.is_busy() {
+                return;
+            }
+            self.step(fsms);
+        }
+    }
+
+    /// Per-frame update of every machine (see `Simulator::tick`)
+    pub fn tick(&mut self, fsms: &[FsmDefinition]) {
+        for machine in &mut self.machines {
+            machine.sim.running = self.running;
+            machine.sim.speed = self.speed;
+            machine.sim.use_fsm_timers = self.use_fsm_timers;
+            machine.sim.action_order = self.action_order;
+            if let Some(fsm) = fsms.iter().find(|f| f.name == machine.fsm) {
+                machine.sim.tick(fsm);
+            }
+        }
+        self.route();
+    }
+
+    /// Carry the machines over to re-parsed FSMs; machines whose FSM is gone are dropped
+    pub fn reconcile(&mut self, fsms: &[FsmDefinition]) {
+        let before = self.machines.len();
+        self.machines.retain(|m| fsms.iter().any(|f| f.name == m.fsm));
+        if self.machines.len() < before {
+            self.log.push("bus: model changed, machines of removed FSMs dropped".to_string());
+        }
+        if self.target.as_ref().is_some_and(|t| self.machine(t).is_none()) {
+            self.target = None;
+        }
+        for machine in &mut self.machines {
+            let fsm = fsms.iter().find(|f| f.name == machine.fsm).expect("kept above");
+            // Machines are matched by FSM name, so this is always the same FSM
+            machine.sim.reconcile(Some(fsm), fsm);
+        }
+        self.route();
+    }
+
+    /// Move the machines' log lines to the bus log and queue their sent events on the targets
+    fn route(&mut self) {
+        let mut sent = Vec::new();
+        for machine in &mut self.machines {
+            for line in machine.sim.log.drain(..) {
+                self.log.push(format!("[{}] {line}", machine.fsm));
+            }
+            sent.extend(machine.sim.outbox.drain(..).map(|(target, event)| (machine.fsm.clone(), target, event)));
+        }
+        for (from, target, event) in sent {
+            match self.machine_mut(&target) {
+                Some(machine) => {
+                    machine.sim.post_event(event.as_str());
+                    self.log.push(format!("[{from}] send '{event}' → {target}"));
+                }
+                None => self.log.push(format!("[{from}] send '{event}' → {target}: not on the bus, dropped")),
+            }
+        }
+    }
+}
@@ -0,0 +1,600 @@
+//! Debug Simulator
+//! Steps an FSM definition through posted events for the GUI debug mode
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Debug;
+use std::time::{Duration, Instant};
+
+use crate::fsm::interpreter::{self, ActionOrder, Firing, RateLimits, StepResult};
+use crate::fsm::{Action, FsmDefinition, TimerMode, TimerOp, TransitionId};
+
+pub mod bus;
+pub mod profiles;
+
+#[cfg(test)]
+mod tests;
+
+/// Where the simulator reads the current time; tests substitute a clock they advance by hand
+pub trait Clock: Debug {
+    fn now(&self) -> Instant;
+}
+
+/// The real time
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Simulator<C: Clock = SystemClock> {
+    pub enabled: bool,
+    pub running: bool,
+    pub speed: f32,
+
+    pub current_state: Option<String>,
+    pub queued_events: VecDeque<String>,
+    pub event_input: String,
+    /// Queue position the sim panel inserts `event_input` at
+    pub insert_position: usize,
+    /// Value of each guard expression (unlisted guards are false, so `[else]` is taken)
+    pub guard_values: BTreeMap<String, bool>,
+    /// Whether a transition's actions run after or before the exit actions (set it
+    /// like the generated code's `CodegenOptions::action_order`)
+    pub action_order: ActionOrder,
+
+    pub auto_tick: bool,
+    pub auto_event: String,
+    pub auto_period_s: f32,
+    pub auto_accum_s: f32,
+
+    /// Fire the FSM's own `timer` definitions while running
+    pub use_fsm_timers: bool,
+    /// Timers started by the current state, in start order
+    pub pending_timers: Vec<PendingTimer>,
+
+    /// Collect the events of fired `send(Machine, event)` actions in `outbox` (set by `SimBus`)
+    pub route_sends: bool,
+    /// (machine, event) pairs sent since the bus last took them
+    pub outbox: Vec<(String, String)>,
+
+    pub clock: C,
+    pub last_frame: Option<Instant>,
+    pub last_fired: Option<SimFired>,
+    pub log: Vec<String>,
+
+    /// Fired transitions since the last reset, oldest first
+    pub history: VecDeque<SimHistoryEntry>,
+    /// Oldest entries are dropped beyond this many
+    pub history_cap: usize,
+    /// Step shown by the timeline scrubber instead of the live state
+    pub scrub: Option<usize>,
+    steps: usize,
+    started_at: Option<Instant>,
+    /// `@debounce` / `@throttle` bookkeeping, on the time since the last reset
+    rate_limits: RateLimits,
+}
+
+/// One fired transition in the simulation history
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimHistoryEntry {
+    /// 1-based, counted since the last reset (dropped entries keep their numbers)
+    pub step: usize,
+    pub event: String,
+    pub from: String,
+    pub to: String,
+    /// Index in `FsmDefinition::transitions` when it fired (the CSV export's column)
+    pub transition_index: Option<usize>,
+    /// The transition, still found after the transitions are reordered
+    pub transition: Option<TransitionId>,
+    /// Time since the last reset
+    pub timestamp: Duration,
+}
+
+/// A started FSM timer waiting to fire
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingTimer {
+    pub timer: String,
+    pub event: String,
+    /// FSM time left until it fires (runs `speed` times faster than real time)
+    pub remaining: Duration,
+    /// Restart period of a periodic timer
+    pub period: Option<Duration>,
+    /// State that started it; leaving that state cancels the timer
+    pub state: String,
+}
+
+/// Last transition taken, for the edge animation
+#[derive(Clone, Debug)]
+pub struct SimFired {
+    /// Looked up by id, so an edit that reorders transitions does not animate the wrong edge
+    pub transition: Option<TransitionId>,
+    pub from: String,
+    pub to: String,
+    pub label: String,
+    pub started_at: Instant,
+    pub duration_s: f32,
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Self::with_clock(SystemClock)
+    }
+}
+
+impl<C: Clock> Simulator<C> {
+    pub fn with_clock(clock: C) -> Self {
+        Self {
+            enabled: false,
+            running: false,
+            speed: 1.0,
+            current_state: None,
+            queued_events: VecDeque::new(),
+            event_input: String::new(),
+            insert_position: 0,
+            guard_values: BTreeMap::new(),
+            action_order: ActionOrder::default(),
+            auto_tick: false,
+            auto_event: "timer_expired".to_string(),
+            auto_period_s: 1.0,
+            auto_accum_s: 0.0,
+            use_fsm_timers: false,
+            pending_timers: Vec::new(),
+            route_sends: false,
+            outbox: Vec::new(),
+            clock,
+            last_frame: None,
+            last_fired: None,
+            log: Vec::new(),
+            history: VecDeque::new(),
+            history_cap: 1000,
+            scrub: None,
+            steps: 0,
+            started_at: None,
+            rate_limits: RateLimits::default(),
+        }
+    }
+
+    pub fn reset_to_initial(&mut self, fsm: &FsmDefinition) {
+        self.restart();
+        // The guard toggles pick among guarded initial transitions
+        let guard = |expression: &str| self.guard_values.get(expression).copied().unwrap_or(false);
+        let initial = interpreter::initial_transition(fsm, &guard);
+        self.current_state = interpreter::initial_state(fsm, &guard).map(str::to_string);
+        let start_actions = interpreter::start_actions(fsm, &guard);
+        match (&self.current_state, initial.and_then(|t| t.guard.as_ref())) {
+            (Some(state), Some(guard)) => self.log.push(format!("reset → {state} [{}]", guard.expression)),
+            (Some(state), None) if initial.is_some() => self.log.push(format!("reset → {state}")),
+            (Some(state), None) => self.log.push(format!("reset → {state} (fallback)")),
+            (None, _) => self.log.push("reset → the machine has no states yet".to_string()),
+        }
+        let actions: Vec<&str> = start_actions.iter().map(|a| a.name.as_str()).collect();
+        if self.current_state.is_some() && !actions.is_empty() {
+            self.log.push(format!("  actions: {}", actions.join(", ")));
+        }
+        if let Some(state) = self.current_state.clone() {
+            self.update_timers(fsm, None, &state, true, &start_actions);
+            self.collect_sends(&start_actions);
+        }
+        self.run_completions(fsm);
+    }
+
+    /// Restart the simulation in `state` rather than the initial state, as if it had
+    /// just been entered (its entry actions start their timers); unknown states are
+    /// ignored
+    pub fn start_at(&mut self, fsm: &FsmDefinition, state: &str) {
+        let Some(entered) = fsm.states.iter().find(|s| s.name == state) else {
+            return;
+        };
+        self.restart();
+        self.current_state = Some(state.to_string());
+        self.log.push(format!("start here → {state}"));
+        let actions: Vec<&Action> = entered.entry_actions.iter().collect();
+        if !actions.is_empty() {
+            let names: Vec<&str> = actions.iter().map(|a| a.name.as_str()).collect();
+            self.log.push(format!("  actions: {}", names.join(", ")));
+        }
+        self.update_timers(fsm, None, state, true, &actions);
+        self.collect_sends(&actions);
+        self.run_completions(fsm);
+    }
+
+    /// Forget the run so far: queue, timers, outbox, history and step count
+    fn restart(&mut self) {
+        self.queued_events.clear();
+        self.pending_timers.clear();
+        self.outbox.clear();
+        self.auto_accum_s = 0.0;
+        self.last_fired = None;
+        self.last_frame = None;
+        self.history.clear();
+        self.scrub = None;
+        self.steps = 0;
+        self.started_at = Some(self.clock.now());
+        self.rate_limits.clear();
+    }
+
+    /// Time since the last reset
+    fn run_time(&self) -> Duration {
+        self.started_at.map(|t| self.clock.now().saturating_duration_since(t)).unwrap_or_default()
+    }
+
+    /// Carry the simulation over to a re-parsed FSM.
+    ///
+    /// The current state and queued events survive as long as they still exist in
+    /// `new_fsm`; the simulation only restarts when the FSM itself changed or the
+    /// current state was removed. The log is always kept.
+    pub fn reconcile(&mut self, old_fsm: Option<&FsmDefinition>, new_fsm: &FsmDefinition) {
+        // Transition indices may have shifted, so drop the in-flight animation.
+        self.last_fired = None;
+        self.scrub = None;
+
+        if old_fsm.map(|f| f.name.as_str()) != Some(new_fsm.name.as_str()) {
+            self.current_state = None;
+            self.queued_events.clear();
+            self.pending_timers.clear();
+            self.auto_accum_s = 0.0;
+            return;
+        }
+
+        if let Some(current) = self.current_state.clone() {
+            if !new_fsm.states.iter().any(|s| s.name == current) {
+                self.log.push(format!("model changed, state {current} no longer exists — reset"));
+                self.reset_to_initial(new_fsm);
+                return;
+            }
+        }
+
+        let events = new_fsm.collect_events();
+        self.queued_events.retain(|q| events.iter().any(|e| e.name == *q));
+        self.pending_timers.retain(|p| new_fsm.timers.iter().any(|t| t.name == p.timer));
+    }
+
+    pub fn post_event(&mut self, event_name: impl Into<String>) {
+        let name = event_name.into();
+        if name.trim().is_empty() {
+            return;
+        }
+        self.queued_events.push_back(name);
+    }
+
+    /// Queue `event_name` at `index` (clamped to the end), ahead of the events behind it
+    pub fn insert_event(&mut self, index: usize, event_name: impl Into<String>) {
+        let name = event_name.into();
+        if name.trim().is_empty() {
+            return;
+        }
+        self.queued_events.insert(index.min(self.queued_events.len()), name);
+    }
+
+    pub fn remove_queued(&mut self, index: usize) {
+        self.queued_events.remove(index);
+    }
+
+    /// Queue a copy of the event at `index` right behind it
+    pub fn duplicate_queued(&mut self, index: usize) {
+        if let Some(event) = self.queued_events.get(index).cloned() {
+            self.queued_events.insert(index + 1, event);
+        }
+    }
+
+    /// Swap the event at `index` with its neighbour towards the head (`up`) or the tail
+    pub fn move_queued(&mut self, index: usize, up: bool) {
+        let other = if up { index.checked_sub(1) } else { Some(index + 1) };
+        if let Some(other) = other.filter(|&other| other < self.queued_events.len() && index < self.queued_events.len()) {
+            self.queued_events.swap(index, other);
+        }
+    }
+
+    /// The transition `event` takes from `state` with the current guard values, as
+    /// [`Simulator::step`] would take it; `None` if the event would be dropped
+    pub fn find_transition<'a>(&self, fsm: &'a FsmDefinition, state: &str, event: &str) -> Option<Firing<'a>> {
+        match self.dispatch(fsm, state, event) {
+            StepResult::Fired(firing) => Some(firing),
+            _ => None,
+        }
+    }
+
+    /// Same transition selection and action order as `fsm::Interpreter`
+    fn dispatch<'a>(&self, fsm: &'a FsmDefinition, state: &str, event: &str) -> StepResult<'a> {
+        let guard = |expression: &str| self.guard_values.get(expression).copied().unwrap_or(false);
+        interpreter::dispatch(fsm, state, event, &guard)
+    }
+
+    pub fn step(&mut self, fsm: &FsmDefinition) {
+        if self.current_state.is_none() {
+            self.reset_to_initial(fsm);
+        }
+        let Some(event) = self.queued_events.pop_front() else {
+            return;
+        };
+        let Some(current) = self.current_state.clone() else {
+            return;
+        };
+        if interpreter::is_final(fsm, &current) {
+            self.log.push(format!("{current}: machine finished, event '{event}' ignored"));
+            return;
+        }
+
+        let firing = match self.dispatch(fsm, &current, &event) {
+            StepResult::Fired(firing) => firing,
+            StepResult::Ignored { guards, .. } if !guards.is_empty() => {
+                let guards: Vec<String> = guards.iter().map(|(g, value)| format!("{g}={value}")).collect();
+                self.log.push(format!("{current}: no enabled transition for event '{event}' ({})", guards.join(", ")));
+                return;
+            }
+            _ => {
+                self.log.push(format!("{current}: no transition for event '{event}'"));
+                return;
+            }
+        };
+        let now_ms = u64::try_from(self.run_time().as_millis()).unwrap_or(u64::MAX);
+        if let Some(constraint) = self.rate_limits.holds_back(fsm, firing.transition, now_ms) {
+            self.log.push(format!("{current}: event '{event}' suppressed ({})", constraint.kind.as_str()));
+            return;
+        }
+        let entered = !firing.is_internal() && firing.transition.enters_target();
+        self.apply(fsm, event, current, firing);
+        if entered {
+            self.run_completions(fsm);
+        }
+    }
+
+    /// Take the completion transitions of the state just entered, logging each hop,
+    /// like `fsm::Interpreter` (at most `DEFAULT_MAX_COMPLETION_HOPS` of them)
+    fn run_completions(&mut self, fsm: &FsmDefinition) {
+        let Some(state) = self.current_state.clone() else {
+            return;
+        };
+        let guard = |expression: &str| self.guard_values.get(expression).copied().unwrap_or(false);
+        let hops = interpreter::completions(fsm, &state, &guard, interpreter::DEFAULT_MAX_COMPLETION_HOPS);
+        let limit_reached = hops.len() == interpreter::DEFAULT_MAX_COMPLETION_HOPS;
+        for hop in hops {
+            let from = hop.from.clone();
+            self.apply(fsm, String::new(), from, hop);
+        }
+        if limit_reached {
+            self.log.push(format!(
+                "  stopped after {} completion transitions in a row",
+                interpreter::DEFAULT_MAX_COMPLETION_HOPS
+            ));
+        }
+    }
+
+    /// Log, animate and record `firing` from `current`, then move to its target;
+    /// an empty `event` marks a completion transition
+    fn apply(&mut self, fsm: &FsmDefinition, event: String, current: String, firing: Firing) {
+        let firing = firing.with_action_order(self.action_order);
+        let duration_s = if firing.is_internal() {
+            (0.4 / self.speed.max(0.05)).clamp(0.10, 2.0)
+        } else {
+            (0.7 / self.speed.max(0.05)).clamp(0.15, 3.0)
+        };
+        let transition = firing.transition_index.and_then(|index| fsm.transition_id(index));
+        let via: String = firing.choices.iter().map(|c| format!(" <<{c}>> →")).collect();
+        if firing.is_internal() {
+            self.log.push(format!("{current} --{event}--> {current} (internal)"));
+        } else if event.is_empty() {
+            let arrow = match firing.transition.guard {
+                Some(ref guard) => format!("--[{}]-->", guard.expression),
+                None => "-->".to_string(),
+            };
+            self.log.push(format!("{current} {arrow}{via} {} (completion)", firing.to));
+        } else if firing.took_else {
+            self.log.push(format!("{current} --{event} [else]-->{via} {}", firing.to));
+        } else {
+            self.log.push(format!("{current} --{event}-->{via} {}", firing.to));
+        }
+        if !firing.actions.is_empty() {
+            let actions: Vec<&str> = firing.actions.iter().map(|a| a.name.as_str()).collect();
+            self.log.push(format!("  actions: {}", actions.join(", ")));
+        }
+        self.last_fired = Some(SimFired {
+            transition,
+            from: current.clone(),
+            // The drawn edge ends at the transition's own target (a choice node, if any)
+            to: if firing.is_internal() { firing.to.clone() } else { firing.transition.target.clone() },
+            label: firing.transition.label(),
+            started_at: self.clock.now(),
+            duration_s,
+        });
+        self.steps += 1;
+        self.history.push_back(SimHistoryEntry {
+            step: self.steps,
+            event,
+            from: current.clone(),
+            to: firing.to.clone(),
+            transition_index: firing.transition_index,
+            transition,
+            timestamp: self.run_time(),
+        });
+        self.trim_history();
+        let left = (!firing.is_internal()).then_some(current.as_str());
+        self.update_timers(fsm, left, &firing.to, left.is_some(), &firing.actions);
+        self.collect_sends(&firing.actions);
+        if interpreter::is_final(fsm, &firing.to) {
+            self.log.push("  machine finished".to_string());
+        }
+        self.current_state = Some(firing.to);
+    }
+
+    /// Per-frame update: advance auto events and FSM timers by the time since the
+    /// last frame, then take at most one queued event (to keep the animation readable)
+    pub fn tick(&mut self, fsm: &FsmDefinition) {
+        let now = self.clock.now();
+        let elapsed = self.last_frame.map(|last| now.saturating_duration_since(last)).unwrap_or_default();
+        self.last_frame = Some(now);
+        if !self.running {
+            return;
+        }
+
+        if self.auto_tick {
+            self.auto_accum_s += elapsed.as_secs_f32();
+            while self.auto_accum_s >= self.auto_period_s {
+                self.auto_accum_s -= self.auto_period_s;
+                let event = self.auto_event.trim().to_string();
+                self.post_event(event);
+            }
+        }
+        if self.use_fsm_timers {
+            self.advance_timers(elapsed.mul_f32(self.speed.max(0.0)));
+        }
+        if !self.queued_events.is_empty() {
+            self.step(fsm);
+        }
+    }
+
+    /// Run the pending timers for `elapsed` FSM time, posting the events of those
+    /// that expire in the order they expire; periodic timers restart
+    pub fn advance_timers(&mut self, elapsed: Duration) {
+        let mut expired: Vec<(Duration, usize)> = Vec::new();
+        for (index, pending) in self.pending_timers.iter().enumerate() {
+            let mut at = pending.remaining;
+            while at <= elapsed {
+                expired.push((at, index));
+                match pending.period {
+                    Some(period) => at += period,
+                    None => break,
+                }
+            }
+        }
+        expired.sort_by_key(|&(at, index)| (at, index));
+        for &(_, index) in &expired {
+            let pending = &self.pending_timers[index];
+            self.log.push(format!("timer {} → {}", pending.timer, pending.event));
+            self.queued_events.push_back(pending.event.clone());
+        }
+
+        for pending in &mut self.pending_timers {
+            match (pending.remaining.checked_sub(elapsed), pending.period) {
+                (Some(remaining), _) if !remaining.is_zero() => pending.remaining = remaining,
+                (_, Some(period)) => {
+                    let overdue = elapsed - pending.remaining;
+                    let since_restart = Duration::from_nanos((overdue.as_nanos() % period.as_nanos()) as u64);
+                    pending.remaining = period - since_restart;
+                }
+                (_, None) => pending.remaining = Duration::ZERO,
+            }
+        }
+        self.pending_timers.retain(|p| !p.remaining.is_zero());
+    }
+
+    /// Stop a pending timer from the sim panel
+    pub fn cancel_timer(&mut self, timer: &str) {
+        let before = self.pending_timers.len();
+        self.pending_timers.retain(|p| p.timer != timer);
+        if self.pending_timers.len() < before {
+            self.log.push(format!("timer {timer} cancelled"));
+        }
+    }
+
+    /// Timer bookkeeping for a step into `state`: leaving `left` cancels the timers it
+    /// started, `stop_timer`/`start_timer` actions run in order, and `entering` the
+    /// state starts the timers that auto-start in it
+    fn update_timers(&mut self, fsm: &FsmDefinition, left: Option<&str>, state: &str, entering: bool, actions: &[&Action]) {
+        if let Some(left) = left {
+            self.pending_timers.retain(|p| p.state != left);
+        }
+        for action in actions {
+            match action.timer_op() {
+                Some((TimerOp::Start, timer)) => self.start_timer(fsm, timer, state),
+                Some((TimerOp::Stop, timer)) => self.pending_timers.retain(|p| p.timer != timer),
+                None => {}
+            }
+        }
+        if entering {
+            for timer in fsm.timers.iter().filter(|t| t.auto_start_state.as_deref() == Some(state)) {
+                self.start_timer(fsm, &timer.name, state);
+            }
+        }
+    }
+
+    /// Queue the events of the `send(Machine, event)` actions in `outbox`, in order
+    fn collect_sends(&mut self, actions: &[&Action]) {
+        if self.route_sends {
+            let sends = actions.iter().filter_map(|a| send_action(a));
+            self.outbox.extend(sends.map(|(target, event)| (target.to_string(), event.to_string())));
+        }
+    }
+
+    /// (Re)start `timer`, owned by `state`; unknown timers are ignored
+    fn start_timer(&mut self, fsm: &FsmDefinition, timer: &str, state: &str) {
+        let Some(definition) = fsm.timers.iter().find(|t| t.name == timer) else {
+            return;
+        };
+        // A zero period would expire forever within one frame
+        let duration = Duration::from_millis(u64::from(definition.duration_ms.max(1)));
+        self.pending_timers.retain(|p| p.timer != timer);
+        self.pending_timers.push(PendingTimer {
+            timer: definition.name.clone(),
+            event: definition.event.name.clone(),
+            remaining: duration,
+            period: (definition.mode == TimerMode::Periodic).then_some(duration),
+            state: state.to_string(),
+        });
+    }
+
+    /// Drop the oldest history entries beyond `history_cap`
+    pub fn trim_history(&mut self) {
+        let excess = self.history.len().saturating_sub(self.history_cap);
+        self.history.drain(..excess);
+        if self.scrub.is_some_and(|step| self.history_entry(step).is_none()) {
+            self.scrub = None;
+        }
+    }
+
+    pub fn history_entry(&self, step: usize) -> Option<&SimHistoryEntry> {
+        let first = self.history.front()?.step;
+        self.history.get(step.checked_sub(first)?)
+    }
+
+    /// The history entry picked with the scrubber, if any
+    pub fn scrubbed(&self) -> Option<&SimHistoryEntry> {
+        self.history_entry(self.scrub?)
+    }
+
+    /// State to highlight: the one reached at the scrubbed step, otherwise the live state
+    pub fn displayed_state(&self) -> Option<&str> {
+        match (self.scrub, self.scrubbed()) {
+            (Some(_), Some(entry)) => Some(&entry.to),
+            _ => self.current_state.as_deref(),
+        }
+    }
+
+    /// History as CSV with a header row; timestamps in seconds
+    pub fn history_csv(&self) -> String {
+        let mut csv = String::from("step,event,from,to,transition_index,timestamp_s\n");
+        for entry in &self.history {
+            let index = entry.transition_index.map(|i| i.to_string()).unwrap_or_default();
+            csv.push_str(&format!(
+                "{},{},{},{},{},{:.3}\n",
+                entry.step,
+                csv_field(&entry.event),
+                csv_field(&entry.from),
+                csv_field(&entry.to),
+                index,
+                entry.timestamp.as_secs_f64()
+            ));
+        }
+        csv
+    }
+}
+
+/// A `send(Machine, event)` action: the target machine and the event
+fn send_action(action: &Action) -> Option<(&str, &str)> {
+    match action.params.as_slice() {
+        [target, event] if action.name == "send" => Some((target.as_str(), event.as_str())),
+        _ => None,
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
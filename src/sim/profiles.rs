@@ -0,0 +1,155 @@
+//! Guard Profiles
+//! Named sets of guard values ("happy path", "attack scenario") the sim panel
+//! switches between, kept per FSM name in the app's eframe storage and shared as
+//! JSON files. A profile stores guard expressions as written when it was saved and
+//! is matched against the FSM's guards again each time it is applied.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// eframe storage key of the serialized profiles
+pub const PROFILES_KEY: &str = "oxidate_guard_profiles";
+
+/// A named assignment of guard values
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuardProfile {
+    pub name: String,
+    /// Value of each guard expression, like `Simulator::guard_values`
+    pub guards: BTreeMap<String, bool>,
+}
+
+/// A profile matched against the guards of the FSM as parsed now
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reconciled {
+    /// Value of every current guard expression (false where the profile has none)
+    pub values: BTreeMap<String, bool>,
+    /// Saved expressions that no current guard matches
+    pub unmatched: Vec<String>,
+    /// Current guards the profile has no value for
+    pub missing: Vec<String>,
+}
+
+impl GuardProfile {
+    /// The values of `guards` (the FSM's guard expressions) in `values`; a guard
+    /// without a value is saved as false, as the simulator treats it
+    pub fn capture(name: &str, values: &BTreeMap<String, bool>, guards: &[String]) -> Self {
+        let guards = guards.iter().map(|g| (g.clone(), values.get(g).copied().unwrap_or(false))).collect();
+        Self { name: name.trim().to_string(), guards }
+    }
+
+    /// Match the saved expressions to `guards`; spacing does not matter, so
+    /// `count>0` still matches `count > 0` after the source is reformatted
+    pub fn reconcile(&self, guards: &[String]) -> Reconciled {
+        let mut reconciled = Reconciled::default();
+        let mut matched: Vec<&String> = Vec::new();
+        for (expression, &value) in &self.guards {
+            match guards.iter().find(|g| same_expression(g, expression)) {
+                Some(guard) => {
+                    reconciled.values.insert(guard.clone(), value);
+                    matched.push(guard);
+                }
+                None => reconciled.unmatched.push(expression.clone()),
+            }
+        }
+        for guard in guards.iter().filter(|g| !matched.contains(g)) {
+            reconciled.values.insert(guard.clone(), false);
+            reconciled.missing.push(guard.clone());
+        }
+        reconciled
+    }
+}
+
+fn same_expression(a: &str, b: &str) -> bool {
+    let unspaced = |s: &str| s.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+    unspaced(a) == unspaced(b)
+}
+
+/// Why a profile file could not be imported
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("not a guard profile file: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("the file has no profiles")]
+    Empty,
+}
+
+/// Profiles exported for one FSM
+#[derive(Debug, Serialize, Deserialize)]
+struct ProfileFile {
+    /// FSM the profiles were saved for (imports go to the FSM shown, whatever its name)
+    fsm: String,
+    profiles: Vec<GuardProfile>,
+}
+
+/// The profiles of every FSM, by FSM name, in the order they were saved
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GuardProfiles {
+    fsms: BTreeMap<String, Vec<GuardProfile>>,
+}
+
+impl GuardProfiles {
+    /// Profiles stored as JSON (see [`PROFILES_KEY`])
+    pub fn from_json(json: &str) -> Option<Self> {
+        serde_json::from_str(json).ok()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Profiles of the FSM called `fsm`
+    pub fn of(&self, fsm: &str) -> &[GuardProfile] {
+        self.fsms.get(fsm).map_or(&[], Vec::as_slice)
+    }
+
+    /// The profile of `fsm` called `name`
+    pub fn get(&self, fsm: &str, name: &str) -> Option<&GuardProfile> {
+        self.of(fsm).iter().find(|p| p.name == name)
+    }
+
+    /// Add `profile` to `fsm`'s, replacing the one with the same name
+    pub fn save(&mut self, fsm: &str, profile: GuardProfile) {
+        let profiles = self.fsms.entry(fsm.to_string()).or_default();
+        match profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => profiles.push(profile),
+        }
+    }
+
+    /// Remove `fsm`'s profile called `name`; returns whether there was one
+    pub fn remove(&mut self, fsm: &str, name: &str) -> bool {
+        let Some(profiles) = self.fsms.get_mut(fsm) else {
+            return false;
+        };
+        let before = profiles.len();
+        profiles.retain(|p| p.name != name);
+        let removed = profiles.len() < before;
+        if profiles.is_empty() {
+            self.fsms.remove(fsm);
+        }
+        removed
+    }
+
+    /// `fsm`'s profiles as a JSON file to share
+    pub fn export(&self, fsm: &str) -> String {
+        let file = ProfileFile { fsm: fsm.to_string(), profiles: self.of(fsm).to_vec() };
+        serde_json::to_string_pretty(&file).unwrap_or_default()
+    }
+
+    /// Add the profiles of an exported file to `fsm`'s (same names are replaced);
+    /// returns their names
+    pub fn import(&mut self, fsm: &str, json: &str) -> Result<Vec<String>, ImportError> {
+        let file: ProfileFile = serde_json::from_str(json)?;
+        if file.profiles.is_empty() {
+            return Err(ImportError::Empty);
+        }
+        let names = file.profiles.iter().map(|p| p.name.clone()).collect();
+        for profile in file.profiles {
+            self.save(fsm, profile);
+        }
+        Ok(names)
+    }
+}
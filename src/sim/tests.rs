@@ -0,0 +1,573 @@
+//! Unit tests for the debug simulator
+
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::fsm::interpreter::guard_expressions;
+use crate::parser::parse_fsm;
+use crate::sim::bus::SimBus;
+use crate::sim::profiles::{GuardProfile, GuardProfiles, ImportError};
+use crate::sim::{Clock, Simulator};
+
+const MOTOR: &str = r#"
+    fsm Motor {
+        [*] --> Off
+        Off --> On : start
+        On --> Off : stop
+        On --> Fault : overheat
+    }
+"#;
+
+fn simulator_in_on() -> Simulator {
+    let fsm = &parse_fsm(MOTOR).unwrap()[0];
+    let mut sim = Simulator::default();
+    sim.reset_to_initial(fsm);
+    sim.post_event("start");
+    sim.step(fsm);
+    sim.post_event("stop");
+    sim.post_event("overheat");
+    assert_eq!(sim.current_state.as_deref(), Some("On"));
+    sim
+}
+
+#[test]
+fn test_reconcile_keeps_state_and_events() {
+    let old_fsm = &parse_fsm(MOTOR).unwrap()[0];
+    // Edit removes the overheat transition but keeps every state
+    let new_fsm = &parse_fsm(&MOTOR.replace("On --> Fault : overheat", "state Fault")).unwrap()[0];
+    let mut sim = simulator_in_on();
+    let log_len = sim.log.len();
+
+    sim.reconcile(Some(old_fsm), new_fsm);
+
+    assert_eq!(sim.current_state.as_deref(), Some("On"));
+    assert_eq!(sim.queued_events, vec!["stop".to_string()]);
+    assert_eq!(sim.log.len(), log_len);
+}
+
+#[test]
+fn test_reconcile_resets_when_state_removed() {
+    let old_fsm = &parse_fsm(MOTOR).unwrap()[0];
+    let new_fsm = &parse_fsm(&MOTOR.replace("On", "Running")).unwrap()[0];
+    let mut sim = simulator_in_on();
+
+    sim.reconcile(Some(old_fsm), new_fsm);
+
+    assert_eq!(sim.current_state.as_deref(), Some("Off"));
+    assert!(sim.queued_events.is_empty());
+    assert!(sim.log.iter().any(|l| l == "model changed, state On no longer exists — reset"));
+    assert!(sim.log.iter().any(|l| l == "Off --start--> On"));
+}
+
+#[test]
+fn test_reconcile_resets_when_fsm_changes() {
+    let old_fsm = &parse_fsm(MOTOR).unwrap()[0];
+    let new_fsm = &parse_fsm(&MOTOR.replace("fsm Motor", "fsm Pump")).unwrap()[0];
+    let mut sim = simulator_in_on();
+
+    sim.reconcile(Some(old_fsm), new_fsm);
+
+    assert!(sim.current_state.is_none());
+    assert!(sim.queued_events.is_empty());
+    assert!(!sim.log.is_empty());
+}
+
+#[test]
+fn test_step_takes_else_branch() {
+    let source = r#"
+        fsm Pump {
+            [*] --> Idle
+            Idle --> Fast : go [is_urgent]
+            Idle --> Slow : go [else]
+        }
+    "#;
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let mut sim = Simulator::default();
+    sim.reset_to_initial(fsm);
+    sim.post_event("go");
+    sim.step(fsm);
+
+    assert_eq!(sim.current_state.as_deref(), Some("Slow"));
+    assert_eq!(sim.last_fired.as_ref().and_then(|f| f.transition), fsm.transition_id(1));
+}
+
+#[test]
+fn test_reset_picks_guarded_initial_transition() {
+    let source = r#"
+        fsm Boot {
+            [*] --> Idle
+            [*] --> Recovery : [recovery_pin_set] / log_recovery
+            Idle --> Recovery : fault
+        }
+    "#;
+    let fsm = &parse_fsm(source).unwrap()[0];
+    assert_eq!(guard_expressions(fsm), ["recovery_pin_set"]);
+    let mut sim = Simulator::default();
+    sim.reset_to_initial(fsm);
+    assert_eq!(sim.current_state.as_deref(), Some("Idle"));
+
+    // The guard toggle decides where the next reset starts
+    sim.guard_values.insert("recovery_pin_set".to_string(), true);
+    sim.reset_to_initial(fsm);
+    assert_eq!(sim.current_state.as_deref(), Some("Recovery"));
+    let log = sim.log.join("\n");
+    assert!(log.ends_with("reset → Recovery [recovery_pin_set]\n  actions: log_recovery"), "{log}");
+}
+
+#[test]
+fn test_step_uses_guard_values_and_choices() {
+    let source = r#"
+        fsm Pump {
+            [*] --> Idle
+            state Fast {
+                entry / spin_up
+            }
+            Idle --> <<Speed>> : go
+            choice Speed {
+                [is_urgent] -> Fast
+                [else] -> Slow
+            }
+        }
+    "#;
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let mut sim = Simulator::default();
+    sim.guard_values.insert("is_urgent".to_string(), true);
+    sim.reset_to_initial(fsm);
+    sim.post_event("go");
+    sim.step(fsm);
+
+    assert_eq!(sim.current_state.as_deref(), Some("Fast"));
+    assert!(sim.log.iter().any(|l| l == "Idle --go--> <<Speed>> → Fast"));
+    assert!(sim.log.iter().any(|l| l == "  actions: spin_up"));
+    // The animation follows the drawn edge into the choice node
+    assert_eq!(sim.last_fired.as_ref().map(|f| f.to.as_str()), Some("<<Speed>>"));
+}
+
+#[test]
+fn test_find_transition_external_internal_and_guarded() {
+    let source = r#"
+        fsm Door {
+            [*] --> Closed
+            state Closed {
+                knock / answer
+            }
+            Closed --> Open : open [is_unlocked]
+            Open --> Closed : close
+        }
+    "#;
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let mut sim = Simulator::default();
+
+    let external = sim.find_transition(fsm, "Open", "close").expect("close is handled in Open");
+    assert_eq!((external.to.as_str(), external.transition_index), ("Closed", Some(1)));
+
+    let internal = sim.find_transition(fsm, "Closed", "knock").expect("knock is handled in Closed");
+    assert!(internal.is_internal());
+    assert_eq!(internal.to, "Closed");
+
+    // A false guard drops the event, a true one takes the transition
+    assert!(sim.find_transition(fsm, "Closed", "open").is_none());
+    sim.guard_values.insert("is_unlocked".to_string(), true);
+    assert_eq!(sim.find_transition(fsm, "Closed", "open").map(|f| f.to), Some("Open".to_string()));
+
+    assert!(sim.find_transition(fsm, "Open", "knock").is_none());
+
+    // The preview agrees with the step that follows
+    sim.reset_to_initial(fsm);
+    sim.post_event("open");
+    sim.step(fsm);
+    assert_eq!(sim.current_state.as_deref(), Some("Open"));
+}
+
+#[test]
+fn test_queue_editing() {
+    let mut sim = simulator_in_on();
+    sim.insert_event(0, "overheat");
+    sim.duplicate_queued(1);
+    assert_eq!(sim.queued_events, ["overheat", "stop", "stop", "overheat"]);
+
+    sim.move_queued(3, true);
+    sim.move_queued(0, true);
+    sim.move_queued(3, false);
+    assert_eq!(sim.queued_events, ["overheat", "stop", "overheat", "stop"]);
+
+    sim.remove_queued(1);
+    sim.insert_event(99, "start");
+    sim.insert_event(1, " ");
+    assert_eq!(sim.queued_events, ["overheat", "overheat", "stop", "start"]);
+}
+
+#[test]
+fn test_completion_transitions_follow_entry() {
+    let source = r#"
+        fsm Boot {
+            [*] --> PowerOn
+            state Ready {
+                entry / greet
+            }
+            PowerOn --> SelfTest
+            SelfTest --> Ready : [checks_pass]
+            Ready --> SelfTest : retest
+        }
+    "#;
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let mut sim = Simulator::default();
+    sim.guard_values.insert("checks_pass".to_string(), true);
+    sim.reset_to_initial(fsm);
+    assert_eq!(sim.current_state.as_deref(), Some("Ready"));
+    assert!(sim.log.iter().any(|l| l == "PowerOn --> SelfTest (completion)"));
+    assert!(sim.log.iter().any(|l| l == "SelfTest --[checks_pass]--> Ready (completion)"));
+    assert!(sim.log.iter().any(|l| l == "  actions: greet"));
+
+    sim.guard_values.insert("checks_pass".to_string(), false);
+    sim.post_event("retest");
+    sim.step(fsm);
+    assert_eq!(sim.current_state.as_deref(), Some("SelfTest"));
+    // Every hop is a step of its own, with no event
+    let steps: Vec<(&str, &str)> = sim.history.iter().map(|h| (h.event.as_str(), h.to.as_str())).collect();
+    assert_eq!(steps, [("", "SelfTest"), ("", "Ready"), ("retest", "SelfTest")]);
+}
+
+#[test]
+fn test_history_records_steps_and_exports_csv() {
+    let fsm = &parse_fsm(MOTOR).unwrap()[0];
+    let mut sim = simulator_in_on();
+    sim.step(fsm);
+    // Ignored events are not history
+    sim.post_event("bogus");
+    sim.step(fsm);
+
+    let steps: Vec<(usize, &str, &str, &str)> = sim
+        .history
+        .iter()
+        .map(|e| (e.step, e.event.as_str(), e.from.as_str(), e.to.as_str()))
+        .collect();
+    assert_eq!(steps, vec![(1, "start", "Off", "On"), (2, "stop", "On", "Off")]);
+    assert_eq!(sim.history[1].transition_index, Some(1));
+
+    let csv = sim.history_csv();
+    let rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(rows[0], "step,event,from,to,transition_index,timestamp_s");
+    assert!(rows[2].starts_with("2,stop,On,Off,1,"), "{}", rows[2]);
+
+    sim.reset_to_initial(fsm);
+    assert!(sim.history.is_empty());
+}
+
+#[test]
+fn test_history_cap_and_scrubbing() {
+    let fsm = &parse_fsm(MOTOR).unwrap()[0];
+    let mut sim = Simulator {
+        history_cap: 2,
+        ..Simulator::default()
+    };
+    sim.reset_to_initial(fsm);
+    for event in ["start", "stop", "start"] {
+        sim.post_event(event);
+        sim.step(fsm);
+    }
+    assert_eq!(sim.history.iter().map(|e| e.step).collect::<Vec<_>>(), vec![2, 3]);
+
+    // Scrubbing shows an earlier state without touching the live one
+    sim.scrub = Some(2);
+    assert_eq!(sim.displayed_state(), Some("Off"));
+    assert_eq!(sim.current_state.as_deref(), Some("On"));
+
+    // Dropping the scrubbed step returns to the live state
+    sim.history_cap = 1;
+    sim.trim_history();
+    assert_eq!(sim.scrub, None);
+    assert_eq!(sim.displayed_state(), Some("On"));
+}
+
+#[test]
+fn test_reset_logs_start_actions() {
+    let fsm = &parse_fsm(&MOTOR.replace("[*] --> Off", "[*] --> Off : / calibrate\n state Off { entry / brake }")).unwrap()[0];
+    let mut sim = Simulator::default();
+    sim.reset_to_initial(fsm);
+
+    assert_eq!(sim.log, ["reset → Off", "  actions: calibrate, brake"]);
+}
+
+#[test]
+fn test_final_state_stops_the_machine() {
+    let fsm = &parse_fsm(&MOTOR.replace("On --> Fault : overheat", "On --> Fault : overheat\n * --> Off : reset\n final state Fault")).unwrap()[0];
+    let mut sim = simulator_in_on();
+    sim.log.clear();
+    sim.queued_events.clear();
+    sim.post_event("overheat");
+    sim.step(fsm);
+    assert_eq!(sim.current_state.as_deref(), Some("Fault"));
+    assert_eq!(sim.log.last().map(String::as_str), Some("  machine finished"));
+
+    // Not even the wildcard leaves it
+    sim.post_event("reset");
+    sim.step(fsm);
+    assert_eq!(sim.current_state.as_deref(), Some("Fault"));
+    assert_eq!(sim.log.last().map(String::as_str), Some("Fault: machine finished, event 'reset' ignored"));
+}
+
+/// A clock that only moves when the test advances it
+#[derive(Clone, Debug)]
+struct FakeClock(Rc<Cell<Instant>>);
+
+impl FakeClock {
+    fn new() -> Self {
+        Self(Rc::new(Cell::new(Instant::now())))
+    }
+
+    fn advance(&self, seconds: u64) {
+        self.0.set(self.0.get() + Duration::from_secs(seconds));
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        self.0.get()
+    }
+}
+
+fn timer_sim(clock: &FakeClock) -> Simulator<FakeClock> {
+    let mut sim = Simulator::with_clock(clock.clone());
+    sim.use_fsm_timers = true;
+    sim.running = true;
+    sim
+}
+
+fn pending(sim: &Simulator<FakeClock>) -> Vec<(&str, Duration)> {
+    sim.pending_timers.iter().map(|p| (p.timer.as_str(), p.remaining)).collect()
+}
+
+#[test]
+fn test_sim_logs_suppressed_events() {
+    let source = "fsm Button {\n    [*] --> Up\n    Up --> Down : press @debounce(2s)\n    Down --> Up : release @throttle(5s)\n}\n";
+    let fsm = &parse_fsm(source).unwrap()[0];
+    let clock = FakeClock::new();
+    let mut sim = Simulator::with_clock(clock.clone());
+    sim.reset_to_initial(fsm);
+    for event in ["press", "release"] {
+        sim.post_event(event);
+        sim.step(fsm);
+    }
+    clock.advance(1);
+    sim.post_event("press");
+    sim.step(fsm);
+    assert_eq!(sim.current_state.as_deref(), Some("Up"));
+    assert_eq!(sim.log.last().map(String::as_str), Some("Up: event 'press' suppressed (debounce)"));
+
+    // Quiet for the window since the bounce: it fires, and the suppressed one is not in the history
+    clock.advance(2);
+    sim.post_event("press");
+    sim.step(fsm);
+    assert_eq!(sim.current_state.as_deref(), Some("Down"));
+    assert_eq!(sim.history.len(), 3);
+
+    // Five seconds since the last release that fired, however many came in between
+    sim.post_event("release");
+    sim.step(fsm);
+    assert_eq!(sim.log.last().map(String::as_str), Some("Down: event 'release' suppressed (throttle)"));
+    clock.advance(2);
+    sim.post_event("release");
+    sim.step(fsm);
+    assert_eq!(sim.current_state.as_deref(), Some("Up"));
+}
+
+#[test]
+fn test_fsm_timer_fires_and_is_cancelled_on_exit() {
+    let fsm = &parse_fsm(include_str!("../../examples/connection_manager.fsm")).unwrap()[0];
+    let clock = FakeClock::new();
+    let mut sim = timer_sim(&clock);
+    sim.reset_to_initial(fsm);
+    sim.post_event("Connect");
+    sim.tick(fsm);
+    assert_eq!(sim.current_state.as_deref(), Some("Connecting"));
+    assert_eq!(pending(&sim), vec![("connect_timeout", Duration::from_secs(10))]);
+
+    clock.advance(9);
+    sim.tick(fsm);
+    assert_eq!(sim.current_state.as_deref(), Some("Connecting"));
+    assert_eq!(pending(&sim), vec![("connect_timeout", Duration::from_secs(1))]);
+
+    clock.advance(1);
+    sim.tick(fsm);
+    assert_eq!(sim.current_state.as_deref(), Some("Disconnected"));
+    assert!(sim.log.iter().any(|l| l == "timer connect_timeout → ConnectTimeout"));
+    assert!(sim.pending_timers.is_empty());
+
+    // Leaving the state before it expires cancels the timer
+    sim.post_event("Connect");
+    sim.tick(fsm);
+    sim.post_event("Cancel");
+    sim.tick(fsm);
+    assert_eq!(sim.current_state.as_deref(), Some("Disconnected"));
+    assert!(sim.pending_timers.is_empty());
+}
+
+#[test]
+fn test_start_at_enters_state_with_its_timers() {
+    let fsm = &parse_fsm(include_str!("../../examples/connection_manager.fsm")).unwrap()[0];
+    let clock = FakeClock::new();
+    let mut sim = timer_sim(&clock);
+    sim.reset_to_initial(fsm);
+    sim.post_event("Connect");
+
+    sim.start_at(fsm, "Connecting");
+    assert_eq!(sim.current_state.as_deref(), Some("Connecting"));
+    assert!(sim.queued_events.is_empty() && sim.history.is_empty());
+    assert!(sim.log.ends_with(&["start here → Connecting".to_string(), "  actions: initiate_connection, start_timer".to_string()]));
+    assert_eq!(pending(&sim), vec![("connect_timeout", Duration::from_secs(10))]);
+
+    sim.start_at(fsm, "Nowhere");
+    assert_eq!(sim.current_state.as_deref(), Some("Connecting"));
+}
+
+#[test]
+fn test_periodic_fsm_timer_scaled_by_speed() {
+    let fsm = &parse_fsm(include_str!("../../examples/connection_manager.fsm")).unwrap()[0];
+    let clock = FakeClock::new();
+    let mut sim = timer_sim(&clock);
+    sim.speed = 2.0;
+    sim.reset_to_initial(fsm);
+    for event in ["Connect", "ConnectionEstablished"] {
+        sim.post_event(event);
+        sim.tick(fsm);
+    }
+    assert_eq!(pending(&sim), vec![("keepalive", Duration::from_secs(30))]);
+
+    // 15s at double speed is the full 30s period; the internal transition keeps the timer
+    clock.advance(15);
+    sim.tick(fsm);
+    assert_eq!(sim.current_state.as_deref(), Some("Connected"));
+    assert!(sim.log.iter().any(|l| l == "Connected --KeepaliveTick--> Connected (internal)"));
+    assert_eq!(pending(&sim), vec![("keepalive", Duration::from_secs(30))]);
+
+    // Every expiry within one long frame is posted
+    sim.advance_timers(Duration::from_secs(65));
+    assert_eq!(sim.queued_events, vec!["KeepaliveTick", "KeepaliveTick"]);
+    assert_eq!(pending(&sim), vec![("keepalive", Duration::from_secs(25))]);
+
+    sim.queued_events.clear();
+    sim.post_event("ConnectionLost");
+    sim.tick(fsm);
+    assert_eq!(pending(&sim), vec![("reconnect_delay", Duration::from_secs(5))]);
+    sim.cancel_timer("reconnect_delay");
+    assert!(sim.pending_timers.is_empty());
+    assert_eq!(sim.log.last().map(String::as_str), Some("timer reconnect_delay cancelled"));
+}
+
+const CONTROLLER_AND_PUMP: &str = r#"
+    fsm Controller {
+        [*] --> Idle
+        Idle --> Filling : fill / send(Pump, start)
+        Filling --> Idle : full / send(Pump, stop); send(Valve, close)
+    }
+
+    fsm Pump {
+        [*] --> Off
+        Off --> On : start
+        On --> Off : stop
+    }
+"#;
+
+#[test]
+fn test_bus_send_action_drives_other_machine() {
+    let fsms = parse_fsm(CONTROLLER_AND_PUMP).unwrap();
+    let mut bus = SimBus::default();
+    for fsm in &fsms {
+        bus.add_machine(fsm);
+    }
+    let state = |bus: &SimBus, name: &str| bus.machine(name).unwrap().sim.current_state.clone();
+    assert_eq!(state(&bus, "Pump").as_deref(), Some("Off"));
+
+    // Only the Controller uses `fill`; its send action queues `start` on the Pump
+    bus.post_event("fill", &fsms);
+    assert!(bus.machine("Pump").unwrap().sim.queued_events.is_empty());
+    bus.step(&fsms);
+    assert_eq!(state(&bus, "Controller").as_deref(), Some("Filling"));
+    assert_eq!(bus.machine("Pump").unwrap().sim.queued_events, vec!["start".to_string()]);
+    bus.run_until_idle(&fsms, 10);
+    assert_eq!(state(&bus, "Pump").as_deref(), Some("On"));
+    assert!(bus.log.iter().any(|l| l == "[Controller] Idle --fill--> Filling"));
+    assert!(bus.log.iter().any(|l| l == "[Controller] send 'start' → Pump"));
+    assert!(bus.log.iter().any(|l| l == "[Pump] Off --start--> On"));
+
+    // Sends to a machine that is not on the bus are reported and dropped
+    bus.post_event("full", &fsms);
+    bus.run_until_idle(&fsms, 10);
+    assert_eq!(state(&bus, "Pump").as_deref(), Some("Off"));
+    assert!(bus.log.iter().any(|l| l == "[Controller] send 'close' → Valve: not on the bus, dropped"));
+
+    // A chosen target gets the event even though it does not handle it
+    bus.target = Some("Pump".to_string());
+    bus.post_event("fill", &fsms);
+    bus.run_until_idle(&fsms, 10);
+    assert_eq!(state(&bus, "Controller").as_deref(), Some("Idle"));
+    assert!(bus.log.iter().any(|l| l == "[Pump] Off: no transition for event 'fill'"));
+
+    bus.target = None;
+    bus.post_event("start", &fsms);
+    bus.post_event("nothing", &fsms);
+    assert_eq!(bus.log.last().map(String::as_str), Some("bus: no machine handles 'nothing'"));
+    bus.reset_all(&fsms);
+    assert!(!bus.is_busy());
+    assert_eq!(state(&bus, "Pump").as_deref(), Some("Off"));
+    assert!(bus.log.iter().any(|l| l == "[Pump] reset → Off"));
+}
+
+const DOOR: &str = r#"
+    fsm DoorLock {
+        [*] --> Locked
+        Locked --> Unlocked : key [valid_key]
+        Locked --> Alarm : key [attempts > 3]
+    }
+"#;
+
+#[test]
+fn test_guard_profile_survives_reparse() {
+    let fsm = &parse_fsm(DOOR).unwrap()[0];
+    let guards = guard_expressions(fsm);
+    let mut sim = Simulator::default();
+    sim.guard_values.insert("valid_key".to_string(), true);
+
+    let mut profiles = GuardProfiles::default();
+    profiles.save("DoorLock", GuardProfile::capture(" happy path ", &sim.guard_values, &guards));
+    profiles.save("DoorLock", GuardProfile::capture("attack", &BTreeMap::from([("attempts > 3".to_string(), true)]), &guards));
+    let stored = GuardProfiles::from_json(&profiles.to_json()).unwrap();
+    assert_eq!(stored, profiles);
+    assert_eq!(stored.of("DoorLock").iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), ["happy path", "attack"]);
+    assert!(stored.of("Motor").is_empty());
+
+    // Reformatted guards still match; a renamed one is flagged, a new one is false
+    let edited = DOOR.replace("[attempts > 3]", "[attempts>3]").replace("valid_key", "key_ok");
+    let fsm = &parse_fsm(&edited).unwrap()[0];
+    let reconciled = stored.get("DoorLock", "attack").unwrap().reconcile(&guard_expressions(fsm));
+    assert_eq!(reconciled.values, BTreeMap::from([("attempts>3".to_string(), true), ("key_ok".to_string(), false)]));
+    assert_eq!(reconciled.unmatched, ["valid_key"]);
+    assert_eq!(reconciled.missing, ["key_ok"]);
+
+    // Saving again under a name replaces that profile
+    profiles.save("DoorLock", GuardProfile::capture("attack", &BTreeMap::new(), &guards));
+    assert_eq!(profiles.of("DoorLock").len(), 2);
+    assert!(profiles.get("DoorLock", "attack").unwrap().guards.values().all(|v| !v));
+    assert!(profiles.remove("DoorLock", "attack"));
+    assert!(!profiles.remove("DoorLock", "attack"));
+}
+
+#[test]
+fn test_guard_profiles_export_and_import() {
+    let mut profiles = GuardProfiles::default();
+    let values = BTreeMap::from([("valid_key".to_string(), true)]);
+    profiles.save("DoorLock", GuardProfile::capture("happy path", &values, &["valid_key".to_string()]));
+    let json = profiles.export("DoorLock");
+
+    // Imported into whatever FSM is shown, replacing profiles of the same name
+    let mut teammate = GuardProfiles::default();
+    teammate.save("Door", GuardProfile::capture("happy path", &BTreeMap::new(), &["valid_key".to_string()]));
+    assert_eq!(teammate.import("Door", &json).unwrap(), ["happy path"]);
+    assert_eq!(teammate.of("Door"), profiles.of("DoorLock"));
+
+    assert!(matches!(teammate.import("Door", "{\"fsm\": \"Door\", \"profiles\": []}"), Err(ImportError::Empty)));
+    assert!(matches!(teammate.import("Door", "not json"), Err(ImportError::Json(_))));
+}
@@ -0,0 +1,108 @@
+//! Split Diagram
+//! View > Split Diagram divides the canvas into two viewports side by side, each
+//! showing an FSM of its own with its own zoom and pan. The left one is the main
+//! canvas, which editing, selection and the code panels follow; this holds the
+//! right one. Its layout goes through the same layout cache as the main canvas and
+//! is only computed again when its FSM's structure or the layout settings change.
+
+use std::collections::HashMap;
+
+use eframe::egui::{Pos2, Rect};
+
+use crate::diagram::render_cache::RenderCache;
+use crate::diagram::{CanvasView, LayoutedDiagram};
+use crate::fsm::FsmDefinition;
+
+#[cfg(test)]
+mod tests;
+
+/// Pixels between the two viewports
+pub const SPLIT_GAP: f32 = 6.0;
+
+/// One of the two viewports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Pane {
+    /// The main canvas (the only one when the diagram is not split)
+    #[default]
+    Left,
+    Right,
+}
+
+/// The right viewport and which viewport the simulator is attached to
+#[derive(Default)]
+pub struct SplitView {
+    pub enabled: bool,
+    /// FSM the right viewport shows, by name so that it survives parses
+    pub fsm: Option<String>,
+    pub view: CanvasView,
+    pub layout: Option<LayoutedDiagram>,
+    pub state_positions: HashMap<String, Pos2>,
+    /// `layout_fingerprint` of the FSM the layout was computed for
+    pub fingerprint: Option<u64>,
+    /// Lay out again even if the fingerprint is the same (the layout settings changed)
+    pub layout_dirty: bool,
+    /// State boxes and text of the right viewport's FSM
+    pub render_cache: RenderCache,
+    /// Zoom to fit on the next frame
+    pub fit_requested: bool,
+    /// Viewport whose FSM the (single-machine) simulator runs
+    pub sim_pane: Pane,
+}
+
+impl SplitView {
+    /// Index in `fsms` of the FSM on the right: the one picked if it still exists,
+    /// else the one after `selected` (the FSM on the left)
+    pub fn fsm_index(&self, fsms: &[FsmDefinition], selected: usize) -> Option<usize> {
+        if fsms.is_empty() {
+            return None;
+        }
+        self.fsm
+            .as_ref()
+            .and_then(|name| fsms.iter().position(|f| f.name == *name))
+            .or(Some((selected + 1) % fsms.len()))
+    }
+
+    /// Show the FSM called `name` on the right, fitted
+    pub fn show(&mut self, name: &str) {
+        if self.fsm.as_deref() != Some(name) {
+            self.fsm = Some(name.to_string());
+            self.fit_requested = true;
+        }
+    }
+
+    /// Whether the right layout is missing or out of date for `fsm`, whose
+    /// `layout_fingerprint` is `fingerprint`
+    pub fn needs_layout(&self, fsm: &FsmDefinition, fingerprint: u64) -> bool {
+        self.layout_dirty
+            || self.fingerprint != Some(fingerprint)
+            || !self.layout.as_ref().is_some_and(|layout| layout.fsm_name == fsm.name)
+    }
+
+    /// A layout was computed for the FSM with `fingerprint`
+    pub fn set_layout(&mut self, layout: Option<LayoutedDiagram>, state_positions: HashMap<String, Pos2>, fingerprint: u64) {
+        let first = self.layout.as_ref().map(|l| &l.fsm_name) != layout.as_ref().map(|l| &l.fsm_name);
+        self.fit_requested |= first;
+        self.layout = layout;
+        self.state_positions = state_positions;
+        self.fingerprint = Some(fingerprint);
+        self.layout_dirty = false;
+    }
+
+    /// Viewport the simulator is attached to
+    pub fn sim_pane(&self) -> Pane {
+        match self.enabled {
+            true => self.sim_pane,
+            false => Pane::Left,
+        }
+    }
+}
+
+/// The left and right viewports of `canvas`, `SPLIT_GAP` apart
+pub fn split_rect(canvas: Rect) -> (Rect, Rect) {
+    let middle = canvas.center().x;
+    let mut left = canvas;
+    let mut right = canvas;
+    left.max.x = (middle - SPLIT_GAP / 2.0).max(canvas.min.x);
+    right.min.x = (middle + SPLIT_GAP / 2.0).min(canvas.max.x);
+    (left, right)
+}
@@ -0,0 +1,83 @@
+//! Unit tests for the split diagram
+
+use std::collections::HashMap;
+
+use eframe::egui;
+
+use crate::diagram::LayoutedDiagram;
+use crate::parser::parse_fsm;
+use crate::split_view::{split_rect, Pane, SplitView, SPLIT_GAP};
+
+fn layout_of(fsm_name: &str) -> LayoutedDiagram {
+    LayoutedDiagram {
+        edges: Vec::new(),
+        labels: Vec::new(),
+        bounds: egui::Rect::ZERO,
+        fsm_name: fsm_name.to_string(),
+        states: Vec::new(),
+    }
+}
+
+#[test]
+fn test_split_rect() {
+    let canvas = egui::Rect::from_min_size(egui::pos2(100.0, 50.0), egui::vec2(800.0, 600.0));
+    let (left, right) = split_rect(canvas);
+    assert_eq!(left.min, canvas.min);
+    assert_eq!(right.max, canvas.max);
+    assert_eq!(left.width(), right.width());
+    assert_eq!(right.min.x - left.max.x, SPLIT_GAP);
+    assert_eq!((left.height(), right.height()), (600.0, 600.0));
+
+    // Too narrow for the gap: nothing is inverted
+    let sliver = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(2.0, 10.0));
+    let (left, right) = split_rect(sliver);
+    assert!(left.width() >= 0.0 && right.width() >= 0.0);
+}
+
+#[test]
+fn test_right_fsm_follows_its_name() {
+    let fsms = parse_fsm("fsm A {\n [*] --> X\n}\nfsm B {\n [*] --> Y\n}\nfsm C {\n [*] --> Z\n}\n").expect("Should parse");
+    let mut split = SplitView::default();
+    // Nothing picked: the FSM after the one on the left
+    assert_eq!(split.fsm_index(&fsms, 0), Some(1));
+    assert_eq!(split.fsm_index(&fsms, 2), Some(0));
+    assert_eq!(split.fsm_index(&[], 0), None);
+
+    split.show("C");
+    assert!(split.fit_requested);
+    assert_eq!(split.fsm_index(&fsms, 0), Some(2));
+    // Still found after the FSMs move around, and the neighbour once it is gone
+    assert_eq!(split.fsm_index(&fsms[1..], 0), Some(1));
+    assert_eq!(split.fsm_index(&fsms[..2], 0), Some(1));
+}
+
+#[test]
+fn test_right_layout_only_when_out_of_date() {
+    let fsms = parse_fsm("fsm A {\n [*] --> X\n}\nfsm B {\n [*] --> Y\n}\n").expect("Should parse");
+    let mut split = SplitView::default();
+    assert!(split.needs_layout(&fsms[0], 7));
+
+    split.set_layout(Some(layout_of("A")), HashMap::new(), 7);
+    assert!(split.fit_requested, "a new FSM is fitted");
+    split.fit_requested = false;
+    assert!(!split.needs_layout(&fsms[0], 7));
+    assert!(split.needs_layout(&fsms[0], 8), "structure changed");
+    assert!(split.needs_layout(&fsms[1], 7), "another FSM");
+    split.layout_dirty = true;
+    assert!(split.needs_layout(&fsms[0], 7), "layout settings changed");
+
+    // Laying out the same FSM again keeps the zoom and pan
+    split.set_layout(Some(layout_of("A")), HashMap::new(), 8);
+    assert!(!split.fit_requested && !split.layout_dirty);
+}
+
+#[test]
+fn test_sim_pane_needs_the_split() {
+    let mut split = SplitView {
+        sim_pane: Pane::Right,
+        ..Default::default()
+    };
+    assert_eq!(split.sim_pane(), Pane::Left);
+    split.enabled = true;
+    assert_eq!(split.sim_pane(), Pane::Right);
+}
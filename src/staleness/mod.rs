@@ -0,0 +1,61 @@
+//! Diagram Staleness
+//! Whether the diagram still shows what the editor holds: parsing is manual (or
+//! waits for typing to pause) and layout is lazy, so the canvas can show an older
+//! version of the machine. Each editor buffer's text is hashed when it parses and
+//! compared with the live buffer, ignoring whitespace that only moves around (the
+//! editor's FSM tabs are trimmed and re-joined with the gaps between them).
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+#[cfg(test)]
+mod tests;
+
+/// `source` as compared for staleness: each line trimmed, blank lines dropped
+pub fn normalize(source: &str) -> String {
+    normalized_lines(source).collect::<Vec<_>>().join("\n")
+}
+
+/// Hash of [`normalize`]`(source)`
+pub fn content_hash(source: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for line in normalized_lines(source) {
+        line.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+fn normalized_lines(source: &str) -> impl Iterator<Item = &str> {
+    source.lines().map(str::trim).filter(|line| !line.is_empty())
+}
+
+/// Hash of the text each editor buffer last parsed from: the single source, or
+/// each member of an open project by index
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Staleness {
+    parsed: Vec<Option<u64>>,
+}
+
+impl Staleness {
+    /// Forget every parse (another file or project was opened)
+    pub fn clear(&mut self) {
+        self.parsed.clear();
+    }
+
+    /// Buffer `buffer` parsed from `source`, so the diagram shows it now
+    pub fn record(&mut self, buffer: usize, source: &str) {
+        if self.parsed.len() <= buffer {
+            self.parsed.resize(buffer + 1, None);
+        }
+        self.parsed[buffer] = Some(content_hash(source));
+    }
+
+    /// Whether some of the live `buffers` differ from what they last parsed from (or
+    /// never parsed)
+    pub fn is_stale<'s>(&self, buffers: impl IntoIterator<Item = &'s str>) -> bool {
+        buffers
+            .into_iter()
+            .enumerate()
+            .any(|(i, source)| self.parsed.get(i).copied().flatten() != Some(content_hash(source)))
+    }
+}
@@ -0,0 +1,57 @@
+//! Unit tests for diagram staleness
+
+use crate::parser::blocks::SourceBlocks;
+use crate::staleness::{content_hash, normalize, Staleness};
+
+#[test]
+fn test_normalize() {
+    assert_eq!(normalize("fsm A {\n    [*] --> Idle   \n\n\t}\n"), "fsm A {\n[*] --> Idle\n}");
+    assert_eq!(normalize("\r\n  \n"), "");
+    assert_eq!(content_hash("fsm A {\r\n  [*] --> Idle\r\n}"), content_hash("fsm A {\n[*] --> Idle\n}\n\n"));
+
+    // Whitespace inside a line, and anything else, still counts
+    assert_ne!(normalize("Idle --> Busy : go"), normalize("Idle  -->  Busy : go"));
+    assert_ne!(content_hash("[*] --> Idle"), content_hash("[*] --> Busy"));
+    assert_ne!(content_hash("a\nb"), content_hash("ab"));
+}
+
+#[test]
+fn test_rejoined_tabs_are_not_an_edit() {
+    let source = "// Lights\n\nfsm A {\n    [*] --> Red\n}\n\n\nfsm B {\n    [*] --> On\n}\n";
+    let mut blocks = SourceBlocks::split(source).expect("Should split");
+    blocks.blocks[1].push_str("\n\n   ");
+    blocks.gaps[1] = "\n".to_string();
+    assert_ne!(blocks.join(), source);
+    assert_eq!(normalize(&blocks.join()), normalize(source));
+}
+
+#[test]
+fn test_staleness_follows_edits_and_parses() {
+    let mut staleness = Staleness::default();
+    let mut source = "fsm A {\n    [*] --> Idle\n}\n".to_string();
+    // Nothing parsed yet
+    assert!(staleness.is_stale([source.as_str()]));
+
+    staleness.record(0, &source);
+    assert!(!staleness.is_stale([source.as_str()]));
+
+    // Typing makes it stale, whitespace alone does not
+    source.push_str("\n\n");
+    assert!(!staleness.is_stale([source.as_str()]));
+    source = source.replace("Idle", "Busy");
+    assert!(staleness.is_stale([source.as_str()]));
+
+    // Undoing the edit is up to date again; parsing the edit is too
+    assert!(!staleness.is_stale(["fsm A {\n    [*] --> Idle\n}"]));
+    staleness.record(0, &source);
+    assert!(!staleness.is_stale([source.as_str()]));
+
+    // A project: each member on its own
+    staleness.clear();
+    let members = ["fsm A {\n}\n", "fsm B {\n}\n"];
+    staleness.record(0, members[0]);
+    assert!(staleness.is_stale(members), "member 1 never parsed");
+    staleness.record(1, members[1]);
+    assert!(!staleness.is_stale(members));
+    assert!(staleness.is_stale([members[0], "fsm B {\n    [*] --> On\n}\n"]));
+}
@@ -0,0 +1,190 @@
+//! Starter Templates
+//! The template gallery's curated FSMs, grouped by category, and the small SVG
+//! thumbnails it shows for them (drawn from the pure-Rust fallback layout, so the
+//! gallery works without Node.js)
+
+use eframe::egui;
+
+use crate::diagram::fallback::fallback_positions;
+use crate::diagram::{estimate_state_size, StateBoxContent, DEFAULT_DIAGRAM_FONT_SIZE};
+use crate::fsm::FsmDefinition;
+
+#[cfg(test)]
+mod tests;
+
+pub struct Template {
+    pub name: &'static str,
+    pub category: &'static str,
+    /// One line for the gallery card
+    pub description: &'static str,
+    /// DSL source with a single `fsm` block
+    pub source: &'static str,
+}
+
+/// Every template, in gallery order (grouped by category)
+pub const TEMPLATES: &[Template] = &[
+    Template {
+        name: "TCP Handshake",
+        category: "Protocols",
+        description: "Client side of a three-way handshake with a SYN timeout",
+        source: include_str!("../../examples/templates/tcp_handshake.fsm"),
+    },
+    Template {
+        name: "MQTT Session",
+        category: "Protocols",
+        description: "Broker connection with periodic keep-alive pings",
+        source: include_str!("../../examples/templates/mqtt_session.fsm"),
+    },
+    Template {
+        name: "Retry with Backoff",
+        category: "Reliability",
+        description: "Bounded retries, doubling the delay between attempts",
+        source: include_str!("../../examples/templates/retry_backoff.fsm"),
+    },
+    Template {
+        name: "Circuit Breaker",
+        category: "Reliability",
+        description: "Fail fast after repeated errors, probe again after a cool-down",
+        source: include_str!("../../examples/templates/circuit_breaker.fsm"),
+    },
+    Template {
+        name: "Pump Control",
+        category: "Control",
+        description: "Priming, pressure interlocks and an emergency stop from any state",
+        source: include_str!("../../examples/templates/pump_control.fsm"),
+    },
+    Template {
+        name: "Elevator Door",
+        category: "Control",
+        description: "Door dwell timer with obstruction detection",
+        source: include_str!("../../examples/templates/elevator_door.fsm"),
+    },
+    Template {
+        name: "Setup Wizard",
+        category: "User Interface",
+        description: "Multi-step form with validation, back and cancel",
+        source: include_str!("../../examples/templates/ui_wizard.fsm"),
+    },
+    Template {
+        name: "Login Session",
+        category: "User Interface",
+        description: "Login with lockout after failed attempts and idle expiry",
+        source: include_str!("../../examples/templates/login_session.fsm"),
+    },
+    Template {
+        name: "Battery Charger",
+        category: "Embedded",
+        description: "Li-ion precharge, constant current and constant voltage phases",
+        source: include_str!("../../examples/templates/battery_charger.fsm"),
+    },
+    Template {
+        name: "Debounced Button",
+        category: "Embedded",
+        description: "Contact debouncing with press, long-press and release events",
+        source: include_str!("../../examples/templates/debounced_button.fsm"),
+    },
+    Template {
+        name: "Firmware Update",
+        category: "Embedded",
+        description: "Download, verify and apply an image, discarding bad ones",
+        source: include_str!("../../examples/templates/firmware_update.fsm"),
+    },
+    Template {
+        name: "Order Workflow",
+        category: "Business",
+        description: "Checkout, payment, shipping and cancellation of an order",
+        source: include_str!("../../examples/templates/order_workflow.fsm"),
+    },
+];
+
+/// Distinct categories in gallery order
+pub fn categories() -> Vec<&'static str> {
+    let mut categories: Vec<&'static str> = Vec::new();
+    for template in TEMPLATES {
+        if !categories.contains(&template.category) {
+            categories.push(template.category);
+        }
+    }
+    categories
+}
+
+// ============================================================================
+// THUMBNAILS
+// ============================================================================
+
+const THUMBNAIL_MARGIN: f32 = 40.0;
+
+/// A small SVG of `fsm`: state boxes at their fallback layout positions, straight
+/// lines for transitions and a dot for the initial state. The view box is in diagram
+/// units, so the image scales to whatever size the gallery shows it at.
+pub fn thumbnail_svg(fsm: &FsmDefinition) -> String {
+    let state_box = StateBoxContent::default();
+    let positions = fallback_positions(fsm, DEFAULT_DIAGRAM_FONT_SIZE, &state_box);
+
+    // Boxes first: edges end at their centers, and the view box must fit them all
+    let boxes: Vec<(&str, egui::Rect)> = fsm
+        .states
+        .iter()
+        .filter_map(|state| {
+            let size = estimate_state_size(state, DEFAULT_DIAGRAM_FONT_SIZE, &state_box);
+            positions.get(&state.name).map(|&center| (state.name.as_str(), egui::Rect::from_center_size(center, size)))
+        })
+        .collect();
+    let bounds = boxes
+        .iter()
+        .map(|(_, rect)| *rect)
+        .reduce(|a, b| a.union(b))
+        .unwrap_or(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(100.0, 100.0)))
+        .expand(THUMBNAIL_MARGIN);
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{:.0} {:.0} {:.0} {:.0}\">\n",
+        bounds.min.x,
+        bounds.min.y,
+        bounds.width(),
+        bounds.height()
+    );
+    svg.push_str(&format!(
+        "<rect x=\"{:.0}\" y=\"{:.0}\" width=\"{:.0}\" height=\"{:.0}\" fill=\"#FAFAFA\"/>\n",
+        bounds.min.x,
+        bounds.min.y,
+        bounds.width(),
+        bounds.height()
+    ));
+
+    for transition in &fsm.transitions {
+        let (Some(from), Some(to)) = (positions.get(&transition.source), positions.get(&transition.target)) else {
+            continue;
+        };
+        if from == to {
+            continue;
+        }
+        let dash = if transition.is_dashed() { " stroke-dasharray=\"16 10\"" } else { "" };
+        svg.push_str(&format!(
+            "<line x1=\"{:.0}\" y1=\"{:.0}\" x2=\"{:.0}\" y2=\"{:.0}\" stroke=\"#8090A0\" stroke-width=\"6\"{dash}/>\n",
+            from.x, from.y, to.x, to.y
+        ));
+    }
+
+    if let Some(initial) = fsm.initial_state().and_then(|name| boxes.iter().find(|(n, _)| *n == name)) {
+        let rect = initial.1;
+        let dot = egui::pos2(rect.center().x, rect.min.y - THUMBNAIL_MARGIN / 2.0);
+        svg.push_str(&format!(
+            "<line x1=\"{:.0}\" y1=\"{:.0}\" x2=\"{:.0}\" y2=\"{:.0}\" stroke=\"#303030\" stroke-width=\"6\"/>\n",
+            dot.x, dot.y, dot.x, rect.min.y
+        ));
+        svg.push_str(&format!("<circle cx=\"{:.0}\" cy=\"{:.0}\" r=\"12\" fill=\"#303030\"/>\n", dot.x, dot.y));
+    }
+
+    for (_, rect) in &boxes {
+        svg.push_str(&format!(
+            "<rect x=\"{:.0}\" y=\"{:.0}\" width=\"{:.0}\" height=\"{:.0}\" rx=\"16\" fill=\"#DDE8F5\" stroke=\"#3060A0\" stroke-width=\"6\"/>\n",
+            rect.min.x,
+            rect.min.y,
+            rect.width(),
+            rect.height()
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
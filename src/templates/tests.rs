@@ -0,0 +1,41 @@
+//! Unit tests for the starter templates
+
+use crate::parser::parse_fsm;
+use crate::templates::{categories, thumbnail_svg, TEMPLATES};
+
+#[test]
+fn test_templates_parse_and_validate() {
+    assert!(TEMPLATES.len() >= 10);
+    for template in TEMPLATES {
+        let fsms = parse_fsm(template.source).unwrap_or_else(|e| panic!("{} does not parse: {}", template.name, e));
+        assert_eq!(fsms.len(), 1, "{}", template.name);
+        let fsm = &fsms[0];
+        assert_eq!(fsm.validate(), Ok(()), "{}", template.name);
+        assert_eq!(fsm.warnings(), Vec::<String>::new(), "{}", template.name);
+        assert!(!template.description.is_empty());
+    }
+
+    // Names are unique (the gallery keys thumbnails by them), categories grouped
+    let mut names: Vec<&str> = TEMPLATES.iter().map(|t| t.name).collect();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), TEMPLATES.len());
+    let order: Vec<&str> = TEMPLATES.iter().map(|t| t.category).collect();
+    let mut grouped = order.clone();
+    grouped.dedup();
+    assert_eq!(grouped, categories());
+}
+
+#[test]
+fn test_thumbnail_has_a_box_per_state() {
+    let template = TEMPLATES.iter().find(|t| t.name == "Pump Control").unwrap();
+    let fsm = &parse_fsm(template.source).unwrap()[0];
+    let svg = thumbnail_svg(fsm);
+
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox="));
+    assert!(svg.trim_end().ends_with("</svg>"));
+    assert_eq!(svg.matches("rx=\"16\"").count(), fsm.states.len());
+    assert_eq!(svg.matches("<circle").count(), 1);
+    // Deterministic, so the gallery can cache it
+    assert_eq!(thumbnail_svg(fsm), svg);
+}
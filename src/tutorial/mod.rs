@@ -0,0 +1,234 @@
+//! Tutorial
+//! Help > Tutorial walks a new user through a first FSM. It is a small state
+//! machine of its own: a list of [`TutorialStep`]s, each pointing at a region of the
+//! UI with an instruction and a predicate over the app telling when the user has
+//! done what it asks. The app checks the current step every frame and moves on when
+//! it holds; the tutorial can be left at any step. Whether it was finished or turned
+//! down is stored with the app's settings, so it is only offered until then.
+
+use eframe::egui::{Pos2, Rect, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::codegen::CodegenTarget;
+use crate::fsm::FsmDefinition;
+use crate::parser::parse_fsm;
+
+#[cfg(test)]
+mod tests;
+
+/// eframe storage key of the serialized [`TutorialRecord`]
+pub const TUTORIAL_KEY: &str = "oxidate_tutorial";
+
+/// Editor content the tutorial starts from
+pub const TUTORIAL_SOURCE: &str = "// Your first state machine\nfsm Blinker {\n    [*] --> Idle\n}\n";
+
+/// Pixels between a highlighted region and the instruction card
+pub const CARD_GAP: f32 = 12.0;
+
+/// Region of the UI a step points at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Spotlight {
+    Editor,
+    ParseButton,
+    Diagram,
+    TargetSelector,
+    /// The "Debug sim" checkbox
+    SimToggle,
+    /// The simulator's event row (Event, Post)
+    SimEvents,
+}
+
+/// What the steps can see of the app
+pub trait TutorialApp {
+    /// Text of the DSL editor, parsed or not
+    fn editor_source(&self) -> &str;
+    /// FSMs of the last successful parse
+    fn parsed_fsms(&self) -> &[FsmDefinition];
+    fn codegen_target(&self) -> CodegenTarget;
+    fn sim_enabled(&self) -> bool;
+    /// State the (single-machine) simulator is in
+    fn sim_state(&self) -> Option<&str>;
+}
+
+/// One thing to do, and how to tell it was done
+pub struct TutorialStep<A> {
+    pub spotlight: Spotlight,
+    pub title: &'static str,
+    pub instruction: &'static str,
+    pub done: fn(&A) -> bool,
+}
+
+/// Whether the tutorial was finished or turned down (persisted)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TutorialRecord {
+    pub completed: bool,
+    /// Turned down when offered, or left before the end
+    pub dismissed: bool,
+}
+
+impl TutorialRecord {
+    pub fn from_json(json: &str) -> Option<Self> {
+        serde_json::from_str(json).ok()
+    }
+
+    pub fn to_json(self) -> String {
+        serde_json::to_string(&self).unwrap_or_default()
+    }
+
+    /// Whether to offer the tutorial at startup
+    pub fn should_offer(&self) -> bool {
+        !self.completed && !self.dismissed
+    }
+}
+
+/// Where the tutorial is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TutorialStage {
+    #[default]
+    Off,
+    /// Asking whether to take it
+    Offered,
+    /// Index of the current step
+    Step(usize),
+    /// Every step done; the last card is showing
+    Finished,
+}
+
+pub struct Tutorial<A> {
+    pub steps: Vec<TutorialStep<A>>,
+    pub stage: TutorialStage,
+    pub record: TutorialRecord,
+}
+
+impl<A> Tutorial<A> {
+    pub fn new(steps: Vec<TutorialStep<A>>) -> Self {
+        Self { steps, stage: TutorialStage::Off, record: TutorialRecord::default() }
+    }
+
+    /// Ask whether to take the tutorial, unless it was finished or turned down
+    pub fn offer(&mut self) {
+        if self.stage == TutorialStage::Off && self.record.should_offer() {
+            self.stage = TutorialStage::Offered;
+        }
+    }
+
+    /// Begin at the first step (again)
+    pub fn start(&mut self) {
+        self.stage = match self.steps.is_empty() {
+            true => TutorialStage::Finished,
+            false => TutorialStage::Step(0),
+        };
+    }
+
+    /// Leave the tutorial; leaving before the end counts as turning it down
+    pub fn exit(&mut self) {
+        if self.stage != TutorialStage::Finished {
+            self.record.dismissed = true;
+        }
+        self.stage = TutorialStage::Off;
+    }
+
+    /// The current step and its index
+    pub fn current(&self) -> Option<(usize, &TutorialStep<A>)> {
+        match self.stage {
+            TutorialStage::Step(index) => self.steps.get(index).map(|step| (index, step)),
+            _ => None,
+        }
+    }
+
+    /// Whether the current step's predicate holds for `app`
+    pub fn step_done(&self, app: &A) -> bool {
+        self.current().is_some_and(|(_, step)| (step.done)(app))
+    }
+
+    /// Go to the next step; after the last one the tutorial is finished
+    pub fn next(&mut self) {
+        if let TutorialStage::Step(index) = self.stage {
+            if index + 1 < self.steps.len() {
+                self.stage = TutorialStage::Step(index + 1);
+            } else {
+                self.stage = TutorialStage::Finished;
+                self.record.completed = true;
+            }
+        }
+    }
+}
+
+/// Whether an FSM in `fsms` goes from `from` to `to` on `event`
+pub fn has_transition(fsms: &[FsmDefinition], from: &str, event: &str, to: &str) -> bool {
+    fsms.iter().flat_map(|fsm| &fsm.transitions).any(|t| {
+        t.source == from && t.target == to && t.event.as_ref().is_some_and(|e| e.name == event)
+    })
+}
+
+/// Building, drawing, generating and running the `Blinker` of [`TUTORIAL_SOURCE`]
+pub fn first_fsm_steps<A: TutorialApp>() -> Vec<TutorialStep<A>> {
+    vec![
+        TutorialStep {
+            spotlight: Spotlight::Editor,
+            title: "Write a transition",
+            instruction: "This is the DSL editor. Inside the fsm block, add the line\n\n    Idle --> Active : start",
+            done: |app| parse_fsm(app.editor_source()).is_ok_and(|fsms| has_transition(&fsms, "Idle", "start", "Active")),
+        },
+        TutorialStep {
+            spotlight: Spotlight::ParseButton,
+            title: "Parse it",
+            instruction: "Press Parse & Visualize (Ctrl+R) to draw the machine. With auto-parse on, \
+                          pausing your typing does it too.",
+            done: |app| has_transition(app.parsed_fsms(), "Idle", "start", "Active"),
+        },
+        TutorialStep {
+            spotlight: Spotlight::Diagram,
+            title: "The diagram",
+            instruction: "The diagram follows the DSL. Give the machine a way back by adding\n\n    Active --> Idle : stop\n\n\
+                          and watch the new arrow appear.",
+            done: |app| has_transition(app.parsed_fsms(), "Active", "stop", "Idle"),
+        },
+        TutorialStep {
+            spotlight: Spotlight::TargetSelector,
+            title: "Generate code",
+            instruction: "The panel on the right shows the code generated for the machine. \
+                          Pick Standard (std) as the target.",
+            done: |app| app.codegen_target() == CodegenTarget::Standard,
+        },
+        TutorialStep {
+            spotlight: Spotlight::SimToggle,
+            title: "Simulate",
+            instruction: "Tick Debug sim to run the machine right here.",
+            done: |app| app.sim_enabled(),
+        },
+        TutorialStep {
+            spotlight: Spotlight::SimEvents,
+            title: "Post an event",
+            instruction: "Type start as the event, press Post, then Step (or Run). \
+                          The machine moves to Active.",
+            done: |app| app.sim_enabled() && app.sim_state() == Some("Active"),
+        },
+    ]
+}
+
+/// The four rectangles of `screen` around `spotlight`, to dim everything but it
+pub fn dim_rects(screen: Rect, spotlight: Rect) -> [Rect; 4] {
+    let hole = spotlight.intersect(screen);
+    [
+        Rect::from_min_max(screen.min, Pos2::new(screen.max.x, hole.min.y)),
+        Rect::from_min_max(Pos2::new(screen.min.x, hole.max.y), screen.max),
+        Rect::from_min_max(Pos2::new(screen.min.x, hole.min.y), Pos2::new(hole.min.x, hole.max.y)),
+        Rect::from_min_max(Pos2::new(hole.max.x, hole.min.y), Pos2::new(screen.max.x, hole.max.y)),
+    ]
+}
+
+/// Top left corner of a `card` sized card beside `spotlight`: right of it if it
+/// fits, else left of it, else below it; always inside `screen`
+pub fn card_position(screen: Rect, spotlight: Rect, card: Vec2) -> Pos2 {
+    let pos = if spotlight.max.x + CARD_GAP + card.x <= screen.max.x {
+        Pos2::new(spotlight.max.x + CARD_GAP, spotlight.min.y)
+    } else if spotlight.min.x - CARD_GAP - card.x >= screen.min.x {
+        Pos2::new(spotlight.min.x - CARD_GAP - card.x, spotlight.min.y)
+    } else {
+        Pos2::new(spotlight.min.x, spotlight.max.y + CARD_GAP)
+    };
+    let max = (screen.max - card).max(screen.min);
+    pos.clamp(screen.min, max)
+}
@@ -0,0 +1,193 @@
+//! Unit tests for the tutorial
+
+use eframe::egui;
+
+use crate::codegen::CodegenTarget;
+use crate::fsm::FsmDefinition;
+use crate::parser::parse_fsm;
+use crate::tutorial::{
+    card_position, dim_rects, first_fsm_steps, Spotlight, Tutorial, TutorialApp, TutorialRecord, TutorialStage,
+    TutorialStep, CARD_GAP, TUTORIAL_SOURCE,
+};
+
+/// What the steps look at, set by hand
+struct MockApp {
+    source: String,
+    fsms: Vec<FsmDefinition>,
+    target: CodegenTarget,
+    sim_enabled: bool,
+    sim_state: Option<String>,
+}
+
+impl MockApp {
+    fn new() -> Self {
+        Self {
+            source: TUTORIAL_SOURCE.to_string(),
+            fsms: parse_fsm(TUTORIAL_SOURCE).unwrap(),
+            target: CodegenTarget::Embassy,
+            sim_enabled: false,
+            sim_state: None,
+        }
+    }
+
+    fn type_line(&mut self, line: &str) {
+        self.source = self.source.replacen("}\n", &format!("    {line}\n}}\n"), 1);
+    }
+
+    fn parse(&mut self) {
+        self.fsms = parse_fsm(&self.source).unwrap();
+    }
+}
+
+impl TutorialApp for MockApp {
+    fn editor_source(&self) -> &str {
+        &self.source
+    }
+
+    fn parsed_fsms(&self) -> &[FsmDefinition] {
+        &self.fsms
+    }
+
+    fn codegen_target(&self) -> CodegenTarget {
+        self.target
+    }
+
+    fn sim_enabled(&self) -> bool {
+        self.sim_enabled
+    }
+
+    fn sim_state(&self) -> Option<&str> {
+        self.sim_state.as_deref()
+    }
+}
+
+/// What the app does every frame: at most one step forward
+fn frame(tutorial: &mut Tutorial<MockApp>, app: &MockApp) {
+    if tutorial.step_done(app) {
+        tutorial.next();
+    }
+}
+
+fn spotlight(tutorial: &Tutorial<MockApp>) -> Option<Spotlight> {
+    tutorial.current().map(|(_, step)| step.spotlight)
+}
+
+#[test]
+fn test_first_fsm_steps_follow_the_user() {
+    let mut app = MockApp::new();
+    let mut tutorial = Tutorial::new(first_fsm_steps());
+    tutorial.start();
+    assert_eq!(spotlight(&tutorial), Some(Spotlight::Editor));
+
+    // Nothing done, nothing moves
+    frame(&mut tutorial, &app);
+    assert_eq!(tutorial.stage, TutorialStage::Step(0));
+    // Half a line does not parse yet
+    app.type_line("Idle --> Active :");
+    frame(&mut tutorial, &app);
+    assert_eq!(tutorial.stage, TutorialStage::Step(0));
+
+    app.source = TUTORIAL_SOURCE.to_string();
+    app.type_line("Idle --> Active : start");
+    frame(&mut tutorial, &app);
+    assert_eq!(spotlight(&tutorial), Some(Spotlight::ParseButton));
+    frame(&mut tutorial, &app);
+    assert_eq!(spotlight(&tutorial), Some(Spotlight::ParseButton), "the diagram is not parsed yet");
+    app.parse();
+    frame(&mut tutorial, &app);
+    assert_eq!(spotlight(&tutorial), Some(Spotlight::Diagram));
+
+    app.type_line("Active --> Idle : stop");
+    app.parse();
+    frame(&mut tutorial, &app);
+    assert_eq!(spotlight(&tutorial), Some(Spotlight::TargetSelector));
+    app.target = CodegenTarget::Standard;
+    frame(&mut tutorial, &app);
+    assert_eq!(spotlight(&tutorial), Some(Spotlight::SimToggle));
+    app.sim_enabled = true;
+    app.sim_state = Some("Idle".to_string());
+    frame(&mut tutorial, &app);
+    assert_eq!(spotlight(&tutorial), Some(Spotlight::SimEvents));
+    frame(&mut tutorial, &app);
+    assert_eq!(spotlight(&tutorial), Some(Spotlight::SimEvents));
+
+    app.sim_state = Some("Active".to_string());
+    frame(&mut tutorial, &app);
+    assert_eq!(tutorial.stage, TutorialStage::Finished);
+    assert!(tutorial.record.completed && !tutorial.record.dismissed);
+    // Closing the last card is not turning it down
+    tutorial.exit();
+    assert_eq!(tutorial.stage, TutorialStage::Off);
+    assert!(!tutorial.record.dismissed);
+}
+
+#[test]
+fn test_steps_are_data() {
+    struct Counter(u32);
+    let step = |done: fn(&Counter) -> bool| TutorialStep { spotlight: Spotlight::Diagram, title: "Count", instruction: "", done };
+    let mut tutorial = Tutorial::new(vec![step(|c| c.0 >= 1), step(|c| c.0 >= 2)]);
+    // Not started: nothing to check
+    assert!(!tutorial.step_done(&Counter(5)));
+    tutorial.start();
+    assert!(!tutorial.step_done(&Counter(0)));
+    assert!(tutorial.step_done(&Counter(1)));
+    tutorial.next();
+    assert!(!tutorial.step_done(&Counter(1)));
+
+    // Skipping the last step finishes it too; starting again goes back to the first
+    tutorial.next();
+    assert_eq!(tutorial.stage, TutorialStage::Finished);
+    tutorial.start();
+    assert_eq!(tutorial.current().map(|(index, _)| index), Some(0));
+
+    let mut empty = Tutorial::<Counter>::new(Vec::new());
+    empty.start();
+    assert_eq!(empty.stage, TutorialStage::Finished);
+}
+
+#[test]
+fn test_offered_until_finished_or_turned_down() {
+    let mut tutorial = Tutorial::new(first_fsm_steps::<MockApp>());
+    tutorial.offer();
+    assert_eq!(tutorial.stage, TutorialStage::Offered);
+    tutorial.exit();
+    assert!(tutorial.record.dismissed);
+    tutorial.offer();
+    assert_eq!(tutorial.stage, TutorialStage::Off);
+
+    // Leaving half way counts as turning it down, and Help > Tutorial still works
+    let mut tutorial = Tutorial::new(first_fsm_steps::<MockApp>());
+    tutorial.start();
+    tutorial.next();
+    tutorial.exit();
+    assert_eq!(tutorial.record, TutorialRecord { completed: false, dismissed: true });
+    tutorial.start();
+    assert_eq!(tutorial.stage, TutorialStage::Step(0));
+
+    // The record survives a restart
+    let stored = TutorialRecord { completed: true, dismissed: false }.to_json();
+    let record = TutorialRecord::from_json(&stored).unwrap();
+    assert!(!record.should_offer());
+    assert!(TutorialRecord::from_json("{}").unwrap().should_offer());
+    assert_eq!(TutorialRecord::from_json("not json"), None);
+}
+
+#[test]
+fn test_overlay_geometry() {
+    let screen = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(1000.0, 800.0));
+    let spot = egui::Rect::from_min_max(egui::pos2(100.0, 200.0), egui::pos2(300.0, 250.0));
+    let dim = dim_rects(screen, spot);
+    let area: f32 = dim.iter().map(|r| r.area()).sum();
+    assert_eq!(area + spot.area(), screen.area());
+    assert!(dim.iter().all(|r| !r.intersects(spot.shrink(0.5))));
+
+    let card = egui::vec2(320.0, 140.0);
+    assert_eq!(card_position(screen, spot, card), egui::pos2(300.0 + CARD_GAP, 200.0));
+    // No room on the right: on the left
+    let right = egui::Rect::from_min_max(egui::pos2(700.0, 100.0), egui::pos2(990.0, 150.0));
+    assert_eq!(card_position(screen, right, card), egui::pos2(700.0 - CARD_GAP - 320.0, 100.0));
+    // Nor on either side: below, kept on screen
+    let wide = egui::Rect::from_min_max(egui::pos2(10.0, 700.0), egui::pos2(990.0, 790.0));
+    let pos = card_position(screen, wide, card);
+    assert!(screen.contains_rect(egui::Rect::from_min_size(pos, card)));
+}
@@ -0,0 +1,130 @@
+//! Project Workspace
+//! Editor state of an open project: one buffer per member file, each parsed
+//! and saved on its own
+
+use std::path::{Path, PathBuf};
+
+use crate::fsm::FsmDefinition;
+use crate::parser::loader::parse_fsm_source;
+use crate::project::{Project, ProjectError};
+
+#[cfg(test)]
+mod tests;
+
+/// An open project
+pub struct Workspace {
+    pub project: Project,
+    pub members: Vec<MemberBuffer>,
+    /// Member shown in the editor
+    pub selected: usize,
+}
+
+/// Editor buffer of one member file
+pub struct MemberBuffer {
+    pub path: PathBuf,
+    pub source: String,
+    /// Contents as last loaded or saved
+    saved_source: String,
+    /// FSMs of the last successful parse (kept while the buffer has errors)
+    pub fsms: Vec<FsmDefinition>,
+    /// Error of the last load or parse
+    pub error: Option<String>,
+}
+
+impl MemberBuffer {
+    /// Read and parse `path`; a file that cannot be read becomes an empty buffer with an error
+    pub fn load(path: PathBuf) -> Self {
+        let (source, error) = match std::fs::read_to_string(&path) {
+            Ok(source) => (source, None),
+            Err(e) => (String::new(), Some(format!("Could not read '{}': {}", path.display(), e))),
+        };
+        let mut member = Self {
+            path,
+            saved_source: source.clone(),
+            source,
+            fsms: Vec::new(),
+            error,
+        };
+        if member.error.is_none() {
+            member.parse();
+        }
+        member
+    }
+
+    /// Re-parse the buffer; returns whether it parsed
+    pub fn parse(&mut self) -> bool {
+        match parse_fsm_source(&self.source, &self.path) {
+            Ok(fsms) => {
+                self.fsms = fsms;
+                self.error = None;
+                true
+            }
+            Err(e) => {
+                self.error = Some(e.to_string());
+                false
+            }
+        }
+    }
+
+    /// Whether the buffer has unsaved edits
+    pub fn is_dirty(&self) -> bool {
+        self.source != self.saved_source
+    }
+
+    pub fn save(&mut self) -> std::io::Result<()> {
+        std::fs::write(&self.path, &self.source)?;
+        self.saved_source = self.source.clone();
+        Ok(())
+    }
+
+    /// Tab label: the path relative to `root`
+    pub fn label(&self, root: &Path) -> String {
+        self.path.strip_prefix(root).unwrap_or(&self.path).display().to_string()
+    }
+}
+
+impl Workspace {
+    /// Load the manifest at `path` (or in the folder `path`) and every member
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ProjectError> {
+        let project = Project::load(path)?;
+        let members = project.member_paths().into_iter().map(MemberBuffer::load).collect();
+        Ok(Self {
+            project,
+            members,
+            selected: 0,
+        })
+    }
+
+    pub fn selected_member(&self) -> &MemberBuffer {
+        &self.members[self.selected]
+    }
+
+    pub fn selected_member_mut(&mut self) -> &mut MemberBuffer {
+        &mut self.members[self.selected]
+    }
+
+    /// The FSMs of every member, in manifest order
+    pub fn fsms(&self) -> Vec<FsmDefinition> {
+        self.members.iter().flat_map(|m| m.fsms.iter().cloned()).collect()
+    }
+
+    /// Range of `fsms()` that belongs to `member`
+    pub fn fsm_range(&self, member: usize) -> std::ops::Range<usize> {
+        let start: usize = self.members[..member].iter().map(|m| m.fsms.len()).sum();
+        start..start + self.members[member].fsms.len()
+    }
+
+    /// Member defining the FSM at `fsm_index` of `fsms()`
+    pub fn member_of_fsm(&self, fsm_index: usize) -> Option<usize> {
+        (0..self.members.len()).find(|&m| self.fsm_range(m).contains(&fsm_index))
+    }
+
+    /// Save every member with unsaved edits; returns the errors
+    pub fn save_all(&mut self) -> Vec<String> {
+        self.members
+            .iter_mut()
+            .filter(|m| m.is_dirty())
+            .filter_map(|m| m.save().err().map(|e| format!("Could not save '{}': {}", m.path.display(), e)))
+            .collect()
+    }
+}
@@ -0,0 +1,43 @@
+//! Unit tests for the project workspace
+
+use crate::workspace::Workspace;
+
+#[test]
+fn test_workspace_buffers() {
+    let dir = std::env::temp_dir().join(format!("oxidate_workspace_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("oxidate.toml"), "[project]\nname = \"Robot\"\nmembers = [\"door.fsm\", \"gone.fsm\", \"motor.fsm\"]\n").unwrap();
+    std::fs::write(dir.join("door.fsm"), "fsm Door {\n    [*] --> Closed\n}\nfsm Latch {\n    [*] --> Up\n}\n").unwrap();
+    std::fs::write(dir.join("motor.fsm"), "fsm Motor {\n    [*] --> Off\n}\n").unwrap();
+
+    let mut workspace = Workspace::open(dir.join("oxidate.toml")).expect("project opens");
+    assert_eq!(workspace.members[0].label(workspace.project.root()), "door.fsm");
+    // An unreadable member is an empty buffer with an error; the others load
+    assert!(workspace.members[1].error.as_ref().unwrap().contains("gone.fsm"));
+    let names: Vec<String> = workspace.fsms().into_iter().map(|f| f.name).collect();
+    assert_eq!(names, ["Door", "Latch", "Motor"]);
+    assert_eq!(workspace.fsm_range(1), 2..2);
+    assert_eq!(workspace.fsm_range(2), 2..3);
+    assert_eq!(workspace.member_of_fsm(1), Some(0));
+    assert_eq!(workspace.member_of_fsm(2), Some(2));
+
+    // A broken edit keeps the member's last good FSMs and leaves the others alone
+    workspace.selected = 2;
+    workspace.selected_member_mut().source = "fsm Motor {\n    [*] --> Off\n".to_string();
+    assert!(!workspace.selected_member_mut().parse());
+    assert!(workspace.selected_member().error.is_some());
+    assert!(workspace.selected_member().is_dirty());
+    assert_eq!(workspace.fsms().len(), 3);
+    assert!(workspace.members[0].error.is_none());
+
+    // Saving writes only the dirty member
+    workspace.selected_member_mut().source = "fsm Motor {\n    [*] --> On\n}\n".to_string();
+    assert!(workspace.selected_member_mut().parse());
+    assert!(workspace.save_all().is_empty());
+    assert!(!workspace.selected_member().is_dirty());
+    assert_eq!(std::fs::read_to_string(dir.join("motor.fsm")).unwrap(), "fsm Motor {\n    [*] --> On\n}\n");
+    assert!(!dir.join("gone.fsm").exists());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,106 @@
+//! Compiles the code generated with each action order and checks the order in which
+//! a transition runs the exit, transition and entry actions (also for a completion
+//! transition and a wildcard)
+
+mod common;
+
+use oxidate_fsm::codegen::{try_generate_rust_code_with_options, ActionOrder, CodegenOptions, CodegenTarget};
+use oxidate_fsm::parser::parse_fsm;
+
+const VALVE: &str = r#"
+    fsm Valve {
+        [*] --> Closed
+        state Closed {
+            entry / enter_closed
+            exit / leave_closed
+        }
+        state Open {
+            entry / enter_open
+            exit / leave_open
+        }
+        state Checking {
+            entry / enter_checking
+            exit / leave_checking
+        }
+        Closed --> Open : open / turn
+        Open --> Checking : check / start_check
+        Checking --> Closed : / finish_check
+        * --> Closed : vent / release
+    }
+"#;
+
+const PROGRAM: &str = r#"
+mod uml;
+mod action_first;
+
+macro_rules! log_actions {
+    ($module:ident) => {
+        impl $module::ValveActions for Log {
+            fn enter_closed(&mut self) { self.0.push("enter_closed"); }
+            fn leave_closed(&mut self) { self.0.push("leave_closed"); }
+            fn enter_open(&mut self) { self.0.push("enter_open"); }
+            fn leave_open(&mut self) { self.0.push("leave_open"); }
+            fn enter_checking(&mut self) { self.0.push("enter_checking"); }
+            fn leave_checking(&mut self) { self.0.push("leave_checking"); }
+            fn turn(&mut self) { self.0.push("turn"); }
+            fn start_check(&mut self) { self.0.push("start_check"); }
+            fn finish_check(&mut self) { self.0.push("finish_check"); }
+            fn release(&mut self) { self.0.push("release"); }
+        }
+    };
+}
+
+#[derive(Default)]
+struct Log(Vec<&'static str>);
+
+log_actions!(uml);
+log_actions!(action_first);
+
+fn main() {
+    let mut valve = uml::Valve::new(Log::default());
+    valve.start();
+    valve.context_mut().0.clear();
+    valve.process(uml::ValveEvent::Open);
+    assert_eq!(valve.context().0, ["leave_closed", "turn", "enter_open"]);
+    valve.context_mut().0.clear();
+    valve.process(uml::ValveEvent::Check);
+    assert_eq!(
+        valve.context().0,
+        ["leave_open", "start_check", "enter_checking", "leave_checking", "finish_check", "enter_closed"]
+    );
+    valve.context_mut().0.clear();
+    valve.process(uml::ValveEvent::Vent);
+    assert_eq!(valve.context().0, ["leave_closed", "release", "enter_closed"]);
+
+    let mut valve = action_first::Valve::new(Log::default());
+    valve.start();
+    valve.context_mut().0.clear();
+    valve.process(action_first::ValveEvent::Open);
+    assert_eq!(valve.context().0, ["turn", "leave_closed", "enter_open"]);
+    valve.context_mut().0.clear();
+    valve.process(action_first::ValveEvent::Check);
+    assert_eq!(
+        valve.context().0,
+        ["start_check", "leave_open", "enter_checking", "finish_check", "leave_checking", "enter_closed"]
+    );
+    valve.context_mut().0.clear();
+    valve.process(action_first::ValveEvent::Vent);
+    assert_eq!(valve.context().0, ["release", "leave_closed", "enter_closed"]);
+}
+"#;
+
+#[test]
+fn test_generated_action_order() {
+    let scratch = common::Scratch::new("action_order");
+
+    let fsm = &parse_fsm(VALVE).expect("Should parse successfully")[0];
+    for (module, order) in [("uml", ActionOrder::UmlStandard), ("action_first", ActionOrder::ActionFirst)] {
+        let options = CodegenOptions::new().with_action_order(order);
+        let code = try_generate_rust_code_with_options(fsm, CodegenTarget::Standard, &options).expect("Should generate");
+        scratch.write(&format!("{module}.rs"), code);
+    }
+    scratch.write("main.rs", PROGRAM);
+
+    let binary = scratch.rustc("action_order", &["-A", "warnings"]);
+    common::run(&binary);
+}
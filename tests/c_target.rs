@@ -0,0 +1,196 @@
+//! Compiles the C target's output with the system C compiler (skipped when there is
+//! none): drivers exercising DoorLock's guards and entry/exit order and a payload
+//! reaching a guard and an action, and every bundled example with warnings as errors
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use oxidate_fsm::codegen::c::split_c_files;
+use oxidate_fsm::codegen::{try_generate_rust_code_with_options, CodegenOptions, CodegenTarget};
+use oxidate_fsm::parser::parse_fsm;
+
+const DRIVER: &str = r#"
+#include <stdio.h>
+#include <string.h>
+
+#include "door_lock.h"
+
+typedef struct {
+    char log[512];
+    bool too_many;
+} Log;
+
+static void record(void *user, const char *what)
+{
+    Log *log = user;
+    strcat(log->log, what);
+    strcat(log->log, ";");
+}
+
+static void engage_lock(void *user) { record(user, "engage_lock"); }
+static void disengage_lock(void *user) { record(user, "disengage_lock"); }
+static void notify_unlocked(void *user) { record(user, "notify_unlocked"); }
+static void start_open_timer(void *user) { record(user, "start_open_timer"); }
+static void stop_open_timer(void *user) { record(user, "stop_open_timer"); }
+static void sound_alarm(void *user) { record(user, "sound_alarm"); }
+static void silence_alarm(void *user) { record(user, "silence_alarm"); }
+static bool too_many_attempts(void *user) { return ((Log *)user)->too_many; }
+static bool held_too_long(void *user) { (void)user; return true; }
+
+#define EXPECT(cond) do { if (!(cond)) { printf("failed: %s (log: %s)\n", #cond, log.log); return 1; } } while (0)
+
+int main(void)
+{
+    door_lock_actions_t actions;
+    memset(&actions, 0, sizeof actions);
+    actions.engage_lock = engage_lock;
+    actions.disengage_lock = disengage_lock;
+    actions.notify_unlocked = notify_unlocked;
+    actions.start_open_timer = start_open_timer;
+    actions.stop_open_timer = stop_open_timer;
+    actions.sound_alarm = sound_alarm;
+    actions.silence_alarm = silence_alarm;
+    actions.too_many_attempts = too_many_attempts;
+    actions.held_too_long = held_too_long;
+
+    Log log = { "", false };
+    door_lock_t lock;
+    door_lock_init(&lock, &actions, &log);
+    EXPECT(lock.state == DOOR_LOCK_STATE_LOCKED);
+    EXPECT(strcmp(log.log, "engage_lock;") == 0);
+
+    // The guard rejects, so nothing happens
+    EXPECT(!door_lock_dispatch(&lock, DOOR_LOCK_EVENT_INVALID_KEY));
+    EXPECT(lock.state == DOOR_LOCK_STATE_LOCKED);
+
+    // Exit actions, then the target's entry actions
+    log.log[0] = '\0';
+    EXPECT(door_lock_dispatch(&lock, DOOR_LOCK_EVENT_VALID_KEY));
+    EXPECT(lock.state == DOOR_LOCK_STATE_UNLOCKED);
+    EXPECT(strcmp(log.log, "disengage_lock;notify_unlocked;") == 0);
+
+    EXPECT(!door_lock_dispatch(&lock, DOOR_LOCK_EVENT_RESET_ALARM));
+    EXPECT(door_lock_dispatch(&lock, DOOR_LOCK_EVENT_LOCK_CMD));
+    log.too_many = true;
+    EXPECT(door_lock_dispatch(&lock, DOOR_LOCK_EVENT_INVALID_KEY));
+    EXPECT(lock.state == DOOR_LOCK_STATE_ALARMING);
+    EXPECT(strcmp(door_lock_state_name(lock.state), "Alarming") == 0);
+    EXPECT(strcmp(door_lock_event_name(DOOR_LOCK_EVENT_VALID_KEY), "valid_key") == 0);
+    return 0;
+}
+"#;
+
+const VALVE: &str = r#"
+    fsm Valve {
+        events {
+            open(percent: u8)
+            close
+        }
+        [*] --> Closed
+        Closed --> Open : open(p) [allowed(p)] / set_opening(p)
+        Open --> Closed : close
+    }
+"#;
+
+const VALVE_DRIVER: &str = r#"
+#include <stdio.h>
+
+#include "valve.h"
+
+static unsigned opening = 0;
+
+static void set_opening(void *user, uint8_t percent) { (void)user; opening = percent; }
+static bool allowed(void *user, uint8_t percent) { (void)user; return percent <= 100; }
+
+#define EXPECT(cond) do { if (!(cond)) { printf("failed: %s\n", #cond); return 1; } } while (0)
+
+int main(void)
+{
+    valve_actions_t actions = { set_opening, allowed };
+    valve_t valve;
+    valve_init(&valve, &actions, NULL);
+
+    valve_event_args_t args;
+    args.open.percent = 150;
+    EXPECT(!valve_dispatch_args(&valve, VALVE_EVENT_OPEN, &args));
+    // Without a payload the transition that needs one is skipped
+    EXPECT(!valve_dispatch(&valve, VALVE_EVENT_OPEN));
+    args.open.percent = 40;
+    EXPECT(valve_dispatch_args(&valve, VALVE_EVENT_OPEN, &args));
+    EXPECT(valve.state == VALVE_STATE_OPEN && opening == 40);
+    EXPECT(valve_dispatch(&valve, VALVE_EVENT_CLOSE));
+    return 0;
+}
+"#;
+
+/// Write the headers and sources generated for the FSMs in `source` into `scratch`,
+/// returning the sources' paths. FSMs with choice points are skipped: no target
+/// resolves transitions into a choice yet.
+fn write_c_files(scratch: &common::Scratch, source: &str, options: &CodegenOptions) -> Vec<PathBuf> {
+    let mut sources = Vec::new();
+    for fsm in parse_fsm(source).expect("Should parse successfully") {
+        if !fsm.choice_points.is_empty() {
+            continue;
+        }
+        let code = try_generate_rust_code_with_options(&fsm, CodegenTarget::C, options).expect("Should generate");
+        for (name, contents) in split_c_files(&code) {
+            let path = scratch.write(&name, contents);
+            if name.ends_with(".c") {
+                sources.push(path);
+            }
+        }
+    }
+    sources
+}
+
+#[test]
+fn test_c_door_lock_runs() {
+    if !common::cc_available() {
+        return;
+    }
+    let scratch = common::Scratch::new("c_target");
+    let source = std::fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/builtin/door_lock.fsm")).unwrap();
+    let mut files = write_c_files(&scratch, &source, &CodegenOptions::new());
+    files.push(scratch.write("main.c", DRIVER));
+
+    let binary = scratch.path("door_lock");
+    scratch.cc(&[], &files, &binary);
+    common::run(&binary);
+}
+
+#[test]
+fn test_c_payloads_run() {
+    if !common::cc_available() {
+        return;
+    }
+    let scratch = common::Scratch::new("c_payloads");
+    let mut files = write_c_files(&scratch, VALVE, &CodegenOptions::new());
+    files.push(scratch.write("main.c", VALVE_DRIVER));
+
+    let binary = scratch.path("valve");
+    scratch.cc(&[], &files, &binary);
+    common::run(&binary);
+}
+
+#[test]
+fn test_c_examples_compile_cleanly() {
+    if !common::cc_available() {
+        return;
+    }
+    let scratch = common::Scratch::new("c_examples");
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    let options = CodegenOptions::new().with_tracing_hook(true).with_lenient_names(true);
+    for folder in ["", "builtin", "templates"] {
+        for entry in std::fs::read_dir(root.join(folder)).unwrap() {
+            let path = entry.unwrap().path();
+            if !path.extension().is_some_and(|e| e == "fsm") {
+                continue;
+            }
+            let files = write_c_files(&scratch, &std::fs::read_to_string(&path).unwrap(), &options);
+            for file in &files {
+                scratch.cc(&["-c"], std::slice::from_ref(file), &scratch.path("out.o"));
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Shared by the integration tests: a scratch directory that generated code is
+//! written into, compiled there with rustc or the C compiler, and run
+
+// Each test crate uses only part of it
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// `oxidate_<name>_<pid>` under the system temp directory, removed when dropped
+pub struct Scratch {
+    dir: PathBuf,
+}
+
+impl Scratch {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("oxidate_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self { dir }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn path(&self, file: &str) -> PathBuf {
+        self.dir.join(file)
+    }
+
+    /// Write `contents` to `file`, returning its path
+    pub fn write(&self, file: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.path(file);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    /// Compile `main.rs` (which declares the generated modules) into the binary `name`
+    /// with rustc and the extra `args`, failing the test with rustc's errors
+    pub fn rustc(&self, name: &str, args: &[&str]) -> PathBuf {
+        let binary = self.path(name);
+        let output = Command::new(rustc_command())
+            .args(["--edition", "2021"])
+            .args(args)
+            .arg("-o")
+            .arg(&binary)
+            .arg(self.path("main.rs"))
+            .output()
+            .expect("rustc should run");
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        binary
+    }
+
+    /// Compile `file` on its own as a library crate, failing the test with rustc's
+    /// errors (and `context`, e.g. the code) if it does not build
+    pub fn rustc_lib(&self, file: &Path, args: &[&str], context: &str) {
+        let library = self.path(&format!("lib{}.rlib", file.file_stem().unwrap().to_string_lossy()));
+        let output = Command::new(rustc_command())
+            .args(["--edition", "2021", "--crate-type", "lib"])
+            .args(args)
+            .arg("-o")
+            .arg(library)
+            .arg(file)
+            .output()
+            .expect("rustc should run");
+        assert!(output.status.success(), "{context}\n{}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    /// Compile `files` with the C compiler as C99 with every warning an error, plus
+    /// the extra `args` (`-c` to stop at object files)
+    pub fn cc(&self, args: &[&str], files: &[PathBuf], output: &Path) {
+        let result = Command::new(cc())
+            .args(["-std=c99", "-Wall", "-Wextra", "-pedantic", "-Werror"])
+            .args(args)
+            .args(files)
+            .arg("-o")
+            .arg(output)
+            .current_dir(&self.dir)
+            .output()
+            .expect("cc should run");
+        assert!(result.status.success(), "{:?}: {}", files, String::from_utf8_lossy(&result.stderr));
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// The Rust compiler: `$RUSTC`, or `rustc`
+fn rustc_command() -> String {
+    std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string())
+}
+
+/// Run `binary`, failing the test if it does, and return its output
+pub fn run(binary: &Path) -> Output {
+    let run = Command::new(binary).output().unwrap();
+    assert!(run.status.success(), "{}{}", String::from_utf8_lossy(&run.stdout), String::from_utf8_lossy(&run.stderr));
+    run
+}
+
+/// The C compiler: `$CC`, or `cc`
+pub fn cc() -> String {
+    std::env::var("CC").unwrap_or_else(|_| "cc".to_string())
+}
+
+/// Whether there is a C compiler to run; the C tests pass without one
+pub fn cc_available() -> bool {
+    Command::new(cc()).arg("--version").output().is_ok_and(|output| output.status.success())
+}
@@ -0,0 +1,71 @@
+//! Compiles generated code and checks that completion (event-less) transitions are
+//! taken right after their source state is entered, and that a loop of them stops
+//! at the hop limit
+
+mod common;
+
+use oxidate_fsm::codegen::{generate_rust_code_with_options, CodegenOptions, CodegenTarget};
+use oxidate_fsm::parser::parse_fsm;
+
+const SPIN: &str = r#"
+    fsm Spin {
+        [*] --> Boot
+        state Idle {
+            entry / idle
+        }
+        state Turning {
+            entry / turn
+        }
+        Boot --> Idle
+        Idle --> Turning : go
+        Turning --> Turned
+        Turned --> Turning
+        Turned --> Idle : halt
+    }
+"#;
+
+const PROGRAM: &str = r#"
+mod spin;
+
+use spin::{Spin, SpinEvent, SpinState};
+
+#[derive(Default)]
+struct Log(Vec<&'static str>);
+
+impl spin::SpinActions for Log {
+    fn idle(&mut self) {
+        self.0.push("idle");
+    }
+    fn turn(&mut self) {
+        self.0.push("turn");
+    }
+}
+
+fn main() {
+    let mut spin = Spin::new(Log::default());
+    assert_eq!(spin.state(), SpinState::Boot);
+    spin.start();
+    assert_eq!(spin.state(), SpinState::Idle);
+    assert_eq!(spin.context().0, ["idle"]);
+
+    // Turning and Turned hand over to each other until the third hop
+    assert!(spin.process(SpinEvent::Go));
+    assert_eq!(spin.state(), SpinState::Turned);
+    assert_eq!(spin.context().0, ["idle", "turn", "turn"]);
+
+    assert!(spin.process(SpinEvent::Halt));
+    assert_eq!(spin.state(), SpinState::Idle);
+}
+"#;
+
+#[test]
+fn test_completion_transitions_and_hop_limit() {
+    let scratch = common::Scratch::new("completions");
+    let fsm = &parse_fsm(SPIN).expect("Should parse successfully")[0];
+    let options = CodegenOptions::new().with_max_completion_hops(3);
+    scratch.write("spin.rs", generate_rust_code_with_options(fsm, CodegenTarget::Standard, &options));
+    scratch.write("main.rs", PROGRAM);
+
+    let binary = scratch.rustc("completions", &["-A", "warnings"]);
+    common::run(&binary);
+}
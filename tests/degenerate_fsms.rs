@@ -0,0 +1,65 @@
+//! Compiles the code generated for machines that are barely started: no states at
+//! all, a single state, and states without transitions (with and without `[*]`)
+
+mod common;
+
+use oxidate_fsm::codegen::c::split_c_files;
+use oxidate_fsm::codegen::{try_generate_rust_code_with_options, CodegenOptions, CodegenTarget, OverflowPolicy, Visibility};
+use oxidate_fsm::parser::parse_fsm;
+
+const MACHINES: &[(&str, &str)] = &[
+    ("empty", "fsm Empty { }"),
+    ("single", "fsm Single {\n    [*] --> Idle\n}"),
+    ("no_initial", "fsm NoInitial {\n    state Idle\n    state Busy\n}"),
+    ("no_transitions", "fsm NoTransitions {\n    [*] --> Idle\n    state Idle {\n        entry / wake\n    }\n    state Busy\n}"),
+];
+
+/// Every option that compiles without external crates (serde derives need serde)
+fn option_sets() -> Vec<(&'static str, CodegenOptions)> {
+    let everything = CodegenOptions::new()
+        .with_tracing_hook(true)
+        .with_visibility(Visibility::Crate)
+        .with_repr_u8(true)
+        .with_event_queue_size(Some(4))
+        .with_overflow_policy(OverflowPolicy::Count)
+        .with_metrics(true)
+        .with_process_result(true);
+    vec![
+        ("default", CodegenOptions::new()),
+        ("everything", everything),
+        ("entry_on_new", CodegenOptions::new().with_run_entry_on_new(true).with_no_std(true)),
+    ]
+}
+
+#[test]
+fn test_degenerate_machines_compile() {
+    let scratch = common::Scratch::new("degenerate");
+
+    for (name, source) in MACHINES {
+        let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+        for (variant, options) in option_sets() {
+            let code = try_generate_rust_code_with_options(fsm, CodegenTarget::Standard, &options).expect("Should generate");
+            let file = scratch.write(&format!("{name}_{variant}.rs"), &code);
+            scratch.rustc_lib(&file, &["-A", "warnings", "--cfg", "feature=\"fsm-metrics\""], &format!("{name} ({variant}):\n{code}"));
+        }
+    }
+}
+
+#[test]
+fn test_degenerate_machines_compile_as_c() {
+    if !common::cc_available() {
+        return;
+    }
+    let scratch = common::Scratch::new("degenerate_c");
+
+    for (name, source) in MACHINES {
+        let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+        let code = try_generate_rust_code_with_options(fsm, CodegenTarget::C, &CodegenOptions::new()).expect("Should generate");
+        for (file, contents) in split_c_files(&code) {
+            let path = scratch.write(&file, contents);
+            if file.ends_with(".c") {
+                scratch.cc(&["-c"], &[path], &scratch.path(&format!("{name}.o")));
+            }
+        }
+    }
+}
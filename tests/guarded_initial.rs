@@ -0,0 +1,96 @@
+//! Compiles the code generated for a machine with guarded initial transitions, and
+//! checks that it and the interpreter start in the same state with the same actions
+//! for every answer of the guards
+
+mod common;
+
+use oxidate_fsm::codegen::generate_rust_code;
+use oxidate_fsm::fsm::interpreter::Interpreter;
+use oxidate_fsm::parser::parse_fsm;
+
+const BOOTLOADER: &str = r#"
+    fsm Bootloader {
+        [*] --> Idle : / init_clocks
+        [*] --> Recovery : [recovery_pin_set] / log_recovery
+        [*] --> Updating : [update_pending && !recovery_pin_set]
+        state Idle {
+            entry / jump_to_app
+        }
+        state Recovery {
+            entry / open_console
+        }
+        state Updating {
+            entry / erase_slot
+        }
+        Idle --> Updating : update_requested
+        Updating --> Idle : done
+        Recovery --> Idle : reboot
+    }
+"#;
+
+const PROGRAM: &str = r#"
+mod bootloader;
+
+use bootloader::Bootloader;
+
+#[derive(Default)]
+struct Board {
+    calls: Vec<&'static str>,
+    pin: bool,
+    pending: bool,
+}
+
+impl bootloader::BootloaderActions for Board {
+    fn init_clocks(&mut self) { self.calls.push("init_clocks"); }
+    fn log_recovery(&mut self) { self.calls.push("log_recovery"); }
+    fn jump_to_app(&mut self) { self.calls.push("jump_to_app"); }
+    fn open_console(&mut self) { self.calls.push("open_console"); }
+    fn erase_slot(&mut self) { self.calls.push("erase_slot"); }
+    fn recovery_pin_set(&self) -> bool { self.pin }
+    fn update_pending(&self) -> bool { self.pending }
+}
+
+fn main() {
+    for (pin, pending) in [(false, false), (true, false), (false, true), (true, true)] {
+        let mut machine = Bootloader::new(Board { pin, pending, ..Board::default() });
+        machine.start();
+        println!("{:?}: {}", machine.state(), machine.context().calls.join(" "));
+    }
+}
+"#;
+
+/// State the interpreter starts in and the actions it runs, as the program prints them
+fn interpreted(pin: bool, pending: bool) -> String {
+    let fsm = &parse_fsm(BOOTLOADER).expect("Should parse successfully")[0];
+    let mut interpreter = Interpreter::new(fsm, |guard: &str| match guard {
+        "recovery_pin_set" => pin,
+        "update_pending" => pending,
+        _ => false,
+    });
+    let actions: Vec<&str> = interpreter.reset().iter().map(|a| a.name.as_str()).collect();
+    format!("{}: {}", interpreter.current_state().unwrap(), actions.join(" "))
+}
+
+#[test]
+fn test_guarded_initial_transitions_pick_the_start() {
+    let expected = [
+        // No guard holds: the default
+        "Idle: init_clocks jump_to_app",
+        "Recovery: log_recovery open_console",
+        "Updating: erase_slot",
+        // The first guard that holds wins
+        "Recovery: log_recovery open_console",
+    ];
+    let interpreted = [interpreted(false, false), interpreted(true, false), interpreted(false, true), interpreted(true, true)];
+    assert_eq!(interpreted, expected);
+
+    let scratch = common::Scratch::new("guarded_initial");
+    let fsm = &parse_fsm(BOOTLOADER).expect("Should parse successfully")[0];
+    assert!(fsm.validate().is_ok(), "{:?}", fsm.validate());
+    scratch.write("bootloader.rs", generate_rust_code(fsm));
+    scratch.write("main.rs", PROGRAM);
+
+    let binary = scratch.rustc("guarded_initial", &["-A", "warnings"]);
+    let run = common::run(&binary);
+    assert_eq!(String::from_utf8_lossy(&run.stdout).lines().collect::<Vec<_>>(), expected);
+}
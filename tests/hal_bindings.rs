@@ -0,0 +1,75 @@
+//! Compiles the door lock example with the HAL bindings of `door_lock_hal.toml`
+//! against a fake GPIO module: the bound methods drive the pins without any
+//! hand-written trait implementation, and the unbound one panics with `todo!()`
+
+mod common;
+
+use oxidate_fsm::codegen::bindings::Bindings;
+use oxidate_fsm::codegen::{try_generate_rust_code_with_options, CodegenOptions, CodegenTarget};
+use oxidate_fsm::parser::parse_fsm;
+
+const PROGRAM: &str = r#"
+mod door_lock;
+
+mod hal {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    pub const LOCK: usize = 0;
+    pub const SIREN: usize = 1;
+    pub const KEYPAD: usize = 2;
+    pub const HIGH: bool = true;
+    pub const LOW: bool = false;
+
+    static PINS: [AtomicBool; 3] = [AtomicBool::new(false), AtomicBool::new(false), AtomicBool::new(false)];
+
+    pub fn gpio_set(pin: usize, level: bool) {
+        PINS[pin].store(level, Ordering::SeqCst);
+    }
+
+    pub fn gpio_read(pin: usize) -> bool {
+        PINS[pin].load(Ordering::SeqCst)
+    }
+}
+
+use door_lock::{DoorLock, DoorLockEvent as Event, DoorLockState as State, HalActions};
+use hal::{gpio_read, HIGH, LOCK, SIREN};
+
+fn main() {
+    let mut machine = DoorLock::new(HalActions);
+    machine.start();
+    assert_eq!(gpio_read(LOCK), HIGH);
+
+    machine.process(Event::ValidCode);
+    assert_eq!(machine.state(), State::Unlocked);
+    assert!(!gpio_read(LOCK));
+    machine.process(Event::LockButton);
+    assert!(gpio_read(LOCK));
+
+    // too_many_attempts is bound to `false`
+    assert!(!machine.process(Event::InvalidCode));
+    assert_eq!(machine.state(), State::Locked);
+    assert!(!gpio_read(SIREN));
+
+    // notify_security has no binding
+    let tamper = std::panic::catch_unwind(move || {
+        machine.process(Event::TamperDetected);
+    });
+    assert!(tamper.is_err());
+}
+"#;
+
+#[test]
+fn test_bound_actions_run_without_hand_editing() {
+    let scratch = common::Scratch::new("hal_bindings");
+
+    let fsm = &parse_fsm(include_str!("../examples/door_lock.fsm")).expect("Should parse successfully")[0];
+    let bindings = Bindings::load(concat!(env!("CARGO_MANIFEST_DIR"), "/examples/door_lock_hal.toml")).expect("Should load");
+    let options = CodegenOptions::new().with_bindings(bindings);
+    let code = try_generate_rust_code_with_options(fsm, CodegenTarget::Standard, &options).expect("Should generate");
+    scratch.write("door_lock.rs", code);
+    scratch.write("main.rs", PROGRAM);
+
+    let binary = scratch.rustc("hal_bindings", &["-A", "warnings"]);
+    let run = common::run(&binary);
+    assert!(String::from_utf8_lossy(&run.stderr).contains("bind notify_security"));
+}
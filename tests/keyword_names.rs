@@ -0,0 +1,73 @@
+//! Compiles the code generated for a machine with Unicode names and methods named
+//! after Rust keywords, which are emitted as raw identifiers
+
+mod common;
+
+use oxidate_fsm::codegen::generate_rust_code;
+use oxidate_fsm::parser::parse_fsm;
+
+const WATCHER: &str = r#"
+    fsm Wächter {
+        [*] --> Überwachung
+        state Loop {
+            entry / zählen
+        }
+        Überwachung --> Loop : match [loop] / type
+        Loop --> Überwachung : ruhe [bereit && !async] / move(ziel)
+    }
+"#;
+
+const PROGRAM: &str = r#"
+mod waechter;
+
+use waechter::{Wächter, WächterEvent, WächterState};
+
+#[derive(Default)]
+struct Log(Vec<&'static str>);
+
+impl waechter::WächterActions for Log {
+    fn zählen(&mut self) {
+        self.0.push("zählen");
+    }
+    fn r#type(&mut self) {
+        self.0.push("type");
+    }
+    fn r#move(&mut self, ziel: u32) {
+        assert_eq!(ziel, 7);
+        self.0.push("move");
+    }
+    fn r#loop(&self) -> bool {
+        true
+    }
+    fn bereit(&self) -> bool {
+        true
+    }
+    fn r#async(&self) -> bool {
+        false
+    }
+    fn ziel(&self) -> u32 {
+        7
+    }
+}
+
+fn main() {
+    let mut watcher = Wächter::new(Log::default());
+    assert!(watcher.process(WächterEvent::Match));
+    assert_eq!(watcher.state(), WächterState::Loop);
+    assert!(watcher.process(WächterEvent::Ruhe));
+    assert_eq!(watcher.state(), WächterState::Überwachung);
+    assert_eq!(watcher.context().0, ["type", "zählen", "move"]);
+}
+"#;
+
+#[test]
+fn test_keyword_and_unicode_names_compile() {
+    let scratch = common::Scratch::new("keyword_names");
+
+    let fsm = &parse_fsm(WATCHER).expect("Should parse successfully")[0];
+    scratch.write("waechter.rs", generate_rust_code(fsm));
+    scratch.write("main.rs", PROGRAM);
+
+    let binary = scratch.rustc("keyword_names", &["-A", "warnings"]);
+    common::run(&binary);
+}
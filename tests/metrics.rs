@@ -0,0 +1,72 @@
+//! Compiles generated code with and without the `fsm-metrics` feature and checks the
+//! counters after a scripted event sequence
+
+mod common;
+
+use oxidate_fsm::codegen::{generate_rust_code_with_options, CodegenOptions, CodegenTarget};
+use oxidate_fsm::parser::parse_fsm;
+
+const DOOR: &str = r#"
+    fsm Door {
+        [*] --> Closed
+        Closed --> Open : open / unlatch
+        Open --> Closed : close
+        Open --> Open : open
+        Closed --> Locked : lock
+        Locked --> Closed : unlock
+    }
+"#;
+
+const PROGRAM: &str = r#"
+mod door;
+
+use door::{Door, DoorEvent, DoorState};
+
+struct Latch;
+
+impl door::DoorActions for Latch {
+    fn unlatch(&mut self) {}
+}
+
+fn main() {
+    let mut door = Door::new(Latch);
+    for event in [DoorEvent::Open, DoorEvent::Close, DoorEvent::Lock] {
+        door.post(event).unwrap();
+    }
+    assert_eq!(door.dispatch_pending(), 3);
+    door.process(DoorEvent::Lock);
+    door.process(DoorEvent::Unlock);
+    door.process(DoorEvent::Open);
+    door.process(DoorEvent::Open);
+
+    #[cfg(feature = "fsm-metrics")]
+    {
+        let metrics = door.metrics();
+        assert_eq!(metrics.entered(DoorState::Closed), 3);
+        assert_eq!(metrics.entered(DoorState::Open), 3);
+        assert_eq!(metrics.entered(DoorState::Locked), 1);
+        assert_eq!(metrics.received(DoorEvent::Open), 3);
+        assert_eq!(metrics.received(DoorEvent::Lock), 2);
+        assert_eq!(metrics.received(DoorEvent::Close), 1);
+        assert_eq!(metrics.queue_high_watermark(), 3);
+    }
+    assert_eq!(door.state(), DoorState::Open);
+}
+"#;
+
+#[test]
+fn test_metrics_count_scripted_events() {
+    let scratch = common::Scratch::new("metrics");
+
+    let fsm = &parse_fsm(DOOR).expect("Should parse successfully")[0];
+    let options = CodegenOptions::new().with_event_queue_size(Some(4)).with_metrics(true);
+    let code = generate_rust_code_with_options(fsm, CodegenTarget::Standard, &options);
+    scratch.write("door.rs", code);
+    scratch.write("main.rs", PROGRAM);
+
+    // Once with the counters compiled in, once without
+    let with_metrics = scratch.rustc("with_metrics", &["-A", "warnings", "--cfg", "feature=\"fsm-metrics\""]);
+    common::run(&with_metrics);
+    let without_metrics = scratch.rustc("without_metrics", &["-A", "warnings"]);
+    common::run(&without_metrics);
+}
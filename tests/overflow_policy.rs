@@ -0,0 +1,65 @@
+//! Compiles the event queue under each overflow policy and checks what `post()`
+//! does once the queue is full
+
+mod common;
+
+use oxidate_fsm::codegen::{generate_rust_code_with_options, CodegenOptions, CodegenTarget, OverflowPolicy};
+use oxidate_fsm::parser::parse_fsm;
+
+const COUNTER: &str = r#"
+    fsm Counter {
+        [*] --> Idle
+        Idle --> Busy : start / begin
+        Busy --> Idle : done
+    }
+"#;
+
+const PROGRAM: &str = r#"
+mod count;
+mod drop_oldest;
+
+struct Log;
+
+impl count::CounterActions for Log {
+    fn begin(&mut self) {}
+}
+
+impl drop_oldest::CounterActions for Log {
+    fn begin(&mut self) {}
+}
+
+fn main() {
+    let mut counter = count::Counter::new(Log);
+    for _ in 0..5 {
+        counter.post(count::CounterEvent::Start);
+    }
+    assert_eq!(counter.dropped_events(), 3);
+    assert_eq!(counter.dispatch_pending(), 1);
+
+    let mut counter = drop_oldest::Counter::new(Log);
+    assert_eq!(counter.post(drop_oldest::CounterEvent::Done), None);
+    assert_eq!(counter.post(drop_oldest::CounterEvent::Done), None);
+    assert_eq!(counter.post(drop_oldest::CounterEvent::Start), Some(drop_oldest::CounterEvent::Done));
+    // Done is ignored in Idle, then Start fires
+    assert_eq!(counter.dispatch_pending(), 1);
+    assert_eq!(counter.state(), drop_oldest::CounterState::Busy);
+}
+"#;
+
+#[test]
+fn test_overflow_policies_compile_and_run() {
+    let scratch = common::Scratch::new("overflow_policy");
+
+    let fsm = &parse_fsm(COUNTER).expect("Should parse successfully")[0];
+    for (file, policy) in [("count.rs", OverflowPolicy::Count), ("drop_oldest.rs", OverflowPolicy::DropOldest)] {
+        let options = CodegenOptions::new()
+            .with_event_queue_size(Some(2))
+            .with_overflow_policy(policy);
+        let code = generate_rust_code_with_options(fsm, CodegenTarget::Standard, &options);
+        scratch.write(file, code);
+    }
+    scratch.write("main.rs", PROGRAM);
+
+    let binary = scratch.rustc("overflow_policy", &["-A", "warnings"]);
+    common::run(&binary);
+}
@@ -0,0 +1,138 @@
+//! Feeds `parse_fsm` random DSL: token soup from the grammar's vocabulary and mutated
+//! copies of the bundled examples. Every input must parse or fail with an error, never
+//! panic, and whatever parses must survive validation and printing. The generator is
+//! seeded, so a failure reproduces with the seed it reports.
+
+use std::panic;
+
+use oxidate_fsm::parser::format::format_fsm;
+use oxidate_fsm::parser::{parse_fsm, parse_fsm_with_limits, ParseError, ParseLimits};
+
+/// xorshift64*: small, deterministic and good enough to pick tokens
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+}
+
+const TOKENS: &[&str] = &[
+    "fsm", "state", "timer", "choice", "note", "left", "of", "end", "events", "include", "entry", "exit", "local",
+    "final", "as", "periodic", "oneshot", "start_timer", "stop_timer", "direction", "description", "meta", "[*]", "*",
+    "-->", "->", "<--", "<-", "-.->", "<-.-", ":", "/", ";", ",", "=", "[", "]", "(", ")", "{", "}", "<<", ">>",
+    "[else]", "\"", "\"text\"", "//", "///", "#color(red)", "@tag(x)", "Idle", "Run", "go", "tick", "x", "a_b",
+    "500", "99999999999", "u8", "\n", "\n", "\n", " ", " ", " ",
+];
+
+fn token_soup(rng: &mut Rng) -> String {
+    let mut source = String::from("fsm F {\n");
+    for _ in 0..rng.below(60) {
+        source.push_str(rng.pick(TOKENS));
+        source.push(' ');
+    }
+    if rng.below(2) == 0 {
+        source.push('}');
+    }
+    source
+}
+
+fn mutate(rng: &mut Rng, seed: &str) -> String {
+    let mut bytes = seed.as_bytes().to_vec();
+    for _ in 0..1 + rng.below(4) {
+        let at = rng.below(bytes.len() + 1);
+        match rng.below(4) {
+            // Cut a slice out
+            0 => {
+                let end = (at + rng.below(40)).min(bytes.len());
+                bytes.drain(at..end);
+            }
+            // Duplicate a slice
+            1 => {
+                let end = (at + rng.below(40)).min(bytes.len());
+                let copy = bytes[at..end].to_vec();
+                bytes.splice(at..at, copy);
+            }
+            // Insert a token
+            2 => {
+                bytes.splice(at..at, rng.pick(TOKENS).bytes());
+            }
+            // Truncate
+            _ => bytes.truncate(at),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn check(source: &str, seed: u64) {
+    let result = panic::catch_unwind(|| {
+        if let Ok(fsms) = parse_fsm(source) {
+            for fsm in &fsms {
+                let _ = fsm.validate();
+                let _ = fsm.warnings();
+                let _ = fsm.infos();
+                let _ = format_fsm(fsm);
+            }
+        }
+    });
+    assert!(result.is_ok(), "seed {seed} panicked on:\n{source}");
+}
+
+#[test]
+fn test_random_sources_never_panic() {
+    let examples: Vec<String> = ["examples/builtin", "examples/templates", "examples"]
+        .iter()
+        .flat_map(|dir| std::fs::read_dir(dir).unwrap())
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "fsm"))
+        .map(|path| std::fs::read_to_string(path).unwrap())
+        .collect();
+    assert!(!examples.is_empty());
+
+    for seed in 1..=3000u64 {
+        let mut rng = Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        let source = match seed % 3 {
+            0 => token_soup(&mut rng),
+            _ => {
+                let example = &examples[rng.below(examples.len())];
+                mutate(&mut rng, example)
+            }
+        };
+        check(&source, seed);
+    }
+}
+
+#[test]
+fn test_deep_nesting_is_a_limit_not_a_stack_overflow() {
+    let depth = 100_000;
+    let source = format!("fsm F {{\n{}{}}}\n", "state S {\n".repeat(depth), "}\n".repeat(depth));
+    match parse_fsm(&source) {
+        Err(ParseError::LimitExceeded { what, limit, found }) => {
+            assert_eq!(what, "nesting depth");
+            assert_eq!(limit, ParseLimits::default().max_nesting_depth);
+            assert!(found > limit);
+        }
+        other => panic!("expected a nesting limit error, got {other:?}"),
+    }
+
+    // Unclosed blocks count too
+    let unclosed = format!("fsm F {{\n{}", "state S {\n".repeat(depth));
+    assert!(matches!(parse_fsm(&unclosed), Err(ParseError::LimitExceeded { .. })));
+
+    let limits = ParseLimits { max_source_len: 64, ..ParseLimits::default() };
+    assert!(matches!(
+        parse_fsm_with_limits(&source, &limits),
+        Err(ParseError::LimitExceeded { limit: 64, .. })
+    ));
+}
@@ -0,0 +1,92 @@
+//! Compiles generated code for events with payloads and runs a program that checks
+//! the guard and actions get the fields, through `process()` and the event queue
+
+mod common;
+
+use oxidate_fsm::codegen::{generate_rust_code_with_options, CodegenOptions, CodegenTarget};
+use oxidate_fsm::parser::parse_fsm;
+
+const HEATER: &str = r#"
+    fsm Heater {
+        events {
+            set_target(celsius: i16)
+            nudge(by: f32)
+            off
+        }
+        [*] --> Idle
+        Idle --> Heating : set_target(t) [in_range(t)] / store_target(t)
+        Heating --> Heating : set_target(t) / store_target(t)
+        Heating --> Idle : off
+        state Heating {
+            nudge(by) / adjust(by)
+        }
+    }
+"#;
+
+const PROGRAM: &str = r#"
+mod heater;
+
+use heater::{Heater, HeaterEvent, HeaterState};
+
+#[derive(Default)]
+struct Thermostat {
+    target: i16,
+    offset: f32,
+    transitions: Vec<&'static str>,
+}
+
+impl heater::HeaterActions for Thermostat {
+    fn adjust(&mut self, by: f32) {
+        self.offset += by;
+    }
+    fn store_target(&mut self, t: i16) {
+        self.target = t;
+    }
+    fn in_range(&self, t: i16) -> bool {
+        (5..=30).contains(&t)
+    }
+    fn on_transition(&mut self, _from: HeaterState, event: HeaterEvent, _to: HeaterState) {
+        self.transitions.push(event.as_str());
+    }
+}
+
+fn main() {
+    let mut heater = Heater::new(Thermostat::default());
+    heater.start();
+    assert!(!heater.process(HeaterEvent::SetTarget { celsius: 90 }).is_handled());
+    assert_eq!(heater.state(), HeaterState::Idle);
+    assert!(heater.process(HeaterEvent::SetTarget { celsius: 21 }).is_handled());
+    assert_eq!(heater.context().target, 21);
+
+    heater.post(HeaterEvent::Nudge { by: 0.5 }).unwrap();
+    heater.post(HeaterEvent::SetTarget { celsius: 90 }).unwrap();
+    heater.post(HeaterEvent::Off).unwrap();
+    assert_eq!(heater.dispatch_pending(), 3);
+    assert_eq!(heater.context().offset, 0.5);
+    assert_eq!(heater.context().target, 90);
+    assert_eq!(heater.state(), HeaterState::Idle);
+    assert_eq!(heater.context().transitions, ["set_target", "set_target", "off"]);
+    #[cfg(feature = "fsm-metrics")]
+    assert_eq!(heater.metrics().received(HeaterEvent::SetTarget { celsius: 0 }), 3);
+}
+"#;
+
+#[test]
+fn test_payloads_reach_guards_and_actions() {
+    let scratch = common::Scratch::new("payloads");
+
+    let fsm = &parse_fsm(HEATER).expect("Should parse successfully")[0];
+    assert_eq!(fsm.validate(), Ok(()));
+    let options = CodegenOptions::new()
+        .with_event_queue_size(Some(4))
+        .with_tracing_hook(true)
+        .with_process_result(true)
+        .with_metrics(true)
+        .with_repr_u8(true);
+    let code = generate_rust_code_with_options(fsm, CodegenTarget::Standard, &options);
+    scratch.write("heater.rs", code);
+    scratch.write("main.rs", PROGRAM);
+
+    let binary = scratch.rustc("heater", &["-D", "warnings", "-A", "dead_code", "--cfg", "feature=\"fsm-metrics\""]);
+    common::run(&binary);
+}
@@ -0,0 +1,135 @@
+//! Compiles the code generated for the connection manager example and drives its
+//! timers with `advance_time()`: the periodic keepalive fires once per period while
+//! connected and stops when the connection is lost. Timers due together fire in
+//! definition order, as in the interpreter
+
+mod common;
+
+use oxidate_fsm::codegen::generate_rust_code;
+use oxidate_fsm::fsm::interpreter::Interpreter;
+use oxidate_fsm::parser::parse_fsm;
+
+const PROGRAM: &str = r#"
+mod connection_manager;
+
+use connection_manager::{ConnectionManager, ConnectionManagerEvent as Event, ConnectionManagerState as State};
+
+#[derive(Default)]
+struct Log(Vec<&'static str>);
+
+impl connection_manager::ConnectionManagerActions for Log {
+    fn graceful_close(&mut self) {
+        self.0.push("graceful_close");
+    }
+    fn initiate_connection(&mut self) {
+        self.0.push("initiate_connection");
+    }
+    fn log_failure(&mut self) {
+        self.0.push("log_failure");
+    }
+    fn log_timeout(&mut self) {
+        self.0.push("log_timeout");
+    }
+    fn on_connected(&mut self) {
+        self.0.push("on_connected");
+    }
+    fn on_disconnected(&mut self) {
+        self.0.push("on_disconnected");
+    }
+    fn reset_connection(&mut self) {
+        self.0.push("reset_connection");
+    }
+    fn send_keepalive(&mut self) {
+        self.0.push("send_keepalive");
+    }
+}
+
+fn keepalives(machine: &ConnectionManager<Log>) -> usize {
+    machine.context().0.iter().filter(|a| **a == "send_keepalive").count()
+}
+
+fn main() {
+    let mut machine = ConnectionManager::new(Log::default());
+    machine.start();
+    assert_eq!(machine.advance_time(60_000), 0);
+    machine.process(Event::Connect);
+    machine.process(Event::ConnectionEstablished);
+    assert_eq!(machine.state(), State::Connected);
+
+    // Every 30 s, however the time is sliced
+    assert_eq!(machine.advance_time(29_999), 0);
+    assert_eq!(machine.advance_time(1), 1);
+    assert_eq!(machine.advance_time(95_000), 3);
+    for _ in 0..5 {
+        machine.advance_time(6_000);
+    }
+    assert_eq!(keepalives(&machine), 5);
+    assert_eq!(machine.state(), State::Connected);
+
+    // The reconnect delay fires once, then the connect timeout gives up
+    machine.process(Event::ConnectionLost);
+    assert_eq!(machine.advance_time(60_000), 2);
+    assert_eq!(keepalives(&machine), 5);
+    assert_eq!(machine.state(), State::Disconnected);
+    assert!(machine.context().0.ends_with(&["log_timeout", "reset_connection"]));
+}
+"#;
+
+#[test]
+fn test_generated_periodic_timer_fires_until_exit() {
+    let scratch = common::Scratch::new("periodic_timers");
+
+    let source = include_str!("../examples/connection_manager.fsm");
+    let fsm = &parse_fsm(source).expect("Should parse successfully")[0];
+    scratch.write("connection_manager.rs", generate_rust_code(fsm));
+    scratch.write("main.rs", PROGRAM);
+
+    let binary = scratch.rustc("periodic_timers", &["-A", "warnings"]);
+    common::run(&binary);
+}
+
+/// Two equal timers, started in reverse definition order
+const RACE: &str = r#"
+fsm Race {
+    timer first = 100 -> First
+    timer second = 100 -> Second
+    [*] --> Armed
+    state Armed {
+        start_timer(second)
+        start_timer(first)
+    }
+    Armed --> FirstWon : First
+    Armed --> SecondWon : Second
+}
+"#;
+
+const RACE_PROGRAM: &str = r#"
+mod race;
+
+use race::{Race, RaceState as State};
+
+struct Context;
+
+impl race::RaceActions for Context {}
+
+fn main() {
+    let mut machine = Race::new(Context);
+    machine.start();
+    assert_eq!(machine.advance_time(100), 1);
+    assert_eq!(machine.state(), State::FirstWon);
+}
+"#;
+
+#[test]
+fn test_timers_due_together_fire_in_definition_order() {
+    let fsm = &parse_fsm(RACE).expect("Should parse successfully")[0];
+    let mut interpreter = Interpreter::new(fsm, |_: &str| false);
+    interpreter.advance_time(100);
+    assert_eq!(interpreter.current_state(), Some("FirstWon"));
+
+    let scratch = common::Scratch::new("timer_ties");
+    scratch.write("race.rs", generate_rust_code(fsm));
+    scratch.write("main.rs", RACE_PROGRAM);
+    let binary = scratch.rustc("timer_ties", &["-A", "warnings"]);
+    common::run(&binary);
+}
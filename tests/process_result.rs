@@ -0,0 +1,104 @@
+//! Compiles generated code with `process_result` and checks what `process()` reports
+//! for transitions, internal transitions, failing guards and unhandled events
+
+mod common;
+
+use oxidate_fsm::codegen::{generate_rust_code_with_options, CodegenOptions, CodegenTarget};
+use oxidate_fsm::parser::parse_fsm;
+
+const HEATER: &str = r#"
+    fsm Heater {
+        [*] --> Off
+        state Off
+        state Heating {
+            tick / read_sensor
+            adjust [in_range] / trim
+        }
+        state Fault
+        Off --> Heating : power_on [supply_ok]
+        Heating --> Off : power_off
+        * --> Fault : overheat [sensor_trusted]
+        Fault --> Off : reset
+    }
+"#;
+
+const PROGRAM: &str = r#"
+mod heater;
+
+use heater::{Heater, HeaterEvent, HeaterProcessResult, HeaterState};
+
+#[derive(Default)]
+struct Rig {
+    log: Vec<&'static str>,
+    supply_ok: bool,
+    in_range: bool,
+    sensor_trusted: bool,
+}
+
+impl heater::HeaterActions for Rig {
+    fn read_sensor(&mut self) {
+        self.log.push("read_sensor");
+    }
+    fn trim(&mut self) {
+        self.log.push("trim");
+    }
+    fn supply_ok(&self) -> bool {
+        self.supply_ok
+    }
+    fn in_range(&self) -> bool {
+        self.in_range
+    }
+    fn sensor_trusted(&self) -> bool {
+        self.sensor_trusted
+    }
+}
+
+fn main() {
+    let mut heater = Heater::new(Rig::default());
+    heater.start();
+
+    assert_eq!(heater.process(HeaterEvent::Tick), HeaterProcessResult::Ignored);
+    assert_eq!(heater.process(HeaterEvent::PowerOn), HeaterProcessResult::GuardRejected);
+    heater.context_mut().supply_ok = true;
+    assert_eq!(
+        heater.process(HeaterEvent::PowerOn),
+        HeaterProcessResult::Transitioned { from: HeaterState::Off, to: HeaterState::Heating }
+    );
+
+    // Internal transitions, with and without a passing guard
+    assert_eq!(heater.process(HeaterEvent::Tick), HeaterProcessResult::InternalHandled);
+    assert_eq!(heater.process(HeaterEvent::Adjust), HeaterProcessResult::GuardRejected);
+    heater.context_mut().in_range = true;
+    assert_eq!(heater.process(HeaterEvent::Adjust), HeaterProcessResult::InternalHandled);
+    assert_eq!(heater.state(), HeaterState::Heating);
+    assert_eq!(heater.context().log, ["read_sensor", "trim"]);
+
+    // A guarded wildcard rejects in any state
+    assert_eq!(heater.process(HeaterEvent::Overheat), HeaterProcessResult::GuardRejected);
+    heater.context_mut().sensor_trusted = true;
+    let result = heater.process(HeaterEvent::Overheat);
+    assert_eq!(result, HeaterProcessResult::Transitioned { from: HeaterState::Heating, to: HeaterState::Fault });
+    assert!(result.is_handled());
+    assert!(!HeaterProcessResult::GuardRejected.is_handled());
+
+    // The queue counts handled events
+    for event in [HeaterEvent::Tick, HeaterEvent::Reset, HeaterEvent::PowerOn, HeaterEvent::Tick] {
+        heater.post(event).unwrap();
+    }
+    assert_eq!(heater.dispatch_pending(), 3);
+    assert_eq!(heater.state(), HeaterState::Heating);
+}
+"#;
+
+#[test]
+fn test_process_result_variants() {
+    let scratch = common::Scratch::new("process_result");
+    let fsm = &parse_fsm(HEATER).expect("Should parse successfully")[0];
+    let options = CodegenOptions::new().with_process_result(true).with_event_queue_size(Some(4));
+    let code = generate_rust_code_with_options(fsm, CodegenTarget::Standard, &options);
+    scratch.write("heater.rs", code);
+    scratch.write("main.rs", PROGRAM);
+
+    let binary = scratch.rustc("process_result", &["-A", "warnings"]);
+    common::run(&binary);
+}
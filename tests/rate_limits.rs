@@ -0,0 +1,77 @@
+//! Compiles the code generated for a button with `@debounce` and `@throttle`
+//! transitions and drives its clock by hand: bouncing presses are dropped until the
+//! button is quiet, and releases fire at most once per window, across a wrap of
+//! `now_ms()`
+
+mod common;
+
+use oxidate_fsm::codegen::generate_rust_code;
+use oxidate_fsm::parser::parse_fsm;
+
+const FSM: &str = "fsm Button {
+    [*] --> Up
+    Up --> Down : press @debounce(50ms) / click
+    Down --> Up : release @throttle(1s)
+}
+";
+
+const PROGRAM: &str = r#"
+mod button;
+
+use button::{Button, ButtonEvent as Event, ButtonState as State};
+
+#[derive(Default)]
+struct Board {
+    now: u32,
+    clicks: usize,
+}
+
+impl button::ButtonActions for Board {
+    fn click(&mut self) {
+        self.clicks += 1;
+    }
+    fn now_ms(&self) -> u32 {
+        self.now
+    }
+}
+
+fn at(machine: &mut Button<Board>, now: u32, event: Event) -> bool {
+    machine.context_mut().now = now;
+    machine.process(event)
+}
+
+fn main() {
+    let mut machine = Button::new(Board { now: u32::MAX - 300, clicks: 0 });
+    machine.start();
+
+    // The first press goes through, then the release
+    assert!(at(&mut machine, u32::MAX - 300, Event::Press));
+    assert!(at(&mut machine, u32::MAX - 300, Event::Release));
+
+    // Bounces 30 ms apart keep pushing the press back
+    assert!(!at(&mut machine, u32::MAX - 270, Event::Press));
+    assert!(!at(&mut machine, u32::MAX - 240, Event::Press));
+    assert_eq!(machine.state(), State::Up);
+    assert!(at(&mut machine, u32::MAX - 190, Event::Press));
+    assert_eq!(machine.context().clicks, 2);
+
+    // Within a second of the last release that fired, even once the clock wraps
+    assert!(!at(&mut machine, u32::MAX - 100, Event::Release));
+    assert!(!at(&mut machine, 698, Event::Release));
+    assert_eq!(machine.state(), State::Down);
+    assert!(at(&mut machine, 699, Event::Release));
+    assert_eq!(machine.state(), State::Up);
+}
+"#;
+
+#[test]
+fn test_generated_rate_limits_follow_the_clock() {
+    let scratch = common::Scratch::new("rate_limits");
+
+    let fsm = &parse_fsm(FSM).expect("Should parse successfully")[0];
+    scratch.write("button.rs", generate_rust_code(fsm));
+    scratch.write("main.rs", PROGRAM);
+
+    let binary = scratch.rustc("rate_limits", &["-A", "warnings"]);
+    common::run(&binary);
+}
@@ -0,0 +1,96 @@
+//! Compiles the code generated for a state with both an internal transition and an
+//! external self-transition on the same event, and checks that it and the
+//! interpreter try the self-transition first
+
+mod common;
+
+use oxidate_fsm::codegen::generate_rust_code;
+use oxidate_fsm::fsm::interpreter::{dispatch, StepResult};
+use oxidate_fsm::parser::parse_fsm;
+
+const COIN_BOX: &str = r#"
+    fsm CoinBox {
+        [*] --> Accepting
+        state Accepting {
+            entry / show_balance
+            exit / hide_balance
+            coin / add_to_balance
+            tap / blink
+        }
+        Accepting --> Accepting : coin [jammed] / eject_coin
+        Accepting --> Accepting : tap / wake
+    }
+"#;
+
+const PROGRAM: &str = r#"
+mod coin_box;
+
+use coin_box::{CoinBox, CoinBoxEvent as Event};
+
+#[derive(Default)]
+struct Log {
+    calls: Vec<&'static str>,
+    jammed: bool,
+}
+
+impl coin_box::CoinBoxActions for Log {
+    fn show_balance(&mut self) { self.calls.push("show_balance"); }
+    fn hide_balance(&mut self) { self.calls.push("hide_balance"); }
+    fn add_to_balance(&mut self) { self.calls.push("add_to_balance"); }
+    fn blink(&mut self) { self.calls.push("blink"); }
+    fn eject_coin(&mut self) { self.calls.push("eject_coin"); }
+    fn wake(&mut self) { self.calls.push("wake"); }
+    fn jammed(&self) -> bool { self.jammed }
+}
+
+fn main() {
+    let mut machine = CoinBox::new(Log::default());
+    machine.start();
+    let mut run = |event, jammed| {
+        machine.context_mut().jammed = jammed;
+        machine.context_mut().calls.clear();
+        machine.process(event);
+        println!("{}", machine.context().calls.join(" "));
+    };
+    run(Event::Coin, false);
+    run(Event::Coin, true);
+    run(Event::Tap, false);
+}
+"#;
+
+/// Actions the interpreter runs for `event` with `jammed`, as the program prints them
+fn interpreted(event: &str, jammed: bool) -> String {
+    let fsm = &parse_fsm(COIN_BOX).expect("Should parse successfully")[0];
+    match dispatch(fsm, "Accepting", event, &|guard| guard == "jammed" && jammed) {
+        StepResult::Fired(firing) => firing.actions.iter().map(|a| a.name.as_str()).collect::<Vec<_>>().join(" "),
+        other => panic!("{event} was not handled: {other:?}"),
+    }
+}
+
+#[test]
+fn test_self_transition_checked_before_internal_transition() {
+    let expected = [
+        // The guarded self-transition fails: the internal transition runs alone
+        "add_to_balance",
+        "hide_balance eject_coin show_balance",
+        // The unguarded one always wins
+        "hide_balance wake show_balance",
+    ];
+    let interpreted = [interpreted("coin", false), interpreted("coin", true), interpreted("tap", false)];
+    assert_eq!(interpreted, expected);
+
+    let scratch = common::Scratch::new("self_transitions");
+    let fsm = &parse_fsm(COIN_BOX).expect("Should parse successfully")[0];
+    scratch.write("coin_box.rs", generate_rust_code(fsm));
+    scratch.write("main.rs", PROGRAM);
+
+    let binary = scratch.rustc("self_transitions", &["-A", "warnings"]);
+    let run = common::run(&binary);
+    assert_eq!(String::from_utf8_lossy(&run.stdout).lines().collect::<Vec<_>>(), expected);
+
+    // Both pairs are reported
+    let warnings = fsm.warnings();
+    assert_eq!(warnings.len(), 2, "{warnings:?}");
+    assert!(warnings[0].ends_with("is checked first, and the internal one runs only when its guard fails"), "{}", warnings[0]);
+    assert!(warnings[1].ends_with("is checked first, so the internal one never runs"), "{}", warnings[1]);
+}
@@ -0,0 +1,83 @@
+//! Compiles two generated FSMs against their shared `events.rs` module and runs
+//! a program broadcasting `SharedEvent`s to both
+
+mod common;
+
+use oxidate_fsm::codegen::autogen::write_autogen_folder;
+use oxidate_fsm::codegen::{CodegenOptions, CodegenTarget};
+use oxidate_fsm::parser::parse_fsm;
+
+const MACHINES: &str = r#"
+    fsm Motor {
+        [*] --> Off
+        Off --> Running : start / spin_up
+        Running --> Off : fault / brake
+        Running --> Running : tick
+    }
+
+    fsm Safety {
+        [*] --> Armed
+        Armed --> Tripped : fault
+        Tripped --> Armed : reset
+        Armed --> Armed : tick
+    }
+"#;
+
+const PROGRAM: &str = r#"
+mod autogen;
+
+use autogen::*;
+
+#[derive(Default)]
+struct Log(Vec<&'static str>);
+
+impl MotorActions for Log {
+    fn spin_up(&mut self) {
+        self.0.push("spin_up");
+    }
+    fn brake(&mut self) {
+        self.0.push("brake");
+    }
+}
+
+impl SafetyActions for Log {}
+
+fn main() {
+    let mut motor = Motor::new(Log::default());
+    let mut safety = Safety::new(Log::default());
+
+    for event in [SharedEvent::Start, SharedEvent::Tick] {
+        motor.process_shared(event);
+        safety.process_shared(event);
+    }
+    assert_eq!(motor.state(), MotorState::Running);
+    assert!(!motor.process_shared(SharedEvent::Reset));
+
+    for event in [SharedEvent::Fault, SharedEvent::Reset] {
+        motor.post_shared(event).unwrap();
+        safety.post_shared(event).unwrap();
+    }
+    assert_eq!(motor.dispatch_pending(), 1);
+    assert_eq!(safety.dispatch_pending(), 2);
+    assert_eq!(motor.state(), MotorState::Off);
+    assert_eq!(safety.state(), SafetyState::Armed);
+    assert_eq!(motor.context().0, ["spin_up", "brake"]);
+
+    assert_eq!(SharedEvent::from(SafetyEvent::Reset), SharedEvent::Reset);
+    assert_eq!(MotorEvent::try_from(SharedEvent::Reset), Err(SharedEvent::Reset));
+    assert_eq!(SharedEvent::Tick.as_str(), "tick");
+}
+"#;
+
+#[test]
+fn test_shared_events_compile_and_run() {
+    let scratch = common::Scratch::new("shared_events");
+
+    let fsms = parse_fsm(MACHINES).expect("Should parse successfully");
+    let options = CodegenOptions::new().with_shared_events(true).with_event_queue_size(Some(4));
+    write_autogen_folder(scratch.dir(), &fsms, CodegenTarget::Standard, &options).unwrap();
+    scratch.write("main.rs", PROGRAM);
+
+    let binary = scratch.rustc("shared_events", &["--cfg", "feature=\"shared-events\"", "-A", "warnings"]);
+    common::run(&binary);
+}
@@ -0,0 +1,66 @@
+//! Compiles generated code and checks that `start()` runs the start and initial entry
+//! actions once, and `new()` none of them
+
+mod common;
+
+use oxidate_fsm::codegen::generate_rust_code;
+use oxidate_fsm::parser::parse_fsm;
+
+const PUMP: &str = r#"
+    fsm Pump {
+        [*] --> Idle : / self_test
+        state Idle {
+            entry / close_valve
+        }
+        Idle --> Pumping : start / open_valve
+        Pumping --> Idle : stop
+    }
+"#;
+
+const PROGRAM: &str = r#"
+mod pump;
+
+use pump::{Pump, PumpEvent, PumpState};
+
+#[derive(Default)]
+struct Log(Vec<&'static str>);
+
+impl pump::PumpActions for Log {
+    fn self_test(&mut self) {
+        self.0.push("self_test");
+    }
+    fn close_valve(&mut self) {
+        self.0.push("close_valve");
+    }
+    fn open_valve(&mut self) {
+        self.0.push("open_valve");
+    }
+}
+
+fn main() {
+    let mut pump = Pump::new(Log::default());
+    assert!(pump.context().0.is_empty());
+
+    assert!(pump.start());
+    assert_eq!(pump.context().0, ["self_test", "close_valve"]);
+    assert!(!pump.start());
+    assert_eq!(pump.context().0, ["self_test", "close_valve"]);
+
+    assert!(pump.process(PumpEvent::Start));
+    assert!(pump.process(PumpEvent::Stop));
+    assert_eq!(pump.state(), PumpState::Idle);
+    assert_eq!(pump.context().0, ["self_test", "close_valve", "open_valve", "close_valve"]);
+}
+"#;
+
+#[test]
+fn test_start_runs_entry_actions_once() {
+    let scratch = common::Scratch::new("start_actions");
+
+    let fsm = &parse_fsm(PUMP).expect("Should parse successfully")[0];
+    scratch.write("pump.rs", generate_rust_code(fsm));
+    scratch.write("main.rs", PROGRAM);
+
+    let binary = scratch.rustc("start_actions", &["-A", "warnings"]);
+    common::run(&binary);
+}
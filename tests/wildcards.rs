@@ -0,0 +1,70 @@
+//! Compiles generated code and checks that a wildcard (`* -->`) transition is taken
+//! only where the current state has no transition of its own for the event
+
+mod common;
+
+use oxidate_fsm::codegen::generate_rust_code;
+use oxidate_fsm::parser::parse_fsm;
+
+const PRESS: &str = r#"
+    fsm Press {
+        [*] --> Idle
+        state Idle
+        state Running {
+            exit / stop_motor
+        }
+        state Stopped
+        Idle --> Running : start
+        Running --> Idle : emergency_stop / brake
+        * --> Stopped : emergency_stop / kill_power
+    }
+"#;
+
+const PROGRAM: &str = r#"
+mod press;
+
+use press::{Press, PressEvent, PressState};
+
+#[derive(Default)]
+struct Log(Vec<&'static str>);
+
+impl press::PressActions for Log {
+    fn stop_motor(&mut self) {
+        self.0.push("stop_motor");
+    }
+    fn brake(&mut self) {
+        self.0.push("brake");
+    }
+    fn kill_power(&mut self) {
+        self.0.push("kill_power");
+    }
+}
+
+fn main() {
+    let mut press = Press::new(Log::default());
+    press.start();
+
+    // Running has its own emergency_stop
+    assert!(press.process(PressEvent::Start));
+    assert!(press.process(PressEvent::EmergencyStop));
+    assert_eq!(press.state(), PressState::Idle);
+    assert_eq!(press.context().0, ["stop_motor", "brake"]);
+
+    // Idle does not, so the wildcard takes it
+    assert!(press.process(PressEvent::EmergencyStop));
+    assert_eq!(press.state(), PressState::Stopped);
+    assert_eq!(press.context().0, ["stop_motor", "brake", "kill_power"]);
+    assert!(!press.process(PressEvent::Start));
+}
+"#;
+
+#[test]
+fn test_wildcard_after_own_transitions() {
+    let scratch = common::Scratch::new("wildcards");
+    let fsm = &parse_fsm(PRESS).expect("Should parse successfully")[0];
+    scratch.write("press.rs", generate_rust_code(fsm));
+    scratch.write("main.rs", PROGRAM);
+
+    let binary = scratch.rustc("wildcards", &["-A", "warnings"]);
+    common::run(&binary);
+}
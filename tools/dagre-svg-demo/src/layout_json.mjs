@@ -3,8 +3,13 @@ import dagre from 'dagre';
 
 // Reads a JSON graph from stdin and prints a JSON layout to stdout.
 //
+// `protocol` is the version of this format (`DAGRE_PROTOCOL` in src/diagram/dagre.rs).
+// The output always carries the version this script implements, so Oxidate can tell
+// an outdated script from a broken one.
+//
 // Input format:
 // {
+//   "protocol": number,
 //   "graph": { "rankdir": "tb"|"lr", "nodesep": number, "ranksep": number, "edgesep": number, "marginx": number, "marginy": number },
 //   "nodes": [ {"id": string, "width": number, "height": number} ],
 //   "edges": [ {"v": string, "w": string, "name"?: string|null, "labelWidth"?: number, "labelHeight"?: number} ]
@@ -12,11 +17,14 @@ import dagre from 'dagre';
 //
 // Output format:
 // {
+//   "protocol": 2,
 //   "graph": {"width": number, "height": number},
 //   "nodes": { [id]: {"x": number, "y": number, "width": number, "height": number} },
 //   "edges": [ {"v": string, "w": string, "name": string|null, "points": [ {"x": number, "y": number} ], "x": number|null, "y": number|null } ]
 // }
 
+const PROTOCOL = 2;
+
 const inputText = fs.readFileSync(0, 'utf8');
 if (!inputText.trim()) {
   console.error('No input received on stdin');
@@ -85,4 +93,4 @@ const outGraph = {
   height: gg.height ?? 0,
 };
 
-process.stdout.write(JSON.stringify({ graph: outGraph, nodes: outNodes, edges: outEdges }));
+process.stdout.write(JSON.stringify({ protocol: PROTOCOL, graph: outGraph, nodes: outNodes, edges: outEdges }));